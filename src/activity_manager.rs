@@ -26,36 +26,88 @@
  * SOFTWARE.
  */
 // Deps
-use crate::filetransfer::FileTransferProtocol;
+use crate::filetransfer::{
+    local_transfer::LocalFileTransfer, memory_transfer::MemoryFileTransfer, FileTransferProtocol,
+    SshAlgorithms,
+};
 use crate::host::{HostError, Localhost};
 use crate::system::config_client::ConfigClient;
-use crate::system::environment;
+use crate::system::staging::Staging;
 use crate::ui::activities::{
-    auth_activity::AuthActivity, filetransfer_activity::FileTransferActivity,
-    setup_activity::SetupActivity, Activity, ExitReason,
+    attach_activity::AttachActivity, auth_activity::AuthActivity,
+    filetransfer_activity::FileTransferActivity, setup_activity::SetupActivity, Activity,
+    ActivityResult, ExitReason,
 };
-use crate::ui::context::{Context, FileTransferParams};
+use crate::ui::context::{Context, FileTransferParams, StartupIssue};
+use crate::ui::tabs::Tabs;
 
 // Namespaces
 use std::path::{Path, PathBuf};
 use std::thread::sleep;
 use std::time::Duration;
 
+/// Minimum interval between two ticks when reduced motion is enabled
+const REDUCED_MOTION_INTERVAL: Duration = Duration::from_millis(200);
+
 /// ### NextActivity
 ///
-/// NextActivity identified the next identity to run once the current has ended
+/// NextActivity identifies the activity to launch, along with its launch parameters
 pub enum NextActivity {
+    Attach(PathBuf), // Daemon control socket
+    Authentication,
+    FileTransfer(Box<FileTransferParams>),
+    SetupActivity,
+}
+
+/// ### ActivityId
+///
+/// Identifies a running activity in the stack
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ActivityId {
+    Attach,
     Authentication,
     FileTransfer,
     SetupActivity,
 }
 
+/// ### Transition
+///
+/// Describes what to do with the activity stack once an activity has terminated
+enum Transition {
+    /// Pause current activity and launch a new one on top of it
+    Push(NextActivity),
+    /// Destroy current activity and resume the previous one
+    Pop(Option<ActivityResult>),
+    /// Keep current file transfer activity open in a tab and resume the previous activity, to open another one
+    NewTab,
+    /// Pause current activity and resume the tab at this offset from the active one
+    SwitchTab(isize),
+    /// Destroy current file transfer activity and resume another tab; the previous activity once no tab is left
+    CloseTab,
+    /// Destroy all the activities
+    Exit,
+}
+
+/// ### StackEntry
+///
+/// An activity in the stack, with the interval between its ticks.
+/// File transfer activities are moved out of the stack into their tab, while another tab runs
+struct StackEntry {
+    id: ActivityId,
+    activity: Box<dyn Activity>,
+    interval: Duration,
+}
+
 /// ### ActivityManager
 ///
 /// The activity manager takes care of running activities and handling them until the application has ended
 pub struct ActivityManager {
     context: Option<Context>,
     interval: Duration,
+    demo: bool,
+    local: Option<PathBuf>, // Some in local-to-local mode; directory of the right explorer
+    root: Option<PathBuf>,  // Session root for the remote explorer
+    tabs: Tabs<StackEntry>, // File transfer activities open in tabs
 }
 
 impl ActivityManager {
@@ -69,220 +121,282 @@ impl ActivityManager {
             Err(e) => return Err(e),
         };
         // Initialize configuration client
-        let (config_client, error): (Option<ConfigClient>, Option<String>) =
-            match Self::init_config_client() {
-                Ok(cli) => (Some(cli), None),
-                Err(err) => (None, Some(err)),
-            };
-        let ctx: Context = Context::new(host, config_client, error);
+        let (config_client, issues): (Option<ConfigClient>, Vec<StartupIssue>) =
+            Context::init_config_client();
+        // Remove the temporary files left behind by the instances which crashed
+        let staging_dir: Option<PathBuf> = config_client.as_ref().and_then(|x| x.get_staging_dir());
+        let _ = Staging::new(staging_dir.as_deref()).cleanup();
+        let ctx: Context = Context::new(host, config_client, issues);
         Ok(ActivityManager {
             context: Some(ctx),
             interval,
+            demo: false,
+            local: None,
+            root: None,
+            tabs: Tabs::new(),
         })
     }
 
-    /// ### set_filetransfer_params
+    /// ### set_demo_mode
     ///
-    /// Set file transfer params
-    pub fn set_filetransfer_params(
-        &mut self,
-        address: String,
-        port: u16,
-        protocol: FileTransferProtocol,
-        username: Option<String>,
-        password: Option<String>,
-        entry_directory: Option<PathBuf>,
-    ) {
-        // Put params into the context
-        self.context.as_mut().unwrap().ft_params = Some(FileTransferParams {
-            address,
-            port,
-            protocol,
-            username,
-            password,
-            entry_directory,
-        });
+    /// Enable demo mode: the file transfer activity will be connected to an in-memory file system
+    /// and disconnecting will terminate the application
+    pub fn set_demo_mode(&mut self) {
+        self.demo = true;
     }
 
-    /// ### run
-    ///
+    /// ### set_local_mode
     ///
-    /// Loop for activity manager. You need to provide the activity to start with
-    /// Returns the exitcode
-    pub fn run(&mut self, launch_activity: NextActivity) {
-        let mut current_activity: Option<NextActivity> = Some(launch_activity);
-        loop {
-            current_activity = match current_activity {
-                Some(activity) => match activity {
-                    NextActivity::Authentication => self.run_authentication(),
-                    NextActivity::FileTransfer => self.run_filetransfer(),
-                    NextActivity::SetupActivity => self.run_setup(),
-                },
-                None => break, // Exit
-            }
-        }
-        // Drop context
-        drop(self.context.take());
+    /// Enable local-to-local mode: the file transfer activity is started without connection params
+    /// and both explorers browse the local host. The right explorer enters `wrkdir`, if provided,
+    /// otherwise the current directory. Disconnecting will terminate the application
+    pub fn set_local_mode(&mut self, wrkdir: Option<PathBuf>) {
+        self.local = Some(match wrkdir {
+            Some(dir) => dir,
+            None => self.context.as_ref().unwrap().local.pwd(),
+        });
     }
 
-    // -- Activity Loops
+    /// ### set_session_root
+    ///
+    /// Jail the remote explorer into `root` for the whole session
+    pub fn set_session_root(&mut self, root: PathBuf) {
+        self.root = Some(root);
+    }
 
-    /// ### run_authentication
+    /// ### run
     ///
-    /// Loop for Authentication activity.
-    /// Returns when activity terminates.
-    /// Returns the next activity to run
-    fn run_authentication(&mut self) -> Option<NextActivity> {
-        // Prepare activity
-        let mut activity: AuthActivity = AuthActivity::default();
-        // Prepare result
-        let result: Option<NextActivity>;
-        // Get context
-        let ctx: Context = match self.context.take() {
-            Some(ctx) => ctx,
-            None => return None,
-        };
-        // Create activity
-        activity.on_create(ctx);
-        loop {
+    /// Loop for activity manager. You need to provide the activity to start with.
+    /// Activities are kept in a stack: an activity can launch another one on top of itself,
+    /// which is resumed, along with the result of the launched activity, once the latter has terminated.
+    /// File transfer activities can be kept open in tabs, which take turns on top of the stack.
+    /// The application ends when the stack is empty
+    pub fn run(&mut self, launch_activity: NextActivity) {
+        let mut stack: Vec<StackEntry> = Vec::new();
+        if let Some(entry) = self.launch(launch_activity) {
+            stack.push(entry);
+        }
+        while let Some(entry) = stack.last_mut() {
             // Draw activity
-            activity.on_draw();
-            // Check if has to be terminated
-            if let Some(exit_reason) = activity.will_umount() {
-                match exit_reason {
-                    ExitReason::Quit => {
-                        result = None;
-                        break;
+            entry.activity.on_draw();
+            // Check if activity has terminated
+            let id: ActivityId = entry.id;
+            let transition: Option<Transition> = entry
+                .activity
+                .will_umount()
+                .and_then(|reason| Self::transition(id, reason));
+            match transition {
+                None => sleep(entry.interval), // Sleep for ticks
+                Some(Transition::Push(next)) => {
+                    self.context = entry.activity.on_pause();
+                    match self.launch(next) {
+                        Some(entry) => stack.push(entry),
+                        None => break, // Context is gone
                     }
-                    ExitReason::EnterSetup => {
-                        // User requested activity
-                        result = Some(NextActivity::SetupActivity);
-                        break;
+                }
+                Some(Transition::Pop(result)) => {
+                    if let Some(mut entry) = stack.pop() {
+                        self.context = entry.activity.on_destroy();
                     }
-                    ExitReason::Connect => {
-                        // User submitted, set next activity
-                        result = Some(NextActivity::FileTransfer);
-                        break;
+                    // Resume previous activity
+                    self.resume(stack.as_mut_slice(), result);
+                }
+                Some(Transition::NewTab) => {
+                    // Session stays open in its tab, while connecting to another host
+                    self.context = entry.activity.on_pause();
+                    if let Some(entry) = stack.pop() {
+                        self.tabs.park(entry);
+                    }
+                    self.resume(stack.as_mut_slice(), None);
+                }
+                Some(Transition::SwitchTab(offset)) => {
+                    self.context = entry.activity.on_pause();
+                    // Authentication stays below the tabs
+                    if id == ActivityId::FileTransfer {
+                        if let Some(entry) = stack.pop() {
+                            self.tabs.park(entry);
+                        }
+                    }
+                    if let Some(entry) = self.tabs.switch(offset) {
+                        stack.push(entry);
+                    }
+                    self.resume(stack.as_mut_slice(), None);
+                }
+                Some(Transition::CloseTab) => {
+                    if let Some(mut entry) = stack.pop() {
+                        self.context = entry.activity.on_destroy();
+                    }
+                    // Resume another tab, if any; otherwise the previous activity.
+                    // There's nothing to go back to in demo and local-to-local mode: stack gets empty
+                    let result: Option<ActivityResult> = match self.tabs.close() {
+                        Some(entry) => {
+                            stack.push(entry);
+                            None
+                        }
+                        None => Some(ActivityResult::Disconnected),
+                    };
+                    self.resume(stack.as_mut_slice(), result);
+                }
+                Some(Transition::Exit) => {
+                    // Destroy all activities; only the running one has the context
+                    while let Some(mut entry) = stack.pop() {
+                        if let Some(ctx) = entry.activity.on_destroy() {
+                            self.context = Some(ctx);
+                        }
+                    }
+                    // Close the sessions open in the other tabs
+                    for mut entry in self.tabs.drain().into_iter() {
+                        let _ = entry.activity.on_destroy();
                     }
-                    _ => { /* Nothing to do */ }
                 }
             }
-            // Sleep for ticks
-            sleep(self.interval);
         }
-        // Destroy activity
-        self.context = activity.on_destroy();
-        result
+        // Drop context
+        drop(self.context.take());
     }
 
-    /// ### run_filetransfer
+    // -- Activity stack
+
+    /// ### launch
     ///
-    /// Loop for FileTransfer activity.
-    /// Returns when activity terminates.
-    /// Returns the next activity to run
-    fn run_filetransfer(&mut self) -> Option<NextActivity> {
-        // Get context
-        let ctx: Context = match self.context.take() {
-            Some(ctx) => ctx,
-            None => return None,
-        };
-        // If ft params is None, return None
-        let ft_params: &FileTransferParams = match ctx.ft_params.as_ref() {
-            Some(ft_params) => &ft_params,
-            None => return None,
-        };
-        // Prepare activity
-        let protocol: FileTransferProtocol = ft_params.protocol;
-        let mut activity: FileTransferActivity = FileTransferActivity::new(protocol);
-        // Prepare result
-        let result: Option<NextActivity>;
-        // Create activity
-        activity.on_create(ctx);
-        loop {
-            // Draw activity
-            activity.on_draw();
-            // Check if has to be terminated
-            if let Some(exit_reason) = activity.will_umount() {
-                match exit_reason {
-                    ExitReason::Quit => {
-                        result = None;
-                        break;
+    /// Create the activity and give it the context.
+    /// Returns None if there's no context to give
+    fn launch(&mut self, next: NextActivity) -> Option<StackEntry> {
+        let mut ctx: Context = self.context.take()?;
+        let (id, mut activity): (ActivityId, Box<dyn Activity>) = match next {
+            NextActivity::Attach(socket) => (
+                ActivityId::Attach,
+                Box::new(AttachActivity::new(socket.as_path())),
+            ),
+            NextActivity::Authentication => (
+                ActivityId::Authentication,
+                Box::new(AuthActivity::default()),
+            ),
+            NextActivity::SetupActivity => (
+                ActivityId::SetupActivity,
+                Box::new(SetupActivity::default()),
+            ),
+            NextActivity::FileTransfer(params) => {
+                let mut activity: FileTransferActivity = match self.local.as_ref() {
+                    // Local-to-local mode doesn't use ft params
+                    Some(wrkdir) => {
+                        FileTransferActivity::with_client(Box::new(LocalFileTransfer::new(wrkdir)))
                     }
-                    ExitReason::Disconnect => {
-                        // User disconnected, set next activity to authentication
-                        result = Some(NextActivity::Authentication);
-                        break;
+                    None if self.demo => {
+                        FileTransferActivity::with_client(Box::new(MemoryFileTransfer::demo()))
                     }
-                    _ => { /* Nothing to do */ }
-                }
+                    None => FileTransferActivity::new(&params),
+                };
+                activity.set_remote_root(self.root.clone());
+                // Put params into the context
+                ctx.ft_params = match (self.local.is_some(), self.demo) {
+                    (true, _) => None,
+                    (false, true) => Some(Self::demo_params()),
+                    (false, false) => Some(*params),
+                };
+                self.tabs.open(match ctx.ft_params.as_ref() {
+                    Some(params) => Self::tab_label(params),
+                    None => String::from("localhost"),
+                });
+                (ActivityId::FileTransfer, Box::new(activity))
             }
-            // Sleep for ticks
-            sleep(self.interval);
+        };
+        ctx.tabs = self
+            .tabs
+            .bar(id == ActivityId::FileTransfer, self.can_open_tabs());
+        let interval: Duration = self.tick_interval(&ctx);
+        // Create activity
+        activity.on_create(ctx);
+        Some(StackEntry {
+            id,
+            activity,
+            interval,
+        })
+    }
+
+    /// ### resume
+    ///
+    /// Give the context back to the activity on top of the `stack`, which is resumed along with `result`
+    fn resume(&mut self, stack: &mut [StackEntry], result: Option<ActivityResult>) {
+        if let (Some(entry), Some(mut ctx)) = (stack.last_mut(), self.context.take()) {
+            ctx.tabs = self
+                .tabs
+                .bar(entry.id == ActivityId::FileTransfer, self.can_open_tabs());
+            entry.interval = self.tick_interval(&ctx);
+            entry.activity.on_resume(ctx, result);
         }
-        // Destroy activity
-        self.context = activity.on_destroy();
-        result
     }
 
-    /// ### run_setup
+    /// ### transition
     ///
-    /// `SetupActivity` run loop.
-    /// Returns when activity terminates.
-    /// Returns the next activity to run
-    fn run_setup(&mut self) -> Option<NextActivity> {
-        // Prepare activity
-        let mut activity: SetupActivity = SetupActivity::default();
-        // Get context
-        let ctx: Context = match self.context.take() {
-            Some(ctx) => ctx,
-            None => return None,
-        };
-        // Create activity
-        activity.on_create(ctx);
-        loop {
-            // Draw activity
-            activity.on_draw();
-            // Check if activity has terminated
-            if let Some(ExitReason::Quit) = activity.will_umount() {
-                break;
+    /// Get the transition to apply to the stack once the activity `id` has terminated
+    fn transition(id: ActivityId, reason: &ExitReason) -> Option<Transition> {
+        match (id, reason) {
+            (_, ExitReason::Quit) if id != ActivityId::SetupActivity => Some(Transition::Exit),
+            (ActivityId::Authentication, ExitReason::EnterSetup) => {
+                Some(Transition::Push(NextActivity::SetupActivity))
+            }
+            (ActivityId::Authentication, ExitReason::Connect(params)) => {
+                Some(Transition::Push(NextActivity::FileTransfer(params.clone())))
+            }
+            (ActivityId::FileTransfer, ExitReason::Disconnect) => Some(Transition::CloseTab),
+            (ActivityId::FileTransfer, ExitReason::NewTab) => Some(Transition::NewTab),
+            (ActivityId::FileTransfer, ExitReason::SwitchTab(offset))
+            | (ActivityId::Authentication, ExitReason::SwitchTab(offset)) => {
+                Some(Transition::SwitchTab(*offset))
+            }
+            (ActivityId::SetupActivity, ExitReason::Quit) => {
+                Some(Transition::Pop(Some(ActivityResult::SetupClosed)))
             }
-            // Sleep for ticks
-            sleep(self.interval);
+            _ => None, // Nothing to do
         }
-        // Destroy activity
-        self.context = activity.on_destroy();
-        // This activity always returns to AuthActivity
-        Some(NextActivity::Authentication)
     }
 
     // -- misc
 
-    /// ### init_config_client
+    /// ### demo_params
     ///
-    /// Initialize configuration client
-    fn init_config_client() -> Result<ConfigClient, String> {
-        // Get config dir
-        match environment::init_config_dir() {
-            Ok(config_dir) => {
-                match config_dir {
-                    Some(config_dir) => {
-                        // Get config client paths
-                        let (config_path, ssh_dir): (PathBuf, PathBuf) =
-                            environment::get_config_paths(config_dir.as_path());
-                        match ConfigClient::new(config_path.as_path(), ssh_dir.as_path()) {
-                            Ok(cli) => Ok(cli),
-                            Err(err) => Err(format!("Could not read configuration: {}", err)),
-                        }
-                    }
-                    None => Err(String::from(
-                        "Your system doesn't support configuration paths",
-                    )),
-                }
-            }
-            Err(err) => Err(format!(
-                "Could not initialize configuration directory: {}",
-                err
-            )),
+    /// Connection params displayed in demo mode
+    fn demo_params() -> FileTransferParams {
+        FileTransferParams {
+            address: String::from("demo"),
+            port: 22,
+            protocol: FileTransferProtocol::Sftp,
+            username: Some(String::from("demo")),
+            password: None,
+            entry_directory: None,
+            endpoint: None,
+            region: None,
+            algorithms: SshAlgorithms::default(),
+        }
+    }
+
+    /// ### tab_label
+    ///
+    /// Get the label of the tab of a session with the remote host of `params`
+    fn tab_label(params: &FileTransferParams) -> String {
+        match params.username.as_deref() {
+            Some(username) => format!("{}@{}", username, params.address),
+            None => params.address.clone(),
+        }
+    }
+
+    /// ### can_open_tabs
+    ///
+    /// Returns whether other sessions can be opened in tabs; there's no authentication to connect
+    /// to another host in demo and local-to-local mode
+    fn can_open_tabs(&self) -> bool {
+        !self.demo && self.local.is_none()
+    }
+
+    /// ### tick_interval
+    ///
+    /// Get the interval between two ticks for the provided context.
+    /// If reduced motion is enabled, ticks can't be shorter than `REDUCED_MOTION_INTERVAL`
+    fn tick_interval(&self, ctx: &Context) -> Duration {
+        match ctx.reduced_motion() {
+            true => self.interval.max(REDUCED_MOTION_INTERVAL),
+            false => self.interval,
         }
     }
 }
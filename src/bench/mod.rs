@@ -0,0 +1,315 @@
+//! ## Bench
+//!
+//! `bench` is the module which provides the benchmark mode, used to measure the throughput
+//! towards a remote host with different buffer sizes and number of concurrent workers
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::filetransfer::builder::FileTransferBuilder;
+use crate::filetransfer::{
+    FileTransfer, FileTransferError, FileTransferErrorType, FileTransferProtocol, SshAlgorithms,
+    TransferOutcome, TransferStream,
+};
+use crate::fs::{FsEntry, FsFile};
+use crate::system::sshkey_storage::SshKeyStorage;
+use crate::utils::random::random_alphanumeric_with_len;
+// Ext
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime};
+
+/// ## BenchParams
+///
+/// Holds the connection parameters of the host to benchmark
+#[derive(Clone)]
+pub struct BenchParams {
+    pub address: String,
+    pub port: u16,
+    pub protocol: FileTransferProtocol,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub endpoint: Option<String>,  // S3 endpoint URL
+    pub region: Option<String>,    // S3 region
+    pub algorithms: SshAlgorithms, // Preferred SSH algorithms
+}
+
+/// ## BenchOptions
+///
+/// Describes the configurations to benchmark
+pub struct BenchOptions {
+    pub payload_size: usize,      // Bytes transferred by each worker
+    pub buffer_sizes: Vec<usize>, // Buffer sizes to try
+    pub workers: Vec<usize>,      // Concurrent workers to try
+}
+
+/// ## BenchResult
+///
+/// Describes the throughput measured for a configuration
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub struct BenchResult {
+    pub buffer_size: usize,
+    pub workers: usize,
+    pub upload: u64,   // Bytes per second
+    pub download: u64, // Bytes per second
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        BenchOptions {
+            payload_size: 8388608, // 8MB
+            buffer_sizes: vec![8192, 32768, 65536, 262144],
+            workers: vec![1, 2, 4],
+        }
+    }
+}
+
+/// ### run_bench
+///
+/// Run benchmark for each configuration described in `opts`.
+/// Each worker opens its own connection, uploads a synthetic payload, downloads it back and then removes it.
+/// `make_storage` is called for each worker to get its ssh key storage;
+/// `on_result` is called each time a configuration has been measured.
+pub fn run_bench<S, F>(
+    params: &BenchParams,
+    opts: &BenchOptions,
+    make_storage: S,
+    mut on_result: F,
+) -> Result<Vec<BenchResult>, FileTransferError>
+where
+    S: Fn() -> SshKeyStorage,
+    F: FnMut(&BenchResult),
+{
+    let mut results: Vec<BenchResult> = Vec::new();
+    for buffer_size in opts.buffer_sizes.iter() {
+        for workers in opts.workers.iter() {
+            // Start workers
+            let handles: Vec<JoinHandle<Result<(Duration, Duration), FileTransferError>>> = (0
+                ..*workers)
+                .map(|_| {
+                    let params: BenchParams = params.clone();
+                    let storage: SshKeyStorage = make_storage();
+                    let payload_size: usize = opts.payload_size;
+                    let buffer_size: usize = *buffer_size;
+                    thread::spawn(move || bench_worker(params, storage, payload_size, buffer_size))
+                })
+                .collect();
+            // Collect workers results; the slowest worker determines the elapsed time
+            let mut upload_time: Duration = Duration::default();
+            let mut download_time: Duration = Duration::default();
+            for handle in handles {
+                match handle.join() {
+                    Ok(Ok((upload, download))) => {
+                        upload_time = upload_time.max(upload);
+                        download_time = download_time.max(download);
+                    }
+                    Ok(Err(err)) => return Err(err),
+                    Err(_) => {
+                        return Err(FileTransferError::new_ex(
+                            FileTransferErrorType::ProtocolError,
+                            String::from("Benchmark worker panicked"),
+                        ))
+                    }
+                }
+            }
+            let total_bytes: usize = opts.payload_size * *workers;
+            let result: BenchResult = BenchResult {
+                buffer_size: *buffer_size,
+                workers: *workers,
+                upload: throughput(total_bytes, upload_time),
+                download: throughput(total_bytes, download_time),
+            };
+            on_result(&result);
+            results.push(result);
+        }
+    }
+    Ok(results)
+}
+
+/// ### throughput
+///
+/// Calculate throughput in bytes per second
+fn throughput(bytes: usize, elapsed: Duration) -> u64 {
+    match elapsed.as_millis() {
+        0 => bytes as u64 * 1000,
+        ms => (bytes as u128 * 1000 / ms) as u64,
+    }
+}
+
+/// ### bench_worker
+///
+/// Connect to remote, upload payload and download it back.
+/// Returns the time elapsed to upload and download the payload
+fn bench_worker(
+    params: BenchParams,
+    storage: SshKeyStorage,
+    payload_size: usize,
+    buffer_size: usize,
+) -> Result<(Duration, Duration), FileTransferError> {
+    let mut client: Box<dyn FileTransfer> = FileTransferBuilder::new(params.protocol)
+        .with_ssh_key_storage(storage)
+        .with_ssh_algorithms(params.algorithms.clone())
+        .with_s3_endpoint(params.endpoint.clone(), params.region.clone())
+        .build();
+    client.connect(
+        params.address,
+        params.port,
+        params.username,
+        params.password,
+    )?;
+    // Make payload file in working directory
+    let mut remote_path: PathBuf = client.pwd()?;
+    remote_path.push(format!(
+        ".termscp-bench-{}",
+        random_alphanumeric_with_len(8)
+    ));
+    let payload: FsFile = make_payload_file(remote_path, payload_size);
+    let result = bench_transfer(client.as_mut(), &payload, buffer_size);
+    // Cleanup
+    let _ = client.remove(&FsEntry::File(payload));
+    let _ = client.disconnect();
+    result
+}
+
+/// ### bench_transfer
+///
+/// Upload and download back payload using a buffer of `buffer_size`
+fn bench_transfer(
+    client: &mut dyn FileTransfer,
+    payload: &FsFile,
+    buffer_size: usize,
+) -> Result<(Duration, Duration), FileTransferError> {
+    let mut buffer: Vec<u8> = vec![0; buffer_size];
+    // Upload
+    let started: Instant = Instant::now();
+    let mut writer: Box<dyn Write> = client.send_file(payload, payload.abs_path.as_path())?;
+    let mut written: usize = 0;
+    while written < payload.size {
+        let len: usize = buffer_size.min(payload.size - written);
+        if let Err(err) = writer.write_all(&buffer[..len]) {
+            return Err(FileTransferError::new(FileTransferErrorType::IoErr(err)));
+        }
+        written += len;
+    }
+    if let Err(err) = writer.flush() {
+        return Err(FileTransferError::new(FileTransferErrorType::IoErr(err)));
+    }
+    client.finalize_transfer(TransferOutcome::completed(
+        TransferStream::Upload(writer),
+        written as u64,
+    ))?;
+    let upload_time: Duration = started.elapsed();
+    // Download
+    let started: Instant = Instant::now();
+    let mut reader: Box<dyn Read> = client.recv_file(payload)?;
+    let mut read: usize = 0;
+    while read < payload.size {
+        match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(bytes) => read += bytes,
+            Err(err) => return Err(FileTransferError::new(FileTransferErrorType::IoErr(err))),
+        }
+    }
+    client.finalize_transfer(TransferOutcome::completed(
+        TransferStream::Download(reader),
+        read as u64,
+    ))?;
+    Ok((upload_time, started.elapsed()))
+}
+
+/// ### make_payload_file
+///
+/// Make the `FsFile` describing the remote payload
+fn make_payload_file(abs_path: PathBuf, size: usize) -> FsFile {
+    FsFile {
+        name: abs_path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        abs_path,
+        last_change_time: SystemTime::now(),
+        last_access_time: SystemTime::now(),
+        creation_time: SystemTime::now(),
+        size,
+        ftype: None,
+        readonly: false,
+        symlink: None,
+        broken_link: None,
+        user: None,
+        group: None,
+        unix_pex: Some((6, 4, 4)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_bench_options_default() {
+        let opts: BenchOptions = BenchOptions::default();
+        assert_eq!(opts.payload_size, 8388608);
+        assert_eq!(opts.buffer_sizes, vec![8192, 32768, 65536, 262144]);
+        assert_eq!(opts.workers, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn test_bench_throughput() {
+        assert_eq!(throughput(1048576, Duration::from_secs(1)), 1048576);
+        assert_eq!(throughput(1048576, Duration::from_millis(500)), 2097152);
+        assert_eq!(throughput(1024, Duration::from_millis(0)), 1024000);
+    }
+
+    #[test]
+    fn test_bench_make_payload_file() {
+        let file: FsFile = make_payload_file(PathBuf::from("/tmp/.termscp-bench-abc"), 1024);
+        assert_eq!(file.name.as_str(), ".termscp-bench-abc");
+        assert_eq!(file.abs_path, PathBuf::from("/tmp/.termscp-bench-abc"));
+        assert_eq!(file.size, 1024);
+    }
+
+    #[test]
+    fn test_bench_run_bench_connection_error() {
+        let params: BenchParams = BenchParams {
+            address: String::from("127.0.0.1"),
+            port: 1,
+            protocol: FileTransferProtocol::Sftp,
+            username: None,
+            password: None,
+            endpoint: None,
+            region: None,
+            algorithms: SshAlgorithms::default(),
+        };
+        let opts: BenchOptions = BenchOptions {
+            payload_size: 1024,
+            buffer_sizes: vec![1024],
+            workers: vec![1],
+        };
+        let mut calls: usize = 0;
+        assert!(run_bench(&params, &opts, SshKeyStorage::empty, |_| calls += 1).is_err());
+        assert_eq!(calls, 0);
+    }
+}
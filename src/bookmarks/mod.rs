@@ -27,6 +27,8 @@
  */
 pub mod serializer;
 
+use crate::filetransfer::SshAlgorithms;
+
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -34,10 +36,25 @@ use std::collections::HashMap;
 /// ## UserHosts
 ///
 /// UserHosts contains all the hosts saved by the user in the data storage
-/// It contains both `Bookmark`
+/// It contains both `Bookmark` and the history of the commands executed on each host
 pub struct UserHosts {
     pub bookmarks: HashMap<String, Bookmark>,
     pub recents: HashMap<String, Bookmark>,
+    #[serde(default)]
+    pub commands: HashMap<String, Vec<String>>, // Commands executed on host; most recent first
+    #[serde(default)]
+    pub filters: HashMap<String, Vec<String>>, // Include/exclude patterns of recursive transfers on host
+    #[serde(default)]
+    pub searches: HashMap<String, Vec<SavedSearch>>, // Searches saved by the user for host
+}
+
+#[derive(Clone, Deserialize, Serialize, std::fmt::Debug, PartialEq)]
+/// ## SavedSearch
+///
+/// SavedSearch is a search query saved with a name, to be run again on the same host
+pub struct SavedSearch {
+    pub name: String,
+    pub query: String, // Query, as parsed by `SearchQuery`
 }
 
 #[derive(Deserialize, Serialize, std::fmt::Debug, PartialEq)]
@@ -50,6 +67,44 @@ pub struct Bookmark {
     pub protocol: String,
     pub username: String,
     pub password: Option<String>, // Password is optional; base64, aes-128 encrypted password
+    #[serde(default)]
+    pub endpoint: Option<String>, // S3 endpoint URL
+    #[serde(default)]
+    pub region: Option<String>, // S3 region
+    #[serde(default)]
+    pub ephemeral: Option<bool>, // Credentials are never persisted for this host
+    #[serde(default)]
+    pub algorithms: Option<SshAlgorithms>, // Preferred SSH algorithms
+    #[serde(default)]
+    pub tag: Option<BookmarkTag>, // Color and label displayed while connected to host
+    #[serde(default)]
+    pub protected: Option<bool>, // Destructive operations on host require a confirmation phrase
+    #[serde(default)]
+    pub quiet_hours: Option<String>, // Daily time range (HH:MM-HH:MM) transfers are paused in
+}
+
+#[derive(Clone, Deserialize, Serialize, std::fmt::Debug, PartialEq)]
+/// ## BookmarkTag
+///
+/// BookmarkTag identifies the environment of a host (e.g. a red "PRODUCTION"), so that it can't be mistaken for another one
+pub struct BookmarkTag {
+    pub color: TagColor,
+    #[serde(default)]
+    pub label: String,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, std::fmt::Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+/// ## TagColor
+///
+/// Color of a `BookmarkTag`
+pub enum TagColor {
+    Red,
+    Yellow,
+    Green,
+    Blue,
+    Magenta,
+    Cyan,
 }
 
 // Errors
@@ -78,6 +133,9 @@ impl Default for UserHosts {
         UserHosts {
             bookmarks: HashMap::new(),
             recents: HashMap::new(),
+            commands: HashMap::new(),
+            filters: HashMap::new(),
+            searches: HashMap::new(),
         }
     }
 }
@@ -129,6 +187,13 @@ mod tests {
             protocol: String::from("SFTP"),
             username: String::from("root"),
             password: Some(String::from("password")),
+            endpoint: None,
+            region: None,
+            ephemeral: None,
+            algorithms: None,
+            tag: None,
+            protected: None,
+            quiet_hours: None,
         };
         let recent: Bookmark = Bookmark {
             address: String::from("192.168.1.2"),
@@ -136,6 +201,13 @@ mod tests {
             protocol: String::from("SCP"),
             username: String::from("admin"),
             password: Some(String::from("password")),
+            endpoint: None,
+            region: None,
+            ephemeral: None,
+            algorithms: None,
+            tag: None,
+            protected: None,
+            quiet_hours: None,
         };
         let mut bookmarks: HashMap<String, Bookmark> = HashMap::with_capacity(1);
         bookmarks.insert(String::from("test"), bookmark);
@@ -144,6 +216,9 @@ mod tests {
         let hosts: UserHosts = UserHosts {
             bookmarks: bookmarks,
             recents: recents,
+            commands: HashMap::new(),
+            filters: HashMap::new(),
+            searches: HashMap::new(),
         };
         // Verify
         let bookmark: &Bookmark = hosts.bookmarks.get(&String::from("test")).unwrap();
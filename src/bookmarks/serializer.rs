@@ -88,8 +88,9 @@ impl BookmarkSerializer {
 #[cfg(test)]
 mod tests {
 
-    use super::super::Bookmark;
+    use super::super::{Bookmark, BookmarkTag, TagColor};
     use super::*;
+    use crate::filetransfer::SshAlgorithms;
 
     use std::collections::HashMap;
     use std::io::{Seek, SeekFrom};
@@ -121,18 +122,40 @@ mod tests {
         assert_eq!(host.protocol, String::from("SFTP"));
         assert_eq!(host.username, String::from("root"));
         assert_eq!(*host.password.as_ref().unwrap(), String::from("mypassword"));
+        let algorithms: &SshAlgorithms = host.algorithms.as_ref().unwrap();
+        assert_eq!(
+            algorithms.kex.as_deref(),
+            Some("diffie-hellman-group14-sha1")
+        );
+        assert!(algorithms.host_key.is_none());
+        assert_eq!(algorithms.cipher.as_deref(), Some("aes128-ctr,aes128-cbc"));
+        assert!(algorithms.mac.is_none());
         let host: &Bookmark = hosts.bookmarks.get("msi-estrem").unwrap();
         assert_eq!(host.address, String::from("192.168.1.30"));
         assert_eq!(host.port, 22);
         assert_eq!(host.protocol, String::from("SFTP"));
         assert_eq!(host.username, String::from("cvisintin"));
         assert_eq!(*host.password.as_ref().unwrap(), String::from("mysecret"));
+        assert!(host.tag.is_none());
+        assert!(host.protected.is_none());
+        assert!(host.quiet_hours.is_none());
         let host: &Bookmark = hosts.bookmarks.get("aws-server-prod1").unwrap();
         assert_eq!(host.address, String::from("51.23.67.12"));
         assert_eq!(host.port, 21);
         assert_eq!(host.protocol, String::from("FTPS"));
         assert_eq!(host.username, String::from("aws001"));
         assert_eq!(host.password, None);
+        // S3 endpoint and algorithms are optional
+        assert!(host.algorithms.is_none());
+        assert!(host.endpoint.is_none());
+        assert!(host.region.is_none());
+        let tag: &BookmarkTag = host.tag.as_ref().unwrap();
+        assert_eq!(tag.color, TagColor::Red);
+        assert_eq!(tag.label.as_str(), "PRODUCTION");
+        assert_eq!(host.protected, Some(true));
+        assert_eq!(host.quiet_hours.as_deref(), Some("09:00-18:00"));
+        // Commands history is optional
+        assert!(hosts.commands.is_empty());
     }
 
     #[test]
@@ -157,6 +180,13 @@ mod tests {
                 protocol: String::from("SFTP"),
                 username: String::from("root"),
                 password: None,
+                endpoint: None,
+                region: None,
+                ephemeral: None,
+                algorithms: None,
+                tag: None,
+                protected: None,
+                quiet_hours: None,
             },
         );
         bookmarks.insert(
@@ -167,6 +197,13 @@ mod tests {
                 protocol: String::from("SFTP"),
                 username: String::from("cvisintin"),
                 password: Some(String::from("password")),
+                endpoint: None,
+                region: None,
+                ephemeral: None,
+                algorithms: None,
+                tag: None,
+                protected: None,
+                quiet_hours: None,
             },
         );
         let mut recents: HashMap<String, Bookmark> = HashMap::with_capacity(1);
@@ -178,12 +215,25 @@ mod tests {
                 protocol: String::from("SCP"),
                 username: String::from("omar"),
                 password: Some(String::from("aaa")),
+                endpoint: None,
+                region: None,
+                ephemeral: None,
+                algorithms: None,
+                tag: None,
+                protected: None,
+                quiet_hours: None,
             },
         );
         let tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
         // Serialize
         let deserializer: BookmarkSerializer = BookmarkSerializer {};
-        let hosts: UserHosts = UserHosts { bookmarks, recents };
+        let hosts: UserHosts = UserHosts {
+            bookmarks,
+            recents,
+            commands: HashMap::new(),
+            filters: HashMap::new(),
+            searches: HashMap::new(),
+        };
         assert!(deserializer.serialize(Box::new(tmpfile), &hosts).is_ok());
     }
 
@@ -192,9 +242,9 @@ mod tests {
         let mut tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
         let file_content: &str = r#"
         [bookmarks]
-        raspberrypi2 = { address = "192.168.1.31", port = 22, protocol = "SFTP", username = "root", password = "mypassword" }
+        raspberrypi2 = { address = "192.168.1.31", port = 22, protocol = "SFTP", username = "root", password = "mypassword", algorithms = { kex = "diffie-hellman-group14-sha1", cipher = "aes128-ctr,aes128-cbc" } }
         msi-estrem = { address = "192.168.1.30", port = 22, protocol = "SFTP", username = "cvisintin", password = "mysecret" }
-        aws-server-prod1 = { address = "51.23.67.12", port = 21, protocol = "FTPS", username = "aws001" }
+        aws-server-prod1 = { address = "51.23.67.12", port = 21, protocol = "FTPS", username = "aws001", tag = { color = "red", label = "PRODUCTION" }, protected = true, quiet_hours = "09:00-18:00" }
 
         [recents]
         ISO20201215T094000Z = { address = "172.16.104.10", port = 22, protocol = "SCP", username = "root" }
@@ -38,6 +38,7 @@ use crate::filetransfer::FileTransferProtocol;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
+use toml::Value;
 
 #[derive(Deserialize, Serialize, std::fmt::Debug)]
 /// ## UserConfig
@@ -47,6 +48,12 @@ use std::path::PathBuf;
 pub struct UserConfig {
     pub user_interface: UserInterfaceConfig,
     pub remote: RemoteConfig,
+    #[serde(default)]
+    pub network: NetworkConfig, // @! Since 0.5.0
+    #[serde(default)]
+    pub transfer: TransferConfig, // @! Since 0.5.0
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub extra: HashMap<String, HashMap<String, Value>>, // @! Since 0.5.0; Namespace => key/values (plugins, scripts...)
 }
 
 #[derive(Deserialize, Serialize, std::fmt::Debug)]
@@ -60,6 +67,9 @@ pub struct UserInterfaceConfig {
     pub check_for_updates: Option<bool>, // @! Since 0.3.3
     pub group_dirs: Option<String>,
     pub file_fmt: Option<String>,
+    pub color_mode: Option<String>,           // @! Since 0.5.0
+    pub reduced_motion: Option<bool>,         // @! Since 0.5.0
+    pub clipboard_clear_timeout: Option<u64>, // @! Since 0.5.0; Seconds; 0 means never
 }
 
 #[derive(Deserialize, Serialize, std::fmt::Debug)]
@@ -67,14 +77,116 @@ pub struct UserInterfaceConfig {
 ///
 /// Contains configuratio related to remote hosts
 pub struct RemoteConfig {
+    pub never_store_credentials: Option<bool>, // @! Since 0.5.0; NOTE: must precede tables in TOML
     pub ssh_keys: HashMap<String, PathBuf>, // Association between host name and path to private key
 }
 
+#[derive(Deserialize, Serialize, std::fmt::Debug, Default)]
+/// ## NetworkConfig
+///
+/// Contains configuration related to network connections
+pub struct NetworkConfig {
+    pub proxy: Option<String>,
+    pub connection_timeout: Option<u64>, // Seconds
+    pub keepalive_interval: Option<u64>, // Seconds; 0 means disabled
+    pub max_concurrency: Option<usize>,
+    pub bandwidth_limit: Option<u64>,   // KiB/s; 0 means unlimited
+    pub retries: Option<u32>,           // Retries of failed file transfers and directory operations
+    pub retry_backoff: Option<u64>, // Milliseconds before the first retry; doubled at each retry
+    pub address_family: Option<String>, // Address family tried first (any, ipv4, ipv6)
+    pub webhook_url: Option<String>, // Notified when the transfer queue has been processed
+    pub webhook_token: Option<String>, // Sent to the webhook as bearer token
+}
+
+#[derive(Deserialize, Serialize, std::fmt::Debug, Default)]
+/// ## TransferConfig
+///
+/// Contains configuration related to file transfers
+pub struct TransferConfig {
+    pub overwrite_policy: Option<String>,
+    pub preserve_timestamps: Option<bool>,
+    pub preserve_permissions: Option<bool>,
+    pub atomic_uploads: Option<bool>,
+    pub partial_files: Option<String>, // What to do with the target of aborted transfers (keep, remove, mark)
+    pub symlinks: Option<String>,      // How symlinks are transferred (follow, recreate, skip)
+    pub verify_checksums: Option<bool>,
+    pub skip_identical: Option<bool>, // Don't transfer files whose target has the same content
+    pub create_remote_dirs: Option<bool>, // Create the missing remote directories uploads are sent into
+    pub one_file_system: Option<bool>, // Don't cross file system boundaries in recursive operations
+    pub inhibit_sleep: Option<bool>,   // Keep the system awake while the transfer queue is running
+    pub compress_uploads: Option<bool>, // Gzip uploads on the fly and decompress them on the remote host
+    pub preallocate: Option<bool>, // Reserve the space of downloads on disk before writing them
+    pub durable_writes: Option<bool>, // Flush downloads to disk before reporting them as done
+    pub use_trash: Option<bool>,   // Move deleted entries to the trash, instead of removing them
+    pub trash_dir: Option<String>, // Trash directory on remote hosts; `~` is the home directory
+    pub upload_permissions: Option<String>, // Name of the permission template applied to uploaded files
+    pub bandwidth_schedule: Option<String>, // Bandwidth limits by time of the week (e.g. `mon-fri 09:00-18:00=1024`)
+    pub staging_dir: Option<String>, // Directory of the temporary files; the system one if unset
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_rules: Vec<String>, // Glob patterns of the entries not to transfer
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub permission_templates: HashMap<String, String>, // Name => `MODE [UID:GID]`; NOTE: must precede profiles
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub profiles: HashMap<String, TransferProfileConfig>, // Name => options; NOTE: must precede rules
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rules: Vec<TransferRuleConfig>, // Per-file options, by file name; NOTE: must follow tables
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hooks: Vec<TransferHookConfig>, // Commands run after successful transfers; NOTE: must follow tables
+}
+
+#[derive(Deserialize, Serialize, std::fmt::Debug, Default, Clone)]
+/// ## TransferRuleConfig
+///
+/// Transfer options applied to the files whose name matches `pattern`
+pub struct TransferRuleConfig {
+    pub pattern: String, // Glob pattern matched against the file name
+    pub overwrite_policy: Option<String>,
+    pub permissions: Option<String>, // Applied to uploaded files; permission template name or `MODE [UID:GID]`
+}
+
+#[derive(Deserialize, Serialize, std::fmt::Debug, Default, Clone)]
+/// ## TransferHookConfig
+///
+/// Command run after a file whose name matches `pattern` has been transferred successfully
+pub struct TransferHookConfig {
+    pub pattern: String,           // Glob pattern matched against the file name
+    pub direction: Option<String>, // upload, download; unset for both
+    pub command: String, // May contain the `{path}`, `{local}`, `{remote}` and `{name}` variables
+    pub remote: Option<bool>, // Run on the remote host instead of the local one
+}
+
+#[derive(Deserialize, Serialize, std::fmt::Debug, Default, Clone)]
+/// ## TransferProfileConfig
+///
+/// A named set of transfer options; unset keys fall back to the transfer configuration
+pub struct TransferProfileConfig {
+    pub overwrite_policy: Option<String>,
+    pub preserve_timestamps: Option<bool>,
+    pub preserve_permissions: Option<bool>,
+    pub atomic_uploads: Option<bool>,
+    pub partial_files: Option<String>,
+    pub symlinks: Option<String>,
+    pub verify_checksums: Option<bool>,
+    pub skip_identical: Option<bool>,
+    pub create_remote_dirs: Option<bool>,
+    pub one_file_system: Option<bool>,
+    pub inhibit_sleep: Option<bool>,
+    pub compress_uploads: Option<bool>,
+    pub preallocate: Option<bool>,
+    pub durable_writes: Option<bool>,
+    pub upload_permissions: Option<String>,
+    pub bandwidth_limit: Option<u64>, // KiB/s; 0 means unlimited
+    pub ignore_rules: Option<Vec<String>>,
+}
+
 impl Default for UserConfig {
     fn default() -> Self {
         UserConfig {
             user_interface: UserInterfaceConfig::default(),
             remote: RemoteConfig::default(),
+            network: NetworkConfig::default(),
+            transfer: TransferConfig::default(),
+            extra: HashMap::new(),
         }
     }
 }
@@ -91,6 +203,9 @@ impl Default for UserInterfaceConfig {
             check_for_updates: Some(true),
             group_dirs: None,
             file_fmt: None,
+            color_mode: None,
+            reduced_motion: None,
+            clipboard_clear_timeout: None,
         }
     }
 }
@@ -98,6 +213,7 @@ impl Default for UserInterfaceConfig {
 impl Default for RemoteConfig {
     fn default() -> Self {
         RemoteConfig {
+            never_store_credentials: None,
             ssh_keys: HashMap::new(),
         }
     }
@@ -171,7 +287,10 @@ mod tests {
             String::from("192.168.1.31"),
             PathBuf::from("/tmp/private.key"),
         );
-        let remote: RemoteConfig = RemoteConfig { ssh_keys: keys };
+        let remote: RemoteConfig = RemoteConfig {
+            never_store_credentials: Some(true),
+            ssh_keys: keys,
+        };
         let ui: UserInterfaceConfig = UserInterfaceConfig {
             default_protocol: String::from("SFTP"),
             text_editor: PathBuf::from("nano"),
@@ -179,10 +298,16 @@ mod tests {
             check_for_updates: Some(true),
             group_dirs: Some(String::from("first")),
             file_fmt: Some(String::from("{NAME}")),
+            color_mode: None,
+            reduced_motion: Some(true),
+            clipboard_clear_timeout: Some(10),
         };
         let cfg: UserConfig = UserConfig {
             user_interface: ui,
             remote: remote,
+            network: NetworkConfig::default(),
+            transfer: TransferConfig::default(),
+            extra: HashMap::new(),
         };
         assert_eq!(
             *cfg.remote
@@ -197,6 +322,7 @@ mod tests {
         assert_eq!(cfg.user_interface.check_for_updates, Some(true));
         assert_eq!(cfg.user_interface.group_dirs, Some(String::from("first")));
         assert_eq!(cfg.user_interface.file_fmt, Some(String::from("{NAME}")));
+        assert_eq!(cfg.remote.never_store_credentials, Some(true));
     }
 
     #[test]
@@ -209,6 +335,12 @@ mod tests {
         assert_eq!(cfg.user_interface.text_editor, PathBuf::from("vim"));
         assert_eq!(cfg.user_interface.check_for_updates.unwrap(), true);
         assert_eq!(cfg.remote.ssh_keys.len(), 0);
+        assert!(cfg.remote.never_store_credentials.is_none());
+        assert!(cfg.network.proxy.is_none());
+        assert!(cfg.network.connection_timeout.is_none());
+        assert!(cfg.transfer.overwrite_policy.is_none());
+        assert!(cfg.transfer.ignore_rules.is_empty());
+        assert!(cfg.transfer.profiles.is_empty());
     }
 
     #[test]
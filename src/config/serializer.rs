@@ -89,6 +89,7 @@ impl ConfigSerializer {
 mod tests {
 
     use super::*;
+    use crate::config::{TransferHookConfig, TransferProfileConfig, TransferRuleConfig};
 
     use std::io::{Seek, SeekFrom};
     use std::path::PathBuf;
@@ -115,7 +116,14 @@ mod tests {
             cfg.user_interface.file_fmt,
             Some(String::from("{NAME} {PEX}"))
         );
+        assert_eq!(
+            cfg.user_interface.color_mode,
+            Some(String::from("no-color"))
+        );
+        assert_eq!(cfg.user_interface.reduced_motion, Some(true));
+        assert_eq!(cfg.user_interface.clipboard_clear_timeout, Some(15));
         // Verify keys
+        assert_eq!(cfg.remote.never_store_credentials, Some(true));
         assert_eq!(
             *cfg.remote
                 .ssh_keys
@@ -131,6 +139,77 @@ mod tests {
             PathBuf::from("/home/omar/.ssh/beaglebone.key")
         );
         assert!(cfg.remote.ssh_keys.get(&String::from("1.1.1.1")).is_none());
+        // Verify network
+        assert_eq!(
+            cfg.network.proxy,
+            Some(String::from("http://proxy.example.com:3128"))
+        );
+        assert_eq!(cfg.network.connection_timeout, Some(10));
+        assert_eq!(cfg.network.keepalive_interval, Some(60));
+        assert_eq!(cfg.network.max_concurrency, Some(2));
+        assert_eq!(cfg.network.bandwidth_limit, Some(512));
+        assert_eq!(cfg.network.retries, Some(5));
+        assert_eq!(cfg.network.retry_backoff, Some(250));
+        assert_eq!(cfg.network.address_family, Some(String::from("ipv6")));
+        assert_eq!(
+            cfg.network.webhook_url,
+            Some(String::from("https://hooks.example.com/termscp"))
+        );
+        assert_eq!(cfg.network.webhook_token, Some(String::from("s3cr3t")));
+        // Verify transfer
+        assert_eq!(cfg.transfer.overwrite_policy, Some(String::from("skip")));
+        assert_eq!(cfg.transfer.preserve_timestamps, Some(true));
+        assert!(cfg.transfer.preserve_permissions.is_none());
+        assert!(cfg.transfer.atomic_uploads.is_none());
+        assert_eq!(cfg.transfer.partial_files, Some(String::from("mark")));
+        assert_eq!(cfg.transfer.symlinks, Some(String::from("recreate")));
+        assert_eq!(cfg.transfer.verify_checksums, Some(true));
+        assert_eq!(cfg.transfer.skip_identical, Some(true));
+        assert_eq!(cfg.transfer.create_remote_dirs, Some(true));
+        assert!(cfg.transfer.one_file_system.is_none());
+        assert!(cfg.transfer.inhibit_sleep.is_none());
+        assert!(cfg.transfer.compress_uploads.is_none());
+        assert_eq!(
+            cfg.transfer.ignore_rules,
+            vec![String::from("*.o"), String::from("node_modules/")]
+        );
+        assert_eq!(cfg.transfer.upload_permissions, Some(String::from("www")));
+        assert_eq!(
+            cfg.transfer.bandwidth_schedule,
+            Some(String::from("mon-fri 09:00-18:00=1024"))
+        );
+        assert_eq!(
+            cfg.transfer.permission_templates.get("www"),
+            Some(&String::from("644 33:33"))
+        );
+        let profile: &TransferProfileConfig = cfg.transfer.profiles.get("deploy").unwrap();
+        assert_eq!(profile.preserve_permissions, Some(true));
+        assert_eq!(profile.verify_checksums, Some(true));
+        assert!(profile.skip_identical.is_none());
+        assert_eq!(profile.bandwidth_limit, Some(2048));
+        assert_eq!(profile.ignore_rules, Some(vec![String::from(".git/")]));
+        assert!(profile.overwrite_policy.is_none());
+        assert_eq!(profile.partial_files, Some(String::from("keep")));
+        assert_eq!(cfg.transfer.rules.len(), 2);
+        assert_eq!(cfg.transfer.rules[0].pattern.as_str(), "*.jpg");
+        assert_eq!(
+            cfg.transfer.rules[0].overwrite_policy,
+            Some(String::from("skip-same-size"))
+        );
+        assert!(cfg.transfer.rules[0].permissions.is_none());
+        assert_eq!(cfg.transfer.rules[1].pattern.as_str(), "*.php");
+        assert_eq!(cfg.transfer.rules[1].permissions, Some(String::from("644")));
+        assert_eq!(cfg.transfer.hooks.len(), 1);
+        assert_eq!(cfg.transfer.hooks[0].pattern.as_str(), "nginx.conf");
+        assert_eq!(
+            cfg.transfer.hooks[0].direction,
+            Some(String::from("upload"))
+        );
+        assert_eq!(
+            cfg.transfer.hooks[0].command.as_str(),
+            "systemctl reload nginx"
+        );
+        assert_eq!(cfg.transfer.hooks[0].remote, Some(true));
     }
 
     #[test]
@@ -152,6 +231,11 @@ mod tests {
         assert_eq!(cfg.user_interface.group_dirs, None);
         assert!(cfg.user_interface.check_for_updates.is_none());
         assert_eq!(cfg.user_interface.file_fmt, None);
+        assert_eq!(cfg.user_interface.color_mode, None);
+        assert!(cfg.user_interface.reduced_motion.is_none());
+        assert!(cfg.user_interface.clipboard_clear_timeout.is_none());
+        assert!(cfg.network.proxy.is_none());
+        assert!(cfg.network.max_concurrency.is_none());
         // Verify keys
         assert_eq!(
             *cfg.remote
@@ -189,6 +273,29 @@ mod tests {
             String::from("192.168.1.31"),
             PathBuf::from("/home/omar/.ssh/id_rsa"),
         );
+        cfg.transfer.upload_permissions = Some(String::from("www"));
+        cfg.transfer
+            .permission_templates
+            .insert(String::from("www"), String::from("644 33:33"));
+        cfg.transfer.profiles.insert(
+            String::from("deploy"),
+            TransferProfileConfig {
+                verify_checksums: Some(true),
+                ignore_rules: Some(vec![String::from(".git/")]),
+                ..Default::default()
+            },
+        );
+        cfg.transfer.rules.push(TransferRuleConfig {
+            pattern: String::from("*.php"),
+            overwrite_policy: Some(String::from("overwrite")),
+            permissions: Some(String::from("644")),
+        });
+        cfg.transfer.hooks.push(TransferHookConfig {
+            pattern: String::from("*.conf"),
+            direction: None,
+            command: String::from("echo {path}"),
+            remote: None,
+        });
         // Serialize
         let serializer: ConfigSerializer = ConfigSerializer {};
         let writer: Box<dyn Write> = Box::new(std::fs::File::create(toml_file.path()).unwrap());
@@ -196,7 +303,10 @@ mod tests {
         // Reload configuration and check if it's ok
         toml_file.as_file().sync_all().unwrap();
         toml_file.as_file().seek(SeekFrom::Start(0)).unwrap();
-        assert!(serializer.deserialize(Box::new(toml_file)).is_ok());
+        let cfg: UserConfig = serializer.deserialize(Box::new(toml_file)).ok().unwrap();
+        assert_eq!(cfg.transfer.rules.len(), 1);
+        assert_eq!(cfg.transfer.hooks.len(), 1);
+        assert!(cfg.transfer.profiles.contains_key("deploy"));
     }
 
     fn create_good_toml() -> tempfile::NamedTempFile {
@@ -210,10 +320,65 @@ mod tests {
         check_for_updates = true
         group_dirs = "last"
         file_fmt = "{NAME} {PEX}"
+        color_mode = "no-color"
+        reduced_motion = true
+        clipboard_clear_timeout = 15
+
+        [remote]
+        never_store_credentials = true
 
         [remote.ssh_keys]
         "192.168.1.31" = "/home/omar/.ssh/raspberry.key"
         "192.168.1.32" = "/home/omar/.ssh/beaglebone.key"
+
+        [network]
+        proxy = "http://proxy.example.com:3128"
+        connection_timeout = 10
+        keepalive_interval = 60
+        max_concurrency = 2
+        bandwidth_limit = 512
+        retries = 5
+        retry_backoff = 250
+        address_family = "ipv6"
+        webhook_url = "https://hooks.example.com/termscp"
+        webhook_token = "s3cr3t"
+
+        [transfer]
+        overwrite_policy = "skip"
+        preserve_timestamps = true
+        partial_files = "mark"
+        symlinks = "recreate"
+        verify_checksums = true
+        skip_identical = true
+        create_remote_dirs = true
+        upload_permissions = "www"
+        bandwidth_schedule = "mon-fri 09:00-18:00=1024"
+        ignore_rules = ["*.o", "node_modules/"]
+
+        [transfer.permission_templates]
+        www = "644 33:33"
+
+        [transfer.profiles.deploy]
+        preserve_permissions = true
+        partial_files = "keep"
+        verify_checksums = true
+        bandwidth_limit = 2048
+        ignore_rules = [".git/"]
+
+        [[transfer.rules]]
+        pattern = "*.jpg"
+        overwrite_policy = "skip-same-size"
+
+        [[transfer.rules]]
+        pattern = "*.php"
+        overwrite_policy = "overwrite"
+        permissions = "644"
+
+        [[transfer.hooks]]
+        pattern = "nginx.conf"
+        direction = "upload"
+        command = "systemctl reload nginx"
+        remote = true
         "#;
         tmpfile.write_all(file_content.as_bytes()).unwrap();
         tmpfile
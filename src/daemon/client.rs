@@ -0,0 +1,204 @@
+//! ## Client
+//!
+//! `client` is the module which sends requests to a running daemon through its control socket
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::rpc::{EnqueueParams, IdParams, LogsParams, Request, Response, StatusParams};
+use super::{Job, LogRecord};
+// Ext
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+/// ## DaemonClient
+///
+/// DaemonClient sends JSON-RPC requests to the daemon and waits for their responses
+pub struct DaemonClient {
+    reader: BufReader<Box<dyn Read + Send>>,
+    writer: Box<dyn Write + Send>,
+    next_id: u64,
+}
+
+impl DaemonClient {
+    /// ### connect
+    ///
+    /// Connect to the daemon listening on `socket`
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    pub fn connect(socket: &Path) -> Result<DaemonClient, String> {
+        use std::os::unix::net::UnixStream;
+
+        let stream: UnixStream = UnixStream::connect(socket).map_err(|e| {
+            format!(
+                "Could not connect to daemon on \"{}\": {}",
+                socket.display(),
+                e
+            )
+        })?;
+        let reader: UnixStream = stream
+            .try_clone()
+            .map_err(|e| format!("Could not connect to daemon: {}", e))?;
+        Ok(DaemonClient {
+            reader: BufReader::new(Box::new(reader)),
+            writer: Box::new(stream),
+            next_id: 0,
+        })
+    }
+
+    #[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+    pub fn connect(_socket: &Path) -> Result<DaemonClient, String> {
+        Err(String::from("Daemon mode is not supported on this system"))
+    }
+
+    /// ### call
+    ///
+    /// Call `method` with `params` and return its result
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.next_id += 1;
+        let req: String = serde_json::to_string(&Request::new(self.next_id, method, params))
+            .map_err(|e| e.to_string())?;
+        writeln!(self.writer, "{}", req).map_err(|e| format!("Could not send request: {}", e))?;
+        let mut line: String = String::new();
+        match self.reader.read_line(&mut line) {
+            Ok(0) => return Err(String::from("Daemon closed the connection")),
+            Ok(_) => {}
+            Err(err) => return Err(format!("Could not read response: {}", err)),
+        }
+        let response: Response =
+            serde_json::from_str(line.as_str()).map_err(|e| format!("Bad response: {}", e))?;
+        match response.error {
+            Some(err) => Err(err.message),
+            None => Ok(response.result.unwrap_or(Value::Null)),
+        }
+    }
+
+    /// ### enqueue
+    ///
+    /// Enqueue a transfer. Returns its id
+    pub fn enqueue(&mut self, params: &EnqueueParams) -> Result<usize, String> {
+        let result: Value = self.call("enqueue", json!(params))?;
+        result
+            .get("id")
+            .and_then(|x| x.as_u64())
+            .map(|x| x as usize)
+            .ok_or_else(|| String::from("Bad response: missing transfer id"))
+    }
+
+    /// ### status
+    ///
+    /// Get all the transfers known by the daemon
+    pub fn status(&mut self) -> Result<Vec<Job>, String> {
+        let result: Value = self.call("status", json!(StatusParams::default()))?;
+        serde_json::from_value(result).map_err(|e| format!("Bad response: {}", e))
+    }
+
+    /// ### retry
+    ///
+    /// Reschedule a failed transfer
+    pub fn retry(&mut self, id: usize) -> Result<(), String> {
+        self.call("retry", json!(IdParams { id })).map(|_| ())
+    }
+
+    /// ### remove
+    ///
+    /// Remove a transfer which is not running
+    pub fn remove(&mut self, id: usize) -> Result<(), String> {
+        self.call("remove", json!(IdParams { id })).map(|_| ())
+    }
+
+    /// ### clear
+    ///
+    /// Remove completed transfers
+    pub fn clear(&mut self) -> Result<(), String> {
+        self.call("clear", Value::Null).map(|_| ())
+    }
+
+    /// ### logs
+    ///
+    /// Get the log records which came after the record `since`; all the records kept by the daemon if `None`
+    pub fn logs(&mut self, since: Option<u64>) -> Result<Vec<LogRecord>, String> {
+        let result: Value = self.call("logs", json!(LogsParams { since }))?;
+        serde_json::from_value(result).map_err(|e| format!("Bad response: {}", e))
+    }
+
+    /// ### shutdown
+    ///
+    /// Ask the daemon to terminate
+    pub fn shutdown(&mut self) -> Result<(), String> {
+        self.call("shutdown", Value::Null).map(|_| ())
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+mod tests {
+
+    use super::*;
+    use crate::daemon::{serve, DaemonState, JobState};
+    use crate::fs::queue::{Priority, TransferDirection};
+
+    use std::os::unix::net::UnixListener;
+    use std::path::PathBuf;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn test_daemon_client() {
+        let tmp_dir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let socket: PathBuf = tmp_dir.path().join("daemon.sock");
+        let listener: UnixListener = UnixListener::bind(socket.as_path()).unwrap();
+        let state: Arc<Mutex<DaemonState>> = Arc::new(Mutex::new(DaemonState::default()));
+        let server_state: Arc<Mutex<DaemonState>> = state.clone();
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            serve(stream, server_state);
+        });
+        let mut client: DaemonClient = DaemonClient::connect(socket.as_path()).unwrap();
+        let id: usize = client
+            .enqueue(&EnqueueParams {
+                bookmark: String::from("nas"),
+                direction: TransferDirection::Download,
+                source: PathBuf::from("/srv/backup.tar"),
+                destination: PathBuf::from("/tmp"),
+                priority: Priority::High,
+            })
+            .unwrap();
+        let jobs: Vec<Job> = client.status().unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].id, id);
+        assert_eq!(jobs[0].state, JobState::Pending);
+        assert!(client.retry(id).is_err());
+        client.remove(id).unwrap();
+        assert!(client.status().unwrap().is_empty());
+        client.clear().unwrap();
+        let logs: Vec<LogRecord> = client.logs(None).unwrap();
+        assert_eq!(logs.len(), 1);
+        assert!(client.logs(Some(logs[0].seq)).unwrap().is_empty());
+        client.shutdown().unwrap();
+        assert!(state.lock().unwrap().is_shutdown());
+        drop(client);
+        server.join().unwrap();
+    }
+}
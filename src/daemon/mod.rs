@@ -0,0 +1,479 @@
+//! ## Daemon
+//!
+//! `daemon` is the module which runs termscp in background: transfers are enqueued and monitored
+//! through a local control socket, which speaks JSON-RPC 2.0
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Mods
+pub mod client;
+pub mod rpc;
+mod worker;
+// Locals
+use crate::fs::queue::{Priority, TransferDirection};
+use crate::utils::fmt::fmt_log_pattern;
+// Ext
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// Amount of log records kept by the daemon; the oldest records are discarded
+const LOG_SIZE: usize = 256;
+
+/// ## JobState
+///
+/// Describes the state of a transfer enqueued in the daemon
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Pending,
+    Running,
+    Done,
+    Failed,
+}
+
+/// ## Job
+///
+/// A transfer enqueued in the daemon. `source` is transferred into the `destination` directory
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct Job {
+    pub id: usize,
+    pub bookmark: String,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub direction: TransferDirection,
+    pub priority: Priority,
+    pub state: JobState,
+    pub error: Option<String>, // Reason of the failure
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// ## LogLevel
+///
+/// Severity of a daemon log record
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+}
+
+/// ## LogRecord
+///
+/// A record of the daemon log. `seq` grows with each record, so that clients can fetch the new records only.
+/// Messages repeating the last record are collapsed into it: its `seq` is bumped, so that clients fetch it again
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct LogRecord {
+    pub seq: u64,
+    pub time: SystemTime,
+    pub level: LogLevel,
+    pub msg: String,
+    #[serde(default)]
+    pub repeated: u64, // Times the message has been repeated after this record
+    #[serde(default)]
+    pub similar: bool, // Whether the repeats only have the same pattern as `msg` (e.g. another file name)
+}
+
+/// ## DaemonState
+///
+/// State shared by the control socket connections and the transfer worker
+#[derive(Default)]
+pub struct DaemonState {
+    jobs: Vec<Job>,
+    next_id: usize,
+    logs: VecDeque<LogRecord>,
+    next_seq: u64,
+    shutdown: bool,
+}
+
+impl DaemonState {
+    /// ### enqueue
+    ///
+    /// Enqueue a new transfer. Returns the id of the new job
+    pub fn enqueue(
+        &mut self,
+        bookmark: &str,
+        source: &Path,
+        destination: &Path,
+        direction: TransferDirection,
+        priority: Priority,
+    ) -> usize {
+        let id: usize = self.next_id;
+        self.next_id += 1;
+        self.jobs.push(Job {
+            id,
+            bookmark: bookmark.to_string(),
+            source: source.to_path_buf(),
+            destination: destination.to_path_buf(),
+            direction,
+            priority,
+            state: JobState::Pending,
+            error: None,
+            bytes_done: 0,
+            bytes_total: 0,
+        });
+        self.log(
+            LogLevel::Info,
+            format!("Enqueued transfer {} ({})", id, source.display()),
+        );
+        id
+    }
+
+    /// ### jobs
+    ///
+    /// Get enqueued jobs in insertion order
+    pub fn jobs(&self) -> &[Job] {
+        self.jobs.as_slice()
+    }
+
+    /// ### get
+    ///
+    /// Get job by id
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.iter().find(|x| x.id == id)
+    }
+
+    /// ### remove
+    ///
+    /// Remove job with provided id. Running jobs can't be removed
+    pub fn remove(&mut self, id: usize) -> Result<Job, String> {
+        match self.jobs.iter().position(|x| x.id == id) {
+            Some(idx) if self.jobs[idx].state == JobState::Running => {
+                Err(format!("Transfer {} is running", id))
+            }
+            Some(idx) => Ok(self.jobs.remove(idx)),
+            None => Err(format!("No such transfer {}", id)),
+        }
+    }
+
+    /// ### retry
+    ///
+    /// Put a failed job back to pending. Returns whether the job has been rescheduled
+    pub fn retry(&mut self, id: usize) -> bool {
+        match self.get_mut(id) {
+            Some(job) if job.state == JobState::Failed => {
+                job.state = JobState::Pending;
+                job.error = None;
+                job.bytes_done = 0;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// ### clear_done
+    ///
+    /// Remove all the completed jobs
+    pub fn clear_done(&mut self) {
+        self.jobs.retain(|x| x.state != JobState::Done);
+    }
+
+    /// ### log
+    ///
+    /// Add a record to the daemon log; repeats of the last record are collapsed into it
+    pub fn log(&mut self, level: LogLevel, msg: String) {
+        self.next_seq += 1;
+        if let Some(last) = self.logs.back_mut() {
+            if last.level == level && fmt_log_pattern(last.msg.as_str()) == fmt_log_pattern(&msg) {
+                last.seq = self.next_seq;
+                last.repeated += 1;
+                last.similar |= last.msg != msg;
+                return;
+            }
+        }
+        if self.logs.len() >= LOG_SIZE {
+            self.logs.pop_front();
+        }
+        self.logs.push_back(LogRecord {
+            seq: self.next_seq,
+            time: SystemTime::now(),
+            level,
+            msg,
+            repeated: 0,
+            similar: false,
+        });
+    }
+
+    /// ### logs
+    ///
+    /// Iterate over the log records which came after the record `since`; all the records if `None`
+    pub fn logs(&self, since: Option<u64>) -> impl Iterator<Item = &LogRecord> + '_ {
+        self.logs
+            .iter()
+            .filter(move |x| since.map(|seq| x.seq > seq).unwrap_or(true))
+    }
+
+    /// ### request_shutdown
+    ///
+    /// Ask the daemon to terminate; the running transfer is interrupted
+    pub fn request_shutdown(&mut self) {
+        self.shutdown = true;
+    }
+
+    /// ### is_shutdown
+    ///
+    /// Returns whether the daemon must terminate
+    pub fn is_shutdown(&self) -> bool {
+        self.shutdown
+    }
+
+    /// ### start_next
+    ///
+    /// Mark the next pending job as running and return it.
+    /// Jobs with the highest priority come first; jobs with the same priority are served in insertion order
+    fn start_next(&mut self) -> Option<Job> {
+        let id: usize = self
+            .jobs
+            .iter()
+            .filter(|x| x.state == JobState::Pending)
+            .min_by_key(|x| (x.priority, x.id))
+            .map(|x| x.id)?;
+        let job: &mut Job = self.get_mut(id)?;
+        job.state = JobState::Running;
+        let job: Job = job.clone();
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Transferring {} ({}) to \"{}\"",
+                job.source.display(),
+                job.bookmark,
+                job.destination.display()
+            ),
+        );
+        Some(job)
+    }
+
+    /// ### set_progress
+    ///
+    /// Update the amount of bytes transferred by job
+    fn set_progress(&mut self, id: usize, done: u64, total: u64) {
+        if let Some(job) = self.get_mut(id) {
+            job.bytes_done = done;
+            job.bytes_total = total;
+        }
+    }
+
+    /// ### finish
+    ///
+    /// Set the outcome of a running job
+    fn finish(&mut self, id: usize, result: Result<(), String>) {
+        let (level, msg): (LogLevel, String) = match &result {
+            Ok(_) => (LogLevel::Info, format!("Transfer {} completed", id)),
+            Err(err) => (LogLevel::Error, format!("Transfer {} failed: {}", id, err)),
+        };
+        if let Some(job) = self.get_mut(id) {
+            match result {
+                Ok(_) => job.state = JobState::Done,
+                Err(err) => {
+                    job.state = JobState::Failed;
+                    job.error = Some(err);
+                }
+            }
+            self.log(level, msg);
+        }
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|x| x.id == id)
+    }
+}
+
+/// ### run
+///
+/// Run the daemon, listening for requests on `socket`, until it's asked to shut down.
+/// Bookmarks and configuration are read from `config_dir` each time a connection is established.
+/// SIGHUP is ignored, so that the daemon survives the terminal which started it
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+pub fn run(socket: &Path, config_dir: &Path) -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+    use std::time::Duration;
+
+    if socket.exists() {
+        if UnixStream::connect(socket).is_ok() {
+            return Err(format!(
+                "A daemon is already listening on \"{}\"",
+                socket.display()
+            ));
+        }
+        // Stale socket of a daemon which didn't terminate cleanly
+        let _ = std::fs::remove_file(socket);
+    }
+    let listener: UnixListener = UnixListener::bind(socket)
+        .map_err(|e| format!("Could not bind \"{}\": {}", socket.display(), e))?;
+    // Only the owner can control the daemon
+    std::fs::set_permissions(socket, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Could not set permissions of socket: {}", e))?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|e| format!("Could not configure socket: {}", e))?;
+    let _ = signal_hook::flag::register(libc::SIGHUP, Arc::new(AtomicBool::new(false)));
+    let state: Arc<Mutex<DaemonState>> = Arc::new(Mutex::new(DaemonState::default()));
+    let worker = {
+        let state: Arc<Mutex<DaemonState>> = state.clone();
+        let config_dir: PathBuf = config_dir.to_path_buf();
+        thread::spawn(move || worker::run(state, config_dir.as_path()))
+    };
+    tracing::info!("daemon listening on {}", socket.display());
+    while !state.lock().map(|x| x.is_shutdown()).unwrap_or(true) {
+        match listener.accept() {
+            Ok((stream, _)) => {
+                let state: Arc<Mutex<DaemonState>> = state.clone();
+                thread::spawn(move || serve(stream, state));
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100))
+            }
+            Err(err) => {
+                tracing::warn!("could not accept connection: {}", err);
+                thread::sleep(Duration::from_millis(100))
+            }
+        }
+    }
+    let _ = worker.join();
+    let _ = std::fs::remove_file(socket);
+    tracing::info!("daemon terminated");
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+pub fn run(_socket: &Path, _config_dir: &Path) -> Result<(), String> {
+    Err(String::from("Daemon mode is not supported on this system"))
+}
+
+/// ### serve
+///
+/// Answer the requests sent on `stream`, one JSON-RPC message per line, until the peer disconnects
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+fn serve(stream: std::os::unix::net::UnixStream, state: Arc<Mutex<DaemonState>>) {
+    use std::io::{BufRead, BufReader, Write};
+
+    // Accepted streams may inherit the non-blocking mode of the listener
+    let _ = stream.set_nonblocking(false);
+    let reader: BufReader<std::os::unix::net::UnixStream> = match stream.try_clone() {
+        Ok(s) => BufReader::new(s),
+        Err(_) => return,
+    };
+    let mut writer = stream;
+    for line in reader.lines() {
+        let line: String = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response: String = rpc::handle(&state, line.as_str());
+        if writeln!(writer, "{}", response).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_daemon_state() {
+        let mut state: DaemonState = DaemonState::default();
+        let a: usize = state.enqueue(
+            "nas",
+            Path::new("/home/omar/a.txt"),
+            Path::new("/srv"),
+            TransferDirection::Upload,
+            Priority::Normal,
+        );
+        let b: usize = state.enqueue(
+            "nas",
+            Path::new("/srv/b.txt"),
+            Path::new("/home/omar"),
+            TransferDirection::Download,
+            Priority::High,
+        );
+        assert_eq!(state.jobs().len(), 2);
+        // High priority first
+        assert_eq!(state.start_next().unwrap().id, b);
+        assert_eq!(state.get(b).unwrap().state, JobState::Running);
+        assert!(state.remove(b).is_err());
+        state.set_progress(b, 512, 1024);
+        assert_eq!(state.get(b).unwrap().bytes_done, 512);
+        state.finish(b, Err(String::from("timeout")));
+        assert_eq!(state.get(b).unwrap().state, JobState::Failed);
+        assert_eq!(state.get(b).unwrap().error.as_deref(), Some("timeout"));
+        assert_eq!(state.start_next().unwrap().id, a);
+        state.finish(a, Ok(()));
+        assert!(state.start_next().is_none());
+        // Retry
+        assert!(!state.retry(a));
+        assert!(state.retry(b));
+        assert_eq!(state.get(b).unwrap().state, JobState::Pending);
+        assert!(state.get(b).unwrap().error.is_none());
+        // Cleanup
+        state.clear_done();
+        assert!(state.get(a).is_none());
+        assert_eq!(state.remove(b).unwrap().id, b);
+        assert!(state.remove(b).is_err());
+        // Log
+        let seqs: Vec<u64> = state.logs(None).map(|x| x.seq).collect();
+        assert_eq!(seqs, vec![1, 2, 3, 4, 5, 6]);
+        let record: &LogRecord = state.logs(None).nth(3).unwrap();
+        assert_eq!(record.level, LogLevel::Error);
+        assert_eq!(record.msg.as_str(), "Transfer 1 failed: timeout");
+        assert_eq!(state.logs(Some(4)).count(), 2);
+        assert_eq!(state.logs(Some(6)).count(), 0);
+        for i in 0..LOG_SIZE {
+            state.log(LogLevel::Info, format!("foo {}", i));
+        }
+        assert_eq!(state.logs(None).count(), LOG_SIZE);
+        assert_eq!(state.logs(None).next().unwrap().seq, 7);
+        // Repeats
+        state.log(LogLevel::Warn, String::from("Could not open \"a\""));
+        state.log(LogLevel::Warn, String::from("Could not open \"a\""));
+        let record: &LogRecord = state.logs(None).last().unwrap();
+        assert_eq!(record.repeated, 1);
+        assert!(!record.similar);
+        assert_eq!(record.seq, 6 + LOG_SIZE as u64 + 2);
+        assert_eq!(state.logs(Some(6 + LOG_SIZE as u64 + 1)).count(), 1);
+        state.log(LogLevel::Warn, String::from("Could not open \"b\""));
+        let record: &LogRecord = state.logs(None).last().unwrap();
+        assert_eq!(record.msg.as_str(), "Could not open \"a\"");
+        assert_eq!(record.repeated, 2);
+        assert!(record.similar);
+        state.log(LogLevel::Error, String::from("Could not open \"b\""));
+        assert_eq!(state.logs(None).last().unwrap().repeated, 0);
+        // Shutdown
+        assert!(!state.is_shutdown());
+        state.request_shutdown();
+        assert!(state.is_shutdown());
+    }
+}
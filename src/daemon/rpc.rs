@@ -0,0 +1,372 @@
+//! ## Rpc
+//!
+//! `rpc` is the module which implements the JSON-RPC 2.0 methods exposed by the daemon control socket
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{DaemonState, Job, LogRecord};
+use crate::fs::queue::{Priority, TransferDirection};
+// Ext
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+// Error codes
+pub const PARSE_ERROR: i64 = -32700;
+pub const INVALID_REQUEST: i64 = -32600;
+pub const METHOD_NOT_FOUND: i64 = -32601;
+pub const INVALID_PARAMS: i64 = -32602;
+pub const INTERNAL_ERROR: i64 = -32603;
+pub const JOB_ERROR: i64 = 1; // The request is valid, but can't be applied to the transfer
+
+/// ## Request
+///
+/// A JSON-RPC request
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+/// ## Response
+///
+/// A JSON-RPC response; either `result` or `error` is set
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+/// ## RpcError
+///
+/// The error object of a JSON-RPC response
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+/// ## EnqueueParams
+///
+/// Params of the `enqueue` method
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EnqueueParams {
+    pub bookmark: String,
+    pub direction: TransferDirection,
+    pub source: PathBuf,
+    pub destination: PathBuf, // Directory the source is transferred into
+    #[serde(default = "EnqueueParams::default_priority")]
+    pub priority: Priority,
+}
+
+impl EnqueueParams {
+    fn default_priority() -> Priority {
+        Priority::Normal
+    }
+}
+
+/// ## IdParams
+///
+/// Params of the methods which refer to a single transfer
+#[derive(Debug, Deserialize, Serialize)]
+pub struct IdParams {
+    pub id: usize,
+}
+
+/// ## StatusParams
+///
+/// Params of the `status` method; all the transfers are reported if `id` is not set
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct StatusParams {
+    #[serde(default)]
+    pub id: Option<usize>,
+}
+
+/// ## LogsParams
+///
+/// Params of the `logs` method; all the records are reported if `since` is not set
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct LogsParams {
+    #[serde(default)]
+    pub since: Option<u64>, // Sequence number of the last record known by the client
+}
+
+impl Request {
+    /// ### new
+    ///
+    /// Instantiates a new `Request`
+    pub fn new(id: u64, method: &str, params: Value) -> Request {
+        Request {
+            jsonrpc: String::from("2.0"),
+            id: Value::from(id),
+            method: method.to_string(),
+            params,
+        }
+    }
+}
+
+impl Response {
+    fn ok(id: Value, result: Value) -> Response {
+        Response {
+            jsonrpc: String::from("2.0"),
+            id,
+            result: Some(result),
+            error: None,
+        }
+    }
+
+    fn error(id: Value, code: i64, message: String) -> Response {
+        Response {
+            jsonrpc: String::from("2.0"),
+            id,
+            result: None,
+            error: Some(RpcError { code, message }),
+        }
+    }
+}
+
+/// ### handle
+///
+/// Handle a request serialized as JSON and return the serialized response.
+///
+/// Methods:
+///
+/// - `enqueue` (`EnqueueParams`): enqueue a transfer; returns `{ "id": <id> }`
+/// - `status` (`StatusParams`): returns the transfers, or the transfer with the provided id
+/// - `retry` (`IdParams`): reschedule a failed transfer
+/// - `remove` (`IdParams`): remove a transfer which is not running; returns the removed transfer
+/// - `clear`: remove completed transfers
+/// - `logs` (`LogsParams`): returns the daemon log records
+/// - `shutdown`: interrupt the running transfer and terminate the daemon
+pub fn handle(state: &Mutex<DaemonState>, line: &str) -> String {
+    let response: Response = match serde_json::from_str::<Value>(line) {
+        Err(err) => Response::error(Value::Null, PARSE_ERROR, err.to_string()),
+        Ok(value) => match serde_json::from_value::<Request>(value) {
+            Ok(req) if req.jsonrpc == "2.0" => dispatch(state, req),
+            Ok(req) => Response::error(
+                req.id,
+                INVALID_REQUEST,
+                String::from("Unsupported JSON-RPC version"),
+            ),
+            Err(err) => Response::error(Value::Null, INVALID_REQUEST, err.to_string()),
+        },
+    };
+    serde_json::to_string(&response).unwrap_or_default()
+}
+
+/// ### dispatch
+///
+/// Call the method of `req`
+fn dispatch(state: &Mutex<DaemonState>, req: Request) -> Response {
+    let mut state = match state.lock() {
+        Ok(state) => state,
+        Err(_) => {
+            return Response::error(
+                req.id,
+                INTERNAL_ERROR,
+                String::from("Daemon state is poisoned"),
+            )
+        }
+    };
+    let result: Result<Value, RpcError> = match req.method.as_str() {
+        "enqueue" => parse_params::<EnqueueParams>(req.params).map(|p| {
+            let id: usize = state.enqueue(
+                p.bookmark.as_str(),
+                p.source.as_path(),
+                p.destination.as_path(),
+                p.direction,
+                p.priority,
+            );
+            json!({ "id": id })
+        }),
+        "status" => parse_params::<StatusParams>(req.params).and_then(|p| match p.id {
+            Some(id) => state
+                .get(id)
+                .map(|x| json!(x))
+                .ok_or_else(|| job_error(format!("No such transfer {}", id))),
+            None => Ok(json!(state.jobs())),
+        }),
+        "retry" => parse_params::<IdParams>(req.params).and_then(|p| match state.retry(p.id) {
+            true => Ok(Value::Null),
+            false => Err(job_error(format!("Transfer {} hasn't failed", p.id))),
+        }),
+        "remove" => parse_params::<IdParams>(req.params)
+            .and_then(|p| state.remove(p.id).map(|x: Job| json!(x)).map_err(job_error)),
+        "clear" => {
+            state.clear_done();
+            Ok(Value::Null)
+        }
+        "logs" => parse_params::<LogsParams>(req.params)
+            .map(|p| json!(state.logs(p.since).collect::<Vec<&LogRecord>>())),
+        "shutdown" => {
+            state.request_shutdown();
+            Ok(Value::Null)
+        }
+        method => Err(RpcError {
+            code: METHOD_NOT_FOUND,
+            message: format!("Unknown method \"{}\"", method),
+        }),
+    };
+    match result {
+        Ok(result) => Response::ok(req.id, result),
+        Err(err) => Response::error(req.id, err.code, err.message),
+    }
+}
+
+/// ### parse_params
+///
+/// Deserialize request params; missing params are treated as an empty object
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    let params: Value = match params {
+        Value::Null => json!({}),
+        params => params,
+    };
+    serde_json::from_value(params).map_err(|e| RpcError {
+        code: INVALID_PARAMS,
+        message: e.to_string(),
+    })
+}
+
+fn job_error(message: String) -> RpcError {
+    RpcError {
+        code: JOB_ERROR,
+        message,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::daemon::JobState;
+
+    fn call(state: &Mutex<DaemonState>, method: &str, params: Value) -> Response {
+        let req: String = serde_json::to_string(&Request::new(1, method, params)).unwrap();
+        serde_json::from_str(handle(state, req.as_str()).as_str()).unwrap()
+    }
+
+    #[test]
+    fn test_daemon_rpc_methods() {
+        let state: Mutex<DaemonState> = Mutex::new(DaemonState::default());
+        // Enqueue
+        let res: Response = call(
+            &state,
+            "enqueue",
+            json!({
+                "bookmark": "nas",
+                "direction": "upload",
+                "source": "/home/omar/a.txt",
+                "destination": "/srv"
+            }),
+        );
+        assert_eq!(res.id, json!(1));
+        assert!(res.error.is_none());
+        assert_eq!(res.result.unwrap(), json!({ "id": 0 }));
+        // Status
+        let jobs: Vec<Job> =
+            serde_json::from_value(call(&state, "status", Value::Null).result.unwrap()).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].bookmark, "nas");
+        assert_eq!(jobs[0].direction, TransferDirection::Upload);
+        assert_eq!(jobs[0].priority, Priority::Normal);
+        assert_eq!(jobs[0].state, JobState::Pending);
+        let job: Job =
+            serde_json::from_value(call(&state, "status", json!({ "id": 0 })).result.unwrap())
+                .unwrap();
+        assert_eq!(job.source, PathBuf::from("/home/omar/a.txt"));
+        assert_eq!(
+            call(&state, "status", json!({ "id": 4 }))
+                .error
+                .unwrap()
+                .code,
+            JOB_ERROR
+        );
+        // Retry a pending transfer
+        assert_eq!(
+            call(&state, "retry", json!({ "id": 0 }))
+                .error
+                .unwrap()
+                .code,
+            JOB_ERROR
+        );
+        // Remove
+        assert!(call(&state, "remove", json!({ "id": 0 })).error.is_none());
+        assert!(state.lock().unwrap().jobs().is_empty());
+        assert!(call(&state, "clear", Value::Null).error.is_none());
+        // Logs
+        let records: Vec<LogRecord> =
+            serde_json::from_value(call(&state, "logs", Value::Null).result.unwrap()).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].seq, 1);
+        assert_eq!(
+            records[0].msg.as_str(),
+            "Enqueued transfer 0 (/home/omar/a.txt)"
+        );
+        let records: Vec<LogRecord> =
+            serde_json::from_value(call(&state, "logs", json!({ "since": 1 })).result.unwrap())
+                .unwrap();
+        assert!(records.is_empty());
+        // Shutdown
+        assert!(call(&state, "shutdown", Value::Null).error.is_none());
+        assert!(state.lock().unwrap().is_shutdown());
+    }
+
+    #[test]
+    fn test_daemon_rpc_errors() {
+        let state: Mutex<DaemonState> = Mutex::new(DaemonState::default());
+        let res: Response = serde_json::from_str(handle(&state, "{ bad json").as_str()).unwrap();
+        assert_eq!(res.error.unwrap().code, PARSE_ERROR);
+        let res: Response = serde_json::from_str(
+            handle(&state, r#"{"jsonrpc":"1.0","id":3,"method":"status"}"#).as_str(),
+        )
+        .unwrap();
+        assert_eq!(res.id, json!(3));
+        assert_eq!(res.error.unwrap().code, INVALID_REQUEST);
+        assert_eq!(
+            call(&state, "omar", Value::Null).error.unwrap().code,
+            METHOD_NOT_FOUND
+        );
+        assert_eq!(
+            call(&state, "enqueue", json!({ "bookmark": "nas" }))
+                .error
+                .unwrap()
+                .code,
+            INVALID_PARAMS
+        );
+        assert_eq!(
+            call(&state, "remove", Value::Null).error.unwrap().code,
+            INVALID_PARAMS
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! ## Worker
+//!
+//! `worker` is the module which runs the transfers enqueued in the daemon
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{DaemonState, Job, LogLevel};
+use crate::filetransfer::builder::FileTransferBuilder;
+use crate::filetransfer::pool::transfer_file;
+use crate::filetransfer::FileTransfer;
+use crate::fs::queue::TransferDirection;
+use crate::fs::transfer::{CancelToken, SymlinkPolicy, TransferOptions};
+use crate::fs::{FsEntry, FsFile};
+use crate::host::Localhost;
+use crate::system::bookmarks_client::BookmarksClient;
+use crate::system::config_client::ConfigClient;
+use crate::system::environment;
+use crate::system::power::WakeLock;
+use crate::system::sshkey_storage::SshKeyStorage;
+use crate::system::transfer_history::{HistoryRecord, TransferHistory, TransferResult};
+use crate::system::webhook::{BatchSummary, TransferSummary, Webhook};
+// Ext
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// ## Step
+///
+/// An operation of a planned transfer
+enum Step {
+    Mkdir(PathBuf),            // Create directory on the target side
+    File(FsFile, PathBuf),     // Transfer file to the target path
+    Symlink(PathBuf, PathBuf), // Create a symlink on the target side, pointing to the second path
+}
+
+/// ### run
+///
+/// Transfer the pending jobs, until the daemon is asked to shut down.
+/// Connections are kept open between jobs towards the same bookmark
+pub(super) fn run(state: Arc<Mutex<DaemonState>>, config_dir: &Path) {
+    let mut sessions: HashMap<String, Box<dyn FileTransfer>> = HashMap::new();
+    let mut wake_lock: Option<WakeLock> = None;
+    let mut batch: Vec<TransferSummary> = Vec::new(); // Transfers processed since the queue was last idle
+    loop {
+        let next: Option<Job> = match state.lock() {
+            Ok(mut state) if !state.is_shutdown() => state.start_next(),
+            _ => break,
+        };
+        let job: Job = match next {
+            Some(job) => job,
+            None => {
+                if !batch.is_empty() {
+                    notify_webhook(&state, config_dir, std::mem::take(&mut batch));
+                }
+                // Allow sleep while idle
+                wake_lock = None;
+                thread::sleep(Duration::from_millis(250));
+                continue;
+            }
+        };
+        // Configuration is reloaded for each job, so that changes apply without restarting the daemon
+        let config: Option<ConfigClient> = load_config(config_dir);
+        if wake_lock.is_none() && config.as_ref().map(|x| x.get_inhibit_sleep()) == Some(true) {
+            wake_lock = match WakeLock::acquire("Transferring files") {
+                Ok(lock) => Some(lock),
+                Err(err) => {
+                    tracing::warn!("could not inhibit system sleep: {}", err);
+                    if let Ok(mut state) = state.lock() {
+                        state.log(
+                            LogLevel::Warn,
+                            format!("Could not inhibit system sleep: {}", err),
+                        );
+                    }
+                    None
+                }
+            };
+        }
+        let opts: TransferOptions = config
+            .as_ref()
+            .map(|x| x.get_transfer_options())
+            .unwrap_or_default();
+        let started: Instant = Instant::now();
+        let mut transferred: u64 = 0;
+        let result: Result<(), String> = match sessions.contains_key(&job.bookmark) {
+            true => Ok(()),
+            false => connect(job.bookmark.as_str(), config_dir, config.as_ref())
+                .map(|client| drop(sessions.insert(job.bookmark.clone(), client))),
+        }
+        .and_then(|_| {
+            let client: &mut dyn FileTransfer = sessions.get_mut(&job.bookmark).unwrap().as_mut();
+            run_job(client, &job, &opts, &mut |done, total| match state.lock() {
+                Ok(mut state) => {
+                    transferred = done;
+                    state.set_progress(job.id, done, total);
+                    !state.is_shutdown()
+                }
+                Err(_) => false,
+            })
+        });
+        match result.as_ref() {
+            Ok(_) => tracing::info!("transfer {} completed", job.id),
+            Err(err) => {
+                tracing::warn!("transfer {} failed: {}", job.id, err);
+                // Reconnect for the next job, in case the connection has been lost
+                if let Some(mut client) = sessions.remove(&job.bookmark) {
+                    let _ = client.disconnect();
+                }
+            }
+        }
+        record_history(config_dir, &job, transferred, started.elapsed(), &result);
+        batch.push(TransferSummary {
+            host: job.bookmark.clone(),
+            source: job.source.clone(),
+            destination: job.destination.clone(),
+            direction: job.direction,
+            error: result.as_ref().err().cloned(),
+        });
+        if let Ok(mut state) = state.lock() {
+            state.finish(job.id, result);
+        }
+    }
+    for (_, mut client) in sessions.drain() {
+        let _ = client.disconnect();
+    }
+}
+
+/// ### load_config
+///
+/// Load configuration from `config_dir`, if possible
+fn load_config(config_dir: &Path) -> Option<ConfigClient> {
+    let (config_path, ssh_keys_path): (PathBuf, PathBuf) =
+        environment::get_config_paths(config_dir);
+    ConfigClient::new(config_path.as_path(), ssh_keys_path.as_path()).ok()
+}
+
+/// ### record_history
+///
+/// Append the outcome of `job` to the transfer history
+fn record_history(
+    config_dir: &Path,
+    job: &Job,
+    size: u64,
+    duration: Duration,
+    result: &Result<(), String>,
+) {
+    let (local, remote): (&Path, &Path) = match job.direction {
+        TransferDirection::Upload => (job.source.as_path(), job.destination.as_path()),
+        TransferDirection::Download => (job.destination.as_path(), job.source.as_path()),
+    };
+    let record: HistoryRecord = HistoryRecord::new(
+        job.direction,
+        job.bookmark.as_str(),
+        local,
+        remote,
+        size,
+        duration,
+        match result {
+            Ok(_) => TransferResult::Completed,
+            Err(err) => TransferResult::Failed(err.clone()),
+        },
+    );
+    let history: TransferHistory =
+        TransferHistory::new(environment::get_transfer_history_path(config_dir).as_path());
+    if let Err(err) = history.append(&record) {
+        tracing::warn!("could not write transfer history: {}", err);
+    }
+}
+
+/// ### notify_webhook
+///
+/// Post the summary of `batch` to the webhook, if configured, and log the outcome
+fn notify_webhook(state: &Arc<Mutex<DaemonState>>, config_dir: &Path, batch: Vec<TransferSummary>) {
+    let config: ConfigClient = match load_config(config_dir) {
+        Some(config) => config,
+        None => return,
+    };
+    let webhook: Webhook = match config.get_webhook() {
+        Some(webhook) => webhook,
+        None => return,
+    };
+    let (level, msg): (LogLevel, String) = match webhook.notify(
+        &BatchSummary::new(batch, false),
+        &config.get_network_options(),
+    ) {
+        Ok(_) => (LogLevel::Info, String::from("Notified webhook")),
+        Err(err) => {
+            tracing::warn!("could not notify webhook: {}", err);
+            (LogLevel::Warn, format!("Could not notify webhook: {}", err))
+        }
+    };
+    if let Ok(mut state) = state.lock() {
+        state.log(level, msg);
+    }
+}
+
+/// ### connect
+///
+/// Connect to the host saved as `bookmark`.
+/// The daemon can't prompt for passwords, so the bookmark must either store it or use a SSH key
+fn connect(
+    bookmark: &str,
+    config_dir: &Path,
+    config: Option<&ConfigClient>,
+) -> Result<Box<dyn FileTransfer>, String> {
+    let bookmarks_file: PathBuf = environment::get_bookmarks_paths(config_dir);
+    let bookmarks: BookmarksClient = BookmarksClient::new(bookmarks_file.as_path(), config_dir, 16)
+        .map_err(|e| format!("Could not load bookmarks: {}", e))?;
+    let (address, port, protocol, username, password) = bookmarks
+        .get_bookmark(bookmark)
+        .ok_or_else(|| format!("No such bookmark \"{}\"", bookmark))?;
+    let (endpoint, region) = bookmarks
+        .get_bookmark_s3_endpoint(bookmark)
+        .unwrap_or_default();
+    let mut client: Box<dyn FileTransfer> = FileTransferBuilder::new(protocol)
+        .with_ssh_key_storage(match config {
+            Some(cli) => SshKeyStorage::storage_from_config(cli),
+            None => SshKeyStorage::empty(),
+        })
+        .with_known_hosts(Some(environment::get_known_hosts_path(config_dir)))
+        .with_network_options(config.map(|x| x.get_network_options()).unwrap_or_default())
+        .with_ssh_algorithms(
+            bookmarks
+                .get_bookmark_ssh_algorithms(bookmark)
+                .unwrap_or_default(),
+        )
+        .with_s3_endpoint(endpoint, region)
+        .build();
+    let username: Option<String> = match username.is_empty() {
+        true => None,
+        false => Some(username),
+    };
+    client
+        .connect(address, port, username, password)
+        .map_err(|e| format!("Could not connect to \"{}\": {}", bookmark, e))?;
+    Ok(client)
+}
+
+/// ### run_job
+///
+/// Transfer `job` with `client`, applying `opts`: entries matching the ignore rules are skipped,
+/// uploads are atomic if enabled and the partial files policy is applied if the job is interrupted.
+/// `on_progress` is called with the bytes transferred and the total bytes to transfer;
+/// the transfer is interrupted if it returns `false`
+fn run_job(
+    client: &mut dyn FileTransfer,
+    job: &Job,
+    opts: &TransferOptions,
+    on_progress: &mut dyn FnMut(u64, u64) -> bool,
+) -> Result<(), String> {
+    let mut steps: Vec<Step> = Vec::new();
+    match job.direction {
+        TransferDirection::Upload => {
+            let host: Localhost = Localhost::new(
+                job.source
+                    .parent()
+                    .unwrap_or(job.source.as_path())
+                    .to_path_buf(),
+            )
+            .map_err(|e| format!("Could not access \"{}\": {}", job.source.display(), e))?;
+            let entry: FsEntry = host
+                .stat(job.source.as_path())
+                .map_err(|e| format!("Could not stat \"{}\": {}", job.source.display(), e))?;
+            plan_upload(&host, &entry, job.destination.as_path(), opts, &mut steps)?;
+        }
+        TransferDirection::Download => {
+            let entry: FsEntry = client
+                .stat(job.source.as_path())
+                .map_err(|e| format!("Could not stat \"{}\": {}", job.source.display(), e))?;
+            plan_download(client, &entry, job.destination.as_path(), opts, &mut steps)?;
+        }
+    }
+    let total: u64 = steps
+        .iter()
+        .map(|x| match x {
+            Step::File(file, _) => file.size as u64,
+            Step::Mkdir(_) | Step::Symlink(_, _) => 0,
+        })
+        .sum();
+    let mut done: u64 = 0;
+    if !on_progress(done, total) {
+        return Err(String::from("Transfer interrupted"));
+    }
+    let cancel: CancelToken = CancelToken::default();
+    for step in steps.iter() {
+        match (job.direction, step) {
+            (TransferDirection::Upload, Step::Mkdir(dir)) => {
+                if !matches!(client.stat(dir.as_path()), Ok(FsEntry::Directory(_))) {
+                    client
+                        .mkdir(dir.as_path())
+                        .map_err(|e| format!("Could not create \"{}\": {}", dir.display(), e))?;
+                }
+            }
+            (TransferDirection::Download, Step::Mkdir(dir)) => std::fs::create_dir_all(dir)
+                .map_err(|e| format!("Could not create \"{}\": {}", dir.display(), e))?,
+            (TransferDirection::Upload, Step::Symlink(path, target)) => client
+                .symlink(path.as_path(), target.as_path())
+                .map_err(|e| format!("Could not create \"{}\": {}", path.display(), e))?,
+            (TransferDirection::Download, Step::Symlink(path, target)) => {
+                local_symlink(path.as_path(), target.as_path())
+                    .map_err(|e| format!("Could not create \"{}\": {}", path.display(), e))?
+            }
+            (_, Step::File(file, target)) => transfer_file(
+                client,
+                job.direction,
+                file,
+                target.as_path(),
+                &cancel,
+                opts,
+                &mut |bytes| {
+                    done += bytes as u64;
+                    if !on_progress(done, total) {
+                        cancel.cancel();
+                    }
+                },
+            )?,
+        }
+    }
+    Ok(())
+}
+
+/// ### plan_upload
+///
+/// Push the steps to upload local `entry` into `dest_dir`.
+/// Symbolic links are transferred according to the symlink policy
+fn plan_upload(
+    host: &Localhost,
+    entry: &FsEntry,
+    dest_dir: &Path,
+    opts: &TransferOptions,
+    steps: &mut Vec<Step>,
+) -> Result<(), String> {
+    if opts.ignore.is_ignored(entry) {
+        return Ok(());
+    }
+    let target: PathBuf = dest_dir.join(entry.get_name());
+    if !opts.symlinks.follows(entry) {
+        plan_symlink(entry, target, opts.symlinks, steps);
+        return Ok(());
+    }
+    match entry {
+        FsEntry::File(file) => steps.push(Step::File(file.clone(), target)),
+        FsEntry::Directory(dir) => {
+            let children: Vec<FsEntry> = host
+                .scan_dir(dir.abs_path.as_path())
+                .map_err(|e| format!("Could not scan \"{}\": {}", dir.abs_path.display(), e))?;
+            steps.push(Step::Mkdir(target.clone()));
+            for child in children.iter() {
+                plan_upload(host, child, target.as_path(), opts, steps)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ### plan_download
+///
+/// Push the steps to download remote `entry` into local `dest_dir`.
+/// Symbolic links are transferred according to the symlink policy
+fn plan_download(
+    client: &mut dyn FileTransfer,
+    entry: &FsEntry,
+    dest_dir: &Path,
+    opts: &TransferOptions,
+    steps: &mut Vec<Step>,
+) -> Result<(), String> {
+    if opts.ignore.is_ignored(entry) {
+        return Ok(());
+    }
+    let target: PathBuf = dest_dir.join(entry.get_name());
+    if !opts.symlinks.follows(entry) {
+        plan_symlink(entry, target, opts.symlinks, steps);
+        return Ok(());
+    }
+    match entry {
+        FsEntry::File(file) => steps.push(Step::File(file.clone(), target)),
+        FsEntry::Directory(dir) => {
+            let children: Vec<FsEntry> = client
+                .list_dir(dir.abs_path.as_path())
+                .map_err(|e| format!("Could not scan \"{}\": {}", dir.abs_path.display(), e))?;
+            steps.push(Step::Mkdir(target.clone()));
+            for child in children.iter() {
+                plan_download(client, child, target.as_path(), opts, steps)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ### plan_symlink
+///
+/// Push the step to recreate the symlink `entry` at `target`, if the policy is to recreate links;
+/// otherwise the link is skipped
+fn plan_symlink(entry: &FsEntry, target: PathBuf, policy: SymlinkPolicy, steps: &mut Vec<Step>) {
+    if let (SymlinkPolicy::Recreate, Some(link)) = (policy, entry.get_link_target()) {
+        steps.push(Step::Symlink(target, link));
+    }
+}
+
+/// ### local_symlink
+///
+/// Create a symlink at local `path`, pointing to `target`
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+fn local_symlink(path: &Path, target: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(target, path)
+}
+
+#[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+fn local_symlink(_path: &Path, _target: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Other,
+        "symlinks are not supported on this system",
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::daemon::JobState;
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    use crate::fs::queue::Priority;
+    use crate::fs::transfer::IgnoreRules;
+
+    fn make_job(source: &Path, destination: &Path, direction: TransferDirection) -> Job {
+        Job {
+            id: 0,
+            bookmark: String::from("memory"),
+            source: source.to_path_buf(),
+            destination: destination.to_path_buf(),
+            direction,
+            priority: Priority::Normal,
+            state: JobState::Running,
+            error: None,
+            bytes_done: 0,
+            bytes_total: 0,
+        }
+    }
+
+    #[test]
+    fn test_daemon_worker_run_job() {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::new();
+        client.connect(String::new(), 0, None, None).unwrap();
+        client.add_dir(Path::new("/srv"));
+        // Local tree
+        let local: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let source: PathBuf = local.path().join("project");
+        std::fs::create_dir_all(source.join("src")).unwrap();
+        std::fs::write(source.join("Cargo.toml"), b"[package]\n").unwrap();
+        std::fs::write(source.join("src/main.rs"), b"fn main() {}\n").unwrap();
+        std::fs::write(source.join("src/main.o"), b"object").unwrap();
+        let opts: TransferOptions = TransferOptions {
+            ignore: IgnoreRules::new(&["*.o"]),
+            ..TransferOptions::default()
+        };
+        // Upload
+        let mut progress: Vec<(u64, u64)> = Vec::new();
+        let job: Job = make_job(
+            source.as_path(),
+            Path::new("/srv"),
+            TransferDirection::Upload,
+        );
+        assert!(run_job(&mut client, &job, &opts, &mut |done, total| {
+            progress.push((done, total));
+            true
+        })
+        .is_ok());
+        assert_eq!(progress.last(), Some(&(23, 23)));
+        assert!(client.stat(Path::new("/srv/project/src/main.rs")).is_ok());
+        assert!(client.stat(Path::new("/srv/project/src/main.o")).is_err());
+        // Upload again, directories already exist
+        assert!(run_job(&mut client, &job, &opts, &mut |_, _| true).is_ok());
+        // Download
+        let target: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let job: Job = make_job(
+            Path::new("/srv/project"),
+            target.path(),
+            TransferDirection::Download,
+        );
+        assert!(run_job(&mut client, &job, &opts, &mut |_, _| true).is_ok());
+        assert_eq!(
+            std::fs::read(target.path().join("project/src/main.rs")).unwrap(),
+            b"fn main() {}\n"
+        );
+        // Interrupted
+        let job: Job = make_job(
+            Path::new("/srv/project/Cargo.toml"),
+            target.path(),
+            TransferDirection::Download,
+        );
+        assert_eq!(
+            run_job(&mut client, &job, &opts, &mut |_, _| false).err(),
+            Some(String::from("Transfer interrupted"))
+        );
+        // No such file
+        let job: Job = make_job(
+            Path::new("/srv/omar.txt"),
+            target.path(),
+            TransferDirection::Download,
+        );
+        assert!(run_job(&mut client, &job, &opts, &mut |_, _| true).is_err());
+    }
+}
@@ -0,0 +1,502 @@
+//! ## Doctor
+//!
+//! `doctor` is the module which diagnoses connection problems, checking step by step
+//! whether a remote host can be reached and what it offers
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::filetransfer::FileTransferProtocol;
+// Ext
+use ssh2::Session;
+use std::fmt;
+use std::io::Read;
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// ## DoctorParams
+///
+/// Holds the parameters of the host to diagnose
+#[derive(Clone)]
+pub struct DoctorParams {
+    pub address: String,
+    pub port: u16,
+    pub protocol: FileTransferProtocol,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub ssh_key: Option<PathBuf>, // SSH key associated to the host, if any
+    pub timeout: Duration,        // Connection timeout
+}
+
+/// ## Stage
+///
+/// Diagnosis stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    Dns,
+    Tcp,
+    Banner,
+    AuthMethods,
+    SftpSubsystem,
+}
+
+/// ## Outcome
+///
+/// Outcome of a diagnosis stage
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Ok,
+    Warn,
+    Fail,
+    Skipped,
+}
+
+/// ## Finding
+///
+/// Describes the outcome of a stage
+#[derive(Clone, Debug, PartialEq)]
+pub struct Finding {
+    pub stage: Stage,
+    pub outcome: Outcome,
+    pub message: String,
+}
+
+impl Finding {
+    fn new<S: AsRef<str>>(stage: Stage, outcome: Outcome, message: S) -> Self {
+        Finding {
+            stage,
+            outcome,
+            message: message.as_ref().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name: &str = match self {
+            Stage::Dns => "DNS",
+            Stage::Tcp => "TCP",
+            Stage::Banner => "Banner",
+            Stage::AuthMethods => "Auth methods",
+            Stage::SftpSubsystem => "SFTP subsystem",
+        };
+        f.pad(name)
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name: &str = match self {
+            Outcome::Ok => "OK",
+            Outcome::Warn => "WARN",
+            Outcome::Fail => "FAIL",
+            Outcome::Skipped => "SKIP",
+        };
+        f.pad(name)
+    }
+}
+
+/// ### stages
+///
+/// Returns the stages which are run to diagnose `protocol`
+pub fn stages(protocol: FileTransferProtocol) -> Vec<Stage> {
+    match protocol {
+        FileTransferProtocol::Sftp | FileTransferProtocol::Scp => vec![
+            Stage::Dns,
+            Stage::Tcp,
+            Stage::Banner,
+            Stage::AuthMethods,
+            Stage::SftpSubsystem,
+        ],
+        FileTransferProtocol::Ftp(_) => vec![Stage::Dns, Stage::Tcp, Stage::Banner],
+        FileTransferProtocol::WebDav(_) | FileTransferProtocol::Smb => {
+            vec![Stage::Dns, Stage::Tcp]
+        }
+        FileTransferProtocol::Kube | FileTransferProtocol::S3 | FileTransferProtocol::Plugin(_) => {
+            vec![]
+        }
+    }
+}
+
+/// ### diagnose
+///
+/// Run the diagnosis stages for `params`; `on_finding` is called as soon as each stage terminates.
+/// Diagnosis stops at the first failed stage
+pub fn diagnose<F>(params: &DoctorParams, mut on_finding: F) -> Vec<Finding>
+where
+    F: FnMut(&Finding),
+{
+    let mut findings: Vec<Finding> = Vec::new();
+    let mut addresses: Vec<SocketAddr> = Vec::new();
+    for stage in stages(params.protocol) {
+        let finding: Finding = match stage {
+            Stage::Dns => resolve(params, &mut addresses),
+            Stage::Tcp => probe_tcp(params, &mut addresses),
+            Stage::Banner => read_banner(params, addresses[0]),
+            Stage::AuthMethods => probe_auth_methods(params, addresses[0]),
+            Stage::SftpSubsystem => probe_sftp(params, addresses[0]),
+        };
+        on_finding(&finding);
+        let failed: bool = finding.outcome == Outcome::Fail;
+        findings.push(finding);
+        if failed {
+            break;
+        }
+    }
+    findings
+}
+
+/// ### resolve
+///
+/// Resolve the host address into `addresses`
+fn resolve(params: &DoctorParams, addresses: &mut Vec<SocketAddr>) -> Finding {
+    match (params.address.as_str(), params.port).to_socket_addrs() {
+        Ok(resolved) => {
+            addresses.extend(resolved);
+            match addresses.is_empty() {
+                true => Finding::new(
+                    Stage::Dns,
+                    Outcome::Fail,
+                    format!("\"{}\" doesn't resolve to any address", params.address),
+                ),
+                false => Finding::new(
+                    Stage::Dns,
+                    Outcome::Ok,
+                    format!(
+                        "\"{}\" resolves to {}",
+                        params.address,
+                        addresses
+                            .iter()
+                            .map(|x| x.ip().to_string())
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                ),
+            }
+        }
+        Err(err) => Finding::new(
+            Stage::Dns,
+            Outcome::Fail,
+            format!("Could not resolve \"{}\": {}", params.address, err),
+        ),
+    }
+}
+
+/// ### probe_tcp
+///
+/// Connect to the resolved addresses; the first reachable address is moved to the head of `addresses`
+fn probe_tcp(params: &DoctorParams, addresses: &mut Vec<SocketAddr>) -> Finding {
+    let mut errors: Vec<String> = Vec::new();
+    for (idx, addr) in addresses.iter().enumerate() {
+        let started: Instant = Instant::now();
+        match TcpStream::connect_timeout(addr, params.timeout) {
+            Ok(_) => {
+                let addr: SocketAddr = addresses.remove(idx);
+                addresses.insert(0, addr);
+                let mut message: String = format!(
+                    "Connected to {} in {}ms",
+                    addr,
+                    started.elapsed().as_millis()
+                );
+                if !errors.is_empty() {
+                    message.push_str(format!(" ({})", errors.join("; ")).as_str());
+                }
+                return Finding::new(
+                    Stage::Tcp,
+                    match errors.is_empty() {
+                        true => Outcome::Ok,
+                        false => Outcome::Warn,
+                    },
+                    message,
+                );
+            }
+            Err(err) => errors.push(format!("{}: {}", addr, err)),
+        }
+    }
+    Finding::new(
+        Stage::Tcp,
+        Outcome::Fail,
+        format!(
+            "Could not connect to port {} ({}); check that the server is running and that no firewall is blocking the port",
+            params.port,
+            errors.join("; ")
+        ),
+    )
+}
+
+/// ### read_banner
+///
+/// Read the greeting sent by the server once connected
+fn read_banner(params: &DoctorParams, addr: SocketAddr) -> Finding {
+    let ssh: bool = matches!(
+        params.protocol,
+        FileTransferProtocol::Sftp | FileTransferProtocol::Scp
+    );
+    let mut stream: TcpStream = match TcpStream::connect_timeout(&addr, params.timeout) {
+        Ok(stream) => stream,
+        Err(err) => {
+            return Finding::new(
+                Stage::Banner,
+                Outcome::Fail,
+                format!("Could not connect to {}: {}", addr, err),
+            )
+        }
+    };
+    let _ = stream.set_read_timeout(Some(params.timeout));
+    // Read first line
+    let mut line: Vec<u8> = Vec::new();
+    let mut byte: [u8; 1] = [0];
+    while line.len() < 255 {
+        match stream.read(&mut byte) {
+            Ok(1) if byte[0] == b'\n' => break,
+            Ok(1) => line.push(byte[0]),
+            _ => break,
+        }
+    }
+    let banner: String = String::from_utf8_lossy(&line).trim().to_string();
+    let expected: &str = match ssh {
+        true => "SSH-",
+        false => "220",
+    };
+    match banner.starts_with(expected) {
+        true => Finding::new(Stage::Banner, Outcome::Ok, banner),
+        false if banner.is_empty() => Finding::new(
+            Stage::Banner,
+            Outcome::Fail,
+            "The server didn't send any greeting; the port may belong to another service",
+        ),
+        false => Finding::new(
+            Stage::Banner,
+            Outcome::Fail,
+            format!(
+                "Unexpected greeting \"{}\"; the port may belong to another service",
+                banner
+            ),
+        ),
+    }
+}
+
+/// ### username
+///
+/// Username used to authenticate; the local user if not provided
+fn username(params: &DoctorParams) -> String {
+    match params.username.as_ref() {
+        Some(username) if !username.is_empty() => username.clone(),
+        _ => whoami::username(),
+    }
+}
+
+/// ### open_session
+///
+/// Connect to `addr` and perform the SSH handshake
+fn open_session(params: &DoctorParams, addr: SocketAddr) -> Result<Session, String> {
+    let stream: TcpStream =
+        TcpStream::connect_timeout(&addr, params.timeout).map_err(|e| e.to_string())?;
+    let mut session: Session = Session::new().map_err(|e| e.to_string())?;
+    session.set_timeout(params.timeout.as_millis() as u32);
+    session.set_tcp_stream(stream);
+    session
+        .handshake()
+        .map_err(|e| format!("SSH handshake failed: {}", e))?;
+    Ok(session)
+}
+
+/// ### probe_auth_methods
+///
+/// Get the authentication methods offered by the server to the user
+fn probe_auth_methods(params: &DoctorParams, addr: SocketAddr) -> Finding {
+    let session: Session = match open_session(params, addr) {
+        Ok(session) => session,
+        Err(err) => return Finding::new(Stage::AuthMethods, Outcome::Fail, err),
+    };
+    let username: String = username(params);
+    let methods: String = match session.auth_methods(username.as_str()) {
+        Ok(methods) => methods.to_string(),
+        Err(err) => {
+            return Finding::new(
+                Stage::AuthMethods,
+                Outcome::Fail,
+                format!("Could not get authentication methods: {}", err),
+            )
+        }
+    };
+    let offered: Vec<&str> = methods.split(',').collect();
+    let usable: bool = match params.ssh_key.is_some() {
+        true => offered.contains(&"publickey"),
+        false => offered.contains(&"password") || offered.contains(&"keyboard-interactive"),
+    };
+    match usable {
+        true => Finding::new(
+            Stage::AuthMethods,
+            Outcome::Ok,
+            format!("Offered to \"{}\": {}", username, methods),
+        ),
+        false => Finding::new(
+            Stage::AuthMethods,
+            Outcome::Warn,
+            format!(
+                "Offered to \"{}\": {}; {}",
+                username,
+                methods,
+                match params.ssh_key.is_some() {
+                    true => "public key authentication is not accepted",
+                    false =>
+                        "password authentication is not accepted; register an SSH key in setup",
+                }
+            ),
+        ),
+    }
+}
+
+/// ### probe_sftp
+///
+/// Authenticate and check whether the SFTP subsystem is available
+fn probe_sftp(params: &DoctorParams, addr: SocketAddr) -> Finding {
+    // SCP doesn't need the SFTP subsystem
+    let missing: Outcome = match params.protocol {
+        FileTransferProtocol::Scp => Outcome::Warn,
+        _ => Outcome::Fail,
+    };
+    if params.ssh_key.is_none() && params.password.is_none() {
+        return Finding::new(
+            Stage::SftpSubsystem,
+            Outcome::Skipped,
+            "Requires authentication; provide a password or an SSH key",
+        );
+    }
+    let session: Session = match open_session(params, addr) {
+        Ok(session) => session,
+        Err(err) => return Finding::new(Stage::SftpSubsystem, Outcome::Fail, err),
+    };
+    let username: String = username(params);
+    let auth = match params.ssh_key.as_ref() {
+        Some(key) => session.userauth_pubkey_file(
+            username.as_str(),
+            None,
+            key.as_path(),
+            params.password.as_deref(),
+        ),
+        None => session.userauth_password(
+            username.as_str(),
+            params.password.as_deref().unwrap_or_default(),
+        ),
+    };
+    if let Err(err) = auth {
+        return Finding::new(
+            Stage::SftpSubsystem,
+            Outcome::Fail,
+            format!("Authentication as \"{}\" failed: {}", username, err),
+        );
+    }
+    match session.sftp() {
+        Ok(_) => Finding::new(Stage::SftpSubsystem, Outcome::Ok, "SFTP is available"),
+        Err(err) => Finding::new(
+            Stage::SftpSubsystem,
+            missing,
+            format!(
+                "SFTP is not available: {}{}",
+                err,
+                match missing {
+                    Outcome::Warn => "; SCP can still be used",
+                    _ => "; try connecting with SCP",
+                }
+            ),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    #[test]
+    fn test_doctor_stages() {
+        assert_eq!(stages(FileTransferProtocol::Sftp).len(), 5);
+        assert_eq!(
+            stages(FileTransferProtocol::Ftp(false)),
+            vec![Stage::Dns, Stage::Tcp, Stage::Banner]
+        );
+        assert!(stages(FileTransferProtocol::Kube).is_empty());
+        assert_eq!(Stage::SftpSubsystem.to_string().as_str(), "SFTP subsystem");
+        assert_eq!(Outcome::Skipped.to_string().as_str(), "SKIP");
+    }
+
+    #[test]
+    fn test_doctor_diagnose_ftp() {
+        // Serve an FTP greeting
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port: u16 = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let _ = stream.write_all(b"220 Welcome\r\n");
+            }
+        });
+        let mut reported: usize = 0;
+        let findings: Vec<Finding> =
+            diagnose(&get_params(port, FileTransferProtocol::Ftp(false)), |_| {
+                reported += 1
+            });
+        assert_eq!(reported, 3);
+        assert!(findings.iter().all(|x| x.outcome == Outcome::Ok));
+        assert_eq!(findings[2].message.as_str(), "220 Welcome");
+        // Not an SSH server
+        let findings: Vec<Finding> =
+            diagnose(&get_params(port, FileTransferProtocol::Sftp), |_| {});
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings[2].outcome, Outcome::Fail);
+    }
+
+    #[test]
+    fn test_doctor_diagnose_unreachable() {
+        // Get a free port, then close it
+        let port: u16 = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let findings: Vec<Finding> =
+            diagnose(&get_params(port, FileTransferProtocol::Sftp), |_| {});
+        assert_eq!(findings.len(), 2);
+        assert_eq!(findings[0].outcome, Outcome::Ok);
+        assert_eq!(findings[1].outcome, Outcome::Fail);
+    }
+
+    fn get_params(port: u16, protocol: FileTransferProtocol) -> DoctorParams {
+        DoctorParams {
+            address: String::from("127.0.0.1"),
+            port,
+            protocol,
+            username: None,
+            password: None,
+            ssh_key: None,
+            timeout: Duration::from_secs(5),
+        }
+    }
+}
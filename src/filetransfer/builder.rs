@@ -0,0 +1,198 @@
+//! ## Builder
+//!
+//! `builder` is the module which provides a builder for `FileTransfer` clients
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::ftp_transfer::FtpFileTransfer;
+use super::kube_transfer::KubeFileTransfer;
+use super::memory_transfer::MemoryFileTransfer;
+use super::registry;
+use super::s3_transfer::S3FileTransfer;
+use super::scp_transfer::ScpFileTransfer;
+use super::sftp_transfer::SftpFileTransfer;
+use super::smb_transfer::SmbFileTransfer;
+use super::webdav_transfer::WebDavFileTransfer;
+use super::{FileTransfer, FileTransferProtocol, NetworkOptions, SshAlgorithms};
+use crate::system::sshkey_storage::SshKeyStorage;
+// Ext
+use std::path::PathBuf;
+
+/// ## FileTransferBuilder
+///
+/// Struct used to create a `FileTransfer` client for a protocol
+pub struct FileTransferBuilder {
+    protocol: FileTransferProtocol,
+    key_storage: Option<SshKeyStorage>,
+    known_hosts: Option<PathBuf>,
+    network: NetworkOptions,
+    ssh_algorithms: SshAlgorithms,
+    s3_endpoint: Option<String>, // S3 endpoint URL; AWS if `None`
+    s3_region: Option<String>,
+}
+
+impl FileTransferBuilder {
+    /// ### new
+    ///
+    /// Instantiates a new `FileTransferBuilder` for the provided protocol
+    pub fn new(protocol: FileTransferProtocol) -> Self {
+        FileTransferBuilder {
+            protocol,
+            key_storage: None,
+            known_hosts: None,
+            network: NetworkOptions::default(),
+            ssh_algorithms: SshAlgorithms::default(),
+            s3_endpoint: None,
+            s3_region: None,
+        }
+    }
+
+    /// ### with_ssh_key_storage
+    ///
+    /// Set ssh key storage used by SSH based protocols to authenticate.
+    /// If not set, an empty storage is used
+    pub fn with_ssh_key_storage(&mut self, storage: SshKeyStorage) -> &mut FileTransferBuilder {
+        self.key_storage = Some(storage);
+        self
+    }
+
+    /// ### with_known_hosts
+    ///
+    /// Set known hosts file used by SSH based protocols to verify the host key.
+    /// If not set, host key is not verified
+    pub fn with_known_hosts(&mut self, path: Option<PathBuf>) -> &mut FileTransferBuilder {
+        self.known_hosts = path;
+        self
+    }
+
+    /// ### with_network_options
+    ///
+    /// Set network options (timeout, keepalive, proxy) used by the protocols which support them.
+    /// If not set, defaults are used
+    pub fn with_network_options(&mut self, options: NetworkOptions) -> &mut FileTransferBuilder {
+        self.network = options;
+        self
+    }
+
+    /// ### with_ssh_algorithms
+    ///
+    /// Set the algorithms preferred by SSH based protocols.
+    /// If not set, the default algorithms are offered
+    pub fn with_ssh_algorithms(&mut self, algorithms: SshAlgorithms) -> &mut FileTransferBuilder {
+        self.ssh_algorithms = algorithms;
+        self
+    }
+
+    /// ### with_s3_endpoint
+    ///
+    /// Set endpoint URL and region used by S3, to connect to S3-compatible object storages.
+    /// If not set, the AWS endpoint and the `us-east-1` region are used
+    pub fn with_s3_endpoint(
+        &mut self,
+        endpoint: Option<String>,
+        region: Option<String>,
+    ) -> &mut FileTransferBuilder {
+        self.s3_endpoint = endpoint;
+        self.s3_region = region;
+        self
+    }
+
+    /// ### build
+    ///
+    /// Build the `FileTransfer` client
+    pub fn build(&mut self) -> Box<dyn FileTransfer> {
+        let key_storage: SshKeyStorage =
+            self.key_storage.take().unwrap_or_else(SshKeyStorage::empty);
+        let network: NetworkOptions = std::mem::take(&mut self.network);
+        let algorithms: SshAlgorithms = std::mem::take(&mut self.ssh_algorithms);
+        match self.protocol {
+            FileTransferProtocol::Sftp => Box::new(
+                SftpFileTransfer::new(key_storage)
+                    .with_known_hosts(self.known_hosts.take())
+                    .with_network_options(network)
+                    .with_ssh_algorithms(algorithms),
+            ),
+            FileTransferProtocol::Scp => Box::new(
+                ScpFileTransfer::new(key_storage)
+                    .with_known_hosts(self.known_hosts.take())
+                    .with_network_options(network)
+                    .with_ssh_algorithms(algorithms),
+            ),
+            FileTransferProtocol::Ftp(ftps) => Box::new(
+                FtpFileTransfer::new(ftps)
+                    .with_known_hosts(self.known_hosts.take())
+                    .with_network_options(network),
+            ),
+            FileTransferProtocol::WebDav(https) => {
+                Box::new(WebDavFileTransfer::new(https).with_network_options(network))
+            }
+            FileTransferProtocol::Smb => {
+                Box::new(SmbFileTransfer::new().with_network_options(network))
+            }
+            FileTransferProtocol::Kube => Box::new(KubeFileTransfer::new()),
+            FileTransferProtocol::S3 => Box::new(
+                S3FileTransfer::new(self.s3_endpoint.take(), self.s3_region.take())
+                    .with_network_options(network),
+            ),
+            // NOTE: plugins can't be unregistered
+            FileTransferProtocol::Plugin(scheme) => {
+                registry::build(scheme).unwrap_or_else(|| Box::new(MemoryFileTransfer::new()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_filetransfer_builder() {
+        let client: Box<dyn FileTransfer> = FileTransferBuilder::new(FileTransferProtocol::Sftp)
+            .with_ssh_key_storage(SshKeyStorage::empty())
+            .build();
+        assert!(!client.is_connected());
+        let client: Box<dyn FileTransfer> =
+            FileTransferBuilder::new(FileTransferProtocol::Scp).build();
+        assert!(!client.is_connected());
+        let client: Box<dyn FileTransfer> =
+            FileTransferBuilder::new(FileTransferProtocol::Ftp(true)).build();
+        assert!(!client.is_connected());
+        let client: Box<dyn FileTransfer> =
+            FileTransferBuilder::new(FileTransferProtocol::WebDav(true)).build();
+        assert!(!client.is_connected());
+        let client: Box<dyn FileTransfer> =
+            FileTransferBuilder::new(FileTransferProtocol::Smb).build();
+        assert!(!client.is_connected());
+        let client: Box<dyn FileTransfer> =
+            FileTransferBuilder::new(FileTransferProtocol::Kube).build();
+        assert!(!client.is_connected());
+        let client: Box<dyn FileTransfer> = FileTransferBuilder::new(FileTransferProtocol::S3)
+            .with_s3_endpoint(Some(String::from("http://127.0.0.1:9000")), None)
+            .build();
+        assert!(!client.is_connected());
+    }
+}
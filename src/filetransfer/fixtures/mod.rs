@@ -0,0 +1,970 @@
+//! ## Fixtures
+//!
+//! `fixtures` is the module which provides embedded servers, used to test the file transfers without any network access.
+//! It is built in tests and, for other crates, with the `fixtures` feature
+// The binary declares the `filetransfer` module too, but only tests and other crates start the servers
+#![cfg_attr(not(test), allow(dead_code, unused_imports))]
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Submodules
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+mod sftp;
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+mod ssh;
+
+// Locals
+use super::memory_transfer::MemoryFileTransfer;
+use super::FileTransfer;
+use crate::fs::FsEntry;
+use crate::utils::fmt::fmt_time;
+use crate::utils::parser::parse_datetime;
+// Ext
+use chrono::{DateTime, Utc};
+use openssl::asn1::Asn1Time;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::PKey;
+use openssl::ssl::{SslAcceptor, SslMethod, SslStream};
+use openssl::x509::{X509NameBuilder, X509};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+// Exports
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+pub use ssh::SshServer;
+
+/// ## FtpServer
+///
+/// A minimal FTP server listening on localhost, which serves a `MemoryFileTransfer`.
+/// It only supports passive mode and the commands issued by `FtpFileTransfer`;
+/// connections can be secured with `AUTH TLS`, using a self-signed certificate.
+/// The server is stopped when dropped
+pub struct FtpServer {
+    addr: SocketAddr,
+    fs: Arc<Mutex<MemoryFileTransfer>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// ## FtpSession
+///
+/// State of a single control connection
+struct FtpSession {
+    fs: Arc<Mutex<MemoryFileTransfer>>,
+    wrkdir: PathBuf,
+    pasv: Option<TcpListener>,
+    rename_from: Option<PathBuf>,
+    windows: bool,              // Identify as a Windows server and list directories with the DOS syntax
+    tls: Option<SslAcceptor>,   // Set once the control connection is secured
+    protected: bool,            // Whether data connections are secured too
+}
+
+/// ## FtpStream
+///
+/// A control or data connection of `FtpServer`, either plain or secured with TLS
+enum FtpStream {
+    Plain(TcpStream),
+    Tls(SslStream<TcpStream>),
+}
+
+/// ### listen
+///
+/// Listen on a random port of localhost.
+/// Each connection is handled by `handler` in a dedicated thread, until `running` is set to false
+fn listen<F>(handler: F) -> io::Result<(SocketAddr, Arc<AtomicBool>, JoinHandle<()>)>
+where
+    F: Fn(TcpStream) + Send + Sync + 'static,
+{
+    let listener: TcpListener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+    listener.set_nonblocking(true)?;
+    let addr: SocketAddr = listener.local_addr()?;
+    let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(true));
+    let thread_running: Arc<AtomicBool> = running.clone();
+    let handler: Arc<F> = Arc::new(handler);
+    let thread: JoinHandle<()> = thread::spawn(move || {
+        while thread_running.load(Ordering::Relaxed) {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let handler: Arc<F> = handler.clone();
+                    thread::spawn(move || handler(stream));
+                }
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => {
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    Ok((addr, running, thread))
+}
+
+/// ### serve
+///
+/// Listen on a random port of localhost, serving `fs`.
+/// Each connection is handled by `handler` in a dedicated thread, until `running` is set to false
+#[allow(clippy::type_complexity)]
+fn serve(
+    fs: MemoryFileTransfer,
+    handler: fn(Arc<Mutex<MemoryFileTransfer>>, TcpStream),
+) -> io::Result<(
+    SocketAddr,
+    Arc<Mutex<MemoryFileTransfer>>,
+    Arc<AtomicBool>,
+    JoinHandle<()>,
+)> {
+    let fs: Arc<Mutex<MemoryFileTransfer>> = Arc::new(Mutex::new(fs));
+    let thread_fs: Arc<Mutex<MemoryFileTransfer>> = fs.clone();
+    let (addr, running, thread) = listen(move |stream| handler(thread_fs.clone(), stream))?;
+    // Session is always open on the file system
+    let _ = fs
+        .lock()
+        .unwrap()
+        .connect(String::from("localhost"), addr.port(), None, None);
+    Ok((addr, fs, running, thread))
+}
+
+impl FtpServer {
+    /// ### start
+    ///
+    /// Start a new FTP server on a random port of localhost, serving `fs`
+    pub fn start(fs: MemoryFileTransfer) -> io::Result<FtpServer> {
+        let (addr, fs, running, thread) = serve(fs, |fs, stream| {
+            let _ = FtpSession::new(fs, false).run(stream);
+        })?;
+        Ok(FtpServer {
+            addr,
+            fs,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// ### start_windows
+    ///
+    /// Start a new FTP server on a random port of localhost, serving `fs`.
+    /// The server identifies itself as a Windows server and lists directories with the DOS syntax
+    pub fn start_windows(fs: MemoryFileTransfer) -> io::Result<FtpServer> {
+        let (addr, fs, running, thread) = serve(fs, |fs, stream| {
+            let _ = FtpSession::new(fs, true).run(stream);
+        })?;
+        Ok(FtpServer {
+            addr,
+            fs,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// ### address
+    ///
+    /// Get the address the server is listening on
+    pub fn address(&self) -> String {
+        self.addr.ip().to_string()
+    }
+
+    /// ### port
+    ///
+    /// Get the port the server is listening on
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// ### fs
+    ///
+    /// Get the file system served by the server, to prepare or verify its content
+    pub fn fs(&self) -> Arc<Mutex<MemoryFileTransfer>> {
+        self.fs.clone()
+    }
+}
+
+impl Drop for FtpServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl FtpSession {
+    /// ### new
+    ///
+    /// Instantiates a new `FtpSession`
+    fn new(fs: Arc<Mutex<MemoryFileTransfer>>, windows: bool) -> FtpSession {
+        let wrkdir: PathBuf = match fs.lock() {
+            Ok(mut fs) => fs.pwd().unwrap_or_else(|_| PathBuf::from("/")),
+            Err(_) => PathBuf::from("/"),
+        };
+        FtpSession {
+            fs,
+            wrkdir,
+            pasv: None,
+            rename_from: None,
+            windows,
+            tls: None,
+            protected: false,
+        }
+    }
+
+    /// ### run
+    ///
+    /// Handle control connection until the client quits
+    fn run(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        let mut reader: BufReader<FtpStream> = BufReader::new(FtpStream::Plain(stream));
+        reader
+            .get_mut()
+            .write_all(b"220 termscp test server ready\r\n")?;
+        loop {
+            let mut line: String = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(());
+            }
+            let line: &str = line.trim_end_matches(['\r', '\n']);
+            let (cmd, arg): (&str, &str) = match line.find(' ') {
+                Some(idx) => (&line[..idx], &line[idx + 1..]),
+                None => (line, ""),
+            };
+            let cmd: String = cmd.to_ascii_uppercase();
+            match cmd.as_str() {
+                "QUIT" => return reader.get_mut().write_all(b"221 Goodbye\r\n"),
+                "AUTH" if arg.eq_ignore_ascii_case("TLS") && self.tls.is_none() => {
+                    let acceptor: SslAcceptor = Self::tls_acceptor()?;
+                    reader
+                        .get_mut()
+                        .write_all(b"234 Proceed with negotiation\r\n")?;
+                    reader = BufReader::new(reader.into_inner().secure(&acceptor)?);
+                    self.tls = Some(acceptor);
+                }
+                _ => {
+                    let reply: String = self.handle(cmd.as_str(), arg, reader.get_mut());
+                    reader
+                        .get_mut()
+                        .write_all(format!("{}\r\n", reply).as_bytes())?;
+                }
+            }
+        }
+    }
+
+    /// ### tls_acceptor
+    ///
+    /// Create the TLS acceptor used to secure the connections, with a new self-signed certificate
+    fn tls_acceptor() -> io::Result<SslAcceptor> {
+        let key = PKey::from_ec_key(EcKey::generate(
+            EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?.as_ref(),
+        )?)?;
+        let mut name = X509NameBuilder::new()?;
+        name.append_entry_by_nid(Nid::COMMONNAME, "localhost")?;
+        let name = name.build();
+        let mut cert = X509::builder()?;
+        cert.set_version(2)?;
+        cert.set_subject_name(name.as_ref())?;
+        cert.set_issuer_name(name.as_ref())?;
+        cert.set_pubkey(key.as_ref())?;
+        cert.set_not_before(Asn1Time::days_from_now(0)?.as_ref())?;
+        cert.set_not_after(Asn1Time::days_from_now(1)?.as_ref())?;
+        cert.sign(key.as_ref(), MessageDigest::sha256())?;
+        let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls())?;
+        acceptor.set_private_key(key.as_ref())?;
+        acceptor.set_certificate(cert.build().as_ref())?;
+        Ok(acceptor.build())
+    }
+
+    /// ### handle
+    ///
+    /// Handle a command and return the reply to send on the control connection.
+    /// Commands which use the data connection send their preliminary reply through `ctrl`
+    fn handle(&mut self, cmd: &str, arg: &str, ctrl: &mut dyn Write) -> String {
+        let path: PathBuf = self.wrkdir.join(arg);
+        match cmd {
+            "USER" => String::from("331 Password required"),
+            "PASS" => String::from("230 Logged in"),
+            "SYST" if self.windows => String::from("215 Windows_NT"),
+            "SYST" => String::from("215 UNIX Type: L8"),
+            "TYPE" | "NOOP" | "PBSZ" => String::from("200 Command okay"),
+            "PROT" => {
+                self.protected = self.tls.is_some() && arg.eq_ignore_ascii_case("P");
+                String::from("200 Protection level set")
+            }
+            "PWD" => format!("257 \"{}\" is the current directory", self.wrkdir.display()),
+            "CWD" | "CDUP" => {
+                let path: PathBuf = match cmd {
+                    "CDUP" => self.wrkdir.join(".."),
+                    _ => path,
+                };
+                let mut fs = self.fs.lock().unwrap();
+                match fs.change_dir(path.as_path()) {
+                    Ok(wrkdir) => {
+                        self.wrkdir = wrkdir;
+                        String::from("250 Directory changed")
+                    }
+                    Err(err) => format!("550 {}", err),
+                }
+            }
+            "MKD" => match self.fs.lock().unwrap().mkdir(path.as_path()) {
+                Ok(_) => format!("257 \"{}\" created", path.display()),
+                Err(err) => format!("550 {}", err),
+            },
+            "DELE" | "RMD" => {
+                let mut fs = self.fs.lock().unwrap();
+                match fs.stat(path.as_path()) {
+                    Ok(entry) if entry.is_dir() == (cmd == "RMD") => match fs.remove(&entry) {
+                        Ok(_) => String::from("250 Removed"),
+                        Err(err) => format!("550 {}", err),
+                    },
+                    Ok(_) => String::from("550 Wrong file type"),
+                    Err(err) => format!("550 {}", err),
+                }
+            }
+            "RNFR" => match self.fs.lock().unwrap().stat(path.as_path()) {
+                Ok(_) => {
+                    self.rename_from = Some(path);
+                    String::from("350 Ready for RNTO")
+                }
+                Err(err) => format!("550 {}", err),
+            },
+            "RNTO" => {
+                let src: PathBuf = match self.rename_from.take() {
+                    Some(src) => src,
+                    None => return String::from("503 RNFR required first"),
+                };
+                let mut fs = self.fs.lock().unwrap();
+                match fs
+                    .stat(src.as_path())
+                    .and_then(|entry| fs.rename(&entry, path.as_path()))
+                {
+                    Ok(_) => String::from("250 Renamed"),
+                    Err(err) => format!("550 {}", err),
+                }
+            }
+            "SITE" => {
+                let mut args = arg.splitn(3, ' ');
+                let (site, mode, path): (&str, &str, &str) = (
+                    args.next().unwrap_or(""),
+                    args.next().unwrap_or(""),
+                    args.next().unwrap_or(""),
+                );
+                let mode: Vec<u8> = mode.bytes().map(|x| x.wrapping_sub(b'0')).collect();
+                match mode.as_slice() {
+                    [u, g, o]
+                        if site.eq_ignore_ascii_case("CHMOD") && *u < 8 && *g < 8 && *o < 8 =>
+                    {
+                        let path: PathBuf = self.wrkdir.join(path);
+                        match self.fs.lock().unwrap().chmod(path.as_path(), (*u, *g, *o)) {
+                            Ok(_) => String::from("200 Permissions changed"),
+                            Err(err) => format!("550 {}", err),
+                        }
+                    }
+                    _ => String::from("504 Command not implemented for that parameter"),
+                }
+            }
+            "MFMT" => {
+                let (tm, path): (&str, &str) = arg.split_once(' ').unwrap_or((arg, ""));
+                match parse_datetime(tm, "%Y%m%d%H%M%S") {
+                    Ok(mtime) => {
+                        let path: PathBuf = self.wrkdir.join(path);
+                        match self.fs.lock().unwrap().set_mtime(path.as_path(), mtime) {
+                            Ok(_) => format!("213 Modify={}; {}", tm, path.display()),
+                            Err(err) => format!("550 {}", err),
+                        }
+                    }
+                    Err(_) => String::from("501 Invalid time"),
+                }
+            }
+            "SIZE" => match self.fs.lock().unwrap().stat(path.as_path()) {
+                Ok(FsEntry::File(file)) => format!("213 {}", file.size),
+                Ok(FsEntry::Directory(_)) => String::from("550 Not a file"),
+                Err(err) => format!("550 {}", err),
+            },
+            "PASV" => match TcpListener::bind((Ipv4Addr::LOCALHOST, 0)) {
+                Ok(listener) => {
+                    let port: u16 = listener.local_addr().map(|x| x.port()).unwrap_or(0);
+                    self.pasv = Some(listener);
+                    format!(
+                        "227 Entering Passive Mode (127,0,0,1,{},{})",
+                        port >> 8,
+                        port & 0xff
+                    )
+                }
+                Err(_) => String::from("425 Can't open data connection"),
+            },
+            "LIST" | "RETR" | "STOR" => {
+                let path: PathBuf = match cmd == "LIST" && arg.is_empty() {
+                    true => self.wrkdir.clone(),
+                    false => path,
+                };
+                match self.transfer(cmd, path.as_path(), ctrl) {
+                    Ok(_) => String::from("226 Transfer complete"),
+                    Err(err) => format!("550 {}", err),
+                }
+            }
+            _ => String::from("502 Command not implemented"),
+        }
+    }
+
+    /// ### transfer
+    ///
+    /// Run a command which uses the data connection
+    fn transfer(&mut self, cmd: &str, path: &Path, ctrl: &mut dyn Write) -> io::Result<()> {
+        let listener: TcpListener = match self.pasv.take() {
+            Some(listener) => listener,
+            None => return Err(io::Error::other("PASV required first")),
+        };
+        let (data, _): (TcpStream, SocketAddr) = listener.accept()?;
+        let mut data: FtpStream = match (self.protected, self.tls.as_ref()) {
+            (true, Some(acceptor)) => FtpStream::Plain(data).secure(acceptor)?,
+            _ => FtpStream::Plain(data),
+        };
+        let io_err = |err: super::FileTransferError| io::Error::other(err.to_string());
+        match cmd {
+            "LIST" => {
+                let entries: Vec<FsEntry> =
+                    self.fs.lock().unwrap().list_dir(path).map_err(io_err)?;
+                ctrl.write_all(b"150 Here comes the directory listing\r\n")?;
+                for entry in entries.iter() {
+                    let line: String = match self.windows {
+                        true => Self::fmt_dos_list_line(entry),
+                        false => Self::fmt_list_line(entry),
+                    };
+                    data.write_all(format!("{}\r\n", line).as_bytes())?;
+                }
+                data.close();
+                Ok(())
+            }
+            "RETR" => {
+                let mut fs = self.fs.lock().unwrap();
+                let file = match fs.stat(path).map_err(io_err)? {
+                    FsEntry::File(file) => file,
+                    FsEntry::Directory(_) => return Err(io::Error::other("Not a file")),
+                };
+                let mut reader: Box<dyn Read> = fs.recv_file(&file).map_err(io_err)?;
+                drop(fs);
+                ctrl.write_all(b"150 Opening data connection\r\n")?;
+                io::copy(&mut reader, &mut data)?;
+                data.close();
+                Ok(())
+            }
+            _ => {
+                let parent: &Path = path.parent().unwrap_or_else(|| Path::new("/"));
+                match self.fs.lock().unwrap().stat(parent) {
+                    Ok(entry) if entry.is_dir() => {}
+                    _ => return Err(io::Error::other("No such directory")),
+                }
+                let mut content: Vec<u8> = Vec::new();
+                ctrl.write_all(b"150 Ok to send data\r\n")?;
+                data.read_to_end(&mut content)?;
+                data.close();
+                self.fs.lock().unwrap().add_file(path, content.as_slice());
+                Ok(())
+            }
+        }
+    }
+
+    /// ### fmt_list_line
+    ///
+    /// Format entry as a UNIX `ls -l` line
+    fn fmt_list_line(entry: &FsEntry) -> String {
+        let (user, group, others): (u8, u8, u8) = entry.get_unix_pex().unwrap_or((6, 4, 4));
+        let pex = |x: u8| -> String {
+            format!(
+                "{}{}{}",
+                if x & 4 != 0 { 'r' } else { '-' },
+                if x & 2 != 0 { 'w' } else { '-' },
+                if x & 1 != 0 { 'x' } else { '-' }
+            )
+        };
+        format!(
+            "{}{}{}{} 1 0 0 {} {} {}",
+            if entry.is_dir() { 'd' } else { '-' },
+            pex(user),
+            pex(group),
+            pex(others),
+            entry.get_size(),
+            fmt_time(entry.get_last_change_time(), "%b %d %H:%M"),
+            entry.get_name()
+        )
+    }
+
+    /// ### fmt_dos_list_line
+    ///
+    /// Format entry as a line of a DOS `dir` listing
+    fn fmt_dos_list_line(entry: &FsEntry) -> String {
+        let size: String = match entry {
+            FsEntry::Directory(_) => format!("{:<14}", "<DIR>"),
+            FsEntry::File(file) => format!("{:>14}", file.size),
+        };
+        format!(
+            "{}       {} {}",
+            fmt_time(entry.get_last_change_time(), "%d-%m-%y  %I:%M%p"),
+            size,
+            entry.get_name()
+        )
+    }
+}
+
+impl FtpStream {
+    /// ### secure
+    ///
+    /// Secure a plain connection with TLS, running the handshake as server
+    fn secure(self, acceptor: &SslAcceptor) -> io::Result<FtpStream> {
+        match self {
+            FtpStream::Plain(stream) => acceptor
+                .accept(stream)
+                .map(FtpStream::Tls)
+                .map_err(|err| io::Error::other(err.to_string())),
+            stream => Ok(stream),
+        }
+    }
+
+    /// ### close
+    ///
+    /// Close the connection, notifying the peer if it's secured
+    fn close(self) {
+        if let FtpStream::Tls(mut stream) = self {
+            let _ = stream.shutdown();
+        }
+    }
+}
+
+impl Read for FtpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            FtpStream::Plain(stream) => stream.read(buf),
+            // Clients may close the connection without notifying it (e.g. after an upload)
+            FtpStream::Tls(stream) => stream.read(buf).or(Ok(0)),
+        }
+    }
+}
+
+impl Write for FtpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            FtpStream::Plain(stream) => stream.write(buf),
+            FtpStream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            FtpStream::Plain(stream) => stream.flush(),
+            FtpStream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// ## WebDavServer
+///
+/// A minimal WebDAV server listening on localhost, which serves a `MemoryFileTransfer`.
+/// It only supports the methods issued by `WebDavFileTransfer`, a request per connection
+/// and bodies with `Content-Length`.
+/// The server is stopped when dropped
+pub struct WebDavServer {
+    addr: SocketAddr,
+    fs: Arc<Mutex<MemoryFileTransfer>>,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// ## DavRequest
+///
+/// A HTTP request received by `WebDavServer`
+struct DavRequest {
+    method: String,
+    path: PathBuf,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+/// ## DavResponse
+///
+/// A HTTP response sent by `WebDavServer`: status, headers and body
+type DavResponse = (u16, Vec<(&'static str, String)>, Vec<u8>);
+
+/// Credentials accepted by `WebDavServer` (`Authorization` header of user "omar" with password "secret")
+const WEBDAV_AUTHORIZATION: &str = "Basic b21hcjpzZWNyZXQ=";
+
+impl WebDavServer {
+    /// ### start
+    ///
+    /// Start a new WebDAV server on a random port of localhost, serving `fs`.
+    /// Requests with credentials different from user "omar" and password "secret" are rejected
+    pub fn start(fs: MemoryFileTransfer) -> io::Result<WebDavServer> {
+        let (addr, fs, running, thread) = serve(fs, |fs, stream| {
+            let _ = Self::run(fs, stream);
+        })?;
+        Ok(WebDavServer {
+            addr,
+            fs,
+            running,
+            thread: Some(thread),
+        })
+    }
+
+    /// ### address
+    ///
+    /// Get the address the server is listening on
+    pub fn address(&self) -> String {
+        self.addr.ip().to_string()
+    }
+
+    /// ### port
+    ///
+    /// Get the port the server is listening on
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// ### fs
+    ///
+    /// Get the file system served by the server, to prepare or verify its content
+    pub fn fs(&self) -> Arc<Mutex<MemoryFileTransfer>> {
+        self.fs.clone()
+    }
+
+    /// ### run
+    ///
+    /// Read a request from `stream`, then send the response and close the connection
+    fn run(fs: Arc<Mutex<MemoryFileTransfer>>, stream: TcpStream) -> io::Result<()> {
+        stream.set_nonblocking(false)?;
+        let mut writer: TcpStream = stream.try_clone()?;
+        let request: DavRequest = Self::read_request(BufReader::new(stream))?;
+        let (status, headers, body): DavResponse =
+            match request.header("authorization") == Some(WEBDAV_AUTHORIZATION) {
+                true => Self::handle(&mut fs.lock().unwrap(), &request),
+                false => (
+                    401,
+                    vec![("WWW-Authenticate", String::from("Basic realm=\"termscp\""))],
+                    Vec::new(),
+                ),
+            };
+        let mut head: String = format!(
+            "HTTP/1.1 {} Status\r\nServer: termscp test server\r\nConnection: close\r\nContent-Length: {}\r\n",
+            status,
+            body.len()
+        );
+        for (name, value) in headers.iter() {
+            head.push_str(format!("{}: {}\r\n", name, value).as_str());
+        }
+        head.push_str("\r\n");
+        writer.write_all(head.as_bytes())?;
+        writer.write_all(body.as_slice())
+    }
+
+    /// ### read_request
+    ///
+    /// Read request line, headers and body
+    fn read_request(mut reader: BufReader<TcpStream>) -> io::Result<DavRequest> {
+        let mut line: String = String::new();
+        reader.read_line(&mut line)?;
+        let mut tokens = line.split_whitespace();
+        let method: String = tokens.next().unwrap_or("").to_string();
+        let path: PathBuf = Self::url_path(tokens.next().unwrap_or("/"));
+        let mut headers: Vec<(String, String)> = Vec::new();
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                break;
+            }
+            let header: &str = line.trim_end_matches(['\r', '\n']);
+            if header.is_empty() {
+                break;
+            }
+            if let Some(idx) = header.find(':') {
+                headers.push((
+                    header[..idx].trim().to_ascii_lowercase(),
+                    header[idx + 1..].trim().to_string(),
+                ));
+            }
+        }
+        let mut request: DavRequest = DavRequest {
+            method,
+            path,
+            headers,
+            body: Vec::new(),
+        };
+        let length: u64 = request
+            .header("content-length")
+            .and_then(|x| x.parse::<u64>().ok())
+            .unwrap_or(0);
+        reader.take(length).read_to_end(&mut request.body)?;
+        // Truncated bodies (e.g. interrupted uploads) are discarded
+        if (request.body.len() as u64) < length {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "request body is truncated",
+            ));
+        }
+        Ok(request)
+    }
+
+    /// ### url_path
+    ///
+    /// Get decoded path from request target or `Destination` URL
+    fn url_path(url: &str) -> PathBuf {
+        let url: &str = match url.find("://") {
+            Some(idx) => match url[idx + 3..].find('/') {
+                Some(slash) => &url[idx + 3 + slash..],
+                None => "/",
+            },
+            None => url,
+        };
+        let path: String = percent_decode_str(url).decode_utf8_lossy().to_string();
+        match path.trim_end_matches('/') {
+            "" => PathBuf::from("/"),
+            path => PathBuf::from(path),
+        }
+    }
+
+    /// ### handle
+    ///
+    /// Handle request on file system
+    fn handle(fs: &mut MemoryFileTransfer, request: &DavRequest) -> DavResponse {
+        let path: &Path = request.path.as_path();
+        let status = |code: u16| -> DavResponse { (code, Vec::new(), Vec::new()) };
+        match request.method.as_str() {
+            "OPTIONS" => (200, vec![("DAV", String::from("1"))], Vec::new()),
+            "PROPFIND" => {
+                let entry: FsEntry = match fs.stat(path) {
+                    Ok(entry) => entry,
+                    Err(_) => return status(404),
+                };
+                let mut entries: Vec<FsEntry> = Vec::new();
+                if entry.is_dir() && request.header("depth") != Some("0") {
+                    entries = fs.list_dir(path).unwrap_or_default();
+                }
+                entries.insert(0, entry);
+                let mut body: String = String::from(
+                    "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus xmlns:d=\"DAV:\">\n",
+                );
+                for entry in entries.iter() {
+                    body.push_str(Self::fmt_propfind_response(entry).as_str());
+                }
+                body.push_str("</d:multistatus>\n");
+                (
+                    207,
+                    vec![("Content-Type", String::from("application/xml"))],
+                    body.into_bytes(),
+                )
+            }
+            "GET" => match fs.stat(path) {
+                Ok(FsEntry::File(file)) => {
+                    let mut data: Vec<u8> = Vec::new();
+                    match fs.recv_file(&file) {
+                        Ok(mut reader) => match reader.read_to_end(&mut data) {
+                            Ok(_) => (200, Vec::new(), data),
+                            Err(_) => status(500),
+                        },
+                        Err(_) => status(500),
+                    }
+                }
+                Ok(FsEntry::Directory(_)) => status(405),
+                Err(_) => status(404),
+            },
+            "PUT" => match fs.stat(path.parent().unwrap_or_else(|| Path::new("/"))) {
+                Ok(FsEntry::Directory(_)) => {
+                    fs.add_file(path, request.body.as_slice());
+                    status(201)
+                }
+                _ => status(409),
+            },
+            "MKCOL" => match fs.stat(path) {
+                Ok(_) => status(405),
+                Err(_) => match fs.mkdir(path) {
+                    Ok(_) => status(201),
+                    Err(_) => status(409),
+                },
+            },
+            "DELETE" => match fs.stat(path) {
+                Ok(entry) => match fs.remove(&entry) {
+                    Ok(_) => status(204),
+                    Err(_) => status(403),
+                },
+                Err(_) => status(404),
+            },
+            "MOVE" | "COPY" => {
+                let dst: PathBuf = match request.header("destination") {
+                    Some(dst) => Self::url_path(dst),
+                    None => return status(400),
+                };
+                let entry: FsEntry = match fs.stat(path) {
+                    Ok(entry) => entry,
+                    Err(_) => return status(404),
+                };
+                if request.header("overwrite") == Some("F") && fs.stat(dst.as_path()).is_ok() {
+                    return status(412);
+                }
+                let result = match request.method.as_str() {
+                    "MOVE" => fs.rename(&entry, dst.as_path()),
+                    _ => fs.copy(&entry, dst.as_path()),
+                };
+                match result {
+                    Ok(_) => status(201),
+                    Err(_) => status(409),
+                }
+            }
+            _ => status(501),
+        }
+    }
+
+    /// ### fmt_propfind_response
+    ///
+    /// Format entry as a multistatus `response` element
+    fn fmt_propfind_response(entry: &FsEntry) -> String {
+        let mut href: String = entry
+            .get_abs_path()
+            .components()
+            .filter_map(|x| match x {
+                Component::Normal(name) => Some(format!(
+                    "/{}",
+                    utf8_percent_encode(name.to_string_lossy().as_ref(), NON_ALPHANUMERIC)
+                )),
+                _ => None,
+            })
+            .collect();
+        if entry.is_dir() {
+            href.push('/');
+        }
+        let (resourcetype, length): (&str, String) = match entry {
+            FsEntry::Directory(_) => ("<d:collection/>", String::new()),
+            FsEntry::File(file) => (
+                "",
+                format!("<d:getcontentlength>{}</d:getcontentlength>", file.size),
+            ),
+        };
+        format!(
+            "<d:response><d:href>{}</d:href><d:propstat><d:prop><d:resourcetype>{}</d:resourcetype>{}<d:getlastmodified>{}</d:getlastmodified></d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat></d:response>\n",
+            href,
+            resourcetype,
+            length,
+            DateTime::<Utc>::from(entry.get_last_change_time()).format("%a, %d %b %Y %H:%M:%S GMT")
+        )
+    }
+}
+
+impl Drop for WebDavServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl DavRequest {
+    /// ### header
+    ///
+    /// Get value of header `name` (lowercase)
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.as_str() == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_filetransfer_fixtures_fmt_list_line() {
+        let mut fs: MemoryFileTransfer = MemoryFileTransfer::demo();
+        assert!(fs.connect(String::from("demo"), 22, None, None).is_ok());
+        let file: FsEntry = fs
+            .stat(Path::new("/home/demo/photos/sea.jpg"))
+            .ok()
+            .unwrap();
+        let line: String = FtpSession::fmt_list_line(&file);
+        assert!(line.starts_with("-rw-r--r-- 1 0 0 8192 "));
+        assert!(line.ends_with(" sea.jpg"));
+        let dir: FsEntry = fs.stat(Path::new("/home/demo/photos")).ok().unwrap();
+        let line: String = FtpSession::fmt_list_line(&dir);
+        assert!(line.starts_with("drwxr-xr-x 1 0 0 4096 "));
+        assert!(line.ends_with(" photos"));
+    }
+
+    #[test]
+    fn test_filetransfer_fixtures_ftp_server() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::new()).ok().unwrap();
+        assert_eq!(server.address().as_str(), "127.0.0.1");
+        assert_ne!(server.port(), 0);
+        let mut stream: BufReader<TcpStream> = BufReader::new(
+            TcpStream::connect((server.address().as_str(), server.port()))
+                .ok()
+                .unwrap(),
+        );
+        let mut line: String = String::new();
+        assert!(stream.read_line(&mut line).is_ok());
+        assert!(line.starts_with("220 "));
+        assert!(stream.get_mut().write_all(b"FOO\r\n").is_ok());
+        line.clear();
+        assert!(stream.read_line(&mut line).is_ok());
+        assert!(line.starts_with("502 "));
+        assert!(stream.get_mut().write_all(b"QUIT\r\n").is_ok());
+        line.clear();
+        assert!(stream.read_line(&mut line).is_ok());
+        assert!(line.starts_with("221 "));
+    }
+
+    #[test]
+    fn test_filetransfer_fixtures_webdav_server() {
+        let server: WebDavServer = WebDavServer::start(MemoryFileTransfer::demo())
+            .ok()
+            .unwrap();
+        assert_eq!(server.address().as_str(), "127.0.0.1");
+        assert_ne!(server.port(), 0);
+        let request = |auth: &str| -> String {
+            let mut stream: TcpStream =
+                TcpStream::connect((server.address().as_str(), server.port()))
+                    .ok()
+                    .unwrap();
+            assert!(stream
+                .write_all(
+                    format!(
+                        "PROPFIND /home/demo HTTP/1.1\r\nDepth: 0\r\nAuthorization: {}\r\n\r\n",
+                        auth
+                    )
+                    .as_bytes()
+                )
+                .is_ok());
+            let mut response: String = String::new();
+            assert!(stream.read_to_string(&mut response).is_ok());
+            response
+        };
+        assert!(request("Basic Zm9vOmJhcg==").starts_with("HTTP/1.1 401 "));
+        let response: String = request(WEBDAV_AUTHORIZATION);
+        assert!(response.starts_with("HTTP/1.1 207 "));
+        assert!(response.contains("<d:href>/home/demo/</d:href>"));
+        assert!(response.contains("<d:collection/>"));
+    }
+}
@@ -0,0 +1,455 @@
+//! ## Sftp
+//!
+//! `sftp` is the module which provides the SFTP (version 3) subsystem of the embedded SSH server
+
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::ssh::{Fields, Message};
+// Ext
+use std::collections::HashMap;
+use std::fs::{self, DirBuilder, File, FileTimes, Metadata, OpenOptions, Permissions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::fs::{symlink, DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+// Packets
+const SSH_FXP_INIT: u8 = 1;
+const SSH_FXP_VERSION: u8 = 2;
+const SSH_FXP_OPEN: u8 = 3;
+const SSH_FXP_CLOSE: u8 = 4;
+const SSH_FXP_READ: u8 = 5;
+const SSH_FXP_WRITE: u8 = 6;
+const SSH_FXP_LSTAT: u8 = 7;
+const SSH_FXP_FSTAT: u8 = 8;
+const SSH_FXP_SETSTAT: u8 = 9;
+const SSH_FXP_FSETSTAT: u8 = 10;
+const SSH_FXP_OPENDIR: u8 = 11;
+const SSH_FXP_READDIR: u8 = 12;
+const SSH_FXP_REMOVE: u8 = 13;
+const SSH_FXP_MKDIR: u8 = 14;
+const SSH_FXP_RMDIR: u8 = 15;
+const SSH_FXP_REALPATH: u8 = 16;
+const SSH_FXP_STAT: u8 = 17;
+const SSH_FXP_RENAME: u8 = 18;
+const SSH_FXP_READLINK: u8 = 19;
+const SSH_FXP_SYMLINK: u8 = 20;
+const SSH_FXP_STATUS: u8 = 101;
+const SSH_FXP_HANDLE: u8 = 102;
+const SSH_FXP_DATA: u8 = 103;
+const SSH_FXP_NAME: u8 = 104;
+const SSH_FXP_ATTRS: u8 = 105;
+// Status codes
+const SSH_FX_OK: u32 = 0;
+const SSH_FX_EOF: u32 = 1;
+const SSH_FX_NO_SUCH_FILE: u32 = 2;
+const SSH_FX_PERMISSION_DENIED: u32 = 3;
+const SSH_FX_FAILURE: u32 = 4;
+const SSH_FX_OP_UNSUPPORTED: u32 = 8;
+// Attributes
+const SSH_FILEXFER_ATTR_SIZE: u32 = 0x01;
+const SSH_FILEXFER_ATTR_UIDGID: u32 = 0x02;
+const SSH_FILEXFER_ATTR_PERMISSIONS: u32 = 0x04;
+const SSH_FILEXFER_ATTR_ACMODTIME: u32 = 0x08;
+// Open flags
+const SSH_FXF_READ: u32 = 0x01;
+const SSH_FXF_WRITE: u32 = 0x02;
+const SSH_FXF_APPEND: u32 = 0x04;
+const SSH_FXF_CREAT: u32 = 0x08;
+const SSH_FXF_TRUNC: u32 = 0x10;
+const SSH_FXF_EXCL: u32 = 0x20;
+/// Max length of data returned by a read
+const READ_CHUNK: u32 = 32768;
+/// Max amount of entries returned by a directory read
+const READDIR_ENTRIES: usize = 100;
+
+/// ## SftpServer
+///
+/// Serves the SFTP requests of a channel. Relative paths are resolved from `home`
+pub(super) struct SftpServer {
+    home: PathBuf,
+    handles: HashMap<u32, SftpHandle>,
+    next_handle: u32,
+}
+
+/// ## SftpHandle
+///
+/// An open file or directory; directories keep the entries which haven't been read yet
+enum SftpHandle {
+    File(File),
+    Dir(Vec<(String, Metadata)>),
+}
+
+/// ## Attrs
+///
+/// File attributes set by the client. Ownership is never changed
+struct Attrs {
+    size: Option<u64>,
+    permissions: Option<u32>,
+    times: Option<(u32, u32)>, // Access and modification time
+}
+
+impl SftpServer {
+    /// ### new
+    ///
+    /// Instantiates a new `SftpServer`
+    pub fn new(home: PathBuf) -> SftpServer {
+        SftpServer {
+            home,
+            handles: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    /// ### run
+    ///
+    /// Read requests from `reader` and write responses to `writer`, until the client sends EOF
+    pub fn run<R: Read, W: Write>(&mut self, mut reader: R, mut writer: W) -> io::Result<()> {
+        loop {
+            let mut len: [u8; 4] = [0; 4];
+            match reader.read_exact(&mut len) {
+                Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(()),
+                result => result?,
+            }
+            let mut packet: Vec<u8> = vec![0; u32::from_be_bytes(len) as usize];
+            reader.read_exact(packet.as_mut_slice())?;
+            let response: Message = self.handle(packet.as_slice())?;
+            writer.write_all(
+                Message::empty()
+                    .string(response.into_bytes().as_slice())
+                    .into_bytes()
+                    .as_slice(),
+            )?;
+        }
+    }
+
+    /// ### handle
+    ///
+    /// Handle a packet and return the response. Failed requests get a status response
+    fn handle(&mut self, packet: &[u8]) -> io::Result<Message> {
+        let (kind, mut fields): (u8, Fields) = match packet.split_first() {
+            Some((kind, data)) => (*kind, Fields::new(data)),
+            None => return Err(io::Error::new(io::ErrorKind::InvalidData, "empty packet")),
+        };
+        if kind == SSH_FXP_INIT {
+            return Ok(Message::new(SSH_FXP_VERSION).u32(3));
+        }
+        let id: u32 = fields.u32()?;
+        Ok(match self.request(kind, id, &mut fields) {
+            Ok(response) => response,
+            Err(err) => {
+                let code: u32 = match err.kind() {
+                    io::ErrorKind::NotFound => SSH_FX_NO_SUCH_FILE,
+                    io::ErrorKind::PermissionDenied => SSH_FX_PERMISSION_DENIED,
+                    _ => SSH_FX_FAILURE,
+                };
+                Self::status(id, code, err.to_string().as_str())
+            }
+        })
+    }
+
+    /// ### request
+    ///
+    /// Run the request `kind` with `id`
+    fn request(&mut self, kind: u8, id: u32, fields: &mut Fields) -> io::Result<Message> {
+        match kind {
+            SSH_FXP_OPEN => {
+                let path: PathBuf = self.path(fields)?;
+                let flags: u32 = fields.u32()?;
+                let attrs: Attrs = Attrs::parse(fields)?;
+                // std refuses append with truncate; on an emptied file writing is the same thing
+                let file: File = OpenOptions::new()
+                    .read(flags & SSH_FXF_READ != 0)
+                    .write(flags & SSH_FXF_WRITE != 0)
+                    .append(flags & SSH_FXF_APPEND != 0 && flags & SSH_FXF_TRUNC == 0)
+                    .create(flags & SSH_FXF_CREAT != 0)
+                    .truncate(flags & SSH_FXF_TRUNC != 0)
+                    .create_new(flags & SSH_FXF_CREAT != 0 && flags & SSH_FXF_EXCL != 0)
+                    .mode(attrs.permissions.unwrap_or(0o644))
+                    .open(path.as_path())?;
+                Ok(self.open_handle(id, SftpHandle::File(file)))
+            }
+            SSH_FXP_CLOSE => {
+                let handle: u32 = Self::handle_id(fields)?;
+                match self.handles.remove(&handle) {
+                    Some(_) => Ok(Self::status(id, SSH_FX_OK, "")),
+                    None => Err(Self::bad_handle()),
+                }
+            }
+            SSH_FXP_READ => {
+                let handle: u32 = Self::handle_id(fields)?;
+                let offset: u64 = fields.u64()?;
+                let len: u32 = fields.u32()?;
+                let file: &mut File = self.file(handle)?;
+                file.seek(SeekFrom::Start(offset))?;
+                let mut data: Vec<u8> = vec![0; len.min(READ_CHUNK) as usize];
+                match file.read(data.as_mut_slice())? {
+                    0 => Ok(Self::status(id, SSH_FX_EOF, "end of file")),
+                    len => Ok(Message::new(SSH_FXP_DATA).u32(id).string(&data[..len])),
+                }
+            }
+            SSH_FXP_WRITE => {
+                let handle: u32 = Self::handle_id(fields)?;
+                let offset: u64 = fields.u64()?;
+                let data: &[u8] = fields.string()?;
+                let file: &mut File = self.file(handle)?;
+                file.seek(SeekFrom::Start(offset))?;
+                file.write_all(data)?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_LSTAT | SSH_FXP_STAT | SSH_FXP_FSTAT => {
+                let metadata: Metadata = match kind {
+                    SSH_FXP_LSTAT => fs::symlink_metadata(self.path(fields)?)?,
+                    SSH_FXP_STAT => fs::metadata(self.path(fields)?)?,
+                    _ => {
+                        let handle: u32 = Self::handle_id(fields)?;
+                        self.file(handle)?.metadata()?
+                    }
+                };
+                Ok(Self::attrs(Message::new(SSH_FXP_ATTRS).u32(id), &metadata))
+            }
+            SSH_FXP_SETSTAT => {
+                let path: PathBuf = self.path(fields)?;
+                let attrs: Attrs = Attrs::parse(fields)?;
+                let file: File = OpenOptions::new()
+                    .read(true)
+                    .write(attrs.size.is_some())
+                    .open(path.as_path())?;
+                attrs.apply(&file)?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_FSETSTAT => {
+                let handle: u32 = Self::handle_id(fields)?;
+                let attrs: Attrs = Attrs::parse(fields)?;
+                attrs.apply(self.file(handle)?)?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_OPENDIR => {
+                let mut entries: Vec<(String, Metadata)> = fs::read_dir(self.path(fields)?)?
+                    .filter_map(|x| x.ok())
+                    .filter_map(|x| {
+                        x.metadata()
+                            .ok()
+                            .map(|metadata| (x.file_name().to_string_lossy().to_string(), metadata))
+                    })
+                    .collect();
+                // Sort entries, so that listings are deterministic
+                entries.sort_by(|a, b| a.0.cmp(&b.0));
+                Ok(self.open_handle(id, SftpHandle::Dir(entries)))
+            }
+            SSH_FXP_READDIR => {
+                let handle: u32 = Self::handle_id(fields)?;
+                let entries: &mut Vec<(String, Metadata)> = match self.handles.get_mut(&handle) {
+                    Some(SftpHandle::Dir(entries)) => entries,
+                    _ => return Err(Self::bad_handle()),
+                };
+                if entries.is_empty() {
+                    return Ok(Self::status(id, SSH_FX_EOF, "no more entries"));
+                }
+                let len: usize = entries.len().min(READDIR_ENTRIES);
+                let mut response: Message = Message::new(SSH_FXP_NAME).u32(id).u32(len as u32);
+                for (name, metadata) in entries.drain(..len) {
+                    response = Self::attrs(response.str(name.as_str()).str(name.as_str()), &metadata);
+                }
+                Ok(response)
+            }
+            SSH_FXP_REMOVE => {
+                fs::remove_file(self.path(fields)?)?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_MKDIR => {
+                let path: PathBuf = self.path(fields)?;
+                let attrs: Attrs = Attrs::parse(fields)?;
+                DirBuilder::new()
+                    .mode(attrs.permissions.unwrap_or(0o755))
+                    .create(path.as_path())?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_RMDIR => {
+                fs::remove_dir(self.path(fields)?)?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_REALPATH | SSH_FXP_READLINK => {
+                let path: PathBuf = self.path(fields)?;
+                let path: PathBuf = match kind {
+                    SSH_FXP_REALPATH => path.canonicalize()?,
+                    _ => fs::read_link(path.as_path())?,
+                };
+                let name: String = path.to_string_lossy().to_string();
+                Ok(Message::new(SSH_FXP_NAME)
+                    .u32(id)
+                    .u32(1)
+                    .str(name.as_str())
+                    .str(name.as_str())
+                    .u32(0))
+            }
+            SSH_FXP_RENAME => {
+                let src: PathBuf = self.path(fields)?;
+                let dst: PathBuf = self.path(fields)?;
+                fs::rename(src.as_path(), dst.as_path())?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            SSH_FXP_SYMLINK => {
+                // Like OpenSSH, the target comes first; it is not resolved
+                let target: String = fields.str()?;
+                let path: PathBuf = self.path(fields)?;
+                symlink(target.as_str(), path.as_path())?;
+                Ok(Self::status(id, SSH_FX_OK, ""))
+            }
+            _ => Ok(Self::status(
+                id,
+                SSH_FX_OP_UNSUPPORTED,
+                "operation not supported",
+            )),
+        }
+    }
+
+    /// ### path
+    ///
+    /// Parse a path, resolving it from home if relative
+    fn path(&self, fields: &mut Fields) -> io::Result<PathBuf> {
+        fields.str().map(|x| self.home.join(x))
+    }
+
+    /// ### open_handle
+    ///
+    /// Register a new handle and return the response with its id
+    fn open_handle(&mut self, id: u32, handle: SftpHandle) -> Message {
+        let handle_id: u32 = self.next_handle;
+        self.next_handle += 1;
+        self.handles.insert(handle_id, handle);
+        Message::new(SSH_FXP_HANDLE)
+            .u32(id)
+            .string(&handle_id.to_be_bytes())
+    }
+
+    /// ### file
+    ///
+    /// Get the file open with `handle`
+    fn file(&mut self, handle: u32) -> io::Result<&mut File> {
+        match self.handles.get_mut(&handle) {
+            Some(SftpHandle::File(file)) => Ok(file),
+            _ => Err(Self::bad_handle()),
+        }
+    }
+
+    /// ### handle_id
+    ///
+    /// Parse a handle
+    fn handle_id(fields: &mut Fields) -> io::Result<u32> {
+        match fields.string()? {
+            [a, b, c, d] => Ok(u32::from_be_bytes([*a, *b, *c, *d])),
+            _ => Err(Self::bad_handle()),
+        }
+    }
+
+    /// ### bad_handle
+    ///
+    /// Error returned for handles which aren't open
+    fn bad_handle() -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidInput, "invalid handle")
+    }
+
+    /// ### status
+    ///
+    /// Make a status response
+    fn status(id: u32, code: u32, msg: &str) -> Message {
+        Message::new(SSH_FXP_STATUS)
+            .u32(id)
+            .u32(code)
+            .str(msg)
+            .str("")
+    }
+
+    /// ### attrs
+    ///
+    /// Append the attributes of a file to `message`
+    fn attrs(message: Message, metadata: &Metadata) -> Message {
+        message
+            .u32(
+                SSH_FILEXFER_ATTR_SIZE
+                    | SSH_FILEXFER_ATTR_UIDGID
+                    | SSH_FILEXFER_ATTR_PERMISSIONS
+                    | SSH_FILEXFER_ATTR_ACMODTIME,
+            )
+            .u64(metadata.size())
+            .u32(metadata.uid())
+            .u32(metadata.gid())
+            .u32(metadata.mode())
+            .u32(metadata.atime() as u32)
+            .u32(metadata.mtime() as u32)
+    }
+}
+
+impl Attrs {
+    /// ### parse
+    ///
+    /// Parse attributes; extended attributes are ignored
+    fn parse(fields: &mut Fields) -> io::Result<Attrs> {
+        let flags: u32 = fields.u32()?;
+        let size: Option<u64> = match flags & SSH_FILEXFER_ATTR_SIZE {
+            0 => None,
+            _ => Some(fields.u64()?),
+        };
+        if flags & SSH_FILEXFER_ATTR_UIDGID != 0 {
+            fields.u32()?;
+            fields.u32()?;
+        }
+        let permissions: Option<u32> = match flags & SSH_FILEXFER_ATTR_PERMISSIONS {
+            0 => None,
+            _ => Some(fields.u32()? & 0o7777),
+        };
+        let times: Option<(u32, u32)> = match flags & SSH_FILEXFER_ATTR_ACMODTIME {
+            0 => None,
+            _ => Some((fields.u32()?, fields.u32()?)),
+        };
+        Ok(Attrs {
+            size,
+            permissions,
+            times,
+        })
+    }
+
+    /// ### apply
+    ///
+    /// Apply attributes to `file`
+    fn apply(&self, file: &File) -> io::Result<()> {
+        if let Some(size) = self.size {
+            file.set_len(size)?;
+        }
+        if let Some(mode) = self.permissions {
+            file.set_permissions(Permissions::from_mode(mode))?;
+        }
+        if let Some((atime, mtime)) = self.times {
+            file.set_times(
+                FileTimes::new()
+                    .set_accessed(UNIX_EPOCH + Duration::from_secs(atime as u64))
+                    .set_modified(UNIX_EPOCH + Duration::from_secs(mtime as u64)),
+            )?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1146 @@
+//! ## Ssh
+//!
+//! `ssh` is the module which provides the embedded SSH server. It serves a temporary directory,
+//! running exec requests with the system shell and the `sftp` subsystem with `SftpServer`
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::listen;
+use super::sftp::SftpServer;
+use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+use crate::filetransfer::FileTransfer;
+use crate::fs::FsEntry;
+// Ext
+use openssl::derive::Deriver;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{Id, PKey, Private};
+use openssl::rand::rand_bytes;
+use openssl::sha::{sha256, Sha256};
+use openssl::sign::Signer;
+use openssl::symm::{Cipher, Crypter, Mode};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use tempfile::TempDir;
+
+/// Identification string sent by `SshServer`
+const SSH_VERSION: &str = "SSH-2.0-termscp_test_server";
+/// Credentials accepted by `SshServer`
+const SSH_USERNAME: &str = "omar";
+const SSH_PASSWORD: &str = "secret";
+/// Flow control of channels opened by clients
+const CHANNEL_WINDOW: u32 = 2 * 1024 * 1024;
+const CHANNEL_MAX_PACKET: u32 = 32768;
+/// Max length of data sent in a single message
+const DATA_CHUNK: usize = 16384;
+/// Max length of packets received
+const MAX_PACKET_LEN: usize = 256 * 1024;
+
+// Messages
+const SSH_MSG_DISCONNECT: u8 = 1;
+const SSH_MSG_IGNORE: u8 = 2;
+const SSH_MSG_UNIMPLEMENTED: u8 = 3;
+const SSH_MSG_DEBUG: u8 = 4;
+const SSH_MSG_SERVICE_REQUEST: u8 = 5;
+const SSH_MSG_SERVICE_ACCEPT: u8 = 6;
+const SSH_MSG_KEXINIT: u8 = 20;
+const SSH_MSG_NEWKEYS: u8 = 21;
+const SSH_MSG_KEX_ECDH_INIT: u8 = 30;
+const SSH_MSG_KEX_ECDH_REPLY: u8 = 31;
+const SSH_MSG_USERAUTH_REQUEST: u8 = 50;
+const SSH_MSG_USERAUTH_FAILURE: u8 = 51;
+const SSH_MSG_USERAUTH_SUCCESS: u8 = 52;
+const SSH_MSG_GLOBAL_REQUEST: u8 = 80;
+const SSH_MSG_REQUEST_FAILURE: u8 = 82;
+const SSH_MSG_CHANNEL_OPEN: u8 = 90;
+const SSH_MSG_CHANNEL_OPEN_CONFIRMATION: u8 = 91;
+const SSH_MSG_CHANNEL_OPEN_FAILURE: u8 = 92;
+const SSH_MSG_CHANNEL_WINDOW_ADJUST: u8 = 93;
+const SSH_MSG_CHANNEL_DATA: u8 = 94;
+const SSH_MSG_CHANNEL_EXTENDED_DATA: u8 = 95;
+const SSH_MSG_CHANNEL_EOF: u8 = 96;
+const SSH_MSG_CHANNEL_CLOSE: u8 = 97;
+const SSH_MSG_CHANNEL_REQUEST: u8 = 98;
+const SSH_MSG_CHANNEL_SUCCESS: u8 = 99;
+const SSH_MSG_CHANNEL_FAILURE: u8 = 100;
+
+/// ## SshServer
+///
+/// A minimal SSH server listening on localhost, which serves a temporary directory.
+/// It only supports the algorithms curve25519-sha256, ssh-ed25519, aes128-ctr and hmac-sha2-256
+/// and the password authentication, with user "omar" and password "secret".
+/// Commands are executed by `sh` in the served directory, which is also the working directory of SFTP.
+/// The server is stopped and the directory is removed when dropped
+pub struct SshServer {
+    addr: SocketAddr,
+    home: PathBuf,
+    running: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+    _home_dir: TempDir, // Removed on drop
+}
+
+/// ## SshSession
+///
+/// State of a single client connection, once keys have been exchanged
+struct SshSession {
+    home: PathBuf,
+    reader: PacketReader,
+    writer: Arc<Mutex<PacketWriter>>,
+    authenticated: bool,
+    channels: HashMap<u32, SessionChannel>,
+    next_channel: u32,
+}
+
+/// ## SessionChannel
+///
+/// A channel opened by the client; `input` receives the data sent by the client to the process
+struct SessionChannel {
+    channel: Arc<SshChannel>,
+    input: Option<Sender<Vec<u8>>>,
+    started: bool,
+}
+
+/// ## SshChannel
+///
+/// The server side of a channel, shared with the process which writes its output
+struct SshChannel {
+    writer: Arc<Mutex<PacketWriter>>,
+    recipient: u32,
+    max_packet: u32,
+    state: Mutex<ChannelState>,
+    cond: Condvar,
+}
+
+/// ## ChannelState
+///
+/// Flow control of a channel: the bytes the client is willing to receive, whether the client
+/// closed the channel (the output is then discarded) and whether it is closed on both sides
+struct ChannelState {
+    window: u64,
+    discard: bool,
+    closed: bool,
+}
+
+/// ## ChannelWriter
+///
+/// Writes data (or extended data of type `ext`) to a channel, waiting for the client window
+struct ChannelWriter {
+    channel: Arc<SshChannel>,
+    ext: Option<u32>,
+}
+
+/// ## ChannelReader
+///
+/// Reads the data sent by the client to a channel, until it sends EOF
+struct ChannelReader {
+    input: Receiver<Vec<u8>>,
+    buffer: Vec<u8>,
+    pos: usize,
+}
+
+/// ## Keys
+///
+/// Cipher and MAC key for a direction of the connection
+struct Keys {
+    crypter: Crypter,
+    mac_key: Vec<u8>,
+}
+
+/// ## PacketWriter
+///
+/// Sends binary packets to the client
+struct PacketWriter {
+    stream: TcpStream,
+    seq: u32,
+    keys: Option<Keys>,
+}
+
+/// ## PacketReader
+///
+/// Receives binary packets from the client
+struct PacketReader {
+    stream: BufReader<TcpStream>,
+    seq: u32,
+    keys: Option<Keys>,
+}
+
+/// ## Message
+///
+/// Builder of a SSH (or SFTP) message, with the data types of RFC 4251
+pub(super) struct Message(Vec<u8>);
+
+/// ## Fields
+///
+/// Parser of the fields of a SSH (or SFTP) message
+pub(super) struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl SshServer {
+    /// ### start
+    ///
+    /// Start a new SSH server on a random port of localhost, serving a temporary directory
+    /// filled with the content of the working directory of `fs`
+    pub fn start(mut fs: MemoryFileTransfer) -> io::Result<SshServer> {
+        let home_dir: TempDir = TempDir::new()?;
+        // Resolve links, so that the path is the same reported by `pwd`
+        let home: PathBuf = home_dir.path().canonicalize()?;
+        let _ = fs.connect(String::from("localhost"), 22, None, None);
+        let wrkdir: PathBuf = fs.pwd().map_err(|err| io::Error::other(err.to_string()))?;
+        Self::copy_dir(&mut fs, wrkdir.as_path(), home.as_path())?;
+        let host_key: Arc<PKey<Private>> = Arc::new(PKey::generate_ed25519()?);
+        let session_home: PathBuf = home.clone();
+        let (addr, running, thread) = listen(move |stream| {
+            if let Ok(mut session) =
+                SshSession::start(session_home.clone(), host_key.as_ref(), stream)
+            {
+                let _ = session.run();
+            }
+        })?;
+        Ok(SshServer {
+            addr,
+            home,
+            running,
+            thread: Some(thread),
+            _home_dir: home_dir,
+        })
+    }
+
+    /// ### address
+    ///
+    /// Get the address the server is listening on
+    pub fn address(&self) -> String {
+        self.addr.ip().to_string()
+    }
+
+    /// ### port
+    ///
+    /// Get the port the server is listening on
+    pub fn port(&self) -> u16 {
+        self.addr.port()
+    }
+
+    /// ### home
+    ///
+    /// Get the path of the served directory, to prepare or verify its content
+    pub fn home(&self) -> &Path {
+        self.home.as_path()
+    }
+
+    /// ### copy_dir
+    ///
+    /// Copy the content of `src` on `fs` to the local directory `dst`
+    fn copy_dir(fs: &mut MemoryFileTransfer, src: &Path, dst: &Path) -> io::Result<()> {
+        let io_err =
+            |err: crate::filetransfer::FileTransferError| io::Error::other(err.to_string());
+        for entry in fs.list_dir(src).map_err(io_err)? {
+            let path: PathBuf = dst.join(entry.get_name());
+            match entry {
+                FsEntry::Directory(dir) => {
+                    fs::create_dir(path.as_path())?;
+                    Self::copy_dir(fs, dir.abs_path.as_path(), path.as_path())?;
+                }
+                FsEntry::File(file) => {
+                    let mut reader: Box<dyn Read> = fs.recv_file(&file).map_err(io_err)?;
+                    io::copy(&mut reader, &mut File::create(path.as_path())?)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SshServer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl SshSession {
+    /// ### start
+    ///
+    /// Exchange versions and keys with the client connected on `stream`
+    fn start(home: PathBuf, host_key: &PKey<Private>, stream: TcpStream) -> io::Result<SshSession> {
+        stream.set_nonblocking(false)?;
+        let mut writer: PacketWriter = PacketWriter {
+            stream: stream.try_clone()?,
+            seq: 0,
+            keys: None,
+        };
+        writer
+            .stream
+            .write_all(format!("{}\r\n", SSH_VERSION).as_bytes())?;
+        let mut reader: PacketReader = PacketReader {
+            stream: BufReader::new(stream),
+            seq: 0,
+            keys: None,
+        };
+        let client_version: String = reader.read_version()?;
+        Self::key_exchange(&mut reader, &mut writer, client_version.as_str(), host_key)?;
+        Ok(SshSession {
+            home,
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+            authenticated: false,
+            channels: HashMap::new(),
+            next_channel: 0,
+        })
+    }
+
+    /// ### key_exchange
+    ///
+    /// Run the key exchange with curve25519-sha256, signing the exchange hash with `host_key`,
+    /// then enable encryption on both directions
+    fn key_exchange(
+        reader: &mut PacketReader,
+        writer: &mut PacketWriter,
+        client_version: &str,
+        host_key: &PKey<Private>,
+    ) -> io::Result<()> {
+        let mut cookie: [u8; 16] = [0; 16];
+        rand_bytes(&mut cookie)?;
+        let server_kexinit: Vec<u8> = Message::new(SSH_MSG_KEXINIT)
+            .raw(&cookie)
+            .str("curve25519-sha256,curve25519-sha256@libssh.org")
+            .str("ssh-ed25519")
+            .str("aes128-ctr")
+            .str("aes128-ctr")
+            .str("hmac-sha2-256")
+            .str("hmac-sha2-256")
+            .str("none")
+            .str("none")
+            .str("")
+            .str("")
+            .bool(false)
+            .u32(0)
+            .into_bytes();
+        writer.send(server_kexinit.as_slice())?;
+        let client_kexinit: Vec<u8> = reader.recv_kind(SSH_MSG_KEXINIT)?;
+        let ecdh_init: Vec<u8> = reader.recv_kind(SSH_MSG_KEX_ECDH_INIT)?;
+        let client_public: &[u8] = Fields::new(&ecdh_init[1..]).string()?;
+        // Compute shared secret
+        let ephemeral: PKey<Private> = PKey::generate_x25519()?;
+        let server_public: Vec<u8> = ephemeral.raw_public_key()?;
+        let peer = PKey::public_key_from_raw_bytes(client_public, Id::X25519)?;
+        let mut deriver: Deriver = Deriver::new(ephemeral.as_ref())?;
+        deriver.set_peer(peer.as_ref())?;
+        let secret: Vec<u8> = Message::empty()
+            .mpint(deriver.derive_to_vec()?.as_slice())
+            .into_bytes();
+        // Sign exchange hash
+        let host_public: Vec<u8> = Message::empty()
+            .str("ssh-ed25519")
+            .string(host_key.raw_public_key()?.as_slice())
+            .into_bytes();
+        let hash: [u8; 32] = sha256(
+            Message::empty()
+                .str(client_version)
+                .str(SSH_VERSION)
+                .string(client_kexinit.as_slice())
+                .string(server_kexinit.as_slice())
+                .string(host_public.as_slice())
+                .string(client_public)
+                .string(server_public.as_slice())
+                .raw(secret.as_slice())
+                .into_bytes()
+                .as_slice(),
+        );
+        let signature: Vec<u8> =
+            Signer::new_without_digest(host_key)?.sign_oneshot_to_vec(&hash)?;
+        writer.send(
+            Message::new(SSH_MSG_KEX_ECDH_REPLY)
+                .string(host_public.as_slice())
+                .string(server_public.as_slice())
+                .string(
+                    Message::empty()
+                        .str("ssh-ed25519")
+                        .string(signature.as_slice())
+                        .into_bytes()
+                        .as_slice(),
+                )
+                .into_bytes()
+                .as_slice(),
+        )?;
+        // Derive keys; the exchange hash is also the session id
+        let derive = |letter: u8, len: usize| -> Vec<u8> {
+            let mut hasher: Sha256 = Sha256::new();
+            hasher.update(secret.as_slice());
+            hasher.update(&hash);
+            hasher.update(&[letter]);
+            hasher.update(&hash);
+            hasher.finish()[..len].to_vec()
+        };
+        writer.send(&[SSH_MSG_NEWKEYS])?;
+        writer.keys = Some(Keys::new(
+            Mode::Encrypt,
+            derive(b'B', 16).as_slice(),
+            derive(b'D', 16).as_slice(),
+            derive(b'F', 32),
+        )?);
+        reader.recv_kind(SSH_MSG_NEWKEYS)?;
+        reader.keys = Some(Keys::new(
+            Mode::Decrypt,
+            derive(b'A', 16).as_slice(),
+            derive(b'C', 16).as_slice(),
+            derive(b'E', 32),
+        )?);
+        Ok(())
+    }
+
+    /// ### run
+    ///
+    /// Handle messages until the client disconnects
+    fn run(&mut self) -> io::Result<()> {
+        loop {
+            let payload: Vec<u8> = self.reader.recv()?;
+            if !self.handle(payload.as_slice())? {
+                return Ok(());
+            }
+        }
+    }
+
+    /// ### handle
+    ///
+    /// Handle a message; returns whether the session goes on
+    fn handle(&mut self, payload: &[u8]) -> io::Result<bool> {
+        let (kind, mut fields): (u8, Fields) = match payload.split_first() {
+            Some((kind, data)) => (*kind, Fields::new(data)),
+            None => return Err(protocol_error("empty message")),
+        };
+        match kind {
+            SSH_MSG_DISCONNECT => return Ok(false),
+            SSH_MSG_IGNORE | SSH_MSG_DEBUG | SSH_MSG_UNIMPLEMENTED => {}
+            SSH_MSG_SERVICE_REQUEST => {
+                let service: &[u8] = fields.string()?;
+                self.send(Message::new(SSH_MSG_SERVICE_ACCEPT).string(service))?;
+            }
+            SSH_MSG_USERAUTH_REQUEST => {
+                let username: String = fields.str()?;
+                fields.string()?; // Service
+                let password: Option<String> = match fields.str()?.as_str() {
+                    "password" => {
+                        fields.bool()?;
+                        Some(fields.str()?)
+                    }
+                    _ => None,
+                };
+                self.authenticated =
+                    username == SSH_USERNAME && password.as_deref() == Some(SSH_PASSWORD);
+                match self.authenticated {
+                    true => self.send(Message::new(SSH_MSG_USERAUTH_SUCCESS))?,
+                    false => self.send(
+                        Message::new(SSH_MSG_USERAUTH_FAILURE)
+                            .str("password")
+                            .bool(false),
+                    )?,
+                }
+            }
+            SSH_MSG_GLOBAL_REQUEST => {
+                fields.string()?;
+                if fields.bool()? {
+                    self.send(Message::new(SSH_MSG_REQUEST_FAILURE))?;
+                }
+            }
+            SSH_MSG_CHANNEL_OPEN if self.authenticated => {
+                let channel_type: String = fields.str()?;
+                let sender: u32 = fields.u32()?;
+                let window: u32 = fields.u32()?;
+                let max_packet: u32 = fields.u32()?;
+                if channel_type != "session" {
+                    self.send(
+                        Message::new(SSH_MSG_CHANNEL_OPEN_FAILURE)
+                            .u32(sender)
+                            .u32(3) // Unknown channel type
+                            .str("unknown channel type")
+                            .str(""),
+                    )?;
+                    return Ok(true);
+                }
+                let id: u32 = self.next_channel;
+                self.next_channel += 1;
+                self.channels.insert(
+                    id,
+                    SessionChannel {
+                        channel: Arc::new(SshChannel::new(
+                            self.writer.clone(),
+                            sender,
+                            window,
+                            max_packet,
+                        )),
+                        input: None,
+                        started: false,
+                    },
+                );
+                self.send(
+                    Message::new(SSH_MSG_CHANNEL_OPEN_CONFIRMATION)
+                        .u32(sender)
+                        .u32(id)
+                        .u32(CHANNEL_WINDOW)
+                        .u32(CHANNEL_MAX_PACKET),
+                )?;
+            }
+            SSH_MSG_CHANNEL_REQUEST => {
+                let id: u32 = fields.u32()?;
+                let request: String = fields.str()?;
+                let want_reply: bool = fields.bool()?;
+                self.request(id, request.as_str(), want_reply, &mut fields)?;
+            }
+            SSH_MSG_CHANNEL_WINDOW_ADJUST => {
+                let id: u32 = fields.u32()?;
+                let bytes: u32 = fields.u32()?;
+                if let Some(channel) = self.channels.get(&id) {
+                    channel.channel.adjust(bytes);
+                }
+            }
+            SSH_MSG_CHANNEL_DATA | SSH_MSG_CHANNEL_EXTENDED_DATA => {
+                let id: u32 = fields.u32()?;
+                if kind == SSH_MSG_CHANNEL_EXTENDED_DATA {
+                    fields.u32()?; // Extended data is discarded
+                }
+                let data: &[u8] = fields.string()?;
+                if let Some(channel) = self.channels.get(&id) {
+                    if let (SSH_MSG_CHANNEL_DATA, Some(input)) = (kind, channel.input.as_ref()) {
+                        let _ = input.send(data.to_vec());
+                    }
+                    // Data is consumed as soon as received
+                    let recipient: u32 = channel.channel.recipient;
+                    self.send(
+                        Message::new(SSH_MSG_CHANNEL_WINDOW_ADJUST)
+                            .u32(recipient)
+                            .u32(data.len() as u32),
+                    )?;
+                }
+            }
+            SSH_MSG_CHANNEL_EOF => {
+                if let Some(channel) = self.channels.get_mut(&fields.u32()?) {
+                    channel.input = None;
+                }
+            }
+            SSH_MSG_CHANNEL_CLOSE => {
+                if let Some(channel) = self.channels.remove(&fields.u32()?) {
+                    channel.channel.close(channel.started)?;
+                }
+            }
+            _ => {
+                let seq: u32 = self.reader.seq.wrapping_sub(1);
+                self.send(Message::new(SSH_MSG_UNIMPLEMENTED).u32(seq))?;
+            }
+        }
+        Ok(true)
+    }
+
+    /// ### request
+    ///
+    /// Handle a channel request. Exec requests and the `sftp` subsystem are run in a new thread,
+    /// other requests are refused
+    fn request(
+        &mut self,
+        id: u32,
+        request: &str,
+        want_reply: bool,
+        fields: &mut Fields,
+    ) -> io::Result<()> {
+        let (channel, started): (Arc<SshChannel>, bool) = match self.channels.get(&id) {
+            Some(channel) => (channel.channel.clone(), channel.started),
+            None => return Err(protocol_error("no such channel")),
+        };
+        let recipient: u32 = channel.recipient;
+        let (sender, input): (Sender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::channel();
+        let input: ChannelReader = ChannelReader::new(input);
+        let home: PathBuf = self.home.clone();
+        let process: Option<Box<dyn FnOnce() + Send>> = match (started, request) {
+            (false, "exec") => {
+                let command: String = fields.str()?;
+                Some(Box::new(move || exec(channel, input, home, command)))
+            }
+            (false, "subsystem") if fields.str()?.as_str() == "sftp" => Some(Box::new(move || {
+                let writer: ChannelWriter = ChannelWriter::new(channel.clone(), None);
+                let _ = SftpServer::new(home).run(input, writer);
+                let _ = channel.finish(Some(0));
+            })),
+            _ => None,
+        };
+        match process {
+            Some(process) => {
+                if let Some(channel) = self.channels.get_mut(&id) {
+                    channel.input = Some(sender);
+                    channel.started = true;
+                }
+                // Reply before the process sends any data
+                if want_reply {
+                    self.send(Message::new(SSH_MSG_CHANNEL_SUCCESS).u32(recipient))?;
+                }
+                thread::spawn(process);
+                Ok(())
+            }
+            None if want_reply => self.send(Message::new(SSH_MSG_CHANNEL_FAILURE).u32(recipient)),
+            None => Ok(()),
+        }
+    }
+
+    /// ### send
+    ///
+    /// Send a message to the client
+    fn send(&self, message: Message) -> io::Result<()> {
+        self.writer
+            .lock()
+            .unwrap()
+            .send(message.into_bytes().as_slice())
+    }
+}
+
+impl Drop for SshSession {
+    fn drop(&mut self) {
+        // Stop processes writing to the client
+        for channel in self.channels.values() {
+            channel.channel.abort();
+        }
+    }
+}
+
+/// ### exec
+///
+/// Execute `command` with `sh` in `home`, sending its output and exit status through `channel`
+fn exec(channel: Arc<SshChannel>, mut input: ChannelReader, home: PathBuf, command: String) {
+    let mut child = match Command::new("sh")
+        .arg("-c")
+        .arg(command.as_str())
+        .current_dir(home.as_path())
+        .env("HOME", home.as_path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => {
+            let _ = channel.finish(Some(127));
+            return;
+        }
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        thread::spawn(move || io::copy(&mut input, &mut stdin));
+    }
+    let stderr: Option<JoinHandle<io::Result<u64>>> = child.stderr.take().map(|mut stderr| {
+        let mut writer: ChannelWriter = ChannelWriter::new(channel.clone(), Some(1));
+        thread::spawn(move || io::copy(&mut stderr, &mut writer))
+    });
+    if let Some(mut stdout) = child.stdout.take() {
+        let _ = io::copy(&mut stdout, &mut ChannelWriter::new(channel.clone(), None));
+    }
+    if let Some(stderr) = stderr {
+        let _ = stderr.join();
+    }
+    let status: i32 = child.wait().ok().and_then(|x| x.code()).unwrap_or(255);
+    let _ = channel.finish(Some(status));
+}
+
+/// ### protocol_error
+///
+/// Error returned when the client violates the protocol
+fn protocol_error(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl SshChannel {
+    /// ### new
+    ///
+    /// Instantiates a new `SshChannel` with the client channel `recipient`
+    fn new(
+        writer: Arc<Mutex<PacketWriter>>,
+        recipient: u32,
+        window: u32,
+        max_packet: u32,
+    ) -> SshChannel {
+        SshChannel {
+            writer,
+            recipient,
+            max_packet,
+            state: Mutex::new(ChannelState {
+                window: window as u64,
+                discard: false,
+                closed: false,
+            }),
+            cond: Condvar::new(),
+        }
+    }
+
+    /// ### send_data
+    ///
+    /// Send data, as much as the client window allows; returns the amount of bytes sent
+    fn send_data(&self, ext: Option<u32>, data: &[u8]) -> io::Result<usize> {
+        let mut state = self.state.lock().unwrap();
+        while state.window == 0 && !state.discard && !state.closed {
+            state = self.cond.wait(state).unwrap();
+        }
+        if state.closed {
+            return Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "channel is closed",
+            ));
+        }
+        if state.discard {
+            return Ok(data.len());
+        }
+        let len: usize = data
+            .len()
+            .min(state.window as usize)
+            .min(self.max_packet as usize)
+            .min(DATA_CHUNK);
+        let message: Message = match ext {
+            Some(code) => Message::new(SSH_MSG_CHANNEL_EXTENDED_DATA)
+                .u32(self.recipient)
+                .u32(code),
+            None => Message::new(SSH_MSG_CHANNEL_DATA).u32(self.recipient),
+        };
+        self.writer
+            .lock()
+            .unwrap()
+            .send(message.string(&data[..len]).into_bytes().as_slice())?;
+        state.window -= len as u64;
+        Ok(len)
+    }
+
+    /// ### adjust
+    ///
+    /// Increase the client window
+    fn adjust(&self, bytes: u32) {
+        self.state.lock().unwrap().window += bytes as u64;
+        self.cond.notify_all();
+    }
+
+    /// ### finish
+    ///
+    /// Send EOF, the exit status (if any) and close the channel, unless it's already closed.
+    /// If the client closed the channel first, only the close is sent
+    fn finish(&self, exit_status: Option<i32>) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Ok(());
+        }
+        state.closed = true;
+        self.cond.notify_all();
+        let mut writer = self.writer.lock().unwrap();
+        if !state.discard {
+            writer.send(
+                Message::new(SSH_MSG_CHANNEL_EOF)
+                    .u32(self.recipient)
+                    .into_bytes()
+                    .as_slice(),
+            )?;
+        }
+        if let (false, Some(exit_status)) = (state.discard, exit_status) {
+            writer.send(
+                Message::new(SSH_MSG_CHANNEL_REQUEST)
+                    .u32(self.recipient)
+                    .str("exit-status")
+                    .bool(false)
+                    .u32(exit_status as u32)
+                    .into_bytes()
+                    .as_slice(),
+            )?;
+        }
+        writer.send(
+            Message::new(SSH_MSG_CHANNEL_CLOSE)
+                .u32(self.recipient)
+                .into_bytes()
+                .as_slice(),
+        )
+    }
+
+    /// ### close
+    ///
+    /// Reply to the client closing the channel, unless it's already closed.
+    /// If a process is `running`, the reply is sent by `finish` once it exits, so that the client
+    /// waits for it (e.g. for scp to write the file); its output is discarded meanwhile
+    fn close(&self, running: bool) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return Ok(());
+        }
+        state.discard = true;
+        self.cond.notify_all();
+        if running {
+            return Ok(());
+        }
+        state.closed = true;
+        self.cond.notify_all();
+        self.writer.lock().unwrap().send(
+            Message::new(SSH_MSG_CHANNEL_CLOSE)
+                .u32(self.recipient)
+                .into_bytes()
+                .as_slice(),
+        )
+    }
+
+    /// ### abort
+    ///
+    /// Mark the channel as closed, without notifying the client (e.g. after it disconnected)
+    fn abort(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.cond.notify_all();
+    }
+}
+
+impl ChannelWriter {
+    /// ### new
+    ///
+    /// Instantiates a new `ChannelWriter`
+    fn new(channel: Arc<SshChannel>, ext: Option<u32>) -> ChannelWriter {
+        ChannelWriter { channel, ext }
+    }
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match buf.is_empty() {
+            true => Ok(0),
+            false => self.channel.send_data(self.ext, buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl ChannelReader {
+    /// ### new
+    ///
+    /// Instantiates a new `ChannelReader`
+    fn new(input: Receiver<Vec<u8>>) -> ChannelReader {
+        ChannelReader {
+            input,
+            buffer: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buffer.len() {
+            match self.input.recv() {
+                Ok(data) => {
+                    self.buffer = data;
+                    self.pos = 0;
+                }
+                // Client sent EOF or closed the channel
+                Err(_) => return Ok(0),
+            }
+        }
+        let len: usize = buf.len().min(self.buffer.len() - self.pos);
+        buf[..len].copy_from_slice(&self.buffer[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+impl Keys {
+    /// ### new
+    ///
+    /// Instantiates new `Keys` for aes128-ctr and hmac-sha2-256
+    fn new(mode: Mode, iv: &[u8], key: &[u8], mac_key: Vec<u8>) -> io::Result<Keys> {
+        Ok(Keys {
+            crypter: Crypter::new(Cipher::aes_128_ctr(), mode, key, Some(iv))?,
+            mac_key,
+        })
+    }
+
+    /// ### crypt
+    ///
+    /// Encrypt or decrypt `data`, depending on mode
+    fn crypt(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        let mut output: Vec<u8> = vec![0; data.len() + 16];
+        let len: usize = self.crypter.update(data, output.as_mut_slice())?;
+        output.truncate(len);
+        Ok(output)
+    }
+
+    /// ### mac
+    ///
+    /// Compute the MAC of the unencrypted `packet`, with sequence number `seq`
+    fn mac(&self, seq: u32, packet: &[u8]) -> io::Result<Vec<u8>> {
+        let key: PKey<Private> = PKey::hmac(self.mac_key.as_slice())?;
+        let mut signer: Signer = Signer::new(MessageDigest::sha256(), key.as_ref())?;
+        signer.update(&seq.to_be_bytes())?;
+        signer.update(packet)?;
+        Ok(signer.sign_to_vec()?)
+    }
+}
+
+impl PacketWriter {
+    /// ### send
+    ///
+    /// Send a packet with `payload`, encrypted if keys have been exchanged
+    fn send(&mut self, payload: &[u8]) -> io::Result<()> {
+        let block: usize = match self.keys.is_some() {
+            true => 16,
+            false => 8,
+        };
+        let mut padding: usize = block - (payload.len() + 5) % block;
+        if padding < 4 {
+            padding += block;
+        }
+        let mut packet: Vec<u8> = Message::empty()
+            .u32((payload.len() + padding + 1) as u32)
+            .byte(padding as u8)
+            .raw(payload)
+            .raw(vec![0; padding].as_slice())
+            .into_bytes();
+        if let Some(keys) = self.keys.as_mut() {
+            let mac: Vec<u8> = keys.mac(self.seq, packet.as_slice())?;
+            packet = keys.crypt(packet.as_slice())?;
+            packet.extend_from_slice(mac.as_slice());
+        }
+        self.seq = self.seq.wrapping_add(1);
+        self.stream.write_all(packet.as_slice())
+    }
+}
+
+impl PacketReader {
+    /// ### read_version
+    ///
+    /// Read the identification string of the client, without line terminator
+    fn read_version(&mut self) -> io::Result<String> {
+        // Other lines may be sent before the identification string
+        for _ in 0..16 {
+            let mut line: String = String::new();
+            if self.stream.read_line(&mut line)? == 0 {
+                break;
+            }
+            if line.starts_with("SSH-") {
+                return Ok(line.trim_end_matches(['\r', '\n']).to_string());
+            }
+        }
+        Err(protocol_error("no identification string"))
+    }
+
+    /// ### recv
+    ///
+    /// Receive a packet and return its payload
+    fn recv(&mut self) -> io::Result<Vec<u8>> {
+        let block: usize = match self.keys.is_some() {
+            true => 16,
+            false => 8,
+        };
+        let mut packet: Vec<u8> = vec![0; block];
+        self.stream.read_exact(packet.as_mut_slice())?;
+        if let Some(keys) = self.keys.as_mut() {
+            packet = keys.crypt(packet.as_slice())?;
+        }
+        let len: usize = u32::from_be_bytes([packet[0], packet[1], packet[2], packet[3]]) as usize;
+        if len + 4 < block || len > MAX_PACKET_LEN {
+            return Err(protocol_error("bad packet length"));
+        }
+        let mut rest: Vec<u8> = vec![0; len + 4 - block];
+        self.stream.read_exact(rest.as_mut_slice())?;
+        if let Some(keys) = self.keys.as_mut() {
+            rest = keys.crypt(rest.as_slice())?;
+            packet.extend_from_slice(rest.as_slice());
+            let mut mac: [u8; 32] = [0; 32];
+            self.stream.read_exact(&mut mac)?;
+            if keys.mac(self.seq, packet.as_slice())?.as_slice() != mac {
+                return Err(protocol_error("bad MAC"));
+            }
+        } else {
+            packet.extend_from_slice(rest.as_slice());
+        }
+        self.seq = self.seq.wrapping_add(1);
+        let padding: usize = packet[4] as usize;
+        if padding + 1 > len {
+            return Err(protocol_error("bad padding length"));
+        }
+        Ok(packet[5..len + 4 - padding].to_vec())
+    }
+
+    /// ### recv_kind
+    ///
+    /// Receive a message of type `kind`, skipping ignored and debug messages
+    fn recv_kind(&mut self, kind: u8) -> io::Result<Vec<u8>> {
+        loop {
+            let payload: Vec<u8> = self.recv()?;
+            match payload.first() {
+                Some(x) if *x == kind => return Ok(payload),
+                Some(&SSH_MSG_IGNORE) | Some(&SSH_MSG_DEBUG) => {}
+                _ => return Err(protocol_error("unexpected message")),
+            }
+        }
+    }
+}
+
+impl Message {
+    /// ### new
+    ///
+    /// Instantiates a new `Message` of type `kind`
+    pub fn new(kind: u8) -> Message {
+        Message(vec![kind])
+    }
+
+    /// ### empty
+    ///
+    /// Instantiates a new empty `Message`, used to encode nested fields
+    pub fn empty() -> Message {
+        Message(Vec::new())
+    }
+
+    /// ### byte
+    ///
+    /// Append a byte
+    pub fn byte(mut self, x: u8) -> Message {
+        self.0.push(x);
+        self
+    }
+
+    /// ### bool
+    ///
+    /// Append a boolean
+    pub fn bool(self, x: bool) -> Message {
+        self.byte(x as u8)
+    }
+
+    /// ### u32
+    ///
+    /// Append a 32 bits unsigned integer
+    pub fn u32(self, x: u32) -> Message {
+        self.raw(&x.to_be_bytes())
+    }
+
+    /// ### u64
+    ///
+    /// Append a 64 bits unsigned integer
+    pub fn u64(self, x: u64) -> Message {
+        self.raw(&x.to_be_bytes())
+    }
+
+    /// ### string
+    ///
+    /// Append a string of bytes, preceded by its length
+    pub fn string(self, x: &[u8]) -> Message {
+        self.u32(x.len() as u32).raw(x)
+    }
+
+    /// ### str
+    ///
+    /// Append a UTF-8 string
+    pub fn str(self, x: &str) -> Message {
+        self.string(x.as_bytes())
+    }
+
+    /// ### mpint
+    ///
+    /// Append the unsigned big endian integer `x` as mpint
+    pub fn mpint(self, x: &[u8]) -> Message {
+        let x: &[u8] = &x[x.iter().take_while(|x| **x == 0).count()..];
+        match x.first() {
+            Some(msb) if *msb & 0x80 != 0 => self.u32(x.len() as u32 + 1).byte(0).raw(x),
+            _ => self.string(x),
+        }
+    }
+
+    /// ### raw
+    ///
+    /// Append bytes as they are
+    pub fn raw(mut self, x: &[u8]) -> Message {
+        self.0.extend_from_slice(x);
+        self
+    }
+
+    /// ### into_bytes
+    ///
+    /// Get the encoded message
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+}
+
+impl<'a> Fields<'a> {
+    /// ### new
+    ///
+    /// Instantiates a new `Fields` parser of `data`
+    pub fn new(data: &'a [u8]) -> Fields<'a> {
+        Fields { data }
+    }
+
+    /// ### take
+    ///
+    /// Take the next `len` bytes
+    fn take(&mut self, len: usize) -> io::Result<&'a [u8]> {
+        if self.data.len() < len {
+            return Err(protocol_error("message is truncated"));
+        }
+        let (field, data): (&'a [u8], &'a [u8]) = self.data.split_at(len);
+        self.data = data;
+        Ok(field)
+    }
+
+    /// ### bool
+    ///
+    /// Parse a boolean
+    pub fn bool(&mut self) -> io::Result<bool> {
+        self.take(1).map(|x| x[0] != 0)
+    }
+
+    /// ### u32
+    ///
+    /// Parse a 32 bits unsigned integer
+    pub fn u32(&mut self) -> io::Result<u32> {
+        self.take(4)
+            .map(|x| u32::from_be_bytes([x[0], x[1], x[2], x[3]]))
+    }
+
+    /// ### u64
+    ///
+    /// Parse a 64 bits unsigned integer
+    pub fn u64(&mut self) -> io::Result<u64> {
+        Ok(((self.u32()? as u64) << 32) | self.u32()? as u64)
+    }
+
+    /// ### string
+    ///
+    /// Parse a string of bytes
+    pub fn string(&mut self) -> io::Result<&'a [u8]> {
+        let len: u32 = self.u32()?;
+        self.take(len as usize)
+    }
+
+    /// ### str
+    ///
+    /// Parse a UTF-8 string; invalid sequences are replaced
+    pub fn str(&mut self) -> io::Result<String> {
+        self.string()
+            .map(|x| String::from_utf8_lossy(x).to_string())
+    }
+}
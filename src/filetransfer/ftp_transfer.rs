@@ -26,24 +26,34 @@
  * SOFTWARE.
  */
 // Dependencies
-extern crate chrono;
 extern crate ftp4;
-extern crate regex;
+extern crate tracing;
 
-use super::{FileTransfer, FileTransferError, FileTransferErrorType};
-use crate::fs::{FsDirectory, FsEntry, FsFile};
-use crate::utils::parser::{parse_datetime, parse_lstime};
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, NetworkOptions, ServerIdentity,
+    TransferOutcome, TransferStream,
+};
+use crate::fs::{FsEntry, FsFile};
+use crate::system::known_hosts::{self, KnownHost};
+use crate::utils::fmt::fmt_utc_time;
+use crate::utils::ls::{parse_dos_ls_line, parse_ls_line, LsParseError};
 
 // Includes
 use ftp4::native_tls::TlsConnector;
 use ftp4::FtpStream;
-use regex::Regex;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpStream};
 use std::path::{Path, PathBuf};
-use std::time::SystemTime;
-use std::{
-    io::{Read, Write},
-    ops::Range,
-};
+use std::time::{Duration, SystemTime};
+use tracing::{info_span, instrument, warn, Span};
+
+/// Replies to the data transfer commands, once the data connection has been closed before the end of the transfer
+const ABORTED_TRANSFER_REPLIES: &[u32] = &[
+    ftp4::status::CLOSING_DATA_CONNECTION,
+    ftp4::status::REQUESTED_FILE_ACTION_OK,
+    ftp4::status::TRANSER_ABORTED,
+    ftp4::status::ACTION_ABORTED,
+];
 
 /// ## FtpFileTransfer
 ///
@@ -51,6 +61,11 @@ use std::{
 pub struct FtpFileTransfer {
     stream: Option<FtpStream>,
     ftps: bool,
+    known_hosts: Option<PathBuf>, // Known hosts file; certificate is not verified if `None`
+    accepted_host_key: Option<KnownHost>, // Certificate accepted by the user; trusted at next connect
+    network: NetworkOptions,
+    identity: ServerIdentity,
+    span: Span, // Session span; parent of each remote operation span
 }
 
 impl FtpFileTransfer {
@@ -58,237 +73,79 @@ impl FtpFileTransfer {
     ///
     /// Instantiates a new `FtpFileTransfer`
     pub fn new(ftps: bool) -> FtpFileTransfer {
-        FtpFileTransfer { stream: None, ftps }
+        FtpFileTransfer {
+            stream: None,
+            ftps,
+            known_hosts: None,
+            accepted_host_key: None,
+            network: NetworkOptions::default(),
+            identity: ServerIdentity::default(),
+            span: Span::none(),
+        }
     }
 
-    /// ### parse_list_line
+    /// ### with_network_options
     ///
-    /// Parse a line of LIST command output and instantiates an FsEntry from it
-    fn parse_list_line(&self, path: &Path, line: &str) -> Result<FsEntry, ()> {
-        // Try to parse using UNIX syntax
-        match self.parse_unix_list_line(path, line) {
-            Ok(entry) => Ok(entry),
-            Err(_) => match self.parse_dos_list_line(path, line) {
-                // If UNIX parsing fails, try DOS
-                Ok(entry) => Ok(entry),
-                Err(_) => Err(()),
-            },
-        }
+    /// Set network options; only the preferred address family is used
+    pub fn with_network_options(mut self, options: NetworkOptions) -> Self {
+        self.network = options;
+        self
     }
 
-    /// ### parse_unix_list_line
+    /// ### with_known_hosts
     ///
-    /// Try to parse a "LIST" output command line in UNIX format.
-    /// Returns error if syntax is not UNIX compliant.
-    /// UNIX syntax has the following syntax:
-    /// {FILE_TYPE}{UNIX_PEX} {HARD_LINKS} {USER} {GROUP} {SIZE} {DATE} {FILENAME}
-    /// -rw-r--r--   1 cvisintin  staff   4968 27 Dic 10:46 CHANGELOG.md
-    fn parse_unix_list_line(&self, path: &Path, line: &str) -> Result<FsEntry, ()> {
-        // Prepare list regex
-        // NOTE: about this damn regex <https://stackoverflow.com/questions/32480890/is-there-a-regex-to-parse-the-values-from-an-ftp-directory-listing>
-        lazy_static! {
-            static ref LS_RE: Regex = Regex::new(r#"^([\-ld])([\-rwxs]{9})\s+(\d+)\s+(\w+)\s+(\w+)\s+(\d+)\s+(\w{3}\s+\d{1,2}\s+(?:\d{1,2}:\d{1,2}|\d{4}))\s+(.+)$"#).unwrap();
-        }
-        // Apply regex to result
-        match LS_RE.captures(line) {
-            // String matches regex
-            Some(metadata) => {
-                // NOTE: metadata fmt: (regex, file_type, permissions, link_count, uid, gid, filesize, mtime, filename)
-                // Expected 7 + 1 (8) values: + 1 cause regex is repeated at 0
-                if metadata.len() < 8 {
-                    return Err(());
-                }
-                // Collect metadata
-                // Get if is directory and if is symlink
-                let (is_dir, _is_symlink): (bool, bool) = match metadata.get(1).unwrap().as_str() {
-                    "-" => (false, false),
-                    "l" => (false, true),
-                    "d" => (true, false),
-                    _ => return Err(()), // Ignore special files
-                };
-                // Check string length (unix pex)
-                if metadata.get(2).unwrap().as_str().len() < 9 {
-                    return Err(());
-                }
-
-                let pex = |range: Range<usize>| {
-                    let mut count: u8 = 0;
-                    for (i, c) in metadata.get(2).unwrap().as_str()[range].chars().enumerate() {
-                        match c {
-                            '-' => {}
-                            _ => {
-                                count += match i {
-                                    0 => 4,
-                                    1 => 2,
-                                    2 => 1,
-                                    _ => 0,
-                                }
-                            }
-                        }
-                    }
-                    count
-                };
-
-                // Get unix pex
-                let unix_pex = (pex(0..3), pex(3..6), pex(6..9));
+    /// Set known hosts file used to verify the server certificate, when connecting with FTPS
+    pub fn with_known_hosts(mut self, path: Option<PathBuf>) -> Self {
+        self.known_hosts = path;
+        self
+    }
 
-                // Parse mtime and convert to SystemTime
-                let mtime: SystemTime = match parse_lstime(
-                    metadata.get(7).unwrap().as_str(),
-                    "%b %d %Y",
-                    "%b %d %H:%M",
-                ) {
-                    Ok(t) => t,
-                    Err(_) => SystemTime::UNIX_EPOCH,
-                };
-                // Get uid
-                let uid: Option<u32> = match metadata.get(4).unwrap().as_str().parse::<u32>() {
-                    Ok(uid) => Some(uid),
-                    Err(_) => None,
-                };
-                // Get gid
-                let gid: Option<u32> = match metadata.get(5).unwrap().as_str().parse::<u32>() {
-                    Ok(gid) => Some(gid),
-                    Err(_) => None,
-                };
-                // Get filesize
-                let filesize: usize = metadata
-                    .get(6)
-                    .unwrap()
-                    .as_str()
-                    .parse::<usize>()
-                    .unwrap_or(0);
-                let file_name: String = String::from(metadata.get(8).unwrap().as_str());
-                // Check if file_name is '.' or '..'
-                if file_name.as_str() == "." || file_name.as_str() == ".." {
-                    return Err(());
-                }
-                let mut abs_path: PathBuf = PathBuf::from(path);
-                abs_path.push(file_name.as_str());
-                // get extension
-                let extension: Option<String> = abs_path
-                    .as_path()
-                    .extension()
-                    .map(|s| String::from(s.to_string_lossy()));
-                // Return
-                // Push to entries
-                Ok(match is_dir {
-                    true => FsEntry::Directory(FsDirectory {
-                        name: file_name,
-                        abs_path,
-                        last_change_time: mtime,
-                        last_access_time: mtime,
-                        creation_time: mtime,
-                        readonly: false,
-                        symlink: None,
-                        user: uid,
-                        group: gid,
-                        unix_pex: Some(unix_pex),
-                    }),
-                    false => FsEntry::File(FsFile {
-                        name: file_name,
-                        abs_path,
-                        last_change_time: mtime,
-                        last_access_time: mtime,
-                        creation_time: mtime,
-                        size: filesize,
-                        ftype: extension,
-                        readonly: false,
-                        symlink: None,
-                        user: uid,
-                        group: gid,
-                        unix_pex: Some(unix_pex),
-                    }),
-                })
-            }
-            None => Err(()),
+    /// ### parse_list_line
+    ///
+    /// Parse a line of LIST command output and instantiates an FsEntry from it.
+    /// Both UNIX and DOS syntaxes are supported; the DOS syntax is tried first on Windows servers
+    fn parse_list_line(&self, path: &Path, line: &str) -> Result<FsEntry, LsParseError> {
+        match self.identity.is_windows() {
+            true => parse_dos_ls_line(line).or_else(|_| parse_ls_line(line)),
+            false => parse_ls_line(line),
         }
+        .map(|entry| entry.into_fsentry(path, None))
     }
 
-    /// ### parse_dos_list_line
+    /// ### perform_raw_cmd
     ///
-    /// Try to parse a "LIST" output command line in DOS format.
-    /// Returns error if syntax is not DOS compliant.
-    /// DOS syntax has the following syntax:
-    /// {DATE} {TIME} {<DIR> | SIZE} {FILENAME}
-    /// 10-19-20  03:19PM <DIR> pub
-    /// 04-08-14  03:09PM 403   readme.txt
-    fn parse_dos_list_line(&self, path: &Path, line: &str) -> Result<FsEntry, ()> {
-        // Prepare list regex
-        // NOTE: you won't find this regex on the internet. It seems I'm the only person in the world who needs this
-        lazy_static! {
-            static ref DOS_RE: Regex = Regex::new(
-                r#"^(\d{2}\-\d{2}\-\d{2}\s+\d{2}:\d{2}\s*[AP]M)\s+(<DIR>)?([\d,]*)\s+(.+)$"#
-            )
-            .unwrap();
-        }
-        // Apply regex to result
-        match DOS_RE.captures(line) {
-            // String matches regex
-            Some(metadata) => {
-                // NOTE: metadata fmt: (regex, date_time, is_dir?, file_size?, file_name)
-                // Expected 4 + 1 (5) values: + 1 cause regex is repeated at 0
-                if metadata.len() < 5 {
-                    return Err(());
+    /// Send a command which is not supported by the ftp client and expect `status` in response;
+    /// `kind` is the error returned when the server replies with a different status.
+    /// Returns the text of the reply, without the status code.
+    /// The command is written straight to the control connection, so this is not supported over FTPS
+    fn perform_raw_cmd(
+        &mut self,
+        cmd: &str,
+        status: u32,
+        kind: FileTransferErrorType,
+    ) -> Result<String, FileTransferError> {
+        let ftps: bool = self.ftps;
+        match &mut self.stream {
+            Some(_) if ftps => Err(FileTransferError::new_ex(
+                FileTransferErrorType::UnsupportedFeature,
+                String::from("raw commands are not supported over FTPS"),
+            )),
+            Some(stream) => {
+                let mut ctrl: &TcpStream = stream.get_ref();
+                if let Err(err) = ctrl.write_all(format!("{}\r\n", cmd).as_bytes()) {
+                    return Err(FileTransferError::new_ex(
+                        FileTransferErrorType::ConnectionError,
+                        format!("{}", err),
+                    ));
                 }
-                // Parse date time
-                let time: SystemTime =
-                    match parse_datetime(metadata.get(1).unwrap().as_str(), "%d-%m-%y %I:%M%p") {
-                        Ok(t) => t,
-                        Err(_) => SystemTime::UNIX_EPOCH, // Don't return error
-                    };
-                // Get if is a directory
-                let is_dir: bool = metadata.get(2).is_some();
-                // Get file size
-                let file_size: usize = match is_dir {
-                    true => 0, // If is directory, filesize is 0
-                    false => match metadata.get(3) {
-                        // If is file, parse arg 3
-                        Some(val) => val.as_str().parse::<usize>().unwrap_or(0),
-                        None => 0, // Should not happen
-                    },
-                };
-                // Get file name
-                let file_name: String = String::from(metadata.get(4).unwrap().as_str());
-                // Get absolute path
-                let mut abs_path: PathBuf = PathBuf::from(path);
-                abs_path.push(file_name.as_str());
-                // Get extension
-                let extension: Option<String> = abs_path
-                    .as_path()
-                    .extension()
-                    .map(|s| String::from(s.to_string_lossy()));
-                // Return entry
-                Ok(match is_dir {
-                    true => FsEntry::Directory(FsDirectory {
-                        name: file_name,
-                        abs_path,
-                        last_change_time: time,
-                        last_access_time: time,
-                        creation_time: time,
-                        readonly: false,
-                        symlink: None,
-                        user: None,
-                        group: None,
-                        unix_pex: None,
-                    }),
-                    false => FsEntry::File(FsFile {
-                        name: file_name,
-                        abs_path,
-                        last_change_time: time,
-                        last_access_time: time,
-                        creation_time: time,
-                        size: file_size,
-                        ftype: extension,
-                        readonly: false,
-                        symlink: None,
-                        user: None,
-                        group: None,
-                        unix_pex: None,
-                    }),
-                })
+                stream
+                    .read_response(status)
+                    .map(|line| line.1.get(4..).unwrap_or("").trim().to_string())
+                    .map_err(|err| FileTransferError::new_ex(kind, format!("{}", err)))
             }
-            None => Err(()), // Invalid syntax
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
         }
     }
 }
@@ -298,6 +155,7 @@ impl FileTransfer for FtpFileTransfer {
     ///
     /// Connect to the remote server
 
+    #[instrument(skip_all, fields(host = %address, port), err)]
     fn connect(
         &mut self,
         address: String,
@@ -306,7 +164,23 @@ impl FileTransfer for FtpFileTransfer {
         password: Option<String>,
     ) -> Result<Option<String>, FileTransferError> {
         // Get stream
-        let mut stream: FtpStream = match FtpStream::connect(format!("{}:{}", address, port)) {
+        let socket_addresses: Vec<SocketAddr> = self.network.resolve(address.as_str(), port)?;
+        // Verify server certificate
+        if let (true, Some(known_hosts)) = (self.ftps, self.known_hosts.as_deref()) {
+            let certificate: Vec<u8> = peer_certificate(
+                socket_addresses.as_slice(),
+                address.as_str(),
+                self.network.connection_timeout,
+            )?;
+            known_hosts::verify_certificate(
+                known_hosts,
+                certificate.as_slice(),
+                address.as_str(),
+                port,
+                self.accepted_host_key.take().as_ref(),
+            )?;
+        }
+        let mut stream: FtpStream = match FtpStream::connect(socket_addresses.as_slice()) {
             Ok(stream) => stream,
             Err(err) => {
                 return Err(FileTransferError::new_ex(
@@ -356,19 +230,53 @@ impl FileTransfer for FtpFileTransfer {
             ));
         }
         // Set stream
+        let welcome: Option<String> = stream.get_welcome_msg();
         self.stream = Some(stream);
+        // Identify server; SYST is optional, so errors are ignored
+        let system: Option<String> = self
+            .perform_raw_cmd(
+                "SYST",
+                ftp4::status::NAME,
+                FileTransferErrorType::UnsupportedFeature,
+            )
+            .ok();
+        self.identity = ServerIdentity::new(welcome.as_deref(), system.as_deref());
+        // Open session span
+        self.span = match self.ftps {
+            true => info_span!("session", protocol = "FTPS", host = %address, port),
+            false => info_span!("session", protocol = "FTP", host = %address, port),
+        };
         // Return OK
-        Ok(self.stream.as_ref().unwrap().get_welcome_msg())
+        Ok(welcome)
+    }
+
+    /// ### set_accepted_host_key
+    ///
+    /// Set the certificate accepted by the user; it is trusted at the next `connect`
+    fn set_accepted_host_key(&mut self, key: KnownHost) {
+        self.accepted_host_key = Some(key);
+    }
+
+    /// ### identity
+    ///
+    /// Returns the remote OS and server software, identified on connect
+    fn identity(&self) -> ServerIdentity {
+        self.identity.clone()
     }
 
     /// ### disconnect
     ///
     /// Disconnect from the remote server
 
+    #[instrument(parent = &self.span, skip_all, err)]
     fn disconnect(&mut self) -> Result<(), FileTransferError> {
         match &mut self.stream {
             Some(stream) => match stream.quit() {
-                Ok(_) => Ok(()),
+                Ok(_) => {
+                    // Close session span
+                    self.span = Span::none();
+                    Ok(())
+                }
                 Err(err) => Err(FileTransferError::new_ex(
                     FileTransferErrorType::ConnectionError,
                     format!("{}", err),
@@ -391,6 +299,7 @@ impl FileTransfer for FtpFileTransfer {
     ///
     /// Print working directory
 
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
     fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
         match &mut self.stream {
             Some(stream) => match stream.pwd() {
@@ -410,6 +319,7 @@ impl FileTransfer for FtpFileTransfer {
     ///
     /// Change working directory
 
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
     fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
         match &mut self.stream {
             Some(stream) => match stream.cwd(&dir.to_string_lossy()) {
@@ -428,7 +338,8 @@ impl FileTransfer for FtpFileTransfer {
     /// ### copy
     ///
     /// Copy file to destination
-    fn copy(&mut self, _src: &FsEntry, _dst: &Path) -> Result<(), FileTransferError> {
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
         // FTP doesn't support file copy
         Err(FileTransferError::new(
             FileTransferErrorType::UnsupportedFeature,
@@ -439,6 +350,7 @@ impl FileTransfer for FtpFileTransfer {
     ///
     /// List directory entries
 
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
     fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
         match &mut self.stream {
             Some(stream) => match stream.list(Some(&path.to_string_lossy())) {
@@ -447,8 +359,10 @@ impl FileTransfer for FtpFileTransfer {
                     let mut result: Vec<FsEntry> = Vec::with_capacity(entries.len());
                     // Iterate over entries
                     for entry in entries.iter() {
-                        if let Ok(file) = self.parse_list_line(path, entry) {
-                            result.push(file);
+                        match self.parse_list_line(path, entry) {
+                            Ok(file) => result.push(file),
+                            Err(err) if err.is_ignorable() => {}
+                            Err(err) => warn!(line = %entry, "Could not parse entry: {}", err),
                         }
                     }
                     Ok(result)
@@ -467,6 +381,7 @@ impl FileTransfer for FtpFileTransfer {
     /// ### mkdir
     ///
     /// Make directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
     fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
         match &mut self.stream {
             Some(stream) => match stream.mkdir(&dir.to_string_lossy()) {
@@ -485,6 +400,7 @@ impl FileTransfer for FtpFileTransfer {
     /// ### remove
     ///
     /// Remove a file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %fsentry.get_abs_path().display()), err)]
     fn remove(&mut self, fsentry: &FsEntry) -> Result<(), FileTransferError> {
         if self.stream.is_none() {
             return Err(FileTransferError::new(
@@ -537,6 +453,7 @@ impl FileTransfer for FtpFileTransfer {
     /// ### rename
     ///
     /// Rename file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
     fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
         match &mut self.stream {
             Some(stream) => {
@@ -572,7 +489,8 @@ impl FileTransfer for FtpFileTransfer {
     /// ### stat
     ///
     /// Stat file and return FsEntry
-    fn stat(&mut self, _path: &Path) -> Result<FsEntry, FileTransferError> {
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
         match &mut self.stream {
             Some(_) => Err(FileTransferError::new(
                 FileTransferErrorType::UnsupportedFeature,
@@ -583,10 +501,45 @@ impl FileTransfer for FtpFileTransfer {
         }
     }
 
+    /// ### chmod
+    ///
+    /// Change the UNIX permissions of `path` to `mode`, through `SITE CHMOD`.
+    /// Not supported over FTPS, since the command is written straight to the control connection
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn chmod(&mut self, path: &Path, mode: (u8, u8, u8)) -> Result<(), FileTransferError> {
+        let (u, g, o) = mode;
+        self.perform_raw_cmd(
+            format!("SITE CHMOD {}{}{} {}", u, g, o, path.display()).as_str(),
+            ftp4::status::COMMAND_OK,
+            FileTransferErrorType::PexError,
+        )
+        .map(|_| ())
+    }
+
+    /// ### set_mtime
+    ///
+    /// Set the modification time of `path` to `mtime`, through `MFMT`.
+    /// Not supported over FTPS, since the command is written straight to the control connection
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn set_mtime(&mut self, path: &Path, mtime: SystemTime) -> Result<(), FileTransferError> {
+        self.perform_raw_cmd(
+            format!(
+                "MFMT {} {}",
+                fmt_utc_time(mtime, "%Y%m%d%H%M%S"),
+                path.display()
+            )
+            .as_str(),
+            ftp4::status::FILE,
+            FileTransferErrorType::ProtocolError,
+        )
+        .map(|_| ())
+    }
+
     /// ### exec
     ///
     /// Execute a command on remote host
-    fn exec(&mut self, _cmd: &str) -> Result<String, FileTransferError> {
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
+    fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
         Err(FileTransferError::new(
             FileTransferErrorType::UnsupportedFeature,
         ))
@@ -598,6 +551,7 @@ impl FileTransfer for FtpFileTransfer {
     /// File name is referred to the name of the file as it will be saved
     /// Data contains the file data
     /// Returns file and its size
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
     fn send_file(
         &mut self,
         _local: &FsFile,
@@ -621,6 +575,7 @@ impl FileTransfer for FtpFileTransfer {
     ///
     /// Receive file from remote with provided name
     /// Returns file and its size
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
     fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
         match &mut self.stream {
             Some(stream) => match stream.get(&file.abs_path.as_path().to_string_lossy()) {
@@ -636,47 +591,122 @@ impl FileTransfer for FtpFileTransfer {
         }
     }
 
-    /// ### on_sent
+    /// ### finalize_transfer
     ///
-    /// Finalize send method.
-    /// This method must be implemented only if necessary; in case you don't need it, just return `Ok(())`
-    /// The purpose of this method is to finalize the connection with the peer when writing data.
-    /// This is necessary for some protocols such as FTP.
-    /// You must call this method each time you want to finalize the write of the remote file.
-    fn on_sent(&mut self, writable: Box<dyn Write>) -> Result<(), FileTransferError> {
+    /// Close the data connection and read the reply of the server.
+    /// If the transfer hasn't been completed, the server may reply that it has been aborted
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(bytes = outcome.bytes), err)]
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        let completed: bool = outcome.is_completed();
         match &mut self.stream {
-            Some(stream) => match stream.finalize_put_stream(writable) {
-                Ok(_) => Ok(()),
-                Err(err) => Err(FileTransferError::new_ex(
-                    FileTransferErrorType::ProtocolError,
-                    format!("{}", err),
-                )),
-            },
+            Some(stream) => {
+                let result = match (outcome.stream, completed) {
+                    (TransferStream::Upload(writer), true) => stream.finalize_put_stream(writer),
+                    (TransferStream::Download(reader), true) => stream.finalize_get(reader),
+                    (TransferStream::Upload(writer), false) => {
+                        drop(writer);
+                        stream
+                            .read_response_in(ABORTED_TRANSFER_REPLIES)
+                            .map(|_| ())
+                    }
+                    (TransferStream::Download(reader), false) => {
+                        drop(reader);
+                        stream
+                            .read_response_in(ABORTED_TRANSFER_REPLIES)
+                            .map(|_| ())
+                    }
+                };
+                result.map_err(|err| {
+                    FileTransferError::new_ex(
+                        FileTransferErrorType::ProtocolError,
+                        format!("{}", err),
+                    )
+                })
+            }
             None => Err(FileTransferError::new(
                 FileTransferErrorType::UninitializedSession,
             )),
         }
     }
+}
 
-    /// ### on_recv
-    ///
-    /// Finalize recv method.
-    /// This method must be implemented only if necessary; in case you don't need it, just return `Ok(())`
-    /// The purpose of this method is to finalize the connection with the peer when reading data.
-    /// This mighe be necessary for some protocols.
-    /// You must call this method each time you want to finalize the read of the remote file.
-    fn on_recv(&mut self, readable: Box<dyn Read>) -> Result<(), FileTransferError> {
-        match &mut self.stream {
-            Some(stream) => match stream.finalize_get(readable) {
-                Ok(_) => Ok(()),
-                Err(err) => Err(FileTransferError::new_ex(
+/// ### peer_certificate
+///
+/// Get the DER encoded certificate of the FTPS server at `addresses`.
+/// Since the ftp client doesn't expose the TLS session, the certificate is read from a separate control connection,
+/// secured with `AUTH TLS` and closed right after the handshake
+fn peer_certificate(
+    addresses: &[SocketAddr],
+    domain: &str,
+    timeout: Duration,
+) -> Result<Vec<u8>, FileTransferError> {
+    let ssl_error = |msg: String| FileTransferError::new_ex(FileTransferErrorType::SslError, msg);
+    let stream: TcpStream = addresses
+        .iter()
+        .find_map(|x| TcpStream::connect_timeout(x, timeout).ok())
+        .ok_or_else(|| {
+            FileTransferError::new_ex(
+                FileTransferErrorType::ConnectionError,
+                format!("Could not connect to {}", domain),
+            )
+        })?;
+    let _ = stream.set_read_timeout(Some(timeout));
+    let mut reader: BufReader<TcpStream> = BufReader::new(
+        stream
+            .try_clone()
+            .map_err(|e| FileTransferError::new(FileTransferErrorType::IoErr(e)))?,
+    );
+    let mut writer: TcpStream = stream;
+    // Read welcome, then ask for TLS
+    read_reply(&mut reader, ftp4::status::READY)?;
+    writer
+        .write_all(b"AUTH TLS\r\n")
+        .map_err(|e| FileTransferError::new(FileTransferErrorType::IoErr(e)))?;
+    read_reply(&mut reader, ftp4::status::AUTH_OK)?;
+    let connector = TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| ssl_error(e.to_string()))?;
+    let tls = connector
+        .connect(domain, writer)
+        .map_err(|e| ssl_error(e.to_string()))?;
+    match tls.peer_certificate() {
+        Ok(Some(certificate)) => certificate.to_der().map_err(|e| ssl_error(e.to_string())),
+        Ok(None) => Err(ssl_error(String::from(
+            "Server didn't provide a certificate",
+        ))),
+        Err(err) => Err(ssl_error(err.to_string())),
+    }
+}
+
+/// ### read_reply
+///
+/// Read a (possibly multi-line) reply from the control connection and check its status is `status`
+fn read_reply(reader: &mut impl BufRead, status: u32) -> Result<(), FileTransferError> {
+    let last_line: String = format!("{} ", status);
+    let mut multi_line: bool = false;
+    loop {
+        let mut line: String = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) => {
+                return Err(FileTransferError::new_ex(
                     FileTransferErrorType::ProtocolError,
-                    format!("{}", err),
-                )),
-            },
-            None => Err(FileTransferError::new(
-                FileTransferErrorType::UninitializedSession,
-            )),
+                    String::from("Connection closed by server"),
+                ))
+            }
+            Ok(_) if line.starts_with(last_line.as_str()) => return Ok(()),
+            // Multi-line replies start with `<status>-` and end with `<status> `
+            Ok(_) if multi_line || line.starts_with(format!("{}-", status).as_str()) => {
+                multi_line = true;
+            }
+            Ok(_) => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::ProtocolError,
+                    format!("Unexpected reply: {}", line.trim_end()),
+                ))
+            }
+            Err(err) => return Err(FileTransferError::new(FileTransferErrorType::IoErr(err))),
         }
     }
 }
@@ -685,8 +715,11 @@ impl FileTransfer for FtpFileTransfer {
 mod tests {
 
     use super::*;
+    use crate::filetransfer::fixtures::FtpServer;
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    use crate::filetransfer::TransferStatus;
     use crate::utils::fmt::fmt_time;
-    use std::time::Duration;
+    use std::time::{Duration, SystemTime};
 
     #[test]
     fn test_filetransfer_ftp_new() {
@@ -909,94 +942,134 @@ mod tests {
         assert!(ftp
             .parse_list_line(PathBuf::from("/").as_path(), "04-08-14  omar.txt")
             .is_err());
+        // Windows server; DOS syntax is tried first, UNIX syntax is still supported
+        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
+        ftp.identity = ServerIdentity::new(Some("220 Microsoft FTP Service"), Some("Windows_NT"));
+        assert!(ftp
+            .parse_list_line(
+                PathBuf::from("/tmp").as_path(),
+                "04-08-14  03:09PM  <DIR> docs",
+            )
+            .ok()
+            .unwrap()
+            .is_dir());
+        assert!(ftp
+            .parse_list_line(
+                PathBuf::from("/tmp").as_path(),
+                "-rw-rw-r-- 1 root  dialout  8192 Nov 5 2018 omar.txt",
+            )
+            .is_ok());
     }
 
     #[test]
     fn test_filetransfer_ftp_connect_unsecure_anonymous() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
-            .connect(String::from("speedtest.tele2.net"), 21, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
         // Disconnect
         assert!(ftp.disconnect().is_ok());
     }
 
     #[test]
     fn test_filetransfer_ftp_connect_unsecure_username() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
             .connect(
-                String::from("test.rebex.net"),
-                21,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
         // Disconnect
         assert!(ftp.disconnect().is_ok());
     }
 
     #[test]
     fn test_filetransfer_ftp_connect_secure() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(true);
         // Connect
         assert!(ftp
             .connect(
-                String::from("test.rebex.net"),
-                21,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
+        // Data connections are protected too
+        let files: Vec<FsEntry> = ftp.list_dir(Path::new("/home/demo")).ok().unwrap();
+        assert_eq!(files.len(), 6);
+        let readme: FsFile = match files.iter().find(|x| x.get_name() == "README.md") {
+            Some(FsEntry::File(file)) => file.clone(),
+            _ => panic!("Expected README.md to be a file"),
+        };
+        let mut reader: Box<dyn Read> = ftp.recv_file(&readme).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert!(ftp
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                content.len() as u64
+            ))
+            .is_ok());
+        assert!(content.starts_with("# Welcome to termscp"));
         // Disconnect
         assert!(ftp.disconnect().is_ok());
     }
 
     #[test]
     fn test_filetransfer_ftp_change_dir() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
-            .connect(String::from("speedtest.tele2.net"), 21, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
         // Cwd
-        assert!(ftp.change_dir(PathBuf::from("upload/").as_path()).is_ok());
+        assert!(ftp.change_dir(PathBuf::from("photos/").as_path()).is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/upload"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo/photos"));
         // Disconnect
         assert!(ftp.disconnect().is_ok());
     }
 
     #[test]
     fn test_filetransfer_ftp_copy() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
-            .connect(String::from("speedtest.tele2.net"), 21, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
         // Copy
         let file: FsFile = FsFile {
-            name: String::from("readme.txt"),
-            abs_path: PathBuf::from("/readme.txt"),
+            name: String::from("README.md"),
+            abs_path: PathBuf::from("/home/demo/README.md"),
             last_change_time: SystemTime::UNIX_EPOCH,
             last_access_time: SystemTime::UNIX_EPOCH,
             creation_time: SystemTime::UNIX_EPOCH,
             size: 0,
-            ftype: Some(String::from("txt")), // File type
+            ftype: Some(String::from("md")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -1008,83 +1081,58 @@ mod tests {
 
     #[test]
     fn test_filetransfer_ftp_list_dir_dos_syntax() {
+        let server: FtpServer = FtpServer::start_windows(MemoryFileTransfer::demo())
+            .ok()
+            .unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
-            .connect(
-                String::from("test.rebex.net"),
-                21,
-                Some(String::from("demo")),
-                Some(String::from("password"))
-            )
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
         // List dir
-        println!("{:?}", ftp.list_dir(PathBuf::from("/").as_path()));
-        let files: Vec<FsEntry> = ftp.list_dir(PathBuf::from("/").as_path()).ok().unwrap();
-        // There should be at least 1 file
-        assert!(files.len() > 0);
+        let files: Vec<FsEntry> = ftp.list_dir(Path::new("/home/demo")).ok().unwrap();
+        assert_eq!(files.len(), 6);
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(readme.is_file());
+        assert_eq!(readme.get_size(), 98);
+        assert!(files
+            .iter()
+            .find(|x| x.get_name() == "photos")
+            .unwrap()
+            .is_dir());
         // Disconnect
         assert!(ftp.disconnect().is_ok());
     }
 
     #[test]
-    #[cfg(not(target_os = "macos"))]
     fn test_filetransfer_ftp_list_dir_unix_syntax() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
-            .connect(String::from("speedtest.tele2.net"), 21, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
         // List dir
-        println!("{:?}", ftp.list_dir(PathBuf::from("/").as_path()));
-        let files: Vec<FsEntry> = ftp.list_dir(PathBuf::from("/").as_path()).ok().unwrap();
-        // There should be at least 1 file
-        assert!(files.len() > 0);
-        // Disconnect
-        assert!(ftp.disconnect().is_ok());
-    }
-
-    /* NOTE: they don't work
-    #[test]
-    fn test_filetransfer_ftp_recv() {
-        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
-        // Connect
-        assert!(ftp.connect(String::from("test.rebex.net"), 21, Some(String::from("demo")), Some(String::from("password"))).is_ok());
-        // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
-        // Recv 100KB
-        assert!(ftp.recv_file(PathBuf::from("readme.txt").as_path()).is_ok());
+        let files: Vec<FsEntry> = ftp.list_dir(Path::new("/home/demo/photos")).ok().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].get_name(), "mountains.jpg");
+        assert_eq!(files[0].get_size(), 4096);
+        assert!(files[0].is_file());
         // Disconnect
         assert!(ftp.disconnect().is_ok());
     }
 
-    #[test]
-    fn test_filetransfer_ftp_send() {
-        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
-        // Connect
-        assert!(ftp.connect(String::from("speedtest.tele2.net"), 21, None, None).is_ok());
-        // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/"));
-        // Cwd
-        assert!(ftp.change_dir(PathBuf::from("upload/").as_path()).is_ok());
-        // Pwd
-        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/upload"));
-        // Send a sample file 100KB
-        assert!(ftp.send_file(PathBuf::from("test.txt").as_path()).is_ok());
-        // Disconnect
-        assert!(ftp.disconnect().is_ok());
-    }*/
-
     #[test]
     fn test_filetransfer_ftp_exec() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(ftp
-            .connect(String::from("speedtest.tele2.net"), 21, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
         assert!(ftp.exec("echo 1;").is_err());
@@ -1094,26 +1142,22 @@ mod tests {
 
     #[test]
     fn test_filetransfer_ftp_find() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: FtpFileTransfer = FtpFileTransfer::new(false);
         // Connect
         assert!(client
-            .connect(
-                String::from("test.rebex.net"),
-                21,
-                Some(String::from("demo")),
-                Some(String::from("password"))
-            )
+            .connect(server.address(), server.port(), None, None)
             .is_ok());
         // Pwd
-        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/"));
-        // Search for file (let's search for pop3-*.png); there should be 2
-        let search_res: Vec<FsEntry> = client.find("pop3-*.png").ok().unwrap();
+        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
+        // Search for file (let's search for *.jpg); there should be 2
+        let search_res: Vec<FsEntry> = client.find("*.jpg").ok().unwrap();
         assert_eq!(search_res.len(), 2);
         // verify names
-        assert_eq!(search_res[0].get_name(), "pop3-browser.png");
-        assert_eq!(search_res[1].get_name(), "pop3-console-client.png");
+        assert_eq!(search_res[0].get_name(), "mountains.jpg");
+        assert_eq!(search_res[1].get_name(), "sea.jpg");
         // Search directory
-        let search_res: Vec<FsEntry> = client.find("pub").ok().unwrap();
+        let search_res: Vec<FsEntry> = client.find("projects").ok().unwrap();
         assert_eq!(search_res.len(), 1);
         // Disconnect
         assert!(client.disconnect().is_ok());
@@ -1121,6 +1165,196 @@ mod tests {
         assert!(client.find("pippo").is_err());
     }
 
+    #[test]
+    fn test_filetransfer_ftp_fixture_session() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
+        // Connect
+        assert!(ftp
+            .connect(server.address(), server.port(), None, None)
+            .ok()
+            .unwrap()
+            .is_some());
+        assert!(ftp.is_connected());
+        // Pwd
+        assert_eq!(ftp.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
+        // List dir
+        let files: Vec<FsEntry> = ftp.list_dir(Path::new("/home/demo")).ok().unwrap();
+        assert_eq!(files.len(), 6);
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(readme.is_file());
+        assert_eq!(readme.get_unix_pex(), Some((6, 4, 4)));
+        assert!(files
+            .iter()
+            .find(|x| x.get_name() == "projects")
+            .unwrap()
+            .is_dir());
+        // Change dir
+        assert!(ftp.change_dir(Path::new("downloads")).is_ok());
+        assert_eq!(
+            ftp.pwd().ok().unwrap(),
+            PathBuf::from("/home/demo/downloads")
+        );
+        assert!(ftp.change_dir(Path::new("/omar")).is_err());
+        // Mkdir
+        assert!(ftp.mkdir(Path::new("music")).is_ok());
+        assert!(ftp.mkdir(Path::new("music")).is_err());
+        // Disconnect
+        assert!(ftp.disconnect().is_ok());
+        assert!(server
+            .fs()
+            .lock()
+            .unwrap()
+            .stat(Path::new("/home/demo/downloads/music"))
+            .ok()
+            .unwrap()
+            .is_dir());
+    }
+
+    #[test]
+    fn test_filetransfer_ftp_fixture_send_and_recv() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
+        assert!(ftp
+            .connect(server.address(), server.port(), None, None)
+            .is_ok());
+        let local: FsFile = FsFile {
+            name: String::from("omar.txt"),
+            abs_path: PathBuf::from("/tmp/omar.txt"),
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            size: 12,
+            ftype: Some(String::from("txt")),
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        };
+        // Send
+        let mut writer: Box<dyn Write> = ftp
+            .send_file(&local, Path::new("/home/demo/downloads/omar.txt"))
+            .ok()
+            .unwrap();
+        assert!(writer.write_all(b"hello world!").is_ok());
+        assert!(ftp
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_ok());
+        // Parent doesn't exist
+        assert!(ftp.send_file(&local, Path::new("/omar/omar.txt")).is_err());
+        // List
+        let files: Vec<FsEntry> = ftp
+            .list_dir(Path::new("/home/demo/downloads"))
+            .ok()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].get_size(), 12);
+        // Recv
+        let remote: FsFile = match files[0].clone() {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("Expected file"),
+        };
+        let mut reader: Box<dyn Read> = ftp.recv_file(&remote).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert!(ftp
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                12
+            ))
+            .is_ok());
+        assert_eq!(content.as_str(), "hello world!");
+        // Aborted download leaves the session usable
+        let reader: Box<dyn Read> = ftp.recv_file(&remote).ok().unwrap();
+        assert!(ftp
+            .finalize_transfer(TransferOutcome::new(
+                TransferStream::Download(reader),
+                0,
+                TransferStatus::Aborted
+            ))
+            .is_ok());
+        assert!(ftp.pwd().is_ok());
+        assert!(ftp.disconnect().is_ok());
+    }
+
+    #[test]
+    fn test_filetransfer_ftp_fixture_rename_and_remove() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
+        assert!(ftp
+            .connect(server.address(), server.port(), None, None)
+            .is_ok());
+        let files: Vec<FsEntry> = ftp.list_dir(Path::new("/home/demo")).ok().unwrap();
+        // Rename
+        let notes: &FsEntry = files.iter().find(|x| x.get_name() == "notes.txt").unwrap();
+        assert!(ftp.rename(notes, Path::new("/home/demo/todo.txt")).is_ok());
+        // Remove file
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(ftp.remove(readme).is_ok());
+        // Remove directory
+        let downloads: &FsEntry = files.iter().find(|x| x.get_name() == "downloads").unwrap();
+        assert!(ftp.remove(downloads).is_ok());
+        // Verify
+        let mut names: Vec<String> = ftp
+            .list_dir(Path::new("/home/demo"))
+            .ok()
+            .unwrap()
+            .iter()
+            .map(|x| x.get_name().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                String::from(".profile"),
+                String::from("photos"),
+                String::from("projects"),
+                String::from("todo.txt")
+            ]
+        );
+        assert!(ftp.disconnect().is_ok());
+    }
+
+    #[test]
+    fn test_filetransfer_ftp_fixture_chmod_and_mfmt() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut ftp: FtpFileTransfer = FtpFileTransfer::new(false);
+        assert!(ftp
+            .connect(server.address(), server.port(), None, None)
+            .is_ok());
+        assert_eq!(
+            ftp.identity(),
+            ServerIdentity {
+                software: Some(String::from("termscp test server ready")),
+                system: Some(String::from("UNIX Type: L8")),
+            }
+        );
+        assert!(ftp
+            .chmod(Path::new("/home/demo/notes.txt"), (6, 0, 0))
+            .is_ok());
+        let files: Vec<FsEntry> = ftp.list_dir(Path::new("/home/demo")).ok().unwrap();
+        let notes: &FsEntry = files.iter().find(|x| x.get_name() == "notes.txt").unwrap();
+        assert_eq!(notes.get_unix_pex(), Some((6, 0, 0)));
+        // Modification time
+        assert!(ftp
+            .set_mtime(Path::new("/home/demo/notes.txt"), SystemTime::UNIX_EPOCH)
+            .is_ok());
+        // Error
+        assert!(ftp
+            .set_mtime(Path::new("/home/demo/omar.txt"), SystemTime::UNIX_EPOCH)
+            .is_err());
+        assert!(ftp
+            .chmod(Path::new("/home/demo/omar.txt"), (6, 4, 4))
+            .is_err());
+        assert!(ftp.chown(Path::new("/home/demo/notes.txt"), 0, 0).is_err());
+        assert!(ftp.disconnect().is_ok());
+    }
+
     #[test]
     fn test_filetransfer_ftp_uninitialized() {
         let file: FsFile = FsFile {
@@ -1133,6 +1367,7 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -0,0 +1,204 @@
+//! ## KeyboardInteractive
+//!
+//! `keyboard_interactive` is the module which provides the keyboard-interactive authentication
+//! (e.g. OTP / 2FA prompts) shared by the SSH based file transfers
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{FileTransferError, FileTransferErrorType};
+// Ext
+use ssh2::{KeyboardInteractivePrompt, Prompt, Session};
+use std::collections::VecDeque;
+
+/// ## AuthPrompt
+///
+/// A challenge sent by the server during keyboard-interactive authentication,
+/// which must be answered by the user
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuthPrompt {
+    pub instructions: String,
+    pub text: String,
+    pub echo: bool,
+}
+
+/// ## Responder
+///
+/// Answers the server prompts during keyboard-interactive authentication.
+/// Password prompts are answered with the user password, while any other prompt is answered with
+/// the responses provided by the user; prompts which couldn't be answered are collected, so that
+/// they can be shown to the user.
+struct Responder {
+    password: Option<String>,
+    responses: VecDeque<String>,
+    pending: Vec<AuthPrompt>,
+}
+
+impl Responder {
+    fn new(password: Option<String>, responses: Vec<String>) -> Self {
+        Responder {
+            password,
+            responses: VecDeque::from(responses),
+            pending: Vec::new(),
+        }
+    }
+}
+
+impl KeyboardInteractivePrompt for Responder {
+    fn prompt<'a>(
+        &mut self,
+        _username: &str,
+        instructions: &str,
+        prompts: &[Prompt<'a>],
+    ) -> Vec<String> {
+        prompts
+            .iter()
+            .map(|prompt| {
+                if is_password_prompt(prompt) {
+                    if let Some(password) = self.password.take() {
+                        return password;
+                    }
+                }
+                match self.responses.pop_front() {
+                    Some(response) => response,
+                    None => {
+                        self.pending.push(AuthPrompt {
+                            instructions: instructions.trim().to_string(),
+                            text: prompt.text.trim().to_string(),
+                            echo: prompt.echo,
+                        });
+                        String::new()
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+/// ### is_password_prompt
+///
+/// Returns whether the prompt is asking for the user password
+fn is_password_prompt(prompt: &Prompt) -> bool {
+    !prompt.echo && prompt.text.to_lowercase().contains("password")
+}
+
+/// ### userauth_password
+///
+/// Authenticate with username and password. If the server doesn't accept the password
+/// authentication, but supports keyboard-interactive, keyboard-interactive authentication is tried.
+/// The provided responses are used to answer the prompts which don't ask for the password;
+/// if some prompt is left without an answer, `FileTransferErrorType::KeyboardInteractive` is returned
+/// with the prompts to show to the user.
+pub(crate) fn userauth_password(
+    session: &Session,
+    username: &str,
+    password: Option<String>,
+    responses: Vec<String>,
+) -> Result<(), FileTransferError> {
+    let methods: String = session
+        .auth_methods(username)
+        .map(|x| x.to_string())
+        .unwrap_or_default();
+    if session.authenticated() {
+        return Ok(());
+    }
+    let supports = |method: &str| methods.split(',').any(|x| x == method);
+    // Try with password first
+    let mut error: Option<FileTransferError> = None;
+    if supports("password") || !supports("keyboard-interactive") {
+        match session.userauth_password(username, password.as_deref().unwrap_or("")) {
+            Ok(_) => return Ok(()),
+            Err(err) => {
+                error = Some(FileTransferError::new_ex(
+                    FileTransferErrorType::AuthenticationFailed,
+                    format!("{}", err),
+                ))
+            }
+        }
+    }
+    if !supports("keyboard-interactive") {
+        return Err(error.unwrap());
+    }
+    // Fallback to keyboard-interactive
+    let mut responder: Responder = Responder::new(password, responses);
+    match session.userauth_keyboard_interactive(username, &mut responder) {
+        Ok(_) => Ok(()),
+        Err(_) if !responder.pending.is_empty() => Err(FileTransferError::new(
+            FileTransferErrorType::KeyboardInteractive(responder.pending),
+        )),
+        Err(err) => Err(FileTransferError::new_ex(
+            FileTransferErrorType::AuthenticationFailed,
+            format!("{}", err),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::borrow::Cow;
+
+    fn prompt(text: &'static str, echo: bool) -> Prompt<'static> {
+        Prompt {
+            text: Cow::Borrowed(text),
+            echo,
+        }
+    }
+
+    #[test]
+    fn test_filetransfer_keyboard_interactive_responder() {
+        let mut responder: Responder =
+            Responder::new(Some(String::from("secret")), vec![String::from("123456")]);
+        let prompts = vec![
+            prompt("Password: ", false),
+            prompt("Verification code: ", false),
+            prompt("Token: ", true),
+        ];
+        assert_eq!(
+            responder.prompt("omar", "Two factor", prompts.as_slice()),
+            vec![
+                String::from("secret"),
+                String::from("123456"),
+                String::new()
+            ]
+        );
+        // Unanswered prompt must be collected
+        assert_eq!(
+            responder.pending,
+            vec![AuthPrompt {
+                instructions: String::from("Two factor"),
+                text: String::from("Token:"),
+                echo: true,
+            }]
+        );
+        // Password is answered only once
+        assert_eq!(
+            responder.prompt("omar", "", &[prompt("Password: ", false)]),
+            vec![String::new()]
+        );
+        assert_eq!(responder.pending.len(), 2);
+    }
+}
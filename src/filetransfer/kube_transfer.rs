@@ -0,0 +1,1206 @@
+//! ## KubeTransfer
+//!
+//! `kube_transfer` is the module which provides the implementation for the Kubernetes pod file transfer
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Dependencies
+extern crate tracing;
+
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, TransferOutcome, TransferStream,
+};
+use crate::fs::{FsEntry, FsFile};
+use crate::utils::ls::{parse_unix_ls_line, LsEntry, LsParseError};
+use crate::utils::shell;
+
+// Includes
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Output, Stdio};
+use std::time::SystemTime;
+use tracing::{info_span, instrument, warn, Span};
+
+/// Size of a tar block
+const TAR_BLOCK_SIZE: usize = 512;
+
+/// ## KubeFileTransfer
+///
+/// Kubernetes file transfer struct. Files are transferred in and out of a container,
+/// running commands through `kubectl exec` and streaming tar archives (the way `kubectl cp` does)
+pub struct KubeFileTransfer {
+    kubectl: PathBuf, // kubectl executable
+    namespace: Option<String>,
+    pod: String,
+    container: Option<String>, // Default container if `None`
+    connected: bool,
+    wrkdir: PathBuf,
+    upload: Option<Child>,   // Pending `tar x`
+    download: Option<Child>, // Pending `tar c`
+    span: Span,              // Session span; parent of each remote operation span
+}
+
+impl Default for KubeFileTransfer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KubeFileTransfer {
+    /// ### new
+    ///
+    /// Instantiates a new `KubeFileTransfer`
+    pub fn new() -> KubeFileTransfer {
+        KubeFileTransfer {
+            kubectl: PathBuf::from("kubectl"),
+            namespace: None,
+            pod: String::new(),
+            container: None,
+            connected: false,
+            wrkdir: PathBuf::from("/"),
+            upload: None,
+            download: None,
+            span: Span::none(),
+        }
+    }
+
+    /// ### parse_address
+    ///
+    /// Split address into pod name and container (`pod/container`)
+    fn parse_address(address: &str) -> (String, Option<String>) {
+        match address.split_once('/') {
+            Some((pod, container)) if !container.is_empty() => {
+                (pod.to_string(), Some(container.to_string()))
+            }
+            Some((pod, _)) => (pod.to_string(), None),
+            None => (address.to_string(), None),
+        }
+    }
+
+    /// ### resolve
+    ///
+    /// Get absolute and normalized path from `p` (relative paths are relative to wrkdir)
+    fn resolve(&self, p: &Path) -> PathBuf {
+        let mut path: PathBuf = PathBuf::from("/");
+        for component in self.wrkdir.join(p).components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::ParentDir => {
+                    path.pop();
+                }
+                _ => {}
+            }
+        }
+        path
+    }
+
+    /// ### command
+    ///
+    /// Make the `kubectl exec` command which runs `args` in the container.
+    /// If `stdin` is true, the standard input is attached to the command
+    fn command(&self, args: &[&str], stdin: bool) -> Command {
+        let mut cmd: Command = Command::new(self.kubectl.as_path());
+        cmd.arg("exec");
+        if stdin {
+            cmd.arg("-i");
+        }
+        if let Some(namespace) = self.namespace.as_ref() {
+            cmd.arg("-n").arg(namespace);
+        }
+        cmd.arg(self.pod.as_str());
+        if let Some(container) = self.container.as_ref() {
+            cmd.arg("-c").arg(container);
+        }
+        cmd.arg("--").args(args);
+        cmd
+    }
+
+    /// ### run
+    ///
+    /// Run a command in the container and return its standard output.
+    /// An error is returned if the command exits with a failure
+    fn run(&self, args: &[&str]) -> Result<String, FileTransferError> {
+        let output: Output = self
+            .command(args, false)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(Self::spawn_error)?;
+        match output.status.success() {
+            true => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+            false => Err(Self::map_error(&output.stderr)),
+        }
+    }
+
+    /// ### spawn_error
+    ///
+    /// Make the error for a kubectl process which couldn't be started
+    fn spawn_error(err: io::Error) -> FileTransferError {
+        FileTransferError::new_ex(
+            FileTransferErrorType::ConnectionError,
+            format!("Could not run kubectl: {}", err),
+        )
+    }
+
+    /// ### map_error
+    ///
+    /// Map the standard error of a failed command to a `FileTransferError`
+    fn map_error(stderr: &[u8]) -> FileTransferError {
+        let msg: String = String::from_utf8_lossy(stderr).trim().to_string();
+        let code: FileTransferErrorType = if msg.contains("No such file or directory") {
+            FileTransferErrorType::NoSuchFileOrDirectory
+        } else if msg.contains("Permission denied") || msg.contains("Read-only file system") {
+            FileTransferErrorType::PexError
+        } else if msg.contains("Forbidden") || msg.contains("Unauthorized") {
+            FileTransferErrorType::AuthenticationFailed
+        } else {
+            FileTransferErrorType::ProtocolError
+        };
+        FileTransferError::new_ex(code, msg)
+    }
+
+    /// ### wait
+    ///
+    /// Wait for a streaming command to terminate; an error is returned if it failed
+    fn wait(child: Child) -> Result<(), FileTransferError> {
+        match child.wait_with_output() {
+            Ok(output) if output.status.success() => Ok(()),
+            Ok(output) => Err(Self::map_error(&output.stderr)),
+            Err(err) => Err(FileTransferError::new(FileTransferErrorType::IoErr(err))),
+        }
+    }
+
+    /// ### path_str
+    ///
+    /// Convert path to string to pass it as argument
+    fn path_str(p: &Path) -> String {
+        p.to_string_lossy().to_string()
+    }
+
+    /// ### parse_ls_output
+    ///
+    /// Parse a line of `ls -l` output and tokenize the output into a `FsEntry`
+    fn parse_ls_output(&mut self, path: &Path, line: &str) -> Result<FsEntry, LsParseError> {
+        let mut entry: LsEntry = parse_unix_ls_line(line)?;
+        // Stat symlink target; the symlink is a directory if its target is
+        let mut broken_link: Option<PathBuf> = None;
+        let symlink: Option<Box<FsEntry>> = match entry.symlink.clone() {
+            None => None,
+            Some(p) => match p.file_name().unwrap_or_else(|| std::ffi::OsStr::new(""))
+                == entry.name.as_str()
+            {
+                // If name is equal, don't stat path; otherwise it would get stuck
+                true => None,
+                false => match self.stat(path.join(p.as_path()).as_path()) {
+                    Ok(target) => Some(Box::new(target)),
+                    Err(_) => {
+                        broken_link = Some(path.join(p));
+                        None
+                    }
+                },
+            },
+        };
+        if let Some(target) = symlink.as_ref() {
+            entry.is_dir = matches!(target.as_ref(), FsEntry::Directory(_));
+        }
+        let mut entry: FsEntry = entry.into_fsentry(path, symlink);
+        if let FsEntry::File(file) = &mut entry {
+            file.broken_link = broken_link;
+        }
+        Ok(entry)
+    }
+}
+
+// -- tar
+
+/// ### tar_header
+///
+/// Make the ustar header for a regular file, preceded by a GNU long name entry
+/// if the name doesn't fit the header
+fn tar_header(name: &str, size: u64, mode: u32, mtime: u64) -> Vec<u8> {
+    let mut data: Vec<u8> = Vec::with_capacity(TAR_BLOCK_SIZE);
+    if name.len() > 100 {
+        let mut long_name: Vec<u8> = name.as_bytes().to_vec();
+        long_name.push(0);
+        data.extend(tar_header_block(
+            "././@LongLink",
+            long_name.len() as u64,
+            0o644,
+            0,
+            b'L',
+        ));
+        long_name.resize(tar_padded(long_name.len()), 0);
+        data.extend(long_name);
+    }
+    data.extend(tar_header_block(name, size, mode, mtime, b'0'));
+    data
+}
+
+/// ### tar_header_block
+///
+/// Make a single header block. Name is truncated to 100 bytes
+fn tar_header_block(name: &str, size: u64, mode: u32, mtime: u64, typeflag: u8) -> [u8; 512] {
+    let mut header: [u8; 512] = [0; 512];
+    let name: &[u8] = name.as_bytes();
+    let name_len: usize = name.len().min(100);
+    header[0..name_len].copy_from_slice(&name[0..name_len]);
+    header[100..108].copy_from_slice(format!("{:07o}\0", mode & 0o7777).as_bytes());
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    if size < 0o77777777777 {
+        header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    } else {
+        // Base-256 encoding
+        header[124] = 0x80;
+        header[128..136].copy_from_slice(&size.to_be_bytes());
+    }
+    header[136..148].copy_from_slice(format!("{:011o}\0", mtime.min(0o77777777777)).as_bytes());
+    header[156] = typeflag;
+    header[257..263].copy_from_slice(b"ustar\0");
+    header[263..265].copy_from_slice(b"00");
+    // Checksum is calculated with the checksum field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|x| *x as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+    header
+}
+
+/// ### tar_padded
+///
+/// Get the size of `size` bytes rounded up to the tar block size
+fn tar_padded(size: usize) -> usize {
+    size.div_ceil(TAR_BLOCK_SIZE) * TAR_BLOCK_SIZE
+}
+
+/// ### tar_number
+///
+/// Parse a numeric header field (octal or base-256)
+fn tar_number(field: &[u8]) -> Option<u64> {
+    match field.first() {
+        Some(x) if x & 0x80 != 0 => {
+            // Base-256
+            let mut value: u64 = (x & 0x7f) as u64;
+            for byte in field[1..].iter() {
+                value = value.checked_mul(256)?.checked_add(*byte as u64)?;
+            }
+            Some(value)
+        }
+        _ => {
+            let text: String = field
+                .iter()
+                .take_while(|x| **x != 0)
+                .map(|x| *x as char)
+                .collect();
+            match text.trim() {
+                "" => Some(0),
+                text => u64::from_str_radix(text, 8).ok(),
+            }
+        }
+    }
+}
+
+/// ### read_tar_file
+///
+/// Read tar headers from `reader` until the first regular file and return its size.
+/// The reader is then positioned at the beginning of the file data
+fn read_tar_file(reader: &mut dyn Read) -> io::Result<u64> {
+    let mut header: [u8; 512] = [0; 512];
+    loop {
+        reader.read_exact(&mut header)?;
+        if header.iter().all(|x| *x == 0) {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "archive doesn't contain any file",
+            ));
+        }
+        // Verify checksum
+        let expected: Option<u64> = tar_number(&header[148..156]);
+        let checksum: u64 = header
+            .iter()
+            .enumerate()
+            .map(|(i, x)| match (148..156).contains(&i) {
+                true => b' ' as u64,
+                false => *x as u64,
+            })
+            .sum();
+        if expected != Some(checksum) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "bad tar header checksum",
+            ));
+        }
+        let size: u64 = tar_number(&header[124..136])
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "bad tar entry size"))?;
+        match header[156] {
+            b'0' | b'\0' | b'7' => return Ok(size),
+            _ => {
+                // Skip entry (long names, pax headers, ...)
+                io::copy(
+                    &mut reader.take(tar_padded(size as usize) as u64),
+                    &mut io::sink(),
+                )?;
+            }
+        }
+    }
+}
+
+/// ## TarWriter
+///
+/// Writes the data of the only file of a tar archive to a `tar x` process.
+/// Archive is terminated when the writer is dropped, only if all the data announced in header has been written;
+/// otherwise the archive is left truncated, so that the extraction fails
+struct TarWriter {
+    stream: BufWriter<ChildStdin>,
+    remaining: u64, // Bytes of file data left to write
+    size: u64,
+}
+
+impl TarWriter {
+    /// ### new
+    ///
+    /// Instantiates a new `TarWriter`, writing the header of the file to `stream`
+    fn new(stream: ChildStdin, header: &[u8], size: u64) -> io::Result<TarWriter> {
+        let mut stream: BufWriter<ChildStdin> = BufWriter::with_capacity(65536, stream);
+        stream.write_all(header)?;
+        Ok(TarWriter {
+            stream,
+            remaining: size,
+            size,
+        })
+    }
+}
+
+impl Write for TarWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() as u64 > self.remaining {
+            return Err(io::Error::other(
+                "file is larger than the size announced in the archive",
+            ));
+        }
+        let written: usize = self.stream.write(buf)?;
+        self.remaining -= written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl Drop for TarWriter {
+    fn drop(&mut self) {
+        if self.remaining == 0 {
+            // Pad file data and write the end of archive (two zero blocks)
+            let padding: usize = tar_padded(self.size as usize) - self.size as usize;
+            let _ = self
+                .stream
+                .write_all(&vec![0; padding + TAR_BLOCK_SIZE * 2]);
+        }
+        let _ = self.stream.flush();
+    }
+}
+
+/// ## TarReader
+///
+/// Reads the data of a file from a `tar c` process, once the header has been consumed
+struct TarReader {
+    stream: BufReader<ChildStdout>,
+    remaining: u64, // Bytes of file data left to read
+}
+
+impl Read for TarReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let max: usize = buf.len().min(self.remaining as usize);
+        let read: usize = self.stream.read(&mut buf[..max])?;
+        if read == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "archive is truncated",
+            ));
+        }
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
+
+impl FileTransfer for KubeFileTransfer {
+    /// ### connect
+    ///
+    /// Connect to the remote server
+    /// Address is the name of the pod, optionally followed by the container name (`pod/container`);
+    /// username is the namespace. Port and password are not used
+    fn connect(
+        &mut self,
+        address: String,
+        _port: u16,
+        username: Option<String>,
+        _password: Option<String>,
+    ) -> Result<Option<String>, FileTransferError> {
+        let (pod, container) = Self::parse_address(address.as_str());
+        if pod.is_empty() {
+            return Err(FileTransferError::new_ex(
+                FileTransferErrorType::BadAddress,
+                String::from("Pod name is missing"),
+            ));
+        }
+        self.pod = pod;
+        self.container = container;
+        self.namespace = username.filter(|x| !x.is_empty());
+        // Check whether the container can be reached and get working directory
+        match self.run(&["pwd"]) {
+            Ok(wrkdir) => {
+                self.wrkdir = match wrkdir.trim() {
+                    "" => PathBuf::from("/"),
+                    wrkdir => PathBuf::from(wrkdir),
+                };
+                self.connected = true;
+                // Open session span
+                self.span = info_span!(
+                    "session",
+                    protocol = "KUBE",
+                    host = %address,
+                    namespace = %self.namespace.as_deref().unwrap_or("default")
+                );
+                Ok(None)
+            }
+            Err(err) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::ConnectionError,
+                format!("{}", err),
+            )),
+        }
+    }
+
+    /// ### disconnect
+    ///
+    /// Disconnect from the remote server
+    fn disconnect(&mut self) -> Result<(), FileTransferError> {
+        match self.connected {
+            true => {
+                // Terminate pending transfers
+                for mut child in self.upload.take().into_iter().chain(self.download.take()) {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                self.connected = false;
+                // Close session span
+                self.span = Span::none();
+                Ok(())
+            }
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### is_connected
+    ///
+    /// Indicates whether the client is connected to remote
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// ### pwd
+    ///
+    /// Print working directory
+    fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
+        match self.connected {
+            true => Ok(self.wrkdir.clone()),
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### change_dir
+    ///
+    /// Change working directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: String = Self::path_str(self.resolve(dir).as_path());
+        // Directory must exist and be accessible
+        self.run(&["sh", "-c", "cd \"$1\"", "sh", path.as_str()])?;
+        self.wrkdir = PathBuf::from(path);
+        Ok(self.wrkdir.clone())
+    }
+
+    /// ### copy
+    ///
+    /// Copy file to destination
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let src: String = Self::path_str(self.resolve(src.get_abs_path().as_path()).as_path());
+        let dst: String = Self::path_str(self.resolve(dst).as_path());
+        self.run(&["cp", "-rf", src.as_str(), dst.as_str()])
+            .map(|_| ())
+    }
+
+    /// ### list_dir
+    ///
+    /// List directory entries
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: PathBuf = self.resolve(path);
+        let output: String = self.run(&["ls", "-la", Self::path_str(path.as_path()).as_str()])?;
+        let mut entries: Vec<FsEntry> = Vec::new();
+        for line in output.lines() {
+            // Parse row, if ok push to entries
+            match self.parse_ls_output(path.as_path(), line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) if err.is_ignorable() => {}
+                Err(err) => warn!(line = %line, "Could not parse entry: {}", err),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// ### mkdir
+    ///
+    /// Make directory
+    /// You must return error in case the directory already exists
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: String = Self::path_str(self.resolve(dir).as_path());
+        self.run(&["mkdir", path.as_str()])
+            .map(|_| ())
+            .map_err(|err| {
+                FileTransferError::new_ex(
+                    FileTransferErrorType::FileCreateDenied,
+                    format!("{}", err),
+                )
+            })
+    }
+
+    /// ### remove
+    ///
+    /// Remove a file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %file.get_abs_path().display()), err)]
+    fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: String = Self::path_str(self.resolve(file.get_abs_path().as_path()).as_path());
+        self.run(&["rm", "-rf", path.as_str()]).map(|_| ())
+    }
+
+    /// ### rename
+    ///
+    /// Rename file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
+    fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let src: String = Self::path_str(self.resolve(file.get_abs_path().as_path()).as_path());
+        let dst: String = Self::path_str(self.resolve(dst).as_path());
+        self.run(&["mv", "-f", src.as_str(), dst.as_str()])
+            .map(|_| ())
+    }
+
+    /// ### stat
+    ///
+    /// Stat file and return FsEntry
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: PathBuf = self.resolve(path);
+        let parent: PathBuf = match path.parent() {
+            Some(p) => PathBuf::from(p),
+            None => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::UnsupportedFeature,
+                    String::from("Path has no parent"),
+                ))
+            }
+        };
+        let line: String = self.run(&["ls", "-ld", Self::path_str(path.as_path()).as_str()])?;
+        match parse_unix_ls_line(line.trim()) {
+            Ok(mut entry) => {
+                // Use the requested name (ls may print the whole path)
+                if let Some(name) = path.file_name() {
+                    entry.name = name.to_string_lossy().to_string();
+                }
+                // A symlink is a directory if its target is (`test` follows symlinks)
+                if entry.symlink.is_some() {
+                    entry.is_dir = self
+                        .run(&["test", "-d", Self::path_str(path.as_path()).as_str()])
+                        .is_ok();
+                }
+                Ok(entry.into_fsentry(parent.as_path(), None))
+            }
+            Err(_) => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### exec
+    ///
+    /// Execute a command on remote host
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
+    fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let script: String = format!(
+            "cd {}; {}",
+            shell::quote(Self::path_str(self.wrkdir.as_path()).as_str()),
+            cmd
+        );
+        // Command failures are reported in the output
+        let output: Output = self
+            .command(&["sh", "-c", script.as_str()], false)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(Self::spawn_error)?;
+        Ok(format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ))
+    }
+
+    /// ### send_file
+    ///
+    /// Send file to remote
+    /// File name is referred to the name of the file as it will be saved
+    /// Data contains the file data
+    /// Returns file and its size
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
+    fn send_file(
+        &mut self,
+        local: &FsFile,
+        file_name: &Path,
+    ) -> Result<Box<dyn Write>, FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: PathBuf = self.resolve(file_name);
+        let (parent, name) = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => {
+                (Self::path_str(parent), name.to_string_lossy().to_string())
+            }
+            _ => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::FileCreateDenied,
+                    format!("\"{}\"", path.display()),
+                ))
+            }
+        };
+        // Size must be known in advance; NOTE: don't use the `size` attribute, since might be out of sync
+        let size: u64 = match std::fs::metadata(local.abs_path.as_path()) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => local.size as u64,
+        };
+        let mode: u32 = match local.unix_pex {
+            None => 0o644,
+            Some((u, g, o)) => ((u as u32) << 6) + ((g as u32) << 3) + (o as u32),
+        };
+        let mtime: u64 = local
+            .last_change_time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or(0);
+        // Extract archive in the parent directory
+        let mut child: Child = self
+            .command(&["tar", "xmf", "-", "-C", parent.as_str()], true)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(Self::spawn_error)?;
+        let stdin: ChildStdin = child.stdin.take().unwrap();
+        let header: Vec<u8> = tar_header(name.as_str(), size, mode, mtime);
+        match TarWriter::new(stdin, header.as_slice(), size) {
+            Ok(writer) => {
+                self.upload = Some(child);
+                Ok(Box::new(writer))
+            }
+            Err(_) => {
+                // Process has terminated; report its error
+                Err(Self::wait(child).err().unwrap_or_else(|| {
+                    FileTransferError::new(FileTransferErrorType::ProtocolError)
+                }))
+            }
+        }
+    }
+
+    /// ### recv_file
+    ///
+    /// Receive file from remote with provided name
+    /// Returns file and its size
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
+    fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
+        if !self.connected {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let path: PathBuf = self.resolve(file.abs_path.as_path());
+        let (parent, name) = match (path.parent(), path.file_name()) {
+            (Some(parent), Some(name)) => {
+                (Self::path_str(parent), name.to_string_lossy().to_string())
+            }
+            _ => {
+                return Err(FileTransferError::new(
+                    FileTransferErrorType::NoSuchFileOrDirectory,
+                ))
+            }
+        };
+        // Archive file, following symlinks
+        let mut child: Child = self
+            .command(
+                &["tar", "chf", "-", "-C", parent.as_str(), name.as_str()],
+                false,
+            )
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(Self::spawn_error)?;
+        let mut stream: BufReader<ChildStdout> =
+            BufReader::with_capacity(65536, child.stdout.take().unwrap());
+        match read_tar_file(&mut stream) {
+            Ok(size) => {
+                self.download = Some(child);
+                Ok(Box::new(TarReader {
+                    stream,
+                    remaining: size,
+                }))
+            }
+            Err(err) => {
+                drop(stream);
+                // Report the error of the process if it failed
+                match Self::wait(child) {
+                    Err(err) => Err(err),
+                    Ok(()) => Err(FileTransferError::new_ex(
+                        FileTransferErrorType::ProtocolError,
+                        format!("{}", err),
+                    )),
+                }
+            }
+        }
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer: once the whole file has been sent, terminate the archive and wait for the extraction.
+    /// Otherwise, and for downloads, the process is killed
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(bytes = outcome.bytes), err)]
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        let completed: bool = outcome.is_completed();
+        match outcome.stream {
+            TransferStream::Upload(writer) => match self.upload.take() {
+                Some(child) if completed => {
+                    // Terminate archive and wait for the extraction
+                    drop(writer);
+                    Self::wait(child)
+                }
+                Some(mut child) => {
+                    // Kill the process before closing the archive, so that it isn't extracted any further
+                    let _ = child.kill();
+                    drop(writer);
+                    let _ = child.wait();
+                    Ok(())
+                }
+                None => Ok(()),
+            },
+            TransferStream::Download(reader) => {
+                drop(reader);
+                // File data has already been read (or the transfer has been aborted): terminate process
+                if let Some(mut child) = self.download.take() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::filetransfer::TransferStatus;
+
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use std::os::unix::fs::PermissionsExt;
+
+    #[test]
+    fn test_filetransfer_kube_new() {
+        let client: KubeFileTransfer = KubeFileTransfer::new();
+        assert_eq!(client.kubectl, PathBuf::from("kubectl"));
+        assert!(client.namespace.is_none());
+        assert!(client.container.is_none());
+        assert!(!client.is_connected());
+        assert_eq!(client.wrkdir, PathBuf::from("/"));
+    }
+
+    #[test]
+    fn test_filetransfer_kube_parse_address() {
+        assert_eq!(
+            KubeFileTransfer::parse_address("nginx-7c5ddbdf54-2lhtz"),
+            (String::from("nginx-7c5ddbdf54-2lhtz"), None)
+        );
+        assert_eq!(
+            KubeFileTransfer::parse_address("nginx-7c5ddbdf54-2lhtz/sidecar"),
+            (
+                String::from("nginx-7c5ddbdf54-2lhtz"),
+                Some(String::from("sidecar"))
+            )
+        );
+        assert_eq!(
+            KubeFileTransfer::parse_address("nginx/"),
+            (String::from("nginx"), None)
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_kube_command() {
+        let mut client: KubeFileTransfer = KubeFileTransfer::new();
+        client.pod = String::from("nginx");
+        let cmd: Command = client.command(&["ls", "-la", "/"], false);
+        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert_eq!(args, vec!["exec", "nginx", "--", "ls", "-la", "/"]);
+        client.namespace = Some(String::from("web"));
+        client.container = Some(String::from("sidecar"));
+        let cmd: Command = client.command(&["tar", "xf", "-"], true);
+        let args: Vec<&std::ffi::OsStr> = cmd.get_args().collect();
+        assert_eq!(
+            args,
+            vec!["exec", "-i", "-n", "web", "nginx", "-c", "sidecar", "--", "tar", "xf", "-"]
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_kube_map_error() {
+        let err: FileTransferError = KubeFileTransfer::map_error(
+            b"ls: cannot access '/foo': No such file or directory\ncommand terminated with exit code 2\n",
+        );
+        assert!(matches!(
+            err.code,
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        let err: FileTransferError =
+            KubeFileTransfer::map_error(b"mkdir: cannot create directory '/a': Permission denied");
+        assert!(matches!(err.code, FileTransferErrorType::PexError));
+        let err: FileTransferError = KubeFileTransfer::map_error(
+            b"Error from server (Forbidden): pods \"nginx\" is forbidden",
+        );
+        assert!(matches!(
+            err.code,
+            FileTransferErrorType::AuthenticationFailed
+        ));
+        let err: FileTransferError =
+            KubeFileTransfer::map_error(b"Error from server (NotFound): pods \"nginx\" not found");
+        assert!(matches!(err.code, FileTransferErrorType::ProtocolError));
+    }
+
+    #[test]
+    fn test_filetransfer_kube_tar_header() {
+        let header: Vec<u8> = tar_header("README.md", 8192, 0o644, 1617192000);
+        assert_eq!(header.len(), TAR_BLOCK_SIZE);
+        assert_eq!(&header[0..9], b"README.md");
+        assert_eq!(&header[100..108], b"0000644\0");
+        assert_eq!(header[156], b'0');
+        assert_eq!(&header[257..263], b"ustar\0");
+        // Read back
+        let mut data: Vec<u8> = header.clone();
+        data.extend(vec![0; 8192]);
+        assert_eq!(read_tar_file(&mut data.as_slice()).unwrap(), 8192);
+        // Long name
+        let name: String = "a".repeat(120);
+        let header: Vec<u8> = tar_header(name.as_str(), 4, 0o644, 0);
+        assert_eq!(header.len(), TAR_BLOCK_SIZE * 3);
+        assert_eq!(header[156], b'L');
+        assert_eq!(
+            &header[TAR_BLOCK_SIZE..TAR_BLOCK_SIZE + 120],
+            name.as_bytes()
+        );
+        assert_eq!(read_tar_file(&mut header.as_slice()).unwrap(), 4);
+        // Base-256 size
+        let header: Vec<u8> = tar_header("big.iso", 0x2_0000_0000, 0o644, 0);
+        assert_eq!(
+            read_tar_file(&mut header.as_slice()).unwrap(),
+            0x2_0000_0000
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_kube_read_tar_file_errors() {
+        // End of archive
+        let data: Vec<u8> = vec![0; TAR_BLOCK_SIZE * 2];
+        assert_eq!(
+            read_tar_file(&mut data.as_slice()).err().unwrap().kind(),
+            io::ErrorKind::NotFound
+        );
+        // Bad checksum
+        let mut data: Vec<u8> = tar_header("README.md", 1, 0o644, 0);
+        data[0] = b'r';
+        assert_eq!(
+            read_tar_file(&mut data.as_slice()).err().unwrap().kind(),
+            io::ErrorKind::InvalidData
+        );
+        // Truncated
+        let data: Vec<u8> = vec![1; 100];
+        assert!(read_tar_file(&mut data.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_kube_not_connected() {
+        let mut client: KubeFileTransfer = KubeFileTransfer::new();
+        assert!(client.pwd().is_err());
+        assert!(client.change_dir(Path::new("/tmp")).is_err());
+        assert!(client.list_dir(Path::new("/")).is_err());
+        assert!(client.mkdir(Path::new("/tmp/a")).is_err());
+        assert!(client.stat(Path::new("/tmp")).is_err());
+        assert!(client.exec("echo 1").is_err());
+        assert!(client.disconnect().is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_kube_connect_no_kubectl() {
+        let mut client: KubeFileTransfer = KubeFileTransfer::new();
+        client.kubectl = PathBuf::from("/nonexisting/kubectl");
+        let err: FileTransferError = client
+            .connect(String::from("nginx"), 0, None, None)
+            .err()
+            .unwrap();
+        assert!(matches!(err.code, FileTransferErrorType::ConnectionError));
+        assert!(!client.is_connected());
+        // Missing pod
+        assert!(matches!(
+            client
+                .connect(String::from(""), 0, None, None)
+                .err()
+                .unwrap()
+                .code,
+            FileTransferErrorType::BadAddress
+        ));
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_filetransfer_kube_session() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: KubeFileTransfer = KubeFileTransfer::new();
+        client.kubectl = make_fake_kubectl(tmpdir.path());
+        assert!(client
+            .connect(
+                String::from("nginx/sidecar"),
+                0,
+                Some(String::from("web")),
+                None
+            )
+            .is_ok());
+        assert!(client.is_connected());
+        assert_eq!(client.namespace.as_deref(), Some("web"));
+        assert_eq!(client.container.as_deref(), Some("sidecar"));
+        // Change directory
+        let root: PathBuf = tmpdir.path().join("root");
+        std::fs::create_dir(root.as_path()).unwrap();
+        assert_eq!(client.change_dir(root.as_path()).unwrap(), root);
+        assert!(client.change_dir(Path::new("nonexisting")).is_err());
+        assert_eq!(client.pwd().unwrap(), root);
+        // Mkdir
+        assert!(client.mkdir(Path::new("data")).is_ok());
+        assert!(client.mkdir(Path::new("data")).is_err());
+        // Upload
+        let local: PathBuf = tmpdir.path().join("local.txt");
+        std::fs::write(local.as_path(), b"Hello, pod!\n").unwrap();
+        let local: FsFile = make_fsfile(local.as_path(), 12);
+        let mut writer: Box<dyn Write> = client
+            .send_file(&local, Path::new("data/hello.txt"))
+            .ok()
+            .unwrap();
+        writer.write_all(b"Hello, pod!\n").unwrap();
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_ok());
+        assert_eq!(
+            std::fs::read(root.join("data/hello.txt")).unwrap(),
+            b"Hello, pod!\n"
+        );
+        // List and stat
+        let entries: Vec<FsEntry> = client.list_dir(Path::new("data")).ok().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].get_name(), "hello.txt");
+        assert_eq!(entries[0].get_abs_path(), root.join("data/hello.txt"));
+        let entry: FsEntry = client.stat(Path::new("data/hello.txt")).ok().unwrap();
+        assert!(matches!(&entry, FsEntry::File(file) if file.size == 12));
+        assert_eq!(entry.get_name(), "hello.txt");
+        assert!(matches!(
+            client.stat(Path::new("data")).ok().unwrap(),
+            FsEntry::Directory(_)
+        ));
+        // Download
+        let file: FsFile = match entry {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("Expected file"),
+        };
+        let mut reader: Box<dyn Read> = client.recv_file(&file).ok().unwrap();
+        let mut data: Vec<u8> = Vec::new();
+        reader.read_to_end(&mut data).unwrap();
+        assert_eq!(data, b"Hello, pod!\n");
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                12
+            ))
+            .is_ok());
+        // Copy, rename and remove
+        let entry: FsEntry = FsEntry::File(file);
+        assert!(client.copy(&entry, Path::new("data/copy.txt")).is_ok());
+        assert!(client.rename(&entry, Path::new("data/moved.txt")).is_ok());
+        assert!(!root.join("data/hello.txt").exists());
+        assert!(root.join("data/moved.txt").exists());
+        assert!(root.join("data/copy.txt").exists());
+        let data: FsEntry = client.stat(Path::new("data")).ok().unwrap();
+        assert!(client.remove(&data).is_ok());
+        assert!(!root.join("data").exists());
+        // Exec runs in working directory
+        assert_eq!(
+            client.exec("pwd").ok().unwrap().trim(),
+            root.to_string_lossy()
+        );
+        assert!(client.disconnect().is_ok());
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_filetransfer_kube_transfer_errors() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: KubeFileTransfer = KubeFileTransfer::new();
+        client.kubectl = make_fake_kubectl(tmpdir.path());
+        assert!(client.connect(String::from("nginx"), 0, None, None).is_ok());
+        // Download missing file
+        let missing: FsFile = make_fsfile(tmpdir.path().join("missing.txt").as_path(), 0);
+        assert!(matches!(
+            client.recv_file(&missing).err().unwrap().code,
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        // Upload less data than announced
+        let local: PathBuf = tmpdir.path().join("local.txt");
+        std::fs::write(local.as_path(), b"0123456789").unwrap();
+        let local: FsFile = make_fsfile(local.as_path(), 10);
+        let dst: PathBuf = tmpdir.path().join("short.txt");
+        let mut writer: Box<dyn Write> = client.send_file(&local, dst.as_path()).ok().unwrap();
+        writer.write_all(b"01234").unwrap();
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                5
+            ))
+            .is_err());
+        // Upload more data than announced
+        let mut writer: Box<dyn Write> = client.send_file(&local, dst.as_path()).ok().unwrap();
+        assert!(writer.write_all(b"0123456789abc").is_err());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                0
+            ))
+            .is_err());
+        // Aborted upload
+        let mut writer: Box<dyn Write> = client.send_file(&local, dst.as_path()).ok().unwrap();
+        writer.write_all(b"01234").unwrap();
+        assert!(client
+            .finalize_transfer(TransferOutcome::new(
+                TransferStream::Upload(writer),
+                5,
+                TransferStatus::Aborted
+            ))
+            .is_ok());
+        assert!(client.upload.is_none());
+    }
+
+    /// Make a fake `kubectl`, which runs the command after `--` on the local host
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn make_fake_kubectl(dir: &Path) -> PathBuf {
+        let path: PathBuf = dir.join("kubectl");
+        std::fs::write(
+            path.as_path(),
+            "#!/bin/sh\nwhile [ \"$1\" != \"--\" ]; do shift; done\nshift\nexec \"$@\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(path.as_path(), std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn make_fsfile(path: &Path, size: usize) -> FsFile {
+        FsFile {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            abs_path: PathBuf::from(path),
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            size,
+            ftype: None,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: Some((6, 4, 4)),
+        }
+    }
+}
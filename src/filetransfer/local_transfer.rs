@@ -0,0 +1,456 @@
+//! ## LocalTransfer
+//!
+//! `local_transfer` is the module which provides the implementation of a file transfer on the local file system.
+//! It is used by the local-to-local mode, where both explorers browse the local host
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, TransferOutcome, TransferStatus,
+    TransferStream,
+};
+use crate::fs::{FsEntry, FsFile};
+use crate::host::{HostError, HostErrorType, Localhost};
+// Ext
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// ## LocalFileTransfer
+///
+/// Local file transfer structure; operations are performed through another `Localhost` instance.
+/// The working directory is tracked here: `Localhost` changes the working directory of the process,
+/// which belongs to the local explorer
+pub struct LocalFileTransfer {
+    host: Option<Localhost>, // Some if connected
+    wrkdir: PathBuf,
+}
+
+impl LocalFileTransfer {
+    /// ### new
+    ///
+    /// Instantiates a new `LocalFileTransfer`, which will enter `wrkdir` once connected
+    pub fn new(wrkdir: &Path) -> LocalFileTransfer {
+        LocalFileTransfer {
+            host: None,
+            wrkdir: PathBuf::from(wrkdir),
+        }
+    }
+
+    /// ### resolve
+    ///
+    /// Make path absolute, relative to the working directory, and normalize `.` and `..` components
+    fn resolve(&self, p: &Path) -> PathBuf {
+        let mut path: PathBuf = match p.is_relative() {
+            true => self.wrkdir.clone(),
+            false => PathBuf::new(),
+        };
+        for component in p.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    path.pop();
+                }
+                c => path.push(c),
+            }
+        }
+        path
+    }
+
+    /// ### host
+    ///
+    /// Get the `Localhost` instance if connected
+    fn host(&mut self) -> Result<&mut Localhost, FileTransferError> {
+        self.host
+            .as_mut()
+            .ok_or_else(|| FileTransferError::new(FileTransferErrorType::UninitializedSession))
+    }
+
+    /// ### map_err
+    ///
+    /// Convert a `HostError` into a `FileTransferError`
+    fn map_err(err: HostError) -> FileTransferError {
+        let code: FileTransferErrorType = match err.error {
+            HostErrorType::NoSuchFileOrDirectory => FileTransferErrorType::NoSuchFileOrDirectory,
+            HostErrorType::DirNotAccessible => FileTransferErrorType::DirStatFailed,
+            HostErrorType::FileAlreadyExists
+            | HostErrorType::CouldNotCreateFile
+            | HostErrorType::PreallocationFailed
+            | HostErrorType::SyncFailed => FileTransferErrorType::FileCreateDenied,
+            HostErrorType::ReadonlyFile
+            | HostErrorType::FileNotAccessible
+            | HostErrorType::DeleteFailed => FileTransferErrorType::PexError,
+            HostErrorType::ExecutionFailed => FileTransferErrorType::ProtocolError,
+        };
+        FileTransferError::new_ex(code, err.to_string())
+    }
+}
+
+impl FileTransfer for LocalFileTransfer {
+    /// ### connect
+    ///
+    /// Connect to the remote server; address and credentials are ignored
+    fn connect(
+        &mut self,
+        _address: String,
+        _port: u16,
+        _username: Option<String>,
+        _password: Option<String>,
+    ) -> Result<Option<String>, FileTransferError> {
+        if !self.wrkdir.is_dir() {
+            return Err(FileTransferError::new_ex(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+                format!("\"{}\" is not a directory", self.wrkdir.display()),
+            ));
+        }
+        // NOTE: Localhost is kept at root; paths are always resolved to absolute paths here
+        self.host = Some(Localhost::new(PathBuf::from("/")).map_err(Self::map_err)?);
+        Ok(None)
+    }
+
+    /// ### disconnect
+    ///
+    /// Disconnect from the remote server
+    fn disconnect(&mut self) -> Result<(), FileTransferError> {
+        self.host()?;
+        self.host = None;
+        Ok(())
+    }
+
+    /// ### is_connected
+    ///
+    /// Indicates whether the client is connected to remote
+    fn is_connected(&self) -> bool {
+        self.host.is_some()
+    }
+
+    /// ### pwd
+    ///
+    /// Print working directory
+    fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
+        self.host()?;
+        Ok(self.wrkdir.clone())
+    }
+
+    /// ### change_dir
+    ///
+    /// Change working directory
+    fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
+        let dir: PathBuf = self.resolve(dir);
+        match self.host()?.stat(dir.as_path()).map_err(Self::map_err)? {
+            FsEntry::Directory(_) => {
+                self.wrkdir = dir;
+                Ok(self.wrkdir.clone())
+            }
+            FsEntry::File(_) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+                format!("\"{}\" is not a directory", dir.display()),
+            )),
+        }
+    }
+
+    /// ### copy
+    ///
+    /// Copy file to destination
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        let dst: PathBuf = self.resolve(dst);
+        self.host()?.copy(src, dst.as_path()).map_err(Self::map_err)
+    }
+
+    /// ### list_dir
+    ///
+    /// List directory entries
+    fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
+        let path: PathBuf = self.resolve(path);
+        self.host()?.scan_dir(path.as_path()).map_err(Self::map_err)
+    }
+
+    /// ### mkdir
+    ///
+    /// Make directory
+    /// You must return error in case the directory already exists
+    fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        let dir: PathBuf = self.resolve(dir);
+        self.host()?.mkdir(dir.as_path()).map_err(Self::map_err)
+    }
+
+    /// ### remove
+    ///
+    /// Remove a file or a directory
+    fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        self.host()?.remove(file).map_err(Self::map_err)
+    }
+
+    /// ### rename
+    ///
+    /// Rename file or a directory
+    fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        let dst: PathBuf = self.resolve(dst);
+        self.host()?
+            .rename(file, dst.as_path())
+            .map_err(Self::map_err)
+    }
+
+    /// ### stat
+    ///
+    /// Stat file and return FsEntry
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
+        let path: PathBuf = self.resolve(path);
+        self.host()?.stat(path.as_path()).map_err(Self::map_err)
+    }
+
+    /// ### exec
+    ///
+    /// Execute a command in the working directory
+    fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
+        self.host()?;
+        // Split arguments as `Localhost` does, but run in this working directory
+        let args: Vec<&str> = cmd.split(' ').collect();
+        match Command::new(args[0])
+            .args(&args[1..])
+            .current_dir(self.wrkdir.as_path())
+            .output()
+        {
+            Ok(output) => Ok(String::from_utf8_lossy(&output.stdout).to_string()),
+            Err(err) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::ProtocolError,
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// ### send_file
+    ///
+    /// Send file to remote
+    /// File name is referred to the name of the file as it will be saved
+    /// Data contains the file data
+    fn send_file(
+        &mut self,
+        _local: &FsFile,
+        file_name: &Path,
+    ) -> Result<Box<dyn Write>, FileTransferError> {
+        let path: PathBuf = self.resolve(file_name);
+        match self.host()?.open_file_write(path.as_path()) {
+            Ok(file) => Ok(Box::new(file)),
+            Err(err) => Err(Self::map_err(err)),
+        }
+    }
+
+    /// ### recv_file
+    ///
+    /// Receive file from remote with provided name
+    fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
+        let path: PathBuf = self.resolve(file.abs_path.as_path());
+        match self.host()?.open_file_read(path.as_path()) {
+            Ok(file) => Ok(Box::new(file)),
+            Err(err) => Err(Self::map_err(err)),
+        }
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer: flush the written file, if the whole file has been sent
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        match outcome.stream {
+            TransferStream::Upload(mut writer) if outcome.status == TransferStatus::Completed => {
+                writer
+                    .flush()
+                    .map_err(|e| FileTransferError::new(FileTransferErrorType::IoErr(e)))
+            }
+            TransferStream::Upload(_) | TransferStream::Download(_) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::fs::File;
+    use std::io::Write;
+
+    #[test]
+    fn test_filetransfer_local_connect() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: LocalFileTransfer = LocalFileTransfer::new(tmpdir.path());
+        assert!(!client.is_connected());
+        assert!(client.pwd().is_err());
+        assert!(client
+            .connect(String::from("localhost"), 0, None, None)
+            .unwrap()
+            .is_none());
+        assert!(client.is_connected());
+        assert_eq!(client.pwd().unwrap(), tmpdir.path());
+        assert!(client.check_connection().is_ok());
+        assert!(client.disconnect().is_ok());
+        assert!(!client.is_connected());
+        assert!(client.disconnect().is_err());
+        // Bad directory
+        let mut client: LocalFileTransfer = LocalFileTransfer::new(Path::new("/tmp/oifoif/omar"));
+        assert!(client
+            .connect(String::from("localhost"), 0, None, None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_local_change_dir() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let cwd: PathBuf = std::env::current_dir().unwrap();
+        let mut client: LocalFileTransfer = connected(tmpdir.path());
+        assert!(client.mkdir(Path::new("omar")).is_ok());
+        assert!(client.mkdir(Path::new("omar")).is_err());
+        let mut omar: PathBuf = PathBuf::from(tmpdir.path());
+        omar.push("omar");
+        assert_eq!(
+            client.change_dir(Path::new("./omar")).unwrap(),
+            omar.as_path()
+        );
+        assert_eq!(client.change_dir(Path::new("..")).unwrap(), tmpdir.path());
+        assert!(client.change_dir(Path::new("foo")).is_err());
+        assert_eq!(client.pwd().unwrap(), tmpdir.path());
+        // Process working directory is untouched
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+    }
+
+    #[test]
+    fn test_filetransfer_local_send_and_recv() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: LocalFileTransfer = connected(tmpdir.path());
+        let src: FsFile = make_fsfile(tmpdir.path(), "a.txt");
+        // Send
+        let mut writer = client.send_file(&src, Path::new("a.txt")).unwrap();
+        writer.write_all(b"Hello, world!\n").unwrap();
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                14
+            ))
+            .is_ok());
+        // List
+        let files: Vec<FsEntry> = client.list_dir(tmpdir.path()).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].get_name(), "a.txt");
+        assert_eq!(files[0].get_size(), 14);
+        // Recv
+        let mut reader = client.recv_file(&src).unwrap();
+        let mut data: String = String::new();
+        reader.read_to_string(&mut data).unwrap();
+        assert_eq!(data.as_str(), "Hello, world!\n");
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                14
+            ))
+            .is_ok());
+        // Recv file which doesn't exist
+        assert!(client
+            .recv_file(&make_fsfile(tmpdir.path(), "b.txt"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_local_copy_rename_remove() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: LocalFileTransfer = connected(tmpdir.path());
+        let mut path: PathBuf = PathBuf::from(tmpdir.path());
+        path.push("a.txt");
+        let mut file: File = File::create(path.as_path()).unwrap();
+        file.write_all(b"test\n").unwrap();
+        let entry: FsEntry = client.stat(Path::new("a.txt")).unwrap();
+        // Copy
+        assert!(client.copy(&entry, Path::new("b.txt")).is_ok());
+        assert!(client.stat(Path::new("b.txt")).is_ok());
+        // Rename
+        assert!(client.rename(&entry, Path::new("c.txt")).is_ok());
+        assert!(client.stat(Path::new("a.txt")).is_err());
+        // Remove
+        let entry: FsEntry = client.stat(Path::new("c.txt")).unwrap();
+        assert!(client.remove(&entry).is_ok());
+        assert_eq!(client.list_dir(Path::new(".")).unwrap().len(), 1);
+        assert!(client.remove(&entry).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_local_remove_one_file_system() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: LocalFileTransfer = connected(tmpdir.path());
+        assert!(client.mkdir(Path::new("docs")).is_ok());
+        File::create(tmpdir.path().join("a.txt")).unwrap();
+        // Devices are not told apart, so directories are kept
+        assert_eq!(client.device_id(Path::new("docs")).unwrap(), None);
+        let dir: FsEntry = client.stat(Path::new("docs")).unwrap();
+        let err: FileTransferError = client.remove_one_file_system(&dir).err().unwrap();
+        assert!(matches!(
+            err.kind(),
+            FileTransferErrorType::UnsupportedFeature
+        ));
+        assert!(client.stat(Path::new("docs")).is_ok());
+        // Files are removed
+        let file: FsEntry = client.stat(Path::new("a.txt")).unwrap();
+        assert!(client.remove_one_file_system(&file).is_ok());
+        assert!(client.stat(Path::new("a.txt")).is_err());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_filetransfer_local_exec() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: LocalFileTransfer = connected(tmpdir.path());
+        assert_eq!(
+            client.exec("pwd").unwrap().trim(),
+            tmpdir.path().display().to_string()
+        );
+        assert!(client.exec("/tmp/oifoif/omar").is_err());
+    }
+
+    fn connected(wrkdir: &Path) -> LocalFileTransfer {
+        let mut client: LocalFileTransfer = LocalFileTransfer::new(wrkdir);
+        assert!(client
+            .connect(String::from("localhost"), 0, None, None)
+            .is_ok());
+        client
+    }
+
+    fn make_fsfile(dir: &Path, name: &str) -> FsFile {
+        let mut abs_path: PathBuf = PathBuf::from(dir);
+        abs_path.push(name);
+        FsFile {
+            name: name.to_string(),
+            abs_path,
+            last_change_time: std::time::SystemTime::UNIX_EPOCH,
+            last_access_time: std::time::SystemTime::UNIX_EPOCH,
+            creation_time: std::time::SystemTime::UNIX_EPOCH,
+            size: 0,
+            ftype: None,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        }
+    }
+}
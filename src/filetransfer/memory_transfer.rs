@@ -0,0 +1,1018 @@
+//! ## MemoryTransfer
+//!
+//! `memory_transfer` is the module which provides the implementation of a file transfer backed by an in-memory file system.
+//! It is used by tests and by the demo mode, which don't require a remote server
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{FileTransfer, FileTransferError, FileTransferErrorType, TransferOutcome};
+use crate::fs::{FsDirectory, FsEntry, FsFile};
+// Ext
+use std::collections::BTreeMap;
+use std::io::{Cursor, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// ## MemoryNode
+///
+/// A node of the in-memory file system
+#[derive(Clone)]
+struct MemoryNode {
+    data: Option<Arc<Mutex<Vec<u8>>>>, // None for directories
+    mtime: SystemTime,
+    pex: Option<(u8, u8, u8)>, // None for the default permissions
+    owner: Option<(u32, u32)>, // (uid, gid)
+}
+
+/// ## MemoryWriter
+///
+/// Writer returned by `send_file`; it writes directly into the file node
+struct MemoryWriter {
+    data: Arc<Mutex<Vec<u8>>>,
+}
+
+/// ## MemoryFileTransfer
+///
+/// In-memory file transfer structure
+pub struct MemoryFileTransfer {
+    tree: BTreeMap<PathBuf, MemoryNode>,
+    wrkdir: PathBuf,
+    connected: bool,
+    mounts: Vec<PathBuf>, // Directories emulating mount points; the device id is the index + 1
+}
+
+impl Default for MemoryFileTransfer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MemoryFileTransfer {
+    /// ### new
+    ///
+    /// Instantiates a new `MemoryFileTransfer` with an empty file system
+    pub fn new() -> MemoryFileTransfer {
+        let mut tree: BTreeMap<PathBuf, MemoryNode> = BTreeMap::new();
+        tree.insert(
+            PathBuf::from("/"),
+            MemoryNode {
+                data: None,
+                mtime: SystemTime::now(),
+                pex: None,
+                owner: None,
+            },
+        );
+        MemoryFileTransfer {
+            tree,
+            wrkdir: PathBuf::from("/"),
+            connected: false,
+            mounts: Vec::new(),
+        }
+    }
+
+    /// ### demo
+    ///
+    /// Instantiates a new `MemoryFileTransfer` with the file system used by the demo mode
+    pub fn demo() -> MemoryFileTransfer {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::new();
+        client.add_file(
+            Path::new("/home/demo/README.md"),
+            b"# Welcome to termscp\n\nThis is an in-memory file system: nothing you do here leaves your computer.\n",
+        );
+        client.add_file(
+            Path::new("/home/demo/notes.txt"),
+            b"- try to upload a file pressing <SPACE>\n- try to edit this file pressing <O>\n",
+        );
+        client.add_file(Path::new("/home/demo/.profile"), b"export EDITOR=vim\n");
+        client.add_file(
+            Path::new("/home/demo/projects/termscp/Cargo.toml"),
+            b"[package]\nname = \"termscp\"\n",
+        );
+        client.add_file(
+            Path::new("/home/demo/projects/termscp/src/main.rs"),
+            b"fn main() {\n    println!(\"Hello, world!\");\n}\n",
+        );
+        client.add_file(Path::new("/home/demo/photos/mountains.jpg"), &[0xff; 4096]);
+        client.add_file(Path::new("/home/demo/photos/sea.jpg"), &[0xff; 8192]);
+        client.add_dir(Path::new("/home/demo/downloads"));
+        client.add_file(Path::new("/etc/hostname"), b"demo.termscp\n");
+        client.wrkdir = PathBuf::from("/home/demo");
+        client
+    }
+
+    /// ### add_dir
+    ///
+    /// Add directory to the file system; parent directories are created if they don't exist
+    pub fn add_dir(&mut self, path: &Path) {
+        let path: PathBuf = self.resolve(path);
+        for ancestor in path.ancestors() {
+            self.tree
+                .entry(ancestor.to_path_buf())
+                .or_insert(MemoryNode {
+                    data: None,
+                    mtime: SystemTime::now(),
+                    pex: None,
+                    owner: None,
+                });
+        }
+    }
+
+    /// ### add_file
+    ///
+    /// Add file with `data` to the file system; parent directories are created if they don't exist
+    pub fn add_file(&mut self, path: &Path, data: &[u8]) {
+        let path: PathBuf = self.resolve(path);
+        if let Some(parent) = path.parent() {
+            self.add_dir(parent);
+        }
+        self.tree.insert(
+            path,
+            MemoryNode {
+                data: Some(Arc::new(Mutex::new(data.to_vec()))),
+                mtime: SystemTime::now(),
+                pex: None,
+                owner: None,
+            },
+        );
+    }
+
+    /// ### add_mount
+    ///
+    /// Add directory to the file system as a mount point: it and its descendants get a different device id
+    #[cfg(test)]
+    pub fn add_mount(&mut self, path: &Path) {
+        self.add_dir(path);
+        let path: PathBuf = self.resolve(path);
+        self.mounts.push(path);
+    }
+
+    /// ### resolve
+    ///
+    /// Make absolute, normalized path from `p`, which can be relative to the working directory
+    fn resolve(&self, p: &Path) -> PathBuf {
+        let mut path: PathBuf = PathBuf::from("/");
+        let joined: PathBuf = match p.is_absolute() {
+            true => PathBuf::from(p),
+            false => self.wrkdir.join(p),
+        };
+        for component in joined.components() {
+            match component {
+                Component::ParentDir => {
+                    path.pop();
+                }
+                Component::Normal(name) => path.push(name),
+                _ => {}
+            }
+        }
+        path
+    }
+
+    /// ### subtree
+    ///
+    /// Get `path` and all of its descendants
+    fn subtree(&self, path: &Path) -> Vec<PathBuf> {
+        self.tree
+            .keys()
+            .filter(|x| x.starts_with(path))
+            .cloned()
+            .collect()
+    }
+
+    /// ### make_fsentry
+    ///
+    /// Make `FsEntry` from node at `path`
+    fn make_fsentry(path: &Path, node: &MemoryNode) -> FsEntry {
+        let name: String = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => String::from("/"),
+        };
+        match &node.data {
+            None => FsEntry::Directory(FsDirectory {
+                name,
+                abs_path: PathBuf::from(path),
+                last_change_time: node.mtime,
+                last_access_time: node.mtime,
+                creation_time: node.mtime,
+                readonly: false,
+                symlink: None,
+                user: node.owner.map(|(uid, _)| uid),
+                group: node.owner.map(|(_, gid)| gid),
+                unix_pex: Some(node.pex.unwrap_or((7, 5, 5))),
+            }),
+            Some(data) => FsEntry::File(FsFile {
+                ftype: path
+                    .extension()
+                    .map(|x| String::from(x.to_str().unwrap_or(""))),
+                name,
+                abs_path: PathBuf::from(path),
+                last_change_time: node.mtime,
+                last_access_time: node.mtime,
+                creation_time: node.mtime,
+                size: data.lock().map(|x| x.len()).unwrap_or(0),
+                readonly: false,
+                symlink: None,
+                broken_link: None,
+                user: node.owner.map(|(uid, _)| uid),
+                group: node.owner.map(|(_, gid)| gid),
+                unix_pex: Some(node.pex.unwrap_or((6, 4, 4))),
+            }),
+        }
+    }
+
+    /// ### check_connected
+    ///
+    /// Returns error if client is not connected
+    fn check_connected(&self) -> Result<(), FileTransferError> {
+        match self.connected {
+            true => Ok(()),
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### check_parent
+    ///
+    /// Returns error if the parent directory of `path` doesn't exist
+    fn check_parent(&self, path: &Path) -> Result<(), FileTransferError> {
+        match path.parent().and_then(|x| self.tree.get(x)) {
+            Some(node) if node.data.is_none() => Ok(()),
+            _ => Err(FileTransferError::new_ex(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+                format!("Parent directory of \"{}\" doesn't exist", path.display()),
+            )),
+        }
+    }
+
+    /// ### copy_subtree
+    ///
+    /// Copy (or move if `keep` is false) the subtree at `src` to `dst`
+    fn copy_subtree(&mut self, src: &Path, dst: &Path, keep: bool) {
+        for path in self.subtree(src).into_iter() {
+            let node: MemoryNode = match keep {
+                true => {
+                    let mut node: MemoryNode = self.tree.get(&path).cloned().unwrap();
+                    // Detach data from source
+                    if let Some(data) = node.data.as_ref() {
+                        let data: Vec<u8> = data.lock().map(|x| x.clone()).unwrap_or_default();
+                        node.data = Some(Arc::new(Mutex::new(data)));
+                    }
+                    node
+                }
+                false => self.tree.remove(&path).unwrap(),
+            };
+            let target: PathBuf = match path.strip_prefix(src) {
+                Ok(rel) if rel.as_os_str().is_empty() => PathBuf::from(dst),
+                Ok(rel) => dst.join(rel),
+                Err(_) => continue,
+            };
+            self.tree.insert(target, node);
+        }
+    }
+}
+
+impl FileTransfer for MemoryFileTransfer {
+    /// ### connect
+    ///
+    /// Connect to the remote server
+    fn connect(
+        &mut self,
+        _address: String,
+        _port: u16,
+        _username: Option<String>,
+        _password: Option<String>,
+    ) -> Result<Option<String>, FileTransferError> {
+        self.connected = true;
+        Ok(Some(String::from("termscp in-memory file system")))
+    }
+
+    /// ### disconnect
+    ///
+    /// Disconnect from the remote server
+    fn disconnect(&mut self) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        self.connected = false;
+        Ok(())
+    }
+
+    /// ### is_connected
+    ///
+    /// Indicates whether the client is connected to remote
+    fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// ### pwd
+    ///
+    /// Print working directory
+    fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
+        self.check_connected()?;
+        Ok(self.wrkdir.clone())
+    }
+
+    /// ### change_dir
+    ///
+    /// Change working directory
+    fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
+        self.check_connected()?;
+        let dir: PathBuf = self.resolve(dir);
+        match self.tree.get(&dir) {
+            Some(node) if node.data.is_none() => {
+                self.wrkdir = dir;
+                Ok(self.wrkdir.clone())
+            }
+            _ => Err(FileTransferError::new_ex(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+                format!("\"{}\" is not a directory", dir.display()),
+            )),
+        }
+    }
+
+    /// ### copy
+    ///
+    /// Copy file to destination
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let src: PathBuf = self.resolve(src.get_abs_path().as_path());
+        let dst: PathBuf = self.resolve(dst);
+        if !self.tree.contains_key(&src) {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            ));
+        }
+        self.check_parent(dst.as_path())?;
+        self.copy_subtree(src.as_path(), dst.as_path(), true);
+        Ok(())
+    }
+
+    /// ### list_dir
+    ///
+    /// List directory entries
+    fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(path);
+        match self.tree.get(&path) {
+            Some(node) if node.data.is_none() => Ok(self
+                .tree
+                .iter()
+                .filter(|(p, _)| p.parent() == Some(path.as_path()))
+                .map(|(p, node)| Self::make_fsentry(p.as_path(), node))
+                .collect()),
+            _ => Err(FileTransferError::new_ex(
+                FileTransferErrorType::DirStatFailed,
+                format!("\"{}\" is not a directory", path.display()),
+            )),
+        }
+    }
+
+    /// ### mkdir
+    ///
+    /// Make directory
+    /// You must return error in case the directory already exists
+    fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let dir: PathBuf = self.resolve(dir);
+        if self.tree.contains_key(&dir) {
+            return Err(FileTransferError::new_ex(
+                FileTransferErrorType::FileCreateDenied,
+                format!("\"{}\" already exists", dir.display()),
+            ));
+        }
+        self.check_parent(dir.as_path())?;
+        self.add_dir(dir.as_path());
+        Ok(())
+    }
+
+    /// ### remove
+    ///
+    /// Remove a file or a directory
+    fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(file.get_abs_path().as_path());
+        if !self.tree.contains_key(&path) || path == Path::new("/") {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            ));
+        }
+        for path in self.subtree(path.as_path()).iter() {
+            self.tree.remove(path);
+        }
+        Ok(())
+    }
+
+    /// ### device_id
+    ///
+    /// Returns the id of the device of the innermost mount point containing `path`; 0 if none
+    fn device_id(&mut self, path: &Path) -> Result<Option<u64>, FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(path);
+        if !self.tree.contains_key(&path) {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            ));
+        }
+        Ok(Some(
+            self.mounts
+                .iter()
+                .enumerate()
+                .filter(|(_, mount)| path.starts_with(mount))
+                .max_by_key(|(_, mount)| mount.components().count())
+                .map(|(idx, _)| idx as u64 + 1)
+                .unwrap_or(0),
+        ))
+    }
+
+    /// ### rename
+    ///
+    /// Rename file or a directory
+    fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let src: PathBuf = self.resolve(file.get_abs_path().as_path());
+        let dst: PathBuf = self.resolve(dst);
+        if !self.tree.contains_key(&src) {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            ));
+        }
+        self.check_parent(dst.as_path())?;
+        self.copy_subtree(src.as_path(), dst.as_path(), false);
+        Ok(())
+    }
+
+    /// ### stat
+    ///
+    /// Stat file and return FsEntry
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(path);
+        match self.tree.get(&path) {
+            Some(node) => Ok(Self::make_fsentry(path.as_path(), node)),
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### chmod
+    ///
+    /// Change the UNIX permissions of `path` to `mode`
+    fn chmod(&mut self, path: &Path, mode: (u8, u8, u8)) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(path);
+        match self.tree.get_mut(&path) {
+            Some(node) => {
+                node.pex = Some(mode);
+                Ok(())
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### chown
+    ///
+    /// Change the owner of `path` to user `uid` and group `gid`
+    fn chown(&mut self, path: &Path, uid: u32, gid: u32) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(path);
+        match self.tree.get_mut(&path) {
+            Some(node) => {
+                node.owner = Some((uid, gid));
+                Ok(())
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### set_mtime
+    ///
+    /// Set the modification time of `path` to `mtime`
+    fn set_mtime(&mut self, path: &Path, mtime: SystemTime) -> Result<(), FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(path);
+        match self.tree.get_mut(&path) {
+            Some(node) => {
+                node.mtime = mtime;
+                Ok(())
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### exec
+    ///
+    /// Execute a command on remote host
+    fn exec(&mut self, _cmd: &str) -> Result<String, FileTransferError> {
+        self.check_connected()?;
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### send_file
+    ///
+    /// Send file to remote
+    /// File name is referred to the name of the file as it will be saved
+    /// Data contains the file data
+    fn send_file(
+        &mut self,
+        _local: &FsFile,
+        file_name: &Path,
+    ) -> Result<Box<dyn Write>, FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(file_name);
+        self.check_parent(path.as_path())?;
+        if let Some(node) = self.tree.get(&path) {
+            if node.data.is_none() {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::FileCreateDenied,
+                    format!("\"{}\" is a directory", path.display()),
+                ));
+            }
+        }
+        let data: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
+        self.tree.insert(
+            path,
+            MemoryNode {
+                data: Some(data.clone()),
+                mtime: SystemTime::now(),
+                pex: None,
+                owner: None,
+            },
+        );
+        Ok(Box::new(MemoryWriter { data }))
+    }
+
+    /// ### recv_file
+    ///
+    /// Receive file from remote with provided name
+    fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(file.abs_path.as_path());
+        match self.tree.get(&path).and_then(|x| x.data.as_ref()) {
+            Some(data) => {
+                let data: Vec<u8> = data.lock().map(|x| x.clone()).unwrap_or_default();
+                Ok(Box::new(Cursor::new(data)))
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### recv_file_at
+    ///
+    /// Read file from the file system starting from byte `offset`
+    fn recv_file_at(
+        &mut self,
+        file: &FsFile,
+        offset: u64,
+    ) -> Result<Box<dyn Read>, FileTransferError> {
+        self.check_connected()?;
+        let path: PathBuf = self.resolve(file.abs_path.as_path());
+        match self.tree.get(&path).and_then(|x| x.data.as_ref()) {
+            Some(data) => {
+                let data: Vec<u8> = data.lock().map(|x| x.clone()).unwrap_or_default();
+                let mut cursor: Cursor<Vec<u8>> = Cursor::new(data);
+                cursor.set_position(offset);
+                Ok(Box::new(cursor))
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer. Data is already written into the file system, so there's nothing to do
+    fn finalize_transfer(&mut self, _outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        Ok(())
+    }
+}
+
+impl Write for MemoryWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self.data.lock() {
+            Ok(mut data) => {
+                data.extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            Err(_) => Err(std::io::Error::other("file is poisoned")),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::filetransfer::TransferStream;
+
+    #[test]
+    fn test_filetransfer_memory_new() {
+        let client: MemoryFileTransfer = MemoryFileTransfer::new();
+        assert_eq!(client.tree.len(), 1);
+        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_connect() {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::demo();
+        assert!(client.pwd().is_err());
+        assert!(client
+            .connect(String::from("demo"), 22, None, None)
+            .ok()
+            .unwrap()
+            .is_some());
+        assert!(client.is_connected());
+        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/home/demo"));
+        assert!(client.disconnect().is_ok());
+        assert!(!client.is_connected());
+        assert!(client.disconnect().is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_check_connection() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert!(client.change_dir(Path::new("projects")).is_ok());
+        assert!(client.check_connection().is_ok());
+        // Working directory is unchanged
+        assert_eq!(
+            client.pwd().ok().unwrap(),
+            PathBuf::from("/home/demo/projects")
+        );
+        assert!(client.disconnect().is_ok());
+        assert!(client.check_connection().is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_change_dir() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert_eq!(
+            client.change_dir(Path::new("projects")).ok().unwrap(),
+            PathBuf::from("/home/demo/projects")
+        );
+        assert_eq!(
+            client.change_dir(Path::new("../..")).ok().unwrap(),
+            PathBuf::from("/home")
+        );
+        assert_eq!(
+            client.change_dir(Path::new("/etc")).ok().unwrap(),
+            PathBuf::from("/etc")
+        );
+        // Errors
+        assert!(client.change_dir(Path::new("/etc/hostname")).is_err());
+        assert!(client.change_dir(Path::new("/omar")).is_err());
+        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/etc"));
+    }
+
+    #[test]
+    fn test_filetransfer_memory_list_dir() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        let entries: Vec<FsEntry> = client.list_dir(Path::new(".")).ok().unwrap();
+        let names: Vec<&str> = entries.iter().map(|x| x.get_name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                ".profile",
+                "README.md",
+                "downloads",
+                "notes.txt",
+                "photos",
+                "projects"
+            ]
+        );
+        let entries: Vec<FsEntry> = client
+            .list_dir(Path::new("/home/demo/photos"))
+            .ok()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries.first().unwrap().get_size(), 4096);
+        assert!(client.list_dir(Path::new("/home/demo/README.md")).is_err());
+        assert!(client.list_dir(Path::new("/omar")).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_mkdir() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert!(client.mkdir(Path::new("music")).is_ok());
+        assert!(client
+            .stat(Path::new("/home/demo/music"))
+            .ok()
+            .unwrap()
+            .is_dir());
+        // Already exists
+        assert!(client.mkdir(Path::new("music")).is_err());
+        // Parent doesn't exist
+        assert!(client.mkdir(Path::new("/omar/music")).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_mkdir_recursive() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert!(client
+            .mkdir_recursive(Path::new("/srv/backups/2021"))
+            .is_ok());
+        assert!(client
+            .stat(Path::new("/srv/backups"))
+            .ok()
+            .unwrap()
+            .is_dir());
+        assert!(client
+            .stat(Path::new("/srv/backups/2021"))
+            .ok()
+            .unwrap()
+            .is_dir());
+        // Relative path; existing directories are kept
+        assert!(client.mkdir_recursive(Path::new("photos/2021")).is_ok());
+        assert!(client
+            .stat(Path::new("/home/demo/photos/2021"))
+            .ok()
+            .unwrap()
+            .is_dir());
+        assert_eq!(client.list_dir(Path::new("photos")).ok().unwrap().len(), 3);
+        assert!(client.mkdir_recursive(Path::new("/srv/backups")).is_ok());
+        // A parent is a file
+        assert!(client
+            .mkdir_recursive(Path::new("README.md/music"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_send_and_recv() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        let local: FsFile = make_fsfile("/tmp/omar.txt", 12);
+        let mut writer: Box<dyn Write> = client
+            .send_file(&local, Path::new("downloads/omar.txt"))
+            .ok()
+            .unwrap();
+        assert!(writer.write_all(b"hello world!").is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_ok());
+        // Stat
+        let remote: FsEntry = client
+            .stat(Path::new("/home/demo/downloads/omar.txt"))
+            .ok()
+            .unwrap();
+        assert_eq!(remote.get_size(), 12);
+        // Receive
+        let remote: FsFile = match remote {
+            FsEntry::File(f) => f,
+            FsEntry::Directory(_) => panic!("Expected file"),
+        };
+        let mut reader: Box<dyn Read> = client.recv_file(&remote).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                12
+            ))
+            .is_ok());
+        assert_eq!(content.as_str(), "hello world!");
+        // Receive from offset
+        let mut reader: Box<dyn Read> = client.recv_file_at(&remote, 6).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert_eq!(content.as_str(), "world!");
+        // Errors
+        assert!(client
+            .send_file(&local, Path::new("/omar/omar.txt"))
+            .is_err());
+        assert!(client.send_file(&local, Path::new("downloads")).is_err());
+        assert!(client
+            .recv_file(&make_fsfile("/home/demo/omar.txt", 0))
+            .is_err());
+        assert!(client
+            .recv_file_at(&make_fsfile("/home/demo/omar.txt", 0), 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_copy() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        let src: FsEntry = client.stat(Path::new("projects")).ok().unwrap();
+        assert!(client.copy(&src, Path::new("/home/demo/backup")).is_ok());
+        assert!(client
+            .stat(Path::new("/home/demo/backup/termscp/src/main.rs"))
+            .is_ok());
+        assert!(client
+            .stat(Path::new("/home/demo/projects/termscp/src/main.rs"))
+            .is_ok());
+        // Copy is detached from source
+        let mut writer: Box<dyn Write> = client
+            .send_file(
+                &make_fsfile("/tmp/Cargo.toml", 0),
+                Path::new("/home/demo/backup/termscp/Cargo.toml"),
+            )
+            .ok()
+            .unwrap();
+        assert!(writer.write_all(b"empty").is_ok());
+        assert_eq!(
+            client
+                .stat(Path::new("/home/demo/projects/termscp/Cargo.toml"))
+                .ok()
+                .unwrap()
+                .get_size(),
+            27
+        );
+        // Error
+        assert!(client.copy(&src, Path::new("/omar/backup")).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_rename() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        let src: FsEntry = client.stat(Path::new("photos")).ok().unwrap();
+        assert!(client.rename(&src, Path::new("pictures")).is_ok());
+        assert!(client.stat(Path::new("photos")).is_err());
+        assert!(client.stat(Path::new("pictures/sea.jpg")).is_ok());
+        assert!(client.rename(&src, Path::new("pictures")).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_remove() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        let dir: FsEntry = client.stat(Path::new("projects")).ok().unwrap();
+        assert!(client.remove(&dir).is_ok());
+        assert!(client.stat(Path::new("projects")).is_err());
+        assert!(client
+            .stat(Path::new("/home/demo/projects/termscp/src/main.rs"))
+            .is_err());
+        assert!(client.remove(&dir).is_err());
+        // Can't remove root
+        let root: FsEntry = client.stat(Path::new("/")).ok().unwrap();
+        assert!(client.remove(&root).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_remove_one_file_system() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        client.add_mount(Path::new("/home/demo/projects/share"));
+        client.add_file(Path::new("/home/demo/projects/share/data.bin"), &[0; 64]);
+        assert_eq!(
+            client.device_id(Path::new("projects")).ok().unwrap(),
+            Some(0)
+        );
+        assert_eq!(
+            client
+                .device_id(Path::new("projects/share/data.bin"))
+                .ok()
+                .unwrap(),
+            Some(1)
+        );
+        let dir: FsEntry = client.stat(Path::new("projects")).ok().unwrap();
+        let err: FileTransferError = client.remove_one_file_system(&dir).err().unwrap();
+        assert!(matches!(
+            err.kind(),
+            FileTransferErrorType::FileSystemBoundary
+        ));
+        assert_eq!(
+            err.to_string(),
+            "Skipped directories on another file system (\"/home/demo/projects/share\")"
+        );
+        // Mount point and its parents are kept
+        assert!(client
+            .stat(Path::new("/home/demo/projects/share/data.bin"))
+            .is_ok());
+        assert!(client
+            .stat(Path::new("/home/demo/projects/termscp"))
+            .is_err());
+        // Without mount points it's the same as remove
+        let dir: FsEntry = client.stat(Path::new("photos")).ok().unwrap();
+        assert!(client.remove_one_file_system(&dir).is_ok());
+        assert!(client.stat(Path::new("photos")).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_find() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        let found: Vec<FsEntry> = client.find("*.jpg").ok().unwrap();
+        assert_eq!(found.len(), 2);
+        assert_eq!(
+            found.first().unwrap().get_abs_path(),
+            PathBuf::from("/home/demo/photos/mountains.jpg")
+        );
+        // Find in directory
+        let found: Vec<FsEntry> = client
+            .find_in(Path::new("/home/demo/photos"), "*")
+            .ok()
+            .unwrap();
+        assert!(found
+            .iter()
+            .all(|x| x.get_abs_path().starts_with("/home/demo/photos")));
+        assert!(client.find_in(Path::new("/omar"), "*").is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_chmod_and_chown() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert!(client.chmod(Path::new("notes.txt"), (6, 0, 0)).is_ok());
+        assert!(client.chown(Path::new("notes.txt"), 1000, 100).is_ok());
+        let file: FsEntry = client.stat(Path::new("notes.txt")).ok().unwrap();
+        assert_eq!(file.get_unix_pex(), Some((6, 0, 0)));
+        assert_eq!(file.get_user(), Some(1000));
+        assert_eq!(file.get_group(), Some(100));
+        // Default permissions are kept for the other files
+        let file: FsEntry = client.stat(Path::new("README.md")).ok().unwrap();
+        assert_eq!(file.get_unix_pex(), Some((6, 4, 4)));
+        // Error
+        assert!(client.chmod(Path::new("omar.txt"), (6, 4, 4)).is_err());
+        assert!(client.chown(Path::new("omar.txt"), 0, 0).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_set_mtime() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert!(client
+            .set_mtime(Path::new("notes.txt"), SystemTime::UNIX_EPOCH)
+            .is_ok());
+        let file: FsEntry = client.stat(Path::new("notes.txt")).ok().unwrap();
+        assert_eq!(file.get_last_change_time(), SystemTime::UNIX_EPOCH);
+        assert!(client
+            .set_mtime(Path::new("omar.txt"), SystemTime::UNIX_EPOCH)
+            .is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_exec() {
+        let mut client: MemoryFileTransfer = connected_demo();
+        assert!(client.exec("ls").is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_memory_uninitialized() {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::demo();
+        assert!(client.change_dir(Path::new("/")).is_err());
+        assert!(client.list_dir(Path::new("/")).is_err());
+        assert!(client.mkdir(Path::new("/omar")).is_err());
+        assert!(client.stat(Path::new("/")).is_err());
+        assert!(client
+            .send_file(&make_fsfile("/omar.txt", 0), Path::new("/omar.txt"))
+            .is_err());
+        assert!(client.recv_file(&make_fsfile("/etc/hostname", 0)).is_err());
+    }
+
+    fn connected_demo() -> MemoryFileTransfer {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::demo();
+        assert!(client.connect(String::from("demo"), 22, None, None).is_ok());
+        client
+    }
+
+    fn make_fsfile(path: &str, size: usize) -> FsFile {
+        let path: PathBuf = PathBuf::from(path);
+        FsFile {
+            name: path.file_name().unwrap().to_string_lossy().to_string(),
+            abs_path: path,
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            size,
+            ftype: None,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        }
+    }
+}
@@ -28,15 +28,137 @@
 // dependencies
 extern crate wildmatch;
 // locals
-use crate::fs::{FsEntry, FsFile};
+use crate::fs::space::DiskSpace;
+use crate::fs::{FsDirectory, FsEntry, FsFile};
+use crate::system::known_hosts::KnownHost;
+use crate::utils::shell::quote_path;
+use keyboard_interactive::AuthPrompt;
+use serde::{Deserialize, Serialize};
 // ext
 use std::io::{Read, Write};
+use std::net::{SocketAddr, ToSocketAddrs};
 use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
 use wildmatch::WildMatch;
 // exports
+pub mod builder;
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 pub mod ftp_transfer;
+pub mod keyboard_interactive;
+pub mod kube_transfer;
+pub mod local_transfer;
+pub mod memory_transfer;
+pub mod pool;
+pub mod registry;
+pub mod s3_transfer;
 pub mod scp_transfer;
 pub mod sftp_transfer;
+pub mod smb_transfer;
+mod upload;
+pub mod webdav_transfer;
+
+/// ## NetworkOptions
+///
+/// Network options shared by the file transfer clients which support them
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkOptions {
+    pub connection_timeout: Duration,
+    pub keepalive_interval: Option<Duration>, // Disabled if `None`
+    pub proxy: Option<String>,                // HTTP proxy URL; used by HTTP based protocols
+    pub address_family: AddressFamily,        // Addresses tried first when a host has both
+}
+
+/// ## AddressFamily
+///
+/// Address family tried first, when a host name resolves to both IPv4 and IPv6 addresses
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AddressFamily {
+    Any, // Keep the order returned by the resolver
+    Ipv4,
+    Ipv6,
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        NetworkOptions {
+            connection_timeout: Duration::from_secs(30),
+            keepalive_interval: None,
+            proxy: None,
+            address_family: AddressFamily::Any,
+        }
+    }
+}
+
+impl NetworkOptions {
+    /// ### resolve
+    ///
+    /// Resolve `address` (a host name or an IP address; IPv6 addresses may be enclosed in brackets) and `port`
+    /// into the socket addresses to try, sorted by the preferred address family
+    pub(crate) fn resolve(
+        &self,
+        address: &str,
+        port: u16,
+    ) -> Result<Vec<SocketAddr>, FileTransferError> {
+        let host: &str = address
+            .strip_prefix('[')
+            .and_then(|x| x.strip_suffix(']'))
+            .unwrap_or(address);
+        let mut addresses: Vec<SocketAddr> = match (host, port).to_socket_addrs() {
+            Ok(addresses) => addresses.collect(),
+            Err(err) => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::BadAddress,
+                    format!("{}", err),
+                ))
+            }
+        };
+        match self.address_family {
+            AddressFamily::Any => {}
+            AddressFamily::Ipv4 => addresses.sort_by_key(|x| x.is_ipv6()),
+            AddressFamily::Ipv6 => addresses.sort_by_key(|x| x.is_ipv4()),
+        }
+        Ok(addresses)
+    }
+
+    /// ### http_agent
+    ///
+    /// Make an HTTP agent builder configured with timeout and proxy
+    pub(crate) fn http_agent(&self) -> Result<ureq::AgentBuilder, FileTransferError> {
+        let builder = ureq::AgentBuilder::new().timeout_connect(self.connection_timeout);
+        match self.proxy.as_ref() {
+            None => Ok(builder),
+            Some(proxy) => match ureq::Proxy::new(proxy) {
+                Ok(proxy) => Ok(builder.proxy(proxy)),
+                Err(err) => Err(FileTransferError::new_ex(
+                    FileTransferErrorType::BadAddress,
+                    format!("Invalid proxy: {}", err),
+                )),
+            },
+        }
+    }
+}
+
+/// ## SshAlgorithms
+///
+/// Preferred SSH algorithms, as comma separated lists in order of preference (e.g. `aes256-ctr,aes128-cbc`).
+/// They are applied before the handshake by SSH based protocols; if `None`, the default algorithms are offered
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct SshAlgorithms {
+    pub kex: Option<String>,
+    pub host_key: Option<String>,
+    pub cipher: Option<String>,
+    pub mac: Option<String>,
+}
+
+impl SshAlgorithms {
+    /// ### is_empty
+    ///
+    /// Returns whether no preference is set
+    pub fn is_empty(&self) -> bool {
+        self.kex.is_none() && self.host_key.is_none() && self.cipher.is_none() && self.mac.is_none()
+    }
+}
 
 /// ## FileTransferProtocol
 ///
@@ -46,7 +168,53 @@ pub mod sftp_transfer;
 pub enum FileTransferProtocol {
     Sftp,
     Scp,
-    Ftp(bool), // Bool is for secure (true => ftps)
+    Ftp(bool),    // Bool is for secure (true => ftps)
+    WebDav(bool), // Bool is for secure (true => https)
+    Smb,
+    Kube,
+    S3,
+    Plugin(&'static str), // Protocol registered by a plugin; scheme
+}
+
+impl FileTransferProtocol {
+    /// ### default_port
+    ///
+    /// Get the default port of the protocol; 0 if the protocol doesn't use it
+    pub fn default_port(&self) -> u16 {
+        match self {
+            FileTransferProtocol::Ftp(_) => 21,
+            FileTransferProtocol::Scp | FileTransferProtocol::Sftp => 22,
+            FileTransferProtocol::WebDav(false) => 80,
+            FileTransferProtocol::WebDav(true) => 443,
+            FileTransferProtocol::Smb => 445,
+            FileTransferProtocol::Kube => 0, // Port is not used
+            FileTransferProtocol::S3 => 0,   // Port is part of the endpoint
+            FileTransferProtocol::Plugin(scheme) => registry::default_port(scheme),
+        }
+    }
+
+    /// ### available
+    ///
+    /// Get all the available protocols: builtin protocols first, then the ones registered by plugins
+    pub fn available() -> Vec<FileTransferProtocol> {
+        let mut protocols: Vec<FileTransferProtocol> = vec![
+            FileTransferProtocol::Sftp,
+            FileTransferProtocol::Scp,
+            FileTransferProtocol::Ftp(false),
+            FileTransferProtocol::Ftp(true),
+            FileTransferProtocol::WebDav(false),
+            FileTransferProtocol::WebDav(true),
+            FileTransferProtocol::Smb,
+            FileTransferProtocol::Kube,
+            FileTransferProtocol::S3,
+        ];
+        protocols.extend(
+            registry::registered_protocols()
+                .into_iter()
+                .map(FileTransferProtocol::Plugin),
+        );
+        protocols
+    }
 }
 
 /// ## FileTransferError
@@ -70,12 +238,15 @@ pub enum FileTransferErrorType {
     SslError,
     DirStatFailed,
     FileCreateDenied,
+    FileSystemBoundary,
     IoErr(std::io::Error),
+    KeyboardInteractive(Vec<AuthPrompt>),
     NoSuchFileOrDirectory,
     PexError,
     ProtocolError,
     UninitializedSession,
     UnsupportedFeature,
+    UnverifiedHostKey(KnownHost), // Host key or certificate must be confirmed by the user
 }
 
 impl FileTransferError {
@@ -94,6 +265,13 @@ impl FileTransferError {
         err.msg = Some(msg);
         err
     }
+
+    /// ### kind
+    ///
+    /// Returns the error type
+    pub fn kind(&self) -> &FileTransferErrorType {
+        &self.code
+    }
 }
 
 impl std::fmt::Display for FileTransferError {
@@ -104,7 +282,13 @@ impl std::fmt::Display for FileTransferError {
             FileTransferErrorType::ConnectionError => String::from("Connection error"),
             FileTransferErrorType::DirStatFailed => String::from("Could not stat directory"),
             FileTransferErrorType::FileCreateDenied => String::from("Failed to create file"),
+            FileTransferErrorType::FileSystemBoundary => {
+                String::from("Skipped directories on another file system")
+            }
             FileTransferErrorType::IoErr(err) => format!("IO error: {}", err),
+            FileTransferErrorType::KeyboardInteractive(_) => {
+                String::from("Keyboard-interactive authentication required")
+            }
             FileTransferErrorType::NoSuchFileOrDirectory => {
                 String::from("No such file or directory")
             }
@@ -113,6 +297,12 @@ impl std::fmt::Display for FileTransferError {
             FileTransferErrorType::SslError => String::from("SSL error"),
             FileTransferErrorType::UninitializedSession => String::from("Uninitialized session"),
             FileTransferErrorType::UnsupportedFeature => String::from("Unsupported feature"),
+            FileTransferErrorType::UnverifiedHostKey(key) => format!(
+                "Host key for {} is not trusted yet: {} {}",
+                key.host,
+                key.key_type,
+                key.fingerprint()
+            ),
         };
         match &self.msg {
             Some(msg) => write!(f, "{} ({})", err, msg),
@@ -121,6 +311,125 @@ impl std::fmt::Display for FileTransferError {
     }
 }
 
+/// ## ServerIdentity
+///
+/// Describes the remote operating system and the server software, as identified on connect
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ServerIdentity {
+    pub software: Option<String>, // Server software (e.g. `OpenSSH_8.4p1 Debian-5`)
+    pub system: Option<String>,   // Remote OS (e.g. output of `uname -a` or reply to `SYST`)
+}
+
+impl ServerIdentity {
+    /// ### new
+    ///
+    /// Instantiates a new ServerIdentity from the server banner (SSH identification string or FTP
+    /// welcome message) and the system description. The protocol version and the reply code are stripped
+    pub fn new(banner: Option<&str>, system: Option<&str>) -> Self {
+        let software: Option<&str> = banner
+            .and_then(|x| x.lines().next())
+            .map(|x| match x.trim().strip_prefix("SSH-") {
+                // Strip protocol version (e.g. `SSH-2.0-`)
+                Some(x) => x.split_once('-').map(|x| x.1).unwrap_or(x),
+                None => x.trim(),
+            })
+            .map(|x| match x.get(0..4) {
+                // Strip FTP reply code (e.g. `220 `)
+                Some(code) if code.chars().take(3).all(|c| c.is_ascii_digit()) => x[4..].trim(),
+                _ => x,
+            });
+        ServerIdentity {
+            software: software.filter(|x| !x.is_empty()).map(String::from),
+            system: system
+                .map(|x| x.trim())
+                .filter(|x| !x.is_empty())
+                .map(String::from),
+        }
+    }
+
+    /// ### is_windows
+    ///
+    /// Returns whether the remote system is Windows; Windows servers list directories with the DOS syntax
+    pub fn is_windows(&self) -> bool {
+        [self.system.as_ref(), self.software.as_ref()]
+            .iter()
+            .flatten()
+            .any(|x| x.to_lowercase().contains("windows"))
+    }
+
+    /// ### summary
+    ///
+    /// Returns a short description of the server (e.g. `Linux, OpenSSH_8.4p1 Debian-5`)
+    pub fn summary(&self) -> Option<String> {
+        let os: Option<&str> = match self.is_windows() {
+            true => Some("Windows"),
+            false => self
+                .system
+                .as_deref()
+                .and_then(|x| x.split_whitespace().next()),
+        };
+        match (os, self.software.as_deref()) {
+            (Some(os), Some(software)) => Some(format!("{}, {}", os, software)),
+            (Some(x), None) | (None, Some(x)) => Some(x.to_string()),
+            (None, None) => None,
+        }
+    }
+}
+
+/// ## TransferStream
+///
+/// Stream returned by `send_file` (upload) or `recv_file` (download), given back to the client to finalize the transfer
+pub enum TransferStream {
+    Upload(Box<dyn Write>),
+    Download(Box<dyn Read>),
+}
+
+/// ## TransferStatus
+///
+/// Describes how the data transfer on a stream has ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum TransferStatus {
+    Completed,
+    Aborted,        // Interrupted by the user
+    Failed(String), // Interrupted by an error while reading or writing the stream
+}
+
+/// ## TransferOutcome
+///
+/// Outcome of a data transfer, passed to `finalize_transfer`
+pub struct TransferOutcome {
+    pub stream: TransferStream,
+    pub bytes: u64, // Bytes written to (or read from) the stream
+    pub status: TransferStatus,
+}
+
+impl TransferOutcome {
+    /// ### new
+    ///
+    /// Instantiates a new `TransferOutcome`
+    pub fn new(stream: TransferStream, bytes: u64, status: TransferStatus) -> Self {
+        TransferOutcome {
+            stream,
+            bytes,
+            status,
+        }
+    }
+
+    /// ### completed
+    ///
+    /// Instantiates the outcome of a transfer which has been completed
+    pub fn completed(stream: TransferStream, bytes: u64) -> Self {
+        Self::new(stream, bytes, TransferStatus::Completed)
+    }
+
+    /// ### is_completed
+    ///
+    /// Returns whether all the data has been transferred
+    pub fn is_completed(&self) -> bool {
+        self.status == TransferStatus::Completed
+    }
+}
+
 /// ## FileTransfer
 ///
 /// File transfer trait must be implemented by all the file transfers and defines the method used by a generic file transfer
@@ -179,6 +488,27 @@ pub trait FileTransfer {
     /// You must return error in case the directory already exists
     fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError>;
 
+    /// ### mkdir_recursive
+    ///
+    /// Make directory `dir` and its missing parents, as `mkdir -p` does.
+    /// Directories which exist already are kept; fails if `dir` or one of its parents is a file
+    fn mkdir_recursive(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        match self.stat(dir) {
+            Ok(FsEntry::Directory(_)) => return Ok(()),
+            Ok(FsEntry::File(_)) => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::FileCreateDenied,
+                    format!("\"{}\" is not a directory", dir.display()),
+                ))
+            }
+            Err(_) => {}
+        }
+        if let Some(parent) = dir.parent().filter(|x| !x.as_os_str().is_empty()) {
+            self.mkdir_recursive(parent)?;
+        }
+        self.mkdir(dir)
+    }
+
     /// ### remove
     ///
     /// Remove a file or a directory
@@ -194,6 +524,46 @@ pub trait FileTransfer {
     /// Stat file and return FsEntry
     fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError>;
 
+    /// ### chmod
+    ///
+    /// Change the UNIX permissions of `path` to `mode` (user, group, others).
+    /// By default changing permissions is not supported
+    fn chmod(&mut self, _path: &Path, _mode: (u8, u8, u8)) -> Result<(), FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### chown
+    ///
+    /// Change the owner of `path` to user `uid` and group `gid`.
+    /// By default changing owner is not supported
+    fn chown(&mut self, _path: &Path, _uid: u32, _gid: u32) -> Result<(), FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### set_mtime
+    ///
+    /// Set the modification time of `path` to `mtime`.
+    /// By default setting the modification time is not supported
+    fn set_mtime(&mut self, _path: &Path, _mtime: SystemTime) -> Result<(), FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### symlink
+    ///
+    /// Create a symlink at `path`, pointing to `target`; relative targets are relative to the directory of the link.
+    /// By default creating symlinks is not supported
+    fn symlink(&mut self, _path: &Path, _target: &Path) -> Result<(), FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
     /// ### exec
     ///
     /// Execute a command on remote host
@@ -217,23 +587,144 @@ pub trait FileTransfer {
     /// Returns file and its size
     fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError>;
 
-    /// ### on_sent
+    /// ### recv_file_at
+    ///
+    /// Receive file from remote starting from byte `offset`, so that a file can be downloaded in segments.
+    /// The stream must be finalized with `finalize_transfer`, as the ones returned by `recv_file`.
+    /// Only some protocols support it
+    fn recv_file_at(
+        &mut self,
+        _file: &FsFile,
+        _offset: u64,
+    ) -> Result<Box<dyn Read>, FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer of a stream returned by `send_file` or `recv_file`.
+    /// You must call this method for each stream, whether the transfer has been completed, aborted or has failed:
+    /// the client releases the resources bound to the stream and, for some protocols such as FTP,
+    /// reads the reply of the server, which would otherwise be mistaken for the reply to the next command.
+    /// If the transfer hasn't been completed, the client interrupts it where the protocol allows it;
+    /// in this case an error is returned only if the session couldn't be cleaned up
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError>;
+
+    /// ### set_keyboard_interactive_responses
+    ///
+    /// Set the responses to the keyboard-interactive prompts (e.g. OTP) the server will send at the next `connect`.
+    /// Responses are consumed by `connect`. By default keyboard-interactive authentication is not supported
+    fn set_keyboard_interactive_responses(&mut self, _responses: Vec<String>) {}
+
+    /// ### set_accepted_host_key
+    ///
+    /// Set the host key (or certificate) the user has accepted after `connect` returned `UnverifiedHostKey`.
+    /// At the next `connect`, the key is trusted and saved, if the server still presents it.
+    /// By default host keys are not verified
+    fn set_accepted_host_key(&mut self, _key: KnownHost) {}
+
+    /// ### server_info
+    ///
+    /// Returns details about the server and the current session (e.g. negotiated algorithms) as name/value pairs.
+    /// By default nothing is reported
+    fn server_info(&self) -> Vec<(String, String)> {
+        Vec::new()
+    }
+
+    /// ### identity
     ///
-    /// Finalize send method.
-    /// This method must be implemented only if necessary; in case you don't need it, just return `Ok(())`
-    /// The purpose of this method is to finalize the connection with the peer when writing data.
-    /// This is necessary for some protocols such as FTP.
-    /// You must call this method each time you want to finalize the write of the remote file.
-    fn on_sent(&mut self, writable: Box<dyn Write>) -> Result<(), FileTransferError>;
+    /// Returns the remote OS and server software, identified on connect.
+    /// By default the server is not identified
+    fn identity(&self) -> ServerIdentity {
+        ServerIdentity::default()
+    }
 
-    /// ### on_recv
+    /// ### check_connection
     ///
-    /// Finalize recv method.
-    /// This method must be implemented only if necessary; in case you don't need it, just return `Ok(())`
-    /// The purpose of this method is to finalize the connection with the peer when reading data.
-    /// This mighe be necessary for some protocols.
-    /// You must call this method each time you want to finalize the read of the remote file.
-    fn on_recv(&mut self, readable: Box<dyn Read>) -> Result<(), FileTransferError>;
+    /// Verify that the session is still alive, with a cheap round trip to the remote host.
+    /// By default the working directory is entered again
+    fn check_connection(&mut self) -> Result<(), FileTransferError> {
+        let wrkdir: PathBuf = self.pwd()?;
+        self.change_dir(wrkdir.as_path()).map(|_| ())
+    }
+
+    /// ### device_id
+    ///
+    /// Returns the id of the device (file system) `path` resides on, which tells mount points apart.
+    /// By default file systems are not distinguished and `None` is returned
+    fn device_id(&mut self, _path: &Path) -> Result<Option<u64>, FileTransferError> {
+        Ok(None)
+    }
+
+    /// ### remove_one_file_system
+    ///
+    /// Remove a file or a directory, as `remove` does, but without descending into the directories
+    /// which reside on another file system (e.g. mounted network shares).
+    /// Mount points and the directories containing them are kept and reported in the error.
+    /// If the file transfer can't tell file systems apart, directories can't be removed and
+    /// an error of kind `UnsupportedFeature` is returned
+    fn remove_one_file_system(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        let dir: &FsDirectory = match file {
+            FsEntry::Directory(dir) if !file.is_symlink() => dir,
+            _ => return self.remove(file),
+        };
+        match self.device_id(dir.abs_path.as_path())? {
+            None => Err(FileTransferError::new_ex(
+                FileTransferErrorType::UnsupportedFeature,
+                String::from("file systems can't be told apart; directory not removed"),
+            )),
+            Some(device) => {
+                let mut skipped: Vec<PathBuf> = Vec::new();
+                self.iter_remove(file, device, &mut skipped)?;
+                match skipped.is_empty() {
+                    true => Ok(()),
+                    false => Err(FileTransferError::new_ex(
+                        FileTransferErrorType::FileSystemBoundary,
+                        skipped
+                            .iter()
+                            .map(|x| format!("\"{}\"", x.display()))
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                    )),
+                }
+            }
+        }
+    }
+
+    /// ### iter_remove
+    ///
+    /// Remove recursively `entry`, which resides on `device`; directories on other devices are pushed to `skipped`.
+    /// NOTE: don't call this method from outside; consider it as private
+    fn iter_remove(
+        &mut self,
+        entry: &FsEntry,
+        device: u64,
+        skipped: &mut Vec<PathBuf>,
+    ) -> Result<(), FileTransferError> {
+        if let FsEntry::Directory(dir) = entry {
+            if !entry.is_symlink() {
+                let mount_points: usize = skipped.len();
+                for child in self.list_dir(dir.abs_path.as_path())?.iter() {
+                    match child {
+                        FsEntry::Directory(subdir)
+                            if !child.is_symlink()
+                                && self.device_id(subdir.abs_path.as_path())? != Some(device) =>
+                        {
+                            skipped.push(subdir.abs_path.clone())
+                        }
+                        _ => self.iter_remove(child, device, skipped)?,
+                    }
+                }
+                // Keep the directory if it contains a mount point
+                if skipped.len() > mount_points {
+                    return Ok(());
+                }
+            }
+        }
+        self.remove(entry)
+    }
 
     /// ### find
     ///
@@ -244,7 +735,7 @@ pub trait FileTransfer {
             true => {
                 // Starting from current directory, iter dir
                 match self.pwd() {
-                    Ok(p) => self.iter_search(p.as_path(), &WildMatch::new(search)),
+                    Ok(p) => self.find_in(p.as_path(), search),
                     Err(err) => Err(err),
                 }
             }
@@ -254,6 +745,63 @@ pub trait FileTransfer {
         }
     }
 
+    /// ### disk_space
+    ///
+    /// Get the space left on the file system where `dir` is, and in the quota of the user on it.
+    /// The quota is queried on a best-effort basis, since `quota` is often not installed
+    fn disk_space(&mut self, dir: &Path) -> Result<DiskSpace, FileTransferError> {
+        let output: String = self.exec(format!("df -Pk {}", quote_path(dir)).as_str())?;
+        let mut space: DiskSpace = DiskSpace::parse_df(output.as_str()).ok_or_else(|| {
+            FileTransferError::new_ex(
+                FileTransferErrorType::ProtocolError,
+                format!("Unexpected output from df: {}", output.trim()),
+            )
+        })?;
+        if let Ok(output) = self.exec("quota -w 2>/dev/null") {
+            space.parse_quota(output.as_str());
+        }
+        Ok(space)
+    }
+
+    /// ### gunzip
+    ///
+    /// Decompress the gzip file `src` into `dest` on the remote host, then remove `src`.
+    /// By default `gzip` is run with `exec`
+    fn gunzip(&mut self, src: &Path, dest: &Path) -> Result<(), FileTransferError> {
+        let output: String = self.exec(
+            format!(
+                "gzip -dc {} > {} && rm -f {} && echo done",
+                quote_path(src),
+                quote_path(dest),
+                quote_path(src)
+            )
+            .as_str(),
+        )?;
+        match output.trim_end().ends_with("done") {
+            true => Ok(()),
+            false => Err(FileTransferError::new_ex(
+                FileTransferErrorType::ProtocolError,
+                format!(
+                    "Could not decompress \"{}\": {}",
+                    src.display(),
+                    output.trim()
+                ),
+            )),
+        }
+    }
+
+    /// ### find_in
+    ///
+    /// Find files from `dir` (in all subdirectories) whose name matches the provided search
+    fn find_in(&mut self, dir: &Path, search: &str) -> Result<Vec<FsEntry>, FileTransferError> {
+        match self.is_connected() {
+            true => self.iter_search(dir, &WildMatch::new(search)),
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
     /// ### iter_search
     ///
     /// Search recursively in `dir` for file matching the wildcard.
@@ -304,26 +852,67 @@ pub trait FileTransfer {
 
 impl std::string::ToString for FileTransferProtocol {
     fn to_string(&self) -> String {
+        if let FileTransferProtocol::Plugin(scheme) = self {
+            return scheme.to_ascii_uppercase();
+        }
         String::from(match self {
             FileTransferProtocol::Ftp(secure) => match secure {
                 true => "FTPS",
                 false => "FTP",
             },
+            FileTransferProtocol::Kube => "KUBE",
+            FileTransferProtocol::S3 => "S3",
             FileTransferProtocol::Scp => "SCP",
             FileTransferProtocol::Sftp => "SFTP",
+            FileTransferProtocol::Smb => "SMB",
+            FileTransferProtocol::WebDav(secure) => match secure {
+                true => "WEBDAVS",
+                false => "WEBDAV",
+            },
+            FileTransferProtocol::Plugin(_) => unreachable!(),
         })
     }
 }
 
+impl std::fmt::Display for AddressFamily {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name: &str = match self {
+            AddressFamily::Any => "any",
+            AddressFamily::Ipv4 => "ipv4",
+            AddressFamily::Ipv6 => "ipv6",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl std::str::FromStr for AddressFamily {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "any" => Ok(AddressFamily::Any),
+            "ipv4" => Ok(AddressFamily::Ipv4),
+            "ipv6" => Ok(AddressFamily::Ipv6),
+            _ => Err(()),
+        }
+    }
+}
+
 impl std::str::FromStr for FileTransferProtocol {
     type Err = ();
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s.to_ascii_uppercase().as_str() {
             "FTP" => Ok(FileTransferProtocol::Ftp(false)),
             "FTPS" => Ok(FileTransferProtocol::Ftp(true)),
+            "KUBE" => Ok(FileTransferProtocol::Kube),
+            "S3" => Ok(FileTransferProtocol::S3),
             "SCP" => Ok(FileTransferProtocol::Scp),
             "SFTP" => Ok(FileTransferProtocol::Sftp),
-            _ => Err(()),
+            "SMB" => Ok(FileTransferProtocol::Smb),
+            "WEBDAV" => Ok(FileTransferProtocol::WebDav(false)),
+            "WEBDAVS" => Ok(FileTransferProtocol::WebDav(true)),
+            _ => registry::lookup(s)
+                .map(FileTransferProtocol::Plugin)
+                .ok_or(()),
         }
     }
 }
@@ -381,6 +970,26 @@ mod tests {
             FileTransferProtocol::from_str("scp").ok().unwrap(),
             FileTransferProtocol::Scp
         );
+        assert_eq!(
+            FileTransferProtocol::from_str("webdav").ok().unwrap(),
+            FileTransferProtocol::WebDav(false)
+        );
+        assert_eq!(
+            FileTransferProtocol::from_str("WEBDAVS").ok().unwrap(),
+            FileTransferProtocol::WebDav(true)
+        );
+        assert_eq!(
+            FileTransferProtocol::from_str("smb").ok().unwrap(),
+            FileTransferProtocol::Smb
+        );
+        assert_eq!(
+            FileTransferProtocol::from_str("kube").ok().unwrap(),
+            FileTransferProtocol::Kube
+        );
+        assert_eq!(
+            FileTransferProtocol::from_str("s3").ok().unwrap(),
+            FileTransferProtocol::S3
+        );
         // Error
         assert!(FileTransferProtocol::from_str("dummy").is_err());
         // To String
@@ -394,6 +1003,91 @@ mod tests {
         );
         assert_eq!(FileTransferProtocol::Scp.to_string(), String::from("SCP"));
         assert_eq!(FileTransferProtocol::Sftp.to_string(), String::from("SFTP"));
+        assert_eq!(FileTransferProtocol::Smb.to_string(), String::from("SMB"));
+        assert_eq!(FileTransferProtocol::Kube.to_string(), String::from("KUBE"));
+        assert_eq!(FileTransferProtocol::S3.to_string(), String::from("S3"));
+        assert_eq!(
+            FileTransferProtocol::WebDav(false).to_string(),
+            String::from("WEBDAV")
+        );
+        assert_eq!(
+            FileTransferProtocol::WebDav(true).to_string(),
+            String::from("WEBDAVS")
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_mod_protocol_default_port() {
+        assert_eq!(FileTransferProtocol::Ftp(true).default_port(), 21);
+        assert_eq!(FileTransferProtocol::Scp.default_port(), 22);
+        assert_eq!(FileTransferProtocol::Sftp.default_port(), 22);
+        assert_eq!(FileTransferProtocol::WebDav(false).default_port(), 80);
+        assert_eq!(FileTransferProtocol::WebDav(true).default_port(), 443);
+        assert_eq!(FileTransferProtocol::Smb.default_port(), 445);
+        assert_eq!(FileTransferProtocol::Kube.default_port(), 0);
+        assert_eq!(FileTransferProtocol::S3.default_port(), 0);
+    }
+
+    #[test]
+    fn test_filetransfer_mod_address_family() {
+        assert_eq!(AddressFamily::from_str("IPv6"), Ok(AddressFamily::Ipv6));
+        assert_eq!(AddressFamily::from_str("ipv4"), Ok(AddressFamily::Ipv4));
+        assert_eq!(AddressFamily::from_str("any"), Ok(AddressFamily::Any));
+        assert!(AddressFamily::from_str("ipx").is_err());
+        assert_eq!(AddressFamily::Ipv6.to_string(), "ipv6");
+    }
+
+    #[test]
+    fn test_filetransfer_mod_network_options_resolve() {
+        let mut options: NetworkOptions = NetworkOptions::default();
+        // IPv6 literals, with or without brackets
+        let expected: SocketAddr = SocketAddr::from_str("[::1]:2222").unwrap();
+        assert_eq!(options.resolve("::1", 2222).ok().unwrap(), vec![expected]);
+        assert_eq!(options.resolve("[::1]", 2222).ok().unwrap(), vec![expected]);
+        assert_eq!(
+            options.resolve("127.0.0.1", 22).ok().unwrap(),
+            vec![SocketAddr::from_str("127.0.0.1:22").unwrap()]
+        );
+        assert!(options.resolve("[::1", 22).is_err());
+        // Preferred family goes first
+        let resolved: Vec<SocketAddr> = options.resolve("localhost", 22).ok().unwrap();
+        options.address_family = AddressFamily::Ipv6;
+        let ipv6: Vec<SocketAddr> = options.resolve("localhost", 22).ok().unwrap();
+        assert_eq!(ipv6.len(), resolved.len());
+        assert!(ipv6.windows(2).all(|x| x[0].is_ipv6() || x[1].is_ipv4()));
+        options.address_family = AddressFamily::Ipv4;
+        let ipv4: Vec<SocketAddr> = options.resolve("localhost", 22).ok().unwrap();
+        assert!(ipv4.windows(2).all(|x| x[0].is_ipv4() || x[1].is_ipv6()));
+    }
+
+    #[test]
+    fn test_filetransfer_mod_server_identity() {
+        let identity: ServerIdentity = ServerIdentity::new(
+            Some("SSH-2.0-OpenSSH_8.4p1 Debian-5"),
+            Some("Linux omar 5.10.0-8-amd64 #1 SMP Debian 5.10.46-4 x86_64 GNU/Linux\n"),
+        );
+        assert_eq!(identity.software.as_deref(), Some("OpenSSH_8.4p1 Debian-5"));
+        assert!(identity.system.as_deref().unwrap().ends_with("GNU/Linux"));
+        assert!(!identity.is_windows());
+        assert_eq!(
+            identity.summary().as_deref(),
+            Some("Linux, OpenSSH_8.4p1 Debian-5")
+        );
+        // FTP
+        let identity: ServerIdentity =
+            ServerIdentity::new(Some("220 Microsoft FTP Service"), Some("Windows_NT"));
+        assert_eq!(identity.software.as_deref(), Some("Microsoft FTP Service"));
+        assert!(identity.is_windows());
+        assert_eq!(
+            identity.summary().as_deref(),
+            Some("Windows, Microsoft FTP Service")
+        );
+        // Windows detected by banner
+        assert!(ServerIdentity::new(Some("SSH-2.0-OpenSSH_for_Windows_8.1"), None).is_windows());
+        // Unknown
+        let identity: ServerIdentity = ServerIdentity::new(None, Some("  "));
+        assert_eq!(identity, ServerIdentity::default());
+        assert!(identity.summary().is_none());
     }
 
     #[test]
@@ -442,6 +1136,20 @@ mod tests {
             ),
             String::from("Failed to create file")
         );
+        assert_eq!(
+            format!(
+                "{}",
+                FileTransferError::new(FileTransferErrorType::FileSystemBoundary)
+            ),
+            String::from("Skipped directories on another file system")
+        );
+        assert_eq!(
+            format!(
+                "{}",
+                FileTransferError::new(FileTransferErrorType::KeyboardInteractive(vec![]))
+            ),
+            String::from("Keyboard-interactive authentication required")
+        );
         assert_eq!(
             format!(
                 "{}",
@@ -0,0 +1,1105 @@
+//! ## Pool
+//!
+//! `pool` is the module which distributes file transfers across concurrent sessions
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{FileTransfer, TransferOutcome, TransferStatus, TransferStream};
+use crate::fs::queue::TransferDirection;
+use crate::fs::transfer::{
+    CancelToken, Cancellable, ChunkSizer, PartialFilePolicy, Throttle, TransferOptions,
+};
+use crate::fs::{FsEntry, FsFile};
+use crate::host::Localhost;
+// Ext
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Files at least this large are downloaded in segments, on concurrent sessions, if the protocol allows it
+pub const SEGMENTED_MIN_SIZE: u64 = 64 * 1024 * 1024;
+/// Minimum size of the segments of a file
+const MIN_SEGMENT_SIZE: u64 = 8 * 1024 * 1024;
+/// Amount of segments per worker
+const SEGMENTS_PER_WORKER: u64 = 4;
+
+/// ## Connector
+///
+/// Function used by the workers to open their own session
+pub type Connector = dyn Fn() -> Result<Box<dyn FileTransfer>, String> + Send + Sync;
+
+/// ## PoolJob
+///
+/// A file to transfer through the pool
+#[derive(Clone, Debug)]
+pub struct PoolJob {
+    pub id: usize, // Identifies the job for the caller (e.g. the queue item the file belongs to)
+    pub direction: TransferDirection,
+    pub file: FsFile,              // Source file
+    pub target: PathBuf,           // Path the file is written to
+    pub range: Option<(u64, u64)>, // Segment (offset, length) of the file to download; None for the whole file
+}
+
+/// ## PoolEvent
+///
+/// Events reported by the workers of the pool
+#[derive(Debug)]
+pub enum PoolEvent {
+    Progress(u64), // Bytes transferred by a worker since the last event
+    Finished(Box<PoolJob>, Result<(), String>), // Job has been transferred or has failed
+    Retrying(Box<PoolJob>, u32, String), // Job has failed with error and is retried (retry number)
+    ConnectionFailed(String), // A worker couldn't open its session and has quit
+}
+
+/// ## TransferPool
+///
+/// Runs file transfers on `workers` concurrent sessions.
+/// Each worker opens its own session and takes the next job as soon as it's idle
+pub struct TransferPool {
+    jobs: Arc<Mutex<VecDeque<PoolJob>>>,
+    cancel: CancelToken,
+    events: Receiver<PoolEvent>,
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl TransferPool {
+    /// ### new
+    ///
+    /// Start `workers` workers, connected through `connect`, to transfer `jobs` with `opts`.
+    /// Of the options, the workers apply preserved timestamps, the bandwidth limit and schedule (shared among them),
+    /// retries, the partial files policy and atomic uploads
+    pub fn new(
+        jobs: Vec<PoolJob>,
+        workers: usize,
+        opts: &TransferOptions,
+        connect: Arc<Connector>,
+    ) -> Self {
+        let workers: usize = workers.clamp(1, jobs.len().max(1));
+        let share: u64 = workers as u64;
+        let jobs: Arc<Mutex<VecDeque<PoolJob>>> = Arc::new(Mutex::new(jobs.into()));
+        let cancel: CancelToken = CancelToken::default();
+        let (sender, events) = mpsc::channel();
+        let workers: Vec<JoinHandle<()>> = (0..workers)
+            .map(|_| {
+                let worker = Worker {
+                    jobs: jobs.clone(),
+                    cancel: cancel.clone(),
+                    events: sender.clone(),
+                    connect: connect.clone(),
+                    opts: opts.clone(),
+                    share,
+                };
+                thread::spawn(move || worker.run())
+            })
+            .collect();
+        TransferPool {
+            jobs,
+            cancel,
+            events,
+            workers,
+        }
+    }
+
+    /// ### recv_timeout
+    ///
+    /// Wait up to `timeout` for the next event.
+    /// Returns `Disconnected` once all the workers have quit and all the events have been read
+    pub fn recv_timeout(&self, timeout: Duration) -> Result<PoolEvent, RecvTimeoutError> {
+        self.events.recv_timeout(timeout)
+    }
+
+    /// ### abort
+    ///
+    /// Interrupt the running transfers and stop the workers
+    pub fn abort(&self) {
+        self.cancel.cancel();
+    }
+
+    /// ### pause
+    ///
+    /// Suspend the running transfers after their current chunk, keeping their streams open
+    pub fn pause(&self) {
+        self.cancel.pause();
+    }
+
+    /// ### resume
+    ///
+    /// Let the paused transfers go on
+    pub fn resume(&self) {
+        self.cancel.resume();
+    }
+
+    /// ### is_paused
+    ///
+    /// Returns whether the pool is paused
+    pub fn is_paused(&self) -> bool {
+        self.cancel.is_paused()
+    }
+
+    /// ### join
+    ///
+    /// Wait for the workers to quit and return the jobs which haven't been taken by any of them
+    pub fn join(self) -> Vec<PoolJob> {
+        for worker in self.workers.into_iter() {
+            let _ = worker.join();
+        }
+        match self.jobs.lock() {
+            Ok(mut jobs) => jobs.drain(..).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+}
+
+/// ## Worker
+///
+/// A worker of the pool, with its own session
+struct Worker {
+    jobs: Arc<Mutex<VecDeque<PoolJob>>>,
+    cancel: CancelToken,
+    events: Sender<PoolEvent>,
+    connect: Arc<Connector>,
+    opts: TransferOptions,
+    share: u64, // Number of workers the bandwidth limit is shared among
+}
+
+impl Worker {
+    /// ### run
+    ///
+    /// Transfer jobs until there are no more jobs left or the pool is aborted.
+    /// Failed jobs are retried; if the session drops, the worker reconnects once before quitting
+    fn run(self) {
+        let mut client: Box<dyn FileTransfer> = match (self.connect)() {
+            Ok(client) => client,
+            Err(err) => {
+                let _ = self.events.send(PoolEvent::ConnectionFailed(err));
+                return;
+            }
+        };
+        while !self.cancel.is_cancelled() {
+            let job: PoolJob = match self.jobs.lock().ok().and_then(|mut x| x.pop_front()) {
+                Some(job) => job,
+                None => break,
+            };
+            let mut retry: u32 = 0;
+            let result: Result<(), String> = loop {
+                let err: String = match self.transfer(client.as_mut(), &job) {
+                    Ok(_) => break Ok(()),
+                    Err(err) if self.cancel.is_cancelled() => break Err(err),
+                    Err(err) => err,
+                };
+                if client.check_connection().is_err() {
+                    let _ = client.disconnect();
+                    client = match (self.connect)() {
+                        Ok(client) => client,
+                        Err(conn_err) => {
+                            let _ = self
+                                .events
+                                .send(PoolEvent::Finished(Box::new(job), Err(err)));
+                            let _ = self.events.send(PoolEvent::ConnectionFailed(conn_err));
+                            return;
+                        }
+                    };
+                }
+                if retry >= self.opts.retry.retries {
+                    break Err(err);
+                }
+                retry += 1;
+                let _ = self
+                    .events
+                    .send(PoolEvent::Retrying(Box::new(job.clone()), retry, err));
+                self.sleep(self.opts.retry.delay(retry));
+            };
+            let _ = self.events.send(PoolEvent::Finished(Box::new(job), result));
+        }
+        let _ = client.disconnect();
+    }
+
+    /// ### sleep
+    ///
+    /// Wait for `delay`, unless the pool is aborted in the meantime
+    fn sleep(&self, delay: Duration) {
+        let started: Instant = Instant::now();
+        while !self.cancel.is_cancelled() {
+            match delay.checked_sub(started.elapsed()) {
+                Some(left) if !left.is_zero() => {
+                    thread::sleep(left.min(Duration::from_millis(100)))
+                }
+                _ => break,
+            }
+        }
+    }
+
+    /// ### bandwidth_limit
+    ///
+    /// Get the share of the current bandwidth limit of the worker, in KiB/s; 0 means unlimited
+    fn bandwidth_limit(&self) -> u64 {
+        match self.opts.current_bandwidth_limit() {
+            0 => 0,
+            kib => (kib / self.share).max(1),
+        }
+    }
+
+    /// ### transfer
+    ///
+    /// Transfer `job` with `client`, reporting the progress to the pool
+    fn transfer(&self, client: &mut dyn FileTransfer, job: &PoolJob) -> Result<(), String> {
+        let mut throttle: Throttle = Throttle::new(self.bandwidth_limit());
+        let mut on_progress = |bytes: usize| {
+            let _ = self.events.send(PoolEvent::Progress(bytes as u64));
+            throttle.skip(self.cancel.wait_resumed());
+            // The schedule may change the limit while transferring
+            throttle.set_limit(self.bandwidth_limit());
+            throttle.consume(bytes);
+            while let Some(delay) = throttle.delay() {
+                if self.cancel.is_cancelled() {
+                    break;
+                }
+                thread::sleep(delay.min(Duration::from_millis(100)));
+            }
+        };
+        // Segments are just written to the target; the caller finalizes the file once they're all done
+        if let Some(range) = job.range {
+            return transfer_segment(
+                client,
+                &job.file,
+                &job.target,
+                range,
+                &self.cancel,
+                &mut on_progress,
+            );
+        }
+        transfer_file(
+            client,
+            job.direction,
+            &job.file,
+            &job.target,
+            &self.cancel,
+            &self.opts,
+            &mut on_progress,
+        )?;
+        if self.opts.preserve_timestamps {
+            let mtime = job.file.last_change_time;
+            match job.direction {
+                TransferDirection::Upload => client
+                    .set_mtime(job.target.as_path(), mtime)
+                    .map_err(|e| e.to_string()),
+                TransferDirection::Download => File::options()
+                    .write(true)
+                    .open(job.target.as_path())
+                    .and_then(|x| x.set_modified(mtime))
+                    .map_err(|e| e.to_string()),
+            }
+            .map_err(|e| {
+                format!(
+                    "Could not set modification time of \"{}\": {}",
+                    job.target.display(),
+                    e
+                )
+            })?;
+        }
+        Ok(())
+    }
+}
+
+/// ### transfer_file
+///
+/// Transfer `file` to `target` with `client`; for uploads `file` is local, for downloads it's remote.
+/// `on_progress` is called with the bytes written by each chunk.
+/// Uploads are written to a temporary file first if `opts` enables atomic uploads;
+/// downloads are flushed to disk before returning if `opts` enables durable writes.
+/// Once `cancel` is cancelled, the transfer is interrupted before the next chunk, the stream is finalized as aborted
+/// and the partial files policy of `opts` is applied to the file written
+pub(crate) fn transfer_file(
+    client: &mut dyn FileTransfer,
+    direction: TransferDirection,
+    file: &FsFile,
+    target: &Path,
+    cancel: &CancelToken,
+    opts: &TransferOptions,
+    on_progress: &mut dyn FnMut(usize),
+) -> Result<(), String> {
+    let written: PathBuf = match direction {
+        TransferDirection::Upload => opts.upload_path(target),
+        TransferDirection::Download => target.to_path_buf(),
+    };
+    let status: TransferStatus = match direction {
+        TransferDirection::Upload => {
+            let reader: File = File::open(file.abs_path.as_path())
+                .map_err(|e| format!("Could not open \"{}\": {}", file.abs_path.display(), e))?;
+            let writer: Box<dyn Write> = client
+                .send_file(file, written.as_path())
+                .map_err(|e| format!("Could not upload \"{}\": {}", target.display(), e))?;
+            let mut reader = Cancellable::new(reader, cancel);
+            let mut writer = Cancellable::new(writer, cancel);
+            let (bytes, status): (u64, TransferStatus) =
+                copy(&mut reader, &mut writer, on_progress);
+            client
+                .finalize_transfer(TransferOutcome::new(
+                    TransferStream::Upload(writer.into_inner()),
+                    bytes,
+                    status.clone(),
+                ))
+                .map_err(|e| format!("Could not upload \"{}\": {}", target.display(), e))?;
+            status
+        }
+        TransferDirection::Download => {
+            let reader: Box<dyn Read> = client.recv_file(file).map_err(|e| {
+                format!("Could not download \"{}\": {}", file.abs_path.display(), e)
+            })?;
+            let writer: File = File::create(target)
+                .map_err(|e| format!("Could not create \"{}\": {}", target.display(), e))?;
+            if opts.preallocate {
+                Localhost::preallocate(&writer, file.size as u64)
+                    .map_err(|e| format!("Could not write \"{}\": {}", target.display(), e))?;
+            }
+            let mut reader = Cancellable::new(reader, cancel);
+            let mut writer = Cancellable::new(writer, cancel);
+            let (bytes, status): (u64, TransferStatus) =
+                copy(&mut reader, &mut writer, on_progress);
+            client
+                .finalize_transfer(TransferOutcome::new(
+                    TransferStream::Download(reader.into_inner()),
+                    bytes,
+                    status.clone(),
+                ))
+                .map_err(|e| {
+                    format!("Could not download \"{}\": {}", file.abs_path.display(), e)
+                })?;
+            status
+        }
+    };
+    if status == TransferStatus::Aborted {
+        match discard_partial(client, direction, written.as_path(), opts.partial_files) {
+            Ok(Some(outcome)) | Err(outcome) => {
+                return Err(format!("Transfer interrupted; {}", outcome))
+            }
+            Ok(None) => {}
+        }
+    }
+    status_to_result(status)?;
+    // Downloads are done once they're on disk
+    if matches!(direction, TransferDirection::Download) && opts.durable_writes {
+        Localhost::sync_file(target)
+            .map_err(|e| format!("Could not write \"{}\": {}", target.display(), e))?;
+    }
+    match written.as_path() != target {
+        true => finalize_atomic_upload(client, written.as_path(), target),
+        false => Ok(()),
+    }
+}
+
+/// ### segments
+///
+/// Split a file of `size` bytes into the segments (offset, length) downloaded by `workers` sessions.
+/// Each worker gets a few segments, so that the faster sessions take over the segments left by the slower ones
+pub fn segments(size: u64, workers: usize) -> Vec<(u64, u64)> {
+    let count: u64 = (workers as u64 * SEGMENTS_PER_WORKER)
+        .min(size / MIN_SEGMENT_SIZE)
+        .max(1);
+    let len: u64 = size.div_ceil(count);
+    (0..count)
+        .map(|x| x * len)
+        .take_while(|offset| *offset < size)
+        .map(|offset| (offset, len.min(size - offset)))
+        .collect()
+}
+
+/// ### transfer_segment
+///
+/// Download the segment (`offset`, `length`) of `file` with `client` into the same range of `target`, which must exist.
+/// Once `cancel` is cancelled, the transfer is interrupted before the next chunk
+pub(crate) fn transfer_segment(
+    client: &mut dyn FileTransfer,
+    file: &FsFile,
+    target: &Path,
+    (offset, length): (u64, u64),
+    cancel: &CancelToken,
+    on_progress: &mut dyn FnMut(usize),
+) -> Result<(), String> {
+    let mut writer: File = File::options()
+        .write(true)
+        .open(target)
+        .map_err(|e| format!("Could not open \"{}\": {}", target.display(), e))?;
+    writer
+        .seek(SeekFrom::Start(offset))
+        .map_err(|e| format!("Could not seek \"{}\": {}", target.display(), e))?;
+    let reader: Box<dyn Read> = client
+        .recv_file_at(file, offset)
+        .map_err(|e| format!("Could not download \"{}\": {}", file.abs_path.display(), e))?;
+    let mut reader = Cancellable::new(reader, cancel);
+    let mut writer = Cancellable::new(writer, cancel);
+    let (bytes, status): (u64, TransferStatus) =
+        copy(&mut (&mut reader).take(length), &mut writer, on_progress);
+    // The file may have been truncated in the meantime
+    let status: TransferStatus = match status {
+        TransferStatus::Completed if bytes < length => {
+            TransferStatus::Failed(format!("Unexpected end of file at byte {}", offset + bytes))
+        }
+        status => status,
+    };
+    client
+        .finalize_transfer(TransferOutcome::new(
+            TransferStream::Download(reader.into_inner()),
+            bytes,
+            status.clone(),
+        ))
+        .map_err(|e| format!("Could not download \"{}\": {}", file.abs_path.display(), e))?;
+    status_to_result(status)
+}
+
+/// ### finalize_atomic_upload
+///
+/// Rename the temporary file `tmp` of an atomic upload to its final name, `remote`
+pub(crate) fn finalize_atomic_upload(
+    client: &mut dyn FileTransfer,
+    tmp: &Path,
+    remote: &Path,
+) -> Result<(), String> {
+    let entry: FsEntry = client
+        .stat(tmp)
+        .map_err(|e| format!("Could not stat \"{}\": {}", tmp.display(), e))?;
+    if let Err(err) = client.rename(&entry, remote) {
+        // Some servers can't rename over an existing file: remove it and try again
+        match client.stat(remote) {
+            Ok(existing) => {
+                client
+                    .remove(&existing)
+                    .map_err(|e| format!("Could not replace \"{}\": {}", remote.display(), e))?;
+                client.rename(&entry, remote).map_err(|e| {
+                    format!(
+                        "Could not rename \"{}\" to \"{}\": {}",
+                        tmp.display(),
+                        remote.display(),
+                        e
+                    )
+                })?;
+            }
+            Err(_) => {
+                return Err(format!(
+                    "Could not rename \"{}\" to \"{}\": {}",
+                    tmp.display(),
+                    remote.display(),
+                    err
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// ### discard_partial
+///
+/// Apply `policy` to `target`, which has been written only partially by an aborted transfer.
+/// Returns what has been done with it, or None if it has been kept
+pub(crate) fn discard_partial(
+    client: &mut dyn FileTransfer,
+    direction: TransferDirection,
+    target: &Path,
+    policy: PartialFilePolicy,
+) -> Result<Option<String>, String> {
+    let marked: PathBuf = PartialFilePolicy::marked(target);
+    let result: Result<(), String> = match (policy, direction) {
+        (PartialFilePolicy::Keep, _) => return Ok(None),
+        (PartialFilePolicy::Remove, TransferDirection::Upload) => client
+            .stat(target)
+            .and_then(|entry| client.remove(&entry))
+            .map_err(|e| e.to_string()),
+        (PartialFilePolicy::Remove, TransferDirection::Download) => {
+            std::fs::remove_file(target).map_err(|e| e.to_string())
+        }
+        (PartialFilePolicy::Mark, TransferDirection::Upload) => client
+            .stat(target)
+            .and_then(|entry| client.rename(&entry, marked.as_path()))
+            .map_err(|e| e.to_string()),
+        (PartialFilePolicy::Mark, TransferDirection::Download) => {
+            std::fs::rename(target, marked.as_path()).map_err(|e| e.to_string())
+        }
+    };
+    match (result, policy) {
+        (Ok(_), PartialFilePolicy::Mark) => Ok(Some(format!(
+            "partial file renamed to \"{}\"",
+            marked.display()
+        ))),
+        (Ok(_), _) => Ok(Some(format!(
+            "partial file \"{}\" removed",
+            target.display()
+        ))),
+        (Err(err), _) => Err(format!(
+            "could not {} partial file \"{}\": {}",
+            match policy {
+                PartialFilePolicy::Mark => "rename",
+                _ => "remove",
+            },
+            target.display(),
+            err
+        )),
+    }
+}
+
+/// ### copy
+///
+/// Copy `reader` into `writer`, calling `on_progress` with the bytes written by each chunk.
+/// The size of the chunks is adapted to the throughput of the streams.
+/// Cancellation errors returned by the streams interrupt the copy.
+/// Returns the bytes written and how the copy has ended
+fn copy(
+    reader: &mut dyn Read,
+    writer: &mut dyn Write,
+    on_progress: &mut dyn FnMut(usize),
+) -> (u64, TransferStatus) {
+    let mut sizer: ChunkSizer = ChunkSizer::default();
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut total: u64 = 0;
+    loop {
+        buffer.resize(sizer.size(), 0);
+        let started: Instant = Instant::now();
+        let bytes: usize = match reader.read(&mut buffer) {
+            Ok(0) => {
+                return match writer.flush() {
+                    Ok(_) => (total, TransferStatus::Completed),
+                    Err(err) if CancelToken::is_cancellation(&err) => {
+                        (total, TransferStatus::Aborted)
+                    }
+                    Err(err) => (
+                        total,
+                        TransferStatus::Failed(format!("Write error: {}", err)),
+                    ),
+                }
+            }
+            Ok(bytes) => bytes,
+            Err(err) if err.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(err) if CancelToken::is_cancellation(&err) => {
+                return (total, TransferStatus::Aborted)
+            }
+            Err(err) => {
+                return (
+                    total,
+                    TransferStatus::Failed(format!("Read error: {}", err)),
+                )
+            }
+        };
+        if let Err(err) = writer.write_all(&buffer[..bytes]) {
+            if CancelToken::is_cancellation(&err) {
+                return (total, TransferStatus::Aborted);
+            }
+            return (
+                total,
+                TransferStatus::Failed(format!("Write error: {}", err)),
+            );
+        }
+        sizer.record(bytes, started.elapsed());
+        total += bytes as u64;
+        on_progress(bytes);
+    }
+}
+
+/// ### status_to_result
+///
+/// Convert the status of a transfer into a result
+fn status_to_result(status: TransferStatus) -> Result<(), String> {
+    match status {
+        TransferStatus::Completed => Ok(()),
+        TransferStatus::Aborted => Err(String::from("Transfer interrupted")),
+        TransferStatus::Failed(err) => Err(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::filetransfer::fixtures::FtpServer;
+    use crate::filetransfer::ftp_transfer::FtpFileTransfer;
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    use crate::fs::transfer::RetryPolicy;
+    use crate::host::Localhost;
+
+    fn make_client() -> MemoryFileTransfer {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::new();
+        client.connect(String::new(), 0, None, None).unwrap();
+        for i in 0..8 {
+            client.add_file(
+                PathBuf::from(format!("/srv/file{}.txt", i)).as_path(),
+                format!("content of file {}\n", i).as_bytes(),
+            );
+        }
+        client
+    }
+
+    fn make_jobs(target: &Path) -> Vec<PoolJob> {
+        let mut client: MemoryFileTransfer = make_client();
+        client
+            .list_dir(Path::new("/srv"))
+            .unwrap()
+            .into_iter()
+            .enumerate()
+            .map(|(id, x)| match x {
+                FsEntry::File(file) => PoolJob {
+                    id,
+                    direction: TransferDirection::Download,
+                    target: target.join(file.name.as_str()),
+                    file,
+                    range: None,
+                },
+                FsEntry::Directory(_) => panic!("not a file"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_filetransfer_pool_transfer() {
+        let target: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let jobs: Vec<PoolJob> = make_jobs(target.path());
+        let total: u64 = jobs.iter().map(|x| x.file.size as u64).sum();
+        let pool: TransferPool = TransferPool::new(
+            jobs,
+            3,
+            &TransferOptions {
+                preserve_timestamps: true,
+                ..TransferOptions::default()
+            },
+            Arc::new(|| Ok(Box::new(make_client()) as Box<dyn FileTransfer>)),
+        );
+        let mut finished: Vec<usize> = Vec::new();
+        let mut bytes: u64 = 0;
+        loop {
+            match pool.recv_timeout(Duration::from_secs(5)) {
+                Ok(PoolEvent::Progress(x)) => bytes += x,
+                Ok(PoolEvent::Finished(job, result)) => {
+                    assert!(result.is_ok());
+                    // Timestamps are preserved
+                    assert_eq!(
+                        std::fs::metadata(job.target.as_path())
+                            .unwrap()
+                            .modified()
+                            .unwrap(),
+                        job.file.last_change_time
+                    );
+                    finished.push(job.id);
+                }
+                Ok(PoolEvent::ConnectionFailed(err)) => panic!("{}", err),
+                Ok(PoolEvent::Retrying(_, _, err)) => panic!("{}", err),
+                Err(RecvTimeoutError::Timeout) => panic!("timeout"),
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        assert!(pool.join().is_empty());
+        finished.sort_unstable();
+        assert_eq!(finished, (0..8).collect::<Vec<usize>>());
+        assert_eq!(bytes, total);
+        assert_eq!(
+            std::fs::read_to_string(target.path().join("file3.txt")).unwrap(),
+            "content of file 3\n"
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_pool_upload() {
+        let server: FtpServer = FtpServer::start(MemoryFileTransfer::new()).unwrap();
+        server.fs().lock().unwrap().add_dir(Path::new("/srv"));
+        let local: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        for i in 0..16 {
+            std::fs::write(local.path().join(format!("file{}.txt", i)), b"uploaded\n").unwrap();
+        }
+        let host: Localhost = Localhost::new(local.path().to_path_buf()).ok().unwrap();
+        let jobs: Vec<PoolJob> = host
+            .list_dir()
+            .into_iter()
+            .filter_map(|x| match x {
+                FsEntry::File(file) => Some(PoolJob {
+                    id: 0,
+                    direction: TransferDirection::Upload,
+                    target: Path::new("/srv").join(file.name.as_str()),
+                    file,
+                    range: None,
+                }),
+                FsEntry::Directory(_) => None,
+            })
+            .collect();
+        let (address, port): (String, u16) = (server.address(), server.port());
+        let pool: TransferPool = TransferPool::new(
+            jobs,
+            4,
+            &TransferOptions::default(),
+            Arc::new(move || {
+                let mut client: FtpFileTransfer = FtpFileTransfer::new(false);
+                client
+                    .connect(address.clone(), port, None, None)
+                    .map_err(|e| e.to_string())?;
+                Ok(Box::new(client) as Box<dyn FileTransfer>)
+            }),
+        );
+        let mut finished: usize = 0;
+        while let Ok(event) = pool.recv_timeout(Duration::from_secs(5)) {
+            match event {
+                PoolEvent::Finished(_, result) => {
+                    assert!(result.is_ok());
+                    finished += 1;
+                }
+                PoolEvent::ConnectionFailed(err) | PoolEvent::Retrying(_, _, err) => {
+                    panic!("{}", err)
+                }
+                PoolEvent::Progress(_) => {}
+            }
+        }
+        assert!(pool.join().is_empty());
+        assert_eq!(finished, 16);
+        let fs = server.fs();
+        let mut fs = fs.lock().unwrap();
+        assert_eq!(fs.list_dir(Path::new("/srv")).unwrap().len(), 16);
+        assert_eq!(fs.stat(Path::new("/srv/file7.txt")).unwrap().get_size(), 9);
+    }
+
+    #[test]
+    fn test_filetransfer_pool_segments() {
+        assert!(segments(0, 4).is_empty());
+        // Small files are downloaded in one segment
+        assert_eq!(segments(1024, 4), vec![(0, 1024)]);
+        // At least 8 MiB per segment
+        let mib: u64 = 1024 * 1024;
+        assert_eq!(
+            segments(20 * mib, 4),
+            vec![(0, 10 * mib), (10 * mib, 10 * mib)]
+        );
+        // Up to 4 segments per worker, covering the whole file
+        let ranges: Vec<(u64, u64)> = segments(1000 * mib + 3, 2);
+        assert_eq!(ranges.len(), 8);
+        assert_eq!(ranges[0].0, 0);
+        assert!(ranges.windows(2).all(|x| x[0].0 + x[0].1 == x[1].0));
+        let (offset, len) = ranges.last().copied().unwrap();
+        assert_eq!(offset + len, 1000 * mib + 3);
+    }
+
+    #[test]
+    fn test_filetransfer_pool_segmented_download() {
+        let content: Vec<u8> = (0..10000).map(|x| (x % 251) as u8).collect();
+        let connect: Arc<Connector> = {
+            let content: Vec<u8> = content.clone();
+            Arc::new(move || {
+                let mut client: MemoryFileTransfer = MemoryFileTransfer::new();
+                client.connect(String::new(), 0, None, None).unwrap();
+                client.add_file(Path::new("/srv/big.bin"), content.as_slice());
+                Ok(Box::new(client) as Box<dyn FileTransfer>)
+            })
+        };
+        let file: FsFile = match connect().unwrap().stat(Path::new("/srv/big.bin")).unwrap() {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("not a file"),
+        };
+        // Preallocate target
+        let target: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        target.as_file().set_len(10000).unwrap();
+        let jobs: Vec<PoolJob> = [(0, 3000), (3000, 3000), (6000, 3000), (9000, 1000)]
+            .iter()
+            .map(|range| PoolJob {
+                id: 0,
+                direction: TransferDirection::Download,
+                file: file.clone(),
+                target: target.path().to_path_buf(),
+                range: Some(*range),
+            })
+            .collect();
+        let pool: TransferPool =
+            TransferPool::new(jobs, 3, &TransferOptions::default(), connect.clone());
+        let mut bytes: u64 = 0;
+        let mut finished: usize = 0;
+        while let Ok(event) = pool.recv_timeout(Duration::from_secs(5)) {
+            match event {
+                PoolEvent::Progress(x) => bytes += x,
+                PoolEvent::Finished(_, result) => {
+                    assert!(result.is_ok());
+                    finished += 1;
+                }
+                PoolEvent::ConnectionFailed(err) | PoolEvent::Retrying(_, _, err) => {
+                    panic!("{}", err)
+                }
+            }
+        }
+        assert!(pool.join().is_empty());
+        assert_eq!(finished, 4);
+        assert_eq!(bytes, 10000);
+        assert_eq!(std::fs::read(target.path()).unwrap(), content);
+        // A segment beyond the end of the file fails
+        let mut client: Box<dyn FileTransfer> = connect().unwrap();
+        assert!(transfer_segment(
+            client.as_mut(),
+            &file,
+            target.path(),
+            (9000, 2000),
+            &CancelToken::default(),
+            &mut |_| {}
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_pool_retry() {
+        let target: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        // Target directory doesn't exist yet: first attempts fail
+        let missing: PathBuf = target.path().join("missing");
+        let mut jobs: Vec<PoolJob> = make_jobs(missing.as_path());
+        jobs.truncate(1);
+        let pool: TransferPool = TransferPool::new(
+            jobs,
+            1,
+            &TransferOptions {
+                retry: RetryPolicy::new(3, Duration::from_millis(50)),
+                ..TransferOptions::default()
+            },
+            Arc::new(|| Ok(Box::new(make_client()) as Box<dyn FileTransfer>)),
+        );
+        let mut retries: Vec<u32> = Vec::new();
+        let mut result: Option<Result<(), String>> = None;
+        while let Ok(event) = pool.recv_timeout(Duration::from_secs(5)) {
+            match event {
+                PoolEvent::Retrying(_, retry, _) => {
+                    retries.push(retry);
+                    if retry == 2 {
+                        std::fs::create_dir(missing.as_path()).unwrap();
+                    }
+                }
+                PoolEvent::Finished(_, res) => result = Some(res),
+                PoolEvent::ConnectionFailed(err) => panic!("{}", err),
+                PoolEvent::Progress(_) => {}
+            }
+        }
+        assert!(pool.join().is_empty());
+        assert_eq!(retries, vec![1, 2]);
+        assert!(result.unwrap().is_ok());
+        assert!(missing.join("file0.txt").exists());
+        // Give up after the last retry
+        let pool: TransferPool = TransferPool::new(
+            make_jobs(target.path().join("not-there").as_path()),
+            1,
+            &TransferOptions {
+                retry: RetryPolicy::new(1, Duration::from_millis(10)),
+                ..TransferOptions::default()
+            },
+            Arc::new(|| Ok(Box::new(make_client()) as Box<dyn FileTransfer>)),
+        );
+        let mut failed: usize = 0;
+        let mut retried: usize = 0;
+        while let Ok(event) = pool.recv_timeout(Duration::from_secs(5)) {
+            match event {
+                PoolEvent::Retrying(..) => retried += 1,
+                PoolEvent::Finished(_, res) => {
+                    assert!(res.is_err());
+                    failed += 1;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(failed, 8);
+        assert_eq!(retried, 8);
+    }
+
+    #[test]
+    fn test_filetransfer_pool_transfer_file_cancelled() {
+        let target: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: MemoryFileTransfer = make_client();
+        client.add_file(Path::new("/srv/big.bin"), vec![0; 1048576].as_slice());
+        let file: FsFile = match client.stat(Path::new("/srv/big.bin")).unwrap() {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("not a file"),
+        };
+        let cancel: CancelToken = CancelToken::default();
+        let mut chunks: usize = 0;
+        let result = transfer_file(
+            &mut client,
+            TransferDirection::Download,
+            &file,
+            target.path().join("big.bin").as_path(),
+            &cancel,
+            &TransferOptions {
+                partial_files: PartialFilePolicy::Keep,
+                ..TransferOptions::default()
+            },
+            &mut |_| {
+                chunks += 1;
+                cancel.cancel();
+            },
+        );
+        assert_eq!(result.err().unwrap().as_str(), "Transfer interrupted");
+        // Stopped after the chunk during which it has been cancelled
+        assert_eq!(chunks, 1);
+        assert_eq!(
+            std::fs::metadata(target.path().join("big.bin"))
+                .unwrap()
+                .len(),
+            65536
+        );
+        // Session is still usable
+        assert!(client.stat(Path::new("/srv/file0.txt")).is_ok());
+    }
+
+    #[test]
+    fn test_filetransfer_pool_transfer_file_cancelled_partial() {
+        let target: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let mut client: MemoryFileTransfer = make_client();
+        client.add_file(Path::new("/srv/big.bin"), vec![0; 1048576].as_slice());
+        let file: FsFile = match client.stat(Path::new("/srv/big.bin")).unwrap() {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("not a file"),
+        };
+        let local: PathBuf = target.path().join("big.bin");
+        // Remove
+        let cancel: CancelToken = CancelToken::default();
+        let result = transfer_file(
+            &mut client,
+            TransferDirection::Download,
+            &file,
+            local.as_path(),
+            &cancel,
+            &TransferOptions::default(),
+            &mut |_| cancel.cancel(),
+        );
+        assert!(result
+            .err()
+            .unwrap()
+            .starts_with("Transfer interrupted; partial file"));
+        assert!(!local.exists());
+        // Mark
+        let cancel: CancelToken = CancelToken::default();
+        assert!(transfer_file(
+            &mut client,
+            TransferDirection::Download,
+            &file,
+            local.as_path(),
+            &cancel,
+            &TransferOptions {
+                partial_files: PartialFilePolicy::Mark,
+                ..TransferOptions::default()
+            },
+            &mut |_| cancel.cancel(),
+        )
+        .is_err());
+        assert!(!local.exists());
+        assert_eq!(
+            std::fs::metadata(target.path().join("big.bin.partial"))
+                .unwrap()
+                .len(),
+            65536
+        );
+        // Remove partial upload
+        assert!(discard_partial(
+            &mut client,
+            TransferDirection::Upload,
+            Path::new("/srv/big.bin"),
+            PartialFilePolicy::Remove
+        )
+        .unwrap()
+        .is_some());
+        assert!(client.stat(Path::new("/srv/big.bin")).is_err());
+        // Keep
+        assert_eq!(
+            discard_partial(
+                &mut client,
+                TransferDirection::Upload,
+                Path::new("/srv/file0.txt"),
+                PartialFilePolicy::Keep
+            ),
+            Ok(None)
+        );
+        assert!(client.stat(Path::new("/srv/file0.txt")).is_ok());
+    }
+
+    #[test]
+    fn test_filetransfer_pool_transfer_file_atomic_upload() {
+        let mut client: MemoryFileTransfer = make_client();
+        let local: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        std::fs::write(local.path().join("file0.txt"), b"new content\n").unwrap();
+        let host: Localhost = Localhost::new(local.path().to_path_buf()).ok().unwrap();
+        let file: FsFile = match host
+            .stat(local.path().join("file0.txt").as_path())
+            .ok()
+            .unwrap()
+        {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("not a file"),
+        };
+        let opts: TransferOptions = TransferOptions {
+            atomic_uploads: true,
+            ..TransferOptions::default()
+        };
+        // Aborted: the temporary file is removed, the existing file is untouched
+        let cancel: CancelToken = CancelToken::default();
+        cancel.cancel();
+        assert!(transfer_file(
+            &mut client,
+            TransferDirection::Upload,
+            &file,
+            Path::new("/srv/file0.txt"),
+            &cancel,
+            &opts,
+            &mut |_| {},
+        )
+        .is_err());
+        assert!(client.stat(Path::new("/srv/.file0.txt.part")).is_err());
+        assert_eq!(
+            client.stat(Path::new("/srv/file0.txt")).unwrap().get_size(),
+            18
+        );
+        // Completed: the temporary file replaces the existing one
+        assert!(transfer_file(
+            &mut client,
+            TransferDirection::Upload,
+            &file,
+            Path::new("/srv/file0.txt"),
+            &CancelToken::default(),
+            &opts,
+            &mut |_| {},
+        )
+        .is_ok());
+        assert!(client.stat(Path::new("/srv/.file0.txt.part")).is_err());
+        assert_eq!(
+            client.stat(Path::new("/srv/file0.txt")).unwrap().get_size(),
+            12
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_pool_connection_failed() {
+        let target: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let pool: TransferPool = TransferPool::new(
+            make_jobs(target.path()),
+            2,
+            &TransferOptions::default(),
+            Arc::new(|| Err(String::from("connection refused"))),
+        );
+        let mut failures: usize = 0;
+        while let Ok(event) = pool.recv_timeout(Duration::from_secs(5)) {
+            assert!(matches!(event, PoolEvent::ConnectionFailed(_)));
+            failures += 1;
+        }
+        assert_eq!(failures, 2);
+        // Jobs are given back
+        assert_eq!(pool.join().len(), 8);
+    }
+}
@@ -0,0 +1,208 @@
+//! ## Registry
+//!
+//! `registry` is the module which allows other crates to provide new file transfer protocols
+//! (e.g. plugins), registering a factory for their `FileTransfer` implementation
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{FileTransfer, FileTransferProtocol};
+// Ext
+use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
+
+/// ## FileTransferFactory
+///
+/// Function which instantiates a new, not connected, `FileTransfer` client
+pub type FileTransferFactory = Box<dyn Fn() -> Box<dyn FileTransfer> + Send + Sync>;
+
+/// ## ProtocolPlugin
+///
+/// A protocol registered by a plugin
+struct ProtocolPlugin {
+    scheme: &'static str,
+    default_port: u16,
+    factory: FileTransferFactory,
+}
+
+/// ## RegistryError
+///
+/// Describes why a protocol couldn't be registered
+#[allow(dead_code)] // Only used by other crates
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegistryError {
+    AlreadyRegistered,
+    InvalidScheme,
+}
+
+lazy_static! {
+    static ref PLUGINS: RwLock<Vec<ProtocolPlugin>> = RwLock::new(Vec::new());
+}
+
+/// ### register_protocol
+///
+/// Register a new protocol, identified by its scheme (e.g. `sshfs`, case insensitive).
+/// The scheme can't be one of the builtin protocols and must be alphanumeric, starting with a letter,
+/// so that it can be used in the address argument too (`sshfs://omar@192.168.1.31`).
+/// Once registered, the protocol is available as `FileTransferProtocol::Plugin(scheme)`
+#[allow(dead_code)] // Only used by other crates
+pub fn register_protocol<F>(
+    scheme: &'static str,
+    default_port: u16,
+    factory: F,
+) -> Result<(), RegistryError>
+where
+    F: Fn() -> Box<dyn FileTransfer> + Send + Sync + 'static,
+{
+    let mut chars = scheme.chars();
+    let valid: bool = matches!(chars.next(), Some(c) if c.is_ascii_alphabetic())
+        && chars.all(|c| c.is_ascii_alphanumeric());
+    if !valid {
+        return Err(RegistryError::InvalidScheme);
+    }
+    // Check builtin protocols and registered ones
+    if FileTransferProtocol::from_str(scheme).is_ok() {
+        return Err(RegistryError::AlreadyRegistered);
+    }
+    let mut plugins = PLUGINS.write().unwrap();
+    // Check again, since the lock was released
+    if plugins
+        .iter()
+        .any(|x| x.scheme.eq_ignore_ascii_case(scheme))
+    {
+        return Err(RegistryError::AlreadyRegistered);
+    }
+    plugins.push(ProtocolPlugin {
+        scheme,
+        default_port,
+        factory: Box::new(factory),
+    });
+    Ok(())
+}
+
+/// ### registered_protocols
+///
+/// Get the schemes of the registered protocols, in registration order
+pub fn registered_protocols() -> Vec<&'static str> {
+    PLUGINS.read().unwrap().iter().map(|x| x.scheme).collect()
+}
+
+/// ### lookup
+///
+/// Get the scheme of the registered protocol matching `scheme`, ignoring case
+pub(crate) fn lookup(scheme: &str) -> Option<&'static str> {
+    PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|x| x.scheme.eq_ignore_ascii_case(scheme))
+        .map(|x| x.scheme)
+}
+
+/// ### default_port
+///
+/// Get the default port of the registered protocol; 0 if not registered
+pub(crate) fn default_port(scheme: &str) -> u16 {
+    PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|x| x.scheme == scheme)
+        .map(|x| x.default_port)
+        .unwrap_or(0)
+}
+
+/// ### build
+///
+/// Instantiate a new client for the registered protocol
+pub(crate) fn build(scheme: &str) -> Option<Box<dyn FileTransfer>> {
+    PLUGINS
+        .read()
+        .unwrap()
+        .iter()
+        .find(|x| x.scheme == scheme)
+        .map(|x| (x.factory)())
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let err: &str = match self {
+            RegistryError::AlreadyRegistered => "A protocol with this scheme is already registered",
+            RegistryError::InvalidScheme => "Invalid scheme",
+        };
+        write!(f, "{}", err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+
+    #[test]
+    fn test_filetransfer_registry_register() {
+        assert!(register_protocol("memtest", 2222, || Box::new(MemoryFileTransfer::new())).is_ok());
+        assert!(registered_protocols().contains(&"memtest"));
+        assert_eq!(lookup("MEMTEST"), Some("memtest"));
+        assert_eq!(default_port("memtest"), 2222);
+        assert!(build("memtest").is_some());
+        // Protocol
+        let protocol: FileTransferProtocol = FileTransferProtocol::from_str("MemTest").unwrap();
+        assert_eq!(protocol, FileTransferProtocol::Plugin("memtest"));
+        assert_eq!(protocol.to_string(), String::from("MEMTEST"));
+        assert_eq!(protocol.default_port(), 2222);
+        assert!(FileTransferProtocol::available().contains(&protocol));
+    }
+
+    #[test]
+    fn test_filetransfer_registry_register_bad() {
+        assert_eq!(
+            register_protocol("sftp", 22, || Box::new(MemoryFileTransfer::new())).err(),
+            Some(RegistryError::AlreadyRegistered)
+        );
+        assert!(register_protocol("memdup", 22, || Box::new(MemoryFileTransfer::new())).is_ok());
+        assert_eq!(
+            register_protocol("MEMDUP", 22, || Box::new(MemoryFileTransfer::new())).err(),
+            Some(RegistryError::AlreadyRegistered)
+        );
+        assert_eq!(
+            register_protocol("", 22, || Box::new(MemoryFileTransfer::new())).err(),
+            Some(RegistryError::InvalidScheme)
+        );
+        assert_eq!(
+            register_protocol("1mem", 22, || Box::new(MemoryFileTransfer::new())).err(),
+            Some(RegistryError::InvalidScheme)
+        );
+        assert_eq!(
+            register_protocol("mem+ssh", 22, || Box::new(MemoryFileTransfer::new())).err(),
+            Some(RegistryError::InvalidScheme)
+        );
+        // Not registered
+        assert!(lookup("memnone").is_none());
+        assert_eq!(default_port("memnone"), 0);
+        assert!(build("memnone").is_none());
+    }
+}
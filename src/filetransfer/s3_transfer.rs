@@ -0,0 +1,1147 @@
+//! ## S3Transfer
+//!
+//! `s3_transfer` is the module which provides the implementation for the S3 file transfer (AWS and S3-compatible object storages)
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Dependencies
+extern crate chrono;
+extern crate hmac;
+extern crate percent_encoding;
+extern crate regex;
+extern crate sha2;
+extern crate tracing;
+extern crate ureq;
+
+use super::upload::{Upload, UploadStream};
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, NetworkOptions, TransferOutcome,
+};
+use crate::fs::{FsDirectory, FsEntry, FsFile};
+
+// Includes
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac, NewMac};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info_span, instrument, Span};
+use ureq::{Agent, Request, Response};
+
+/// Characters which must be percent-encoded in URIs and query strings
+const URI_ENCODE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Region used if none is provided
+const DEFAULT_REGION: &str = "us-east-1";
+
+/// Payload hash of requests whose body is not signed
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+lazy_static! {
+    /**
+     * Matches an object of a `ListObjectsV2` response
+     */
+    static ref CONTENTS_REGEX: Regex = Regex::new(r"(?s)<Contents>(.*?)</Contents>").unwrap();
+    /**
+     * Matches a common prefix (a directory) of a `ListObjectsV2` response
+     */
+    static ref PREFIX_REGEX: Regex =
+        Regex::new(r"(?s)<CommonPrefixes>\s*<Prefix>([^<]*)</Prefix>").unwrap();
+    /**
+     * Matches a leaf element (e.g. `<Key>a.txt</Key>`): 1. name; 2. text content
+     */
+    static ref LEAF_REGEX: Regex = Regex::new(r"<(\w+)>([^<]*)</\w+>").unwrap();
+}
+
+/// ## S3Object
+///
+/// An object described in a `ListObjectsV2` response
+#[derive(Debug, PartialEq)]
+struct S3Object {
+    key: String,
+    size: usize,
+    mtime: Option<SystemTime>,
+}
+
+/// ## ObjectList
+///
+/// The objects and the common prefixes described in a `ListObjectsV2` response
+#[derive(Debug, Default, PartialEq)]
+struct ObjectList {
+    objects: Vec<S3Object>,
+    prefixes: Vec<String>, // Common prefixes (directories); they end with a slash
+    continuation: Option<String>, // Token of the next page, if the response is truncated
+}
+
+/// ## S3FileTransfer
+///
+/// S3 file transfer struct. Works with AWS and with any S3-compatible object storage
+/// (e.g. MinIO, Wasabi, Backblaze B2), addressing the bucket with path-style URLs.
+/// Directories are emulated with key prefixes
+pub struct S3FileTransfer {
+    agent: Option<Agent>,
+    endpoint: String, // Endpoint URL (scheme, host and port)
+    region: String,
+    bucket: String,
+    credentials: Option<(String, String)>, // Access key id and secret access key
+    wrkdir: PathBuf,
+    upload: Option<Upload>, // Pending PUT request
+    network: NetworkOptions,
+    span: Span, // Session span; parent of each remote operation span
+}
+
+impl S3FileTransfer {
+    /// ### new
+    ///
+    /// Instantiates a new `S3FileTransfer`. If `endpoint` is `None`, the AWS endpoint of the region
+    /// is used. The region defaults to `us-east-1`
+    pub fn new(endpoint: Option<String>, region: Option<String>) -> S3FileTransfer {
+        let region: String = region
+            .filter(|x| !x.trim().is_empty())
+            .map(|x| x.trim().to_string())
+            .unwrap_or_else(|| String::from(DEFAULT_REGION));
+        let endpoint: String = match endpoint.as_ref().map(|x| x.trim().trim_end_matches('/')) {
+            Some(url) if url.contains("://") => url.to_string(),
+            Some(host) if !host.is_empty() => format!("https://{}", host),
+            _ => format!("https://s3.{}.amazonaws.com", region),
+        };
+        S3FileTransfer {
+            agent: None,
+            endpoint,
+            region,
+            bucket: String::new(),
+            credentials: None,
+            wrkdir: PathBuf::from("/"),
+            upload: None,
+            network: NetworkOptions::default(),
+            span: Span::none(),
+        }
+    }
+
+    /// ### with_network_options
+    ///
+    /// Set connection timeout and proxy
+    pub fn with_network_options(mut self, options: NetworkOptions) -> Self {
+        self.network = options;
+        self
+    }
+
+    /// ### resolve
+    ///
+    /// Get absolute and normalized path from `p` (relative paths are relative to wrkdir)
+    fn resolve(&self, p: &Path) -> PathBuf {
+        let mut path: PathBuf = PathBuf::from("/");
+        for component in self.wrkdir.join(p).components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::ParentDir => {
+                    path.pop();
+                }
+                _ => {}
+            }
+        }
+        path
+    }
+
+    /// ### key_of
+    ///
+    /// Get the key of the object at `p`. Directory keys end with a slash, except for the root
+    /// directory, whose key is empty
+    fn key_of(&self, p: &Path, dir: bool) -> String {
+        let mut key: String = self
+            .resolve(p)
+            .components()
+            .filter_map(|x| match x {
+                Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join("/");
+        if dir && !key.is_empty() {
+            key.push('/');
+        }
+        key
+    }
+
+    /// ### path_of
+    ///
+    /// Get the absolute path of the object with key `key`
+    fn path_of(key: &str) -> PathBuf {
+        PathBuf::from("/").join(key.trim_end_matches('/'))
+    }
+
+    /// ### host
+    ///
+    /// Get the value of the `Host` header from the endpoint
+    fn host(&self) -> &str {
+        let url: &str = match self.endpoint.split_once("://") {
+            Some((_, url)) => url,
+            None => self.endpoint.as_str(),
+        };
+        url.split('/').next().unwrap_or(url)
+    }
+
+    /// ### canonical_uri
+    ///
+    /// Get the URI of the object with key `key` (the bucket itself if empty)
+    fn canonical_uri(&self, key: &str) -> String {
+        let key: Vec<String> = key
+            .split('/')
+            .map(|x| utf8_percent_encode(x, URI_ENCODE).to_string())
+            .collect();
+        format!(
+            "/{}/{}",
+            utf8_percent_encode(self.bucket.as_str(), URI_ENCODE),
+            key.join("/")
+        )
+    }
+
+    /// ### canonical_query
+    ///
+    /// Make the query string from parameters, encoded and sorted as required by the signature
+    fn canonical_query(query: &[(&str, &str)]) -> String {
+        let mut params: Vec<String> = query
+            .iter()
+            .map(|(k, v)| {
+                format!(
+                    "{}={}",
+                    utf8_percent_encode(k, URI_ENCODE),
+                    utf8_percent_encode(v, URI_ENCODE)
+                )
+            })
+            .collect();
+        params.sort();
+        params.join("&")
+    }
+
+    /// ### canonical_request
+    ///
+    /// Make the canonical request, which is hashed to sign a request.
+    /// Headers must be sorted by name and names must be lowercase
+    fn canonical_request(
+        method: &str,
+        uri: &str,
+        query: &str,
+        headers: &[(&str, &str)],
+        payload_hash: &str,
+    ) -> String {
+        let canonical_headers: String = headers
+            .iter()
+            .map(|(k, v)| format!("{}:{}\n", k, v.trim()))
+            .collect();
+        let signed_headers: Vec<&str> = headers.iter().map(|(k, _)| *k).collect();
+        format!(
+            "{}\n{}\n{}\n{}\n{}\n{}",
+            method,
+            uri,
+            query,
+            canonical_headers,
+            signed_headers.join(";"),
+            payload_hash
+        )
+    }
+
+    /// ### signature
+    ///
+    /// Calculate the AWS signature (version 4) of the canonical request.
+    /// `amz_date` is the request time, formatted as `YYYYMMDD'T'HHMMSS'Z'`
+    fn signature(
+        secret_key: &str,
+        region: &str,
+        amz_date: &str,
+        canonical_request: &str,
+    ) -> String {
+        let date: &str = &amz_date[..8];
+        let scope: String = format!("{}/{}/s3/aws4_request", date, region);
+        let string_to_sign: String = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            Self::hex(Sha256::digest(canonical_request.as_bytes()).as_slice())
+        );
+        let mut key: Vec<u8> = format!("AWS4{}", secret_key).into_bytes();
+        for data in [date, region, "s3", "aws4_request"] {
+            key = Self::hmac_sha256(key.as_slice(), data);
+        }
+        Self::hex(Self::hmac_sha256(key.as_slice(), string_to_sign.as_str()).as_slice())
+    }
+
+    /// ### hmac_sha256
+    ///
+    /// Calculate the HMAC-SHA256 of `data`
+    fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+        let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC can take keys of any size");
+        mac.update(data.as_bytes());
+        mac.finalize().into_bytes().to_vec()
+    }
+
+    /// ### hex
+    ///
+    /// Format bytes as lowercase hex string
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|x| format!("{:02x}", x)).collect()
+    }
+
+    /// ### request
+    ///
+    /// Prepare a signed request for the object with key `key` (the bucket itself if empty).
+    /// Requests are not signed if the session has no credentials (public buckets)
+    fn request(
+        &self,
+        method: &str,
+        key: &str,
+        query: &[(&str, &str)],
+    ) -> Result<Request, FileTransferError> {
+        let agent: &Agent = match self.agent.as_ref() {
+            Some(agent) => agent,
+            None => {
+                return Err(FileTransferError::new(
+                    FileTransferErrorType::UninitializedSession,
+                ))
+            }
+        };
+        let uri: String = self.canonical_uri(key);
+        let query: String = Self::canonical_query(query);
+        let url: String = match query.is_empty() {
+            true => format!("{}{}", self.endpoint, uri),
+            false => format!("{}{}?{}", self.endpoint, uri, query),
+        };
+        let amz_date: String = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+        let request: Request = agent
+            .request(method, url.as_str())
+            .set("Host", self.host())
+            .set("x-amz-content-sha256", UNSIGNED_PAYLOAD)
+            .set("x-amz-date", amz_date.as_str());
+        Ok(match self.credentials.as_ref() {
+            Some((access_key, secret_key)) => {
+                let headers: [(&str, &str); 3] = [
+                    ("host", self.host()),
+                    ("x-amz-content-sha256", UNSIGNED_PAYLOAD),
+                    ("x-amz-date", amz_date.as_str()),
+                ];
+                let canonical_request: String = Self::canonical_request(
+                    method,
+                    uri.as_str(),
+                    query.as_str(),
+                    &headers,
+                    UNSIGNED_PAYLOAD,
+                );
+                let authorization: String = format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}/{}/s3/aws4_request, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+                    access_key,
+                    &amz_date[..8],
+                    self.region,
+                    Self::signature(
+                        secret_key.as_str(),
+                        self.region.as_str(),
+                        amz_date.as_str(),
+                        canonical_request.as_str()
+                    )
+                );
+                request.set("Authorization", authorization.as_str())
+            }
+            None => request,
+        })
+    }
+
+    /// ### list_page
+    ///
+    /// List the objects whose key starts with `prefix`, sending a single `ListObjectsV2` request.
+    /// If `delimiter` is true, the objects in "subdirectories" are grouped into common prefixes
+    fn list_page(
+        &self,
+        prefix: &str,
+        delimiter: bool,
+        max_keys: Option<usize>,
+        continuation: Option<&str>,
+    ) -> Result<ObjectList, FileTransferError> {
+        let max_keys: String = max_keys.map(|x| x.to_string()).unwrap_or_default();
+        let mut query: Vec<(&str, &str)> = vec![("list-type", "2"), ("prefix", prefix)];
+        if delimiter {
+            query.push(("delimiter", "/"));
+        }
+        if !max_keys.is_empty() {
+            query.push(("max-keys", max_keys.as_str()));
+        }
+        if let Some(token) = continuation {
+            query.push(("continuation-token", token));
+        }
+        let response: Response = self
+            .request("GET", "", query.as_slice())?
+            .call()
+            .map_err(Self::map_error)?;
+        match response.into_string() {
+            Ok(body) => Ok(Self::parse_object_list(body.as_str())),
+            Err(err) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::ProtocolError,
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// ### list_objects
+    ///
+    /// List all the objects whose key starts with `prefix`, following pagination
+    fn list_objects(&self, prefix: &str, delimiter: bool) -> Result<ObjectList, FileTransferError> {
+        let mut list: ObjectList = self.list_page(prefix, delimiter, None, None)?;
+        while let Some(token) = list.continuation.take() {
+            let mut page: ObjectList =
+                self.list_page(prefix, delimiter, None, Some(token.as_str()))?;
+            list.objects.append(&mut page.objects);
+            list.prefixes.append(&mut page.prefixes);
+            list.continuation = page.continuation;
+        }
+        Ok(list)
+    }
+
+    /// ### dir_exists
+    ///
+    /// Returns whether there is any object with prefix `prefix` (a directory key)
+    fn dir_exists(&self, prefix: &str) -> Result<bool, FileTransferError> {
+        if prefix.is_empty() {
+            return Ok(true);
+        }
+        let list: ObjectList = self.list_page(prefix, true, Some(1), None)?;
+        Ok(!list.objects.is_empty() || !list.prefixes.is_empty())
+    }
+
+    /// ### stat_path
+    ///
+    /// Stat the object at `p`; if there's no such object, but some objects have its key as
+    /// prefix, `p` is a directory
+    fn stat_path(&self, p: &Path) -> Result<FsEntry, FileTransferError> {
+        let path: PathBuf = self.resolve(p);
+        let key: String = self.key_of(path.as_path(), false);
+        if key.is_empty() {
+            return Ok(Self::make_directory(path));
+        }
+        match self.request("HEAD", key.as_str(), &[])?.call() {
+            Ok(response) => {
+                let size: usize = response
+                    .header("Content-Length")
+                    .and_then(|x| x.parse::<usize>().ok())
+                    .unwrap_or(0);
+                let mtime: Option<SystemTime> = response
+                    .header("Last-Modified")
+                    .and_then(|x| DateTime::parse_from_rfc2822(x).ok())
+                    .map(SystemTime::from);
+                Ok(Self::make_file(path, size, mtime))
+            }
+            Err(ureq::Error::Status(404, _)) => {
+                match self.dir_exists(self.key_of(path.as_path(), true).as_str())? {
+                    true => Ok(Self::make_directory(path)),
+                    false => Err(FileTransferError::new(
+                        FileTransferErrorType::NoSuchFileOrDirectory,
+                    )),
+                }
+            }
+            Err(err) => Err(Self::map_error(err)),
+        }
+    }
+
+    /// ### copy_object
+    ///
+    /// Copy object with key `src` to `dst` (server side)
+    fn copy_object(&self, src: &str, dst: &str) -> Result<(), FileTransferError> {
+        let source: String = self.canonical_uri(src);
+        let response: Response = self
+            .request("PUT", dst, &[])?
+            .set("x-amz-copy-source", source.as_str())
+            .send_bytes(&[])
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())
+    }
+
+    /// ### copy_entry
+    ///
+    /// Copy file or directory to `dst`. Directories are copied object by object
+    fn copy_entry(&self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        match src {
+            FsEntry::File(file) => self.copy_object(
+                self.key_of(file.abs_path.as_path(), false).as_str(),
+                self.key_of(dst, false).as_str(),
+            ),
+            FsEntry::Directory(dir) => {
+                let src_prefix: String = self.key_of(dir.abs_path.as_path(), true);
+                let dst_prefix: String = self.key_of(dst, true);
+                if dst_prefix.starts_with(src_prefix.as_str()) {
+                    return Err(FileTransferError::new_ex(
+                        FileTransferErrorType::FileCreateDenied,
+                        String::from("Cannot copy a directory into itself"),
+                    ));
+                }
+                for object in self.list_objects(src_prefix.as_str(), false)?.objects {
+                    let dst_key: String =
+                        format!("{}{}", dst_prefix, &object.key[src_prefix.len()..]);
+                    self.copy_object(object.key.as_str(), dst_key.as_str())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// ### delete_object
+    ///
+    /// Delete object with key `key`
+    fn delete_object(&self, key: &str) -> Result<(), FileTransferError> {
+        let response: Response = self
+            .request("DELETE", key, &[])?
+            .call()
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())
+    }
+
+    /// ### check_status
+    ///
+    /// Convert a status code different from 2xx into an error
+    fn check_status(status: u16) -> Result<(), FileTransferError> {
+        match status {
+            200..=299 => Ok(()),
+            status => Err(Self::status_error(status, None)),
+        }
+    }
+
+    /// ### status_error
+    ///
+    /// Make error from HTTP status code and from the message of the error response, if any
+    fn status_error(status: u16, message: Option<String>) -> FileTransferError {
+        let kind: FileTransferErrorType = match status {
+            301 | 307 => FileTransferErrorType::ConnectionError, // Bucket is in another region
+            401 | 403 => FileTransferErrorType::PexError,
+            404 => FileTransferErrorType::NoSuchFileOrDirectory,
+            405 | 501 => FileTransferErrorType::UnsupportedFeature,
+            _ => FileTransferErrorType::ProtocolError,
+        };
+        FileTransferError::new_ex(
+            kind,
+            match message {
+                Some(message) => format!("HTTP status {}: {}", status, message),
+                None => format!("HTTP status {}", status),
+            },
+        )
+    }
+
+    /// ### map_error
+    ///
+    /// Convert ureq error into `FileTransferError`
+    fn map_error(err: ureq::Error) -> FileTransferError {
+        match err {
+            ureq::Error::Status(status, response) => {
+                let message: Option<String> = response
+                    .into_string()
+                    .ok()
+                    .and_then(|body| Self::parse_error_message(body.as_str()));
+                Self::status_error(status, message)
+            }
+            ureq::Error::Transport(err) => {
+                FileTransferError::new_ex(FileTransferErrorType::ConnectionError, err.to_string())
+            }
+        }
+    }
+
+    /// ### parse_error_message
+    ///
+    /// Get the message of an error response body
+    fn parse_error_message(body: &str) -> Option<String> {
+        LEAF_REGEX
+            .captures_iter(body)
+            .find(|x| &x[1] == "Message")
+            .map(|x| Self::unescape(&x[2]))
+    }
+
+    /// ### parse_object_list
+    ///
+    /// Parse the body of a `ListObjectsV2` response
+    fn parse_object_list(body: &str) -> ObjectList {
+        let objects: Vec<S3Object> = CONTENTS_REGEX
+            .captures_iter(body)
+            .map(|contents| {
+                let mut object: S3Object = S3Object {
+                    key: String::new(),
+                    size: 0,
+                    mtime: None,
+                };
+                for leaf in LEAF_REGEX.captures_iter(&contents[1]) {
+                    let text: &str = leaf[2].trim();
+                    match &leaf[1] {
+                        "Key" => object.key = Self::unescape(text),
+                        "Size" => object.size = text.parse::<usize>().unwrap_or(0),
+                        "LastModified" => {
+                            object.mtime = DateTime::parse_from_rfc3339(text)
+                                .ok()
+                                .map(SystemTime::from)
+                        }
+                        _ => {}
+                    }
+                }
+                object
+            })
+            .collect();
+        let prefixes: Vec<String> = PREFIX_REGEX
+            .captures_iter(body)
+            .map(|x| Self::unescape(&x[1]))
+            .collect();
+        let mut truncated: bool = false;
+        let mut continuation: Option<String> = None;
+        for leaf in LEAF_REGEX.captures_iter(body) {
+            match &leaf[1] {
+                "IsTruncated" => truncated = leaf[2].trim() == "true",
+                "NextContinuationToken" => continuation = Some(Self::unescape(&leaf[2])),
+                _ => {}
+            }
+        }
+        ObjectList {
+            objects,
+            prefixes,
+            continuation: continuation.filter(|_| truncated),
+        }
+    }
+
+    /// ### unescape
+    ///
+    /// Replace XML entities in text
+    fn unescape(text: &str) -> String {
+        text.replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&")
+    }
+
+    /// ### make_directory
+    ///
+    /// Make directory entry at `path`
+    fn make_directory(path: PathBuf) -> FsEntry {
+        FsEntry::Directory(FsDirectory {
+            name: match path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => String::from("/"),
+            },
+            abs_path: path,
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            readonly: false,
+            symlink: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        })
+    }
+
+    /// ### make_file
+    ///
+    /// Make file entry at `path`
+    fn make_file(path: PathBuf, size: usize, mtime: Option<SystemTime>) -> FsEntry {
+        let mtime: SystemTime = mtime.unwrap_or(SystemTime::UNIX_EPOCH);
+        FsEntry::File(FsFile {
+            name: match path.file_name() {
+                Some(name) => name.to_string_lossy().to_string(),
+                None => String::from("/"),
+            },
+            ftype: path.extension().map(|x| x.to_string_lossy().to_string()),
+            abs_path: path,
+            last_change_time: mtime,
+            last_access_time: mtime,
+            creation_time: mtime,
+            size,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        })
+    }
+}
+
+impl FileTransfer for S3FileTransfer {
+    /// ### connect
+    ///
+    /// Connect to the remote server.
+    /// `address` is the bucket name, `username` and `password` are the access key id and the
+    /// secret access key; port is not used, since it's part of the endpoint.
+    /// HTTP is stateless, so the bucket is listed to check whether it exists and the credentials are valid
+
+    #[instrument(skip_all, fields(bucket = %address), err)]
+    fn connect(
+        &mut self,
+        address: String,
+        _port: u16,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Option<String>, FileTransferError> {
+        let agent: Agent = self.network.http_agent()?.redirects(0).build();
+        self.bucket = address.trim().trim_matches('/').to_string();
+        self.credentials = username
+            .filter(|x| !x.is_empty())
+            .map(|access_key| (access_key, password.unwrap_or_default()));
+        self.agent = Some(agent);
+        self.wrkdir = PathBuf::from("/");
+        match self.list_page("", true, Some(1), None) {
+            Ok(_) => {
+                // Open session span
+                self.span = info_span!("session", protocol = "S3", host = %self.endpoint, bucket = %self.bucket);
+                Ok(None)
+            }
+            Err(err) => {
+                self.agent = None;
+                self.credentials = None;
+                match err.code {
+                    FileTransferErrorType::PexError => Err(FileTransferError {
+                        code: FileTransferErrorType::AuthenticationFailed,
+                        msg: err.msg,
+                    }),
+                    _ => Err(err),
+                }
+            }
+        }
+    }
+
+    /// ### disconnect
+    ///
+    /// Disconnect from the remote server
+
+    #[instrument(parent = &self.span, skip_all, err)]
+    fn disconnect(&mut self) -> Result<(), FileTransferError> {
+        match self.agent.take() {
+            Some(_) => {
+                self.credentials = None;
+                // Close session span
+                self.span = Span::none();
+                Ok(())
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### is_connected
+    ///
+    /// Indicates whether the client is connected to remote
+    fn is_connected(&self) -> bool {
+        self.agent.is_some()
+    }
+
+    /// ### pwd
+    ///
+    /// Print working directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
+    fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
+        match self.is_connected() {
+            true => Ok(self.wrkdir.clone()),
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### change_dir
+    ///
+    /// Change working directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
+        let path: PathBuf = self.resolve(dir);
+        match self.dir_exists(self.key_of(path.as_path(), true).as_str())? {
+            true => {
+                self.wrkdir = path;
+                Ok(self.wrkdir.clone())
+            }
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### copy
+    ///
+    /// Copy file to destination
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        self.copy_entry(src, dst)
+    }
+
+    /// ### list_dir
+    ///
+    /// List directory entries
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
+        let prefix: String = self.key_of(path, true);
+        let list: ObjectList = self.list_objects(prefix.as_str(), true)?;
+        let mut entries: Vec<FsEntry> = list
+            .prefixes
+            .iter()
+            .map(|x| Self::make_directory(Self::path_of(x.as_str())))
+            .collect();
+        entries.extend(
+            list.objects
+                .into_iter()
+                .filter(|x| x.key != prefix) // Directory marker
+                .map(|x| Self::make_file(Self::path_of(x.key.as_str()), x.size, x.mtime)),
+        );
+        Ok(entries)
+    }
+
+    /// ### mkdir
+    ///
+    /// Make directory, creating an empty object whose key ends with a slash
+    /// In case the directory already exists, an error of kind `FileTransferErrorType::FileCreateDenied` is returned
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        let key: String = self.key_of(dir, true);
+        if self.dir_exists(key.as_str())? {
+            return Err(FileTransferError::new_ex(
+                FileTransferErrorType::FileCreateDenied,
+                String::from("Directory already exists"),
+            ));
+        }
+        let response: Response = self
+            .request("PUT", key.as_str(), &[])?
+            .send_bytes(&[])
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())
+    }
+
+    /// ### remove
+    ///
+    /// Remove a file or a directory (all the objects with its prefix are removed)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %file.get_abs_path().display()), err)]
+    fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        match file {
+            FsEntry::File(file) => {
+                self.delete_object(self.key_of(file.abs_path.as_path(), false).as_str())
+            }
+            FsEntry::Directory(dir) => {
+                let prefix: String = self.key_of(dir.abs_path.as_path(), true);
+                for object in self.list_objects(prefix.as_str(), false)?.objects {
+                    self.delete_object(object.key.as_str())?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// ### rename
+    ///
+    /// Rename file or a directory. S3 can't move objects, so they're copied and then removed
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
+    fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        self.copy_entry(file, dst)?;
+        self.remove(file)
+    }
+
+    /// ### stat
+    ///
+    /// Stat file and return FsEntry
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
+        self.stat_path(path)
+    }
+
+    /// ### exec
+    ///
+    /// Execute a command on remote host (not supported by S3)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
+    fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### send_file
+    ///
+    /// Send file to remote
+    /// File name is referred to the name of the file as it will be saved
+    /// Data contains the file data.
+    /// The PUT request is sent by a worker thread, which is fed by the returned stream
+    /// and joined by `finalize_transfer`
+
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
+    fn send_file(
+        &mut self,
+        local: &FsFile,
+        file_name: &Path,
+    ) -> Result<Box<dyn Write>, FileTransferError> {
+        // Size must be known in advance; NOTE: don't use the `size` attribute, since might be out of sync
+        let size: u64 = match std::fs::metadata(local.abs_path.as_path()) {
+            Ok(metadata) => metadata.len(),
+            Err(_) => local.size as u64,
+        };
+        let request: Request = self
+            .request("PUT", self.key_of(file_name, false).as_str(), &[])?
+            .set("Content-Length", size.to_string().as_str())
+            .set("Content-Type", "application/octet-stream");
+        let (upload, stream): (Upload, UploadStream) = Upload::start(move |body| {
+            let response: Response = request.send(body).map_err(Self::map_error)?;
+            Self::check_status(response.status())
+        });
+        self.upload = Some(upload);
+        Ok(Box::new(stream))
+    }
+
+    /// ### recv_file
+    ///
+    /// Receive file from remote with provided name
+
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
+    fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
+        let response: Response = self
+            .request(
+                "GET",
+                self.key_of(file.abs_path.as_path(), false).as_str(),
+                &[],
+            )?
+            .call()
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())?;
+        Ok(Box::new(response.into_reader()))
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer: for uploads, close the stream and wait for the server response.
+    /// If the upload hasn't been completed, the request body is interrupted, so that the server discards it
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(bytes = outcome.bytes), err)]
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        let completed: bool = outcome.is_completed();
+        // Dropping the stream terminates the request body (or the response, for downloads)
+        drop(outcome.stream);
+        match self.upload.take() {
+            Some(upload) => upload.finish(completed),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_filetransfer_s3_new() {
+        let client: S3FileTransfer = S3FileTransfer::new(None, None);
+        assert!(client.agent.is_none());
+        assert_eq!(
+            client.endpoint.as_str(),
+            "https://s3.us-east-1.amazonaws.com"
+        );
+        assert_eq!(client.region.as_str(), "us-east-1");
+        assert_eq!(client.host(), "s3.us-east-1.amazonaws.com");
+        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert!(!client.is_connected());
+        let client: S3FileTransfer = S3FileTransfer::new(None, Some(String::from("eu-west-1")));
+        assert_eq!(
+            client.endpoint.as_str(),
+            "https://s3.eu-west-1.amazonaws.com"
+        );
+        // Custom endpoint
+        let client: S3FileTransfer = S3FileTransfer::new(
+            Some(String::from("http://127.0.0.1:9000/")),
+            Some(String::new()),
+        );
+        assert_eq!(client.endpoint.as_str(), "http://127.0.0.1:9000");
+        assert_eq!(client.region.as_str(), "us-east-1");
+        assert_eq!(client.host(), "127.0.0.1:9000");
+        let client: S3FileTransfer = S3FileTransfer::new(
+            Some(String::from("s3.us-west-004.backblazeb2.com")),
+            Some(String::from("us-west-004")),
+        );
+        assert_eq!(
+            client.endpoint.as_str(),
+            "https://s3.us-west-004.backblazeb2.com"
+        );
+        assert_eq!(client.region.as_str(), "us-west-004");
+    }
+
+    #[test]
+    fn test_filetransfer_s3_keys() {
+        let mut client: S3FileTransfer = S3FileTransfer::new(None, None);
+        client.bucket = String::from("my-bucket");
+        client.wrkdir = PathBuf::from("/photos");
+        assert_eq!(client.key_of(Path::new("/"), true).as_str(), "");
+        assert_eq!(client.key_of(Path::new("/"), false).as_str(), "");
+        assert_eq!(
+            client.key_of(Path::new("2021"), true).as_str(),
+            "photos/2021/"
+        );
+        assert_eq!(
+            client.key_of(Path::new("../docs/a b.txt"), false).as_str(),
+            "docs/a b.txt"
+        );
+        assert_eq!(
+            S3FileTransfer::path_of("photos/2021/"),
+            PathBuf::from("/photos/2021")
+        );
+        assert_eq!(client.canonical_uri("").as_str(), "/my-bucket/");
+        assert_eq!(
+            client.canonical_uri("docs/a b+c.txt").as_str(),
+            "/my-bucket/docs/a%20b%2Bc.txt"
+        );
+        assert_eq!(
+            S3FileTransfer::canonical_query(&[("prefix", "a/b c/"), ("list-type", "2")]).as_str(),
+            "list-type=2&prefix=a%2Fb%20c%2F"
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_s3_signature() {
+        // Example from AWS documentation (GET object)
+        let canonical_request: String = S3FileTransfer::canonical_request(
+            "GET",
+            "/test.txt",
+            "",
+            &[
+                ("host", "examplebucket.s3.amazonaws.com"),
+                ("range", "bytes=0-9"),
+                (
+                    "x-amz-content-sha256",
+                    "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+                ),
+                ("x-amz-date", "20130524T000000Z"),
+            ],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        );
+        assert_eq!(
+            canonical_request.as_str(),
+            "GET\n/test.txt\n\nhost:examplebucket.s3.amazonaws.com\nrange:bytes=0-9\nx-amz-content-sha256:e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855\nx-amz-date:20130524T000000Z\n\nhost;range;x-amz-content-sha256;x-amz-date\ne3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(
+            S3FileTransfer::signature(
+                "wJalrXUtnFEMI/K7MDENG/bPxRfiCYEXAMPLEKEY",
+                "us-east-1",
+                "20130524T000000Z",
+                canonical_request.as_str()
+            )
+            .as_str(),
+            "f0e8bdb87c964420e857bd35b5d6ed310bd44f0170aba48dd91039c6036bdb41"
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_s3_parse_object_list() {
+        let body: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<ListBucketResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+  <Name>my-bucket</Name>
+  <Prefix>photos/</Prefix>
+  <KeyCount>3</KeyCount>
+  <MaxKeys>1000</MaxKeys>
+  <Delimiter>/</Delimiter>
+  <IsTruncated>true</IsTruncated>
+  <NextContinuationToken>1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=</NextContinuationToken>
+  <Contents>
+    <Key>photos/</Key>
+    <LastModified>2021-01-18T08:30:00.000Z</LastModified>
+    <Size>0</Size>
+  </Contents>
+  <Contents>
+    <Key>photos/Tom&amp;Jerry.png</Key>
+    <LastModified>2021-01-20T10:00:00.000Z</LastModified>
+    <ETag>"599bab3ed2c697f1d26842727561fd94"</ETag>
+    <Size>8192</Size>
+    <StorageClass>STANDARD</StorageClass>
+  </Contents>
+  <CommonPrefixes>
+    <Prefix>photos/2021/</Prefix>
+  </CommonPrefixes>
+</ListBucketResult>"#;
+        let list: ObjectList = S3FileTransfer::parse_object_list(body);
+        assert_eq!(list.objects.len(), 2);
+        assert_eq!(list.objects[0].key.as_str(), "photos/");
+        assert_eq!(list.objects[1].key.as_str(), "photos/Tom&Jerry.png");
+        assert_eq!(list.objects[1].size, 8192);
+        assert_eq!(
+            list.objects[1].mtime.unwrap(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1611136800)
+        );
+        assert_eq!(list.prefixes, vec![String::from("photos/2021/")]);
+        assert_eq!(
+            list.continuation.as_deref(),
+            Some("1ueGcxLPRx1Tr/XYExHnhbYLgveDs2J/wm36Hy4vbOwM=")
+        );
+        // Not truncated
+        let list: ObjectList = S3FileTransfer::parse_object_list(
+            "<ListBucketResult><IsTruncated>false</IsTruncated></ListBucketResult>",
+        );
+        assert_eq!(list, ObjectList::default());
+        // Error message
+        assert_eq!(
+            S3FileTransfer::parse_error_message(
+                "<Error><Code>NoSuchBucket</Code><Message>The specified bucket does not exist</Message></Error>"
+            )
+            .as_deref(),
+            Some("The specified bucket does not exist")
+        );
+        assert!(S3FileTransfer::parse_error_message("").is_none());
+    }
+
+    #[test]
+    fn test_filetransfer_s3_status_error() {
+        assert!(matches!(
+            S3FileTransfer::status_error(403, None).code,
+            FileTransferErrorType::PexError
+        ));
+        assert!(matches!(
+            S3FileTransfer::status_error(404, Some(String::from("No such key"))).code,
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        assert_eq!(
+            S3FileTransfer::status_error(301, Some(String::from("Wrong region")))
+                .msg
+                .unwrap()
+                .as_str(),
+            "HTTP status 301: Wrong region"
+        );
+        assert!(S3FileTransfer::check_status(204).is_ok());
+        assert!(S3FileTransfer::check_status(500).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_s3_uninitialized() {
+        let mut client: S3FileTransfer = S3FileTransfer::new(None, None);
+        assert!(client.pwd().is_err());
+        assert!(client.list_dir(Path::new("/")).is_err());
+        assert!(client.stat(Path::new("/a.txt")).is_err());
+        assert!(client.disconnect().is_err());
+        assert!(client.exec("echo 5").is_err());
+    }
+}
@@ -26,23 +26,30 @@
  * SOFTWARE.
  */
 // Dependencies
-extern crate regex;
 extern crate ssh2;
+extern crate tracing;
 
 // Locals
-use super::{FileTransfer, FileTransferError, FileTransferErrorType};
+use super::keyboard_interactive;
+use super::sftp_transfer::{ssh_device_id, ssh_identity, ssh_method_prefs, ssh_session_info};
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, NetworkOptions, ServerIdentity,
+    SshAlgorithms, TransferOutcome,
+};
 use crate::fs::{FsDirectory, FsEntry, FsFile};
+use crate::system::known_hosts::{self, KnownHost};
 use crate::system::sshkey_storage::SshKeyStorage;
-use crate::utils::parser::parse_lstime;
+use crate::utils::fmt::fmt_utc_time;
+use crate::utils::ls::{parse_unix_ls_line, LsEntry, LsParseError};
+use crate::utils::shell::quote_path;
 
 // Includes
-use regex::Regex;
 use ssh2::{Channel, Session};
 use std::io::{BufReader, BufWriter, Read, Write};
-use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
-use std::ops::Range;
+use std::net::{SocketAddr, TcpStream};
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
+use tracing::{info_span, instrument, warn, Span};
 
 /// ## ScpFileTransfer
 ///
@@ -51,6 +58,22 @@ pub struct ScpFileTransfer {
     session: Option<Session>,
     wrkdir: PathBuf,
     key_storage: SshKeyStorage,
+    kbdint_responses: Vec<String>, // Responses for keyboard-interactive prompts
+    known_hosts: Option<PathBuf>,  // Known hosts file; host key is not verified if `None`
+    accepted_host_key: Option<KnownHost>, // Host key accepted by the user; trusted at next connect
+    network: NetworkOptions,
+    algorithms: SshAlgorithms, // Preferred algorithms, applied before handshake
+    identity: ServerIdentity,
+    gnu_ls: bool, // Whether remote `ls` supports `--time-style`
+    span: Span,   // Session span; parent of each remote operation span
+}
+
+/// ## ScpUpload
+///
+/// Stream of a file sent with `scp`. Once dropped, it waits for the remote `scp` to exit,
+/// so that the file is complete when the transfer is finalized
+struct ScpUpload {
+    channel: Channel,
 }
 
 impl ScpFileTransfer {
@@ -62,166 +85,158 @@ impl ScpFileTransfer {
             session: None,
             wrkdir: PathBuf::from("~"),
             key_storage,
+            kbdint_responses: Vec::new(),
+            known_hosts: None,
+            accepted_host_key: None,
+            network: NetworkOptions::default(),
+            algorithms: SshAlgorithms::default(),
+            identity: ServerIdentity::default(),
+            gnu_ls: false,
+            span: Span::none(),
         }
     }
 
+    /// ### with_known_hosts
+    ///
+    /// Set known hosts file used to verify the host key after handshake
+    pub fn with_known_hosts(mut self, path: Option<PathBuf>) -> Self {
+        self.known_hosts = path;
+        self
+    }
+
+    /// ### with_network_options
+    ///
+    /// Set connection timeout and keepalive interval
+    pub fn with_network_options(mut self, options: NetworkOptions) -> Self {
+        self.network = options;
+        self
+    }
+
+    /// ### with_ssh_algorithms
+    ///
+    /// Set the preferred key exchange, host key, cipher and MAC algorithms
+    pub fn with_ssh_algorithms(mut self, algorithms: SshAlgorithms) -> Self {
+        self.algorithms = algorithms;
+        self
+    }
+
     /// ### parse_ls_output
     ///
     /// Parse a line of `ls -l` output and tokenize the output into a `FsEntry`
-    fn parse_ls_output(&mut self, path: &Path, line: &str) -> Result<FsEntry, ()> {
-        // Prepare list regex
-        // NOTE: about this damn regex <https://stackoverflow.com/questions/32480890/is-there-a-regex-to-parse-the-values-from-an-ftp-directory-listing>
-        lazy_static! {
-            static ref LS_RE: Regex = Regex::new(r#"^([\-ld])([\-rwxs]{9})\s+(\d+)\s+(\w+)\s+(\w+)\s+(\d+)\s+(\w{3}\s+\d{1,2}\s+(?:\d{1,2}:\d{1,2}|\d{4}))\s+(.+)$"#).unwrap();
-        }
-        // Apply regex to result
-        match LS_RE.captures(line) {
-            // String matches regex
-            Some(metadata) => {
-                // NOTE: metadata fmt: (regex, file_type, permissions, link_count, uid, gid, filesize, mtime, filename)
-                // Expected 7 + 1 (8) values: + 1 cause regex is repeated at 0
-                if metadata.len() < 8 {
-                    return Err(());
-                }
-                // Collect metadata
-                // Get if is directory and if is symlink
-                let (mut is_dir, is_symlink): (bool, bool) = match metadata.get(1).unwrap().as_str()
-                {
-                    "-" => (false, false),
-                    "l" => (false, true),
-                    "d" => (true, false),
-                    _ => return Err(()), // Ignore special files
-                };
-                // Check string length (unix pex)
-                if metadata.get(2).unwrap().as_str().len() < 9 {
-                    return Err(());
-                }
-
-                let pex = |range: Range<usize>| {
-                    let mut count: u8 = 0;
-                    for (i, c) in metadata.get(2).unwrap().as_str()[range].chars().enumerate() {
-                        match c {
-                            '-' => {}
-                            _ => {
-                                count += match i {
-                                    0 => 4,
-                                    1 => 2,
-                                    2 => 1,
-                                    _ => 0,
+    fn parse_ls_output(&mut self, path: &Path, line: &str) -> Result<FsEntry, LsParseError> {
+        let mut entry: LsEntry = parse_unix_ls_line(line)?;
+        // Get symlink; PATH mustn't be equal to filename
+        let mut broken_link: Option<PathBuf> = None;
+        let symlink: Option<Box<FsEntry>> = match entry.symlink.clone() {
+            None => None,
+            Some(p) => match p.file_name().unwrap_or_else(|| std::ffi::OsStr::new(""))
+                == entry.name.as_str()
+            {
+                // If name is equal, don't stat path; otherwise it would get stuck
+                true => None,
+                false => {
+                    // Relative targets are relative to the directory of the symlink
+                    let target: PathBuf = path.join(p);
+                    // stat fails on directories; links to directories are directories, as with sftp
+                    match self.is_remote_dir(target.as_path()) {
+                        true => {
+                            entry.is_dir = true;
+                            Some(Box::new(Self::make_dir_entry(target.as_path())))
+                        }
+                        false => match self.stat(target.as_path()) {
+                            Ok(e) => Some(Box::new(e)),
+                            Err(_) => {
+                                if !self.exists(target.as_path()) {
+                                    broken_link = Some(target);
                                 }
+                                None
                             }
-                        }
+                        },
                     }
-                    count
-                };
+                }
+            },
+        };
+        let mut entry: FsEntry = entry.into_fsentry(path, symlink);
+        if let FsEntry::File(file) = &mut entry {
+            file.broken_link = broken_link;
+        }
+        Ok(entry)
+    }
 
-                // Get unix pex
-                let unix_pex = (pex(0..3), pex(3..6), pex(6..9));
+    /// ### exists
+    ///
+    /// Returns whether `path` exists on the remote host; symlinks are followed
+    fn exists(&mut self, path: &Path) -> bool {
+        let wrkdir: PathBuf = self.wrkdir.clone();
+        match self.perform_shell_cmd_with_path(
+            wrkdir.as_path(),
+            format!("test -e {}; echo $?", quote_path(path)).as_str(),
+        ) {
+            Ok(output) => output.trim() == "0",
+            Err(_) => true, // Don't report links as broken if the check fails
+        }
+    }
 
-                // Parse mtime and convert to SystemTime
-                let mtime: SystemTime = match parse_lstime(
-                    metadata.get(7).unwrap().as_str(),
-                    "%b %d %Y",
-                    "%b %d %H:%M",
-                ) {
-                    Ok(t) => t,
-                    Err(_) => SystemTime::UNIX_EPOCH,
-                };
-                // Get uid
-                let uid: Option<u32> = match metadata.get(4).unwrap().as_str().parse::<u32>() {
-                    Ok(uid) => Some(uid),
-                    Err(_) => None,
-                };
-                // Get gid
-                let gid: Option<u32> = match metadata.get(5).unwrap().as_str().parse::<u32>() {
-                    Ok(gid) => Some(gid),
-                    Err(_) => None,
-                };
-                // Get filesize
-                let filesize: usize = metadata
-                    .get(6)
-                    .unwrap()
-                    .as_str()
-                    .parse::<usize>()
-                    .unwrap_or(0);
-                // Get link and name
-                let (file_name, symlink_path): (String, Option<PathBuf>) = match is_symlink {
-                    true => self.get_name_and_link(metadata.get(8).unwrap().as_str()),
-                    false => (String::from(metadata.get(8).unwrap().as_str()), None),
-                };
-                // Check if symlink points to a directory
-                if let Some(symlink_path) = symlink_path.as_ref() {
-                    is_dir = symlink_path.is_dir();
-                }
-                // Get symlink; PATH mustn't be equal to filename
-                let symlink: Option<Box<FsEntry>> = match symlink_path {
-                    None => None,
-                    Some(p) => match p.file_name().unwrap_or(&std::ffi::OsStr::new(""))
-                        == file_name.as_str()
-                    {
-                        // If name is equal, don't stat path; otherwise it would get stuck
-                        true => None,
-                        false => match self.stat(p.as_path()) {
-                            // If path match filename
-                            Ok(e) => Some(Box::new(e)),
-                            Err(_) => None, // Ignore errors
-                        },
-                    },
-                };
-                // Check if file_name is '.' or '..'
-                if file_name.as_str() == "." || file_name.as_str() == ".." {
-                    return Err(());
-                }
-                let mut abs_path: PathBuf = PathBuf::from(path);
-                abs_path.push(file_name.as_str());
-                // Get extension
-                let extension: Option<String> = abs_path
-                    .as_path()
-                    .extension()
-                    .map(|s| String::from(s.to_string_lossy()));
-                // Return
-                // Push to entries
-                Ok(match is_dir {
-                    true => FsEntry::Directory(FsDirectory {
-                        name: file_name,
-                        abs_path,
-                        last_change_time: mtime,
-                        last_access_time: mtime,
-                        creation_time: mtime,
-                        readonly: false,
-                        symlink,
-                        user: uid,
-                        group: gid,
-                        unix_pex: Some(unix_pex),
-                    }),
-                    false => FsEntry::File(FsFile {
-                        name: file_name,
-                        abs_path,
-                        last_change_time: mtime,
-                        last_access_time: mtime,
-                        creation_time: mtime,
-                        size: filesize,
-                        ftype: extension,
-                        readonly: false,
-                        symlink,
-                        user: uid,
-                        group: gid,
-                        unix_pex: Some(unix_pex),
-                    }),
-                })
-            }
-            None => Err(()),
+    /// ### is_remote_dir
+    ///
+    /// Returns whether `path` is a directory on the remote host; symlinks are followed
+    fn is_remote_dir(&mut self, path: &Path) -> bool {
+        let wrkdir: PathBuf = self.wrkdir.clone();
+        match self.perform_shell_cmd_with_path(
+            wrkdir.as_path(),
+            format!("test -d {}; echo $?", quote_path(path)).as_str(),
+        ) {
+            Ok(output) => output.trim() == "0",
+            Err(_) => false,
         }
     }
 
-    /// ### get_name_and_link
+    /// ### make_dir_entry
     ///
-    /// Returns from a `ls -l` command output file name token, the name of the file and the symbolic link (if there is any)
-    fn get_name_and_link(&self, token: &str) -> (String, Option<PathBuf>) {
-        let tokens: Vec<&str> = token.split(" -> ").collect();
-        let filename: String = String::from(*tokens.get(0).unwrap());
-        let symlink: Option<PathBuf> = tokens.get(1).map(PathBuf::from);
-        (filename, symlink)
+    /// Make the entry of the directory at `path`, for which `ls` reports no attributes
+    fn make_dir_entry(path: &Path) -> FsEntry {
+        FsEntry::Directory(FsDirectory {
+            name: path
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_else(|| String::from("/")),
+            abs_path: PathBuf::from(path),
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            readonly: false,
+            symlink: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        })
+    }
+
+    /// ### ls_cmd
+    ///
+    /// Returns the `ls` command used to list files with `opts`. The C locale is always forced, so
+    /// that month names are in english; full ISO times, which have the timezone and the seconds,
+    /// are used when the remote `ls` supports them
+    fn ls_cmd(&self, opts: &str) -> String {
+        match self.gnu_ls {
+            true => format!("LC_ALL=C ls {} --time-style=full-iso", opts),
+            false => format!("LC_ALL=C ls {}", opts),
+        }
+    }
+
+    /// ### probe_gnu_ls
+    ///
+    /// Check whether the remote `ls` supports `--time-style`; this is usually the case with GNU coreutils
+    fn probe_gnu_ls(&mut self) -> bool {
+        if self.identity.is_windows() {
+            return false;
+        }
+        match self
+            .perform_shell_cmd("LC_ALL=C ls -ld --time-style=full-iso / >/dev/null 2>&1; echo $?")
+        {
+            Ok(output) => output.trim() == "0",
+            Err(_) => false,
+        }
     }
 
     /// ### perform_shell_cmd_with
@@ -232,7 +247,31 @@ impl ScpFileTransfer {
         path: &Path,
         cmd: &str,
     ) -> Result<String, FileTransferError> {
-        self.perform_shell_cmd(format!("cd \"{}\"; {}", path.display(), cmd).as_str())
+        self.perform_shell_cmd(format!("cd {}; {}", quote_path(path), cmd).as_str())
+    }
+
+    /// ### perform_checked_cmd
+    ///
+    /// Perform a shell command, which must echo its exit code, in the working directory.
+    /// Returns error if the exit code is not 0
+    fn perform_checked_cmd(&mut self, path: &Path, cmd: &str) -> Result<(), FileTransferError> {
+        if !self.is_connected() {
+            return Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            ));
+        }
+        let wrkdir: PathBuf = self.wrkdir.clone();
+        match self.perform_shell_cmd_with_path(wrkdir.as_path(), cmd) {
+            Ok(output) if output.trim() == "0" => Ok(()),
+            Ok(_) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::PexError,
+                format!("\"{}\"", path.display()),
+            )),
+            Err(err) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::ProtocolError,
+                format!("{}", err),
+            )),
+        }
     }
 
     /// ### perform_shell_cmd
@@ -284,6 +323,7 @@ impl FileTransfer for ScpFileTransfer {
     /// ### connect
     ///
     /// Connect to the remote server
+    #[instrument(skip_all, fields(host = %address, port), err)]
     fn connect(
         &mut self,
         address: String,
@@ -292,20 +332,11 @@ impl FileTransfer for ScpFileTransfer {
         password: Option<String>,
     ) -> Result<Option<String>, FileTransferError> {
         // Setup tcp stream
-        let socket_addresses: Vec<SocketAddr> =
-            match format!("{}:{}", address, port).to_socket_addrs() {
-                Ok(s) => s.collect(),
-                Err(err) => {
-                    return Err(FileTransferError::new_ex(
-                        FileTransferErrorType::BadAddress,
-                        format!("{}", err),
-                    ))
-                }
-            };
+        let socket_addresses: Vec<SocketAddr> = self.network.resolve(address.as_str(), port)?;
         let mut tcp: Option<TcpStream> = None;
         // Try addresses
         for socket_addr in socket_addresses.iter() {
-            match TcpStream::connect_timeout(&socket_addr, Duration::from_secs(30)) {
+            match TcpStream::connect_timeout(socket_addr, self.network.connection_timeout) {
                 Ok(stream) => {
                     tcp = Some(stream);
                     break;
@@ -335,6 +366,8 @@ impl FileTransfer for ScpFileTransfer {
         };
         // Set TCP stream
         session.set_tcp_stream(tcp);
+        // Apply preferred algorithms
+        ssh_method_prefs(&session, &self.algorithms)?;
         // Open connection
         if let Err(err) = session.handshake() {
             return Err(FileTransferError::new_ex(
@@ -342,6 +375,20 @@ impl FileTransfer for ScpFileTransfer {
                 format!("{}", err),
             ));
         }
+        // Send keepalive messages to prevent idle disconnections
+        if let Some(interval) = self.network.keepalive_interval {
+            session.set_keepalive(true, interval.as_secs() as u32);
+        }
+        // Verify host key
+        if let Some(known_hosts) = self.known_hosts.as_deref() {
+            known_hosts::verify_session(
+                known_hosts,
+                &session,
+                address.as_str(),
+                port,
+                self.accepted_host_key.take().as_ref(),
+            )?;
+        }
         let username: String = match username {
             Some(u) => u,
             None => String::from(""),
@@ -366,20 +413,18 @@ impl FileTransfer for ScpFileTransfer {
                 }
             }
             None => {
-                // Proceeed with username/password authentication
-                if let Err(err) = session.userauth_password(
+                // Proceeed with username/password authentication (or keyboard-interactive)
+                keyboard_interactive::userauth_password(
+                    &session,
                     username.as_str(),
-                    password.unwrap_or_else(|| String::from("")).as_str(),
-                ) {
-                    return Err(FileTransferError::new_ex(
-                        FileTransferErrorType::AuthenticationFailed,
-                        format!("{}", err),
-                    ));
-                }
+                    password,
+                    std::mem::take(&mut self.kbdint_responses),
+                )?;
             }
         }
         // Get banner
         let banner: Option<String> = session.banner().map(String::from);
+        self.identity = ssh_identity(&session);
         // Set session
         self.session = Some(session);
         // Get working directory
@@ -387,12 +432,17 @@ impl FileTransfer for ScpFileTransfer {
             Ok(output) => self.wrkdir = PathBuf::from(output.as_str().trim()),
             Err(err) => return Err(err),
         }
+        // Check ls capabilities
+        self.gnu_ls = self.probe_gnu_ls();
+        // Open session span
+        self.span = info_span!("session", protocol = "SCP", host = %address, port);
         Ok(banner)
     }
 
     /// ### disconnect
     ///
     /// Disconnect from the remote server
+    #[instrument(parent = &self.span, skip_all, err)]
     fn disconnect(&mut self) -> Result<(), FileTransferError> {
         match self.session.as_ref() {
             Some(session) => {
@@ -401,6 +451,8 @@ impl FileTransfer for ScpFileTransfer {
                     Ok(()) => {
                         // Set session to none
                         self.session = None;
+                        // Close session span
+                        self.span = Span::none();
                         Ok(())
                     }
                     Err(err) => Err(FileTransferError::new_ex(
@@ -422,10 +474,63 @@ impl FileTransfer for ScpFileTransfer {
         self.session.as_ref().is_some()
     }
 
+    /// ### server_info
+    ///
+    /// Returns the algorithms negotiated with the server and the listing format
+    fn server_info(&self) -> Vec<(String, String)> {
+        let mut info: Vec<(String, String)> = self
+            .session
+            .as_ref()
+            .map(ssh_session_info)
+            .unwrap_or_default();
+        if self.is_connected() {
+            let format: &str = match self.gnu_ls {
+                true => "full-iso",
+                false => "default",
+            };
+            info.push((String::from("Listing time style"), String::from(format)));
+        }
+        info
+    }
+
+    /// ### identity
+    ///
+    /// Returns the remote OS and server software, identified on connect
+    fn identity(&self) -> ServerIdentity {
+        self.identity.clone()
+    }
+
+    /// ### device_id
+    ///
+    /// Returns the id of the device `path` resides on, as reported by `stat`
+    fn device_id(&mut self, path: &Path) -> Result<Option<u64>, FileTransferError> {
+        match self.session.as_ref() {
+            Some(session) => Ok(ssh_device_id(session, path)),
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### set_keyboard_interactive_responses
+    ///
+    /// Set the responses to the keyboard-interactive prompts for the next connection
+    fn set_keyboard_interactive_responses(&mut self, responses: Vec<String>) {
+        self.kbdint_responses = responses;
+    }
+
+    /// ### set_accepted_host_key
+    ///
+    /// Set the host key accepted by the user; it is trusted at the next `connect`
+    fn set_accepted_host_key(&mut self, key: KnownHost) {
+        self.accepted_host_key = Some(key);
+    }
+
     /// ### pwd
     ///
     /// Print working directory
 
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
     fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
         match self.is_connected() {
             true => Ok(self.wrkdir.clone()),
@@ -439,6 +544,7 @@ impl FileTransfer for ScpFileTransfer {
     ///
     /// Change working directory
 
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
     fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
         match self.is_connected() {
             true => {
@@ -454,7 +560,7 @@ impl FileTransfer for ScpFileTransfer {
                 // Change directory
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
-                    format!("cd \"{}\"; echo $?; pwd", remote_path.display()).as_str(),
+                    format!("cd {}; echo $?; pwd", quote_path(remote_path.as_path())).as_str(),
                 ) {
                     Ok(output) => {
                         // Trim
@@ -488,6 +594,7 @@ impl FileTransfer for ScpFileTransfer {
     /// ### copy
     ///
     /// Copy file to destination
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
     fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
         match self.is_connected() {
             true => {
@@ -496,9 +603,9 @@ impl FileTransfer for ScpFileTransfer {
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
                     format!(
-                        "cp -rf \"{}\" \"{}\"; echo $?",
-                        src.get_abs_path().display(),
-                        dst.display()
+                        "cp -rf {} {}; echo $?",
+                        quote_path(src.get_abs_path().as_path()),
+                        quote_path(dst)
                     )
                     .as_str(),
                 ) {
@@ -530,6 +637,7 @@ impl FileTransfer for ScpFileTransfer {
     ///
     /// List directory entries
 
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
     fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
         match self.is_connected() {
             true => {
@@ -537,17 +645,18 @@ impl FileTransfer for ScpFileTransfer {
                 let p: PathBuf = self.wrkdir.clone();
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
-                    format!("unset LANG; ls -la \"{}\"", path.display()).as_str(),
+                    format!("{} {}", self.ls_cmd("-la"), quote_path(path)).as_str(),
                 ) {
                     Ok(output) => {
                         // Split output by (\r)\n
                         let lines: Vec<&str> = output.as_str().lines().collect();
                         let mut entries: Vec<FsEntry> = Vec::with_capacity(lines.len());
                         for line in lines.iter() {
-                            // First line must always be ignored
                             // Parse row, if ok push to entries
-                            if let Ok(entry) = self.parse_ls_output(path, line) {
-                                entries.push(entry);
+                            match self.parse_ls_output(path, line) {
+                                Ok(entry) => entries.push(entry),
+                                Err(err) if err.is_ignorable() => {}
+                                Err(err) => warn!(line = %line, "Could not parse entry: {}", err),
                             }
                         }
                         Ok(entries)
@@ -568,6 +677,7 @@ impl FileTransfer for ScpFileTransfer {
     ///
     /// Make directory
     /// You must return error in case the directory already exists
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
     fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
         match self.is_connected() {
             true => {
@@ -575,7 +685,7 @@ impl FileTransfer for ScpFileTransfer {
                 // Mkdir dir && echo 0
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
-                    format!("mkdir \"{}\"; echo $?", dir.display()).as_str(),
+                    format!("mkdir {}; echo $?", quote_path(dir)).as_str(),
                 ) {
                     Ok(output) => {
                         // Check if output is 0
@@ -603,6 +713,7 @@ impl FileTransfer for ScpFileTransfer {
     /// ### remove
     ///
     /// Remove a file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %file.get_abs_path().display()), err)]
     fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
         // Yay, we have rm -rf here :D
         match self.is_connected() {
@@ -612,7 +723,7 @@ impl FileTransfer for ScpFileTransfer {
                 let p: PathBuf = self.wrkdir.clone();
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
-                    format!("rm -rf \"{}\"; echo $?", path.display()).as_str(),
+                    format!("rm -rf {}; echo $?", quote_path(path.as_path())).as_str(),
                 ) {
                     Ok(output) => {
                         // Check if output is 0
@@ -637,9 +748,60 @@ impl FileTransfer for ScpFileTransfer {
         }
     }
 
+    /// ### chmod
+    ///
+    /// Change the UNIX permissions of `path` to `mode`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn chmod(&mut self, path: &Path, mode: (u8, u8, u8)) -> Result<(), FileTransferError> {
+        let (u, g, o) = mode;
+        self.perform_checked_cmd(
+            path,
+            format!("chmod {}{}{} {}; echo $?", u, g, o, quote_path(path)).as_str(),
+        )
+    }
+
+    /// ### chown
+    ///
+    /// Change the owner of `path` to user `uid` and group `gid`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn chown(&mut self, path: &Path, uid: u32, gid: u32) -> Result<(), FileTransferError> {
+        self.perform_checked_cmd(
+            path,
+            format!("chown {}:{} {}; echo $?", uid, gid, quote_path(path)).as_str(),
+        )
+    }
+
+    /// ### set_mtime
+    ///
+    /// Set the modification time of `path` to `mtime`, through `touch`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn set_mtime(&mut self, path: &Path, mtime: SystemTime) -> Result<(), FileTransferError> {
+        self.perform_checked_cmd(
+            path,
+            format!(
+                "TZ=UTC touch -m -t {} {}; echo $?",
+                fmt_utc_time(mtime, "%Y%m%d%H%M.%S"),
+                quote_path(path)
+            )
+            .as_str(),
+        )
+    }
+
+    /// ### symlink
+    ///
+    /// Create a symlink at `path`, pointing to `target`, through `ln`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn symlink(&mut self, path: &Path, target: &Path) -> Result<(), FileTransferError> {
+        self.perform_checked_cmd(
+            path,
+            format!("ln -s {} {}; echo $?", quote_path(target), quote_path(path)).as_str(),
+        )
+    }
+
     /// ### rename
     ///
     /// Rename file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
     fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
         match self.is_connected() {
             true => {
@@ -649,9 +811,9 @@ impl FileTransfer for ScpFileTransfer {
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
                     format!(
-                        "mv -f \"{}\" \"{}\"; echo $?",
-                        path.display(),
-                        dst.display()
+                        "mv -f {} {}; echo $?",
+                        quote_path(path.as_path()),
+                        quote_path(dst)
                     )
                     .as_str(),
                 ) {
@@ -681,6 +843,7 @@ impl FileTransfer for ScpFileTransfer {
     /// ### stat
     ///
     /// Stat file and return FsEntry
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
     fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
         if path.is_dir() {
             return Err(FileTransferError::new_ex(
@@ -701,7 +864,7 @@ impl FileTransfer for ScpFileTransfer {
                 let p: PathBuf = self.wrkdir.clone();
                 match self.perform_shell_cmd_with_path(
                     p.as_path(),
-                    format!("ls -l \"{}\"", path.display()).as_str(),
+                    format!("{} {}", self.ls_cmd("-l"), quote_path(path.as_path())).as_str(),
                 ) {
                     Ok(line) => {
                         // Parse ls line
@@ -736,6 +899,7 @@ impl FileTransfer for ScpFileTransfer {
     /// ### exec
     ///
     /// Execute a command on remote host
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
     fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
         match self.is_connected() {
             true => {
@@ -760,6 +924,7 @@ impl FileTransfer for ScpFileTransfer {
     /// File name is referred to the name of the file as it will be saved
     /// Data contains the file data
     /// Returns file and its size
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
     fn send_file(
         &mut self,
         local: &FsFile,
@@ -799,7 +964,10 @@ impl FileTransfer for ScpFileTransfer {
                 };
                 // Send file
                 match session.scp_send(file_name, mode, file_size, Some(times)) {
-                    Ok(channel) => Ok(Box::new(BufWriter::with_capacity(65536, channel))),
+                    Ok(channel) => Ok(Box::new(BufWriter::with_capacity(
+                        65536,
+                        ScpUpload { channel },
+                    ))),
                     Err(err) => Err(FileTransferError::new_ex(
                         FileTransferErrorType::ProtocolError,
                         format!("{}", err),
@@ -816,6 +984,7 @@ impl FileTransfer for ScpFileTransfer {
     ///
     /// Receive file from remote with provided name
     /// Returns file and its size
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
     fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
         match self.session.as_ref() {
             Some(session) => {
@@ -835,28 +1004,32 @@ impl FileTransfer for ScpFileTransfer {
         }
     }
 
-    /// ### on_sent
+    /// ### finalize_transfer
     ///
-    /// Finalize send method.
-    /// This method must be implemented only if necessary; in case you don't need it, just return `Ok(())`
-    /// The purpose of this method is to finalize the connection with the peer when writing data.
-    /// This is necessary for some protocols such as FTP.
-    /// You must call this method each time you want to finalize the write of the remote file.
-    fn on_sent(&mut self, _writable: Box<dyn Write>) -> Result<(), FileTransferError> {
-        // Nothing to do
+    /// Finalize the transfer. Nothing to do: dropping the stream waits for the remote scp to close the channel
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
+    fn finalize_transfer(&mut self, _outcome: TransferOutcome) -> Result<(), FileTransferError> {
         Ok(())
     }
+}
 
-    /// ### on_recv
-    ///
-    /// Finalize recv method.
-    /// This method must be implemented only if necessary; in case you don't need it, just return `Ok(())`
-    /// The purpose of this method is to finalize the connection with the peer when reading data.
-    /// This mighe be necessary for some protocols.
-    /// You must call this method each time you want to finalize the read of the remote file.
-    fn on_recv(&mut self, _readable: Box<dyn Read>) -> Result<(), FileTransferError> {
-        // Nothing to do
-        Ok(())
+impl Write for ScpUpload {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.channel.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.channel.flush()
+    }
+}
+
+impl Drop for ScpUpload {
+    fn drop(&mut self) {
+        // Send EOF, then wait for scp to write the file and close the channel
+        let _ = self.channel.send_eof();
+        let _ = self.channel.wait_eof();
+        let _ = self.channel.close();
+        let _ = self.channel.wait_close();
     }
 }
 
@@ -864,6 +1037,12 @@ impl FileTransfer for ScpFileTransfer {
 mod tests {
 
     use super::*;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::fixtures::SshServer;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::{TransferOutcome, TransferStream};
 
     #[test]
     fn test_filetransfer_scp_new() {
@@ -873,15 +1052,17 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_connect() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert_eq!(client.is_connected(), false);
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
@@ -892,23 +1073,27 @@ mod tests {
         assert_eq!(client.is_connected(), false);
     }
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_bad_auth() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
                 Some(String::from("badpassword"))
             )
             .is_err());
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_no_credentials() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
-            .connect(String::from("test.rebex.net"), 22, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_err());
     }
 
@@ -925,20 +1110,22 @@ mod tests {
             .is_err());
     }
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_pwd() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
         assert!(client.session.is_some());
         // Pwd
-        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/"));
+        assert_eq!(client.pwd().ok().unwrap(), server.home());
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
@@ -946,34 +1133,43 @@ mod tests {
     #[test]
     #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_cwd() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
         assert!(client.session.is_some());
         // Cwd (relative)
-        assert!(client.change_dir(PathBuf::from("pub/").as_path()).is_ok());
+        assert!(client
+            .change_dir(PathBuf::from("photos/").as_path())
+            .is_ok());
+        assert_eq!(client.pwd().ok().unwrap(), server.home().join("photos"));
         // Cwd (absolute)
-        assert!(client.change_dir(PathBuf::from("/pub").as_path()).is_ok());
+        assert!(client
+            .change_dir(server.home().join("projects").as_path())
+            .is_ok());
+        assert_eq!(client.pwd().ok().unwrap(), server.home().join("projects"));
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_cwd_error() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Cwd (abs)
@@ -989,14 +1185,16 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_ls() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
@@ -1004,44 +1202,56 @@ mod tests {
         // List dir
         let pwd: PathBuf = client.pwd().ok().unwrap();
         let files: Vec<FsEntry> = client.list_dir(pwd.as_path()).ok().unwrap();
-        assert_eq!(files.len(), 3); // There are 3 files
-                                    // Disconnect
+        assert_eq!(files.len(), 6); // There are 6 files
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(readme.is_file());
+        assert_eq!(readme.get_size(), 98);
+        assert!(files
+            .iter()
+            .find(|x| x.get_name() == "photos")
+            .unwrap()
+            .is_dir());
+        // Disconnect
         assert!(client.disconnect().is_ok());
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_stat() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
         assert!(client.session.is_some());
         let file: FsEntry = client
-            .stat(PathBuf::from("readme.txt").as_path())
+            .stat(PathBuf::from("README.md").as_path())
             .ok()
             .unwrap();
         if let FsEntry::File(file) = file {
-            assert_eq!(file.abs_path, PathBuf::from("/readme.txt"));
+            assert_eq!(file.abs_path, server.home().join("README.md"));
         } else {
-            panic!("Expected readme.txt to be a file");
+            panic!("Expected README.md to be a file");
         }
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_exec() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
@@ -1055,25 +1265,26 @@ mod tests {
     #[test]
     #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_find() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
         assert!(client.session.is_some());
-        // Search for file (let's search for pop3-*.png); there should be 2
-        let search_res: Vec<FsEntry> = client.find("pop3-*.png").ok().unwrap();
+        // Search for file (let's search for *.jpg); there should be 2
+        let search_res: Vec<FsEntry> = client.find("*.jpg").ok().unwrap();
         assert_eq!(search_res.len(), 2);
         // verify names
-        assert_eq!(search_res[0].get_name(), "pop3-browser.png");
-        assert_eq!(search_res[1].get_name(), "pop3-console-client.png");
+        assert_eq!(search_res[0].get_name(), "mountains.jpg");
+        assert_eq!(search_res[1].get_name(), "sea.jpg");
         // Search directory
-        let search_res: Vec<FsEntry> = client.find("pub").ok().unwrap();
+        let search_res: Vec<FsEntry> = client.find("projects").ok().unwrap();
         assert_eq!(search_res.len(), 1);
         // Disconnect
         assert!(client.disconnect().is_ok());
@@ -1082,21 +1293,23 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_recv() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
         assert!(client.session.is_some());
         let file: FsFile = FsFile {
-            name: String::from("readme.txt"),
-            abs_path: PathBuf::from("/readme.txt"),
+            name: String::from("README.md"),
+            abs_path: server.home().join("README.md"),
             last_change_time: SystemTime::UNIX_EPOCH,
             last_access_time: SystemTime::UNIX_EPOCH,
             creation_time: SystemTime::UNIX_EPOCH,
@@ -1104,24 +1317,31 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
         };
         // Receive file
-        assert!(client.recv_file(&file).is_ok());
+        let mut reader: Box<dyn Read> = client.recv_file(&file).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert!(content.starts_with("# Welcome to termscp"));
+        drop(reader);
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_recv_failed_nosuchfile() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
@@ -1129,7 +1349,7 @@ mod tests {
         // Receive file
         let file: FsFile = FsFile {
             name: String::from("omar.txt"),
-            abs_path: PathBuf::from("/omar.txt"),
+            abs_path: server.home().join("omar.txt"),
             last_change_time: SystemTime::UNIX_EPOCH,
             last_access_time: SystemTime::UNIX_EPOCH,
             creation_time: SystemTime::UNIX_EPOCH,
@@ -1137,6 +1357,7 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -1145,23 +1366,75 @@ mod tests {
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
-    // NOTE: other functions doesn't work with this test scp server
 
-    /* NOTE: the server doesn't allow you to create directories
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_scp_mkdir() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
-        assert!(client.connect(String::from("test.rebex.net"), 22, Some(String::from("demo")), Some(String::from("password"))).is_ok());
-        let dir: String = String::from("foo");
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
+            )
+            .is_ok());
         // Mkdir
-        assert!(client.mkdir(dir).is_ok());
+        assert!(client.mkdir(Path::new("foo")).is_ok());
+        assert!(client.mkdir(Path::new("foo")).is_err());
         // cwd
         assert!(client.change_dir(PathBuf::from("foo/").as_path()).is_ok());
-        assert_eq!(client.wrkdir, PathBuf::from("/foo"));
+        assert_eq!(client.wrkdir, server.home().join("foo"));
+        // Disconnect
+        assert!(client.disconnect().is_ok());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_filetransfer_scp_send() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
+            )
+            .is_ok());
+        let file: FsFile = FsFile {
+            name: String::from("omar.txt"),
+            abs_path: PathBuf::from("/omar.txt"), // Doesn't exist: size is used
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            size: 12,
+            ftype: Some(String::from("txt")), // File type
+            readonly: false,
+            symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
+            user: None,                // UNIX only
+            group: None,               // UNIX only
+            unix_pex: Some((6, 4, 4)), // UNIX only
+        };
+        // Send file
+        let dest: PathBuf = server.home().join("downloads/omar.txt");
+        let mut writer: Box<dyn Write> = client.send_file(&file, dest.as_path()).ok().unwrap();
+        assert!(writer.write_all(b"hello world!").is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_ok());
+        assert_eq!(
+            std::fs::read_to_string(dest.as_path()).ok().unwrap(),
+            "hello world!"
+        );
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
-    */
 
     #[test]
     fn test_filetransfer_scp_uninitialized() {
@@ -1175,6 +1448,7 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -27,18 +27,26 @@
  */
 // Dependencies
 extern crate ssh2;
+extern crate tracing;
 
 // Locals
-use super::{FileTransfer, FileTransferError, FileTransferErrorType};
+use super::keyboard_interactive;
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, NetworkOptions, ServerIdentity,
+    SshAlgorithms, TransferOutcome,
+};
 use crate::fs::{FsDirectory, FsEntry, FsFile};
+use crate::system::known_hosts::{self, KnownHost};
 use crate::system::sshkey_storage::SshKeyStorage;
+use crate::utils::shell::quote_path;
 
 // Includes
-use ssh2::{Channel, FileStat, OpenFlags, OpenType, Session, Sftp};
-use std::io::{BufReader, BufWriter, Read, Write};
-use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use ssh2::{Channel, FileStat, MethodType, OpenFlags, OpenType, Session, Sftp};
+use std::io::{BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::net::{SocketAddr, TcpStream};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
+use tracing::{info_span, instrument, Span};
 
 /// ## SftpFileTransfer
 ///
@@ -48,6 +56,13 @@ pub struct SftpFileTransfer {
     sftp: Option<Sftp>,
     wrkdir: PathBuf,
     key_storage: SshKeyStorage,
+    kbdint_responses: Vec<String>, // Responses for keyboard-interactive prompts
+    known_hosts: Option<PathBuf>,  // Known hosts file; host key is not verified if `None`
+    accepted_host_key: Option<KnownHost>, // Host key accepted by the user; trusted at next connect
+    network: NetworkOptions,
+    algorithms: SshAlgorithms, // Preferred algorithms, applied before handshake
+    identity: ServerIdentity,
+    span: Span, // Session span; parent of each remote operation span
 }
 
 impl SftpFileTransfer {
@@ -60,9 +75,40 @@ impl SftpFileTransfer {
             sftp: None,
             wrkdir: PathBuf::from("~"),
             key_storage,
+            kbdint_responses: Vec::new(),
+            known_hosts: None,
+            accepted_host_key: None,
+            network: NetworkOptions::default(),
+            algorithms: SshAlgorithms::default(),
+            identity: ServerIdentity::default(),
+            span: Span::none(),
         }
     }
 
+    /// ### with_known_hosts
+    ///
+    /// Set known hosts file used to verify the host key after handshake
+    pub fn with_known_hosts(mut self, path: Option<PathBuf>) -> Self {
+        self.known_hosts = path;
+        self
+    }
+
+    /// ### with_network_options
+    ///
+    /// Set connection timeout and keepalive interval
+    pub fn with_network_options(mut self, options: NetworkOptions) -> Self {
+        self.network = options;
+        self
+    }
+
+    /// ### with_ssh_algorithms
+    ///
+    /// Set the preferred key exchange, host key, cipher and MAC algorithms
+    pub fn with_ssh_algorithms(mut self, algorithms: SshAlgorithms) -> Self {
+        self.algorithms = algorithms;
+        self
+    }
+
     /// ### get_abs_path
     ///
     /// Get absolute path from path argument and check if it exists
@@ -117,6 +163,23 @@ impl SftpFileTransfer {
         }
     }
 
+    /// ### setstat
+    ///
+    /// Set the attributes of `path` which are defined in `stat`
+    fn setstat(&mut self, path: &Path, stat: FileStat) -> Result<(), FileTransferError> {
+        match self.sftp.as_ref() {
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+            Some(sftp) => {
+                let path: PathBuf = self.get_abs_path(path);
+                sftp.setstat(path.as_path(), stat).map_err(|err| {
+                    FileTransferError::new_ex(FileTransferErrorType::PexError, format!("{}", err))
+                })
+            }
+        }
+    }
+
     /// ### make_fsentry
     ///
     /// Make fsentry from path and metadata
@@ -146,21 +209,27 @@ impl SftpFileTransfer {
             .unwrap_or(SystemTime::UNIX_EPOCH);
         // Check if symlink
         let is_symlink: bool = metadata.file_type().is_symlink();
-        let symlink: Option<Box<FsEntry>> = match is_symlink {
+        let (symlink, broken_link): (Option<Box<FsEntry>>, Option<PathBuf>) = match is_symlink {
             true => {
-                // Read symlink
+                // Read symlink; relative targets are relative to the directory of the symlink
                 match self.sftp.as_ref().unwrap().readlink(path) {
-                    Ok(p) => match self.stat(p.as_path()) {
-                        Ok(entry) => Some(Box::new(entry)),
-                        Err(_) => None, // Ignore errors
-                    },
-                    Err(_) => None,
+                    Ok(p) => {
+                        let p: PathBuf = match path.parent() {
+                            Some(parent) => parent.join(p),
+                            None => p,
+                        };
+                        match self.stat(p.as_path()) {
+                            Ok(entry) => (Some(Box::new(entry)), None),
+                            Err(_) => (None, Some(p)), // Target doesn't exist
+                        }
+                    }
+                    Err(_) => (None, None),
                 }
             }
-            false => None,
+            false => (None, None),
         };
-        // Is a directory?
-        match metadata.is_dir() {
+        // Is a directory? Links to directories are directories too
+        match metadata.is_dir() || symlink.as_ref().map(|x| x.is_dir()).unwrap_or(false) {
             true => FsEntry::Directory(FsDirectory {
                 name: file_name,
                 abs_path: PathBuf::from(path),
@@ -183,6 +252,7 @@ impl SftpFileTransfer {
                 creation_time: SystemTime::UNIX_EPOCH,
                 readonly: false,
                 symlink,
+                broken_link,
                 user: uid,
                 group: gid,
                 unix_pex: pex,
@@ -246,6 +316,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### connect
     ///
     /// Connect to the remote server
+    #[instrument(skip_all, fields(host = %address, port), err)]
     fn connect(
         &mut self,
         address: String,
@@ -254,20 +325,11 @@ impl FileTransfer for SftpFileTransfer {
         password: Option<String>,
     ) -> Result<Option<String>, FileTransferError> {
         // Setup tcp stream
-        let socket_addresses: Vec<SocketAddr> =
-            match format!("{}:{}", address, port).to_socket_addrs() {
-                Ok(s) => s.collect(),
-                Err(err) => {
-                    return Err(FileTransferError::new_ex(
-                        FileTransferErrorType::BadAddress,
-                        format!("{}", err),
-                    ))
-                }
-            };
+        let socket_addresses: Vec<SocketAddr> = self.network.resolve(address.as_str(), port)?;
         let mut tcp: Option<TcpStream> = None;
         // Try addresses
         for socket_addr in socket_addresses.iter() {
-            match TcpStream::connect_timeout(&socket_addr, Duration::from_secs(30)) {
+            match TcpStream::connect_timeout(socket_addr, self.network.connection_timeout) {
                 Ok(stream) => {
                     tcp = Some(stream);
                     break;
@@ -297,6 +359,8 @@ impl FileTransfer for SftpFileTransfer {
         };
         // Set TCP stream
         session.set_tcp_stream(tcp);
+        // Apply preferred algorithms
+        ssh_method_prefs(&session, &self.algorithms)?;
         // Open connection
         if let Err(err) = session.handshake() {
             return Err(FileTransferError::new_ex(
@@ -304,6 +368,20 @@ impl FileTransfer for SftpFileTransfer {
                 format!("{}", err),
             ));
         }
+        // Send keepalive messages to prevent idle disconnections
+        if let Some(interval) = self.network.keepalive_interval {
+            session.set_keepalive(true, interval.as_secs() as u32);
+        }
+        // Verify host key
+        if let Some(known_hosts) = self.known_hosts.as_deref() {
+            known_hosts::verify_session(
+                known_hosts,
+                &session,
+                address.as_str(),
+                port,
+                self.accepted_host_key.take().as_ref(),
+            )?;
+        }
         let username: String = match username {
             Some(u) => u,
             None => String::from(""),
@@ -328,16 +406,13 @@ impl FileTransfer for SftpFileTransfer {
                 }
             }
             None => {
-                // Proceeed with username/password authentication
-                if let Err(err) = session.userauth_password(
+                // Proceeed with username/password authentication (or keyboard-interactive)
+                keyboard_interactive::userauth_password(
+                    &session,
                     username.as_str(),
-                    password.unwrap_or_else(|| String::from("")).as_str(),
-                ) {
-                    return Err(FileTransferError::new_ex(
-                        FileTransferErrorType::AuthenticationFailed,
-                        format!("{}", err),
-                    ));
-                }
+                    password,
+                    std::mem::take(&mut self.kbdint_responses),
+                )?;
             }
         }
         // Set blocking to true
@@ -364,15 +439,19 @@ impl FileTransfer for SftpFileTransfer {
         };
         // Set session
         let banner: Option<String> = session.banner().map(String::from);
+        self.identity = ssh_identity(&session);
         self.session = Some(session);
         // Set sftp
         self.sftp = Some(sftp);
+        // Open session span
+        self.span = info_span!("session", protocol = "SFTP", host = %address, port);
         Ok(banner)
     }
 
     /// ### disconnect
     ///
     /// Disconnect from the remote server
+    #[instrument(parent = &self.span, skip_all, err)]
     fn disconnect(&mut self) -> Result<(), FileTransferError> {
         match self.session.as_ref() {
             Some(session) => {
@@ -382,6 +461,8 @@ impl FileTransfer for SftpFileTransfer {
                         // Set session and sftp to none
                         self.session = None;
                         self.sftp = None;
+                        // Close session span
+                        self.span = Span::none();
                         Ok(())
                     }
                     Err(err) => Err(FileTransferError::new_ex(
@@ -403,9 +484,53 @@ impl FileTransfer for SftpFileTransfer {
         self.session.is_some()
     }
 
+    /// ### server_info
+    ///
+    /// Returns the algorithms negotiated with the server
+    fn server_info(&self) -> Vec<(String, String)> {
+        self.session
+            .as_ref()
+            .map(ssh_session_info)
+            .unwrap_or_default()
+    }
+
+    /// ### identity
+    ///
+    /// Returns the remote OS and server software, identified on connect
+    fn identity(&self) -> ServerIdentity {
+        self.identity.clone()
+    }
+
+    /// ### device_id
+    ///
+    /// Returns the id of the device `path` resides on, as reported by `stat`
+    fn device_id(&mut self, path: &Path) -> Result<Option<u64>, FileTransferError> {
+        match self.session.as_ref() {
+            Some(session) => Ok(ssh_device_id(session, path)),
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### set_keyboard_interactive_responses
+    ///
+    /// Set the responses to the keyboard-interactive prompts for the next connection
+    fn set_keyboard_interactive_responses(&mut self, responses: Vec<String>) {
+        self.kbdint_responses = responses;
+    }
+
+    /// ### set_accepted_host_key
+    ///
+    /// Set the host key accepted by the user; it is trusted at the next `connect`
+    fn set_accepted_host_key(&mut self, key: KnownHost) {
+        self.accepted_host_key = Some(key);
+    }
+
     /// ### pwd
     ///
     /// Print working directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
     fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
         match self.sftp {
             Some(_) => Ok(self.wrkdir.clone()),
@@ -418,6 +543,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### change_dir
     ///
     /// Change working directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
     fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
         match self.sftp.as_ref() {
             Some(_) => {
@@ -437,7 +563,8 @@ impl FileTransfer for SftpFileTransfer {
     /// ### copy
     ///
     /// Copy file to destination
-    fn copy(&mut self, _src: &FsEntry, _dst: &Path) -> Result<(), FileTransferError> {
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
         // SFTP doesn't support file copy
         Err(FileTransferError::new(
             FileTransferErrorType::UnsupportedFeature,
@@ -447,6 +574,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### list_dir
     ///
     /// List directory entries
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
     fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
         match self.sftp.as_ref() {
             Some(sftp) => {
@@ -481,6 +609,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### mkdir
     ///
     /// Make directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
     fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
         match self.sftp.as_ref() {
             Some(sftp) => {
@@ -503,6 +632,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### remove
     ///
     /// Remove a file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %file.get_abs_path().display()), err)]
     fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
         if self.sftp.is_none() {
             return Err(FileTransferError::new(
@@ -511,9 +641,18 @@ impl FileTransfer for SftpFileTransfer {
         }
         // Match if file is a file or a directory
         match file {
-            FsEntry::File(f) => {
+            // Symlinks to directories are unlinked too: their target must be left untouched
+            FsEntry::File(_)
+            | FsEntry::Directory(FsDirectory {
+                symlink: Some(_), ..
+            }) => {
                 // Remove file
-                match self.sftp.as_ref().unwrap().unlink(f.abs_path.as_path()) {
+                match self
+                    .sftp
+                    .as_ref()
+                    .unwrap()
+                    .unlink(file.get_abs_path().as_path())
+                {
                     Ok(_) => Ok(()),
                     Err(err) => Err(FileTransferError::new_ex(
                         FileTransferErrorType::PexError,
@@ -548,6 +687,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### rename
     ///
     /// Rename file or a directory
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
     fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
         match self.sftp.as_ref() {
             None => Err(FileTransferError::new(
@@ -572,6 +712,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### stat
     ///
     /// Stat file and return FsEntry
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
     fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
         match self.sftp.as_ref() {
             Some(sftp) => {
@@ -595,9 +736,91 @@ impl FileTransfer for SftpFileTransfer {
         }
     }
 
+    /// ### chmod
+    ///
+    /// Change the UNIX permissions of `path` to `mode`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn chmod(&mut self, path: &Path, mode: (u8, u8, u8)) -> Result<(), FileTransferError> {
+        let (u, g, o) = mode;
+        self.setstat(
+            path,
+            FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: Some(((u as u32) << 6) + ((g as u32) << 3) + (o as u32)),
+                atime: None,
+                mtime: None,
+            },
+        )
+    }
+
+    /// ### chown
+    ///
+    /// Change the owner of `path` to user `uid` and group `gid`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn chown(&mut self, path: &Path, uid: u32, gid: u32) -> Result<(), FileTransferError> {
+        self.setstat(
+            path,
+            FileStat {
+                size: None,
+                uid: Some(uid),
+                gid: Some(gid),
+                perm: None,
+                atime: None,
+                mtime: None,
+            },
+        )
+    }
+
+    /// ### symlink
+    ///
+    /// Create a symlink at `path`, pointing to `target`
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn symlink(&mut self, path: &Path, target: &Path) -> Result<(), FileTransferError> {
+        match self.sftp.as_ref() {
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+            Some(sftp) => {
+                let path: PathBuf = self.get_abs_path(path);
+                // The link is created at the second argument
+                sftp.symlink(target, path.as_path()).map_err(|err| {
+                    FileTransferError::new_ex(
+                        FileTransferErrorType::FileCreateDenied,
+                        format!("{}", err),
+                    )
+                })
+            }
+        }
+    }
+
+    /// ### set_mtime
+    ///
+    /// Set the modification time of `path` to `mtime`; access time is set to `mtime` too
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn set_mtime(&mut self, path: &Path, mtime: SystemTime) -> Result<(), FileTransferError> {
+        let secs: u64 = mtime
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|x| x.as_secs())
+            .unwrap_or(0);
+        self.setstat(
+            path,
+            FileStat {
+                size: None,
+                uid: None,
+                gid: None,
+                perm: None,
+                atime: Some(secs),
+                mtime: Some(secs),
+            },
+        )
+    }
+
     /// ### exec
     ///
     /// Execute a command on remote host
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
     fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
         match self.is_connected() {
             true => match self.perform_shell_cmd_with_path(cmd) {
@@ -618,6 +841,7 @@ impl FileTransfer for SftpFileTransfer {
     /// Send file to remote
     /// File name is referred to the name of the file as it will be saved
     /// Data contains the file data
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
     fn send_file(
         &mut self,
         local: &FsFile,
@@ -653,6 +877,7 @@ impl FileTransfer for SftpFileTransfer {
     /// ### recv_file
     ///
     /// Receive file from remote with provided name
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
     fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
         match self.sftp.as_ref() {
             None => Err(FileTransferError::new(
@@ -676,31 +901,144 @@ impl FileTransfer for SftpFileTransfer {
         }
     }
 
-    /// ### on_sent
+    /// ### recv_file_at
     ///
-    /// Finalize send method. This method must be implemented only if necessary.
-    /// The purpose of this method is to finalize the connection with the peer when writing data.
-    /// This is necessary for some protocols such as FTP.
-    /// You must call this method each time you want to finalize the write of the remote file.
-    fn on_sent(&mut self, _writable: Box<dyn Write>) -> Result<(), FileTransferError> {
-        Ok(())
+    /// Receive file from remote starting from byte `offset`
+    fn recv_file_at(
+        &mut self,
+        file: &FsFile,
+        offset: u64,
+    ) -> Result<Box<dyn Read>, FileTransferError> {
+        let sftp = self
+            .sftp
+            .as_ref()
+            .ok_or_else(|| FileTransferError::new(FileTransferErrorType::UninitializedSession))?;
+        let remote_path: PathBuf = self.get_remote_path(file.abs_path.as_path())?;
+        let mut file = sftp.open(remote_path.as_path()).map_err(|e| {
+            FileTransferError::new_ex(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+                format!("{}", e),
+            )
+        })?;
+        file.seek(SeekFrom::Start(offset)).map_err(|e| {
+            FileTransferError::new_ex(FileTransferErrorType::ProtocolError, format!("{}", e))
+        })?;
+        Ok(Box::new(BufReader::with_capacity(65536, file)))
     }
 
-    /// ### on_recv
+    /// ### finalize_transfer
     ///
-    /// Finalize recv method. This method must be implemented only if necessary.
-    /// The purpose of this method is to finalize the connection with the peer when reading data.
-    /// This mighe be necessary for some protocols.
-    /// You must call this method each time you want to finalize the read of the remote file.
-    fn on_recv(&mut self, _readable: Box<dyn Read>) -> Result<(), FileTransferError> {
+    /// Finalize the transfer. Nothing to do: the channel is closed when the stream is dropped
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
+    fn finalize_transfer(&mut self, _outcome: TransferOutcome) -> Result<(), FileTransferError> {
         Ok(())
     }
 }
 
+/// ### ssh_method_prefs
+///
+/// Set the preferred algorithms of `session`; must be called before the handshake.
+/// Ciphers and MACs apply to both directions
+pub(super) fn ssh_method_prefs(
+    session: &Session,
+    algorithms: &SshAlgorithms,
+) -> Result<(), FileTransferError> {
+    [
+        ("key exchange", MethodType::Kex, algorithms.kex.as_ref()),
+        (
+            "host key",
+            MethodType::HostKey,
+            algorithms.host_key.as_ref(),
+        ),
+        ("cipher", MethodType::CryptCs, algorithms.cipher.as_ref()),
+        ("cipher", MethodType::CryptSc, algorithms.cipher.as_ref()),
+        ("MAC", MethodType::MacCs, algorithms.mac.as_ref()),
+        ("MAC", MethodType::MacSc, algorithms.mac.as_ref()),
+    ]
+    .iter()
+    .filter_map(|(name, method, prefs)| prefs.map(|prefs| (name, method, prefs)))
+    .try_for_each(|(name, method, prefs)| {
+        session.method_pref(*method, prefs.as_str()).map_err(|err| {
+            FileTransferError::new_ex(
+                FileTransferErrorType::ConnectionError,
+                format!("Unsupported {} algorithms \"{}\": {}", name, prefs, err),
+            )
+        })
+    })
+}
+
+/// ### ssh_session_info
+///
+/// Returns the algorithms negotiated for `session`
+pub(super) fn ssh_session_info(session: &Session) -> Vec<(String, String)> {
+    [
+        ("Key exchange", MethodType::Kex),
+        ("Host key", MethodType::HostKey),
+        ("Cipher", MethodType::CryptCs),
+        ("MAC", MethodType::MacCs),
+        ("Compression", MethodType::CompCs),
+    ]
+    .iter()
+    .filter_map(|(name, method)| {
+        session
+            .methods(*method)
+            .map(|x| (name.to_string(), x.to_string()))
+    })
+    .collect()
+}
+
+/// ### ssh_identity
+///
+/// Identify the server of `session` by its SSH banner and the system description, reported by
+/// `uname -a` (or by `ver` on Windows). The system is unknown if the server doesn't allow to exec commands
+pub(super) fn ssh_identity(session: &Session) -> ServerIdentity {
+    let system: Option<String> =
+        ssh_exec_ok(session, "uname -a").or_else(|| ssh_exec_ok(session, "ver"));
+    ServerIdentity::new(session.banner(), system.as_deref())
+}
+
+/// ### ssh_device_id
+///
+/// Get the id of the device `path` resides on with `stat` (GNU syntax first, then BSD's).
+/// Returns `None` if it can't be determined (e.g. the server doesn't allow to exec commands)
+pub(super) fn ssh_device_id(session: &Session, path: &Path) -> Option<u64> {
+    let path: String = quote_path(path);
+    ssh_exec_ok(
+        session,
+        format!(
+            "stat -L -c %d {} 2>/dev/null || stat -L -f %d {}",
+            path, path
+        )
+        .as_str(),
+    )
+    .and_then(|x| x.trim().parse::<u64>().ok())
+}
+
+/// ### ssh_exec_ok
+///
+/// Exec `cmd` on a new channel of `session`; returns its output if it exited successfully
+fn ssh_exec_ok(session: &Session, cmd: &str) -> Option<String> {
+    let mut channel: Channel = session.channel_session().ok()?;
+    channel.exec(cmd).ok()?;
+    let mut output: String = String::new();
+    channel.read_to_string(&mut output).ok()?;
+    let _ = channel.wait_close();
+    match channel.exit_status() {
+        Ok(0) => Some(output),
+        _ => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::fixtures::SshServer;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::{TransferOutcome, TransferStream};
 
     #[test]
     fn test_filetransfer_sftp_new() {
@@ -712,21 +1050,41 @@ mod tests {
     }
 
     #[test]
+    fn test_filetransfer_sftp_method_prefs() {
+        let session: Session = Session::new().unwrap();
+        assert!(ssh_method_prefs(&session, &SshAlgorithms::default()).is_ok());
+        let algorithms: SshAlgorithms = SshAlgorithms {
+            kex: Some(String::from("diffie-hellman-group14-sha1")),
+            host_key: Some(String::from("ssh-rsa")),
+            cipher: Some(String::from("aes128-ctr,aes256-cbc")),
+            mac: Some(String::from("hmac-sha1")),
+        };
+        assert!(ssh_method_prefs(&session, &algorithms).is_ok());
+        let algorithms: SshAlgorithms = SshAlgorithms {
+            cipher: Some(String::from("rot13")),
+            ..SshAlgorithms::default()
+        };
+        assert!(ssh_method_prefs(&session, &algorithms).is_err());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_connect() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert_eq!(client.is_connected(), false);
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         assert_eq!(client.is_connected(), true);
         // Disconnect
         assert!(client.disconnect().is_ok());
@@ -734,23 +1092,27 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_bad_auth() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
                 Some(String::from("badpassword"))
             )
             .is_err());
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_no_credentials() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
-            .connect(String::from("test.rebex.net"), 22, None, None)
+            .connect(server.address(), server.port(), None, None)
             .is_err());
     }
 
@@ -768,20 +1130,22 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_pwd() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         // Pwd
         assert_eq!(client.wrkdir.clone(), client.pwd().ok().unwrap());
         // Disconnect
@@ -789,51 +1153,57 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_cwd() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         // Pwd
         assert_eq!(client.wrkdir.clone(), client.pwd().ok().unwrap());
         // Cwd (relative)
-        assert!(client.change_dir(PathBuf::from("pub/").as_path()).is_ok());
-        assert_eq!(client.wrkdir, PathBuf::from("/pub"));
+        assert!(client
+            .change_dir(PathBuf::from("photos/").as_path())
+            .is_ok());
+        assert_eq!(client.wrkdir, server.home().join("photos"));
         // Cwd (absolute)
-        assert!(client.change_dir(PathBuf::from("/").as_path()).is_ok());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert!(client.change_dir(server.home()).is_ok());
+        assert_eq!(client.wrkdir, server.home());
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_copy() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         // Copy
         let file: FsFile = FsFile {
-            name: String::from("readme.txt"),
-            abs_path: PathBuf::from("/readme.txt"),
+            name: String::from("README.md"),
+            abs_path: server.home().join("README.md"),
             last_change_time: SystemTime::UNIX_EPOCH,
             last_access_time: SystemTime::UNIX_EPOCH,
             creation_time: SystemTime::UNIX_EPOCH,
@@ -841,6 +1211,7 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -851,14 +1222,16 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_cwd_error() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Cwd (abs)
@@ -877,65 +1250,80 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_ls() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         // List dir
         let pwd: PathBuf = client.pwd().ok().unwrap();
         let files: Vec<FsEntry> = client.list_dir(pwd.as_path()).ok().unwrap();
-        assert_eq!(files.len(), 3); // There are 3 files
-                                    // Disconnect
+        assert_eq!(files.len(), 6); // There are 6 files
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(readme.is_file());
+        assert_eq!(readme.get_size(), 98);
+        assert!(files
+            .iter()
+            .find(|x| x.get_name() == "photos")
+            .unwrap()
+            .is_dir());
+        // Disconnect
         assert!(client.disconnect().is_ok());
         // Verify err
         assert!(client.list_dir(pwd.as_path()).is_err());
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_stat() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         let file: FsEntry = client
-            .stat(PathBuf::from("readme.txt").as_path())
+            .stat(PathBuf::from("README.md").as_path())
             .ok()
             .unwrap();
         if let FsEntry::File(file) = file {
-            assert_eq!(file.abs_path, PathBuf::from("/readme.txt"));
+            assert_eq!(file.abs_path, server.home().join("README.md"));
+            assert_eq!(file.size, 98);
         } else {
-            panic!("Expected readme.txt to be a file");
+            panic!("Expected README.md to be a file");
         }
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_exec() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
@@ -949,26 +1337,28 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_find() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and scp
         assert!(client.session.is_some());
-        // Search for file (let's search for pop3-*.png); there should be 2
-        let search_res: Vec<FsEntry> = client.find("pop3-*.png").ok().unwrap();
+        // Search for file (let's search for *.jpg); there should be 2
+        let search_res: Vec<FsEntry> = client.find("*.jpg").ok().unwrap();
         assert_eq!(search_res.len(), 2);
         // verify names
-        assert_eq!(search_res[0].get_name(), "pop3-browser.png");
-        assert_eq!(search_res[1].get_name(), "pop3-console-client.png");
+        assert_eq!(search_res[0].get_name(), "mountains.jpg");
+        assert_eq!(search_res[1].get_name(), "sea.jpg");
         // Search directory
-        let search_res: Vec<FsEntry> = client.find("pub").ok().unwrap();
+        let search_res: Vec<FsEntry> = client.find("projects").ok().unwrap();
         assert_eq!(search_res.len(), 1);
         // Disconnect
         assert!(client.disconnect().is_ok());
@@ -977,23 +1367,25 @@ mod tests {
     }
 
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_recv() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         let file: FsFile = FsFile {
-            name: String::from("readme.txt"),
-            abs_path: PathBuf::from("/readme.txt"),
+            name: String::from("README.md"),
+            abs_path: server.home().join("README.md"),
             last_change_time: SystemTime::UNIX_EPOCH,
             last_access_time: SystemTime::UNIX_EPOCH,
             creation_time: SystemTime::UNIX_EPOCH,
@@ -1001,34 +1393,46 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
         };
         // Receive file
-        assert!(client.recv_file(&file).is_ok());
+        let mut reader: Box<dyn Read> = client.recv_file(&file).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert!(content.starts_with("# Welcome to termscp"));
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                content.len() as u64
+            ))
+            .is_ok());
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_recv_failed_nosuchfile() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
         assert!(client
             .connect(
-                String::from("test.rebex.net"),
-                22,
-                Some(String::from("demo")),
-                Some(String::from("password"))
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
             )
             .is_ok());
         // Check session and sftp
         assert!(client.session.is_some());
         assert!(client.sftp.is_some());
-        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert_eq!(client.wrkdir, server.home());
         // Receive file
         let file: FsFile = FsFile {
             name: String::from("omar.txt"),
-            abs_path: PathBuf::from("/omar.txt"),
+            abs_path: server.home().join("omar.txt"),
             last_change_time: SystemTime::UNIX_EPOCH,
             last_access_time: SystemTime::UNIX_EPOCH,
             creation_time: SystemTime::UNIX_EPOCH,
@@ -1036,6 +1440,7 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -1045,23 +1450,82 @@ mod tests {
         assert!(client.disconnect().is_ok());
     }
 
-    // NOTE: other functions doesn't work with this test SFTP server
-
-    /* NOTE: the server doesn't allow you to create directories
     #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_filetransfer_sftp_mkdir() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
         let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
-        assert!(client.connect(String::from("test.rebex.net"), 22, Some(String::from("demo")), Some(String::from("password"))).is_ok());
-        let dir: String = String::from("foo");
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
+            )
+            .is_ok());
         // Mkdir
-        assert!(client.mkdir(dir).is_ok());
+        assert!(client.mkdir(Path::new("foo")).is_ok());
+        assert!(client.mkdir(Path::new("foo")).is_err());
         // cwd
         assert!(client.change_dir(PathBuf::from("foo/").as_path()).is_ok());
-        assert_eq!(client.wrkdir, PathBuf::from("/foo"));
+        assert_eq!(client.wrkdir, server.home().join("foo"));
+        // Disconnect
+        assert!(client.disconnect().is_ok());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_filetransfer_sftp_send() {
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut client: SftpFileTransfer = SftpFileTransfer::new(SshKeyStorage::empty());
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
+            )
+            .is_ok());
+        let file: FsFile = FsFile {
+            name: String::from("omar.txt"),
+            abs_path: PathBuf::from("/omar.txt"),
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            size: 12,
+            ftype: Some(String::from("txt")), // File type
+            readonly: false,
+            symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
+            user: None,                // UNIX only
+            group: None,               // UNIX only
+            unix_pex: Some((6, 4, 4)), // UNIX only
+        };
+        // Send file
+        let dest: PathBuf = server.home().join("downloads/omar.txt");
+        let mut writer: Box<dyn Write> = client.send_file(&file, dest.as_path()).ok().unwrap();
+        assert!(writer.write_all(b"hello world!").is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_ok());
+        assert_eq!(
+            std::fs::read_to_string(dest.as_path()).ok().unwrap(),
+            "hello world!"
+        );
+        // Rename and remove
+        let entry: FsEntry = client.stat(dest.as_path()).ok().unwrap();
+        let renamed: PathBuf = server.home().join("downloads/hello.txt");
+        assert!(client.rename(&entry, renamed.as_path()).is_ok());
+        assert!(!dest.exists());
+        let entry: FsEntry = client.stat(renamed.as_path()).ok().unwrap();
+        assert!(client.remove(&entry).is_ok());
+        assert!(!renamed.exists());
         // Disconnect
         assert!(client.disconnect().is_ok());
     }
-    */
 
     #[test]
     fn test_filetransfer_sftp_uninitialized() {
@@ -1075,6 +1539,7 @@ mod tests {
             ftype: Some(String::from("txt")), // File type
             readonly: true,
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -0,0 +1,1918 @@
+//! ## SmbTransfer
+//!
+//! `smb_transfer` is the module which provides the implementation for the SMB file transfer
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Dependencies
+extern crate md5;
+extern crate rand;
+extern crate sha2;
+extern crate tracing;
+
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, NetworkOptions, TransferOutcome,
+    TransferStream,
+};
+use crate::fs::{FsDirectory, FsEntry, FsFile};
+
+// Includes
+use hmac::{Hmac, Mac, NewMac};
+use md4::{Digest, Md4};
+use md5::Md5;
+use sha2::Sha256;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info_span, instrument, Span};
+
+// SMB2 commands
+const SMB2_NEGOTIATE: u16 = 0x0000;
+const SMB2_SESSION_SETUP: u16 = 0x0001;
+const SMB2_LOGOFF: u16 = 0x0002;
+const SMB2_TREE_CONNECT: u16 = 0x0003;
+const SMB2_TREE_DISCONNECT: u16 = 0x0004;
+const SMB2_CREATE: u16 = 0x0005;
+const SMB2_CLOSE: u16 = 0x0006;
+const SMB2_READ: u16 = 0x0008;
+const SMB2_WRITE: u16 = 0x0009;
+const SMB2_QUERY_DIRECTORY: u16 = 0x000e;
+const SMB2_SET_INFO: u16 = 0x0011;
+
+// NT status codes
+const STATUS_SUCCESS: u32 = 0x0000_0000;
+const STATUS_PENDING: u32 = 0x0000_0103;
+const STATUS_NO_MORE_FILES: u32 = 0x8000_0006;
+const STATUS_END_OF_FILE: u32 = 0xc000_0011;
+const STATUS_MORE_PROCESSING_REQUIRED: u32 = 0xc000_0016;
+
+// Access masks
+const FILE_READ_DATA: u32 = 0x0000_0001;
+const FILE_WRITE_DATA: u32 = 0x0000_0002;
+const FILE_READ_ATTRIBUTES: u32 = 0x0000_0080;
+const FILE_WRITE_ATTRIBUTES: u32 = 0x0000_0100;
+const DELETE: u32 = 0x0001_0000;
+const SYNCHRONIZE: u32 = 0x0010_0000;
+
+// Create dispositions
+const FILE_OPEN: u32 = 1;
+const FILE_CREATE: u32 = 2;
+const FILE_OVERWRITE_IF: u32 = 5;
+
+// Create options
+const FILE_DIRECTORY_FILE: u32 = 0x0000_0001;
+const FILE_NON_DIRECTORY_FILE: u32 = 0x0000_0040;
+const FILE_DELETE_ON_CLOSE: u32 = 0x0000_1000;
+
+// File attributes
+const FILE_ATTRIBUTE_READONLY: u32 = 0x0000_0001;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x0000_0010;
+
+// NTLMSSP negotiate flags
+const NTLMSSP_NEGOTIATE_UNICODE: u32 = 0x0000_0001;
+const NTLMSSP_REQUEST_TARGET: u32 = 0x0000_0004;
+const NTLMSSP_NEGOTIATE_NTLM: u32 = 0x0000_0200;
+const NTLMSSP_NEGOTIATE_ALWAYS_SIGN: u32 = 0x0000_8000;
+const NTLMSSP_NEGOTIATE_EXTENDED_SESSIONSECURITY: u32 = 0x0008_0000;
+const NTLMSSP_NEGOTIATE_TARGET_INFO: u32 = 0x0080_0000;
+const NTLMSSP_NEGOTIATE_128: u32 = 0x2000_0000;
+const NTLMSSP_NEGOTIATE_56: u32 = 0x8000_0000;
+
+/// Largest payload of a single READ or WRITE request (multi-credit requests are not used)
+const SMB2_MAX_IO_SIZE: u32 = 65536;
+/// Length of the fixed part of a `FileDirectoryInformation` entry; the file name follows it
+const DIRECTORY_ENTRY_HEADER_LEN: usize = 64;
+/// Difference between the FILETIME epoch (1601-01-01) and the UNIX epoch, in 100ns intervals
+const FILETIME_UNIX_EPOCH: u64 = 116_444_736_000_000_000;
+
+/// ## SmbResponse
+///
+/// A SMB2 response message (header included: offsets are relative to the start of the header)
+struct SmbResponse {
+    status: u32,
+    data: Vec<u8>,
+}
+
+/// ## SmbConnection
+///
+/// An authenticated SMB2 session over a TCP connection
+struct SmbConnection {
+    stream: TcpStream,
+    server: String,
+    dialect: u16,
+    message_id: u64,
+    session_id: u64,
+    signing_key: Option<[u8; 16]>, // Set if the server requires signing
+    trees: HashMap<String, u32>,   // Share name => tree id
+}
+
+/// ## SmbFile
+///
+/// A file opened on a share
+#[derive(Clone, Copy)]
+struct SmbFile {
+    tree_id: u32,
+    file_id: [u8; 16],
+}
+
+/// ## SmbFileTransfer
+///
+/// SMB2 file transfer struct.
+/// The first component of a remote path is the name of the share (e.g. `/public/docs/a.txt`)
+pub struct SmbFileTransfer {
+    connection: Option<Rc<RefCell<SmbConnection>>>,
+    wrkdir: PathBuf,
+    transfer: Option<SmbFile>, // File opened by `send_file` or `recv_file`
+    network: NetworkOptions,
+    span: Span, // Session span; parent of each remote operation span
+}
+
+impl SmbResponse {
+    /// ### u8_at
+    ///
+    /// Read byte at `offset`
+    fn u8_at(&self, offset: usize) -> Result<u8, FileTransferError> {
+        Ok(self.slice(offset, 1)?[0])
+    }
+
+    /// ### u16_at
+    ///
+    /// Read little endian u16 at `offset`
+    fn u16_at(&self, offset: usize) -> Result<u16, FileTransferError> {
+        let bytes: &[u8] = self.slice(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// ### u32_at
+    ///
+    /// Read little endian u32 at `offset`
+    fn u32_at(&self, offset: usize) -> Result<u32, FileTransferError> {
+        le_u32(self.data.as_slice(), offset).ok_or_else(Self::malformed)
+    }
+
+    /// ### u64_at
+    ///
+    /// Read little endian u64 at `offset`
+    fn u64_at(&self, offset: usize) -> Result<u64, FileTransferError> {
+        le_u64(self.data.as_slice(), offset).ok_or_else(Self::malformed)
+    }
+
+    /// ### slice
+    ///
+    /// Get `len` bytes at `offset`
+    fn slice(&self, offset: usize, len: usize) -> Result<&[u8], FileTransferError> {
+        self.data
+            .get(offset..offset.saturating_add(len))
+            .ok_or_else(Self::malformed)
+    }
+
+    /// ### malformed
+    ///
+    /// Make error for a response shorter than expected
+    fn malformed() -> FileTransferError {
+        FileTransferError::new_ex(
+            FileTransferErrorType::ProtocolError,
+            String::from("Malformed SMB2 response"),
+        )
+    }
+}
+
+impl SmbConnection {
+    /// ### connect
+    ///
+    /// Connect to the server, negotiate the dialect and authenticate with NTLMv2.
+    /// The connection timeout of `network` also bounds the wait for each response
+    fn connect(
+        address: &str,
+        port: u16,
+        username: &str,
+        password: &str,
+        network: &NetworkOptions,
+    ) -> Result<SmbConnection, FileTransferError> {
+        let socket_addresses: Vec<SocketAddr> = network.resolve(address, port)?;
+        let mut last_error: Option<io::Error> = None;
+        let mut stream: Option<TcpStream> = None;
+        // Try addresses
+        for socket_addr in socket_addresses.iter() {
+            match TcpStream::connect_timeout(socket_addr, network.connection_timeout) {
+                Ok(s) => {
+                    stream = Some(s);
+                    break;
+                }
+                Err(err) => last_error = Some(err),
+            }
+        }
+        let stream: TcpStream = match stream {
+            Some(stream) => stream,
+            None => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::ConnectionError,
+                    last_error
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| String::from("Connection timeout")),
+                ))
+            }
+        };
+        let _ = stream.set_read_timeout(Some(network.connection_timeout));
+        let mut connection: SmbConnection = SmbConnection {
+            stream,
+            server: address.to_string(),
+            dialect: 0,
+            message_id: 0,
+            session_id: 0,
+            signing_key: None,
+            trees: HashMap::new(),
+        };
+        let signing_required: bool = connection.negotiate()?;
+        connection.session_setup(username, password, signing_required)?;
+        Ok(connection)
+    }
+
+    /// ### negotiate
+    ///
+    /// Negotiate SMB 2.0.2 or 2.1. Returns whether the server requires signing
+    fn negotiate(&mut self) -> Result<bool, FileTransferError> {
+        let mut body: Vec<u8> = Vec::with_capacity(40);
+        body.extend_from_slice(&36u16.to_le_bytes()); // Structure size
+        body.extend_from_slice(&2u16.to_le_bytes()); // Dialect count
+        body.extend_from_slice(&1u16.to_le_bytes()); // Security mode: signing enabled
+        body.extend_from_slice(&0u16.to_le_bytes()); // Reserved
+        body.extend_from_slice(&0u32.to_le_bytes()); // Capabilities
+        body.extend_from_slice(&rand::random::<[u8; 16]>()); // Client guid
+        body.extend_from_slice(&0u64.to_le_bytes()); // Client start time
+        body.extend_from_slice(&0x0202u16.to_le_bytes());
+        body.extend_from_slice(&0x0210u16.to_le_bytes());
+        let response: SmbResponse = self.request(SMB2_NEGOTIATE, 0, body.as_slice())?;
+        check_status(response.status)?;
+        self.dialect = response.u16_at(64 + 4)?;
+        if self.dialect != 0x0202 && self.dialect != 0x0210 {
+            return Err(FileTransferError::new_ex(
+                FileTransferErrorType::UnsupportedFeature,
+                format!("Unsupported dialect 0x{:04x}", self.dialect),
+            ));
+        }
+        Ok(response.u16_at(64 + 2)? & 0x02 != 0)
+    }
+
+    /// ### session_setup
+    ///
+    /// Authenticate with NTLMv2 (wrapped into SPNEGO).
+    /// Username may contain the domain (`DOMAIN\user`)
+    fn session_setup(
+        &mut self,
+        username: &str,
+        password: &str,
+        signing_required: bool,
+    ) -> Result<(), FileTransferError> {
+        let (domain, user): (&str, &str) = match username.find('\\') {
+            Some(idx) => (&username[..idx], &username[idx + 1..]),
+            None => ("", username),
+        };
+        // Negotiate
+        let response: SmbResponse =
+            self.session_setup_request(spnego_init(ntlm_negotiate().as_slice()).as_slice())?;
+        if response.status != STATUS_MORE_PROCESSING_REQUIRED {
+            return Err(auth_error(response.status));
+        }
+        self.session_id = response.u64_at(40)?;
+        let challenge: &[u8] = match spnego_token(Self::security_buffer(&response)?) {
+            Some(challenge) => challenge,
+            None => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::ProtocolError,
+                    String::from("Missing NTLM challenge"),
+                ))
+            }
+        };
+        // Authenticate
+        let (authenticate, session_key): (Vec<u8>, [u8; 16]) = ntlm_authenticate(
+            challenge,
+            domain,
+            user,
+            password,
+            rand::random::<[u8; 8]>(),
+            filetime(SystemTime::now()),
+        )?;
+        let response: SmbResponse =
+            self.session_setup_request(spnego_response(authenticate.as_slice()).as_slice())?;
+        if response.status != STATUS_SUCCESS {
+            return Err(auth_error(response.status));
+        }
+        // Guest and anonymous sessions can't be signed
+        let session_flags: u16 = response.u16_at(64 + 2)?;
+        if signing_required && session_flags & 0x03 == 0 {
+            self.signing_key = Some(session_key);
+        }
+        Ok(())
+    }
+
+    /// ### session_setup_request
+    ///
+    /// Send SESSION_SETUP request with security buffer
+    fn session_setup_request(&mut self, token: &[u8]) -> Result<SmbResponse, FileTransferError> {
+        let mut body: Vec<u8> = Vec::with_capacity(24 + token.len());
+        body.extend_from_slice(&25u16.to_le_bytes()); // Structure size
+        body.push(0); // Flags
+        body.push(1); // Security mode: signing enabled
+        body.extend_from_slice(&0u32.to_le_bytes()); // Capabilities
+        body.extend_from_slice(&0u32.to_le_bytes()); // Channel
+        body.extend_from_slice(&(64u16 + 24).to_le_bytes()); // Security buffer offset
+        body.extend_from_slice(&(token.len() as u16).to_le_bytes());
+        body.extend_from_slice(&0u64.to_le_bytes()); // Previous session id
+        body.extend_from_slice(token);
+        self.request(SMB2_SESSION_SETUP, 0, body.as_slice())
+    }
+
+    /// ### security_buffer
+    ///
+    /// Get security buffer from SESSION_SETUP response
+    fn security_buffer(response: &SmbResponse) -> Result<&[u8], FileTransferError> {
+        let offset: usize = response.u16_at(64 + 4)? as usize;
+        let len: usize = response.u16_at(64 + 6)? as usize;
+        response.slice(offset, len)
+    }
+
+    /// ### tree
+    ///
+    /// Get tree id of `share`, connecting to it if necessary
+    fn tree(&mut self, share: &str) -> Result<u32, FileTransferError> {
+        if let Some(tree_id) = self.trees.get(share) {
+            return Ok(*tree_id);
+        }
+        let path: Vec<u8> = utf16le(format!("\\\\{}\\{}", self.server, share).as_str());
+        let mut body: Vec<u8> = Vec::with_capacity(8 + path.len());
+        body.extend_from_slice(&9u16.to_le_bytes()); // Structure size
+        body.extend_from_slice(&0u16.to_le_bytes()); // Reserved
+        body.extend_from_slice(&(64u16 + 8).to_le_bytes()); // Path offset
+        body.extend_from_slice(&(path.len() as u16).to_le_bytes());
+        body.extend_from_slice(path.as_slice());
+        let response: SmbResponse = self.request(SMB2_TREE_CONNECT, 0, body.as_slice())?;
+        check_status(response.status)?;
+        let tree_id: u32 = response.u32_at(36)?;
+        self.trees.insert(share.to_string(), tree_id);
+        Ok(tree_id)
+    }
+
+    /// ### logoff
+    ///
+    /// Disconnect from all the shares and close session
+    fn logoff(&mut self) -> Result<(), FileTransferError> {
+        let body: [u8; 4] = [4, 0, 0, 0];
+        let trees: Vec<u32> = self.trees.drain().map(|(_, tree_id)| tree_id).collect();
+        for tree_id in trees.into_iter() {
+            self.request(SMB2_TREE_DISCONNECT, tree_id, &body)?;
+        }
+        let response: SmbResponse = self.request(SMB2_LOGOFF, 0, &body)?;
+        check_status(response.status)
+    }
+
+    /// ### create
+    ///
+    /// Open (or create) `name` on share. Returns the file and the CREATE response,
+    /// which contains the file times, size and attributes
+    fn create(
+        &mut self,
+        tree_id: u32,
+        name: &str,
+        access: u32,
+        disposition: u32,
+        options: u32,
+    ) -> Result<(SmbFile, SmbResponse), FileTransferError> {
+        let mut name: Vec<u8> = utf16le(name);
+        let name_len: usize = name.len();
+        if name.is_empty() {
+            name.push(0); // Buffer can't be empty
+        }
+        let mut body: Vec<u8> = Vec::with_capacity(56 + name.len());
+        body.extend_from_slice(&57u16.to_le_bytes()); // Structure size
+        body.push(0); // Security flags
+        body.push(0); // Oplock level: none
+        body.extend_from_slice(&2u32.to_le_bytes()); // Impersonation level
+        body.extend_from_slice(&0u64.to_le_bytes()); // Create flags
+        body.extend_from_slice(&0u64.to_le_bytes()); // Reserved
+        body.extend_from_slice(&access.to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes()); // File attributes
+        body.extend_from_slice(&7u32.to_le_bytes()); // Share access: read, write, delete
+        body.extend_from_slice(&disposition.to_le_bytes());
+        body.extend_from_slice(&options.to_le_bytes());
+        body.extend_from_slice(&(64u16 + 56).to_le_bytes()); // Name offset
+        body.extend_from_slice(&(name_len as u16).to_le_bytes());
+        body.extend_from_slice(&0u32.to_le_bytes()); // Create contexts offset
+        body.extend_from_slice(&0u32.to_le_bytes()); // Create contexts length
+        body.extend_from_slice(name.as_slice());
+        let response: SmbResponse = self.request(SMB2_CREATE, tree_id, body.as_slice())?;
+        check_status(response.status)?;
+        let mut file_id: [u8; 16] = [0; 16];
+        file_id.copy_from_slice(response.slice(64 + 64, 16)?);
+        Ok((SmbFile { tree_id, file_id }, response))
+    }
+
+    /// ### close
+    ///
+    /// Close file
+    fn close(&mut self, file: SmbFile) -> Result<(), FileTransferError> {
+        let mut body: Vec<u8> = Vec::with_capacity(24);
+        body.extend_from_slice(&24u16.to_le_bytes()); // Structure size
+        body.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        body.extend_from_slice(&0u32.to_le_bytes()); // Reserved
+        body.extend_from_slice(&file.file_id);
+        let response: SmbResponse = self.request(SMB2_CLOSE, file.tree_id, body.as_slice())?;
+        check_status(response.status)
+    }
+
+    /// ### query_directory
+    ///
+    /// List the entries of an open directory (`FileDirectoryInformation`)
+    fn query_directory(&mut self, dir: SmbFile) -> Result<Vec<u8>, FileTransferError> {
+        let pattern: Vec<u8> = utf16le("*");
+        let mut entries: Vec<u8> = Vec::new();
+        let mut flags: u8 = 0x01; // Restart scans
+        loop {
+            let mut body: Vec<u8> = Vec::with_capacity(32 + pattern.len());
+            body.extend_from_slice(&33u16.to_le_bytes()); // Structure size
+            body.push(0x01); // FileDirectoryInformation
+            body.push(flags);
+            body.extend_from_slice(&0u32.to_le_bytes()); // File index
+            body.extend_from_slice(&dir.file_id);
+            body.extend_from_slice(&(64u16 + 32).to_le_bytes()); // Pattern offset
+            body.extend_from_slice(&(pattern.len() as u16).to_le_bytes());
+            body.extend_from_slice(&SMB2_MAX_IO_SIZE.to_le_bytes()); // Output buffer length
+            body.extend_from_slice(pattern.as_slice());
+            let response: SmbResponse =
+                self.request(SMB2_QUERY_DIRECTORY, dir.tree_id, body.as_slice())?;
+            if response.status == STATUS_NO_MORE_FILES {
+                return Ok(entries);
+            }
+            check_status(response.status)?;
+            let offset: usize = response.u16_at(64 + 2)? as usize;
+            let len: usize = response.u32_at(64 + 4)? as usize;
+            let buffer: &[u8] = response.slice(offset, len)?;
+            // Make entries in buffer contiguous to the previous ones
+            let mut cursor: usize = 0;
+            while cursor < buffer.len() {
+                let next: usize =
+                    le_u32(buffer, cursor).ok_or_else(SmbResponse::malformed)? as usize;
+                let end: usize = match next {
+                    0 => buffer.len(),
+                    next => cursor.saturating_add(next).min(buffer.len()),
+                };
+                // Each entry starts with a fixed 64 bytes header
+                if end - cursor < DIRECTORY_ENTRY_HEADER_LEN {
+                    return Err(SmbResponse::malformed());
+                }
+                let entry_start: usize = entries.len();
+                entries.extend_from_slice(&buffer[cursor..end]);
+                let entry_len: u32 = (end - cursor) as u32;
+                entries[entry_start..entry_start + 4].copy_from_slice(&entry_len.to_le_bytes());
+                if next == 0 {
+                    break;
+                }
+                cursor = end;
+            }
+            flags = 0;
+        }
+    }
+
+    /// ### read
+    ///
+    /// Read up to `len` bytes at `offset`. Returns an empty buffer at EOF
+    fn read(&mut self, file: SmbFile, offset: u64, len: u32) -> Result<Vec<u8>, FileTransferError> {
+        let mut body: Vec<u8> = Vec::with_capacity(49);
+        body.extend_from_slice(&49u16.to_le_bytes()); // Structure size
+        body.push(0x50); // Padding
+        body.push(0); // Flags
+        body.extend_from_slice(&len.min(SMB2_MAX_IO_SIZE).to_le_bytes());
+        body.extend_from_slice(&offset.to_le_bytes());
+        body.extend_from_slice(&file.file_id);
+        body.extend_from_slice(&0u32.to_le_bytes()); // Minimum count
+        body.extend_from_slice(&0u32.to_le_bytes()); // Channel
+        body.extend_from_slice(&0u32.to_le_bytes()); // Remaining bytes
+        body.extend_from_slice(&0u16.to_le_bytes()); // Channel info offset
+        body.extend_from_slice(&0u16.to_le_bytes()); // Channel info length
+        body.push(0); // Buffer
+        let response: SmbResponse = self.request(SMB2_READ, file.tree_id, body.as_slice())?;
+        if response.status == STATUS_END_OF_FILE {
+            return Ok(Vec::new());
+        }
+        check_status(response.status)?;
+        let data_offset: usize = response.u8_at(64 + 2)? as usize;
+        let data_len: usize = response.u32_at(64 + 4)? as usize;
+        Ok(response.slice(data_offset, data_len)?.to_vec())
+    }
+
+    /// ### write
+    ///
+    /// Write `data` at `offset`. Returns the amount of bytes written
+    fn write(
+        &mut self,
+        file: SmbFile,
+        offset: u64,
+        data: &[u8],
+    ) -> Result<usize, FileTransferError> {
+        let mut body: Vec<u8> = Vec::with_capacity(48 + data.len());
+        body.extend_from_slice(&49u16.to_le_bytes()); // Structure size
+        body.extend_from_slice(&(64u16 + 48).to_le_bytes()); // Data offset
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&offset.to_le_bytes());
+        body.extend_from_slice(&file.file_id);
+        body.extend_from_slice(&0u32.to_le_bytes()); // Channel
+        body.extend_from_slice(&0u32.to_le_bytes()); // Remaining bytes
+        body.extend_from_slice(&0u16.to_le_bytes()); // Channel info offset
+        body.extend_from_slice(&0u16.to_le_bytes()); // Channel info length
+        body.extend_from_slice(&0u32.to_le_bytes()); // Flags
+        body.extend_from_slice(data);
+        let response: SmbResponse = self.request(SMB2_WRITE, file.tree_id, body.as_slice())?;
+        check_status(response.status)?;
+        Ok(response.u32_at(64 + 4)? as usize)
+    }
+
+    /// ### set_info
+    ///
+    /// Set file information of class `class`
+    fn set_info(&mut self, file: SmbFile, class: u8, info: &[u8]) -> Result<(), FileTransferError> {
+        let mut body: Vec<u8> = Vec::with_capacity(32 + info.len());
+        body.extend_from_slice(&33u16.to_le_bytes()); // Structure size
+        body.push(0x01); // Info type: file
+        body.push(class);
+        body.extend_from_slice(&(info.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(64u16 + 32).to_le_bytes()); // Buffer offset
+        body.extend_from_slice(&0u16.to_le_bytes()); // Reserved
+        body.extend_from_slice(&0u32.to_le_bytes()); // Additional information
+        body.extend_from_slice(&file.file_id);
+        body.extend_from_slice(info);
+        let response: SmbResponse = self.request(SMB2_SET_INFO, file.tree_id, body.as_slice())?;
+        check_status(response.status)
+    }
+
+    /// ### request
+    ///
+    /// Send a request and wait for its response
+    fn request(
+        &mut self,
+        command: u16,
+        tree_id: u32,
+        body: &[u8],
+    ) -> Result<SmbResponse, FileTransferError> {
+        let message: Vec<u8> = self.make_message(command, tree_id, body);
+        let mut frame: Vec<u8> = Vec::with_capacity(4 + message.len());
+        // Direct TCP transport header: zero and 24 bits length
+        frame.extend_from_slice(&(message.len() as u32).to_be_bytes());
+        frame.extend_from_slice(message.as_slice());
+        self.stream.write_all(frame.as_slice()).map_err(io_error)?;
+        loop {
+            let mut header: [u8; 4] = [0; 4];
+            self.stream.read_exact(&mut header).map_err(io_error)?;
+            let len: usize = (u32::from_be_bytes(header) & 0x00ff_ffff) as usize;
+            let mut data: Vec<u8> = vec![0; len];
+            self.stream
+                .read_exact(data.as_mut_slice())
+                .map_err(io_error)?;
+            if len < 64 || data[0..4] != *b"\xfeSMB" {
+                return Err(SmbResponse::malformed());
+            }
+            let response: SmbResponse = SmbResponse {
+                status: le_u32(data.as_slice(), 8).unwrap_or(0),
+                data,
+            };
+            // Interim responses are followed by the final response
+            if response.status != STATUS_PENDING {
+                return Ok(response);
+            }
+        }
+    }
+
+    /// ### make_message
+    ///
+    /// Make SMB2 message (header and body), signing it if required
+    fn make_message(&mut self, command: u16, tree_id: u32, body: &[u8]) -> Vec<u8> {
+        let signed: bool = self.signing_key.is_some();
+        let mut message: Vec<u8> = Vec::with_capacity(64 + body.len());
+        message.extend_from_slice(b"\xfeSMB");
+        message.extend_from_slice(&64u16.to_le_bytes()); // Structure size
+        message.extend_from_slice(&(self.dialect_credit_charge()).to_le_bytes());
+        message.extend_from_slice(&0u32.to_le_bytes()); // Status
+        message.extend_from_slice(&command.to_le_bytes());
+        message.extend_from_slice(&8u16.to_le_bytes()); // Credits requested
+        message.extend_from_slice(&(if signed { 0x08u32 } else { 0 }).to_le_bytes());
+        message.extend_from_slice(&0u32.to_le_bytes()); // Next command
+        message.extend_from_slice(&self.message_id.to_le_bytes());
+        message.extend_from_slice(&0xfeffu32.to_le_bytes()); // Process id
+        message.extend_from_slice(&tree_id.to_le_bytes());
+        message.extend_from_slice(&self.session_id.to_le_bytes());
+        message.extend_from_slice(&[0; 16]); // Signature
+        message.extend_from_slice(body);
+        self.message_id += 1;
+        if let Some(key) = self.signing_key.as_ref() {
+            let signature: Vec<u8> = hmac_sha256(key, message.as_slice());
+            message[48..64].copy_from_slice(&signature[..16]);
+        }
+        message
+    }
+
+    /// ### dialect_credit_charge
+    ///
+    /// Credit charge of a request; it is reserved (zero) in SMB 2.0.2
+    fn dialect_credit_charge(&self) -> u16 {
+        match self.dialect {
+            0x0210 => 1,
+            _ => 0,
+        }
+    }
+}
+
+impl SmbFileTransfer {
+    /// ### new
+    ///
+    /// Instantiates a new `SmbFileTransfer`
+    pub fn new() -> SmbFileTransfer {
+        SmbFileTransfer {
+            connection: None,
+            wrkdir: PathBuf::from("/"),
+            transfer: None,
+            network: NetworkOptions::default(),
+            span: Span::none(),
+        }
+    }
+
+    /// ### with_network_options
+    ///
+    /// Set network options; the connection timeout is used to connect and to wait for responses
+    pub fn with_network_options(mut self, options: NetworkOptions) -> Self {
+        self.network = options;
+        self
+    }
+
+    /// ### resolve
+    ///
+    /// Get absolute and normalized path from `p` (relative paths are relative to wrkdir)
+    fn resolve(&self, p: &Path) -> PathBuf {
+        let mut path: PathBuf = PathBuf::from("/");
+        for component in self.wrkdir.join(p).components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::ParentDir => {
+                    path.pop();
+                }
+                _ => {}
+            }
+        }
+        path
+    }
+
+    /// ### split_path
+    ///
+    /// Split absolute path into share name and path on the share (with backslashes).
+    /// Returns None for the root directory, which contains the shares
+    fn split_path(path: &Path) -> Option<(String, String)> {
+        let mut components = path.components().filter_map(|x| match x {
+            Component::Normal(name) => Some(name.to_string_lossy().to_string()),
+            _ => None,
+        });
+        let share: String = components.next()?;
+        Some((share, components.collect::<Vec<String>>().join("\\")))
+    }
+
+    /// ### connection
+    ///
+    /// Get connection
+    fn connection(&self) -> Result<Rc<RefCell<SmbConnection>>, FileTransferError> {
+        match self.connection.as_ref() {
+            Some(connection) => Ok(connection.clone()),
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### open
+    ///
+    /// Open file at `p` on its share
+    fn open(
+        &self,
+        p: &Path,
+        access: u32,
+        disposition: u32,
+        options: u32,
+    ) -> Result<(SmbFile, SmbResponse), FileTransferError> {
+        let connection: Rc<RefCell<SmbConnection>> = self.connection()?;
+        let mut connection = connection.borrow_mut();
+        match Self::split_path(self.resolve(p).as_path()) {
+            Some((share, name)) => {
+                let tree_id: u32 = connection.tree(share.as_str())?;
+                connection.create(tree_id, name.as_str(), access, disposition, options)
+            }
+            None => Err(FileTransferError::new_ex(
+                FileTransferErrorType::PexError,
+                String::from("The root directory contains the shares only"),
+            )),
+        }
+    }
+
+    /// ### close
+    ///
+    /// Close file
+    fn close(&self, file: SmbFile) -> Result<(), FileTransferError> {
+        self.connection()?.borrow_mut().close(file)
+    }
+
+    /// ### share_entry
+    ///
+    /// Make the directory entry of a share (or of the root directory, if `share` is empty)
+    fn share_entry(share: &str) -> FsEntry {
+        FsEntry::Directory(FsDirectory {
+            name: match share.is_empty() {
+                true => String::from("/"),
+                false => share.to_string(),
+            },
+            abs_path: PathBuf::from("/").join(share),
+            last_change_time: UNIX_EPOCH,
+            last_access_time: UNIX_EPOCH,
+            creation_time: UNIX_EPOCH,
+            readonly: false,
+            symlink: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        })
+    }
+
+    /// ### make_fsentry
+    ///
+    /// Make `FsEntry` from file times (creation, last access and last write), size and attributes
+    fn make_fsentry(path: PathBuf, times: [u64; 3], size: u64, attributes: u32) -> FsEntry {
+        let creation_time: SystemTime = systime(times[0]);
+        let last_access_time: SystemTime = systime(times[1]);
+        let last_change_time: SystemTime = systime(times[2]);
+        let size: usize = size as usize;
+        let name: String = match path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => String::from("/"),
+        };
+        let readonly: bool = attributes & FILE_ATTRIBUTE_READONLY != 0;
+        match attributes & FILE_ATTRIBUTE_DIRECTORY != 0 {
+            true => FsEntry::Directory(FsDirectory {
+                name,
+                abs_path: path,
+                last_change_time,
+                last_access_time,
+                creation_time,
+                readonly,
+                symlink: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            }),
+            false => FsEntry::File(FsFile {
+                ftype: path.extension().map(|x| x.to_string_lossy().to_string()),
+                name,
+                abs_path: path,
+                last_change_time,
+                last_access_time,
+                creation_time,
+                size,
+                readonly,
+                symlink: None,
+                broken_link: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            }),
+        }
+    }
+
+    /// ### parse_directory
+    ///
+    /// Parse `FileDirectoryInformation` entries of directory at `path`
+    fn parse_directory(path: &Path, buffer: &[u8]) -> Result<Vec<FsEntry>, FileTransferError> {
+        let mut entries: Vec<FsEntry> = Vec::new();
+        let mut cursor: usize = 0;
+        while cursor < buffer.len() {
+            let entry: &[u8] = &buffer[cursor..];
+            let field = |offset: usize| le_u64(entry, offset).ok_or_else(SmbResponse::malformed);
+            let next: usize = le_u32(entry, 0).ok_or_else(SmbResponse::malformed)? as usize;
+            let name_len: usize = le_u32(entry, 60).ok_or_else(SmbResponse::malformed)? as usize;
+            let name_end: usize = DIRECTORY_ENTRY_HEADER_LEN
+                .checked_add(name_len)
+                .filter(|x| next == 0 || *x <= next)
+                .ok_or_else(SmbResponse::malformed)?;
+            let name: &[u8] = entry
+                .get(DIRECTORY_ENTRY_HEADER_LEN..name_end)
+                .ok_or_else(SmbResponse::malformed)?;
+            let name: String = String::from_utf16_lossy(
+                name.chunks_exact(2)
+                    .map(|x| u16::from_le_bytes([x[0], x[1]]))
+                    .collect::<Vec<u16>>()
+                    .as_slice(),
+            );
+            if name != "." && name != ".." {
+                entries.push(Self::make_fsentry(
+                    path.join(name),
+                    [field(8)?, field(16)?, field(24)?],
+                    field(40)?,
+                    le_u32(entry, 56).ok_or_else(SmbResponse::malformed)?,
+                ));
+            }
+            if next == 0 {
+                break;
+            }
+            cursor = cursor.saturating_add(next);
+        }
+        Ok(entries)
+    }
+}
+
+impl Default for SmbFileTransfer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FileTransfer for SmbFileTransfer {
+    /// ### connect
+    ///
+    /// Connect to the remote server.
+    /// The address may contain the share to enter (e.g. `nas.local/public`)
+
+    #[instrument(skip_all, fields(host = %address, port), err)]
+    fn connect(
+        &mut self,
+        address: String,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Option<String>, FileTransferError> {
+        let (host, share): (&str, Option<&str>) = match address.find('/') {
+            Some(idx) => (&address[..idx], Some(address[idx + 1..].trim_matches('/'))),
+            None => (address.as_str(), None),
+        };
+        let mut connection: SmbConnection = SmbConnection::connect(
+            host,
+            port,
+            username.unwrap_or_default().as_str(),
+            password.unwrap_or_default().as_str(),
+            &self.network,
+        )?;
+        self.wrkdir = PathBuf::from("/");
+        if let Some(share) = share.filter(|x| !x.is_empty()) {
+            connection.tree(share)?;
+            self.wrkdir.push(share);
+        }
+        let dialect: u16 = connection.dialect;
+        self.connection = Some(Rc::new(RefCell::new(connection)));
+        // Open session span
+        self.span = info_span!("session", protocol = "SMB", host = %host, port);
+        Ok(Some(String::from(match dialect {
+            0x0202 => "SMB 2.0.2",
+            _ => "SMB 2.1",
+        })))
+    }
+
+    /// ### disconnect
+    ///
+    /// Disconnect from the remote server
+
+    #[instrument(parent = &self.span, skip_all, err)]
+    fn disconnect(&mut self) -> Result<(), FileTransferError> {
+        match self.connection.take() {
+            Some(connection) => {
+                let result = connection.borrow_mut().logoff();
+                // Close session span
+                self.span = Span::none();
+                result
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### is_connected
+    ///
+    /// Indicates whether the client is connected to remote
+    fn is_connected(&self) -> bool {
+        self.connection.is_some()
+    }
+
+    /// ### pwd
+    ///
+    /// Print working directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
+    fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
+        self.connection()?;
+        Ok(self.wrkdir.clone())
+    }
+
+    /// ### change_dir
+    ///
+    /// Change working directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
+        match self.stat(dir)? {
+            FsEntry::Directory(dir) => {
+                self.wrkdir = dir.abs_path;
+                Ok(self.wrkdir.clone())
+            }
+            FsEntry::File(_) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::DirStatFailed,
+                String::from("Not a directory"),
+            )),
+        }
+    }
+
+    /// ### copy
+    ///
+    /// Copy file to destination
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        // Server side copy requires IOCTLs, which are not supported
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### list_dir
+    ///
+    /// List directory entries. The root directory lists the shares connected so far
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
+        let path: PathBuf = self.resolve(path);
+        if Self::split_path(path.as_path()).is_none() {
+            let mut shares: Vec<String> =
+                self.connection()?.borrow().trees.keys().cloned().collect();
+            shares.sort();
+            return Ok(shares.iter().map(|x| Self::share_entry(x)).collect());
+        }
+        let (dir, _): (SmbFile, SmbResponse) = self.open(
+            path.as_path(),
+            FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OPEN,
+            FILE_DIRECTORY_FILE,
+        )?;
+        let buffer: Result<Vec<u8>, FileTransferError> =
+            self.connection()?.borrow_mut().query_directory(dir);
+        self.close(dir)?;
+        Self::parse_directory(path.as_path(), buffer?.as_slice())
+    }
+
+    /// ### mkdir
+    ///
+    /// Make directory
+    /// In case the directory already exists, an error of kind `FileTransferErrorType::FileCreateDenied` is returned
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        let (dir, _): (SmbFile, SmbResponse) = self.open(
+            dir,
+            FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_CREATE,
+            FILE_DIRECTORY_FILE,
+        )?;
+        self.close(dir)
+    }
+
+    /// ### remove
+    ///
+    /// Remove a file or a directory (recursively)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %file.get_abs_path().display()), err)]
+    fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        let options: u32 = match file {
+            FsEntry::Directory(dir) => {
+                // Directories can be removed only if empty
+                for entry in self.list_dir(dir.abs_path.as_path())?.iter() {
+                    self.remove(entry)?;
+                }
+                FILE_DIRECTORY_FILE
+            }
+            FsEntry::File(_) => FILE_NON_DIRECTORY_FILE,
+        };
+        let (file, _): (SmbFile, SmbResponse) = self.open(
+            file.get_abs_path().as_path(),
+            DELETE | FILE_READ_ATTRIBUTES,
+            FILE_OPEN,
+            options | FILE_DELETE_ON_CLOSE,
+        )?;
+        self.close(file)
+    }
+
+    /// ### rename
+    ///
+    /// Rename file or a directory (on the same share)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
+    fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        let src: Option<(String, String)> =
+            Self::split_path(self.resolve(file.get_abs_path().as_path()).as_path());
+        let dst: Option<(String, String)> = Self::split_path(self.resolve(dst).as_path());
+        let name: String = match (src, dst) {
+            (Some((src_share, _)), Some((dst_share, name)))
+                if src_share == dst_share && !name.is_empty() =>
+            {
+                name
+            }
+            _ => {
+                return Err(FileTransferError::new_ex(
+                    FileTransferErrorType::UnsupportedFeature,
+                    String::from("Files can't be moved to another share"),
+                ))
+            }
+        };
+        // FileRenameInformation
+        let name: Vec<u8> = utf16le(name.as_str());
+        let mut info: Vec<u8> = Vec::with_capacity(20 + name.len());
+        info.push(0); // Don't replace if exists
+        info.extend_from_slice(&[0; 7]); // Reserved
+        info.extend_from_slice(&0u64.to_le_bytes()); // Root directory
+        info.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        info.extend_from_slice(name.as_slice());
+        let (handle, _): (SmbFile, SmbResponse) = self.open(
+            file.get_abs_path().as_path(),
+            DELETE | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OPEN,
+            0,
+        )?;
+        let result: Result<(), FileTransferError> =
+            self.connection()?
+                .borrow_mut()
+                .set_info(handle, 10, info.as_slice());
+        self.close(handle)?;
+        result
+    }
+
+    /// ### stat
+    ///
+    /// Stat file and return FsEntry
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
+        let path: PathBuf = self.resolve(path);
+        match Self::split_path(path.as_path()) {
+            None => {
+                self.connection()?;
+                Ok(Self::share_entry(""))
+            }
+            Some(_) => {
+                let (file, response): (SmbFile, SmbResponse) =
+                    self.open(path.as_path(), FILE_READ_ATTRIBUTES, FILE_OPEN, 0)?;
+                self.close(file)?;
+                Ok(Self::make_fsentry(
+                    path,
+                    [
+                        response.u64_at(64 + 8)?,
+                        response.u64_at(64 + 16)?,
+                        response.u64_at(64 + 24)?,
+                    ],
+                    response.u64_at(64 + 48)?,
+                    response.u32_at(64 + 56)?,
+                ))
+            }
+        }
+    }
+
+    /// ### exec
+    ///
+    /// Execute a command on remote host (not supported by SMB)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
+    fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### send_file
+    ///
+    /// Send file to remote
+    /// File name is referred to the name of the file as it will be saved
+    /// Data contains the file data
+
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
+    fn send_file(
+        &mut self,
+        _local: &FsFile,
+        file_name: &Path,
+    ) -> Result<Box<dyn Write>, FileTransferError> {
+        let (file, _): (SmbFile, SmbResponse) = self.open(
+            file_name,
+            FILE_WRITE_DATA | FILE_READ_ATTRIBUTES | FILE_WRITE_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OVERWRITE_IF,
+            FILE_NON_DIRECTORY_FILE,
+        )?;
+        self.transfer = Some(file);
+        Ok(Box::new(SmbWriter {
+            connection: self.connection()?,
+            file,
+            offset: 0,
+            buffer: Vec::with_capacity(SMB2_MAX_IO_SIZE as usize),
+        }))
+    }
+
+    /// ### recv_file
+    ///
+    /// Receive file from remote with provided name
+
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
+    fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
+        let (file, _): (SmbFile, SmbResponse) = self.open(
+            file.abs_path.as_path(),
+            FILE_READ_DATA | FILE_READ_ATTRIBUTES | SYNCHRONIZE,
+            FILE_OPEN,
+            FILE_NON_DIRECTORY_FILE,
+        )?;
+        self.transfer = Some(file);
+        Ok(Box::new(SmbReader {
+            connection: self.connection()?,
+            file,
+            offset: 0,
+        }))
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer: write buffered data, if the whole file has been sent, and close the file
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(bytes = outcome.bytes), err)]
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        let completed: bool = outcome.is_completed();
+        let result: io::Result<()> = match outcome.stream {
+            TransferStream::Upload(mut writer) if completed => writer.flush(),
+            TransferStream::Upload(_) | TransferStream::Download(_) => Ok(()),
+        };
+        if let Some(file) = self.transfer.take() {
+            self.close(file)?;
+        }
+        result.map_err(|err| FileTransferError::new(FileTransferErrorType::IoErr(err)))
+    }
+}
+
+/// ## SmbWriter
+///
+/// Writes data to an open file, in chunks of at most `SMB2_MAX_IO_SIZE` bytes
+struct SmbWriter {
+    connection: Rc<RefCell<SmbConnection>>,
+    file: SmbFile,
+    offset: u64,
+    buffer: Vec<u8>,
+}
+
+impl SmbWriter {
+    /// ### write_buffer
+    ///
+    /// Write buffered data to file
+    fn write_buffer(&mut self) -> io::Result<()> {
+        let mut written: usize = 0;
+        while written < self.buffer.len() {
+            let size: usize = self
+                .connection
+                .borrow_mut()
+                .write(self.file, self.offset, &self.buffer[written..])
+                .map_err(|err| io::Error::other(err.to_string()))?;
+            if size == 0 {
+                return Err(io::Error::from(io::ErrorKind::WriteZero));
+            }
+            written += size;
+            self.offset += size as u64;
+        }
+        self.buffer.clear();
+        Ok(())
+    }
+}
+
+impl Write for SmbWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let size: usize = buf.len().min(SMB2_MAX_IO_SIZE as usize - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..size]);
+        if self.buffer.len() == SMB2_MAX_IO_SIZE as usize {
+            self.write_buffer()?;
+        }
+        Ok(size)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer()
+    }
+}
+
+/// ## SmbReader
+///
+/// Reads data from an open file
+struct SmbReader {
+    connection: Rc<RefCell<SmbConnection>>,
+    file: SmbFile,
+    offset: u64,
+}
+
+impl Read for SmbReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data: Vec<u8> = self
+            .connection
+            .borrow_mut()
+            .read(self.file, self.offset, buf.len() as u32)
+            .map_err(|err| io::Error::other(err.to_string()))?;
+        let size: usize = data.len().min(buf.len());
+        buf[..size].copy_from_slice(&data[..size]);
+        self.offset += size as u64;
+        Ok(size)
+    }
+}
+
+// Helpers
+
+/// ### le_u32
+///
+/// Read little endian u32 at `offset` of `data`
+fn le_u32(data: &[u8], offset: usize) -> Option<u32> {
+    let bytes: &[u8] = data.get(offset..offset.checked_add(4)?)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+/// ### le_u64
+///
+/// Read little endian u64 at `offset` of `data`
+fn le_u64(data: &[u8], offset: usize) -> Option<u64> {
+    let low: u64 = le_u32(data, offset)? as u64;
+    let high: u64 = le_u32(data, offset.checked_add(4)?)? as u64;
+    Some(high << 32 | low)
+}
+
+/// ### utf16le
+///
+/// Encode string as UTF-16LE
+fn utf16le(s: &str) -> Vec<u8> {
+    s.encode_utf16().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+/// ### filetime
+///
+/// Convert `SystemTime` into FILETIME (100ns intervals since 1601-01-01)
+fn filetime(time: SystemTime) -> u64 {
+    let since_epoch: Duration = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    FILETIME_UNIX_EPOCH + (since_epoch.as_nanos() / 100) as u64
+}
+
+/// ### systime
+///
+/// Convert FILETIME into `SystemTime`; times before the UNIX epoch are clamped
+fn systime(filetime: u64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_nanos(filetime.saturating_sub(FILETIME_UNIX_EPOCH) * 100)
+}
+
+/// ### io_error
+///
+/// Make `FileTransferError` from a transport error
+fn io_error(err: io::Error) -> FileTransferError {
+    FileTransferError::new_ex(FileTransferErrorType::ConnectionError, err.to_string())
+}
+
+/// ### check_status
+///
+/// Convert NT status different from success into an error
+fn check_status(status: u32) -> Result<(), FileTransferError> {
+    let kind: FileTransferErrorType = match status {
+        STATUS_SUCCESS => return Ok(()),
+        0xc000_0022 => FileTransferErrorType::PexError, // Access denied
+        0xc000_0034 | 0xc000_003a | 0xc000_00cc => FileTransferErrorType::NoSuchFileOrDirectory,
+        0xc000_0035 => FileTransferErrorType::FileCreateDenied, // Name collision
+        0xc000_00ba | 0xc000_0103 => FileTransferErrorType::DirStatFailed, // Wrong file type
+        0xc000_0101 => FileTransferErrorType::PexError,         // Directory not empty
+        _ => FileTransferErrorType::ProtocolError,
+    };
+    Err(FileTransferError::new_ex(
+        kind,
+        format!("NT status 0x{:08x}", status),
+    ))
+}
+
+/// ### auth_error
+///
+/// Make error for a failed session setup
+fn auth_error(status: u32) -> FileTransferError {
+    FileTransferError::new_ex(
+        FileTransferErrorType::AuthenticationFailed,
+        format!("NT status 0x{:08x}", status),
+    )
+}
+
+// NTLM
+
+/// ### md4
+///
+/// MD4 digest (RFC 1320), required by NTLM
+fn md4(data: &[u8]) -> [u8; 16] {
+    let mut digest: [u8; 16] = [0; 16];
+    digest.copy_from_slice(Md4::digest(data).as_slice());
+    digest
+}
+
+/// ### hmac_md5
+///
+/// HMAC-MD5
+fn hmac_md5(key: &[u8], data: &[u8]) -> [u8; 16] {
+    let mut mac = Hmac::<Md5>::new_varkey(key).expect("HMAC can take keys of any size");
+    mac.update(data);
+    let mut digest: [u8; 16] = [0; 16];
+    digest.copy_from_slice(mac.finalize().into_bytes().as_slice());
+    digest
+}
+
+/// ### hmac_sha256
+///
+/// HMAC-SHA256
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_varkey(key).expect("HMAC can take keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// ### ntlm_negotiate
+///
+/// Make NTLMSSP NEGOTIATE message
+fn ntlm_negotiate() -> Vec<u8> {
+    let flags: u32 = NTLMSSP_NEGOTIATE_UNICODE
+        | NTLMSSP_REQUEST_TARGET
+        | NTLMSSP_NEGOTIATE_NTLM
+        | NTLMSSP_NEGOTIATE_ALWAYS_SIGN
+        | NTLMSSP_NEGOTIATE_EXTENDED_SESSIONSECURITY
+        | NTLMSSP_NEGOTIATE_TARGET_INFO
+        | NTLMSSP_NEGOTIATE_128
+        | NTLMSSP_NEGOTIATE_56;
+    let mut message: Vec<u8> = Vec::with_capacity(32);
+    message.extend_from_slice(b"NTLMSSP\0");
+    message.extend_from_slice(&1u32.to_le_bytes());
+    message.extend_from_slice(&flags.to_le_bytes());
+    message.extend_from_slice(&[0; 16]); // Domain and workstation
+    message
+}
+
+/// ### ntlmv2_response
+///
+/// Compute the NTLMv2 response and the session base key
+fn ntlmv2_response(
+    response_key: &[u8; 16],
+    server_challenge: &[u8],
+    client_challenge: [u8; 8],
+    time: u64,
+    target_info: &[u8],
+) -> (Vec<u8>, [u8; 16]) {
+    let mut temp: Vec<u8> = vec![1, 1, 0, 0, 0, 0, 0, 0];
+    temp.extend_from_slice(&time.to_le_bytes());
+    temp.extend_from_slice(&client_challenge);
+    temp.extend_from_slice(&[0; 4]);
+    temp.extend_from_slice(target_info);
+    temp.extend_from_slice(&[0; 4]);
+    let mut challenge: Vec<u8> = server_challenge.to_vec();
+    challenge.extend_from_slice(temp.as_slice());
+    let proof: [u8; 16] = hmac_md5(response_key, challenge.as_slice());
+    let session_key: [u8; 16] = hmac_md5(response_key, &proof);
+    let mut response: Vec<u8> = proof.to_vec();
+    response.extend_from_slice(temp.as_slice());
+    (response, session_key)
+}
+
+/// ### ntlm_authenticate
+///
+/// Make NTLMSSP AUTHENTICATE message answering to `challenge`.
+/// Returns the message and the session key
+fn ntlm_authenticate(
+    challenge: &[u8],
+    domain: &str,
+    user: &str,
+    password: &str,
+    client_challenge: [u8; 8],
+    time: u64,
+) -> Result<(Vec<u8>, [u8; 16]), FileTransferError> {
+    let malformed = || {
+        FileTransferError::new_ex(
+            FileTransferErrorType::ProtocolError,
+            String::from("Malformed NTLM challenge"),
+        )
+    };
+    if challenge.len() < 48 || &challenge[0..8] != b"NTLMSSP\0" || le_u32(challenge, 8) != Some(2) {
+        return Err(malformed());
+    }
+    let flags: u32 = le_u32(challenge, 20).ok_or_else(malformed)?;
+    let server_challenge: &[u8] = &challenge[24..32];
+    let info_len: usize = le_u32(challenge, 40).ok_or_else(malformed)? as usize & 0xffff;
+    let info_offset: usize = le_u32(challenge, 44).ok_or_else(malformed)? as usize;
+    let target_info: &[u8] = challenge
+        .get(info_offset..info_offset + info_len)
+        .ok_or_else(malformed)?;
+    // Use the server time if provided (MsvAvTimestamp)
+    let mut time: u64 = time;
+    let mut cursor: usize = 0;
+    while let Some(pair) = le_u32(target_info, cursor) {
+        let (id, len): (u32, usize) = (pair & 0xffff, (pair >> 16) as usize);
+        match id {
+            0 => break,
+            7 => time = le_u64(target_info, cursor + 4).unwrap_or(time),
+            _ => {}
+        }
+        cursor += 4 + len;
+    }
+    // NTOWFv2
+    let response_key: [u8; 16] = hmac_md5(
+        &md4(utf16le(password).as_slice()),
+        utf16le(format!("{}{}", user.to_uppercase(), domain).as_str()).as_slice(),
+    );
+    let (nt_response, session_key): (Vec<u8>, [u8; 16]) = ntlmv2_response(
+        &response_key,
+        server_challenge,
+        client_challenge,
+        time,
+        target_info,
+    );
+    // Message: header (64 bytes), then lm response, nt response, domain, user and workstation
+    let domain: Vec<u8> = utf16le(domain);
+    let user: Vec<u8> = utf16le(user);
+    let lm_response: [u8; 24] = [0; 24];
+    let payloads: [&[u8]; 6] = [
+        &lm_response,
+        nt_response.as_slice(),
+        domain.as_slice(),
+        user.as_slice(),
+        &[],
+        &[],
+    ];
+    let mut message: Vec<u8> = Vec::with_capacity(256);
+    message.extend_from_slice(b"NTLMSSP\0");
+    message.extend_from_slice(&3u32.to_le_bytes());
+    let mut offset: usize = 64;
+    for payload in payloads.iter() {
+        message.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(payload.len() as u16).to_le_bytes());
+        message.extend_from_slice(&(offset as u32).to_le_bytes());
+        offset += payload.len();
+    }
+    message.extend_from_slice(&(flags & !0x4000_0000).to_le_bytes()); // Without key exchange
+    for payload in payloads.iter() {
+        message.extend_from_slice(payload);
+    }
+    Ok((message, session_key))
+}
+
+// SPNEGO
+
+/// ### der
+///
+/// Encode DER element
+fn der(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut element: Vec<u8> = vec![tag];
+    match content.len() {
+        len if len < 0x80 => element.push(len as u8),
+        len if len < 0x100 => element.extend_from_slice(&[0x81, len as u8]),
+        len => element.extend_from_slice(&[0x82, (len >> 8) as u8, len as u8]),
+    }
+    element.extend_from_slice(content);
+    element
+}
+
+/// ### spnego_init
+///
+/// Wrap NTLMSSP token into SPNEGO NegTokenInit
+fn spnego_init(token: &[u8]) -> Vec<u8> {
+    let spnego_oid: &[u8] = &[0x06, 0x06, 0x2b, 0x06, 0x01, 0x05, 0x05, 0x02];
+    let ntlmssp_oid: &[u8] = &[
+        0x06, 0x0a, 0x2b, 0x06, 0x01, 0x04, 0x01, 0x82, 0x37, 0x02, 0x02, 0x0a,
+    ];
+    let mut fields: Vec<u8> = der(0xa0, der(0x30, ntlmssp_oid).as_slice());
+    fields.extend_from_slice(der(0xa2, der(0x04, token).as_slice()).as_slice());
+    let mut content: Vec<u8> = spnego_oid.to_vec();
+    content.extend_from_slice(der(0xa0, der(0x30, fields.as_slice()).as_slice()).as_slice());
+    der(0x60, content.as_slice())
+}
+
+/// ### spnego_response
+///
+/// Wrap NTLMSSP token into SPNEGO NegTokenResp
+fn spnego_response(token: &[u8]) -> Vec<u8> {
+    der(
+        0xa1,
+        der(0x30, der(0xa2, der(0x04, token).as_slice()).as_slice()).as_slice(),
+    )
+}
+
+/// ### spnego_token
+///
+/// Get NTLMSSP token from SPNEGO blob (or raw NTLMSSP token)
+fn spnego_token(blob: &[u8]) -> Option<&[u8]> {
+    blob.windows(8)
+        .position(|x| x == b"NTLMSSP\0")
+        .map(|idx| &blob[idx..])
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::net::{Ipv4Addr, TcpListener};
+    use std::thread::{self, JoinHandle};
+
+    /// ### hex
+    ///
+    /// Format bytes as hex string
+    fn hex(data: &[u8]) -> String {
+        data.iter().map(|x| format!("{:02x}", x)).collect()
+    }
+
+    #[test]
+    fn test_filetransfer_smb_new() {
+        let client: SmbFileTransfer = SmbFileTransfer::new();
+        assert!(client.connection.is_none());
+        assert!(client.transfer.is_none());
+        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn test_filetransfer_smb_uninitialized() {
+        let mut client: SmbFileTransfer = SmbFileTransfer::new();
+        assert!(client.pwd().is_err());
+        assert!(client.list_dir(Path::new("/")).is_err());
+        assert!(client.stat(Path::new("/public/a.txt")).is_err());
+        assert!(client.disconnect().is_err());
+        assert!(client.exec("ls").is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_smb_split_path() {
+        assert!(SmbFileTransfer::split_path(Path::new("/")).is_none());
+        assert_eq!(
+            SmbFileTransfer::split_path(Path::new("/public")).unwrap(),
+            (String::from("public"), String::new())
+        );
+        assert_eq!(
+            SmbFileTransfer::split_path(Path::new("/public/docs/omar.txt")).unwrap(),
+            (String::from("public"), String::from("docs\\omar.txt"))
+        );
+        let mut client: SmbFileTransfer = SmbFileTransfer::new();
+        client.wrkdir = PathBuf::from("/public/docs");
+        assert_eq!(
+            client.resolve(Path::new("../pics/./sea.jpg")),
+            PathBuf::from("/public/pics/sea.jpg")
+        );
+        assert_eq!(client.resolve(Path::new("/")), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn test_filetransfer_smb_filetime() {
+        assert_eq!(filetime(UNIX_EPOCH), FILETIME_UNIX_EPOCH);
+        let time: SystemTime = UNIX_EPOCH + Duration::from_secs(1611136800);
+        assert_eq!(systime(filetime(time)), time);
+        // Before UNIX epoch
+        assert_eq!(systime(0), UNIX_EPOCH);
+    }
+
+    #[test]
+    fn test_filetransfer_smb_md4() {
+        assert_eq!(hex(&md4(b"")), "31d6cfe0d16ae931b73c59d7e0c089c0");
+        assert_eq!(hex(&md4(b"abc")), "a448017aaf21d8525fc10ae87aa6729d");
+        assert_eq!(
+            hex(&md4(b"message digest")),
+            "d9130a8164549fe818874806e1c7014b"
+        );
+        assert_eq!(
+            hex(&md4(
+                b"12345678901234567890123456789012345678901234567890123456789012345678901234567890"
+            )),
+            "e33b4ddc9c38f2199c3e7b164fcc0536"
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_smb_hmac() {
+        assert_eq!(
+            hex(&hmac_md5(b"Jefe", b"what do ya want for nothing?")),
+            "750c783e6ab0b503eaa86e310a5db738"
+        );
+        assert_eq!(
+            hex(hmac_sha256(b"Jefe", b"what do ya want for nothing?").as_slice()),
+            "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_smb_ntlmv2() {
+        // Test vectors from MS-NLMP 4.2.4
+        let response_key: [u8; 16] = hmac_md5(
+            &md4(utf16le("Password").as_slice()),
+            utf16le("USERDomain").as_slice(),
+        );
+        assert_eq!(hex(&response_key), "0c868a403bfd7a93a3001ef22ef02e3f");
+        let mut target_info: Vec<u8> = vec![0x02, 0x00, 0x0c, 0x00];
+        target_info.extend_from_slice(utf16le("Domain").as_slice());
+        target_info.extend_from_slice(&[0x01, 0x00, 0x0c, 0x00]);
+        target_info.extend_from_slice(utf16le("Server").as_slice());
+        target_info.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]);
+        let (response, session_key): (Vec<u8>, [u8; 16]) = ntlmv2_response(
+            &response_key,
+            &[0x01, 0x23, 0x45, 0x67, 0x89, 0xab, 0xcd, 0xef],
+            [0xaa; 8],
+            0,
+            target_info.as_slice(),
+        );
+        assert_eq!(hex(&response[..16]), "68cd0ab851e51c96aabc927bebef6a1c");
+        assert_eq!(hex(&session_key), "8de40ccadbc14a82f15cb0ad0de95ca3");
+    }
+
+    #[test]
+    fn test_filetransfer_smb_spnego() {
+        let negotiate: Vec<u8> = ntlm_negotiate();
+        assert_eq!(&negotiate[0..8], b"NTLMSSP\0");
+        let blob: Vec<u8> = spnego_init(negotiate.as_slice());
+        assert_eq!(blob[0], 0x60);
+        assert_eq!(blob[1] as usize, blob.len() - 2);
+        assert_eq!(spnego_token(blob.as_slice()).unwrap(), negotiate.as_slice());
+        // Long tokens
+        let mut token: Vec<u8> = b"NTLMSSP\0".to_vec();
+        token.resize(300, 0xff);
+        let blob: Vec<u8> = spnego_response(token.as_slice());
+        assert_eq!(&blob[0..4], &[0xa1, 0x82, 0x01, 0x38]);
+        assert_eq!(spnego_token(blob.as_slice()).unwrap(), token.as_slice());
+        assert!(spnego_token(&[0xa1, 0x00]).is_none());
+    }
+
+    #[test]
+    fn test_filetransfer_smb_parse_directory() {
+        let make_entry = |name: &str, attributes: u32, size: u64, last: bool| -> Vec<u8> {
+            let name: Vec<u8> = utf16le(name);
+            let mut entry: Vec<u8> = Vec::new();
+            let len: usize = (64 + name.len()).div_ceil(8) * 8;
+            entry.extend_from_slice(&(if last { 0 } else { len as u32 }).to_le_bytes());
+            entry.extend_from_slice(&0u32.to_le_bytes());
+            for _ in 0..4 {
+                entry.extend_from_slice(&(FILETIME_UNIX_EPOCH + 10_000_000).to_le_bytes());
+            }
+            entry.extend_from_slice(&size.to_le_bytes());
+            entry.extend_from_slice(&4096u64.to_le_bytes());
+            entry.extend_from_slice(&attributes.to_le_bytes());
+            entry.extend_from_slice(&(name.len() as u32).to_le_bytes());
+            entry.extend_from_slice(name.as_slice());
+            if !last {
+                entry.resize(len, 0);
+            }
+            entry
+        };
+        let mut buffer: Vec<u8> = make_entry(".", FILE_ATTRIBUTE_DIRECTORY, 0, false);
+        buffer.extend(make_entry("..", FILE_ATTRIBUTE_DIRECTORY, 0, false));
+        buffer.extend(make_entry("docs", FILE_ATTRIBUTE_DIRECTORY, 0, false));
+        buffer.extend(make_entry(
+            "Ünicode.txt",
+            FILE_ATTRIBUTE_READONLY,
+            8192,
+            true,
+        ));
+        let entries: Vec<FsEntry> =
+            SmbFileTransfer::parse_directory(Path::new("/public"), buffer.as_slice())
+                .ok()
+                .unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].is_dir());
+        assert_eq!(entries[0].get_abs_path(), PathBuf::from("/public/docs"));
+        assert!(entries[1].is_file());
+        assert_eq!(entries[1].get_name(), "Ünicode.txt");
+        assert_eq!(entries[1].get_size(), 8192);
+        assert!(matches!(&entries[1], FsEntry::File(file) if file.readonly));
+        assert_eq!(
+            entries[1].get_last_change_time(),
+            UNIX_EPOCH + Duration::from_secs(1)
+        );
+        // Truncated
+        assert!(SmbFileTransfer::parse_directory(Path::new("/"), &buffer[0..60]).is_err());
+        // Name overflowing into the next entry
+        let mut bad: Vec<u8> = make_entry("docs", FILE_ATTRIBUTE_DIRECTORY, 0, false);
+        bad[60..64].copy_from_slice(&16u32.to_le_bytes());
+        bad.extend(make_entry("a.txt", 0, 1, true));
+        assert!(SmbFileTransfer::parse_directory(Path::new("/"), bad.as_slice()).is_err());
+        let mut bad: Vec<u8> = make_entry("docs", FILE_ATTRIBUTE_DIRECTORY, 0, true);
+        bad[60..64].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(SmbFileTransfer::parse_directory(Path::new("/"), bad.as_slice()).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_smb_check_status() {
+        assert!(check_status(STATUS_SUCCESS).is_ok());
+        assert!(matches!(
+            check_status(0xc000_0034).err().unwrap().code,
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        assert!(matches!(
+            check_status(0xc000_0022).err().unwrap().code,
+            FileTransferErrorType::PexError
+        ));
+        assert!(matches!(
+            check_status(0xc000_0035).err().unwrap().code,
+            FileTransferErrorType::FileCreateDenied
+        ));
+        assert!(matches!(
+            check_status(0xc000_0001).err().unwrap().code,
+            FileTransferErrorType::ProtocolError
+        ));
+    }
+
+    /// ### mock_server
+    ///
+    /// Start a server answering to a single connection: it authenticates "omar" with password
+    /// "secret", connects to the share "public" and answers to CREATE requests on "docs"
+    fn mock_server() -> (u16, JoinHandle<()>) {
+        let listener: TcpListener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).unwrap();
+        let port: u16 = listener.local_addr().unwrap().port();
+        let thread: JoinHandle<()> = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let server_challenge: [u8; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+            loop {
+                let mut header: [u8; 4] = [0; 4];
+                if stream.read_exact(&mut header).is_err() {
+                    return;
+                }
+                let mut request: Vec<u8> = vec![0; u32::from_be_bytes(header) as usize];
+                stream.read_exact(request.as_mut_slice()).unwrap();
+                let command: u16 = u16::from_le_bytes([request[12], request[13]]);
+                let (status, body): (u32, Vec<u8>) = match command {
+                    SMB2_NEGOTIATE => {
+                        let mut body: Vec<u8> = vec![65, 0, 1, 0, 0x10, 0x02, 0, 0];
+                        body.resize(64, 0);
+                        (STATUS_SUCCESS, body)
+                    }
+                    SMB2_SESSION_SETUP => {
+                        let offset: usize =
+                            le_u32(request.as_slice(), 64 + 12).unwrap() as usize & 0xffff;
+                        let token: &[u8] = spnego_token(&request[offset..]).unwrap();
+                        match le_u32(token, 8).unwrap() {
+                            1 => {
+                                let mut challenge: Vec<u8> = b"NTLMSSP\0".to_vec();
+                                challenge.extend_from_slice(&2u32.to_le_bytes());
+                                challenge.extend_from_slice(&[0; 8]); // Target name
+                                challenge.extend_from_slice(&0x0088_8205u32.to_le_bytes());
+                                challenge.extend_from_slice(&server_challenge);
+                                challenge.extend_from_slice(&[0; 8]);
+                                challenge.extend_from_slice(&[4, 0, 4, 0, 48, 0, 0, 0]);
+                                challenge.extend_from_slice(&[0, 0, 0, 0]); // EOL
+                                let mut body: Vec<u8> = vec![9, 0, 0, 0, 72, 0];
+                                body.extend_from_slice(&(challenge.len() as u16).to_le_bytes());
+                                body.extend(challenge);
+                                (STATUS_MORE_PROCESSING_REQUIRED, body)
+                            }
+                            _ => {
+                                // Verify NTLMv2 response
+                                let field = |idx: usize| -> &[u8] {
+                                    let len: usize =
+                                        u16::from_le_bytes([token[idx], token[idx + 1]]) as usize;
+                                    let offset: usize = le_u32(token, idx + 4).unwrap() as usize;
+                                    &token[offset..offset + len]
+                                };
+                                let nt_response: &[u8] = field(20);
+                                let user: &[u8] = field(36);
+                                let key: [u8; 16] = hmac_md5(
+                                    &md4(utf16le("secret").as_slice()),
+                                    utf16le("OMAR").as_slice(),
+                                );
+                                let mut challenge: Vec<u8> = server_challenge.to_vec();
+                                challenge.extend_from_slice(&nt_response[16..]);
+                                let valid: bool = user == utf16le("omar").as_slice()
+                                    && hmac_md5(&key, challenge.as_slice()) == nt_response[..16];
+                                match valid {
+                                    true => (STATUS_SUCCESS, vec![9, 0, 0, 0, 0, 0, 0, 0]),
+                                    false => (0xc000_006d, vec![9, 0, 0, 0, 0, 0, 0, 0]),
+                                }
+                            }
+                        }
+                    }
+                    SMB2_TREE_CONNECT => {
+                        let path: Vec<u8> = request[72..].to_vec();
+                        match path == utf16le("\\\\127.0.0.1\\public") {
+                            true => (STATUS_SUCCESS, vec![16, 0, 1, 0, 0, 0, 0, 0]),
+                            false => (0xc000_00cc, vec![9, 0, 0, 0, 0, 0, 0, 0]),
+                        }
+                    }
+                    SMB2_CREATE => {
+                        let name: Vec<u8> = request[120..].to_vec();
+                        match name == utf16le("docs") {
+                            true => {
+                                let mut body: Vec<u8> = vec![89, 0, 0, 0, 1, 0, 0, 0];
+                                for _ in 0..4 {
+                                    body.extend_from_slice(&FILETIME_UNIX_EPOCH.to_le_bytes());
+                                }
+                                body.extend_from_slice(&0u64.to_le_bytes());
+                                body.extend_from_slice(&0u64.to_le_bytes());
+                                body.extend_from_slice(&FILE_ATTRIBUTE_DIRECTORY.to_le_bytes());
+                                body.extend_from_slice(&[0; 4]);
+                                body.extend_from_slice(&[0xee; 16]); // File id
+                                body.extend_from_slice(&[0; 8]);
+                                (STATUS_SUCCESS, body)
+                            }
+                            false => (0xc000_0034, vec![9, 0, 0, 0, 0, 0, 0, 0]),
+                        }
+                    }
+                    SMB2_CLOSE => {
+                        assert_eq!(&request[72..88], &[0xee; 16]);
+                        let mut body: Vec<u8> = vec![60, 0];
+                        body.resize(60, 0);
+                        (STATUS_SUCCESS, body)
+                    }
+                    _ => (STATUS_SUCCESS, vec![4, 0, 0, 0]),
+                };
+                let mut response: Vec<u8> = request[0..64].to_vec();
+                response[8..12].copy_from_slice(&status.to_le_bytes());
+                response[16] |= 0x01; // Server to redirector
+                response[36..40].copy_from_slice(&7u32.to_le_bytes()); // Tree id
+                response[40..48].copy_from_slice(&0x42u64.to_le_bytes()); // Session id
+                response.extend(body);
+                let mut frame: Vec<u8> = (response.len() as u32).to_be_bytes().to_vec();
+                frame.extend(response);
+                stream.write_all(frame.as_slice()).unwrap();
+            }
+        });
+        (port, thread)
+    }
+
+    #[test]
+    fn test_filetransfer_smb_mock_session() {
+        let (port, server) = mock_server();
+        let mut client: SmbFileTransfer = SmbFileTransfer::new();
+        assert_eq!(
+            client
+                .connect(
+                    String::from("127.0.0.1/public"),
+                    port,
+                    Some(String::from("omar")),
+                    Some(String::from("secret")),
+                )
+                .ok()
+                .unwrap()
+                .unwrap()
+                .as_str(),
+            "SMB 2.1"
+        );
+        assert!(client.is_connected());
+        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/public"));
+        // Root lists shares
+        let shares: Vec<FsEntry> = client.list_dir(Path::new("/")).ok().unwrap();
+        assert_eq!(shares.len(), 1);
+        assert_eq!(shares[0].get_name(), "public");
+        // Stat
+        let docs: FsEntry = client.stat(Path::new("docs")).ok().unwrap();
+        assert!(docs.is_dir());
+        assert_eq!(docs.get_abs_path(), PathBuf::from("/public/docs"));
+        assert!(matches!(
+            client.stat(Path::new("omar.txt")).err().unwrap().code,
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        assert!(client.change_dir(Path::new("docs")).is_ok());
+        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/public/docs"));
+        // Moving files between shares is not supported
+        assert!(client.rename(&docs, Path::new("/private/docs")).is_err());
+        assert!(client.disconnect().is_ok());
+        assert!(!client.is_connected());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn test_filetransfer_smb_mock_bad_credentials() {
+        let (port, server) = mock_server();
+        let mut client: SmbFileTransfer = SmbFileTransfer::new();
+        let err: FileTransferError = client
+            .connect(
+                String::from("127.0.0.1"),
+                port,
+                Some(String::from("omar")),
+                Some(String::from("pippo")),
+            )
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.code,
+            FileTransferErrorType::AuthenticationFailed
+        ));
+        assert!(!client.is_connected());
+        server.join().unwrap();
+    }
+}
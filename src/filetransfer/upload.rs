@@ -0,0 +1,212 @@
+//! ## Upload
+//!
+//! `upload` is the module which provides the streamed request bodies used to upload files over HTTP
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use super::{FileTransferError, FileTransferErrorType};
+
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+
+/// ## Upload
+///
+/// PUT request sent by a worker thread, whose body is fed through an `UploadStream`
+pub(super) struct Upload {
+    worker: JoinHandle<Result<(), FileTransferError>>,
+    sender: SyncSender<Vec<u8>>, // Used to interrupt the request body
+}
+
+impl Upload {
+    /// ### start
+    ///
+    /// Start a worker calling `send` with the request body; returns the upload and the stream feeding the body
+    pub(super) fn start<F>(send: F) -> (Upload, UploadStream)
+    where
+        F: FnOnce(UploadReader) -> Result<(), FileTransferError> + Send + 'static,
+    {
+        let (sender, receiver): (SyncSender<Vec<u8>>, Receiver<Vec<u8>>) = mpsc::sync_channel(16);
+        let worker = thread::spawn(move || send(UploadReader::new(receiver)));
+        (
+            Upload {
+                worker,
+                sender: sender.clone(),
+            },
+            UploadStream::new(sender),
+        )
+    }
+
+    /// ### finish
+    ///
+    /// Wait for the worker, once the stream has been dropped, and return the result of the request.
+    /// If the upload hasn't been `completed`, the request body is interrupted, so that the request fails
+    /// instead of sending a truncated file; the result of the request is then discarded
+    pub(super) fn finish(self, completed: bool) -> Result<(), FileTransferError> {
+        if !completed {
+            // An empty chunk interrupts the body
+            let _ = self.sender.send(Vec::new());
+        }
+        drop(self.sender);
+        let result: Result<(), FileTransferError> = match self.worker.join() {
+            Ok(result) => result,
+            Err(_) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::ProtocolError,
+                String::from("Upload worker panicked"),
+            )),
+        };
+        match completed {
+            true => result,
+            false => Ok(()),
+        }
+    }
+}
+
+/// ## UploadStream
+///
+/// Writable end of an upload: written data is sent to the worker which sends the PUT request
+pub(super) struct UploadStream {
+    sender: SyncSender<Vec<u8>>,
+}
+
+impl UploadStream {
+    /// ### new
+    ///
+    /// Instantiates a new `UploadStream`
+    pub(super) fn new(sender: SyncSender<Vec<u8>>) -> UploadStream {
+        UploadStream { sender }
+    }
+}
+
+impl Write for UploadStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Empty chunks are reserved to interrupt the upload
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        match self.sender.send(buf.to_vec()) {
+            Ok(_) => Ok(buf.len()),
+            Err(_) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "Upload has been interrupted",
+            )),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// ## UploadReader
+///
+/// Readable end of an upload, used as request body; it reaches EOF when the stream is dropped,
+/// while it fails if the upload has been interrupted
+pub(super) struct UploadReader {
+    receiver: Receiver<Vec<u8>>,
+    chunk: Vec<u8>,
+    pos: usize,
+}
+
+impl UploadReader {
+    /// ### new
+    ///
+    /// Instantiates a new `UploadReader`
+    pub(super) fn new(receiver: Receiver<Vec<u8>>) -> UploadReader {
+        UploadReader {
+            receiver,
+            chunk: Vec::new(),
+            pos: 0,
+        }
+    }
+}
+
+impl Read for UploadReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos >= self.chunk.len() {
+            match self.receiver.recv() {
+                Ok(chunk) if chunk.is_empty() => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "Upload has been interrupted",
+                    ))
+                }
+                Ok(chunk) => {
+                    self.chunk = chunk;
+                    self.pos = 0;
+                }
+                Err(_) => return Ok(0), // Stream has been dropped
+            }
+        }
+        let size: usize = buf.len().min(self.chunk.len() - self.pos);
+        buf[..size].copy_from_slice(&self.chunk[self.pos..self.pos + size]);
+        self.pos += size;
+        Ok(size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_filetransfer_upload_completed() {
+        let (upload, mut stream): (Upload, UploadStream) = Upload::start(|mut body| {
+            let mut data: Vec<u8> = Vec::new();
+            body.read_to_end(&mut data).ok().unwrap();
+            assert_eq!(data.as_slice(), b"Hello, world!");
+            Ok(())
+        });
+        assert!(stream.write_all(b"Hello, ").is_ok());
+        assert!(stream.write_all(b"world!").is_ok());
+        // Empty writes don't interrupt the upload
+        assert_eq!(stream.write(&[]).ok().unwrap(), 0);
+        drop(stream);
+        assert!(upload.finish(true).is_ok());
+    }
+
+    #[test]
+    fn test_filetransfer_upload_interrupted() {
+        let (upload, mut stream): (Upload, UploadStream) = Upload::start(|mut body| {
+            let mut data: Vec<u8> = Vec::new();
+            match body.read_to_end(&mut data) {
+                Ok(_) => Ok(()),
+                Err(err) => Err(FileTransferError::new_ex(
+                    FileTransferErrorType::ProtocolError,
+                    err.to_string(),
+                )),
+            }
+        });
+        assert!(stream.write_all(b"Hello").is_ok());
+        drop(stream);
+        // Request result is discarded
+        assert!(upload.finish(false).is_ok());
+        // Request fails
+        let (upload, stream): (Upload, UploadStream) =
+            Upload::start(|_| Err(FileTransferError::new(FileTransferErrorType::ProtocolError)));
+        drop(stream);
+        assert!(upload.finish(true).is_err());
+    }
+}
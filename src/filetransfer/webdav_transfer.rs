@@ -0,0 +1,1018 @@
+//! ## WebDavTransfer
+//!
+//! `webdav_transfer` is the module which provides the implementation for the WebDAV file transfer
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Dependencies
+extern crate base64;
+extern crate chrono;
+extern crate percent_encoding;
+extern crate regex;
+extern crate tracing;
+extern crate ureq;
+
+use super::upload::{Upload, UploadStream};
+use super::{
+    FileTransfer, FileTransferError, FileTransferErrorType, NetworkOptions, TransferOutcome,
+};
+use crate::fs::{FsDirectory, FsEntry, FsFile};
+
+// Includes
+use chrono::DateTime;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+use tracing::{info_span, instrument, Span};
+use ureq::{Agent, Request, Response};
+
+/// Characters which must be percent-encoded in a path segment
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Body of PROPFIND requests
+const PROPFIND_BODY: &str = "<?xml version=\"1.0\" encoding=\"utf-8\"?>\
+<D:propfind xmlns:D=\"DAV:\"><D:prop>\
+<D:resourcetype/><D:getcontentlength/><D:getlastmodified/><D:creationdate/>\
+</D:prop></D:propfind>";
+
+lazy_static! {
+    /**
+     * Matches a `response` element of a multistatus body, whatever its namespace prefix is
+     */
+    static ref RESPONSE_REGEX: Regex = Regex::new(
+        r"(?s)<(?:[\w-]+:)?response\b[^>]*>(.*?)</(?:[\w-]+:)?response>"
+    )
+    .unwrap();
+    /**
+     * Matches a leaf element (e.g. `<D:href>/a</D:href>` or `<D:collection/>`):
+     * 1. name; 2. text content
+     */
+    static ref LEAF_REGEX: Regex = Regex::new(
+        r"(?s)<(?:[\w-]+:)?([\w-]+)\b[^>]*?(?:/>|>([^<]*)</(?:[\w-]+:)?[\w-]+>)"
+    )
+    .unwrap();
+}
+
+/// ## DavResource
+///
+/// A resource described in a PROPFIND multistatus response
+#[derive(Debug, PartialEq)]
+struct DavResource {
+    path: PathBuf,
+    is_dir: bool,
+    size: usize,
+    mtime: Option<SystemTime>,
+    ctime: Option<SystemTime>,
+}
+
+/// ## WebDavFileTransfer
+///
+/// WebDAV file transfer struct (over HTTP or HTTPS)
+pub struct WebDavFileTransfer {
+    agent: Option<Agent>,
+    secure: bool,
+    url: String,                   // Server URL (scheme, host and port)
+    authorization: Option<String>, // Value of `Authorization` header
+    wrkdir: PathBuf,
+    upload: Option<Upload>, // Pending PUT request
+    network: NetworkOptions,
+    span: Span, // Session span; parent of each remote operation span
+}
+
+impl WebDavFileTransfer {
+    /// ### new
+    ///
+    /// Instantiates a new `WebDavFileTransfer`. If `secure` is true, HTTPS is used
+    pub fn new(secure: bool) -> WebDavFileTransfer {
+        WebDavFileTransfer {
+            agent: None,
+            secure,
+            url: String::new(),
+            authorization: None,
+            wrkdir: PathBuf::from("/"),
+            upload: None,
+            network: NetworkOptions::default(),
+            span: Span::none(),
+        }
+    }
+
+    /// ### with_network_options
+    ///
+    /// Set connection timeout and proxy
+    pub fn with_network_options(mut self, options: NetworkOptions) -> Self {
+        self.network = options;
+        self
+    }
+
+    /// ### resolve
+    ///
+    /// Get absolute and normalized path from `p` (relative paths are relative to wrkdir)
+    fn resolve(&self, p: &Path) -> PathBuf {
+        let mut path: PathBuf = PathBuf::from("/");
+        for component in self.wrkdir.join(p).components() {
+            match component {
+                Component::Normal(name) => path.push(name),
+                Component::ParentDir => {
+                    path.pop();
+                }
+                _ => {}
+            }
+        }
+        path
+    }
+
+    /// ### url_of
+    ///
+    /// Get the URL of the resource at `p`. Collections' URLs end with a slash
+    fn url_of(&self, p: &Path, collection: bool) -> String {
+        let mut url: String = self.url.clone();
+        for component in self.resolve(p).components() {
+            if let Component::Normal(name) = component {
+                url.push('/');
+                url.push_str(
+                    utf8_percent_encode(name.to_string_lossy().as_ref(), PATH_SEGMENT)
+                        .to_string()
+                        .as_str(),
+                );
+            }
+        }
+        if collection || url.len() == self.url.len() {
+            url.push('/');
+        }
+        url
+    }
+
+    /// ### request
+    ///
+    /// Prepare a request for the resource at `p`
+    fn request(
+        &self,
+        method: &str,
+        p: &Path,
+        collection: bool,
+    ) -> Result<Request, FileTransferError> {
+        match self.agent.as_ref() {
+            Some(agent) => {
+                let request: Request = agent.request(method, self.url_of(p, collection).as_str());
+                Ok(match self.authorization.as_ref() {
+                    Some(auth) => request.set("Authorization", auth.as_str()),
+                    None => request,
+                })
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### propfind
+    ///
+    /// Get the properties of the resource at `p` (depth 0) or of its children too (depth 1).
+    /// If the server redirects to the collection URL, the request is sent again to it
+    fn propfind(
+        &self,
+        p: &Path,
+        collection: bool,
+        depth: u8,
+    ) -> Result<Vec<DavResource>, FileTransferError> {
+        let response: Response = self
+            .request("PROPFIND", p, collection)?
+            .set("Depth", depth.to_string().as_str())
+            .set("Content-Type", "application/xml; charset=utf-8")
+            .send_string(PROPFIND_BODY)
+            .map_err(Self::map_error)?;
+        match response.status() {
+            301 | 302 | 307 | 308 if !collection => self.propfind(p, true, depth),
+            207 => match response.into_string() {
+                Ok(body) => Ok(Self::parse_multistatus(body.as_str())),
+                Err(err) => Err(FileTransferError::new_ex(
+                    FileTransferErrorType::ProtocolError,
+                    err.to_string(),
+                )),
+            },
+            status => Err(Self::status_error(status)),
+        }
+    }
+
+    /// ### stat_resource
+    ///
+    /// Stat resource at `p`
+    fn stat_resource(&self, p: &Path, collection: bool) -> Result<FsEntry, FileTransferError> {
+        let path: PathBuf = self.resolve(p);
+        match self.propfind(path.as_path(), collection, 0)?.pop() {
+            Some(resource) => Ok(Self::make_fsentry(resource)),
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::NoSuchFileOrDirectory,
+            )),
+        }
+    }
+
+    /// ### move_or_copy
+    ///
+    /// Send a MOVE or COPY request for `src` to `dst`
+    fn move_or_copy(
+        &self,
+        method: &str,
+        src: &FsEntry,
+        dst: &Path,
+    ) -> Result<(), FileTransferError> {
+        let destination: String = self.url_of(dst, src.is_dir());
+        let response: Response = self
+            .request(method, src.get_abs_path().as_path(), src.is_dir())?
+            .set("Destination", destination.as_str())
+            .set("Overwrite", "F")
+            .set("Depth", "infinity")
+            .call()
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())
+    }
+
+    /// ### check_status
+    ///
+    /// Convert a status code different from 2xx into an error
+    fn check_status(status: u16) -> Result<(), FileTransferError> {
+        match status {
+            200..=299 => Ok(()),
+            status => Err(Self::status_error(status)),
+        }
+    }
+
+    /// ### status_error
+    ///
+    /// Make error from HTTP status code
+    fn status_error(status: u16) -> FileTransferError {
+        let kind: FileTransferErrorType = match status {
+            401 => FileTransferErrorType::AuthenticationFailed,
+            403 => FileTransferErrorType::FileCreateDenied,
+            404 | 409 => FileTransferErrorType::NoSuchFileOrDirectory,
+            405 | 501 => FileTransferErrorType::UnsupportedFeature,
+            _ => FileTransferErrorType::ProtocolError,
+        };
+        FileTransferError::new_ex(kind, format!("HTTP status {}", status))
+    }
+
+    /// ### map_error
+    ///
+    /// Convert ureq error into `FileTransferError`
+    fn map_error(err: ureq::Error) -> FileTransferError {
+        match err {
+            ureq::Error::Status(status, _) => Self::status_error(status),
+            ureq::Error::Transport(err) => {
+                FileTransferError::new_ex(FileTransferErrorType::ConnectionError, err.to_string())
+            }
+        }
+    }
+
+    /// ### parse_multistatus
+    ///
+    /// Parse the resources described in a multistatus response body
+    fn parse_multistatus(body: &str) -> Vec<DavResource> {
+        RESPONSE_REGEX
+            .captures_iter(body)
+            .filter_map(|response| {
+                let mut href: Option<PathBuf> = None;
+                let mut resource: DavResource = DavResource {
+                    path: PathBuf::new(),
+                    is_dir: false,
+                    size: 0,
+                    mtime: None,
+                    ctime: None,
+                };
+                for leaf in LEAF_REGEX.captures_iter(&response[1]) {
+                    let text: &str = leaf.get(2).map(|x| x.as_str().trim()).unwrap_or("");
+                    match &leaf[1] {
+                        "href" => href = Some(Self::parse_href(text)),
+                        "collection" => resource.is_dir = true,
+                        "getcontentlength" => resource.size = text.parse::<usize>().unwrap_or(0),
+                        "getlastmodified" => {
+                            resource.mtime = DateTime::parse_from_rfc2822(text)
+                                .ok()
+                                .map(SystemTime::from)
+                        }
+                        "creationdate" => {
+                            resource.ctime = DateTime::parse_from_rfc3339(text)
+                                .ok()
+                                .map(SystemTime::from)
+                        }
+                        _ => {}
+                    }
+                }
+                href.map(|path| {
+                    resource.path = path;
+                    resource
+                })
+            })
+            .collect()
+    }
+
+    /// ### parse_href
+    ///
+    /// Get path from href (which may be an absolute URL)
+    fn parse_href(href: &str) -> PathBuf {
+        let href: String = href
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&apos;", "'")
+            .replace("&amp;", "&");
+        // Strip scheme and host
+        let path: &str = match href.find("://") {
+            Some(idx) => match href[idx + 3..].find('/') {
+                Some(slash) => &href[idx + 3 + slash..],
+                None => "/",
+            },
+            None => href.as_str(),
+        };
+        let path: String = percent_decode_str(path).decode_utf8_lossy().to_string();
+        match path.trim_end_matches('/') {
+            "" => PathBuf::from("/"),
+            path => PathBuf::from(path),
+        }
+    }
+
+    /// ### make_fsentry
+    ///
+    /// Make `FsEntry` from resource
+    fn make_fsentry(resource: DavResource) -> FsEntry {
+        let name: String = match resource.path.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => String::from("/"),
+        };
+        let mtime: SystemTime = resource.mtime.unwrap_or(SystemTime::UNIX_EPOCH);
+        let ctime: SystemTime = resource.ctime.unwrap_or(mtime);
+        match resource.is_dir {
+            true => FsEntry::Directory(FsDirectory {
+                name,
+                abs_path: resource.path,
+                last_change_time: mtime,
+                last_access_time: mtime,
+                creation_time: ctime,
+                readonly: false,
+                symlink: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            }),
+            false => FsEntry::File(FsFile {
+                ftype: resource
+                    .path
+                    .extension()
+                    .map(|x| x.to_string_lossy().to_string()),
+                name,
+                abs_path: resource.path,
+                last_change_time: mtime,
+                last_access_time: mtime,
+                creation_time: ctime,
+                size: resource.size,
+                readonly: false,
+                symlink: None,
+                broken_link: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            }),
+        }
+    }
+}
+
+impl FileTransfer for WebDavFileTransfer {
+    /// ### connect
+    ///
+    /// Connect to the remote server.
+    /// HTTP is stateless, so an OPTIONS request is sent to check whether the server is reachable
+    /// and the credentials are valid
+
+    #[instrument(skip_all, fields(host = %address, port), err)]
+    fn connect(
+        &mut self,
+        address: String,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+    ) -> Result<Option<String>, FileTransferError> {
+        let agent: Agent = self.network.http_agent()?.redirects(0).build();
+        self.url = format!(
+            "{}://{}:{}",
+            match self.secure {
+                true => "https",
+                false => "http",
+            },
+            address,
+            port
+        );
+        self.authorization = username.map(|username| {
+            format!(
+                "Basic {}",
+                base64::encode(format!("{}:{}", username, password.unwrap_or_default()))
+            )
+        });
+        self.agent = Some(agent);
+        self.wrkdir = PathBuf::from("/");
+        let result: Result<Response, FileTransferError> = self
+            .request("OPTIONS", Path::new("/"), true)
+            .and_then(|request| request.call().map_err(Self::map_error));
+        match result {
+            Ok(response) => {
+                // Open session span
+                self.span = match self.secure {
+                    true => info_span!("session", protocol = "WEBDAVS", host = %address, port),
+                    false => info_span!("session", protocol = "WEBDAV", host = %address, port),
+                };
+                Ok(response.header("Server").map(String::from))
+            }
+            Err(err) => {
+                self.agent = None;
+                Err(err)
+            }
+        }
+    }
+
+    /// ### disconnect
+    ///
+    /// Disconnect from the remote server
+
+    #[instrument(parent = &self.span, skip_all, err)]
+    fn disconnect(&mut self) -> Result<(), FileTransferError> {
+        match self.agent.take() {
+            Some(_) => {
+                self.authorization = None;
+                // Close session span
+                self.span = Span::none();
+                Ok(())
+            }
+            None => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### is_connected
+    ///
+    /// Indicates whether the client is connected to remote
+    fn is_connected(&self) -> bool {
+        self.agent.is_some()
+    }
+
+    /// ### pwd
+    ///
+    /// Print working directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, err)]
+    fn pwd(&mut self) -> Result<PathBuf, FileTransferError> {
+        match self.is_connected() {
+            true => Ok(self.wrkdir.clone()),
+            false => Err(FileTransferError::new(
+                FileTransferErrorType::UninitializedSession,
+            )),
+        }
+    }
+
+    /// ### change_dir
+    ///
+    /// Change working directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn change_dir(&mut self, dir: &Path) -> Result<PathBuf, FileTransferError> {
+        match self.stat_resource(dir, true)? {
+            FsEntry::Directory(dir) => {
+                self.wrkdir = dir.abs_path;
+                Ok(self.wrkdir.clone())
+            }
+            FsEntry::File(_) => Err(FileTransferError::new_ex(
+                FileTransferErrorType::DirStatFailed,
+                String::from("Not a directory"),
+            )),
+        }
+    }
+
+    /// ### copy
+    ///
+    /// Copy file to destination
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %src.get_abs_path().display(), dst = %dst.display()), err)]
+    fn copy(&mut self, src: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        self.move_or_copy("COPY", src, dst)
+    }
+
+    /// ### list_dir
+    ///
+    /// List directory entries
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn list_dir(&mut self, path: &Path) -> Result<Vec<FsEntry>, FileTransferError> {
+        let path: PathBuf = self.resolve(path);
+        Ok(self
+            .propfind(path.as_path(), true, 1)?
+            .into_iter()
+            .filter(|x| x.path != path) // Directory itself is listed too
+            .map(Self::make_fsentry)
+            .collect())
+    }
+
+    /// ### mkdir
+    ///
+    /// Make directory
+    /// In case the directory already exists, an error of kind `FileTransferErrorType::FileCreateDenied` is returned
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %dir.display()), err)]
+    fn mkdir(&mut self, dir: &Path) -> Result<(), FileTransferError> {
+        let response: Result<Response, FileTransferError> = self
+            .request("MKCOL", dir, true)?
+            .call()
+            .map_err(Self::map_error);
+        match response {
+            Ok(response) => Self::check_status(response.status()),
+            // 405 is returned if the resource already exists
+            Err(err) if matches!(err.code, FileTransferErrorType::UnsupportedFeature) => {
+                Err(FileTransferError::new_ex(
+                    FileTransferErrorType::FileCreateDenied,
+                    String::from("Directory already exists"),
+                ))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    /// ### remove
+    ///
+    /// Remove a file or a directory (collections are removed recursively by the server)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %file.get_abs_path().display()), err)]
+    fn remove(&mut self, file: &FsEntry) -> Result<(), FileTransferError> {
+        let response: Response = self
+            .request("DELETE", file.get_abs_path().as_path(), file.is_dir())?
+            .call()
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())
+    }
+
+    /// ### rename
+    ///
+    /// Rename file or a directory
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(src = %file.get_abs_path().display(), dst = %dst.display()), err)]
+    fn rename(&mut self, file: &FsEntry, dst: &Path) -> Result<(), FileTransferError> {
+        self.move_or_copy("MOVE", file, dst)
+    }
+
+    /// ### stat
+    ///
+    /// Stat file and return FsEntry
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(path = %path.display()), err)]
+    fn stat(&mut self, path: &Path) -> Result<FsEntry, FileTransferError> {
+        self.stat_resource(path, false)
+    }
+
+    /// ### exec
+    ///
+    /// Execute a command on remote host (not supported by WebDAV)
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(cmd = %cmd), err)]
+    fn exec(&mut self, cmd: &str) -> Result<String, FileTransferError> {
+        Err(FileTransferError::new(
+            FileTransferErrorType::UnsupportedFeature,
+        ))
+    }
+
+    /// ### send_file
+    ///
+    /// Send file to remote
+    /// File name is referred to the name of the file as it will be saved
+    /// Data contains the file data.
+    /// The PUT request is sent by a worker thread, which is fed by the returned stream
+    /// and joined by `finalize_transfer`
+
+    #[instrument(parent = &self.span, skip_all, fields(path = %file_name.display()), err)]
+    fn send_file(
+        &mut self,
+        local: &FsFile,
+        file_name: &Path,
+    ) -> Result<Box<dyn Write>, FileTransferError> {
+        let request: Request = self
+            .request("PUT", file_name, false)?
+            .set("Content-Length", local.size.to_string().as_str())
+            .set("Content-Type", "application/octet-stream");
+        let (upload, stream): (Upload, UploadStream) = Upload::start(move |body| {
+            let response: Response = request.send(body).map_err(Self::map_error)?;
+            Self::check_status(response.status())
+        });
+        self.upload = Some(upload);
+        Ok(Box::new(stream))
+    }
+
+    /// ### recv_file
+    ///
+    /// Receive file from remote with provided name
+
+    #[instrument(parent = &self.span, skip_all, fields(path = %file.abs_path.display()), err)]
+    fn recv_file(&mut self, file: &FsFile) -> Result<Box<dyn Read>, FileTransferError> {
+        let response: Response = self
+            .request("GET", file.abs_path.as_path(), false)?
+            .call()
+            .map_err(Self::map_error)?;
+        Self::check_status(response.status())?;
+        Ok(Box::new(response.into_reader()))
+    }
+
+    /// ### finalize_transfer
+    ///
+    /// Finalize the transfer: for uploads, close the stream and wait for the server response.
+    /// If the upload hasn't been completed, the request body is interrupted, so that the server discards it
+
+    #[instrument(parent = &self.span, level = "debug", skip_all, fields(bytes = outcome.bytes), err)]
+    fn finalize_transfer(&mut self, outcome: TransferOutcome) -> Result<(), FileTransferError> {
+        let completed: bool = outcome.is_completed();
+        // Dropping the stream terminates the request body (or the response, for downloads)
+        drop(outcome.stream);
+        match self.upload.take() {
+            Some(upload) => upload.finish(completed),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::filetransfer::fixtures::WebDavServer;
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    use crate::filetransfer::{TransferStatus, TransferStream};
+    use std::time::Duration;
+
+    #[test]
+    fn test_filetransfer_webdav_new() {
+        let client: WebDavFileTransfer = WebDavFileTransfer::new(true);
+        assert!(client.agent.is_none());
+        assert!(client.secure);
+        assert_eq!(client.wrkdir, PathBuf::from("/"));
+        assert!(!client.is_connected());
+    }
+
+    #[test]
+    fn test_filetransfer_webdav_url_of() {
+        let mut client: WebDavFileTransfer = WebDavFileTransfer::new(false);
+        client.url = String::from("http://127.0.0.1:8080");
+        client.wrkdir = PathBuf::from("/home/omar");
+        assert_eq!(
+            client.url_of(Path::new("/"), false).as_str(),
+            "http://127.0.0.1:8080/"
+        );
+        assert_eq!(
+            client
+                .url_of(Path::new("my docs/../Ünicode.txt"), false)
+                .as_str(),
+            "http://127.0.0.1:8080/home/omar/%C3%9Cnicode.txt"
+        );
+        assert_eq!(
+            client.url_of(Path::new("/tmp/a&b"), true).as_str(),
+            "http://127.0.0.1:8080/tmp/a%26b/"
+        );
+    }
+
+    #[test]
+    fn test_filetransfer_webdav_parse_multistatus() {
+        let body: &str = r#"<?xml version="1.0" encoding="utf-8"?>
+<D:multistatus xmlns:D="DAV:">
+  <D:response>
+    <D:href>https://cloud.example.com/remote.php/dav/files/omar/</D:href>
+    <D:propstat>
+      <D:prop>
+        <D:resourcetype><D:collection/></D:resourcetype>
+        <D:getlastmodified>Wed, 20 Jan 2021 10:00:00 GMT</D:getlastmodified>
+      </D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+  <D:response>
+    <D:href>/remote.php/dav/files/omar/my%20notes.txt</D:href>
+    <D:propstat>
+      <D:prop>
+        <D:resourcetype/>
+        <D:getcontentlength>8192</D:getcontentlength>
+        <D:creationdate>2021-01-18T08:30:00Z</D:creationdate>
+      </D:prop>
+      <D:status>HTTP/1.1 200 OK</D:status>
+    </D:propstat>
+  </D:response>
+  <response xmlns="DAV:"><href>/remote.php/dav/files/omar/Tom&amp;Jerry/</href><propstat><prop><resourcetype><collection/></resourcetype></prop></propstat></response>
+</D:multistatus>"#;
+        let resources: Vec<DavResource> = WebDavFileTransfer::parse_multistatus(body);
+        assert_eq!(resources.len(), 3);
+        assert_eq!(
+            resources[0].path,
+            PathBuf::from("/remote.php/dav/files/omar")
+        );
+        assert!(resources[0].is_dir);
+        assert_eq!(
+            resources[0].mtime.unwrap(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1611136800)
+        );
+        assert!(resources[0].ctime.is_none());
+        assert_eq!(
+            resources[1].path,
+            PathBuf::from("/remote.php/dav/files/omar/my notes.txt")
+        );
+        assert!(!resources[1].is_dir);
+        assert_eq!(resources[1].size, 8192);
+        assert!(resources[1].mtime.is_none());
+        assert_eq!(
+            resources[1].ctime.unwrap(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1610958600)
+        );
+        assert_eq!(
+            resources[2].path,
+            PathBuf::from("/remote.php/dav/files/omar/Tom&Jerry")
+        );
+        assert!(resources[2].is_dir);
+        // Make entries
+        let file: FsEntry = WebDavFileTransfer::make_fsentry(resources.into_iter().nth(1).unwrap());
+        assert!(file.is_file());
+        assert_eq!(file.get_name(), "my notes.txt");
+        assert_eq!(file.get_size(), 8192);
+        // Garbage
+        assert!(WebDavFileTransfer::parse_multistatus("<html>Not found</html>").is_empty());
+    }
+
+    #[test]
+    fn test_filetransfer_webdav_status_error() {
+        assert!(matches!(
+            WebDavFileTransfer::status_error(401).code,
+            FileTransferErrorType::AuthenticationFailed
+        ));
+        assert!(matches!(
+            WebDavFileTransfer::status_error(404).code,
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        assert!(matches!(
+            WebDavFileTransfer::status_error(500).code,
+            FileTransferErrorType::ProtocolError
+        ));
+        assert!(WebDavFileTransfer::check_status(201).is_ok());
+        assert!(WebDavFileTransfer::check_status(412).is_err());
+    }
+
+    #[test]
+    fn test_filetransfer_webdav_fixture_session() {
+        let server: WebDavServer = WebDavServer::start(MemoryFileTransfer::demo())
+            .ok()
+            .unwrap();
+        let mut client: WebDavFileTransfer = WebDavFileTransfer::new(false);
+        // Bad credentials
+        let err: FileTransferError = client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("pippo")),
+            )
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.code,
+            FileTransferErrorType::AuthenticationFailed
+        ));
+        assert!(!client.is_connected());
+        // Connect
+        assert_eq!(
+            client
+                .connect(
+                    server.address(),
+                    server.port(),
+                    Some(String::from("omar")),
+                    Some(String::from("secret")),
+                )
+                .ok()
+                .unwrap()
+                .unwrap()
+                .as_str(),
+            "termscp test server"
+        );
+        assert!(client.is_connected());
+        assert_eq!(client.pwd().ok().unwrap(), PathBuf::from("/"));
+        // List dir
+        let files: Vec<FsEntry> = client.list_dir(Path::new("/home/demo")).ok().unwrap();
+        assert_eq!(files.len(), 6);
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(readme.is_file());
+        assert!(files
+            .iter()
+            .find(|x| x.get_name() == "projects")
+            .unwrap()
+            .is_dir());
+        // Change dir
+        assert!(client.change_dir(Path::new("/home/demo/downloads")).is_ok());
+        assert_eq!(
+            client.pwd().ok().unwrap(),
+            PathBuf::from("/home/demo/downloads")
+        );
+        assert!(client.change_dir(Path::new("../README.md")).is_err());
+        assert!(client.change_dir(Path::new("/omar")).is_err());
+        // Stat
+        assert!(client
+            .stat(Path::new("../README.md"))
+            .ok()
+            .unwrap()
+            .is_file());
+        // Mkdir
+        assert!(client.mkdir(Path::new("music")).is_ok());
+        assert!(client.mkdir(Path::new("music")).is_err());
+        // Exec
+        assert!(client.exec("ls").is_err());
+        // Disconnect
+        assert!(client.disconnect().is_ok());
+        assert!(!client.is_connected());
+        assert!(client.disconnect().is_err());
+        assert!(client.list_dir(Path::new("/")).is_err());
+        assert!(server
+            .fs()
+            .lock()
+            .unwrap()
+            .stat(Path::new("/home/demo/downloads/music"))
+            .ok()
+            .unwrap()
+            .is_dir());
+    }
+
+    #[test]
+    fn test_filetransfer_webdav_fixture_send_and_recv() {
+        let server: WebDavServer = WebDavServer::start(MemoryFileTransfer::demo())
+            .ok()
+            .unwrap();
+        let mut client: WebDavFileTransfer = WebDavFileTransfer::new(false);
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret")),
+            )
+            .is_ok());
+        let local: FsFile = FsFile {
+            name: String::from("omar.txt"),
+            abs_path: PathBuf::from("/tmp/omar.txt"),
+            last_change_time: SystemTime::UNIX_EPOCH,
+            last_access_time: SystemTime::UNIX_EPOCH,
+            creation_time: SystemTime::UNIX_EPOCH,
+            size: 12,
+            ftype: Some(String::from("txt")),
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        };
+        // Send
+        let mut writer: Box<dyn Write> = client
+            .send_file(&local, Path::new("/home/demo/downloads/omar.txt"))
+            .ok()
+            .unwrap();
+        assert!(writer.write_all(b"hello ").is_ok());
+        assert!(writer.write_all(b"world!").is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_ok());
+        // Parent doesn't exist
+        let mut writer: Box<dyn Write> = client
+            .send_file(&local, Path::new("/omar/omar.txt"))
+            .ok()
+            .unwrap();
+        let _ = writer.write_all(b"hello world!");
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                12
+            ))
+            .is_err());
+        // Aborted upload is discarded
+        let mut writer: Box<dyn Write> = client
+            .send_file(&local, Path::new("/home/demo/downloads/aborted.txt"))
+            .ok()
+            .unwrap();
+        assert!(writer.write_all(b"hello ").is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::new(
+                TransferStream::Upload(writer),
+                6,
+                TransferStatus::Aborted
+            ))
+            .is_ok());
+        // List
+        let files: Vec<FsEntry> = client
+            .list_dir(Path::new("/home/demo/downloads"))
+            .ok()
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].get_size(), 12);
+        // Recv
+        let remote: FsFile = match files[0].clone() {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("Expected file"),
+        };
+        let mut reader: Box<dyn Read> = client.recv_file(&remote).ok().unwrap();
+        let mut content: String = String::new();
+        assert!(reader.read_to_string(&mut content).is_ok());
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Download(reader),
+                12
+            ))
+            .is_ok());
+        assert_eq!(content.as_str(), "hello world!");
+        assert!(client.disconnect().is_ok());
+    }
+
+    #[test]
+    fn test_filetransfer_webdav_fixture_copy_rename_and_remove() {
+        let server: WebDavServer = WebDavServer::start(MemoryFileTransfer::demo())
+            .ok()
+            .unwrap();
+        let mut client: WebDavFileTransfer = WebDavFileTransfer::new(false);
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret")),
+            )
+            .is_ok());
+        let files: Vec<FsEntry> = client.list_dir(Path::new("/home/demo")).ok().unwrap();
+        // Copy
+        let readme: &FsEntry = files.iter().find(|x| x.get_name() == "README.md").unwrap();
+        assert!(client
+            .copy(readme, Path::new("/home/demo/README.bak"))
+            .is_ok());
+        // Rename (destination exists)
+        let notes: &FsEntry = files.iter().find(|x| x.get_name() == "notes.txt").unwrap();
+        assert!(client
+            .rename(notes, Path::new("/home/demo/README.bak"))
+            .is_err());
+        assert!(client
+            .rename(notes, Path::new("/home/demo/todo.txt"))
+            .is_ok());
+        // Remove file
+        assert!(client.remove(readme).is_ok());
+        assert!(client.remove(readme).is_err());
+        // Remove directory
+        let downloads: &FsEntry = files.iter().find(|x| x.get_name() == "downloads").unwrap();
+        assert!(client.remove(downloads).is_ok());
+        // Verify
+        let mut names: Vec<String> = client
+            .list_dir(Path::new("/home/demo"))
+            .ok()
+            .unwrap()
+            .iter()
+            .map(|x| x.get_name().to_string())
+            .collect();
+        names.sort();
+        let fs = server.fs();
+        let mut fs = fs.lock().unwrap();
+        let expected: Vec<String> = {
+            let mut names: Vec<String> = fs
+                .list_dir(Path::new("/home/demo"))
+                .ok()
+                .unwrap()
+                .iter()
+                .map(|x| x.get_name().to_string())
+                .collect();
+            names.sort();
+            names
+        };
+        assert_eq!(names, expected);
+        assert!(names.contains(&String::from("README.bak")));
+        assert!(names.contains(&String::from("todo.txt")));
+        assert!(!names.contains(&String::from("notes.txt")));
+        assert!(!names.contains(&String::from("downloads")));
+    }
+}
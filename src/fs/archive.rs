@@ -0,0 +1,612 @@
+//! ## Archive
+//!
+//! `archive` is the module which provides a virtual file system on top of tar and zip archives,
+//! which can be browsed like a directory
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Deps
+extern crate flate2;
+extern crate tar;
+extern crate zip;
+// Locals
+use super::{FsDirectory, FsEntry, FsFile};
+// Ext
+use chrono::NaiveDate;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tempfile::NamedTempFile;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+/// ## ArchiveFormat
+///
+/// Describes the archive formats which can be browsed
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ArchiveFormat {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl ArchiveFormat {
+    /// ### from_path
+    ///
+    /// Get the archive format from the file name. Returns None if file is not a supported archive
+    pub fn from_path(p: &Path) -> Option<ArchiveFormat> {
+        let name: String = p.file_name()?.to_string_lossy().to_lowercase();
+        if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveFormat::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveFormat::Tar)
+        } else if name.ends_with(".zip") {
+            Some(ArchiveFormat::Zip)
+        } else {
+            None
+        }
+    }
+}
+
+/// ## ArchiveError
+///
+/// Describes an error occurred while working on an archive
+#[derive(Debug)]
+pub enum ArchiveError {
+    Io(io::Error),
+    Zip(zip::result::ZipError),
+    UnsupportedFormat,
+    NoSuchEntry(PathBuf),
+    AlreadyExists(PathBuf),
+}
+
+impl From<io::Error> for ArchiveError {
+    fn from(err: io::Error) -> Self {
+        ArchiveError::Io(err)
+    }
+}
+
+impl From<zip::result::ZipError> for ArchiveError {
+    fn from(err: zip::result::ZipError) -> Self {
+        ArchiveError::Zip(err)
+    }
+}
+
+impl std::fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ArchiveError::Io(err) => write!(f, "IO error: {}", err),
+            ArchiveError::Zip(err) => write!(f, "Zip error: {}", err),
+            ArchiveError::UnsupportedFormat => write!(f, "Unsupported archive format"),
+            ArchiveError::NoSuchEntry(p) => {
+                write!(f, "No such entry in archive: {}", p.display())
+            }
+            ArchiveError::AlreadyExists(p) => {
+                write!(f, "Entry already exists in archive: {}", p.display())
+            }
+        }
+    }
+}
+
+/// ## Archive
+///
+/// Archive holds the index of a tar or zip archive on the local host.
+/// Entries are exposed as `FsEntry`, with absolute paths rooted at `/` (the root of the archive)
+pub struct Archive {
+    path: PathBuf,
+    format: ArchiveFormat,
+    entries: Vec<FsEntry>,
+}
+
+impl Archive {
+    /// ### open
+    ///
+    /// Open archive at `path` and read its index
+    pub fn open(path: &Path) -> Result<Archive, ArchiveError> {
+        let format: ArchiveFormat =
+            ArchiveFormat::from_path(path).ok_or(ArchiveError::UnsupportedFormat)?;
+        let mut archive: Archive = Archive {
+            path: path.to_path_buf(),
+            format,
+            entries: Vec::new(),
+        };
+        archive.reload()?;
+        Ok(archive)
+    }
+
+    /// ### path
+    ///
+    /// Get path of the archive on the local host
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// ### list_dir
+    ///
+    /// List the entries in the provided directory of the archive
+    pub fn list_dir(&self, dir: &Path) -> Vec<FsEntry> {
+        self.entries
+            .iter()
+            .filter(|x| x.get_abs_path().parent() == Some(dir))
+            .cloned()
+            .collect()
+    }
+
+    /// ### extract
+    ///
+    /// Extract the entry at `path` (recursively, if a directory) into the local directory `dest`.
+    /// Returns the path of the extracted entry
+    pub fn extract(&self, path: &Path, dest: &Path) -> Result<PathBuf, ArchiveError> {
+        let entry: &FsEntry = self
+            .entries
+            .iter()
+            .find(|x| x.get_abs_path() == path)
+            .ok_or_else(|| ArchiveError::NoSuchEntry(path.to_path_buf()))?;
+        let parent: PathBuf = path.parent().map(|x| x.to_path_buf()).unwrap_or_default();
+        // Target path of each member of the archive
+        let target = |member: &Path| -> Option<PathBuf> {
+            match member.starts_with(path) {
+                true => member
+                    .strip_prefix(parent.as_path())
+                    .ok()
+                    .map(|x| dest.join(x)),
+                false => None,
+            }
+        };
+        if let FsEntry::Directory(_) = entry {
+            fs::create_dir_all(dest.join(entry.get_name()))?;
+        }
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(BufReader::new(File::open(self.path())?))?;
+                for i in 0..archive.len() {
+                    let mut file = archive.by_index(i)?;
+                    let target: PathBuf = match target(normalize_path(file.name()).as_path()) {
+                        Some(t) => t,
+                        None => continue,
+                    };
+                    match file.is_dir() {
+                        true => fs::create_dir_all(target.as_path())?,
+                        false => write_file(&mut file, target.as_path())?,
+                    }
+                }
+            }
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                let mut archive = tar::Archive::new(self.open_tar()?);
+                for member in archive.entries()? {
+                    let mut member = member?;
+                    let member_path: PathBuf = normalize_path(&member.path()?.to_string_lossy());
+                    let target: PathBuf = match target(member_path.as_path()) {
+                        Some(t) => t,
+                        None => continue,
+                    };
+                    match member.header().entry_type() {
+                        tar::EntryType::Directory => fs::create_dir_all(target.as_path())?,
+                        tar::EntryType::Regular => write_file(&mut member, target.as_path())?,
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Ok(dest.join(entry.get_name()))
+    }
+
+    /// ### append
+    ///
+    /// Add the local file or directory `src` into the directory `dir` of the archive
+    pub fn append(&mut self, src: &Path, dir: &Path) -> Result<(), ArchiveError> {
+        let name: PathBuf = match src.file_name() {
+            Some(name) => dir.join(name),
+            None => return Err(ArchiveError::NoSuchEntry(src.to_path_buf())),
+        };
+        if self.entries.iter().any(|x| x.get_abs_path() == name) {
+            return Err(ArchiveError::AlreadyExists(name));
+        }
+        // Paths in archive are relative
+        let name: PathBuf = name.strip_prefix("/").unwrap_or(&name).to_path_buf();
+        match self.format {
+            ArchiveFormat::Zip => {
+                let file: File = OpenOptions::new()
+                    .read(true)
+                    .write(true)
+                    .open(self.path())?;
+                let mut writer = ZipWriter::new_append(file)?;
+                zip_append(&mut writer, src, name.as_path())?;
+                writer.finish()?;
+            }
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                // Compressed archives can't be appended in place; rewrite the archive
+                let parent: &Path = self.path.parent().unwrap_or_else(|| Path::new("."));
+                let tmp: NamedTempFile = NamedTempFile::new_in(parent)?;
+                match self.format {
+                    ArchiveFormat::TarGz => {
+                        let encoder = GzEncoder::new(tmp.reopen()?, Compression::default());
+                        self.tar_rewrite(encoder, src, name.as_path())?.finish()?;
+                    }
+                    _ => {
+                        self.tar_rewrite(tmp.reopen()?, src, name.as_path())?;
+                    }
+                }
+                tmp.persist(self.path.as_path()).map_err(|e| e.error)?;
+            }
+        }
+        self.reload()
+    }
+
+    /// ### tar_rewrite
+    ///
+    /// Copy all the members of the tar archive to `writer`, then add `src` with `name`
+    fn tar_rewrite<W: Write>(&self, writer: W, src: &Path, name: &Path) -> Result<W, ArchiveError> {
+        let mut builder = tar::Builder::new(writer);
+        let mut archive = tar::Archive::new(self.open_tar()?);
+        for member in archive.entries()? {
+            let mut member = member?;
+            let header: tar::Header = member.header().clone();
+            builder.append(&header, &mut member)?;
+        }
+        match src.is_dir() {
+            true => builder.append_dir_all(name, src)?,
+            false => builder.append_path_with_name(src, name)?,
+        }
+        Ok(builder.into_inner()?)
+    }
+
+    /// ### reload
+    ///
+    /// Read the archive index
+    fn reload(&mut self) -> Result<(), ArchiveError> {
+        let mut entries: Vec<FsEntry> = Vec::new();
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut archive = ZipArchive::new(BufReader::new(File::open(self.path())?))?;
+                for i in 0..archive.len() {
+                    let file = archive.by_index(i)?;
+                    let dt = file.last_modified();
+                    let mtime: SystemTime = NaiveDate::from_ymd_opt(
+                        dt.year() as i32,
+                        dt.month() as u32,
+                        dt.day() as u32,
+                    )
+                    .and_then(|d| {
+                        d.and_hms_opt(dt.hour() as u32, dt.minute() as u32, dt.second() as u32)
+                    })
+                    .map(|d| SystemTime::UNIX_EPOCH + Duration::from_secs(d.timestamp() as u64))
+                    .unwrap_or(SystemTime::UNIX_EPOCH);
+                    push_entry(
+                        &mut entries,
+                        normalize_path(file.name()),
+                        file.is_dir(),
+                        file.size() as usize,
+                        mtime,
+                        file.unix_mode(),
+                        None,
+                    );
+                }
+            }
+            ArchiveFormat::Tar | ArchiveFormat::TarGz => {
+                let mut archive = tar::Archive::new(self.open_tar()?);
+                for member in archive.entries()? {
+                    let member = member?;
+                    let header: &tar::Header = member.header();
+                    let is_dir: bool = match header.entry_type() {
+                        tar::EntryType::Directory => true,
+                        tar::EntryType::Regular => false,
+                        _ => continue,
+                    };
+                    push_entry(
+                        &mut entries,
+                        normalize_path(&member.path()?.to_string_lossy()),
+                        is_dir,
+                        header.size().unwrap_or(0) as usize,
+                        SystemTime::UNIX_EPOCH + Duration::from_secs(header.mtime().unwrap_or(0)),
+                        header.mode().ok(),
+                        Some((
+                            header.uid().unwrap_or(0) as u32,
+                            header.gid().unwrap_or(0) as u32,
+                        )),
+                    );
+                }
+            }
+        }
+        self.entries = entries;
+        Ok(())
+    }
+
+    /// ### open_tar
+    ///
+    /// Open a reader for the tar stream, decompressing it if necessary
+    fn open_tar(&self) -> Result<Box<dyn Read>, ArchiveError> {
+        let reader = BufReader::new(File::open(self.path())?);
+        match self.format {
+            ArchiveFormat::TarGz => Ok(Box::new(GzDecoder::new(reader))),
+            ArchiveFormat::Tar => Ok(Box::new(reader)),
+            ArchiveFormat::Zip => Err(ArchiveError::UnsupportedFormat),
+        }
+    }
+}
+
+/// ### normalize_path
+///
+/// Make path of an archive member absolute from the root of the archive, discarding `.`, `..` and
+/// any prefix, so that members can never point outside of the archive
+fn normalize_path(name: &str) -> PathBuf {
+    let mut path: PathBuf = PathBuf::from("/");
+    Path::new(name)
+        .components()
+        .filter_map(|x| match x {
+            Component::Normal(c) => Some(c),
+            _ => None,
+        })
+        .for_each(|x| path.push(x));
+    path
+}
+
+/// ### push_entry
+///
+/// Push an entry into the archive index, creating its parent directories if missing
+fn push_entry(
+    entries: &mut Vec<FsEntry>,
+    path: PathBuf,
+    is_dir: bool,
+    size: usize,
+    mtime: SystemTime,
+    mode: Option<u32>,
+    owner: Option<(u32, u32)>,
+) {
+    if path.parent().is_none() {
+        // Root
+        return;
+    }
+    // Make parents
+    if let Some(parent) = path.parent() {
+        if parent.parent().is_some() && !entries.iter().any(|x| x.get_abs_path() == parent) {
+            push_entry(entries, parent.to_path_buf(), true, 0, mtime, None, owner);
+        }
+    }
+    let name: String = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let unix_pex: Option<(u8, u8, u8)> = mode.map(|m| {
+        (
+            ((m >> 6) & 0x7) as u8,
+            ((m >> 3) & 0x7) as u8,
+            (m & 0x7) as u8,
+        )
+    });
+    // If entry already exists (e.g. created as parent), replace it
+    entries.retain(|x| x.get_abs_path() != path);
+    match is_dir {
+        true => entries.push(FsEntry::Directory(FsDirectory {
+            name,
+            abs_path: path,
+            last_change_time: mtime,
+            last_access_time: mtime,
+            creation_time: mtime,
+            readonly: false,
+            symlink: None,
+            user: owner.map(|(u, _)| u),
+            group: owner.map(|(_, g)| g),
+            unix_pex,
+        })),
+        false => entries.push(FsEntry::File(FsFile {
+            name,
+            ftype: path
+                .extension()
+                .map(|s| String::from(s.to_str().unwrap_or(""))),
+            abs_path: path,
+            last_change_time: mtime,
+            last_access_time: mtime,
+            creation_time: mtime,
+            size,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: owner.map(|(u, _)| u),
+            group: owner.map(|(_, g)| g),
+            unix_pex,
+        })),
+    }
+}
+
+/// ### write_file
+///
+/// Write the content of `reader` to the local file at `target`, creating its parent directories
+fn write_file(reader: &mut dyn Read, target: &Path) -> Result<(), ArchiveError> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file: File = File::create(target)?;
+    io::copy(reader, &mut file)?;
+    Ok(())
+}
+
+/// ### zip_append
+///
+/// Add the local file or directory `src` to a zip archive with `name`
+fn zip_append(writer: &mut ZipWriter<File>, src: &Path, name: &Path) -> Result<(), ArchiveError> {
+    let name_str: String = name.to_string_lossy().to_string();
+    match src.is_dir() {
+        true => {
+            writer.add_directory(name_str, FileOptions::default())?;
+            for child in fs::read_dir(src)? {
+                let child = child?.path();
+                if let Some(child_name) = child.file_name() {
+                    zip_append(writer, child.as_path(), name.join(child_name).as_path())?;
+                }
+            }
+        }
+        false => {
+            writer.start_file(name_str, FileOptions::default())?;
+            io::copy(&mut File::open(src)?, writer)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_fs_archive_format() {
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("/tmp/a.tar.gz")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("a.TGZ")),
+            Some(ArchiveFormat::TarGz)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("a.tar")),
+            Some(ArchiveFormat::Tar)
+        );
+        assert_eq!(
+            ArchiveFormat::from_path(Path::new("a.zip")),
+            Some(ArchiveFormat::Zip)
+        );
+        assert_eq!(ArchiveFormat::from_path(Path::new("a.txt")), None);
+        assert!(Archive::open(Path::new("a.txt")).is_err());
+    }
+
+    #[test]
+    fn test_fs_archive_normalize_path() {
+        assert_eq!(normalize_path("./a/b.txt"), PathBuf::from("/a/b.txt"));
+        assert_eq!(normalize_path("a/"), PathBuf::from("/a"));
+        assert_eq!(
+            normalize_path("../../etc/passwd"),
+            PathBuf::from("/etc/passwd")
+        );
+    }
+
+    #[test]
+    fn test_fs_archive_tar_gz() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let path: PathBuf = tmpdir.path().join("test.tar.gz");
+        // Make archive
+        {
+            let encoder = GzEncoder::new(
+                File::create(path.as_path()).unwrap(),
+                Compression::default(),
+            );
+            let mut builder = tar::Builder::new(encoder);
+            append_tar_file(&mut builder, "docs/readme.md", b"Hello!\n");
+            append_tar_file(&mut builder, "docs/src/main.rs", b"fn main() {}\n");
+            append_tar_file(&mut builder, "Cargo.toml", b"[package]\n");
+            builder.into_inner().unwrap().finish().unwrap();
+        }
+        let mut archive: Archive = Archive::open(path.as_path()).unwrap();
+        // List
+        let root: Vec<FsEntry> = archive.list_dir(Path::new("/"));
+        assert_eq!(root.len(), 2);
+        let docs: Vec<FsEntry> = archive.list_dir(Path::new("/docs"));
+        assert_eq!(docs.len(), 2);
+        let readme: &FsEntry = docs.iter().find(|x| x.get_name() == "readme.md").unwrap();
+        assert_eq!(readme.get_size(), 7);
+        assert_eq!(readme.get_abs_path(), PathBuf::from("/docs/readme.md"));
+        // Extract file
+        let dest: TempDir = TempDir::new().unwrap();
+        assert_eq!(
+            archive
+                .extract(Path::new("/docs/readme.md"), dest.path())
+                .unwrap(),
+            dest.path().join("readme.md")
+        );
+        assert_eq!(
+            fs::read_to_string(dest.path().join("readme.md")).unwrap(),
+            "Hello!\n"
+        );
+        // Extract directory
+        archive.extract(Path::new("/docs"), dest.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.path().join("docs/src/main.rs")).unwrap(),
+            "fn main() {}\n"
+        );
+        assert!(archive.extract(Path::new("/nope"), dest.path()).is_err());
+        // Append
+        let src: PathBuf = dest.path().join("readme.md");
+        archive
+            .append(src.as_path(), Path::new("/docs/src"))
+            .unwrap();
+        assert_eq!(archive.list_dir(Path::new("/docs/src")).len(), 2);
+        // Reopen archive
+        let archive: Archive = Archive::open(path.as_path()).unwrap();
+        assert_eq!(archive.list_dir(Path::new("/docs/src")).len(), 2);
+        assert_eq!(archive.list_dir(Path::new("/")).len(), 2);
+    }
+
+    #[test]
+    fn test_fs_archive_zip() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let path: PathBuf = tmpdir.path().join("test.zip");
+        // Make archive
+        {
+            let mut writer = ZipWriter::new(File::create(path.as_path()).unwrap());
+            writer
+                .add_directory("docs/", FileOptions::default())
+                .unwrap();
+            writer
+                .start_file("docs/readme.md", FileOptions::default())
+                .unwrap();
+            writer.write_all(b"Hello!\n").unwrap();
+            writer.finish().unwrap();
+        }
+        let mut archive: Archive = Archive::open(path.as_path()).unwrap();
+        assert_eq!(archive.list_dir(Path::new("/")).len(), 1);
+        assert_eq!(archive.list_dir(Path::new("/docs")).len(), 1);
+        // Extract
+        let dest: TempDir = TempDir::new().unwrap();
+        archive.extract(Path::new("/docs"), dest.path()).unwrap();
+        assert_eq!(
+            fs::read_to_string(dest.path().join("docs/readme.md")).unwrap(),
+            "Hello!\n"
+        );
+        // Append directory
+        assert!(archive
+            .append(dest.path().join("docs").as_path(), Path::new("/"))
+            .is_err());
+        archive
+            .append(dest.path().join("docs").as_path(), Path::new("/docs"))
+            .unwrap();
+        assert_eq!(archive.list_dir(Path::new("/docs")).len(), 2);
+        assert_eq!(archive.list_dir(Path::new("/docs/docs")).len(), 1);
+    }
+
+    fn append_tar_file<W: Write>(builder: &mut tar::Builder<W>, name: &str, data: &[u8]) {
+        let mut header: tar::Header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(0);
+        header.set_cksum();
+        builder.append_data(&mut header, name, data).unwrap();
+    }
+}
@@ -32,10 +32,13 @@ extern crate regex;
 extern crate users;
 // Locals
 use super::FsEntry;
-use crate::utils::fmt::{fmt_path_elide, fmt_pex, fmt_time};
+use crate::utils::fmt::{
+    fmt_elide_width, fmt_pad_width, fmt_path_elide, fmt_pex, fmt_sanitize, fmt_time,
+};
 // Ext
 use bytesize::ByteSize;
 use regex::Regex;
+use unicode_width::UnicodeWidthStr;
 #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
 use users::{get_group_by_gid, get_user_by_uid};
 // Types
@@ -53,7 +56,7 @@ const FMT_KEY_SIZE: &str = "SIZE";
 const FMT_KEY_SYMLINK: &str = "SYMLINK";
 const FMT_KEY_USER: &str = "USER";
 // Default
-const FMT_DEFAULT_STX: &str = "{NAME} {PEX} {USER} {SIZE} {MTIME}";
+const FMT_DEFAULT_STX: &str = "{NAME} {PEX} {USER} {SIZE} {MTIME} {SYMLINK}";
 // Regex
 lazy_static! {
     /**
@@ -319,21 +322,26 @@ impl Formatter {
             Some(l) => *l,
             None => 24,
         };
-        let name: &str = fsentry.get_name();
-        let last_idx: usize = match fsentry.is_dir() {
-            // NOTE: For directories is 19, since we push '/' to name
-            true => file_len - 5,
-            false => file_len - 4,
+        let name: String = fmt_sanitize(fsentry.get_name());
+        let max_width: usize = match fsentry.is_dir() {
+            // NOTE: For directories is 22, since we push '/' to name
+            true => file_len - 2,
+            false => file_len - 1,
         };
-        let mut name: String = match name.len() >= file_len {
-            false => name.to_string(),
-            true => format!("{}...", &name[0..last_idx]),
+        let mut name: String = match name.width() >= file_len {
+            false => name,
+            true => fmt_elide_width(name.as_str(), max_width),
         };
         if fsentry.is_dir() {
             name.push('/');
         }
-        // Add to cur str, prefix and the key value
-        format!("{}{}{:0width$}", cur_str, prefix, name, width = file_len)
+        // Add to cur str, prefix and the key value (pad according to terminal width)
+        format!(
+            "{}{}{}",
+            cur_str,
+            prefix,
+            fmt_pad_width(name.as_str(), file_len)
+        )
     }
 
     /// ### fmt_pex
@@ -404,17 +412,16 @@ impl Formatter {
             None => 21,
         };
         // Replace `FMT_KEY_NAME` with name
-        match fsentry.is_symlink() {
-            false => format!("{}{}                        ", cur_str, prefix),
-            true => format!(
-                "{}{}-> {:0width$}",
+        match fsentry.get_symlink_target() {
+            None => format!("{}{}                        ", cur_str, prefix),
+            Some(target) => format!(
+                "{}{}-> {}",
                 cur_str,
                 prefix,
-                fmt_path_elide(
-                    fsentry.get_realfile().get_abs_path().as_path(),
-                    file_len - 1
+                fmt_pad_width(
+                    fmt_sanitize(fmt_path_elide(target.as_path(), file_len - 1).as_str()).as_str(),
+                    file_len
                 ),
-                width = file_len
             ),
         }
     }
@@ -558,6 +565,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -599,6 +607,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -607,7 +616,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "bar.txt                  -rw-r--r-- root         8.2 KB     {}",
+                "bar.txt                  -rw-r--r-- root         8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -615,7 +624,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "bar.txt                  -rw-r--r-- 0            8.2 KB     {}",
+                "bar.txt                  -rw-r--r-- 0            8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -630,6 +639,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -638,7 +648,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "piroparoporoperoperu...  -rw-r--r-- root         8.2 KB     {}",
+                "piroparoporoperoperu...  -rw-r--r-- root         8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -646,7 +656,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "piroparoporoperoperu...  -rw-r--r-- 0            8.2 KB     {}",
+                "piroparoporoperoperu...  -rw-r--r-- 0            8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -660,16 +670,17 @@ mod tests {
             size: 8192,
             readonly: false,
             ftype: Some(String::from("txt")),
-            symlink: None,  // UNIX only
-            user: Some(0),  // UNIX only
-            group: Some(0), // UNIX only
-            unix_pex: None, // UNIX only
+            symlink: None,     // UNIX only
+            broken_link: None, // UNIX only
+            user: Some(0),     // UNIX only
+            group: Some(0),    // UNIX only
+            unix_pex: None,    // UNIX only
         });
         #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "bar.txt                  -????????? root         8.2 KB     {}",
+                "bar.txt                  -????????? root         8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -677,7 +688,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "bar.txt                  -????????? 0            8.2 KB     {}",
+                "bar.txt                  -????????? 0            8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -691,16 +702,17 @@ mod tests {
             size: 8192,
             readonly: false,
             ftype: Some(String::from("txt")),
-            symlink: None,  // UNIX only
-            user: None,     // UNIX only
-            group: Some(0), // UNIX only
-            unix_pex: None, // UNIX only
+            symlink: None,     // UNIX only
+            broken_link: None, // UNIX only
+            user: None,        // UNIX only
+            group: Some(0),    // UNIX only
+            unix_pex: None,    // UNIX only
         });
         #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "bar.txt                  -????????? 0            8.2 KB     {}",
+                "bar.txt                  -????????? 0            8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -708,7 +720,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "bar.txt                  -????????? 0            8.2 KB     {}",
+                "bar.txt                  -????????? 0            8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -736,7 +748,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "projects/                drwxr-xr-x root                    {}",
+                "projects/                drwxr-xr-x root                    {}                         ",
                 fmt_time(t_now, "%b %d %Y %H:%M")
             )
         );
@@ -744,7 +756,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "projects/                drwxr-xr-x 0                       {}",
+                "projects/                drwxr-xr-x 0                       {}                         ",
                 fmt_time(t_now, "%b %d %Y %H:%M")
             )
         );
@@ -765,7 +777,7 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "projects/                d????????? 0                       {}",
+                "projects/                d????????? 0                       {}                         ",
                 fmt_time(t_now, "%b %d %Y %H:%M")
             )
         );
@@ -773,12 +785,59 @@ mod tests {
         assert_eq!(
             formatter.fmt(&entry),
             format!(
-                "projects/                d????????? 0                       {}",
+                "projects/                d????????? 0                       {}                         ",
                 fmt_time(t_now, "%b %d %Y %H:%M")
             )
         );
     }
 
+    #[test]
+    fn test_fs_explorer_formatter_format_wide_names() {
+        let formatter: Formatter = Formatter::new("{NAME:12}");
+        let t: SystemTime = SystemTime::now();
+        let make_entry = |name: &str| -> FsEntry {
+            FsEntry::File(FsFile {
+                name: String::from(name),
+                abs_path: PathBuf::from("/").join(name),
+                last_change_time: t,
+                last_access_time: t,
+                creation_time: t,
+                size: 8192,
+                readonly: false,
+                ftype: None,
+                symlink: None,
+                broken_link: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            })
+        };
+        // Wide chars are padded according to their width
+        assert_eq!(
+            formatter.fmt(&make_entry("日本語.txt")),
+            String::from("日本語.txt  ")
+        );
+        // Wide chars are elided without splitting them
+        assert_eq!(
+            formatter.fmt(&make_entry("日本語のファイル.txt")),
+            String::from("日本語の... ")
+        );
+        assert_eq!(
+            formatter.fmt(&make_entry("🦀🦀🦀🦀🦀🦀.rs")),
+            String::from("🦀🦀🦀🦀... ")
+        );
+        // Combining characters
+        assert_eq!(
+            formatter.fmt(&make_entry("cafe\u{301}.txt")),
+            String::from("cafe\u{301}.txt    ")
+        );
+        // Bidirectional overrides are neutralized
+        assert_eq!(
+            formatter.fmt(&make_entry("a\u{202e}txt.exe")),
+            String::from("a?txt.exe   ")
+        );
+    }
+
     #[test]
     fn test_fs_explorer_formatter_all_together_now() {
         let formatter: Formatter =
@@ -794,10 +853,11 @@ mod tests {
             size: 8192,
             readonly: false,
             ftype: Some(String::from("txt")),
-            symlink: None,  // UNIX only
-            user: None,     // UNIX only
-            group: None,    // UNIX only
-            unix_pex: None, // UNIX only
+            symlink: None,     // UNIX only
+            broken_link: None, // UNIX only
+            user: None,        // UNIX only
+            group: None,       // UNIX only
+            unix_pex: None,    // UNIX only
         });
         let entry: FsEntry = FsEntry::Directory(FsDirectory {
             name: String::from("projects"),
@@ -846,10 +906,11 @@ mod tests {
             size: 8192,
             readonly: false,
             ftype: Some(String::from("txt")),
-            symlink: None,  // UNIX only
-            user: None,     // UNIX only
-            group: None,    // UNIX only
-            unix_pex: None, // UNIX only
+            symlink: None,     // UNIX only
+            broken_link: None, // UNIX only
+            user: None,        // UNIX only
+            group: None,       // UNIX only
+            unix_pex: None,    // UNIX only
         });
         let entry: FsEntry = FsEntry::File(FsFile {
             name: String::from("bar.txt"),
@@ -861,6 +922,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: Some(Box::new(pointer)), // UNIX only
+            broken_link: None,                // UNIX only
             user: None,                       // UNIX only
             group: None,                      // UNIX only
             unix_pex: Some((6, 4, 4)),        // UNIX only
@@ -882,6 +944,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: None,                // UNIX only
             group: None,               // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
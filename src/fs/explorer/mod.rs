@@ -80,6 +80,7 @@ pub struct FileExplorer {
     pub(crate) group_dirs: Option<GroupDirs>, // If Some, defines how to group directories
     pub(crate) opts: ExplorerOpts,            // Explorer options
     pub(crate) fmt: Formatter,                // FsEntry formatter
+    pub(crate) pins: Vec<PathBuf>,            // Pinned directories for this session
     files: Vec<FsEntry>,                      // Files in directory
 }
 
@@ -93,6 +94,7 @@ impl Default for FileExplorer {
             group_dirs: None,
             opts: ExplorerOpts::empty(),
             fmt: Formatter::default(),
+            pins: Vec::new(),
             files: Vec::new(),
         }
     }
@@ -306,6 +308,41 @@ impl FileExplorer {
     pub fn toggle_hidden_files(&mut self) {
         self.opts.toggle(ExplorerOpts::SHOW_HIDDEN_FILES);
     }
+
+    // Pins
+
+    /// ### toggle_pin
+    ///
+    /// Pin provided directory if not pinned yet, otherwise unpin it.
+    /// Returns whether the directory is pinned after the call
+    pub fn toggle_pin(&mut self, dir: &Path) -> bool {
+        match self.pins.iter().position(|x| x.as_path() == dir) {
+            Some(idx) => {
+                self.pins.remove(idx);
+                false
+            }
+            None => {
+                self.pins.push(PathBuf::from(dir));
+                true
+            }
+        }
+    }
+
+    /// ### unpin
+    ///
+    /// Remove pin at provided index
+    pub fn unpin(&mut self, idx: usize) {
+        if self.pins.len() > idx {
+            self.pins.remove(idx);
+        }
+    }
+
+    /// ### pins
+    ///
+    /// Get pinned directories
+    pub fn pins(&self) -> &[PathBuf] {
+        self.pins.as_slice()
+    }
 }
 
 // Traits
@@ -405,6 +442,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fs_explorer_pins() {
+        let mut explorer: FileExplorer = FileExplorer::default();
+        assert!(explorer.pins().is_empty());
+        // Pin
+        assert!(explorer.toggle_pin(Path::new("/etc")));
+        assert!(explorer.toggle_pin(Path::new("/var/log")));
+        assert!(explorer.toggle_pin(Path::new("/srv")));
+        assert_eq!(
+            explorer.pins(),
+            &[
+                PathBuf::from("/etc"),
+                PathBuf::from("/var/log"),
+                PathBuf::from("/srv")
+            ]
+        );
+        // Unpin by toggling
+        assert!(!explorer.toggle_pin(Path::new("/var/log")));
+        assert_eq!(
+            explorer.pins(),
+            &[PathBuf::from("/etc"), PathBuf::from("/srv")]
+        );
+        // Unpin by index
+        explorer.unpin(0);
+        assert_eq!(explorer.pins(), &[PathBuf::from("/srv")]);
+        explorer.unpin(5); // Out of range
+        assert_eq!(explorer.pins().len(), 1);
+    }
+
     #[test]
     fn test_fs_explorer_files() {
         let mut explorer: FileExplorer = FileExplorer::default();
@@ -581,6 +647,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -589,7 +656,7 @@ mod tests {
         assert_eq!(
             explorer.fmt_file(&entry),
             format!(
-                "bar.txt                  -rw-r--r-- root         8.2 KB     {}",
+                "bar.txt                  -rw-r--r-- root         8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -597,7 +664,7 @@ mod tests {
         assert_eq!(
             explorer.fmt_file(&entry),
             format!(
-                "bar.txt                  -rw-r--r-- 0            8.2 KB     {}",
+                "bar.txt                  -rw-r--r-- 0            8.2 KB     {}                         ",
                 fmt_time(t, "%b %d %Y %H:%M")
             )
         );
@@ -665,6 +732,7 @@ mod tests {
                 ftype: None, // File type
                 readonly: false,
                 symlink: None,             // UNIX only
+                broken_link: None,         // UNIX only
                 user: Some(0),             // UNIX only
                 group: Some(0),            // UNIX only
                 unix_pex: Some((6, 4, 4)), // UNIX only
@@ -697,6 +765,7 @@ mod tests {
                 ftype: None, // File type
                 readonly: false,
                 symlink: None,             // UNIX only
+                broken_link: None,         // UNIX only
                 user: Some(0),             // UNIX only
                 group: Some(0),            // UNIX only
                 unix_pex: Some((6, 4, 4)), // UNIX only
@@ -26,7 +26,12 @@
  * SOFTWARE.
  */
 // Mod
+pub mod archive;
 pub mod explorer;
+pub mod queue;
+pub mod search;
+pub mod space;
+pub mod transfer;
 // Ext
 use std::path::PathBuf;
 use std::time::SystemTime;
@@ -74,8 +79,9 @@ pub struct FsFile {
     pub ftype: Option<String>, // File type
     pub readonly: bool,
     pub symlink: Option<Box<FsEntry>>,  // UNIX only
-    pub user: Option<u32>,              // UNIX only
-    pub group: Option<u32>,             // UNIX only
+    pub broken_link: Option<PathBuf>, // Target of a symlink which couldn't be resolved (UNIX only)
+    pub user: Option<u32>,            // UNIX only
+    pub group: Option<u32>,           // UNIX only
     pub unix_pex: Option<(u8, u8, u8)>, // UNIX only
 }
 
@@ -182,11 +188,57 @@ impl FsEntry {
 
     /// ### is_symlink
     ///
-    /// Returns whether the `FsEntry` is a symlink
+    /// Returns whether the `FsEntry` is a symlink (even if broken)
     pub fn is_symlink(&self) -> bool {
         match self {
             FsEntry::Directory(dir) => dir.symlink.is_some(),
-            FsEntry::File(file) => file.symlink.is_some(),
+            FsEntry::File(file) => file.symlink.is_some() || file.broken_link.is_some(),
+        }
+    }
+
+    /// ### is_broken_link
+    ///
+    /// Returns whether the `FsEntry` is a symlink whose target couldn't be resolved
+    pub fn is_broken_link(&self) -> bool {
+        match self {
+            FsEntry::Directory(_) => false,
+            FsEntry::File(file) => file.broken_link.is_some(),
+        }
+    }
+
+    /// ### get_symlink_target
+    ///
+    /// Returns the path of the file pointed by the symlink, if `FsEntry` is a symlink
+    pub fn get_symlink_target(&self) -> Option<PathBuf> {
+        match self {
+            FsEntry::File(FsFile {
+                broken_link: Some(target),
+                ..
+            }) => Some(target.clone()),
+            entry if entry.is_symlink() => Some(entry.get_realfile().get_abs_path()),
+            _ => None,
+        }
+    }
+
+    /// ### get_link_target
+    ///
+    /// Returns the path the symlink points to, if `FsEntry` is a symlink; unlike `get_symlink_target`,
+    /// chained links are not resolved
+    pub fn get_link_target(&self) -> Option<PathBuf> {
+        match self {
+            FsEntry::Directory(FsDirectory {
+                symlink: Some(target),
+                ..
+            })
+            | FsEntry::File(FsFile {
+                symlink: Some(target),
+                ..
+            }) => Some(target.get_abs_path()),
+            FsEntry::File(FsFile {
+                broken_link: Some(target),
+                ..
+            }) => Some(target.clone()),
+            _ => None,
         }
     }
 
@@ -276,6 +328,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -308,6 +361,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -323,6 +377,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -357,6 +412,7 @@ mod tests {
             readonly: false,
             ftype: Some(String::from("txt")),
             symlink: None,             // UNIX only
+            broken_link: None,         // UNIX only
             user: Some(0),             // UNIX only
             group: Some(0),            // UNIX only
             unix_pex: Some((6, 4, 4)), // UNIX only
@@ -380,6 +436,7 @@ mod tests {
             unix_pex: Some((7, 5, 5)), // UNIX only
         });
         assert_eq!(entry.get_realfile().get_abs_path(), PathBuf::from("/foo"));
+        assert!(entry.get_link_target().is_none());
     }
 
     #[test]
@@ -421,11 +478,17 @@ mod tests {
             readonly: false,
             ftype: None,
             symlink: Some(Box::new(entry_child)),
+            broken_link: None,
             user: Some(0),
             group: Some(0),
             unix_pex: Some((7, 7, 7)),
         });
         assert_eq!(entry_root.is_symlink(), true);
+        // Link target is not resolved
+        assert_eq!(
+            entry_root.get_link_target(),
+            Some(PathBuf::from("/develop/projects"))
+        );
         // get real file
         let real_file: FsEntry = entry_root.get_realfile();
         // real file must be projects in /home/cvisintin
@@ -0,0 +1,571 @@
+//! ## Queue
+//!
+//! `queue` is the module which provides the transfer queue, where files are enqueued before being transferred
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Mods
+pub mod scheduler;
+pub mod serializer;
+// Locals
+use super::transfer::Throughput;
+use super::FsEntry;
+// Ext
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// ## TransferDirection
+///
+/// Describes whether a queued entry must be uploaded or downloaded
+#[derive(Copy, Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferDirection {
+    Upload,
+    Download,
+}
+
+/// ## Priority
+///
+/// Priority of a queued transfer. Items with higher priority are transferred first
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    Normal,
+    Low,
+}
+
+/// ## QueueItemState
+///
+/// Describes the state of a queued transfer
+#[derive(Clone, Debug, PartialEq)]
+pub enum QueueItemState {
+    Pending,
+    Done,
+    Failed(String),
+}
+
+/// ## QueueItem
+///
+/// A transfer in the queue
+#[derive(Clone, Debug)]
+pub struct QueueItem {
+    pub id: usize,
+    pub entry: FsEntry,
+    pub dest: PathBuf,
+    pub direction: TransferDirection,
+    pub priority: Priority,
+    pub state: QueueItemState,
+    pub remove_source: bool, // Remove the source entry once transferred (i.e. move it to the destination)
+}
+
+/// ## QueueManifest
+///
+/// QueueManifest describes the transfers of a queue, so that it can be exported and imported later
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct QueueManifest {
+    pub transfers: Vec<ManifestItem>,
+}
+
+/// ## ManifestItem
+///
+/// A transfer in the `QueueManifest`
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ManifestItem {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub direction: TransferDirection,
+    #[serde(default = "ManifestItem::default_priority")]
+    pub priority: Priority,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remove_source: bool,
+}
+
+impl ManifestItem {
+    fn default_priority() -> Priority {
+        Priority::Normal
+    }
+}
+
+// Errors
+
+/// ## SerializerError
+///
+/// Contains the error for serializer/deserializer
+#[derive(std::fmt::Debug)]
+pub struct SerializerError {
+    kind: SerializerErrorKind,
+    msg: Option<String>,
+}
+
+/// ## SerializerErrorKind
+///
+/// Describes the kind of error for the serializer/deserializer
+#[derive(std::fmt::Debug, PartialEq)]
+#[allow(clippy::enum_variant_names)]
+pub enum SerializerErrorKind {
+    IoError,
+    SerializationError,
+    SyntaxError,
+}
+
+impl SerializerError {
+    /// ### new
+    ///
+    /// Instantiate a new `SerializerError`
+    pub fn new(kind: SerializerErrorKind) -> SerializerError {
+        SerializerError { kind, msg: None }
+    }
+
+    /// ### new_ex
+    ///
+    /// Instantiates a new `SerializerError` with description message
+    pub fn new_ex(kind: SerializerErrorKind, msg: String) -> SerializerError {
+        let mut err: SerializerError = SerializerError::new(kind);
+        err.msg = Some(msg);
+        err
+    }
+}
+
+impl std::fmt::Display for SerializerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let err: String = match &self.kind {
+            SerializerErrorKind::IoError => String::from("IO error"),
+            SerializerErrorKind::SerializationError => String::from("Serialization error"),
+            SerializerErrorKind::SyntaxError => String::from("Syntax error"),
+        };
+        match &self.msg {
+            Some(msg) => write!(f, "{} ({})", err, msg),
+            None => write!(f, "{}", err),
+        }
+    }
+}
+
+/// ## PlanItem
+///
+/// A transfer of the batch about to run, as previewed by the `BatchPlan`
+#[derive(Clone, Debug)]
+pub struct PlanItem {
+    pub direction: TransferDirection,
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub size: Option<u64>, // Bytes to transfer; None if unknown (e.g. remote directories)
+    pub exists: bool,      // Whether the target already exists
+    pub remove_source: bool,
+}
+
+/// ## BatchPlan
+///
+/// Describes what running the pending transfers of the queue will do, without doing it
+#[derive(Debug, Default)]
+pub struct BatchPlan {
+    pub items: Vec<PlanItem>,
+    pub throughput: Option<u64>, // Bytes per second of the recent transfers with the host, if any
+}
+
+/// ## TransferQueue
+///
+/// TransferQueue keeps the list of enqueued transfers
+#[derive(Default)]
+pub struct TransferQueue {
+    items: Vec<QueueItem>,
+    next_id: usize,
+}
+
+impl TransferQueue {
+    /// ### push
+    ///
+    /// Enqueue a new transfer. Returns the id of the new item
+    pub fn push(
+        &mut self,
+        entry: FsEntry,
+        dest: PathBuf,
+        direction: TransferDirection,
+        priority: Priority,
+    ) -> usize {
+        let id: usize = self.next_id;
+        self.next_id += 1;
+        self.items.push(QueueItem {
+            id,
+            entry,
+            dest,
+            direction,
+            priority,
+            state: QueueItemState::Pending,
+            remove_source: false,
+        });
+        id
+    }
+
+    /// ### next_pending
+    ///
+    /// Get the id of the next item to transfer.
+    /// Items with the highest priority come first; items with the same priority are served in insertion order
+    pub fn next_pending(&self) -> Option<usize> {
+        self.items
+            .iter()
+            .filter(|x| x.state == QueueItemState::Pending)
+            .min_by_key(|x| (x.priority, x.id))
+            .map(|x| x.id)
+    }
+
+    /// ### pending
+    ///
+    /// Get the ids of the items to transfer, in the order they would be returned by `next_pending`
+    pub fn pending(&self) -> Vec<usize> {
+        let mut items: Vec<&QueueItem> = self
+            .items
+            .iter()
+            .filter(|x| x.state == QueueItemState::Pending)
+            .collect();
+        items.sort_by_key(|x| (x.priority, x.id));
+        items.into_iter().map(|x| x.id).collect()
+    }
+
+    /// ### get
+    ///
+    /// Get item by id
+    pub fn get(&self, id: usize) -> Option<&QueueItem> {
+        self.items.iter().find(|x| x.id == id)
+    }
+
+    /// ### set_state
+    ///
+    /// Set state for item with provided id
+    pub fn set_state(&mut self, id: usize, state: QueueItemState) {
+        if let Some(item) = self.get_mut(id) {
+            item.state = state;
+        }
+    }
+
+    /// ### set_priority
+    ///
+    /// Set priority for item with provided id
+    pub fn set_priority(&mut self, id: usize, priority: Priority) {
+        if let Some(item) = self.get_mut(id) {
+            item.priority = priority;
+        }
+    }
+
+    /// ### set_remove_source
+    ///
+    /// Set whether the source of item with provided id must be removed once transferred
+    pub fn set_remove_source(&mut self, id: usize, remove_source: bool) {
+        if let Some(item) = self.get_mut(id) {
+            item.remove_source = remove_source;
+        }
+    }
+
+    /// ### retry
+    ///
+    /// Put a failed item back to pending. Returns whether the item has been rescheduled
+    pub fn retry(&mut self, id: usize) -> bool {
+        match self.get_mut(id) {
+            Some(item) if matches!(item.state, QueueItemState::Failed(_)) => {
+                item.state = QueueItemState::Pending;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// ### remove
+    ///
+    /// Remove item with provided id from queue
+    pub fn remove(&mut self, id: usize) -> Option<QueueItem> {
+        let idx: usize = self.items.iter().position(|x| x.id == id)?;
+        Some(self.items.remove(idx))
+    }
+
+    /// ### clear_done
+    ///
+    /// Remove all the completed items from queue
+    pub fn clear_done(&mut self) {
+        self.items.retain(|x| x.state != QueueItemState::Done);
+    }
+
+    /// ### manifest
+    ///
+    /// Make the manifest for the transfers in queue which haven't been completed yet
+    pub fn manifest(&self) -> QueueManifest {
+        QueueManifest {
+            transfers: self
+                .items
+                .iter()
+                .filter(|x| x.state != QueueItemState::Done)
+                .map(|x| ManifestItem {
+                    source: x.entry.get_abs_path(),
+                    destination: x.dest.clone(),
+                    direction: x.direction,
+                    priority: x.priority,
+                    remove_source: x.remove_source,
+                })
+                .collect(),
+        }
+    }
+
+    /// ### iter
+    ///
+    /// Iterate over queued items in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &QueueItem> + '_ {
+        self.items.iter()
+    }
+
+    /// ### is_empty
+    ///
+    /// Returns whether the queue is empty
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    fn get_mut(&mut self, id: usize) -> Option<&mut QueueItem> {
+        self.items.iter_mut().find(|x| x.id == id)
+    }
+}
+
+impl BatchPlan {
+    /// ### count
+    ///
+    /// Get the amount of transfers in `direction`
+    pub fn count(&self, direction: TransferDirection) -> usize {
+        self.items
+            .iter()
+            .filter(|x| x.direction == direction)
+            .count()
+    }
+
+    /// ### overwrites
+    ///
+    /// Get the amount of transfers whose target already exists
+    pub fn overwrites(&self) -> usize {
+        self.items.iter().filter(|x| x.exists).count()
+    }
+
+    /// ### deletions
+    ///
+    /// Get the amount of sources removed once transferred
+    pub fn deletions(&self) -> usize {
+        self.items.iter().filter(|x| x.remove_source).count()
+    }
+
+    /// ### total
+    ///
+    /// Get the bytes to transfer; transfers of unknown size are not counted
+    pub fn total(&self) -> u64 {
+        self.items.iter().filter_map(|x| x.size).sum()
+    }
+
+    /// ### unknown
+    ///
+    /// Get the amount of transfers whose size is unknown
+    pub fn unknown(&self) -> usize {
+        self.items.iter().filter(|x| x.size.is_none()).count()
+    }
+
+    /// ### eta
+    ///
+    /// Estimate the time needed to transfer the batch, from the throughput of the recent transfers
+    pub fn eta(&self) -> Option<Duration> {
+        self.throughput
+            .and_then(|rate| Throughput::eta(self.total(), rate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::fs::FsFile;
+
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_fs_queue_priority() {
+        let mut queue: TransferQueue = TransferQueue::default();
+        assert!(queue.is_empty());
+        assert_eq!(queue.next_pending(), None);
+        let a: usize = queue.push(
+            make_fs_entry("a.txt"),
+            PathBuf::from("/tmp"),
+            TransferDirection::Upload,
+            Priority::Normal,
+        );
+        let b: usize = queue.push(
+            make_fs_entry("b.txt"),
+            PathBuf::from("/tmp"),
+            TransferDirection::Download,
+            Priority::Low,
+        );
+        let c: usize = queue.push(
+            make_fs_entry("c.txt"),
+            PathBuf::from("/tmp"),
+            TransferDirection::Upload,
+            Priority::Normal,
+        );
+        assert_eq!(queue.iter().count(), 3);
+        // FIFO within the same priority
+        assert_eq!(queue.next_pending(), Some(a));
+        // High priority preempts
+        queue.set_priority(b, Priority::High);
+        assert_eq!(queue.next_pending(), Some(b));
+        assert_eq!(queue.pending(), vec![b, a, c]);
+        queue.set_state(b, QueueItemState::Done);
+        assert_eq!(queue.next_pending(), Some(a));
+        queue.set_state(a, QueueItemState::Done);
+        assert_eq!(queue.next_pending(), Some(c));
+        queue.set_state(c, QueueItemState::Done);
+        assert_eq!(queue.next_pending(), None);
+        queue.clear_done();
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_fs_queue_retry() {
+        let mut queue: TransferQueue = TransferQueue::default();
+        let a: usize = queue.push(
+            make_fs_entry("a.txt"),
+            PathBuf::from("/tmp"),
+            TransferDirection::Upload,
+            Priority::Normal,
+        );
+        let b: usize = queue.push(
+            make_fs_entry("b.txt"),
+            PathBuf::from("/tmp"),
+            TransferDirection::Upload,
+            Priority::Normal,
+        );
+        queue.set_state(a, QueueItemState::Failed(String::from("timeout")));
+        queue.set_state(b, QueueItemState::Done);
+        assert_eq!(queue.next_pending(), None);
+        // Can't retry a successful item
+        assert!(!queue.retry(b));
+        assert!(!queue.retry(42));
+        assert!(queue.retry(a));
+        assert_eq!(queue.get(a).unwrap().state, QueueItemState::Pending);
+        assert_eq!(queue.next_pending(), Some(a));
+        // Remove
+        assert_eq!(queue.remove(a).unwrap().id, a);
+        assert!(queue.remove(a).is_none());
+        assert_eq!(queue.iter().count(), 1);
+    }
+
+    #[test]
+    fn test_fs_queue_manifest() {
+        let mut queue: TransferQueue = TransferQueue::default();
+        let a: usize = queue.push(
+            make_fs_entry("/home/omar/a.txt"),
+            PathBuf::from("/srv"),
+            TransferDirection::Upload,
+            Priority::High,
+        );
+        let b: usize = queue.push(
+            make_fs_entry("/srv/b.txt"),
+            PathBuf::from("/home/omar"),
+            TransferDirection::Download,
+            Priority::Normal,
+        );
+        queue.set_state(a, QueueItemState::Done);
+        queue.set_state(b, QueueItemState::Failed(String::from("timeout")));
+        queue.set_remove_source(b, true);
+        assert!(queue.get(b).unwrap().remove_source);
+        assert!(!queue.get(a).unwrap().remove_source);
+        let manifest: QueueManifest = queue.manifest();
+        assert_eq!(
+            manifest.transfers,
+            vec![ManifestItem {
+                source: PathBuf::from("/srv/b.txt"),
+                destination: PathBuf::from("/home/omar"),
+                direction: TransferDirection::Download,
+                priority: Priority::Normal,
+                remove_source: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fs_queue_batch_plan() {
+        let mut plan: BatchPlan = BatchPlan {
+            items: vec![
+                PlanItem {
+                    direction: TransferDirection::Upload,
+                    source: PathBuf::from("/home/omar/a.txt"),
+                    target: PathBuf::from("/srv/a.txt"),
+                    size: Some(4096),
+                    exists: true,
+                    remove_source: false,
+                },
+                PlanItem {
+                    direction: TransferDirection::Upload,
+                    source: PathBuf::from("/home/omar/b.txt"),
+                    target: PathBuf::from("/srv/b.txt"),
+                    size: Some(6144),
+                    exists: false,
+                    remove_source: false,
+                },
+                PlanItem {
+                    direction: TransferDirection::Download,
+                    source: PathBuf::from("/srv/logs"),
+                    target: PathBuf::from("/home/omar/logs"),
+                    size: None,
+                    exists: false,
+                    remove_source: true,
+                },
+            ],
+            throughput: None,
+        };
+        assert_eq!(plan.count(TransferDirection::Upload), 2);
+        assert_eq!(plan.count(TransferDirection::Download), 1);
+        assert_eq!(plan.overwrites(), 1);
+        assert_eq!(plan.deletions(), 1);
+        assert_eq!(plan.total(), 10240);
+        assert_eq!(plan.unknown(), 1);
+        // Unknown throughput
+        assert_eq!(plan.eta(), None);
+        plan.throughput = Some(1024);
+        assert_eq!(plan.eta(), Some(Duration::from_secs(10)));
+        plan.throughput = Some(0);
+        assert_eq!(plan.eta(), None);
+        assert_eq!(BatchPlan::default().total(), 0);
+    }
+
+    fn make_fs_entry(name: &str) -> FsEntry {
+        let t_now: SystemTime = SystemTime::now();
+        FsEntry::File(FsFile {
+            name: name.to_string(),
+            abs_path: PathBuf::from(name),
+            last_change_time: t_now,
+            last_access_time: t_now,
+            creation_time: t_now,
+            size: 64,
+            ftype: None,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: Some(0),
+            group: Some(0),
+            unix_pex: Some((6, 4, 4)),
+        })
+    }
+}
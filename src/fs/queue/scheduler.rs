@@ -0,0 +1,244 @@
+//! ## Scheduler
+//!
+//! `scheduler` is the module which keeps the transfer jobs to run periodically while termscp is open
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use super::QueueManifest;
+
+use chrono::{DateTime, Duration, Local};
+
+/// ## ScheduledJob
+///
+/// A transfer job run every `interval` minutes
+#[derive(Clone, Debug)]
+pub struct ScheduledJob {
+    pub id: usize,
+    pub name: String,
+    pub manifest: QueueManifest, // Transfers enqueued on each run
+    pub interval: u64,           // Minutes between two runs
+    pub next_run: DateTime<Local>,
+    pub last_run: Option<JobRun>,
+}
+
+/// ## JobRun
+///
+/// Outcome of the last run of a scheduled job
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobRun {
+    pub at: DateTime<Local>,
+    pub transferred: usize,
+    pub failed: usize, // Failed transfers, including sources which couldn't be found
+}
+
+/// ## JobScheduler
+///
+/// JobScheduler keeps the scheduled jobs and tells which one must run next
+#[derive(Default)]
+pub struct JobScheduler {
+    jobs: Vec<ScheduledJob>,
+    next_id: usize,
+}
+
+impl JobScheduler {
+    /// ### add
+    ///
+    /// Schedule `manifest` to run every `interval` minutes, starting from `now`.
+    /// Returns the id of the job
+    pub fn add(
+        &mut self,
+        name: String,
+        manifest: QueueManifest,
+        interval: u64,
+        now: DateTime<Local>,
+    ) -> usize {
+        let id: usize = self.next_id;
+        self.next_id += 1;
+        let interval: u64 = interval.max(1);
+        self.jobs.push(ScheduledJob {
+            id,
+            name,
+            manifest,
+            interval,
+            next_run: now + Duration::minutes(interval as i64),
+            last_run: None,
+        });
+        id
+    }
+
+    /// ### remove
+    ///
+    /// Remove job with `id` from the scheduler
+    pub fn remove(&mut self, id: usize) -> Option<ScheduledJob> {
+        let idx: usize = self.jobs.iter().position(|x| x.id == id)?;
+        Some(self.jobs.remove(idx))
+    }
+
+    /// ### get
+    ///
+    /// Get job by id
+    pub fn get(&self, id: usize) -> Option<&ScheduledJob> {
+        self.jobs.iter().find(|x| x.id == id)
+    }
+
+    /// ### due
+    ///
+    /// Get the id of the job which should have run the longest ago at `now`, if any
+    pub fn due(&self, now: DateTime<Local>) -> Option<usize> {
+        self.jobs
+            .iter()
+            .filter(|x| x.next_run <= now)
+            .min_by_key(|x| x.next_run)
+            .map(|x| x.id)
+    }
+
+    /// ### complete
+    ///
+    /// Record the outcome of a run of job `id`.
+    /// Runs missed in the meantime (e.g. while a transfer was in progress) are skipped,
+    /// while running a job ahead of time doesn't change its schedule
+    pub fn complete(&mut self, id: usize, run: JobRun) {
+        if let Some(job) = self.jobs.iter_mut().find(|x| x.id == id) {
+            if job.next_run <= run.at {
+                let interval: Duration = Duration::minutes(job.interval as i64);
+                let missed: i64 = (run.at - job.next_run).num_seconds() / interval.num_seconds();
+                job.next_run = job.next_run + interval * (missed + 1) as i32;
+            }
+            job.last_run = Some(run);
+        }
+    }
+
+    /// ### iter
+    ///
+    /// Iterate over scheduled jobs in insertion order
+    pub fn iter(&self) -> impl Iterator<Item = &ScheduledJob> + '_ {
+        self.jobs.iter()
+    }
+
+    /// ### is_empty
+    ///
+    /// Returns whether there's no job scheduled
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{ManifestItem, Priority, TransferDirection};
+    use super::*;
+
+    use chrono::TimeZone;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fs_queue_scheduler() {
+        let mut scheduler: JobScheduler = JobScheduler::default();
+        assert!(scheduler.is_empty());
+        let now: DateTime<Local> = Local.ymd(2021, 6, 1).and_hms(10, 0, 0);
+        let logs: usize = scheduler.add(String::from("logs"), make_manifest("/var/log"), 15, now);
+        let backup: usize = scheduler.add(String::from("backup"), make_manifest("/home"), 5, now);
+        assert_eq!(scheduler.iter().count(), 2);
+        assert_eq!(
+            scheduler.get(logs).unwrap().next_run,
+            Local.ymd(2021, 6, 1).and_hms(10, 15, 0)
+        );
+        assert_eq!(scheduler.get(logs).unwrap().manifest.transfers.len(), 1);
+        assert!(scheduler.get(logs).unwrap().last_run.is_none());
+        // Nothing to run yet
+        assert_eq!(scheduler.due(now), None);
+        assert_eq!(
+            scheduler.due(Local.ymd(2021, 6, 1).and_hms(10, 4, 59)),
+            None
+        );
+        assert_eq!(
+            scheduler.due(Local.ymd(2021, 6, 1).and_hms(10, 5, 0)),
+            Some(backup)
+        );
+        // The job which should have run first goes first
+        let late: DateTime<Local> = Local.ymd(2021, 6, 1).and_hms(10, 20, 0);
+        assert_eq!(scheduler.due(late), Some(backup));
+        // Missed runs are skipped
+        let run: JobRun = JobRun {
+            at: Local.ymd(2021, 6, 1).and_hms(10, 21, 0),
+            transferred: 3,
+            failed: 1,
+        };
+        scheduler.complete(backup, run.clone());
+        assert_eq!(
+            scheduler.get(backup).unwrap().next_run,
+            Local.ymd(2021, 6, 1).and_hms(10, 25, 0)
+        );
+        assert_eq!(scheduler.get(backup).unwrap().last_run, Some(run));
+        assert_eq!(scheduler.due(late), Some(logs));
+        // Running ahead of time keeps the schedule
+        scheduler.complete(
+            logs,
+            JobRun {
+                at: Local.ymd(2021, 6, 1).and_hms(10, 1, 0),
+                transferred: 1,
+                failed: 0,
+            },
+        );
+        assert_eq!(
+            scheduler.get(logs).unwrap().next_run,
+            Local.ymd(2021, 6, 1).and_hms(10, 15, 0)
+        );
+        assert!(scheduler.get(logs).unwrap().last_run.is_some());
+        // Run exactly on time
+        scheduler.complete(
+            logs,
+            JobRun {
+                at: Local.ymd(2021, 6, 1).and_hms(10, 15, 0),
+                transferred: 1,
+                failed: 0,
+            },
+        );
+        assert_eq!(
+            scheduler.get(logs).unwrap().next_run,
+            Local.ymd(2021, 6, 1).and_hms(10, 30, 0)
+        );
+        // Interval is at least one minute
+        let fast: usize = scheduler.add(String::from("fast"), QueueManifest::default(), 0, now);
+        assert_eq!(scheduler.get(fast).unwrap().interval, 1);
+        // Remove
+        assert_eq!(scheduler.remove(backup).unwrap().id, backup);
+        assert!(scheduler.remove(backup).is_none());
+        assert!(scheduler.get(backup).is_none());
+        assert_eq!(scheduler.iter().count(), 2);
+    }
+
+    fn make_manifest(source: &str) -> QueueManifest {
+        QueueManifest {
+            transfers: vec![ManifestItem {
+                source: PathBuf::from(source),
+                destination: PathBuf::from("/tmp"),
+                direction: TransferDirection::Download,
+                priority: Priority::Normal,
+                remove_source: false,
+            }],
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! ## Serializer
+//!
+//! `serializer` is the module which provides the serializer/deserializer for transfer queue manifests
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use super::{QueueManifest, SerializerError, SerializerErrorKind};
+
+use std::io::{Read, Write};
+
+pub struct QueueSerializer;
+
+impl QueueSerializer {
+    /// ### serialize
+    ///
+    /// Serialize `QueueManifest` into JSON and write content to writable
+    pub fn serialize(
+        &self,
+        mut writable: Box<dyn Write>,
+        manifest: &QueueManifest,
+    ) -> Result<(), SerializerError> {
+        // Serialize content
+        let data: String = match serde_json::to_string_pretty(manifest) {
+            Ok(dt) => dt,
+            Err(err) => {
+                return Err(SerializerError::new_ex(
+                    SerializerErrorKind::SerializationError,
+                    err.to_string(),
+                ))
+            }
+        };
+        // Write file
+        match writable.write_all(data.as_bytes()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(SerializerError::new_ex(
+                SerializerErrorKind::IoError,
+                err.to_string(),
+            )),
+        }
+    }
+
+    /// ### deserialize
+    ///
+    /// Read data from readable and deserialize its content as JSON
+    pub fn deserialize(
+        &self,
+        mut readable: Box<dyn Read>,
+    ) -> Result<QueueManifest, SerializerError> {
+        // Read file content
+        let mut data: String = String::new();
+        if let Err(err) = readable.read_to_string(&mut data) {
+            return Err(SerializerError::new_ex(
+                SerializerErrorKind::IoError,
+                err.to_string(),
+            ));
+        }
+        // Deserialize
+        match serde_json::from_str(data.as_str()) {
+            Ok(manifest) => Ok(manifest),
+            Err(err) => Err(SerializerError::new_ex(
+                SerializerErrorKind::SyntaxError,
+                err.to_string(),
+            )),
+        }
+    }
+}
+
+// Tests
+
+#[cfg(test)]
+mod tests {
+
+    use super::super::{ManifestItem, Priority, TransferDirection};
+    use super::*;
+
+    use std::io::{Seek, SeekFrom};
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_fs_queue_serializer_deserialize_ok() {
+        let mut tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        let data: &str = r#"{
+            "transfers": [
+                {
+                    "source": "/home/omar/site.tar.gz",
+                    "destination": "/var/www",
+                    "direction": "upload",
+                    "priority": "high"
+                },
+                {
+                    "source": "/var/log/nginx/access.log",
+                    "destination": "/home/omar/logs",
+                    "direction": "download",
+                    "remove_source": true
+                }
+            ]
+        }"#;
+        tmpfile.write_all(data.as_bytes()).unwrap();
+        tmpfile.as_file().seek(SeekFrom::Start(0)).unwrap();
+        let deserializer: QueueSerializer = QueueSerializer {};
+        let manifest: QueueManifest = deserializer.deserialize(Box::new(tmpfile)).unwrap();
+        assert_eq!(manifest.transfers.len(), 2);
+        assert_eq!(
+            manifest.transfers[0],
+            ManifestItem {
+                source: PathBuf::from("/home/omar/site.tar.gz"),
+                destination: PathBuf::from("/var/www"),
+                direction: TransferDirection::Upload,
+                priority: Priority::High,
+                remove_source: false,
+            }
+        );
+        // Priority defaults to normal
+        assert_eq!(manifest.transfers[1].direction, TransferDirection::Download);
+        assert_eq!(manifest.transfers[1].priority, Priority::Normal);
+        assert!(manifest.transfers[1].remove_source);
+    }
+
+    #[test]
+    fn test_fs_queue_serializer_deserialize_nok() {
+        let mut tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        tmpfile
+            .write_all(br#"{"transfers": [{"source": "/a", "direction": "sideways"}]}"#)
+            .unwrap();
+        tmpfile.as_file().seek(SeekFrom::Start(0)).unwrap();
+        let deserializer: QueueSerializer = QueueSerializer {};
+        assert!(deserializer.deserialize(Box::new(tmpfile)).is_err());
+    }
+
+    #[test]
+    fn test_fs_queue_serializer_serialize() {
+        let manifest: QueueManifest = QueueManifest {
+            transfers: vec![ManifestItem {
+                source: PathBuf::from("/srv/backup.zip"),
+                destination: PathBuf::from("/tmp"),
+                direction: TransferDirection::Download,
+                priority: Priority::Low,
+                remove_source: false,
+            }],
+        };
+        let tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        let serializer: QueueSerializer = QueueSerializer {};
+        assert!(serializer
+            .serialize(Box::new(tmpfile.reopen().unwrap()), &manifest)
+            .is_ok());
+        // Read it back
+        let manifest: QueueManifest = serializer
+            .deserialize(Box::new(tmpfile.reopen().unwrap()))
+            .unwrap();
+        assert_eq!(manifest.transfers.len(), 1);
+        assert_eq!(manifest.transfers[0].priority, Priority::Low);
+        assert_eq!(
+            manifest.transfers[0].source,
+            PathBuf::from("/srv/backup.zip")
+        );
+    }
+}
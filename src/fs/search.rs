@@ -0,0 +1,329 @@
+//! ## Search
+//!
+//! `search` is the module which parses the criteria of the searches in the file explorers
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::FsEntry;
+// Ext
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime};
+
+/// Units of the ages in search queries, from the largest one
+const AGE_UNITS: [(char, u64); 5] = [
+    ('w', 604800),
+    ('d', 86400),
+    ('h', 3600),
+    ('m', 60),
+    ('s', 1),
+];
+
+/// Units of the sizes in search queries, from the largest one
+const SIZE_UNITS: [(char, u64); 4] = [('G', 1073741824), ('M', 1048576), ('K', 1024), ('B', 1)];
+
+/// ## SearchQuery
+///
+/// Criteria of a search: the pattern matched against the name of the entries (supports wildcards),
+/// the directory to search in (the working directory if not set), the range of modification times
+/// and the range of file sizes.
+/// Queries are written as `<pattern> [in:<dir>] [newer:<age>] [older:<age>] [larger:<size>] [smaller:<size>]`,
+/// where ages are a number followed by `s`, `m`, `h`, `d` or `w` (e.g. `*.log in:/var/log newer:1d`)
+/// and sizes a number optionally followed by `K`, `M` or `G` (e.g. `larger:100M`)
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    pub directory: Option<PathBuf>,
+    pub newer: Option<Duration>, // Entries modified within this time
+    pub older: Option<Duration>, // Entries modified before this time
+    pub larger: Option<u64>,     // Files of at least this size (bytes); excludes directories
+    pub smaller: Option<u64>,    // Files of at most this size (bytes); excludes directories
+}
+
+impl SearchQuery {
+    /// ### from_pattern
+    ///
+    /// Instantiates a query matching `pattern` in the working directory
+    pub fn from_pattern(pattern: &str) -> Self {
+        SearchQuery {
+            pattern: pattern.to_string(),
+            directory: None,
+            newer: None,
+            older: None,
+            larger: None,
+            smaller: None,
+        }
+    }
+
+    /// ### matches
+    ///
+    /// Returns whether `entry`, whose name matches the pattern, matches the other criteria of the query
+    pub fn matches(&self, entry: &FsEntry, now: SystemTime) -> bool {
+        self.is_in_time_range(entry, now) && self.is_in_size_range(entry)
+    }
+
+    /// ### is_in_size_range
+    ///
+    /// Returns whether `entry` is in the size range of the query; if set, directories are never in range
+    pub fn is_in_size_range(&self, entry: &FsEntry) -> bool {
+        if self.larger.is_none() && self.smaller.is_none() {
+            return true;
+        }
+        let size: u64 = entry.get_size() as u64;
+        entry.is_file()
+            && self.larger.map(|x| size >= x).unwrap_or(true)
+            && self.smaller.map(|x| size <= x).unwrap_or(true)
+    }
+
+    /// ### is_in_time_range
+    ///
+    /// Returns whether `entry` has been modified in the time range of the query, relative to `now`
+    pub fn is_in_time_range(&self, entry: &FsEntry, now: SystemTime) -> bool {
+        let age: Duration = now
+            .duration_since(entry.get_last_change_time())
+            .unwrap_or(Duration::ZERO);
+        self.newer.map(|x| age <= x).unwrap_or(true) && self.older.map(|x| age >= x).unwrap_or(true)
+    }
+}
+
+impl FromStr for SearchQuery {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pattern: Vec<&str> = Vec::new();
+        let mut query: SearchQuery = SearchQuery::from_pattern("");
+        for token in s.split_whitespace() {
+            if let Some(dir) = token.strip_prefix("in:") {
+                query.directory = Some(PathBuf::from(dir));
+            } else if let Some(age) = token.strip_prefix("newer:") {
+                query.newer = Some(parse_age(age)?);
+            } else if let Some(age) = token.strip_prefix("older:") {
+                query.older = Some(parse_age(age)?);
+            } else if let Some(size) = token.strip_prefix("larger:") {
+                query.larger = Some(parse_size(size)?);
+            } else if let Some(size) = token.strip_prefix("smaller:") {
+                query.smaller = Some(parse_size(size)?);
+            } else {
+                pattern.push(token);
+            }
+        }
+        query.pattern = pattern.join(" ");
+        match (
+            query.pattern.is_empty(),
+            query == SearchQuery::from_pattern(""),
+        ) {
+            (_, true) => Err(String::from("Empty search")),
+            (true, false) => Ok(SearchQuery {
+                pattern: String::from("*"),
+                ..query
+            }),
+            (false, false) => Ok(query),
+        }
+    }
+}
+
+impl fmt::Display for SearchQuery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pattern)?;
+        if let Some(dir) = self.directory.as_ref() {
+            write!(f, " in:{}", dir.display())?;
+        }
+        if let Some(age) = self.newer {
+            write!(f, " newer:{}", fmt_age(age))?;
+        }
+        if let Some(age) = self.older {
+            write!(f, " older:{}", fmt_age(age))?;
+        }
+        if let Some(size) = self.larger {
+            write!(f, " larger:{}", fmt_size(size))?;
+        }
+        if let Some(size) = self.smaller {
+            write!(f, " smaller:{}", fmt_size(size))?;
+        }
+        Ok(())
+    }
+}
+
+/// ### parse_age
+///
+/// Parse an age made of a number and a unit (e.g. `12h`)
+fn parse_age(s: &str) -> Result<Duration, String> {
+    let unit: u64 = s
+        .chars()
+        .last()
+        .and_then(|u| AGE_UNITS.iter().find(|(x, _)| *x == u))
+        .map(|(_, secs)| *secs)
+        .ok_or_else(|| format!("Invalid age \"{}\" (e.g. 30m, 12h, 1d, 2w)", s))?;
+    s[..s.len() - 1]
+        .parse::<u64>()
+        .map(|x| Duration::from_secs(x * unit))
+        .map_err(|_| format!("Invalid age \"{}\" (e.g. 30m, 12h, 1d, 2w)", s))
+}
+
+/// ### fmt_age
+///
+/// Format an age with the largest unit it's a multiple of
+fn fmt_age(age: Duration) -> String {
+    let secs: u64 = age.as_secs();
+    let (unit, size): (char, u64) = AGE_UNITS
+        .iter()
+        .copied()
+        .find(|(_, size)| secs > 0 && secs.checked_rem(*size) == Some(0))
+        .unwrap_or(('s', 1));
+    format!("{}{}", secs / size, unit)
+}
+
+/// ### parse_size
+///
+/// Parse a size made of a number, optionally followed by a unit (e.g. `100M`); units are powers of 1024
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (digits, unit): (&str, u64) = match s.chars().last().map(|x| x.to_ascii_uppercase()) {
+        Some(u) if u.is_ascii_alphabetic() => (
+            &s[..s.len() - 1],
+            SIZE_UNITS
+                .iter()
+                .find(|(x, _)| *x == u)
+                .map(|(_, size)| *size)
+                .ok_or_else(|| format!("Invalid size \"{}\" (e.g. 512K, 100M, 2G)", s))?,
+        ),
+        _ => (s, 1),
+    };
+    digits
+        .parse::<u64>()
+        .map(|x| x * unit)
+        .map_err(|_| format!("Invalid size \"{}\" (e.g. 512K, 100M, 2G)", s))
+}
+
+/// ### fmt_size
+///
+/// Format a size with the largest unit it's a multiple of
+fn fmt_size(size: u64) -> String {
+    match SIZE_UNITS
+        .iter()
+        .find(|(_, x)| size > 0 && *x > 1 && size.checked_rem(*x) == Some(0))
+    {
+        Some((unit, x)) => format!("{}{}", size / x, unit),
+        None => size.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::fs::FsFile;
+
+    #[test]
+    fn test_fs_search_query() {
+        let query: SearchQuery = SearchQuery::from_str("*.log  in:/var/log newer:1d").unwrap();
+        assert_eq!(query.pattern.as_str(), "*.log");
+        assert_eq!(query.directory, Some(PathBuf::from("/var/log")));
+        assert_eq!(query.newer, Some(Duration::from_secs(86400)));
+        assert_eq!(query.older, None);
+        assert_eq!(query.to_string().as_str(), "*.log in:/var/log newer:1d");
+        // Pattern only
+        let query: SearchQuery = SearchQuery::from_str("my file*").unwrap();
+        assert_eq!(query, SearchQuery::from_pattern("my file*"));
+        assert_eq!(query.to_string().as_str(), "my file*");
+        // Options only
+        let query: SearchQuery = SearchQuery::from_str("older:90m").unwrap();
+        assert_eq!(query.pattern.as_str(), "*");
+        assert_eq!(query.older, Some(Duration::from_secs(5400)));
+        assert_eq!(query.to_string().as_str(), "* older:90m");
+        assert_eq!(
+            SearchQuery::from_str("* newer:2w")
+                .unwrap()
+                .to_string()
+                .as_str(),
+            "* newer:2w"
+        );
+        // Bad queries
+        assert!(SearchQuery::from_str("   ").is_err());
+        assert!(SearchQuery::from_str("*.log newer:1y").is_err());
+        assert!(SearchQuery::from_str("*.log newer:d").is_err());
+        assert!(SearchQuery::from_str("*.log older:").is_err());
+        assert!(SearchQuery::from_str("*.log larger:10X").is_err());
+        assert!(SearchQuery::from_str("*.log smaller:M").is_err());
+    }
+
+    #[test]
+    fn test_fs_search_query_size() {
+        let query: SearchQuery = SearchQuery::from_str("*.iso larger:100M smaller:2g").unwrap();
+        assert_eq!(query.larger, Some(104857600));
+        assert_eq!(query.smaller, Some(2147483648));
+        assert_eq!(query.to_string().as_str(), "*.iso larger:100M smaller:2G");
+        assert_eq!(
+            SearchQuery::from_str("* larger:1500")
+                .unwrap()
+                .to_string()
+                .as_str(),
+            "* larger:1500"
+        );
+        assert_eq!(
+            SearchQuery::from_str("* larger:2048B").unwrap().larger,
+            Some(2048)
+        );
+        // Size range
+        let query: SearchQuery = SearchQuery::from_str("* larger:1K smaller:4K").unwrap();
+        let now: SystemTime = SystemTime::now();
+        assert!(query.matches(&make_file(now, 2048), now));
+        assert!(!query.matches(&make_file(now, 512), now));
+        assert!(!query.matches(&make_file(now, 8192), now));
+    }
+
+    #[test]
+    fn test_fs_search_query_time_range() {
+        let now: SystemTime = SystemTime::now();
+        let entry = |age: u64| make_file(now - Duration::from_secs(age), 64);
+        let query: SearchQuery = SearchQuery::from_str("* newer:1d").unwrap();
+        assert!(query.is_in_time_range(&entry(3600), now));
+        assert!(!query.is_in_time_range(&entry(172800), now));
+        let query: SearchQuery = SearchQuery::from_str("* newer:1d older:1h").unwrap();
+        assert!(!query.is_in_time_range(&entry(60), now));
+        assert!(query.is_in_time_range(&entry(7200), now));
+        assert!(!query.is_in_time_range(&entry(172800), now));
+        // No time range
+        assert!(SearchQuery::from_pattern("*").is_in_time_range(&entry(172800), now));
+    }
+
+    fn make_file(mtime: SystemTime, size: usize) -> FsEntry {
+        FsEntry::File(FsFile {
+            name: String::from("syslog"),
+            abs_path: PathBuf::from("/var/log/syslog"),
+            last_change_time: mtime,
+            last_access_time: mtime,
+            creation_time: mtime,
+            size,
+            ftype: None,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        })
+    }
+}
@@ -0,0 +1,257 @@
+//! ## Space
+//!
+//! `space` is the module which tells whether the files to upload fit in the space left on a host
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::cmp::Reverse;
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+use std::ffi::CString;
+use std::io;
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// ## DiskSpace
+///
+/// Space left on the file system of a host, and in the quota of the user on it
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiskSpace {
+    pub filesystem: String, // Device or name of the file system, as reported by `df`
+    pub available: u64,     // Bytes available on the file system
+    pub quota: Option<u64>, // Bytes left before reaching the quota of the user, if any
+}
+
+/// ## UploadPlan
+///
+/// Tells whether files fit in the space left on a host; if they don't, which of them to skip to fit
+#[derive(Clone, Debug, PartialEq)]
+pub struct UploadPlan {
+    pub total: u64,       // Bytes to upload
+    pub headroom: u64,    // Bytes left
+    pub skip: Vec<usize>, // Indexes of the files to skip, so that the others fit
+}
+
+impl DiskSpace {
+    /// ### parse_df
+    ///
+    /// Parse the output of `df -Pk <path>`; sizes are in KiB
+    pub fn parse_df(output: &str) -> Option<DiskSpace> {
+        // Skip header; the capacity column is the only one ending with '%'
+        let fields: Vec<&str> = output.lines().nth(1)?.split_whitespace().collect();
+        let capacity: usize = fields.iter().position(|x| x.ends_with('%'))?;
+        if capacity < 4 {
+            return None;
+        }
+        let available: u64 = fields[capacity - 1].parse().ok()?;
+        Some(DiskSpace {
+            filesystem: fields[..capacity - 3].join(" "),
+            available: available.saturating_mul(1024),
+            quota: None,
+        })
+    }
+
+    /// ### parse_quota
+    ///
+    /// Parse the output of `quota -w` and set the space left in the quota of the user on this file system.
+    /// Sizes are in KiB; the soft limit applies, or the hard limit if there's no soft limit
+    pub fn parse_quota(&mut self, output: &str) {
+        self.quota = output
+            .lines()
+            .map(|x| x.split_whitespace().collect::<Vec<&str>>())
+            .find(|x| x.len() >= 4 && x[0] == self.filesystem)
+            .and_then(|x| {
+                // Usage is marked with '*' once over quota
+                let used: u64 = x[1].trim_end_matches('*').parse().ok()?;
+                let soft: u64 = x[2].parse().ok()?;
+                let hard: u64 = x[3].parse().ok()?;
+                match (soft, hard) {
+                    (0, 0) => None,
+                    (0, limit) | (limit, _) => {
+                        Some(limit.saturating_sub(used).saturating_mul(1024))
+                    }
+                }
+            });
+    }
+
+    /// ### headroom
+    ///
+    /// Get the bytes which can still be written by the user
+    pub fn headroom(&self) -> u64 {
+        match self.quota {
+            Some(quota) => quota.min(self.available),
+            None => self.available,
+        }
+    }
+
+    /// ### local
+    ///
+    /// Get the space left on the local file system where `path` is.
+    /// `path` may not exist yet, in this case its closest existing ancestor is checked
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    pub fn local(path: &Path) -> io::Result<DiskSpace> {
+        let dir: &Path = path.ancestors().find(|x| x.exists()).unwrap_or(path);
+        let c_path: CString = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(DiskSpace {
+            filesystem: dir.display().to_string(),
+            available: (stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64),
+            quota: None,
+        })
+    }
+
+    #[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+    pub fn local(_path: &Path) -> io::Result<DiskSpace> {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            "not supported on this platform",
+        ))
+    }
+}
+
+impl UploadPlan {
+    /// ### new
+    ///
+    /// Plan the upload of files of `sizes` into `headroom` bytes.
+    /// The largest files are skipped first, so that as few files as possible are skipped
+    pub fn new(sizes: &[u64], headroom: u64) -> Self {
+        let total: u64 = sizes.iter().sum();
+        let mut by_size: Vec<usize> = (0..sizes.len()).collect();
+        by_size.sort_by_key(|x| Reverse(sizes[*x]));
+        let mut left: u64 = total;
+        let skip: Vec<usize> = by_size
+            .into_iter()
+            .take_while(|x| {
+                let skip: bool = left > headroom;
+                left -= sizes[*x];
+                skip
+            })
+            .collect();
+        UploadPlan {
+            total,
+            headroom,
+            skip,
+        }
+    }
+
+    /// ### fits
+    ///
+    /// Returns whether all the files fit
+    pub fn fits(&self) -> bool {
+        self.total <= self.headroom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_fs_space_parse_df() {
+        let space: DiskSpace = DiskSpace::parse_df(
+            "Filesystem     1024-blocks     Used Available Capacity Mounted on\n/dev/sda1         41152736 20000000  19000000      52% /home\n",
+        )
+        .unwrap();
+        assert_eq!(space.filesystem.as_str(), "/dev/sda1");
+        assert_eq!(space.available, 19456000000);
+        assert_eq!(space.quota, None);
+        assert_eq!(space.headroom(), 19456000000);
+        // File system name with spaces
+        let space: DiskSpace = DiskSpace::parse_df(
+            "Filesystem 1024-blocks Used Available Capacity Mounted on\nmap auto home 0 0 0 100% /System/Volumes/Data/home\n",
+        )
+        .unwrap();
+        assert_eq!(space.filesystem.as_str(), "map auto home");
+        assert_eq!(space.available, 0);
+        // Bad output
+        assert!(DiskSpace::parse_df("").is_none());
+        assert!(DiskSpace::parse_df("df: /omar: No such file or directory\n").is_none());
+        assert!(DiskSpace::parse_df("Filesystem\n/dev/sda1 1 2 three 4% /\n").is_none());
+    }
+
+    #[test]
+    fn test_fs_space_parse_quota() {
+        let mut space: DiskSpace = DiskSpace {
+            filesystem: String::from("/dev/sda1"),
+            available: 1048576000,
+            quota: None,
+        };
+        // Soft limit
+        space.parse_quota("Disk quotas for user omar (uid 1000):\n     Filesystem  blocks   quota   limit   grace   files   quota   limit   grace\n      /dev/sda1  150000  200000  220000            1234       0       0\n");
+        assert_eq!(space.quota, Some(51200000));
+        assert_eq!(space.headroom(), 51200000);
+        // Over quota, hard limit only
+        space.parse_quota("     Filesystem  blocks   quota   limit   grace   files   quota   limit   grace\n      /dev/sda1  230000*      0  220000            1234       0       0\n");
+        assert_eq!(space.quota, Some(0));
+        // Another file system, no limits, no quota
+        space.parse_quota(
+            "      /dev/sdb1  150000  200000  220000            1234       0       0\n",
+        );
+        assert_eq!(space.quota, None);
+        space.parse_quota(
+            "      /dev/sda1  150000       0       0            1234       0       0\n",
+        );
+        assert_eq!(space.quota, None);
+        space.parse_quota("Disk quotas for user omar (uid 1000): none\n");
+        assert_eq!(space.quota, None);
+        assert_eq!(space.headroom(), 1048576000);
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_fs_space_local() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let space: DiskSpace = DiskSpace::local(tmpdir.path()).unwrap();
+        assert!(space.available > 0);
+        assert_eq!(space.quota, None);
+        // Missing directories are checked through their ancestors
+        let missing: DiskSpace = DiskSpace::local(&tmpdir.path().join("a/b")).unwrap();
+        assert_eq!(missing.filesystem, tmpdir.path().display().to_string());
+    }
+
+    #[test]
+    fn test_fs_space_upload_plan() {
+        // Fits
+        let plan: UploadPlan = UploadPlan::new(&[100, 200, 300], 1000);
+        assert!(plan.fits());
+        assert_eq!(plan.total, 600);
+        assert!(plan.skip.is_empty());
+        // Skip the largest files first
+        let plan: UploadPlan = UploadPlan::new(&[100, 500, 300, 50], 500);
+        assert!(!plan.fits());
+        assert_eq!(plan.skip, vec![1]);
+        let plan: UploadPlan = UploadPlan::new(&[100, 500, 300, 50], 100);
+        assert_eq!(plan.skip, vec![1, 2, 0]);
+        // Nothing fits
+        let plan: UploadPlan = UploadPlan::new(&[100, 500], 0);
+        assert_eq!(plan.skip, vec![1, 0]);
+        assert!(UploadPlan::new(&[], 0).fits());
+    }
+}
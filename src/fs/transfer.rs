@@ -0,0 +1,1932 @@
+//! ## Transfer
+//!
+//! `transfer` provides the options which define how files are transferred
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::queue::TransferDirection;
+use super::FsEntry;
+use crate::system::trash::REMOTE_TRASH_DIR;
+use crate::utils::parser::Permissions;
+use crate::utils::shell;
+// Ext
+use chrono::{Datelike, Local, NaiveDateTime, NaiveTime, Timelike, Weekday};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::VecDeque;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use wildmatch::WildMatch;
+
+/// Extensions of the files which are already compressed, so they're not compressed again when uploaded
+const COMPRESSED_EXTENSIONS: [&str; 24] = [
+    "7z", "apk", "avi", "br", "bz2", "deb", "docx", "gif", "gz", "jar", "jpeg", "jpg", "lz4",
+    "mkv", "mp3", "mp4", "png", "rar", "rpm", "tgz", "webp", "xz", "zip", "zst",
+];
+
+/// Size of the first chunk of a transfer
+const CHUNK_SIZE: usize = 65536;
+/// Bounds of the chunk size
+const MIN_CHUNK_SIZE: usize = 16384;
+const MAX_CHUNK_SIZE: usize = 4194304;
+/// A chunk taking longer than this is shrunk, so the UI and the bandwidth limit keep reacting in time
+const MAX_CHUNK_TIME: Duration = Duration::from_secs(1);
+/// Chunks are grown only while they take less than this
+const GROW_CHUNK_TIME: Duration = Duration::from_millis(750);
+
+/// ## OverwritePolicy
+///
+/// Defines what to do when the target of a transfer already exists
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub enum OverwritePolicy {
+    Overwrite,
+    Skip,
+    SkipSameSize,     // Skip only if the target has the same size as the source
+    OverwriteIfNewer, // Overwrite only if the source is newer than the target
+    Ask,              // Ask the user what to do for each conflict
+}
+
+/// ## PartialFilePolicy
+///
+/// Defines what to do with the target of a transfer which has been aborted while being written
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub enum PartialFilePolicy {
+    Keep,
+    Remove,
+    Mark, // Rename it with the `.partial` extension
+}
+
+/// ## SymlinkPolicy
+///
+/// Defines how the symlinks met while transferring entries are transferred
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub enum SymlinkPolicy {
+    Follow,   // Transfer the target of the link
+    Recreate, // Create a link with the same target on the destination
+    Skip,
+}
+
+/// ## ConflictChoice
+///
+/// Describes what the user chose to do with a file whose target already exists
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub enum ConflictChoice {
+    Overwrite,
+    Skip,
+    Rename, // Save the file with a free name, next to the existing one
+    OverwriteIfNewer,
+}
+
+/// ## IgnoreRules
+///
+/// Glob patterns matched against the name of the entries to transfer.
+/// Patterns ending with `/` match directories only
+#[derive(Clone, std::fmt::Debug, Default)]
+pub struct IgnoreRules {
+    rules: Vec<(WildMatch, bool)>, // Pattern and whether it applies only to directories
+}
+
+/// ## TransferFilters
+///
+/// Include and exclude patterns set by the user for the recursive transfers of a session.
+/// Patterns starting with `+` are include patterns, the others are exclude patterns; as for the ignore rules,
+/// patterns ending with `/` match directories only.
+/// Excluded entries are skipped; if there are include patterns for files (or directories),
+/// only the files (or directories) matching one of them are transferred
+#[derive(Clone, std::fmt::Debug, Default)]
+pub struct TransferFilters {
+    patterns: Vec<String>, // Patterns as set by the user
+    exclude: IgnoreRules,
+    include_files: Vec<WildMatch>,
+    include_dirs: Vec<WildMatch>,
+}
+
+/// ## TransferOptions
+///
+/// Options applied to each file transfer
+#[derive(Clone, std::fmt::Debug)]
+pub struct TransferOptions {
+    pub overwrite: OverwritePolicy,
+    pub preserve_timestamps: bool,
+    pub preserve_permissions: bool,
+    pub atomic_uploads: bool,
+    pub partial_files: PartialFilePolicy, // Applied to the target of aborted transfers
+    pub symlinks: SymlinkPolicy,
+    pub verify_checksums: bool,
+    pub skip_identical: bool, // Skip the files whose target has the same content, compared by hash
+    pub create_remote_dirs: bool, // Create the missing remote directories uploads are sent into
+    pub one_file_system: bool, // Don't descend into directories on other file systems
+    pub inhibit_sleep: bool,  // Keep the system awake while the transfer queue is running
+    pub compress_uploads: bool, // Gzip uploads on the fly; decompressed on the remote host
+    pub preallocate: bool,    // Reserve the space of downloads on disk before writing them
+    pub durable_writes: bool, // Flush downloads to disk before reporting them as done
+    pub use_trash: bool,      // Move deleted entries to the trash, instead of removing them
+    pub trash_dir: String,    // Trash directory on remote hosts; `~` is the home directory
+    pub upload_permissions: Option<Permissions>, // Applied to uploaded files, instead of the source mode
+    pub bandwidth_limit: u64,                    // KiB/s; 0 means unlimited
+    pub bandwidth_schedule: BandwidthSchedule, // Limits applied at given times, in place of the default one
+    pub retry: RetryPolicy,
+    pub ignore: IgnoreRules,
+    pub rules: Vec<TransferRule>, // Per-file options; the first rule matching the file name applies
+    pub hooks: Vec<TransferHook>, // Commands run after each file transferred successfully
+}
+
+/// ## TransferRule
+///
+/// Options applied to the files whose name matches a glob pattern, in place of the default ones
+#[derive(Clone, std::fmt::Debug)]
+pub struct TransferRule {
+    pattern: WildMatch,
+    overwrite: Option<OverwritePolicy>,
+    upload_permissions: Option<Permissions>,
+}
+
+/// ## TransferHook
+///
+/// A command run after a file whose name matches a glob pattern has been transferred successfully,
+/// either on the local or on the remote host
+#[derive(Clone, std::fmt::Debug)]
+pub struct TransferHook {
+    pattern: WildMatch,
+    direction: Option<TransferDirection>, // None to run after both uploads and downloads
+    command: String, // May contain the `{path}`, `{local}`, `{remote}` and `{name}` variables
+    remote: bool,    // Run on the remote host instead of the local one
+}
+
+/// ## RetryPolicy
+///
+/// Defines how many times a failed file transfer or directory operation is retried,
+/// waiting longer before each retry (exponential backoff)
+#[derive(Clone, Copy, Default, PartialEq, std::fmt::Debug)]
+pub struct RetryPolicy {
+    pub retries: u32,      // Attempts after the first failure; 0 disables retries
+    pub backoff: Duration, // Delay before the first retry; doubled at each retry
+}
+
+/// ## TimeWindow
+///
+/// A daily time range, such as `09:00-18:00`; ranges ending before they start span midnight (e.g. `22:00-06:00`)
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub struct TimeWindow {
+    start: u32, // Minutes since midnight
+    end: u32,   // Minutes since midnight; excluded
+}
+
+/// ## BandwidthSchedule
+///
+/// Bandwidth limits applied at given times of the week, such as `mon-fri 09:00-18:00=1024`.
+/// Rules are separated by `;`; the first rule matching the local time applies, the default limit applies otherwise
+#[derive(Clone, Default, PartialEq, std::fmt::Debug)]
+pub struct BandwidthSchedule {
+    rules: Vec<ScheduleRule>,
+}
+
+/// ## ScheduleRule
+///
+/// A rule of the bandwidth schedule: `[DAYS ]HH:MM-HH:MM=KIB`, where days are listed as `mon,wed` or `mon-fri`
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+struct ScheduleRule {
+    days: u8, // One bit per day, from monday (lowest bit)
+    window: TimeWindow,
+    limit: u64, // KiB/s; 0 means unlimited
+}
+
+/// ## Throttle
+///
+/// Keeps the rate of a transfer below a bandwidth limit
+#[derive(std::fmt::Debug)]
+pub struct Throttle {
+    limit: u64, // Bytes per second; 0 means unlimited
+    started: Instant,
+    bytes: u64,
+}
+
+/// ## Throughput
+///
+/// Measures the current rate of a transfer over the samples of the last `window`,
+/// so it follows the changes of speed which the average rate smooths out
+#[derive(std::fmt::Debug)]
+pub struct Throughput {
+    window: Duration,
+    samples: VecDeque<(Instant, u64)>, // Bytes transferred so far, at the instant of the sample
+}
+
+/// ## ChunkSizer
+///
+/// Adapts the size of the chunks a transfer is read and written with to the measured throughput.
+/// The chunk grows as long as larger chunks move data faster (e.g. on links with high latency, where each
+/// chunk waits for a round trip, or on SFTP, where a larger write keeps more requests in flight), and shrinks back
+/// once a larger chunk makes the transfer slower or a single chunk takes too long
+#[derive(std::fmt::Debug)]
+pub struct ChunkSizer {
+    size: usize,
+    max_size: usize, // Lowered once a larger chunk turns out slower than a smaller one
+    last_rate: Option<f64>, // Bytes per second measured with the previous chunk size
+    samples: (u64, Duration, usize), // Bytes, time and chunks measured with the current size
+}
+
+/// ## GzipBuffer
+///
+/// Compresses the data of a transfer in memory, so that the compressed chunks can be written to the transfer stream
+pub struct GzipBuffer {
+    encoder: GzEncoder<Vec<u8>>,
+}
+
+/// ## CancelToken
+///
+/// Flags used to request the interruption or the pause of a transfer; clones share the same flags
+#[derive(Clone, Default, std::fmt::Debug)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+    paused: Arc<AtomicBool>,
+}
+
+/// ## Cancellable
+///
+/// Wraps the stream of a transfer. Once its token has been cancelled, reads and writes fail
+/// with a cancellation error without reaching the inner stream, so the transfer stops within one buffer
+pub struct Cancellable<S> {
+    inner: S,
+    token: CancelToken,
+}
+
+/// ## Cancelled
+///
+/// Error returned by a `Cancellable` stream whose token has been cancelled
+#[derive(std::fmt::Debug)]
+struct Cancelled;
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        TransferOptions {
+            overwrite: OverwritePolicy::Overwrite,
+            preserve_timestamps: false,
+            preserve_permissions: true,
+            atomic_uploads: false,
+            partial_files: PartialFilePolicy::Remove,
+            symlinks: SymlinkPolicy::Follow,
+            verify_checksums: false,
+            skip_identical: false,
+            create_remote_dirs: false,
+            one_file_system: false,
+            inhibit_sleep: false,
+            compress_uploads: false,
+            preallocate: true,
+            durable_writes: false,
+            use_trash: false,
+            trash_dir: String::from(REMOTE_TRASH_DIR),
+            upload_permissions: None,
+            bandwidth_limit: 0,
+            bandwidth_schedule: BandwidthSchedule::default(),
+            retry: RetryPolicy::default(),
+            ignore: IgnoreRules::default(),
+            rules: Vec::new(),
+            hooks: Vec::new(),
+        }
+    }
+}
+
+impl TransferOptions {
+    /// ### overwrite_policy
+    ///
+    /// Get the overwrite policy for the file named `name`
+    pub fn overwrite_policy(&self, name: &str) -> OverwritePolicy {
+        self.get_rule(name)
+            .and_then(|x| x.overwrite)
+            .unwrap_or(self.overwrite)
+    }
+
+    /// ### upload_permissions
+    ///
+    /// Get the permissions to apply to the uploaded file named `name`; None to keep the source mode
+    pub fn upload_permissions(&self, name: &str) -> Option<Permissions> {
+        match self.get_rule(name).and_then(|x| x.upload_permissions) {
+            Some(permissions) => Some(permissions),
+            None => self.upload_permissions,
+        }
+    }
+
+    /// ### hooks
+    ///
+    /// Get the hooks to run after the file named `name` has been transferred in `direction`, in the order they're defined
+    pub fn hooks<'a>(
+        &'a self,
+        direction: TransferDirection,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a TransferHook> + 'a {
+        self.hooks
+            .iter()
+            .filter(move |x| x.matches(direction, name))
+    }
+
+    /// ### current_bandwidth_limit
+    ///
+    /// Get the bandwidth limit applied now, in KiB/s: the limit of the schedule rule matching the local time,
+    /// or the default limit if none matches
+    pub fn current_bandwidth_limit(&self) -> u64 {
+        self.bandwidth_schedule
+            .limit_at(Local::now().naive_local())
+            .unwrap_or(self.bandwidth_limit)
+    }
+
+    /// ### upload_path
+    ///
+    /// Get the path `remote` is written to while being uploaded.
+    /// With atomic uploads, that's a hidden temporary file (`.<name>.part`), renamed to `remote` once complete
+    pub fn upload_path(&self, remote: &Path) -> PathBuf {
+        match self.atomic_uploads {
+            true => remote.with_file_name(format!(
+                ".{}.part",
+                remote
+                    .file_name()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            )),
+            false => remote.to_path_buf(),
+        }
+    }
+
+    /// ### compresses
+    ///
+    /// Returns whether the file named `name` must be compressed while being uploaded.
+    /// Files which are already compressed (archives, images, videos...) are sent as they are
+    pub fn compresses(&self, name: &str) -> bool {
+        let extension: String = Path::new(name)
+            .extension()
+            .map(|x| x.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        self.compress_uploads && !COMPRESSED_EXTENSIONS.contains(&extension.as_str())
+    }
+
+    /// ### compressed_path
+    ///
+    /// Get the path the compressed data of `remote` is uploaded to, before being decompressed (`.<name>.gz`)
+    pub fn compressed_path(remote: &Path) -> PathBuf {
+        remote.with_file_name(format!(
+            ".{}.gz",
+            remote
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_default()
+        ))
+    }
+
+    /// ### applies_permissions
+    ///
+    /// Returns whether permissions are applied to any of the uploaded files
+    pub fn applies_permissions(&self) -> bool {
+        self.upload_permissions.is_some()
+            || self.rules.iter().any(|x| x.upload_permissions.is_some())
+    }
+
+    /// ### get_rule
+    ///
+    /// Get the first rule matching `name`
+    fn get_rule(&self, name: &str) -> Option<&TransferRule> {
+        self.rules.iter().find(|x| x.pattern.is_match(name))
+    }
+}
+
+impl TransferRule {
+    /// ### new
+    ///
+    /// Instantiates a new `TransferRule` for the files matching `pattern`
+    pub fn new(
+        pattern: &str,
+        overwrite: Option<OverwritePolicy>,
+        upload_permissions: Option<Permissions>,
+    ) -> Self {
+        TransferRule {
+            pattern: WildMatch::new(pattern.trim()),
+            overwrite,
+            upload_permissions,
+        }
+    }
+}
+
+impl TransferHook {
+    /// ### new
+    ///
+    /// Instantiates a new `TransferHook` running `command` after the files matching `pattern` have been transferred
+    /// in `direction` (None for both)
+    pub fn new(
+        pattern: &str,
+        direction: Option<TransferDirection>,
+        command: &str,
+        remote: bool,
+    ) -> Self {
+        TransferHook {
+            pattern: WildMatch::new(pattern.trim()),
+            direction,
+            command: command.trim().to_string(),
+            remote,
+        }
+    }
+
+    /// ### is_remote
+    ///
+    /// Returns whether the command runs on the remote host
+    pub fn is_remote(&self) -> bool {
+        self.remote
+    }
+
+    /// ### matches
+    ///
+    /// Returns whether the hook applies to the file named `name`, transferred in `direction`
+    pub fn matches(&self, direction: TransferDirection, name: &str) -> bool {
+        self.direction.map(|x| x == direction).unwrap_or(true) && self.pattern.is_match(name)
+    }
+
+    /// ### command
+    ///
+    /// Get the command to run for the file transferred between `local` and `remote`.
+    /// Variables are replaced with the quoted paths: `{local}`, `{remote}`, `{path}` (the path on the host
+    /// the command runs on) and `{name}` (the file name).
+    /// Variables are expanded in a single pass, so the paths are never expanded in turn
+    pub fn command(&self, local: &Path, remote: &Path) -> String {
+        let path: &Path = match self.remote {
+            true => remote,
+            false => local,
+        };
+        let name: String = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let vars: [(&str, String); 4] = [
+            ("{path}", shell::quote_path(path)),
+            ("{local}", shell::quote_path(local)),
+            ("{remote}", shell::quote_path(remote)),
+            ("{name}", shell::quote(name.as_str())),
+        ];
+        let mut command: String = String::with_capacity(self.command.len());
+        let mut rest: &str = self.command.as_str();
+        while let Some(start) = rest.find('{') {
+            command.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match vars.iter().find(|(var, _)| rest.starts_with(var)) {
+                Some((var, value)) => {
+                    command.push_str(value.as_str());
+                    rest = &rest[var.len()..];
+                }
+                None => {
+                    command.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        command.push_str(rest);
+        command
+    }
+}
+
+impl OverwritePolicy {
+    /// ### skips
+    ///
+    /// Returns whether a file must be skipped, given the size and the modification time of the source
+    /// and of the target (None if it doesn't exist).
+    /// `Ask` never skips a file: conflicts must be resolved by the user before
+    pub fn skips(&self, source: (usize, SystemTime), target: Option<(usize, SystemTime)>) -> bool {
+        match (self, target) {
+            (_, None) => false,
+            (OverwritePolicy::Overwrite, _) | (OverwritePolicy::Ask, _) => false,
+            (OverwritePolicy::Skip, Some(_)) => true,
+            (OverwritePolicy::SkipSameSize, Some((size, _))) => size == source.0,
+            (OverwritePolicy::OverwriteIfNewer, Some((_, mtime))) => source.1 <= mtime,
+        }
+    }
+}
+
+impl ConflictChoice {
+    /// ### renamed
+    ///
+    /// Returns the `n`-th alternative name for `path`, used when the user chooses to rename the file
+    /// (e.g. `report.txt` becomes `report (1).txt`)
+    pub fn renamed(path: &Path, n: usize) -> PathBuf {
+        let name: String = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // Keep extension; hidden files without extension are renamed as a whole
+        let name: String = match name.rfind('.') {
+            Some(idx) if idx > 0 => format!("{} ({}){}", &name[..idx], n, &name[idx..]),
+            _ => format!("{} ({})", name, n),
+        };
+        path.with_file_name(name)
+    }
+}
+
+impl PartialFilePolicy {
+    /// ### marked
+    ///
+    /// Returns the path the partial file at `path` is renamed to by `PartialFilePolicy::Mark`
+    pub fn marked(path: &Path) -> PathBuf {
+        let name: String = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        path.with_file_name(format!("{}.partial", name))
+    }
+
+    /// ### is_partial
+    ///
+    /// Returns whether the file named `name` has been left behind by an interrupted transfer:
+    /// either the temporary file of an atomic upload, or a partial file marked by `PartialFilePolicy::Mark`
+    pub fn is_partial(name: &str) -> bool {
+        let temporary: bool = name
+            .strip_prefix('.')
+            .and_then(|x| x.strip_suffix(".part"))
+            .map(|x| !x.is_empty())
+            .unwrap_or(false);
+        let marked: bool = name
+            .strip_suffix(".partial")
+            .map(|x| !x.is_empty())
+            .unwrap_or(false);
+        temporary || marked
+    }
+}
+
+impl SymlinkPolicy {
+    /// ### follows
+    ///
+    /// Returns whether `entry` is transferred as a regular file or directory with this policy:
+    /// that's the case of the entries which are not symlinks, and of the links which can be followed.
+    /// Links to one of their parent directories are never followed, since they would be walked forever
+    pub fn follows(&self, entry: &FsEntry) -> bool {
+        !entry.is_symlink()
+            || (*self == SymlinkPolicy::Follow
+                && !entry.is_broken_link()
+                && !entry
+                    .get_abs_path()
+                    .starts_with(entry.get_realfile().get_abs_path()))
+    }
+}
+
+impl IgnoreRules {
+    /// ### new
+    ///
+    /// Instantiates a new `IgnoreRules` from patterns. Empty patterns are discarded
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        IgnoreRules {
+            rules: patterns
+                .iter()
+                .map(|x| x.as_ref().trim())
+                .filter(|x| !x.is_empty())
+                .map(|x| match x.strip_suffix('/') {
+                    Some(dir) => (WildMatch::new(dir), true),
+                    None => (WildMatch::new(x), false),
+                })
+                .collect(),
+        }
+    }
+
+    /// ### is_ignored
+    ///
+    /// Returns whether `entry` must be skipped
+    pub fn is_ignored(&self, entry: &FsEntry) -> bool {
+        let name: &str = entry.get_name();
+        let is_dir: bool = entry.is_dir();
+        self.rules
+            .iter()
+            .any(|(pattern, dir_only)| (is_dir || !dir_only) && pattern.is_match(name))
+    }
+}
+
+impl TransferFilters {
+    /// ### new
+    ///
+    /// Instantiates a new `TransferFilters` from patterns. Empty patterns are discarded
+    pub fn new<S: AsRef<str>>(patterns: &[S]) -> Self {
+        let patterns: Vec<String> = patterns
+            .iter()
+            .map(|x| x.as_ref().trim())
+            .filter(|x| !x.is_empty() && *x != "+")
+            .map(|x| x.to_string())
+            .collect();
+        let mut include_files: Vec<WildMatch> = Vec::new();
+        let mut include_dirs: Vec<WildMatch> = Vec::new();
+        for pattern in patterns.iter().filter_map(|x| x.strip_prefix('+')) {
+            match pattern.strip_suffix('/') {
+                Some(dir) => include_dirs.push(WildMatch::new(dir)),
+                None => include_files.push(WildMatch::new(pattern)),
+            }
+        }
+        let exclude: Vec<&String> = patterns.iter().filter(|x| !x.starts_with('+')).collect();
+        TransferFilters {
+            exclude: IgnoreRules::new(exclude.as_slice()),
+            include_files,
+            include_dirs,
+            patterns,
+        }
+    }
+
+    /// ### patterns
+    ///
+    /// Get the patterns the filters have been made of
+    pub fn patterns(&self) -> &[String] {
+        self.patterns.as_slice()
+    }
+
+    /// ### is_empty
+    ///
+    /// Returns whether there are no filters
+    pub fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    /// ### is_filtered
+    ///
+    /// Returns whether `entry` must be skipped
+    pub fn is_filtered(&self, entry: &FsEntry) -> bool {
+        if self.exclude.is_ignored(entry) {
+            return true;
+        }
+        let include: &[WildMatch] = match entry.is_dir() {
+            true => self.include_dirs.as_slice(),
+            false => self.include_files.as_slice(),
+        };
+        !include.is_empty() && !include.iter().any(|x| x.is_match(entry.get_name()))
+    }
+}
+
+impl RetryPolicy {
+    /// Longest delay between two attempts
+    const MAX_DELAY: Duration = Duration::from_secs(60);
+
+    /// ### new
+    ///
+    /// Instantiates a new `RetryPolicy`
+    pub fn new(retries: u32, backoff: Duration) -> Self {
+        RetryPolicy { retries, backoff }
+    }
+
+    /// ### delay
+    ///
+    /// Get how long to wait before the retry number `retry` (starting from 1)
+    pub fn delay(&self, retry: u32) -> Duration {
+        self.backoff
+            .saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1)))
+            .min(Self::MAX_DELAY)
+    }
+}
+
+impl TimeWindow {
+    /// ### contains
+    ///
+    /// Returns whether `time` is within the window
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        let minute: u32 = time.hour() * 60 + time.minute();
+        match self.start < self.end {
+            true => minute >= self.start && minute < self.end,
+            false => minute >= self.start || minute < self.end,
+        }
+    }
+
+    /// ### fmt_end
+    ///
+    /// Format the time the window ends at, as `HH:MM`
+    pub fn fmt_end(&self) -> String {
+        Self::fmt_minutes(self.end)
+    }
+
+    /// ### parse_minutes
+    ///
+    /// Parse `HH:MM` into minutes since midnight; `24:00` is accepted as the end of the day
+    fn parse_minutes(s: &str) -> Option<u32> {
+        let (hours, minutes): (&str, &str) = s.trim().split_once(':')?;
+        let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+        match (hours, minutes) {
+            (24, 0) => Some(1440),
+            (h, m) if h < 24 && m < 60 => Some(h * 60 + m),
+            _ => None,
+        }
+    }
+
+    /// ### fmt_minutes
+    ///
+    /// Format minutes since midnight as `HH:MM`
+    fn fmt_minutes(minutes: u32) -> String {
+        format!("{:02}:{:02}", minutes / 60, minutes % 60)
+    }
+}
+
+impl BandwidthSchedule {
+    /// ### is_empty
+    ///
+    /// Returns whether the schedule has no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// ### limit_at
+    ///
+    /// Get the limit, in KiB/s, of the first rule matching `at`; None if no rule matches
+    pub fn limit_at(&self, at: NaiveDateTime) -> Option<u64> {
+        let day: u8 = 1 << at.weekday().num_days_from_monday();
+        self.rules
+            .iter()
+            .find(|x| x.days & day != 0 && x.window.contains(at.time()))
+            .map(|x| x.limit)
+    }
+}
+
+impl ScheduleRule {
+    /// Days as written in rules
+    const DAYS: [&'static str; 7] = ["mon", "tue", "wed", "thu", "fri", "sat", "sun"];
+
+    /// ### parse_days
+    ///
+    /// Parse a comma separated list of days and day ranges (e.g. `mon-fri,sun`); ranges may wrap (e.g. `fri-mon`)
+    fn parse_days(s: &str) -> Option<u8> {
+        let day = |x: &str| -> Option<u32> {
+            Weekday::from_str(x.trim())
+                .ok()
+                .map(|x| x.num_days_from_monday())
+        };
+        let mut days: u8 = 0;
+        for item in s.split(',') {
+            let (first, last): (u32, u32) = match item.split_once('-') {
+                Some((first, last)) => (day(first)?, day(last)?),
+                None => (day(item)?, day(item)?),
+            };
+            let mut n: u32 = first;
+            loop {
+                days |= 1 << n;
+                if n == last {
+                    break;
+                }
+                n = (n + 1) % 7;
+            }
+        }
+        Some(days)
+    }
+
+    /// ### fmt_days
+    ///
+    /// Format days as a comma separated list of day ranges
+    fn fmt_days(days: u8) -> String {
+        let mut ranges: Vec<String> = Vec::new();
+        let mut n: usize = 0;
+        while n < 7 {
+            if days & (1 << n) == 0 {
+                n += 1;
+                continue;
+            }
+            let first: usize = n;
+            while n + 1 < 7 && days & (1 << (n + 1)) != 0 {
+                n += 1;
+            }
+            ranges.push(match n == first {
+                true => Self::DAYS[first].to_string(),
+                false => format!("{}-{}", Self::DAYS[first], Self::DAYS[n]),
+            });
+            n += 1;
+        }
+        ranges.join(",")
+    }
+}
+
+impl Throttle {
+    /// ### new
+    ///
+    /// Instantiates a new `Throttle` capping the transfer to `kib` KiB/s; 0 means unlimited
+    pub fn new(kib: u64) -> Self {
+        Throttle {
+            limit: kib.saturating_mul(1024),
+            started: Instant::now(),
+            bytes: 0,
+        }
+    }
+
+    /// ### consume
+    ///
+    /// Report `bytes` as transferred
+    pub fn consume(&mut self, bytes: usize) {
+        self.bytes += bytes as u64;
+    }
+
+    /// ### delay
+    ///
+    /// Returns how long the transfer must wait before sending more data; None if it can go on
+    pub fn delay(&self) -> Option<Duration> {
+        if self.limit == 0 {
+            return None;
+        }
+        let expected: Duration = Duration::from_secs_f64(self.bytes as f64 / self.limit as f64);
+        expected
+            .checked_sub(self.started.elapsed())
+            .filter(|x| !x.is_zero())
+    }
+
+    /// ### skip
+    ///
+    /// Don't count `pause` as transfer time, so the transfer doesn't burst once resumed
+    pub fn skip(&mut self, pause: Duration) {
+        self.started += pause;
+    }
+
+    /// ### set_limit
+    ///
+    /// Cap the transfer to `kib` KiB/s from now on; 0 means unlimited
+    pub fn set_limit(&mut self, kib: u64) {
+        let limit: u64 = kib.saturating_mul(1024);
+        if limit != self.limit {
+            self.limit = limit;
+            self.started = Instant::now();
+            self.bytes = 0;
+        }
+    }
+}
+
+impl Default for ChunkSizer {
+    fn default() -> Self {
+        ChunkSizer {
+            size: CHUNK_SIZE,
+            max_size: MAX_CHUNK_SIZE,
+            last_rate: None,
+            samples: (0, Duration::ZERO, 0),
+        }
+    }
+}
+
+impl ChunkSizer {
+    /// ### size
+    ///
+    /// Get the size of the next chunk
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// ### record
+    ///
+    /// Report a chunk of `bytes` as read and written in `elapsed`.
+    /// The chunk size is adjusted once enough chunks have been measured with the current size
+    pub fn record(&mut self, bytes: usize, elapsed: Duration) {
+        // A single slow chunk is enough to shrink
+        if elapsed > MAX_CHUNK_TIME {
+            self.resize(self.size / 2, None);
+            return;
+        }
+        self.samples.0 += bytes as u64;
+        self.samples.1 += elapsed;
+        self.samples.2 += 1;
+        let (bytes, elapsed, chunks): (u64, Duration, usize) = self.samples;
+        if elapsed < Duration::from_millis(200)
+            && (chunks < 4 || elapsed < Duration::from_millis(50))
+        {
+            return;
+        }
+        let rate: f64 = bytes as f64 / elapsed.as_secs_f64();
+        match self.last_rate {
+            // Larger chunks made the transfer slower: go back, and don't try them again
+            Some(last) if rate < last * 0.9 => {
+                self.max_size = self.size / 2;
+                self.resize(self.size / 2, Some(last));
+            }
+            // Grow, as long as the chunks stay short enough
+            _ if elapsed < GROW_CHUNK_TIME * chunks as u32 => {
+                self.resize(self.size * 2, Some(rate))
+            }
+            _ => self.resize(self.size, Some(rate)),
+        }
+    }
+
+    /// ### resize
+    ///
+    /// Set the chunk size, within the bounds, and start measuring it from scratch
+    fn resize(&mut self, size: usize, rate: Option<f64>) {
+        self.size = size.clamp(MIN_CHUNK_SIZE, self.max_size.max(MIN_CHUNK_SIZE));
+        self.last_rate = rate;
+        self.samples = (0, Duration::ZERO, 0);
+    }
+}
+
+impl GzipBuffer {
+    /// ### new
+    ///
+    /// Instantiates a new, empty, `GzipBuffer`
+    pub fn new() -> Self {
+        GzipBuffer {
+            encoder: GzEncoder::new(Vec::new(), Compression::default()),
+        }
+    }
+
+    /// ### compress
+    ///
+    /// Compress `data`; returns the compressed bytes available so far, which may be none
+    pub fn compress(&mut self, data: &[u8]) -> io::Result<Vec<u8>> {
+        self.encoder.write_all(data)?;
+        Ok(std::mem::take(self.encoder.get_mut()))
+    }
+
+    /// ### finish
+    ///
+    /// Complete the compression; returns the remaining compressed bytes, gzip trailer included
+    pub fn finish(self) -> io::Result<Vec<u8>> {
+        self.encoder.finish()
+    }
+
+    /// ### compress_stream
+    ///
+    /// Compress all the data read from `reader` into `writer`, passing the uncompressed chunks to `inspect`.
+    /// Returns the amount of bytes read and the amount of compressed bytes written
+    pub fn compress_stream(
+        reader: &mut dyn Read,
+        writer: &mut dyn Write,
+        mut inspect: impl FnMut(&[u8]),
+    ) -> io::Result<(u64, u64)> {
+        let mut gzip: GzipBuffer = GzipBuffer::new();
+        let mut buffer: Vec<u8> = vec![0; 65536];
+        let (mut bytes_read, mut bytes_written): (u64, u64) = (0, 0);
+        loop {
+            let chunk: usize = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(chunk) => chunk,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            inspect(&buffer[..chunk]);
+            let data: Vec<u8> = gzip.compress(&buffer[..chunk])?;
+            writer.write_all(data.as_slice())?;
+            bytes_read += chunk as u64;
+            bytes_written += data.len() as u64;
+        }
+        let data: Vec<u8> = gzip.finish()?;
+        writer.write_all(data.as_slice())?;
+        Ok((bytes_read, bytes_written + data.len() as u64))
+    }
+}
+
+impl Default for GzipBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Throughput {
+    /// ### new
+    ///
+    /// Instantiates a new `Throughput` measuring the rate over `window`
+    pub fn new(window: Duration) -> Self {
+        Throughput {
+            window,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// ### record
+    ///
+    /// Report `bytes` as the amount of bytes transferred so far
+    pub fn record(&mut self, bytes: u64) {
+        self.record_at(bytes, Instant::now());
+    }
+
+    /// ### record_at
+    ///
+    /// Report `bytes` as the amount of bytes transferred at `now`; older samples than the window are dropped,
+    /// but the last of them is kept as starting point
+    fn record_at(&mut self, bytes: u64, now: Instant) {
+        while self.samples.len() > 1 && now.duration_since(self.samples[1].0) >= self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, bytes));
+    }
+
+    /// ### bytes_per_second
+    ///
+    /// Get the current rate; None until the samples span at least 100ms
+    pub fn bytes_per_second(&self) -> Option<u64> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        let elapsed: Duration = last.0.duration_since(first.0);
+        match elapsed >= Duration::from_millis(100) {
+            true => Some((last.1.saturating_sub(first.1) as f64 / elapsed.as_secs_f64()) as u64),
+            false => None,
+        }
+    }
+
+    /// ### reset
+    ///
+    /// Forget the samples; e.g. once resumed, the time spent in pause doesn't count
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+
+    /// ### eta
+    ///
+    /// Get the time needed to transfer `remaining` bytes at `bytes_per_second`; None if the rate is 0
+    pub fn eta(remaining: u64, bytes_per_second: u64) -> Option<Duration> {
+        match bytes_per_second {
+            0 => None,
+            rate => Some(Duration::from_secs(remaining / rate)),
+        }
+    }
+}
+
+impl CancelToken {
+    /// ### cancel
+    ///
+    /// Request the interruption of the transfers using this token
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// ### is_cancelled
+    ///
+    /// Returns whether the interruption has been requested
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// ### is_cancellation
+    ///
+    /// Returns whether `err` has been returned because the transfer has been cancelled
+    pub fn is_cancellation(err: &io::Error) -> bool {
+        err.get_ref().map(|x| x.is::<Cancelled>()).unwrap_or(false)
+    }
+
+    /// ### pause
+    ///
+    /// Request the transfers using this token to stop reading and writing, until resumed
+    pub fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// ### resume
+    ///
+    /// Let the paused transfers go on
+    pub fn resume(&self) {
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// ### is_paused
+    ///
+    /// Returns whether the pause has been requested
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    /// ### wait_resumed
+    ///
+    /// Block the calling thread while the token is paused, unless it gets cancelled.
+    /// Returns how long the thread has been blocked
+    pub fn wait_resumed(&self) -> Duration {
+        let started: Instant = Instant::now();
+        while self.is_paused() && !self.is_cancelled() {
+            std::thread::sleep(Duration::from_millis(50));
+        }
+        started.elapsed()
+    }
+
+    /// ### reset
+    ///
+    /// Clear the cancellation and pause requests, so the token can be used for a new transfer
+    pub fn reset(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+        self.paused.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<S> Cancellable<S> {
+    /// ### new
+    ///
+    /// Wrap `inner`, which is interrupted when `token` is cancelled
+    pub fn new(inner: S, token: &CancelToken) -> Self {
+        Cancellable {
+            inner,
+            token: token.clone(),
+        }
+    }
+
+    /// ### into_inner
+    ///
+    /// Get back the inner stream, e.g. to close it once the transfer has been interrupted
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+
+    fn check(&self) -> io::Result<()> {
+        match self.token.is_cancelled() {
+            true => Err(io::Error::other(Cancelled)),
+            false => Ok(()),
+        }
+    }
+}
+
+impl<R: Read> Read for Cancellable<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.check()?;
+        self.inner.read(buf)
+    }
+}
+
+impl<W: Write> Write for Cancellable<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.check()?;
+        self.inner.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.check()?;
+        self.inner.flush()
+    }
+}
+
+impl std::fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "transfer cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+impl std::fmt::Display for OverwritePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let policy: &str = match self {
+            OverwritePolicy::Overwrite => "overwrite",
+            OverwritePolicy::Skip => "skip",
+            OverwritePolicy::SkipSameSize => "skip-same-size",
+            OverwritePolicy::OverwriteIfNewer => "newer",
+            OverwritePolicy::Ask => "ask",
+        };
+        write!(f, "{}", policy)
+    }
+}
+
+impl std::fmt::Display for PartialFilePolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let policy: &str = match self {
+            PartialFilePolicy::Keep => "keep",
+            PartialFilePolicy::Remove => "remove",
+            PartialFilePolicy::Mark => "mark",
+        };
+        write!(f, "{}", policy)
+    }
+}
+
+impl std::fmt::Display for SymlinkPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let policy: &str = match self {
+            SymlinkPolicy::Follow => "follow",
+            SymlinkPolicy::Recreate => "recreate",
+            SymlinkPolicy::Skip => "skip",
+        };
+        write!(f, "{}", policy)
+    }
+}
+
+impl FromStr for PartialFilePolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "keep" => Ok(PartialFilePolicy::Keep),
+            "remove" => Ok(PartialFilePolicy::Remove),
+            "mark" => Ok(PartialFilePolicy::Mark),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for SymlinkPolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "follow" => Ok(SymlinkPolicy::Follow),
+            "recreate" => Ok(SymlinkPolicy::Recreate),
+            "skip" => Ok(SymlinkPolicy::Skip),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for OverwritePolicy {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "overwrite" => Ok(OverwritePolicy::Overwrite),
+            "skip" => Ok(OverwritePolicy::Skip),
+            "skip-same-size" => Ok(OverwritePolicy::SkipSameSize),
+            "newer" => Ok(OverwritePolicy::OverwriteIfNewer),
+            "ask" => Ok(OverwritePolicy::Ask),
+            _ => Err(()),
+        }
+    }
+}
+
+impl FromStr for TimeWindow {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || format!("\"{}\" is not a valid time range (HH:MM-HH:MM)", s);
+        let (start, end): (&str, &str) = s.split_once('-').ok_or_else(err)?;
+        let start: u32 = Self::parse_minutes(start)
+            .filter(|x| *x < 1440)
+            .ok_or_else(err)?;
+        let end: u32 = Self::parse_minutes(end).ok_or_else(err)? % 1440;
+        match start == end {
+            true => Err(format!("\"{}\" is an empty time range", s)),
+            false => Ok(TimeWindow { start, end }),
+        }
+    }
+}
+
+impl FromStr for BandwidthSchedule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rules: Vec<ScheduleRule> = s
+            .split(';')
+            .filter(|x| !x.trim().is_empty())
+            .map(ScheduleRule::from_str)
+            .collect::<Result<Vec<ScheduleRule>, String>>()?;
+        Ok(BandwidthSchedule { rules })
+    }
+}
+
+impl std::fmt::Display for BandwidthSchedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let rules: Vec<String> = self.rules.iter().map(|x| x.to_string()).collect();
+        write!(f, "{}", rules.join("; "))
+    }
+}
+
+impl FromStr for ScheduleRule {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || {
+            format!(
+                "\"{}\" is not a valid schedule rule ([DAYS ]HH:MM-HH:MM=KIB)",
+                s.trim()
+            )
+        };
+        let (when, limit): (&str, &str) = s.split_once('=').ok_or_else(err)?;
+        let limit: u64 = limit.trim().parse().map_err(|_| err())?;
+        let when: &str = when.trim();
+        // Days are optional; without them the rule applies every day
+        let with_days: bool = when.starts_with(|x: char| x.is_ascii_alphabetic());
+        let (days, window): (u8, &str) = match with_days {
+            true => {
+                let (days, window): (&str, &str) =
+                    when.split_once(char::is_whitespace).ok_or_else(err)?;
+                (Self::parse_days(days).ok_or_else(err)?, window)
+            }
+            false => (0x7f, when),
+        };
+        Ok(ScheduleRule {
+            days,
+            window: TimeWindow::from_str(window)?,
+            limit,
+        })
+    }
+}
+
+impl std::fmt::Display for ScheduleRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.days {
+            0x7f => write!(f, "{}={}", self.window, self.limit),
+            days => write!(f, "{} {}={}", Self::fmt_days(days), self.window, self.limit),
+        }
+    }
+}
+
+impl std::fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            Self::fmt_minutes(self.start),
+            Self::fmt_minutes(self.end)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::fs::{FsDirectory, FsFile};
+
+    use chrono::NaiveDate;
+    use std::path::PathBuf;
+    use std::time::SystemTime;
+
+    #[test]
+    fn test_fs_transfer_overwrite_policy() {
+        assert_eq!(
+            OverwritePolicy::from_str("Skip").ok().unwrap(),
+            OverwritePolicy::Skip
+        );
+        assert_eq!(
+            OverwritePolicy::from_str("overwrite").ok().unwrap(),
+            OverwritePolicy::Overwrite
+        );
+        assert_eq!(
+            OverwritePolicy::from_str("skip-same-size").ok().unwrap(),
+            OverwritePolicy::SkipSameSize
+        );
+        assert_eq!(
+            OverwritePolicy::from_str("newer").ok().unwrap(),
+            OverwritePolicy::OverwriteIfNewer
+        );
+        assert_eq!(
+            OverwritePolicy::from_str("ask").ok().unwrap(),
+            OverwritePolicy::Ask
+        );
+        assert!(OverwritePolicy::from_str("prompt").is_err());
+        assert_eq!(OverwritePolicy::Skip.to_string().as_str(), "skip");
+        assert_eq!(OverwritePolicy::Overwrite.to_string().as_str(), "overwrite");
+        assert_eq!(
+            OverwritePolicy::SkipSameSize.to_string().as_str(),
+            "skip-same-size"
+        );
+        assert_eq!(
+            OverwritePolicy::OverwriteIfNewer.to_string().as_str(),
+            "newer"
+        );
+        assert_eq!(OverwritePolicy::Ask.to_string().as_str(), "ask");
+        // Skip
+        let t_old: SystemTime = SystemTime::UNIX_EPOCH;
+        let t_new: SystemTime = SystemTime::now();
+        assert!(!OverwritePolicy::Overwrite.skips((64, t_new), Some((64, t_new))));
+        assert!(OverwritePolicy::Skip.skips((64, t_new), Some((32, t_new))));
+        assert!(!OverwritePolicy::Skip.skips((64, t_new), None));
+        assert!(OverwritePolicy::SkipSameSize.skips((64, t_new), Some((64, t_new))));
+        assert!(!OverwritePolicy::SkipSameSize.skips((64, t_new), Some((32, t_new))));
+        assert!(!OverwritePolicy::SkipSameSize.skips((64, t_new), None));
+        assert!(!OverwritePolicy::OverwriteIfNewer.skips((64, t_new), Some((64, t_old))));
+        assert!(OverwritePolicy::OverwriteIfNewer.skips((64, t_old), Some((64, t_new))));
+        assert!(OverwritePolicy::OverwriteIfNewer.skips((64, t_new), Some((64, t_new))));
+        assert!(!OverwritePolicy::OverwriteIfNewer.skips((64, t_old), None));
+        assert!(!OverwritePolicy::Ask.skips((64, t_new), Some((64, t_new))));
+    }
+
+    #[test]
+    fn test_fs_transfer_partial_file_policy() {
+        assert_eq!(
+            PartialFilePolicy::from_str("Keep").ok().unwrap(),
+            PartialFilePolicy::Keep
+        );
+        assert_eq!(
+            PartialFilePolicy::from_str("remove").ok().unwrap(),
+            PartialFilePolicy::Remove
+        );
+        assert_eq!(
+            PartialFilePolicy::from_str("mark").ok().unwrap(),
+            PartialFilePolicy::Mark
+        );
+        assert!(PartialFilePolicy::from_str("delete").is_err());
+        assert_eq!(PartialFilePolicy::Keep.to_string().as_str(), "keep");
+        assert_eq!(PartialFilePolicy::Remove.to_string().as_str(), "remove");
+        assert_eq!(PartialFilePolicy::Mark.to_string().as_str(), "mark");
+        assert_eq!(
+            TransferOptions::default().partial_files,
+            PartialFilePolicy::Remove
+        );
+        assert_eq!(
+            PartialFilePolicy::marked(Path::new("/tmp/backup.tar.gz")),
+            PathBuf::from("/tmp/backup.tar.gz.partial")
+        );
+        assert!(PartialFilePolicy::is_partial("backup.tar.gz.partial"));
+        assert!(PartialFilePolicy::is_partial(".index.html.part"));
+        assert!(!PartialFilePolicy::is_partial("index.html.part"));
+        assert!(!PartialFilePolicy::is_partial("..part"));
+        assert!(!PartialFilePolicy::is_partial(".partial"));
+        assert!(!PartialFilePolicy::is_partial("backup.tar.gz"));
+    }
+
+    #[test]
+    fn test_fs_transfer_symlink_policy() {
+        assert_eq!(
+            SymlinkPolicy::from_str("Follow").ok().unwrap(),
+            SymlinkPolicy::Follow
+        );
+        assert_eq!(
+            SymlinkPolicy::from_str("recreate").ok().unwrap(),
+            SymlinkPolicy::Recreate
+        );
+        assert_eq!(
+            SymlinkPolicy::from_str("skip").ok().unwrap(),
+            SymlinkPolicy::Skip
+        );
+        assert!(SymlinkPolicy::from_str("copy").is_err());
+        assert_eq!(SymlinkPolicy::Recreate.to_string().as_str(), "recreate");
+        assert_eq!(TransferOptions::default().symlinks, SymlinkPolicy::Follow);
+        // Regular entries are always transferred
+        let file: FsEntry = make_fs_entry("omar.txt", false);
+        assert!(SymlinkPolicy::Skip.follows(&file));
+        assert!(SymlinkPolicy::Recreate.follows(&file));
+        // Links are followed only by follow, unless broken
+        let mut link: FsEntry = make_fs_entry("link.txt", false);
+        if let FsEntry::File(f) = &mut link {
+            f.symlink = Some(Box::new(file.clone()));
+        }
+        assert!(SymlinkPolicy::Follow.follows(&link));
+        assert!(!SymlinkPolicy::Recreate.follows(&link));
+        assert!(!SymlinkPolicy::Skip.follows(&link));
+        // Links to a parent directory
+        let mut parent: FsEntry = make_fs_entry("/home/omar", true);
+        if let FsEntry::Directory(dir) = &mut parent {
+            dir.abs_path = PathBuf::from("/home/omar/backup/home");
+            dir.symlink = Some(Box::new(make_fs_entry("/home/omar", true)));
+        }
+        assert!(!SymlinkPolicy::Follow.follows(&parent));
+        if let FsEntry::File(f) = &mut link {
+            f.symlink = None;
+            f.broken_link = Some(PathBuf::from("/tmp/gone.txt"));
+        }
+        assert!(!SymlinkPolicy::Follow.follows(&link));
+    }
+
+    #[test]
+    fn test_fs_transfer_upload_path() {
+        let mut opts: TransferOptions = TransferOptions::default();
+        assert_eq!(
+            opts.upload_path(Path::new("/srv/www/index.html")),
+            PathBuf::from("/srv/www/index.html")
+        );
+        opts.atomic_uploads = true;
+        assert_eq!(
+            opts.upload_path(Path::new("/srv/www/index.html")),
+            PathBuf::from("/srv/www/.index.html.part")
+        );
+    }
+
+    #[test]
+    fn test_fs_transfer_time_window() {
+        let time = |h: u32, m: u32| NaiveTime::from_hms(h, m, 0);
+        let office: TimeWindow = TimeWindow::from_str("09:00-18:00").unwrap();
+        assert_eq!(office.to_string(), "09:00-18:00");
+        assert_eq!(office.fmt_end(), "18:00");
+        assert!(!office.contains(time(8, 59)));
+        assert!(office.contains(time(9, 0)));
+        assert!(office.contains(time(17, 59)));
+        assert!(!office.contains(time(18, 0)));
+        // Across midnight
+        let night: TimeWindow = TimeWindow::from_str(" 22:30 - 06:00 ").unwrap();
+        assert!(night.contains(time(23, 0)));
+        assert!(night.contains(time(0, 0)));
+        assert!(night.contains(time(5, 59)));
+        assert!(!night.contains(time(6, 0)));
+        assert!(!night.contains(time(22, 29)));
+        // Until the end of the day
+        let evening: TimeWindow = TimeWindow::from_str("20:00-24:00").unwrap();
+        assert_eq!(evening.fmt_end(), "00:00");
+        assert!(evening.contains(time(23, 59)));
+        assert!(!evening.contains(time(0, 0)));
+        // Bad windows
+        assert!(TimeWindow::from_str("09:00").is_err());
+        assert!(TimeWindow::from_str("9-18").is_err());
+        assert!(TimeWindow::from_str("09:00-25:00").is_err());
+        assert!(TimeWindow::from_str("09:60-18:00").is_err());
+        assert!(TimeWindow::from_str("24:00-06:00").is_err());
+        assert!(TimeWindow::from_str("09:00-09:00").is_err());
+    }
+
+    #[test]
+    fn test_fs_transfer_bandwidth_schedule() {
+        // 2021-06-07 is a monday
+        let at = |d: u32, h: u32, m: u32| NaiveDate::from_ymd(2021, 6, d).and_hms(h, m, 0);
+        let schedule: BandwidthSchedule =
+            BandwidthSchedule::from_str("mon-fri 09:00-18:00=1024; Sat,sun 10:00-12:00=4096;")
+                .unwrap();
+        assert!(!schedule.is_empty());
+        assert_eq!(
+            schedule.to_string(),
+            "mon-fri 09:00-18:00=1024; sat-sun 10:00-12:00=4096"
+        );
+        assert_eq!(schedule.limit_at(at(7, 9, 0)), Some(1024));
+        assert_eq!(schedule.limit_at(at(11, 17, 59)), Some(1024));
+        assert_eq!(schedule.limit_at(at(11, 18, 0)), None);
+        assert_eq!(schedule.limit_at(at(12, 9, 0)), None);
+        assert_eq!(schedule.limit_at(at(13, 11, 0)), Some(4096));
+        // Every day; the first matching rule applies; ranges of days may wrap
+        let schedule: BandwidthSchedule =
+            BandwidthSchedule::from_str("fri-mon 00:00-06:00=0;22:00-06:00=256").unwrap();
+        assert_eq!(
+            schedule.to_string(),
+            "mon,fri-sun 00:00-06:00=0; 22:00-06:00=256"
+        );
+        assert_eq!(schedule.limit_at(at(7, 1, 0)), Some(0));
+        assert_eq!(schedule.limit_at(at(8, 1, 0)), Some(256));
+        assert_eq!(schedule.limit_at(at(8, 23, 0)), Some(256));
+        assert_eq!(schedule.limit_at(at(8, 12, 0)), None);
+        // Empty schedule
+        let schedule: BandwidthSchedule = BandwidthSchedule::from_str(" ").unwrap();
+        assert!(schedule.is_empty());
+        assert_eq!(schedule.to_string(), "");
+        // Bad rules
+        assert!(BandwidthSchedule::from_str("09:00-18:00").is_err());
+        assert!(BandwidthSchedule::from_str("09:00-18:00=fast").is_err());
+        assert!(BandwidthSchedule::from_str("someday 09:00-18:00=1024").is_err());
+        assert!(BandwidthSchedule::from_str("mon-fri=1024").is_err());
+        assert!(BandwidthSchedule::from_str("mon-fri 09:00-09:00=1024").is_err());
+        // Options
+        let mut opts: TransferOptions = TransferOptions::default();
+        opts.bandwidth_limit = 2048;
+        assert_eq!(opts.current_bandwidth_limit(), 2048);
+        opts.bandwidth_schedule =
+            BandwidthSchedule::from_str("00:00-12:00=512; 12:00-24:00=512").unwrap();
+        assert_eq!(opts.current_bandwidth_limit(), 512);
+    }
+
+    #[test]
+    fn test_fs_transfer_compression() {
+        let mut opts: TransferOptions = TransferOptions::default();
+        assert_eq!(opts.compresses("access.log"), false);
+        opts.compress_uploads = true;
+        assert_eq!(opts.compresses("access.log"), true);
+        assert_eq!(opts.compresses("Makefile"), true);
+        assert_eq!(opts.compresses("backup.tar.gz"), false);
+        assert_eq!(opts.compresses("IMG_0001.JPG"), false);
+        assert_eq!(
+            TransferOptions::compressed_path(Path::new("/var/log/access.log")),
+            PathBuf::from("/var/log/.access.log.gz")
+        );
+        // Compress in chunks, then decompress
+        let data: Vec<u8> = "GET /index.html HTTP/1.1 200\n".repeat(4096).into_bytes();
+        let mut gzip: GzipBuffer = GzipBuffer::new();
+        let mut compressed: Vec<u8> = Vec::new();
+        for chunk in data.chunks(1000) {
+            compressed.append(&mut gzip.compress(chunk).unwrap());
+        }
+        compressed.append(&mut gzip.finish().unwrap());
+        assert!(compressed.len() < data.len());
+        let mut decompressed: Vec<u8> = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+        // Compress a stream
+        let mut inspected: usize = 0;
+        let mut compressed: Vec<u8> = Vec::new();
+        let (bytes_read, bytes_written) =
+            GzipBuffer::compress_stream(&mut data.as_slice(), &mut compressed, |chunk| {
+                inspected += chunk.len()
+            })
+            .unwrap();
+        assert_eq!(bytes_read, data.len() as u64);
+        assert_eq!(inspected, data.len());
+        assert_eq!(bytes_written, compressed.len() as u64);
+        let mut decompressed: Vec<u8> = Vec::new();
+        flate2::read::GzDecoder::new(compressed.as_slice())
+            .read_to_end(&mut decompressed)
+            .unwrap();
+        assert_eq!(decompressed, data);
+    }
+
+    #[test]
+    fn test_fs_transfer_conflict_renamed() {
+        assert_eq!(
+            ConflictChoice::renamed(Path::new("/tmp/report.txt"), 1),
+            PathBuf::from("/tmp/report (1).txt")
+        );
+        assert_eq!(
+            ConflictChoice::renamed(Path::new("/tmp/archive.tar.gz"), 2),
+            PathBuf::from("/tmp/archive.tar (2).gz")
+        );
+        assert_eq!(
+            ConflictChoice::renamed(Path::new("/home/omar/.bashrc"), 1),
+            PathBuf::from("/home/omar/.bashrc (1)")
+        );
+        assert_eq!(
+            ConflictChoice::renamed(Path::new("Makefile"), 3),
+            PathBuf::from("Makefile (3)")
+        );
+    }
+
+    #[test]
+    fn test_fs_transfer_hooks() {
+        let opts: TransferOptions = TransferOptions {
+            hooks: vec![
+                TransferHook::new(
+                    "nginx.conf",
+                    Some(TransferDirection::Upload),
+                    "nginx -t -c {path} && systemctl reload nginx",
+                    true,
+                ),
+                TransferHook::new("*", None, "notify-send {name} {local} {remote} {x}", false),
+            ],
+            ..Default::default()
+        };
+        let hooks: Vec<&TransferHook> = opts
+            .hooks(TransferDirection::Upload, "nginx.conf")
+            .collect();
+        assert_eq!(hooks.len(), 2);
+        assert!(hooks[0].is_remote());
+        assert!(!hooks[1].is_remote());
+        let (local, remote): (&Path, &Path) = (
+            Path::new("/home/omar/nginx.conf"),
+            Path::new("/etc/nginx/nginx.conf"),
+        );
+        assert_eq!(
+            hooks[0].command(local, remote).as_str(),
+            "nginx -t -c '/etc/nginx/nginx.conf' && systemctl reload nginx"
+        );
+        assert_eq!(
+            hooks[1].command(local, remote).as_str(),
+            "notify-send 'nginx.conf' '/home/omar/nginx.conf' '/etc/nginx/nginx.conf' {x}"
+        );
+        assert_eq!(
+            opts.hooks(TransferDirection::Download, "nginx.conf")
+                .count(),
+            1
+        );
+        // Paths are quoted and never expanded in turn
+        assert_eq!(
+            hooks[1]
+                .command(Path::new("/tmp/{remote}'; reboot"), remote)
+                .as_str(),
+            "notify-send '{remote}'\\''; reboot' '/tmp/{remote}'\\''; reboot' '/etc/nginx/nginx.conf' {x}"
+        );
+    }
+
+    #[test]
+    fn test_fs_transfer_rules() {
+        let opts: TransferOptions = TransferOptions {
+            overwrite: OverwritePolicy::Skip,
+            upload_permissions: Some(((6, 0, 0), None)),
+            rules: vec![
+                TransferRule::new("*.jpg", Some(OverwritePolicy::SkipSameSize), None),
+                TransferRule::new(
+                    "*.php",
+                    Some(OverwritePolicy::Overwrite),
+                    Some(((6, 4, 4), None)),
+                ),
+                TransferRule::new("index.*", Some(OverwritePolicy::Overwrite), None),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(
+            opts.overwrite_policy("cat.jpg"),
+            OverwritePolicy::SkipSameSize
+        );
+        assert_eq!(
+            opts.overwrite_policy("index.php"),
+            OverwritePolicy::Overwrite
+        );
+        assert_eq!(opts.overwrite_policy("main.c"), OverwritePolicy::Skip);
+        assert_eq!(
+            opts.upload_permissions("index.php"),
+            Some(((6, 4, 4), None))
+        );
+        // First matching rule applies
+        assert_eq!(
+            opts.upload_permissions("index.jpg"),
+            Some(((6, 0, 0), None))
+        );
+        assert_eq!(
+            opts.overwrite_policy("index.jpg"),
+            OverwritePolicy::SkipSameSize
+        );
+        assert!(opts.applies_permissions());
+        assert!(!TransferOptions::default().applies_permissions());
+    }
+
+    #[test]
+    fn test_fs_transfer_ignore_rules() {
+        let rules: IgnoreRules = IgnoreRules::new(&["*.o", " node_modules/ ", ""]);
+        assert_eq!(rules.rules.len(), 2);
+        assert!(rules.is_ignored(&make_fs_entry("main.o", false)));
+        assert!(!rules.is_ignored(&make_fs_entry("main.c", false)));
+        assert!(rules.is_ignored(&make_fs_entry("node_modules", true)));
+        // Directory-only rule doesn't apply to files
+        assert!(!rules.is_ignored(&make_fs_entry("node_modules", false)));
+        // Empty rules
+        assert!(!IgnoreRules::default().is_ignored(&make_fs_entry("main.o", false)));
+    }
+
+    #[test]
+    fn test_fs_transfer_filters() {
+        let filters: TransferFilters = TransferFilters::new(&["node_modules/", "*.o", " ", "+"]);
+        assert_eq!(filters.patterns(), &["node_modules/", "*.o"]);
+        assert!(!filters.is_empty());
+        assert!(filters.is_filtered(&make_fs_entry("node_modules", true)));
+        assert!(filters.is_filtered(&make_fs_entry("main.o", false)));
+        assert!(!filters.is_filtered(&make_fs_entry("main.c", false)));
+        assert!(!filters.is_filtered(&make_fs_entry("src", true)));
+        // Include patterns
+        let filters: TransferFilters = TransferFilters::new(&["+*.c", "+*.h", "test_*"]);
+        assert!(!filters.is_filtered(&make_fs_entry("main.c", false)));
+        assert!(!filters.is_filtered(&make_fs_entry("main.h", false)));
+        assert!(filters.is_filtered(&make_fs_entry("test_main.c", false)));
+        assert!(filters.is_filtered(&make_fs_entry("Makefile", false)));
+        // File patterns don't apply to directories
+        assert!(!filters.is_filtered(&make_fs_entry("src", true)));
+        let filters: TransferFilters = TransferFilters::new(&["+src/"]);
+        assert!(!filters.is_filtered(&make_fs_entry("src", true)));
+        assert!(filters.is_filtered(&make_fs_entry("target", true)));
+        assert!(!filters.is_filtered(&make_fs_entry("Cargo.toml", false)));
+        // No filters
+        let filters: TransferFilters = TransferFilters::default();
+        assert!(filters.is_empty());
+        assert!(!filters.is_filtered(&make_fs_entry("main.o", false)));
+    }
+
+    #[test]
+    fn test_fs_transfer_throttle() {
+        // Unlimited
+        let mut throttle: Throttle = Throttle::new(0);
+        throttle.consume(1048576);
+        assert!(throttle.delay().is_none());
+        // 1 KiB/s
+        let mut throttle: Throttle = Throttle::new(1);
+        assert!(throttle.delay().is_none());
+        throttle.consume(4096);
+        let delay: Duration = throttle.delay().unwrap();
+        assert!(delay > Duration::from_secs(3) && delay <= Duration::from_secs(4));
+        // Pause isn't counted as transfer time
+        let mut throttle: Throttle = Throttle::new(1);
+        throttle.started -= Duration::from_secs(8);
+        throttle.consume(4096);
+        assert!(throttle.delay().is_none());
+        throttle.skip(Duration::from_secs(8));
+        assert!(throttle.delay().unwrap() > Duration::from_secs(3));
+        // Changing the limit applies from now on
+        throttle.set_limit(1);
+        assert!(throttle.delay().unwrap() > Duration::from_secs(3));
+        throttle.set_limit(0);
+        assert!(throttle.delay().is_none());
+        throttle.set_limit(1);
+        throttle.consume(1024);
+        assert!(throttle.delay().unwrap() <= Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_fs_transfer_chunk_sizer() {
+        let record = |sizer: &mut ChunkSizer, chunks: usize, millis: u64| {
+            for _ in 0..chunks {
+                sizer.record(sizer.size(), Duration::from_millis(millis));
+            }
+        };
+        // Not enough time measured
+        let mut sizer: ChunkSizer = ChunkSizer::default();
+        assert_eq!(sizer.size(), 65536);
+        record(&mut sizer, 8, 1);
+        assert_eq!(sizer.size(), 65536);
+        // Grows while larger chunks are faster
+        let mut sizer: ChunkSizer = ChunkSizer::default();
+        record(&mut sizer, 4, 20);
+        assert_eq!(sizer.size(), 131072);
+        record(&mut sizer, 4, 20);
+        assert_eq!(sizer.size(), 262144);
+        // Slower: back to the previous size, which is not exceeded anymore
+        record(&mut sizer, 3, 80);
+        assert_eq!(sizer.size(), 131072);
+        record(&mut sizer, 4, 20);
+        assert_eq!(sizer.size(), 131072);
+        // Chunks taking too long are shrunk; long chunks don't grow
+        record(&mut sizer, 1, 2000);
+        assert_eq!(sizer.size(), 65536);
+        record(&mut sizer, 4, 800);
+        assert_eq!(sizer.size(), 65536);
+        for _ in 0..8 {
+            sizer.record(sizer.size(), Duration::from_secs(2));
+        }
+        assert_eq!(sizer.size(), 16384);
+    }
+
+    #[test]
+    fn test_fs_transfer_throughput() {
+        let mut throughput: Throughput = Throughput::new(Duration::from_secs(2));
+        assert!(throughput.bytes_per_second().is_none());
+        let start: Instant = Instant::now();
+        throughput.record_at(0, start);
+        throughput.record_at(1024, start + Duration::from_millis(50));
+        // Not enough time
+        assert!(throughput.bytes_per_second().is_none());
+        throughput.record_at(4096, start + Duration::from_secs(1));
+        assert_eq!(throughput.bytes_per_second(), Some(4096));
+        // Slows down: old samples are dropped
+        throughput.record_at(5120, start + Duration::from_secs(3));
+        throughput.record_at(6144, start + Duration::from_secs(4));
+        assert_eq!(throughput.bytes_per_second(), Some(682));
+        throughput.record_at(7168, start + Duration::from_secs(5));
+        assert_eq!(throughput.bytes_per_second(), Some(1024));
+        throughput.reset();
+        assert!(throughput.bytes_per_second().is_none());
+        // ETA
+        assert_eq!(Throughput::eta(10240, 1024), Some(Duration::from_secs(10)));
+        assert_eq!(Throughput::eta(10240, 0), None);
+    }
+
+    #[test]
+    fn test_fs_transfer_retry_policy() {
+        assert_eq!(RetryPolicy::default().retries, 0);
+        let policy: RetryPolicy = RetryPolicy::new(4, Duration::from_millis(500));
+        assert_eq!(policy.delay(1), Duration::from_millis(500));
+        assert_eq!(policy.delay(2), Duration::from_secs(1));
+        assert_eq!(policy.delay(4), Duration::from_secs(4));
+        // Capped
+        assert_eq!(policy.delay(12), Duration::from_secs(60));
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_fs_transfer_cancellable() {
+        let token: CancelToken = CancelToken::default();
+        let mut reader = Cancellable::new(io::Cursor::new(vec![1, 2, 3, 4]), &token);
+        let mut writer = Cancellable::new(Vec::new(), &token);
+        let mut buf: [u8; 2] = [0; 2];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        assert_eq!(writer.write(&buf).unwrap(), 2);
+        // Cancel; clones share the flag
+        token.clone().cancel();
+        assert!(token.is_cancelled());
+        let err: io::Error = reader.read(&mut buf).err().unwrap();
+        assert!(CancelToken::is_cancellation(&err));
+        let err: io::Error = writer.write(&buf).err().unwrap();
+        assert!(CancelToken::is_cancellation(&err));
+        assert!(writer.flush().is_err());
+        assert!(!CancelToken::is_cancellation(&io::Error::other(
+            "broken pipe"
+        )));
+        // Inner stream has been left untouched
+        assert_eq!(writer.into_inner(), vec![1, 2]);
+        // Reset
+        token.reset();
+        assert!(!token.is_cancelled());
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+    }
+
+    #[test]
+    fn test_fs_transfer_cancel_token_pause() {
+        let token: CancelToken = CancelToken::default();
+        assert!(!token.is_paused());
+        assert!(token.wait_resumed() < Duration::from_millis(50));
+        // Pause; clones share the flag
+        token.clone().pause();
+        assert!(token.is_paused());
+        // Resumed by another thread
+        let resumer: CancelToken = token.clone();
+        let hnd = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(200));
+            resumer.resume();
+        });
+        assert!(token.wait_resumed() >= Duration::from_millis(200));
+        assert!(!token.is_paused());
+        hnd.join().unwrap();
+        // Cancelling unblocks the paused thread
+        token.pause();
+        token.cancel();
+        assert!(token.wait_resumed() < Duration::from_secs(1));
+        // Reset clears both
+        token.reset();
+        assert!(!token.is_paused());
+        assert!(!token.is_cancelled());
+    }
+
+    fn make_fs_entry(name: &str, is_dir: bool) -> FsEntry {
+        let t_now: SystemTime = SystemTime::now();
+        match is_dir {
+            false => FsEntry::File(FsFile {
+                name: name.to_string(),
+                abs_path: PathBuf::from(name),
+                last_change_time: t_now,
+                last_access_time: t_now,
+                creation_time: t_now,
+                size: 64,
+                ftype: None,
+                readonly: false,
+                symlink: None,
+                broken_link: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            }),
+            true => FsEntry::Directory(FsDirectory {
+                name: name.to_string(),
+                abs_path: PathBuf::from(name),
+                last_change_time: t_now,
+                last_access_time: t_now,
+                creation_time: t_now,
+                readonly: false,
+                symlink: None,
+                user: None,
+                group: None,
+                unix_pex: None,
+            }),
+        }
+    }
+}
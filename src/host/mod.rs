@@ -37,6 +37,8 @@ use wildmatch::WildMatch;
 use std::fs::set_permissions;
 #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
 use std::os::unix::fs::{MetadataExt, PermissionsExt};
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+use std::os::unix::io::AsRawFd;
 
 // Locals
 use crate::fs::{FsDirectory, FsEntry, FsFile};
@@ -54,6 +56,8 @@ pub enum HostErrorType {
     CouldNotCreateFile,
     ExecutionFailed,
     DeleteFailed,
+    PreallocationFailed,
+    SyncFailed,
 }
 
 /// ### HostError
@@ -88,6 +92,8 @@ impl std::fmt::Display for HostError {
             HostErrorType::CouldNotCreateFile => "Could not create file",
             HostErrorType::ExecutionFailed => "Could not run command",
             HostErrorType::DeleteFailed => "Could not delete file",
+            HostErrorType::PreallocationFailed => "Could not preallocate file",
+            HostErrorType::SyncFailed => "Could not sync file to disk",
         };
         match &self.ioerr {
             Some(err) => write!(f, "{}: {}", code_str, err),
@@ -328,8 +334,13 @@ impl Localhost {
         let path: PathBuf = self.to_abs_path(path);
         let attr: Metadata = match fs::metadata(path.as_path()) {
             Ok(metadata) => metadata,
-            Err(err) => return Err(HostError::new(HostErrorType::FileNotAccessible, Some(err))),
+            // Broken symlinks can't be followed; stat the link itself
+            Err(err) => match fs::symlink_metadata(path.as_path()) {
+                Ok(metadata) if metadata.file_type().is_symlink() => metadata,
+                _ => return Err(HostError::new(HostErrorType::FileNotAccessible, Some(err))),
+            },
         };
+        let (symlink, broken_link) = self.read_symlink(path.as_path());
         let file_name: String = String::from(path.file_name().unwrap().to_str().unwrap_or(""));
         // Match dir / file
         Ok(match path.is_dir() {
@@ -340,13 +351,7 @@ impl Localhost {
                 last_access_time: attr.accessed().unwrap_or(SystemTime::UNIX_EPOCH),
                 creation_time: attr.created().unwrap_or(SystemTime::UNIX_EPOCH),
                 readonly: attr.permissions().readonly(),
-                symlink: match fs::read_link(path.as_path()) {
-                    Ok(p) => match self.stat(p.as_path()) {
-                        Ok(entry) => Some(Box::new(entry)),
-                        Err(_) => None,
-                    },
-                    Err(_) => None,
-                },
+                symlink,
                 user: Some(attr.uid()),
                 group: Some(attr.gid()),
                 unix_pex: Some(self.u32_to_mode(attr.mode())),
@@ -365,13 +370,8 @@ impl Localhost {
                     readonly: attr.permissions().readonly(),
                     size: attr.len() as usize,
                     ftype: extension,
-                    symlink: match fs::read_link(path.as_path()) {
-                        Ok(p) => match self.stat(p.as_path()) {
-                            Ok(entry) => Some(Box::new(entry)),
-                            Err(_) => None,
-                        },
-                        Err(_) => None, // Ignore errors
-                    },
+                    symlink,
+                    broken_link,
                     user: Some(attr.uid()),
                     group: Some(attr.gid()),
                     unix_pex: Some(self.u32_to_mode(attr.mode())),
@@ -434,6 +434,7 @@ impl Localhost {
                         },
                         Err(_) => None,
                     },
+                    broken_link: None,
                     user: None,
                     group: None,
                     unix_pex: None,
@@ -479,6 +480,30 @@ impl Localhost {
         }
     }
 
+    /// ### chown
+    ///
+    /// Change owner of file to user `uid` and group `gid`
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    pub fn chown(&self, path: &Path, uid: u32, gid: u32) -> Result<(), HostError> {
+        let path: PathBuf = self.to_abs_path(path);
+        match std::os::unix::fs::chown(path.as_path(), Some(uid), Some(gid)) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HostError::new(HostErrorType::FileNotAccessible, Some(err))),
+        }
+    }
+
+    /// ### symlink
+    ///
+    /// Create a symlink at `path`, pointing to `target`; relative targets are relative to the directory of the link
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    pub fn symlink(&self, path: &Path, target: &Path) -> Result<(), HostError> {
+        let path: PathBuf = self.to_abs_path(path);
+        match std::os::unix::fs::symlink(target, path.as_path()) {
+            Ok(_) => Ok(()),
+            Err(err) => Err(HostError::new(HostErrorType::CouldNotCreateFile, Some(err))),
+        }
+    }
+
     /// ### open_file_read
     ///
     /// Open file for read
@@ -547,11 +572,174 @@ impl Localhost {
     /// Find files matching `search` on localhost starting from current directory. Search supports recursive search of course.
     /// The `search` argument supports wilcards ('*', '?')
     pub fn find(&self, search: &str) -> Result<Vec<FsEntry>, HostError> {
-        self.iter_search(self.wrkdir.as_path(), &WildMatch::new(search))
+        self.find_in(self.wrkdir.as_path(), search)
+    }
+
+    /// ### find_in
+    ///
+    /// Find files matching `search` on localhost starting from `dir`
+    pub fn find_in(&self, dir: &Path, search: &str) -> Result<Vec<FsEntry>, HostError> {
+        self.iter_search(dir, &WildMatch::new(search))
+    }
+
+    /// ### disk_usage
+    ///
+    /// Calculate the size of the files in `dir` and in its subdirectories. Symbolic links are not followed.
+    /// `on_progress` is called with the amount of files counted so far, once in a while
+    pub fn disk_usage(dir: &Path, on_progress: &mut dyn FnMut(usize)) -> Result<u64, HostError> {
+        let mut files: usize = 0;
+        Self::iter_disk_usage(dir, &mut files, on_progress)
+    }
+
+    /// ### preallocate
+    ///
+    /// Reserve `size` bytes on disk for `file`, without changing its size, so that a full disk is reported
+    /// before writing it and large files aren't fragmented.
+    /// Returns whether the space has been reserved; the file system may not support it
+    #[cfg(target_os = "linux")]
+    pub fn preallocate(file: &File, size: u64) -> Result<bool, HostError> {
+        if size == 0 {
+            return Ok(false);
+        }
+        let rc: libc::c_int = unsafe {
+            libc::fallocate(
+                file.as_raw_fd(),
+                libc::FALLOC_FL_KEEP_SIZE,
+                0,
+                size as libc::off_t,
+            )
+        };
+        match rc {
+            0 => Ok(true),
+            _ => Self::preallocate_error(std::io::Error::last_os_error()),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    pub fn preallocate(file: &File, size: u64) -> Result<bool, HostError> {
+        if size == 0 {
+            return Ok(false);
+        }
+        let store: libc::fstore_t = libc::fstore_t {
+            fst_flags: libc::F_ALLOCATEALL,
+            fst_posmode: libc::F_PEOFPOSMODE,
+            fst_offset: 0,
+            fst_length: size as libc::off_t,
+            fst_bytesalloc: 0,
+        };
+        match unsafe { libc::fcntl(file.as_raw_fd(), libc::F_PREALLOCATE, &store) } {
+            -1 => Self::preallocate_error(std::io::Error::last_os_error()),
+            _ => Ok(true),
+        }
+    }
+
+    #[cfg(not(any(target_os = "macos", target_os = "linux")))]
+    pub fn preallocate(_file: &File, _size: u64) -> Result<bool, HostError> {
+        Ok(false)
+    }
+
+    /// ### sync_file
+    ///
+    /// Flush the file at `path` to disk, then the directory containing it, so that the file and its name
+    /// survive a crash or a power loss once this function returns
+    pub fn sync_file(path: &Path) -> Result<(), HostError> {
+        // Opening it for read is enough on UNIX, even if the file is readonly
+        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+        let file = File::open(path);
+        #[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+        let file = OpenOptions::new().write(true).open(path);
+        file.and_then(|x| x.sync_all())
+            .map_err(|e| HostError::new(HostErrorType::SyncFailed, Some(e)))?;
+        // Directories can't be opened on Windows, where the entries are flushed with the file
+        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+        if let Some(parent) = path.parent().filter(|x| !x.as_os_str().is_empty()) {
+            File::open(parent)
+                .and_then(|x| x.sync_all())
+                .map_err(|e| HostError::new(HostErrorType::SyncFailed, Some(e)))?;
+        }
+        Ok(())
     }
 
     // -- privates
 
+    /// ### preallocate_error
+    ///
+    /// Convert the error returned by the preallocation call; unsupported preallocation is not an error
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    fn preallocate_error(err: std::io::Error) -> Result<bool, HostError> {
+        match err.raw_os_error() {
+            // EOPNOTSUPP and ENOTSUP are the same on Linux
+            Some(code)
+                if code == libc::EOPNOTSUPP || code == libc::ENOTSUP || code == libc::ENOSYS =>
+            {
+                Ok(false)
+            }
+            _ => Err(HostError::new(
+                HostErrorType::PreallocationFailed,
+                Some(err),
+            )),
+        }
+    }
+
+    /// ### read_symlink
+    ///
+    /// Read the symlink at `path`. Returns the entry pointed by the symlink or, if the target
+    /// can't be stat (e.g. it doesn't exist), the path of the target
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn read_symlink(&self, path: &Path) -> (Option<Box<FsEntry>>, Option<PathBuf>) {
+        match fs::read_link(path) {
+            Ok(target) => {
+                // Relative targets are relative to the directory of the symlink
+                let target: PathBuf = match path.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+                // Target is checked first; stat would recurse forever on symlink loops
+                match fs::metadata(target.as_path()).is_ok() {
+                    true => match self.stat(target.as_path()) {
+                        Ok(entry) => (Some(Box::new(entry)), None),
+                        Err(_) => (None, Some(target)),
+                    },
+                    false => (None, Some(target)),
+                }
+            }
+            Err(_) => (None, None),
+        }
+    }
+
+    /// ### iter_disk_usage
+    ///
+    /// Recursive call for `disk_usage` method.
+    /// Directories which can't be read are not counted
+    fn iter_disk_usage(
+        dir: &Path,
+        files: &mut usize,
+        on_progress: &mut dyn FnMut(usize),
+    ) -> Result<u64, HostError> {
+        let entries = match fs::read_dir(dir) {
+            Ok(e) => e,
+            Err(err) => return Err(HostError::new(HostErrorType::DirNotAccessible, Some(err))),
+        };
+        let mut size: u64 = 0;
+        for entry in entries.flatten() {
+            let metadata: Metadata = match fs::symlink_metadata(entry.path()) {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+            if metadata.is_dir() {
+                size +=
+                    Self::iter_disk_usage(entry.path().as_path(), files, on_progress).unwrap_or(0);
+            } else {
+                size += metadata.len();
+                *files += 1;
+                if *files & 0xff == 0 {
+                    on_progress(*files);
+                }
+            }
+        }
+        Ok(size)
+    }
+
     /// ### iter_search
     ///
     /// Recursive call for `find` method.
@@ -824,6 +1012,30 @@ mod tests {
         };
     }
 
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn test_host_localhost_broken_symlinks() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        // Dangling and looping symlinks
+        assert!(symlink("missing.txt", tmpdir.path().join("dangling.txt")).is_ok());
+        assert!(symlink("loop_b", tmpdir.path().join("loop_a")).is_ok());
+        assert!(symlink("loop_a", tmpdir.path().join("loop_b")).is_ok());
+        let host: Localhost = Localhost::new(PathBuf::from(tmpdir.path())).ok().unwrap();
+        let files: Vec<FsEntry> = host.list_dir();
+        assert_eq!(files.len(), 3);
+        let dangling: &FsEntry = files
+            .iter()
+            .find(|x| x.get_name() == "dangling.txt")
+            .unwrap();
+        assert!(dangling.is_symlink());
+        assert!(dangling.is_broken_link());
+        assert_eq!(
+            dangling.get_symlink_target(),
+            Some(tmpdir.path().join("missing.txt"))
+        );
+        assert!(files.iter().all(|x| x.is_broken_link()));
+    }
+
     #[test]
     #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     fn test_host_localhost_mkdir() {
@@ -912,6 +1124,42 @@ mod tests {
             .is_err());
     }
 
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn test_host_chown() {
+        let file: tempfile::NamedTempFile = create_sample_file();
+        let host: Localhost = Localhost::new(PathBuf::from("/tmp")).ok().unwrap();
+        // Chown to the current owner is always allowed
+        let metadata: Metadata = fs::metadata(file.path()).ok().unwrap();
+        assert!(host
+            .chown(file.path(), metadata.uid(), metadata.gid())
+            .is_ok());
+        // Error
+        assert!(host
+            .chown(Path::new("/tmp/krgiogoiegj/kwrgnoerig"), 0, 0)
+            .is_err());
+    }
+
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    #[test]
+    fn test_host_symlink() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        fs::write(tmpdir.path().join("notes.txt"), "hello").unwrap();
+        let host: Localhost = Localhost::new(PathBuf::from(tmpdir.path())).ok().unwrap();
+        // Relative target
+        let link: PathBuf = tmpdir.path().join("link.txt");
+        assert!(host.symlink(link.as_path(), Path::new("notes.txt")).is_ok());
+        assert_eq!(
+            fs::read_link(link.as_path()).unwrap(),
+            PathBuf::from("notes.txt")
+        );
+        assert_eq!(fs::read_to_string(link.as_path()).unwrap(), "hello");
+        // Already exists
+        assert!(host
+            .symlink(link.as_path(), Path::new("notes.txt"))
+            .is_err());
+    }
+
     #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
     #[test]
     fn test_host_copy_file_absolute() {
@@ -1061,6 +1309,65 @@ mod tests {
         assert_eq!(result.len(), 2);
         assert_eq!(result[0].get_name(), "examples");
         assert_eq!(result[1].get_name(), "examples.csv");
+        // Find in subdirectory
+        let result: Vec<FsEntry> = host.find_in(subdir.as_path(), "*.txt").ok().unwrap();
+        assert_eq!(result.len(), 2);
+    }
+
+    #[test]
+    fn test_host_disk_usage() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let dir_path: &Path = tmpdir.path();
+        assert!(make_sample_file(dir_path, "pippo.txt").is_ok());
+        assert!(make_dir(dir_path, "examples").is_ok());
+        let mut subdir: PathBuf = PathBuf::from(dir_path);
+        subdir.push("examples/");
+        assert!(make_sample_file(subdir.as_path(), "omar.txt").is_ok());
+        let file_size: u64 = fs::metadata(subdir.join("omar.txt")).unwrap().len();
+        let mut progress: Vec<usize> = Vec::new();
+        assert_eq!(
+            Localhost::disk_usage(dir_path, &mut |x| progress.push(x))
+                .ok()
+                .unwrap(),
+            file_size * 2
+        );
+        assert!(progress.is_empty());
+        // Not existing directory
+        assert!(Localhost::disk_usage(subdir.join("nope").as_path(), &mut |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_host_preallocate() {
+        let tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        // Nothing to reserve for empty files
+        assert_eq!(
+            Localhost::preallocate(tmpfile.as_file(), 0).ok(),
+            Some(false)
+        );
+        // Size is kept, whether the file system supports preallocation or not
+        assert!(Localhost::preallocate(tmpfile.as_file(), 1048576).is_ok());
+        assert_eq!(tmpfile.as_file().metadata().unwrap().len(), 0);
+    }
+
+    #[test]
+    fn test_host_sync_file() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let path: PathBuf = tmpdir.path().join("backup.tar");
+        fs::write(path.as_path(), "data").unwrap();
+        assert!(Localhost::sync_file(path.as_path()).is_ok());
+        // Readonly files can be synced too
+        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+        {
+            fs::set_permissions(path.as_path(), PermissionsExt::from_mode(0o444)).unwrap();
+            assert!(Localhost::sync_file(path.as_path()).is_ok());
+        }
+        assert_eq!(
+            Localhost::sync_file(tmpdir.path().join("nope").as_path())
+                .err()
+                .unwrap()
+                .error,
+            HostErrorType::SyncFailed
+        );
     }
 
     #[test]
@@ -1077,6 +1384,17 @@ mod tests {
             format!("{}", HostError::new(HostErrorType::DeleteFailed, None)),
             String::from("Could not delete file")
         );
+        assert_eq!(
+            format!(
+                "{}",
+                HostError::new(HostErrorType::PreallocationFailed, None)
+            ),
+            String::from("Could not preallocate file")
+        );
+        assert_eq!(
+            format!("{}", HostError::new(HostErrorType::SyncFailed, None)),
+            String::from("Could not sync file to disk")
+        );
         assert_eq!(
             format!("{}", HostError::new(HostErrorType::ExecutionFailed, None)),
             String::from("Could not run command")
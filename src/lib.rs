@@ -30,8 +30,11 @@ extern crate lazy_static;
 extern crate magic_crypt;
 
 pub mod activity_manager;
+pub mod bench;
 pub mod bookmarks;
 pub mod config;
+pub mod daemon;
+pub mod doctor;
 pub mod filetransfer;
 pub mod fs;
 pub mod host;
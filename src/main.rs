@@ -36,6 +36,7 @@ extern crate magic_crypt;
 extern crate rpassword;
 
 // External libs
+use bytesize::ByteSize;
 use getopts::Options;
 use std::env;
 use std::path::PathBuf;
@@ -43,8 +44,11 @@ use std::time::Duration;
 
 // Include
 mod activity_manager;
+mod bench;
 mod bookmarks;
 mod config;
+mod daemon;
+mod doctor;
 mod filetransfer;
 mod fs;
 mod host;
@@ -54,7 +58,19 @@ mod utils;
 
 // namespaces
 use activity_manager::{ActivityManager, NextActivity};
-use filetransfer::FileTransferProtocol;
+use bench::{BenchOptions, BenchParams, BenchResult};
+use daemon::client::DaemonClient;
+use daemon::rpc::EnqueueParams;
+use daemon::{Job, JobState};
+use doctor::{DoctorParams, Finding, Outcome};
+use filetransfer::{FileTransferProtocol, SshAlgorithms};
+use fs::queue::{Priority, TransferDirection};
+use system::bookmarks_client::BookmarksClient;
+use system::config_client::ConfigClient;
+use system::environment;
+use system::sshkey_storage::SshKeyStorage;
+use ui::context::FileTransferParams;
+use utils::secret::Secret;
 
 /// ### print_usage
 ///
@@ -62,12 +78,327 @@ use filetransfer::FileTransferProtocol;
 
 fn print_usage(opts: Options) {
     let brief = String::from(
-        "Usage: termscp [options]... [protocol://user@address:port:wrkdir] [local-wrkdir]",
+        "Usage: termscp [options]... [protocol://user@address:port:wrkdir] [local-wrkdir]\n       termscp [options]... -l [right-dir] [local-wrkdir]\n       termscp [options]... bench <bookmark>\n       termscp [options]... doctor [protocol://user@]address[:port]\n       termscp [options]... daemon [enqueue <bookmark> <upload|download> <source> <dest-dir> | status | retry <id> | remove <id> | stop]\n       termscp [options]... attach",
     );
     print!("{}", opts.usage(&brief));
     println!("\nPlease, report issues to <https://github.com/veeso/termscp>");
 }
 
+/// ### run_bench
+///
+/// Run benchmark towards the host saved as `bookmark` and print the throughput for each configuration.
+/// Returns the exit code
+fn run_bench(bookmark: &str, password: Option<String>) -> i32 {
+    // Get configuration directory
+    let config_dir: PathBuf = match environment::init_config_dir() {
+        Ok(Some(dir)) => dir,
+        Ok(None) => {
+            eprintln!("Could not find the configuration directory");
+            return 255;
+        }
+        Err(err) => {
+            eprintln!("Could not initialize configuration directory: {}", err);
+            return 255;
+        }
+    };
+    // Load bookmark
+    let bookmarks_file: PathBuf = environment::get_bookmarks_paths(config_dir.as_path());
+    let bookmarks_client: BookmarksClient =
+        match BookmarksClient::new(bookmarks_file.as_path(), config_dir.as_path(), 16) {
+            Ok(cli) => cli,
+            Err(err) => {
+                eprintln!("Could not load bookmarks: {}", err);
+                return 255;
+            }
+        };
+    let (address, port, protocol, username, bookmark_password) =
+        match bookmarks_client.get_bookmark(bookmark) {
+            Some(bookmark) => bookmark,
+            None => {
+                eprintln!("No such bookmark \"{}\"", bookmark);
+                return 255;
+            }
+        };
+    // Get password (CLI, then bookmark, then prompt)
+    let password: Option<String> = match password.or(bookmark_password) {
+        Some(p) => Some(p),
+        None => match rpassword::read_password_from_tty(Some("Password: ")) {
+            Ok(p) if p.is_empty() => None,
+            Ok(p) => Some(p),
+            Err(_) => {
+                eprintln!("Could not read password from prompt");
+                return 255;
+            }
+        },
+    };
+    // Load configuration for ssh keys
+    let (config_path, ssh_keys_path): (PathBuf, PathBuf) =
+        environment::get_config_paths(config_dir.as_path());
+    let config_client: Option<ConfigClient> =
+        ConfigClient::new(config_path.as_path(), ssh_keys_path.as_path()).ok();
+    let (endpoint, region) = bookmarks_client
+        .get_bookmark_s3_endpoint(bookmark)
+        .unwrap_or_default();
+    let params: BenchParams = BenchParams {
+        address,
+        port,
+        protocol,
+        username: match username.is_empty() {
+            true => None,
+            false => Some(username),
+        },
+        password,
+        endpoint,
+        region,
+        algorithms: bookmarks_client
+            .get_bookmark_ssh_algorithms(bookmark)
+            .unwrap_or_default(),
+    };
+    let opts: BenchOptions = BenchOptions::default();
+    println!(
+        "Benchmarking \"{}\" ({}://{}:{}) transferring {} per worker",
+        bookmark,
+        params.protocol.to_string().to_lowercase(),
+        params.address,
+        params.port,
+        ByteSize(opts.payload_size as u64)
+    );
+    println!(
+        "{:<12}{:<10}{:<16}{:<16}",
+        "BUFFER", "WORKERS", "UPLOAD", "DOWNLOAD"
+    );
+    let make_storage = || match config_client.as_ref() {
+        Some(cli) => SshKeyStorage::storage_from_config(cli),
+        None => SshKeyStorage::empty(),
+    };
+    let print_result = |r: &BenchResult| {
+        println!(
+            "{:<12}{:<10}{:<16}{:<16}",
+            ByteSize(r.buffer_size as u64).to_string(),
+            r.workers,
+            format!("{}/s", ByteSize(r.upload)),
+            format!("{}/s", ByteSize(r.download)),
+        )
+    };
+    match bench::run_bench(&params, &opts, make_storage, print_result) {
+        Ok(_) => 0,
+        Err(err) => {
+            eprintln!("Benchmark failed: {}", err);
+            255
+        }
+    }
+}
+
+/// ### run_doctor
+///
+/// Diagnose the connection towards `remote` and print the findings of each stage.
+/// Returns the exit code
+fn run_doctor(remote: &str, password: Option<String>) -> i32 {
+    let host_opts = match utils::parser::parse_remote_opt(remote) {
+        Ok(opts) => opts,
+        Err(err) => {
+            eprintln!("Bad address option: {}", err);
+            return 255;
+        }
+    };
+    if doctor::stages(host_opts.protocol).is_empty() {
+        eprintln!(
+            "Diagnosis is not supported for {}",
+            host_opts.protocol.to_string()
+        );
+        return 255;
+    }
+    // Load configuration for ssh keys and connection timeout
+    let config_client: Option<ConfigClient> = match environment::init_config_dir() {
+        Ok(Some(dir)) => {
+            let (config_path, ssh_keys_path) = environment::get_config_paths(dir.as_path());
+            ConfigClient::new(config_path.as_path(), ssh_keys_path.as_path()).ok()
+        }
+        _ => None,
+    };
+    let ssh_key: Option<PathBuf> = config_client.as_ref().and_then(|cli| {
+        SshKeyStorage::storage_from_config(cli)
+            .resolve(
+                host_opts.hostname.as_str(),
+                host_opts.username.as_deref().unwrap_or_default(),
+            )
+            .cloned()
+    });
+    let params: DoctorParams = DoctorParams {
+        address: host_opts.hostname,
+        port: host_opts.port,
+        protocol: host_opts.protocol,
+        username: host_opts.username,
+        password,
+        ssh_key,
+        timeout: config_client
+            .as_ref()
+            .map(|x| x.get_network_options().connection_timeout)
+            .unwrap_or_else(|| Duration::from_secs(30)),
+    };
+    println!(
+        "Diagnosing {}://{}:{}",
+        params.protocol.to_string().to_lowercase(),
+        params.address,
+        params.port
+    );
+    let findings: Vec<Finding> = doctor::diagnose(&params, |x: &Finding| {
+        println!(
+            "[{:<4}] {:<16}{}",
+            x.outcome,
+            x.stage.to_string(),
+            x.message
+        )
+    });
+    match findings.iter().any(|x| x.outcome == Outcome::Fail) {
+        true => 1,
+        false => 0,
+    }
+}
+
+/// ### run_daemon
+///
+/// Run the daemon if `args` is empty; otherwise send the command in `args` to the running daemon.
+/// Returns the exit code
+fn run_daemon(args: &[String]) -> i32 {
+    let config_dir: PathBuf = match environment::init_config_dir() {
+        Ok(Some(dir)) => dir,
+        Ok(None) => {
+            eprintln!("Could not find the configuration directory");
+            return 255;
+        }
+        Err(err) => {
+            eprintln!("Could not initialize configuration directory: {}", err);
+            return 255;
+        }
+    };
+    let socket: PathBuf = environment::get_daemon_socket_path(config_dir.as_path());
+    if args.is_empty() {
+        println!("termscp daemon listening on \"{}\"", socket.display());
+        return match daemon::run(socket.as_path(), config_dir.as_path()) {
+            Ok(_) => 0,
+            Err(err) => {
+                eprintln!("{}", err);
+                255
+            }
+        };
+    }
+    let mut client: DaemonClient = match DaemonClient::connect(socket.as_path()) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{}", err);
+            return 255;
+        }
+    };
+    let parse_id = |arg: Option<&String>| arg.and_then(|x| x.parse::<usize>().ok());
+    let result: Result<(), String> = match (args[0].as_str(), args.len()) {
+        ("enqueue", 5) => {
+            let direction: TransferDirection = match args[2].as_str() {
+                "upload" => TransferDirection::Upload,
+                "download" => TransferDirection::Download,
+                other => {
+                    eprintln!(
+                        "Unknown direction \"{}\": expected upload or download",
+                        other
+                    );
+                    return 255;
+                }
+            };
+            // Local paths are resolved here, since the daemon may run in another directory
+            let local = |p: &String| match env::current_dir() {
+                Ok(cwd) => utils::path::normalize_path(cwd.join(p).as_path()),
+                Err(_) => PathBuf::from(p),
+            };
+            let (source, destination) = match direction {
+                TransferDirection::Upload => (local(&args[3]), PathBuf::from(&args[4])),
+                TransferDirection::Download => (PathBuf::from(&args[3]), local(&args[4])),
+            };
+            client
+                .enqueue(&EnqueueParams {
+                    bookmark: args[1].clone(),
+                    direction,
+                    source,
+                    destination,
+                    priority: Priority::Normal,
+                })
+                .map(|id| println!("Enqueued transfer {}", id))
+        }
+        ("status", 1) => client.status().map(|jobs: Vec<Job>| {
+            println!(
+                "{:<6}{:<10}{:<10}{:<20}{:<8}SOURCE -> DESTINATION",
+                "ID", "STATE", "DIRECTION", "BOOKMARK", "DONE"
+            );
+            for job in jobs.iter() {
+                let percent: u64 = match job.bytes_total {
+                    0 if job.state == JobState::Done => 100,
+                    0 => 0,
+                    total => job.bytes_done * 100 / total,
+                };
+                println!(
+                    "{:<6}{:<10}{:<10}{:<20}{:<8}{} -> {}{}",
+                    job.id,
+                    format!("{:?}", job.state).to_lowercase(),
+                    format!("{:?}", job.direction).to_lowercase(),
+                    job.bookmark,
+                    format!("{}%", percent),
+                    job.source.display(),
+                    job.destination.display(),
+                    job.error
+                        .as_ref()
+                        .map(|x| format!(" ({})", x))
+                        .unwrap_or_default()
+                );
+            }
+        }),
+        ("retry", 2) => match parse_id(args.get(1)) {
+            Some(id) => client.retry(id),
+            None => Err(format!("Bad transfer id \"{}\"", args[1])),
+        },
+        ("remove", 2) => match parse_id(args.get(1)) {
+            Some(id) => client.remove(id),
+            None => Err(format!("Bad transfer id \"{}\"", args[1])),
+        },
+        ("stop", 1) => client.shutdown(),
+        (cmd, _) => Err(format!("Bad daemon command \"{}\"", cmd)),
+    };
+    match result {
+        Ok(_) => 0,
+        Err(err) => {
+            eprintln!("{}", err);
+            255
+        }
+    }
+}
+
+/// ### run_attach
+///
+/// Attach the UI to the running daemon, to monitor and control its transfers.
+/// Returns the exit code
+fn run_attach(ticks: Duration) -> i32 {
+    let config_dir: PathBuf = match environment::init_config_dir() {
+        Ok(Some(dir)) => dir,
+        Ok(None) => {
+            eprintln!("Could not find the configuration directory");
+            return 255;
+        }
+        Err(err) => {
+            eprintln!("Could not initialize configuration directory: {}", err);
+            return 255;
+        }
+    };
+    let socket: PathBuf = environment::get_daemon_socket_path(config_dir.as_path());
+    let wrkdir: PathBuf = env::current_dir().unwrap_or_else(|_| PathBuf::from("/"));
+    let mut manager: ActivityManager = match ActivityManager::new(&wrkdir, ticks) {
+        Ok(m) => m,
+        Err(err) => {
+            eprintln!("Could not start activity manager: {}", err);
+            return 255;
+        }
+    };
+    manager.run(NextActivity::Attach(socket));
+    0
+}
+
 fn main() {
     let args: Vec<String> = env::args().collect();
     //Program CLI options
@@ -86,7 +417,35 @@ fn main() {
         "Provide password from CLI (use at your own risk)",
         "<password>",
     );
+    opts.optflag(
+        "D",
+        "demo",
+        "Start termscp connected to an in-memory file system (nothing leaves your computer)",
+    );
+    opts.optflag(
+        "l",
+        "local",
+        "Browse two local directories side by side (dual-pane file manager)",
+    );
+    opts.optopt(
+        "",
+        "endpoint",
+        "Endpoint URL of S3-compatible object storages (e.g. MinIO); AWS if unset",
+        "<url>",
+    );
+    opts.optopt("", "region", "S3 region; default us-east-1", "<region>");
+    opts.optopt(
+        "r",
+        "root",
+        "Jail the remote explorer into <dir> for the whole session",
+        "<dir>",
+    );
     opts.optopt("T", "ticks", "Set UI ticks; default 10ms", "<ms>");
+    opts.optflag(
+        "t",
+        "trace",
+        "Trace remote operations to termscp.log in the configuration directory",
+    );
     opts.optflag("v", "version", "");
     opts.optflag("h", "help", "Print this menu");
     let matches = match opts.parse(&args[1..]) {
@@ -124,10 +483,79 @@ fn main() {
             }
         }
     }
+    // Match trace
+    if matches.opt_present("t") {
+        let trace_file: PathBuf = match system::environment::init_config_dir() {
+            Ok(Some(config_dir)) => system::environment::get_trace_path(config_dir.as_path()),
+            _ => system::environment::get_trace_path(env::temp_dir().as_path()),
+        };
+        if let Err(err) = system::trace::init_tracer(trace_file.as_path()) {
+            eprintln!("{}", err);
+            std::process::exit(255);
+        }
+    }
+    // Match session root
+    let root: Option<PathBuf> = match matches.opt_str("r") {
+        Some(dir) if dir.starts_with('/') => {
+            Some(utils::path::normalize_path(PathBuf::from(dir).as_path()))
+        }
+        Some(dir) => {
+            eprintln!("Session root must be an absolute path: '{}'", dir);
+            print_usage(opts);
+            std::process::exit(255);
+        }
+        None => None,
+    };
+    // Match S3 endpoint and region
+    let s3_endpoint: Option<String> = matches.opt_str("endpoint");
+    let s3_region: Option<String> = matches.opt_str("region");
+    // Demo mode
+    let demo: bool = matches.opt_present("D");
+    // Local-to-local mode
+    let local: bool = matches.opt_present("l");
     // Check free args
     let extra_args: Vec<String> = matches.free;
-    // Remote argument
-    if let Some(remote) = extra_args.get(0) {
+    // Benchmark mode
+    if extra_args.first().map(|x| x.as_str()) == Some("bench") {
+        match extra_args.get(1) {
+            Some(bookmark) => std::process::exit(run_bench(bookmark.as_str(), password)),
+            None => {
+                eprintln!("Missing bookmark name for bench");
+                print_usage(opts);
+                std::process::exit(255);
+            }
+        }
+    }
+    // Doctor mode
+    if extra_args.first().map(|x| x.as_str()) == Some("doctor") {
+        match extra_args.get(1) {
+            Some(remote) => std::process::exit(run_doctor(remote.as_str(), password)),
+            None => {
+                eprintln!("Missing address for doctor");
+                print_usage(opts);
+                std::process::exit(255);
+            }
+        }
+    }
+    // Daemon mode
+    if extra_args.first().map(|x| x.as_str()) == Some("daemon") {
+        std::process::exit(run_daemon(&extra_args[1..]));
+    }
+    // Attach to daemon
+    if extra_args.first().map(|x| x.as_str()) == Some("attach") {
+        std::process::exit(run_attach(ticks));
+    }
+    // Remote argument (a directory in local-to-local mode)
+    let mut local_remote_dir: Option<PathBuf> = None;
+    if let (true, Some(remote)) = (local, extra_args.first()) {
+        match PathBuf::from(remote).canonicalize() {
+            Ok(dir) => local_remote_dir = Some(dir),
+            Err(err) => {
+                eprintln!("Bad directory argument: {}", err);
+                std::process::exit(255);
+            }
+        }
+    } else if let Some(remote) = extra_args.get(0) {
         // Parse address
         match utils::parser::parse_remote_opt(remote) {
             Ok(host_opts) => {
@@ -161,25 +589,26 @@ fn main() {
     };
     // Initialize client if necessary
     let mut start_activity: NextActivity = NextActivity::Authentication;
-    if address.is_some() {
-        if password.is_none() {
-            // Ask password if unspecified
-            password = match rpassword::read_password_from_tty(Some("Password: ")) {
-                Ok(p) => {
-                    if p.is_empty() {
-                        None
-                    } else {
-                        Some(p)
-                    }
-                }
-                Err(_) => {
-                    eprintln!("Could not read password from prompt");
-                    std::process::exit(255);
+    // Ask password if unspecified (Kubernetes doesn't use it)
+    if address.is_some()
+        && !demo
+        && !local
+        && password.is_none()
+        && protocol != FileTransferProtocol::Kube
+    {
+        password = match rpassword::read_password_from_tty(Some("Password: ")) {
+            Ok(p) => {
+                if p.is_empty() {
+                    None
+                } else {
+                    Some(p)
                 }
-            };
-        }
-        // In this case the first activity will be FileTransfer
-        start_activity = NextActivity::FileTransfer;
+            }
+            Err(_) => {
+                eprintln!("Could not read password from prompt");
+                std::process::exit(255);
+            }
+        };
     }
     // Create activity manager (and context too)
     let mut manager: ActivityManager = match ActivityManager::new(&wrkdir, ticks) {
@@ -190,8 +619,28 @@ fn main() {
         }
     };
     // Set file transfer params if set
-    if let Some(address) = address {
-        manager.set_filetransfer_params(address, port, protocol, username, password, remote_wrkdir);
+    if demo {
+        manager.set_demo_mode();
+        start_activity = NextActivity::FileTransfer(Box::default());
+    } else if local {
+        manager.set_local_mode(local_remote_dir);
+        start_activity = NextActivity::FileTransfer(Box::default());
+    } else if let Some(address) = address {
+        // In this case the first activity will be FileTransfer
+        start_activity = NextActivity::FileTransfer(Box::new(FileTransferParams {
+            address,
+            port,
+            protocol,
+            username,
+            password: password.map(Secret::new),
+            entry_directory: remote_wrkdir,
+            endpoint: s3_endpoint,
+            region: s3_region,
+            algorithms: SshAlgorithms::default(),
+        }));
+    }
+    if let Some(root) = root {
+        manager.set_session_root(root);
     }
     // Run
     manager.run(start_activity);
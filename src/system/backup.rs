@@ -0,0 +1,318 @@
+//! ## Backup
+//!
+//! `backup` keeps a copy of the configuration files before they are overwritten and allows to
+//! restore them, either from the automatic backups or from an exported bundle
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::bookmarks::serializer::BookmarkSerializer;
+use crate::config::serializer::ConfigSerializer;
+// Ext
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Cursor};
+use std::path::{Path, PathBuf};
+
+/// ## RestoreSource
+///
+/// Where configuration is restored from
+pub enum RestoreSource {
+    Backups,         // `.bak` files next to the configuration files
+    Bundle(PathBuf), // Bundle exported with `export_bundle`
+}
+
+/// ## DiffLine
+///
+/// A line of the difference between the current and the restored file
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Added(String),
+    Removed(String),
+    Unchanged(String),
+}
+
+/// ## RestoreFile
+///
+/// A file which will be replaced by the restore
+pub struct RestoreFile {
+    pub path: PathBuf,
+    pub contents: String,
+    pub diff: Vec<DiffLine>,
+}
+
+/// ## RestorePlan
+///
+/// Files changed by a restore
+pub struct RestorePlan {
+    pub files: Vec<RestoreFile>,
+}
+
+/// ## Bundle
+///
+/// Exported configuration; each field contains the raw file content
+#[derive(Deserialize, Serialize, Default)]
+struct Bundle {
+    config: Option<String>,
+    bookmarks: Option<String>,
+}
+
+/// ### backup_path
+///
+/// Returns the path of the backup of `path` (e.g. `config.toml.bak`)
+pub fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".bak");
+    PathBuf::from(name)
+}
+
+/// ### backup_file
+///
+/// Copy `path` to its backup file. Does nothing if `path` doesn't exist
+pub fn backup_file(path: &Path) -> io::Result<()> {
+    match path.exists() {
+        true => fs::copy(path, backup_path(path)).map(|_| ()),
+        false => Ok(()),
+    }
+}
+
+/// ### export_bundle
+///
+/// Export configuration and bookmarks into a single bundle file at `dest`
+pub fn export_bundle(config: &Path, bookmarks: &Path, dest: &Path) -> Result<(), String> {
+    let bundle: Bundle = Bundle {
+        config: fs::read_to_string(config).ok(),
+        bookmarks: fs::read_to_string(bookmarks).ok(),
+    };
+    let data: String =
+        toml::ser::to_string(&bundle).map_err(|e| format!("Could not serialize bundle: {}", e))?;
+    fs::write(dest, data).map_err(|e| format!("Could not write \"{}\": {}", dest.display(), e))
+}
+
+/// ### plan_restore
+///
+/// Prepare the restore of configuration and bookmarks from `source`.
+/// Restored files are validated; files which wouldn't change are not part of the plan
+pub fn plan_restore(
+    source: &RestoreSource,
+    config: &Path,
+    bookmarks: &Path,
+) -> Result<RestorePlan, String> {
+    let bundle: Bundle = match source {
+        RestoreSource::Backups => Bundle {
+            config: fs::read_to_string(backup_path(config)).ok(),
+            bookmarks: fs::read_to_string(backup_path(bookmarks)).ok(),
+        },
+        RestoreSource::Bundle(path) => {
+            let data: String = fs::read_to_string(path)
+                .map_err(|e| format!("Could not read \"{}\": {}", path.display(), e))?;
+            toml::de::from_str(data.as_str())
+                .map_err(|e| format!("Invalid bundle \"{}\": {}", path.display(), e))?
+        }
+    };
+    if bundle.config.is_none() && bundle.bookmarks.is_none() {
+        return Err(String::from("There is nothing to restore"));
+    }
+    // Validate
+    if let Some(contents) = bundle.config.as_ref() {
+        ConfigSerializer {}
+            .deserialize(Box::new(Cursor::new(contents.clone().into_bytes())))
+            .map_err(|e| format!("Invalid configuration: {}", e))?;
+    }
+    if let Some(contents) = bundle.bookmarks.as_ref() {
+        BookmarkSerializer {}
+            .deserialize(Box::new(Cursor::new(contents.clone().into_bytes())))
+            .map_err(|e| format!("Invalid bookmarks: {}", e))?;
+    }
+    let files: Vec<RestoreFile> = vec![(config, bundle.config), (bookmarks, bundle.bookmarks)]
+        .into_iter()
+        .filter_map(|(path, contents)| contents.map(|x| (path, x)))
+        .map(|(path, contents)| {
+            let current: String = fs::read_to_string(path).unwrap_or_default();
+            RestoreFile {
+                path: path.to_path_buf(),
+                diff: diff_lines(current.as_str(), contents.as_str()),
+                contents,
+            }
+        })
+        .filter(|x| x.diff.iter().any(|l| !matches!(l, DiffLine::Unchanged(_))))
+        .collect();
+    Ok(RestorePlan { files })
+}
+
+impl RestorePlan {
+    /// ### is_empty
+    ///
+    /// Returns whether the restore wouldn't change anything
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// ### apply
+    ///
+    /// Replace the files with the restored ones. Current files are backed up first
+    pub fn apply(&self) -> io::Result<()> {
+        for file in self.files.iter() {
+            backup_file(file.path.as_path())?;
+            fs::write(file.path.as_path(), file.contents.as_bytes())?;
+        }
+        Ok(())
+    }
+}
+
+/// ### diff_lines
+///
+/// Make a line by line diff between `old` and `new` (longest common subsequence)
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffLine> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // lcs[i][j] is the LCS length of old[i..] and new[j..]
+    let mut lcs: Vec<Vec<usize>> = vec![vec![0; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = match old[i] == new[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+    let mut diff: Vec<DiffLine> = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(DiffLine::Unchanged(old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            diff.push(DiffLine::Added(new[j].to_string()));
+            j += 1;
+        } else {
+            diff.push(DiffLine::Removed(old[i].to_string()));
+            i += 1;
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_system_backup_diff_lines() {
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nc\nd\n"),
+            vec![
+                DiffLine::Unchanged(String::from("a")),
+                DiffLine::Removed(String::from("b")),
+                DiffLine::Unchanged(String::from("c")),
+                DiffLine::Added(String::from("d")),
+            ]
+        );
+        assert_eq!(
+            diff_lines("", "a"),
+            vec![DiffLine::Added(String::from("a"))]
+        );
+        assert!(diff_lines("", "").is_empty());
+    }
+
+    #[test]
+    fn test_system_backup_restore_from_backups() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let (config, bookmarks) = get_paths(&tmpdir);
+        // Nothing to restore
+        assert!(plan_restore(
+            &RestoreSource::Backups,
+            config.as_path(),
+            bookmarks.as_path()
+        )
+        .is_err());
+        // Write config, then backup and change it
+        fs::write(config.as_path(), CONFIG_V1).unwrap();
+        assert!(backup_file(config.as_path()).is_ok());
+        assert_eq!(
+            backup_path(config.as_path()),
+            tmpdir.path().join("config.toml.bak")
+        );
+        fs::write(config.as_path(), CONFIG_V2).unwrap();
+        // Plan
+        let plan: RestorePlan = plan_restore(
+            &RestoreSource::Backups,
+            config.as_path(),
+            bookmarks.as_path(),
+        )
+        .unwrap();
+        assert_eq!(plan.files.len(), 1);
+        assert!(plan.files[0]
+            .diff
+            .contains(&DiffLine::Removed(String::from("show_hidden_files = false"))));
+        assert!(plan.files[0]
+            .diff
+            .contains(&DiffLine::Added(String::from("show_hidden_files = true"))));
+        // Apply; current file is backed up
+        assert!(plan.apply().is_ok());
+        assert_eq!(fs::read_to_string(config.as_path()).unwrap(), CONFIG_V1);
+        assert_eq!(
+            fs::read_to_string(backup_path(config.as_path())).unwrap(),
+            CONFIG_V2
+        );
+    }
+
+    #[test]
+    fn test_system_backup_restore_from_bundle() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let (config, bookmarks) = get_paths(&tmpdir);
+        let bundle: PathBuf = tmpdir.path().join("termscp.bundle");
+        fs::write(config.as_path(), CONFIG_V1).unwrap();
+        assert!(export_bundle(config.as_path(), bookmarks.as_path(), bundle.as_path()).is_ok());
+        // Same configuration: nothing changes
+        let source: RestoreSource = RestoreSource::Bundle(bundle.clone());
+        let plan: RestorePlan =
+            plan_restore(&source, config.as_path(), bookmarks.as_path()).unwrap();
+        assert!(plan.is_empty());
+        // Change configuration
+        fs::write(config.as_path(), CONFIG_V2).unwrap();
+        let plan: RestorePlan =
+            plan_restore(&source, config.as_path(), bookmarks.as_path()).unwrap();
+        assert_eq!(plan.files.len(), 1);
+        assert!(plan.apply().is_ok());
+        assert_eq!(fs::read_to_string(config.as_path()).unwrap(), CONFIG_V1);
+        // Invalid bundle
+        fs::write(bundle.as_path(), "config = \"[user_interface]\"\n").unwrap();
+        assert!(plan_restore(&source, config.as_path(), bookmarks.as_path()).is_err());
+    }
+
+    fn get_paths(dir: &TempDir) -> (PathBuf, PathBuf) {
+        (
+            dir.path().join("config.toml"),
+            dir.path().join("bookmarks.toml"),
+        )
+    }
+
+    const CONFIG_V1: &str = "[user_interface]\ndefault_protocol = \"SFTP\"\ntext_editor = \"vim\"\nshow_hidden_files = true\n\n[remote.ssh_keys]\n";
+    const CONFIG_V2: &str = "[user_interface]\ndefault_protocol = \"SFTP\"\ntext_editor = \"vim\"\nshow_hidden_files = false\n\n[remote.ssh_keys]\n";
+}
@@ -28,13 +28,17 @@
 // Deps
 extern crate whoami;
 // Crate
+use super::backup;
 #[cfg(any(target_os = "windows", target_os = "macos"))]
 use super::keys::keyringstorage::KeyringStorage;
 use super::keys::{filestorage::FileStorage, KeyStorage, KeyStorageError};
 // Local
 use crate::bookmarks::serializer::BookmarkSerializer;
-use crate::bookmarks::{Bookmark, SerializerError, SerializerErrorKind, UserHosts};
-use crate::filetransfer::FileTransferProtocol;
+use crate::bookmarks::{
+    Bookmark, BookmarkTag, SavedSearch, SerializerError, SerializerErrorKind, UserHosts,
+};
+use crate::filetransfer::{FileTransferProtocol, SshAlgorithms};
+use crate::fs::transfer::TimeWindow;
 use crate::utils::crypto;
 use crate::utils::fmt::fmt_time;
 use crate::utils::random::random_alphanumeric_with_len;
@@ -45,6 +49,8 @@ use std::str::FromStr;
 use std::string::ToString;
 use std::time::SystemTime;
 
+const COMMANDS_HISTORY_SIZE: usize = 64; // Commands kept in history for each host
+
 /// ## BookmarksClient
 ///
 /// BookmarksClient provides a layer between the host system and the bookmarks module
@@ -53,6 +59,7 @@ pub struct BookmarksClient {
     bookmarks_file: PathBuf,
     key: String,
     recents_size: usize,
+    never_store_credentials: bool, // Passwords are never written to the bookmarks file
 }
 
 impl BookmarksClient {
@@ -121,6 +128,7 @@ impl BookmarksClient {
             bookmarks_file: PathBuf::from(bookmarks_file),
             key,
             recents_size,
+            never_store_credentials: false,
         };
         // If bookmark file doesn't exist, initialize it
         if !bookmarks_file.exists() {
@@ -137,6 +145,27 @@ impl BookmarksClient {
         Ok(client)
     }
 
+    /// ### set_never_store_credentials
+    ///
+    /// Set whether credentials must never be persisted.
+    /// If enabled, the passwords stored previously are forgotten and no host is added to recents.
+    /// Returns whether some password has been forgotten
+    pub fn set_never_store_credentials(&mut self, value: bool) -> bool {
+        self.never_store_credentials = value;
+        let mut forgotten: bool = false;
+        if value {
+            for host in self
+                .hosts
+                .bookmarks
+                .values_mut()
+                .chain(self.hosts.recents.values_mut())
+            {
+                forgotten |= host.password.take().is_some();
+            }
+        }
+        forgotten
+    }
+
     /// ### iter_bookmarks
     ///
     /// Iterate over bookmarks keys
@@ -186,11 +215,234 @@ impl BookmarksClient {
         if name.is_empty() {
             panic!("Bookmark name can't be empty");
         }
-        // Make bookmark
-        let host: Bookmark = self.make_bookmark(addr, port, protocol, username, password);
+        // Make bookmark; SSH algorithms, tag, protection and quiet hours of the replaced bookmark are kept
+        let mut host: Bookmark = self.make_bookmark(addr, port, protocol, username, password);
+        if let Some(prev) = self.hosts.bookmarks.get(name.as_str()) {
+            host.algorithms = prev.algorithms.clone();
+            host.tag = prev.tag.clone();
+            host.protected = prev.protected;
+            host.quiet_hours = prev.quiet_hours.clone();
+        }
         self.hosts.bookmarks.insert(name, host);
     }
 
+    /// ### clone_bookmark
+    ///
+    /// Copy the bookmark `key` into a new bookmark named `name`, switching its protocol to `protocol`.
+    /// If the bookmark uses the default port of its protocol, the default port of `protocol` is used.
+    /// S3 endpoint and region are kept only if `protocol` is S3; SSH algorithms only if it's SFTP or SCP.
+    /// Returns whether the bookmark has been cloned
+    pub fn clone_bookmark(
+        &mut self,
+        key: &str,
+        name: String,
+        protocol: FileTransferProtocol,
+    ) -> bool {
+        if name.is_empty() {
+            return false;
+        }
+        let entry: &Bookmark = match self.hosts.bookmarks.get(key) {
+            Some(entry) => entry,
+            None => return false,
+        };
+        let src_protocol: FileTransferProtocol =
+            FileTransferProtocol::from_str(entry.protocol.as_str())
+                .unwrap_or(FileTransferProtocol::Sftp);
+        let s3: bool = protocol == FileTransferProtocol::S3;
+        let ssh: bool = matches!(
+            protocol,
+            FileTransferProtocol::Sftp | FileTransferProtocol::Scp
+        );
+        let bookmark: Bookmark = Bookmark {
+            address: entry.address.clone(),
+            port: match entry.port == src_protocol.default_port() {
+                true => protocol.default_port(),
+                false => entry.port,
+            },
+            protocol: protocol.to_string(),
+            username: entry.username.clone(),
+            password: entry.password.clone(),
+            endpoint: entry.endpoint.clone().filter(|_| s3),
+            region: entry.region.clone().filter(|_| s3),
+            ephemeral: entry.ephemeral,
+            algorithms: entry.algorithms.clone().filter(|_| ssh),
+            tag: entry.tag.clone(),
+            protected: entry.protected,
+            quiet_hours: entry.quiet_hours.clone(),
+        };
+        self.hosts.bookmarks.insert(name, bookmark);
+        true
+    }
+
+    /// ### get_bookmark_s3_endpoint
+    ///
+    /// Get S3 endpoint URL and region of bookmark
+    pub fn get_bookmark_s3_endpoint(&self, key: &str) -> Option<(Option<String>, Option<String>)> {
+        let entry: &Bookmark = self.hosts.bookmarks.get(key)?;
+        Some((entry.endpoint.clone(), entry.region.clone()))
+    }
+
+    /// ### set_bookmark_s3_endpoint
+    ///
+    /// Set S3 endpoint URL and region of bookmark, if it exists
+    pub fn set_bookmark_s3_endpoint(
+        &mut self,
+        key: &str,
+        endpoint: Option<String>,
+        region: Option<String>,
+    ) {
+        if let Some(entry) = self.hosts.bookmarks.get_mut(key) {
+            entry.endpoint = endpoint;
+            entry.region = region;
+        }
+    }
+
+    /// ### get_bookmark_ssh_algorithms
+    ///
+    /// Get the SSH algorithms preferred by bookmark
+    pub fn get_bookmark_ssh_algorithms(&self, key: &str) -> Option<SshAlgorithms> {
+        self.hosts.bookmarks.get(key)?.algorithms.clone()
+    }
+
+    /// ### set_bookmark_ssh_algorithms
+    ///
+    /// Set the SSH algorithms preferred by bookmark, if it exists. Empty preferences are removed
+    pub fn set_bookmark_ssh_algorithms(&mut self, key: &str, algorithms: SshAlgorithms) {
+        if let Some(entry) = self.hosts.bookmarks.get_mut(key) {
+            entry.algorithms = Some(algorithms).filter(|x| !x.is_empty());
+        }
+    }
+
+    /// ### set_bookmark_ephemeral
+    ///
+    /// Set whether credentials must never be persisted for the host of bookmark `key`.
+    /// The password of an ephemeral bookmark is forgotten and the host is removed from recents
+    pub fn set_bookmark_ephemeral(&mut self, key: &str, ephemeral: bool) {
+        if let Some(entry) = self.hosts.bookmarks.get_mut(key) {
+            entry.ephemeral = Some(ephemeral).filter(|x| *x);
+            if ephemeral {
+                entry.password = None;
+                let entry: &Bookmark = &self.hosts.bookmarks[key];
+                let recents = &mut self.hosts.recents;
+                recents.retain(|_, x| !Self::same_host(entry, x));
+            }
+        }
+    }
+
+    /// ### get_bookmark_tag
+    ///
+    /// Get the color and label of bookmark
+    pub fn get_bookmark_tag(&self, key: &str) -> Option<BookmarkTag> {
+        self.hosts.bookmarks.get(key)?.tag.clone()
+    }
+
+    /// ### set_bookmark_tag
+    ///
+    /// Set the color and label of bookmark, if it exists. `None` removes the tag
+    pub fn set_bookmark_tag(&mut self, key: &str, tag: Option<BookmarkTag>) {
+        if let Some(entry) = self.hosts.bookmarks.get_mut(key) {
+            entry.tag = tag;
+        }
+    }
+
+    /// ### get_host_tag
+    ///
+    /// Get the tag of the first bookmark pointing to the host identified by `host`.
+    /// Host key can be obtained with `make_host_key`
+    pub fn get_host_tag(&self, host: &str) -> Option<BookmarkTag> {
+        let mut bookmarks: Vec<(&String, &Bookmark)> = self.hosts.bookmarks.iter().collect();
+        // Sort by name, so that the same tag is always returned
+        bookmarks.sort_by(|a, b| a.0.cmp(b.0));
+        bookmarks
+            .into_iter()
+            .filter(|(_, x)| x.tag.is_some())
+            .find(|(_, x)| {
+                let protocol: FileTransferProtocol =
+                    FileTransferProtocol::from_str(x.protocol.as_str())
+                        .unwrap_or(FileTransferProtocol::Sftp);
+                Self::make_host_key(x.address.as_str(), x.port, protocol, x.username.as_str())
+                    == host
+            })
+            .and_then(|(_, x)| x.tag.clone())
+    }
+
+    /// ### is_bookmark_protected
+    ///
+    /// Returns whether destructive operations on the host of bookmark require a confirmation phrase
+    pub fn is_bookmark_protected(&self, key: &str) -> bool {
+        self.hosts
+            .bookmarks
+            .get(key)
+            .and_then(|x| x.protected)
+            .unwrap_or(false)
+    }
+
+    /// ### set_bookmark_protected
+    ///
+    /// Set whether destructive operations on the host of bookmark require a confirmation phrase
+    pub fn set_bookmark_protected(&mut self, key: &str, protected: bool) {
+        if let Some(entry) = self.hosts.bookmarks.get_mut(key) {
+            entry.protected = Some(protected).filter(|x| *x);
+        }
+    }
+
+    /// ### is_host_protected
+    ///
+    /// Returns whether any bookmark pointing to the host identified by `host` is protected.
+    /// Host key can be obtained with `make_host_key`
+    pub fn is_host_protected(&self, host: &str) -> bool {
+        self.hosts.bookmarks.values().any(|x| {
+            let protocol: FileTransferProtocol =
+                FileTransferProtocol::from_str(x.protocol.as_str())
+                    .unwrap_or(FileTransferProtocol::Sftp);
+            x.protected.unwrap_or(false)
+                && Self::make_host_key(x.address.as_str(), x.port, protocol, x.username.as_str())
+                    == host
+        })
+    }
+
+    /// ### get_bookmark_quiet_hours
+    ///
+    /// Get the daily time range transfers with the host of bookmark are paused in; invalid ranges are ignored
+    pub fn get_bookmark_quiet_hours(&self, key: &str) -> Option<TimeWindow> {
+        self.hosts
+            .bookmarks
+            .get(key)?
+            .quiet_hours
+            .as_deref()
+            .and_then(|x| TimeWindow::from_str(x).ok())
+    }
+
+    /// ### set_bookmark_quiet_hours
+    ///
+    /// Set the daily time range transfers with the host of bookmark are paused in. `None` removes it
+    pub fn set_bookmark_quiet_hours(&mut self, key: &str, quiet_hours: Option<TimeWindow>) {
+        if let Some(entry) = self.hosts.bookmarks.get_mut(key) {
+            entry.quiet_hours = quiet_hours.map(|x| x.to_string());
+        }
+    }
+
+    /// ### get_host_quiet_hours
+    ///
+    /// Get the quiet hours of the first bookmark pointing to the host identified by `host`.
+    /// Host key can be obtained with `make_host_key`
+    pub fn get_host_quiet_hours(&self, host: &str) -> Option<TimeWindow> {
+        let mut bookmarks: Vec<(&String, &Bookmark)> = self.hosts.bookmarks.iter().collect();
+        // Sort by name, so that the same quiet hours are always returned
+        bookmarks.sort_by(|a, b| a.0.cmp(b.0));
+        bookmarks
+            .into_iter()
+            .filter(|(_, x)| x.quiet_hours.is_some())
+            .find(|(_, x)| {
+                let protocol: FileTransferProtocol =
+                    FileTransferProtocol::from_str(x.protocol.as_str())
+                        .unwrap_or(FileTransferProtocol::Sftp);
+                Self::make_host_key(x.address.as_str(), x.port, protocol, x.username.as_str())
+                    == host
+            })
+            .and_then(|(key, _)| self.get_bookmark_quiet_hours(key))
+    }
+
     /// ### del_bookmark
     ///
     /// Delete entry from bookmarks
@@ -233,6 +485,10 @@ impl BookmarksClient {
     ) {
         // Make bookmark
         let host: Bookmark = self.make_bookmark(addr, port, protocol, username, None);
+        // Ephemeral hosts are never saved
+        if self.is_ephemeral(&host) {
+            return;
+        }
         // Check if duplicated
         for recent_host in self.hosts.recents.values() {
             if *recent_host == host {
@@ -269,10 +525,107 @@ impl BookmarksClient {
         let _ = self.hosts.recents.remove(name);
     }
 
+    /// ### iter_commands
+    ///
+    /// Iterate over the commands executed on host, starting from the most recent one.
+    /// Host key can be obtained with `make_host_key`
+    pub fn iter_commands(&self, host: &str) -> impl Iterator<Item = &String> + '_ {
+        Box::new(self.hosts.commands.get(host).into_iter().flatten())
+    }
+
+    /// ### add_command
+    ///
+    /// Add a command to the history of host.
+    /// If the command is already in the history, it's moved on top
+    pub fn add_command(&mut self, host: &str, cmd: &str) {
+        if cmd.is_empty() {
+            return;
+        }
+        let commands: &mut Vec<String> = self.hosts.commands.entry(host.to_string()).or_default();
+        commands.retain(|x| x != cmd);
+        commands.insert(0, cmd.to_string());
+        commands.truncate(COMMANDS_HISTORY_SIZE);
+    }
+
+    /// ### iter_transfer_filters
+    ///
+    /// Iterate over the include/exclude patterns applied to the recursive transfers on host.
+    /// Host key can be obtained with `make_host_key`
+    pub fn iter_transfer_filters(&self, host: &str) -> impl Iterator<Item = &String> + '_ {
+        Box::new(self.hosts.filters.get(host).into_iter().flatten())
+    }
+
+    /// ### set_transfer_filters
+    ///
+    /// Set the include/exclude patterns of host; empty patterns remove the filters
+    pub fn set_transfer_filters(&mut self, host: &str, patterns: &[String]) {
+        match patterns.is_empty() {
+            true => {
+                let _ = self.hosts.filters.remove(host);
+            }
+            false => {
+                let _ = self
+                    .hosts
+                    .filters
+                    .insert(host.to_string(), patterns.to_vec());
+            }
+        }
+    }
+
+    /// ### iter_saved_searches
+    ///
+    /// Iterate over the searches saved for host.
+    /// Host key can be obtained with `make_host_key`
+    pub fn iter_saved_searches(&self, host: &str) -> impl Iterator<Item = &SavedSearch> + '_ {
+        Box::new(self.hosts.searches.get(host).into_iter().flatten())
+    }
+
+    /// ### set_saved_searches
+    ///
+    /// Set the searches saved for host; no searches remove the entry of host
+    pub fn set_saved_searches(&mut self, host: &str, searches: &[SavedSearch]) {
+        match searches.is_empty() {
+            true => {
+                let _ = self.hosts.searches.remove(host);
+            }
+            false => {
+                let _ = self
+                    .hosts
+                    .searches
+                    .insert(host.to_string(), searches.to_vec());
+            }
+        }
+    }
+
+    /// ### make_host_key
+    ///
+    /// Make the key which identifies a host in the commands history, in the transfer filters and in the saved searches
+    pub fn make_host_key(
+        addr: &str,
+        port: u16,
+        protocol: FileTransferProtocol,
+        username: &str,
+    ) -> String {
+        format!(
+            "{}://{}@{}:{}",
+            protocol.to_string().to_lowercase(),
+            username,
+            addr,
+            port
+        )
+    }
+
     /// ### write_bookmarks
     ///
     /// Write bookmarks to file
     pub fn write_bookmarks(&self) -> Result<(), SerializerError> {
+        // Keep a copy of the previous bookmarks
+        if let Err(err) = backup::backup_file(self.bookmarks_file.as_path()) {
+            return Err(SerializerError::new_ex(
+                SerializerErrorKind::IoError,
+                format!("Could not backup bookmarks: {}", err),
+            ));
+        }
         // Open file
         match OpenOptions::new()
             .create(true)
@@ -342,10 +695,41 @@ impl BookmarksClient {
             port,
             username,
             protocol: protocol.to_string(),
-            password: password.map(|p| self.encrypt_str(p.as_str())),
+            password: password
+                .filter(|_| !self.never_store_credentials)
+                .map(|p| self.encrypt_str(p.as_str())),
+            endpoint: None,
+            region: None,
+            ephemeral: None,
+            algorithms: None,
+            tag: None,
+            protected: None,
+            quiet_hours: None,
         }
     }
 
+    /// ### is_ephemeral
+    ///
+    /// Returns whether credentials must never be persisted for `host`
+    fn is_ephemeral(&self, host: &Bookmark) -> bool {
+        self.never_store_credentials
+            || self
+                .hosts
+                .bookmarks
+                .values()
+                .any(|x| x.ephemeral.unwrap_or(false) && Self::same_host(x, host))
+    }
+
+    /// ### same_host
+    ///
+    /// Returns whether two bookmarks point to the same host with the same user
+    fn same_host(a: &Bookmark, b: &Bookmark) -> bool {
+        a.address == b.address
+            && a.port == b.port
+            && a.protocol == b.protocol
+            && a.username == b.username
+    }
+
     /// ### encrypt_str
     ///
     /// Encrypt provided string using AES-128. Encrypted buffer is then converted to BASE64
@@ -372,6 +756,7 @@ impl BookmarksClient {
 mod tests {
 
     use super::*;
+    use crate::bookmarks::TagColor;
     use std::thread::sleep;
     use std::time::Duration;
 
@@ -489,6 +874,24 @@ mod tests {
         assert_eq!(bookmark.2, FileTransferProtocol::Sftp);
         assert_eq!(bookmark.3, String::from("pi"));
         assert_eq!(*bookmark.4.as_ref().unwrap(), String::from("mypassword"));
+        // S3 endpoint
+        assert_eq!(
+            client.get_bookmark_s3_endpoint("raspberry").unwrap(),
+            (None, None)
+        );
+        client.set_bookmark_s3_endpoint(
+            "raspberry2",
+            Some(String::from("http://127.0.0.1:9000")),
+            Some(String::from("eu-west-1")),
+        );
+        assert_eq!(
+            client.get_bookmark_s3_endpoint("raspberry2").unwrap(),
+            (
+                Some(String::from("http://127.0.0.1:9000")),
+                Some(String::from("eu-west-1"))
+            )
+        );
+        assert!(client.get_bookmark_s3_endpoint("omar").is_none());
         // Write bookmarks
         assert!(client.write_bookmarks().is_ok());
         // Delete bookmark
@@ -499,6 +902,62 @@ mod tests {
         assert!(client.write_bookmarks().is_ok());
     }
 
+    #[test]
+    fn test_system_bookmarks_clone_bookmark() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_bookmark(
+            String::from("legacy"),
+            String::from("192.168.1.31"),
+            21,
+            FileTransferProtocol::Ftp(false),
+            String::from("pi"),
+            Some(String::from("mypassword")),
+        );
+        client.add_bookmark(
+            String::from("custom"),
+            String::from("192.168.1.32"),
+            2121,
+            FileTransferProtocol::Ftp(false),
+            String::from("pi"),
+            None,
+        );
+        // Default port is adjusted
+        assert!(client.clone_bookmark(
+            "legacy",
+            String::from("legacy-sftp"),
+            FileTransferProtocol::Sftp
+        ));
+        let bookmark: (String, u16, FileTransferProtocol, String, Option<String>) =
+            client.get_bookmark("legacy-sftp").unwrap();
+        assert_eq!(bookmark.0, String::from("192.168.1.31"));
+        assert_eq!(bookmark.1, 22);
+        assert_eq!(bookmark.2, FileTransferProtocol::Sftp);
+        assert_eq!(bookmark.3, String::from("pi"));
+        assert_eq!(bookmark.4.unwrap(), String::from("mypassword"));
+        // Original bookmark is kept
+        assert_eq!(
+            client.get_bookmark("legacy").unwrap().2,
+            FileTransferProtocol::Ftp(false)
+        );
+        // Custom port is kept
+        assert!(client.clone_bookmark(
+            "custom",
+            String::from("custom"),
+            FileTransferProtocol::Ftp(true)
+        ));
+        let bookmark: (String, u16, FileTransferProtocol, String, Option<String>) =
+            client.get_bookmark("custom").unwrap();
+        assert_eq!(bookmark.1, 2121);
+        assert_eq!(bookmark.2, FileTransferProtocol::Ftp(true));
+        // Bad clones
+        assert!(!client.clone_bookmark("omar", String::from("omar2"), FileTransferProtocol::Sftp));
+        assert!(!client.clone_bookmark("legacy", String::new(), FileTransferProtocol::Sftp));
+        assert_eq!(client.iter_bookmarks().count(), 3);
+    }
+
     #[test]
     #[should_panic]
 
@@ -554,6 +1013,289 @@ mod tests {
         assert!(client.write_bookmarks().is_ok());
     }
 
+    #[test]
+    fn test_system_bookmarks_ephemeral_bookmark() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_recent(
+            String::from("bastion.example.com"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("omar"),
+        );
+        client.add_bookmark(
+            String::from("bastion"),
+            String::from("bastion.example.com"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("omar"),
+            Some(String::from("123456")),
+        );
+        client.set_bookmark_ephemeral("bastion", true);
+        // Password is forgotten and host is removed from recents
+        assert!(client.get_bookmark("bastion").unwrap().4.is_none());
+        assert_eq!(client.iter_recents().count(), 0);
+        client.add_recent(
+            String::from("bastion.example.com"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("omar"),
+        );
+        assert_eq!(client.iter_recents().count(), 0);
+        // Other users are saved
+        client.add_recent(
+            String::from("bastion.example.com"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("root"),
+        );
+        assert_eq!(client.iter_recents().count(), 1);
+        // Clone keeps flag
+        assert!(client.clone_bookmark(
+            "bastion",
+            String::from("bastion2"),
+            FileTransferProtocol::Scp
+        ));
+        assert_eq!(client.hosts.bookmarks["bastion2"].ephemeral, Some(true));
+        assert!(client.write_bookmarks().is_ok());
+    }
+
+    #[test]
+    fn test_system_bookmarks_ssh_algorithms() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_bookmark(
+            String::from("switch"),
+            String::from("10.0.0.2"),
+            22,
+            FileTransferProtocol::Scp,
+            String::from("admin"),
+            None,
+        );
+        assert!(client.get_bookmark_ssh_algorithms("switch").is_none());
+        let algorithms: SshAlgorithms = SshAlgorithms {
+            kex: Some(String::from("diffie-hellman-group1-sha1")),
+            host_key: None,
+            cipher: Some(String::from("aes128-cbc,3des-cbc")),
+            mac: None,
+        };
+        client.set_bookmark_ssh_algorithms("switch", algorithms.clone());
+        assert_eq!(
+            client.get_bookmark_ssh_algorithms("switch").as_ref(),
+            Some(&algorithms)
+        );
+        // Algorithms are kept when the bookmark is saved again
+        client.add_bookmark(
+            String::from("switch"),
+            String::from("10.0.0.2"),
+            2222,
+            FileTransferProtocol::Scp,
+            String::from("admin"),
+            None,
+        );
+        assert_eq!(
+            client.get_bookmark_ssh_algorithms("switch").as_ref(),
+            Some(&algorithms)
+        );
+        // Clone keeps them for SSH protocols only
+        assert!(client.clone_bookmark("switch", String::from("sftp"), FileTransferProtocol::Sftp));
+        assert!(client.get_bookmark_ssh_algorithms("sftp").is_some());
+        assert!(client.clone_bookmark(
+            "switch",
+            String::from("ftp"),
+            FileTransferProtocol::Ftp(false)
+        ));
+        assert!(client.get_bookmark_ssh_algorithms("ftp").is_none());
+        assert!(client.write_bookmarks().is_ok());
+        // Empty preferences are removed
+        client.set_bookmark_ssh_algorithms("switch", SshAlgorithms::default());
+        assert!(client.get_bookmark_ssh_algorithms("switch").is_none());
+    }
+
+    #[test]
+    fn test_system_bookmarks_tag() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_bookmark(
+            String::from("prod"),
+            String::from("10.0.0.1"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("deploy"),
+            None,
+        );
+        assert!(client.get_bookmark_tag("prod").is_none());
+        let tag: BookmarkTag = BookmarkTag {
+            color: TagColor::Red,
+            label: String::from("PRODUCTION"),
+        };
+        client.set_bookmark_tag("prod", Some(tag.clone()));
+        assert_eq!(client.get_bookmark_tag("prod").as_ref(), Some(&tag));
+        // Tag is kept when the bookmark is saved again and when it's cloned
+        client.add_bookmark(
+            String::from("prod"),
+            String::from("10.0.0.1"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("deploy"),
+            None,
+        );
+        assert_eq!(client.get_bookmark_tag("prod").as_ref(), Some(&tag));
+        assert!(client.clone_bookmark("prod", String::from("prod-scp"), FileTransferProtocol::Scp));
+        assert_eq!(client.get_bookmark_tag("prod-scp").as_ref(), Some(&tag));
+        // Get tag by host
+        let host: String =
+            BookmarksClient::make_host_key("10.0.0.1", 22, FileTransferProtocol::Sftp, "deploy");
+        assert_eq!(client.get_host_tag(host.as_str()).as_ref(), Some(&tag));
+        let host: String =
+            BookmarksClient::make_host_key("10.0.0.1", 22, FileTransferProtocol::Sftp, "root");
+        assert!(client.get_host_tag(host.as_str()).is_none());
+        // Tag is persisted
+        assert!(client.write_bookmarks().is_ok());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        assert_eq!(client.get_bookmark_tag("prod").as_ref(), Some(&tag));
+        // Remove tag
+        client.set_bookmark_tag("prod", None);
+        assert!(client.get_bookmark_tag("prod").is_none());
+    }
+
+    #[test]
+    fn test_system_bookmarks_protected() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_bookmark(
+            String::from("prod"),
+            String::from("10.0.0.1"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("deploy"),
+            None,
+        );
+        let host: String =
+            BookmarksClient::make_host_key("10.0.0.1", 22, FileTransferProtocol::Sftp, "deploy");
+        assert_eq!(client.is_bookmark_protected("prod"), false);
+        assert_eq!(client.is_host_protected(host.as_str()), false);
+        client.set_bookmark_protected("prod", true);
+        assert_eq!(client.is_bookmark_protected("prod"), true);
+        assert_eq!(client.is_host_protected(host.as_str()), true);
+        assert_eq!(
+            client.is_host_protected(
+                BookmarksClient::make_host_key("10.0.0.1", 22, FileTransferProtocol::Sftp, "root")
+                    .as_str()
+            ),
+            false
+        );
+        // Protection is kept when the bookmark is saved again and when it's cloned
+        client.add_bookmark(
+            String::from("prod"),
+            String::from("10.0.0.1"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("deploy"),
+            None,
+        );
+        assert_eq!(client.is_bookmark_protected("prod"), true);
+        assert!(client.clone_bookmark("prod", String::from("prod-scp"), FileTransferProtocol::Scp));
+        assert_eq!(client.is_bookmark_protected("prod-scp"), true);
+        // Unprotect
+        client.set_bookmark_protected("prod", false);
+        assert!(client.hosts.bookmarks["prod"].protected.is_none());
+        assert_eq!(client.is_host_protected(host.as_str()), false);
+    }
+
+    #[test]
+    fn test_system_bookmarks_quiet_hours() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_bookmark(
+            String::from("office"),
+            String::from("10.0.0.1"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("omar"),
+            None,
+        );
+        let host: String =
+            BookmarksClient::make_host_key("10.0.0.1", 22, FileTransferProtocol::Sftp, "omar");
+        assert!(client.get_bookmark_quiet_hours("office").is_none());
+        assert!(client.get_host_quiet_hours(host.as_str()).is_none());
+        let window: TimeWindow = TimeWindow::from_str("09:00-18:00").unwrap();
+        client.set_bookmark_quiet_hours("office", Some(window));
+        assert_eq!(client.get_bookmark_quiet_hours("office"), Some(window));
+        assert_eq!(client.get_host_quiet_hours(host.as_str()), Some(window));
+        // Quiet hours are kept when the bookmark is saved again and persisted
+        client.add_bookmark(
+            String::from("office"),
+            String::from("10.0.0.1"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("omar"),
+            None,
+        );
+        assert!(client.write_bookmarks().is_ok());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        assert_eq!(client.get_bookmark_quiet_hours("office"), Some(window));
+        // Invalid ranges are ignored
+        client
+            .hosts
+            .bookmarks
+            .get_mut("office")
+            .unwrap()
+            .quiet_hours = Some(String::from("morning"));
+        assert!(client.get_bookmark_quiet_hours("office").is_none());
+        client.set_bookmark_quiet_hours("office", None);
+        assert!(client.hosts.bookmarks["office"].quiet_hours.is_none());
+    }
+
+    #[test]
+    fn test_system_bookmarks_never_store_credentials() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        client.add_bookmark(
+            String::from("raspberry"),
+            String::from("192.168.1.31"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("pi"),
+            Some(String::from("mypassword")),
+        );
+        assert!(client.set_never_store_credentials(true));
+        // Stored passwords are forgotten
+        assert!(client.get_bookmark("raspberry").unwrap().4.is_none());
+        assert!(!client.set_never_store_credentials(true));
+        client.add_bookmark(
+            String::from("beaglebone"),
+            String::from("192.168.1.32"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("root"),
+            Some(String::from("mypassword")),
+        );
+        assert!(client.get_bookmark("beaglebone").unwrap().4.is_none());
+        // Recents are not saved
+        client.add_recent(
+            String::from("192.168.1.33"),
+            22,
+            FileTransferProtocol::Sftp,
+            String::from("pi"),
+        );
+        assert_eq!(client.iter_recents().count(), 0);
+    }
+
     #[test]
 
     fn test_system_bookmarks_dup_recent() {
@@ -650,6 +1392,137 @@ mod tests {
     ///
     /// Get paths for configuration and key for bookmarks
 
+    #[test]
+    fn test_system_bookmarks_commands() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        let host: String =
+            BookmarksClient::make_host_key("192.168.1.31", 22, FileTransferProtocol::Sftp, "pi");
+        assert_eq!(host.as_str(), "sftp://pi@192.168.1.31:22");
+        assert_eq!(client.iter_commands(host.as_str()).count(), 0);
+        // Add commands
+        client.add_command(host.as_str(), "uptime");
+        client.add_command(host.as_str(), "df -h");
+        client.add_command(host.as_str(), "");
+        client.add_command("scp://omar@10.0.0.1:22", "whoami");
+        assert_eq!(
+            client
+                .iter_commands(host.as_str())
+                .collect::<Vec<&String>>(),
+            vec!["df -h", "uptime"]
+        );
+        // Duplicated command is moved on top
+        client.add_command(host.as_str(), "uptime");
+        assert_eq!(
+            client
+                .iter_commands(host.as_str())
+                .collect::<Vec<&String>>(),
+            vec!["uptime", "df -h"]
+        );
+        // History is limited
+        for i in 0..100 {
+            client.add_command(host.as_str(), format!("echo {}", i).as_str());
+        }
+        assert_eq!(
+            client.iter_commands(host.as_str()).count(),
+            COMMANDS_HISTORY_SIZE
+        );
+        assert_eq!(
+            client.iter_commands(host.as_str()).next().unwrap().as_str(),
+            "echo 99"
+        );
+        // History is persisted
+        assert!(client.write_bookmarks().is_ok());
+        let client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        assert_eq!(
+            client.iter_commands(host.as_str()).count(),
+            COMMANDS_HISTORY_SIZE
+        );
+        assert_eq!(
+            client
+                .iter_commands("scp://omar@10.0.0.1:22")
+                .collect::<Vec<&String>>(),
+            vec!["whoami"]
+        );
+    }
+
+    #[test]
+    fn test_system_bookmarks_transfer_filters() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        let host: String =
+            BookmarksClient::make_host_key("192.168.1.31", 22, FileTransferProtocol::Sftp, "pi");
+        assert_eq!(client.iter_transfer_filters(host.as_str()).count(), 0);
+        client.set_transfer_filters(
+            host.as_str(),
+            &[String::from("node_modules/"), String::from("*.o")],
+        );
+        // Filters are persisted
+        assert!(client.write_bookmarks().is_ok());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        assert_eq!(
+            client
+                .iter_transfer_filters(host.as_str())
+                .collect::<Vec<&String>>(),
+            vec!["node_modules/", "*.o"]
+        );
+        assert_eq!(
+            client
+                .iter_transfer_filters("scp://omar@10.0.0.1:22")
+                .count(),
+            0
+        );
+        // Remove filters
+        client.set_transfer_filters(host.as_str(), &[]);
+        assert_eq!(client.iter_transfer_filters(host.as_str()).count(), 0);
+    }
+
+    #[test]
+    fn test_system_bookmarks_saved_searches() {
+        let tmp_dir: tempfile::TempDir = create_tmp_dir();
+        let (cfg_path, key_path): (PathBuf, PathBuf) = get_paths(tmp_dir.path());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        let host: String =
+            BookmarksClient::make_host_key("192.168.1.31", 22, FileTransferProtocol::Sftp, "pi");
+        assert_eq!(client.iter_saved_searches(host.as_str()).count(), 0);
+        let searches: Vec<SavedSearch> = vec![
+            SavedSearch {
+                name: String::from("recent logs"),
+                query: String::from("*.log in:/var/log newer:1d"),
+            },
+            SavedSearch {
+                name: String::from("images"),
+                query: String::from("*.png"),
+            },
+        ];
+        client.set_saved_searches(host.as_str(), searches.as_slice());
+        // Searches are persisted
+        assert!(client.write_bookmarks().is_ok());
+        let mut client: BookmarksClient =
+            BookmarksClient::new(cfg_path.as_path(), key_path.as_path(), 16).unwrap();
+        assert_eq!(
+            client
+                .iter_saved_searches(host.as_str())
+                .cloned()
+                .collect::<Vec<SavedSearch>>(),
+            searches
+        );
+        assert_eq!(
+            client.iter_saved_searches("scp://omar@10.0.0.1:22").count(),
+            0
+        );
+        // Remove searches
+        client.set_saved_searches(host.as_str(), &[]);
+        assert_eq!(client.iter_saved_searches(host.as_str()).count(), 0);
+    }
+
     fn get_paths(dir: &Path) -> (PathBuf, PathBuf) {
         let k: PathBuf = PathBuf::from(dir);
         let mut c: PathBuf = k.clone();
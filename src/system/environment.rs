@@ -87,6 +87,66 @@ pub fn get_config_paths(config_dir: &Path) -> (PathBuf, PathBuf) {
     (bookmarks_file, keys_dir)
 }
 
+/// ### get_trace_path
+///
+/// Get path for the trace file
+/// Returns: path of termscp.log
+pub fn get_trace_path(config_dir: &Path) -> PathBuf {
+    let mut trace_file: PathBuf = PathBuf::from(config_dir);
+    trace_file.push("termscp.log");
+    trace_file
+}
+
+/// ### get_transfer_history_path
+///
+/// Get path for the history of the transfers
+/// Returns: path of transfers.log
+pub fn get_transfer_history_path(config_dir: &Path) -> PathBuf {
+    let mut history_file: PathBuf = PathBuf::from(config_dir);
+    history_file.push("transfers.log");
+    history_file
+}
+
+/// ### get_hash_cache_path
+///
+/// Get path for the cache of the digests of local files
+/// Returns: path of hashes.json
+pub fn get_hash_cache_path(config_dir: &Path) -> PathBuf {
+    let mut cache_file: PathBuf = PathBuf::from(config_dir);
+    cache_file.push("hashes.json");
+    cache_file
+}
+
+/// ### get_queue_journal_path
+///
+/// Get path for the journal of the transfer queues being run
+/// Returns: path of queues.json
+pub fn get_queue_journal_path(config_dir: &Path) -> PathBuf {
+    let mut journal_file: PathBuf = PathBuf::from(config_dir);
+    journal_file.push("queues.json");
+    journal_file
+}
+
+/// ### get_known_hosts_path
+///
+/// Get path for the known hosts file
+/// Returns: path of known_hosts
+pub fn get_known_hosts_path(config_dir: &Path) -> PathBuf {
+    let mut known_hosts: PathBuf = PathBuf::from(config_dir);
+    known_hosts.push("known_hosts");
+    known_hosts
+}
+
+/// ### get_daemon_socket_path
+///
+/// Get path for the control socket of the daemon
+/// Returns: path of daemon.sock
+pub fn get_daemon_socket_path(config_dir: &Path) -> PathBuf {
+    let mut socket: PathBuf = PathBuf::from(config_dir);
+    socket.push("daemon.sock");
+    socket
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -124,6 +184,14 @@ mod tests {
         assert!(std::fs::remove_file(conf_dir.as_path()).is_ok());
     }
 
+    #[test]
+    fn test_system_environment_get_known_hosts_path() {
+        assert_eq!(
+            get_known_hosts_path(Path::new("/home/omar/.config/termscp/")),
+            PathBuf::from("/home/omar/.config/termscp/known_hosts"),
+        );
+    }
+
     #[test]
     fn test_system_environment_get_bookmarks_paths() {
         assert_eq!(
@@ -132,6 +200,46 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_system_environment_get_trace_path() {
+        assert_eq!(
+            get_trace_path(Path::new("/home/omar/.config/termscp/")),
+            PathBuf::from("/home/omar/.config/termscp/termscp.log"),
+        );
+    }
+
+    #[test]
+    fn test_system_environment_get_transfer_history_path() {
+        assert_eq!(
+            get_transfer_history_path(Path::new("/home/omar/.config/termscp/")),
+            PathBuf::from("/home/omar/.config/termscp/transfers.log"),
+        );
+    }
+
+    #[test]
+    fn test_system_environment_get_hash_cache_path() {
+        assert_eq!(
+            get_hash_cache_path(Path::new("/home/omar/.config/termscp/")),
+            PathBuf::from("/home/omar/.config/termscp/hashes.json"),
+        );
+    }
+
+    #[test]
+    fn test_system_environment_get_queue_journal_path() {
+        assert_eq!(
+            get_queue_journal_path(Path::new("/home/omar/.config/termscp/")),
+            PathBuf::from("/home/omar/.config/termscp/queues.json"),
+        );
+    }
+
+    #[test]
+    fn test_system_environment_get_daemon_socket_path() {
+        assert_eq!(
+            get_daemon_socket_path(Path::new("/home/omar/.config/termscp/")),
+            PathBuf::from("/home/omar/.config/termscp/daemon.sock"),
+        );
+    }
+
     #[test]
     fn test_system_environment_get_config_paths() {
         assert_eq!(
@@ -0,0 +1,187 @@
+//! ## HashCache
+//!
+//! `hash_cache` is the module which keeps the SHA256 digest of local files,
+//! so that files which haven't changed since they've been hashed aren't read again
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// ## CachedHash
+///
+/// The digest of a file, valid as long as the file keeps its size and modification time
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+struct CachedHash {
+    size: u64,
+    mtime: u64, // Nanoseconds since unix epoch
+    digest: String,
+}
+
+/// ## HashCache
+///
+/// The cache file of the digests of local files, by absolute path
+pub struct HashCache {
+    path: PathBuf,
+    hashes: HashMap<PathBuf, CachedHash>,
+    dirty: bool, // Whether some digests haven't been saved yet
+}
+
+impl HashCache {
+    /// ### load
+    ///
+    /// Load the `HashCache` stored at `path`; a missing or malformed cache is empty
+    pub fn load(path: &Path) -> Self {
+        HashCache {
+            path: path.to_path_buf(),
+            hashes: fs::read_to_string(path)
+                .ok()
+                .and_then(|x| serde_json::from_str(x.as_str()).ok())
+                .unwrap_or_default(),
+            dirty: false,
+        }
+    }
+
+    /// ### digest
+    ///
+    /// Get the SHA256 digest, as lowercase hex, of the local file at `path`.
+    /// The file is hashed only if it's changed since the digest has been cached
+    pub fn digest(&mut self, path: &Path) -> io::Result<String> {
+        let metadata: fs::Metadata = fs::metadata(path)?;
+        let size: u64 = metadata.len();
+        let mtime: u64 = metadata
+            .modified()
+            .ok()
+            .and_then(|x| x.duration_since(UNIX_EPOCH).ok())
+            .map(|x| x.as_nanos() as u64)
+            .unwrap_or(0);
+        if let Some(cached) = self
+            .hashes
+            .get(path)
+            .filter(|x| x.size == size && x.mtime == mtime)
+        {
+            return Ok(cached.digest.clone());
+        }
+        let digest: String = sha256_file(path)?;
+        self.hashes.insert(
+            path.to_path_buf(),
+            CachedHash {
+                size,
+                mtime,
+                digest: digest.clone(),
+            },
+        );
+        self.dirty = true;
+        Ok(digest)
+    }
+
+    /// ### save
+    ///
+    /// Write the cache file, if some digests have changed; the digests of the files which don't exist anymore are dropped
+    pub fn save(&mut self) -> Result<(), String> {
+        if !self.dirty {
+            return Ok(());
+        }
+        self.hashes.retain(|path, _| path.exists());
+        let data: String = serde_json::to_string(&self.hashes).map_err(|e| e.to_string())?;
+        fs::write(self.path.as_path(), data)
+            .map_err(|e| format!("Could not write \"{}\": {}", self.path.display(), e))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
+
+/// ### sha256_file
+///
+/// Calculate the SHA256 digest, as lowercase hex, of the local file at `path`
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let mut file: File = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buffer: [u8; 65536] = [0; 65536];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            bytes_read => hasher.update(&buffer[..bytes_read]),
+        }
+    }
+    Ok(to_hex(hasher.finalize().as_slice()))
+}
+
+/// ### to_hex
+///
+/// Format `digest` as lowercase hex
+pub fn to_hex(digest: &[u8]) -> String {
+    digest.iter().map(|x| format!("{:02x}", x)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_system_hash_cache_digest() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let cache_path: PathBuf = tmpdir.path().join("hashes.json");
+        let file: PathBuf = tmpdir.path().join("readme.txt");
+        fs::write(file.as_path(), "hello").unwrap();
+        let mut cache: HashCache = HashCache::load(cache_path.as_path());
+        let digest: String = cache.digest(file.as_path()).unwrap();
+        assert_eq!(
+            digest.as_str(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+        assert!(cache.save().is_ok());
+        assert!(cache_path.exists());
+        // Reload: the cached digest is used while size and modification time don't change
+        let mut cache: HashCache = HashCache::load(cache_path.as_path());
+        cache.hashes.get_mut(file.as_path()).unwrap().digest = String::from("cached");
+        assert_eq!(cache.digest(file.as_path()).unwrap().as_str(), "cached");
+        assert!(!cache.dirty);
+        // Changed file is hashed again
+        fs::write(file.as_path(), "hello world").unwrap();
+        assert_eq!(
+            cache.digest(file.as_path()).unwrap().as_str(),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+        assert!(cache.dirty);
+        // Digests of removed files are dropped
+        fs::remove_file(file.as_path()).unwrap();
+        assert!(cache.save().is_ok());
+        assert!(HashCache::load(cache_path.as_path()).hashes.is_empty());
+        // Not existing file
+        assert!(cache.digest(file.as_path()).is_err());
+    }
+
+    #[test]
+    fn test_system_hash_cache_to_hex() {
+        assert_eq!(to_hex(&[0x00, 0x0f, 0xab, 0xff]).as_str(), "000fabff");
+    }
+}
@@ -0,0 +1,521 @@
+//! ## KnownHosts
+//!
+//! `known_hosts` is the module which keeps track of the host keys of the SSH servers and of the
+//! certificates of the FTPS servers termscp has connected to
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::filetransfer::{FileTransferError, FileTransferErrorType};
+// Ext
+use sha2::{Digest, Sha256};
+use ssh2::{HostKeyType, Session};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Marker prepended to entries which must be re-verified on next connect
+const REVERIFY_MARKER: &str = "@reverify";
+/// Key type used for the certificates of TLS servers
+pub const CERTIFICATE_KEY_TYPE: &str = "x509";
+
+/// ## KnownHost
+///
+/// A host key entry in the known hosts file
+#[derive(Clone, Debug, PartialEq)]
+pub struct KnownHost {
+    pub host: String,
+    pub key_type: String,
+    pub key: String, // Base64 encoded key
+    pub reverify: bool,
+}
+
+/// ## HostKeyStatus
+///
+/// Describes the result of the verification of a host key
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HostKeyStatus {
+    Trusted,
+    Unknown,
+    Reverify,
+    Changed,
+}
+
+/// ## KnownHosts
+///
+/// Known hosts storage, backed by a file in the configuration directory
+pub struct KnownHosts {
+    path: PathBuf,
+    hosts: Vec<KnownHost>,
+}
+
+impl KnownHost {
+    /// ### fingerprint
+    ///
+    /// Returns the SHA256 fingerprint of the host key, formatted as OpenSSH does
+    pub fn fingerprint(&self) -> String {
+        let key: Vec<u8> = base64::decode(self.key.as_str()).unwrap_or_default();
+        let digest = Sha256::digest(key.as_slice());
+        format!(
+            "SHA256:{}",
+            base64::encode_config(digest.as_slice(), base64::STANDARD_NO_PAD)
+        )
+    }
+
+    /// ### parse
+    ///
+    /// Parse a known hosts line. Returns None if line is empty, a comment or malformed
+    fn parse(line: &str) -> Option<Self> {
+        let line: &str = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+        let mut tokens = line.split_whitespace().peekable();
+        let reverify: bool = tokens.peek() == Some(&REVERIFY_MARKER);
+        if reverify {
+            tokens.next();
+        }
+        let host: String = tokens.next()?.to_string();
+        let key_type: String = tokens.next()?.to_string();
+        let key: String = tokens.next()?.to_string();
+        Some(KnownHost {
+            host,
+            key_type,
+            key,
+            reverify,
+        })
+    }
+}
+
+impl std::fmt::Display for KnownHost {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.reverify {
+            write!(f, "{} ", REVERIFY_MARKER)?;
+        }
+        write!(f, "{} {} {}", self.host, self.key_type, self.key)
+    }
+}
+
+impl KnownHosts {
+    /// ### load
+    ///
+    /// Load known hosts from file. If the file doesn't exist, the storage is empty
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let hosts: Vec<KnownHost> = match File::open(path) {
+            Ok(file) => {
+                let mut hosts: Vec<KnownHost> = Vec::new();
+                for line in BufReader::new(file).lines() {
+                    if let Some(host) = KnownHost::parse(line?.as_str()) {
+                        hosts.push(host);
+                    }
+                }
+                hosts
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        Ok(KnownHosts {
+            path: path.to_path_buf(),
+            hosts,
+        })
+    }
+
+    /// ### save
+    ///
+    /// Write known hosts to file
+    pub fn save(&self) -> io::Result<()> {
+        let mut file: File = File::create(self.path.as_path())?;
+        for host in self.hosts.iter() {
+            writeln!(file, "{}", host)?;
+        }
+        Ok(())
+    }
+
+    /// ### iter
+    ///
+    /// Iterate over known hosts
+    pub fn iter(&self) -> impl Iterator<Item = &KnownHost> + '_ {
+        self.hosts.iter()
+    }
+
+    /// ### get
+    ///
+    /// Get known host at index
+    pub fn get(&self, idx: usize) -> Option<&KnownHost> {
+        self.hosts.get(idx)
+    }
+
+    /// ### remove
+    ///
+    /// Remove known host at index
+    pub fn remove(&mut self, idx: usize) -> Option<KnownHost> {
+        match idx < self.hosts.len() {
+            true => Some(self.hosts.remove(idx)),
+            false => None,
+        }
+    }
+
+    /// ### set_reverify
+    ///
+    /// Set whether the host key at index must be re-verified on next connect
+    pub fn set_reverify(&mut self, idx: usize, reverify: bool) {
+        if let Some(host) = self.hosts.get_mut(idx) {
+            host.reverify = reverify;
+        }
+    }
+
+    /// ### check
+    ///
+    /// Check host key for `host` against the known hosts
+    pub fn check(&self, host: &str, key_type: &str, key: &str) -> HostKeyStatus {
+        match self
+            .hosts
+            .iter()
+            .find(|x| x.host == host && x.key_type == key_type)
+        {
+            None => HostKeyStatus::Unknown,
+            Some(entry) if entry.reverify => HostKeyStatus::Reverify,
+            Some(entry) if entry.key == key => HostKeyStatus::Trusted,
+            Some(_) => HostKeyStatus::Changed,
+        }
+    }
+
+    /// ### trust
+    ///
+    /// Trust the provided key for `host`, replacing the previous key with the same type
+    pub fn trust(&mut self, host: &str, key_type: &str, key: &str) {
+        self.hosts
+            .retain(|x| !(x.host == host && x.key_type == key_type));
+        self.hosts.push(KnownHost {
+            host: host.to_string(),
+            key_type: key_type.to_string(),
+            key: key.to_string(),
+            reverify: false,
+        });
+    }
+
+    /// ### host_id
+    ///
+    /// Returns the host name used in the known hosts file for `address` and `port`
+    pub fn host_id(address: &str, port: u16) -> String {
+        match port {
+            22 => address.to_string(),
+            _ => format!("[{}]:{}", address, port),
+        }
+    }
+}
+
+/// ### key_type_name
+///
+/// Returns the OpenSSH name for a host key type
+fn key_type_name(key_type: HostKeyType) -> &'static str {
+    match key_type {
+        HostKeyType::Rsa => "ssh-rsa",
+        HostKeyType::Dss => "ssh-dss",
+        HostKeyType::Ecdsa256 => "ecdsa-sha2-nistp256",
+        HostKeyType::Ecdsa384 => "ecdsa-sha2-nistp384",
+        HostKeyType::Ecdsa521 => "ecdsa-sha2-nistp521",
+        HostKeyType::Ed255219 => "ssh-ed25519",
+        HostKeyType::Unknown => "unknown",
+    }
+}
+
+/// ### verify_host_key
+///
+/// Verify `key` for `host` against the known hosts stored at `path`.
+/// Unknown hosts and hosts marked for re-verification must be confirmed by the user:
+/// if `accepted` is the very same key, it is trusted and saved; otherwise `UnverifiedHostKey` is returned,
+/// carrying the key to show to the user.
+/// Returns a connection error if the host key has changed
+pub(crate) fn verify_host_key(
+    path: &Path,
+    host: &str,
+    key_type: &str,
+    key: &str,
+    accepted: Option<&KnownHost>,
+) -> Result<(), FileTransferError> {
+    let mut known_hosts: KnownHosts = KnownHosts::load(path).map_err(|e| {
+        FileTransferError::new_ex(
+            FileTransferErrorType::ConnectionError,
+            format!("Could not read known hosts: {}", e),
+        )
+    })?;
+    match known_hosts.check(host, key_type, key) {
+        HostKeyStatus::Trusted => Ok(()),
+        HostKeyStatus::Changed => Err(FileTransferError::new_ex(
+            FileTransferErrorType::ConnectionError,
+            format!(
+                "Host key for {} has changed! If this is expected, delete it from known hosts",
+                host
+            ),
+        )),
+        status => match accepted {
+            Some(accepted)
+                if accepted.host == host
+                    && accepted.key_type == key_type
+                    && accepted.key == key =>
+            {
+                known_hosts.trust(host, key_type, key);
+                known_hosts.save().map_err(|e| {
+                    FileTransferError::new_ex(
+                        FileTransferErrorType::ConnectionError,
+                        format!("Could not write known hosts: {}", e),
+                    )
+                })
+            }
+            _ => Err(FileTransferError::new(
+                FileTransferErrorType::UnverifiedHostKey(KnownHost {
+                    host: host.to_string(),
+                    key_type: key_type.to_string(),
+                    key: key.to_string(),
+                    reverify: status == HostKeyStatus::Reverify,
+                }),
+            )),
+        },
+    }
+}
+
+/// ### verify_session
+///
+/// Verify the host key of an established ssh session against the known hosts stored at `path`.
+/// See `verify_host_key`
+pub(crate) fn verify_session(
+    path: &Path,
+    session: &Session,
+    address: &str,
+    port: u16,
+    accepted: Option<&KnownHost>,
+) -> Result<(), FileTransferError> {
+    let (key, key_type) = match session.host_key() {
+        Some((key, key_type)) => (base64::encode(key), key_type_name(key_type)),
+        None => {
+            return Err(FileTransferError::new_ex(
+                FileTransferErrorType::ConnectionError,
+                String::from("Server didn't provide a host key"),
+            ))
+        }
+    };
+    let host: String = KnownHosts::host_id(address, port);
+    verify_host_key(path, host.as_str(), key_type, key.as_str(), accepted)
+}
+
+/// ### verify_certificate
+///
+/// Verify the DER encoded certificate of a TLS server against the known hosts stored at `path`.
+/// See `verify_host_key`
+pub(crate) fn verify_certificate(
+    path: &Path,
+    certificate: &[u8],
+    address: &str,
+    port: u16,
+    accepted: Option<&KnownHost>,
+) -> Result<(), FileTransferError> {
+    let host: String = KnownHosts::host_id(address, port);
+    verify_host_key(
+        path,
+        host.as_str(),
+        CERTIFICATE_KEY_TYPE,
+        base64::encode(certificate).as_str(),
+        accepted,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_system_known_hosts_parse() {
+        let host: KnownHost = KnownHost::parse("example.com ssh-ed25519 AAAA").unwrap();
+        assert_eq!(host.host.as_str(), "example.com");
+        assert_eq!(host.key_type.as_str(), "ssh-ed25519");
+        assert_eq!(host.key.as_str(), "AAAA");
+        assert!(!host.reverify);
+        assert_eq!(host.to_string().as_str(), "example.com ssh-ed25519 AAAA");
+        let host: KnownHost = KnownHost::parse("@reverify [10.0.0.1]:2022 ssh-rsa AAAA").unwrap();
+        assert_eq!(host.host.as_str(), "[10.0.0.1]:2022");
+        assert!(host.reverify);
+        assert_eq!(
+            host.to_string().as_str(),
+            "@reverify [10.0.0.1]:2022 ssh-rsa AAAA"
+        );
+        assert!(KnownHost::parse("# comment").is_none());
+        assert!(KnownHost::parse("").is_none());
+        assert!(KnownHost::parse("example.com ssh-rsa").is_none());
+        assert_eq!(
+            host.fingerprint().as_str(),
+            "SHA256:cJ6AyISHokEeHuTfufIqhhSS0gxHZRUMDHlKvXD4FHw"
+        );
+    }
+
+    #[test]
+    fn test_system_known_hosts_storage() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let path: PathBuf = get_path(&tmpdir);
+        let mut hosts: KnownHosts = KnownHosts::load(path.as_path()).unwrap();
+        assert_eq!(hosts.iter().count(), 0);
+        let host: String = KnownHosts::host_id("example.com", 22);
+        assert_eq!(host.as_str(), "example.com");
+        assert_eq!(
+            KnownHosts::host_id("example.com", 2022).as_str(),
+            "[example.com]:2022"
+        );
+        assert_eq!(
+            hosts.check(host.as_str(), "ssh-rsa", "AAAA"),
+            HostKeyStatus::Unknown
+        );
+        hosts.trust(host.as_str(), "ssh-rsa", "AAAA");
+        assert_eq!(
+            hosts.check(host.as_str(), "ssh-rsa", "AAAA"),
+            HostKeyStatus::Trusted
+        );
+        assert_eq!(
+            hosts.check(host.as_str(), "ssh-rsa", "BBBB"),
+            HostKeyStatus::Changed
+        );
+        assert_eq!(
+            hosts.check(host.as_str(), "ssh-ed25519", "CCCC"),
+            HostKeyStatus::Unknown
+        );
+        hosts.set_reverify(0, true);
+        assert_eq!(
+            hosts.check(host.as_str(), "ssh-rsa", "BBBB"),
+            HostKeyStatus::Reverify
+        );
+        // Save and reload
+        assert!(hosts.save().is_ok());
+        let mut hosts: KnownHosts = KnownHosts::load(path.as_path()).unwrap();
+        assert_eq!(hosts.iter().count(), 1);
+        assert!(hosts.get(0).unwrap().reverify);
+        // Trust replaces key
+        hosts.trust(host.as_str(), "ssh-rsa", "BBBB");
+        assert_eq!(hosts.iter().count(), 1);
+        assert_eq!(
+            hosts.check(host.as_str(), "ssh-rsa", "BBBB"),
+            HostKeyStatus::Trusted
+        );
+        // Remove
+        assert!(hosts.remove(1).is_none());
+        assert!(hosts.remove(0).is_some());
+        assert_eq!(hosts.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_system_known_hosts_verify_host_key() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let path: PathBuf = get_path(&tmpdir);
+        // Unknown host must be accepted first
+        let key: KnownHost =
+            match verify_host_key(path.as_path(), "example.com", "ssh-ed25519", "AAAA", None) {
+                Err(err) => match err.kind() {
+                    FileTransferErrorType::UnverifiedHostKey(key) => key.clone(),
+                    _ => panic!("unexpected error: {}", err),
+                },
+                Ok(_) => panic!("unknown host key has been trusted"),
+            };
+        assert_eq!(key.host.as_str(), "example.com");
+        assert_eq!(key.key_type.as_str(), "ssh-ed25519");
+        assert_eq!(key.key.as_str(), "AAAA");
+        assert!(!key.reverify);
+        assert!(!path.exists());
+        // A different key has been accepted
+        let other: KnownHost = KnownHost {
+            key: String::from("BBBB"),
+            ..key.clone()
+        };
+        assert!(verify_host_key(
+            path.as_path(),
+            "example.com",
+            "ssh-ed25519",
+            "AAAA",
+            Some(&other)
+        )
+        .is_err());
+        // Accept key
+        assert!(verify_host_key(
+            path.as_path(),
+            "example.com",
+            "ssh-ed25519",
+            "AAAA",
+            Some(&key)
+        )
+        .is_ok());
+        assert!(
+            verify_host_key(path.as_path(), "example.com", "ssh-ed25519", "AAAA", None).is_ok()
+        );
+        // Changed key is never trusted
+        let err = verify_host_key(
+            path.as_path(),
+            "example.com",
+            "ssh-ed25519",
+            "BBBB",
+            Some(&other),
+        )
+        .err()
+        .unwrap();
+        assert!(matches!(err.kind(), FileTransferErrorType::ConnectionError));
+        // Re-verify
+        let mut hosts: KnownHosts = KnownHosts::load(path.as_path()).unwrap();
+        hosts.set_reverify(0, true);
+        assert!(hosts.save().is_ok());
+        match verify_host_key(path.as_path(), "example.com", "ssh-ed25519", "AAAA", None) {
+            Err(err) => match err.kind() {
+                FileTransferErrorType::UnverifiedHostKey(key) => assert!(key.reverify),
+                _ => panic!("unexpected error: {}", err),
+            },
+            Ok(_) => panic!("host key has not been re-verified"),
+        }
+    }
+
+    #[test]
+    fn test_system_known_hosts_verify_certificate() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let path: PathBuf = get_path(&tmpdir);
+        let certificate: &[u8] = &[0x30, 0x82, 0x01, 0x0a];
+        let key: KnownHost =
+            match verify_certificate(path.as_path(), certificate, "10.0.0.1", 21, None) {
+                Err(err) => match err.kind() {
+                    FileTransferErrorType::UnverifiedHostKey(key) => key.clone(),
+                    _ => panic!("unexpected error: {}", err),
+                },
+                Ok(_) => panic!("unknown certificate has been trusted"),
+            };
+        assert_eq!(key.host.as_str(), "[10.0.0.1]:21");
+        assert_eq!(key.key_type.as_str(), CERTIFICATE_KEY_TYPE);
+        assert_eq!(key.key.as_str(), "MIIBCg==");
+        assert!(
+            verify_certificate(path.as_path(), certificate, "10.0.0.1", 21, Some(&key)).is_ok()
+        );
+        assert!(verify_certificate(path.as_path(), certificate, "10.0.0.1", 21, None).is_ok());
+    }
+
+    fn get_path(dir: &TempDir) -> PathBuf {
+        let mut p: PathBuf = PathBuf::from(dir.path());
+        p.push("known_hosts");
+        p
+    }
+}
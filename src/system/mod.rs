@@ -26,8 +26,20 @@
  * SOFTWARE.
  */
 // modules
+pub mod backup;
 pub mod bookmarks_client;
 pub mod config_client;
 pub mod environment;
+pub mod hash_cache;
 pub(crate) mod keys;
+pub mod known_hosts;
+pub mod power;
+pub mod queue_journal;
+pub mod report;
 pub mod sshkey_storage;
+pub mod staging;
+pub mod trace;
+pub mod transfer_history;
+pub mod trash;
+pub mod watcher;
+pub mod webhook;
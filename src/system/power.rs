@@ -0,0 +1,158 @@
+//! ## Power
+//!
+//! `power` is the module which prevents the system from going to sleep during long operations
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+use std::process::{Child, Command, Stdio};
+
+/// ## WakeLock
+///
+/// Keeps the system awake while it is alive; sleep is allowed again when the lock is dropped.
+/// On Linux sleep is inhibited through `systemd-inhibit`, on MacOS through `caffeinate`
+/// and on Windows with `SetThreadExecutionState`
+pub struct WakeLock {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    inhibitor: Child,
+}
+
+impl WakeLock {
+    /// ### acquire
+    ///
+    /// Prevent the system from going to sleep, until the returned lock is dropped.
+    /// `reason` is shown to the user by the systems which support it.
+    /// Returns an error if sleep can't be inhibited on this system
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    pub fn acquire(reason: &str) -> Result<WakeLock, String> {
+        let mut cmd: Command = inhibit_command(reason);
+        match cmd
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+        {
+            Ok(inhibitor) => Ok(WakeLock { inhibitor }),
+            Err(err) => Err(format!(
+                "Could not run \"{}\": {}",
+                cmd.get_program().to_string_lossy(),
+                err
+            )),
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    pub fn acquire(_reason: &str) -> Result<WakeLock, String> {
+        match unsafe { SetThreadExecutionState(ES_CONTINUOUS | ES_SYSTEM_REQUIRED) } {
+            0 => Err(String::from("SetThreadExecutionState failed")),
+            _ => Ok(WakeLock {}),
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    pub fn acquire(_reason: &str) -> Result<WakeLock, String> {
+        Err(String::from(
+            "Sleep inhibition is not supported on this system",
+        ))
+    }
+}
+
+impl Drop for WakeLock {
+    #[cfg(any(target_os = "linux", target_os = "macos"))]
+    fn drop(&mut self) {
+        // Terminating the inhibitor releases the lock
+        let _ = self.inhibitor.kill();
+        let _ = self.inhibitor.wait();
+    }
+
+    #[cfg(target_os = "windows")]
+    fn drop(&mut self) {
+        unsafe {
+            SetThreadExecutionState(ES_CONTINUOUS);
+        }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    fn drop(&mut self) {}
+}
+
+/// ### inhibit_command
+///
+/// Build the command which inhibits sleep for as long as it runs
+#[cfg(target_os = "linux")]
+fn inhibit_command(reason: &str) -> Command {
+    let mut cmd: Command = Command::new("systemd-inhibit");
+    cmd.args([
+        "--what=sleep:idle",
+        "--who=termscp",
+        "--mode=block",
+        &format!("--why={}", reason),
+        "sleep",
+        "infinity",
+    ]);
+    cmd
+}
+
+/// ### inhibit_command
+///
+/// Build the command which inhibits sleep for as long as it runs.
+/// `caffeinate` also exits with termscp, if it terminates without dropping the lock
+#[cfg(target_os = "macos")]
+fn inhibit_command(_reason: &str) -> Command {
+    let mut cmd: Command = Command::new("caffeinate");
+    cmd.args(["-i", "-w", &std::process::id().to_string()]);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+const ES_CONTINUOUS: u32 = 0x8000_0000;
+#[cfg(target_os = "windows")]
+const ES_SYSTEM_REQUIRED: u32 = 0x0000_0001;
+
+#[cfg(target_os = "windows")]
+#[link(name = "kernel32")]
+extern "system" {
+    fn SetThreadExecutionState(flags: u32) -> u32;
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_system_power_inhibit_command() {
+        let cmd: Command = inhibit_command("Transferring files");
+        assert_eq!(cmd.get_program(), "systemd-inhibit");
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|x| x.to_string_lossy().to_string())
+            .collect();
+        assert!(args.contains(&String::from("--what=sleep:idle")));
+        assert!(args.contains(&String::from("--why=Transferring files")));
+        assert_eq!(&args[args.len() - 2..], &["sleep", "infinity"]);
+    }
+}
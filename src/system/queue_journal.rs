@@ -0,0 +1,160 @@
+//! ## QueueJournal
+//!
+//! `queue_journal` is the module which keeps track of the transfer queues being run,
+//! so that a queue interrupted before being completed can be recovered at the next connection
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::fs::queue::QueueManifest;
+// Ext
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// ## QueueJournal
+///
+/// The journal file of the transfer queues being run, by remote host.
+/// A queue is recorded while it's being run and forgotten once it's done: a queue still recorded
+/// when connecting to its host has been interrupted (e.g. because termscp has been killed)
+pub struct QueueJournal {
+    path: PathBuf,
+}
+
+impl QueueJournal {
+    /// ### new
+    ///
+    /// Instantiates a new `QueueJournal` stored at `path`
+    pub fn new(path: &Path) -> Self {
+        QueueJournal {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// ### get
+    ///
+    /// Get the transfers left by the queue run with `host`; None if no queue has been interrupted
+    pub fn get(&self, host: &str) -> Option<QueueManifest> {
+        self.load().remove(host)
+    }
+
+    /// ### record
+    ///
+    /// Record `manifest`, the transfers left by the queue being run with `host`.
+    /// An empty manifest forgets the queue
+    pub fn record(&self, host: &str, manifest: &QueueManifest) -> Result<(), String> {
+        let mut queues: HashMap<String, QueueManifest> = self.load();
+        match manifest.transfers.is_empty() {
+            true => queues.remove(host),
+            false => queues.insert(host.to_string(), manifest.clone()),
+        };
+        self.save(&queues)
+    }
+
+    /// ### finish
+    ///
+    /// Forget the queue run with `host`
+    pub fn finish(&self, host: &str) -> Result<(), String> {
+        self.record(host, &QueueManifest::default())
+    }
+
+    /// ### load
+    ///
+    /// Read the queues in the journal; a missing or malformed journal has no queues
+    fn load(&self) -> HashMap<String, QueueManifest> {
+        fs::read_to_string(self.path.as_path())
+            .ok()
+            .and_then(|x| serde_json::from_str(x.as_str()).ok())
+            .unwrap_or_default()
+    }
+
+    /// ### save
+    ///
+    /// Write `queues` to the journal; the file is removed once there are no queues left
+    fn save(&self, queues: &HashMap<String, QueueManifest>) -> Result<(), String> {
+        if queues.is_empty() {
+            return match fs::remove_file(self.path.as_path()) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(format!(
+                    "Could not remove \"{}\": {}",
+                    self.path.display(),
+                    err
+                )),
+                _ => Ok(()),
+            };
+        }
+        let data: String = serde_json::to_string(queues).map_err(|e| e.to_string())?;
+        fs::write(self.path.as_path(), data)
+            .map_err(|e| format!("Could not write \"{}\": {}", self.path.display(), e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::fs::queue::{ManifestItem, Priority, TransferDirection};
+
+    #[test]
+    fn test_system_queue_journal() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let path: PathBuf = tmpdir.path().join("queues.json");
+        let journal: QueueJournal = QueueJournal::new(path.as_path());
+        assert!(journal.get("sftp://omar@192.168.1.31:22").is_none());
+        // Record
+        let manifest: QueueManifest = QueueManifest {
+            transfers: vec![ManifestItem {
+                source: PathBuf::from("/home/omar/backup.tar.gz"),
+                destination: PathBuf::from("/srv/backups"),
+                direction: TransferDirection::Upload,
+                priority: Priority::High,
+                remove_source: false,
+            }],
+        };
+        assert!(journal
+            .record("sftp://omar@192.168.1.31:22", &manifest)
+            .is_ok());
+        assert!(journal.record("localhost", &manifest).is_ok());
+        assert_eq!(
+            journal
+                .get("sftp://omar@192.168.1.31:22")
+                .unwrap()
+                .transfers,
+            manifest.transfers
+        );
+        // Finish
+        assert!(journal.finish("sftp://omar@192.168.1.31:22").is_ok());
+        assert!(journal.get("sftp://omar@192.168.1.31:22").is_none());
+        assert!(journal.get("localhost").is_some());
+        // An empty manifest forgets the queue; the file is removed with the last queue
+        assert!(journal
+            .record("localhost", &QueueManifest::default())
+            .is_ok());
+        assert!(journal.get("localhost").is_none());
+        assert!(!path.exists());
+        assert!(journal.finish("localhost").is_ok());
+        // Malformed journal
+        fs::write(path.as_path(), "{ not json").unwrap();
+        assert!(journal.get("localhost").is_none());
+    }
+}
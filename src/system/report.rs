@@ -0,0 +1,149 @@
+//! ## Report
+//!
+//! `report` is the module which builds the capability report of a remote server, to be attached
+//! to bug reports. Host names, user names and paths are redacted from the report
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::fmt::Write;
+
+const REDACTED: &str = "<redacted>";
+
+/// ## CapabilityReport
+///
+/// Details about termscp, the remote server and the configuration in use
+#[derive(Default)]
+pub struct CapabilityReport {
+    pub protocol: String,
+    pub banner: Option<String>,
+    pub server: Vec<(String, String)>, // Details reported by the file transfer client
+    pub shell: Option<String>,
+    pub os: Option<String>,
+    pub config: Vec<(String, String)>, // Configuration flags
+    secrets: Vec<String>,
+}
+
+impl CapabilityReport {
+    /// ### new
+    ///
+    /// Instantiates a new CapabilityReport for `protocol`
+    pub fn new(protocol: &str) -> Self {
+        CapabilityReport {
+            protocol: protocol.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// ### redact
+    ///
+    /// Hide `secret` (e.g. the host name) wherever it appears in the report
+    pub fn redact<S: AsRef<str>>(&mut self, secret: S) {
+        let secret: &str = secret.as_ref().trim();
+        if !secret.is_empty() && secret != "/" {
+            self.secrets.push(secret.to_string());
+        }
+    }
+
+    /// ### render
+    ///
+    /// Render report as markdown, ready to be pasted into an issue
+    pub fn render(&self) -> String {
+        let mut report: String = String::from("### Capability report\n\n");
+        let _ = writeln!(
+            report,
+            "- termscp: {} ({} {})",
+            env!("CARGO_PKG_VERSION"),
+            std::env::consts::OS,
+            std::env::consts::ARCH
+        );
+        let _ = writeln!(report, "- Protocol: {}", self.protocol);
+        let unknown = || String::from("unknown");
+        let _ = writeln!(
+            report,
+            "- Server banner: {}",
+            self.banner.clone().unwrap_or_else(unknown)
+        );
+        let _ = writeln!(
+            report,
+            "- Remote OS: {}",
+            self.os.clone().unwrap_or_else(unknown)
+        );
+        let _ = writeln!(
+            report,
+            "- Remote shell: {}",
+            self.shell.clone().unwrap_or_else(unknown)
+        );
+        for (section, values) in [("Server", &self.server), ("Configuration", &self.config)].iter()
+        {
+            if values.is_empty() {
+                continue;
+            }
+            let _ = writeln!(report, "\n#### {}\n", section);
+            for (name, value) in values.iter() {
+                let _ = writeln!(report, "- {}: {}", name, value);
+            }
+        }
+        self.anonymize(report)
+    }
+
+    /// ### anonymize
+    ///
+    /// Replace secrets in `text`; longest secrets are replaced first, since they may contain shorter ones
+    fn anonymize(&self, mut text: String) -> String {
+        let mut secrets: Vec<&String> = self.secrets.iter().collect();
+        secrets.sort_by_key(|x| std::cmp::Reverse(x.len()));
+        for secret in secrets {
+            text = text.replace(secret.as_str(), REDACTED);
+        }
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_system_report_render() {
+        let mut report: CapabilityReport = CapabilityReport::new("SFTP");
+        report.banner = Some(String::from("SSH-2.0-OpenSSH_8.4 build.example.com"));
+        report.shell = Some(String::from("/bin/bash"));
+        report.server = vec![(String::from("Cipher"), String::from("aes128-ctr"))];
+        report.config = vec![(String::from("Atomic uploads"), String::from("yes"))];
+        report.redact("example.com");
+        report.redact("build.example.com");
+        report.redact("/");
+        report.redact("");
+        let text: String = report.render();
+        assert!(text.contains("- Protocol: SFTP\n"));
+        assert!(text.contains("- Server banner: SSH-2.0-OpenSSH_8.4 <redacted>\n"));
+        assert!(text.contains("- Remote OS: unknown\n"));
+        assert!(text.contains("- Remote shell: /bin/bash\n"));
+        assert!(text.contains("#### Server\n\n- Cipher: aes128-ctr\n"));
+        assert!(text.contains("#### Configuration\n\n- Atomic uploads: yes\n"));
+        assert!(!text.contains("example.com"));
+    }
+}
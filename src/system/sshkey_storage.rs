@@ -31,6 +31,7 @@ use super::config_client::ConfigClient;
 use std::collections::HashMap;
 use std::path::PathBuf;
 
+#[derive(Clone)]
 pub struct SshKeyStorage {
     hosts: HashMap<String, PathBuf>, // Association between {user}@{host} and RSA key path
 }
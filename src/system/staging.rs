@@ -0,0 +1,211 @@
+//! ## Staging
+//!
+//! `staging` is the module which provides the temporary files remote files are staged in, to be edited or browsed
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, SystemTime};
+use tempfile::{Builder, NamedTempFile, TempDir};
+
+/// Staging entries are named `termscp-staging-<PID>-<RANDOM>`, after the process which created them
+const STAGING_PREFIX: &str = "termscp-staging-";
+/// Where processes can't be looked up, staging entries are orphaned once they're this old
+const ORPHAN_AGE: Duration = Duration::from_secs(86400);
+
+/// ## Staging
+///
+/// Directory the temporary files and directories of termscp are created in
+pub struct Staging {
+    dir: PathBuf,
+}
+
+impl Staging {
+    /// ### new
+    ///
+    /// Instantiates a new Staging in `dir`; if None, the temporary directory of the system is used
+    pub fn new(dir: Option<&Path>) -> Self {
+        Staging {
+            dir: dir.map(Path::to_path_buf).unwrap_or_else(env::temp_dir),
+        }
+    }
+
+    /// ### tempfile
+    ///
+    /// Create a temporary file in the staging directory; the file is removed once dropped
+    pub fn tempfile(&self) -> io::Result<NamedTempFile> {
+        Builder::new()
+            .prefix(Self::prefix().as_str())
+            .tempfile_in(self.dir.as_path())
+    }
+
+    /// ### tempdir
+    ///
+    /// Create a temporary directory in the staging directory; the directory is removed once dropped
+    pub fn tempdir(&self) -> io::Result<TempDir> {
+        Builder::new()
+            .prefix(Self::prefix().as_str())
+            .tempdir_in(self.dir.as_path())
+    }
+
+    /// ### cleanup
+    ///
+    /// Remove the staging entries left behind by the instances of termscp which are not running anymore
+    /// (e.g. because they crashed). Returns the amount of entries removed
+    pub fn cleanup(&self) -> io::Result<usize> {
+        let mut removed: usize = 0;
+        for entry in fs::read_dir(self.dir.as_path())?.flatten() {
+            let pid: u32 = match parse_pid(entry.file_name().to_string_lossy().as_ref()) {
+                Some(pid) => pid,
+                None => continue,
+            };
+            let modified: Option<SystemTime> = entry.metadata().and_then(|x| x.modified()).ok();
+            if !is_orphaned(pid, modified) {
+                continue;
+            }
+            let result: io::Result<()> = match entry.file_type() {
+                Ok(kind) if kind.is_dir() => fs::remove_dir_all(entry.path()),
+                _ => fs::remove_file(entry.path()),
+            };
+            if result.is_ok() {
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// ### prefix
+    ///
+    /// Get the prefix of the staging entries created by this process
+    fn prefix() -> String {
+        format!("{}{}-", STAGING_PREFIX, process::id())
+    }
+}
+
+/// ### parse_pid
+///
+/// Get the id of the process which created the staging entry named `name`; None if it's not a staging entry
+fn parse_pid(name: &str) -> Option<u32> {
+    let (pid, random): (&str, &str) = name.strip_prefix(STAGING_PREFIX)?.split_once('-')?;
+    match random.is_empty() {
+        true => None,
+        false => pid.parse().ok().filter(|x| *x > 0 && *x <= i32::MAX as u32),
+    }
+}
+
+/// ### is_orphaned
+///
+/// Returns whether the process `pid` has left behind its staging entry, last modified at `modified`
+fn is_orphaned(pid: u32, modified: Option<SystemTime>) -> bool {
+    if pid == process::id() {
+        return false;
+    }
+    match is_running(pid) {
+        Some(running) => !running,
+        None => modified
+            .and_then(|x| x.elapsed().ok())
+            .map(|x| x >= ORPHAN_AGE)
+            .unwrap_or(false),
+    }
+}
+
+/// ### is_running
+///
+/// Returns whether the process `pid` is running; None if it can't be told
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+fn is_running(pid: u32) -> Option<bool> {
+    // Signal 0 only checks whether the process exists
+    match unsafe { libc::kill(pid as libc::pid_t, 0) } {
+        0 => Some(true),
+        _ => Some(io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)),
+    }
+}
+
+#[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+fn is_running(_pid: u32) -> Option<bool> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_system_staging_entries() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let staging: Staging = Staging::new(Some(tmpdir.path()));
+        assert_eq!(staging.dir.as_path(), tmpdir.path());
+        let file: NamedTempFile = staging.tempfile().unwrap();
+        assert_eq!(file.path().parent().unwrap(), tmpdir.path());
+        assert_eq!(
+            parse_pid(file.path().file_name().unwrap().to_str().unwrap()),
+            Some(process::id())
+        );
+        let dir: TempDir = staging.tempdir().unwrap();
+        assert!(dir.path().is_dir());
+        assert_eq!(dir.path().parent().unwrap(), tmpdir.path());
+        // Default
+        assert_eq!(Staging::new(None).dir, env::temp_dir());
+    }
+
+    #[test]
+    fn test_system_staging_parse_pid() {
+        assert_eq!(parse_pid("termscp-staging-1234-a1b2c3"), Some(1234));
+        assert_eq!(parse_pid("termscp-staging-1234-"), None);
+        assert_eq!(parse_pid("termscp-staging-0-a1b2c3"), None);
+        assert_eq!(parse_pid("termscp-staging-4294967295-a1b2c3"), None);
+        assert_eq!(parse_pid("termscp-staging-abc-a1b2c3"), None);
+        assert_eq!(parse_pid("termscp.log"), None);
+    }
+
+    #[test]
+    fn test_system_staging_cleanup() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let staging: Staging = Staging::new(Some(tmpdir.path()));
+        // Entries of this process and other files are kept
+        let file: NamedTempFile = staging.tempfile().unwrap();
+        fs::write(tmpdir.path().join("notes.txt"), "hello").unwrap();
+        // Entries of processes which are not running are removed
+        let orphan: PathBuf = tmpdir.path().join("termscp-staging-999999999-a1b2c3");
+        fs::create_dir(orphan.as_path()).unwrap();
+        fs::write(orphan.join("archive.zip"), "data").unwrap();
+        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+        {
+            assert_eq!(staging.cleanup().unwrap(), 1);
+            assert!(!orphan.exists());
+        }
+        assert!(file.path().exists());
+        assert!(tmpdir.path().join("notes.txt").exists());
+        // Not existing directory
+        assert!(Staging::new(Some(tmpdir.path().join("nope").as_path()))
+            .cleanup()
+            .is_err());
+    }
+}
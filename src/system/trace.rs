@@ -0,0 +1,94 @@
+//! ## Trace
+//!
+//! `trace` is the module which provides the subscriber for the tracing spans emitted by termscp
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Deps
+extern crate tracing;
+extern crate tracing_subscriber;
+
+// Ext
+use std::fs::OpenOptions;
+use std::path::Path;
+use std::sync::Arc;
+use tracing::Level;
+use tracing_subscriber::fmt::format::FmtSpan;
+
+/// ### init_tracer
+///
+/// Initialize the global tracing subscriber, which writes spans and events to `log_file`.
+/// Spans are reported when closed, along with their duration.
+/// Can be called only once.
+pub fn init_tracer(log_file: &Path) -> Result<(), String> {
+    // Open log file (append)
+    let file = match OpenOptions::new().create(true).append(true).open(log_file) {
+        Ok(f) => f,
+        Err(err) => {
+            return Err(format!(
+                "Could not open trace file \"{}\": {}",
+                log_file.display(),
+                err
+            ))
+        }
+    };
+    // Set global subscriber
+    match tracing_subscriber::fmt()
+        .with_writer(Arc::new(file))
+        .with_max_level(Level::TRACE)
+        .with_span_events(FmtSpan::CLOSE)
+        .try_init()
+    {
+        Ok(_) => Ok(()),
+        Err(err) => Err(format!("Could not initialize tracer: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_system_trace_init_tracer() {
+        let tmpfile: tempfile::NamedTempFile = tempfile::NamedTempFile::new().unwrap();
+        assert!(init_tracer(tmpfile.path()).is_ok());
+        // Emit a span
+        {
+            let span = tracing::info_span!("list_dir", path = "/tmp");
+            let _enter = span.enter();
+        }
+        // Subscriber can be set only once
+        assert!(init_tracer(tmpfile.path()).is_err());
+        // Verify span has been reported
+        let content: String = std::fs::read_to_string(tmpfile.path()).unwrap();
+        assert!(content.contains("list_dir"));
+        assert!(content.contains("path=\"/tmp\""));
+    }
+
+    #[test]
+    fn test_system_trace_init_tracer_err() {
+        assert!(init_tracer(Path::new("/tmp/efnnu/omar/termscp.log")).is_err());
+    }
+}
@@ -0,0 +1,373 @@
+//! ## TransferHistory
+//!
+//! `transfer_history` is the module which persists the history of the transfers into the configuration
+//! directory, one JSON record per line, so that past transfers can be browsed and searched
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::fs::queue::TransferDirection;
+// Ext
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Records kept in the history file; the oldest ones are dropped when the history is loaded
+const HISTORY_MAX_RECORDS: usize = 5000;
+
+/// ## TransferResult
+///
+/// Describes how a transfer ended
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferResult {
+    Completed,
+    Aborted,
+    Failed(String),
+}
+
+/// ## HistoryRecord
+///
+/// A single file transfer in the history
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize)]
+pub struct HistoryRecord {
+    pub time: u64, // Seconds since unix epoch, when the transfer ended
+    pub direction: TransferDirection,
+    pub host: String, // Remote host, as `protocol://user@address:port`
+    pub local: PathBuf,
+    pub remote: PathBuf,
+    pub size: u64,     // Bytes transferred
+    pub duration: u64, // Milliseconds
+    pub result: TransferResult,
+}
+
+impl HistoryRecord {
+    /// ### new
+    ///
+    /// Instantiates a new `HistoryRecord` for a transfer which has just ended
+    pub fn new(
+        direction: TransferDirection,
+        host: &str,
+        local: &Path,
+        remote: &Path,
+        size: u64,
+        duration: Duration,
+        result: TransferResult,
+    ) -> Self {
+        HistoryRecord {
+            time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|x| x.as_secs())
+                .unwrap_or(0),
+            direction,
+            host: host.to_string(),
+            local: local.to_path_buf(),
+            remote: remote.to_path_buf(),
+            size,
+            duration: duration.as_millis() as u64,
+            result,
+        }
+    }
+
+    /// ### time
+    ///
+    /// Get the time the transfer ended at
+    pub fn time(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.time)
+    }
+
+    /// ### matches
+    ///
+    /// Returns whether `text` is found (case insensitive) in the paths, the host, the direction or the result of the record
+    pub fn matches(&self, text: &str) -> bool {
+        let text: String = text.to_lowercase();
+        let direction: &str = match self.direction {
+            TransferDirection::Upload => "upload",
+            TransferDirection::Download => "download",
+        };
+        let result: String = match &self.result {
+            TransferResult::Completed => String::from("completed"),
+            TransferResult::Aborted => String::from("aborted"),
+            TransferResult::Failed(err) => format!("failed {}", err),
+        };
+        [
+            self.local.to_string_lossy().to_string(),
+            self.remote.to_string_lossy().to_string(),
+            self.host.clone(),
+            direction.to_string(),
+            result,
+        ]
+        .iter()
+        .any(|x| x.to_lowercase().contains(text.as_str()))
+    }
+}
+
+/// ### average_throughput
+///
+/// Get the average speed, in bytes per second, of the last `samples` transfers completed with `host`.
+/// `records` must be sorted from the most recent; returns None if there are no such transfers
+pub fn average_throughput(records: &[HistoryRecord], host: &str, samples: usize) -> Option<u64> {
+    let (bytes, millis): (u64, u64) = records
+        .iter()
+        .filter(|x| x.host == host && x.result == TransferResult::Completed && x.size > 0)
+        .take(samples)
+        .fold((0, 0), |(bytes, millis), x| {
+            (bytes + x.size, millis + x.duration)
+        });
+    match (bytes, millis) {
+        (0, _) | (_, 0) => None,
+        (bytes, millis) => Some(bytes * 1000 / millis),
+    }
+}
+
+/// ## TransferHistory
+///
+/// The history file of the transfers
+pub struct TransferHistory {
+    path: PathBuf,
+}
+
+impl TransferHistory {
+    /// ### new
+    ///
+    /// Instantiates a new `TransferHistory` stored at `path`
+    pub fn new(path: &Path) -> Self {
+        TransferHistory {
+            path: path.to_path_buf(),
+        }
+    }
+
+    /// ### append
+    ///
+    /// Append `record` to the history file
+    pub fn append(&self, record: &HistoryRecord) -> Result<(), String> {
+        let line: String = serde_json::to_string(record).map_err(|e| e.to_string())?;
+        let mut file: File = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.path.as_path())
+            .map_err(|e| format!("Could not open \"{}\": {}", self.path.display(), e))?;
+        writeln!(file, "{}", line)
+            .map_err(|e| format!("Could not write \"{}\": {}", self.path.display(), e))
+    }
+
+    /// ### load
+    ///
+    /// Read the records of the history, most recent first; malformed lines are ignored.
+    /// If the history exceeds `HISTORY_MAX_RECORDS`, the oldest records are removed from the file
+    pub fn load(&self) -> Result<Vec<HistoryRecord>, String> {
+        let file: File = match File::open(self.path.as_path()) {
+            Ok(f) => f,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(format!(
+                    "Could not open \"{}\": {}",
+                    self.path.display(),
+                    err
+                ))
+            }
+        };
+        let mut records: Vec<HistoryRecord> = BufReader::new(file)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|x| serde_json::from_str(x.as_str()).ok())
+            .collect();
+        if records.len() > HISTORY_MAX_RECORDS {
+            records.drain(..records.len() - HISTORY_MAX_RECORDS);
+            self.rewrite(records.as_slice())?;
+        }
+        records.reverse();
+        Ok(records)
+    }
+
+    /// ### rewrite
+    ///
+    /// Replace the content of the history file with `records`
+    fn rewrite(&self, records: &[HistoryRecord]) -> Result<(), String> {
+        let mut file: File = File::create(self.path.as_path())
+            .map_err(|e| format!("Could not open \"{}\": {}", self.path.display(), e))?;
+        for record in records.iter() {
+            let line: String = serde_json::to_string(record).map_err(|e| e.to_string())?;
+            writeln!(file, "{}", line)
+                .map_err(|e| format!("Could not write \"{}\": {}", self.path.display(), e))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_system_transfer_history_append_load() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let history: TransferHistory =
+            TransferHistory::new(tmpdir.path().join("history").as_path());
+        // Empty history
+        assert_eq!(history.load().unwrap().len(), 0);
+        let upload: HistoryRecord = HistoryRecord::new(
+            TransferDirection::Upload,
+            "sftp://omar@192.168.1.31:22",
+            Path::new("/home/omar/nginx.conf"),
+            Path::new("/etc/nginx/nginx.conf"),
+            2048,
+            Duration::from_millis(320),
+            TransferResult::Completed,
+        );
+        let download: HistoryRecord = HistoryRecord::new(
+            TransferDirection::Download,
+            "sftp://omar@192.168.1.31:22",
+            Path::new("/home/omar/backup.tar.gz"),
+            Path::new("/var/backups/backup.tar.gz"),
+            512,
+            Duration::from_secs(2),
+            TransferResult::Failed(String::from("Connection reset")),
+        );
+        assert!(history.append(&upload).is_ok());
+        assert!(history.append(&download).is_ok());
+        // Most recent first
+        assert_eq!(
+            history.load().unwrap(),
+            vec![download.clone(), upload.clone()]
+        );
+        // Malformed lines are ignored
+        let mut file: File = OpenOptions::new()
+            .append(true)
+            .open(tmpdir.path().join("history"))
+            .unwrap();
+        writeln!(file, "{{ not a record").unwrap();
+        assert_eq!(history.load().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_system_transfer_history_max_records() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let history: TransferHistory =
+            TransferHistory::new(tmpdir.path().join("history").as_path());
+        let records: Vec<HistoryRecord> = (0..HISTORY_MAX_RECORDS + 10)
+            .map(|x| {
+                HistoryRecord::new(
+                    TransferDirection::Upload,
+                    "scp://root@10.0.0.1:22",
+                    Path::new("/tmp/a.txt"),
+                    Path::new("/tmp/a.txt"),
+                    x as u64,
+                    Duration::from_secs(0),
+                    TransferResult::Completed,
+                )
+            })
+            .collect();
+        assert!(history.rewrite(records.as_slice()).is_ok());
+        let loaded: Vec<HistoryRecord> = history.load().unwrap();
+        assert_eq!(loaded.len(), HISTORY_MAX_RECORDS);
+        // Oldest records are dropped from the file too
+        assert_eq!(loaded.last().unwrap().size, 10);
+        assert_eq!(history.load().unwrap().len(), HISTORY_MAX_RECORDS);
+    }
+
+    #[test]
+    fn test_system_transfer_history_matches() {
+        let record: HistoryRecord = HistoryRecord::new(
+            TransferDirection::Download,
+            "sftp://omar@192.168.1.31:22",
+            Path::new("/home/omar/backup.tar.gz"),
+            Path::new("/var/backups/backup.tar.gz"),
+            512,
+            Duration::from_secs(2),
+            TransferResult::Failed(String::from("Connection reset")),
+        );
+        assert!(record.matches("backup"));
+        assert!(record.matches("VAR/BACKUPS"));
+        assert!(record.matches("192.168.1.31"));
+        assert!(record.matches("download"));
+        assert!(record.matches("failed"));
+        assert!(record.matches("reset"));
+        assert!(!record.matches("upload"));
+        assert!(!record.matches("completed"));
+    }
+
+    #[test]
+    fn test_system_transfer_history_average_throughput() {
+        let make_record = |host: &str, size: u64, millis: u64, result: TransferResult| {
+            HistoryRecord::new(
+                TransferDirection::Upload,
+                host,
+                Path::new("/tmp/a.txt"),
+                Path::new("/tmp/a.txt"),
+                size,
+                Duration::from_millis(millis),
+                result,
+            )
+        };
+        let records: Vec<HistoryRecord> = vec![
+            make_record(
+                "sftp://omar@10.0.0.1:22",
+                4096,
+                1000,
+                TransferResult::Completed,
+            ),
+            make_record(
+                "sftp://omar@10.0.0.2:22",
+                1024,
+                1000,
+                TransferResult::Completed,
+            ),
+            make_record(
+                "sftp://omar@10.0.0.1:22",
+                1024,
+                8000,
+                TransferResult::Failed(String::from("Connection reset")),
+            ),
+            make_record(
+                "sftp://omar@10.0.0.1:22",
+                2048,
+                1000,
+                TransferResult::Completed,
+            ),
+            make_record(
+                "sftp://omar@10.0.0.1:22",
+                100,
+                9000,
+                TransferResult::Completed,
+            ),
+        ];
+        // Only completed transfers with the host, most recent first
+        assert_eq!(
+            average_throughput(records.as_slice(), "sftp://omar@10.0.0.1:22", 2),
+            Some(3072)
+        );
+        assert_eq!(
+            average_throughput(records.as_slice(), "sftp://omar@10.0.0.2:22", 10),
+            Some(1024)
+        );
+        assert_eq!(
+            average_throughput(records.as_slice(), "scp://root@10.0.0.3:22", 10),
+            None
+        );
+    }
+}
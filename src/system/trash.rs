@@ -0,0 +1,317 @@
+//! ## Trash
+//!
+//! `trash` is the module which moves deleted entries to a trash directory, instead of removing them
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use chrono::{DateTime, Local};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Default trash directory on remote hosts; `~` is the home directory of the user
+pub const REMOTE_TRASH_DIR: &str = "~/.termscp_trash";
+
+/// Characters which must be percent-encoded in the paths of trash info files
+const TRASH_PATH: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
+/// ## Trash
+///
+/// A trash directory following the freedesktop.org specification:
+/// trashed entries are moved into `files`, and described by a `.trashinfo` file in `info`
+pub struct Trash {
+    root: PathBuf,
+}
+
+impl Trash {
+    /// ### new
+    ///
+    /// Instantiates a new Trash in `root`
+    pub fn new(root: PathBuf) -> Self {
+        Trash { root }
+    }
+
+    /// ### home
+    ///
+    /// Get the home trash of the user (`$XDG_DATA_HOME/Trash`).
+    /// Returns None on the systems which don't follow the freedesktop.org specification
+    #[cfg(all(unix, not(target_os = "macos")))]
+    pub fn home() -> Option<Self> {
+        dirs::data_dir().map(|x| Trash::new(x.join("Trash")))
+    }
+
+    #[cfg(not(all(unix, not(target_os = "macos"))))]
+    pub fn home() -> Option<Self> {
+        None
+    }
+
+    /// ### root
+    ///
+    /// Get the directory of the trash
+    pub fn root(&self) -> &Path {
+        self.root.as_path()
+    }
+
+    /// ### put
+    ///
+    /// Move the entry at absolute `path` into the trash, keeping where it comes from and when it's been deleted.
+    /// Returns the path of the entry in the trash
+    pub fn put(&self, path: &Path) -> io::Result<PathBuf> {
+        let name: String = path
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "no file name"))?;
+        let (files, info): (PathBuf, PathBuf) = (self.root.join("files"), self.root.join("info"));
+        fs::create_dir_all(files.as_path())?;
+        fs::create_dir_all(info.as_path())?;
+        // Claim a free name by creating its trash info
+        let mut attempt: usize = 0;
+        let (trashed, info_path): (PathBuf, PathBuf) = loop {
+            let candidate: String = trashed_name(name.as_str(), attempt);
+            let info_path: PathBuf = info.join(format!("{}.trashinfo", candidate));
+            attempt += 1;
+            if files.join(candidate.as_str()).exists() {
+                continue;
+            }
+            match OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(info_path.as_path())
+            {
+                Ok(mut file) => {
+                    write!(
+                        file,
+                        "[Trash Info]\nPath={}\nDeletionDate={}\n",
+                        utf8_percent_encode(&path.to_string_lossy(), TRASH_PATH),
+                        Local::now().format("%Y-%m-%dT%H:%M:%S")
+                    )?;
+                    break (files.join(candidate), info_path);
+                }
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+        };
+        let moved: io::Result<()> = match fs::rename(path, trashed.as_path()) {
+            // The trash is on another file system
+            Err(err) if err.kind() == io::ErrorKind::CrossesDevices => {
+                move_across(path, trashed.as_path())
+            }
+            result => result,
+        };
+        if let Err(err) = moved {
+            // Keep the trash info if the entry has been copied into the trash anyway
+            if fs::symlink_metadata(trashed.as_path()).is_err() {
+                let _ = fs::remove_file(info_path);
+            }
+            return Err(err);
+        }
+        Ok(trashed)
+    }
+}
+
+/// ### move_across
+///
+/// Move the entry at `src` to `dst` on another file system: the entry is copied, then removed.
+/// If the copy fails, what has been copied is removed and `src` is left untouched
+fn move_across(src: &Path, dst: &Path) -> io::Result<()> {
+    if let Err(err) = copy_all(src, dst) {
+        let _ = remove_all(dst);
+        return Err(err);
+    }
+    remove_all(src)
+}
+
+/// ### copy_all
+///
+/// Copy the entry at `src` to `dst`, with the content of directories; symbolic links are copied as links
+fn copy_all(src: &Path, dst: &Path) -> io::Result<()> {
+    let metadata: fs::Metadata = fs::symlink_metadata(src)?;
+    if metadata.file_type().is_symlink() {
+        #[cfg(unix)]
+        return std::os::unix::fs::symlink(fs::read_link(src)?, dst);
+        #[cfg(not(unix))]
+        return fs::copy(src, dst).map(|_| ());
+    }
+    if metadata.is_dir() {
+        fs::create_dir(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry: fs::DirEntry = entry?;
+            copy_all(
+                entry.path().as_path(),
+                dst.join(entry.file_name()).as_path(),
+            )?;
+        }
+        fs::set_permissions(dst, metadata.permissions())
+    } else {
+        fs::copy(src, dst).map(|_| ())
+    }
+}
+
+/// ### remove_all
+///
+/// Remove the entry at `path`, with the content of directories
+fn remove_all(path: &Path) -> io::Result<()> {
+    match fs::symlink_metadata(path)?.is_dir() {
+        true => fs::remove_dir_all(path),
+        false => fs::remove_file(path),
+    }
+}
+
+/// ### trashed_name
+///
+/// Get the name of an entry named `name` in a trash; further attempts are numbered, after a name has been taken
+pub fn trashed_name(name: &str, attempt: usize) -> String {
+    match attempt {
+        0 => name.to_string(),
+        n => format!("{}.{}", name, n + 1),
+    }
+}
+
+/// ### remote_trashed_name
+///
+/// Get the name of an entry named `name` in a remote trash; the deletion time is appended to the name,
+/// since remote trashes keep no trash info
+pub fn remote_trashed_name(name: &str, deleted: DateTime<Local>, attempt: usize) -> String {
+    trashed_name(
+        format!("{}.{}", name, deleted.format("%Y%m%d-%H%M%S")).as_str(),
+        attempt,
+    )
+}
+
+/// ### resolve_trash_dir
+///
+/// Resolve a trash directory; a leading `~` and relative paths are resolved from `home`
+pub fn resolve_trash_dir(dir: &str, home: &Path) -> PathBuf {
+    match dir.strip_prefix('~') {
+        Some(rest) => home.join(rest.trim_start_matches('/')),
+        None => home.join(dir),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_system_trash_put() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let trash: Trash = Trash::new(tmpdir.path().join("Trash"));
+        let source: PathBuf = tmpdir.path().join("my notes.txt");
+        fs::write(source.as_path(), "hello").unwrap();
+        let trashed: PathBuf = trash.put(source.as_path()).unwrap();
+        assert!(!source.exists());
+        assert_eq!(trashed, tmpdir.path().join("Trash/files/my notes.txt"));
+        assert_eq!(fs::read_to_string(trashed.as_path()).unwrap(), "hello");
+        let info: String =
+            fs::read_to_string(tmpdir.path().join("Trash/info/my notes.txt.trashinfo")).unwrap();
+        assert!(info.starts_with("[Trash Info]\n"));
+        assert!(
+            info.contains(format!("Path={}/my%20notes.txt\n", tmpdir.path().display()).as_str())
+        );
+        assert!(info.contains("DeletionDate="));
+        // Same name again
+        fs::write(source.as_path(), "world").unwrap();
+        let trashed: PathBuf = trash.put(source.as_path()).unwrap();
+        assert_eq!(trashed, tmpdir.path().join("Trash/files/my notes.txt.2"));
+        assert!(tmpdir
+            .path()
+            .join("Trash/info/my notes.txt.2.trashinfo")
+            .exists());
+        // Not existing; trash info is removed
+        assert!(trash.put(source.as_path()).is_err());
+        assert!(!tmpdir
+            .path()
+            .join("Trash/info/my notes.txt.3.trashinfo")
+            .exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_system_trash_move_across() {
+        let tmpdir: tempfile::TempDir = tempfile::TempDir::new().unwrap();
+        let source: PathBuf = tmpdir.path().join("project");
+        fs::create_dir_all(source.join("src")).unwrap();
+        fs::write(source.join("src/main.rs"), "fn main() {}").unwrap();
+        std::os::unix::fs::symlink("src/main.rs", source.join("main.rs")).unwrap();
+        let dest: PathBuf = tmpdir.path().join("Trash/files/project");
+        fs::create_dir_all(tmpdir.path().join("Trash/files")).unwrap();
+        assert!(move_across(source.as_path(), dest.as_path()).is_ok());
+        assert!(!source.exists());
+        assert_eq!(
+            fs::read_to_string(dest.join("src/main.rs")).unwrap(),
+            "fn main() {}"
+        );
+        assert_eq!(
+            fs::read_link(dest.join("main.rs")).unwrap(),
+            PathBuf::from("src/main.rs")
+        );
+        // Copy fails: source is kept and nothing is left at the destination
+        let source: PathBuf = tmpdir.path().join("notes.txt");
+        fs::write(source.as_path(), "hello").unwrap();
+        let dest: PathBuf = tmpdir.path().join("missing/notes.txt");
+        assert!(move_across(source.as_path(), dest.as_path()).is_err());
+        assert!(source.exists());
+        assert!(!dest.exists());
+    }
+
+    #[test]
+    fn test_system_trash_names() {
+        assert_eq!(trashed_name("a.txt", 0).as_str(), "a.txt");
+        assert_eq!(trashed_name("a.txt", 1).as_str(), "a.txt.2");
+        let deleted: DateTime<Local> = Local.ymd(2021, 6, 14).and_hms(9, 30, 5);
+        assert_eq!(
+            remote_trashed_name("logs", deleted, 0).as_str(),
+            "logs.20210614-093005"
+        );
+        assert_eq!(
+            remote_trashed_name("logs", deleted, 2).as_str(),
+            "logs.20210614-093005.3"
+        );
+        assert_eq!(
+            resolve_trash_dir(REMOTE_TRASH_DIR, Path::new("/home/omar")),
+            PathBuf::from("/home/omar/.termscp_trash")
+        );
+        assert_eq!(
+            resolve_trash_dir("~", Path::new("/home/omar")),
+            PathBuf::from("/home/omar")
+        );
+        assert_eq!(
+            resolve_trash_dir("/var/trash", Path::new("/home/omar")),
+            PathBuf::from("/var/trash")
+        );
+        assert_eq!(
+            resolve_trash_dir("trash", Path::new("/home/omar")),
+            PathBuf::from("/home/omar/trash")
+        );
+    }
+}
@@ -0,0 +1,347 @@
+//! ## Watcher
+//!
+//! `watcher` notifies the files written in a local directory tree
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Deps
+#[cfg(target_os = "linux")]
+extern crate libc;
+
+// Ext
+use std::collections::HashMap;
+#[cfg(target_os = "linux")]
+use std::ffi::CString;
+use std::fs;
+#[cfg(target_os = "linux")]
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Minimum interval between two scans of the directory tree, when it is polled
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// ## DirWatcher
+///
+/// DirWatcher reports the files created or modified in a directory and its subdirectories.
+/// On Linux changes are notified by inotify; on the other systems (or if inotify
+/// can't be initialized) the directory tree is polled instead
+pub struct DirWatcher {
+    root: PathBuf,
+    backend: Backend,
+}
+
+enum Backend {
+    #[cfg(target_os = "linux")]
+    Inotify(Inotify),
+    Polling(Snapshot),
+}
+
+impl DirWatcher {
+    /// ### new
+    ///
+    /// Start watching `root`
+    pub fn new(root: &Path) -> std::io::Result<DirWatcher> {
+        if !root.is_dir() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("\"{}\" is not a directory", root.display()),
+            ));
+        }
+        #[cfg(target_os = "linux")]
+        if let Ok(inotify) = Inotify::new(root) {
+            return Ok(DirWatcher {
+                root: root.to_path_buf(),
+                backend: Backend::Inotify(inotify),
+            });
+        }
+        Ok(DirWatcher {
+            root: root.to_path_buf(),
+            backend: Backend::Polling(Snapshot::new(root)),
+        })
+    }
+
+    /// ### root
+    ///
+    /// Get the watched directory
+    pub fn root(&self) -> &Path {
+        self.root.as_path()
+    }
+
+    /// ### poll
+    ///
+    /// Get the files written since the last call, without blocking.
+    /// Paths are absolute, sorted and unique
+    pub fn poll(&mut self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = match &mut self.backend {
+            #[cfg(target_os = "linux")]
+            Backend::Inotify(inotify) => inotify.read_events(),
+            Backend::Polling(snapshot) => snapshot.changes(self.root.as_path()),
+        };
+        files.sort();
+        files.dedup();
+        files
+    }
+}
+
+/// ## Snapshot
+///
+/// Modification time and size of the files in a directory tree
+struct Snapshot {
+    files: HashMap<PathBuf, (Option<SystemTime>, u64)>,
+    last_scan: Instant,
+}
+
+impl Snapshot {
+    fn new(root: &Path) -> Self {
+        let mut files = HashMap::new();
+        Self::scan(root, &mut files);
+        Snapshot {
+            files,
+            last_scan: Instant::now(),
+        }
+    }
+
+    /// ### changes
+    ///
+    /// Rescan the tree and return the files which are new or changed since the previous scan.
+    /// The tree is scanned at most once per `POLL_INTERVAL`
+    fn changes(&mut self, root: &Path) -> Vec<PathBuf> {
+        if self.last_scan.elapsed() < POLL_INTERVAL {
+            return Vec::new();
+        }
+        self.last_scan = Instant::now();
+        let mut files = HashMap::new();
+        Self::scan(root, &mut files);
+        let changed: Vec<PathBuf> = files
+            .iter()
+            .filter(|(path, stat)| self.files.get(*path) != Some(*stat))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.files = files;
+        changed
+    }
+
+    fn scan(dir: &Path, files: &mut HashMap<PathBuf, (Option<SystemTime>, u64)>) {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let path: PathBuf = entry.path();
+            match entry.metadata() {
+                Ok(meta) if meta.is_dir() => Self::scan(path.as_path(), files),
+                Ok(meta) if meta.is_file() => {
+                    files.insert(path, (meta.modified().ok(), meta.len()));
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// ## Inotify
+///
+/// Inotify instance watching every directory of a tree
+#[cfg(target_os = "linux")]
+struct Inotify {
+    fd: libc::c_int,
+    watches: HashMap<libc::c_int, PathBuf>,
+}
+
+#[cfg(target_os = "linux")]
+impl Inotify {
+    /// Size of the header of `inotify_event`, followed by `len` bytes of name
+    const EVENT_HEADER_SIZE: usize = 16;
+
+    fn new(root: &Path) -> std::io::Result<Self> {
+        let fd: libc::c_int = unsafe { libc::inotify_init1(libc::IN_NONBLOCK | libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut inotify = Inotify {
+            fd,
+            watches: HashMap::new(),
+        };
+        inotify.watch_tree(root, None)?;
+        Ok(inotify)
+    }
+
+    /// ### watch_tree
+    ///
+    /// Watch `dir` and its subdirectories. If `files` is Some, the files found in the tree
+    /// are pushed into it, since they may have been written before the watch was added
+    fn watch_tree(
+        &mut self,
+        dir: &Path,
+        mut files: Option<&mut Vec<PathBuf>>,
+    ) -> std::io::Result<()> {
+        let path: CString = CString::new(dir.as_os_str().as_bytes())
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        let mask: u32 =
+            libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE | libc::IN_ONLYDIR;
+        let wd: libc::c_int = unsafe { libc::inotify_add_watch(self.fd, path.as_ptr(), mask) };
+        if wd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        self.watches.insert(wd, dir.to_path_buf());
+        for entry in fs::read_dir(dir)?.flatten() {
+            match entry.file_type() {
+                Ok(t) if t.is_dir() => {
+                    let _ = self.watch_tree(entry.path().as_path(), files.as_deref_mut());
+                }
+                Ok(t) if t.is_file() => {
+                    if let Some(files) = files.as_deref_mut() {
+                        files.push(entry.path());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// ### read_events
+    ///
+    /// Read the pending events and return the files which have been written
+    fn read_events(&mut self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = Vec::new();
+        let mut buf: [u8; 4096] = [0; 4096];
+        loop {
+            let size: isize =
+                unsafe { libc::read(self.fd, buf.as_mut_ptr() as *mut libc::c_void, buf.len()) };
+            if size <= 0 {
+                break;
+            }
+            let size: usize = size as usize;
+            let mut offset: usize = 0;
+            while offset + Self::EVENT_HEADER_SIZE <= size {
+                let field = |at: usize| -> [u8; 4] {
+                    let mut bytes: [u8; 4] = [0; 4];
+                    bytes.copy_from_slice(&buf[offset + at..offset + at + 4]);
+                    bytes
+                };
+                let wd: libc::c_int = libc::c_int::from_ne_bytes(field(0));
+                let mask: u32 = u32::from_ne_bytes(field(4));
+                let len: usize = u32::from_ne_bytes(field(12)) as usize;
+                let name_start: usize = offset + Self::EVENT_HEADER_SIZE;
+                let name: &[u8] = &buf[name_start..(name_start + len).min(size)];
+                // Name is padded with nul bytes
+                let name: &[u8] = match name.iter().position(|x| *x == 0) {
+                    Some(end) => &name[..end],
+                    None => name,
+                };
+                offset = name_start + len;
+                if mask & libc::IN_IGNORED != 0 {
+                    self.watches.remove(&wd);
+                    continue;
+                }
+                let path: PathBuf = match self.watches.get(&wd) {
+                    Some(dir) if !name.is_empty() => dir.join(std::ffi::OsStr::from_bytes(name)),
+                    _ => continue,
+                };
+                if mask & libc::IN_ISDIR != 0 {
+                    if mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0 {
+                        let _ = self.watch_tree(path.as_path(), Some(&mut files));
+                    }
+                } else if mask & (libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO) != 0 {
+                    files.push(path);
+                }
+            }
+        }
+        files
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Inotify {
+    fn drop(&mut self) {
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::io::Write;
+    use tempfile::TempDir;
+
+    fn write_file(path: &Path, content: &str) {
+        let mut file = fs::File::create(path).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
+    }
+
+    #[test]
+    fn test_system_watcher_poll() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        write_file(tmpdir.path().join("existing.txt").as_path(), "old");
+        let mut watcher: DirWatcher = DirWatcher::new(tmpdir.path()).unwrap();
+        assert_eq!(watcher.root(), tmpdir.path());
+        // Files existing when the watch started are not reported
+        std::thread::sleep(POLL_INTERVAL);
+        assert!(watcher.poll().is_empty());
+        // Write files, also in a new subdirectory
+        write_file(tmpdir.path().join("existing.txt").as_path(), "new content");
+        fs::create_dir(tmpdir.path().join("src")).unwrap();
+        write_file(tmpdir.path().join("src/main.rs").as_path(), "fn main() {}");
+        std::thread::sleep(POLL_INTERVAL);
+        assert_eq!(
+            watcher.poll(),
+            vec![
+                tmpdir.path().join("existing.txt"),
+                tmpdir.path().join("src/main.rs"),
+            ]
+        );
+        // Changes are reported once
+        assert!(watcher.poll().is_empty());
+        // Not a directory
+        assert!(DirWatcher::new(tmpdir.path().join("existing.txt").as_path()).is_err());
+    }
+
+    #[test]
+    fn test_system_watcher_snapshot() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        write_file(tmpdir.path().join("a.txt").as_path(), "a");
+        let mut snapshot: Snapshot = Snapshot::new(tmpdir.path());
+        // Not scanned before the poll interval
+        write_file(tmpdir.path().join("b.txt").as_path(), "b");
+        assert!(snapshot.changes(tmpdir.path()).is_empty());
+        std::thread::sleep(POLL_INTERVAL);
+        assert_eq!(
+            snapshot.changes(tmpdir.path()),
+            vec![tmpdir.path().join("b.txt")]
+        );
+        // Size changes are detected
+        write_file(tmpdir.path().join("a.txt").as_path(), "aaa");
+        std::thread::sleep(POLL_INTERVAL);
+        assert_eq!(
+            snapshot.changes(tmpdir.path()),
+            vec![tmpdir.path().join("a.txt")]
+        );
+    }
+}
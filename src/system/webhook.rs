@@ -0,0 +1,241 @@
+//! ## Webhook
+//!
+//! `webhook` is the module which notifies an HTTP endpoint when a batch of transfers has been processed,
+//! to integrate termscp with chats, alerting and deployment pipelines
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::filetransfer::NetworkOptions;
+use crate::fs::queue::TransferDirection;
+// Ext
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// ## Webhook
+///
+/// HTTP endpoint called with a JSON summary of each batch of transfers
+#[derive(Clone, Debug, PartialEq)]
+pub struct Webhook {
+    url: String,
+    token: Option<String>, // Sent as bearer token
+}
+
+/// ## BatchStatus
+///
+/// Outcome of a batch of transfers
+#[derive(Copy, Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BatchStatus {
+    Completed, // All the transfers have succeeded
+    Failed,    // Some transfers have failed or the batch has been aborted
+}
+
+/// ## BatchSummary
+///
+/// The JSON payload posted to the webhook
+#[derive(Debug, Serialize)]
+pub struct BatchSummary {
+    pub status: BatchStatus,
+    pub text: String, // Human readable summary; understood by Slack and Mattermost incoming webhooks
+    pub completed: usize,
+    pub failed: usize,
+    pub transfers: Vec<TransferSummary>,
+}
+
+/// ## TransferSummary
+///
+/// A transfer of the batch
+#[derive(Debug, Serialize)]
+pub struct TransferSummary {
+    pub host: String,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub direction: TransferDirection,
+    pub error: Option<String>, // `None` if the transfer has succeeded
+}
+
+impl Webhook {
+    /// ### new
+    ///
+    /// Instantiates a new `Webhook`; an empty token is not sent
+    pub fn new<S: AsRef<str>>(url: S, token: Option<S>) -> Self {
+        Webhook {
+            url: url.as_ref().to_string(),
+            token: token
+                .map(|x| x.as_ref().trim().to_string())
+                .filter(|x| !x.is_empty()),
+        }
+    }
+
+    /// ### notify
+    ///
+    /// Post `summary` to the webhook. The request honours the timeout and the proxy of `network`
+    pub fn notify(&self, summary: &BatchSummary, network: &NetworkOptions) -> Result<(), String> {
+        let agent: ureq::Agent = network
+            .http_agent()
+            .map_err(|e| e.to_string())?
+            .timeout_read(network.connection_timeout)
+            .build();
+        let mut request: ureq::Request = agent.post(self.url.as_str());
+        if let Some(token) = self.token.as_ref() {
+            request = request.set("Authorization", format!("Bearer {}", token).as_str());
+        }
+        let payload: serde_json::Value =
+            serde_json::to_value(summary).map_err(|e| e.to_string())?;
+        match request.send_json(payload) {
+            Ok(_) => Ok(()),
+            Err(ureq::Error::Status(status, response)) => Err(format!(
+                "the webhook answered {} {}",
+                status,
+                response.status_text()
+            )),
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+impl BatchSummary {
+    /// ### new
+    ///
+    /// Summarize `transfers`; the batch has failed if any transfer has failed or if it has been `aborted`
+    pub fn new(transfers: Vec<TransferSummary>, aborted: bool) -> Self {
+        let failed: usize = transfers.iter().filter(|x| x.error.is_some()).count();
+        let completed: usize = transfers.len() - failed;
+        let status: BatchStatus = match failed == 0 && !aborted {
+            true => BatchStatus::Completed,
+            false => BatchStatus::Failed,
+        };
+        let mut text: String = format!(
+            "termscp: {} transfers completed, {} failed",
+            completed, failed
+        );
+        if aborted {
+            text.push_str(" (aborted)");
+        }
+        BatchSummary {
+            status,
+            text,
+            completed,
+            failed,
+            transfers,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn make_transfer(error: Option<&str>) -> TransferSummary {
+        TransferSummary {
+            host: String::from("example.com"),
+            source: PathBuf::from("/home/omar/site"),
+            destination: PathBuf::from("/var/www"),
+            direction: TransferDirection::Upload,
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_system_webhook_summary() {
+        let summary: BatchSummary = BatchSummary::new(vec![make_transfer(None)], false);
+        assert_eq!(summary.status, BatchStatus::Completed);
+        assert_eq!(
+            summary.text.as_str(),
+            "termscp: 1 transfers completed, 0 failed"
+        );
+        let summary: BatchSummary = BatchSummary::new(vec![make_transfer(None)], true);
+        assert_eq!(summary.status, BatchStatus::Failed);
+        let summary: BatchSummary = BatchSummary::new(
+            vec![
+                make_transfer(None),
+                make_transfer(Some("Permission denied")),
+            ],
+            false,
+        );
+        assert_eq!(summary.status, BatchStatus::Failed);
+        assert_eq!((summary.completed, summary.failed), (1, 1));
+        // Serialize
+        let json: serde_json::Value = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["status"], "failed");
+        assert_eq!(json["transfers"][0]["direction"], "upload");
+        assert_eq!(json["transfers"][1]["error"], "Permission denied");
+        // Empty token is not sent
+        assert_eq!(
+            Webhook::new("http://localhost", Some(" ")),
+            Webhook::new("http://localhost", None)
+        );
+    }
+
+    #[test]
+    fn test_system_webhook_notify() {
+        let listener: TcpListener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url: String = format!("http://{}/hooks/deploy", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader: BufReader<_> = BufReader::new(stream);
+            let mut headers: Vec<String> = Vec::new();
+            loop {
+                let mut line: String = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push(line.trim().to_lowercase());
+            }
+            let length: usize = headers
+                .iter()
+                .find_map(|x| x.strip_prefix("content-length: "))
+                .map(|x| x.parse().unwrap())
+                .unwrap();
+            let mut body: Vec<u8> = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            reader
+                .get_mut()
+                .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+                .unwrap();
+            (headers, body)
+        });
+        let webhook: Webhook = Webhook::new(url.as_str(), Some("s3cr3t"));
+        let summary: BatchSummary = BatchSummary::new(vec![make_transfer(None)], false);
+        assert!(webhook.notify(&summary, &NetworkOptions::default()).is_ok());
+        let (headers, body) = server.join().unwrap();
+        assert_eq!(headers[0].as_str(), "post /hooks/deploy http/1.1");
+        assert!(headers.contains(&String::from("authorization: bearer s3cr3t")));
+        let json: serde_json::Value = serde_json::from_slice(body.as_slice()).unwrap();
+        assert_eq!(json["status"], "completed");
+        assert_eq!(json["transfers"][0]["host"], "example.com");
+        // Unreachable endpoint
+        let webhook: Webhook = Webhook::new("http://127.0.0.1:1/hooks", None);
+        assert!(webhook
+            .notify(&summary, &NetworkOptions::default())
+            .is_err());
+    }
+}
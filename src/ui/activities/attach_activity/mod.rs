@@ -0,0 +1,273 @@
+//! ## AttachActivity
+//!
+//! `attach_activity` is the module which implements the Attach activity, which is the activity
+//! to monitor and control the transfers of a running daemon
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Submodules
+mod update;
+mod view;
+
+// Deps
+extern crate crossterm;
+extern crate tui;
+
+// Locals
+use super::{Activity, Context, ExitReason};
+use crate::daemon::client::DaemonClient;
+use crate::daemon::{Job, LogRecord};
+use crate::ui::input::InputHandler;
+use crate::ui::layout::view::View;
+use crate::ui::layout::Payload;
+// Ext
+use crossterm::event::Event as InputEvent;
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+// -- storage keys
+const STORAGE_LOGBOX_WIDTH: &str = "ATTACH_LOGBOX_WIDTH";
+
+// -- components
+const COMPONENT_LIST_JOBS: &str = "LIST_JOBS";
+const COMPONENT_LOG_BOX: &str = "LOG_BOX";
+const COMPONENT_PROGRESS_BAR: &str = "PROGRESS_BAR";
+const COMPONENT_TEXT_ERROR: &str = "TEXT_ERROR";
+const COMPONENT_TEXT_FATAL: &str = "TEXT_FATAL";
+const COMPONENT_TEXT_FOOTER: &str = "TEXT_FOOTER";
+
+/// Interval between two requests of the daemon state
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// Amount of log records kept by the activity
+const LOG_SIZE: usize = 256;
+
+/// ## AttachActivity
+///
+/// Attach activity states holder.
+/// The activity only mirrors the state of the daemon: detaching leaves the transfers running
+pub struct AttachActivity {
+    exit_reason: Option<ExitReason>,
+    context: Option<Context>,
+    view: View,
+    socket: PathBuf,                  // Daemon control socket
+    client: Option<DaemonClient>,     // `None` if the connection with the daemon has been lost
+    jobs: Vec<Job>,                   // Transfers, as reported by the last poll
+    log_records: VecDeque<LogRecord>, // Daemon log records; newest first
+    last_poll: Option<Instant>,       // Last time the daemon state has been requested
+    redraw: bool,
+}
+
+impl AttachActivity {
+    /// ### new
+    ///
+    /// Instantiates a new AttachActivity, which will connect to the daemon listening on `socket`
+    pub fn new(socket: &Path) -> AttachActivity {
+        AttachActivity {
+            exit_reason: None,
+            context: None,
+            view: View::init(),
+            socket: socket.to_path_buf(),
+            client: None,
+            jobs: Vec::new(),
+            log_records: VecDeque::with_capacity(LOG_SIZE),
+            last_poll: None,
+            redraw: true, // Draw at first `on_draw`
+        }
+    }
+
+    /// ### poll
+    ///
+    /// Get the transfers and the new log records from the daemon, then update the view.
+    /// If the daemon can't be reached anymore, the connection is closed and the user is told so
+    fn poll(&mut self) {
+        self.last_poll = Some(Instant::now());
+        let since: Option<u64> = self.log_records.front().map(|x| x.seq);
+        let result: Result<(Vec<Job>, Vec<LogRecord>), String> = match self.client.as_mut() {
+            Some(client) => client
+                .status()
+                .and_then(|jobs| client.logs(since).map(|logs| (jobs, logs))),
+            None => return,
+        };
+        match result {
+            Ok((jobs, logs)) => {
+                self.jobs = jobs;
+                // Log box scrolls back to top when updated: update it only if there are new records
+                let new_records: bool = !logs.is_empty();
+                // Newest records first
+                for record in logs.into_iter() {
+                    // Repeats of the last record are fetched again with the new count
+                    if record.repeated > 0
+                        && self
+                            .log_records
+                            .front()
+                            .map(|x| x.level == record.level && x.msg == record.msg)
+                            .unwrap_or(false)
+                    {
+                        self.log_records.pop_front();
+                    }
+                    if self.log_records.len() >= LOG_SIZE {
+                        self.log_records.pop_back();
+                    }
+                    self.log_records.push_front(record);
+                }
+                let _ = self.update_jobs();
+                let _ = self.update_progress_bar();
+                if new_records {
+                    let _ = self.update_logbox();
+                }
+            }
+            Err(err) => {
+                self.client = None;
+                self.mount_fatal(format!("Connection with daemon lost: {}", err).as_str());
+            }
+        }
+        self.redraw = true;
+    }
+
+    /// ### call
+    ///
+    /// Send a request to the daemon through `op`, then poll the daemon state.
+    /// Errors are shown in a popup
+    fn call<F>(&mut self, op: F)
+    where
+        F: FnOnce(&mut DaemonClient) -> Result<(), String>,
+    {
+        if let Some(Err(err)) = self.client.as_mut().map(op) {
+            self.mount_error(err.as_str());
+        }
+        self.poll();
+    }
+
+    /// ### selected_job
+    ///
+    /// Get the id of the transfer selected in the list
+    fn selected_job(&self) -> Option<usize> {
+        match self.view.get_value(COMPONENT_LIST_JOBS) {
+            Some(Payload::Unsigned(idx)) => self.jobs.get(idx).map(|x| x.id),
+            _ => None,
+        }
+    }
+}
+
+impl Activity for AttachActivity {
+    /// ### on_create
+    ///
+    /// `on_create` is the function which must be called to initialize the activity.
+    /// `on_create` must initialize all the data structures used by the activity
+    /// Context is taken from activity manager and will be released only when activity is destroyed
+    fn on_create(&mut self, context: Context) {
+        // Set context
+        self.context = Some(context);
+        // Clear terminal
+        self.context.as_mut().unwrap().clear_screen();
+        // Put raw mode on enabled
+        let _ = enable_raw_mode();
+        // Init view
+        self.init();
+        // Connect to daemon; the transfers and the log are restored at first poll
+        match DaemonClient::connect(self.socket.as_path()) {
+            Ok(client) => self.client = Some(client),
+            Err(err) => self.mount_fatal(err.as_str()),
+        }
+    }
+
+    /// ### on_draw
+    ///
+    /// `on_draw` is the function which draws the graphical interface.
+    /// This function must be called at each tick to refresh the interface
+    fn on_draw(&mut self) {
+        // Context must be something
+        if self.context.is_none() {
+            return;
+        }
+        // Read one event
+        if let Ok(Some(event)) = self.context.as_ref().unwrap().input_hnd.read_event() {
+            // Set redraw to true
+            self.redraw = true;
+            // Lay out ui again if terminal has been resized
+            if let InputEvent::Resize(width, height) = event {
+                self.context.as_mut().unwrap().resize(width, height);
+            }
+            // Suspend to shell; screen is redrawn at next tick once resumed
+            if InputHandler::is_suspend_event(&event) {
+                self.context.as_mut().unwrap().suspend();
+                return;
+            }
+            // Handle event
+            let msg = self.view.on(event);
+            self.update(msg);
+        }
+        // Poll daemon
+        if self
+            .last_poll
+            .map(|x| x.elapsed() >= POLL_INTERVAL)
+            .unwrap_or(true)
+        {
+            self.poll();
+        }
+        // Show toasts
+        if self.context.as_mut().unwrap().poll_toast() {
+            self.redraw = true;
+        }
+        // Redraw if necessary
+        if self.redraw {
+            // View
+            self.view();
+            // Redraw back to false
+            self.redraw = false;
+        }
+    }
+
+    /// ### will_umount
+    ///
+    /// `will_umount` is the method which must be able to report to the activity manager, whether
+    /// the activity should be terminated or not.
+    /// If not, the call will return `None`, otherwise return`Some(ExitReason)`
+    fn will_umount(&self) -> Option<&ExitReason> {
+        self.exit_reason.as_ref()
+    }
+
+    /// ### on_destroy
+    ///
+    /// `on_destroy` is the function which cleans up runtime variables and data before terminating the activity.
+    /// This function must be called once before terminating the activity.
+    /// This function finally releases the context
+    fn on_destroy(&mut self) -> Option<Context> {
+        // Detach; the daemon keeps running
+        self.client = None;
+        // Disable raw mode
+        let _ = disable_raw_mode();
+        self.context.as_ref()?;
+        // Clear terminal and return
+        match self.context.take() {
+            Some(mut ctx) => {
+                ctx.clear_screen();
+                Some(ctx)
+            }
+            None => None,
+        }
+    }
+}
@@ -0,0 +1,289 @@
+//! ## AttachActivity
+//!
+//! `attach_activity` is the module which implements the Attach activity, which is the activity
+//! to monitor and control the transfers of a running daemon
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// locals
+use super::{
+    AttachActivity, COMPONENT_LIST_JOBS, COMPONENT_LOG_BOX, COMPONENT_PROGRESS_BAR,
+    COMPONENT_TEXT_ERROR, COMPONENT_TEXT_FATAL, STORAGE_LOGBOX_WIDTH,
+};
+use crate::daemon::{Job, JobState, LogLevel};
+use crate::fs::queue::TransferDirection;
+use crate::ui::activities::keymap::*;
+use crate::ui::activities::ExitReason;
+use crate::ui::layout::props::{PropValue, TableBuilder, TextParts, TextSpan, TextSpanBuilder};
+use crate::ui::layout::Msg;
+// externals
+use bytesize::ByteSize;
+use chrono::{DateTime, Local};
+use tui::style::Color;
+
+impl AttachActivity {
+    /// ### update
+    ///
+    /// Update attach activity model based on msg
+    /// The function exits when returns None
+    pub(super) fn update(&mut self, msg: Option<(String, Msg)>) -> Option<(String, Msg)> {
+        let ref_msg: Option<(&str, &Msg)> = msg.as_ref().map(|(s, msg)| (s.as_str(), msg));
+        match ref_msg {
+            None => None,
+            Some(msg) => match msg {
+                // -- jobs
+                (COMPONENT_LIST_JOBS, &MSG_KEY_TAB) => {
+                    self.view.active(COMPONENT_LOG_BOX);
+                    None
+                }
+                (COMPONENT_LIST_JOBS, &MSG_KEY_CHAR_R) => {
+                    if let Some(id) = self.selected_job() {
+                        self.call(|client| client.retry(id));
+                    }
+                    None
+                }
+                (COMPONENT_LIST_JOBS, &MSG_KEY_DEL) | (COMPONENT_LIST_JOBS, &MSG_KEY_CHAR_E) => {
+                    if let Some(id) = self.selected_job() {
+                        self.call(|client| client.remove(id));
+                    }
+                    None
+                }
+                (COMPONENT_LIST_JOBS, &MSG_KEY_CHAR_C) => {
+                    self.call(|client| client.clear());
+                    None
+                }
+                // -- log box
+                (COMPONENT_LOG_BOX, &MSG_KEY_TAB) => {
+                    self.view.active(COMPONENT_LIST_JOBS);
+                    None
+                }
+                // -- detach
+                (COMPONENT_LIST_JOBS, &MSG_KEY_ESC)
+                | (COMPONENT_LIST_JOBS, &MSG_KEY_CHAR_Q)
+                | (COMPONENT_LOG_BOX, &MSG_KEY_ESC)
+                | (COMPONENT_LOG_BOX, &MSG_KEY_CHAR_Q) => {
+                    self.exit_reason = Some(ExitReason::Quit);
+                    None
+                }
+                // -- error
+                (COMPONENT_TEXT_ERROR, &MSG_KEY_ESC) | (COMPONENT_TEXT_ERROR, &MSG_KEY_ENTER) => {
+                    self.umount_error();
+                    None
+                }
+                (COMPONENT_TEXT_FATAL, &MSG_KEY_ESC) | (COMPONENT_TEXT_FATAL, &MSG_KEY_ENTER) => {
+                    self.exit_reason = Some(ExitReason::Quit);
+                    None
+                }
+                (_, _) => None, // Nothing to do
+            },
+        }
+    }
+
+    /// ### update_jobs
+    ///
+    /// Update the list of the transfers
+    pub(super) fn update_jobs(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_LIST_JOBS).as_mut() {
+            Some(props) => {
+                let items: Vec<TextSpan> = self
+                    .jobs
+                    .iter()
+                    .map(|x| {
+                        let direction: &str = match x.direction {
+                            TransferDirection::Upload => "↑",
+                            TransferDirection::Download => "↓",
+                        };
+                        let state: String = match (x.state, x.error.as_ref()) {
+                            (JobState::Pending, _) => String::from("pending"),
+                            (JobState::Running, _) => format!("{}%", Self::percent(x) as u64),
+                            (JobState::Done, _) => String::from("done"),
+                            (JobState::Failed, Some(err)) => format!("failed: {}", err),
+                            (JobState::Failed, None) => String::from("failed"),
+                        };
+                        TextSpan::from(
+                            format!(
+                                "{:<4} {} {} {} => {} ({})",
+                                x.id,
+                                direction,
+                                x.bookmark,
+                                x.source.display(),
+                                x.destination.display(),
+                                state
+                            )
+                            .as_str(),
+                        )
+                    })
+                    .collect();
+                let title: String = format!("Daemon transfers ({})", self.socket.display());
+                let props = props
+                    .with_texts(TextParts::new(Some(title), Some(items)))
+                    .build();
+                self.view.update(COMPONENT_LIST_JOBS, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_progress_bar
+    ///
+    /// Update the progress bar with the progress of the running transfer
+    pub(super) fn update_progress_bar(&mut self) -> Option<(String, Msg)> {
+        let running: Option<&Job> = self.jobs.iter().find(|x| x.state == JobState::Running);
+        let (title, label, progress): (String, String, f64) = match running {
+            Some(job) => (
+                format!("Transferring {}", job.source.display()),
+                format!(
+                    "{:.2}% - {}/{}",
+                    Self::percent(job),
+                    ByteSize(job.bytes_done),
+                    ByteSize(job.bytes_total)
+                ),
+                Self::percent(job) / 100.0,
+            ),
+            None => (
+                String::from("Idle"),
+                format!(
+                    "{} pending transfers",
+                    self.jobs
+                        .iter()
+                        .filter(|x| x.state == JobState::Pending)
+                        .count()
+                ),
+                0.0,
+            ),
+        };
+        match self.view.get_props(COMPONENT_PROGRESS_BAR).as_mut() {
+            Some(props) => {
+                let props = props
+                    .with_texts(TextParts::new(
+                        Some(title),
+                        Some(vec![TextSpan::from(label)]),
+                    ))
+                    .with_value(PropValue::Float(progress))
+                    .build();
+                self.view.update(COMPONENT_PROGRESS_BAR, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_logbox
+    ///
+    /// Update the log box with the daemon log records
+    pub(super) fn update_logbox(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_LOG_BOX).as_mut() {
+            Some(props) => {
+                // Get width
+                let width: usize = self
+                    .context
+                    .as_ref()
+                    .unwrap()
+                    .store
+                    .get_unsigned(STORAGE_LOGBOX_WIDTH)
+                    .unwrap_or(256);
+                // Make log entries
+                let mut table: TableBuilder = TableBuilder::default();
+                for (idx, record) in self.log_records.iter().enumerate() {
+                    // Split rows by width  NOTE: -37 'cause log prefix -3 cause of log line cursor
+                    let record_rows =
+                        textwrap::wrap(record.msg.as_str(), width.saturating_sub(40).max(1));
+                    // Add row if not first row
+                    if idx > 0 {
+                        table.add_row();
+                    }
+                    let (fg, level): (Color, &str) = match record.level {
+                        LogLevel::Error => (Color::Red, "ERROR"),
+                        LogLevel::Warn => (Color::Yellow, "WARN"),
+                        LogLevel::Info => (Color::Green, "INFO"),
+                    };
+                    let time: DateTime<Local> = DateTime::from(record.time);
+                    for (idx, row) in record_rows.iter().enumerate() {
+                        match idx {
+                            0 => {
+                                // First row
+                                table
+                                    .add_col(TextSpan::from(format!(
+                                        "{}",
+                                        time.format("%Y-%m-%dT%H:%M:%S%Z")
+                                    )))
+                                    .add_col(TextSpan::from(" ["))
+                                    .add_col(
+                                        TextSpanBuilder::new(format!("{:5}", level).as_str())
+                                            .with_foreground(fg)
+                                            .build(),
+                                    )
+                                    .add_col(TextSpan::from("]: "))
+                                    .add_col(TextSpan::from(row.as_ref()));
+                            }
+                            _ => {
+                                table.add_col(TextSpan::from(textwrap::indent(
+                                    row.as_ref(),
+                                    "                                        ",
+                                )));
+                            }
+                        }
+                    }
+                    // Collapsed repeats
+                    if record.repeated > 0 {
+                        let repeats: String = format!(
+                            "{} message repeated {} times",
+                            match record.similar {
+                                true => "similar",
+                                false => "previous",
+                            },
+                            record.repeated
+                        );
+                        table.add_col(
+                            TextSpanBuilder::new(
+                                textwrap::indent(
+                                    repeats.as_str(),
+                                    "                                        ",
+                                )
+                                .as_str(),
+                            )
+                            .italic()
+                            .build(),
+                        );
+                    }
+                }
+                let table = table.build();
+                let props = props
+                    .with_texts(TextParts::table(Some(String::from("Daemon log")), table))
+                    .build();
+                self.view.update(COMPONENT_LOG_BOX, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### percent
+    ///
+    /// Get the percentage of the bytes transferred by `job`
+    fn percent(job: &Job) -> f64 {
+        match job.bytes_total {
+            0 => 0.0,
+            total => (job.bytes_done as f64 * 100.0 / total as f64).min(100.0),
+        }
+    }
+}
@@ -0,0 +1,225 @@
+//! ## AttachActivity
+//!
+//! `attach_activity` is the module which implements the Attach activity, which is the activity
+//! to monitor and control the transfers of a running daemon
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::{AttachActivity, Context};
+use crate::ui::layout::accessibility::{ColorFilter, ColorMode};
+use crate::ui::layout::components::{
+    bookmark_list::BookmarkList, logbox::LogBox, msgbox::MsgBox, progress_bar::ProgressBar,
+    text::Text,
+};
+use crate::ui::layout::props::{PropsBuilder, TextParts, TextSpan, TextSpanBuilder};
+use crate::ui::layout::toast::Toast;
+use crate::ui::layout::utils::draw_area_in;
+use crate::ui::store::Store;
+// Ext
+use tui::{
+    layout::{Constraint, Direction, Layout},
+    style::Color,
+    widgets::Clear,
+};
+
+impl AttachActivity {
+    // -- init
+
+    /// ### init
+    ///
+    /// Initialize attach activity's view
+    pub(super) fn init(&mut self) {
+        // Mount transfers
+        self.view.mount(
+            super::COMPONENT_LIST_JOBS,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightGreen)
+                    .with_foreground(Color::Black)
+                    .build(),
+            )),
+        );
+        // Mount progress bar
+        self.view.mount(
+            super::COMPONENT_PROGRESS_BAR,
+            Box::new(ProgressBar::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from("Idle")), None))
+                    .build(),
+            )),
+        );
+        // Mount log box
+        self.view.mount(
+            super::COMPONENT_LOG_BOX,
+            Box::new(LogBox::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .bold()
+                    .build(),
+            )),
+        );
+        // Mount footer
+        let key = |k: &str| {
+            TextSpanBuilder::new(k)
+                .bold()
+                .with_foreground(Color::Cyan)
+                .build()
+        };
+        self.view.mount(
+            super::COMPONENT_TEXT_FOOTER,
+            Box::new(Text::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        None,
+                        Some(vec![
+                            key("<ESC>"),
+                            TextSpan::from(" detach  "),
+                            key("<R>"),
+                            TextSpan::from(" retry  "),
+                            key("<DEL>"),
+                            TextSpan::from(" remove  "),
+                            key("<C>"),
+                            TextSpan::from(" clear done  "),
+                            key("<TAB>"),
+                            TextSpan::from(" switch to log"),
+                        ]),
+                    ))
+                    .build(),
+            )),
+        );
+        // Update components
+        let _ = self.update_jobs();
+        let _ = self.update_progress_bar();
+        // Give focus to transfers
+        self.view.active(super::COMPONENT_LIST_JOBS);
+    }
+
+    /// ### view
+    ///
+    /// View gui
+    pub(super) fn view(&mut self) {
+        let mut ctx: Context = self.context.take().unwrap();
+        let color_mode: ColorMode = ctx.color_mode();
+        let toast: Option<Toast> = ctx.toast();
+        let store: &mut Store = &mut ctx.store;
+        let _ = ctx.terminal.draw(|f| {
+            // Prepare chunks
+            let chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .margin(1)
+                .constraints(
+                    [
+                        Constraint::Percentage(50), // Transfers
+                        Constraint::Length(3),      // Progress bar
+                        Constraint::Percentage(50), // Log
+                        Constraint::Length(1),      // Footer
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
+            // Keep log box width in the storage up to date (it changes when terminal is resized)
+            store.set_unsigned(super::STORAGE_LOGBOX_WIDTH, chunks[2].width as usize);
+            self.view.render(super::COMPONENT_LIST_JOBS, f, chunks[0]);
+            self.view
+                .render(super::COMPONENT_PROGRESS_BAR, f, chunks[1]);
+            self.view.render(super::COMPONENT_LOG_BOX, f, chunks[2]);
+            self.view.render(super::COMPONENT_TEXT_FOOTER, f, chunks[3]);
+            // Draw popups
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_TEXT_ERROR) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_TEXT_ERROR, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_TEXT_FATAL) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_TEXT_FATAL, f, popup);
+                }
+            }
+            // Toast on top of everything
+            if let Some(toast) = toast {
+                f.render_widget(toast, f.size());
+            }
+            // Apply color mode (must come last)
+            f.render_widget(ColorFilter::new(color_mode), f.size());
+        });
+        // Put context back to context
+        self.context = Some(ctx);
+    }
+
+    // -- mount
+
+    /// ### mount_error
+    ///
+    /// Mount error box
+    pub(super) fn mount_error(&mut self, text: &str) {
+        // Mount
+        self.view.mount(
+            super::COMPONENT_TEXT_ERROR,
+            Box::new(MsgBox::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .bold()
+                    .with_texts(TextParts::new(None, Some(vec![TextSpan::from(text)])))
+                    .build(),
+            )),
+        );
+        // Give focus to error
+        self.view.active(super::COMPONENT_TEXT_ERROR);
+    }
+
+    /// ### umount_error
+    ///
+    /// Umount error message
+    pub(super) fn umount_error(&mut self) {
+        self.view.umount(super::COMPONENT_TEXT_ERROR);
+    }
+
+    /// ### mount_fatal
+    ///
+    /// Mount fatal error box; the activity terminates once it's closed
+    pub(super) fn mount_fatal(&mut self, text: &str) {
+        // Mount
+        self.view.mount(
+            super::COMPONENT_TEXT_FATAL,
+            Box::new(MsgBox::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .bold()
+                    .with_texts(TextParts::new(None, Some(vec![TextSpan::from(text)])))
+                    .build(),
+            )),
+        );
+        // Give focus to error
+        self.view.active(super::COMPONENT_TEXT_FATAL);
+    }
+}
@@ -29,9 +29,12 @@
 extern crate dirs;
 
 // Locals
-use super::{AuthActivity, FileTransferProtocol};
+use super::{AuthActivity, FileTransferProtocol, SshAlgorithms};
+use crate::bookmarks::BookmarkTag;
+use crate::fs::transfer::TimeWindow;
 use crate::system::bookmarks_client::BookmarksClient;
 use crate::system::environment;
+use crate::ui::context::{StartupIssue, StartupIssueKind};
 use crate::ui::layout::props::PropValue;
 use crate::ui::layout::Payload;
 
@@ -64,10 +67,25 @@ impl AuthActivity {
             // Iterate over bookmarks
             if let Some(key) = self.bookmarks_list.get(idx) {
                 if let Some(bookmark) = bookmarks_cli.get_bookmark(&key) {
+                    let (endpoint, region) = bookmarks_cli
+                        .get_bookmark_s3_endpoint(key)
+                        .unwrap_or_default();
+                    let algorithms: SshAlgorithms = bookmarks_cli
+                        .get_bookmark_ssh_algorithms(key)
+                        .unwrap_or_default();
+                    let tag: Option<BookmarkTag> = bookmarks_cli.get_bookmark_tag(key);
+                    let protected: bool = bookmarks_cli.is_bookmark_protected(key);
+                    let quiet_hours: Option<TimeWindow> =
+                        bookmarks_cli.get_bookmark_quiet_hours(key);
                     // Load parameters into components
                     self.load_bookmark_into_gui(
                         bookmark.0, bookmark.1, bookmark.2, bookmark.3, bookmark.4,
                     );
+                    self.load_s3_endpoint_into_gui(endpoint, region);
+                    self.ssh_algorithms = algorithms;
+                    self.bookmark_tag = tag;
+                    self.bookmark_protected = protected;
+                    self.bookmark_quiet_hours = quiet_hours;
                 }
             }
         }
@@ -75,9 +93,25 @@ impl AuthActivity {
 
     /// ### save_bookmark
     ///
-    /// Save current input fields as a bookmark
-    pub(super) fn save_bookmark(&mut self, name: String, save_password: bool) {
+    /// Save current input fields as a bookmark.
+    /// Invalid quiet hours are discarded; the error is returned once the bookmark has been saved
+    pub(super) fn save_bookmark(
+        &mut self,
+        name: String,
+        save_password: bool,
+    ) -> Result<(), String> {
         let (address, port, protocol, username, password) = self.get_input();
+        let (endpoint, region) = match protocol {
+            FileTransferProtocol::S3 => self.get_s3_input(),
+            _ => (None, None),
+        };
+        let tag: Option<BookmarkTag> = self.get_bookmark_tag_input();
+        let protected: bool = matches!(
+            self.view
+                .get_value(super::COMPONENT_RADIO_BOOKMARK_PROTECTED),
+            Some(Payload::Unsigned(0))
+        );
+        let quiet_hours: Result<Option<TimeWindow>, String> = self.get_bookmark_quiet_hours_input();
         if let Some(bookmarks_cli) = self.bookmarks_client.as_mut() {
             // Check if password must be saved
             let password: Option<String> = match save_password {
@@ -86,11 +120,77 @@ impl AuthActivity {
                     .get_value(super::COMPONENT_RADIO_BOOKMARK_SAVE_PWD)
                 {
                     Some(Payload::Unsigned(0)) => Some(password), // Yes
-                    _ => None,                                    // No such component / No
+                    _ => None,                                    // No such component / No / Never
                 },
                 false => None,
             };
+            let ephemeral: bool = matches!(
+                self.view
+                    .get_value(super::COMPONENT_RADIO_BOOKMARK_SAVE_PWD),
+                Some(Payload::Unsigned(2))
+            );
             bookmarks_cli.add_bookmark(name.clone(), address, port, protocol, username, password);
+            bookmarks_cli.set_bookmark_s3_endpoint(name.as_str(), endpoint, region);
+            bookmarks_cli.set_bookmark_ephemeral(name.as_str(), ephemeral);
+            bookmarks_cli.set_bookmark_tag(name.as_str(), tag.clone());
+            bookmarks_cli.set_bookmark_protected(name.as_str(), protected);
+            bookmarks_cli
+                .set_bookmark_quiet_hours(name.as_str(), quiet_hours.clone().unwrap_or_default());
+            // Algorithms of the loaded bookmark are copied to the new one
+            if !self.ssh_algorithms.is_empty() {
+                bookmarks_cli
+                    .set_bookmark_ssh_algorithms(name.as_str(), self.ssh_algorithms.clone());
+            }
+            // Ephemeral hosts are removed from recents
+            let recents: Vec<String> = bookmarks_cli.iter_recents().cloned().collect();
+            self.recents_list.retain(|x| recents.contains(x));
+            self.bookmark_tag = tag;
+            self.bookmark_protected = protected;
+            self.bookmark_quiet_hours = quiet_hours.clone().unwrap_or_default();
+            // Save bookmarks
+            self.write_bookmarks();
+            // Remove `name` from bookmarks if exists
+            self.bookmarks_list.retain(|b| b.as_str() != name.as_str());
+            // Push bookmark to list and sort
+            self.bookmarks_list.push(name);
+            self.sort_bookmarks();
+        }
+        quiet_hours.map(|_| ())
+    }
+
+    /// ### get_bookmark_protocol
+    ///
+    /// Get name and protocol of bookmark at index
+    pub(super) fn get_bookmark_protocol(
+        &self,
+        idx: usize,
+    ) -> Option<(String, FileTransferProtocol)> {
+        let bookmarks_cli = self.bookmarks_client.as_ref()?;
+        let key: &String = self.bookmarks_list.get(idx)?;
+        bookmarks_cli
+            .get_bookmark(key)
+            .map(|bookmark| (key.clone(), bookmark.2))
+    }
+
+    /// ### clone_bookmark
+    ///
+    /// Clone bookmark at index as `name`, switching its protocol to `protocol`.
+    /// Using the same name of the original bookmark converts it in place
+    pub(super) fn clone_bookmark(
+        &mut self,
+        idx: usize,
+        name: String,
+        protocol: FileTransferProtocol,
+    ) {
+        let key: String = match self.bookmarks_list.get(idx) {
+            Some(key) => key.clone(),
+            None => return,
+        };
+        if let Some(bookmarks_cli) = self.bookmarks_client.as_mut() {
+            if !bookmarks_cli.clone_bookmark(key.as_str(), name.clone(), protocol) {
+                self.mount_error("Could not clone bookmark: bookmark name can't be empty");
+                return;
+            }
             // Save bookmarks
             self.write_bookmarks();
             // Remove `name` from bookmarks if exists
@@ -100,6 +200,7 @@ impl AuthActivity {
             self.sort_bookmarks();
         }
     }
+
     /// ### del_recent
     ///
     /// Delete recent
@@ -128,6 +229,11 @@ impl AuthActivity {
                     self.load_bookmark_into_gui(
                         bookmark.0, bookmark.1, bookmark.2, bookmark.3, None,
                     );
+                    self.load_s3_endpoint_into_gui(None, None);
+                    self.ssh_algorithms = SshAlgorithms::default();
+                    self.bookmark_tag = None;
+                    self.bookmark_protected = false;
+                    self.bookmark_quiet_hours = None;
                 }
             }
         }
@@ -173,7 +279,24 @@ impl AuthActivity {
                         config_dir_path.as_path(),
                         16,
                     ) {
-                        Ok(cli) => {
+                        Ok(mut cli) => {
+                            // Forget stored passwords if credentials mustn't be stored
+                            let never_store: bool = self
+                                .context
+                                .as_ref()
+                                .unwrap()
+                                .config_client
+                                .as_ref()
+                                .map(|x| x.get_never_store_credentials())
+                                .unwrap_or(false);
+                            if cli.set_never_store_credentials(never_store) {
+                                if let Err(err) = cli.write_bookmarks() {
+                                    self.issues.push(StartupIssue::new(
+                                        StartupIssueKind::BookmarksUnwritable,
+                                        format!("Could not forget stored passwords: {}", err),
+                                    ));
+                                }
+                            }
                             // Load bookmarks into list
                             let mut bookmarks_list: Vec<String> =
                                 Vec::with_capacity(cli.iter_bookmarks().count());
@@ -194,23 +317,24 @@ impl AuthActivity {
                             self.sort_recents();
                         }
                         Err(err) => {
-                            self.mount_error(
+                            self.issues.push(StartupIssue::new(
+                                StartupIssueKind::BookmarksCorrupt,
                                 format!(
                                     "Could not initialize bookmarks (at \"{}\", \"{}\"): {}",
                                     bookmarks_file.display(),
                                     config_dir_path.display(),
                                     err
-                                )
-                                .as_str(),
-                            );
+                                ),
+                            ));
                         }
                     }
                 }
             }
             Err(err) => {
-                self.mount_error(
-                    format!("Could not initialize configuration directory: {}", err).as_str(),
-                );
+                self.issues.push(StartupIssue::new(
+                    StartupIssueKind::ConfigUnreadable,
+                    format!("Could not initialize configuration directory: {}", err),
+                ));
             }
         }
     }
@@ -246,25 +370,29 @@ impl AuthActivity {
         password: Option<String>,
     ) {
         // Load parameters into components
-        if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_ADDR) {
-            let props = props.with_value(PropValue::Str(addr)).build();
-            self.view.update(super::COMPONENT_INPUT_ADDR, props);
-        }
         if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_PORT) {
             let props = props.with_value(PropValue::Unsigned(port as usize)).build();
             self.view.update(super::COMPONENT_INPUT_PORT, props);
         }
         if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_PROTOCOL) {
             let props = props
-                .with_value(PropValue::Unsigned(match protocol {
-                    FileTransferProtocol::Sftp => 0,
-                    FileTransferProtocol::Scp => 1,
-                    FileTransferProtocol::Ftp(false) => 2,
-                    FileTransferProtocol::Ftp(true) => 3,
-                }))
+                .with_value(PropValue::Unsigned(Self::protocol_to_radio_index(protocol)))
                 .build();
             self.view.update(super::COMPONENT_RADIO_PROTOCOL, props);
         }
+        // Kubernetes form asks for the container instead of the password
+        self.update_auth_form();
+        let (addr, password) = match protocol {
+            FileTransferProtocol::Kube => match addr.split_once('/') {
+                Some((pod, container)) => (pod.to_string(), Some(container.to_string())),
+                None => (addr, Some(String::new())),
+            },
+            _ => (addr, password),
+        };
+        if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_ADDR) {
+            let props = props.with_value(PropValue::Str(addr)).build();
+            self.view.update(super::COMPONENT_INPUT_ADDR, props);
+        }
         if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_USERNAME) {
             let props = props.with_value(PropValue::Str(username)).build();
             self.view.update(super::COMPONENT_INPUT_USERNAME, props);
@@ -276,4 +404,21 @@ impl AuthActivity {
             }
         }
     }
+
+    /// ### load_s3_endpoint_into_gui
+    ///
+    /// Load S3 endpoint URL and region into the gui components
+    fn load_s3_endpoint_into_gui(&mut self, endpoint: Option<String>, region: Option<String>) {
+        for (component, value) in [
+            (super::COMPONENT_INPUT_S3_ENDPOINT, endpoint),
+            (super::COMPONENT_INPUT_S3_REGION, region),
+        ] {
+            if let Some(mut props) = self.view.get_props(component) {
+                let props = props
+                    .with_value(PropValue::Str(value.unwrap_or_default()))
+                    .build();
+                self.view.update(component, props);
+            }
+        }
+    }
 }
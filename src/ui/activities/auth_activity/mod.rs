@@ -35,15 +35,26 @@ extern crate crossterm;
 extern crate tui;
 
 // locals
-use super::{Activity, Context, ExitReason};
-use crate::filetransfer::FileTransferProtocol;
+use super::{Activity, ActivityResult, Context, ExitReason};
+use crate::bookmarks::BookmarkTag;
+use crate::doctor::{self, DoctorParams, Finding};
+use crate::filetransfer::{FileTransferProtocol, SshAlgorithms};
+use crate::fs::transfer::TimeWindow;
 use crate::system::bookmarks_client::BookmarksClient;
-use crate::ui::context::FileTransferParams;
+use crate::system::sshkey_storage::SshKeyStorage;
+use crate::ui::bus::{Mailbox, Notification, ToastLevel, Topic};
+use crate::ui::context::{FileTransferParams, StartupIssue};
+use crate::ui::input::InputHandler;
 use crate::ui::layout::view::View;
+use crate::ui::layout::Payload;
+use crate::ui::tasks::{TaskId, TaskOutput, TaskResult};
 use crate::utils::git;
+use crate::utils::secret::Secret;
 
 // Includes
+use crossterm::event::Event as InputEvent;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::path::PathBuf;
 
 // -- components
 const COMPONENT_TEXT_HEADER: &str = "TEXT_HEADER";
@@ -51,16 +62,26 @@ const COMPONENT_TEXT_NEW_VERSION: &str = "TEXT_NEW_VERSION";
 const COMPONENT_TEXT_FOOTER: &str = "TEXT_FOOTER";
 const COMPONENT_TEXT_HELP: &str = "TEXT_HELP";
 const COMPONENT_TEXT_ERROR: &str = "TEXT_ERROR";
+const COMPONENT_TABLE_ISSUES: &str = "TABLE_ISSUES";
+const COMPONENT_TABLE_DIAGNOSIS: &str = "TABLE_DIAGNOSIS";
 const COMPONENT_INPUT_ADDR: &str = "INPUT_ADDRESS";
 const COMPONENT_INPUT_PORT: &str = "INPUT_PORT";
 const COMPONENT_INPUT_USERNAME: &str = "INPUT_USERNAME";
 const COMPONENT_INPUT_PASSWORD: &str = "INPUT_PASSWORD";
+const COMPONENT_INPUT_S3_ENDPOINT: &str = "INPUT_S3_ENDPOINT";
+const COMPONENT_INPUT_S3_REGION: &str = "INPUT_S3_REGION";
 const COMPONENT_INPUT_BOOKMARK_NAME: &str = "INPUT_BOOKMARK_NAME";
+const COMPONENT_INPUT_BOOKMARK_CLONE_NAME: &str = "INPUT_BOOKMARK_CLONE_NAME";
+const COMPONENT_INPUT_BOOKMARK_TAG_LABEL: &str = "INPUT_BOOKMARK_TAG_LABEL";
+const COMPONENT_INPUT_BOOKMARK_QUIET_HOURS: &str = "INPUT_BOOKMARK_QUIET_HOURS";
 const COMPONENT_RADIO_PROTOCOL: &str = "RADIO_PROTOCOL";
 const COMPONENT_RADIO_QUIT: &str = "RADIO_QUIT";
 const COMPONENT_RADIO_BOOKMARK_DEL_BOOKMARK: &str = "RADIO_DELETE_BOOKMARK";
 const COMPONENT_RADIO_BOOKMARK_DEL_RECENT: &str = "RADIO_DELETE_RECENT";
 const COMPONENT_RADIO_BOOKMARK_SAVE_PWD: &str = "RADIO_SAVE_PASSWORD";
+const COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL: &str = "RADIO_CLONE_PROTOCOL";
+const COMPONENT_RADIO_BOOKMARK_TAG_COLOR: &str = "RADIO_BOOKMARK_TAG_COLOR";
+const COMPONENT_RADIO_BOOKMARK_PROTECTED: &str = "RADIO_BOOKMARK_PROTECTED";
 const COMPONENT_BOOKMARKS_LIST: &str = "BOOKMARKS_LIST";
 const COMPONENT_RECENTS_LIST: &str = "RECENTS_LIST";
 
@@ -75,9 +96,17 @@ pub struct AuthActivity {
     context: Option<Context>,
     view: View,
     bookmarks_client: Option<BookmarksClient>,
-    redraw: bool,                // Should ui actually be redrawned?
-    bookmarks_list: Vec<String>, // List of bookmarks
-    recents_list: Vec<String>,   // list of recents
+    redraw: bool,                             // Should ui actually be redrawned?
+    bookmarks_list: Vec<String>,              // List of bookmarks
+    recents_list: Vec<String>,                // list of recents
+    issues: Vec<StartupIssue>,                // Issues occurred loading configuration and bookmarks
+    update_task: Option<TaskId>,              // Background task checking for updates
+    doctor_task: Option<TaskId>,              // Background task diagnosing the connection
+    notifications: Option<Mailbox>,           // Theme and configuration changes
+    ssh_algorithms: SshAlgorithms,            // Preferred SSH algorithms of the loaded bookmark
+    bookmark_tag: Option<BookmarkTag>,        // Color and label of the loaded bookmark
+    bookmark_protected: bool, // Whether the loaded bookmark requires a confirmation phrase
+    bookmark_quiet_hours: Option<TimeWindow>, // Time range transfers with the loaded bookmark are paused in
 }
 
 impl Default for AuthActivity {
@@ -99,44 +128,204 @@ impl AuthActivity {
             redraw: true, // True at startup
             bookmarks_list: Vec::new(),
             recents_list: Vec::new(),
+            issues: Vec::new(),
+            update_task: None,
+            doctor_task: None,
+            notifications: None,
+            ssh_algorithms: SshAlgorithms::default(),
+            bookmark_tag: None,
+            bookmark_protected: false,
+            bookmark_quiet_hours: None,
         }
     }
 
-    /// ### on_create
+    /// ### check_for_updates
     ///
-    /// If enabled in configuration, check for updates from Github
+    /// If enabled in configuration, check for updates from Github.
+    /// The request is sent from a background task, whose result is collected by `poll_tasks`
     fn check_for_updates(&mut self) {
-        // Check version only if unset in the store
-        let ctx: &Context = self.context.as_ref().unwrap();
-        if !ctx.store.isset(STORE_KEY_LATEST_VERSION) {
-            let mut new_version: Option<String> = match ctx.config_client.as_ref() {
-                Some(client) => {
-                    if client.get_check_for_updates() {
-                        // Send request
-                        match git::check_for_updates(env!("CARGO_PKG_VERSION")) {
-                            Ok(version) => version,
-                            Err(err) => {
-                                // Report error
-                                self.mount_error(
-                                    format!("Could not check for new updates: {}", err).as_str(),
-                                );
-                                // None
-                                None
-                            }
-                        }
-                    } else {
-                        None
+        // Check version only if unset in the store and not already checking
+        let ctx: &mut Context = self.context.as_mut().unwrap();
+        if ctx.store.isset(STORE_KEY_LATEST_VERSION) || self.update_task.is_some() {
+            return;
+        }
+        let enabled: bool = ctx
+            .config_client
+            .as_ref()
+            .map(|x| x.get_check_for_updates())
+            .unwrap_or(false);
+        match enabled {
+            true => {
+                // Notify the new version wherever the user is, once found
+                let publisher = ctx.bus.publisher();
+                self.update_task = Some(ctx.tasks.spawn("Checking for updates", move |_| {
+                    let version = git::check_for_updates(env!("CARGO_PKG_VERSION"))?;
+                    if let Some(version) = version.as_ref() {
+                        publisher.toast(
+                            ToastLevel::Info,
+                            format!("TermSCP {} is now available!", version),
+                        );
                     }
-                }
-                None => None,
-            };
-            let ctx: &mut Context = self.context.as_mut().unwrap();
+                    Ok(Box::new(version) as TaskOutput)
+                }));
+            }
+            false => ctx.store.set(STORE_KEY_LATEST_VERSION), // Just set flag
+        }
+    }
+
+    /// ### diagnose
+    ///
+    /// Diagnose the connection towards the host in the form, in background.
+    /// Findings are shown once the diagnosis has terminated
+    fn diagnose(&mut self) {
+        if self.doctor_task.is_some() {
+            return;
+        }
+        let (address, port, protocol, username, password) = self.get_input();
+        if doctor::stages(protocol).is_empty() {
+            self.mount_error(
+                format!("Diagnosis is not supported for {}", protocol.to_string()).as_str(),
+            );
+            return;
+        }
+        let ctx: &mut Context = self.context.as_mut().unwrap();
+        let ssh_key: Option<PathBuf> = ctx.config_client.as_ref().and_then(|cli| {
+            SshKeyStorage::storage_from_config(cli)
+                .resolve(address.as_str(), username.as_str())
+                .cloned()
+        });
+        let timeout = ctx
+            .config_client
+            .as_ref()
+            .map(|x| x.get_network_options())
+            .unwrap_or_default()
+            .connection_timeout;
+        let params: DoctorParams = DoctorParams {
+            address,
+            port,
+            protocol,
+            username: match username.is_empty() {
+                true => None,
+                false => Some(username),
+            },
+            password: match password.is_empty() {
+                true => None,
+                false => Some(password),
+            },
+            ssh_key,
+            timeout,
+        };
+        let name: String = format!("Diagnosing {}", params.address);
+        self.doctor_task = Some(ctx.tasks.spawn(name.as_str(), move |reporter| {
+            let stages: usize = doctor::stages(params.protocol).len();
+            let mut done: usize = 0;
+            let findings: Vec<Finding> = doctor::diagnose(&params, |x| {
+                done += 1;
+                reporter.report(
+                    Some(done as f64 / stages as f64),
+                    format!("{}: {}", x.stage, x.outcome).as_str(),
+                );
+            });
+            Ok(Box::new(findings) as TaskOutput)
+        }));
+    }
+
+    /// ### copy_password
+    ///
+    /// Copy the password in the form to the clipboard
+    fn copy_password(&mut self) {
+        let password: Secret = match self.view.get_value(COMPONENT_INPUT_PASSWORD) {
+            Some(Payload::Text(password)) if !password.is_empty() => Secret::new(password),
+            _ => return,
+        };
+        if let Err(err) = self
+            .context
+            .as_mut()
+            .unwrap()
+            .copy_secret(password.expose())
+        {
+            self.mount_error(format!("Could not copy password: {}", err).as_str());
+        }
+    }
+
+    /// ### poll_tasks
+    ///
+    /// Collect background tasks state; if the update check has terminated, its result is put into the store,
+    /// while the findings of a terminated diagnosis are shown
+    fn poll_tasks(&mut self) {
+        let ctx: &mut Context = self.context.as_mut().unwrap();
+        let mut changed: bool = ctx.tasks.poll();
+        let findings: Option<TaskResult> = self.doctor_task.and_then(|x| ctx.tasks.take_result(x));
+        let result: Option<TaskResult> = self.update_task.and_then(|x| ctx.tasks.take_result(x));
+        if let Some(result) = result {
+            self.update_task = None;
+            changed = true;
             // Set version into the store (or just a flag)
-            match new_version.take() {
-                Some(new_version) => ctx.store.set_string(STORE_KEY_LATEST_VERSION, new_version), // If Some, set String
-                None => ctx.store.set(STORE_KEY_LATEST_VERSION), // If None, just set flag
+            match result.map(|x| x.downcast::<Option<String>>()) {
+                Ok(Ok(version)) => match *version {
+                    Some(version) => ctx.store.set_string(STORE_KEY_LATEST_VERSION, version), // If Some, set String
+                    None => ctx.store.set(STORE_KEY_LATEST_VERSION), // If None, just set flag
+                },
+                Ok(Err(_)) => ctx.store.set(STORE_KEY_LATEST_VERSION),
+                Err(err) => {
+                    ctx.store.set(STORE_KEY_LATEST_VERSION);
+                    // Report error, without interrupting the user
+                    ctx.bus.publish(Notification::Toast(
+                        ToastLevel::Warn,
+                        format!("Could not check for new updates: {}", err),
+                    ));
+                }
             }
         }
+        if let Some(findings) = findings {
+            self.doctor_task = None;
+            changed = true;
+            match findings.map(|x| x.downcast::<Vec<Finding>>()) {
+                Ok(Ok(findings)) => self.mount_diagnosis(findings.as_slice()),
+                Ok(Err(_)) => {}
+                Err(err) => self.mount_error(format!("Diagnosis failed: {}", err).as_str()),
+            }
+        }
+        // Update tasks progress and version notice
+        if changed {
+            self.mount_notice();
+            self.redraw = true;
+        }
+    }
+
+    /// ### poll_notifications
+    ///
+    /// Read notifications from the bus: the ui is restyled when the theme or the configuration change
+    fn poll_notifications(&mut self) {
+        let ctx: &mut Context = self.context.as_mut().unwrap();
+        if ctx.poll_toast() {
+            self.redraw = true;
+        }
+        let notifications: Vec<Notification> = match self.notifications.as_ref() {
+            Some(mailbox) => mailbox.read(),
+            None => Vec::new(),
+        };
+        if notifications
+            .iter()
+            .any(|x| matches!(x, Notification::ThemeChanged | Notification::ConfigReloaded))
+        {
+            self.redraw = true;
+        }
+    }
+
+    /// ### retry_load
+    ///
+    /// Load configuration and bookmarks again, reporting the issues which are still there
+    fn retry_load(&mut self) {
+        self.issues = self.context.as_mut().unwrap().reload_config();
+        self.bookmarks_client = None;
+        self.bookmarks_list.clear();
+        self.recents_list.clear();
+        self.init_bookmarks_client();
+        // Reload view
+        let _ = self.view_bookmarks();
+        let _ = self.view_recent_connections();
+        self.mount_issues();
     }
 }
 
@@ -146,27 +335,35 @@ impl Activity for AuthActivity {
     /// `on_create` is the function which must be called to initialize the activity.
     /// `on_create` must initialize all the data structures used by the activity
     /// Context is taken from activity manager and will be released only when activity is destroyed
-    fn on_create(&mut self, mut context: Context) {
-        // Initialize file transfer params
-        context.ft_params = Some(FileTransferParams::default());
+    fn on_create(&mut self, context: Context) {
         // Set context
         self.context = Some(context);
         // Clear terminal
         self.context.as_mut().unwrap().clear_screen();
         // Put raw mode on enabled
         let _ = enable_raw_mode();
+        // Verify startup issues from context
+        self.issues = self.context.as_mut().unwrap().take_issues();
+        // Subscribe to theme and configuration changes
+        if self.notifications.is_none() {
+            self.notifications = Some(
+                self.context
+                    .as_mut()
+                    .unwrap()
+                    .bus
+                    .subscribe(&[Topic::Config]),
+            );
+        }
         // Init bookmarks client
         if self.bookmarks_client.is_none() {
             self.init_bookmarks_client();
         }
-        // Verify error state from context
-        if let Some(err) = self.context.as_mut().unwrap().get_error() {
-            self.mount_error(err.as_str());
-        }
         // If check for updates is enabled, check for updates
         self.check_for_updates();
         // Initialize view
         self.init();
+        // Report issues
+        self.mount_issues();
     }
 
     /// ### on_draw
@@ -182,10 +379,22 @@ impl Activity for AuthActivity {
         if let Ok(Some(event)) = self.context.as_ref().unwrap().input_hnd.read_event() {
             // Set redraw to true
             self.redraw = true;
+            // Lay out ui again if terminal has been resized
+            if let InputEvent::Resize(width, height) = event {
+                self.context.as_mut().unwrap().resize(width, height);
+            }
+            // Suspend to shell; screen is redrawn at next tick once resumed
+            if InputHandler::is_suspend_event(&event) {
+                self.context.as_mut().unwrap().suspend();
+                return;
+            }
             // Handle event on view and update
             let msg = self.view.on(event);
             self.update(msg);
         }
+        // Collect background tasks and notifications
+        self.poll_tasks();
+        self.poll_notifications();
         // Redraw if necessary
         if self.redraw {
             // View
@@ -222,4 +431,23 @@ impl Activity for AuthActivity {
             None => None,
         }
     }
+
+    /// ### on_resume
+    ///
+    /// Authentication form is kept as it was, except for the password after a disconnection.
+    /// Once setup is closed, configuration and bookmarks are loaded again if there were issues
+    fn on_resume(&mut self, context: Context, result: Option<ActivityResult>) {
+        self.context = Some(context);
+        self.exit_reason = None;
+        self.redraw = true;
+        // Clear terminal
+        self.context.as_mut().unwrap().clear_screen();
+        // Put raw mode on enabled
+        let _ = enable_raw_mode();
+        match result {
+            Some(ActivityResult::Disconnected) => self.clear_password(),
+            Some(ActivityResult::SetupClosed) if !self.issues.is_empty() => self.retry_load(),
+            _ => {}
+        }
+    }
 }
@@ -27,15 +27,19 @@
  */
 // locals
 use super::{
-    AuthActivity, FileTransferParams, COMPONENT_BOOKMARKS_LIST, COMPONENT_INPUT_ADDR,
-    COMPONENT_INPUT_BOOKMARK_NAME, COMPONENT_INPUT_PASSWORD, COMPONENT_INPUT_PORT,
-    COMPONENT_INPUT_USERNAME, COMPONENT_RADIO_BOOKMARK_DEL_BOOKMARK,
-    COMPONENT_RADIO_BOOKMARK_DEL_RECENT, COMPONENT_RADIO_BOOKMARK_SAVE_PWD,
-    COMPONENT_RADIO_PROTOCOL, COMPONENT_RADIO_QUIT, COMPONENT_RECENTS_LIST, COMPONENT_TEXT_ERROR,
-    COMPONENT_TEXT_HELP,
+    AuthActivity, FileTransferParams, FileTransferProtocol, COMPONENT_BOOKMARKS_LIST,
+    COMPONENT_INPUT_ADDR, COMPONENT_INPUT_BOOKMARK_CLONE_NAME, COMPONENT_INPUT_BOOKMARK_NAME,
+    COMPONENT_INPUT_BOOKMARK_QUIET_HOURS, COMPONENT_INPUT_BOOKMARK_TAG_LABEL,
+    COMPONENT_INPUT_PASSWORD, COMPONENT_INPUT_PORT, COMPONENT_INPUT_S3_ENDPOINT,
+    COMPONENT_INPUT_S3_REGION, COMPONENT_INPUT_USERNAME, COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL,
+    COMPONENT_RADIO_BOOKMARK_DEL_BOOKMARK, COMPONENT_RADIO_BOOKMARK_DEL_RECENT,
+    COMPONENT_RADIO_BOOKMARK_PROTECTED, COMPONENT_RADIO_BOOKMARK_SAVE_PWD,
+    COMPONENT_RADIO_BOOKMARK_TAG_COLOR, COMPONENT_RADIO_PROTOCOL, COMPONENT_RADIO_QUIT,
+    COMPONENT_RECENTS_LIST, COMPONENT_TABLE_DIAGNOSIS, COMPONENT_TEXT_ERROR, COMPONENT_TEXT_HELP,
 };
 use crate::ui::activities::keymap::*;
 use crate::ui::layout::{Msg, Payload};
+use crate::utils::secret::Secret;
 
 // -- update
 
@@ -52,8 +56,22 @@ impl AuthActivity {
             Some(msg) => match msg {
                 // Focus ( DOWN )
                 (COMPONENT_INPUT_ADDR, &MSG_KEY_DOWN) => {
-                    // Give focus to port
-                    self.view.active(COMPONENT_INPUT_PORT);
+                    // Give focus to port (hidden for Kubernetes and S3)
+                    match self.get_protocol() {
+                        FileTransferProtocol::Kube => self.view.active(COMPONENT_RADIO_PROTOCOL),
+                        FileTransferProtocol::S3 => self.view.active(COMPONENT_INPUT_S3_ENDPOINT),
+                        _ => self.view.active(COMPONENT_INPUT_PORT),
+                    }
+                    None
+                }
+                (COMPONENT_INPUT_S3_ENDPOINT, &MSG_KEY_DOWN) => {
+                    // Give focus to region
+                    self.view.active(COMPONENT_INPUT_S3_REGION);
+                    None
+                }
+                (COMPONENT_INPUT_S3_REGION, &MSG_KEY_DOWN) => {
+                    // Give focus to protocol
+                    self.view.active(COMPONENT_RADIO_PROTOCOL);
                     None
                 }
                 (COMPONENT_INPUT_PORT, &MSG_KEY_DOWN) => {
@@ -88,8 +106,27 @@ impl AuthActivity {
                     None
                 }
                 (COMPONENT_RADIO_PROTOCOL, &MSG_KEY_UP) => {
-                    // Give focus to port
-                    self.view.active(COMPONENT_INPUT_PORT);
+                    // Give focus to port (hidden for Kubernetes and S3)
+                    match self.get_protocol() {
+                        FileTransferProtocol::Kube => self.view.active(COMPONENT_INPUT_ADDR),
+                        FileTransferProtocol::S3 => self.view.active(COMPONENT_INPUT_S3_REGION),
+                        _ => self.view.active(COMPONENT_INPUT_PORT),
+                    }
+                    None
+                }
+                (COMPONENT_INPUT_S3_REGION, &MSG_KEY_UP) => {
+                    // Give focus to endpoint
+                    self.view.active(COMPONENT_INPUT_S3_ENDPOINT);
+                    None
+                }
+                (COMPONENT_INPUT_S3_ENDPOINT, &MSG_KEY_UP) => {
+                    // Give focus to address
+                    self.view.active(COMPONENT_INPUT_ADDR);
+                    None
+                }
+                // Protocol changed
+                (COMPONENT_RADIO_PROTOCOL, Msg::OnChange(_)) => {
+                    self.update_auth_form();
                     None
                 }
                 (COMPONENT_INPUT_PORT, &MSG_KEY_UP) => {
@@ -133,6 +170,17 @@ impl AuthActivity {
                     self.mount_bookmark_del_dialog();
                     None
                 }
+                // <C> clone bookmark
+                (COMPONENT_BOOKMARKS_LIST, &MSG_KEY_CHAR_C) => {
+                    if let Some(Payload::Unsigned(idx)) =
+                        self.view.get_value(COMPONENT_BOOKMARKS_LIST)
+                    {
+                        if let Some((name, protocol)) = self.get_bookmark_protocol(idx) {
+                            self.mount_bookmark_clone_dialog(name, protocol);
+                        }
+                    }
+                    None
+                }
                 (COMPONENT_RECENTS_LIST, &MSG_KEY_DEL)
                 | (COMPONENT_RECENTS_LIST, &MSG_KEY_CHAR_E) => {
                     // Show delete popup
@@ -241,11 +289,37 @@ impl AuthActivity {
                     self.umount_help();
                     None
                 }
+                // Diagnose connection
+                (_, &MSG_KEY_CTRL_T) => {
+                    self.diagnose();
+                    None
+                }
+                (_, &MSG_KEY_CTRL_Y) => {
+                    self.copy_password();
+                    None
+                }
+                (COMPONENT_TABLE_DIAGNOSIS, &MSG_KEY_ENTER)
+                | (COMPONENT_TABLE_DIAGNOSIS, &MSG_KEY_ESC) => {
+                    self.umount_diagnosis();
+                    None
+                }
                 // Enter setup
                 (_, &MSG_KEY_CTRL_C) => {
                     self.exit_reason = Some(super::ExitReason::EnterSetup);
                     None
                 }
+                // Startup issues
+                (_, &MSG_KEY_CTRL_R) => {
+                    // Load configuration and bookmarks again
+                    self.retry_load();
+                    None
+                }
+                (_, &MSG_KEY_CTRL_D) => {
+                    // Dismiss issues
+                    self.issues.clear();
+                    self.umount_issues();
+                    None
+                }
                 // Save bookmark; show popup
                 (_, &MSG_KEY_CTRL_S) => {
                     // Show popup
@@ -264,9 +338,53 @@ impl AuthActivity {
                     self.view.active(COMPONENT_INPUT_BOOKMARK_NAME);
                     None
                 }
+                (COMPONENT_RADIO_BOOKMARK_SAVE_PWD, &MSG_KEY_DOWN) => {
+                    // Give focus to tag label
+                    self.view.active(COMPONENT_INPUT_BOOKMARK_TAG_LABEL);
+                    None
+                }
+                (COMPONENT_INPUT_BOOKMARK_TAG_LABEL, &MSG_KEY_UP) => {
+                    // Give focus to pwd
+                    self.view.active(COMPONENT_RADIO_BOOKMARK_SAVE_PWD);
+                    None
+                }
+                (COMPONENT_INPUT_BOOKMARK_TAG_LABEL, &MSG_KEY_DOWN) => {
+                    // Give focus to tag color
+                    self.view.active(COMPONENT_RADIO_BOOKMARK_TAG_COLOR);
+                    None
+                }
+                (COMPONENT_RADIO_BOOKMARK_TAG_COLOR, &MSG_KEY_UP) => {
+                    // Give focus to tag label
+                    self.view.active(COMPONENT_INPUT_BOOKMARK_TAG_LABEL);
+                    None
+                }
+                (COMPONENT_RADIO_BOOKMARK_TAG_COLOR, &MSG_KEY_DOWN) => {
+                    // Give focus to protected
+                    self.view.active(COMPONENT_RADIO_BOOKMARK_PROTECTED);
+                    None
+                }
+                (COMPONENT_RADIO_BOOKMARK_PROTECTED, &MSG_KEY_UP) => {
+                    // Give focus to tag color
+                    self.view.active(COMPONENT_RADIO_BOOKMARK_TAG_COLOR);
+                    None
+                }
+                (COMPONENT_RADIO_BOOKMARK_PROTECTED, &MSG_KEY_DOWN) => {
+                    // Give focus to quiet hours
+                    self.view.active(COMPONENT_INPUT_BOOKMARK_QUIET_HOURS);
+                    None
+                }
+                (COMPONENT_INPUT_BOOKMARK_QUIET_HOURS, &MSG_KEY_UP) => {
+                    // Give focus to protected
+                    self.view.active(COMPONENT_RADIO_BOOKMARK_PROTECTED);
+                    None
+                }
                 // Save bookmark
                 (COMPONENT_INPUT_BOOKMARK_NAME, Msg::OnSubmit(_))
-                | (COMPONENT_RADIO_BOOKMARK_SAVE_PWD, Msg::OnSubmit(_)) => {
+                | (COMPONENT_RADIO_BOOKMARK_SAVE_PWD, Msg::OnSubmit(_))
+                | (COMPONENT_INPUT_BOOKMARK_TAG_LABEL, Msg::OnSubmit(_))
+                | (COMPONENT_RADIO_BOOKMARK_TAG_COLOR, Msg::OnSubmit(_))
+                | (COMPONENT_RADIO_BOOKMARK_PROTECTED, Msg::OnSubmit(_))
+                | (COMPONENT_INPUT_BOOKMARK_QUIET_HOURS, Msg::OnSubmit(_)) => {
                     // Get values
                     let bookmark_name: String =
                         match self.view.get_value(COMPONENT_INPUT_BOOKMARK_NAME) {
@@ -278,19 +396,70 @@ impl AuthActivity {
                         Some(Payload::Unsigned(0))
                     );
                     // Save bookmark
-                    self.save_bookmark(bookmark_name, save_pwd);
+                    let result: Result<(), String> = self.save_bookmark(bookmark_name, save_pwd);
                     // Umount popup
                     self.umount_bookmark_save_dialog();
-                    // Reload bookmarks
+                    if let Err(err) = result {
+                        self.mount_error(
+                            format!("Bookmark saved without quiet hours: {}", err).as_str(),
+                        );
+                    }
+                    // Reload bookmarks and recents (ephemeral hosts are removed from recents)
+                    self.view_recent_connections();
                     self.view_bookmarks()
                 }
                 // Hide save bookmark
                 (COMPONENT_INPUT_BOOKMARK_NAME, &MSG_KEY_ESC)
-                | (COMPONENT_RADIO_BOOKMARK_SAVE_PWD, &MSG_KEY_ESC) => {
+                | (COMPONENT_RADIO_BOOKMARK_SAVE_PWD, &MSG_KEY_ESC)
+                | (COMPONENT_INPUT_BOOKMARK_TAG_LABEL, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_BOOKMARK_TAG_COLOR, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_BOOKMARK_PROTECTED, &MSG_KEY_ESC)
+                | (COMPONENT_INPUT_BOOKMARK_QUIET_HOURS, &MSG_KEY_ESC) => {
                     // Umount popup
                     self.umount_bookmark_save_dialog();
                     None
                 }
+                // Clone bookmark
+                (COMPONENT_INPUT_BOOKMARK_CLONE_NAME, &MSG_KEY_DOWN) => {
+                    // Give focus to protocol
+                    self.view.active(COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL);
+                    None
+                }
+                (COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL, &MSG_KEY_UP) => {
+                    // Give focus to name
+                    self.view.active(COMPONENT_INPUT_BOOKMARK_CLONE_NAME);
+                    None
+                }
+                (COMPONENT_INPUT_BOOKMARK_CLONE_NAME, Msg::OnSubmit(_))
+                | (COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL, Msg::OnSubmit(_)) => {
+                    // Get values
+                    let name: String =
+                        match self.view.get_value(COMPONENT_INPUT_BOOKMARK_CLONE_NAME) {
+                            Some(Payload::Text(s)) => s.trim().to_string(),
+                            _ => String::new(),
+                        };
+                    let protocol: FileTransferProtocol =
+                        match self.view.get_value(COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL) {
+                            Some(Payload::Unsigned(idx)) => Self::radio_index_to_protocol(idx),
+                            _ => FileTransferProtocol::Sftp,
+                        };
+                    // Umount popup
+                    self.umount_bookmark_clone_dialog();
+                    // Clone selected bookmark
+                    if let Some(Payload::Unsigned(idx)) =
+                        self.view.get_value(COMPONENT_BOOKMARKS_LIST)
+                    {
+                        self.clone_bookmark(idx, name, protocol);
+                    }
+                    // Reload bookmarks
+                    self.view_bookmarks()
+                }
+                (COMPONENT_INPUT_BOOKMARK_CLONE_NAME, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL, &MSG_KEY_ESC) => {
+                    // Umount popup
+                    self.umount_bookmark_clone_dialog();
+                    None
+                }
                 // Error message
                 (COMPONENT_TEXT_ERROR, &MSG_KEY_ENTER) => {
                     // Umount text error
@@ -315,26 +484,38 @@ impl AuthActivity {
                     // Match <ENTER> key for all other components
                     self.save_recent();
                     let (address, port, protocol, username, password) = self.get_input();
-                    // Set file transfer params to context
-                    let mut ft_params: &mut FileTransferParams =
-                        &mut self.context.as_mut().unwrap().ft_params.as_mut().unwrap();
-                    ft_params.address = address;
-                    ft_params.port = port;
-                    ft_params.protocol = protocol;
-                    ft_params.username = match username.is_empty() {
-                        true => None,
-                        false => Some(username),
+                    let (endpoint, region) = match protocol {
+                        FileTransferProtocol::S3 => self.get_s3_input(),
+                        _ => (None, None),
                     };
-                    ft_params.password = match password.is_empty() {
-                        true => None,
-                        false => Some(password),
+                    // Launch file transfer with these params
+                    let ft_params: FileTransferParams = FileTransferParams {
+                        address,
+                        port,
+                        protocol,
+                        username: match username.is_empty() {
+                            true => None,
+                            false => Some(username),
+                        },
+                        password: match password.is_empty() {
+                            true => None,
+                            false => Some(Secret::new(password)),
+                        },
+                        entry_directory: None,
+                        endpoint,
+                        region,
+                        algorithms: self.ssh_algorithms.clone(),
                     };
                     // Set exit reason
-                    self.exit_reason = Some(super::ExitReason::Connect);
+                    self.exit_reason = Some(super::ExitReason::Connect(Box::new(ft_params)));
                     // Return None
                     None
                 }
-                // <ESC> => Quit
+                // <ESC> => Back to the sessions open in tabs, if any; otherwise quit
+                (_, &MSG_KEY_ESC) if !self.context.as_ref().unwrap().tabs.labels.is_empty() => {
+                    self.exit_reason = Some(super::ExitReason::SwitchTab(0));
+                    None
+                }
                 (_, &MSG_KEY_ESC) => {
                     self.mount_quit();
                     None
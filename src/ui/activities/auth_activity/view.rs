@@ -27,6 +27,10 @@
  */
 // Locals
 use super::{AuthActivity, Context, FileTransferProtocol};
+use crate::bookmarks::{BookmarkTag, TagColor};
+use crate::doctor::{Finding, Outcome};
+use crate::fs::transfer::TimeWindow;
+use crate::ui::layout::accessibility::{ColorFilter, ColorMode};
 use crate::ui::layout::components::{
     bookmark_list::BookmarkList, input::Input, msgbox::MsgBox, radio_group::RadioGroup,
     table::Table, text::Text, title::Title,
@@ -34,15 +38,28 @@ use crate::ui::layout::components::{
 use crate::ui::layout::props::{
     InputType, PropValue, PropsBuilder, TableBuilder, TextParts, TextSpan, TextSpanBuilder,
 };
+use crate::ui::layout::toast::Toast;
 use crate::ui::layout::utils::draw_area_in;
 use crate::ui::layout::{Msg, Payload};
+use crate::utils::fmt::fmt_host;
 // Ext
+use std::str::FromStr;
 use tui::{
     layout::{Constraint, Direction, Layout},
     style::Color,
     widgets::Clear,
 };
 
+/// Colors which can be assigned to a bookmark tag, as displayed in the bookmark save dialog
+const TAG_COLORS: [(TagColor, &str); 6] = [
+    (TagColor::Red, "Red"),
+    (TagColor::Yellow, "Yellow"),
+    (TagColor::Green, "Green"),
+    (TagColor::Blue, "Blue"),
+    (TagColor::Magenta, "Magenta"),
+    (TagColor::Cyan, "Cyan"),
+];
+
 impl AuthActivity {
     /// ### init
     ///
@@ -115,12 +132,7 @@ impl AuthActivity {
                     .with_background(Color::Black)
                     .with_texts(TextParts::new(
                         Some(String::from("Protocol")),
-                        Some(vec![
-                            TextSpan::from("SFTP"),
-                            TextSpan::from("SCP"),
-                            TextSpan::from("FTP"),
-                            TextSpan::from("FTPS"),
-                        ]),
+                        Some(Self::protocol_radio_options()),
                     ))
                     .build(),
             )),
@@ -146,23 +158,30 @@ impl AuthActivity {
                     .build(),
             )),
         );
+        // S3 endpoint and region
+        self.view.mount(
+            super::COMPONENT_INPUT_S3_ENDPOINT,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightCyan)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Endpoint URL (empty for AWS)")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_S3_REGION,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightCyan)
+                    .with_texts(TextParts::new(Some(String::from("Region")), None))
+                    .build(),
+            )),
+        );
         // Version notice
-        if let Some(version) = self
-            .context
-            .as_ref()
-            .unwrap()
-            .store
-            .get_string(super::STORE_KEY_LATEST_VERSION)
-        {
-            self.view.mount(
-                super::COMPONENT_TEXT_NEW_VERSION,
-                Box::new(Text::new(
-                        PropsBuilder::default()
-                        .with_texts(TextParts::new(None, Some(vec![TextSpanBuilder::new(format!("TermSCP {} is now available! Download it from <https://github.com/veeso/termscp/releases/latest>", version).as_str()).with_foreground(Color::Yellow).bold().build()])))
-                        .build()
-                ))
-            );
-        }
+        self.mount_notice();
         // Bookmarks
         self.view.mount(
             super::COMPONENT_BOOKMARKS_LIST,
@@ -194,12 +213,66 @@ impl AuthActivity {
         self.view.active(super::COMPONENT_INPUT_ADDR);
     }
 
+    /// ### mount_notice
+    ///
+    /// Mount the notice below the header: the new version available, if any, otherwise the progress
+    /// of the running background tasks
+    pub(super) fn mount_notice(&mut self) {
+        let ctx = self.context.as_ref().unwrap();
+        let notice: Option<TextSpan> =
+            match ctx.store.get_string(super::STORE_KEY_LATEST_VERSION) {
+                Some(version) => Some(TextSpanBuilder::new(format!("TermSCP {} is now available! Download it from <https://github.com/veeso/termscp/releases/latest>", version).as_str()).with_foreground(Color::Yellow).bold().build()),
+                None => ctx.tasks.summary().map(|x| {
+                    TextSpanBuilder::new(x.as_str())
+                        .with_foreground(Color::Gray)
+                        .italic()
+                        .build()
+                }),
+            };
+        match notice {
+            Some(notice) => self.view.mount(
+                super::COMPONENT_TEXT_NEW_VERSION,
+                Box::new(Text::new(
+                    PropsBuilder::default()
+                        .with_texts(TextParts::new(None, Some(vec![notice])))
+                        .build(),
+                )),
+            ),
+            None => self.view.umount(super::COMPONENT_TEXT_NEW_VERSION),
+        }
+    }
+
     /// ### view
     ///
     /// Display view on canvas
     pub(super) fn view(&mut self) {
         let mut ctx: Context = self.context.take().unwrap();
+        let color_mode: ColorMode = ctx.color_mode();
+        // Port is not used by Kubernetes; S3 shows endpoint and region instead
+        let protocol: FileTransferProtocol = self.get_protocol();
+        let show_port: bool = !matches!(
+            protocol,
+            FileTransferProtocol::Kube | FileTransferProtocol::S3
+        );
+        let show_s3_endpoint: bool = protocol == FileTransferProtocol::S3;
+        // Issues panel: one row for each issue, plus actions and borders
+        let issues_height: u16 = match self.issues.is_empty() {
+            true => 0,
+            false => (self.issues.len() as u16 + 3).min(8),
+        };
+        let toast: Option<Toast> = ctx.toast();
         let _ = ctx.terminal.draw(|f| {
+            // Make room for issues
+            let main_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints(
+                    [
+                        Constraint::Min(0),                // Main
+                        Constraint::Length(issues_height), // Issues
+                    ]
+                    .as_ref(),
+                )
+                .split(f.size());
             // Prepare chunks
             let chunks = Layout::default()
                 .direction(Direction::Vertical)
@@ -211,7 +284,7 @@ impl AuthActivity {
                     ]
                     .as_ref(),
                 )
-                .split(f.size());
+                .split(main_chunks[0]);
             // Create explorer chunks
             let auth_chunks = Layout::default()
                 .constraints(
@@ -242,8 +315,20 @@ impl AuthActivity {
                 .render(super::COMPONENT_TEXT_NEW_VERSION, f, auth_chunks[1]);
             self.view
                 .render(super::COMPONENT_INPUT_ADDR, f, auth_chunks[2]);
-            self.view
-                .render(super::COMPONENT_INPUT_PORT, f, auth_chunks[3]);
+            if show_port {
+                self.view
+                    .render(super::COMPONENT_INPUT_PORT, f, auth_chunks[3]);
+            }
+            if show_s3_endpoint {
+                let s3_chunks = Layout::default()
+                    .constraints([Constraint::Percentage(70), Constraint::Percentage(30)].as_ref())
+                    .direction(Direction::Horizontal)
+                    .split(auth_chunks[3]);
+                self.view
+                    .render(super::COMPONENT_INPUT_S3_ENDPOINT, f, s3_chunks[0]);
+                self.view
+                    .render(super::COMPONENT_INPUT_S3_REGION, f, s3_chunks[1]);
+            }
             self.view
                 .render(super::COMPONENT_RADIO_PROTOCOL, f, auth_chunks[4]);
             self.view
@@ -257,6 +342,11 @@ impl AuthActivity {
                 .render(super::COMPONENT_BOOKMARKS_LIST, f, bookmark_chunks[0]);
             self.view
                 .render(super::COMPONENT_RECENTS_LIST, f, bookmark_chunks[1]);
+            // Issues
+            if issues_height > 0 {
+                self.view
+                    .render(super::COMPONENT_TABLE_ISSUES, f, main_chunks[1]);
+            }
             // Popups
             if let Some(mut props) = self.view.get_props(super::COMPONENT_TEXT_ERROR) {
                 if props.build().visible {
@@ -306,13 +396,21 @@ impl AuthActivity {
                     self.view.render(super::COMPONENT_TEXT_HELP, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_TABLE_DIAGNOSIS) {
+                if props.build().visible {
+                    // make popup
+                    let popup = draw_area_in(f.size(), 80, 40);
+                    f.render_widget(Clear, popup);
+                    self.view.render(super::COMPONENT_TABLE_DIAGNOSIS, f, popup);
+                }
+            }
             if let Some(mut props) = self
                 .view
                 .get_props(super::COMPONENT_RADIO_BOOKMARK_SAVE_PWD)
             {
                 if props.build().visible {
                     // make popup
-                    let popup = draw_area_in(f.size(), 20, 20);
+                    let popup = draw_area_in(f.size(), 60, 40);
                     f.render_widget(Clear, popup);
                     let popup_chunks = Layout::default()
                         .direction(Direction::Vertical)
@@ -320,6 +418,10 @@ impl AuthActivity {
                             [
                                 Constraint::Length(3), // Input form
                                 Constraint::Length(2), // Yes/No
+                                Constraint::Length(3), // Tag label
+                                Constraint::Length(3), // Tag color
+                                Constraint::Length(3), // Protected
+                                Constraint::Length(3), // Quiet hours
                             ]
                             .as_ref(),
                         )
@@ -328,8 +430,64 @@ impl AuthActivity {
                         .render(super::COMPONENT_INPUT_BOOKMARK_NAME, f, popup_chunks[0]);
                     self.view
                         .render(super::COMPONENT_RADIO_BOOKMARK_SAVE_PWD, f, popup_chunks[1]);
+                    self.view.render(
+                        super::COMPONENT_INPUT_BOOKMARK_TAG_LABEL,
+                        f,
+                        popup_chunks[2],
+                    );
+                    self.view.render(
+                        super::COMPONENT_RADIO_BOOKMARK_TAG_COLOR,
+                        f,
+                        popup_chunks[3],
+                    );
+                    self.view.render(
+                        super::COMPONENT_RADIO_BOOKMARK_PROTECTED,
+                        f,
+                        popup_chunks[4],
+                    );
+                    self.view.render(
+                        super::COMPONENT_INPUT_BOOKMARK_QUIET_HOURS,
+                        f,
+                        popup_chunks[5],
+                    );
+                }
+            }
+            if let Some(mut props) = self
+                .view
+                .get_props(super::COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL)
+            {
+                if props.build().visible {
+                    // make popup
+                    let popup = draw_area_in(f.size(), 60, 20);
+                    f.render_widget(Clear, popup);
+                    let popup_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Length(3), // Input form
+                                Constraint::Length(3), // Protocol
+                            ]
+                            .as_ref(),
+                        )
+                        .split(popup);
+                    self.view.render(
+                        super::COMPONENT_INPUT_BOOKMARK_CLONE_NAME,
+                        f,
+                        popup_chunks[0],
+                    );
+                    self.view.render(
+                        super::COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL,
+                        f,
+                        popup_chunks[1],
+                    );
                 }
             }
+            // Toast on top of everything
+            if let Some(toast) = toast {
+                f.render_widget(toast, f.size());
+            }
+            // Apply color mode (must come last)
+            f.render_widget(ColorFilter::new(color_mode), f.size());
         });
         self.context = Some(ctx);
     }
@@ -356,7 +514,7 @@ impl AuthActivity {
                         x,
                         entry.2.to_string().to_lowercase(),
                         entry.3,
-                        entry.0,
+                        fmt_host(entry.0.as_str()),
                         entry.1
                     )
                     .as_str(),
@@ -403,7 +561,7 @@ impl AuthActivity {
                         "{}://{}@{}:{}",
                         entry.2.to_string().to_lowercase(),
                         entry.3,
-                        entry.0,
+                        fmt_host(entry.0.as_str()),
                         entry.1
                     )
                     .as_str(),
@@ -455,6 +613,116 @@ impl AuthActivity {
         self.view.umount(super::COMPONENT_TEXT_ERROR);
     }
 
+    /// ### mount_issues
+    ///
+    /// Mount the panel which reports the issues occurred loading configuration and bookmarks.
+    /// The panel doesn't get focus; it is umounted if there are no issues
+    pub(super) fn mount_issues(&mut self) {
+        if self.issues.is_empty() {
+            self.umount_issues();
+            return;
+        }
+        let mut table: TableBuilder = TableBuilder::default();
+        for issue in self.issues.iter() {
+            table
+                .add_col(
+                    TextSpanBuilder::new(format!("{}: ", issue.kind).as_str())
+                        .bold()
+                        .with_foreground(Color::Red)
+                        .build(),
+                )
+                .add_col(TextSpan::from(issue.message.as_str()))
+                .add_row();
+        }
+        table
+            .add_col(
+                TextSpanBuilder::new("<CTRL+R>")
+                    .bold()
+                    .with_foreground(Color::Cyan)
+                    .build(),
+            )
+            .add_col(TextSpan::from(" Retry load  "))
+            .add_col(
+                TextSpanBuilder::new("<CTRL+C>")
+                    .bold()
+                    .with_foreground(Color::Cyan)
+                    .build(),
+            )
+            .add_col(TextSpan::from(" Open setup  "))
+            .add_col(
+                TextSpanBuilder::new("<CTRL+D>")
+                    .bold()
+                    .with_foreground(Color::Cyan)
+                    .build(),
+            )
+            .add_col(TextSpan::from(" Dismiss"));
+        self.view.mount(
+            super::COMPONENT_TABLE_ISSUES,
+            Box::new(Table::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .with_texts(TextParts::table(
+                        Some(String::from("Could not load configuration")),
+                        table.build(),
+                    ))
+                    .build(),
+            )),
+        );
+    }
+
+    /// ### mount_diagnosis
+    ///
+    /// Mount the findings of the connection diagnosis
+    pub(super) fn mount_diagnosis(&mut self, findings: &[Finding]) {
+        let mut table: TableBuilder = TableBuilder::default();
+        for (idx, finding) in findings.iter().enumerate() {
+            if idx > 0 {
+                table.add_row();
+            }
+            let color: Color = match finding.outcome {
+                Outcome::Ok => Color::Green,
+                Outcome::Warn => Color::Yellow,
+                Outcome::Fail => Color::Red,
+                Outcome::Skipped => Color::Gray,
+            };
+            table
+                .add_col(
+                    TextSpanBuilder::new(format!("[{:<4}] ", finding.outcome).as_str())
+                        .bold()
+                        .with_foreground(color)
+                        .build(),
+                )
+                .add_col(TextSpan::from(format!("{:<16}", finding.stage).as_str()))
+                .add_col(TextSpan::from(finding.message.as_str()));
+        }
+        self.view.mount(
+            super::COMPONENT_TABLE_DIAGNOSIS,
+            Box::new(Table::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::table(
+                        Some(String::from("Diagnosis")),
+                        table.build(),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_TABLE_DIAGNOSIS);
+    }
+
+    /// ### umount_diagnosis
+    ///
+    /// Umount diagnosis findings
+    pub(super) fn umount_diagnosis(&mut self) {
+        self.view.umount(super::COMPONENT_TABLE_DIAGNOSIS);
+    }
+
+    /// ### umount_issues
+    ///
+    /// Umount issues panel
+    pub(super) fn umount_issues(&mut self) {
+        self.view.umount(super::COMPONENT_TABLE_ISSUES);
+    }
+
     /// ### mount_quit
     ///
     /// Mount quit popup
@@ -547,6 +815,15 @@ impl AuthActivity {
     ///
     /// Mount bookmark save dialog
     pub(super) fn mount_bookmark_save_dialog(&mut self) {
+        // Passwords are never saved if credentials mustn't be stored
+        let never_store: bool = self
+            .context
+            .as_ref()
+            .unwrap()
+            .config_client
+            .as_ref()
+            .map(|x| x.get_never_store_credentials())
+            .unwrap_or(false);
         self.view.mount(
             super::COMPONENT_INPUT_BOOKMARK_NAME,
             Box::new(Input::new(
@@ -568,9 +845,93 @@ impl AuthActivity {
                     //.with_borders(Borders::BOTTOM | Borders::RIGHT | Borders::LEFT)
                     .with_texts(TextParts::new(
                         Some(String::from("Save password?")),
+                        Some(vec![
+                            TextSpan::from("Yes"),
+                            TextSpan::from("No"),
+                            TextSpan::from("Never"),
+                        ]),
+                    ))
+                    .with_value(PropValue::Unsigned(match never_store {
+                        true => 2,
+                        false => 0,
+                    }))
+                    .build(),
+            )),
+        );
+        // Tag of the loaded bookmark is proposed
+        let (label, color): (String, Option<TagColor>) = match self.bookmark_tag.as_ref() {
+            Some(tag) => (tag.label.clone(), Some(tag.color)),
+            None => (String::new(), None),
+        };
+        self.view.mount(
+            super::COMPONENT_INPUT_BOOKMARK_TAG_LABEL,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightMagenta)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Tag label (e.g. PRODUCTION)")),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(label))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_BOOKMARK_TAG_COLOR,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightMagenta)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Tag color")),
+                        Some(
+                            std::iter::once("None")
+                                .chain(TAG_COLORS.iter().map(|(_, name)| *name))
+                                .map(TextSpan::from)
+                                .collect(),
+                        ),
+                    ))
+                    .with_value(PropValue::Unsigned(
+                        TAG_COLORS
+                            .iter()
+                            .position(|(x, _)| Some(*x) == color)
+                            .map(|x| x + 1)
+                            .unwrap_or(0),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_BOOKMARK_PROTECTED,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Confirm destructive operations with a phrase?",
+                        )),
                         Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
                     ))
-                    //.with_value(PropValue::Unsigned(1))
+                    .with_value(PropValue::Unsigned(match self.bookmark_protected {
+                        true => 0,
+                        false => 1,
+                    }))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_BOOKMARK_QUIET_HOURS,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Quiet hours, no transfers (e.g. 09:00-18:00)")),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(
+                        self.bookmark_quiet_hours
+                            .map(|x| x.to_string())
+                            .unwrap_or_default(),
+                    ))
                     .build(),
             )),
         );
@@ -582,10 +943,63 @@ impl AuthActivity {
     ///
     /// Umount bookmark save dialog
     pub(super) fn umount_bookmark_save_dialog(&mut self) {
+        self.view
+            .umount(super::COMPONENT_INPUT_BOOKMARK_QUIET_HOURS);
+        self.view.umount(super::COMPONENT_RADIO_BOOKMARK_PROTECTED);
+        self.view.umount(super::COMPONENT_RADIO_BOOKMARK_TAG_COLOR);
+        self.view.umount(super::COMPONENT_INPUT_BOOKMARK_TAG_LABEL);
         self.view.umount(super::COMPONENT_RADIO_BOOKMARK_SAVE_PWD);
         self.view.umount(super::COMPONENT_INPUT_BOOKMARK_NAME);
     }
 
+    /// ### mount_bookmark_clone_dialog
+    ///
+    /// Mount bookmark clone dialog; name and protocol are initialized with the ones of the bookmark
+    pub(super) fn mount_bookmark_clone_dialog(
+        &mut self,
+        name: String,
+        protocol: FileTransferProtocol,
+    ) {
+        self.view.mount(
+            super::COMPONENT_INPUT_BOOKMARK_CLONE_NAME,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightCyan)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Clone bookmark as...")),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(name))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Protocol")),
+                        Some(Self::protocol_radio_options()),
+                    ))
+                    .with_value(PropValue::Unsigned(Self::protocol_to_radio_index(protocol)))
+                    .build(),
+            )),
+        );
+        // Give focus to input bookmark name
+        self.view.active(super::COMPONENT_INPUT_BOOKMARK_CLONE_NAME);
+    }
+
+    /// ### umount_bookmark_clone_dialog
+    ///
+    /// Umount bookmark clone dialog
+    pub(super) fn umount_bookmark_clone_dialog(&mut self) {
+        self.view
+            .umount(super::COMPONENT_RADIO_BOOKMARK_CLONE_PROTOCOL);
+        self.view.umount(super::COMPONENT_INPUT_BOOKMARK_CLONE_NAME);
+    }
+
     /// ### mount_help
     ///
     /// Mount help
@@ -603,7 +1017,9 @@ impl AuthActivity {
                                     .with_foreground(Color::Cyan)
                                     .build(),
                             )
-                            .add_col(TextSpan::from("           Quit TermSCP"))
+                            .add_col(TextSpan::from(
+                                "           Quit TermSCP (or go back to the open sessions)",
+                            ))
                             .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<TAB>")
@@ -645,6 +1061,16 @@ impl AuthActivity {
                             )
                             .add_col(TextSpan::from("         Delete selected bookmark"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<C>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "             Clone selected bookmark with another protocol",
+                            ))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<CTRL+C>")
                                     .bold()
@@ -660,6 +1086,38 @@ impl AuthActivity {
                                     .build(),
                             )
                             .add_col(TextSpan::from("        Save bookmark"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+T>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Diagnose connection problems"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+Y>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Copy password to clipboard"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+R>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Retry loading configuration"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+D>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Dismiss configuration issues"))
                             .build(),
                     ))
                     .build(),
@@ -688,16 +1146,7 @@ impl AuthActivity {
             Some(Payload::Unsigned(p)) => p as u16,
             _ => 0,
         };
-        let protocol: FileTransferProtocol =
-            match self.view.get_value(super::COMPONENT_RADIO_PROTOCOL) {
-                Some(Payload::Unsigned(p)) => match p {
-                    1 => FileTransferProtocol::Scp,
-                    2 => FileTransferProtocol::Ftp(false),
-                    3 => FileTransferProtocol::Ftp(true),
-                    _ => FileTransferProtocol::Sftp,
-                },
-                _ => FileTransferProtocol::Sftp,
-            };
+        let protocol: FileTransferProtocol = self.get_protocol();
         let username: String = match self.view.get_value(super::COMPONENT_INPUT_USERNAME) {
             Some(Payload::Text(a)) => a,
             _ => String::new(),
@@ -706,6 +1155,168 @@ impl AuthActivity {
             Some(Payload::Text(a)) => a,
             _ => String::new(),
         };
-        (addr, port, protocol, username, password)
+        match protocol {
+            // Container is part of the address (`pod/container`)
+            FileTransferProtocol::Kube if !password.is_empty() => (
+                format!("{}/{}", addr, password),
+                port,
+                protocol,
+                username,
+                String::new(),
+            ),
+            _ => (addr, port, protocol, username, password),
+        }
+    }
+
+    /// ### clear_password
+    ///
+    /// Clear the password input field
+    pub(super) fn clear_password(&mut self) {
+        if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_PASSWORD) {
+            let props = props.with_value(PropValue::Str(String::new())).build();
+            self.view.update(super::COMPONENT_INPUT_PASSWORD, props);
+        }
+    }
+
+    /// ### get_s3_input
+    ///
+    /// Collect S3 endpoint URL and region from view; empty values are `None`
+    pub(super) fn get_s3_input(&self) -> (Option<String>, Option<String>) {
+        let get_text = |component: &str| match self.view.get_value(component) {
+            Some(Payload::Text(a)) if !a.trim().is_empty() => Some(a.trim().to_string()),
+            _ => None,
+        };
+        (
+            get_text(super::COMPONENT_INPUT_S3_ENDPOINT),
+            get_text(super::COMPONENT_INPUT_S3_REGION),
+        )
+    }
+
+    /// ### get_bookmark_tag_input
+    ///
+    /// Collect the tag from the bookmark save dialog; no color means no tag
+    pub(super) fn get_bookmark_tag_input(&self) -> Option<BookmarkTag> {
+        let color: TagColor = match self
+            .view
+            .get_value(super::COMPONENT_RADIO_BOOKMARK_TAG_COLOR)
+        {
+            Some(Payload::Unsigned(idx)) if idx > 0 => TAG_COLORS.get(idx - 1)?.0,
+            _ => return None,
+        };
+        let label: String = match self
+            .view
+            .get_value(super::COMPONENT_INPUT_BOOKMARK_TAG_LABEL)
+        {
+            Some(Payload::Text(label)) => label.trim().to_string(),
+            _ => String::new(),
+        };
+        Some(BookmarkTag { color, label })
+    }
+
+    /// ### get_bookmark_quiet_hours_input
+    ///
+    /// Collect the quiet hours from the bookmark save dialog; an empty input means no quiet hours
+    pub(super) fn get_bookmark_quiet_hours_input(&self) -> Result<Option<TimeWindow>, String> {
+        match self
+            .view
+            .get_value(super::COMPONENT_INPUT_BOOKMARK_QUIET_HOURS)
+        {
+            Some(Payload::Text(input)) if !input.trim().is_empty() => {
+                TimeWindow::from_str(input.trim()).map(Some)
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// ### get_protocol
+    ///
+    /// Get the protocol selected in the form
+    pub(super) fn get_protocol(&self) -> FileTransferProtocol {
+        match self.view.get_value(super::COMPONENT_RADIO_PROTOCOL) {
+            Some(Payload::Unsigned(p)) => Self::radio_index_to_protocol(p),
+            _ => FileTransferProtocol::Sftp,
+        }
+    }
+
+    /// ### protocol_radio_options
+    ///
+    /// Get the options of the protocol radio groups; protocols registered by plugins come last
+    fn protocol_radio_options() -> Vec<TextSpan> {
+        FileTransferProtocol::available()
+            .into_iter()
+            .map(|protocol| match protocol {
+                FileTransferProtocol::Sftp => TextSpan::from("SFTP"),
+                FileTransferProtocol::Scp => TextSpan::from("SCP"),
+                FileTransferProtocol::Ftp(false) => TextSpan::from("FTP"),
+                FileTransferProtocol::Ftp(true) => TextSpan::from("FTPS"),
+                FileTransferProtocol::WebDav(false) => TextSpan::from("WebDAV"),
+                FileTransferProtocol::WebDav(true) => TextSpan::from("WebDAVS"),
+                FileTransferProtocol::Smb => TextSpan::from("SMB"),
+                FileTransferProtocol::Kube => TextSpan::from("Kube"),
+                FileTransferProtocol::S3 => TextSpan::from("S3"),
+                FileTransferProtocol::Plugin(scheme) => TextSpan::from(scheme),
+            })
+            .collect()
+    }
+
+    /// ### radio_index_to_protocol
+    ///
+    /// Convert the index of the protocol radio groups to a protocol
+    pub(super) fn radio_index_to_protocol(idx: usize) -> FileTransferProtocol {
+        FileTransferProtocol::available()
+            .get(idx)
+            .copied()
+            .unwrap_or(FileTransferProtocol::Sftp)
+    }
+
+    /// ### protocol_to_radio_index
+    ///
+    /// Convert a protocol to the index of the protocol radio groups
+    pub(super) fn protocol_to_radio_index(protocol: FileTransferProtocol) -> usize {
+        FileTransferProtocol::available()
+            .iter()
+            .position(|x| *x == protocol)
+            .unwrap_or(0)
+    }
+
+    /// ### update_auth_form
+    ///
+    /// Update the fields of the authentication form according to the selected protocol.
+    /// Kubernetes asks for pod name, namespace and container instead of address, username and password;
+    /// S3 asks for bucket name, access key and secret access key
+    pub(super) fn update_auth_form(&mut self) {
+        let (addr, username, password, password_type) = match self.get_protocol() {
+            FileTransferProtocol::Kube => ("Pod name", "Namespace", "Container", InputType::Text),
+            FileTransferProtocol::S3 => (
+                "Bucket name",
+                "Access key",
+                "Secret access key",
+                InputType::Password,
+            ),
+            _ => (
+                "Remote address",
+                "Username",
+                "Password",
+                InputType::Password,
+            ),
+        };
+        for (component, label) in [
+            (super::COMPONENT_INPUT_ADDR, addr),
+            (super::COMPONENT_INPUT_USERNAME, username),
+        ] {
+            if let Some(mut props) = self.view.get_props(component) {
+                let props = props
+                    .with_texts(TextParts::new(Some(String::from(label)), None))
+                    .build();
+                self.view.update(component, props);
+            }
+        }
+        if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_PASSWORD) {
+            let props = props
+                .with_texts(TextParts::new(Some(String::from(password)), None))
+                .with_input(password_type)
+                .build();
+            self.view.update(super::COMPONENT_INPUT_PASSWORD, props);
+        }
     }
 }
@@ -26,12 +26,65 @@
  * SOFTWARE.
  */
 // locals
-use super::{FileExplorerTab, FileTransferActivity, FsEntry, LogLevel};
+use super::{
+    ArchiveBrowser, FileExplorerTab, FileTransferActivity, FindSelection, FsEntry, LogLevel,
+    QueueRecovery,
+};
+use crate::bookmarks::SavedSearch;
+use crate::filetransfer::{NetworkOptions, ServerIdentity, TransferOutcome, TransferStream};
+use crate::fs::archive::Archive;
+use crate::fs::queue::scheduler::JobRun;
+use crate::fs::queue::serializer::QueueSerializer;
+use crate::fs::queue::{
+    BatchPlan, PlanItem, Priority, QueueItem, QueueItemState, QueueManifest, TransferDirection,
+    TransferQueue,
+};
+use crate::fs::search::SearchQuery;
+use crate::fs::space::{DiskSpace, UploadPlan};
+use crate::fs::transfer::{PartialFilePolicy, TransferFilters, TransferOptions};
+use crate::host::Localhost;
+use crate::system::power::WakeLock;
+use crate::system::report::CapabilityReport;
+use crate::system::transfer_history::average_throughput;
+use crate::system::trash::{remote_trashed_name, resolve_trash_dir, Trash};
+use crate::system::watcher::DirWatcher;
+use crate::system::webhook::{BatchSummary, TransferSummary, Webhook};
 use crate::ui::layout::Payload;
+use crate::ui::tasks::TaskOutput;
+use crate::utils::parser::{parse_permissions, Permissions};
+use crate::utils::template::{self, Placeholders};
 // externals
-use std::path::PathBuf;
+use bytesize::ByteSize;
+use chrono::{DateTime, Local};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::SystemTime;
+use tempfile::TempDir;
 
 impl FileTransferActivity {
+    /// ### action_local_disk_usage
+    ///
+    /// Start calculating the size of a local directory in background
+    pub(super) fn action_local_disk_usage(&mut self, dir: &FsEntry) {
+        let path: PathBuf = dir.get_abs_path();
+        let name: String = format!("Calculating size of {}", dir.get_name());
+        let id = self
+            .context
+            .as_mut()
+            .unwrap()
+            .tasks
+            .spawn(name.as_str(), move |reporter| {
+                Localhost::disk_usage(path.as_path(), &mut |files| {
+                    reporter.report(None, format!("{} files", files).as_str())
+                })
+                .map(|x| Box::new(x) as TaskOutput)
+                .map_err(|x| x.to_string())
+            });
+        self.disk_usage_task = Some((id, dir.clone()));
+    }
+
     /// ### action_change_local_dir
     ///
     /// Change local directory reading value from input
@@ -64,6 +117,227 @@ impl FileTransferActivity {
         self.remote_changedir(abs_dir_path.as_path(), true);
     }
 
+    /// ### action_toggle_pin
+    ///
+    /// Pin or unpin the working directory of the current explorer
+    pub(super) fn action_toggle_pin(&mut self) {
+        let (explorer, side) = match self.tab {
+            FileExplorerTab::Local => (&mut self.local, "local"),
+            FileExplorerTab::Remote => (&mut self.remote, "remote"),
+            _ => return,
+        };
+        let wrkdir: PathBuf = explorer.wrkdir.clone();
+        let msg: String = match explorer.toggle_pin(wrkdir.as_path()) {
+            true => format!("Pinned {} directory {}", side, wrkdir.display()),
+            false => format!("Unpinned {} directory {}", side, wrkdir.display()),
+        };
+        self.log(LogLevel::Info, msg.as_str());
+    }
+
+    /// ### action_toggle_watch
+    ///
+    /// Start watching the local working directory, uploading the files written in it to the
+    /// remote working directory; or stop watching, if a directory is already watched
+    pub(super) fn action_toggle_watch(&mut self) {
+        if let Some((watcher, _)) = self.watch.take() {
+            self.log(
+                LogLevel::Info,
+                format!("Stopped watching \"{}\"", watcher.root().display()).as_str(),
+            );
+            return;
+        }
+        let local: PathBuf = self.local.wrkdir.clone();
+        let remote: PathBuf = self.remote.wrkdir.clone();
+        match DirWatcher::new(local.as_path()) {
+            Ok(watcher) => {
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Watching \"{}\": changes are uploaded to \"{}\"",
+                        local.display(),
+                        remote.display()
+                    )
+                    .as_str(),
+                );
+                self.watch = Some((watcher, remote));
+            }
+            Err(err) => self.log_and_alert(
+                LogLevel::Error,
+                format!("Could not watch \"{}\": {}", local.display(), err),
+            ),
+        }
+    }
+
+    /// ### action_set_transfer_filters
+    ///
+    /// Set the include/exclude patterns of recursive transfers from `input` (patterns separated by whitespace)
+    /// and save them for the remote host
+    pub(super) fn action_set_transfer_filters(&mut self, input: String) {
+        let patterns: Vec<&str> = input.split_whitespace().collect();
+        self.filters = TransferFilters::new(patterns.as_slice());
+        let patterns: Vec<String> = self.filters.patterns().to_vec();
+        match patterns.is_empty() {
+            true => self.log(LogLevel::Info, "Removed transfer filters"),
+            false => self.log(
+                LogLevel::Info,
+                format!("Transfer filters set to \"{}\"", patterns.join(" ")).as_str(),
+            ),
+        }
+        if let Err(err) =
+            self.write_host_data(|cli, host| cli.set_transfer_filters(host, patterns.as_slice()))
+        {
+            self.log(
+                LogLevel::Warn,
+                format!("Could not save transfer filters: {}", err).as_str(),
+            );
+        }
+    }
+
+    /// ### action_upload_watched
+    ///
+    /// Upload the files written in the watched directory since the last call.
+    /// Returns whether any file has been uploaded
+    pub(super) fn action_upload_watched(&mut self) -> bool {
+        if !self.client.is_connected() {
+            return false;
+        }
+        let (files, local_root, remote_root): (Vec<PathBuf>, PathBuf, PathBuf) =
+            match self.watch.as_mut() {
+                Some((watcher, remote)) => {
+                    (watcher.poll(), watcher.root().to_path_buf(), remote.clone())
+                }
+                None => return false,
+            };
+        let mut uploaded: bool = false;
+        for path in files.into_iter() {
+            let entry: FsEntry = match self.context.as_ref().unwrap().local.stat(path.as_path()) {
+                Ok(entry) if entry.is_file() => entry,
+                // Removed or replaced in the meantime
+                _ => continue,
+            };
+            // Skip files matching ignore rules or filters, also through their parent directories
+            let excluded: bool = path
+                .ancestors()
+                .skip(1)
+                .take_while(|x| *x != local_root.as_path())
+                .filter_map(|x| self.context.as_ref().unwrap().local.stat(x).ok())
+                .chain(std::iter::once(entry.clone()))
+                .any(|x| self.transfer_opts.ignore.is_ignored(&x) || self.filters.is_filtered(&x));
+            if excluded {
+                continue;
+            }
+            // Mirror the path relative to the watched directory
+            let remote_dir: PathBuf =
+                match path.parent().and_then(|x| x.strip_prefix(&local_root).ok()) {
+                    Some(rel) => remote_root.join(rel),
+                    None => continue,
+                };
+            if let Err(err) = self.make_remote_dirs(remote_root.as_path(), remote_dir.as_path()) {
+                self.log(LogLevel::Error, err.as_str());
+                continue;
+            }
+            self.log(
+                LogLevel::Info,
+                format!("\"{}\" changed; uploading it", path.display()).as_str(),
+            );
+            uploaded |= self
+                .filetransfer_send(&entry, remote_dir.as_path(), None)
+                .is_ok();
+        }
+        if uploaded {
+            self.update_remote_filelist();
+        }
+        uploaded
+    }
+
+    /// ### make_remote_dirs
+    ///
+    /// Create the directories from `root` (which must exist) down to `dir` on remote
+    fn make_remote_dirs(&mut self, root: &Path, dir: &Path) -> Result<(), String> {
+        let mut path: PathBuf = root.to_path_buf();
+        for component in dir.strip_prefix(root).unwrap_or(Path::new("")).iter() {
+            path.push(component);
+            if self
+                .with_reconnect(|client| client.stat(path.as_path()))
+                .is_ok()
+            {
+                continue;
+            }
+            match self.with_reconnect(|client| client.mkdir(path.as_path())) {
+                Ok(_) => self.log(
+                    LogLevel::Info,
+                    format!("Created directory \"{}\"", path.display()).as_str(),
+                ),
+                Err(err) => {
+                    return Err(format!(
+                        "Failed to create directory \"{}\": {}",
+                        path.display(),
+                        err
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// ### make_local_dirs
+    ///
+    /// Create the directories from `root` (which must exist) down to `dir` on localhost
+    fn make_local_dirs(&mut self, root: &Path, dir: &Path) -> Result<(), String> {
+        let mut path: PathBuf = root.to_path_buf();
+        for component in dir.strip_prefix(root).unwrap_or(Path::new("")).iter() {
+            path.push(component);
+            if self
+                .context
+                .as_ref()
+                .unwrap()
+                .local
+                .file_exists(path.as_path())
+            {
+                continue;
+            }
+            match self
+                .context
+                .as_mut()
+                .unwrap()
+                .local
+                .mkdir_ex(path.as_path(), true)
+            {
+                Ok(_) => self.log(
+                    LogLevel::Info,
+                    format!("Created directory \"{}\"", path.display()).as_str(),
+                ),
+                Err(err) => {
+                    return Err(format!(
+                        "Failed to create directory \"{}\": {}",
+                        path.display(),
+                        err
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// ### action_jump_to_pin
+    ///
+    /// Change working directory of the current explorer to the pin at provided index
+    pub(super) fn action_jump_to_pin(&mut self, idx: usize) {
+        match self.tab {
+            FileExplorerTab::Local => {
+                if let Some(dir) = self.local.pins().get(idx).cloned() {
+                    self.local_changedir(dir.as_path(), true);
+                }
+            }
+            FileExplorerTab::Remote => {
+                if let Some(dir) = self.remote.pins().get(idx).cloned() {
+                    self.remote_changedir(dir.as_path(), true);
+                }
+            }
+            _ => {}
+        }
+    }
+
     /// ### action_local_copy
     ///
     /// Copy file on local
@@ -108,7 +382,7 @@ impl FileTransferActivity {
         if let Some(idx) = self.get_remote_file_idx() {
             let dest_path: PathBuf = PathBuf::from(input);
             let entry: FsEntry = self.remote.get(idx).unwrap().clone();
-            match self.client.as_mut().copy(&entry, dest_path.as_path()) {
+            match self.with_reconnect(|client| client.copy(&entry, dest_path.as_path())) {
                 Ok(_) => {
                     self.log(
                         LogLevel::Info,
@@ -230,11 +504,11 @@ impl FileTransferActivity {
 
     pub(super) fn action_remote_rename(&mut self, input: String) {
         if let Some(idx) = self.get_remote_file_idx() {
-            if let Some(entry) = self.remote.get(idx) {
+            if let Some(entry) = self.remote.get(idx).cloned() {
                 let dst_path: PathBuf = PathBuf::from(input);
                 let full_path: PathBuf = entry.get_abs_path();
                 // Rename file or directory and report status as popup
-                match self.client.as_mut().rename(entry, dst_path.as_path()) {
+                match self.with_reconnect(|client| client.rename(&entry, dst_path.as_path())) {
                     Ok(_) => {
                         // Reload files
                         let path: PathBuf = self.remote.wrkdir.clone();
@@ -261,21 +535,132 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### resolve_permissions
+    ///
+    /// Get permissions from `input`, which is either `MODE [UID:GID]` or the name of a permission template
+    fn resolve_permissions(&self, input: &str) -> Option<Permissions> {
+        parse_permissions(input).or_else(|| {
+            self.context
+                .as_ref()
+                .unwrap()
+                .config_client
+                .as_ref()
+                .and_then(|cli| cli.get_permission_template(input.trim()))
+        })
+    }
+
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    pub(super) fn action_local_chmod(&mut self, input: String) {
+        let entry: Option<FsEntry> = self.get_local_file_entry().cloned();
+        if let Some(entry) = entry {
+            let (mode, owner) = match self.resolve_permissions(input.as_str()) {
+                Some(permissions) => permissions,
+                None => {
+                    self.mount_error(format!("Invalid permissions \"{}\"", input).as_str());
+                    return;
+                }
+            };
+            let full_path: PathBuf = entry.get_abs_path();
+            let local: &Localhost = &self.context.as_ref().unwrap().local;
+            let result = local
+                .chmod(full_path.as_path(), mode)
+                .and_then(|_| match owner {
+                    Some((uid, gid)) => local.chown(full_path.as_path(), uid, gid),
+                    None => Ok(()),
+                });
+            match result {
+                Ok(_) => {
+                    // Reload files
+                    let path: PathBuf = self.local.wrkdir.clone();
+                    self.local_scan(path.as_path());
+                    self.log(
+                        LogLevel::Info,
+                        format!(
+                            "Changed permissions of \"{}\" to {}",
+                            full_path.display(),
+                            input.trim()
+                        )
+                        .as_ref(),
+                    );
+                }
+                Err(err) => {
+                    self.log_and_alert(
+                        LogLevel::Error,
+                        format!(
+                            "Could not change permissions of \"{}\": {}",
+                            full_path.display(),
+                            err
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    #[cfg(target_os = "windows")]
+    pub(super) fn action_local_chmod(&mut self, _input: String) {
+        self.mount_error("Permissions can't be changed on this system");
+    }
+
+    pub(super) fn action_remote_chmod(&mut self, input: String) {
+        let entry: Option<FsEntry> = self.get_remote_file_entry().cloned();
+        if let Some(entry) = entry {
+            let (mode, owner) = match self.resolve_permissions(input.as_str()) {
+                Some(permissions) => permissions,
+                None => {
+                    self.mount_error(format!("Invalid permissions \"{}\"", input).as_str());
+                    return;
+                }
+            };
+            let full_path: PathBuf = entry.get_abs_path();
+            let result = self.with_reconnect(|client| {
+                client.chmod(full_path.as_path(), mode)?;
+                match owner {
+                    Some((uid, gid)) => client.chown(full_path.as_path(), uid, gid),
+                    None => Ok(()),
+                }
+            });
+            match result {
+                Ok(_) => {
+                    // Reload files
+                    let path: PathBuf = self.remote.wrkdir.clone();
+                    self.remote_scan(path.as_path());
+                    self.log(
+                        LogLevel::Info,
+                        format!(
+                            "Changed permissions of \"{}\" to {}",
+                            full_path.display(),
+                            input.trim()
+                        )
+                        .as_ref(),
+                    );
+                }
+                Err(err) => {
+                    self.log_and_alert(
+                        LogLevel::Error,
+                        format!(
+                            "Could not change permissions of \"{}\": {}",
+                            full_path.display(),
+                            err
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
     pub(super) fn action_local_delete(&mut self) {
         let entry: Option<FsEntry> = self.get_local_file_entry().cloned();
         if let Some(entry) = entry {
             let full_path: PathBuf = entry.get_abs_path();
             // Delete file or directory and report status as popup
-            match self.context.as_mut().unwrap().local.remove(&entry) {
-                Ok(_) => {
+            match self.local_delete(&entry) {
+                Ok(trashed) => {
                     // Reload files
                     let p: PathBuf = self.local.wrkdir.clone();
                     self.local_scan(p.as_path());
                     // Log
-                    self.log(
-                        LogLevel::Info,
-                        format!("Removed file \"{}\"", full_path.display()).as_ref(),
-                    );
+                    self.log_deleted(full_path.as_path(), trashed);
                 }
                 Err(err) => {
                     self.log_and_alert(
@@ -290,16 +675,13 @@ impl FileTransferActivity {
     pub(super) fn action_remote_delete(&mut self) {
         if let Some(idx) = self.get_remote_file_idx() {
             // Check if file entry exists
-            if let Some(entry) = self.remote.get(idx) {
+            if let Some(entry) = self.remote.get(idx).cloned() {
                 let full_path: PathBuf = entry.get_abs_path();
                 // Delete file
-                match self.client.remove(entry) {
-                    Ok(_) => {
+                match self.remote_delete(&entry) {
+                    Ok(trashed) => {
                         self.reload_remote_dir();
-                        self.log(
-                            LogLevel::Info,
-                            format!("Removed file \"{}\"", full_path.display()).as_ref(),
-                        );
+                        self.log_deleted(full_path.as_path(), trashed);
                     }
                     Err(err) => {
                         self.log_and_alert(
@@ -312,6 +694,201 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### action_purge_trash
+    ///
+    /// Remove all the entries in the remote trash
+    pub(super) fn action_purge_trash(&mut self) {
+        let trash: PathBuf = match self.remote_trash_dir() {
+            Ok(trash) => trash,
+            Err(err) => {
+                self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not empty the trash: {}", err),
+                );
+                return;
+            }
+        };
+        // A trash which doesn't exist yet has never been used
+        if self
+            .with_reconnect(|client| client.stat(trash.as_path()))
+            .is_err()
+        {
+            self.log(
+                LogLevel::Info,
+                format!("Trash \"{}\" is already empty", trash.display()).as_str(),
+            );
+            return;
+        }
+        let entries: Vec<FsEntry> =
+            match self.with_reconnect(|client| client.list_dir(trash.as_path())) {
+                Ok(entries) => entries,
+                Err(err) => {
+                    self.log_and_alert(
+                        LogLevel::Error,
+                        format!("Could not empty the trash \"{}\": {}", trash.display(), err),
+                    );
+                    return;
+                }
+            };
+        let (mut removed, mut freed): (usize, u64) = (0, 0);
+        for entry in entries.iter() {
+            match self.remote_remove(entry) {
+                Ok(_) => {
+                    removed += 1;
+                    freed += entry.get_size() as u64;
+                }
+                Err(err) => self.log(
+                    LogLevel::Error,
+                    format!(
+                        "Could not delete file \"{}\": {}",
+                        entry.get_abs_path().display(),
+                        err
+                    )
+                    .as_str(),
+                ),
+            }
+        }
+        self.reload_remote_dir();
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Emptied trash \"{}\": removed {} entries ({} freed)",
+                trash.display(),
+                removed,
+                ByteSize(freed)
+            )
+            .as_str(),
+        );
+    }
+
+    /// ### local_delete
+    ///
+    /// Delete `entry` from localhost; if the trash is used, the entry is moved to the trash of the user instead.
+    /// Returns the path of the entry in the trash, if it's been moved there
+    fn local_delete(&mut self, entry: &FsEntry) -> Result<Option<PathBuf>, String> {
+        let path: PathBuf = entry.get_abs_path();
+        if self.transfer_opts.use_trash {
+            let trash: Trash = Trash::home()
+                .ok_or_else(|| String::from("the trash is not supported on this platform"))?;
+            // Entries in the trash are removed for good
+            if !path.starts_with(trash.root()) {
+                return trash
+                    .put(path.as_path())
+                    .map(Some)
+                    .map_err(|e| e.to_string());
+            }
+        }
+        self.context
+            .as_mut()
+            .unwrap()
+            .local
+            .remove(entry)
+            .map(|_| None)
+            .map_err(|e| e.to_string())
+    }
+
+    /// ### remote_delete
+    ///
+    /// Delete `entry` from remote; if the trash is used, the entry is moved to the remote trash instead.
+    /// Returns the path of the entry in the trash, if it's been moved there
+    fn remote_delete(&mut self, entry: &FsEntry) -> Result<Option<PathBuf>, String> {
+        let trash: Option<PathBuf> = match self.transfer_opts.use_trash {
+            true => Some(self.remote_trash_dir()?),
+            false => None,
+        };
+        // Entries in the trash are removed for good
+        let trash: PathBuf = match trash {
+            Some(trash) if !entry.get_abs_path().starts_with(trash.as_path()) => trash,
+            _ => {
+                return self
+                    .remote_remove(entry)
+                    .map(|_| None)
+                    .map_err(|e| e.to_string())
+            }
+        };
+        self.make_remote_dirs(Path::new("/"), trash.as_path())?;
+        // Names in the trash are suffixed with the time of deletion
+        let deleted: DateTime<Local> = Local::now();
+        let mut attempt: usize = 0;
+        let target: PathBuf = loop {
+            let target: PathBuf =
+                trash.join(remote_trashed_name(entry.get_name(), deleted, attempt));
+            if self
+                .with_reconnect(|client| client.stat(target.as_path()))
+                .is_err()
+            {
+                break target;
+            }
+            attempt += 1;
+        };
+        self.with_reconnect(|client| client.rename(entry, target.as_path()))
+            .map(|_| Some(target))
+            .map_err(|e| e.to_string())
+    }
+
+    /// ### remote_trash_dir
+    ///
+    /// Get the absolute path of the remote trash
+    fn remote_trash_dir(&self) -> Result<PathBuf, String> {
+        let dir: &str = self.transfer_opts.trash_dir.as_str();
+        match self.remote_home.as_deref() {
+            Some(home) => Ok(resolve_trash_dir(dir, home)),
+            None if Path::new(dir).is_absolute() => Ok(PathBuf::from(dir)),
+            None => Err(format!(
+                "could not resolve \"{}\": the remote home directory is unknown",
+                dir
+            )),
+        }
+    }
+
+    /// ### log_deleted
+    ///
+    /// Log the deletion of `path`; `trashed` is where it's been moved to, if it's been moved to the trash
+    fn log_deleted(&mut self, path: &Path, trashed: Option<PathBuf>) {
+        let msg: String = match trashed {
+            Some(trashed) => format!(
+                "Moved \"{}\" to the trash (\"{}\")",
+                path.display(),
+                trashed.display()
+            ),
+            None => format!("Removed file \"{}\"", path.display()),
+        };
+        self.log(LogLevel::Info, msg.as_str());
+    }
+
+    /// ### action_local_move
+    ///
+    /// Upload the selected local entry to the remote working directory, then remove it
+    pub(super) fn action_local_move(&mut self) {
+        if let Some(entry) = self.get_local_file_entry().cloned() {
+            let wrkdir: PathBuf = self.remote.wrkdir.clone();
+            let name: String = entry.get_name().to_string();
+            // Pass realfile, keep link name
+            let result: Result<(), String> =
+                self.filetransfer_send(&entry.get_realfile(), wrkdir.as_path(), Some(name));
+            self.complete_move(TransferDirection::Upload, &entry, wrkdir.as_path(), result);
+        }
+    }
+
+    /// ### action_remote_move
+    ///
+    /// Download the selected remote entry to the local working directory, then remove it
+    pub(super) fn action_remote_move(&mut self) {
+        if let Some(entry) = self.get_remote_file_entry().cloned() {
+            let wrkdir: PathBuf = self.local.wrkdir.clone();
+            let name: String = entry.get_name().to_string();
+            // Pass realfile, keep link name
+            let result: Result<(), String> =
+                self.filetransfer_recv(&entry.get_realfile(), wrkdir.as_path(), Some(name));
+            self.complete_move(
+                TransferDirection::Download,
+                &entry,
+                wrkdir.as_path(),
+                result,
+            );
+        }
+    }
+
     pub(super) fn action_local_saveas(&mut self, input: String) {
         if let Some(idx) = self.get_local_file_idx() {
             // Get pwd
@@ -319,7 +896,7 @@ impl FileTransferActivity {
             if self.local.get(idx).is_some() {
                 let file: FsEntry = self.local.get(idx).unwrap().clone();
                 // Call upload; pass realfile, keep link name
-                self.filetransfer_send(&file.get_realfile(), wrkdir.as_path(), Some(input));
+                let _ = self.filetransfer_send(&file.get_realfile(), wrkdir.as_path(), Some(input));
             }
         }
     }
@@ -331,7 +908,7 @@ impl FileTransferActivity {
             if self.remote.get(idx).is_some() {
                 let file: FsEntry = self.remote.get(idx).unwrap().clone();
                 // Call upload; pass realfile, keep link name
-                self.filetransfer_recv(&file.get_realfile(), wrkdir.as_path(), Some(input));
+                let _ = self.filetransfer_recv(&file.get_realfile(), wrkdir.as_path(), Some(input));
             }
         }
     }
@@ -389,7 +966,7 @@ impl FileTransferActivity {
         // Get path on remote
         let file_path: PathBuf = PathBuf::from(input.as_str());
         // Create file (on local)
-        match tempfile::NamedTempFile::new() {
+        match self.staging().tempfile() {
             Err(err) => self.log_and_alert(
                 LogLevel::Error,
                 format!("Could not create tempfile: {}", err),
@@ -409,7 +986,9 @@ impl FileTransferActivity {
                     };
                     if let FsEntry::File(local_file) = local_file {
                         // Create file
-                        match self.client.send_file(&local_file, file_path.as_path()) {
+                        match self.with_reconnect(|client| {
+                            client.send_file(&local_file, file_path.as_path())
+                        }) {
                             Err(err) => self.log_and_alert(
                                 LogLevel::Error,
                                 format!(
@@ -420,7 +999,9 @@ impl FileTransferActivity {
                             ),
                             Ok(writer) => {
                                 // Finalize write
-                                if let Err(err) = self.client.on_sent(writer) {
+                                if let Err(err) = self.client.finalize_transfer(
+                                    TransferOutcome::completed(TransferStream::Upload(writer), 0),
+                                ) {
                                     self.log_and_alert(
                                         LogLevel::Warn,
                                         format!("Could not finalize file: {}", err),
@@ -444,13 +1025,19 @@ impl FileTransferActivity {
     }
 
     pub(super) fn action_local_exec(&mut self, input: String) {
-        match self.context.as_mut().unwrap().local.exec(input.as_str()) {
+        let cmd: String = match template::expand(input.as_str(), &self.get_placeholders(), false) {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                return self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not execute command \"{}\": {}", input, err),
+                )
+            }
+        };
+        match self.context.as_mut().unwrap().local.exec(cmd.as_str()) {
             Ok(output) => {
                 // Reload files
-                self.log(
-                    LogLevel::Info,
-                    format!("\"{}\": {}", input, output).as_ref(),
-                );
+                self.log(LogLevel::Info, format!("\"{}\": {}", cmd, output).as_ref());
                 let wrkdir: PathBuf = self.local.wrkdir.clone();
                 self.local_scan(wrkdir.as_path());
             }
@@ -458,44 +1045,193 @@ impl FileTransferActivity {
                 // Report err
                 self.log_and_alert(
                     LogLevel::Error,
-                    format!("Could not execute command \"{}\": {}", input, err),
+                    format!("Could not execute command \"{}\": {}", cmd, err),
                 );
             }
         }
     }
 
     pub(super) fn action_remote_exec(&mut self, input: String) {
-        match self.client.as_mut().exec(input.as_str()) {
-            Ok(output) => {
-                // Reload files
+        // Save command to history
+        self.cmd_history.push(input.as_str());
+        if !input.is_empty() {
+            if let Err(err) =
+                self.write_host_data(|cli, host| cli.add_command(host, input.as_str()))
+            {
                 self.log(
-                    LogLevel::Info,
-                    format!("\"{}\": {}", input, output).as_ref(),
+                    LogLevel::Warn,
+                    format!("Could not save command history: {}", err).as_str(),
                 );
+            }
+        }
+        let cmd: String = match template::expand(input.as_str(), &self.get_placeholders(), true) {
+            Ok(cmd) => cmd,
+            Err(err) => {
+                return self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not execute command \"{}\": {}", input, err),
+                )
+            }
+        };
+        match self.with_reconnect(|client| client.exec(cmd.as_str())) {
+            Ok(output) => {
+                // Reload files
+                self.log(LogLevel::Info, format!("\"{}\": {}", cmd, output).as_ref());
                 self.reload_remote_dir();
             }
             Err(err) => {
                 // Report err
                 self.log_and_alert(
                     LogLevel::Error,
-                    format!("Could not execute command \"{}\": {}", input, err),
+                    format!("Could not execute command \"{}\": {}", cmd, err),
                 );
             }
         }
     }
 
-    pub(super) fn action_local_find(&mut self, input: String) -> Result<Vec<FsEntry>, String> {
-        match self.context.as_mut().unwrap().local.find(input.as_str()) {
-            Ok(entries) => Ok(entries),
-            Err(err) => Err(format!("Could not search for files: {}", err)),
-        }
-    }
-
-    pub(super) fn action_remote_find(&mut self, input: String) -> Result<Vec<FsEntry>, String> {
-        match self.client.as_mut().find(input.as_str()) {
-            Ok(entries) => Ok(entries),
-            Err(err) => Err(format!("Could not search for files: {}", err)),
-        }
+    /// ### action_find
+    ///
+    /// Search for the entries matching the query in `input` on the current explorer, then show the results
+    pub(super) fn action_find(&mut self, input: String, cleanup: bool) {
+        let query: SearchQuery = match SearchQuery::from_str(input.as_str()) {
+            Ok(query) => query,
+            Err(err) => return self.mount_error(err.as_str()),
+        };
+        // Find
+        let res: Result<Vec<FsEntry>, String> = match self.tab {
+            FileExplorerTab::Local => self.action_local_find(&query),
+            FileExplorerTab::Remote => self.action_remote_find(&query),
+            _ => panic!("Trying to search for files, while already in a find result"),
+        };
+        // Match result
+        match res {
+            Err(err) => {
+                // Mount error
+                self.mount_error(err.as_str());
+            }
+            Ok(mut files) => {
+                let now: SystemTime = SystemTime::now();
+                files.retain(|x| query.matches(x, now));
+                // Only regular files can be cleaned up; they're all selected at first
+                if cleanup {
+                    files.retain(|x| !x.is_dir() && !x.is_symlink());
+                }
+                let marked: BTreeSet<usize> = match cleanup {
+                    true => (0..files.len()).collect(),
+                    false => BTreeSet::new(),
+                };
+                // Create explorer and load files
+                let mut explorer = Self::build_found_explorer();
+                explorer.set_files(files);
+                self.found = Some(explorer);
+                self.found_selection = FindSelection {
+                    search: query.to_string(),
+                    marked,
+                    keep_tree: cleanup,
+                    cleanup,
+                };
+                // Mount result widget
+                self.mount_find(query.to_string().as_str());
+                self.update_find_list();
+                // Initialize tab
+                self.tab = match self.tab {
+                    FileExplorerTab::Local => FileExplorerTab::FindLocal,
+                    FileExplorerTab::Remote => FileExplorerTab::FindRemote,
+                    _ => FileExplorerTab::FindLocal,
+                };
+            }
+        }
+    }
+
+    pub(super) fn action_local_find(
+        &mut self,
+        query: &SearchQuery,
+    ) -> Result<Vec<FsEntry>, String> {
+        let local: &Localhost = &self.context.as_ref().unwrap().local;
+        let result = match query.directory.as_ref() {
+            Some(dir) => local.find_in(self.local.wrkdir.join(dir).as_path(), &query.pattern),
+            None => local.find(query.pattern.as_str()),
+        };
+        match result {
+            Ok(entries) => Ok(entries),
+            Err(err) => Err(format!("Could not search for files: {}", err)),
+        }
+    }
+
+    pub(super) fn action_remote_find(
+        &mut self,
+        query: &SearchQuery,
+    ) -> Result<Vec<FsEntry>, String> {
+        let dir: Option<PathBuf> = query.directory.as_ref().map(|x| self.remote.wrkdir.join(x));
+        match self.with_reconnect(|client| match dir.as_ref() {
+            Some(dir) => client.find_in(dir.as_path(), query.pattern.as_str()),
+            None => client.find(query.pattern.as_str()),
+        }) {
+            Ok(entries) => Ok(entries),
+            Err(err) => Err(format!("Could not search for files: {}", err)),
+        }
+    }
+
+    /// ### action_save_search
+    ///
+    /// Save the current search as `name` for the remote host; a search with the same name is replaced.
+    /// If `name` is empty, the query is used as name
+    pub(super) fn action_save_search(&mut self, name: String) {
+        let query: String = self.found_selection.search.clone();
+        let name: String = match name.trim() {
+            "" => query.clone(),
+            name => name.to_string(),
+        };
+        match self.saved_searches.iter_mut().find(|x| x.name == name) {
+            Some(search) => search.query = query.clone(),
+            None => self.saved_searches.push(SavedSearch {
+                name: name.clone(),
+                query: query.clone(),
+            }),
+        }
+        self.log(
+            LogLevel::Info,
+            format!("Saved search \"{}\" as \"{}\"", query, name).as_str(),
+        );
+        self.write_saved_searches();
+    }
+
+    /// ### action_run_saved_search
+    ///
+    /// Run the saved search at `idx` on the current explorer
+    pub(super) fn action_run_saved_search(&mut self, idx: usize) {
+        if let Some(search) = self.saved_searches.get(idx).cloned() {
+            self.action_find(search.query, false);
+        }
+    }
+
+    /// ### action_delete_saved_search
+    ///
+    /// Delete the saved search at `idx`
+    pub(super) fn action_delete_saved_search(&mut self, idx: usize) {
+        if idx < self.saved_searches.len() {
+            let search: SavedSearch = self.saved_searches.remove(idx);
+            self.log(
+                LogLevel::Info,
+                format!("Deleted saved search \"{}\"", search.name).as_str(),
+            );
+            self.write_saved_searches();
+        }
+    }
+
+    /// ### write_saved_searches
+    ///
+    /// Write the saved searches to the bookmarks file
+    fn write_saved_searches(&mut self) {
+        let searches: Vec<SavedSearch> = self.saved_searches.clone();
+        if let Err(err) =
+            self.write_host_data(|cli, host| cli.set_saved_searches(host, searches.as_slice()))
+        {
+            self.log(
+                LogLevel::Warn,
+                format!("Could not save searches: {}", err).as_str(),
+            );
+        }
     }
 
     pub(super) fn action_find_changedir(&mut self, idx: usize) {
@@ -528,16 +1264,64 @@ impl FileTransferActivity {
             match self.tab {
                 FileExplorerTab::FindLocal | FileExplorerTab::Local => {
                     let wrkdir: PathBuf = self.remote.wrkdir.clone();
-                    self.filetransfer_send(&entry.get_realfile(), wrkdir.as_path(), name);
+                    let _ = self.filetransfer_send(&entry.get_realfile(), wrkdir.as_path(), name);
                 }
                 FileExplorerTab::FindRemote | FileExplorerTab::Remote => {
                     let wrkdir: PathBuf = self.local.wrkdir.clone();
-                    self.filetransfer_recv(&entry.get_realfile(), wrkdir.as_path(), name);
+                    let _ = self.filetransfer_recv(&entry.get_realfile(), wrkdir.as_path(), name);
                 }
             }
         }
     }
 
+    /// ### action_find_delete_marked
+    ///
+    /// Delete all the selected find results; the explorer is reloaded once, after all of them have been removed
+    pub(super) fn action_find_delete_marked(&mut self) {
+        let indexes: Vec<usize> = self.found_selection.marked.iter().rev().cloned().collect();
+        let (mut removed, mut freed): (usize, u64) = (0, 0);
+        for idx in indexes.into_iter() {
+            let entry: FsEntry = match self.found.as_ref().unwrap().get(idx).cloned() {
+                Some(entry) => entry,
+                None => continue,
+            };
+            let full_path: PathBuf = entry.get_abs_path();
+            let result: Result<Option<PathBuf>, String> = match self.tab {
+                FileExplorerTab::FindLocal => self.local_delete(&entry),
+                FileExplorerTab::FindRemote => self.remote_delete(&entry),
+                _ => return,
+            };
+            match result {
+                Ok(trashed) => {
+                    // Trashed entries still take up space
+                    if trashed.is_none() {
+                        freed += entry.get_size() as u64;
+                    }
+                    self.log_deleted(full_path.as_path(), trashed);
+                    removed += 1;
+                    self.found.as_mut().unwrap().del_entry(idx);
+                    self.found_selection.remove(idx);
+                }
+                Err(err) => self.log(
+                    LogLevel::Error,
+                    format!("Could not delete file \"{}\": {}", full_path.display(), err).as_str(),
+                ),
+            }
+        }
+        // Reload files
+        match self.tab {
+            FileExplorerTab::FindLocal => {
+                let wrkdir: PathBuf = self.local.wrkdir.clone();
+                self.local_scan(wrkdir.as_path());
+            }
+            _ => self.reload_remote_dir(),
+        }
+        self.log(
+            LogLevel::Info,
+            format!("Removed {} files ({} freed)", removed, ByteSize(freed)).as_str(),
+        );
+    }
+
     pub(super) fn action_find_delete(&mut self, idx: usize) {
         let entry: Option<FsEntry> = self.found.as_ref().unwrap().get(idx).cloned();
         if let Some(entry) = entry {
@@ -546,16 +1330,13 @@ impl FileTransferActivity {
                 FileExplorerTab::FindLocal | FileExplorerTab::Local => {
                     let full_path: PathBuf = entry.get_abs_path();
                     // Delete file or directory and report status as popup
-                    match self.context.as_mut().unwrap().local.remove(&entry) {
-                        Ok(_) => {
+                    match self.local_delete(&entry) {
+                        Ok(trashed) => {
                             // Reload files
                             let p: PathBuf = self.local.wrkdir.clone();
                             self.local_scan(p.as_path());
                             // Log
-                            self.log(
-                                LogLevel::Info,
-                                format!("Removed file \"{}\"", full_path.display()).as_ref(),
-                            );
+                            self.log_deleted(full_path.as_path(), trashed);
                         }
                         Err(err) => {
                             self.log_and_alert(
@@ -572,13 +1353,10 @@ impl FileTransferActivity {
                 FileExplorerTab::FindRemote | FileExplorerTab::Remote => {
                     let full_path: PathBuf = entry.get_abs_path();
                     // Delete file
-                    match self.client.remove(&entry) {
-                        Ok(_) => {
+                    match self.remote_delete(&entry) {
+                        Ok(trashed) => {
                             self.reload_remote_dir();
-                            self.log(
-                                LogLevel::Info,
-                                format!("Removed file \"{}\"", full_path.display()).as_ref(),
-                            );
+                            self.log_deleted(full_path.as_path(), trashed);
                         }
                         Err(err) => {
                             self.log_and_alert(
@@ -596,6 +1374,1407 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### action_find_enqueue
+    ///
+    /// Add the selected find results (or the result at `idx`, if none is selected) to the transfer queue.
+    /// If `keep_tree` is set, results are transferred to their path relative to the working directory,
+    /// creating the missing directories on the other host; otherwise into its working directory
+    pub(super) fn action_find_enqueue(&mut self, idx: usize) {
+        let (root, dest_root, direction): (PathBuf, PathBuf, TransferDirection) = match self.tab {
+            FileExplorerTab::FindLocal => (
+                self.local.wrkdir.clone(),
+                self.remote.wrkdir.clone(),
+                TransferDirection::Upload,
+            ),
+            FileExplorerTab::FindRemote => (
+                self.remote.wrkdir.clone(),
+                self.local.wrkdir.clone(),
+                TransferDirection::Download,
+            ),
+            _ => return,
+        };
+        let indexes: Vec<usize> = match self.found_selection.marked.is_empty() {
+            true => vec![idx],
+            false => self.found_selection.marked.iter().cloned().collect(),
+        };
+        let entries: Vec<FsEntry> = indexes
+            .into_iter()
+            .filter_map(|x| self.found.as_ref().unwrap().get(x).cloned())
+            .collect();
+        for entry in entries.into_iter() {
+            let path: PathBuf = entry.get_abs_path();
+            let dest: PathBuf = match (
+                self.found_selection.keep_tree,
+                path.parent().and_then(|x| x.strip_prefix(&root).ok()),
+            ) {
+                (true, Some(rel)) => dest_root.join(rel),
+                _ => dest_root.clone(),
+            };
+            let created: Result<(), String> = match direction {
+                TransferDirection::Upload => {
+                    self.make_remote_dirs(dest_root.as_path(), dest.as_path())
+                }
+                TransferDirection::Download => {
+                    self.make_local_dirs(dest_root.as_path(), dest.as_path())
+                }
+            };
+            if let Err(err) = created {
+                self.log(LogLevel::Error, err.as_str());
+                continue;
+            }
+            // Cleaned up files are archived on the other host, then removed
+            let remove_source: bool = self.found_selection.cleanup;
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Added \"{}\" to transfer queue (to \"{}\"{})",
+                    path.display(),
+                    dest.display(),
+                    match remove_source {
+                        true => ", then removed",
+                        false => "",
+                    }
+                )
+                .as_str(),
+            );
+            let id: usize =
+                self.queue
+                    .push(entry.get_realfile(), dest, direction, Priority::Normal);
+            self.queue.set_remove_source(id, remove_source);
+        }
+        self.found_selection.marked.clear();
+        // Directories may have been created on the other host
+        if self.found_selection.keep_tree {
+            match direction {
+                TransferDirection::Upload => self.reload_remote_dir(),
+                TransferDirection::Download => {
+                    let wrkdir: PathBuf = self.local.wrkdir.clone();
+                    self.local_scan(wrkdir.as_path());
+                }
+            }
+        }
+    }
+
+    /// ### action_enqueue
+    ///
+    /// Add the selected file to the transfer queue.
+    /// Local files are uploaded to the remote working directory, remote files are downloaded to the local one
+    pub(super) fn action_enqueue(&mut self) {
+        let (entry, dest, direction): (Option<FsEntry>, PathBuf, TransferDirection) = match self.tab
+        {
+            FileExplorerTab::Local => (
+                self.get_local_file_entry().cloned(),
+                self.remote.wrkdir.clone(),
+                TransferDirection::Upload,
+            ),
+            FileExplorerTab::Remote => (
+                self.get_remote_file_entry().cloned(),
+                self.local.wrkdir.clone(),
+                TransferDirection::Download,
+            ),
+            _ => return,
+        };
+        if let Some(entry) = entry {
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Added \"{}\" to transfer queue",
+                    entry.get_abs_path().display()
+                )
+                .as_str(),
+            );
+            self.queue
+                .push(entry.get_realfile(), dest, direction, Priority::Normal);
+        }
+    }
+
+    /// ### action_run_queue
+    ///
+    /// Transfer all the pending items in queue.
+    /// The next item is chosen after each transfer, so items with higher priority are always transferred first
+    pub(super) fn action_run_queue(&mut self) {
+        if self.queue.is_empty() {
+            self.log(LogLevel::Warn, "Transfer queue is empty");
+            return;
+        }
+        self.transfer.queue_aborted = false;
+        // Whether to create missing remote directories may be chosen for the whole run
+        self.transfer.queue_running = true;
+        self.transfer.create_dirs = None;
+        let batch: Vec<usize> = self.queue.pending();
+        self.record_queue(false);
+        // Apply the selected profile for this run only
+        let default_opts: Option<TransferOptions> = match self.transfer_profile.clone() {
+            Some(name) => match self
+                .context
+                .as_ref()
+                .unwrap()
+                .config_client
+                .as_ref()
+                .and_then(|x| x.get_profile_transfer_options(name.as_str()))
+            {
+                Some(opts) => {
+                    self.log(
+                        LogLevel::Info,
+                        format!("Running transfer queue with profile \"{}\"", name).as_str(),
+                    );
+                    Some(std::mem::replace(&mut self.transfer_opts, opts))
+                }
+                None => {
+                    self.log(
+                        LogLevel::Warn,
+                        format!("Transfer profile \"{}\" doesn't exist anymore", name).as_str(),
+                    );
+                    self.transfer_profile = None;
+                    None
+                }
+            },
+            None => None,
+        };
+        // Keep the system awake until the queue has been processed; the lock is released on drop
+        let _wake_lock: Option<WakeLock> = match self.transfer_opts.inhibit_sleep {
+            true => match WakeLock::acquire("Transferring files") {
+                Ok(lock) => Some(lock),
+                Err(err) => {
+                    self.log(
+                        LogLevel::Warn,
+                        format!("Could not inhibit system sleep: {}", err).as_str(),
+                    );
+                    None
+                }
+            },
+            false => None,
+        };
+        // Distribute files across concurrent sessions, if possible
+        if let (workers, Some(connect)) = (self.transfer_workers(), self.make_connector()) {
+            if workers > 1 {
+                self.filetransfer_parallel(workers, connect);
+            }
+        }
+        while let Some(id) = self.queue.next_pending() {
+            if self.transfer.queue_aborted {
+                break;
+            }
+            let item: QueueItem = self.queue.get(id).unwrap().clone();
+            let result: Result<(), String> = match item.direction {
+                TransferDirection::Upload => {
+                    self.filetransfer_send(&item.entry, item.dest.as_path(), None)
+                }
+                TransferDirection::Download => {
+                    self.filetransfer_recv(&item.entry, item.dest.as_path(), None)
+                }
+            };
+            self.complete_queue_item(id, result);
+            if self.transfer.queue_aborted {
+                break;
+            }
+        }
+        // Sources removed after being transferred must disappear from the explorers
+        if batch
+            .iter()
+            .filter_map(|x| self.queue.get(*x))
+            .any(|x| x.remove_source && x.state == QueueItemState::Done)
+        {
+            let wrkdir: PathBuf = self.local.wrkdir.clone();
+            self.local_scan(wrkdir.as_path());
+            self.reload_remote_dir();
+        }
+        if self.transfer.queue_aborted {
+            let done: usize = batch
+                .iter()
+                .filter_map(|x| self.queue.get(*x))
+                .filter(|x| x.state == QueueItemState::Done)
+                .count();
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "Transfer queue aborted ({} of {} items completed)",
+                    done,
+                    batch.len()
+                )
+                .as_str(),
+            );
+        }
+        self.notify_webhook(batch.as_slice(), self.transfer.queue_aborted);
+        self.record_queue(true);
+        self.transfer.queue_aborted = false;
+        self.transfer.queue_running = false;
+        self.transfer.create_dirs = None;
+        if let Some(opts) = default_opts {
+            self.transfer_opts = opts;
+        }
+    }
+
+    /// ### complete_queue_item
+    ///
+    /// Set the state of queue item `id` from the result of its transfer.
+    /// Once transferred, the source of the items marked with `remove_source` is removed
+    pub(super) fn complete_queue_item(&mut self, id: usize, result: Result<(), String>) {
+        let result: Result<(), String> = match (result, self.queue.get(id).cloned()) {
+            (Ok(_), Some(item)) if item.remove_source => {
+                self.remove_source(item.direction, &item.entry, item.dest.as_path())
+            }
+            (result, _) => result,
+        };
+        self.queue.set_state(
+            id,
+            match result {
+                Ok(_) => QueueItemState::Done,
+                Err(err) => QueueItemState::Failed(err),
+            },
+        );
+        self.record_queue(false);
+    }
+
+    /// ### complete_move
+    ///
+    /// Remove the source `entry` of a move, given the `result` of its transfer in `direction` to `dest`.
+    /// The source is kept unless all of its files have been transferred: skipped files
+    /// (e.g. because of a conflict or the transfer filters) would be lost otherwise
+    fn complete_move(
+        &mut self,
+        direction: TransferDirection,
+        entry: &FsEntry,
+        dest: &Path,
+        result: Result<(), String>,
+    ) {
+        let path: PathBuf = entry.get_abs_path();
+        if let Err(err) = result {
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "Kept \"{}\", since it couldn't be moved: {}",
+                    path.display(),
+                    err
+                )
+                .as_str(),
+            );
+            return;
+        }
+        let transferred: usize = self.transfer.done_files;
+        match self.count_source_files(direction, &entry.get_realfile()) {
+            Some(files) if files == transferred => {
+                if let Err(err) = self.remove_source(direction, entry, dest) {
+                    self.log_and_alert(
+                        LogLevel::Error,
+                        format!("Could not move \"{}\": {}", path.display(), err),
+                    );
+                }
+            }
+            Some(files) => self.log_and_alert(
+                LogLevel::Warn,
+                format!(
+                    "Kept \"{}\": only {} of its {} files have been transferred",
+                    path.display(),
+                    transferred,
+                    files
+                ),
+            ),
+            None => self.log_and_alert(
+                LogLevel::Warn,
+                format!(
+                    "Kept \"{}\": could not tell whether all of its files have been transferred",
+                    path.display()
+                ),
+            ),
+        }
+        // Reload both explorers
+        let wrkdir: PathBuf = self.local.wrkdir.clone();
+        self.local_scan(wrkdir.as_path());
+        self.reload_remote_dir();
+    }
+
+    /// ### count_source_files
+    ///
+    /// Count all the files in `entry`, the source of a transfer in `direction`,
+    /// including the ones excluded from transfers. Returns None if a directory couldn't be scanned
+    fn count_source_files(
+        &mut self,
+        direction: TransferDirection,
+        entry: &FsEntry,
+    ) -> Option<usize> {
+        match entry {
+            FsEntry::File(_) => Some(1),
+            FsEntry::Directory(dir) => {
+                let entries: Vec<FsEntry> = match direction {
+                    TransferDirection::Upload => self
+                        .context
+                        .as_ref()
+                        .unwrap()
+                        .local
+                        .scan_dir(dir.abs_path.as_path())
+                        .ok()?,
+                    TransferDirection::Download => self
+                        .with_reconnect(|client| client.list_dir(dir.abs_path.as_path()))
+                        .ok()?,
+                };
+                let mut files: usize = 0;
+                for entry in entries.iter() {
+                    files += self.count_source_files(direction, entry)?;
+                }
+                Some(files)
+            }
+        }
+    }
+
+    /// ### remove_source
+    ///
+    /// Remove the source `entry` once it has been transferred in `direction` to `dest`
+    fn remove_source(
+        &mut self,
+        direction: TransferDirection,
+        entry: &FsEntry,
+        dest: &Path,
+    ) -> Result<(), String> {
+        let path: PathBuf = entry.get_abs_path();
+        let result: Result<(), String> = match direction {
+            TransferDirection::Upload => self
+                .context
+                .as_mut()
+                .unwrap()
+                .local
+                .remove(entry)
+                .map_err(|e| e.to_string()),
+            TransferDirection::Download => self.remote_remove(entry).map_err(|e| e.to_string()),
+        };
+        match result {
+            Ok(_) => {
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Removed \"{}\" after transferring it to \"{}\"",
+                        path.display(),
+                        dest.display()
+                    )
+                    .as_str(),
+                );
+                Ok(())
+            }
+            Err(err) => Err(format!("transferred, but could not remove source: {}", err)),
+        }
+    }
+
+    /// ### action_queue_next_profile
+    ///
+    /// Select the next transfer profile to run the queue with; after the last one, go back to the default options
+    pub(super) fn action_queue_next_profile(&mut self) {
+        let profiles: Vec<String> = match self.context.as_ref().unwrap().config_client.as_ref() {
+            Some(cli) => cli
+                .get_transfer_profiles()
+                .into_iter()
+                .map(|x| x.to_string())
+                .collect(),
+            None => Vec::new(),
+        };
+        let next: usize = match self.transfer_profile.as_ref() {
+            Some(name) => profiles
+                .iter()
+                .position(|x| x == name)
+                .map(|x| x + 1)
+                .unwrap_or(0),
+            None => 0,
+        };
+        self.transfer_profile = profiles.get(next).cloned();
+    }
+
+    /// ### notify_webhook
+    ///
+    /// Post the summary of the transfers of `batch` which have been processed to the webhook, if configured.
+    /// The request is sent in background
+    fn notify_webhook(&mut self, batch: &[usize], aborted: bool) {
+        let (webhook, network): (Webhook, NetworkOptions) =
+            match self.context.as_ref().unwrap().config_client.as_ref() {
+                Some(cli) => match cli.get_webhook() {
+                    Some(webhook) => (webhook, cli.get_network_options()),
+                    None => return,
+                },
+                None => return,
+            };
+        let host: String = self.get_remote_address();
+        let transfers: Vec<TransferSummary> = batch
+            .iter()
+            .filter_map(|x| self.queue.get(*x))
+            .filter_map(|x| {
+                let error: Option<String> = match &x.state {
+                    QueueItemState::Pending => return None,
+                    QueueItemState::Done => None,
+                    QueueItemState::Failed(err) => Some(err.clone()),
+                };
+                Some(TransferSummary {
+                    host: host.clone(),
+                    source: x.entry.get_abs_path(),
+                    destination: x.dest.clone(),
+                    direction: x.direction,
+                    error,
+                })
+            })
+            .collect();
+        if transfers.is_empty() {
+            return;
+        }
+        let summary: BatchSummary = BatchSummary::new(transfers, aborted);
+        let id = self
+            .context
+            .as_mut()
+            .unwrap()
+            .tasks
+            .spawn("Notifying webhook", move |_| {
+                webhook
+                    .notify(&summary, &network)
+                    .map(|_| Box::new(()) as TaskOutput)
+            });
+        self.webhook_tasks.push(id);
+    }
+
+    /// ### action_queue_set_priority
+    ///
+    /// Set priority for the selected item in the transfer queue
+    pub(super) fn action_queue_set_priority(&mut self, priority: Priority) {
+        if let Some(id) = self.get_queue_item_id() {
+            self.queue.set_priority(id, priority);
+        }
+    }
+
+    /// ### action_queue_retry
+    ///
+    /// Reschedule the selected item in the transfer queue, if it has failed
+    pub(super) fn action_queue_retry(&mut self) {
+        if let Some(id) = self.get_queue_item_id() {
+            if !self.queue.retry(id) {
+                self.log(LogLevel::Warn, "Only failed transfers can be retried");
+            }
+        }
+    }
+
+    /// ### action_queue_remove
+    ///
+    /// Remove the selected item from the transfer queue
+    pub(super) fn action_queue_remove(&mut self) {
+        if let Some(id) = self.get_queue_item_id() {
+            let _ = self.queue.remove(id);
+        }
+    }
+
+    /// ### action_queue_plan
+    ///
+    /// Check whether the pending uploads in queue fit in the space left on the remote host.
+    /// Returns a report for each remote file system; the uploads to skip to fit are kept in `upload_plan`
+    pub(super) fn action_queue_plan(&mut self) -> Vec<String> {
+        self.upload_plan.clear();
+        let uploads: Vec<QueueItem> = self
+            .queue
+            .pending()
+            .into_iter()
+            .filter_map(|x| self.queue.get(x))
+            .filter(|x| x.direction == TransferDirection::Upload)
+            .cloned()
+            .collect();
+        if uploads.is_empty() {
+            return vec![String::from("There are no pending uploads in queue")];
+        }
+        let mut report: Vec<String> = Vec::new();
+        // Query space once per destination, then group uploads by remote file system
+        let mut spaces: BTreeMap<PathBuf, Option<DiskSpace>> = BTreeMap::new();
+        let mut groups: Vec<(DiskSpace, Vec<(QueueItem, u64)>)> = Vec::new();
+        for item in uploads.into_iter() {
+            if !spaces.contains_key(&item.dest) {
+                let space: Option<DiskSpace> = match self.client.disk_space(item.dest.as_path()) {
+                    Ok(space) => Some(space),
+                    Err(err) => {
+                        let msg: String = format!(
+                            "Could not get the space left in \"{}\": {}",
+                            item.dest.display(),
+                            err
+                        );
+                        self.log(LogLevel::Warn, msg.as_str());
+                        report.push(msg);
+                        None
+                    }
+                };
+                spaces.insert(item.dest.clone(), space);
+            }
+            if let Some(space) = spaces.get(&item.dest).cloned().flatten() {
+                let (_, size) = self.local_batch_size(&item.entry);
+                match groups
+                    .iter_mut()
+                    .find(|(x, _)| x.filesystem == space.filesystem)
+                {
+                    Some((_, items)) => items.push((item, size as u64)),
+                    None => groups.push((space, vec![(item, size as u64)])),
+                }
+            }
+        }
+        for (space, items) in groups.into_iter() {
+            let sizes: Vec<u64> = items.iter().map(|(_, x)| *x).collect();
+            let plan: UploadPlan = UploadPlan::new(sizes.as_slice(), space.headroom());
+            let quota: String = match space.quota {
+                Some(quota) => format!(" ({} left in quota)", ByteSize(quota)),
+                None => String::new(),
+            };
+            report.push(format!(
+                "{}: {} uploads, {} of {} available{}",
+                space.filesystem,
+                items.len(),
+                ByteSize(plan.total),
+                ByteSize(space.available),
+                quota
+            ));
+            match plan.fits() {
+                true => report.push(format!(
+                    "  Fits; {} left after uploading",
+                    ByteSize(plan.headroom - plan.total)
+                )),
+                false => {
+                    report.push(format!(
+                        "  Doesn't fit; {} missing. Skip to fit:",
+                        ByteSize(plan.total - plan.headroom)
+                    ));
+                    for idx in plan.skip.iter() {
+                        let (item, size) = &items[*idx];
+                        report.push(format!(
+                            "  - {} ({})",
+                            item.entry.get_abs_path().display(),
+                            ByteSize(*size)
+                        ));
+                        self.upload_plan.push(item.id);
+                    }
+                }
+            }
+        }
+        report
+    }
+
+    /// ### action_queue_preview
+    ///
+    /// Describe what running the pending transfers of the queue will do, without transferring anything.
+    /// Remote directories aren't walked, so their size is unknown
+    pub(super) fn action_queue_preview(&mut self) -> BatchPlan {
+        let items: Vec<QueueItem> = self
+            .queue
+            .pending()
+            .into_iter()
+            .filter_map(|x| self.queue.get(x))
+            .cloned()
+            .collect();
+        let mut plan: BatchPlan = BatchPlan::default();
+        for item in items.into_iter() {
+            let target: PathBuf = item.dest.join(item.entry.get_name());
+            let (size, exists): (Option<u64>, bool) = match item.direction {
+                TransferDirection::Upload => (
+                    Some(self.local_batch_size(&item.entry).1 as u64),
+                    self.client.stat(target.as_path()).is_ok(),
+                ),
+                TransferDirection::Download => (
+                    match &item.entry {
+                        FsEntry::File(file) => Some(file.size as u64),
+                        FsEntry::Directory(_) => None,
+                    },
+                    target.exists(),
+                ),
+            };
+            plan.items.push(PlanItem {
+                direction: item.direction,
+                source: item.entry.get_abs_path(),
+                target,
+                size,
+                exists,
+                remove_source: item.remove_source,
+            });
+        }
+        // Estimate the time from the last transfers with the host
+        plan.throughput = match self.history.as_ref().map(|x| x.load()) {
+            Some(Ok(records)) => average_throughput(records.as_slice(), self.history_host(), 20),
+            _ => None,
+        };
+        plan
+    }
+
+    /// ### action_queue_skip_planned
+    ///
+    /// Remove from queue the uploads the last upload plan suggested to skip
+    pub(super) fn action_queue_skip_planned(&mut self) {
+        let ids: Vec<usize> = std::mem::take(&mut self.upload_plan);
+        let removed: usize = ids.into_iter().filter_map(|x| self.queue.remove(x)).count();
+        if removed > 0 {
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Removed {} uploads from queue to fit in the remote space left",
+                    removed
+                )
+                .as_str(),
+            );
+        }
+    }
+
+    /// ### action_queue_export
+    ///
+    /// Export the transfers in queue which haven't been completed yet to the JSON file at `input`
+    /// (relative to the local working directory)
+    pub(super) fn action_queue_export(&mut self, input: String) {
+        let path: PathBuf = self.local.wrkdir.join(input);
+        let manifest: QueueManifest = self.queue.manifest();
+        let result: Result<(), String> = File::create(path.as_path())
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                QueueSerializer {}
+                    .serialize(Box::new(file), &manifest)
+                    .map_err(|e| e.to_string())
+            });
+        match result {
+            Ok(_) => self.log(
+                LogLevel::Info,
+                format!(
+                    "Exported {} transfers to \"{}\"",
+                    manifest.transfers.len(),
+                    path.display()
+                )
+                .as_str(),
+            ),
+            Err(err) => self.log_and_alert(
+                LogLevel::Error,
+                format!("Could not export queue to \"{}\": {}", path.display(), err),
+            ),
+        }
+    }
+
+    /// ### action_capability_report
+    ///
+    /// Write the anonymized capability report of the remote server to the file at `input`
+    /// (relative to the local working directory)
+    pub(super) fn action_capability_report(&mut self, input: String) {
+        let path: PathBuf = self.local.wrkdir.join(input);
+        let mut report: CapabilityReport = match self.context.as_ref().unwrap().ft_params.as_ref() {
+            Some(params) => {
+                let mut report: CapabilityReport =
+                    CapabilityReport::new(params.protocol.to_string().as_str());
+                report.redact(params.address.as_str());
+                if let Some(username) = params.username.as_ref() {
+                    report.redact(username);
+                }
+                report
+            }
+            None => CapabilityReport::new("Localhost"),
+        };
+        report.banner = self.server_banner.clone();
+        report.server = self.client.server_info();
+        let identity: ServerIdentity = self.client.identity();
+        // Probe remote shell; not all the protocols support exec
+        let mut probe = |cmd: &str| -> Option<String> {
+            match self.client.exec(cmd) {
+                Ok(output) if !output.trim().is_empty() => Some(output.trim().to_string()),
+                _ => None,
+            }
+        };
+        report.shell = probe("echo $SHELL");
+        report.os = identity.system.or_else(|| probe("uname -s"));
+        if let Some(config) = self.context.as_ref().unwrap().config_client.as_ref() {
+            let yes_no = |x: bool| String::from(if x { "yes" } else { "no" });
+            report.config = vec![
+                (
+                    String::from("Show hidden files"),
+                    yes_no(config.get_show_hidden_files()),
+                ),
+                (
+                    String::from("Group directories"),
+                    config
+                        .get_group_dirs()
+                        .map(|x| x.to_string())
+                        .unwrap_or_else(|| String::from("no")),
+                ),
+                (
+                    String::from("Color mode"),
+                    config.get_color_mode().to_string(),
+                ),
+                (
+                    String::from("Reduced motion"),
+                    yes_no(config.get_reduced_motion()),
+                ),
+                (
+                    String::from("Overwrite policy"),
+                    format!("{:?}", config.get_overwrite_policy()),
+                ),
+                (
+                    String::from("Preserve timestamps"),
+                    yes_no(config.get_preserve_timestamps()),
+                ),
+                (
+                    String::from("Preserve permissions"),
+                    yes_no(config.get_preserve_permissions()),
+                ),
+                (
+                    String::from("Atomic uploads"),
+                    yes_no(config.get_atomic_uploads()),
+                ),
+                (String::from("Symlinks"), config.get_symlinks().to_string()),
+                (
+                    String::from("Verify checksums"),
+                    yes_no(config.get_verify_checksums()),
+                ),
+                (
+                    String::from("Skip identical files"),
+                    yes_no(config.get_skip_identical()),
+                ),
+                (
+                    String::from("Create remote directories"),
+                    yes_no(config.get_create_remote_dirs()),
+                ),
+                (
+                    String::from("One file system"),
+                    yes_no(config.get_one_file_system()),
+                ),
+                (
+                    String::from("Inhibit sleep"),
+                    yes_no(config.get_inhibit_sleep()),
+                ),
+                (
+                    String::from("Compress uploads"),
+                    yes_no(config.get_compress_uploads()),
+                ),
+                (
+                    String::from("Preallocate downloads"),
+                    yes_no(config.get_preallocate()),
+                ),
+                (
+                    String::from("Durable writes"),
+                    yes_no(config.get_durable_writes()),
+                ),
+                (String::from("Use trash"), yes_no(config.get_use_trash())),
+                (
+                    String::from("Ignore rules"),
+                    config.get_ignore_rules().len().to_string(),
+                ),
+                (String::from("Proxy"), yes_no(config.get_proxy().is_some())),
+                (
+                    String::from("Connection timeout"),
+                    format!("{}s", config.get_connection_timeout()),
+                ),
+                (
+                    String::from("Keepalive interval"),
+                    format!("{}s", config.get_keepalive_interval()),
+                ),
+                (
+                    String::from("Max concurrency"),
+                    config.get_max_concurrency().to_string(),
+                ),
+                (
+                    String::from("Bandwidth limit"),
+                    format!("{} KiB/s", config.get_bandwidth_limit()),
+                ),
+                (
+                    String::from("Bandwidth schedule"),
+                    yes_no(!config.get_bandwidth_schedule().is_empty()),
+                ),
+            ];
+        }
+        // Paths may contain user names
+        report.redact(self.local.wrkdir.to_string_lossy());
+        report.redact(self.remote.wrkdir.to_string_lossy());
+        if let Some(root) = self.remote_root.as_ref() {
+            report.redact(root.to_string_lossy());
+        }
+        match std::fs::write(path.as_path(), report.render()) {
+            Ok(_) => self.log(
+                LogLevel::Info,
+                format!("Saved capability report to \"{}\"", path.display()).as_str(),
+            ),
+            Err(err) => self.log_and_alert(
+                LogLevel::Error,
+                format!(
+                    "Could not save capability report to \"{}\": {}",
+                    path.display(),
+                    err
+                ),
+            ),
+        }
+    }
+
+    /// ### action_queue_import
+    ///
+    /// Enqueue the transfers of the JSON file at `input` (relative to the local working directory).
+    /// Sources are looked up on the local host for uploads and on the remote host for downloads
+    pub(super) fn action_queue_import(&mut self, input: String) {
+        let path: PathBuf = self.local.wrkdir.join(input);
+        let manifest: QueueManifest = match File::open(path.as_path())
+            .map_err(|e| e.to_string())
+            .and_then(|file| {
+                QueueSerializer {}
+                    .deserialize(Box::new(file))
+                    .map_err(|e| e.to_string())
+            }) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                self.log_and_alert(
+                    LogLevel::Error,
+                    format!(
+                        "Could not import queue from \"{}\": {}",
+                        path.display(),
+                        err
+                    ),
+                );
+                return;
+            }
+        };
+        let imported: usize = self.enqueue_manifest(manifest);
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Imported {} transfers from \"{}\"",
+                imported,
+                path.display()
+            )
+            .as_str(),
+        );
+    }
+
+    /// ### enqueue_manifest
+    ///
+    /// Enqueue the transfers of `manifest`.
+    /// Sources are looked up on the local host for uploads and on the remote host for downloads.
+    /// Returns the amount of enqueued transfers
+    fn enqueue_manifest(&mut self, manifest: QueueManifest) -> usize {
+        let mut imported: usize = 0;
+        for item in manifest.transfers.into_iter() {
+            let entry: Result<FsEntry, String> = match item.direction {
+                TransferDirection::Upload => self
+                    .context
+                    .as_ref()
+                    .unwrap()
+                    .local
+                    .stat(item.source.as_path())
+                    .map_err(|e| e.to_string()),
+                TransferDirection::Download => self
+                    .client
+                    .stat(item.source.as_path())
+                    .map_err(|e| e.to_string()),
+            };
+            match entry {
+                Ok(entry) => {
+                    let id: usize =
+                        self.queue
+                            .push(entry, item.destination, item.direction, item.priority);
+                    self.queue.set_remove_source(id, item.remove_source);
+                    imported += 1;
+                }
+                Err(err) => self.log(
+                    LogLevel::Warn,
+                    format!("Skipped \"{}\": {}", item.source.display(), err).as_str(),
+                ),
+            }
+        }
+        imported
+    }
+
+    /// ### check_interrupted_queue
+    ///
+    /// Look for a transfer queue interrupted during a previous session with this host; this is done once.
+    /// If there's one, the partial files it has left in its destinations are looked for,
+    /// and the user is asked whether to resume the queue or to clean them up
+    pub(super) fn check_interrupted_queue(&mut self) {
+        if self.recovery_checked {
+            return;
+        }
+        self.recovery_checked = true;
+        let manifest: QueueManifest = match self
+            .journal
+            .as_ref()
+            .and_then(|x| x.get(self.history_host()))
+        {
+            Some(manifest) => manifest,
+            None => return,
+        };
+        let mut partials: Vec<(TransferDirection, FsEntry)> = Vec::new();
+        for item in manifest.transfers.iter() {
+            let name: String = item
+                .source
+                .file_name()
+                .map(|x| x.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let target: PathBuf = item.destination.join(name.as_str());
+            // Temporary file of an atomic upload and marked partial file of the entry itself
+            for path in [
+                item.destination.join(format!(".{}.part", name)),
+                PartialFilePolicy::marked(target.as_path()),
+            ] {
+                if let Some(entry) = self.stat_destination(item.direction, path.as_path()) {
+                    partials.push((item.direction, entry));
+                }
+            }
+            // Partial files of the directory entries
+            if let Some(true) = self
+                .stat_destination(item.direction, target.as_path())
+                .map(|x| x.is_dir() && !x.is_symlink())
+            {
+                let found: Vec<FsEntry> = self.find_partial_files(item.direction, target.as_path());
+                partials.extend(found.into_iter().map(|x| (item.direction, x)));
+            }
+        }
+        // Items may share their destinations
+        partials.sort_by_key(|(direction, x)| {
+            (*direction == TransferDirection::Upload, x.get_abs_path())
+        });
+        partials.dedup_by(|a, b| a.0 == b.0 && a.1.get_abs_path() == b.1.get_abs_path());
+        self.log(
+            LogLevel::Warn,
+            format!(
+                "A transfer queue has been interrupted: {} transfers left, {} partial files found",
+                manifest.transfers.len(),
+                partials.len()
+            )
+            .as_str(),
+        );
+        self.mount_radio_recovery(manifest.transfers.len(), partials.len());
+        self.recovery = Some(QueueRecovery { manifest, partials });
+    }
+
+    /// ### action_resume_queue
+    ///
+    /// Enqueue again the transfers left by the interrupted queue; its partial files are removed,
+    /// since the transfers start over
+    pub(super) fn action_resume_queue(&mut self) {
+        if let Some(recovery) = self.recovery.take() {
+            self.remove_partial_files(recovery.partials.as_slice());
+            let resumed: usize = self.enqueue_manifest(recovery.manifest);
+            self.record_queue(true);
+            self.log(
+                LogLevel::Info,
+                format!("Resumed {} transfers of the interrupted queue", resumed).as_str(),
+            );
+        }
+    }
+
+    /// ### action_cleanup_queue
+    ///
+    /// Remove the partial files left by the interrupted queue and forget it
+    pub(super) fn action_cleanup_queue(&mut self) {
+        if let Some(recovery) = self.recovery.take() {
+            let removed: usize = self.remove_partial_files(recovery.partials.as_slice());
+            self.record_queue(true);
+            self.log(
+                LogLevel::Info,
+                format!(
+                    "Cleaned up the interrupted queue: removed {} partial files",
+                    removed
+                )
+                .as_str(),
+            );
+        }
+    }
+
+    /// ### stat_destination
+    ///
+    /// Stat `path` where the transfers in `direction` are written: on the remote host for uploads,
+    /// on the local host for downloads
+    fn stat_destination(&mut self, direction: TransferDirection, path: &Path) -> Option<FsEntry> {
+        match direction {
+            TransferDirection::Upload => self.client.stat(path).ok(),
+            TransferDirection::Download => self.context.as_ref().unwrap().local.stat(path).ok(),
+        }
+    }
+
+    /// ### find_partial_files
+    ///
+    /// Find recursively the partial files in `dir`, where the transfers in `direction` are written.
+    /// Symlinks to directories aren't walked
+    fn find_partial_files(&mut self, direction: TransferDirection, dir: &Path) -> Vec<FsEntry> {
+        let entries: Vec<FsEntry> = match direction {
+            TransferDirection::Upload => self.client.list_dir(dir).unwrap_or_default(),
+            TransferDirection::Download => self
+                .context
+                .as_ref()
+                .unwrap()
+                .local
+                .scan_dir(dir)
+                .unwrap_or_default(),
+        };
+        let mut partials: Vec<FsEntry> = Vec::new();
+        for entry in entries.into_iter() {
+            if entry.is_dir() && !entry.is_symlink() {
+                let path: PathBuf = entry.get_abs_path();
+                partials.extend(self.find_partial_files(direction, path.as_path()));
+            } else if entry.is_file() && PartialFilePolicy::is_partial(entry.get_name()) {
+                partials.push(entry);
+            }
+        }
+        partials
+    }
+
+    /// ### remove_partial_files
+    ///
+    /// Remove the partial files left by an interrupted queue; returns the amount of files removed
+    fn remove_partial_files(&mut self, partials: &[(TransferDirection, FsEntry)]) -> usize {
+        let mut removed: usize = 0;
+        for (direction, entry) in partials.iter() {
+            let result: Result<(), String> = match direction {
+                TransferDirection::Upload => self.remote_remove(entry).map_err(|e| e.to_string()),
+                TransferDirection::Download => self
+                    .context
+                    .as_mut()
+                    .unwrap()
+                    .local
+                    .remove(entry)
+                    .map_err(|e| e.to_string()),
+            };
+            match result {
+                Ok(_) => removed += 1,
+                Err(err) => self.log(
+                    LogLevel::Warn,
+                    format!(
+                        "Could not remove partial file \"{}\": {}",
+                        entry.get_abs_path().display(),
+                        err
+                    )
+                    .as_str(),
+                ),
+            }
+        }
+        // Removed files must disappear from the explorers
+        if removed > 0 {
+            let wrkdir: PathBuf = self.local.wrkdir.clone();
+            self.local_scan(wrkdir.as_path());
+            self.reload_remote_dir();
+        }
+        removed
+    }
+
+    /// ### action_schedule_queue
+    ///
+    /// Schedule the transfers in queue which haven't been completed yet to run every `input` minutes
+    pub(super) fn action_schedule_queue(&mut self, input: String) {
+        let interval: u64 = match input.trim().parse::<u64>() {
+            Ok(interval) if interval > 0 => interval,
+            _ => {
+                self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Invalid interval \"{}\": expected minutes", input),
+                );
+                return;
+            }
+        };
+        let manifest: QueueManifest = self.queue.manifest();
+        if manifest.transfers.is_empty() {
+            self.log(LogLevel::Warn, "There are no transfers to schedule");
+            return;
+        }
+        // Nobody would be there to type the confirmation phrase
+        if self.protected_phrase.is_some()
+            && manifest
+                .transfers
+                .iter()
+                .any(|x| x.remove_source && x.direction == TransferDirection::Download)
+        {
+            self.log_and_alert(
+                LogLevel::Error,
+                String::from("Moves from a protected host can't be scheduled"),
+            );
+            return;
+        }
+        let first: String = manifest.transfers[0].source.display().to_string();
+        let name: String = match manifest.transfers.len() {
+            1 => first,
+            n => format!("{} (+{} more)", first, n - 1),
+        };
+        let id: usize = self
+            .jobs
+            .add(name.clone(), manifest, interval, Local::now());
+        let next_run: String = self
+            .jobs
+            .get(id)
+            .map(|x| x.next_run.format("%H:%M").to_string())
+            .unwrap_or_default();
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Scheduled \"{}\" every {} minutes; next run at {}",
+                name, interval, next_run
+            )
+            .as_str(),
+        );
+    }
+
+    /// ### action_run_scheduled_jobs
+    ///
+    /// Run the scheduled job which is due, if any.
+    /// Jobs wait while the user is busy with a popup.
+    /// Returns whether a job has been run
+    pub(super) fn action_run_scheduled_jobs(&mut self) -> bool {
+        if !self.client.is_connected() {
+            return false;
+        }
+        let idle: bool = matches!(
+            self.view.focus(),
+            Some(super::COMPONENT_EXPLORER_LOCAL)
+                | Some(super::COMPONENT_EXPLORER_REMOTE)
+                | Some(super::COMPONENT_LOG_BOX)
+        );
+        if !idle {
+            return false;
+        }
+        match self.jobs.due(Local::now()) {
+            Some(id) => {
+                self.action_run_job(id);
+                let _ = self.update_local_filelist();
+                let _ = self.update_remote_filelist();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// ### action_run_job
+    ///
+    /// Run the scheduled job `id` now.
+    /// The job runs on its own queue, so the transfers enqueued by the user are left untouched
+    pub(super) fn action_run_job(&mut self, id: usize) {
+        let (name, manifest): (String, QueueManifest) = match self.jobs.get(id) {
+            Some(job) => (job.name.clone(), job.manifest.clone()),
+            None => return,
+        };
+        self.log(
+            LogLevel::Info,
+            format!("Running scheduled job \"{}\"", name).as_str(),
+        );
+        let total: usize = manifest.transfers.len();
+        let user_queue: TransferQueue = std::mem::take(&mut self.queue);
+        if self.enqueue_manifest(manifest) > 0 {
+            self.action_run_queue();
+        }
+        let transferred: usize = self
+            .queue
+            .iter()
+            .filter(|x| x.state == QueueItemState::Done)
+            .count();
+        self.queue = user_queue;
+        self.jobs.complete(
+            id,
+            JobRun {
+                at: Local::now(),
+                transferred,
+                failed: total - transferred,
+            },
+        );
+        let next_run: String = self
+            .jobs
+            .get(id)
+            .map(|x| x.next_run.format("%H:%M").to_string())
+            .unwrap_or_default();
+        self.log(
+            match transferred == total {
+                true => LogLevel::Info,
+                false => LogLevel::Warn,
+            },
+            format!(
+                "Scheduled job \"{}\" transferred {} of {} items; next run at {}",
+                name, transferred, total, next_run
+            )
+            .as_str(),
+        );
+    }
+
+    /// ### action_remove_job
+    ///
+    /// Remove the selected job from the scheduler
+    pub(super) fn action_remove_job(&mut self) {
+        if let Some(id) = self.get_job_id() {
+            if let Some(job) = self.jobs.remove(id) {
+                self.log(
+                    LogLevel::Info,
+                    format!("Unscheduled job \"{}\"", job.name).as_str(),
+                );
+            }
+        }
+    }
+
+    /// ### action_open_archive
+    ///
+    /// Open the archive `entry` of the current explorer and browse it as a directory.
+    /// Remote archives are downloaded to a temporary directory first
+    pub(super) fn action_open_archive(&mut self, entry: &FsEntry) {
+        let (tmpdir, path): (Option<TempDir>, PathBuf) = match self.tab {
+            FileExplorerTab::Local => (None, entry.get_abs_path()),
+            FileExplorerTab::Remote => {
+                let tmpdir: TempDir = match self.staging().tempdir() {
+                    Ok(d) => d,
+                    Err(err) => {
+                        self.log_and_alert(
+                            LogLevel::Error,
+                            format!("Could not create temporary directory: {}", err),
+                        );
+                        return;
+                    }
+                };
+                if self.filetransfer_recv(entry, tmpdir.path(), None).is_err() {
+                    return;
+                }
+                let path: PathBuf = tmpdir.path().join(entry.get_name());
+                (Some(tmpdir), path)
+            }
+            _ => return,
+        };
+        match Archive::open(path.as_path()) {
+            Ok(archive) => {
+                let explorer =
+                    Self::build_explorer(self.context.as_ref().unwrap().config_client.as_ref());
+                let remote: bool = matches!(self.tab, FileExplorerTab::Remote);
+                self.archive = Some(ArchiveBrowser::new(archive, explorer, remote, tmpdir));
+                self.mount_archive(entry.get_name());
+            }
+            Err(err) => self.log_and_alert(
+                LogLevel::Error,
+                format!(
+                    "Could not open archive \"{}\": {}",
+                    entry.get_abs_path().display(),
+                    err
+                ),
+            ),
+        }
+    }
+
+    /// ### action_archive_changedir
+    ///
+    /// Change directory inside the opened archive
+    pub(super) fn action_archive_changedir(&mut self, dir: &Path) {
+        if let Some(browser) = self.archive.as_mut() {
+            browser.changedir(dir);
+        }
+    }
+
+    /// ### action_archive_extract
+    ///
+    /// Extract the selected entry of the opened archive to the working directory of the other explorer.
+    /// Entries of local archives are extracted to a temporary directory and then uploaded
+    pub(super) fn action_archive_extract(&mut self) {
+        let (path, remote): (PathBuf, bool) = match self.get_archive_entry() {
+            Some(entry) => (entry.get_abs_path(), self.archive.as_ref().unwrap().remote),
+            None => return,
+        };
+        if remote {
+            let wrkdir: PathBuf = self.local.wrkdir.clone();
+            match self
+                .archive
+                .as_ref()
+                .unwrap()
+                .archive
+                .extract(path.as_path(), wrkdir.as_path())
+            {
+                Ok(extracted) => self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Extracted \"{}\" to \"{}\"",
+                        path.display(),
+                        extracted.display()
+                    )
+                    .as_str(),
+                ),
+                Err(err) => self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not extract \"{}\": {}", path.display(), err),
+                ),
+            }
+        } else {
+            let tmpdir: TempDir = match self.staging().tempdir() {
+                Ok(d) => d,
+                Err(err) => {
+                    self.log_and_alert(
+                        LogLevel::Error,
+                        format!("Could not create temporary directory: {}", err),
+                    );
+                    return;
+                }
+            };
+            let extracted: Result<FsEntry, String> = self
+                .archive
+                .as_ref()
+                .unwrap()
+                .archive
+                .extract(path.as_path(), tmpdir.path())
+                .map_err(|e| e.to_string())
+                .and_then(|p| {
+                    self.context
+                        .as_ref()
+                        .unwrap()
+                        .local
+                        .stat(p.as_path())
+                        .map_err(|e| e.to_string())
+                });
+            match extracted {
+                Ok(entry) => {
+                    let wrkdir: PathBuf = self.remote.wrkdir.clone();
+                    let _ = self.filetransfer_send(&entry, wrkdir.as_path(), None);
+                }
+                Err(err) => self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not extract \"{}\": {}", path.display(), err),
+                ),
+            }
+        }
+    }
+
+    /// ### action_archive_add
+    ///
+    /// Add the local file at `input` (relative to the local working directory) to the current directory
+    /// of the opened archive. Only local archives can be modified
+    pub(super) fn action_archive_add(&mut self, input: String) {
+        let src: PathBuf = match PathBuf::from(input.as_str()) {
+            p if p.is_relative() => self.local.wrkdir.join(p),
+            p => p,
+        };
+        if let Some(browser) = self.archive.as_mut() {
+            let dir: PathBuf = browser.explorer.wrkdir.clone();
+            let result = browser.archive.append(src.as_path(), dir.as_path());
+            browser.reload();
+            match result {
+                Ok(_) => self.log(
+                    LogLevel::Info,
+                    format!("Added \"{}\" to archive", src.display()).as_str(),
+                ),
+                Err(err) => self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not add \"{}\" to archive: {}", src.display(), err),
+                ),
+            }
+        }
+    }
+
+    /// ### get_archive_entry
+    ///
+    /// Get selected entry in the opened archive
+    pub(super) fn get_archive_entry(&self) -> Option<&FsEntry> {
+        match self.view.get_value(super::COMPONENT_EXPLORER_ARCHIVE) {
+            Some(Payload::Unsigned(idx)) => self.archive.as_ref()?.explorer.get(idx),
+            _ => None,
+        }
+    }
+
+    /// ### action_local_goto_link_target
+    ///
+    /// Change local directory to the directory of the target of the selected symlink
+    pub(super) fn action_local_goto_link_target(&mut self) {
+        match Self::get_link_target(self.get_local_file_entry()) {
+            Some(target) => self.local_changedir(target.as_path(), true),
+            None => self.log(LogLevel::Warn, "Selected entry is not a symbolic link"),
+        }
+    }
+
+    /// ### action_remote_goto_link_target
+    ///
+    /// Change remote directory to the directory of the target of the selected symlink
+    pub(super) fn action_remote_goto_link_target(&mut self) {
+        match Self::get_link_target(self.get_remote_file_entry()) {
+            Some(target) => self.remote_changedir(target.as_path(), true),
+            None => self.log(LogLevel::Warn, "Selected entry is not a symbolic link"),
+        }
+    }
+
+    /// ### get_link_target
+    ///
+    /// Get the directory containing the target of `entry`, if `entry` is a symlink
+    fn get_link_target(entry: Option<&FsEntry>) -> Option<PathBuf> {
+        entry
+            .and_then(|x| x.get_symlink_target())
+            .and_then(|x| x.parent().map(PathBuf::from))
+    }
+
     /// ### get_local_file_entry
     ///
     /// Get local file entry
@@ -616,6 +2795,47 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### get_placeholders
+    ///
+    /// Get the values of the command placeholders from the current explorer
+    pub(super) fn get_placeholders(&self) -> Placeholders {
+        let (wrkdir, entry): (&PathBuf, Option<&FsEntry>) = match self.tab {
+            FileExplorerTab::Remote => (&self.remote.wrkdir, self.get_remote_file_entry()),
+            _ => (&self.local.wrkdir, self.get_local_file_entry()),
+        };
+        Placeholders {
+            file: entry.map(|x| x.get_abs_path().display().to_string()),
+            dir: wrkdir.display().to_string(),
+            selection: entry.map(|x| x.get_name().to_string()),
+            host: self.get_remote_address(),
+        }
+    }
+
+    /// ### is_recursive_remote_delete
+    ///
+    /// Returns whether deleting the selection removes a remote directory or several remote entries at once
+    pub(super) fn is_recursive_remote_delete(&self) -> bool {
+        match self.tab {
+            FileExplorerTab::Remote => self
+                .get_remote_file_entry()
+                .map(|x| x.is_dir())
+                .unwrap_or(false),
+            FileExplorerTab::FindRemote if !self.found_selection.marked.is_empty() => true,
+            FileExplorerTab::FindRemote => {
+                match self.view.get_value(super::COMPONENT_EXPLORER_FIND) {
+                    Some(Payload::Unsigned(idx)) => self
+                        .found
+                        .as_ref()
+                        .and_then(|x| x.get(idx))
+                        .map(|x| x.is_dir())
+                        .unwrap_or(false),
+                    _ => false,
+                }
+            }
+            _ => false,
+        }
+    }
+
     // -- private
 
     /// ### get_local_file_idx
@@ -637,4 +2857,53 @@ impl FileTransferActivity {
             _ => None,
         }
     }
+
+    /// ### get_queue_item_id
+    ///
+    /// Get id of the selected item in the transfer queue
+    fn get_queue_item_id(&self) -> Option<usize> {
+        match self.view.get_value(super::COMPONENT_LIST_QUEUE) {
+            Some(Payload::Unsigned(idx)) => self.queue.iter().nth(idx).map(|x| x.id),
+            _ => None,
+        }
+    }
+
+    /// ### action_new_tab
+    ///
+    /// Connect to another host in a new tab; this session is kept open in its own tab.
+    /// Not available in demo and local-to-local mode
+    pub(super) fn action_new_tab(&mut self) {
+        if !self.context.as_ref().unwrap().tabs.can_open {
+            self.log_and_alert(
+                LogLevel::Warn,
+                String::from("Other sessions can't be opened in this mode"),
+            );
+            return;
+        }
+        self.exit_reason = Some(super::ExitReason::NewTab);
+    }
+
+    /// ### action_switch_tab
+    ///
+    /// Switch to the session tab `offset` positions after this one (before, if negative); tabs wrap around
+    pub(super) fn action_switch_tab(&mut self, offset: isize) {
+        if self.context.as_ref().unwrap().tabs.labels.len() < 2 {
+            self.log(
+                LogLevel::Warn,
+                "There are no other sessions open; connect to another host with <CTRL+T>",
+            );
+            return;
+        }
+        self.exit_reason = Some(super::ExitReason::SwitchTab(offset));
+    }
+
+    /// ### get_job_id
+    ///
+    /// Get id of the selected job in the jobs panel
+    pub(super) fn get_job_id(&self) -> Option<usize> {
+        match self.view.get_value(super::COMPONENT_LIST_JOBS) {
+            Some(Payload::Unsigned(idx)) => self.jobs.iter().nth(idx).map(|x| x.id),
+            _ => None,
+        }
+    }
 }
@@ -22,19 +22,47 @@
  * SOFTWARE.
  */
 // Locals
-use super::{ConfigClient, FileTransferActivity, LogLevel, LogRecord};
+use super::{
+    BookmarksClient, ConfigClient, FileTransferActivity, LogLevel, LogRecord, ProtectedOp,
+    LOG_REFRESH_INTERVAL,
+};
+use crate::bookmarks::TagColor;
+use crate::filetransfer::{FileTransferProtocol, NetworkOptions};
 use crate::fs::explorer::{builder::FileExplorerBuilder, FileExplorer, FileSorting, GroupDirs};
+use crate::fs::queue::{QueueManifest, TransferDirection};
+use crate::fs::transfer::{TimeWindow, TransferFilters};
 use crate::system::environment;
+use crate::system::hash_cache::{self, HashCache};
+use crate::system::queue_journal::QueueJournal;
 use crate::system::sshkey_storage::SshKeyStorage;
+use crate::system::staging::Staging;
+use crate::system::transfer_history::{HistoryRecord, TransferHistory, TransferResult};
+use crate::ui::bus::{Notification, ToastLevel};
+use crate::ui::input::InputHandler;
 // Ext
+use chrono::Local;
+use crossterm::event::Event as InputEvent;
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tui::style::Color;
 
 impl FileTransferActivity {
     /// ### log
     ///
-    /// Add message to log events
+    /// Add message to log events.
+    /// Messages repeating the last record are collapsed into it, and the log box is refreshed
+    /// at most once every `LOG_REFRESH_INTERVAL` while they keep coming
     pub(super) fn log(&mut self, level: LogLevel, msg: &str) {
+        if let Some(last) = self.log_records.front_mut() {
+            if last.repeat(&level, msg) {
+                self.log_stale = true;
+                if self.log_refreshed.elapsed() >= LOG_REFRESH_INTERVAL {
+                    self.refresh_logbox();
+                }
+                return;
+            }
+        }
         // Create log record
         let record: LogRecord = LogRecord::new(level, msg);
         //Check if history overflows the size
@@ -43,11 +71,68 @@ impl FileTransferActivity {
         }
         // Eventually push front the new record
         self.log_records.push_front(record);
-        // Update log
+        self.refresh_logbox();
+    }
+
+    /// ### refresh_logbox
+    ///
+    /// Update log box with the current log records
+    pub(super) fn refresh_logbox(&mut self) {
+        self.log_refreshed = Instant::now();
+        self.log_stale = false;
         let msg = self.update_logbox();
         self.update(msg);
     }
 
+    /// ### poll_tasks
+    ///
+    /// Collect background tasks state and results. Returns whether the ui should be redrawn
+    pub(super) fn poll_tasks(&mut self) -> bool {
+        let ctx = self.context.as_mut().unwrap();
+        let changed: bool = ctx.tasks.poll();
+        if let Some((id, dir)) = self.disk_usage_task.take() {
+            match ctx.tasks.take_result(id) {
+                Some(Ok(size)) => {
+                    // Show size if file info is still open for the directory
+                    if let Ok(size) = size.downcast::<u64>() {
+                        if self
+                            .view
+                            .get_props(super::COMPONENT_LIST_FILEINFO)
+                            .is_some()
+                        {
+                            self.mount_file_info(&dir, Some(*size));
+                        }
+                    }
+                }
+                Some(Err(err)) => {
+                    let msg: String = format!(
+                        "Could not calculate size of \"{}\": {}",
+                        dir.get_abs_path().display(),
+                        err
+                    );
+                    ctx.bus
+                        .publish(Notification::Toast(ToastLevel::Error, msg.clone()));
+                    self.log(LogLevel::Error, msg.as_str());
+                }
+                None => self.disk_usage_task = Some((id, dir)),
+            }
+        }
+        for id in std::mem::take(&mut self.webhook_tasks).into_iter() {
+            match self.context.as_mut().unwrap().tasks.take_result(id) {
+                Some(Ok(_)) => self.log(LogLevel::Info, "Notified webhook"),
+                Some(Err(err)) => self.log(
+                    LogLevel::Warn,
+                    format!("Could not notify webhook: {}", err).as_str(),
+                ),
+                None => self.webhook_tasks.push(id),
+            }
+        }
+        if changed {
+            self.update_status_bar();
+        }
+        changed
+    }
+
     /// ### log_and_alert
     ///
     /// Add message to log events and also display it as an alert
@@ -81,6 +166,312 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### staging
+    ///
+    /// Get the staging directory temporary files are created in
+    pub(super) fn staging(&self) -> Staging {
+        let dir: Option<PathBuf> = self
+            .context
+            .as_ref()
+            .unwrap()
+            .config_client
+            .as_ref()
+            .and_then(|x| x.get_staging_dir());
+        Staging::new(dir.as_deref())
+    }
+
+    /// ### init_transfer_history
+    ///
+    /// Get the transfer history in the configuration directory, if available
+    pub(super) fn init_transfer_history() -> Option<TransferHistory> {
+        match environment::init_config_dir() {
+            Ok(Some(config_dir)) => Some(TransferHistory::new(
+                environment::get_transfer_history_path(config_dir.as_path()).as_path(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// ### init_queue_journal
+    ///
+    /// Open the journal of the transfer queues, stored in the configuration directory
+    pub(super) fn init_queue_journal() -> Option<QueueJournal> {
+        match environment::init_config_dir() {
+            Ok(Some(config_dir)) => Some(QueueJournal::new(
+                environment::get_queue_journal_path(config_dir.as_path()).as_path(),
+            )),
+            _ => None,
+        }
+    }
+
+    /// ### local_digest
+    ///
+    /// Get the SHA256 digest, as lowercase hex, of the local file at `path`.
+    /// Digests are cached in the configuration directory, if available, so that unchanged files aren't hashed again
+    pub(super) fn local_digest(&mut self, path: &Path) -> Result<String, String> {
+        if self.hash_cache.is_none() {
+            if let Ok(Some(config_dir)) = environment::init_config_dir() {
+                self.hash_cache = Some(HashCache::load(
+                    environment::get_hash_cache_path(config_dir.as_path()).as_path(),
+                ));
+            }
+        }
+        let digest: std::io::Result<String> = match self.hash_cache.as_mut() {
+            Some(cache) => cache.digest(path),
+            None => hash_cache::sha256_file(path),
+        };
+        digest.map_err(|e| format!("Could not hash \"{}\": {}", path.display(), e))
+    }
+
+    /// ### save_hash_cache
+    ///
+    /// Write the digests of the local files hashed during the session to the hash cache
+    pub(super) fn save_hash_cache(&mut self) {
+        if let Some(Err(err)) = self.hash_cache.as_mut().map(|x| x.save()) {
+            self.log(
+                LogLevel::Warn,
+                format!("Could not save hash cache: {}", err).as_str(),
+            );
+        }
+    }
+
+    /// ### record_queue
+    ///
+    /// Record the transfers left in queue in the queue journal, so that they can be recovered if termscp
+    /// is interrupted; once the queue is `done`, it's forgotten
+    pub(super) fn record_queue(&mut self, done: bool) {
+        let host: &str = self.history_host();
+        let result: Option<Result<(), String>> = match done {
+            true => self.journal.as_ref().map(|x| x.finish(host)),
+            false => {
+                let manifest: QueueManifest = self.queue.manifest();
+                self.journal.as_ref().map(|x| x.record(host, &manifest))
+            }
+        };
+        if let Some(Err(err)) = result {
+            self.log(
+                LogLevel::Warn,
+                format!("Could not save queue journal: {}", err).as_str(),
+            );
+        }
+    }
+
+    /// ### record_transfer
+    ///
+    /// Append the outcome of the transfer of a file to the transfer history.
+    /// A transfer which succeeded after being cancelled has been aborted
+    pub(super) fn record_transfer(
+        &mut self,
+        direction: TransferDirection,
+        (local, remote): (&Path, &Path),
+        size: usize,
+        duration: Duration,
+        result: &Result<(), String>,
+    ) {
+        let status: TransferResult = match result {
+            Err(err) => TransferResult::Failed(err.clone()),
+            Ok(_) if self.transfer.cancel.is_cancelled() => TransferResult::Aborted,
+            Ok(_) => TransferResult::Completed,
+        };
+        let record: HistoryRecord = HistoryRecord::new(
+            direction,
+            self.history_host(),
+            local,
+            remote,
+            size as u64,
+            duration,
+            status,
+        );
+        if let Some(Err(err)) = self.history.as_ref().map(|x| x.append(&record)) {
+            self.log(
+                LogLevel::Warn,
+                format!("Could not save transfer history: {}", err).as_str(),
+            );
+        }
+    }
+
+    /// ### get_remote_address
+    ///
+    /// Get the address of the remote host.
+    /// In local-to-local mode there are no connection params: the remote host is `localhost`
+    pub(super) fn get_remote_address(&self) -> String {
+        match self.context.as_ref().and_then(|x| x.ft_params.as_ref()) {
+            Some(params) => params.address.clone(),
+            None => String::from("localhost"),
+        }
+    }
+
+    /// ### get_remote_display_path
+    ///
+    /// Get remote path as it must be displayed: relative to the session root (which becomes `/`),
+    /// if set and relative paths are enabled
+    pub(super) fn get_remote_display_path(&self, p: &Path) -> PathBuf {
+        match (self.remote_root.as_ref(), self.relative_paths) {
+            (Some(root), true) => match p.strip_prefix(root) {
+                Ok(rel) => PathBuf::from("/").join(rel),
+                Err(_) => p.to_path_buf(),
+            },
+            _ => p.to_path_buf(),
+        }
+    }
+
+    /// ### init_host_data
+    ///
+    /// Load the history of the commands executed on the remote host, the transfer filters, the saved searches
+    /// and the tag and protection of its bookmark from the bookmarks file.
+    /// If the bookmarks file is not available, they're kept for this session only
+    pub(super) fn init_host_data(&mut self) {
+        let (host, address): (String, String) =
+            match self.context.as_ref().unwrap().ft_params.as_ref() {
+                Some(params) => (
+                    BookmarksClient::make_host_key(
+                        params.address.as_str(),
+                        params.port,
+                        params.protocol,
+                        params.username.as_deref().unwrap_or(""),
+                    ),
+                    params.address.clone(),
+                ),
+                None => return,
+            };
+        let client: Option<BookmarksClient> = match environment::init_config_dir() {
+            Ok(Some(config_dir)) => BookmarksClient::new(
+                environment::get_bookmarks_paths(config_dir.as_path()).as_path(),
+                config_dir.as_path(),
+                16,
+            )
+            .ok(),
+            _ => None,
+        };
+        if let Some(cli) = client.as_ref() {
+            self.cmd_history
+                .load(cli.iter_commands(host.as_str()).cloned().collect());
+            let patterns: Vec<String> = cli.iter_transfer_filters(host.as_str()).cloned().collect();
+            self.filters = TransferFilters::new(patterns.as_slice());
+            self.saved_searches = cli.iter_saved_searches(host.as_str()).cloned().collect();
+            self.host_tag = cli.get_host_tag(host.as_str());
+            // The address of the host must be typed to confirm destructive operations
+            self.protected_phrase = Some(address).filter(|_| cli.is_host_protected(host.as_str()));
+            self.quiet_hours = cli.get_host_quiet_hours(host.as_str());
+        }
+        self.host_data = client;
+        self.host_key = host;
+    }
+
+    /// ### require_confirm_phrase
+    ///
+    /// If the remote host is protected, keep `op` aside and ask for the confirmation phrase.
+    /// Returns whether `op` must wait for the phrase
+    pub(super) fn require_confirm_phrase(&mut self, op: ProtectedOp) -> bool {
+        match self.protected_phrase.clone() {
+            Some(phrase) => {
+                self.protected_op = Some(op);
+                self.mount_confirm_phrase(phrase.as_str());
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// ### check_quiet_hours
+    ///
+    /// Pause the transfer when the quiet hours of the host begin, and resume it once they're over.
+    /// If the user resumes the transfer, quiet hours are ignored until they end
+    pub(super) fn check_quiet_hours(&mut self) {
+        let window: TimeWindow = match self.quiet_hours {
+            Some(window) => window,
+            None => return,
+        };
+        let quiet: bool = window.contains(Local::now().time());
+        if !quiet {
+            self.transfer.quiet_skipped = false;
+        }
+        match (
+            quiet,
+            self.transfer.paused.is_some(),
+            self.transfer.quiet_pause,
+        ) {
+            (true, false, _) if !self.transfer.quiet_skipped => {
+                self.transfer.toggle_pause();
+                self.transfer.quiet_pause = true;
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Quiet hours ({}): transfers paused until {}",
+                        window,
+                        window.fmt_end()
+                    )
+                    .as_str(),
+                );
+            }
+            (false, true, true) => {
+                self.transfer.toggle_pause();
+                self.transfer.quiet_pause = false;
+                self.log(LogLevel::Info, "Quiet hours are over: transfers resumed");
+            }
+            _ => return,
+        }
+        let _ = self.update_status_bar();
+    }
+
+    /// ### history_host
+    ///
+    /// Get the host the transfers are recorded with in the transfer history
+    pub(super) fn history_host(&self) -> &str {
+        match self.host_key.is_empty() {
+            true => "localhost",
+            false => self.host_key.as_str(),
+        }
+    }
+
+    /// ### compresses_upload
+    ///
+    /// Returns whether the file named `name` must be compressed while being uploaded.
+    /// Compressed files are decompressed with `gzip` on the remote host, so only SSH protocols are supported
+    pub(super) fn compresses_upload(&self, name: &str) -> bool {
+        let ssh: bool = matches!(
+            self.context
+                .as_ref()
+                .unwrap()
+                .ft_params
+                .as_ref()
+                .map(|x| x.protocol),
+            Some(FileTransferProtocol::Sftp) | Some(FileTransferProtocol::Scp)
+        );
+        ssh && self.transfer_opts.compresses(name)
+    }
+
+    /// ### host_tag_color
+    ///
+    /// Get the color of the tag of the remote host, if any
+    pub(super) fn host_tag_color(&self) -> Option<Color> {
+        self.host_tag.as_ref().map(|tag| match tag.color {
+            TagColor::Red => Color::Red,
+            TagColor::Yellow => Color::Yellow,
+            TagColor::Green => Color::Green,
+            TagColor::Blue => Color::Blue,
+            TagColor::Magenta => Color::Magenta,
+            TagColor::Cyan => Color::Cyan,
+        })
+    }
+
+    /// ### write_host_data
+    ///
+    /// Update the data of the remote host with `update`, then write the bookmarks file.
+    /// Does nothing if the bookmarks file is not available
+    pub(super) fn write_host_data<F>(&mut self, update: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut BookmarksClient, &str),
+    {
+        match self.host_data.as_mut() {
+            Some(cli) => {
+                update(cli, self.host_key.as_str());
+                cli.write_bookmarks().map_err(|e| e.to_string())
+            }
+            None => Ok(()),
+        }
+    }
+
     /// ### make_ssh_storage
     ///
     /// Make ssh storage from `ConfigClient` if possible, empty otherwise
@@ -91,6 +482,39 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### transfer_workers
+    ///
+    /// Get the amount of concurrent sessions used to run the transfer queue.
+    /// Checksum verification and permission templates require the main session
+    pub(super) fn transfer_workers(&self) -> usize {
+        match self.context.as_ref().unwrap().config_client.as_ref() {
+            Some(cli)
+                if !self.transfer_opts.verify_checksums
+                    && !self.transfer_opts.applies_permissions() =>
+            {
+                cli.get_max_concurrency()
+            }
+            _ => 1,
+        }
+    }
+
+    /// ### make_network_options
+    ///
+    /// Make network options from `ConfigClient` if possible, defaults otherwise
+    pub(super) fn make_network_options(cli: Option<&ConfigClient>) -> NetworkOptions {
+        cli.map(|x| x.get_network_options()).unwrap_or_default()
+    }
+
+    /// ### known_hosts_path
+    ///
+    /// Get path of the known hosts file in the configuration directory, if available
+    pub(super) fn known_hosts_path() -> Option<PathBuf> {
+        match environment::init_config_dir() {
+            Ok(Some(termscp_dir)) => Some(environment::get_known_hosts_path(termscp_dir.as_path())),
+            _ => None,
+        }
+    }
+
     /// ### build_explorer
     ///
     /// Build explorer reading configuration from `ConfigClient`
@@ -124,6 +548,17 @@ impl FileTransferActivity {
             .build()
     }
 
+    /// ### progress_redraw_step
+    ///
+    /// Get the progress (in percentage) which must be made before redrawing the progress bar.
+    /// With reduced motion the progress bar is redrawn only every 10%
+    pub(super) fn progress_redraw_step(&self) -> f64 {
+        match self.context.as_ref().unwrap().reduced_motion() {
+            true => 10.0,
+            false => 1.0,
+        }
+    }
+
     /// ### setup_text_editor
     ///
     /// Set text editor to use
@@ -134,12 +569,36 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### on_resize
+    ///
+    /// Handle terminal resize: resize the terminal, then update the components which depend
+    /// on the terminal width and redraw immediately (it may happen while transferring files)
+    fn on_resize(&mut self, width: u16, height: u16) {
+        self.context.as_mut().unwrap().resize(width, height);
+        // Draw once to get the new widths, then update components
+        self.view();
+        let _ = self.update_local_filelist();
+        let _ = self.update_remote_filelist();
+        let _ = self.update_logbox();
+        self.view();
+    }
+
     /// ### read_input_event
     ///
     /// Read one event.
     /// Returns whether at least one event has been handled
     pub(super) fn read_input_event(&mut self) -> bool {
         if let Ok(Some(event)) = self.context.as_ref().unwrap().input_hnd.read_event() {
+            // Lay out ui again if terminal has been resized
+            if let InputEvent::Resize(width, height) = event {
+                self.on_resize(width, height);
+            }
+            // Suspend to shell, then redraw once resumed
+            if InputHandler::is_suspend_event(&event) {
+                self.context.as_mut().unwrap().suspend();
+                self.view();
+                return true;
+            }
             // Handle event
             let msg = self.view.on(event);
             self.update(msg);
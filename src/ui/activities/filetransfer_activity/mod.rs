@@ -39,52 +39,114 @@ extern crate textwrap;
 extern crate tui;
 
 // locals
-use super::{Activity, Context, ExitReason};
-use crate::filetransfer::ftp_transfer::FtpFileTransfer;
-use crate::filetransfer::scp_transfer::ScpFileTransfer;
-use crate::filetransfer::sftp_transfer::SftpFileTransfer;
-use crate::filetransfer::{FileTransfer, FileTransferProtocol};
+use super::{Activity, ActivityResult, Context, ExitReason};
+use crate::bookmarks::{BookmarkTag, SavedSearch};
+use crate::filetransfer::builder::FileTransferBuilder;
+use crate::filetransfer::keyboard_interactive::AuthPrompt;
+use crate::filetransfer::FileTransfer;
+use crate::fs::archive::Archive;
 use crate::fs::explorer::FileExplorer;
+use crate::fs::queue::scheduler::JobScheduler;
+use crate::fs::queue::{BatchPlan, QueueManifest, TransferDirection, TransferQueue};
+use crate::fs::transfer::{
+    CancelToken, ConflictChoice, Throughput, TimeWindow, TransferFilters, TransferOptions,
+};
 use crate::fs::FsEntry;
+use crate::system::bookmarks_client::BookmarksClient;
 use crate::system::config_client::ConfigClient;
+use crate::system::hash_cache::HashCache;
+use crate::system::known_hosts::KnownHost;
+use crate::system::queue_journal::QueueJournal;
+use crate::system::transfer_history::TransferHistory;
+use crate::system::watcher::DirWatcher;
+use crate::ui::context::{FileTransferParams, StartupIssueKind};
 use crate::ui::layout::view::View;
+use crate::ui::tasks::TaskId;
+use crate::utils::fmt::{fmt_host, fmt_log_pattern};
+use crate::utils::secret::Secret;
 
 // Includes
+use bytesize::ByteSize;
 use chrono::{DateTime, Local};
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-use std::collections::VecDeque;
-use std::path::PathBuf;
-use std::time::Instant;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tempfile::TempDir;
 
 // -- Storage keys
 
 const STORAGE_EXPLORER_WIDTH: &str = "FILETRANSFER_EXPLORER_WIDTH";
 const STORAGE_LOGBOX_WIDTH: &str = "LOGBOX_WIDTH";
 
+// -- connection health
+
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const HEALTH_SLOW_THRESHOLD: Duration = Duration::from_secs(2); // Round trip above which connection is slow
+const RECONNECT_ATTEMPTS: usize = 3; // Attempts to restore a dropped session
+const RECONNECT_DELAY: Duration = Duration::from_secs(2); // Delay between reconnection attempts
+const LOG_REFRESH_INTERVAL: Duration = Duration::from_millis(500); // Min interval between log box refreshes for repeated messages
+
 // -- components
 
 const COMPONENT_EXPLORER_LOCAL: &str = "EXPLORER_LOCAL";
 const COMPONENT_EXPLORER_REMOTE: &str = "EXPLORER_REMOTE";
 const COMPONENT_EXPLORER_FIND: &str = "EXPLORER_FIND";
+const COMPONENT_EXPLORER_ARCHIVE: &str = "EXPLORER_ARCHIVE";
 const COMPONENT_LOG_BOX: &str = "LOG_BOX";
 const COMPONENT_PROGRESS_BAR: &str = "PROGRESS_BAR";
+const COMPONENT_PROGRESS_BAR_TOTAL: &str = "PROGRESS_BAR_TOTAL";
 const COMPONENT_TEXT_ERROR: &str = "TEXT_ERROR";
 const COMPONENT_TEXT_FATAL: &str = "TEXT_FATAL";
+const COMPONENT_TEXT_CONFLICT: &str = "TEXT_CONFLICT";
+const COMPONENT_TEXT_SPACE: &str = "TEXT_SPACE";
+const COMPONENT_TEXT_HOST_KEY: &str = "TEXT_HOST_KEY";
 const COMPONENT_TEXT_HELP: &str = "TEXT_HELP";
+const COMPONENT_TEXT_STATUS: &str = "TEXT_STATUS";
 const COMPONENT_TEXT_WAIT: &str = "TEXT_WAIT";
 const COMPONENT_INPUT_COPY: &str = "INPUT_COPY";
 const COMPONENT_INPUT_EXEC: &str = "INPUT_EXEC";
+const COMPONENT_INPUT_FILTERS: &str = "INPUT_FILTERS";
 const COMPONENT_INPUT_FIND: &str = "INPUT_FIND";
 const COMPONENT_INPUT_GOTO: &str = "INPUT_GOTO";
+const COMPONENT_INPUT_LOG_SEARCH: &str = "INPUT_LOG_SEARCH";
 const COMPONENT_INPUT_MKDIR: &str = "INPUT_MKDIR";
 const COMPONENT_INPUT_NEWFILE: &str = "INPUT_NEWFILE";
 const COMPONENT_INPUT_RENAME: &str = "INPUT_RENAME";
+const COMPONENT_INPUT_CHMOD: &str = "INPUT_CHMOD";
 const COMPONENT_INPUT_SAVEAS: &str = "INPUT_SAVEAS";
+const COMPONENT_INPUT_ARCHIVE_ADD: &str = "INPUT_ARCHIVE_ADD";
+const COMPONENT_INPUT_AUTH_PROMPT: &str = "INPUT_AUTH_PROMPT";
+const COMPONENT_INPUT_QUEUE_EXPORT: &str = "INPUT_QUEUE_EXPORT";
+const COMPONENT_INPUT_QUEUE_IMPORT: &str = "INPUT_QUEUE_IMPORT";
+const COMPONENT_INPUT_SCHEDULE: &str = "INPUT_SCHEDULE";
+const COMPONENT_INPUT_REPORT: &str = "INPUT_REPORT";
+const COMPONENT_INPUT_SAVE_SEARCH: &str = "INPUT_SAVE_SEARCH";
+const COMPONENT_INPUT_CLEANUP: &str = "INPUT_CLEANUP";
+const COMPONENT_INPUT_HISTORY_SEARCH: &str = "INPUT_HISTORY_SEARCH";
+const COMPONENT_INPUT_CONFIRM_PHRASE: &str = "INPUT_CONFIRM_PHRASE";
+const COMPONENT_RADIO_CONFLICT: &str = "RADIO_CONFLICT";
+const COMPONENT_RADIO_CONFLICT_ALL: &str = "RADIO_CONFLICT_ALL";
+const COMPONENT_RADIO_CONFLICT_MKDIRS: &str = "RADIO_CONFLICT_MKDIRS";
 const COMPONENT_RADIO_DELETE: &str = "RADIO_DELETE";
 const COMPONENT_RADIO_DISCONNECT: &str = "RADIO_DISCONNECT";
+const COMPONENT_RADIO_HOST_KEY: &str = "RADIO_HOST_KEY";
+const COMPONENT_RADIO_MOVE: &str = "RADIO_MOVE";
+const COMPONENT_RADIO_PURGE: &str = "RADIO_PURGE";
 const COMPONENT_RADIO_QUIT: &str = "RADIO_QUIT";
+const COMPONENT_RADIO_RECOVERY: &str = "RADIO_RECOVERY";
+const COMPONENT_RADIO_RECONNECT: &str = "RADIO_RECONNECT";
 const COMPONENT_RADIO_SORTING: &str = "RADIO_SORTING";
+const COMPONENT_RADIO_SPACE: &str = "RADIO_SPACE";
 const COMPONENT_LIST_FILEINFO: &str = "LIST_FILEINFO";
+const COMPONENT_LIST_PINS: &str = "LIST_PINS";
+const COMPONENT_LIST_HISTORY: &str = "LIST_HISTORY";
+const COMPONENT_LIST_QUEUE: &str = "LIST_QUEUE";
+const COMPONENT_LIST_JOBS: &str = "LIST_JOBS";
+const COMPONENT_LIST_SAVED_SEARCHES: &str = "LIST_SAVED_SEARCHES";
+const COMPONENT_LIST_UPLOAD_PLAN: &str = "LIST_UPLOAD_PLAN";
+const COMPONENT_LIST_BATCH_PLAN: &str = "LIST_BATCH_PLAN";
+const COMPONENT_LIST_TRANSFER_HISTORY: &str = "LIST_TRANSFER_HISTORY";
 
 /// ## FileExplorerTab
 ///
@@ -96,9 +158,21 @@ enum FileExplorerTab {
     FindRemote, // Find result tab
 }
 
+/// ## ProtectedOp
+///
+/// Destructive operation on a protected host, waiting for the confirmation phrase
+enum ProtectedOp {
+    Delete,       // Recursive delete of the selected remote entries
+    Exec(String), // Remote command
+    Move,         // Move of the selected remote directory, which removes it once downloaded
+    PurgeTrash,   // Removal of all the entries in the remote trash
+    RunQueue,     // Transfer queue, which removes remote sources
+}
+
 /// ## LogLevel
 ///
-/// Log level type
+/// Log level type; levels are sorted from the most severe
+#[derive(PartialEq, PartialOrd)]
 enum LogLevel {
     Error,
     Warn,
@@ -112,6 +186,9 @@ struct LogRecord {
     pub time: DateTime<Local>,
     pub level: LogLevel,
     pub msg: String,
+    pub repeated: usize, // Times the message has been repeated after this record
+    pub similar: bool,   // Whether the repeats only have the same pattern as `msg`
+    pattern: String,     // Message with the quoted parts masked
 }
 
 impl LogRecord {
@@ -123,19 +200,145 @@ impl LogRecord {
             time: Local::now(),
             level,
             msg: String::from(msg),
+            repeated: 0,
+            similar: false,
+            pattern: fmt_log_pattern(msg),
+        }
+    }
+
+    /// ### repeat
+    ///
+    /// If a new message with `level` repeats this record (ignoring its quoted parts, such as file names),
+    /// count it as a repeat and return true
+    pub fn repeat(&mut self, level: &LogLevel, msg: &str) -> bool {
+        if self.level != *level || self.pattern != fmt_log_pattern(msg) {
+            return false;
+        }
+        self.repeated += 1;
+        self.similar |= self.msg != msg;
+        true
+    }
+}
+
+/// ## LogFilter
+///
+/// Filters of the records displayed in the log box
+#[derive(Default)]
+struct LogFilter {
+    pub level: Option<LogLevel>, // Display only the records at least as severe as this level
+    pub text: String,            // Display only the records containing this text (lowercase)
+}
+
+impl LogFilter {
+    /// ### is_empty
+    ///
+    /// Returns whether no filter is set
+    pub fn is_empty(&self) -> bool {
+        self.level.is_none() && self.text.is_empty()
+    }
+
+    /// ### cycle_level
+    ///
+    /// Switch to the next severity filter: all records, warnings and errors, errors only
+    pub fn cycle_level(&mut self) {
+        self.level = match self.level {
+            None => Some(LogLevel::Warn),
+            Some(LogLevel::Warn) => Some(LogLevel::Error),
+            Some(_) => None,
+        };
+    }
+
+    /// ### set_text
+    ///
+    /// Set the text to search in the records; an empty text removes the filter
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.trim().to_lowercase();
+    }
+
+    /// ### matches
+    ///
+    /// Returns whether `record` passes the filters
+    pub fn matches(&self, record: &LogRecord) -> bool {
+        if let Some(level) = self.level.as_ref() {
+            if record.level > *level {
+                return false;
+            }
+        }
+        self.text.is_empty() || record.msg.to_lowercase().contains(self.text.as_str())
+    }
+}
+
+/// ## FindSelection
+///
+/// Find results selected to be added to the transfer queue
+#[derive(Default)]
+struct FindSelection {
+    pub search: String,          // Searched pattern
+    pub marked: BTreeSet<usize>, // Indexes of the selected results
+    pub keep_tree: bool,         // Keep the path of the results relative to the search directory
+    pub cleanup: bool,           // Results are candidates for a cleanup; enqueued ones are moved
+}
+
+impl FindSelection {
+    /// ### toggle
+    ///
+    /// Select the result at `idx`, or unselect it if already selected
+    pub fn toggle(&mut self, idx: usize) {
+        if !self.marked.remove(&idx) {
+            self.marked.insert(idx);
+        }
+    }
+
+    /// ### toggle_all
+    ///
+    /// Select all the `len` results, or unselect them if they're all selected already
+    pub fn toggle_all(&mut self, len: usize) {
+        match self.marked.len() == len {
+            true => self.marked.clear(),
+            false => self.marked = (0..len).collect(),
         }
     }
+
+    /// ### remove
+    ///
+    /// Forget the result at `idx`, which has been removed from the results
+    pub fn remove(&mut self, idx: usize) {
+        self.marked = self
+            .marked
+            .iter()
+            .filter(|x| **x != idx)
+            .map(|x| match *x > idx {
+                true => x - 1,
+                false => *x,
+            })
+            .collect();
+    }
 }
 
 /// ### TransferStates
 ///
 /// TransferStates contains the states related to the transfer process
 struct TransferStates {
-    pub progress: f64,        // Current read/write progress (percentage)
-    pub started: Instant,     // Instant the transfer process started
-    pub aborted: bool,        // Describes whether the transfer process has been aborted
+    pub progress: f64,           // Current read/write progress (percentage)
+    pub started: Instant,        // Instant the transfer process started
+    pub cancel: CancelToken,     // Cancelled when the transfer process is aborted
+    pub paused: Option<Instant>, // Instant the transfer has been paused at
+    pub quiet_pause: bool, // Whether the transfer has been paused by the quiet hours of the host
+    pub quiet_skipped: bool, // Whether the user resumed the transfer during the current quiet hours
+    pub queue_aborted: bool, // Describes whether the transfer queue has been aborted
     pub bytes_written: usize, // Bytes written during transfer
-    pub bytes_total: usize,   // Total bytes to write
+    pub bytes_total: usize, // Total bytes to write
+    pub throughput: Throughput, // Current rate of the send/recv
+    pub done_files: usize, // Files completed by the current send/recv
+    pub done_bytes: usize, // Bytes of the files completed by the current send/recv
+    pub batch_files: usize, // Files to transfer by the current send/recv, if known in advance; 0 otherwise
+    pub batch_bytes: usize, // Bytes to transfer by the current send/recv, if known in advance
+    // Conflicts between the files to transfer and the existing targets
+    pub conflict_answer: Option<ConflictChoice>, // Answer given to the conflict popup
+    pub conflict_all: Option<ConflictChoice>,    // Choice applied to all conflicts of the entry
+    pub space_answer: Option<bool>, // Whether to transfer anyway files which don't fit in the space left
+    pub create_dirs: Option<bool>, // Whether to create missing remote directories during the current run, if chosen by the user
+    pub queue_running: bool,       // Whether the transfer queue is being processed
 }
 
 impl TransferStates {
@@ -146,9 +349,23 @@ impl TransferStates {
         TransferStates {
             progress: 0.0,
             started: Instant::now(),
-            aborted: false,
+            cancel: CancelToken::default(),
+            paused: None,
+            quiet_pause: false,
+            quiet_skipped: false,
+            queue_aborted: false,
             bytes_written: 0,
             bytes_total: 0,
+            throughput: Throughput::new(Duration::from_secs(3)),
+            done_files: 0,
+            done_bytes: 0,
+            batch_files: 0,
+            batch_bytes: 0,
+            conflict_answer: None,
+            conflict_all: None,
+            space_answer: None,
+            create_dirs: None,
+            queue_running: false,
         }
     }
 
@@ -158,17 +375,62 @@ impl TransferStates {
     pub fn reset(&mut self) {
         self.progress = 0.0;
         self.started = Instant::now();
-        self.aborted = false;
+        self.cancel.reset();
+        self.paused = None;
+        self.quiet_pause = false;
         self.bytes_written = 0;
         self.bytes_total = 0;
     }
 
+    /// ### reset_batch
+    ///
+    /// Start a new send/recv of `files` files and `bytes` bytes; 0 if they're not known in advance
+    pub fn reset_batch(&mut self, files: usize, bytes: usize) {
+        self.done_files = 0;
+        self.done_bytes = 0;
+        self.batch_files = files;
+        self.batch_bytes = bytes;
+        self.throughput.reset();
+    }
+
+    /// ### fmt_completed
+    ///
+    /// Describe the files completed by the current send/recv
+    pub fn fmt_completed(&self) -> String {
+        format!(
+            "{} file{} completed ({})",
+            self.done_files,
+            if self.done_files == 1 { "" } else { "s" },
+            ByteSize(self.done_bytes as u64)
+        )
+    }
+
+    /// ### toggle_pause
+    ///
+    /// Pause the transfer, or resume it if already paused.
+    /// Once resumed, the time spent in pause is not counted in the transfer speed
+    pub fn toggle_pause(&mut self) {
+        match self.paused.take() {
+            Some(since) => {
+                self.started += since.elapsed();
+                self.throughput.reset();
+                self.cancel.resume();
+            }
+            None => {
+                self.paused = Some(Instant::now());
+                self.cancel.pause();
+            }
+        }
+    }
+
     /// ### set_progress
     ///
     /// Calculate progress percentage based on current progress
     pub fn set_progress(&mut self, w: usize, sz: usize) {
         self.bytes_written = w;
         self.bytes_total = sz;
+        // Measured over the whole send/recv, so that the rate of small files can be measured too
+        self.throughput.record(self.batch_written() as u64);
         let mut prog: f64 = ((self.bytes_written as f64) * 100.0) / (self.bytes_total as f64);
         // Check value
         if prog > 100.0 {
@@ -194,6 +456,42 @@ impl TransferStates {
             _ => self.bytes_written as u64 / elapsed_secs,
         }
     }
+
+    /// ### current_bytes_per_second
+    ///
+    /// Get the rate of the last seconds; the average rate until it can be measured
+    pub fn current_bytes_per_second(&self) -> u64 {
+        self.throughput
+            .bytes_per_second()
+            .unwrap_or_else(|| self.bytes_per_second())
+    }
+
+    /// ### eta
+    ///
+    /// Get the time left to complete the current file, at the current rate
+    pub fn eta(&self) -> Option<Duration> {
+        Throughput::eta(
+            self.bytes_total.saturating_sub(self.bytes_written) as u64,
+            self.current_bytes_per_second(),
+        )
+    }
+
+    /// ### batch_written
+    ///
+    /// Get the bytes written by the current send/recv, including the file being transferred
+    pub fn batch_written(&self) -> usize {
+        self.done_bytes + self.bytes_written
+    }
+
+    /// ### batch_eta
+    ///
+    /// Get the time left to complete the current send/recv, at the current rate
+    pub fn batch_eta(&self) -> Option<Duration> {
+        Throughput::eta(
+            self.batch_bytes.saturating_sub(self.batch_written()) as u64,
+            self.current_bytes_per_second(),
+        )
+    }
 }
 
 impl Default for TransferStates {
@@ -202,50 +500,408 @@ impl Default for TransferStates {
     }
 }
 
+/// ## ConnectionHealth
+///
+/// Describes the outcome of the last connection health check
+#[derive(Clone, Copy, PartialEq)]
+enum ConnectionHealth {
+    Alive,
+    Slow,
+    Lost,
+}
+
+/// ### HealthStates
+///
+/// HealthStates contains the states related to the connection health checks
+struct HealthStates {
+    pub health: ConnectionHealth,
+    pub round_trip: Option<Duration>, // Round trip of the last check
+    pub last_check: Instant,          // Instant the last check has been performed
+}
+
+impl HealthStates {
+    /// ### new
+    ///
+    /// Instantiates a new health states
+    pub fn new() -> HealthStates {
+        HealthStates {
+            health: ConnectionHealth::Alive,
+            round_trip: None,
+            last_check: Instant::now(),
+        }
+    }
+
+    /// ### reset
+    ///
+    /// Re-initialize health states (e.g. once connected)
+    pub fn reset(&mut self) {
+        self.health = ConnectionHealth::Alive;
+        self.round_trip = None;
+        self.last_check = Instant::now();
+    }
+
+    /// ### should_check
+    ///
+    /// Returns whether the connection should be checked now.
+    /// A lost connection is not checked again, until reconnected
+    pub fn should_check(&self) -> bool {
+        self.health != ConnectionHealth::Lost && self.last_check.elapsed() >= HEALTH_CHECK_INTERVAL
+    }
+
+    /// ### set_round_trip
+    ///
+    /// Report a successful check, which took `round_trip`
+    pub fn set_round_trip(&mut self, round_trip: Duration) {
+        self.health = match round_trip > HEALTH_SLOW_THRESHOLD {
+            true => ConnectionHealth::Slow,
+            false => ConnectionHealth::Alive,
+        };
+        self.round_trip = Some(round_trip);
+        self.last_check = Instant::now();
+    }
+
+    /// ### set_lost
+    ///
+    /// Report a failed check
+    pub fn set_lost(&mut self) {
+        self.health = ConnectionHealth::Lost;
+        self.round_trip = None;
+        self.last_check = Instant::now();
+    }
+}
+
+impl Default for HealthStates {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ### AuthChallengeStates
+///
+/// AuthChallengeStates contains the states of the keyboard-interactive authentication (e.g. OTP prompts)
+#[derive(Default)]
+struct AuthChallengeStates {
+    pub prompts: VecDeque<AuthPrompt>, // Server prompts still to be answered
+    pub responses: Vec<Secret>,        // Responses given by the user; kept in memory only
+}
+
+impl AuthChallengeStates {
+    /// ### reset
+    ///
+    /// Forget prompts and responses
+    pub fn reset(&mut self) {
+        self.prompts.clear();
+        self.responses.clear();
+    }
+}
+
+/// ## CommandHistory
+///
+/// CommandHistory contains the commands executed on the remote host and the state of the history browsing
+struct CommandHistory {
+    commands: Vec<String>, // Commands executed on remote; most recent first
+    cursor: Option<usize>, // Index of the command currently browsed
+}
+
+impl CommandHistory {
+    /// ### new
+    ///
+    /// Instantiates a new empty command history
+    pub fn new() -> CommandHistory {
+        CommandHistory {
+            commands: Vec::new(),
+            cursor: None,
+        }
+    }
+
+    /// ### load
+    ///
+    /// Load history from the commands previously executed on host; most recent first
+    pub fn load(&mut self, commands: Vec<String>) {
+        self.commands = commands;
+        self.cursor = None;
+    }
+
+    /// ### push
+    ///
+    /// Push command on top of history
+    pub fn push(&mut self, cmd: &str) {
+        self.cursor = None;
+        if cmd.is_empty() {
+            return;
+        }
+        self.commands.retain(|x| x != cmd);
+        self.commands.insert(0, cmd.to_string());
+    }
+
+    /// ### get
+    ///
+    /// Get command at index
+    pub fn get(&self, idx: usize) -> Option<&str> {
+        self.commands.get(idx).map(|x| x.as_str())
+    }
+
+    /// ### iter
+    ///
+    /// Iterate over commands, starting from the most recent one
+    pub fn iter(&self) -> impl Iterator<Item = &String> + '_ {
+        self.commands.iter()
+    }
+
+    /// ### prev
+    ///
+    /// Move to the previous (older) command and return it
+    pub fn prev(&mut self) -> Option<&str> {
+        let idx: usize = match self.cursor {
+            None => 0,
+            Some(idx) if idx + 1 < self.commands.len() => idx + 1,
+            Some(idx) => idx,
+        };
+        if idx < self.commands.len() {
+            self.cursor = Some(idx);
+        }
+        self.get(idx)
+    }
+
+    /// ### next
+    ///
+    /// Move to the next (more recent) command and return it.
+    /// Returns `None` once the most recent command has been passed
+    pub fn next(&mut self) -> Option<&str> {
+        match self.cursor {
+            None | Some(0) => {
+                self.cursor = None;
+                None
+            }
+            Some(idx) => {
+                self.cursor = Some(idx - 1);
+                self.get(idx - 1)
+            }
+        }
+    }
+
+    /// ### reset_cursor
+    ///
+    /// Stop browsing history
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+}
+
+impl Default for CommandHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// ## ArchiveBrowser
+///
+/// ArchiveBrowser holds the state of an archive browsed as a directory in one of the explorers
+struct ArchiveBrowser {
+    pub archive: Archive,       // Opened archive
+    pub explorer: FileExplorer, // Explorer for archive entries; wrkdir is relative to archive root
+    pub remote: bool,           // Whether the archive has been opened from the remote explorer
+    _tmpdir: Option<TempDir>,   // Holds the local copy of a remote archive until closed
+}
+
+impl ArchiveBrowser {
+    /// ### new
+    ///
+    /// Instantiates a new ArchiveBrowser at the root of the archive
+    pub fn new(
+        archive: Archive,
+        mut explorer: FileExplorer,
+        remote: bool,
+        tmpdir: Option<TempDir>,
+    ) -> ArchiveBrowser {
+        explorer.wrkdir = PathBuf::from("/");
+        explorer.set_files(archive.list_dir(explorer.wrkdir.as_path()));
+        ArchiveBrowser {
+            archive,
+            explorer,
+            remote,
+            _tmpdir: tmpdir,
+        }
+    }
+
+    /// ### changedir
+    ///
+    /// Change directory inside the archive
+    pub fn changedir(&mut self, dir: &Path) {
+        self.explorer.wrkdir = dir.to_path_buf();
+        self.explorer
+            .set_files(self.archive.list_dir(self.explorer.wrkdir.as_path()));
+    }
+
+    /// ### reload
+    ///
+    /// Reload entries of the current directory
+    pub fn reload(&mut self) {
+        let wrkdir: PathBuf = self.explorer.wrkdir.clone();
+        self.changedir(wrkdir.as_path());
+    }
+}
+
+/// ## QueueRecovery
+///
+/// QueueRecovery holds a transfer queue interrupted during a previous session, until the user decides
+/// whether to resume it or to clean up what it has left behind
+struct QueueRecovery {
+    pub manifest: QueueManifest, // Transfers left by the interrupted queue
+    pub partials: Vec<(TransferDirection, FsEntry)>, // Partial files found in the destinations; remote for uploads
+}
+
 /// ## FileTransferActivity
 ///
 /// FileTransferActivity is the data holder for the file transfer activity
 pub struct FileTransferActivity {
-    exit_reason: Option<ExitReason>,  // Exit reason
-    context: Option<Context>,         // Context holder
-    view: View,                       // View
-    client: Box<dyn FileTransfer>,    // File transfer client
-    local: FileExplorer,              // Local File explorer state
-    remote: FileExplorer,             // Remote File explorer state
-    found: Option<FileExplorer>,      // File explorer for find result
-    tab: FileExplorerTab,             // Current selected tab
-    log_records: VecDeque<LogRecord>, // Log records
-    log_size: usize,                  // Log records size (max)
-    transfer: TransferStates,         // Transfer states
+    exit_reason: Option<ExitReason>,            // Exit reason
+    context: Option<Context>,                   // Context holder
+    view: View,                                 // View
+    client: Box<dyn FileTransfer>,              // File transfer client
+    local: FileExplorer,                        // Local File explorer state
+    remote: FileExplorer,                       // Remote File explorer state
+    found: Option<FileExplorer>,                // File explorer for find result
+    found_selection: FindSelection,             // Find results selected for the transfer queue
+    archive: Option<ArchiveBrowser>,            // Archive browsed in one of the explorers
+    tab: FileExplorerTab,                       // Current selected tab
+    browsing_sync: bool,                        // Replicate directory changes on the other explorer
+    remote_root: Option<PathBuf>,               // Session root; the remote explorer can't leave it
+    remote_home: Option<PathBuf>,               // Working directory of the remote host on connect
+    relative_paths: bool,                       // Display remote paths relative to session root
+    log_records: VecDeque<LogRecord>,           // Log records
+    log_size: usize,                            // Log records size (max)
+    log_refreshed: Instant,                     // Last time the log box has been refreshed
+    log_stale: bool,                            // Whether the log box misses some repeats
+    log_filter: LogFilter,                      // Filters of the records displayed in the log box
+    transfer: TransferStates,                   // Transfer states
+    health: HealthStates,                       // Connection health states
+    auth_challenge: AuthChallengeStates,        // Keyboard-interactive authentication states
+    pending_host_key: Option<KnownHost>,        // Host key waiting for the user's confirmation
+    disk_usage_task: Option<(TaskId, FsEntry)>, // Background task calculating a local directory size
+    webhook_tasks: Vec<TaskId>,                 // Background tasks notifying the webhook
+    cmd_history: CommandHistory,                // Remote commands history
+    host_data: Option<BookmarksClient>,         // Persists commands history and filters of host
+    host_key: String,                           // Key of the remote host in the bookmarks file
+    host_tag: Option<BookmarkTag>, // Color and label of the bookmark of the remote host
+    protected_phrase: Option<String>, // Phrase to type before destructive operations, if the remote host is protected
+    protected_op: Option<ProtectedOp>, // Operation waiting for the confirmation phrase
+    quiet_hours: Option<TimeWindow>,  // Time range transfers with the remote host are paused in
+    filters: TransferFilters,         // Include/exclude patterns of recursive transfers
+    saved_searches: Vec<SavedSearch>, // Searches saved for the remote host
+    queue: TransferQueue,             // Transfer queue
+    upload_plan: Vec<usize>,          // Queued uploads to skip to fit in the remote space left
+    batch_plan: Option<BatchPlan>,    // Preview of the queue run, waiting to be confirmed
+    batch_plan_details: bool,         // Whether the preview of the queue run lists each transfer
+    transfer_opts: TransferOptions,   // Options applied to file transfers
+    transfer_profile: Option<String>, // Transfer profile applied to the queue; None for defaults
+    jobs: JobScheduler,               // Transfer jobs run periodically while the session is open
+    server_banner: Option<String>,    // Welcome message sent by the server on connect
+    watch: Option<(DirWatcher, PathBuf)>, // Watched local directory and the remote directory changes are uploaded to
+    history: Option<TransferHistory>, // History of the transfers; None if the configuration directory is not available
+    history_search: String,           // Text searched in the transfer history viewer
+    journal: Option<QueueJournal>, // Journal of the queues being run; None if the configuration directory is not available
+    recovery: Option<QueueRecovery>, // Interrupted queue waiting for the user's choice
+    recovery_checked: bool, // Whether interrupted queues have been looked for since the activity started
+    hash_cache: Option<HashCache>, // Digests of local files; loaded once a file must be hashed
+    paused_params: Option<FileTransferParams>, // Connection params, kept while the tab of another session runs
 }
 
 impl FileTransferActivity {
     /// ### new
     ///
-    /// Instantiates a new FileTransferActivity
-    pub fn new(protocol: FileTransferProtocol) -> FileTransferActivity {
+    /// Instantiates a new FileTransferActivity, with a client for the connection params
+    pub fn new(params: &FileTransferParams) -> FileTransferActivity {
         // Get config client
         let config_client: Option<ConfigClient> = Self::init_config_client();
+        Self::build(
+            FileTransferBuilder::new(params.protocol)
+                .with_ssh_key_storage(Self::make_ssh_storage(config_client.as_ref()))
+                .with_known_hosts(Self::known_hosts_path())
+                .with_network_options(Self::make_network_options(config_client.as_ref()))
+                .with_ssh_algorithms(params.algorithms.clone())
+                .with_s3_endpoint(params.endpoint.clone(), params.region.clone())
+                .build(),
+            config_client,
+        )
+    }
+
+    /// ### with_client
+    ///
+    /// Instantiates a new FileTransferActivity which uses the provided file transfer client
+    pub fn with_client(client: Box<dyn FileTransfer>) -> FileTransferActivity {
+        Self::build(client, Self::init_config_client())
+    }
+
+    /// ### set_remote_root
+    ///
+    /// Jail the remote explorer into `root` for the whole session: navigation above it is blocked
+    /// and paths are displayed relative to it
+    pub fn set_remote_root(&mut self, root: Option<PathBuf>) {
+        self.remote_root = root;
+    }
+
+    /// ### build
+    ///
+    /// Build FileTransferActivity with client and configuration
+    fn build(
+        client: Box<dyn FileTransfer>,
+        config_client: Option<ConfigClient>,
+    ) -> FileTransferActivity {
         FileTransferActivity {
             exit_reason: None,
             context: None,
             view: View::init(),
-            client: match protocol {
-                FileTransferProtocol::Sftp => Box::new(SftpFileTransfer::new(
-                    Self::make_ssh_storage(config_client.as_ref()),
-                )),
-                FileTransferProtocol::Ftp(ftps) => Box::new(FtpFileTransfer::new(ftps)),
-                FileTransferProtocol::Scp => Box::new(ScpFileTransfer::new(
-                    Self::make_ssh_storage(config_client.as_ref()),
-                )),
-            },
+            client,
             local: Self::build_explorer(config_client.as_ref()),
             remote: Self::build_explorer(config_client.as_ref()),
             found: None,
+            found_selection: FindSelection::default(),
+            archive: None,
             tab: FileExplorerTab::Local,
+            browsing_sync: false,
+            remote_root: None,
+            remote_home: None,
+            relative_paths: true,
             log_records: VecDeque::with_capacity(256), // 256 events is enough I guess
             log_size: 256,                             // Must match with capacity
+            log_refreshed: Instant::now(),
+            log_stale: false,
+            log_filter: LogFilter::default(),
             transfer: TransferStates::default(),
+            health: HealthStates::default(),
+            auth_challenge: AuthChallengeStates::default(),
+            pending_host_key: None,
+            disk_usage_task: None,
+            webhook_tasks: Vec::new(),
+            cmd_history: CommandHistory::default(),
+            host_data: None,
+            host_key: String::new(),
+            host_tag: None,
+            protected_phrase: None,
+            protected_op: None,
+            quiet_hours: None,
+            filters: TransferFilters::default(),
+            saved_searches: Vec::new(),
+            queue: TransferQueue::default(),
+            upload_plan: Vec::new(),
+            batch_plan: None,
+            batch_plan_details: false,
+            transfer_opts: config_client
+                .as_ref()
+                .map(|x| x.get_transfer_options())
+                .unwrap_or_default(),
+            transfer_profile: None,
+            jobs: JobScheduler::default(),
+            server_banner: None,
+            watch: None,
+            history: Self::init_transfer_history(),
+            history_search: String::new(),
+            journal: Self::init_queue_journal(),
+            recovery: None,
+            recovery_checked: false,
+            hash_cache: None,
+            paused_params: None,
         }
     }
 }
@@ -275,11 +931,20 @@ impl Activity for FileTransferActivity {
         self.local.wrkdir = pwd;
         // Configure text editor
         self.setup_text_editor();
+        // Load remote commands history and transfer filters
+        self.init_host_data();
         // init view
         self.init();
-        // Verify error state from context
-        if let Some(err) = self.context.as_mut().unwrap().get_error() {
-            self.mount_fatal(&err);
+        // Verify startup issues from context; configuration must be readable
+        if let Some(issue) = self
+            .context
+            .as_mut()
+            .unwrap()
+            .take_issues()
+            .into_iter()
+            .find(|x| x.kind == StartupIssueKind::ConfigUnreadable)
+        {
+            self.mount_fatal(&issue.message);
         }
     }
 
@@ -295,9 +960,20 @@ impl Activity for FileTransferActivity {
             return;
         }
         // Check if connected (popup must be None, otherwise would try reconnecting in loop in case of error)
-        if !self.client.is_connected() && self.view.get_props(COMPONENT_TEXT_FATAL).is_none() {
-            let params = self.context.as_ref().unwrap().ft_params.as_ref().unwrap();
-            let msg: String = format!("Connecting to {}:{}...", params.address, params.port);
+        // Don't connect while the user is answering the authentication prompts or checking the host key
+        if !self.client.is_connected()
+            && self.view.get_props(COMPONENT_TEXT_FATAL).is_none()
+            && self.view.get_props(COMPONENT_INPUT_AUTH_PROMPT).is_none()
+            && self.view.get_props(COMPONENT_RADIO_HOST_KEY).is_none()
+        {
+            let msg: String = match self.context.as_ref().unwrap().ft_params.as_ref() {
+                Some(params) => format!(
+                    "Connecting to {}:{}...",
+                    fmt_host(params.address.as_str()),
+                    params.port
+                ),
+                None => String::from("Opening local directory..."),
+            };
             // Set init state to connecting popup
             self.mount_wait(msg.as_str());
             // Force ui draw
@@ -307,6 +983,21 @@ impl Activity for FileTransferActivity {
             // Redraw
             redraw = true;
         }
+        // Verify periodically that the connection is still alive
+        if self.client.is_connected() && self.health.should_check() {
+            self.check_connection_health();
+            redraw = true;
+        }
+        // Collect background tasks and show toasts
+        redraw |= self.poll_tasks();
+        redraw |= self.action_upload_watched();
+        redraw |= self.action_run_scheduled_jobs();
+        redraw |= self.context.as_mut().unwrap().poll_toast();
+        // Show the repeats which haven't been refreshed yet
+        if self.log_stale && self.log_refreshed.elapsed() >= LOG_REFRESH_INTERVAL {
+            self.refresh_logbox();
+            redraw = true;
+        }
         // Handle input events (if false, becomes true; otherwise remains true)
         redraw |= self.read_input_event();
         // @! draw interface
@@ -331,6 +1022,7 @@ impl Activity for FileTransferActivity {
     fn on_destroy(&mut self) -> Option<Context> {
         // Disable raw mode
         let _ = disable_raw_mode();
+        self.save_hash_cache();
         // Disconnect client
         if self.client.is_connected() {
             let _ = self.client.disconnect();
@@ -344,4 +1036,46 @@ impl Activity for FileTransferActivity {
             None => None,
         }
     }
+
+    /// ### on_pause
+    ///
+    /// `on_pause` is called when the tab of another session is run, or when connecting to another host in a new tab.
+    /// The session is kept open, along with its connection params; the context is released
+    fn on_pause(&mut self) -> Option<Context> {
+        let _ = disable_raw_mode();
+        self.save_hash_cache();
+        let mut ctx: Context = self.context.take()?;
+        self.paused_params = ctx.ft_params.take();
+        ctx.clear_screen();
+        Some(ctx)
+    }
+
+    /// ### on_resume
+    ///
+    /// `on_resume` is called when the tab of this session becomes the active one again.
+    /// The local working directory of the session is entered again and both explorers are reloaded,
+    /// since other sessions may have changed them
+    fn on_resume(&mut self, context: Context, _result: Option<ActivityResult>) {
+        self.context = Some(context);
+        self.exit_reason = None;
+        let ctx = self.context.as_mut().unwrap();
+        ctx.ft_params = self.paused_params.take();
+        ctx.clear_screen();
+        let _ = enable_raw_mode();
+        let wrkdir: PathBuf = self.local.wrkdir.clone();
+        if let Err(err) = ctx.local.change_wrkdir(wrkdir.as_path()) {
+            self.log(
+                LogLevel::Error,
+                format!("Could not change working directory: {}", err).as_str(),
+            );
+        }
+        self.local_scan(wrkdir.as_path());
+        let _ = self.update_local_filelist();
+        if self.client.is_connected() {
+            self.reload_remote_dir();
+            let _ = self.update_remote_filelist();
+        }
+        self.update_status_bar();
+        self.view();
+    }
 }
@@ -30,50 +30,106 @@ extern crate bytesize;
 extern crate content_inspector;
 extern crate crossterm;
 extern crate tempfile;
+extern crate tracing;
 
 // Locals
-use super::{FileTransferActivity, LogLevel};
+use super::{
+    FileTransferActivity, LogLevel, COMPONENT_RADIO_CONFLICT, COMPONENT_RADIO_CONFLICT_ALL,
+    COMPONENT_RADIO_CONFLICT_MKDIRS, COMPONENT_RADIO_SPACE, RECONNECT_ATTEMPTS, RECONNECT_DELAY,
+};
+use crate::filetransfer::builder::FileTransferBuilder;
+use crate::filetransfer::pool::{self, Connector, PoolEvent, PoolJob, TransferPool};
+use crate::filetransfer::{
+    FileTransfer, FileTransferError, FileTransferErrorType, FileTransferProtocol, NetworkOptions,
+    TransferOutcome, TransferStatus, TransferStream,
+};
+use crate::fs::queue::{QueueItem, TransferDirection};
+use crate::fs::space::DiskSpace;
+use crate::fs::transfer::{
+    CancelToken, Cancellable, ChunkSizer, ConflictChoice, GzipBuffer, OverwritePolicy,
+    PartialFilePolicy, RetryPolicy, SymlinkPolicy, Throttle, TransferHook, TransferOptions,
+};
 use crate::fs::{FsEntry, FsFile};
+use crate::host::Localhost;
+use crate::system::hash_cache;
+use crate::system::sshkey_storage::SshKeyStorage;
+use crate::system::staging::Staging;
+use crate::ui::context::FileTransferParams;
+use crate::ui::layout::Payload;
+use crate::ui::store::STORE_KEY_TRANSFER_FINISHED;
 use crate::utils::fmt::fmt_millis;
+use crate::utils::path::{is_jailed, mirror_path};
+use crate::utils::secret::Secret;
+use crate::utils::shell;
 
 // Ext
 use bytesize::ByteSize;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
 use std::fs::OpenOptions;
 use std::io::{Read, Seek, Write};
 use std::path::{Path, PathBuf};
-use std::time::{Instant, SystemTime};
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use tempfile::NamedTempFile;
+use tracing::{field, info_span};
 
 impl FileTransferActivity {
     /// ### connect
     ///
     /// Connect to remote
     pub(super) fn connect(&mut self) {
-        let params = self.context.as_ref().unwrap().ft_params.as_ref().unwrap();
-        let addr: String = params.address.clone();
-        let entry_dir: Option<PathBuf> = params.entry_directory.clone();
-        // Connect to remote
-        match self.client.connect(
-            params.address.clone(),
-            params.port,
-            params.username.clone(),
-            params.password.clone(),
-        ) {
+        let addr: String = self.get_remote_address();
+        // Connect to remote (there are no params in local-to-local mode)
+        let (port, username, password, entry_dir) =
+            match self.context.as_ref().unwrap().ft_params.as_ref() {
+                Some(params) => (
+                    params.port,
+                    params.username.clone(),
+                    params.password.as_ref().map(|x| x.expose().to_string()),
+                    params.entry_directory.clone(),
+                ),
+                None => (0, None, None, None),
+            };
+        match self.client.connect(addr.clone(), port, username, password) {
             Ok(welcome) => {
-                if let Some(banner) = welcome {
+                if let Some(banner) = welcome.as_ref() {
                     // Log welcome
                     self.log(
                         LogLevel::Info,
                         format!("Established connection with '{}': \"{}\"", addr, banner).as_ref(),
                     );
                 }
+                self.server_banner = welcome;
+                // Log remote system
+                if let Some(system) = self.client.identity().system {
+                    self.log(
+                        LogLevel::Info,
+                        format!("Remote system: \"{}\"", system).as_ref(),
+                    );
+                }
+                // Remote paths starting with `~` are resolved from the directory the session starts in
+                self.remote_home = self.client.pwd().ok();
                 // Try to change directory to entry directory
                 let mut remote_chdir: Option<PathBuf> = None;
                 if let Some(entry_directory) = &entry_dir {
                     remote_chdir = Some(entry_directory.clone());
                 }
+                // Session must start inside of the session root
+                if let Some(root) = self.remote_root.as_ref() {
+                    match remote_chdir.as_ref() {
+                        Some(dir) if is_jailed(dir.as_path(), root.as_path()) => {}
+                        _ => remote_chdir = Some(root.clone()),
+                    }
+                }
                 if let Some(entry_directory) = remote_chdir {
+                    // Entering the entry directory must not move the local explorer
+                    let browsing_sync: bool = std::mem::replace(&mut self.browsing_sync, false);
                     self.remote_changedir(entry_directory.as_path(), false);
+                    self.browsing_sync = browsing_sync;
                 }
                 // Set state to explorer
                 self.umount_wait();
@@ -81,20 +137,103 @@ impl FileTransferActivity {
                 // Update file lists
                 self.update_local_filelist();
                 self.update_remote_filelist();
+                // Connection is alive
+                self.health.reset();
+                self.auth_challenge.reset();
+                self.update_status_bar();
+                // Offer to recover the queue interrupted during the previous session
+                self.check_interrupted_queue();
             }
-            Err(err) => {
-                // Set popup fatal error
-                self.mount_fatal(&err.to_string());
-            }
+            Err(err) => match err.kind() {
+                FileTransferErrorType::KeyboardInteractive(prompts) => {
+                    // Ask the user to answer the server prompts
+                    self.umount_wait();
+                    if let Some(instructions) = prompts
+                        .iter()
+                        .map(|x| x.instructions.as_str())
+                        .find(|x| !x.is_empty())
+                    {
+                        self.log(LogLevel::Info, instructions);
+                    }
+                    self.auth_challenge.prompts.extend(prompts.iter().cloned());
+                    self.mount_auth_prompt();
+                }
+                FileTransferErrorType::UnverifiedHostKey(key) => {
+                    // Ask the user to confirm the host key
+                    self.umount_wait();
+                    self.log(LogLevel::Warn, err.to_string().as_str());
+                    self.mount_host_key(key);
+                    self.pending_host_key = Some(key.clone());
+                }
+                _ => {
+                    // Set popup fatal error
+                    self.auth_challenge.reset();
+                    self.mount_fatal(&err.to_string());
+                }
+            },
+        }
+    }
+
+    /// ### answer_auth_prompt
+    ///
+    /// Store the response to the current keyboard-interactive prompt.
+    /// Once all the prompts have been answered, responses are given to the client, which will use them
+    /// at the next connection attempt
+    pub(super) fn answer_auth_prompt(&mut self, response: String) {
+        self.auth_challenge.prompts.pop_front();
+        self.auth_challenge.responses.push(Secret::new(response));
+        if self.auth_challenge.prompts.is_empty() {
+            self.client.set_keyboard_interactive_responses(
+                self.auth_challenge
+                    .responses
+                    .iter()
+                    .map(|x| x.expose().to_string())
+                    .collect(),
+            );
+        }
+    }
+
+    /// ### accept_host_key
+    ///
+    /// Give the host key confirmed by the user to the client, which will trust it at the next connection attempt
+    pub(super) fn accept_host_key(&mut self) {
+        if let Some(key) = self.pending_host_key.take() {
+            self.log(
+                LogLevel::Info,
+                format!("Trusting host key {} for {}", key.fingerprint(), key.host).as_str(),
+            );
+            self.client.set_accepted_host_key(key);
+        }
+    }
+
+    /// ### answer_conflict
+    ///
+    /// Take the choice made by the user in the conflict popup; if it must be applied to all conflicts,
+    /// it is remembered until the end of the current transfer.
+    /// Whether to create missing remote directories is remembered until the end of the current run
+    pub(super) fn answer_conflict(&mut self) {
+        let choice: ConflictChoice = match self.view.get_value(COMPONENT_RADIO_CONFLICT) {
+            Some(Payload::Unsigned(0)) => ConflictChoice::Overwrite,
+            Some(Payload::Unsigned(2)) => ConflictChoice::Rename,
+            Some(Payload::Unsigned(3)) => ConflictChoice::OverwriteIfNewer,
+            _ => ConflictChoice::Skip,
+        };
+        if let Some(Payload::Unsigned(1)) = self.view.get_value(COMPONENT_RADIO_CONFLICT_ALL) {
+            self.transfer.conflict_all = Some(choice);
+        }
+        if let Some(Payload::Unsigned(create_dirs)) =
+            self.view.get_value(COMPONENT_RADIO_CONFLICT_MKDIRS)
+        {
+            self.transfer.create_dirs = Some(create_dirs == 1);
         }
+        self.transfer.conflict_answer = Some(choice);
     }
 
     /// ### disconnect
     ///
     /// disconnect from remote
     pub(super) fn disconnect(&mut self) {
-        let params = self.context.as_ref().unwrap().ft_params.as_ref().unwrap();
-        let msg: String = format!("Disconnecting from {}...", params.address);
+        let msg: String = format!("Disconnecting from {}...", self.get_remote_address());
         // Show popup disconnecting
         self.mount_wait(msg.as_str());
         // Disconnect
@@ -103,6 +242,230 @@ impl FileTransferActivity {
         self.exit_reason = Some(super::ExitReason::Disconnect);
     }
 
+    /// ### check_connection_health
+    ///
+    /// Verify that the remote session is still alive and update the status bar.
+    /// If the connection has been lost, reconnection is offered
+    pub(super) fn check_connection_health(&mut self) {
+        let started: Instant = Instant::now();
+        match self.client.check_connection() {
+            Ok(()) => self.health.set_round_trip(started.elapsed()),
+            Err(err) => {
+                self.health.set_lost();
+                self.log(
+                    LogLevel::Error,
+                    format!("Connection with remote has been lost: {}", err).as_str(),
+                );
+                if !self.restore_session() {
+                    self.mount_reconnect();
+                }
+            }
+        }
+        self.update_status_bar();
+    }
+
+    /// ### reconnect
+    ///
+    /// Close current session; connection is then established again at next tick,
+    /// entering the current remote working directory
+    pub(super) fn reconnect(&mut self) {
+        if let Some(params) = self.context.as_mut().unwrap().ft_params.as_mut() {
+            params.entry_directory = Some(self.remote.wrkdir.clone());
+        }
+        self.close_session();
+    }
+
+    /// ### close_session
+    ///
+    /// Disconnect client; if the session is broken and refuses to disconnect, the client is replaced
+    fn close_session(&mut self) {
+        let _ = self.client.disconnect();
+        let ctx = self.context.as_ref().unwrap();
+        if let (true, Some(params)) = (self.client.is_connected(), ctx.ft_params.as_ref()) {
+            self.client = FileTransferBuilder::new(params.protocol)
+                .with_ssh_key_storage(Self::make_ssh_storage(ctx.config_client.as_ref()))
+                .with_known_hosts(Self::known_hosts_path())
+                .with_network_options(Self::make_network_options(ctx.config_client.as_ref()))
+                .with_ssh_algorithms(params.algorithms.clone())
+                .with_s3_endpoint(params.endpoint.clone(), params.region.clone())
+                .build();
+        }
+    }
+
+    /// ### with_reconnect
+    ///
+    /// Run `op` on the file transfer client. If it fails because the session has dropped,
+    /// the session is restored and `op` is retried once
+    pub(super) fn with_reconnect<T, F>(&mut self, mut op: F) -> Result<T, FileTransferError>
+    where
+        F: FnMut(&mut dyn FileTransfer) -> Result<T, FileTransferError>,
+    {
+        match op(self.client.as_mut()) {
+            Err(err) if self.is_session_dropped(&err) && self.restore_session() => {
+                op(self.client.as_mut())
+            }
+            result => result,
+        }
+    }
+
+    /// ### with_retry
+    ///
+    /// Run `op` until it succeeds. Failures are retried according to the retry policy,
+    /// unless the transfer has been aborted; each retry is logged, described by `what`
+    pub(super) fn with_retry<T, F>(&mut self, what: &str, mut op: F) -> Result<T, String>
+    where
+        F: FnMut(&mut Self) -> Result<T, String>,
+    {
+        let policy: RetryPolicy = self.transfer_opts.retry;
+        let mut retry: u32 = 0;
+        loop {
+            match op(self) {
+                Err(err) if retry < policy.retries && !self.transfer.cancel.is_cancelled() => {
+                    retry += 1;
+                    let delay: Duration = policy.delay(retry);
+                    self.log(
+                        LogLevel::Warn,
+                        format!(
+                            "{} failed: {}; retrying in {} seconds ({}/{})",
+                            what,
+                            err,
+                            fmt_millis(delay),
+                            retry,
+                            policy.retries
+                        )
+                        .as_str(),
+                    );
+                    self.view();
+                    // Aborted while waiting
+                    if !self.wait_retry(delay) {
+                        return Err(err);
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// ### wait_retry
+    ///
+    /// Wait `delay` before retrying, while handling input events, so that the transfer can be aborted.
+    /// Returns whether the delay has elapsed, or false if the transfer has been aborted meanwhile
+    fn wait_retry(&mut self, delay: Duration) -> bool {
+        let started: Instant = Instant::now();
+        while !self.transfer.cancel.is_cancelled() {
+            let elapsed: Duration = started.elapsed();
+            if elapsed >= delay {
+                return true;
+            }
+            if self.read_input_event() {
+                self.view();
+            }
+            thread::sleep((delay - elapsed).min(Duration::from_millis(100)));
+        }
+        false
+    }
+
+    /// ### is_session_dropped
+    ///
+    /// Returns whether `err` has been caused by a dropped session.
+    /// Since not all the protocols report a lost connection as such, the session is probed otherwise
+    fn is_session_dropped(&mut self, err: &FileTransferError) -> bool {
+        if self
+            .context
+            .as_ref()
+            .and_then(|x| x.ft_params.as_ref())
+            .is_none()
+        {
+            return false;
+        }
+        match err.kind() {
+            FileTransferErrorType::ConnectionError
+            | FileTransferErrorType::UninitializedSession => true,
+            FileTransferErrorType::AuthenticationFailed
+            | FileTransferErrorType::BadAddress
+            | FileTransferErrorType::FileSystemBoundary
+            | FileTransferErrorType::KeyboardInteractive(_)
+            | FileTransferErrorType::UnsupportedFeature
+            | FileTransferErrorType::UnverifiedHostKey(_) => false,
+            _ => self.client.check_connection().is_err(),
+        }
+    }
+
+    /// ### restore_session
+    ///
+    /// Establish the session again, using the connection parameters of the activity, and enter
+    /// the remote working directory. Returns whether the session has been restored
+    fn restore_session(&mut self) -> bool {
+        let (addr, port, username, password) =
+            match self.context.as_ref().unwrap().ft_params.as_ref() {
+                Some(params) => (
+                    params.address.clone(),
+                    params.port,
+                    params.username.clone(),
+                    params.password.as_ref().map(|x| x.expose().to_string()),
+                ),
+                None => return false,
+            };
+        self.log(
+            LogLevel::Warn,
+            format!("Session with '{}' has dropped; reconnecting...", addr).as_str(),
+        );
+        for attempt in 1..=RECONNECT_ATTEMPTS {
+            self.close_session();
+            match self
+                .client
+                .connect(addr.clone(), port, username.clone(), password.clone())
+            {
+                Ok(_) => {
+                    let wrkdir: PathBuf = self.remote.wrkdir.clone();
+                    if let Err(err) = self.client.change_dir(wrkdir.as_path()) {
+                        self.log(
+                            LogLevel::Warn,
+                            format!(
+                                "Could not enter \"{}\" after reconnecting: {}",
+                                wrkdir.display(),
+                                err
+                            )
+                            .as_str(),
+                        );
+                    }
+                    self.log(
+                        LogLevel::Info,
+                        format!("Reconnected to '{}'", addr).as_str(),
+                    );
+                    self.health.reset();
+                    self.update_status_bar();
+                    return true;
+                }
+                Err(err) => {
+                    self.log(
+                        LogLevel::Error,
+                        format!(
+                            "Reconnection attempt {}/{} failed: {}",
+                            attempt, RECONNECT_ATTEMPTS, err
+                        )
+                        .as_str(),
+                    );
+                    // Authentication won't succeed by retrying
+                    if matches!(
+                        err.kind(),
+                        FileTransferErrorType::AuthenticationFailed
+                            | FileTransferErrorType::KeyboardInteractive(_)
+                            | FileTransferErrorType::UnverifiedHostKey(_)
+                    ) {
+                        break;
+                    }
+                    if attempt < RECONNECT_ATTEMPTS {
+                        thread::sleep(RECONNECT_DELAY);
+                    }
+                }
+            }
+        }
+        self.health.set_lost();
+        self.update_status_bar();
+        false
+    }
+
     /// ### disconnect_and_quit
     ///
     /// disconnect from remote and then quit
@@ -116,7 +479,7 @@ impl FileTransferActivity {
     /// Reload remote directory entries
     pub(super) fn reload_remote_dir(&mut self) {
         // Get current entries
-        if let Ok(pwd) = self.client.pwd() {
+        if let Ok(pwd) = self.with_reconnect(|client| client.pwd()) {
             self.remote_scan(pwd.as_path());
             // Set wrkdir
             self.remote.wrkdir = pwd;
@@ -127,13 +490,97 @@ impl FileTransferActivity {
     ///
     /// Send fs entry to remote.
     /// If dst_name is Some, entry will be saved with a different name.
-    /// If entry is a directory, this applies to directory only
+    /// If entry is a directory, this applies to directory only.
+    /// Returns the first error occurred while transferring the entry
     pub(super) fn filetransfer_send(
         &mut self,
         entry: &FsEntry,
         curr_remote_path: &Path,
         dst_name: Option<String>,
-    ) {
+    ) -> Result<(), String> {
+        // Choices applied to all conflicts last for this entry only
+        self.transfer.conflict_all = None;
+        if !self.transfer.queue_running {
+            self.transfer.create_dirs = None;
+        }
+        self.prepare_remote_dir(curr_remote_path)?;
+        // Symlinks are transferred according to the symlink policy
+        let remote_path: PathBuf =
+            curr_remote_path.join(dst_name.as_deref().unwrap_or_else(|| entry.get_name()));
+        if let Some(result) =
+            self.apply_symlink_policy(TransferDirection::Upload, entry, remote_path.as_path())
+        {
+            let wrkdir: PathBuf = self.remote.wrkdir.clone();
+            self.remote_scan(wrkdir.as_path());
+            return result;
+        }
+        // Local directories are walked in advance, to report the overall progress
+        let (files, bytes): (usize, usize) = match entry {
+            FsEntry::Directory(_) => self.local_batch_size(entry),
+            FsEntry::File(_) => (0, 0),
+        };
+        if entry.is_dir() && !self.preflight(TransferDirection::Upload, curr_remote_path, bytes) {
+            return Err(format!(
+                "Upload cancelled for \"{}\": not enough space left on the remote host",
+                entry.get_abs_path().display()
+            ));
+        }
+        self.transfer.reset_batch(files, bytes);
+        let result: Result<(), String> =
+            self.filetransfer_send_entry(entry, curr_remote_path, dst_name);
+        // If aborted; show popup
+        if self.transfer.cancel.is_cancelled() {
+            // Log abort and what has been completed
+            let err: String = format!(
+                "Upload aborted for \"{}\"! {}",
+                entry.get_abs_path().display(),
+                self.transfer.fmt_completed()
+            );
+            self.log_and_alert(LogLevel::Warn, err.clone());
+            // Set aborted to false
+            self.transfer.cancel.reset();
+            Err(err)
+        } else {
+            result
+        }
+    }
+
+    /// ### prepare_remote_dir
+    ///
+    /// Create the remote directory `dir` an upload is sent into, with its parents,
+    /// if it doesn't exist and missing remote directories must be created
+    fn prepare_remote_dir(&mut self, dir: &Path) -> Result<(), String> {
+        if !self.create_remote_dirs() || self.remote_version(dir).is_some() {
+            return Ok(());
+        }
+        self.with_reconnect(|client| client.mkdir_recursive(dir))
+            .map_err(|e| format!("Failed to create directory \"{}\": {}", dir.display(), e))?;
+        self.log(
+            LogLevel::Info,
+            format!("Created remote directory \"{}\"", dir.display()).as_str(),
+        );
+        Ok(())
+    }
+
+    /// ### create_remote_dirs
+    ///
+    /// Returns whether missing remote directories must be created during the current run
+    fn create_remote_dirs(&self) -> bool {
+        self.transfer
+            .create_dirs
+            .unwrap_or(self.transfer_opts.create_remote_dirs)
+    }
+
+    /// ### filetransfer_send_entry
+    ///
+    /// Send fs entry to remote; directories are sent recursively
+    fn filetransfer_send_entry(
+        &mut self,
+        entry: &FsEntry,
+        curr_remote_path: &Path,
+        dst_name: Option<String>,
+    ) -> Result<(), String> {
+        let mut result: Result<(), String> = Ok(());
         // Write popup
         let file_name: String = match entry {
             FsEntry::Directory(dir) => dir.name.clone(),
@@ -149,11 +596,24 @@ impl FileTransferActivity {
         // Match entry
         match entry {
             FsEntry::File(file) => {
-                let _ = self.filetransfer_send_file(file, remote_path.as_path(), file_name);
+                // Upload file, unless it exists and must be skipped
+                if let Some(target) = self.upload_target(file, remote_path.as_path()) {
+                    let what: String = format!("Upload of \"{}\"", file.abs_path.display());
+                    if let Err(err) = self.with_retry(what.as_str(), |this| {
+                        this.filetransfer_send_file(file, target.as_path(), file_name.clone())
+                    }) {
+                        self.log_and_alert(LogLevel::Error, err.clone());
+                        result = Err(err);
+                    }
+                }
             }
             FsEntry::Directory(dir) => {
                 // Create directory on remote
-                match self.client.mkdir(remote_path.as_path()) {
+                let what: String = format!("Creation of \"{}\"", remote_path.display());
+                match self.with_retry(what.as_str(), |this| {
+                    this.with_reconnect(|client| client.mkdir(remote_path.as_path()))
+                        .map_err(|e| e.to_string())
+                }) {
                     Ok(_) => {
                         self.log(
                             LogLevel::Info,
@@ -171,34 +631,50 @@ impl FileTransferActivity {
                                 // Iterate over files
                                 for entry in entries.iter() {
                                     // If aborted; break
-                                    if self.transfer.aborted {
+                                    if self.transfer.cancel.is_cancelled() {
                                         break;
                                     }
+                                    // Skip entries matching ignore rules or filters
+                                    if self.skip_excluded(entry) {
+                                        continue;
+                                    }
+                                    if let Some(entry_result) = self.apply_symlink_policy(
+                                        TransferDirection::Upload,
+                                        entry,
+                                        remote_path.join(entry.get_name()).as_path(),
+                                    ) {
+                                        result = result.and(entry_result);
+                                        continue;
+                                    }
                                     // Send entry; name is always None after first call
-                                    self.filetransfer_send(&entry, remote_path.as_path(), None);
+                                    let entry_result: Result<(), String> = self
+                                        .filetransfer_send_entry(
+                                            entry,
+                                            remote_path.as_path(),
+                                            None,
+                                        );
+                                    result = result.and(entry_result);
                                 }
                             }
                             Err(err) => {
-                                self.log_and_alert(
-                                    LogLevel::Error,
-                                    format!(
-                                        "Could not scan directory \"{}\": {}",
-                                        dir.abs_path.display(),
-                                        err
-                                    ),
+                                let err: String = format!(
+                                    "Could not scan directory \"{}\": {}",
+                                    dir.abs_path.display(),
+                                    err
                                 );
+                                self.log_and_alert(LogLevel::Error, err.clone());
+                                result = Err(err);
                             }
                         }
                     }
                     Err(err) => {
-                        self.log_and_alert(
-                            LogLevel::Error,
-                            format!(
-                                "Failed to create directory \"{}\": {}",
-                                remote_path.display(),
-                                err
-                            ),
+                        let err: String = format!(
+                            "Failed to create directory \"{}\": {}",
+                            remote_path.display(),
+                            err
                         );
+                        self.log_and_alert(LogLevel::Error, err.clone());
+                        result = Err(err);
                     }
                 }
             }
@@ -206,19 +682,18 @@ impl FileTransferActivity {
         // Scan dir on remote
         let path: PathBuf = self.remote.wrkdir.clone();
         self.remote_scan(path.as_path());
-        // If aborted; show popup
-        if self.transfer.aborted {
-            // Log abort
-            self.log_and_alert(
-                LogLevel::Warn,
-                format!("Upload aborted for \"{}\"!", entry.get_abs_path().display()),
-            );
-            // Set aborted to false
-            self.transfer.aborted = false;
-        } else {
-            // @! Successful
-            // Eventually, Remove progress bar
-            self.umount_progress_bar();
+        // Eventually, Remove progress bar
+        self.umount_progress_bar();
+        result
+    }
+
+    /// ### remote_remove
+    ///
+    /// Remove `entry` from remote; if configured, mount points are not crossed
+    pub(super) fn remote_remove(&mut self, entry: &FsEntry) -> Result<(), FileTransferError> {
+        match self.transfer_opts.one_file_system {
+            true => self.with_reconnect(|client| client.remove_one_file_system(entry)),
+            false => self.with_reconnect(|client| client.remove(entry)),
         }
     }
 
@@ -226,13 +701,83 @@ impl FileTransferActivity {
     ///
     /// Recv fs entry from remote.
     /// If dst_name is Some, entry will be saved with a different name.
-    /// If entry is a directory, this applies to directory only
+    /// If entry is a directory, this applies to directory only.
+    /// Returns the first error occurred while transferring the entry
     pub(super) fn filetransfer_recv(
         &mut self,
         entry: &FsEntry,
         local_path: &Path,
         dst_name: Option<String>,
-    ) {
+    ) -> Result<(), String> {
+        // Choices applied to all conflicts last for this entry only
+        self.transfer.conflict_all = None;
+        // Symlinks are transferred according to the symlink policy
+        let local_entry_path: PathBuf =
+            local_path.join(dst_name.as_deref().unwrap_or_else(|| entry.get_name()));
+        if let Some(result) = self.apply_symlink_policy(
+            TransferDirection::Download,
+            entry,
+            local_entry_path.as_path(),
+        ) {
+            self.local_scan(local_path);
+            return result;
+        }
+        // Remote directories are walked in advance, to check they fit on the local host and to report the overall progress
+        let (files, bytes): (usize, usize) = match entry {
+            FsEntry::Directory(dir) => {
+                self.mount_wait(format!("Scanning \"{}\"...", dir.name).as_str());
+                self.view();
+                let (files, bytes): (usize, usize) = self.remote_batch_size(entry);
+                self.umount_wait();
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Downloading \"{}\": {} files ({})",
+                        dir.abs_path.display(),
+                        files,
+                        ByteSize(bytes as u64)
+                    )
+                    .as_str(),
+                );
+                (files, bytes)
+            }
+            FsEntry::File(_) => (0, 0),
+        };
+        if entry.is_dir() && !self.preflight(TransferDirection::Download, local_path, bytes) {
+            return Err(format!(
+                "Download cancelled for \"{}\": not enough space left on the local host",
+                entry.get_abs_path().display()
+            ));
+        }
+        self.transfer.reset_batch(files, bytes);
+        let result: Result<(), String> = self.filetransfer_recv_entry(entry, local_path, dst_name);
+        // if aborted; show alert
+        if self.transfer.cancel.is_cancelled() {
+            // Log abort and what has been completed
+            let err: String = format!(
+                "Download aborted for \"{}\"! {}",
+                entry.get_abs_path().display(),
+                self.transfer.fmt_completed()
+            );
+            self.log_and_alert(LogLevel::Warn, err.clone());
+            // Reset aborted to false
+            self.transfer.cancel.reset();
+            Err(err)
+        } else {
+            result
+        }
+    }
+
+    /// ### filetransfer_recv_entry
+    ///
+    /// Recv fs entry from remote; directories are received recursively
+    fn filetransfer_recv_entry(
+        &mut self,
+        entry: &FsEntry,
+        local_path: &Path,
+        dst_name: Option<String>,
+    ) -> Result<(), String> {
+        let mut result: Result<(), String> = Ok(());
         // Write popup
         let file_name: String = match entry {
             FsEntry::Directory(dir) => dir.name.clone(),
@@ -248,11 +793,17 @@ impl FileTransferActivity {
                     None => file.name.clone(),
                 };
                 local_file_path.push(local_file_name.as_str());
-                // Download file
-                if let Err(err) =
-                    self.filetransfer_recv_file(local_file_path.as_path(), file, file_name)
-                {
-                    self.log_and_alert(LogLevel::Error, err);
+                // Download file, unless it exists and must be skipped
+                if let Some(target) = self.download_target(file, local_file_path.as_path()) {
+                    if let Err(err) = self.with_retry(
+                        format!("Download of \"{}\"", file.abs_path.display()).as_str(),
+                        |this| {
+                            this.filetransfer_recv_file(target.as_path(), file, file_name.clone())
+                        },
+                    ) {
+                        self.log_and_alert(LogLevel::Error, err.clone());
+                        result = Err(err);
+                    }
                 }
             }
             FsEntry::Directory(dir) => {
@@ -273,7 +824,10 @@ impl FileTransferActivity {
                     Ok(_) => {
                         // Apply file mode to directory
                         #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
-                        if let Some(pex) = dir.unix_pex {
+                        if let Some(pex) = dir
+                            .unix_pex
+                            .filter(|_| self.transfer_opts.preserve_permissions)
+                        {
                             if let Err(err) = self
                                 .context
                                 .as_ref()
@@ -297,75 +851,164 @@ impl FileTransferActivity {
                             LogLevel::Info,
                             format!("Created directory \"{}\"", local_dir_path.display()).as_ref(),
                         );
+                        // Get device of dir, to detect mount points
+                        let device: Option<u64> = match self.transfer_opts.one_file_system {
+                            true => self
+                                .with_reconnect(|client| client.device_id(dir.abs_path.as_path()))
+                                .unwrap_or(None),
+                            false => None,
+                        };
                         // Get files in dir
-                        match self.client.list_dir(dir.abs_path.as_path()) {
+                        let what: String = format!("Scan of \"{}\"", dir.abs_path.display());
+                        match self.with_retry(what.as_str(), |this| {
+                            this.with_reconnect(|client| client.list_dir(dir.abs_path.as_path()))
+                                .map_err(|e| e.to_string())
+                        }) {
                             Ok(entries) => {
                                 // Iterate over files
                                 for entry in entries.iter() {
                                     // If transfer has been aborted; break
-                                    if self.transfer.aborted {
+                                    if self.transfer.cancel.is_cancelled() {
                                         break;
                                     }
+                                    // Skip entries matching ignore rules or filters
+                                    if self.skip_excluded(entry) {
+                                        continue;
+                                    }
+                                    // Skip directories on another file system
+                                    if let (Some(device), FsEntry::Directory(subdir)) =
+                                        (device, entry)
+                                    {
+                                        if !entry.is_symlink()
+                                            && self
+                                                .with_reconnect(|client| {
+                                                    client.device_id(subdir.abs_path.as_path())
+                                                })
+                                                .unwrap_or(None)
+                                                != Some(device)
+                                        {
+                                            self.log(
+                                                LogLevel::Warn,
+                                                format!(
+                                                    "Skipped \"{}\": on another file system",
+                                                    subdir.abs_path.display()
+                                                )
+                                                .as_str(),
+                                            );
+                                            continue;
+                                        }
+                                    }
+                                    if let Some(entry_result) = self.apply_symlink_policy(
+                                        TransferDirection::Download,
+                                        entry,
+                                        local_dir_path.join(entry.get_name()).as_path(),
+                                    ) {
+                                        result = result.and(entry_result);
+                                        continue;
+                                    }
                                     // Receive entry; name is always None after first call
                                     // Local path becomes local_dir_path
-                                    self.filetransfer_recv(&entry, local_dir_path.as_path(), None);
+                                    let entry_result: Result<(), String> = self
+                                        .filetransfer_recv_entry(
+                                            entry,
+                                            local_dir_path.as_path(),
+                                            None,
+                                        );
+                                    result = result.and(entry_result);
                                 }
                             }
                             Err(err) => {
-                                self.log_and_alert(
-                                    LogLevel::Error,
-                                    format!(
-                                        "Could not scan directory \"{}\": {}",
-                                        dir.abs_path.display(),
-                                        err
-                                    ),
+                                let err: String = format!(
+                                    "Could not scan directory \"{}\": {}",
+                                    dir.abs_path.display(),
+                                    err
                                 );
+                                self.log_and_alert(LogLevel::Error, err.clone());
+                                result = Err(err);
                             }
                         }
                     }
                     Err(err) => {
-                        self.log(
-                            LogLevel::Error,
-                            format!(
-                                "Failed to create directory \"{}\": {}",
-                                local_dir_path.display(),
-                                err
-                            )
-                            .as_ref(),
+                        let err: String = format!(
+                            "Failed to create directory \"{}\": {}",
+                            local_dir_path.display(),
+                            err
                         );
+                        self.log(LogLevel::Error, err.as_str());
+                        result = Err(err);
                     }
                 }
             }
         }
         // Reload directory on local
         self.local_scan(local_path);
-        // if aborted; show alert
-        if self.transfer.aborted {
-            // Log abort
-            self.log_and_alert(
-                LogLevel::Warn,
-                format!(
-                    "Download aborted for \"{}\"!",
-                    entry.get_abs_path().display()
-                ),
-            );
-            // Reset aborted to false
-            self.transfer.aborted = false;
-        } else {
-            // Eventually, Reset input mode to explorer
-            self.umount_progress_bar();
-        }
+        // Eventually, Reset input mode to explorer
+        self.umount_progress_bar();
+        result
     }
 
     /// ### filetransfer_send_file
     ///
-    /// Send local file and write it to remote path
+    /// Send local file and write it to remote path; the outcome is recorded in the transfer history
     fn filetransfer_send_file(
         &mut self,
         local: &FsFile,
         remote: &Path,
         file_name: String,
     ) -> Result<(), String> {
+        let started: Instant = Instant::now();
+        self.transfer.bytes_written = 0;
+        let result: Result<(), String> = self.upload_file(local, remote, file_name);
+        self.record_transfer(
+            TransferDirection::Upload,
+            (local.abs_path.as_path(), remote),
+            self.transfer.bytes_written,
+            started.elapsed(),
+            &result,
+        );
+        if result.is_ok() && !self.transfer.cancel.is_cancelled() {
+            self.run_transfer_hooks(TransferDirection::Upload, local.abs_path.as_path(), remote);
+        }
+        result
+    }
+
+    /// ### upload_file
+    ///
+    /// Send local file and write it to remote path
+    fn upload_file(
+        &mut self,
+        local: &FsFile,
+        remote: &Path,
+        file_name: String,
+    ) -> Result<(), String> {
+        // Open transfer span; bytes are recorded once the stream has been written
+        let span = info_span!(
+            "upload",
+            local = %local.abs_path.display(),
+            remote = %remote.display(),
+            bytes = field::Empty
+        );
+        let _enter = span.enter();
+        // Atomic uploads are written to a temporary file, renamed once complete
+        let target: PathBuf = self.transfer_opts.upload_path(remote);
+        let mut hasher: Option<Sha256> = match self.transfer_opts.verify_checksums {
+            true => Some(Sha256::new()),
+            false => None,
+        };
+        // Compressed uploads are written to `.<name>.gz`, then decompressed into the target.
+        // The data is compressed into a staged file first, since SCP sends the size before the data
+        let staged: Option<(NamedTempFile, FsFile, u64)> =
+            match self.compresses_upload(local.name.as_str()) {
+                true => match stage_compressed(&self.staging(), local, hasher.as_mut()) {
+                    Ok(staged) => Some(staged),
+                    Err(err) => return Err(format!("Could not compress local file: {}", err)),
+                },
+                false => None,
+            };
+        let (source, dest): (&FsFile, PathBuf) = match staged.as_ref() {
+            Some((_, compressed, _)) => (compressed, TransferOptions::compressed_path(remote)),
+            None => (local, target.clone()),
+        };
         // Upload file
         // Try to open local file
         match self
@@ -373,245 +1016,1907 @@ impl FileTransferActivity {
             .as_ref()
             .unwrap()
             .local
-            .open_file_read(local.abs_path.as_path())
+            .open_file_read(source.abs_path.as_path())
         {
-            Ok(mut fhnd) => match self.client.send_file(local, remote) {
-                Ok(mut rhnd) => {
-                    // Write file
-                    let file_size: usize =
-                        fhnd.seek(std::io::SeekFrom::End(0)).unwrap_or(0) as usize;
-                    // rewind
-                    if let Err(err) = fhnd.seek(std::io::SeekFrom::Start(0)) {
-                        return Err(format!("Could not rewind local file: {}", err));
-                    }
-                    // Write remote file
-                    let mut total_bytes_written: usize = 0;
-                    // Reset transfer states
-                    self.transfer.reset();
-                    let mut last_progress_val: f64 = 0.0;
-                    let mut last_input_event_fetch: Instant = Instant::now();
-                    // Mount progress bar
-                    self.mount_progress_bar();
-                    // While the entire file hasn't been completely written,
-                    // Or filetransfer has been aborted
-                    while total_bytes_written < file_size && !self.transfer.aborted {
-                        // Handle input events (each 500ms)
-                        if last_input_event_fetch.elapsed().as_millis() >= 500 {
-                            // Read events
-                            self.read_input_event();
-                            // Reset instant
-                            last_input_event_fetch = Instant::now();
+            Ok(mut fhnd) => {
+                match self.with_reconnect(|client| client.send_file(source, dest.as_path())) {
+                    Ok(rhnd) => {
+                        // Write file
+                        let file_size: usize =
+                            fhnd.seek(std::io::SeekFrom::End(0)).unwrap_or(0) as usize;
+                        // rewind
+                        if let Err(err) = fhnd.seek(std::io::SeekFrom::Start(0)) {
+                            return Err(format!("Could not rewind local file: {}", err));
                         }
-                        // Read till you can
-                        let mut buffer: [u8; 65536] = [0; 65536];
-                        match fhnd.read(&mut buffer) {
-                            Ok(bytes_read) => {
-                                total_bytes_written += bytes_read;
-                                if bytes_read == 0 {
-                                    continue;
-                                } else {
-                                    let mut buf_start: usize = 0;
-                                    while buf_start < bytes_read {
-                                        // Write bytes
-                                        match rhnd.write(&buffer[buf_start..bytes_read]) {
-                                            Ok(bytes) => {
-                                                buf_start += bytes;
-                                            }
-                                            Err(err) => {
-                                                self.umount_progress_bar();
-                                                return Err(format!(
-                                                    "Could not write remote file: {}",
-                                                    err
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
+                        // Write remote file
+                        let mut total_bytes_written: usize = 0;
+                        // Reset transfer states
+                        self.transfer.reset();
+                        let mut rhnd = Cancellable::new(rhnd, &self.transfer.cancel);
+                        let mut last_progress_val: f64 = 0.0;
+                        let progress_step: f64 = self.progress_redraw_step();
+                        let mut last_input_event_fetch: Instant = Instant::now();
+                        let mut throttle: Throttle =
+                            Throttle::new(self.transfer_opts.current_bandwidth_limit());
+                        let mut sizer: ChunkSizer = ChunkSizer::default();
+                        let mut buffer: Vec<u8> = Vec::new();
+                        // Mount progress bar
+                        self.mount_progress_bar();
+                        // Quiet hours may have already begun
+                        self.check_quiet_hours();
+                        // While the entire file hasn't been completely written,
+                        // Or filetransfer has been aborted
+                        while total_bytes_written < file_size
+                            && !self.transfer.cancel.is_cancelled()
+                        {
+                            // Handle input events (each 500ms)
+                            if last_input_event_fetch.elapsed().as_millis() >= 500 {
+                                // Read events
+                                self.read_input_event();
+                                self.check_quiet_hours();
+                                // Follow the bandwidth schedule
+                                throttle.set_limit(self.transfer_opts.current_bandwidth_limit());
+                                // Reset instant
+                                last_input_event_fetch = Instant::now();
                             }
-                            Err(err) => {
-                                self.umount_progress_bar();
-                                return Err(format!("Could not read local file: {}", err));
+                            // Hold the stream while paused
+                            if self.transfer.paused.is_some() {
+                                let text: String = format!("Uploading \"{}\"...", file_name);
+                                throttle.skip(self.wait_resume(text));
+                                continue;
                             }
-                        }
-                        // Increase progress
-                        self.transfer.set_progress(total_bytes_written, file_size);
-                        // Draw only if a significant progress has been made (performance improvement)
-                        if last_progress_val < self.transfer.progress - 1.0 {
-                            // Draw
-                            self.update_progress_bar(format!("Uploading \"{}\"...", file_name));
-                            self.view();
-                            last_progress_val = self.transfer.progress;
-                        }
-                    }
-                    // Umount progress bar
-                    self.umount_progress_bar();
-                    // Finalize stream
-                    if let Err(err) = self.client.on_sent(rhnd) {
-                        self.log(
-                            LogLevel::Warn,
-                            format!("Could not finalize remote stream: \"{}\"", err).as_str(),
-                        );
-                    }
-                    self.log(
+                            // Keep below bandwidth limit
+                            if let Some(delay) = throttle.delay() {
+                                thread::sleep(delay.min(Duration::from_millis(100)));
+                                continue;
+                            }
+                            // Read till you can
+                            buffer.resize(sizer.size(), 0);
+                            let chunk_started: Instant = Instant::now();
+                            let chunk_offset: usize = total_bytes_written;
+                            match fhnd.read(&mut buffer) {
+                                Ok(bytes_read) => {
+                                    total_bytes_written += bytes_read;
+                                    if bytes_read == 0 {
+                                        continue;
+                                    } else {
+                                        // Staged data has already been hashed uncompressed
+                                        if let (Some(hasher), None) =
+                                            (hasher.as_mut(), staged.as_ref())
+                                        {
+                                            hasher.update(&buffer[..bytes_read]);
+                                        }
+                                        throttle.consume(bytes_read);
+                                        let mut buf_start: usize = 0;
+                                        while buf_start < bytes_read {
+                                            // Write bytes
+                                            match rhnd.write(&buffer[buf_start..bytes_read]) {
+                                                Ok(bytes) => {
+                                                    buf_start += bytes;
+                                                }
+                                                // Aborted; exit the loop
+                                                Err(err) if CancelToken::is_cancellation(&err) => {
+                                                    break
+                                                }
+                                                Err(err) => {
+                                                    self.umount_progress_bar();
+                                                    let err: String = format!(
+                                                        "Could not write remote file: {}",
+                                                        err
+                                                    );
+                                                    self.finalize_stream(
+                                                        TransferStream::Upload(rhnd.into_inner()),
+                                                        total_bytes_written,
+                                                        TransferStatus::Failed(err.clone()),
+                                                    );
+                                                    return Err(err);
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                Err(err) => {
+                                    self.umount_progress_bar();
+                                    let err: String = format!("Could not read local file: {}", err);
+                                    self.finalize_stream(
+                                        TransferStream::Upload(rhnd.into_inner()),
+                                        total_bytes_written,
+                                        TransferStatus::Failed(err.clone()),
+                                    );
+                                    return Err(err);
+                                }
+                            }
+                            // Adapt the chunk size to the throughput
+                            sizer.record(
+                                total_bytes_written - chunk_offset,
+                                chunk_started.elapsed(),
+                            );
+                            // Increase progress
+                            self.transfer.set_progress(total_bytes_written, file_size);
+                            // Draw only if a significant progress has been made (performance improvement)
+                            if last_progress_val < self.transfer.progress - progress_step {
+                                // Draw
+                                self.update_progress_bar(format!("Uploading \"{}\"...", file_name));
+                                self.view();
+                                last_progress_val = self.transfer.progress;
+                            }
+                        }
+                        // Umount progress bar
+                        self.umount_progress_bar();
+                        span.record("bytes", total_bytes_written as u64);
+                        // Finalize stream
+                        let status: TransferStatus = match self.transfer.cancel.is_cancelled() {
+                            true => TransferStatus::Aborted,
+                            false => TransferStatus::Completed,
+                        };
+                        self.finalize_stream(
+                            TransferStream::Upload(rhnd.into_inner()),
+                            total_bytes_written,
+                            status,
+                        );
+                        // Apply the partial files policy to the target, if aborted
+                        if self.transfer.cancel.is_cancelled() {
+                            self.discard_partial(TransferDirection::Upload, dest.as_path());
+                            return Ok(());
+                        }
+                        // Decompress the uploaded data into the target
+                        if let Some((_, _, uncompressed_bytes)) = staged.as_ref() {
+                            if let Err(err) = self.with_reconnect(|client| {
+                                client.gunzip(dest.as_path(), target.as_path())
+                            }) {
+                                return Err(format!(
+                                    "Could not decompress \"{}\": {}",
+                                    dest.display(),
+                                    err
+                                ));
+                            }
+                            self.log(
+                                LogLevel::Info,
+                                format!(
+                                    "Sent \"{}\" compressed: {} instead of {}",
+                                    local.abs_path.display(),
+                                    ByteSize(total_bytes_written as u64),
+                                    ByteSize(*uncompressed_bytes),
+                                )
+                                .as_ref(),
+                            );
+                        }
+                        if self.transfer_opts.atomic_uploads {
+                            pool::finalize_atomic_upload(
+                                self.client.as_mut(),
+                                target.as_path(),
+                                remote,
+                            )?;
+                        }
+                        if let Some(hasher) = hasher {
+                            self.verify_remote_checksum(remote, hasher.finalize().as_slice())?;
+                        }
+                        // Apply permission template to file
+                        if let Some((mode, owner)) =
+                            self.transfer_opts.upload_permissions(local.name.as_str())
+                        {
+                            let result = self.with_reconnect(|client| {
+                                client.chmod(remote, mode)?;
+                                match owner {
+                                    Some((uid, gid)) => client.chown(remote, uid, gid),
+                                    None => Ok(()),
+                                }
+                            });
+                            if let Err(err) = result {
+                                self.log(
+                                    LogLevel::Warn,
+                                    format!(
+                                        "Could not apply permissions to \"{}\": {}",
+                                        remote.display(),
+                                        err
+                                    )
+                                    .as_ref(),
+                                );
+                            }
+                        }
+                        // Apply modification time to file
+                        if self.transfer_opts.preserve_timestamps {
+                            let mtime: SystemTime = local.last_change_time;
+                            if let Err(err) =
+                                self.with_reconnect(|client| client.set_mtime(remote, mtime))
+                            {
+                                self.log(
+                                    LogLevel::Warn,
+                                    format!(
+                                        "Could not set modification time of \"{}\": {}",
+                                        remote.display(),
+                                        err
+                                    )
+                                    .as_ref(),
+                                );
+                            }
+                        }
+                        self.log(
+                            LogLevel::Info,
+                            format!(
+                                "Saved file \"{}\" to \"{}\" (took {} seconds; at {}/s)",
+                                local.abs_path.display(),
+                                remote.display(),
+                                fmt_millis(self.transfer.started.elapsed()),
+                                ByteSize(self.transfer.bytes_per_second()),
+                            )
+                            .as_ref(),
+                        );
+                        self.context.as_mut().unwrap().store.set_string(
+                            STORE_KEY_TRANSFER_FINISHED,
+                            remote.to_string_lossy().to_string(),
+                        );
+                        self.transfer.done_files += 1;
+                        self.transfer.done_bytes += total_bytes_written;
+                    }
+                    Err(err) => {
+                        return Err(format!(
+                            "Failed to upload file \"{}\": {}",
+                            local.abs_path.display(),
+                            err
+                        ))
+                    }
+                }
+            }
+            Err(err) => {
+                return Err(format!(
+                    "Failed to open file \"{}\": {}",
+                    local.abs_path.display(),
+                    err
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// ### filetransfer_recv_file
+    ///
+    /// Receive file from remote and write it to local path; the outcome is recorded in the transfer history
+    fn filetransfer_recv_file(
+        &mut self,
+        local: &Path,
+        remote: &FsFile,
+        file_name: String,
+    ) -> Result<(), String> {
+        let started: Instant = Instant::now();
+        self.transfer.bytes_written = 0;
+        let result: Result<(), String> = match self.segmented_download_workers(remote) {
+            Some((workers, connect)) => {
+                self.download_segmented(local, remote, file_name, workers, connect)
+            }
+            None => self.download_file(local, remote, file_name),
+        };
+        self.record_transfer(
+            TransferDirection::Download,
+            (local, remote.abs_path.as_path()),
+            self.transfer.bytes_written,
+            started.elapsed(),
+            &result,
+        );
+        if result.is_ok() && !self.transfer.cancel.is_cancelled() {
+            self.run_transfer_hooks(
+                TransferDirection::Download,
+                local,
+                remote.abs_path.as_path(),
+            );
+        }
+        result
+    }
+
+    /// ### download_file
+    ///
+    /// Receive file from remote and write it to local path
+    fn download_file(
+        &mut self,
+        local: &Path,
+        remote: &FsFile,
+        file_name: String,
+    ) -> Result<(), String> {
+        // Open transfer span; bytes are recorded once the stream has been read
+        let span = info_span!(
+            "download",
+            remote = %remote.abs_path.display(),
+            local = %local.display(),
+            bytes = field::Empty
+        );
+        let _enter = span.enter();
+        let mut hasher: Option<Sha256> = match self.transfer_opts.verify_checksums {
+            true => Some(Sha256::new()),
+            false => None,
+        };
+        // Try to open local file
+        match self.context.as_ref().unwrap().local.open_file_write(local) {
+            Ok(mut local_file) => {
+                // Reserve space first, so that a full disk is reported before downloading
+                if self.transfer_opts.preallocate {
+                    if let Err(err) = Localhost::preallocate(&local_file, remote.size as u64) {
+                        return Err(format!(
+                            "Failed to write local file \"{}\": {}",
+                            local.display(),
+                            err
+                        ));
+                    }
+                }
+                // Download file from remote
+                match self.with_reconnect(|client| client.recv_file(remote)) {
+                    Ok(rhnd) => {
+                        let mut total_bytes_written: usize = 0;
+                        // Reset transfer states
+                        self.transfer.reset();
+                        let mut rhnd = Cancellable::new(rhnd, &self.transfer.cancel);
+                        // Write local file
+                        let mut last_progress_val: f64 = 0.0;
+                        let progress_step: f64 = self.progress_redraw_step();
+                        let mut last_input_event_fetch: Instant = Instant::now();
+                        let mut throttle: Throttle =
+                            Throttle::new(self.transfer_opts.current_bandwidth_limit());
+                        let mut sizer: ChunkSizer = ChunkSizer::default();
+                        let mut buffer: Vec<u8> = Vec::new();
+                        // Mount progress bar
+                        self.mount_progress_bar();
+                        // Quiet hours may have already begun
+                        self.check_quiet_hours();
+                        // While the entire file hasn't been completely read,
+                        // Or filetransfer has been aborted
+                        while total_bytes_written < remote.size
+                            && !self.transfer.cancel.is_cancelled()
+                        {
+                            // Handle input events (each 500 ms)
+                            if last_input_event_fetch.elapsed().as_millis() >= 500 {
+                                // Read events
+                                self.read_input_event();
+                                self.check_quiet_hours();
+                                // Follow the bandwidth schedule
+                                throttle.set_limit(self.transfer_opts.current_bandwidth_limit());
+                                // Reset instant
+                                last_input_event_fetch = Instant::now();
+                            }
+                            // Hold the stream while paused
+                            if self.transfer.paused.is_some() {
+                                let text: String = format!("Downloading \"{}\"...", file_name);
+                                throttle.skip(self.wait_resume(text));
+                                continue;
+                            }
+                            // Keep below bandwidth limit
+                            if let Some(delay) = throttle.delay() {
+                                thread::sleep(delay.min(Duration::from_millis(100)));
+                                continue;
+                            }
+                            // Read till you can
+                            buffer.resize(sizer.size(), 0);
+                            let chunk_started: Instant = Instant::now();
+                            let chunk_offset: usize = total_bytes_written;
+                            match rhnd.read(&mut buffer) {
+                                Ok(bytes_read) => {
+                                    total_bytes_written += bytes_read;
+                                    if bytes_read == 0 {
+                                        continue;
+                                    } else {
+                                        if let Some(hasher) = hasher.as_mut() {
+                                            hasher.update(&buffer[..bytes_read]);
+                                        }
+                                        throttle.consume(bytes_read);
+                                        let mut buf_start: usize = 0;
+                                        while buf_start < bytes_read {
+                                            // Write bytes
+                                            match local_file.write(&buffer[buf_start..bytes_read]) {
+                                                Ok(bytes) => buf_start += bytes,
+                                                Err(err) => {
+                                                    self.umount_progress_bar();
+                                                    let err: String = format!(
+                                                        "Could not write local file: {}",
+                                                        err
+                                                    );
+                                                    self.finalize_stream(
+                                                        TransferStream::Download(rhnd.into_inner()),
+                                                        total_bytes_written,
+                                                        TransferStatus::Failed(err.clone()),
+                                                    );
+                                                    return Err(err);
+                                                }
+                                            }
+                                        }
+                                    }
+                                }
+                                // Aborted; exit the loop
+                                Err(err) if CancelToken::is_cancellation(&err) => break,
+                                Err(err) => {
+                                    self.umount_progress_bar();
+                                    let err: String =
+                                        format!("Could not read remote file: {}", err);
+                                    self.finalize_stream(
+                                        TransferStream::Download(rhnd.into_inner()),
+                                        total_bytes_written,
+                                        TransferStatus::Failed(err.clone()),
+                                    );
+                                    return Err(err);
+                                }
+                            }
+                            // Adapt the chunk size to the throughput
+                            sizer.record(
+                                total_bytes_written - chunk_offset,
+                                chunk_started.elapsed(),
+                            );
+                            // Set progress
+                            self.transfer.set_progress(total_bytes_written, remote.size);
+                            // Draw only if a significant progress has been made (performance improvement)
+                            if last_progress_val < self.transfer.progress - progress_step {
+                                // Draw
+                                self.update_progress_bar(format!("Downloading \"{}\"", file_name));
+                                self.view();
+                                last_progress_val = self.transfer.progress;
+                            }
+                        }
+                        // Umount progress bar
+                        self.umount_progress_bar();
+                        span.record("bytes", total_bytes_written as u64);
+                        // Finalize stream
+                        let status: TransferStatus = match self.transfer.cancel.is_cancelled() {
+                            true => TransferStatus::Aborted,
+                            false => TransferStatus::Completed,
+                        };
+                        self.finalize_stream(
+                            TransferStream::Download(rhnd.into_inner()),
+                            total_bytes_written,
+                            status,
+                        );
+                        // Apply the partial files policy to the target, if aborted
+                        if self.transfer.cancel.is_cancelled() {
+                            drop(local_file);
+                            self.discard_partial(TransferDirection::Download, local);
+                            return Ok(());
+                        }
+                        // Apply modification time to file
+                        if self.transfer_opts.preserve_timestamps {
+                            if let Err(err) = local_file.set_modified(remote.last_change_time) {
+                                self.log(
+                                    LogLevel::Error,
+                                    format!(
+                                        "Could not set modification time of \"{}\": {}",
+                                        local.display(),
+                                        err
+                                    )
+                                    .as_ref(),
+                                );
+                            }
+                        }
+                        if let Some(hasher) = hasher {
+                            self.verify_local_checksum(local, hasher.finalize().as_slice())?;
+                        }
+                        // Apply file mode to file
+                        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+                        if let Some(pex) = remote
+                            .unix_pex
+                            .filter(|_| self.transfer_opts.preserve_permissions)
+                        {
+                            if let Err(err) = self.context.as_ref().unwrap().local.chmod(local, pex)
+                            {
+                                self.log(
+                                    LogLevel::Error,
+                                    format!(
+                                        "Could not apply file mode {:?} to \"{}\": {}",
+                                        pex,
+                                        local.display(),
+                                        err
+                                    )
+                                    .as_ref(),
+                                );
+                            }
+                        }
+                        // Flush the file to disk before reporting it as done
+                        if self.transfer_opts.durable_writes {
+                            drop(local_file);
+                            Localhost::sync_file(local).map_err(|e| {
+                                format!("Failed to write local file \"{}\": {}", local.display(), e)
+                            })?;
+                        }
+                        // Log
+                        self.log(
+                            LogLevel::Info,
+                            format!(
+                                "Saved file \"{}\" to \"{}\" (took {} seconds; at {}/s)",
+                                remote.abs_path.display(),
+                                local.display(),
+                                fmt_millis(self.transfer.started.elapsed()),
+                                ByteSize(self.transfer.bytes_per_second()),
+                            )
+                            .as_ref(),
+                        );
+                        self.context.as_mut().unwrap().store.set_string(
+                            STORE_KEY_TRANSFER_FINISHED,
+                            local.to_string_lossy().to_string(),
+                        );
+                        self.transfer.done_files += 1;
+                        self.transfer.done_bytes += total_bytes_written;
+                    }
+                    Err(err) => {
+                        return Err(format!(
+                            "Failed to download file \"{}\": {}",
+                            remote.abs_path.display(),
+                            err
+                        ))
+                    }
+                }
+            }
+            Err(err) => {
+                return Err(format!(
+                    "Failed to open local file for write \"{}\": {}",
+                    local.display(),
+                    err
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// ### segmented_download_workers
+    ///
+    /// Get the amount of concurrent sessions `remote` should be downloaded on, in segments, and how to open them.
+    /// Only large files are downloaded in segments, over SFTP, if concurrent transfers are enabled
+    fn segmented_download_workers(&self, remote: &FsFile) -> Option<(usize, Arc<Connector>)> {
+        let sftp: bool = matches!(
+            self.context
+                .as_ref()
+                .unwrap()
+                .ft_params
+                .as_ref()
+                .map(|x| x.protocol),
+            Some(FileTransferProtocol::Sftp)
+        );
+        let workers: usize = self.transfer_workers();
+        if !sftp || workers < 2 || (remote.size as u64) < pool::SEGMENTED_MIN_SIZE {
+            return None;
+        }
+        self.make_connector().map(|connect| (workers, connect))
+    }
+
+    /// ### download_segmented
+    ///
+    /// Download `remote` to `local` in segments, on `workers` concurrent sessions, showing the progress of the whole file.
+    /// The segments are written into a file set to the size of `remote`; the file is sparse, unless preallocation is enabled.
+    /// If the sessions can't be opened (e.g. the server doesn't allow them), the file is downloaded on the main session
+    fn download_segmented(
+        &mut self,
+        local: &Path,
+        remote: &FsFile,
+        file_name: String,
+        workers: usize,
+        connect: Arc<Connector>,
+    ) -> Result<(), String> {
+        let size: u64 = remote.size as u64;
+        let local_file: std::fs::File = std::fs::File::create(local)
+            .and_then(|x| x.set_len(size).map(|_| x))
+            .map_err(|e| {
+                format!(
+                    "Failed to open local file for write \"{}\": {}",
+                    local.display(),
+                    e
+                )
+            })?;
+        // Allocate the segments too, instead of leaving holes
+        if self.transfer_opts.preallocate {
+            Localhost::preallocate(&local_file, size).map_err(|e| {
+                format!("Failed to write local file \"{}\": {}", local.display(), e)
+            })?;
+        }
+        drop(local_file);
+        let jobs: Vec<PoolJob> = pool::segments(size, workers)
+            .into_iter()
+            .map(|range| PoolJob {
+                id: 0,
+                direction: TransferDirection::Download,
+                file: remote.clone(),
+                target: local.to_path_buf(),
+                range: Some(range),
+            })
+            .collect();
+        let (segments, sessions): (usize, usize) = (jobs.len(), workers.min(jobs.len()));
+        let text: String = format!("Downloading \"{}\" on {} sessions...", file_name, sessions);
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Downloading \"{}\" in {} segments on {} sessions",
+                remote.abs_path.display(),
+                segments,
+                sessions
+            )
+            .as_str(),
+        );
+        // Run pool
+        let pool: TransferPool = TransferPool::new(jobs, workers, &self.transfer_opts, connect);
+        let mut total_bytes_written: usize = 0;
+        let mut error: Option<String> = None;
+        let mut connection_failed: bool = false;
+        self.transfer.reset();
+        self.transfer.set_progress(0, remote.size.max(1));
+        let mut last_progress_val: f64 = 0.0;
+        let progress_step: f64 = self.progress_redraw_step();
+        let mut last_input_event_fetch: Instant = Instant::now();
+        self.mount_progress_bar();
+        self.check_quiet_hours();
+        self.update_progress_bar(text.clone());
+        self.view();
+        loop {
+            // Handle input events (each 500ms)
+            if last_input_event_fetch.elapsed().as_millis() >= 500 {
+                self.read_input_event();
+                self.check_quiet_hours();
+                last_input_event_fetch = Instant::now();
+                if self.transfer.cancel.is_cancelled() {
+                    pool.abort();
+                }
+                // Pause or resume workers
+                if self.transfer.paused.is_some() != pool.is_paused() {
+                    match self.transfer.paused.is_some() {
+                        true => pool.pause(),
+                        false => pool.resume(),
+                    }
+                    self.update_progress_bar(text.clone());
+                    self.view();
+                }
+            }
+            match pool.recv_timeout(Duration::from_millis(100)) {
+                Ok(PoolEvent::Progress(bytes)) => {
+                    total_bytes_written += bytes as usize;
+                    self.transfer
+                        .set_progress(total_bytes_written, remote.size.max(1));
+                    // Draw only if a significant progress has been made (performance improvement)
+                    if last_progress_val < self.transfer.progress - progress_step {
+                        self.update_progress_bar(text.clone());
+                        self.view();
+                        last_progress_val = self.transfer.progress;
+                    }
+                }
+                Ok(PoolEvent::Finished(_, Ok(_))) => {}
+                // The file can't be completed without the segment: stop the others
+                Ok(PoolEvent::Finished(_, Err(err))) => {
+                    if error.is_none() {
+                        error = Some(err);
+                        pool.abort();
+                    }
+                }
+                Ok(PoolEvent::Retrying(job, retry, err)) => {
+                    let policy: RetryPolicy = self.transfer_opts.retry;
+                    self.log(
+                        LogLevel::Warn,
+                        format!(
+                            "Segment at byte {} of \"{}\" failed: {}; retrying in {} seconds ({}/{})",
+                            job.range.map(|x| x.0).unwrap_or_default(),
+                            job.file.abs_path.display(),
+                            err,
+                            fmt_millis(policy.delay(retry)),
+                            retry,
+                            policy.retries
+                        )
+                        .as_str(),
+                    );
+                }
+                Ok(PoolEvent::ConnectionFailed(err)) => {
+                    // Report it once; the other sessions take over the segments
+                    if !connection_failed {
+                        self.log(
+                            LogLevel::Warn,
+                            format!("Could not open transfer session: {}", err).as_str(),
+                        );
+                        connection_failed = true;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        self.umount_progress_bar();
+        let left: Vec<PoolJob> = pool.join();
+        // Apply the partial files policy to the target, if aborted
+        if self.transfer.cancel.is_cancelled() {
+            self.discard_partial(TransferDirection::Download, local);
+            return Ok(());
+        }
+        if let Some(err) = error {
+            return Err(format!(
+                "Failed to download file \"{}\": {}",
+                remote.abs_path.display(),
+                err
+            ));
+        }
+        // Segments are left only if no session could be opened
+        if !left.is_empty() {
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "Downloading \"{}\" on the main session",
+                    remote.abs_path.display()
+                )
+                .as_str(),
+            );
+            return self.download_file(local, remote, file_name);
+        }
+        // Apply modification time to file
+        if self.transfer_opts.preserve_timestamps {
+            if let Err(err) = OpenOptions::new()
+                .write(true)
+                .open(local)
+                .and_then(|x| x.set_modified(remote.last_change_time))
+            {
+                self.log(
+                    LogLevel::Error,
+                    format!(
+                        "Could not set modification time of \"{}\": {}",
+                        local.display(),
+                        err
+                    )
+                    .as_ref(),
+                );
+            }
+        }
+        // Apply file mode to file
+        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+        if let Some(pex) = remote
+            .unix_pex
+            .filter(|_| self.transfer_opts.preserve_permissions)
+        {
+            if let Err(err) = self.context.as_ref().unwrap().local.chmod(local, pex) {
+                self.log(
+                    LogLevel::Error,
+                    format!(
+                        "Could not apply file mode {:?} to \"{}\": {}",
+                        pex,
+                        local.display(),
+                        err
+                    )
+                    .as_ref(),
+                );
+            }
+        }
+        // Flush the file to disk before reporting it as done
+        if self.transfer_opts.durable_writes {
+            Localhost::sync_file(local).map_err(|e| {
+                format!("Failed to write local file \"{}\": {}", local.display(), e)
+            })?;
+        }
+        self.log(
+            LogLevel::Info,
+            format!(
+                "Saved file \"{}\" to \"{}\" (took {} seconds; at {}/s on {} sessions)",
+                remote.abs_path.display(),
+                local.display(),
+                fmt_millis(self.transfer.started.elapsed()),
+                ByteSize(self.transfer.bytes_per_second()),
+                sessions
+            )
+            .as_ref(),
+        );
+        self.context.as_mut().unwrap().store.set_string(
+            STORE_KEY_TRANSFER_FINISHED,
+            local.to_string_lossy().to_string(),
+        );
+        self.transfer.done_files += 1;
+        self.transfer.done_bytes += remote.size;
+        Ok(())
+    }
+
+    /// ### filetransfer_parallel
+    ///
+    /// Transfer the pending items of the queue on `workers` concurrent sessions.
+    /// Directories are created on the main session, then the files are distributed across the workers;
+    /// the files which the workers couldn't take (e.g. because they failed to connect) are transferred on the main session
+    pub(super) fn filetransfer_parallel(&mut self, workers: usize, connect: Arc<Connector>) {
+        // Plan transfers; keep the amount of files left and the first error of each item
+        let mut jobs: Vec<PoolJob> = Vec::new();
+        let mut items: HashMap<usize, (usize, Option<String>)> = HashMap::new();
+        for id in self.queue.pending() {
+            let item: QueueItem = self.queue.get(id).unwrap().clone();
+            let planned: usize = jobs.len();
+            // Choices applied to all conflicts last for this item only
+            self.transfer.conflict_all = None;
+            let result: Result<(), String> = match item.direction {
+                TransferDirection::Upload => self
+                    .prepare_remote_dir(item.dest.as_path())
+                    .and_then(|_| self.plan_send(id, &item.entry, item.dest.as_path(), &mut jobs)),
+                TransferDirection::Download => {
+                    self.plan_recv(id, &item.entry, item.dest.as_path(), &mut jobs)
+                }
+            };
+            if let Err(err) = result.as_ref() {
+                self.log(LogLevel::Error, err.as_str());
+            }
+            items.insert(id, (jobs.len() - planned, result.err()));
+        }
+        // A single file is transferred on the main session, instead of opening a new one
+        let jobs: Vec<PoolJob> = match jobs.len() {
+            0 | 1 => jobs,
+            _ => self.run_transfer_pool(jobs, workers, connect, &mut items),
+        };
+        // Transfer files left over on the main session
+        let aborted: bool = self.transfer.cancel.is_cancelled();
+        for job in jobs.into_iter() {
+            if aborted || self.transfer.cancel.is_cancelled() {
+                break;
+            }
+            let result: Result<(), String> = match job.direction {
+                TransferDirection::Upload => self.filetransfer_send_file(
+                    &job.file,
+                    job.target.as_path(),
+                    job.file.name.clone(),
+                ),
+                TransferDirection::Download => self.filetransfer_recv_file(
+                    job.target.as_path(),
+                    &job.file,
+                    job.file.name.clone(),
+                ),
+            };
+            self.on_parallel_job_finished(&mut items, &job, result);
+        }
+        // Update queue; items which haven't been transferred at all stay pending
+        for (id, (files_left, error)) in items.into_iter() {
+            match (files_left, error) {
+                (_, Some(err)) => self.complete_queue_item(id, Err(err)),
+                (0, None) => self.complete_queue_item(id, Ok(())),
+                (_, None) => {}
+            }
+        }
+        // Reload directories
+        let wrkdir: PathBuf = self.local.wrkdir.clone();
+        self.local_scan(wrkdir.as_path());
+        let wrkdir: PathBuf = self.remote.wrkdir.clone();
+        self.remote_scan(wrkdir.as_path());
+        if aborted || self.transfer.cancel.is_cancelled() {
+            self.transfer.queue_aborted = true;
+            self.transfer.cancel.reset();
+        }
+    }
+
+    /// ### run_transfer_pool
+    ///
+    /// Run `jobs` on a transfer pool with `workers` sessions, showing the overall progress.
+    /// Returns the jobs which haven't been taken by any worker
+    fn run_transfer_pool(
+        &mut self,
+        jobs: Vec<PoolJob>,
+        workers: usize,
+        connect: Arc<Connector>,
+        items: &mut HashMap<usize, (usize, Option<String>)>,
+    ) -> Vec<PoolJob> {
+        let total_bytes: usize = jobs.iter().map(|x| x.file.size).sum();
+        let (files, sessions): (usize, usize) = (jobs.len(), workers.min(jobs.len()));
+        let mut text: String = format!("Transferring {} files on {} sessions...", files, sessions);
+        self.log(LogLevel::Info, text.as_str());
+        let mut done_files: usize = 0;
+        // Run pool
+        let pool: TransferPool = TransferPool::new(jobs, workers, &self.transfer_opts, connect);
+        let mut total_bytes_written: usize = 0;
+        let mut connection_failed: bool = false;
+        self.transfer.reset();
+        self.transfer.reset_batch(0, 0);
+        self.transfer.set_progress(0, total_bytes.max(1));
+        let mut last_progress_val: f64 = 0.0;
+        let progress_step: f64 = self.progress_redraw_step();
+        let mut last_input_event_fetch: Instant = Instant::now();
+        self.mount_progress_bar();
+        self.check_quiet_hours();
+        self.update_progress_bar(text.clone());
+        self.view();
+        loop {
+            // Handle input events (each 500ms)
+            if last_input_event_fetch.elapsed().as_millis() >= 500 {
+                self.read_input_event();
+                self.check_quiet_hours();
+                last_input_event_fetch = Instant::now();
+                if self.transfer.cancel.is_cancelled() {
+                    pool.abort();
+                }
+                // Pause or resume workers
+                if self.transfer.paused.is_some() != pool.is_paused() {
+                    match self.transfer.paused.is_some() {
+                        true => pool.pause(),
+                        false => pool.resume(),
+                    }
+                    self.update_progress_bar(text.clone());
+                    self.view();
+                }
+            }
+            match pool.recv_timeout(Duration::from_millis(100)) {
+                Ok(PoolEvent::Progress(bytes)) => {
+                    total_bytes_written += bytes as usize;
+                    self.transfer
+                        .set_progress(total_bytes_written, total_bytes.max(1));
+                    // Draw only if a significant progress has been made (performance improvement)
+                    if last_progress_val < self.transfer.progress - progress_step {
+                        self.update_progress_bar(text.clone());
+                        self.view();
+                        last_progress_val = self.transfer.progress;
+                    }
+                }
+                Ok(PoolEvent::Finished(job, result)) => {
+                    // Apply file mode to downloaded file
+                    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+                    if let (TransferDirection::Download, true, Some(pex)) = (
+                        job.direction,
+                        result.is_ok() && self.transfer_opts.preserve_permissions,
+                        job.file.unix_pex,
+                    ) {
+                        if let Err(err) = self
+                            .context
+                            .as_ref()
+                            .unwrap()
+                            .local
+                            .chmod(job.target.as_path(), pex)
+                        {
+                            self.log(
+                                LogLevel::Error,
+                                format!(
+                                    "Could not apply file mode {:?} to \"{}\": {}",
+                                    pex,
+                                    job.target.display(),
+                                    err
+                                )
+                                .as_ref(),
+                            );
+                        }
+                    }
+                    if result.is_ok() {
+                        done_files += 1;
+                        text = format!(
+                            "Transferring {} files on {} sessions ({} done)...",
+                            files, sessions, done_files
+                        );
+                    }
+                    // Workers don't report the duration of the single transfers
+                    let (local, remote): (&Path, &Path) = match job.direction {
+                        TransferDirection::Upload => {
+                            (job.file.abs_path.as_path(), job.target.as_path())
+                        }
+                        TransferDirection::Download => {
+                            (job.target.as_path(), job.file.abs_path.as_path())
+                        }
+                    };
+                    let size: usize = match result.is_ok() {
+                        true => job.file.size,
+                        false => 0,
+                    };
+                    self.record_transfer(
+                        job.direction,
+                        (local, remote),
+                        size,
+                        Duration::from_secs(0),
+                        &result,
+                    );
+                    self.on_parallel_job_finished(items, &job, result);
+                }
+                Ok(PoolEvent::Retrying(job, retry, err)) => {
+                    let policy: RetryPolicy = self.transfer_opts.retry;
+                    self.log(
+                        LogLevel::Warn,
+                        format!(
+                            "Transfer of \"{}\" failed: {}; retrying in {} seconds ({}/{})",
+                            job.file.abs_path.display(),
+                            err,
+                            fmt_millis(policy.delay(retry)),
+                            retry,
+                            policy.retries
+                        )
+                        .as_str(),
+                    );
+                }
+                Ok(PoolEvent::ConnectionFailed(err)) => {
+                    // Report it once; the other sessions take over the files
+                    if !connection_failed {
+                        self.log(
+                            LogLevel::Warn,
+                            format!("Could not open transfer session: {}", err).as_str(),
+                        );
+                        connection_failed = true;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+        self.umount_progress_bar();
+        pool.join()
+    }
+
+    /// ### on_parallel_job_finished
+    ///
+    /// Log the result of a file transferred by `filetransfer_parallel` and account it to its queue item
+    fn on_parallel_job_finished(
+        &mut self,
+        items: &mut HashMap<usize, (usize, Option<String>)>,
+        job: &PoolJob,
+        result: Result<(), String>,
+    ) {
+        match result {
+            Ok(_) => {
+                self.log(
+                    LogLevel::Info,
+                    format!(
+                        "Saved file \"{}\" to \"{}\"",
+                        job.file.abs_path.display(),
+                        job.target.display()
+                    )
+                    .as_str(),
+                );
+                let (local, remote): (&Path, &Path) = match job.direction {
+                    TransferDirection::Upload => {
+                        (job.file.abs_path.as_path(), job.target.as_path())
+                    }
+                    TransferDirection::Download => {
+                        (job.target.as_path(), job.file.abs_path.as_path())
+                    }
+                };
+                self.run_transfer_hooks(job.direction, local, remote);
+            }
+            Err(err) => {
+                self.log(LogLevel::Error, err.as_str());
+                if let Some((_, error)) = items.get_mut(&job.id) {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        if let Some((files_left, _)) = items.get_mut(&job.id) {
+            *files_left = files_left.saturating_sub(1);
+        }
+    }
+
+    /// ### run_transfer_hooks
+    ///
+    /// Run the hooks configured for the file transferred in `direction` between `local` and `remote`.
+    /// Hooks are matched against the name of the target; a failing hook is reported, but doesn't fail the transfer
+    fn run_transfer_hooks(&mut self, direction: TransferDirection, local: &Path, remote: &Path) {
+        let target: &Path = match direction {
+            TransferDirection::Upload => remote,
+            TransferDirection::Download => local,
+        };
+        let name: String = target
+            .file_name()
+            .map(|x| x.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let hooks: Vec<TransferHook> = self
+            .transfer_opts
+            .hooks(direction, name.as_str())
+            .cloned()
+            .collect();
+        for hook in hooks.iter() {
+            let command: String = hook.command(local, remote);
+            let result: Result<String, String> = match hook.is_remote() {
+                true => self
+                    .with_reconnect(|client| client.exec(command.as_str()))
+                    .map_err(|e| e.to_string()),
+                false => match shell::command(command.as_str()).output() {
+                    Ok(output) if output.status.success() => {
+                        Ok(String::from_utf8_lossy(&output.stdout).to_string())
+                    }
+                    Ok(output) => Err(format!(
+                        "{} ({})",
+                        String::from_utf8_lossy(&output.stderr).trim(),
+                        output.status
+                    )),
+                    Err(err) => Err(err.to_string()),
+                },
+            };
+            match result {
+                Ok(output) => self.log(
+                    LogLevel::Info,
+                    format!("Hook \"{}\" completed: {}", command, output.trim()).as_str(),
+                ),
+                Err(err) => self.log(
+                    LogLevel::Error,
+                    format!("Hook \"{}\" failed: {}", command, err).as_str(),
+                ),
+            }
+        }
+    }
+
+    /// ### plan_send
+    ///
+    /// Plan the upload of `entry` into `remote_dir` for the transfer pool:
+    /// directories are created on the remote, while the files to send are pushed to `jobs` as part of item `id`
+    fn plan_send(
+        &mut self,
+        id: usize,
+        entry: &FsEntry,
+        remote_dir: &Path,
+        jobs: &mut Vec<PoolJob>,
+    ) -> Result<(), String> {
+        let remote_path: PathBuf = remote_dir.join(entry.get_name());
+        if let Some(result) =
+            self.apply_symlink_policy(TransferDirection::Upload, entry, remote_path.as_path())
+        {
+            return result;
+        }
+        match entry {
+            FsEntry::File(file) => {
+                if let Some(target) = self.upload_target(file, remote_path.as_path()) {
+                    jobs.push(PoolJob {
+                        id,
+                        direction: TransferDirection::Upload,
+                        file: file.clone(),
+                        target,
+                        range: None,
+                    });
+                }
+            }
+            FsEntry::Directory(dir) => {
+                // Create directory on remote, unless it exists already
+                if !matches!(
+                    self.with_reconnect(|client| client.stat(remote_path.as_path())),
+                    Ok(FsEntry::Directory(_))
+                ) {
+                    self.with_reconnect(|client| client.mkdir(remote_path.as_path()))
+                        .map_err(|e| {
+                            format!(
+                                "Failed to create directory \"{}\": {}",
+                                remote_path.display(),
+                                e
+                            )
+                        })?;
+                    self.log(
                         LogLevel::Info,
+                        format!("Created directory \"{}\"", remote_path.display()).as_ref(),
+                    );
+                }
+                let entries: Vec<FsEntry> = self
+                    .context
+                    .as_ref()
+                    .unwrap()
+                    .local
+                    .scan_dir(dir.abs_path.as_path())
+                    .map_err(|e| {
+                        format!(
+                            "Could not scan directory \"{}\": {}",
+                            dir.abs_path.display(),
+                            e
+                        )
+                    })?;
+                for entry in entries.iter() {
+                    // Skip entries matching ignore rules or filters
+                    if self.skip_excluded(entry) {
+                        continue;
+                    }
+                    self.plan_send(id, entry, remote_path.as_path(), jobs)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// ### plan_recv
+    ///
+    /// Plan the download of `entry` into `local_dir` for the transfer pool:
+    /// directories are created locally, while the files to receive are pushed to `jobs` as part of item `id`
+    fn plan_recv(
+        &mut self,
+        id: usize,
+        entry: &FsEntry,
+        local_dir: &Path,
+        jobs: &mut Vec<PoolJob>,
+    ) -> Result<(), String> {
+        let local_path: PathBuf = local_dir.join(entry.get_name());
+        if let Some(result) =
+            self.apply_symlink_policy(TransferDirection::Download, entry, local_path.as_path())
+        {
+            return result;
+        }
+        match entry {
+            FsEntry::File(file) => {
+                if let Some(target) = self.download_target(file, local_path.as_path()) {
+                    jobs.push(PoolJob {
+                        id,
+                        direction: TransferDirection::Download,
+                        file: file.clone(),
+                        target,
+                        range: None,
+                    });
+                }
+            }
+            FsEntry::Directory(dir) => {
+                // Create directory on local
+                self.context
+                    .as_mut()
+                    .unwrap()
+                    .local
+                    .mkdir_ex(local_path.as_path(), true)
+                    .map_err(|e| {
+                        format!(
+                            "Failed to create directory \"{}\": {}",
+                            local_path.display(),
+                            e
+                        )
+                    })?;
+                // Apply file mode to directory
+                #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+                if let Some(pex) = dir
+                    .unix_pex
+                    .filter(|_| self.transfer_opts.preserve_permissions)
+                {
+                    if let Err(err) = self
+                        .context
+                        .as_ref()
+                        .unwrap()
+                        .local
+                        .chmod(local_path.as_path(), pex)
+                    {
+                        self.log(
+                            LogLevel::Error,
+                            format!(
+                                "Could not apply file mode {:?} to \"{}\": {}",
+                                pex,
+                                local_path.display(),
+                                err
+                            )
+                            .as_ref(),
+                        );
+                    }
+                }
+                self.log(
+                    LogLevel::Info,
+                    format!("Created directory \"{}\"", local_path.display()).as_ref(),
+                );
+                // Get device of dir, to detect mount points
+                let device: Option<u64> = match self.transfer_opts.one_file_system {
+                    true => self
+                        .with_reconnect(|client| client.device_id(dir.abs_path.as_path()))
+                        .unwrap_or(None),
+                    false => None,
+                };
+                let entries: Vec<FsEntry> = self
+                    .with_reconnect(|client| client.list_dir(dir.abs_path.as_path()))
+                    .map_err(|e| {
+                        format!(
+                            "Could not scan directory \"{}\": {}",
+                            dir.abs_path.display(),
+                            e
+                        )
+                    })?;
+                for entry in entries.iter() {
+                    // Skip entries matching ignore rules or filters
+                    if self.skip_excluded(entry) {
+                        continue;
+                    }
+                    // Skip directories on another file system
+                    if let (Some(device), FsEntry::Directory(subdir)) = (device, entry) {
+                        if !entry.is_symlink()
+                            && self
+                                .with_reconnect(|client| {
+                                    client.device_id(subdir.abs_path.as_path())
+                                })
+                                .unwrap_or(None)
+                                != Some(device)
+                        {
+                            self.log(
+                                LogLevel::Warn,
+                                format!(
+                                    "Skipped \"{}\": on another file system",
+                                    subdir.abs_path.display()
+                                )
+                                .as_str(),
+                            );
+                            continue;
+                        }
+                    }
+                    self.plan_recv(id, entry, local_path.as_path(), jobs)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// ### make_connector
+    ///
+    /// Make the function used by the workers of the transfer pool to open their own session,
+    /// with the connection parameters of the activity
+    pub(super) fn make_connector(&self) -> Option<Arc<Connector>> {
+        let ctx = self.context.as_ref().unwrap();
+        let params: FileTransferParams = ctx.ft_params.clone()?;
+        let key_storage: SshKeyStorage = Self::make_ssh_storage(ctx.config_client.as_ref());
+        let known_hosts: Option<PathBuf> = Self::known_hosts_path();
+        let network: NetworkOptions = Self::make_network_options(ctx.config_client.as_ref());
+        Some(Arc::new(move || {
+            let mut client: Box<dyn FileTransfer> = FileTransferBuilder::new(params.protocol)
+                .with_ssh_key_storage(key_storage.clone())
+                .with_known_hosts(known_hosts.clone())
+                .with_network_options(network.clone())
+                .with_ssh_algorithms(params.algorithms.clone())
+                .with_s3_endpoint(params.endpoint.clone(), params.region.clone())
+                .build();
+            client
+                .connect(
+                    params.address.clone(),
+                    params.port,
+                    params.username.clone(),
+                    params.password.as_ref().map(|x| x.expose().to_string()),
+                )
+                .map_err(|e| e.to_string())?;
+            Ok(client)
+        }))
+    }
+
+    /// ### finalize_stream
+    ///
+    /// Give `stream` back to the client once `bytes` have been transferred.
+    /// Errors are only logged, since the outcome of the transfer has already been established
+    fn finalize_stream(&mut self, stream: TransferStream, bytes: usize, status: TransferStatus) {
+        if let Err(err) =
+            self.client
+                .finalize_transfer(TransferOutcome::new(stream, bytes as u64, status))
+        {
+            self.log(
+                LogLevel::Warn,
+                format!("Could not finalize remote stream: \"{}\"", err).as_str(),
+            );
+        }
+    }
+
+    /// ### upload_target
+    ///
+    /// Returns the path `file` must be uploaded to, in case `remote` already exists, according to the overwrite policy of the file.
+    /// Returns None if the upload must be skipped
+    fn upload_target(&mut self, file: &FsFile, remote: &Path) -> Option<PathBuf> {
+        let policy: OverwritePolicy = self.transfer_opts.overwrite_policy(file.name.as_str());
+        if policy == OverwritePolicy::Overwrite && !self.transfer_opts.skip_identical {
+            return Some(remote.to_path_buf());
+        }
+        let existing: Option<(usize, SystemTime)> = self.remote_version(remote);
+        if self.is_identical(TransferDirection::Upload, file, remote, existing) {
+            return None;
+        }
+        self.resolve_conflict(
+            TransferDirection::Upload,
+            policy,
+            file,
+            remote,
+            existing,
+            |this, path| this.remote_version(path).is_some(),
+        )
+    }
+
+    /// ### download_target
+    ///
+    /// Returns the path `file` must be downloaded to, in case `local` already exists, according to the overwrite policy of the file.
+    /// Returns None if the download must be skipped
+    fn download_target(&mut self, file: &FsFile, local: &Path) -> Option<PathBuf> {
+        let policy: OverwritePolicy = self.transfer_opts.overwrite_policy(file.name.as_str());
+        let existing: Option<(usize, SystemTime)> = std::fs::metadata(local).ok().map(|x| {
+            (
+                x.len() as usize,
+                x.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+            )
+        });
+        if self.is_identical(TransferDirection::Download, file, local, existing) {
+            return None;
+        }
+        self.resolve_conflict(
+            TransferDirection::Download,
+            policy,
+            file,
+            local,
+            existing,
+            |_, path| path.exists(),
+        )
+    }
+
+    /// ### remote_version
+    ///
+    /// Returns size and modification time of the remote file at `path`, if it exists
+    fn remote_version(&mut self, path: &Path) -> Option<(usize, SystemTime)> {
+        self.with_reconnect(|client| client.stat(path))
+            .ok()
+            .map(|x| (x.get_size(), x.get_last_change_time()))
+    }
+
+    /// ### is_identical
+    ///
+    /// Returns whether the `target` of `file`, transferred in `direction`, already has the same content as `file`,
+    /// when identical files must be skipped. `existing` is the size and the modification time of the target,
+    /// if it exists: only targets with the same size as the source are hashed
+    fn is_identical(
+        &mut self,
+        direction: TransferDirection,
+        file: &FsFile,
+        target: &Path,
+        existing: Option<(usize, SystemTime)>,
+    ) -> bool {
+        if !self.transfer_opts.skip_identical || existing.map(|(size, _)| size) != Some(file.size) {
+            return false;
+        }
+        let (local, remote): (&Path, &Path) = match direction {
+            TransferDirection::Upload => (file.abs_path.as_path(), target),
+            TransferDirection::Download => (target, file.abs_path.as_path()),
+        };
+        // Local file first: its digest is likely cached
+        let digests: Result<(String, String), String> = self
+            .local_digest(local)
+            .and_then(|digest| self.remote_digest(remote).map(|x| (digest, x)));
+        match digests {
+            Ok((local, remote)) if local == remote => {
+                self.log(
+                    LogLevel::Info,
+                    format!("Skipped \"{}\": identical to the source", target.display()).as_str(),
+                );
+                true
+            }
+            Ok(_) => false,
+            Err(err) => {
+                self.log(
+                    LogLevel::Warn,
+                    format!(
+                        "Could not compare \"{}\" with its source: {}",
+                        target.display(),
+                        err
+                    )
+                    .as_str(),
+                );
+                false
+            }
+        }
+    }
+
+    /// ### remote_digest
+    ///
+    /// Get the SHA256 digest, as lowercase hex, of the remote file at `path`.
+    /// The file is hashed on the remote host with `sha256sum` if possible, otherwise it's read back
+    fn remote_digest(&mut self, path: &Path) -> Result<String, String> {
+        let cmd: String = format!("sha256sum {}", shell::quote_path(path));
+        if let Ok(output) = self.with_reconnect(|client| client.exec(cmd.as_str())) {
+            if let Some(digest) = output
+                .split_whitespace()
+                .next()
+                .filter(|x| x.len() == 64 && x.chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                return Ok(digest.to_lowercase());
+            }
+        }
+        let file: FsFile = match self.client.stat(path) {
+            Ok(FsEntry::File(file)) => file,
+            Ok(FsEntry::Directory(_)) => {
+                return Err(format!("\"{}\" is not a file", path.display()))
+            }
+            Err(err) => return Err(format!("Could not stat \"{}\": {}", path.display(), err)),
+        };
+        let mut rhnd = self
+            .client
+            .recv_file(&file)
+            .map_err(|e| format!("Could not read \"{}\": {}", path.display(), e))?;
+        let digest: std::io::Result<Vec<u8>> = sha256_reader(rhnd.as_mut());
+        let status: TransferStatus = match digest.as_ref() {
+            Ok(_) => TransferStatus::Completed,
+            Err(err) => TransferStatus::Failed(err.to_string()),
+        };
+        self.finalize_stream(TransferStream::Download(rhnd), file.size, status);
+        digest
+            .map(|x| hash_cache::to_hex(x.as_slice()))
+            .map_err(|e| format!("Could not read \"{}\": {}", path.display(), e))
+    }
+
+    /// ### resolve_conflict
+    ///
+    /// Decide where to transfer `file` in `direction`, given the version of the `target` which already exists (if any).
+    /// With the `Ask` policy, the user is asked what to do, unless a choice has been applied to all conflicts.
+    /// `exists` tells whether a path is already taken on the target side, to find a free name when renaming.
+    /// Returns None if the file must be skipped
+    fn resolve_conflict(
+        &mut self,
+        direction: TransferDirection,
+        policy: OverwritePolicy,
+        file: &FsFile,
+        target: &Path,
+        existing: Option<(usize, SystemTime)>,
+        exists: fn(&mut Self, &Path) -> bool,
+    ) -> Option<PathBuf> {
+        let source: (usize, SystemTime) = (file.size, file.last_change_time);
+        let existing: (usize, SystemTime) = match existing {
+            Some(existing) => existing,
+            None => return Some(target.to_path_buf()),
+        };
+        let choice: ConflictChoice = match policy {
+            OverwritePolicy::Ask => match self.transfer.conflict_all {
+                Some(choice) => choice,
+                None => self.ask_conflict(direction, target, source, existing),
+            },
+            policy if policy.skips(source, Some(existing)) => ConflictChoice::Skip,
+            _ => ConflictChoice::Overwrite,
+        };
+        let resolved: Option<PathBuf> = match choice {
+            ConflictChoice::Overwrite => Some(target.to_path_buf()),
+            ConflictChoice::OverwriteIfNewer
+                if !OverwritePolicy::OverwriteIfNewer.skips(source, Some(existing)) =>
+            {
+                Some(target.to_path_buf())
+            }
+            ConflictChoice::Skip | ConflictChoice::OverwriteIfNewer => None,
+            // Give up after a reasonable amount of names
+            ConflictChoice::Rename => (1..1000)
+                .map(|n| ConflictChoice::renamed(target, n))
+                .find(|path| !exists(self, path.as_path())),
+        };
+        match resolved.as_ref() {
+            None => self.log(
+                LogLevel::Info,
+                format!("Skipped \"{}\": file already exists", target.display()).as_str(),
+            ),
+            Some(path) if path != target => self.log(
+                LogLevel::Info,
+                format!(
+                    "\"{}\" already exists; saving file as \"{}\"",
+                    target.display(),
+                    path.display()
+                )
+                .as_str(),
+            ),
+            Some(_) => {}
+        }
+        resolved
+    }
+
+    /// ### ask_conflict
+    ///
+    /// Ask the user what to do with a file, transferred in `direction`, whose `target` already exists.
+    /// On uploads, the user can also choose whether to create missing remote directories for the rest of the run.
+    /// Blocks until the user answers; closing the popup skips the file
+    fn ask_conflict(
+        &mut self,
+        direction: TransferDirection,
+        target: &Path,
+        source: (usize, SystemTime),
+        existing: (usize, SystemTime),
+    ) -> ConflictChoice {
+        self.transfer.conflict_answer = None;
+        let create_dirs: Option<bool> = match direction {
+            TransferDirection::Upload => Some(self.create_remote_dirs()),
+            TransferDirection::Download => None,
+        };
+        self.mount_conflict(target, source, existing, create_dirs);
+        self.view();
+        while self.transfer.conflict_answer.is_none()
+            && self.view.get_props(COMPONENT_RADIO_CONFLICT).is_some()
+        {
+            if self.read_input_event() {
+                self.view();
+            }
+        }
+        self.umount_conflict();
+        self.view();
+        self.transfer
+            .conflict_answer
+            .take()
+            .unwrap_or(ConflictChoice::Skip)
+    }
+
+    /// ### ask_space
+    ///
+    /// Ask the user whether to transfer anyway, since the files won't fit in the space left.
+    /// Blocks until the user answers; closing the popup cancels the transfer
+    fn ask_space(&mut self, text: &str) -> bool {
+        self.transfer.space_answer = None;
+        self.mount_space(text);
+        self.view();
+        while self.transfer.space_answer.is_none()
+            && self.view.get_props(COMPONENT_RADIO_SPACE).is_some()
+        {
+            if self.read_input_event() {
+                self.view();
+            }
+        }
+        self.umount_space();
+        self.view();
+        self.transfer.space_answer.take().unwrap_or(false)
+    }
+
+    /// ### preflight
+    ///
+    /// Check whether `bytes` fit in the space left in `dir`, on the destination host of a transfer in `direction`.
+    /// If they don't, the user is asked whether to transfer anyway. Returns whether to go on with the transfer;
+    /// if the space left can't be told, the transfer goes on
+    fn preflight(&mut self, direction: TransferDirection, dir: &Path, bytes: usize) -> bool {
+        let space: Result<DiskSpace, String> = match direction {
+            TransferDirection::Download => DiskSpace::local(dir).map_err(|e| e.to_string()),
+            TransferDirection::Upload => self
+                .with_reconnect(|client| client.disk_space(dir))
+                .map_err(|e| e.to_string()),
+        };
+        let space: DiskSpace = match space {
+            Ok(space) => space,
+            Err(err) => {
+                self.log(
+                    LogLevel::Warn,
+                    format!(
+                        "Could not get the space left in \"{}\": {}",
+                        dir.display(),
+                        err
+                    )
+                    .as_str(),
+                );
+                return true;
+            }
+        };
+        let headroom: u64 = space.headroom();
+        if bytes as u64 <= headroom {
+            return true;
+        }
+        let msg: String = format!(
+            "{} to transfer, but only {} left in \"{}\" ({})",
+            ByteSize(bytes as u64),
+            ByteSize(headroom),
+            dir.display(),
+            space.filesystem
+        );
+        self.log(LogLevel::Warn, msg.as_str());
+        self.ask_space(msg.as_str())
+    }
+
+    /// ### wait_resume
+    ///
+    /// Keep handling input events while the transfer is paused, until it's resumed or aborted.
+    /// The stream is kept open meanwhile. Returns how long the transfer has been paused
+    fn wait_resume(&mut self, text: String) -> Duration {
+        let started: Instant = Instant::now();
+        self.update_progress_bar(text.clone());
+        self.view();
+        while self.transfer.paused.is_some() && !self.transfer.cancel.is_cancelled() {
+            if self.read_input_event() {
+                self.view();
+            }
+            self.check_quiet_hours();
+        }
+        self.update_progress_bar(text);
+        self.view();
+        started.elapsed()
+    }
+
+    /// ### local_batch_size
+    ///
+    /// Get the amount and the total size of the files `filetransfer_send` transfers for local `entry`;
+    /// the entries matching the ignore rules or the filters, and the symlinks which aren't followed are not counted
+    pub(super) fn local_batch_size(&self, entry: &FsEntry) -> (usize, usize) {
+        match entry {
+            FsEntry::File(file) => (1, file.size),
+            FsEntry::Directory(dir) => self
+                .context
+                .as_ref()
+                .unwrap()
+                .local
+                .scan_dir(dir.abs_path.as_path())
+                .unwrap_or_default()
+                .iter()
+                .filter(|x| {
+                    !self.transfer_opts.ignore.is_ignored(x)
+                        && !self.filters.is_filtered(x)
+                        && self.transfer_opts.symlinks.follows(x)
+                })
+                .map(|x| self.local_batch_size(x))
+                .fold((0, 0), |(files, bytes), (f, b)| (files + f, bytes + b)),
+        }
+    }
+
+    /// ### remote_batch_size
+    ///
+    /// Get the amount and the total size of the files `filetransfer_recv` transfers for remote `entry`;
+    /// the entries matching the ignore rules or the filters, and the symlinks which aren't followed are not counted.
+    /// While walking large trees, the wait popup reports the files found so far
+    fn remote_batch_size(&mut self, entry: &FsEntry) -> (usize, usize) {
+        let mut total: (usize, usize) = (0, 0);
+        let mut last_redraw: Instant = Instant::now();
+        self.iter_remote_batch_size(entry, &mut total, &mut last_redraw);
+        total
+    }
+
+    fn iter_remote_batch_size(
+        &mut self,
+        entry: &FsEntry,
+        total: &mut (usize, usize),
+        last_redraw: &mut Instant,
+    ) {
+        match entry {
+            FsEntry::File(file) => *total = (total.0 + 1, total.1 + file.size),
+            FsEntry::Directory(dir) => {
+                if last_redraw.elapsed() >= Duration::from_millis(500) {
+                    self.mount_wait(
                         format!(
-                            "Saved file \"{}\" to \"{}\" (took {} seconds; at {}/s)",
-                            local.abs_path.display(),
-                            remote.display(),
-                            fmt_millis(self.transfer.started.elapsed()),
-                            ByteSize(self.transfer.bytes_per_second()),
+                            "Scanning \"{}\"... {} files found ({})",
+                            dir.abs_path.display(),
+                            total.0,
+                            ByteSize(total.1 as u64)
                         )
-                        .as_ref(),
+                        .as_str(),
                     );
+                    self.view();
+                    *last_redraw = Instant::now();
                 }
-                Err(err) => {
-                    return Err(format!(
-                        "Failed to upload file \"{}\": {}",
-                        local.abs_path.display(),
-                        err
-                    ))
+                let entries: Vec<FsEntry> = self
+                    .with_reconnect(|client| client.list_dir(dir.abs_path.as_path()))
+                    .unwrap_or_default();
+                for entry in entries.iter() {
+                    if self.transfer_opts.ignore.is_ignored(entry)
+                        || self.filters.is_filtered(entry)
+                        || !self.transfer_opts.symlinks.follows(entry)
+                    {
+                        continue;
+                    }
+                    self.iter_remote_batch_size(entry, total, last_redraw);
                 }
-            },
-            Err(err) => {
-                return Err(format!(
-                    "Failed to open file \"{}\": {}",
-                    local.abs_path.display(),
-                    err
-                ))
             }
         }
-        Ok(())
     }
 
-    /// ### filetransfer_recv_file
+    /// ### skip_excluded
     ///
-    /// Receive file from remote and write it to local path
-    fn filetransfer_recv_file(
+    /// Returns whether `entry` must be skipped, because it matches the ignore rules or the transfer filters.
+    /// Skipped entries are logged
+    fn skip_excluded(&mut self, entry: &FsEntry) -> bool {
+        let reason: &str = if self.transfer_opts.ignore.is_ignored(entry) {
+            "matches ignore rules"
+        } else if self.filters.is_filtered(entry) {
+            "excluded by filters"
+        } else {
+            return false;
+        };
+        self.log(
+            LogLevel::Info,
+            format!("Skipped \"{}\": {}", entry.get_abs_path().display(), reason).as_str(),
+        );
+        true
+    }
+
+    /// ### apply_symlink_policy
+    ///
+    /// Apply the symlink policy to `entry`, to be transferred to `dest`.
+    /// Returns None if `entry` must be transferred as a regular entry, otherwise the outcome of the policy
+    fn apply_symlink_policy(
         &mut self,
-        local: &Path,
-        remote: &FsFile,
-        file_name: String,
-    ) -> Result<(), String> {
-        // Try to open local file
-        match self.context.as_ref().unwrap().local.open_file_write(local) {
-            Ok(mut local_file) => {
-                // Download file from remote
-                match self.client.recv_file(remote) {
-                    Ok(mut rhnd) => {
-                        let mut total_bytes_written: usize = 0;
-                        // Reset transfer states
-                        self.transfer.reset();
-                        // Write local file
-                        let mut last_progress_val: f64 = 0.0;
-                        let mut last_input_event_fetch: Instant = Instant::now();
-                        // Mount progress bar
-                        self.mount_progress_bar();
-                        // While the entire file hasn't been completely read,
-                        // Or filetransfer has been aborted
-                        while total_bytes_written < remote.size && !self.transfer.aborted {
-                            // Handle input events (each 500 ms)
-                            if last_input_event_fetch.elapsed().as_millis() >= 500 {
-                                // Read events
-                                self.read_input_event();
-                                // Reset instant
-                                last_input_event_fetch = Instant::now();
-                            }
-                            // Read till you can
-                            let mut buffer: [u8; 65536] = [0; 65536];
-                            match rhnd.read(&mut buffer) {
-                                Ok(bytes_read) => {
-                                    total_bytes_written += bytes_read;
-                                    if bytes_read == 0 {
-                                        continue;
-                                    } else {
-                                        let mut buf_start: usize = 0;
-                                        while buf_start < bytes_read {
-                                            // Write bytes
-                                            match local_file.write(&buffer[buf_start..bytes_read]) {
-                                                Ok(bytes) => buf_start += bytes,
-                                                Err(err) => {
-                                                    self.umount_progress_bar();
-                                                    return Err(format!(
-                                                        "Could not write local file: {}",
-                                                        err
-                                                    ));
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                                Err(err) => {
-                                    self.umount_progress_bar();
-                                    return Err(format!("Could not read remote file: {}", err));
-                                }
-                            }
-                            // Set progress
-                            self.transfer.set_progress(total_bytes_written, remote.size);
-                            // Draw only if a significant progress has been made (performance improvement)
-                            if last_progress_val < self.transfer.progress - 1.0 {
-                                // Draw
-                                self.update_progress_bar(format!("Downloading \"{}\"", file_name));
-                                self.view();
-                                last_progress_val = self.transfer.progress;
-                            }
-                        }
-                        // Umount progress bar
-                        self.umount_progress_bar();
-                        // Finalize stream
-                        if let Err(err) = self.client.on_recv(rhnd) {
-                            self.log(
-                                LogLevel::Warn,
-                                format!("Could not finalize remote stream: \"{}\"", err).as_str(),
-                            );
-                        }
-                        // Apply file mode to file
-                        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
-                        if let Some(pex) = remote.unix_pex {
-                            if let Err(err) = self.context.as_ref().unwrap().local.chmod(local, pex)
-                            {
-                                self.log(
-                                    LogLevel::Error,
-                                    format!(
-                                        "Could not apply file mode {:?} to \"{}\": {}",
-                                        pex,
-                                        local.display(),
-                                        err
-                                    )
-                                    .as_ref(),
-                                );
-                            }
-                        }
-                        // Log
+        direction: TransferDirection,
+        entry: &FsEntry,
+        dest: &Path,
+    ) -> Option<Result<(), String>> {
+        let policy: SymlinkPolicy = self.transfer_opts.symlinks;
+        if policy.follows(entry) {
+            return None;
+        }
+        let source: PathBuf = entry.get_abs_path();
+        match (policy, entry.get_link_target()) {
+            (SymlinkPolicy::Recreate, Some(target)) => {
+                let result: Result<(), String> = match direction {
+                    TransferDirection::Upload => self
+                        .with_reconnect(|client| client.symlink(dest, target.as_path()))
+                        .map_err(|e| e.to_string()),
+                    TransferDirection::Download => self.local_symlink(dest, target.as_path()),
+                };
+                match result {
+                    Ok(_) => {
                         self.log(
                             LogLevel::Info,
                             format!(
-                                "Saved file \"{}\" to \"{}\" (took {} seconds; at {}/s)",
-                                remote.abs_path.display(),
-                                local.display(),
-                                fmt_millis(self.transfer.started.elapsed()),
-                                ByteSize(self.transfer.bytes_per_second()),
+                                "Created symlink \"{}\" -> \"{}\"",
+                                dest.display(),
+                                target.display()
                             )
-                            .as_ref(),
+                            .as_str(),
                         );
+                        Some(Ok(()))
                     }
                     Err(err) => {
-                        return Err(format!(
-                            "Failed to download file \"{}\": {}",
-                            remote.abs_path.display(),
-                            err
-                        ))
+                        let err: String =
+                            format!("Could not create symlink \"{}\": {}", dest.display(), err);
+                        self.log_and_alert(LogLevel::Error, err.clone());
+                        Some(Err(err))
                     }
                 }
             }
-            Err(err) => {
-                return Err(format!(
-                    "Failed to open local file for write \"{}\": {}",
-                    local.display(),
-                    err
-                ))
+            (SymlinkPolicy::Skip, _) => {
+                self.log(
+                    LogLevel::Info,
+                    format!("Skipped \"{}\": symlink", source.display()).as_str(),
+                );
+                Some(Ok(()))
+            }
+            _ => {
+                let reason: &str = match entry.is_broken_link() {
+                    true => "broken symlink",
+                    false => "symlink to one of its parent directories",
+                };
+                self.log(
+                    LogLevel::Warn,
+                    format!("Skipped \"{}\": {}", source.display(), reason).as_str(),
+                );
+                Some(Ok(()))
             }
         }
-        Ok(())
+    }
+
+    /// ### local_symlink
+    ///
+    /// Create a symlink at local `path`, pointing to `target`
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn local_symlink(&self, path: &Path, target: &Path) -> Result<(), String> {
+        self.context
+            .as_ref()
+            .unwrap()
+            .local
+            .symlink(path, target)
+            .map_err(|e| e.to_string())
+    }
+
+    #[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+    fn local_symlink(&self, _path: &Path, _target: &Path) -> Result<(), String> {
+        Err(String::from("symlinks are not supported on this system"))
+    }
+
+    /// ### discard_partial
+    ///
+    /// Apply the partial files policy to `target`, which has been written only partially by an aborted transfer
+    fn discard_partial(&mut self, direction: TransferDirection, target: &Path) {
+        let policy: PartialFilePolicy = self.transfer_opts.partial_files;
+        match pool::discard_partial(self.client.as_mut(), direction, target, policy) {
+            Ok(Some(outcome)) => self.log(
+                LogLevel::Info,
+                format!("Aborted transfer: {}", outcome).as_str(),
+            ),
+            Ok(None) => self.log(
+                LogLevel::Warn,
+                format!(
+                    "Aborted transfer: kept partial file \"{}\"",
+                    target.display()
+                )
+                .as_str(),
+            ),
+            Err(err) => self.log(
+                LogLevel::Error,
+                format!("Aborted transfer: {}", err).as_str(),
+            ),
+        }
+    }
+
+    /// ### verify_remote_checksum
+    ///
+    /// Read back the uploaded file and compare its checksum with the expected one
+    fn verify_remote_checksum(&mut self, remote: &Path, expected: &[u8]) -> Result<(), String> {
+        let file: FsFile = match self.client.stat(remote) {
+            Ok(FsEntry::File(file)) => file,
+            Ok(FsEntry::Directory(_)) => {
+                return Err(format!("\"{}\" is not a file", remote.display()))
+            }
+            Err(err) => return Err(format!("Could not stat \"{}\": {}", remote.display(), err)),
+        };
+        let mut rhnd = self
+            .client
+            .recv_file(&file)
+            .map_err(|e| format!("Could not read back \"{}\": {}", remote.display(), e))?;
+        let digest: std::io::Result<Vec<u8>> = sha256_reader(rhnd.as_mut());
+        let status: TransferStatus = match digest.as_ref() {
+            Ok(_) => TransferStatus::Completed,
+            Err(err) => TransferStatus::Failed(err.to_string()),
+        };
+        self.finalize_stream(TransferStream::Download(rhnd), file.size, status);
+        match digest {
+            Ok(digest) if digest.as_slice() == expected => Ok(()),
+            Ok(_) => Err(format!("Checksum mismatch for \"{}\"", remote.display())),
+            Err(err) => Err(format!(
+                "Could not read back \"{}\": {}",
+                remote.display(),
+                err
+            )),
+        }
+    }
+
+    /// ### verify_local_checksum
+    ///
+    /// Read the downloaded file and compare its checksum with the expected one
+    fn verify_local_checksum(&self, local: &Path, expected: &[u8]) -> Result<(), String> {
+        let mut fhnd = self
+            .context
+            .as_ref()
+            .unwrap()
+            .local
+            .open_file_read(local)
+            .map_err(|e| format!("Could not read back \"{}\": {}", local.display(), e))?;
+        match sha256_reader(&mut fhnd) {
+            Ok(digest) if digest.as_slice() == expected => Ok(()),
+            Ok(_) => Err(format!("Checksum mismatch for \"{}\"", local.display())),
+            Err(err) => Err(format!(
+                "Could not read back \"{}\": {}",
+                local.display(),
+                err
+            )),
+        }
     }
 
     /// ### local_scan
@@ -636,7 +2941,7 @@ impl FileTransferActivity {
     ///
     /// Scan current remote directory
     pub(super) fn remote_scan(&mut self, path: &Path) {
-        match self.client.list_dir(path) {
+        match self.with_reconnect(|client| client.list_dir(path)) {
             Ok(files) => {
                 // Set files and sort (sorting is implicit)
                 self.remote.set_files(files);
@@ -671,6 +2976,8 @@ impl FileTransferActivity {
                 if push {
                     self.local.pushd(prev_dir.as_path())
                 }
+                // Synchronize remote explorer
+                self.sync_changedir(prev_dir.as_path(), path, true, push);
             }
             Err(err) => {
                 // Report err
@@ -683,10 +2990,20 @@ impl FileTransferActivity {
     }
 
     pub(super) fn remote_changedir(&mut self, path: &Path, push: bool) {
+        if !self.is_remote_jailed(path) {
+            self.log_and_alert(
+                LogLevel::Error,
+                format!(
+                    "Could not change working directory: \"{}\" is outside of the session root",
+                    path.display()
+                ),
+            );
+            return;
+        }
         // Get current directory
         let prev_dir: PathBuf = self.remote.wrkdir.clone();
         // Change directory
-        match self.client.as_mut().change_dir(path) {
+        match self.with_reconnect(|client| client.change_dir(path)) {
             Ok(_) => {
                 self.log(
                     LogLevel::Info,
@@ -700,6 +3017,8 @@ impl FileTransferActivity {
                 if push {
                     self.remote.pushd(prev_dir.as_path())
                 }
+                // Synchronize local explorer
+                self.sync_changedir(prev_dir.as_path(), path, false, push);
             }
             Err(err) => {
                 // Report err
@@ -711,6 +3030,103 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### sync_changedir
+    ///
+    /// If browsing is synchronized, replicate the change of working directory from `prev` to `next`
+    /// of the local (if `local`) or remote explorer on the other explorer.
+    /// If the equivalent directory doesn't exist on the other explorer, its working directory is kept
+    fn sync_changedir(&mut self, prev: &Path, next: &Path, local: bool, push: bool) {
+        if !self.browsing_sync {
+            return;
+        }
+        let other: PathBuf = match local {
+            true => self.remote.wrkdir.clone(),
+            false => self.local.wrkdir.clone(),
+        };
+        let target: PathBuf = match mirror_path(prev, next, other.as_path()) {
+            Some(target) => target,
+            None => {
+                self.log(
+                    LogLevel::Warn,
+                    format!(
+                        "Could not synchronize directory: \"{}\" has no parent directory",
+                        other.display()
+                    )
+                    .as_str(),
+                );
+                return;
+            }
+        };
+        if local && !self.is_remote_jailed(target.as_path()) {
+            self.log(
+                LogLevel::Warn,
+                format!(
+                    "Could not synchronize directory \"{}\": outside of the session root",
+                    target.display()
+                )
+                .as_str(),
+            );
+            return;
+        }
+        let result: Result<PathBuf, String> = match local {
+            true => self
+                .client
+                .as_mut()
+                .change_dir(target.as_path())
+                .map_err(|e| e.to_string()),
+            false => self
+                .context
+                .as_mut()
+                .unwrap()
+                .local
+                .change_wrkdir(target.as_path())
+                .map_err(|e| e.to_string()),
+        };
+        match result {
+            Ok(_) => {
+                self.log(
+                    LogLevel::Info,
+                    format!("Synchronized directory: {}", target.display()).as_str(),
+                );
+                let explorer = match local {
+                    true => {
+                        self.remote_scan(target.as_path());
+                        &mut self.remote
+                    }
+                    false => {
+                        self.local_scan(target.as_path());
+                        &mut self.local
+                    }
+                };
+                explorer.wrkdir = target;
+                if push {
+                    explorer.pushd(other.as_path());
+                }
+            }
+            Err(err) => {
+                self.log(
+                    LogLevel::Warn,
+                    format!(
+                        "Could not synchronize directory \"{}\": {}",
+                        target.display(),
+                        err
+                    )
+                    .as_str(),
+                );
+            }
+        }
+    }
+
+    /// ### is_remote_jailed
+    ///
+    /// Returns whether the remote explorer can enter `path`, according to the session root
+    pub(super) fn is_remote_jailed(&self, path: &Path) -> bool {
+        match self.remote_root.as_ref() {
+            Some(root) => is_jailed(path, root.as_path()),
+            None => true,
+        }
+    }
+
     /// ### edit_local_file
     ///
     /// Edit a file on localhost
@@ -769,7 +3185,7 @@ impl FileTransferActivity {
     /// Edit file on remote host
     pub(super) fn edit_remote_file(&mut self, file: &FsFile) -> Result<(), String> {
         // Create temp file
-        let tmpfile: tempfile::NamedTempFile = match tempfile::NamedTempFile::new() {
+        let tmpfile: tempfile::NamedTempFile = match self.staging().tempfile() {
             Ok(f) => f,
             Err(err) => {
                 return Err(format!("Could not create temporary file: {}", err));
@@ -854,3 +3270,359 @@ impl FileTransferActivity {
         Ok(())
     }
 }
+
+/// ### stage_compressed
+///
+/// Compress `local` into a temporary file of `staging`, feeding `hasher` with the uncompressed data.
+/// Returns the temporary file, the entry to upload in place of `local` and the size of the uncompressed data
+fn stage_compressed(
+    staging: &Staging,
+    local: &FsFile,
+    mut hasher: Option<&mut Sha256>,
+) -> std::io::Result<(NamedTempFile, FsFile, u64)> {
+    let mut tmpfile: NamedTempFile = staging.tempfile()?;
+    let mut reader: std::fs::File = std::fs::File::open(local.abs_path.as_path())?;
+    let (bytes_read, bytes_written): (u64, u64) =
+        GzipBuffer::compress_stream(&mut reader, tmpfile.as_file_mut(), |chunk| {
+            if let Some(hasher) = hasher.as_mut() {
+                hasher.update(chunk);
+            }
+        })?;
+    let compressed: FsFile = FsFile {
+        abs_path: tmpfile.path().to_path_buf(),
+        size: bytes_written as usize,
+        ..local.clone()
+    };
+    Ok((tmpfile, compressed, bytes_read))
+}
+
+/// ### sha256_reader
+///
+/// Calculate the SHA256 digest of the data read from `reader`
+fn sha256_reader(reader: &mut dyn Read) -> std::io::Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    let mut buffer: [u8; 65536] = [0; 65536];
+    loop {
+        match reader.read(&mut buffer)? {
+            0 => break,
+            bytes_read => hasher.update(&buffer[..bytes_read]),
+        }
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::fixtures::SshServer;
+    use crate::filetransfer::memory_transfer::MemoryFileTransfer;
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    use crate::filetransfer::scp_transfer::ScpFileTransfer;
+    use crate::system::hash_cache::HashCache;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_ui_activities_filetransfer_upload_target() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let mut activity: FileTransferActivity = memory_activity(tmpdir.path());
+        let target: &Path = Path::new("/notes.txt");
+        let file: FsFile = local_file(tmpdir.path(), "notes.txt", b"world", SystemTime::now());
+        // Overwrite
+        assert_eq!(
+            activity.upload_target(&file, target),
+            Some(target.to_path_buf())
+        );
+        // Skip
+        activity.transfer_opts.overwrite = OverwritePolicy::Skip;
+        assert_eq!(activity.upload_target(&file, target), None);
+        assert_eq!(
+            activity.log_records.front().unwrap().msg.as_str(),
+            "Skipped \"/notes.txt\": file already exists"
+        );
+        assert_eq!(
+            activity.upload_target(&file, Path::new("/new.txt")),
+            Some(PathBuf::from("/new.txt"))
+        );
+        // Skip same size
+        activity.transfer_opts.overwrite = OverwritePolicy::SkipSameSize;
+        assert_eq!(activity.upload_target(&file, target), None);
+        let longer: FsFile = local_file(tmpdir.path(), "notes.txt", b"world!", SystemTime::now());
+        assert_eq!(
+            activity.upload_target(&longer, target),
+            Some(target.to_path_buf())
+        );
+        // Overwrite if newer
+        activity.transfer_opts.overwrite = OverwritePolicy::OverwriteIfNewer;
+        let older: FsFile =
+            local_file(tmpdir.path(), "notes.txt", b"world", SystemTime::UNIX_EPOCH);
+        assert_eq!(activity.upload_target(&older, target), None);
+        let newer: FsFile = local_file(
+            tmpdir.path(),
+            "notes.txt",
+            b"world",
+            SystemTime::now() + Duration::from_secs(3600),
+        );
+        assert_eq!(
+            activity.upload_target(&newer, target),
+            Some(target.to_path_buf())
+        );
+        // Ask; the choice made for all the conflicts applies
+        activity.transfer_opts.overwrite = OverwritePolicy::Ask;
+        activity.transfer.conflict_all = Some(ConflictChoice::Rename);
+        assert_eq!(
+            activity.upload_target(&file, target),
+            Some(PathBuf::from("/notes (1).txt"))
+        );
+        assert_eq!(
+            activity.log_records.front().unwrap().msg.as_str(),
+            "\"/notes.txt\" already exists; saving file as \"/notes (1).txt\""
+        );
+        activity.transfer.conflict_all = Some(ConflictChoice::Skip);
+        assert_eq!(activity.upload_target(&file, target), None);
+    }
+
+    #[test]
+    fn test_ui_activities_filetransfer_is_identical() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let mut activity: FileTransferActivity = memory_activity(tmpdir.path());
+        let target: &Path = Path::new("/notes.txt");
+        let same: FsFile = local_file(tmpdir.path(), "same.txt", b"hello", SystemTime::now());
+        let other: FsFile = local_file(tmpdir.path(), "other.txt", b"world", SystemTime::now());
+        let existing: Option<(usize, SystemTime)> = activity.remote_version(target);
+        assert_eq!(existing.map(|(size, _)| size), Some(5));
+        // Disabled
+        assert!(!activity.is_identical(TransferDirection::Upload, &same, target, existing));
+        activity.transfer_opts.skip_identical = true;
+        assert!(activity.is_identical(TransferDirection::Upload, &same, target, existing));
+        assert_eq!(
+            activity.log_records.front().unwrap().msg.as_str(),
+            "Skipped \"/notes.txt\": identical to the source"
+        );
+        // Same size, different content
+        assert!(!activity.is_identical(TransferDirection::Upload, &other, target, existing));
+        // Not existing
+        assert!(!activity.is_identical(TransferDirection::Upload, &same, target, None));
+        // Identical files are skipped whatever the overwrite policy
+        assert_eq!(activity.upload_target(&same, target), None);
+        assert_eq!(
+            activity.upload_target(&other, target),
+            Some(target.to_path_buf())
+        );
+        // Remote file can't be read
+        assert!(!activity.is_identical(
+            TransferDirection::Upload,
+            &same,
+            Path::new("/missing.txt"),
+            existing
+        ));
+        assert!(activity
+            .log_records
+            .front()
+            .unwrap()
+            .msg
+            .starts_with("Could not compare \"/missing.txt\" with its source"));
+    }
+
+    #[test]
+    fn test_ui_activities_filetransfer_resolve_conflict() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let mut activity: FileTransferActivity = memory_activity(tmpdir.path());
+        let remote: FsFile = match activity.client.stat(Path::new("/notes.txt")).unwrap() {
+            FsEntry::File(file) => file,
+            FsEntry::Directory(_) => panic!("Expected a file"),
+        };
+        let local: PathBuf = tmpdir.path().join("notes.txt");
+        // Target doesn't exist
+        assert_eq!(
+            activity.resolve_conflict(
+                TransferDirection::Download,
+                OverwritePolicy::Skip,
+                &remote,
+                local.as_path(),
+                None,
+                |_, path| path.exists(),
+            ),
+            Some(local.clone())
+        );
+        // Rename to the first free name
+        std::fs::write(local.as_path(), b"hello").unwrap();
+        std::fs::write(tmpdir.path().join("notes (1).txt"), b"hello").unwrap();
+        activity.transfer.conflict_all = Some(ConflictChoice::Rename);
+        assert_eq!(
+            activity.resolve_conflict(
+                TransferDirection::Download,
+                OverwritePolicy::Ask,
+                &remote,
+                local.as_path(),
+                Some((5, SystemTime::now())),
+                |_, path| path.exists(),
+            ),
+            Some(tmpdir.path().join("notes (2).txt"))
+        );
+        // Overwrite if newer, chosen for all the conflicts
+        activity.transfer.conflict_all = Some(ConflictChoice::OverwriteIfNewer);
+        assert_eq!(
+            activity.resolve_conflict(
+                TransferDirection::Download,
+                OverwritePolicy::Ask,
+                &remote,
+                local.as_path(),
+                Some((5, SystemTime::UNIX_EPOCH)),
+                |_, path| path.exists(),
+            ),
+            Some(local.clone())
+        );
+        assert_eq!(
+            activity.resolve_conflict(
+                TransferDirection::Download,
+                OverwritePolicy::Ask,
+                &remote,
+                local.as_path(),
+                Some((5, SystemTime::now() + Duration::from_secs(3600))),
+                |_, path| path.exists(),
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_ui_activities_filetransfer_with_reconnect() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let mut activity: FileTransferActivity = memory_activity(tmpdir.path());
+        let mut calls: usize = 0;
+        assert_eq!(
+            activity
+                .with_reconnect(|client| {
+                    calls += 1;
+                    client.pwd()
+                })
+                .unwrap(),
+            PathBuf::from("/")
+        );
+        assert_eq!(calls, 1);
+        // Errors are returned as they are
+        let mut calls: usize = 0;
+        let err: FileTransferError = activity
+            .with_reconnect(|client| {
+                calls += 1;
+                client.stat(Path::new("/missing.txt"))
+            })
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.kind(),
+            FileTransferErrorType::NoSuchFileOrDirectory
+        ));
+        assert_eq!(calls, 1);
+        // Session dropped; it can't be restored without connection params
+        assert!(activity.client.disconnect().is_ok());
+        let mut calls: usize = 0;
+        let err: FileTransferError = activity
+            .with_reconnect(|client| {
+                calls += 1;
+                client.pwd()
+            })
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.kind(),
+            FileTransferErrorType::UninitializedSession
+        ));
+        assert_eq!(calls, 1);
+        assert!(!activity.client.is_connected());
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_ui_activities_filetransfer_stage_compressed() {
+        let tmpdir: TempDir = TempDir::new().unwrap();
+        let data: Vec<u8> = "GET /index.html HTTP/1.1 200\n".repeat(4096).into_bytes();
+        let local: FsFile = local_file(tmpdir.path(), "access.log", &data, SystemTime::now());
+        // Stage
+        let mut hasher: Sha256 = Sha256::new();
+        let (tmpfile, compressed, uncompressed): (NamedTempFile, FsFile, u64) = stage_compressed(
+            &Staging::new(Some(tmpdir.path())),
+            &local,
+            Some(&mut hasher),
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(uncompressed, data.len() as u64);
+        assert_eq!(
+            hasher.finalize().to_vec(),
+            sha256_reader(&mut data.as_slice()).unwrap()
+        );
+        assert_eq!(compressed.abs_path.as_path(), tmpfile.path());
+        assert_eq!(compressed.name.as_str(), "access.log");
+        assert_eq!(
+            compressed.size as u64,
+            tmpfile.as_file().metadata().unwrap().len()
+        );
+        assert!(compressed.size < data.len());
+        // SCP sends the size before the data: upload the staged file, then decompress it
+        let server: SshServer = SshServer::start(MemoryFileTransfer::demo()).ok().unwrap();
+        let mut client: ScpFileTransfer = ScpFileTransfer::new(SshKeyStorage::empty());
+        assert!(client
+            .connect(
+                server.address(),
+                server.port(),
+                Some(String::from("omar")),
+                Some(String::from("secret"))
+            )
+            .is_ok());
+        let target: PathBuf = server.home().join("access.log");
+        let dest: PathBuf = TransferOptions::compressed_path(target.as_path());
+        let mut writer: Box<dyn Write> =
+            client.send_file(&compressed, dest.as_path()).ok().unwrap();
+        let bytes: u64 = std::io::copy(&mut tmpfile.reopen().unwrap(), &mut writer).unwrap();
+        assert_eq!(bytes, compressed.size as u64);
+        assert!(client
+            .finalize_transfer(TransferOutcome::completed(
+                TransferStream::Upload(writer),
+                bytes
+            ))
+            .is_ok());
+        assert!(client.gunzip(dest.as_path(), target.as_path()).is_ok());
+        assert_eq!(std::fs::read(target.as_path()).unwrap(), data);
+        assert!(!dest.exists());
+        assert!(client.disconnect().is_ok());
+    }
+
+    fn memory_activity(tmpdir: &Path) -> FileTransferActivity {
+        let mut client: MemoryFileTransfer = MemoryFileTransfer::new();
+        client.add_file(Path::new("/notes.txt"), b"hello");
+        assert!(client
+            .connect(String::from("memory"), 22, None, None)
+            .is_ok());
+        let mut activity: FileTransferActivity =
+            FileTransferActivity::build(Box::new(client), None);
+        // Keep history, queue journal and digests out of the configuration directory
+        activity.history = None;
+        activity.journal = None;
+        activity.hash_cache = Some(HashCache::load(tmpdir.join("hashes.json").as_path()));
+        activity
+    }
+
+    fn local_file(dir: &Path, name: &str, data: &[u8], mtime: SystemTime) -> FsFile {
+        let path: PathBuf = dir.join(name);
+        std::fs::write(path.as_path(), data).unwrap();
+        FsFile {
+            name: name.to_string(),
+            abs_path: path,
+            last_change_time: mtime,
+            last_access_time: mtime,
+            creation_time: mtime,
+            size: data.len(),
+            ftype: None,
+            readonly: false,
+            symlink: None,
+            broken_link: None,
+            user: None,
+            group: None,
+            unix_pex: None,
+        }
+    }
+}
@@ -29,24 +29,40 @@
 extern crate bytesize;
 // locals
 use super::{
-    FileExplorerTab, FileTransferActivity, LogLevel, COMPONENT_EXPLORER_FIND,
-    COMPONENT_EXPLORER_LOCAL, COMPONENT_EXPLORER_REMOTE, COMPONENT_INPUT_COPY,
-    COMPONENT_INPUT_EXEC, COMPONENT_INPUT_FIND, COMPONENT_INPUT_GOTO, COMPONENT_INPUT_MKDIR,
-    COMPONENT_INPUT_NEWFILE, COMPONENT_INPUT_RENAME, COMPONENT_INPUT_SAVEAS,
-    COMPONENT_LIST_FILEINFO, COMPONENT_LOG_BOX, COMPONENT_PROGRESS_BAR, COMPONENT_RADIO_DELETE,
-    COMPONENT_RADIO_DISCONNECT, COMPONENT_RADIO_QUIT, COMPONENT_RADIO_SORTING,
-    COMPONENT_TEXT_ERROR, COMPONENT_TEXT_FATAL, COMPONENT_TEXT_HELP,
+    ConnectionHealth, FileExplorerTab, FileTransferActivity, FindSelection, LogLevel, ProtectedOp,
+    COMPONENT_EXPLORER_ARCHIVE, COMPONENT_EXPLORER_FIND, COMPONENT_EXPLORER_LOCAL,
+    COMPONENT_EXPLORER_REMOTE, COMPONENT_INPUT_ARCHIVE_ADD, COMPONENT_INPUT_AUTH_PROMPT,
+    COMPONENT_INPUT_CHMOD, COMPONENT_INPUT_CLEANUP, COMPONENT_INPUT_CONFIRM_PHRASE,
+    COMPONENT_INPUT_COPY, COMPONENT_INPUT_EXEC, COMPONENT_INPUT_FILTERS, COMPONENT_INPUT_FIND,
+    COMPONENT_INPUT_GOTO, COMPONENT_INPUT_HISTORY_SEARCH, COMPONENT_INPUT_LOG_SEARCH,
+    COMPONENT_INPUT_MKDIR, COMPONENT_INPUT_NEWFILE, COMPONENT_INPUT_QUEUE_EXPORT,
+    COMPONENT_INPUT_QUEUE_IMPORT, COMPONENT_INPUT_RENAME, COMPONENT_INPUT_REPORT,
+    COMPONENT_INPUT_SAVEAS, COMPONENT_INPUT_SAVE_SEARCH, COMPONENT_INPUT_SCHEDULE,
+    COMPONENT_LIST_BATCH_PLAN, COMPONENT_LIST_FILEINFO, COMPONENT_LIST_HISTORY,
+    COMPONENT_LIST_JOBS, COMPONENT_LIST_PINS, COMPONENT_LIST_QUEUE, COMPONENT_LIST_SAVED_SEARCHES,
+    COMPONENT_LIST_TRANSFER_HISTORY, COMPONENT_LIST_UPLOAD_PLAN, COMPONENT_LOG_BOX,
+    COMPONENT_PROGRESS_BAR, COMPONENT_PROGRESS_BAR_TOTAL, COMPONENT_RADIO_CONFLICT,
+    COMPONENT_RADIO_CONFLICT_ALL, COMPONENT_RADIO_CONFLICT_MKDIRS, COMPONENT_RADIO_DELETE,
+    COMPONENT_RADIO_DISCONNECT, COMPONENT_RADIO_HOST_KEY, COMPONENT_RADIO_MOVE,
+    COMPONENT_RADIO_PURGE, COMPONENT_RADIO_QUIT, COMPONENT_RADIO_RECONNECT,
+    COMPONENT_RADIO_RECOVERY, COMPONENT_RADIO_SORTING, COMPONENT_RADIO_SPACE, COMPONENT_TEXT_ERROR,
+    COMPONENT_TEXT_FATAL, COMPONENT_TEXT_HELP,
 };
-use crate::fs::explorer::FileSorting;
+use crate::fs::archive::ArchiveFormat;
+use crate::fs::explorer::{FileExplorer, FileSorting};
+use crate::fs::queue::{BatchPlan, Priority, QueueItemState, TransferDirection};
+use crate::fs::transfer::{ConflictChoice, OverwritePolicy};
 use crate::fs::FsEntry;
+use crate::system::transfer_history::{HistoryRecord, TransferResult};
 use crate::ui::activities::keymap::*;
-use crate::ui::layout::props::{
-    PropValue, PropsBuilder, TableBuilder, TextParts, TextSpan, TextSpanBuilder,
-};
+use crate::ui::layout::props::{PropValue, TableBuilder, TextParts, TextSpan, TextSpanBuilder};
 use crate::ui::layout::{Msg, Payload};
+use crate::ui::tabs::TabBar;
+use crate::utils::fmt::{fmt_host, fmt_millis, fmt_time};
 // externals
 use bytesize::ByteSize;
 use std::path::{Path, PathBuf};
+use std::time::Duration;
 use tui::style::Color;
 
 impl FileTransferActivity {
@@ -100,6 +116,12 @@ impl FileTransferActivity {
                                         }
                                         _ => None,
                                     },
+                                    None if ArchiveFormat::from_path(file.abs_path.as_path())
+                                        .is_some() =>
+                                    {
+                                        self.action_open_archive(&FsEntry::File(file.clone()));
+                                        self.update_archive_list()
+                                    }
                                     None => None,
                                 }
                             }
@@ -116,7 +138,11 @@ impl FileTransferActivity {
                         let file: FsEntry = self.get_local_file_entry().unwrap().clone();
                         let name: String = file.get_name().to_string();
                         // Call upload; pass realfile, keep link name
-                        self.filetransfer_send(&file.get_realfile(), wrkdir.as_path(), Some(name));
+                        let _ = self.filetransfer_send(
+                            &file.get_realfile(),
+                            wrkdir.as_path(),
+                            Some(name),
+                        );
                         self.update_remote_filelist()
                     } else {
                         None
@@ -131,7 +157,11 @@ impl FileTransferActivity {
                 (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_I) => {
                     let file: Option<FsEntry> = self.get_local_file_entry().cloned();
                     if let Some(file) = file {
-                        self.mount_file_info(&file);
+                        // Calculate directory size in background
+                        if file.is_dir() {
+                            self.action_local_disk_usage(&file);
+                        }
+                        self.mount_file_info(&file, None);
                     }
                     None
                 }
@@ -209,6 +239,12 @@ impl FileTransferActivity {
                                             _ => None,
                                         }
                                     }
+                                    None if ArchiveFormat::from_path(file.abs_path.as_path())
+                                        .is_some() =>
+                                    {
+                                        self.action_open_archive(&FsEntry::File(file.clone()));
+                                        self.update_archive_list()
+                                    }
                                     None => None,
                                 }
                             }
@@ -224,7 +260,11 @@ impl FileTransferActivity {
                         let name: String = file.get_name().to_string();
                         // Call upload; pass realfile, keep link name
                         let wrkdir: PathBuf = self.local.wrkdir.clone();
-                        self.filetransfer_recv(&file.get_realfile(), wrkdir.as_path(), Some(name));
+                        let _ = self.filetransfer_recv(
+                            &file.get_realfile(),
+                            wrkdir.as_path(),
+                            Some(name),
+                        );
                         self.update_local_filelist()
                     } else {
                         None
@@ -247,7 +287,7 @@ impl FileTransferActivity {
                 (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_I) => {
                     let file: Option<FsEntry> = self.get_remote_file_entry().cloned();
                     if let Some(file) = file {
-                        self.mount_file_info(&file);
+                        self.mount_file_info(&file, None);
                     }
                     None
                 }
@@ -325,11 +365,57 @@ impl FileTransferActivity {
                     self.mount_help();
                     None
                 }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_J)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_J) => {
+                    self.mount_pins();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_S)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_S) => {
+                    self.mount_saved_searches();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_R)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_R) => {
+                    self.mount_transfer_history();
+                    None
+                }
+                // -- tabs
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_T)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_T) => {
+                    self.action_new_tab();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_N)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_N) => {
+                    self.action_switch_tab(1);
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_P)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_P) => {
+                    self.action_switch_tab(-1);
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_K)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_K) => {
+                    self.mount_report_input();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_M)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_M) => {
+                    self.action_enqueue();
+                    None
+                }
                 (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_N)
                 | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_N) => {
                     self.mount_newfile();
                     None
                 }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_P)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_P) => {
+                    self.action_toggle_pin();
+                    None
+                }
                 (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_Q)
                 | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_Q)
                 | (COMPONENT_LOG_BOX, &MSG_KEY_CHAR_Q) => {
@@ -349,6 +435,79 @@ impl FileTransferActivity {
                     self.mount_saveas();
                     None
                 }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_T)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_T) => {
+                    self.mount_queue();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_Y)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_Y) => {
+                    self.browsing_sync = !self.browsing_sync;
+                    let msg: &str = match self.browsing_sync {
+                        true => "Synchronized browsing enabled",
+                        false => "Synchronized browsing disabled",
+                    };
+                    self.log(LogLevel::Info, msg);
+                    self.update_status_bar()
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_W) => {
+                    self.action_toggle_watch();
+                    self.update_status_bar()
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_F)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_F) => {
+                    self.mount_transfer_filters();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_X)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_X) => {
+                    self.mount_radio_move();
+                    None
+                }
+                (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_E) => {
+                    self.mount_radio_purge();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CTRL_D)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CTRL_D) => {
+                    self.mount_cleanup_input();
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_W) => {
+                    self.action_local_goto_link_target();
+                    self.update_local_filelist()
+                }
+                (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_W) => {
+                    self.action_remote_goto_link_target();
+                    self.update_remote_filelist()
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_Z) => {
+                    if let Some(entry) = self.get_local_file_entry() {
+                        let mode: Option<(u8, u8, u8)> = entry.get_unix_pex();
+                        self.mount_chmod(mode);
+                    }
+                    None
+                }
+                (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_Z) => {
+                    if let Some(entry) = self.get_remote_file_entry() {
+                        let mode: Option<(u8, u8, u8)> = entry.get_unix_pex();
+                        self.mount_chmod(mode);
+                    }
+                    None
+                }
+                (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_V)
+                | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_V) => {
+                    match self.remote_root.is_some() {
+                        true => {
+                            self.relative_paths = !self.relative_paths;
+                            self.update_remote_filelist()
+                        }
+                        false => {
+                            self.log(LogLevel::Warn, "Session root is not set");
+                            None
+                        }
+                    }
+                }
                 (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_CHAR_X)
                 | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_CHAR_X) => {
                     // Mount exec
@@ -392,6 +551,46 @@ impl FileTransferActivity {
                         _ => None,
                     }
                 }
+                (COMPONENT_EXPLORER_FIND, &MSG_KEY_CHAR_M) => {
+                    if let Some(Payload::Unsigned(idx)) =
+                        self.view.get_value(COMPONENT_EXPLORER_FIND)
+                    {
+                        self.found_selection.toggle(idx);
+                    }
+                    self.update_find_list()
+                }
+                (COMPONENT_EXPLORER_FIND, &MSG_KEY_CHAR_A) => {
+                    let len: usize = self
+                        .found
+                        .as_ref()
+                        .map(|x| x.iter_files().count())
+                        .unwrap_or(0);
+                    self.found_selection.toggle_all(len);
+                    self.update_find_list()
+                }
+                (COMPONENT_EXPLORER_FIND, &MSG_KEY_CTRL_S) => {
+                    self.mount_save_search();
+                    None
+                }
+                (COMPONENT_EXPLORER_FIND, &MSG_KEY_CHAR_K) => {
+                    self.found_selection.keep_tree = !self.found_selection.keep_tree;
+                    self.update_find_list()
+                }
+                (COMPONENT_EXPLORER_FIND, &MSG_KEY_CHAR_T) => {
+                    if let Some(Payload::Unsigned(idx)) =
+                        self.view.get_value(COMPONENT_EXPLORER_FIND)
+                    {
+                        self.action_find_enqueue(idx);
+                    }
+                    self.update_find_list();
+                    // Reload files
+                    match self.tab {
+                        // NOTE: swapped by purpose
+                        FileExplorerTab::FindLocal => self.update_remote_filelist(),
+                        FileExplorerTab::FindRemote => self.update_local_filelist(),
+                        _ => None,
+                    }
+                }
                 (COMPONENT_EXPLORER_FIND, &MSG_KEY_SPACE) => {
                     // Get entry
                     match self.view.get_value(COMPONENT_EXPLORER_FIND) {
@@ -408,6 +607,54 @@ impl FileTransferActivity {
                         _ => None,
                     }
                 }
+                // -- archive explorer
+                (COMPONENT_EXPLORER_ARCHIVE, &MSG_KEY_ESC) => {
+                    self.umount_archive();
+                    self.archive = None;
+                    None
+                }
+                (COMPONENT_EXPLORER_ARCHIVE, Msg::OnSubmit(Payload::Unsigned(idx))) => {
+                    let entry: Option<FsEntry> = self
+                        .archive
+                        .as_ref()
+                        .and_then(|x| x.explorer.get(*idx))
+                        .cloned();
+                    if let Some(FsEntry::Directory(dir)) = entry {
+                        self.action_archive_changedir(dir.abs_path.as_path());
+                        self.update_archive_list()
+                    } else {
+                        None
+                    }
+                }
+                (COMPONENT_EXPLORER_ARCHIVE, &MSG_KEY_BACKSPACE)
+                | (COMPONENT_EXPLORER_ARCHIVE, &MSG_KEY_CHAR_U) => {
+                    let parent: Option<PathBuf> = self
+                        .archive
+                        .as_ref()
+                        .and_then(|x| x.explorer.wrkdir.parent().map(|p| p.to_path_buf()));
+                    if let Some(parent) = parent {
+                        self.action_archive_changedir(parent.as_path());
+                    }
+                    self.update_archive_list()
+                }
+                (COMPONENT_EXPLORER_ARCHIVE, &MSG_KEY_SPACE) => {
+                    self.action_archive_extract();
+                    // Reload files of the other explorer
+                    match self.tab {
+                        FileExplorerTab::Local => self.update_remote_filelist(),
+                        _ => self.update_local_filelist(),
+                    }
+                }
+                (COMPONENT_EXPLORER_ARCHIVE, &MSG_KEY_CHAR_A) => {
+                    match self.archive.as_ref().map(|x| x.remote) {
+                        Some(false) => self.mount_archive_add(),
+                        _ => self.log_and_alert(
+                            LogLevel::Warn,
+                            String::from("Files can be added only to local archives"),
+                        ),
+                    }
+                    None
+                }
                 // -- switch to log
                 (COMPONENT_EXPLORER_LOCAL, &MSG_KEY_TAB)
                 | (COMPONENT_EXPLORER_REMOTE, &MSG_KEY_TAB) => {
@@ -419,6 +666,14 @@ impl FileTransferActivity {
                     self.view.blur(); // Blur log box
                     None
                 }
+                (COMPONENT_LOG_BOX, &MSG_KEY_CHAR_E) => {
+                    self.log_filter.cycle_level();
+                    self.update_logbox()
+                }
+                (COMPONENT_LOG_BOX, &MSG_KEY_CHAR_F) => {
+                    self.mount_log_search();
+                    None
+                }
                 // -- copy popup
                 (COMPONENT_INPUT_COPY, &MSG_KEY_ESC) => {
                     self.umount_copy();
@@ -445,13 +700,17 @@ impl FileTransferActivity {
                     None
                 }
                 (COMPONENT_INPUT_EXEC, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_exec();
                     // Exex command
                     match self.tab {
                         FileExplorerTab::Local => self.action_local_exec(input.to_string()),
-                        FileExplorerTab::Remote => self.action_remote_exec(input.to_string()),
+                        FileExplorerTab::Remote => {
+                            if !self.require_confirm_phrase(ProtectedOp::Exec(input.to_string())) {
+                                self.action_remote_exec(input.to_string());
+                            }
+                        }
                         _ => panic!("Found tab doesn't support EXEC"),
                     }
-                    self.umount_exec();
                     // Reload files
                     match self.tab {
                         FileExplorerTab::Local => self.update_local_filelist(),
@@ -459,6 +718,39 @@ impl FileTransferActivity {
                         _ => None,
                     }
                 }
+                // Browse remote commands history
+                (COMPONENT_INPUT_EXEC, &MSG_KEY_UP) => self.update_exec_from_history(true),
+                (COMPONENT_INPUT_EXEC, &MSG_KEY_DOWN) => self.update_exec_from_history(false),
+                (COMPONENT_INPUT_EXEC, &MSG_KEY_TAB) => {
+                    if let FileExplorerTab::Remote = self.tab {
+                        self.mount_command_history();
+                    }
+                    None
+                }
+                // -- command history
+                (COMPONENT_LIST_HISTORY, &MSG_KEY_ESC) | (COMPONENT_LIST_HISTORY, &MSG_KEY_TAB) => {
+                    self.umount_command_history();
+                    self.view.active(COMPONENT_INPUT_EXEC);
+                    None
+                }
+                (COMPONENT_LIST_HISTORY, Msg::OnSubmit(Payload::Unsigned(idx))) => {
+                    // Re-run command
+                    let cmd: Option<String> = self.cmd_history.get(*idx).map(|x| x.to_string());
+                    self.umount_command_history();
+                    self.umount_exec();
+                    match cmd {
+                        Some(cmd)
+                            if self.require_confirm_phrase(ProtectedOp::Exec(cmd.clone())) =>
+                        {
+                            None
+                        }
+                        Some(cmd) => {
+                            self.action_remote_exec(cmd);
+                            self.update_remote_filelist()
+                        }
+                        None => None,
+                    }
+                }
                 // -- find popup
                 (COMPONENT_INPUT_FIND, &MSG_KEY_ESC) => {
                     self.umount_find_input();
@@ -466,37 +758,59 @@ impl FileTransferActivity {
                 }
                 (COMPONENT_INPUT_FIND, Msg::OnSubmit(Payload::Text(input))) => {
                     self.umount_find_input();
-                    // Find
-                    let res: Result<Vec<FsEntry>, String> = match self.tab {
-                        FileExplorerTab::Local => self.action_local_find(input.to_string()),
-                        FileExplorerTab::Remote => self.action_remote_find(input.to_string()),
-                        _ => panic!("Trying to search for files, while already in a find result"),
-                    };
-                    // Match result
-                    match res {
-                        Err(err) => {
-                            // Mount error
-                            self.mount_error(err.as_str());
-                        }
-                        Ok(files) => {
-                            // Create explorer and load files
-                            let mut explorer = Self::build_found_explorer();
-                            explorer.set_files(files);
-                            self.found = Some(explorer);
-                            // Mount result widget
-                            self.mount_find(input);
-                            self.update_find_list();
-                            // Initialize tab
-                            self.tab = match self.tab {
-                                FileExplorerTab::Local => FileExplorerTab::FindLocal,
-                                FileExplorerTab::Remote => FileExplorerTab::FindRemote,
-                                _ => FileExplorerTab::FindLocal,
-                            };
-                        }
-                    }
+                    self.action_find(input.to_string(), false);
                     None
                 }
-                // -- goto popup
+                // -- cleanup popup
+                (COMPONENT_INPUT_CLEANUP, &MSG_KEY_ESC) => {
+                    self.umount_cleanup_input();
+                    None
+                }
+                (COMPONENT_INPUT_CLEANUP, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_cleanup_input();
+                    self.action_find(input.to_string(), true);
+                    None
+                }
+                // -- save search popup
+                (COMPONENT_INPUT_SAVE_SEARCH, &MSG_KEY_ESC) => {
+                    self.umount_save_search();
+                    None
+                }
+                (COMPONENT_INPUT_SAVE_SEARCH, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_save_search();
+                    self.action_save_search(input.to_string());
+                    None
+                }
+                // -- add to archive popup
+                (COMPONENT_INPUT_ARCHIVE_ADD, &MSG_KEY_ESC) => {
+                    self.umount_archive_add();
+                    None
+                }
+                (COMPONENT_INPUT_ARCHIVE_ADD, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_archive_add();
+                    self.action_archive_add(input.to_string());
+                    self.update_archive_list()
+                }
+                // -- log search
+                (COMPONENT_INPUT_LOG_SEARCH, &MSG_KEY_ESC) => {
+                    self.umount_log_search();
+                    None
+                }
+                (COMPONENT_INPUT_LOG_SEARCH, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_log_search();
+                    self.log_filter.set_text(input.as_str());
+                    self.update_logbox()
+                }
+                // -- transfer filters
+                (COMPONENT_INPUT_FILTERS, &MSG_KEY_ESC) => {
+                    self.umount_transfer_filters();
+                    None
+                }
+                (COMPONENT_INPUT_FILTERS, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_transfer_filters();
+                    self.action_set_transfer_filters(input.to_string());
+                    self.update_status_bar()
+                }
                 (COMPONENT_INPUT_GOTO, &MSG_KEY_ESC) => {
                     self.umount_goto();
                     None
@@ -516,6 +830,328 @@ impl FileTransferActivity {
                         _ => None,
                     }
                 }
+                // -- pinned directories
+                (COMPONENT_LIST_PINS, &MSG_KEY_ESC) | (COMPONENT_LIST_PINS, &MSG_KEY_CHAR_J) => {
+                    self.umount_pins();
+                    None
+                }
+                (COMPONENT_LIST_PINS, Msg::OnSubmit(Payload::Unsigned(idx))) => {
+                    self.action_jump_to_pin(*idx);
+                    // Umount
+                    self.umount_pins();
+                    // Reload files
+                    match self.tab {
+                        FileExplorerTab::Local => self.update_local_filelist(),
+                        FileExplorerTab::Remote => self.update_remote_filelist(),
+                        _ => None,
+                    }
+                }
+                (COMPONENT_LIST_PINS, &MSG_KEY_DEL) | (COMPONENT_LIST_PINS, &MSG_KEY_CHAR_E) => {
+                    if let Some(Payload::Unsigned(idx)) = self.view.get_value(COMPONENT_LIST_PINS) {
+                        match self.tab {
+                            FileExplorerTab::Local => self.local.unpin(idx),
+                            FileExplorerTab::Remote => self.remote.unpin(idx),
+                            _ => {}
+                        }
+                    }
+                    self.update_pins()
+                }
+                // -- saved searches
+                (COMPONENT_LIST_SAVED_SEARCHES, &MSG_KEY_ESC)
+                | (COMPONENT_LIST_SAVED_SEARCHES, &MSG_KEY_CTRL_S) => {
+                    self.umount_saved_searches();
+                    None
+                }
+                (COMPONENT_LIST_SAVED_SEARCHES, Msg::OnSubmit(Payload::Unsigned(idx))) => {
+                    self.umount_saved_searches();
+                    self.action_run_saved_search(*idx);
+                    None
+                }
+                (COMPONENT_LIST_SAVED_SEARCHES, &MSG_KEY_DEL)
+                | (COMPONENT_LIST_SAVED_SEARCHES, &MSG_KEY_CHAR_E) => {
+                    if let Some(Payload::Unsigned(idx)) =
+                        self.view.get_value(COMPONENT_LIST_SAVED_SEARCHES)
+                    {
+                        self.action_delete_saved_search(idx);
+                    }
+                    self.update_saved_searches()
+                }
+                // -- confirmation phrase
+                (COMPONENT_INPUT_CONFIRM_PHRASE, &MSG_KEY_ESC) => {
+                    self.umount_confirm_phrase();
+                    self.protected_op = None;
+                    self.log(LogLevel::Info, "Operation cancelled");
+                    None
+                }
+                (COMPONENT_INPUT_CONFIRM_PHRASE, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_confirm_phrase();
+                    let op: Option<ProtectedOp> = self.protected_op.take();
+                    match (op, self.protected_phrase.as_deref() == Some(input.trim())) {
+                        (Some(op), true) => self.run_protected_op(op),
+                        (Some(_), false) => {
+                            self.log_and_alert(
+                                LogLevel::Error,
+                                String::from(
+                                    "Confirmation phrase doesn't match; operation cancelled",
+                                ),
+                            );
+                            None
+                        }
+                        (None, _) => None,
+                    }
+                }
+                // -- transfer history
+                (COMPONENT_LIST_TRANSFER_HISTORY, &MSG_KEY_ESC)
+                | (COMPONENT_LIST_TRANSFER_HISTORY, &MSG_KEY_CTRL_R) => {
+                    self.umount_transfer_history();
+                    None
+                }
+                (COMPONENT_LIST_TRANSFER_HISTORY, &MSG_KEY_CHAR_F) => {
+                    self.mount_history_search();
+                    None
+                }
+                (COMPONENT_INPUT_HISTORY_SEARCH, &MSG_KEY_ESC) => {
+                    self.umount_history_search();
+                    None
+                }
+                (COMPONENT_INPUT_HISTORY_SEARCH, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_history_search();
+                    self.history_search = input.to_string();
+                    self.update_transfer_history()
+                }
+                // -- transfer queue
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_ESC) | (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_T) => {
+                    self.umount_queue();
+                    None
+                }
+                (COMPONENT_LIST_QUEUE, Msg::OnSubmit(_)) => {
+                    self.umount_queue();
+                    match self.queue.next_pending() {
+                        // Preview the run before transferring
+                        Some(_) => {
+                            self.batch_plan = Some(self.action_queue_preview());
+                            self.mount_batch_plan();
+                        }
+                        None => self.run_queue(),
+                    }
+                    None
+                }
+                (COMPONENT_LIST_BATCH_PLAN, &MSG_KEY_ESC) => {
+                    self.umount_batch_plan();
+                    self.batch_plan = None;
+                    self.mount_queue();
+                    None
+                }
+                (COMPONENT_LIST_BATCH_PLAN, &MSG_KEY_CHAR_D) => {
+                    self.batch_plan_details = !self.batch_plan_details;
+                    self.update_batch_plan()
+                }
+                (COMPONENT_LIST_BATCH_PLAN, Msg::OnSubmit(_)) => {
+                    self.umount_batch_plan();
+                    self.batch_plan = None;
+                    // Moves from protected hosts require the confirmation phrase
+                    let remote_moves: bool = self.queue.iter().any(|x| {
+                        x.remove_source
+                            && x.direction == TransferDirection::Download
+                            && x.state == QueueItemState::Pending
+                    });
+                    if !(remote_moves && self.require_confirm_phrase(ProtectedOp::RunQueue)) {
+                        self.run_queue();
+                    }
+                    None
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_H) => {
+                    self.action_queue_set_priority(Priority::High);
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_N) => {
+                    self.action_queue_set_priority(Priority::Normal);
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_L) => {
+                    self.action_queue_set_priority(Priority::Low);
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_C) => {
+                    self.queue.clear_done();
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_F) => {
+                    let report: Vec<String> = self.action_queue_plan();
+                    self.mount_upload_plan(report);
+                    None
+                }
+                (COMPONENT_LIST_UPLOAD_PLAN, &MSG_KEY_ESC) => {
+                    self.umount_upload_plan();
+                    None
+                }
+                (COMPONENT_LIST_UPLOAD_PLAN, Msg::OnSubmit(_)) => {
+                    self.umount_upload_plan();
+                    self.action_queue_skip_planned();
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_S) => {
+                    self.mount_queue_export();
+                    None
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_O) => {
+                    self.mount_queue_import();
+                    None
+                }
+                (COMPONENT_INPUT_QUEUE_EXPORT, &MSG_KEY_ESC) => {
+                    self.umount_queue_export();
+                    None
+                }
+                (COMPONENT_INPUT_QUEUE_EXPORT, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_queue_export();
+                    self.action_queue_export(input.to_string());
+                    None
+                }
+                (COMPONENT_INPUT_REPORT, &MSG_KEY_ESC) => {
+                    self.umount_report_input();
+                    None
+                }
+                (COMPONENT_INPUT_REPORT, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_report_input();
+                    self.action_capability_report(input.to_string());
+                    // Reload local directory, the report may have been saved there
+                    let pwd: PathBuf = self.local.wrkdir.clone();
+                    self.local_scan(pwd.as_path());
+                    self.update_local_filelist()
+                }
+                (COMPONENT_INPUT_QUEUE_IMPORT, &MSG_KEY_ESC) => {
+                    self.umount_queue_import();
+                    None
+                }
+                (COMPONENT_INPUT_QUEUE_IMPORT, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_queue_import();
+                    self.action_queue_import(input.to_string());
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_I) => {
+                    self.mount_schedule_input();
+                    None
+                }
+                (COMPONENT_INPUT_SCHEDULE, &MSG_KEY_ESC) => {
+                    self.umount_schedule_input();
+                    None
+                }
+                (COMPONENT_INPUT_SCHEDULE, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_schedule_input();
+                    self.action_schedule_queue(input.to_string());
+                    None
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_J) => {
+                    self.mount_jobs();
+                    None
+                }
+                // -- scheduled jobs
+                (COMPONENT_LIST_JOBS, &MSG_KEY_ESC) | (COMPONENT_LIST_JOBS, &MSG_KEY_CHAR_J) => {
+                    self.umount_jobs();
+                    None
+                }
+                (COMPONENT_LIST_JOBS, Msg::OnSubmit(_)) => {
+                    if let Some(id) = self.get_job_id() {
+                        self.umount_jobs();
+                        self.action_run_job(id);
+                        let _ = self.update_local_filelist();
+                        let _ = self.update_remote_filelist();
+                        self.mount_jobs();
+                    }
+                    None
+                }
+                (COMPONENT_LIST_JOBS, &MSG_KEY_DEL) | (COMPONENT_LIST_JOBS, &MSG_KEY_CHAR_E) => {
+                    self.action_remove_job();
+                    self.update_jobs()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_R) => {
+                    self.action_queue_retry();
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_P) => {
+                    self.action_queue_next_profile();
+                    self.update_queue()
+                }
+                (COMPONENT_LIST_QUEUE, &MSG_KEY_DEL) | (COMPONENT_LIST_QUEUE, &MSG_KEY_CHAR_E) => {
+                    self.action_queue_remove();
+                    self.update_queue()
+                }
+                // -- keyboard-interactive authentication
+                (COMPONENT_INPUT_AUTH_PROMPT, &MSG_KEY_ESC) => {
+                    self.umount_auth_prompt();
+                    self.auth_challenge.reset();
+                    self.mount_fatal("Authentication cancelled");
+                    None
+                }
+                (COMPONENT_INPUT_AUTH_PROMPT, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.answer_auth_prompt(input.to_string());
+                    // Ask next prompt or connect again
+                    self.mount_auth_prompt();
+                    None
+                }
+                // -- host key
+                (COMPONENT_RADIO_HOST_KEY, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    // Accept; connect again
+                    self.umount_host_key();
+                    self.accept_host_key();
+                    None
+                }
+                (COMPONENT_RADIO_HOST_KEY, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_HOST_KEY, Msg::OnSubmit(Payload::Unsigned(1))) => {
+                    self.umount_host_key();
+                    self.pending_host_key = None;
+                    self.mount_fatal("Host key rejected");
+                    None
+                }
+                // -- conflict
+                (COMPONENT_RADIO_CONFLICT, &MSG_KEY_DOWN)
+                | (COMPONENT_RADIO_CONFLICT, &MSG_KEY_TAB) => {
+                    self.view.active(COMPONENT_RADIO_CONFLICT_ALL);
+                    None
+                }
+                (COMPONENT_RADIO_CONFLICT_ALL, &MSG_KEY_DOWN)
+                | (COMPONENT_RADIO_CONFLICT_ALL, &MSG_KEY_TAB)
+                    if self
+                        .view
+                        .get_props(COMPONENT_RADIO_CONFLICT_MKDIRS)
+                        .is_some() =>
+                {
+                    self.view.active(COMPONENT_RADIO_CONFLICT_MKDIRS);
+                    None
+                }
+                (COMPONENT_RADIO_CONFLICT_ALL, &MSG_KEY_UP)
+                | (COMPONENT_RADIO_CONFLICT_ALL, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_CONFLICT_MKDIRS, &MSG_KEY_TAB) => {
+                    self.view.active(COMPONENT_RADIO_CONFLICT);
+                    None
+                }
+                (COMPONENT_RADIO_CONFLICT_MKDIRS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_CONFLICT_ALL);
+                    None
+                }
+                (COMPONENT_RADIO_CONFLICT, Msg::OnSubmit(_))
+                | (COMPONENT_RADIO_CONFLICT_ALL, Msg::OnSubmit(_))
+                | (COMPONENT_RADIO_CONFLICT_MKDIRS, Msg::OnSubmit(_)) => {
+                    self.answer_conflict();
+                    None
+                }
+                (COMPONENT_RADIO_CONFLICT, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_CONFLICT_ALL, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_CONFLICT_MKDIRS, &MSG_KEY_ESC) => {
+                    // Skip this file only
+                    self.transfer.conflict_answer = Some(ConflictChoice::Skip);
+                    None
+                }
+                // -- space
+                (COMPONENT_RADIO_SPACE, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    self.transfer.space_answer = Some(true);
+                    None
+                }
+                (COMPONENT_RADIO_SPACE, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_SPACE, Msg::OnSubmit(Payload::Unsigned(1))) => {
+                    self.transfer.space_answer = Some(false);
+                    None
+                }
                 // -- make directory
                 (COMPONENT_INPUT_MKDIR, &MSG_KEY_ESC) => {
                     self.umount_mkdir();
@@ -573,6 +1209,25 @@ impl FileTransferActivity {
                         _ => None,
                     }
                 }
+                // -- chmod
+                (COMPONENT_INPUT_CHMOD, &MSG_KEY_ESC) => {
+                    self.umount_chmod();
+                    None
+                }
+                (COMPONENT_INPUT_CHMOD, Msg::OnSubmit(Payload::Text(input))) => {
+                    self.umount_chmod();
+                    match self.tab {
+                        FileExplorerTab::Local => {
+                            self.action_local_chmod(input.to_string());
+                            self.update_local_filelist()
+                        }
+                        FileExplorerTab::Remote => {
+                            self.action_remote_chmod(input.to_string());
+                            self.update_remote_filelist()
+                        }
+                        _ => None,
+                    }
+                }
                 // -- save as
                 (COMPONENT_INPUT_SAVEAS, &MSG_KEY_ESC) => {
                     self.umount_saveas();
@@ -615,28 +1270,66 @@ impl FileTransferActivity {
                 }
                 (COMPONENT_RADIO_DELETE, Msg::OnSubmit(Payload::Unsigned(0))) => {
                     // Choice is 'YES'
-                    match self.tab {
-                        FileExplorerTab::Local => self.action_local_delete(),
-                        FileExplorerTab::Remote => self.action_remote_delete(),
-                        FileExplorerTab::FindLocal | FileExplorerTab::FindRemote => {
-                            // Get entry
-                            if let Some(Payload::Unsigned(idx)) =
-                                self.view.get_value(COMPONENT_EXPLORER_FIND)
-                            {
-                                self.action_find_delete(idx);
-                                // Reload entries
-                                self.found.as_mut().unwrap().del_entry(idx);
-                                self.update_find_list();
-                            }
-                        }
-                    }
                     self.umount_radio_delete();
-                    // Reload files
-                    match self.tab {
-                        FileExplorerTab::Local => self.update_local_filelist(),
-                        FileExplorerTab::Remote => self.update_remote_filelist(),
-                        FileExplorerTab::FindLocal => self.update_local_filelist(),
-                        FileExplorerTab::FindRemote => self.update_remote_filelist(),
+                    // Recursive deletes on protected hosts require the confirmation phrase
+                    match self.is_recursive_remote_delete()
+                        && self.require_confirm_phrase(ProtectedOp::Delete)
+                    {
+                        true => None,
+                        false => self.delete_selected(),
+                    }
+                }
+                // -- move
+                (COMPONENT_RADIO_MOVE, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_MOVE, Msg::OnSubmit(Payload::Unsigned(1))) => {
+                    self.umount_radio_move();
+                    None
+                }
+                (COMPONENT_RADIO_MOVE, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    // Choice is 'YES'
+                    self.umount_radio_move();
+                    // Moving a remote directory removes it, as a recursive delete does
+                    match self.is_recursive_remote_delete()
+                        && self.require_confirm_phrase(ProtectedOp::Move)
+                    {
+                        true => None,
+                        false => self.move_selected(),
+                    }
+                }
+                // -- interrupted queue
+                (COMPONENT_RADIO_RECOVERY, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    self.umount_radio_recovery();
+                    self.action_resume_queue();
+                    self.mount_queue();
+                    None
+                }
+                (COMPONENT_RADIO_RECOVERY, Msg::OnSubmit(Payload::Unsigned(1))) => {
+                    self.umount_radio_recovery();
+                    self.action_cleanup_queue();
+                    None
+                }
+                (COMPONENT_RADIO_RECOVERY, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_RECOVERY, Msg::OnSubmit(Payload::Unsigned(2))) => {
+                    // Ask again at the next connection
+                    self.umount_radio_recovery();
+                    self.recovery = None;
+                    None
+                }
+                // -- purge trash
+                (COMPONENT_RADIO_PURGE, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_PURGE, Msg::OnSubmit(Payload::Unsigned(1))) => {
+                    self.umount_radio_purge();
+                    None
+                }
+                (COMPONENT_RADIO_PURGE, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    // Choice is 'YES'
+                    self.umount_radio_purge();
+                    match self.require_confirm_phrase(ProtectedOp::PurgeTrash) {
+                        true => None,
+                        false => {
+                            self.action_purge_trash();
+                            self.update_remote_filelist()
+                        }
                     }
                 }
                 // -- disconnect
@@ -650,6 +1343,17 @@ impl FileTransferActivity {
                     self.umount_disconnect();
                     None
                 }
+                // -- reconnect
+                (COMPONENT_RADIO_RECONNECT, &MSG_KEY_ESC)
+                | (COMPONENT_RADIO_RECONNECT, Msg::OnSubmit(Payload::Unsigned(1))) => {
+                    self.umount_reconnect();
+                    None
+                }
+                (COMPONENT_RADIO_RECONNECT, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    self.umount_reconnect();
+                    self.reconnect();
+                    None
+                }
                 // -- quit
                 (COMPONENT_RADIO_QUIT, &MSG_KEY_ESC)
                 | (COMPONENT_RADIO_QUIT, Msg::OnSubmit(Payload::Unsigned(1))) => {
@@ -705,7 +1409,17 @@ impl FileTransferActivity {
                 // -- progress bar
                 (COMPONENT_PROGRESS_BAR, &MSG_KEY_CTRL_C) => {
                     // Set transfer aborted to True
-                    self.transfer.aborted = true;
+                    self.transfer.cancel.cancel();
+                    self.transfer.queue_aborted = true;
+                    None
+                }
+                (COMPONENT_PROGRESS_BAR, &MSG_KEY_CHAR_P) => {
+                    self.transfer.toggle_pause();
+                    // Resuming during the quiet hours overrides them until they're over
+                    if std::mem::take(&mut self.transfer.quiet_pause) {
+                        self.transfer.quiet_skipped = true;
+                        self.update_status_bar();
+                    }
                     None
                 }
                 // -- fallback
@@ -714,6 +1428,86 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### delete_selected
+    ///
+    /// Delete the selected entries of the current tab, then reload the files
+    fn delete_selected(&mut self) -> Option<(String, Msg)> {
+        match self.tab {
+            FileExplorerTab::Local => self.action_local_delete(),
+            FileExplorerTab::Remote => self.action_remote_delete(),
+            FileExplorerTab::FindLocal | FileExplorerTab::FindRemote
+                if !self.found_selection.marked.is_empty() =>
+            {
+                self.action_find_delete_marked();
+                self.update_find_list();
+            }
+            FileExplorerTab::FindLocal | FileExplorerTab::FindRemote => {
+                // Get entry
+                if let Some(Payload::Unsigned(idx)) = self.view.get_value(COMPONENT_EXPLORER_FIND) {
+                    self.action_find_delete(idx);
+                    // Reload entries
+                    self.found.as_mut().unwrap().del_entry(idx);
+                    self.found_selection.remove(idx);
+                    self.update_find_list();
+                }
+            }
+        }
+        // Reload files
+        match self.tab {
+            FileExplorerTab::Local => self.update_local_filelist(),
+            FileExplorerTab::Remote => self.update_remote_filelist(),
+            FileExplorerTab::FindLocal => self.update_local_filelist(),
+            FileExplorerTab::FindRemote => self.update_remote_filelist(),
+        }
+    }
+
+    /// ### move_selected
+    ///
+    /// Move the selected entry of the current explorer to the other host, then reload the files
+    fn move_selected(&mut self) -> Option<(String, Msg)> {
+        match self.tab {
+            FileExplorerTab::Local => self.action_local_move(),
+            FileExplorerTab::Remote => self.action_remote_move(),
+            FileExplorerTab::FindLocal | FileExplorerTab::FindRemote => return None,
+        }
+        let _ = self.update_local_filelist();
+        self.update_remote_filelist()
+    }
+
+    /// ### run_queue
+    ///
+    /// Run the pending transfers of the queue, then show its outcome
+    fn run_queue(&mut self) {
+        self.action_run_queue();
+        // Reload files and show queue outcome
+        let _ = self.update_local_filelist();
+        let _ = self.update_remote_filelist();
+        self.mount_queue();
+    }
+
+    /// ### run_protected_op
+    ///
+    /// Run the operation on the protected host, once the confirmation phrase has been typed
+    fn run_protected_op(&mut self, op: ProtectedOp) -> Option<(String, Msg)> {
+        self.log(LogLevel::Info, "Operation on protected host confirmed");
+        match op {
+            ProtectedOp::Delete => self.delete_selected(),
+            ProtectedOp::Move => self.move_selected(),
+            ProtectedOp::PurgeTrash => {
+                self.action_purge_trash();
+                self.update_remote_filelist()
+            }
+            ProtectedOp::Exec(cmd) => {
+                self.action_remote_exec(cmd);
+                self.update_remote_filelist()
+            }
+            ProtectedOp::RunQueue => {
+                self.run_queue();
+                None
+            }
+        }
+    }
+
     /// ### update_local_filelist
     ///
     /// Update local file list
@@ -753,7 +1547,7 @@ impl FileTransferActivity {
                 let files: Vec<TextSpan> = self
                     .local
                     .iter_files()
-                    .map(|x: &FsEntry| TextSpan::from(self.local.fmt_file(x)))
+                    .map(|x: &FsEntry| Self::fmt_file_row(&self.local, x))
                     .collect();
                 // Update
                 let props = props
@@ -784,13 +1578,19 @@ impl FileTransferActivity {
                     .store
                     .get_unsigned(super::STORAGE_EXPLORER_WIDTH)
                     .unwrap_or(256);
-                let params = self.context.as_ref().unwrap().ft_params.as_ref().unwrap();
+                let address: String = fmt_host(self.get_remote_address().as_str());
+                let wrkdir: PathBuf = self.get_remote_display_path(self.remote.wrkdir.as_path());
+                let tag: String = match self.host_tag.as_ref() {
+                    Some(tag) if !tag.label.is_empty() => format!("[{}] ", tag.label),
+                    _ => String::new(),
+                };
                 let hostname: String = format!(
-                    "{}:{} ",
-                    params.address,
+                    "{}{}:{} ",
+                    tag,
+                    address,
                     FileTransferActivity::elide_wrkdir_path(
-                        self.remote.wrkdir.as_path(),
-                        params.address.as_str(),
+                        wrkdir.as_path(),
+                        format!("{}{}", tag, address).as_str(),
                         width
                     )
                     .display()
@@ -798,7 +1598,7 @@ impl FileTransferActivity {
                 let files: Vec<TextSpan> = self
                     .remote
                     .iter_files()
-                    .map(|x: &FsEntry| TextSpan::from(self.remote.fmt_file(x)))
+                    .map(|x: &FsEntry| Self::fmt_file_row(&self.remote, x))
                     .collect();
                 // Update
                 let props = props
@@ -810,6 +1610,18 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### fmt_file_row
+    ///
+    /// Format `entry` as a row of the file explorer; broken symlinks are highlighted
+    fn fmt_file_row(explorer: &FileExplorer, entry: &FsEntry) -> TextSpan {
+        match entry.is_broken_link() {
+            true => TextSpanBuilder::new(explorer.fmt_file(entry).as_str())
+                .with_foreground(Color::Red)
+                .build(),
+            false => TextSpan::from(explorer.fmt_file(entry).as_str()),
+        }
+    }
+
     /// ### update_logbox
     ///
     /// Update log box
@@ -826,13 +1638,20 @@ impl FileTransferActivity {
                     .unwrap_or(256);
                 // Make log entries
                 let mut table: TableBuilder = TableBuilder::default();
-                for (idx, record) in self.log_records.iter().enumerate() {
+                let mut shown: usize = 0;
+                for record in self
+                    .log_records
+                    .iter()
+                    .filter(|x| self.log_filter.matches(x))
+                {
                     // Split rows by width  NOTE: -37 'cause log prefix -3 cause of log line cursor
-                    let record_rows = textwrap::wrap(record.msg.as_str(), (width as usize) - 40);
+                    let record_rows =
+                        textwrap::wrap(record.msg.as_str(), width.saturating_sub(40).max(1));
                     // Add row if not first row
-                    if idx > 0 {
+                    if shown > 0 {
                         table.add_row();
                     }
+                    shown += 1;
                     let fg = match record.level {
                         LogLevel::Error => Color::Red,
                         LogLevel::Warn => Color::Yellow,
@@ -874,10 +1693,53 @@ impl FileTransferActivity {
                             }
                         }
                     }
+                    // Collapsed repeats
+                    if record.repeated > 0 {
+                        let repeats: String = format!(
+                            "{} message repeated {} times",
+                            match record.similar {
+                                true => "similar",
+                                false => "previous",
+                            },
+                            record.repeated
+                        );
+                        table.add_col(
+                            TextSpanBuilder::new(
+                                textwrap::indent(
+                                    repeats.as_str(),
+                                    "                                        ",
+                                )
+                                .as_str(),
+                            )
+                            .italic()
+                            .build(),
+                        );
+                    }
                 }
                 let table = table.build();
+                // Report filters in title
+                let title: String = match self.log_filter.is_empty() {
+                    true => String::from("Log"),
+                    false => {
+                        let mut filters: Vec<String> = Vec::with_capacity(2);
+                        match self.log_filter.level {
+                            Some(LogLevel::Error) => filters.push(String::from("errors")),
+                            Some(LogLevel::Warn) => filters.push(String::from("warnings")),
+                            _ => {}
+                        }
+                        if !self.log_filter.text.is_empty() {
+                            filters.push(format!("\"{}\"", self.log_filter.text));
+                        }
+                        format!(
+                            "Log ({}; {} of {})",
+                            filters.join(", "),
+                            shown,
+                            self.log_records.len()
+                        )
+                    }
+                };
                 let props = props
-                    .with_texts(TextParts::table(Some(String::from("Log")), table))
+                    .with_texts(TextParts::table(Some(title), table))
                     .build();
                 self.view.update(super::COMPONENT_LOG_BOX, props)
             }
@@ -885,29 +1747,496 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### update_saved_searches
+    ///
+    /// Update the list of the saved searches
+    pub(super) fn update_saved_searches(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_LIST_SAVED_SEARCHES).as_mut() {
+            Some(props) => {
+                let searches: Vec<TextSpan> = self
+                    .saved_searches
+                    .iter()
+                    .map(|x| match x.name == x.query {
+                        true => TextSpan::from(x.query.as_str()),
+                        false => TextSpan::from(format!("{}: {}", x.name, x.query).as_str()),
+                    })
+                    .collect();
+                let title: Option<String> = props.build().texts.title;
+                let props = props
+                    .with_texts(TextParts::new(title, Some(searches)))
+                    .build();
+                self.view.update(COMPONENT_LIST_SAVED_SEARCHES, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_transfer_history
+    ///
+    /// Update the list of the past transfers, showing only those matching the history search
+    pub(super) fn update_transfer_history(&mut self) -> Option<(String, Msg)> {
+        let records: Vec<HistoryRecord> = match self.history.as_ref().map(|x| x.load()) {
+            Some(Ok(records)) => records,
+            Some(Err(err)) => {
+                self.log_and_alert(
+                    LogLevel::Error,
+                    format!("Could not read transfer history: {}", err),
+                );
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        match self
+            .view
+            .get_props(COMPONENT_LIST_TRANSFER_HISTORY)
+            .as_mut()
+        {
+            Some(props) => {
+                let items: Vec<TextSpan> = records
+                    .iter()
+                    .filter(|x| self.history_search.is_empty() || x.matches(&self.history_search))
+                    .map(|x| {
+                        let (direction, source, dest): (&str, &Path, &Path) = match x.direction {
+                            TransferDirection::Upload => {
+                                ("↑", x.local.as_path(), x.remote.as_path())
+                            }
+                            TransferDirection::Download => {
+                                ("↓", x.remote.as_path(), x.local.as_path())
+                            }
+                        };
+                        let result: String = match &x.result {
+                            TransferResult::Completed => String::from("completed"),
+                            TransferResult::Aborted => String::from("aborted"),
+                            TransferResult::Failed(err) => format!("failed: {}", err),
+                        };
+                        TextSpan::from(
+                            format!(
+                                "{} {} {} => {} ({}; {}; {}s; {})",
+                                fmt_time(x.time(), "%Y-%m-%d %H:%M"),
+                                direction,
+                                source.display(),
+                                dest.display(),
+                                x.host,
+                                ByteSize(x.size),
+                                fmt_millis(Duration::from_millis(x.duration)),
+                                result
+                            )
+                            .as_str(),
+                        )
+                    })
+                    .collect();
+                let title: String = match self.history_search.is_empty() {
+                    true => format!("Transfer history ({})", items.len()),
+                    false => format!(
+                        "Transfer history ({}) - \"{}\"",
+                        items.len(),
+                        self.history_search
+                    ),
+                };
+                let props = props
+                    .with_texts(TextParts::new(Some(title), Some(items)))
+                    .build();
+                self.view.update(COMPONENT_LIST_TRANSFER_HISTORY, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_batch_plan
+    ///
+    /// Update the preview of the queue run; transfers are listed one by one if details are shown
+    pub(super) fn update_batch_plan(&mut self) -> Option<(String, Msg)> {
+        // The overwrite policy of the selected profile applies to the run
+        let policy: OverwritePolicy = self
+            .transfer_profile
+            .as_deref()
+            .and_then(|name| {
+                self.context
+                    .as_ref()
+                    .unwrap()
+                    .config_client
+                    .as_ref()
+                    .and_then(|x| x.get_profile_transfer_options(name))
+            })
+            .map(|x| x.overwrite)
+            .unwrap_or(self.transfer_opts.overwrite);
+        let plan: &BatchPlan = self.batch_plan.as_ref()?;
+        let mut rows: Vec<String> = vec![
+            format!(
+                "{} uploads, {} downloads",
+                plan.count(TransferDirection::Upload),
+                plan.count(TransferDirection::Download)
+            ),
+            format!(
+                "{} targets already exist (overwrite policy: {})",
+                plan.overwrites(),
+                policy
+            ),
+            format!("{} sources deleted once transferred", plan.deletions()),
+            match plan.unknown() {
+                0 => format!("{} to transfer", ByteSize(plan.total())),
+                dirs => format!(
+                    "{} to transfer, plus {} remote directories of unknown size",
+                    ByteSize(plan.total()),
+                    dirs
+                ),
+            },
+            match (plan.eta(), plan.throughput) {
+                (Some(eta), Some(rate)) => format!(
+                    "Estimated time: {} (at {}/s, as the recent transfers)",
+                    Self::fmt_eta(Some(eta)),
+                    ByteSize(rate)
+                ),
+                _ => String::from("Estimated time: unknown (no recent transfers with this host)"),
+            },
+        ];
+        if self.batch_plan_details {
+            rows.push(String::new());
+            rows.extend(plan.items.iter().map(|x| {
+                let direction: &str = match x.direction {
+                    TransferDirection::Upload => "↑",
+                    TransferDirection::Download => "↓",
+                };
+                let mut notes: Vec<String> = vec![match x.size {
+                    Some(size) => ByteSize(size).to_string(),
+                    None => String::from("size unknown"),
+                }];
+                if x.exists {
+                    notes.push(String::from("target exists"));
+                }
+                if x.remove_source {
+                    notes.push(String::from("deletes source"));
+                }
+                format!(
+                    "{} {} => {} ({})",
+                    direction,
+                    x.source.display(),
+                    x.target.display(),
+                    notes.join("; ")
+                )
+            }));
+        }
+        let title: &str = match self.batch_plan_details {
+            true => "Run plan (<ENTER> run, <D> hide details, <ESC> back)",
+            false => "Run plan (<ENTER> run, <D> show details, <ESC> back)",
+        };
+        match self.view.get_props(COMPONENT_LIST_BATCH_PLAN).as_mut() {
+            Some(props) => {
+                let rows: Vec<TextSpan> = rows.iter().map(|x| TextSpan::from(x.as_str())).collect();
+                let props = props
+                    .with_texts(TextParts::new(Some(String::from(title)), Some(rows)))
+                    .build();
+                self.view.update(COMPONENT_LIST_BATCH_PLAN, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_pins
+    ///
+    /// Update quick-jump list with the pinned directories of the current explorer
+    pub(super) fn update_pins(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_LIST_PINS).as_mut() {
+            Some(props) => {
+                let pins: Vec<TextSpan> = match self.tab {
+                    FileExplorerTab::Remote => self.remote.pins(),
+                    _ => self.local.pins(),
+                }
+                .iter()
+                .map(|x| TextSpan::from(x.display().to_string().as_str()))
+                .collect();
+                let title: Option<String> = props.build().texts.title;
+                let props = props.with_texts(TextParts::new(title, Some(pins))).build();
+                self.view.update(COMPONENT_LIST_PINS, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_queue
+    ///
+    /// Update transfer queue list
+    pub(super) fn update_queue(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_LIST_QUEUE).as_mut() {
+            Some(props) => {
+                let items: Vec<TextSpan> = self
+                    .queue
+                    .iter()
+                    .map(|x| {
+                        let priority: &str = match x.priority {
+                            Priority::High => "HIGH  ",
+                            Priority::Normal => "NORMAL",
+                            Priority::Low => "LOW   ",
+                        };
+                        let direction: &str = match x.direction {
+                            TransferDirection::Upload => "↑",
+                            TransferDirection::Download => "↓",
+                        };
+                        let state: String = match &x.state {
+                            QueueItemState::Pending => String::from("pending"),
+                            QueueItemState::Done => String::from("done"),
+                            QueueItemState::Failed(err) => format!("failed: {}", err),
+                        };
+                        let mode: &str = match x.remove_source {
+                            true => "move; ",
+                            false => "",
+                        };
+                        TextSpan::from(
+                            format!(
+                                "{} {} {} => {} ({}{})",
+                                priority,
+                                direction,
+                                x.entry.get_abs_path().display(),
+                                x.dest.display(),
+                                mode,
+                                state
+                            )
+                            .as_str(),
+                        )
+                    })
+                    .collect();
+                let title: Option<String> = Some(self.queue_title());
+                let props = props.with_texts(TextParts::new(title, Some(items))).build();
+                self.view.update(COMPONENT_LIST_QUEUE, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_jobs
+    ///
+    /// Update the list of scheduled jobs
+    pub(super) fn update_jobs(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_LIST_JOBS).as_mut() {
+            Some(props) => {
+                let items: Vec<TextSpan> = self
+                    .jobs
+                    .iter()
+                    .map(|x| {
+                        let last_run: String = match x.last_run.as_ref() {
+                            None => String::from("never run"),
+                            Some(run) if run.failed == 0 => format!(
+                                "last run at {}: {} done",
+                                run.at.format("%H:%M"),
+                                run.transferred
+                            ),
+                            Some(run) => format!(
+                                "last run at {}: {} done, {} failed",
+                                run.at.format("%H:%M"),
+                                run.transferred,
+                                run.failed
+                            ),
+                        };
+                        TextSpan::from(
+                            format!(
+                                "every {}m, next at {} ({}) {}",
+                                x.interval,
+                                x.next_run.format("%H:%M"),
+                                last_run,
+                                x.name
+                            )
+                            .as_str(),
+                        )
+                    })
+                    .collect();
+                let title: String = match self.jobs.is_empty() {
+                    true => String::from("Scheduled jobs (<I> in the transfer queue to add one)"),
+                    false => {
+                        String::from("Scheduled jobs (<ENTER> run now, <DEL> remove, <ESC> close)")
+                    }
+                };
+                let props = props
+                    .with_texts(TextParts::new(Some(title), Some(items)))
+                    .build();
+                self.view.update(COMPONENT_LIST_JOBS, props)
+            }
+            None => None,
+        }
+    }
+
+    /// ### update_exec_from_history
+    ///
+    /// Put the previous (if `older`) or the next command of the remote commands history into the exec input
+    pub(super) fn update_exec_from_history(&mut self, older: bool) -> Option<(String, Msg)> {
+        if let FileExplorerTab::Remote = self.tab {
+            let cmd: String = match older {
+                true => self.cmd_history.prev(),
+                false => self.cmd_history.next(),
+            }
+            .unwrap_or("")
+            .to_string();
+            if let Some(props) = self.view.get_props(COMPONENT_INPUT_EXEC).as_mut() {
+                let props = props.with_value(PropValue::Str(cmd)).build();
+                return self.view.update(COMPONENT_INPUT_EXEC, props);
+            }
+        }
+        None
+    }
+
+    /// ### update_status_bar
+    ///
+    /// Update status bar with the remote host, the remote system and the connection health
+    pub(super) fn update_status_bar(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(super::COMPONENT_TEXT_STATUS).as_mut() {
+            Some(props) => {
+                let host: String = match self.context.as_ref().unwrap().ft_params.as_ref() {
+                    Some(params) => format!(
+                        " {}://{}:{}",
+                        params.protocol.to_string().to_lowercase(),
+                        params.address,
+                        params.port
+                    ),
+                    None => String::from(" localhost"),
+                };
+                let server: String = match self.client.identity().summary() {
+                    Some(summary) => format!(" [{}]", summary),
+                    None => String::new(),
+                };
+                let round_trip: String = match self.health.round_trip {
+                    Some(round_trip) => format!(" (round trip {} ms)", round_trip.as_millis()),
+                    None => String::new(),
+                };
+                let sync: &str = match self.browsing_sync {
+                    true => " (synchronized browsing)",
+                    false => "",
+                };
+                let watch: String = match self.watch.as_ref() {
+                    Some((watcher, _)) => format!(" (watching {})", watcher.root().display()),
+                    None => String::new(),
+                };
+                let filters: &str = match self.filters.is_empty() {
+                    true => "",
+                    false => " (filtered)",
+                };
+                let root: String = match self.remote_root.as_ref() {
+                    Some(root) => format!(" (root {})", root.display()),
+                    None => String::new(),
+                };
+                let quiet: String = match (self.transfer.quiet_pause, self.quiet_hours.as_ref()) {
+                    (true, Some(window)) => format!(" (paused until {})", window.fmt_end()),
+                    _ => String::new(),
+                };
+                let tasks: String = match self.context.as_ref().unwrap().tasks.summary() {
+                    Some(summary) => format!(" - {}", summary),
+                    None => String::new(),
+                };
+                // Tag is displayed before anything else, so that the environment is always visible
+                let tag: Option<TextSpan> = match (self.host_tag.as_ref(), self.host_tag_color()) {
+                    (Some(tag), Some(color)) if !tag.label.is_empty() => Some(
+                        TextSpanBuilder::new(format!(" {} ", tag.label).as_str())
+                            .with_foreground(Color::Black)
+                            .with_background(color)
+                            .bold()
+                            .build(),
+                    ),
+                    _ => None,
+                };
+                let (fg, status): (Color, String) = match self.health.health {
+                    ConnectionHealth::Alive => (Color::Green, format!("Connected{}", round_trip)),
+                    ConnectionHealth::Slow => {
+                        (Color::Yellow, format!("Slow connection{}", round_trip))
+                    }
+                    ConnectionHealth::Lost => (Color::Red, String::from("Connection lost")),
+                };
+                let mut spans: Vec<TextSpan> = vec![
+                    TextSpanBuilder::new("● ").with_foreground(fg).build(),
+                    TextSpanBuilder::new(status.as_str())
+                        .with_foreground(fg)
+                        .bold()
+                        .build(),
+                    TextSpan::from(host.as_str()),
+                    TextSpanBuilder::new(server.as_str())
+                        .with_foreground(Color::Gray)
+                        .build(),
+                    TextSpan::from(root.as_str()),
+                    TextSpanBuilder::new(sync)
+                        .with_foreground(Color::LightYellow)
+                        .build(),
+                    TextSpanBuilder::new(watch.as_str())
+                        .with_foreground(Color::LightYellow)
+                        .build(),
+                    TextSpanBuilder::new(filters)
+                        .with_foreground(Color::LightYellow)
+                        .build(),
+                    TextSpanBuilder::new(quiet.as_str())
+                        .with_foreground(Color::LightYellow)
+                        .build(),
+                    TextSpanBuilder::new(tasks.as_str())
+                        .with_foreground(Color::Gray)
+                        .italic()
+                        .build(),
+                ];
+                if let Some(tag) = tag {
+                    spans.insert(0, TextSpan::from(" "));
+                    spans.insert(0, tag);
+                }
+                // Tabs are displayed first, once another session is open
+                let tabs: &TabBar = &self.context.as_ref().unwrap().tabs;
+                if tabs.labels.len() > 1 {
+                    let mut bar: Vec<TextSpan> = tabs
+                        .labels
+                        .iter()
+                        .enumerate()
+                        .map(|(i, label)| {
+                            let mut span =
+                                TextSpanBuilder::new(format!(" {}:{} ", i + 1, label).as_str());
+                            match tabs.active == Some(i) {
+                                true => span
+                                    .with_foreground(Color::Black)
+                                    .with_background(Color::LightBlue)
+                                    .bold()
+                                    .build(),
+                                false => span.with_foreground(Color::Gray).build(),
+                            }
+                        })
+                        .collect();
+                    bar.push(TextSpan::from(" "));
+                    spans.splice(0..0, bar);
+                }
+                let props = props.with_texts(TextParts::new(None, Some(spans))).build();
+                self.view.update(super::COMPONENT_TEXT_STATUS, props)
+            }
+            None => None,
+        }
+    }
+
     pub(super) fn update_progress_bar(&mut self, text: String) -> Option<(String, Msg)> {
+        let _ = self.update_progress_bar_total();
         match self.view.get_props(COMPONENT_PROGRESS_BAR).as_mut() {
             Some(props) => {
-                // Calculate ETA
-                let elapsed_secs: u64 = self.transfer.started.elapsed().as_secs();
-                let eta: String = match self.transfer.progress as u64 {
-                    0 => String::from("--:--"), // NOTE: would divide by 0 :D
-                    _ => {
-                        let eta: u64 =
-                            ((elapsed_secs * 100) / (self.transfer.progress as u64)) - elapsed_secs;
-                        format!("{:0width$}:{:0width$}", (eta / 60), (eta % 60), width = 2)
+                let quiet_end: Option<String> = match self.transfer.quiet_pause {
+                    true => self.quiet_hours.as_ref().map(|x| x.fmt_end()),
+                    false => None,
+                };
+                let label = match (self.transfer.paused, quiet_end) {
+                    (Some(_), Some(end)) => format!(
+                        "{:.2}% - PAUSED until {}, quiet hours (press <P> to resume now)",
+                        self.transfer.progress, end
+                    ),
+                    (Some(_), None) => format!(
+                        "{:.2}% - PAUSED (press <P> to resume)",
+                        self.transfer.progress
+                    ),
+                    (None, _) => format!(
+                        "{:.2}% - {} of {} - {}/s (avg {}/s) - ETA {}",
+                        self.transfer.progress,
+                        ByteSize(self.transfer.bytes_written as u64),
+                        ByteSize(self.transfer.bytes_total as u64),
+                        ByteSize(self.transfer.current_bytes_per_second()),
+                        ByteSize(self.transfer.bytes_per_second()),
+                        Self::fmt_eta(self.transfer.eta())
+                    ),
+                };
+                // Without the overall progress, report the files already completed
+                let title: String = match (self.transfer.batch_files, self.transfer.done_files) {
+                    (0, done) if done > 0 => {
+                        format!("{} - {}", text, self.transfer.fmt_completed())
                     }
+                    _ => text,
                 };
-                // Calculate bytes/s
-                let label = format!(
-                    "{:.2}% - ETA {} ({}/s)",
-                    self.transfer.progress,
-                    eta,
-                    ByteSize(self.transfer.bytes_per_second())
-                );
                 let props = props
                     .with_texts(TextParts::new(
-                        Some(text),
+                        Some(title),
                         Some(vec![TextSpan::from(label)]),
                     ))
                     .with_value(PropValue::Float(self.transfer.progress / 100.0))
@@ -918,12 +2247,58 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### update_progress_bar_total
+    ///
+    /// Update the overall progress of the current send/recv
+    fn update_progress_bar_total(&mut self) -> Option<(String, Msg)> {
+        match self.view.get_props(COMPONENT_PROGRESS_BAR_TOTAL).as_mut() {
+            Some(props) if self.transfer.batch_files > 0 => {
+                let written: usize = self.transfer.batch_written();
+                let ratio: f64 =
+                    (written as f64 / self.transfer.batch_bytes.max(1) as f64).clamp(0.0, 1.0);
+                // Number of the file being transferred
+                let title: String = format!(
+                    "Overall: file {}/{}",
+                    (self.transfer.done_files + 1).min(self.transfer.batch_files),
+                    self.transfer.batch_files
+                );
+                let label: String = format!(
+                    "{:.2}% - {} of {} - ETA {}",
+                    ratio * 100.0,
+                    ByteSize(written as u64),
+                    ByteSize(self.transfer.batch_bytes as u64),
+                    Self::fmt_eta(self.transfer.batch_eta())
+                );
+                let props = props
+                    .with_texts(TextParts::new(
+                        Some(title),
+                        Some(vec![TextSpan::from(label)]),
+                    ))
+                    .with_value(PropValue::Float(ratio))
+                    .build();
+                self.view.update(COMPONENT_PROGRESS_BAR_TOTAL, props)
+            }
+            _ => None,
+        }
+    }
+
+    /// ### fmt_eta
+    ///
+    /// Format the time left to complete a transfer as `mm:ss`
+    fn fmt_eta(eta: Option<Duration>) -> String {
+        match eta.map(|x| x.as_secs()) {
+            Some(eta) => format!("{:0width$}:{:0width$}", (eta / 60), (eta % 60), width = 2),
+            None => String::from("--:--"),
+        }
+    }
+
     /// ### finalize_find
     ///
     /// Finalize find process
     fn finalize_find(&mut self) {
         // Set found to none
         self.found = None;
+        self.found_selection = FindSelection::default();
         // Restore tab
         self.tab = match self.tab {
             FileExplorerTab::FindLocal => FileExplorerTab::Local,
@@ -932,20 +2307,48 @@ impl FileTransferActivity {
         };
     }
 
-    fn update_find_list(&mut self) -> Option<(String, Msg)> {
+    pub(super) fn update_find_list(&mut self) -> Option<(String, Msg)> {
         match self.view.get_props(COMPONENT_EXPLORER_FIND).as_mut() {
             None => None,
             Some(props) => {
-                let props = props.build();
-                let title: String = props.texts.title.clone().unwrap_or_default();
-                let mut props = PropsBuilder::from(props);
-                // Prepare files
+                // Report selection in title
+                let mut title: String = match self.found_selection.cleanup {
+                    true => format!("Cleanup of \"{}\"", self.found_selection.search),
+                    false => format!("Search results for \"{}\"", self.found_selection.search),
+                };
+                if !self.found_selection.marked.is_empty()
+                    || self.found_selection.keep_tree
+                    || self.found_selection.cleanup
+                {
+                    let mut flags: Vec<String> = Vec::with_capacity(2);
+                    let (count, size): (usize, u64) = self.found_size();
+                    if !self.found_selection.marked.is_empty() {
+                        flags.push(format!("{} selected, {}", count, ByteSize(size)));
+                    } else if self.found_selection.cleanup {
+                        flags.push(format!("{} files, {}", count, ByteSize(size)));
+                    }
+                    if self.found_selection.keep_tree {
+                        flags.push(String::from("keeping paths"));
+                    }
+                    title.push_str(format!(" ({})", flags.join(", ")).as_str());
+                }
+                // Prepare files; selected ones are highlighted
                 let file_texts: Vec<TextSpan> = self
                     .found
                     .as_ref()
                     .unwrap()
                     .iter_files()
-                    .map(|x: &FsEntry| TextSpan::from(self.found.as_ref().unwrap().fmt_file(x)))
+                    .enumerate()
+                    .map(
+                        |(idx, x)| match self.found_selection.marked.contains(&idx) {
+                            true => TextSpanBuilder::new(
+                                self.found.as_ref().unwrap().fmt_file(x).as_str(),
+                            )
+                            .with_foreground(Color::LightGreen)
+                            .build(),
+                            false => Self::fmt_file_row(self.found.as_ref().unwrap(), x),
+                        },
+                    )
                     .collect();
                 let props = props
                     .with_texts(TextParts::new(Some(title), Some(file_texts)))
@@ -955,6 +2358,55 @@ impl FileTransferActivity {
         }
     }
 
+    /// ### found_size
+    ///
+    /// Get the amount and the total size of the selected find results; all the results if none is selected
+    pub(super) fn found_size(&self) -> (usize, u64) {
+        let found: &FileExplorer = match self.found.as_ref() {
+            Some(found) => found,
+            None => return (0, 0),
+        };
+        found
+            .iter_files()
+            .enumerate()
+            .filter(|(idx, _)| {
+                self.found_selection.marked.is_empty() || self.found_selection.marked.contains(idx)
+            })
+            .fold((0, 0), |(count, size), (_, x)| {
+                (count + 1, size + x.get_size() as u64)
+            })
+    }
+
+    /// ### update_archive_list
+    ///
+    /// Update the explorer of the opened archive
+    pub(super) fn update_archive_list(&mut self) -> Option<(String, Msg)> {
+        match (
+            self.view.get_props(COMPONENT_EXPLORER_ARCHIVE).as_mut(),
+            self.archive.as_ref(),
+        ) {
+            (Some(props), Some(browser)) => {
+                let name: String = browser
+                    .archive
+                    .path()
+                    .file_name()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let title: String = format!("{}:{} ", name, browser.explorer.wrkdir.display());
+                let files: Vec<TextSpan> = browser
+                    .explorer
+                    .iter_files()
+                    .map(|x: &FsEntry| TextSpan::from(browser.explorer.fmt_file(x)))
+                    .collect();
+                let props = props
+                    .with_texts(TextParts::new(Some(title), Some(files)))
+                    .build();
+                self.view.update(COMPONENT_EXPLORER_ARCHIVE, props)
+            }
+            _ => None,
+        }
+    }
+
     /// ### elide_wrkdir_path
     ///
     /// Elide working directory path if longer than width + host.len
@@ -34,21 +34,25 @@ extern crate users;
 use super::{Context, FileExplorerTab, FileTransferActivity};
 use crate::fs::explorer::FileSorting;
 use crate::fs::FsEntry;
+use crate::system::known_hosts::KnownHost;
+use crate::ui::layout::accessibility::{ColorFilter, ColorMode};
 use crate::ui::layout::components::{
-    file_list::FileList, input::Input, logbox::LogBox, msgbox::MsgBox, progress_bar::ProgressBar,
-    radio_group::RadioGroup, table::Table,
+    bookmark_list::BookmarkList, file_list::FileList, input::Input, logbox::LogBox, msgbox::MsgBox,
+    progress_bar::ProgressBar, radio_group::RadioGroup, table::Table, text::Text,
 };
 use crate::ui::layout::props::{
-    PropValue, PropsBuilder, TableBuilder, TextParts, TextSpan, TextSpanBuilder,
+    InputType, PropValue, PropsBuilder, TableBuilder, TextParts, TextSpan, TextSpanBuilder,
 };
+use crate::ui::layout::toast::Toast;
 use crate::ui::layout::utils::draw_area_in;
 use crate::ui::store::Store;
 use crate::utils::fmt::fmt_time;
 // Ext
 use bytesize::ByteSize;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 use tui::{
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::Color,
     widgets::Clear,
 };
@@ -72,13 +76,14 @@ impl FileTransferActivity {
                     .build(),
             )),
         );
-        // Mount remote file explorer
+        // Mount remote file explorer; tagged hosts use the color of their tag
+        let remote_color: Color = self.host_tag_color().unwrap_or(Color::LightBlue);
         self.view.mount(
             super::COMPONENT_EXPLORER_REMOTE,
             Box::new(FileList::new(
                 PropsBuilder::default()
-                    .with_background(Color::LightBlue)
-                    .with_foreground(Color::LightBlue)
+                    .with_background(remote_color)
+                    .with_foreground(remote_color)
                     .build(),
             )),
         );
@@ -92,6 +97,11 @@ impl FileTransferActivity {
                     .build(),
             )),
         );
+        // Mount status bar
+        self.view.mount(
+            super::COMPONENT_TEXT_STATUS,
+            Box::new(Text::new(PropsBuilder::default().build())),
+        );
         // Update components
         let _ = self.update_local_filelist();
         let _ = self.update_remote_filelist();
@@ -106,7 +116,10 @@ impl FileTransferActivity {
     /// View gui
     pub(super) fn view(&mut self) {
         let mut context: Context = self.context.take().unwrap();
+        let color_mode: ColorMode = context.color_mode();
+        let toast: Option<Toast> = context.toast();
         let store: &mut Store = &mut context.store;
+        let connected: bool = self.client.is_connected();
         let _ = context.terminal.draw(|f| {
             // Prepare chunks
             let chunks = Layout::default()
@@ -116,6 +129,7 @@ impl FileTransferActivity {
                     [
                         Constraint::Percentage(70), // Explorer
                         Constraint::Percentage(30), // Log
+                        Constraint::Length(1),      // Status bar
                     ]
                     .as_ref(),
                 )
@@ -125,36 +139,45 @@ impl FileTransferActivity {
                 .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
                 .direction(Direction::Horizontal)
                 .split(chunks[0]);
-            // If width is unset in the storage, set width
-            if !store.isset(super::STORAGE_EXPLORER_WIDTH) {
-                store.set_unsigned(super::STORAGE_EXPLORER_WIDTH, tabs_chunks[0].width as usize);
-            }
-            if !store.isset(super::STORAGE_LOGBOX_WIDTH) {
-                store.set_unsigned(super::STORAGE_LOGBOX_WIDTH, chunks[1].width as usize);
-            }
+            // Keep widths in the storage up to date (they change when terminal is resized)
+            store.set_unsigned(super::STORAGE_EXPLORER_WIDTH, tabs_chunks[0].width as usize);
+            store.set_unsigned(super::STORAGE_LOGBOX_WIDTH, chunks[1].width as usize);
             // Draw explorers
-            // @! Local explorer (Find or default)
+            // @! Local explorer (Find, archive or default)
+            let archive_remote: Option<bool> = self.archive.as_ref().map(|x| x.remote);
             match self.tab {
                 FileExplorerTab::FindLocal => {
                     self.view
                         .render(super::COMPONENT_EXPLORER_FIND, f, tabs_chunks[0])
                 }
+                _ if archive_remote == Some(false) => {
+                    self.view
+                        .render(super::COMPONENT_EXPLORER_ARCHIVE, f, tabs_chunks[0])
+                }
                 _ => self
                     .view
                     .render(super::COMPONENT_EXPLORER_LOCAL, f, tabs_chunks[0]),
             }
-            // @! Remote explorer (Find or default)
+            // @! Remote explorer (Find, archive or default)
             match self.tab {
                 FileExplorerTab::FindRemote => {
                     self.view
                         .render(super::COMPONENT_EXPLORER_FIND, f, tabs_chunks[1])
                 }
+                _ if archive_remote == Some(true) => {
+                    self.view
+                        .render(super::COMPONENT_EXPLORER_ARCHIVE, f, tabs_chunks[1])
+                }
                 _ => self
                     .view
                     .render(super::COMPONENT_EXPLORER_REMOTE, f, tabs_chunks[1]),
             }
             // Draw log box
             self.view.render(super::COMPONENT_LOG_BOX, f, chunks[1]);
+            // Draw status bar
+            if connected {
+                self.view.render(super::COMPONENT_TEXT_STATUS, f, chunks[2]);
+            }
             // @! Draw popups
             if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_COPY) {
                 if props.build().visible {
@@ -164,6 +187,14 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_INPUT_COPY, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_HISTORY) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 40);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_LIST_HISTORY, f, popup);
+                }
+            }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_FIND) {
                 if props.build().visible {
                     let popup = draw_area_in(f.size(), 40, 10);
@@ -172,6 +203,49 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_INPUT_FIND, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_CLEANUP) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_INPUT_CLEANUP, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_ARCHIVE_ADD) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_ARCHIVE_ADD, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_AUTH_PROMPT) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_AUTH_PROMPT, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_FILTERS) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_INPUT_FILTERS, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_LOG_SEARCH) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_LOG_SEARCH, f, popup);
+                }
+            }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_GOTO) {
                 if props.build().visible {
                     let popup = draw_area_in(f.size(), 40, 10);
@@ -228,12 +302,155 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_LIST_FILEINFO, f, popup);
                 }
             }
-            if let Some(mut props) = self.view.get_props(super::COMPONENT_PROGRESS_BAR) {
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_PINS) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 40);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_LIST_PINS, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_SAVED_SEARCHES) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 40);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_LIST_SAVED_SEARCHES, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_CONFIRM_PHRASE) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_CONFIRM_PHRASE, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_TRANSFER_HISTORY) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 80, 50);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_LIST_TRANSFER_HISTORY, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_HISTORY_SEARCH) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_HISTORY_SEARCH, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_SAVE_SEARCH) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_SAVE_SEARCH, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_QUEUE) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 70, 50);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_LIST_QUEUE, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_JOBS) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 70, 40);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_LIST_JOBS, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_UPLOAD_PLAN) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 40);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_LIST_UPLOAD_PLAN, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_LIST_BATCH_PLAN) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 70, 50);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_LIST_BATCH_PLAN, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_QUEUE_EXPORT) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_QUEUE_EXPORT, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_CHMOD) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_INPUT_CHMOD, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_REPORT) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_INPUT_REPORT, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_QUEUE_IMPORT) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view
+                        .render(super::COMPONENT_INPUT_QUEUE_IMPORT, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_SCHEDULE) {
                 if props.build().visible {
                     let popup = draw_area_in(f.size(), 40, 10);
                     f.render_widget(Clear, popup);
                     // make popup
+                    self.view.render(super::COMPONENT_INPUT_SCHEDULE, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_PROGRESS_BAR) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
                     self.view.render(super::COMPONENT_PROGRESS_BAR, f, popup);
+                    // Overall progress goes right below
+                    if let Some(mut props) =
+                        self.view.get_props(super::COMPONENT_PROGRESS_BAR_TOTAL)
+                    {
+                        if props.build().visible {
+                            let popup: Rect = Rect {
+                                y: popup.y + popup.height,
+                                ..popup
+                            }
+                            .intersection(f.size());
+                            f.render_widget(Clear, popup);
+                            self.view
+                                .render(super::COMPONENT_PROGRESS_BAR_TOTAL, f, popup);
+                        }
+                    }
                 }
             }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_DELETE) {
@@ -244,6 +461,30 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_RADIO_DELETE, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_MOVE) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 40, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_RADIO_MOVE, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_PURGE) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_RADIO_PURGE, f, popup);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_RECOVERY) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_RADIO_RECOVERY, f, popup);
+                }
+            }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_DISCONNECT) {
                 if props.build().visible {
                     let popup = draw_area_in(f.size(), 30, 10);
@@ -261,6 +502,14 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_RADIO_QUIT, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_RECONNECT) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 10);
+                    f.render_widget(Clear, popup);
+                    // make popup
+                    self.view.render(super::COMPONENT_RADIO_RECONNECT, f, popup);
+                }
+            }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_SORTING) {
                 if props.build().visible {
                     let popup = draw_area_in(f.size(), 50, 10);
@@ -285,6 +534,84 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_TEXT_FATAL, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_HOST_KEY) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 30);
+                    f.render_widget(Clear, popup);
+                    let popup_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Min(5),    // Host key
+                                Constraint::Length(3), // Accept/Reject
+                            ]
+                            .as_ref(),
+                        )
+                        .split(popup);
+                    self.view
+                        .render(super::COMPONENT_TEXT_HOST_KEY, f, popup_chunks[0]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_HOST_KEY, f, popup_chunks[1]);
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_CONFLICT) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 60, 40);
+                    f.render_widget(Clear, popup);
+                    let mkdirs: bool = self
+                        .view
+                        .get_props(super::COMPONENT_RADIO_CONFLICT_MKDIRS)
+                        .is_some();
+                    let popup_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Min(5),    // Files
+                                Constraint::Length(3), // Choice
+                                Constraint::Length(3), // Apply to all
+                                Constraint::Length(match mkdirs {
+                                    true => 3, // Create remote directories
+                                    false => 0,
+                                }),
+                            ]
+                            .as_ref(),
+                        )
+                        .split(popup);
+                    self.view
+                        .render(super::COMPONENT_TEXT_CONFLICT, f, popup_chunks[0]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_CONFLICT, f, popup_chunks[1]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_CONFLICT_ALL, f, popup_chunks[2]);
+                    if mkdirs {
+                        self.view.render(
+                            super::COMPONENT_RADIO_CONFLICT_MKDIRS,
+                            f,
+                            popup_chunks[3],
+                        );
+                    }
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_SPACE) {
+                if props.build().visible {
+                    let popup = draw_area_in(f.size(), 50, 30);
+                    f.render_widget(Clear, popup);
+                    let popup_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Min(4),    // Space
+                                Constraint::Length(3), // Transfer anyway
+                            ]
+                            .as_ref(),
+                        )
+                        .split(popup);
+                    self.view
+                        .render(super::COMPONENT_TEXT_SPACE, f, popup_chunks[0]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_SPACE, f, popup_chunks[1]);
+                }
+            }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_TEXT_WAIT) {
                 if props.build().visible {
                     let popup = draw_area_in(f.size(), 50, 10);
@@ -301,6 +628,12 @@ impl FileTransferActivity {
                     self.view.render(super::COMPONENT_TEXT_HELP, f, popup);
                 }
             }
+            // Toast on top of everything
+            if let Some(toast) = toast {
+                f.render_widget(toast, f.size());
+            }
+            // Apply color mode (must come last)
+            f.render_widget(ColorFilter::new(color_mode), f.size());
         });
         // Re-give context
         self.context = Some(context);
@@ -426,6 +759,33 @@ impl FileTransferActivity {
         self.view.umount(super::COMPONENT_RADIO_DISCONNECT);
     }
 
+    /// ### mount_reconnect
+    ///
+    /// Mount reconnect popup, shown when the connection has been lost
+    pub(super) fn mount_reconnect(&mut self) {
+        self.view.mount(
+            super::COMPONENT_RADIO_RECONNECT,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Connection lost. Reconnect?")),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_RADIO_RECONNECT);
+    }
+
+    /// ### umount_reconnect
+    ///
+    /// Umount reconnect popup
+    pub(super) fn umount_reconnect(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_RECONNECT);
+    }
+
     pub(super) fn mount_copy(&mut self) {
         self.view.mount(
             super::COMPONENT_INPUT_COPY,
@@ -455,12 +815,42 @@ impl FileTransferActivity {
             )),
         );
         self.view.active(super::COMPONENT_INPUT_EXEC);
+        self.cmd_history.reset_cursor();
     }
 
     pub(super) fn umount_exec(&mut self) {
         self.view.umount(super::COMPONENT_INPUT_EXEC);
     }
 
+    /// ### mount_command_history
+    ///
+    /// Mount list with the commands executed on the remote host
+    pub(super) fn mount_command_history(&mut self) {
+        let commands: Vec<TextSpan> = self
+            .cmd_history
+            .iter()
+            .map(|x| TextSpan::from(x.as_str()))
+            .collect();
+        self.view.mount(
+            super::COMPONENT_LIST_HISTORY,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightBlue)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Command history")),
+                        Some(commands),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_LIST_HISTORY);
+    }
+
+    pub(super) fn umount_command_history(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_HISTORY);
+    }
+
     pub(super) fn mount_find(&mut self, search: &str) {
         // Get color
         let color: Color = match self.tab {
@@ -489,44 +879,704 @@ impl FileTransferActivity {
         self.view.umount(super::COMPONENT_EXPLORER_FIND);
     }
 
+    /// ### mount_archive
+    ///
+    /// Mount explorer for the opened archive
+    pub(super) fn mount_archive(&mut self, name: &str) {
+        let color: Color = match self.tab {
+            FileExplorerTab::Remote => Color::LightBlue,
+            _ => Color::Yellow,
+        };
+        self.view.mount(
+            super::COMPONENT_EXPLORER_ARCHIVE,
+            Box::new(FileList::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(Some(String::from(name)), Some(vec![])))
+                    .with_background(color)
+                    .with_foreground(color)
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_EXPLORER_ARCHIVE);
+    }
+
+    pub(super) fn umount_archive(&mut self) {
+        self.view.umount(super::COMPONENT_EXPLORER_ARCHIVE);
+    }
+
+    pub(super) fn mount_archive_add(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_ARCHIVE_ADD,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Add local file to archive")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_ARCHIVE_ADD);
+    }
+
+    pub(super) fn umount_archive_add(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_ARCHIVE_ADD);
+    }
+
     pub(super) fn mount_find_input(&mut self) {
         self.view.mount(
             super::COMPONENT_INPUT_FIND,
             Box::new(Input::new(
                 PropsBuilder::default()
                     .with_texts(TextParts::new(
-                        Some(String::from("Search files by name")),
-                        None,
+                        Some(String::from(
+                            "Search files (e.g. *.log in:/var/log newer:1d older:2h)",
+                        )),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        // Give focus to input find
+        self.view.active(super::COMPONENT_INPUT_FIND);
+    }
+
+    pub(super) fn umount_find_input(&mut self) {
+        // Umount input find
+        self.view.umount(super::COMPONENT_INPUT_FIND);
+    }
+
+    /// ### mount_cleanup_input
+    ///
+    /// Mount the input to look for files to clean up in the current explorer
+    pub(super) fn mount_cleanup_input(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_CLEANUP,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Clean up files (e.g. * in:logs older:30d larger:100M)",
+                        )),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_CLEANUP);
+    }
+
+    pub(super) fn umount_cleanup_input(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_CLEANUP);
+    }
+
+    pub(super) fn mount_goto(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_GOTO,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Change working directory")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_GOTO);
+    }
+
+    pub(super) fn umount_goto(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_GOTO);
+    }
+
+    /// ### mount_pins
+    ///
+    /// Mount quick-jump list with the pinned directories of the current explorer
+    pub(super) fn mount_pins(&mut self) {
+        let (title, color): (&str, Color) = match self.tab {
+            FileExplorerTab::Remote => ("Pinned remote directories", Color::LightBlue),
+            _ => ("Pinned local directories", Color::LightYellow),
+        };
+        self.view.mount(
+            super::COMPONENT_LIST_PINS,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(color)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from(title)), None))
+                    .build(),
+            )),
+        );
+        let _ = self.update_pins();
+        self.view.active(super::COMPONENT_LIST_PINS);
+    }
+
+    pub(super) fn umount_pins(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_PINS);
+    }
+
+    /// ### mount_saved_searches
+    ///
+    /// Mount the list of the searches saved for the remote host
+    pub(super) fn mount_saved_searches(&mut self) {
+        let color: Color = match self.tab {
+            FileExplorerTab::Remote => Color::LightBlue,
+            _ => Color::LightYellow,
+        };
+        self.view.mount(
+            super::COMPONENT_LIST_SAVED_SEARCHES,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(color)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from("Saved searches")), None))
+                    .build(),
+            )),
+        );
+        let _ = self.update_saved_searches();
+        self.view.active(super::COMPONENT_LIST_SAVED_SEARCHES);
+    }
+
+    pub(super) fn umount_saved_searches(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_SAVED_SEARCHES);
+    }
+
+    /// ### mount_save_search
+    ///
+    /// Mount the input to name the current search, before saving it
+    pub(super) fn mount_save_search(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_SAVE_SEARCH,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Save search as (empty to use the query)")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_SAVE_SEARCH);
+    }
+
+    pub(super) fn umount_save_search(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_SAVE_SEARCH);
+    }
+
+    /// ### mount_queue
+    ///
+    /// Mount transfer queue list
+    pub(super) fn mount_queue(&mut self) {
+        self.view.mount(
+            super::COMPONENT_LIST_QUEUE,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightGreen)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(self.queue_title()), None))
+                    .build(),
+            )),
+        );
+        let _ = self.update_queue();
+        self.view.active(super::COMPONENT_LIST_QUEUE);
+    }
+
+    pub(super) fn umount_queue(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_QUEUE);
+    }
+
+    /// ### queue_title
+    ///
+    /// Make the title of the transfer queue list, with the selected transfer profile
+    pub(super) fn queue_title(&self) -> String {
+        format!(
+            "Transfer queue [{}] (<ENTER> run, <P> profile, <H/N/L> priority, <R> retry, <C> clear done, <F> check space, <S> export, <O> import, <I> schedule, <J> jobs)",
+            self.transfer_profile.as_deref().unwrap_or("default")
+        )
+    }
+
+    /// ### mount_upload_plan
+    ///
+    /// Mount the report telling whether the pending uploads fit in the remote space left
+    pub(super) fn mount_upload_plan(&mut self, report: Vec<String>) {
+        let title: &str = match self.upload_plan.is_empty() {
+            true => "Upload plan",
+            false => "Upload plan (<ENTER> skip suggested uploads, <ESC> close)",
+        };
+        let report: Vec<TextSpan> = report.iter().map(|x| TextSpan::from(x.as_str())).collect();
+        self.view.mount(
+            super::COMPONENT_LIST_UPLOAD_PLAN,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightCyan)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from(title)), Some(report)))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_LIST_UPLOAD_PLAN);
+    }
+
+    pub(super) fn umount_upload_plan(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_UPLOAD_PLAN);
+    }
+
+    /// ### mount_batch_plan
+    ///
+    /// Mount the preview of the queue run, which must be confirmed before transferring
+    pub(super) fn mount_batch_plan(&mut self) {
+        self.view.mount(
+            super::COMPONENT_LIST_BATCH_PLAN,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightGreen)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from("Run plan")), None))
+                    .build(),
+            )),
+        );
+        let _ = self.update_batch_plan();
+        self.view.active(super::COMPONENT_LIST_BATCH_PLAN);
+    }
+
+    pub(super) fn umount_batch_plan(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_BATCH_PLAN);
+    }
+
+    pub(super) fn mount_queue_export(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_QUEUE_EXPORT,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Export queue to file")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_QUEUE_EXPORT);
+    }
+
+    pub(super) fn umount_queue_export(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_QUEUE_EXPORT);
+    }
+
+    pub(super) fn mount_report_input(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_REPORT,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Save capability report to")),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(String::from("termscp-report.md")))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_REPORT);
+    }
+
+    pub(super) fn umount_report_input(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_REPORT);
+    }
+
+    /// ### mount_transfer_filters
+    ///
+    /// Mount the input to edit the include (`+pattern`) and exclude patterns of recursive transfers
+    pub(super) fn mount_transfer_filters(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_FILTERS,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Exclude patterns; +pattern to include (e.g. node_modules/ *.o +*.c)",
+                        )),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(self.filters.patterns().join(" ")))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_FILTERS);
+    }
+
+    pub(super) fn umount_transfer_filters(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_FILTERS);
+    }
+
+    /// ### mount_log_search
+    ///
+    /// Mount the input to search a text in the log records
+    pub(super) fn mount_log_search(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_LOG_SEARCH,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_texts(TextParts::new(Some(String::from("Search in log")), None))
+                    .with_value(PropValue::Str(self.log_filter.text.clone()))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_LOG_SEARCH);
+    }
+
+    pub(super) fn umount_log_search(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_LOG_SEARCH);
+    }
+
+    /// ### mount_confirm_phrase
+    ///
+    /// Mount the input to type the confirmation phrase of a destructive operation on a protected host
+    pub(super) fn mount_confirm_phrase(&mut self, phrase: &str) {
+        self.view.mount(
+            super::COMPONENT_INPUT_CONFIRM_PHRASE,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .with_texts(TextParts::new(
+                        Some(format!("Protected host: type \"{}\" to confirm", phrase)),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_CONFIRM_PHRASE);
+    }
+
+    pub(super) fn umount_confirm_phrase(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_CONFIRM_PHRASE);
+    }
+
+    /// ### mount_transfer_history
+    ///
+    /// Mount the list of the past transfers
+    pub(super) fn mount_transfer_history(&mut self) {
+        self.view.mount(
+            super::COMPONENT_LIST_TRANSFER_HISTORY,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightGreen)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from("Transfer history")), None))
+                    .build(),
+            )),
+        );
+        let _ = self.update_transfer_history();
+        self.view.active(super::COMPONENT_LIST_TRANSFER_HISTORY);
+    }
+
+    pub(super) fn umount_transfer_history(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_TRANSFER_HISTORY);
+    }
+
+    /// ### mount_history_search
+    ///
+    /// Mount the input to search a text in the transfer history
+    pub(super) fn mount_history_search(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_HISTORY_SEARCH,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Search in transfer history")),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(self.history_search.clone()))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_HISTORY_SEARCH);
+    }
+
+    pub(super) fn umount_history_search(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_HISTORY_SEARCH);
+    }
+
+    pub(super) fn mount_queue_import(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_QUEUE_IMPORT,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Import queue from file")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_QUEUE_IMPORT);
+    }
+
+    pub(super) fn umount_queue_import(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_QUEUE_IMPORT);
+    }
+
+    pub(super) fn mount_schedule_input(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_SCHEDULE,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Run these transfers every (minutes)")),
+                        None,
+                    ))
+                    .with_input(InputType::Number)
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_SCHEDULE);
+    }
+
+    pub(super) fn umount_schedule_input(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_SCHEDULE);
+    }
+
+    /// ### mount_jobs
+    ///
+    /// Mount the panel of the scheduled jobs
+    pub(super) fn mount_jobs(&mut self) {
+        self.view.mount(
+            super::COMPONENT_LIST_JOBS,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_background(Color::LightMagenta)
+                    .with_foreground(Color::Black)
+                    .with_texts(TextParts::new(Some(String::from("Scheduled jobs")), None))
+                    .build(),
+            )),
+        );
+        let _ = self.update_jobs();
+        self.view.active(super::COMPONENT_LIST_JOBS);
+    }
+
+    pub(super) fn umount_jobs(&mut self) {
+        self.view.umount(super::COMPONENT_LIST_JOBS);
+    }
+
+    /// ### mount_host_key
+    ///
+    /// Mount the popup asking the user whether to trust the host key (or certificate) presented by the server
+    pub(super) fn mount_host_key(&mut self, key: &KnownHost) {
+        let title: &str = match key.reverify {
+            true => "Host key must be re-verified",
+            false => "Unknown host key",
+        };
+        self.view.mount(
+            super::COMPONENT_TEXT_HOST_KEY,
+            Box::new(MsgBox::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from(title)),
+                        Some(vec![
+                            TextSpan::from(key.host.as_str()),
+                            TextSpan::from(key.key_type.as_str()),
+                            TextSpanBuilder::new(key.fingerprint().as_str())
+                                .bold()
+                                .build(),
+                        ]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_HOST_KEY,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Trust this key?")),
+                        Some(vec![TextSpan::from("Accept"), TextSpan::from("Reject")]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_RADIO_HOST_KEY);
+    }
+
+    /// ### umount_host_key
+    ///
+    /// Umount host key popup
+    pub(super) fn umount_host_key(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_HOST_KEY);
+        self.view.umount(super::COMPONENT_TEXT_HOST_KEY);
+    }
+
+    /// ### mount_conflict
+    ///
+    /// Mount the popup asking the user what to do with `source`, since `target` already exists.
+    /// Both files are described by their size and their modification time
+    pub(super) fn mount_conflict(
+        &mut self,
+        target: &Path,
+        source: (usize, SystemTime),
+        existing: (usize, SystemTime),
+        create_dirs: Option<bool>,
+    ) {
+        let describe = |(size, mtime): (usize, SystemTime)| -> String {
+            format!(
+                "{} - modified {}",
+                ByteSize(size as u64),
+                fmt_time(mtime, "%b %d %Y %H:%M:%S")
+            )
+        };
+        self.view.mount(
+            super::COMPONENT_TEXT_CONFLICT,
+            Box::new(MsgBox::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("File already exists")),
+                        Some(vec![
+                            TextSpanBuilder::new(target.display().to_string().as_str())
+                                .bold()
+                                .build(),
+                            TextSpan::from(format!("Source: {}", describe(source)).as_str()),
+                            TextSpan::from(format!("Target: {}", describe(existing)).as_str()),
+                        ]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_CONFLICT,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("What to do?")),
+                        Some(vec![
+                            TextSpan::from("Overwrite"),
+                            TextSpan::from("Skip"),
+                            TextSpan::from("Rename"),
+                            TextSpan::from("Overwrite if newer"),
+                        ]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_CONFLICT_ALL,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Apply to all conflicts?")),
+                        Some(vec![TextSpan::from("No"), TextSpan::from("Yes")]),
+                    ))
+                    .build(),
+            )),
+        );
+        // Uploads only
+        if let Some(create_dirs) = create_dirs {
+            self.view.mount(
+                super::COMPONENT_RADIO_CONFLICT_MKDIRS,
+                Box::new(RadioGroup::new(
+                    PropsBuilder::default()
+                        .with_foreground(Color::LightGreen)
+                        .with_texts(TextParts::new(
+                            Some(String::from(
+                                "Create missing remote directories for this run?",
+                            )),
+                            Some(vec![TextSpan::from("No"), TextSpan::from("Yes")]),
+                        ))
+                        .with_value(PropValue::Unsigned(create_dirs as usize))
+                        .build(),
+                )),
+            );
+        }
+        self.view.active(super::COMPONENT_RADIO_CONFLICT);
+    }
+
+    /// ### umount_conflict
+    ///
+    /// Umount conflict popup
+    pub(super) fn umount_conflict(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_CONFLICT_MKDIRS);
+        self.view.umount(super::COMPONENT_RADIO_CONFLICT_ALL);
+        self.view.umount(super::COMPONENT_RADIO_CONFLICT);
+        self.view.umount(super::COMPONENT_TEXT_CONFLICT);
+    }
+
+    /// ### mount_space
+    ///
+    /// Mount the popup warning the files to transfer won't fit in the space left, as told by `text`
+    pub(super) fn mount_space(&mut self, text: &str) {
+        self.view.mount(
+            super::COMPONENT_TEXT_SPACE,
+            Box::new(MsgBox::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Not enough space")),
+                        Some(vec![TextSpan::from(text)]),
                     ))
                     .build(),
             )),
         );
-        // Give focus to input find
-        self.view.active(super::COMPONENT_INPUT_FIND);
-    }
-
-    pub(super) fn umount_find_input(&mut self) {
-        // Umount input find
-        self.view.umount(super::COMPONENT_INPUT_FIND);
-    }
-
-    pub(super) fn mount_goto(&mut self) {
         self.view.mount(
-            super::COMPONENT_INPUT_GOTO,
-            Box::new(Input::new(
+            super::COMPONENT_RADIO_SPACE,
+            Box::new(RadioGroup::new(
                 PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
                     .with_texts(TextParts::new(
-                        Some(String::from("Change working directory")),
-                        None,
+                        Some(String::from("Transfer anyway?")),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
                     ))
+                    .with_value(PropValue::Unsigned(1))
                     .build(),
             )),
         );
-        self.view.active(super::COMPONENT_INPUT_GOTO);
+        self.view.active(super::COMPONENT_RADIO_SPACE);
     }
 
-    pub(super) fn umount_goto(&mut self) {
-        self.view.umount(super::COMPONENT_INPUT_GOTO);
+    /// ### umount_space
+    ///
+    /// Umount space popup
+    pub(super) fn umount_space(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_SPACE);
+        self.view.umount(super::COMPONENT_TEXT_SPACE);
+    }
+
+    /// ### mount_auth_prompt
+    ///
+    /// Mount the input for the current keyboard-interactive prompt; if there's no prompt left, the popup is umounted
+    pub(super) fn mount_auth_prompt(&mut self) {
+        match self.auth_challenge.prompts.front() {
+            Some(prompt) => {
+                let input_type: InputType = match prompt.echo {
+                    true => InputType::Text,
+                    false => InputType::Password,
+                };
+                let title: String = match prompt.text.is_empty() {
+                    true => String::from("Verification code"),
+                    false => prompt.text.trim_end_matches(':').to_string(),
+                };
+                self.view.mount(
+                    super::COMPONENT_INPUT_AUTH_PROMPT,
+                    Box::new(Input::new(
+                        PropsBuilder::default()
+                            .with_texts(TextParts::new(Some(title), None))
+                            .with_input(input_type)
+                            .build(),
+                    )),
+                );
+                self.view.active(super::COMPONENT_INPUT_AUTH_PROMPT);
+            }
+            None => self.umount_auth_prompt(),
+        }
+    }
+
+    pub(super) fn umount_auth_prompt(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_AUTH_PROMPT);
     }
 
     pub(super) fn mount_mkdir(&mut self) {
@@ -580,6 +1630,36 @@ impl FileTransferActivity {
         self.view.umount(super::COMPONENT_INPUT_RENAME);
     }
 
+    pub(super) fn mount_chmod(&mut self, mode: Option<(u8, u8, u8)>) {
+        let value: String = match mode {
+            Some((u, g, o)) => format!("{}{}{}", u, g, o),
+            None => String::new(),
+        };
+        // Permission templates can be typed instead of the mode
+        let templates: Vec<String> = match self.context.as_ref().unwrap().config_client.as_ref() {
+            Some(cli) => cli.get_permission_templates().into_keys().collect(),
+            None => Vec::new(),
+        };
+        let title: String = match templates.is_empty() {
+            true => String::from("Permissions (mode [uid:gid])"),
+            false => format!("Permissions (mode [uid:gid] or {})", templates.join(", ")),
+        };
+        self.view.mount(
+            super::COMPONENT_INPUT_CHMOD,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(Some(title), None))
+                    .with_value(PropValue::Str(value))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_CHMOD);
+    }
+
+    pub(super) fn umount_chmod(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_CHMOD);
+    }
+
     pub(super) fn mount_saveas(&mut self) {
         self.view.mount(
             super::COMPONENT_INPUT_SAVEAS,
@@ -607,11 +1687,25 @@ impl FileTransferActivity {
                     .build(),
             )),
         );
+        // Multi-file transfers whose size is known in advance report the overall progress too
+        if self.transfer.batch_files > 0 {
+            self.view.mount(
+                super::COMPONENT_PROGRESS_BAR_TOTAL,
+                Box::new(ProgressBar::new(
+                    PropsBuilder::default()
+                        .with_foreground(Color::LightBlue)
+                        .with_background(Color::Black)
+                        .with_texts(TextParts::new(Some(String::from("Overall")), None))
+                        .build(),
+                )),
+            );
+        }
         self.view.active(super::COMPONENT_PROGRESS_BAR);
     }
 
     pub(super) fn umount_progress_bar(&mut self) {
         self.view.umount(super::COMPONENT_PROGRESS_BAR);
+        self.view.umount(super::COMPONENT_PROGRESS_BAR_TOTAL);
     }
 
     pub(super) fn mount_file_sorting(&mut self) {
@@ -653,6 +1747,20 @@ impl FileTransferActivity {
     }
 
     pub(super) fn mount_radio_delete(&mut self) {
+        let verb: &str = match self.transfer_opts.use_trash {
+            true => "Trash",
+            false => "Delete",
+        };
+        // Selected find results are deleted all at once
+        let title: String = match self.tab {
+            FileExplorerTab::FindLocal | FileExplorerTab::FindRemote
+                if !self.found_selection.marked.is_empty() =>
+            {
+                let (count, size): (usize, u64) = self.found_size();
+                format!("{} {} selected files ({})", verb, count, ByteSize(size))
+            }
+            _ => format!("{} file", verb),
+        };
         self.view.mount(
             super::COMPONENT_RADIO_DELETE,
             Box::new(RadioGroup::new(
@@ -660,7 +1768,7 @@ impl FileTransferActivity {
                     .with_foreground(Color::Red)
                     .with_background(Color::Black)
                     .with_texts(TextParts::new(
-                        Some(String::from("Delete file")),
+                        Some(title),
                         Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
                     ))
                     .with_value(PropValue::Unsigned(1))
@@ -674,7 +1782,97 @@ impl FileTransferActivity {
         self.view.umount(super::COMPONENT_RADIO_DELETE);
     }
 
-    pub(super) fn mount_file_info(&mut self, file: &FsEntry) {
+    /// ### mount_radio_move
+    ///
+    /// Mount the popup confirming the move of the selected entry to the other host
+    pub(super) fn mount_radio_move(&mut self) {
+        let (entry, host): (Option<&FsEntry>, &str) = match self.tab {
+            FileExplorerTab::Local => (self.get_local_file_entry(), "remote"),
+            _ => (self.get_remote_file_entry(), "local"),
+        };
+        let title: String = match entry {
+            Some(entry) => format!("Move \"{}\" to {} host", entry.get_name(), host),
+            None => return,
+        };
+        self.view.mount(
+            super::COMPONENT_RADIO_MOVE,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(title),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .with_value(PropValue::Unsigned(1))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_RADIO_MOVE);
+    }
+
+    pub(super) fn umount_radio_move(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_MOVE);
+    }
+
+    /// ### mount_radio_purge
+    ///
+    /// Mount the popup confirming the removal of all the entries in the remote trash
+    pub(super) fn mount_radio_purge(&mut self) {
+        self.view.mount(
+            super::COMPONENT_RADIO_PURGE,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Red)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(format!(
+                            "Empty remote trash \"{}\"?",
+                            self.transfer_opts.trash_dir
+                        )),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .with_value(PropValue::Unsigned(1))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_RADIO_PURGE);
+    }
+
+    pub(super) fn umount_radio_purge(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_PURGE);
+    }
+
+    pub(super) fn mount_radio_recovery(&mut self, transfers: usize, partials: usize) {
+        self.view.mount(
+            super::COMPONENT_RADIO_RECOVERY,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(format!(
+                            "Interrupted queue: {} transfers left, {} partial files",
+                            transfers, partials
+                        )),
+                        Some(vec![
+                            TextSpan::from("Resume"),
+                            TextSpan::from("Clean up"),
+                            TextSpan::from("Later"),
+                        ]),
+                    ))
+                    .with_value(PropValue::Unsigned(0))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_RADIO_RECOVERY);
+    }
+
+    pub(super) fn umount_radio_recovery(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_RECOVERY);
+    }
+
+    pub(super) fn mount_file_info(&mut self, file: &FsEntry, dir_size: Option<u64>) {
         let mut texts: TableBuilder = TableBuilder::default();
         // Abs path
         let real_path: Option<PathBuf> = {
@@ -704,9 +1902,14 @@ impl FileTransferActivity {
                         .build(),
                 );
         }
-        let (bsize, size): (ByteSize, usize) = (ByteSize(file.get_size() as u64), file.get_size());
+        let calculating: bool = matches!(self.disk_usage_task.as_ref(), Some((_, x)) if x.get_abs_path() == file.get_abs_path());
+        let size: String = match (dir_size, calculating) {
+            (Some(size), _) => format!("{} ({})", ByteSize(size), size),
+            (None, true) => String::from("Calculating..."),
+            (None, false) => format!("{} ({})", ByteSize(file.get_size() as u64), file.get_size()),
+        };
         texts.add_row().add_col(TextSpan::from("Size: ")).add_col(
-            TextSpanBuilder::new(format!("{} ({})", bsize, size).as_str())
+            TextSpanBuilder::new(size.as_str())
                 .with_foreground(Color::Cyan)
                 .build(),
         );
@@ -846,7 +2049,7 @@ impl FileTransferActivity {
                                     .with_foreground(Color::Cyan)
                                     .build(),
                             )
-                            .add_col(TextSpan::from("         Enter directory"))
+                            .add_col(TextSpan::from("         Enter directory or archive"))
                             .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<SPACE>")
@@ -912,6 +2115,22 @@ impl FileTransferActivity {
                             )
                             .add_col(TextSpan::from("             Show info about selected file"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<J>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Jump to pinned directory"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<K>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Save server capability report"))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<L>")
                                     .bold()
@@ -920,6 +2139,16 @@ impl FileTransferActivity {
                             )
                             .add_col(TextSpan::from("             Reload directory content"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<M>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "             Add selected file to transfer queue",
+                            ))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<N>")
                                     .bold()
@@ -936,6 +2165,14 @@ impl FileTransferActivity {
                             )
                             .add_col(TextSpan::from("             Open text file"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<P>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Pin/unpin working directory"))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<Q>")
                                     .bold()
@@ -960,6 +2197,14 @@ impl FileTransferActivity {
                             )
                             .add_col(TextSpan::from("             Save file as"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<T>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Show transfer queue"))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<U>")
                                     .bold()
@@ -968,6 +2213,40 @@ impl FileTransferActivity {
                             )
                             .add_col(TextSpan::from("             Go to parent directory"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<V>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "             Toggle paths relative to session root",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<W>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Go to symlink target"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<Y>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Toggle synchronized browsing"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<Z>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Change file permissions"))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<DEL|E>")
                                     .bold()
@@ -976,6 +2255,24 @@ impl FileTransferActivity {
                             )
                             .add_col(TextSpan::from("         Delete selected file"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+X>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Move selected file to the other host",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+E>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Empty remote trash"))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<CTRL+C>")
                                     .bold()
@@ -983,6 +2280,108 @@ impl FileTransferActivity {
                                     .build(),
                             )
                             .add_col(TextSpan::from("        Interrupt file transfer"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<P>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Pause/resume file transfer"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+W>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Watch local directory and upload changes",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+F>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Edit include/exclude filters of transfers",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+S>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Saved searches (save the search from its results)",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+D>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Clean up old or large files (delete or move them)",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+R>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Browse and search the transfer history",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+T>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "        Connect to another host in a new tab",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+N>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Switch to the next tab"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+P>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Switch to the previous tab"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<E>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "             Log: show all, warnings or errors only",
+                            ))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<F>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("             Log: search text"))
                             .build(),
                     ))
                     .build(),
@@ -109,7 +109,6 @@ pub const MSG_KEY_CHAR_I: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('i'),
     modifiers: KeyModifiers::NONE,
 });
-/*
 pub const MSG_KEY_CHAR_J: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('j'),
     modifiers: KeyModifiers::NONE,
@@ -118,17 +117,14 @@ pub const MSG_KEY_CHAR_K: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('k'),
     modifiers: KeyModifiers::NONE,
 });
-*/
 pub const MSG_KEY_CHAR_L: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('l'),
     modifiers: KeyModifiers::NONE,
 });
-/*
 pub const MSG_KEY_CHAR_M: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('m'),
     modifiers: KeyModifiers::NONE,
 });
-*/
 pub const MSG_KEY_CHAR_N: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('n'),
     modifiers: KeyModifiers::NONE,
@@ -137,12 +133,10 @@ pub const MSG_KEY_CHAR_O: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('o'),
     modifiers: KeyModifiers::NONE,
 });
-/*
 pub const MSG_KEY_CHAR_P: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('p'),
     modifiers: KeyModifiers::NONE,
 });
-*/
 pub const MSG_KEY_CHAR_Q: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('q'),
     modifiers: KeyModifiers::NONE,
@@ -155,17 +149,14 @@ pub const MSG_KEY_CHAR_S: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('s'),
     modifiers: KeyModifiers::NONE,
 });
-/*
 pub const MSG_KEY_CHAR_T: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('t'),
     modifiers: KeyModifiers::NONE,
 });
-*/
 pub const MSG_KEY_CHAR_U: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('u'),
     modifiers: KeyModifiers::NONE,
 });
-/*
 pub const MSG_KEY_CHAR_V: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('v'),
     modifiers: KeyModifiers::NONE,
@@ -174,12 +165,10 @@ pub const MSG_KEY_CHAR_W: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('w'),
     modifiers: KeyModifiers::NONE,
 });
-*/
 pub const MSG_KEY_CHAR_X: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('x'),
     modifiers: KeyModifiers::NONE,
 });
-/*
 pub const MSG_KEY_CHAR_Y: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('y'),
     modifiers: KeyModifiers::NONE,
@@ -188,17 +177,28 @@ pub const MSG_KEY_CHAR_Z: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('z'),
     modifiers: KeyModifiers::NONE,
 });
-*/
 
 // -- control
+pub const MSG_KEY_CTRL_B: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('b'),
+    modifiers: KeyModifiers::CONTROL,
+});
 pub const MSG_KEY_CTRL_C: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('c'),
     modifiers: KeyModifiers::CONTROL,
 });
+pub const MSG_KEY_CTRL_D: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('d'),
+    modifiers: KeyModifiers::CONTROL,
+});
 pub const MSG_KEY_CTRL_E: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('e'),
     modifiers: KeyModifiers::CONTROL,
 });
+pub const MSG_KEY_CTRL_F: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('f'),
+    modifiers: KeyModifiers::CONTROL,
+});
 pub const MSG_KEY_CTRL_H: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('h'),
     modifiers: KeyModifiers::CONTROL,
@@ -207,6 +207,14 @@ pub const MSG_KEY_CTRL_N: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('n'),
     modifiers: KeyModifiers::CONTROL,
 });
+pub const MSG_KEY_CTRL_O: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('o'),
+    modifiers: KeyModifiers::CONTROL,
+});
+pub const MSG_KEY_CTRL_P: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('p'),
+    modifiers: KeyModifiers::CONTROL,
+});
 pub const MSG_KEY_CTRL_R: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('r'),
     modifiers: KeyModifiers::CONTROL,
@@ -215,3 +223,19 @@ pub const MSG_KEY_CTRL_S: Msg = Msg::OnKey(KeyEvent {
     code: KeyCode::Char('s'),
     modifiers: KeyModifiers::CONTROL,
 });
+pub const MSG_KEY_CTRL_T: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('t'),
+    modifiers: KeyModifiers::CONTROL,
+});
+pub const MSG_KEY_CTRL_W: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('w'),
+    modifiers: KeyModifiers::CONTROL,
+});
+pub const MSG_KEY_CTRL_X: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('x'),
+    modifiers: KeyModifiers::CONTROL,
+});
+pub const MSG_KEY_CTRL_Y: Msg = Msg::OnKey(KeyEvent {
+    code: KeyCode::Char('y'),
+    modifiers: KeyModifiers::CONTROL,
+});
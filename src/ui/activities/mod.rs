@@ -27,10 +27,11 @@
  * SOFTWARE.
  */
 // Locals
-use super::context::Context;
+use super::context::{Context, FileTransferParams};
 // keymap
 pub(crate) mod keymap;
 // Activities
+pub mod attach_activity;
 pub mod auth_activity;
 pub mod filetransfer_activity;
 pub mod setup_activity;
@@ -39,9 +40,21 @@ pub mod setup_activity;
 
 pub enum ExitReason {
     Quit,
-    Connect,
+    Connect(Box<FileTransferParams>),
     Disconnect,
     EnterSetup,
+    NewTab, // Keep the session open in the background and connect to another host in a new tab
+    SwitchTab(isize), // Switch to the session tab at this offset from the active one
+}
+
+// -- Activity result
+
+/// ## ActivityResult
+///
+/// Result passed to an activity when it is resumed, once the activity it has launched has terminated
+pub enum ActivityResult {
+    Disconnected, // File transfer activity has disconnected from the remote host
+    SetupClosed,  // Setup activity has been closed; configuration may have changed
 }
 
 // -- Activity trait
@@ -73,4 +86,23 @@ pub trait Activity {
     /// This function must be called once before terminating the activity.
     /// This function finally releases the context
     fn on_destroy(&mut self) -> Option<Context>;
+
+    /// ### on_pause
+    ///
+    /// `on_pause` is called when another activity is launched on top of this one.
+    /// The activity must release the context, keeping its state, since it can be resumed later.
+    /// By default the activity is destroyed
+    fn on_pause(&mut self) -> Option<Context> {
+        self.on_destroy()
+    }
+
+    /// ### on_resume
+    ///
+    /// `on_resume` is called when the activity launched on top of this one has terminated.
+    /// The context is given back to the activity, along with the result of the terminated activity.
+    /// By default the activity is created again
+    fn on_resume(&mut self, context: Context, result: Option<ActivityResult>) {
+        let _ = result;
+        self.on_create(context);
+    }
 }
@@ -28,10 +28,15 @@
  */
 // Locals
 use super::SetupActivity;
+use crate::system::backup::RestoreSource;
+use crate::ui::bus::{Notification, ToastLevel};
 use crate::ui::layout::Payload;
+use crate::utils::secret::Secret;
 // Ext
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use std::env;
+use std::fs;
+use std::path::PathBuf;
 
 impl SetupActivity {
     /// ### action_save_config
@@ -40,6 +45,8 @@ impl SetupActivity {
     pub(super) fn action_save_config(&mut self) -> Result<(), String> {
         // Collect input values
         self.collect_input_values();
+        self.collect_network_values()?;
+        self.collect_transfer_values()?;
         self.save_config()
     }
 
@@ -93,6 +100,31 @@ impl SetupActivity {
         }
     }
 
+    /// ### action_copy_ssh_key
+    ///
+    /// Copy the selected private key to the clipboard
+    pub(super) fn action_copy_ssh_key(&mut self) {
+        let idx: usize = match self.view.get_value(super::COMPONENT_LIST_SSH_KEYS) {
+            Some(Payload::Unsigned(idx)) => idx,
+            _ => return,
+        };
+        let ctx = self.context.as_mut().unwrap();
+        let path: Option<PathBuf> = ctx.config_client.as_ref().and_then(|cli| {
+            cli.iter_ssh_keys()
+                .nth(idx)
+                .and_then(|key| cli.get_ssh_key(key).ok().flatten())
+                .map(|(_, _, path)| path)
+        });
+        if let Some(path) = path {
+            let result = fs::read_to_string(path.as_path())
+                .map(Secret::new)
+                .and_then(|key| ctx.copy_secret(key.expose()));
+            if let Err(err) = result {
+                self.mount_error(format!("Could not copy ssh key: {}", err).as_str());
+            }
+        }
+    }
+
     /// ### action_new_ssh_key
     ///
     /// Create a new ssh key
@@ -153,4 +185,107 @@ impl SetupActivity {
             }
         }
     }
+
+    /// ### action_delete_known_host
+    ///
+    /// Delete selected known host entry
+    pub(super) fn action_delete_known_host(&mut self) {
+        if let Some(Payload::Unsigned(idx)) = self.view.get_value(super::COMPONENT_LIST_KNOWN_HOSTS)
+        {
+            if let Some(hosts) = self.known_hosts.as_mut() {
+                hosts.remove(idx);
+            }
+            if let Err(err) = self.save_known_hosts() {
+                self.mount_error(err.as_str());
+            }
+        }
+    }
+
+    /// ### action_reverify_known_host
+    ///
+    /// Toggle whether the selected known host must be re-verified on next connect
+    pub(super) fn action_reverify_known_host(&mut self) {
+        if let Some(Payload::Unsigned(idx)) = self.view.get_value(super::COMPONENT_LIST_KNOWN_HOSTS)
+        {
+            if let Some(hosts) = self.known_hosts.as_mut() {
+                let reverify: bool = hosts.get(idx).map(|x| !x.reverify).unwrap_or(false);
+                hosts.set_reverify(idx, reverify);
+            }
+            if let Err(err) = self.save_known_hosts() {
+                self.mount_error(err.as_str());
+            }
+        }
+    }
+
+    /// ### action_prepare_restore
+    ///
+    /// Prepare restore from the bundle at `path`, or from the automatic backups if `path` is empty;
+    /// then show what would change
+    pub(super) fn action_prepare_restore(&mut self, path: &str) {
+        let source: RestoreSource = match path.trim() {
+            "" => RestoreSource::Backups,
+            path => RestoreSource::Bundle(PathBuf::from(path)),
+        };
+        match self.prepare_restore(&source) {
+            Ok(plan) if plan.is_empty() => {
+                self.context
+                    .as_mut()
+                    .unwrap()
+                    .bus
+                    .publish(Notification::Toast(
+                        ToastLevel::Info,
+                        String::from("Configuration is already up to date"),
+                    ));
+            }
+            Ok(plan) => {
+                self.restore = Some(plan);
+                self.mount_restore_preview();
+            }
+            Err(err) => self.mount_error(err.as_str()),
+        }
+    }
+
+    /// ### action_apply_restore
+    ///
+    /// Apply the pending restore and reload configuration
+    pub(super) fn action_apply_restore(&mut self) {
+        if let Some(plan) = self.restore.take() {
+            if let Err(err) = plan.apply() {
+                self.mount_error(format!("Could not restore configuration: {}", err).as_str());
+                return;
+            }
+            let ctx = self.context.as_mut().unwrap();
+            let issues: Vec<String> = ctx.reload_config().iter().map(|x| x.to_string()).collect();
+            ctx.bus.publish(Notification::Toast(
+                ToastLevel::Info,
+                String::from("Configuration restored"),
+            ));
+            // Reload view with restored values
+            self.init_setup();
+            if !issues.is_empty() {
+                self.mount_error(issues.join("\n").as_str());
+            }
+        }
+    }
+
+    /// ### action_export_config
+    ///
+    /// Export configuration and bookmarks to the bundle at `path`
+    pub(super) fn action_export_config(&mut self, path: &str) {
+        if path.trim().is_empty() {
+            return;
+        }
+        match self.export_config(PathBuf::from(path.trim()).as_path()) {
+            Ok(_) => self
+                .context
+                .as_mut()
+                .unwrap()
+                .bus
+                .publish(Notification::Toast(
+                    ToastLevel::Info,
+                    format!("Configuration exported to \"{}\"", path.trim()),
+                )),
+            Err(err) => self.mount_error(err.as_str()),
+        }
+    }
 }
@@ -28,9 +28,14 @@
  */
 // Locals
 use super::SetupActivity;
+use crate::system::backup::{self, RestorePlan, RestoreSource};
+use crate::system::environment;
+use crate::system::known_hosts::KnownHosts;
+use crate::ui::bus::{Notification, ToastLevel};
 // Ext
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 use std::env;
+use std::path::{Path, PathBuf};
 
 impl SetupActivity {
     /// ### save_config
@@ -39,13 +44,83 @@ impl SetupActivity {
     pub(super) fn save_config(&mut self) -> Result<(), String> {
         match self.context.as_ref().unwrap().config_client.as_ref() {
             Some(cli) => match cli.write_config() {
-                Ok(_) => Ok(()),
+                Ok(_) => {
+                    // Notify theme changes
+                    let ctx = self.context.as_mut().unwrap();
+                    ctx.update_color_mode();
+                    ctx.bus.publish(Notification::Toast(
+                        ToastLevel::Info,
+                        String::from("Configuration saved"),
+                    ));
+                    Ok(())
+                }
                 Err(err) => Err(format!("Could not save configuration: {}", err)),
             },
             None => Ok(()),
         }
     }
 
+    /// ### load_known_hosts
+    ///
+    /// Load known hosts from the configuration directory
+    pub(super) fn load_known_hosts(&mut self) -> Result<(), String> {
+        let config_dir = match environment::init_config_dir() {
+            Ok(Some(dir)) => dir,
+            Ok(None) => return Err(String::from("Could not find configuration directory")),
+            Err(err) => return Err(err),
+        };
+        let path = environment::get_known_hosts_path(config_dir.as_path());
+        match KnownHosts::load(path.as_path()) {
+            Ok(hosts) => {
+                self.known_hosts = Some(hosts);
+                Ok(())
+            }
+            Err(err) => Err(format!("Could not read known hosts: {}", err)),
+        }
+    }
+
+    /// ### save_known_hosts
+    ///
+    /// Write known hosts to file
+    pub(super) fn save_known_hosts(&self) -> Result<(), String> {
+        match self.known_hosts.as_ref() {
+            Some(hosts) => hosts
+                .save()
+                .map_err(|e| format!("Could not write known hosts: {}", e)),
+            None => Ok(()),
+        }
+    }
+
+    /// ### get_config_files
+    ///
+    /// Get paths of configuration and bookmarks files
+    fn get_config_files(&self) -> Result<(PathBuf, PathBuf), String> {
+        match environment::init_config_dir() {
+            Ok(Some(dir)) => {
+                let (config, _) = environment::get_config_paths(dir.as_path());
+                Ok((config, environment::get_bookmarks_paths(dir.as_path())))
+            }
+            Ok(None) => Err(String::from("Could not find configuration directory")),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// ### prepare_restore
+    ///
+    /// Prepare restore of configuration and bookmarks from `source`
+    pub(super) fn prepare_restore(&self, source: &RestoreSource) -> Result<RestorePlan, String> {
+        let (config, bookmarks) = self.get_config_files()?;
+        backup::plan_restore(source, config.as_path(), bookmarks.as_path())
+    }
+
+    /// ### export_config
+    ///
+    /// Export configuration and bookmarks to bundle at `dest`
+    pub(super) fn export_config(&self, dest: &Path) -> Result<(), String> {
+        let (config, bookmarks) = self.get_config_files()?;
+        backup::export_bundle(config.as_path(), bookmarks.as_path(), dest)
+    }
+
     /// ### reset_config_changes
     ///
     /// Reset configuration changes; pratically read config from file, overwriting any change made
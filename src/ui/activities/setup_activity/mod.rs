@@ -38,8 +38,12 @@ extern crate tui;
 
 // Locals
 use super::{Activity, Context, ExitReason};
+use crate::system::backup::RestorePlan;
+use crate::system::known_hosts::KnownHosts;
+use crate::ui::input::InputHandler;
 use crate::ui::layout::view::View;
 // Ext
+use crossterm::event::Event as InputEvent;
 use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
 
 // -- components
@@ -54,11 +58,51 @@ const COMPONENT_RADIO_HIDDEN_FILES: &str = "RADIO_HIDDEN_FILES";
 const COMPONENT_RADIO_UPDATES: &str = "RADIO_CHECK_UPDATES";
 const COMPONENT_RADIO_GROUP_DIRS: &str = "RADIO_GROUP_DIRS";
 const COMPONENT_INPUT_FILE_FMT: &str = "INPUT_FILE_FMT";
+const COMPONENT_RADIO_COLOR_MODE: &str = "RADIO_COLOR_MODE";
+const COMPONENT_RADIO_REDUCED_MOTION: &str = "RADIO_REDUCED_MOTION";
+const COMPONENT_RADIO_NEVER_STORE_CREDENTIALS: &str = "RADIO_NEVER_STORE_CREDENTIALS";
+const COMPONENT_INPUT_CLIPBOARD_TIMEOUT: &str = "INPUT_CLIPBOARD_TIMEOUT";
 const COMPONENT_RADIO_TAB: &str = "RADIO_TAB";
 const COMPONENT_LIST_SSH_KEYS: &str = "LIST_SSH_KEYS";
 const COMPONENT_INPUT_SSH_HOST: &str = "INPUT_SSH_HOST";
 const COMPONENT_INPUT_SSH_USERNAME: &str = "INPUT_SSH_USERNAME";
 const COMPONENT_RADIO_DEL_SSH_KEY: &str = "RADIO_DEL_SSH_KEY";
+const COMPONENT_LIST_KNOWN_HOSTS: &str = "LIST_KNOWN_HOSTS";
+const COMPONENT_RADIO_DEL_KNOWN_HOST: &str = "RADIO_DEL_KNOWN_HOST";
+const COMPONENT_INPUT_PROXY: &str = "INPUT_PROXY";
+const COMPONENT_INPUT_CONNECTION_TIMEOUT: &str = "INPUT_CONNECTION_TIMEOUT";
+const COMPONENT_INPUT_KEEPALIVE_INTERVAL: &str = "INPUT_KEEPALIVE_INTERVAL";
+const COMPONENT_INPUT_MAX_CONCURRENCY: &str = "INPUT_MAX_CONCURRENCY";
+const COMPONENT_INPUT_BANDWIDTH_LIMIT: &str = "INPUT_BANDWIDTH_LIMIT";
+const COMPONENT_INPUT_RETRIES: &str = "INPUT_RETRIES";
+const COMPONENT_INPUT_RETRY_BACKOFF: &str = "INPUT_RETRY_BACKOFF";
+const COMPONENT_RADIO_ADDRESS_FAMILY: &str = "RADIO_ADDRESS_FAMILY";
+const COMPONENT_INPUT_WEBHOOK_URL: &str = "INPUT_WEBHOOK_URL";
+const COMPONENT_INPUT_WEBHOOK_TOKEN: &str = "INPUT_WEBHOOK_TOKEN";
+const COMPONENT_RADIO_OVERWRITE_POLICY: &str = "RADIO_OVERWRITE_POLICY";
+const COMPONENT_RADIO_PRESERVE_TIMESTAMPS: &str = "RADIO_PRESERVE_TIMESTAMPS";
+const COMPONENT_RADIO_PRESERVE_PERMISSIONS: &str = "RADIO_PRESERVE_PERMISSIONS";
+const COMPONENT_RADIO_ATOMIC_UPLOADS: &str = "RADIO_ATOMIC_UPLOADS";
+const COMPONENT_RADIO_PARTIAL_FILES: &str = "RADIO_PARTIAL_FILES";
+const COMPONENT_RADIO_SYMLINKS: &str = "RADIO_SYMLINKS";
+const COMPONENT_RADIO_VERIFY_CHECKSUMS: &str = "RADIO_VERIFY_CHECKSUMS";
+const COMPONENT_RADIO_SKIP_IDENTICAL: &str = "RADIO_SKIP_IDENTICAL";
+const COMPONENT_RADIO_CREATE_REMOTE_DIRS: &str = "RADIO_CREATE_REMOTE_DIRS";
+const COMPONENT_RADIO_ONE_FILE_SYSTEM: &str = "RADIO_ONE_FILE_SYSTEM";
+const COMPONENT_RADIO_INHIBIT_SLEEP: &str = "RADIO_INHIBIT_SLEEP";
+const COMPONENT_RADIO_COMPRESS_UPLOADS: &str = "RADIO_COMPRESS_UPLOADS";
+const COMPONENT_RADIO_PREALLOCATE: &str = "RADIO_PREALLOCATE";
+const COMPONENT_RADIO_DURABLE_WRITES: &str = "RADIO_DURABLE_WRITES";
+const COMPONENT_RADIO_USE_TRASH: &str = "RADIO_USE_TRASH";
+const COMPONENT_INPUT_TRASH_DIR: &str = "INPUT_TRASH_DIR";
+const COMPONENT_INPUT_IGNORE_RULES: &str = "INPUT_IGNORE_RULES";
+const COMPONENT_INPUT_UPLOAD_PERMISSIONS: &str = "INPUT_UPLOAD_PERMISSIONS";
+const COMPONENT_INPUT_BANDWIDTH_SCHEDULE: &str = "INPUT_BANDWIDTH_SCHEDULE";
+const COMPONENT_INPUT_STAGING_DIR: &str = "INPUT_STAGING_DIR";
+const COMPONENT_INPUT_RESTORE_BUNDLE: &str = "INPUT_RESTORE_BUNDLE";
+const COMPONENT_INPUT_EXPORT_BUNDLE: &str = "INPUT_EXPORT_BUNDLE";
+const COMPONENT_TABLE_RESTORE_PREVIEW: &str = "TABLE_RESTORE_PREVIEW";
+const COMPONENT_RADIO_RESTORE: &str = "RADIO_RESTORE";
 
 /// ### ViewLayout
 ///
@@ -67,6 +111,9 @@ const COMPONENT_RADIO_DEL_SSH_KEY: &str = "RADIO_DEL_SSH_KEY";
 enum ViewLayout {
     SetupForm,
     SshKeys,
+    KnownHosts,
+    Network,
+    Transfer,
 }
 
 /// ## SetupActivity
@@ -74,9 +121,11 @@ enum ViewLayout {
 /// Setup activity states holder
 pub struct SetupActivity {
     exit_reason: Option<ExitReason>,
-    context: Option<Context>, // Context holder
-    view: View,               // View
-    layout: ViewLayout,       // View layout
+    context: Option<Context>,        // Context holder
+    view: View,                      // View
+    layout: ViewLayout,              // View layout
+    known_hosts: Option<KnownHosts>, // Known hosts; loaded when the tab is shown
+    restore: Option<RestorePlan>,    // Restore waiting for confirmation
     redraw: bool,
 }
 
@@ -92,6 +141,8 @@ impl Default for SetupActivity {
             context: None,
             view: View::init(),
             layout: ViewLayout::SetupForm,
+            known_hosts: None,
+            restore: None,
             redraw: true, // Draw at first `on_draw`
         }
     }
@@ -112,9 +163,17 @@ impl Activity for SetupActivity {
         let _ = enable_raw_mode();
         // Init view
         self.init_setup();
-        // Verify error state from context
-        if let Some(err) = self.context.as_mut().unwrap().get_error() {
-            self.mount_error(err.as_str());
+        // Verify startup issues from context
+        let issues: Vec<String> = self
+            .context
+            .as_mut()
+            .unwrap()
+            .take_issues()
+            .iter()
+            .map(|x| x.to_string())
+            .collect();
+        if !issues.is_empty() {
+            self.mount_error(issues.join("\n").as_str());
         }
     }
 
@@ -131,10 +190,23 @@ impl Activity for SetupActivity {
         if let Ok(Some(event)) = self.context.as_ref().unwrap().input_hnd.read_event() {
             // Set redraw to true
             self.redraw = true;
+            // Lay out ui again if terminal has been resized
+            if let InputEvent::Resize(width, height) = event {
+                self.context.as_mut().unwrap().resize(width, height);
+            }
+            // Suspend to shell; screen is redrawn at next tick once resumed
+            if InputHandler::is_suspend_event(&event) {
+                self.context.as_mut().unwrap().suspend();
+                return;
+            }
             // Handle event
             let msg = self.view.on(event);
             self.update(msg);
         }
+        // Show toasts
+        if self.context.as_mut().unwrap().poll_toast() {
+            self.redraw = true;
+        }
         // Redraw if necessary
         if self.redraw {
             // View
@@ -28,11 +28,26 @@
  */
 // locals
 use super::{
-    SetupActivity, COMPONENT_INPUT_FILE_FMT, COMPONENT_INPUT_SSH_HOST,
-    COMPONENT_INPUT_SSH_USERNAME, COMPONENT_INPUT_TEXT_EDITOR, COMPONENT_LIST_SSH_KEYS,
-    COMPONENT_RADIO_DEFAULT_PROTOCOL, COMPONENT_RADIO_DEL_SSH_KEY, COMPONENT_RADIO_GROUP_DIRS,
-    COMPONENT_RADIO_HIDDEN_FILES, COMPONENT_RADIO_QUIT, COMPONENT_RADIO_SAVE,
-    COMPONENT_RADIO_UPDATES, COMPONENT_TEXT_ERROR, COMPONENT_TEXT_HELP,
+    SetupActivity, COMPONENT_INPUT_BANDWIDTH_LIMIT, COMPONENT_INPUT_BANDWIDTH_SCHEDULE,
+    COMPONENT_INPUT_CLIPBOARD_TIMEOUT, COMPONENT_INPUT_CONNECTION_TIMEOUT,
+    COMPONENT_INPUT_EXPORT_BUNDLE, COMPONENT_INPUT_FILE_FMT, COMPONENT_INPUT_IGNORE_RULES,
+    COMPONENT_INPUT_KEEPALIVE_INTERVAL, COMPONENT_INPUT_MAX_CONCURRENCY, COMPONENT_INPUT_PROXY,
+    COMPONENT_INPUT_RESTORE_BUNDLE, COMPONENT_INPUT_RETRIES, COMPONENT_INPUT_RETRY_BACKOFF,
+    COMPONENT_INPUT_SSH_HOST, COMPONENT_INPUT_SSH_USERNAME, COMPONENT_INPUT_STAGING_DIR,
+    COMPONENT_INPUT_TEXT_EDITOR, COMPONENT_INPUT_TRASH_DIR, COMPONENT_INPUT_UPLOAD_PERMISSIONS,
+    COMPONENT_INPUT_WEBHOOK_TOKEN, COMPONENT_INPUT_WEBHOOK_URL, COMPONENT_LIST_KNOWN_HOSTS,
+    COMPONENT_LIST_SSH_KEYS, COMPONENT_RADIO_ADDRESS_FAMILY, COMPONENT_RADIO_ATOMIC_UPLOADS,
+    COMPONENT_RADIO_COLOR_MODE, COMPONENT_RADIO_COMPRESS_UPLOADS,
+    COMPONENT_RADIO_CREATE_REMOTE_DIRS, COMPONENT_RADIO_DEFAULT_PROTOCOL,
+    COMPONENT_RADIO_DEL_KNOWN_HOST, COMPONENT_RADIO_DEL_SSH_KEY, COMPONENT_RADIO_DURABLE_WRITES,
+    COMPONENT_RADIO_GROUP_DIRS, COMPONENT_RADIO_HIDDEN_FILES, COMPONENT_RADIO_INHIBIT_SLEEP,
+    COMPONENT_RADIO_NEVER_STORE_CREDENTIALS, COMPONENT_RADIO_ONE_FILE_SYSTEM,
+    COMPONENT_RADIO_OVERWRITE_POLICY, COMPONENT_RADIO_PARTIAL_FILES, COMPONENT_RADIO_PREALLOCATE,
+    COMPONENT_RADIO_PRESERVE_PERMISSIONS, COMPONENT_RADIO_PRESERVE_TIMESTAMPS,
+    COMPONENT_RADIO_QUIT, COMPONENT_RADIO_REDUCED_MOTION, COMPONENT_RADIO_RESTORE,
+    COMPONENT_RADIO_SAVE, COMPONENT_RADIO_SKIP_IDENTICAL, COMPONENT_RADIO_SYMLINKS,
+    COMPONENT_RADIO_UPDATES, COMPONENT_RADIO_USE_TRASH, COMPONENT_RADIO_VERIFY_CHECKSUMS,
+    COMPONENT_TEXT_ERROR, COMPONENT_TEXT_HELP,
 };
 use crate::ui::activities::keymap::*;
 use crate::ui::layout::{Msg, Payload};
@@ -70,10 +85,42 @@ impl SetupActivity {
                     None
                 }
                 (COMPONENT_INPUT_FILE_FMT, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_COLOR_MODE);
+                    None
+                }
+                (COMPONENT_RADIO_COLOR_MODE, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_REDUCED_MOTION);
+                    None
+                }
+                (COMPONENT_RADIO_REDUCED_MOTION, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_NEVER_STORE_CREDENTIALS);
+                    None
+                }
+                (COMPONENT_RADIO_NEVER_STORE_CREDENTIALS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_CLIPBOARD_TIMEOUT);
+                    None
+                }
+                (COMPONENT_INPUT_CLIPBOARD_TIMEOUT, &MSG_KEY_DOWN) => {
                     self.view.active(COMPONENT_INPUT_TEXT_EDITOR);
                     None
                 }
                 // Input field <UP>
+                (COMPONENT_INPUT_CLIPBOARD_TIMEOUT, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_NEVER_STORE_CREDENTIALS);
+                    None
+                }
+                (COMPONENT_RADIO_NEVER_STORE_CREDENTIALS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_REDUCED_MOTION);
+                    None
+                }
+                (COMPONENT_RADIO_REDUCED_MOTION, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_COLOR_MODE);
+                    None
+                }
+                (COMPONENT_RADIO_COLOR_MODE, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_FILE_FMT);
+                    None
+                }
                 (COMPONENT_INPUT_FILE_FMT, &MSG_KEY_UP) => {
                     self.view.active(COMPONENT_RADIO_GROUP_DIRS);
                     None
@@ -95,7 +142,7 @@ impl SetupActivity {
                     None
                 }
                 (COMPONENT_INPUT_TEXT_EDITOR, &MSG_KEY_UP) => {
-                    self.view.active(COMPONENT_INPUT_FILE_FMT);
+                    self.view.active(COMPONENT_INPUT_CLIPBOARD_TIMEOUT);
                     None
                 }
                 // Error <ENTER> or <ESC>
@@ -146,6 +193,48 @@ impl SetupActivity {
                     self.umount_del_ssh_key();
                     None
                 }
+                // Delete known host
+                (COMPONENT_RADIO_DEL_KNOWN_HOST, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    self.action_delete_known_host();
+                    self.reload_known_hosts();
+                    self.umount_del_known_host();
+                    None
+                }
+                (COMPONENT_RADIO_DEL_KNOWN_HOST, Msg::OnSubmit(_)) => {
+                    self.umount_del_known_host();
+                    None
+                }
+                // Restore
+                (COMPONENT_INPUT_RESTORE_BUNDLE, Msg::OnSubmit(Payload::Text(path))) => {
+                    let path: String = path.clone();
+                    self.umount_restore_input();
+                    self.action_prepare_restore(path.as_str());
+                    None
+                }
+                (COMPONENT_INPUT_RESTORE_BUNDLE, &MSG_KEY_ESC) => {
+                    self.umount_restore_input();
+                    None
+                }
+                (COMPONENT_RADIO_RESTORE, Msg::OnSubmit(Payload::Unsigned(0))) => {
+                    self.action_apply_restore();
+                    self.umount_restore_preview();
+                    None
+                }
+                (COMPONENT_RADIO_RESTORE, Msg::OnSubmit(_)) => {
+                    self.umount_restore_preview();
+                    None
+                }
+                // Export
+                (COMPONENT_INPUT_EXPORT_BUNDLE, Msg::OnSubmit(Payload::Text(path))) => {
+                    let path: String = path.clone();
+                    self.umount_export_input();
+                    self.action_export_config(path.as_str());
+                    None
+                }
+                (COMPONENT_INPUT_EXPORT_BUNDLE, &MSG_KEY_ESC) => {
+                    self.umount_export_input();
+                    None
+                }
                 // Save popup
                 (COMPONENT_RADIO_SAVE, Msg::OnSubmit(Payload::Unsigned(0))) => {
                     // Save config
@@ -164,7 +253,300 @@ impl SetupActivity {
                 // <TAB> Change view
                 (COMPONENT_LIST_SSH_KEYS, &MSG_KEY_TAB) => {
                     // Change view
-                    self.init_setup();
+                    self.init_known_hosts();
+                    None
+                }
+                (COMPONENT_LIST_KNOWN_HOSTS, &MSG_KEY_TAB) => {
+                    // Change view
+                    self.init_network();
+                    None
+                }
+                (COMPONENT_INPUT_PROXY, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_CONNECTION_TIMEOUT, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_KEEPALIVE_INTERVAL, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_MAX_CONCURRENCY, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_BANDWIDTH_LIMIT, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_RETRIES, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_RETRY_BACKOFF, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_ADDRESS_FAMILY, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_WEBHOOK_URL, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_WEBHOOK_TOKEN, &MSG_KEY_TAB) => {
+                    // Validate values before leaving the page
+                    match self.collect_network_values() {
+                        Ok(_) => self.init_transfer(),
+                        Err(err) => self.mount_error(err.as_str()),
+                    }
+                    None
+                }
+                (COMPONENT_RADIO_OVERWRITE_POLICY, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_PRESERVE_TIMESTAMPS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_PRESERVE_PERMISSIONS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_ATOMIC_UPLOADS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_PARTIAL_FILES, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_SYMLINKS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_VERIFY_CHECKSUMS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_SKIP_IDENTICAL, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_CREATE_REMOTE_DIRS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_ONE_FILE_SYSTEM, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_INHIBIT_SLEEP, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_COMPRESS_UPLOADS, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_PREALLOCATE, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_DURABLE_WRITES, &MSG_KEY_TAB)
+                | (COMPONENT_RADIO_USE_TRASH, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_TRASH_DIR, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_IGNORE_RULES, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_UPLOAD_PERMISSIONS, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_BANDWIDTH_SCHEDULE, &MSG_KEY_TAB)
+                | (COMPONENT_INPUT_STAGING_DIR, &MSG_KEY_TAB) => {
+                    // Validate values before leaving the page
+                    match self.collect_transfer_values() {
+                        Ok(_) => self.init_setup(),
+                        Err(err) => self.mount_error(err.as_str()),
+                    }
+                    None
+                }
+                // Transfer <DOWN>
+                (COMPONENT_RADIO_OVERWRITE_POLICY, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_PRESERVE_TIMESTAMPS);
+                    None
+                }
+                (COMPONENT_RADIO_PRESERVE_TIMESTAMPS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_PRESERVE_PERMISSIONS);
+                    None
+                }
+                (COMPONENT_RADIO_PRESERVE_PERMISSIONS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_ATOMIC_UPLOADS);
+                    None
+                }
+                (COMPONENT_RADIO_ATOMIC_UPLOADS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_PARTIAL_FILES);
+                    None
+                }
+                (COMPONENT_RADIO_PARTIAL_FILES, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_SYMLINKS);
+                    None
+                }
+                (COMPONENT_RADIO_SYMLINKS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_VERIFY_CHECKSUMS);
+                    None
+                }
+                (COMPONENT_RADIO_VERIFY_CHECKSUMS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_SKIP_IDENTICAL);
+                    None
+                }
+                (COMPONENT_RADIO_SKIP_IDENTICAL, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_CREATE_REMOTE_DIRS);
+                    None
+                }
+                (COMPONENT_RADIO_CREATE_REMOTE_DIRS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_ONE_FILE_SYSTEM);
+                    None
+                }
+                (COMPONENT_RADIO_ONE_FILE_SYSTEM, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_INHIBIT_SLEEP);
+                    None
+                }
+                (COMPONENT_RADIO_INHIBIT_SLEEP, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_COMPRESS_UPLOADS);
+                    None
+                }
+                (COMPONENT_RADIO_COMPRESS_UPLOADS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_PREALLOCATE);
+                    None
+                }
+                (COMPONENT_RADIO_PREALLOCATE, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_DURABLE_WRITES);
+                    None
+                }
+                (COMPONENT_RADIO_DURABLE_WRITES, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_USE_TRASH);
+                    None
+                }
+                (COMPONENT_RADIO_USE_TRASH, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_TRASH_DIR);
+                    None
+                }
+                (COMPONENT_INPUT_TRASH_DIR, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_IGNORE_RULES);
+                    None
+                }
+                (COMPONENT_INPUT_IGNORE_RULES, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_UPLOAD_PERMISSIONS);
+                    None
+                }
+                (COMPONENT_INPUT_UPLOAD_PERMISSIONS, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_BANDWIDTH_SCHEDULE);
+                    None
+                }
+                (COMPONENT_INPUT_BANDWIDTH_SCHEDULE, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_STAGING_DIR);
+                    None
+                }
+                (COMPONENT_INPUT_STAGING_DIR, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_OVERWRITE_POLICY);
+                    None
+                }
+                // Transfer <UP>
+                (COMPONENT_RADIO_OVERWRITE_POLICY, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_STAGING_DIR);
+                    None
+                }
+                (COMPONENT_INPUT_STAGING_DIR, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_BANDWIDTH_SCHEDULE);
+                    None
+                }
+                (COMPONENT_INPUT_BANDWIDTH_SCHEDULE, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_UPLOAD_PERMISSIONS);
+                    None
+                }
+                (COMPONENT_INPUT_UPLOAD_PERMISSIONS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_IGNORE_RULES);
+                    None
+                }
+                (COMPONENT_RADIO_PRESERVE_TIMESTAMPS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_OVERWRITE_POLICY);
+                    None
+                }
+                (COMPONENT_RADIO_PRESERVE_PERMISSIONS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_PRESERVE_TIMESTAMPS);
+                    None
+                }
+                (COMPONENT_RADIO_ATOMIC_UPLOADS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_PRESERVE_PERMISSIONS);
+                    None
+                }
+                (COMPONENT_RADIO_PARTIAL_FILES, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_ATOMIC_UPLOADS);
+                    None
+                }
+                (COMPONENT_RADIO_SYMLINKS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_PARTIAL_FILES);
+                    None
+                }
+                (COMPONENT_RADIO_VERIFY_CHECKSUMS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_SYMLINKS);
+                    None
+                }
+                (COMPONENT_INPUT_IGNORE_RULES, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_TRASH_DIR);
+                    None
+                }
+                (COMPONENT_INPUT_TRASH_DIR, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_USE_TRASH);
+                    None
+                }
+                (COMPONENT_RADIO_USE_TRASH, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_DURABLE_WRITES);
+                    None
+                }
+                (COMPONENT_RADIO_DURABLE_WRITES, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_PREALLOCATE);
+                    None
+                }
+                (COMPONENT_RADIO_PREALLOCATE, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_COMPRESS_UPLOADS);
+                    None
+                }
+                (COMPONENT_RADIO_COMPRESS_UPLOADS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_INHIBIT_SLEEP);
+                    None
+                }
+                (COMPONENT_RADIO_INHIBIT_SLEEP, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_ONE_FILE_SYSTEM);
+                    None
+                }
+                (COMPONENT_RADIO_SKIP_IDENTICAL, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_VERIFY_CHECKSUMS);
+                    None
+                }
+                (COMPONENT_RADIO_CREATE_REMOTE_DIRS, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_SKIP_IDENTICAL);
+                    None
+                }
+                (COMPONENT_RADIO_ONE_FILE_SYSTEM, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_CREATE_REMOTE_DIRS);
+                    None
+                }
+                // Network <DOWN>
+                (COMPONENT_INPUT_PROXY, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_CONNECTION_TIMEOUT);
+                    None
+                }
+                (COMPONENT_INPUT_CONNECTION_TIMEOUT, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_KEEPALIVE_INTERVAL);
+                    None
+                }
+                (COMPONENT_INPUT_KEEPALIVE_INTERVAL, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_MAX_CONCURRENCY);
+                    None
+                }
+                (COMPONENT_INPUT_MAX_CONCURRENCY, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_BANDWIDTH_LIMIT);
+                    None
+                }
+                (COMPONENT_INPUT_BANDWIDTH_LIMIT, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_RETRIES);
+                    None
+                }
+                (COMPONENT_INPUT_RETRIES, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_RETRY_BACKOFF);
+                    None
+                }
+                (COMPONENT_INPUT_RETRY_BACKOFF, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_RADIO_ADDRESS_FAMILY);
+                    None
+                }
+                (COMPONENT_RADIO_ADDRESS_FAMILY, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_WEBHOOK_URL);
+                    None
+                }
+                (COMPONENT_INPUT_WEBHOOK_URL, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_WEBHOOK_TOKEN);
+                    None
+                }
+                (COMPONENT_INPUT_WEBHOOK_TOKEN, &MSG_KEY_DOWN) => {
+                    self.view.active(COMPONENT_INPUT_PROXY);
+                    None
+                }
+                // Network <UP>
+                (COMPONENT_INPUT_WEBHOOK_TOKEN, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_WEBHOOK_URL);
+                    None
+                }
+                (COMPONENT_INPUT_WEBHOOK_URL, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_RADIO_ADDRESS_FAMILY);
+                    None
+                }
+                (COMPONENT_RADIO_ADDRESS_FAMILY, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_RETRY_BACKOFF);
+                    None
+                }
+                (COMPONENT_INPUT_RETRY_BACKOFF, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_RETRIES);
+                    None
+                }
+                (COMPONENT_INPUT_RETRIES, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_BANDWIDTH_LIMIT);
+                    None
+                }
+                (COMPONENT_INPUT_BANDWIDTH_LIMIT, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_MAX_CONCURRENCY);
+                    None
+                }
+                (COMPONENT_INPUT_MAX_CONCURRENCY, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_KEEPALIVE_INTERVAL);
+                    None
+                }
+                (COMPONENT_INPUT_KEEPALIVE_INTERVAL, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_CONNECTION_TIMEOUT);
+                    None
+                }
+                (COMPONENT_INPUT_CONNECTION_TIMEOUT, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_PROXY);
+                    None
+                }
+                (COMPONENT_INPUT_PROXY, &MSG_KEY_UP) => {
+                    self.view.active(COMPONENT_INPUT_WEBHOOK_TOKEN);
                     None
                 }
                 // <CTRL+H> Show help
@@ -215,6 +597,11 @@ impl SetupActivity {
                     self.mount_new_ssh_key();
                     None
                 }
+                // <CTRL+Y> Copy key
+                (COMPONENT_LIST_SSH_KEYS, &MSG_KEY_CTRL_Y) => {
+                    self.action_copy_ssh_key();
+                    None
+                }
                 // <ENTER> Edit key
                 (COMPONENT_LIST_SSH_KEYS, Msg::OnSubmit(Payload::Unsigned(idx))) => {
                     // Edit ssh key
@@ -230,11 +617,33 @@ impl SetupActivity {
                     self.mount_del_ssh_key();
                     None
                 }
+                // <DEL | CTRL+E> Show delete known host
+                (COMPONENT_LIST_KNOWN_HOSTS, &MSG_KEY_CTRL_E)
+                | (COMPONENT_LIST_KNOWN_HOSTS, &MSG_KEY_DEL) => {
+                    self.mount_del_known_host();
+                    None
+                }
+                // <V> Re-verify known host
+                (COMPONENT_LIST_KNOWN_HOSTS, &MSG_KEY_CHAR_V) => {
+                    self.action_reverify_known_host();
+                    self.reload_known_hosts();
+                    None
+                }
                 (_, &MSG_KEY_TAB) => {
                     // Change view
                     self.init_ssh_keys();
                     None
                 }
+                // <CTRL+O> Restore configuration
+                (_, &MSG_KEY_CTRL_O) => {
+                    self.mount_restore_input();
+                    None
+                }
+                // <CTRL+B> Export configuration
+                (_, &MSG_KEY_CTRL_B) => {
+                    self.mount_export_input();
+                    None
+                }
                 // <CTRL+R> Revert changes
                 (_, &MSG_KEY_CTRL_R) => {
                     // Revert changes
@@ -28,15 +28,19 @@
  */
 // Locals
 use super::{Context, SetupActivity, ViewLayout};
-use crate::filetransfer::FileTransferProtocol;
+use crate::filetransfer::{AddressFamily, FileTransferProtocol};
 use crate::fs::explorer::GroupDirs;
+use crate::fs::transfer::{OverwritePolicy, PartialFilePolicy, SymlinkPolicy};
+use crate::system::backup::DiffLine;
+use crate::ui::layout::accessibility::{ColorFilter, ColorMode};
 use crate::ui::layout::components::{
     bookmark_list::BookmarkList, input::Input, msgbox::MsgBox, radio_group::RadioGroup,
     table::Table, text::Text,
 };
 use crate::ui::layout::props::{
-    PropValue, PropsBuilder, TableBuilder, TextParts, TextSpan, TextSpanBuilder,
+    InputType, PropValue, PropsBuilder, TableBuilder, TextParts, TextSpan, TextSpanBuilder,
 };
+use crate::ui::layout::toast::Toast;
 use crate::ui::layout::utils::draw_area_in;
 use crate::ui::layout::view::View;
 use crate::ui::layout::Payload;
@@ -58,44 +62,7 @@ impl SetupActivity {
         // Init view
         self.view = View::init();
         // Common stuff
-        // Radio tab
-        self.view.mount(
-            super::COMPONENT_RADIO_TAB,
-            Box::new(RadioGroup::new(
-                PropsBuilder::default()
-                    .with_foreground(Color::LightYellow)
-                    .with_background(Color::Black)
-                    .with_borders(Borders::BOTTOM)
-                    .with_texts(TextParts::new(
-                        None,
-                        Some(vec![
-                            TextSpan::from("User Interface"),
-                            TextSpan::from("SSH Keys"),
-                        ]),
-                    ))
-                    .with_value(PropValue::Unsigned(0))
-                    .build(),
-            )),
-        );
-        // Footer
-        self.view.mount(
-            super::COMPONENT_TEXT_FOOTER,
-            Box::new(Text::new(
-                PropsBuilder::default()
-                    .with_texts(TextParts::new(
-                        None,
-                        Some(vec![
-                            TextSpanBuilder::new("Press ").bold().build(),
-                            TextSpanBuilder::new("<CTRL+H>")
-                                .bold()
-                                .with_foreground(Color::Cyan)
-                                .build(),
-                            TextSpanBuilder::new(" to show keybindings").bold().build(),
-                        ]),
-                    ))
-                    .build(),
-            )),
-        );
+        self.mount_tabs(0);
         // Input fields
         self.view.mount(
             super::COMPONENT_INPUT_TEXT_EDITOR,
@@ -115,12 +82,23 @@ impl SetupActivity {
                     .with_background(Color::Black)
                     .with_texts(TextParts::new(
                         Some(String::from("Default file transfer protocol")),
-                        Some(vec![
-                            TextSpan::from("SFTP"),
-                            TextSpan::from("SCP"),
-                            TextSpan::from("FTP"),
-                            TextSpan::from("FTPS"),
-                        ]),
+                        Some(
+                            FileTransferProtocol::available()
+                                .into_iter()
+                                .map(|protocol| match protocol {
+                                    FileTransferProtocol::Sftp => TextSpan::from("SFTP"),
+                                    FileTransferProtocol::Scp => TextSpan::from("SCP"),
+                                    FileTransferProtocol::Ftp(false) => TextSpan::from("FTP"),
+                                    FileTransferProtocol::Ftp(true) => TextSpan::from("FTPS"),
+                                    FileTransferProtocol::WebDav(false) => TextSpan::from("WebDAV"),
+                                    FileTransferProtocol::WebDav(true) => TextSpan::from("WebDAVS"),
+                                    FileTransferProtocol::Smb => TextSpan::from("SMB"),
+                                    FileTransferProtocol::Kube => TextSpan::from("Kube"),
+                                    FileTransferProtocol::S3 => TextSpan::from("S3"),
+                                    FileTransferProtocol::Plugin(scheme) => TextSpan::from(scheme),
+                                })
+                                .collect(),
+                        ),
                     ))
                     .build(),
             )),
@@ -180,6 +158,64 @@ impl SetupActivity {
                     .build(),
             )),
         );
+        self.view.mount(
+            super::COMPONENT_RADIO_COLOR_MODE,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Color mode")),
+                        Some(vec![
+                            TextSpan::from("Default"),
+                            TextSpan::from("High contrast"),
+                            TextSpan::from("No color"),
+                        ]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_REDUCED_MOTION,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightCyan)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Reduced motion (for slow terminals)")),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_NEVER_STORE_CREDENTIALS,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Never store credentials")),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_CLIPBOARD_TIMEOUT,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Clear copied secrets after (seconds; 0 = never)",
+                        )),
+                        None,
+                    ))
+                    .with_input(InputType::Number)
+                    .build(),
+            )),
+        );
         // Load values
         self.load_input_values();
         // Set view
@@ -193,6 +229,538 @@ impl SetupActivity {
         // Init view
         self.view = View::init();
         // Common stuff
+        self.mount_tabs(1);
+        self.view.mount(
+            super::COMPONENT_LIST_SSH_KEYS,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(Some(String::from("SSH Keys")), Some(vec![])))
+                    .with_background(Color::LightGreen)
+                    .with_foreground(Color::Black)
+                    .build(),
+            )),
+        );
+        // Give focus
+        self.view.active(super::COMPONENT_LIST_SSH_KEYS);
+        // Load keys
+        self.reload_ssh_keys();
+        // Set view
+        self.layout = ViewLayout::SshKeys;
+    }
+
+    /// ### init_known_hosts
+    ///
+    /// Initialize known hosts view
+    pub(super) fn init_known_hosts(&mut self) {
+        // Init view
+        self.view = View::init();
+        // Common stuff
+        self.mount_tabs(2);
+        self.view.mount(
+            super::COMPONENT_LIST_KNOWN_HOSTS,
+            Box::new(BookmarkList::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Known Hosts")),
+                        Some(vec![]),
+                    ))
+                    .with_background(Color::LightGreen)
+                    .with_foreground(Color::Black)
+                    .build(),
+            )),
+        );
+        // Give focus
+        self.view.active(super::COMPONENT_LIST_KNOWN_HOSTS);
+        // Load known hosts
+        if let Err(err) = self.load_known_hosts() {
+            self.mount_error(err.as_str());
+        }
+        self.reload_known_hosts();
+        // Set view
+        self.layout = ViewLayout::KnownHosts;
+    }
+
+    /// ### init_network
+    ///
+    /// Initialize network settings view
+    pub(super) fn init_network(&mut self) {
+        // Init view
+        self.view = View::init();
+        // Common stuff
+        self.mount_tabs(3);
+        // Get values from configuration
+        let (
+            proxy,
+            timeout,
+            keepalive,
+            concurrency,
+            bandwidth,
+            retries,
+            backoff,
+            family,
+            webhook,
+            token,
+        ) = match self.context.as_ref().unwrap().config_client.as_ref() {
+            Some(cli) => (
+                cli.get_proxy().unwrap_or_default(),
+                cli.get_connection_timeout().to_string(),
+                cli.get_keepalive_interval().to_string(),
+                cli.get_max_concurrency().to_string(),
+                cli.get_bandwidth_limit().to_string(),
+                cli.get_retries().to_string(),
+                cli.get_retry_backoff().to_string(),
+                cli.get_address_family(),
+                cli.get_webhook_url().unwrap_or_default(),
+                cli.get_webhook_token().unwrap_or_default(),
+            ),
+            None => (
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                AddressFamily::Any,
+                String::new(),
+                String::new(),
+            ),
+        };
+        self.mount_network_input(
+            super::COMPONENT_INPUT_PROXY,
+            "Proxy (http://host:port; empty to disable)",
+            InputType::Text,
+            proxy,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_CONNECTION_TIMEOUT,
+            "Connection timeout (seconds)",
+            InputType::Number,
+            timeout,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_KEEPALIVE_INTERVAL,
+            "Keepalive interval (seconds; 0 to disable)",
+            InputType::Number,
+            keepalive,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_MAX_CONCURRENCY,
+            "Max concurrent transfers",
+            InputType::Number,
+            concurrency,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_BANDWIDTH_LIMIT,
+            "Default bandwidth limit (KiB/s; 0 for unlimited)",
+            InputType::Number,
+            bandwidth,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_RETRIES,
+            "Retries of failed transfers (0 to disable)",
+            InputType::Number,
+            retries,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_RETRY_BACKOFF,
+            "Delay before the first retry (ms; doubled at each retry)",
+            InputType::Number,
+            backoff,
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_ADDRESS_FAMILY,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Preferred address family (when a host has both)",
+                        )),
+                        Some(vec![
+                            TextSpan::from("Any"),
+                            TextSpan::from("IPv4"),
+                            TextSpan::from("IPv6"),
+                        ]),
+                    ))
+                    .with_value(PropValue::Unsigned(match family {
+                        AddressFamily::Any => 0,
+                        AddressFamily::Ipv4 => 1,
+                        AddressFamily::Ipv6 => 2,
+                    }))
+                    .build(),
+            )),
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_WEBHOOK_URL,
+            "Webhook notified when the transfer queue is done (http(s)://...; empty to disable)",
+            InputType::Text,
+            webhook,
+        );
+        self.mount_network_input(
+            super::COMPONENT_INPUT_WEBHOOK_TOKEN,
+            "Webhook token (sent as bearer token; optional)",
+            InputType::Password,
+            token,
+        );
+        // Give focus
+        self.view.active(super::COMPONENT_INPUT_PROXY);
+        // Set view
+        self.layout = ViewLayout::Network;
+    }
+
+    /// ### init_transfer
+    ///
+    /// Initialize transfer behaviour view
+    pub(super) fn init_transfer(&mut self) {
+        // Init view
+        self.view = View::init();
+        // Common stuff
+        self.mount_tabs(4);
+        // Get values from configuration
+        let (
+            overwrite,
+            timestamps,
+            permissions,
+            atomic,
+            partial_files,
+            symlinks,
+            verify,
+            skip_identical,
+            create_remote_dirs,
+            one_fs,
+            inhibit_sleep,
+            compress_uploads,
+            preallocate,
+            durable_writes,
+            use_trash,
+            trash_dir,
+            ignore,
+            upload_permissions,
+            bandwidth_schedule,
+            staging_dir,
+        ) = match self.context.as_ref().unwrap().config_client.as_ref() {
+            Some(cli) => (
+                cli.get_overwrite_policy(),
+                cli.get_preserve_timestamps(),
+                cli.get_preserve_permissions(),
+                cli.get_atomic_uploads(),
+                cli.get_partial_files(),
+                cli.get_symlinks(),
+                cli.get_verify_checksums(),
+                cli.get_skip_identical(),
+                cli.get_create_remote_dirs(),
+                cli.get_one_file_system(),
+                cli.get_inhibit_sleep(),
+                cli.get_compress_uploads(),
+                cli.get_preallocate(),
+                cli.get_durable_writes(),
+                cli.get_use_trash(),
+                cli.get_trash_dir().to_string(),
+                cli.get_ignore_rules().join(", "),
+                cli.get_upload_permissions().unwrap_or_default().to_string(),
+                cli.get_bandwidth_schedule().to_string(),
+                cli.get_staging_dir()
+                    .map(|x| x.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            ),
+            None => (
+                OverwritePolicy::Overwrite,
+                false,
+                true,
+                false,
+                PartialFilePolicy::Remove,
+                SymlinkPolicy::Follow,
+                false,
+                false,
+                false,
+                false,
+                false,
+                false,
+                true,
+                false,
+                false,
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+            ),
+        };
+        self.view.mount(
+            super::COMPONENT_RADIO_OVERWRITE_POLICY,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "When the target already exists (overwrite it, skip the file, skip it if it has the same size, overwrite it if older, or ask)",
+                        )),
+                        Some(vec![
+                            TextSpan::from("Overwrite"),
+                            TextSpan::from("Skip"),
+                            TextSpan::from("Skip if same size"),
+                            TextSpan::from("Overwrite if newer"),
+                            TextSpan::from("Ask"),
+                        ]),
+                    ))
+                    .with_value(PropValue::Unsigned(match overwrite {
+                        OverwritePolicy::Overwrite => 0,
+                        OverwritePolicy::Skip => 1,
+                        OverwritePolicy::SkipSameSize => 2,
+                        OverwritePolicy::OverwriteIfNewer => 3,
+                        OverwritePolicy::Ask => 4,
+                    }))
+                    .build(),
+            )),
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_PRESERVE_TIMESTAMPS,
+            "Preserve timestamps (keep the modification time of the source file)",
+            Color::LightYellow,
+            timestamps,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_PRESERVE_PERMISSIONS,
+            "Preserve permissions (apply the file mode of the source file)",
+            Color::LightGreen,
+            permissions,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_ATOMIC_UPLOADS,
+            "Atomic uploads (upload to a temporary file, then rename it)",
+            Color::LightCyan,
+            atomic,
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_PARTIAL_FILES,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::Yellow)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "When a transfer is aborted (keep the partial file, remove it, or rename it to \"*.partial\")",
+                        )),
+                        Some(vec![
+                            TextSpan::from("Keep"),
+                            TextSpan::from("Remove"),
+                            TextSpan::from("Mark"),
+                        ]),
+                    ))
+                    .with_value(PropValue::Unsigned(match partial_files {
+                        PartialFilePolicy::Keep => 0,
+                        PartialFilePolicy::Remove => 1,
+                        PartialFilePolicy::Mark => 2,
+                    }))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_SYMLINKS,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightBlue)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Symlinks (transfer their target, recreate them as links, or skip them)",
+                        )),
+                        Some(vec![
+                            TextSpan::from("Follow"),
+                            TextSpan::from("Recreate"),
+                            TextSpan::from("Skip"),
+                        ]),
+                    ))
+                    .with_value(PropValue::Unsigned(match symlinks {
+                        SymlinkPolicy::Follow => 0,
+                        SymlinkPolicy::Recreate => 1,
+                        SymlinkPolicy::Skip => 2,
+                    }))
+                    .build(),
+            )),
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_VERIFY_CHECKSUMS,
+            "Verify checksums (compare SHA256 of source and target after transfer)",
+            Color::LightMagenta,
+            verify,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_SKIP_IDENTICAL,
+            "Skip identical files (compare SHA256 of source and target when they have the same size)",
+            Color::LightCyan,
+            skip_identical,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_CREATE_REMOTE_DIRS,
+            "Create missing remote directories (when uploading into a path which doesn't exist)",
+            Color::LightGreen,
+            create_remote_dirs,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_ONE_FILE_SYSTEM,
+            "One file system (don't descend into mount points when deleting or downloading)",
+            Color::LightRed,
+            one_fs,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_INHIBIT_SLEEP,
+            "Inhibit sleep (keep the system awake while the transfer queue is running)",
+            Color::LightYellow,
+            inhibit_sleep,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_COMPRESS_UPLOADS,
+            "Compress uploads (gzip on the fly; decompressed with gzip on SFTP/SCP hosts)",
+            Color::LightGreen,
+            compress_uploads,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_PREALLOCATE,
+            "Preallocate downloads (reserve disk space first; reports a full disk early)",
+            Color::LightCyan,
+            preallocate,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_DURABLE_WRITES,
+            "Durable writes (flush downloads to disk before reporting them as done)",
+            Color::LightBlue,
+            durable_writes,
+        );
+        self.mount_transfer_flag(
+            super::COMPONENT_RADIO_USE_TRASH,
+            "Use trash (deleted entries are moved to the trash; <CTRL+E> empties the remote one)",
+            Color::LightRed,
+            use_trash,
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_TRASH_DIR,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Trash directory on remote hosts (`~` is the home directory)",
+                        )),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(trash_dir))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_IGNORE_RULES,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightBlue)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Ignore rules (comma separated globs; skipped in directories)",
+                        )),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(ignore))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_UPLOAD_PERMISSIONS,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightGreen)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Permission template for uploads (empty to keep the source mode)",
+                        )),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(upload_permissions))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_BANDWIDTH_SCHEDULE,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Bandwidth schedule (e.g. mon-fri 09:00-18:00=1024; KiB/s, 0 for unlimited)",
+                        )),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(bandwidth_schedule))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_INPUT_STAGING_DIR,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightBlue)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Staging directory for temporary files (empty for the system one)",
+                        )),
+                        None,
+                    ))
+                    .with_value(PropValue::Str(staging_dir))
+                    .build(),
+            )),
+        );
+        // Give focus
+        self.view.active(super::COMPONENT_RADIO_OVERWRITE_POLICY);
+        // Set view
+        self.layout = ViewLayout::Transfer;
+    }
+
+    /// ### mount_transfer_flag
+    ///
+    /// Mount a yes/no option of the transfer behaviour page
+    fn mount_transfer_flag(&mut self, id: &str, title: &str, color: Color, value: bool) {
+        self.view.mount(
+            id,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(color)
+                    .with_background(Color::Black)
+                    .with_texts(TextParts::new(
+                        Some(String::from(title)),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .with_value(PropValue::Unsigned(match value {
+                        true => 0,
+                        false => 1,
+                    }))
+                    .build(),
+            )),
+        );
+    }
+
+    /// ### mount_network_input
+    ///
+    /// Mount an input field of the network settings page
+    fn mount_network_input(&mut self, id: &str, title: &str, itype: InputType, value: String) {
+        self.view.mount(
+            id,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightBlue)
+                    .with_texts(TextParts::new(Some(String::from(title)), None))
+                    .with_input(itype)
+                    .with_value(PropValue::Str(value))
+                    .build(),
+            )),
+        );
+    }
+
+    /// ### mount_tabs
+    ///
+    /// Mount components shared by all the setup pages: tabs, with `tab` selected, and footer
+    fn mount_tabs(&mut self, tab: usize) {
         // Radio tab
         self.view.mount(
             super::COMPONENT_RADIO_TAB,
@@ -206,9 +774,12 @@ impl SetupActivity {
                         Some(vec![
                             TextSpan::from("User Interface"),
                             TextSpan::from("SSH Keys"),
+                            TextSpan::from("Known Hosts"),
+                            TextSpan::from("Network"),
+                            TextSpan::from("Transfers"),
                         ]),
                     ))
-                    .with_value(PropValue::Unsigned(1))
+                    .with_value(PropValue::Unsigned(tab))
                     .build(),
             )),
         );
@@ -231,22 +802,6 @@ impl SetupActivity {
                     .build(),
             )),
         );
-        self.view.mount(
-            super::COMPONENT_LIST_SSH_KEYS,
-            Box::new(BookmarkList::new(
-                PropsBuilder::default()
-                    .with_texts(TextParts::new(Some(String::from("SSH Keys")), Some(vec![])))
-                    .with_background(Color::LightGreen)
-                    .with_foreground(Color::Black)
-                    .build(),
-            )),
-        );
-        // Give focus
-        self.view.active(super::COMPONENT_LIST_SSH_KEYS);
-        // Load keys
-        self.reload_ssh_keys();
-        // Set view
-        self.layout = ViewLayout::SshKeys;
     }
 
     /// ### view
@@ -254,6 +809,8 @@ impl SetupActivity {
     /// View gui
     pub(super) fn view(&mut self) {
         let mut ctx: Context = self.context.take().unwrap();
+        let color_mode: ColorMode = ctx.color_mode();
+        let toast: Option<Toast> = ctx.toast();
         let _ = ctx.terminal.draw(|f| {
             // Prepare main chunks
             let chunks = Layout::default()
@@ -284,6 +841,10 @@ impl SetupActivity {
                                 Constraint::Length(3), // Updates tab
                                 Constraint::Length(3), // Group dirs
                                 Constraint::Length(3), // Format input
+                                Constraint::Length(3), // Color mode
+                                Constraint::Length(3), // Reduced motion
+                                Constraint::Length(3), // Never store credentials
+                                Constraint::Length(3), // Clipboard timeout
                                 Constraint::Length(1), // Empty ?
                             ]
                             .as_ref(),
@@ -301,6 +862,20 @@ impl SetupActivity {
                         .render(super::COMPONENT_RADIO_GROUP_DIRS, f, ui_cfg_chunks[4]);
                     self.view
                         .render(super::COMPONENT_INPUT_FILE_FMT, f, ui_cfg_chunks[5]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_COLOR_MODE, f, ui_cfg_chunks[6]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_REDUCED_MOTION, f, ui_cfg_chunks[7]);
+                    self.view.render(
+                        super::COMPONENT_RADIO_NEVER_STORE_CREDENTIALS,
+                        f,
+                        ui_cfg_chunks[8],
+                    );
+                    self.view.render(
+                        super::COMPONENT_INPUT_CLIPBOARD_TIMEOUT,
+                        f,
+                        ui_cfg_chunks[9],
+                    );
                 }
                 ViewLayout::SshKeys => {
                     let sshcfg_chunks = Layout::default()
@@ -310,6 +885,115 @@ impl SetupActivity {
                     self.view
                         .render(super::COMPONENT_LIST_SSH_KEYS, f, sshcfg_chunks[0]);
                 }
+                ViewLayout::Network => {
+                    let network_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Length(3), // Proxy
+                                Constraint::Length(3), // Connection timeout
+                                Constraint::Length(3), // Keepalive
+                                Constraint::Length(3), // Concurrency
+                                Constraint::Length(3), // Bandwidth
+                                Constraint::Length(3), // Retries
+                                Constraint::Length(3), // Retry backoff
+                                Constraint::Length(3), // Address family
+                                Constraint::Length(3), // Webhook URL
+                                Constraint::Length(3), // Webhook token
+                                Constraint::Length(1), // Empty ?
+                            ]
+                            .as_ref(),
+                        )
+                        .split(chunks[1]);
+                    self.view
+                        .render(super::COMPONENT_INPUT_PROXY, f, network_chunks[0]);
+                    self.view.render(
+                        super::COMPONENT_INPUT_CONNECTION_TIMEOUT,
+                        f,
+                        network_chunks[1],
+                    );
+                    self.view.render(
+                        super::COMPONENT_INPUT_KEEPALIVE_INTERVAL,
+                        f,
+                        network_chunks[2],
+                    );
+                    self.view
+                        .render(super::COMPONENT_INPUT_MAX_CONCURRENCY, f, network_chunks[3]);
+                    self.view
+                        .render(super::COMPONENT_INPUT_BANDWIDTH_LIMIT, f, network_chunks[4]);
+                    self.view
+                        .render(super::COMPONENT_INPUT_RETRIES, f, network_chunks[5]);
+                    self.view
+                        .render(super::COMPONENT_INPUT_RETRY_BACKOFF, f, network_chunks[6]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_ADDRESS_FAMILY, f, network_chunks[7]);
+                    self.view
+                        .render(super::COMPONENT_INPUT_WEBHOOK_URL, f, network_chunks[8]);
+                    self.view
+                        .render(super::COMPONENT_INPUT_WEBHOOK_TOKEN, f, network_chunks[9]);
+                }
+                ViewLayout::Transfer => {
+                    let transfer_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Length(3), // Overwrite policy
+                                Constraint::Length(3), // Preserve timestamps
+                                Constraint::Length(3), // Preserve permissions
+                                Constraint::Length(3), // Atomic uploads
+                                Constraint::Length(3), // Partial files
+                                Constraint::Length(3), // Symlinks
+                                Constraint::Length(3), // Verify checksums
+                                Constraint::Length(3), // Skip identical files
+                                Constraint::Length(3), // Create remote directories
+                                Constraint::Length(3), // One file system
+                                Constraint::Length(3), // Inhibit sleep
+                                Constraint::Length(3), // Compress uploads
+                                Constraint::Length(3), // Preallocate
+                                Constraint::Length(3), // Durable writes
+                                Constraint::Length(3), // Use trash
+                                Constraint::Length(3), // Trash directory
+                                Constraint::Length(3), // Ignore rules
+                                Constraint::Length(3), // Upload permissions
+                                Constraint::Length(3), // Bandwidth schedule
+                                Constraint::Length(3), // Staging directory
+                                Constraint::Length(1), // Empty ?
+                            ]
+                            .as_ref(),
+                        )
+                        .split(chunks[1]);
+                    for (id, chunk) in [
+                        super::COMPONENT_RADIO_OVERWRITE_POLICY,
+                        super::COMPONENT_RADIO_PRESERVE_TIMESTAMPS,
+                        super::COMPONENT_RADIO_PRESERVE_PERMISSIONS,
+                        super::COMPONENT_RADIO_ATOMIC_UPLOADS,
+                        super::COMPONENT_RADIO_PARTIAL_FILES,
+                        super::COMPONENT_RADIO_SYMLINKS,
+                        super::COMPONENT_RADIO_VERIFY_CHECKSUMS,
+                        super::COMPONENT_RADIO_SKIP_IDENTICAL,
+                        super::COMPONENT_RADIO_CREATE_REMOTE_DIRS,
+                        super::COMPONENT_RADIO_ONE_FILE_SYSTEM,
+                        super::COMPONENT_RADIO_INHIBIT_SLEEP,
+                        super::COMPONENT_RADIO_COMPRESS_UPLOADS,
+                        super::COMPONENT_RADIO_PREALLOCATE,
+                        super::COMPONENT_RADIO_DURABLE_WRITES,
+                        super::COMPONENT_RADIO_USE_TRASH,
+                        super::COMPONENT_INPUT_TRASH_DIR,
+                        super::COMPONENT_INPUT_IGNORE_RULES,
+                        super::COMPONENT_INPUT_UPLOAD_PERMISSIONS,
+                        super::COMPONENT_INPUT_BANDWIDTH_SCHEDULE,
+                        super::COMPONENT_INPUT_STAGING_DIR,
+                    ]
+                    .iter()
+                    .zip(transfer_chunks.iter())
+                    {
+                        self.view.render(id, f, *chunk);
+                    }
+                }
+                ViewLayout::KnownHosts => {
+                    self.view
+                        .render(super::COMPONENT_LIST_KNOWN_HOSTS, f, chunks[1]);
+                }
             }
             // Popups
             if let Some(mut props) = self.view.get_props(super::COMPONENT_TEXT_ERROR) {
@@ -353,6 +1037,15 @@ impl SetupActivity {
                         .render(super::COMPONENT_RADIO_DEL_SSH_KEY, f, popup);
                 }
             }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_DEL_KNOWN_HOST) {
+                if props.build().visible {
+                    // make popup
+                    let popup = draw_area_in(f.size(), 30, 10);
+                    f.render_widget(Clear, popup);
+                    self.view
+                        .render(super::COMPONENT_RADIO_DEL_KNOWN_HOST, f, popup);
+                }
+            }
             if let Some(mut props) = self.view.get_props(super::COMPONENT_INPUT_SSH_HOST) {
                 if props.build().visible {
                     // make popup
@@ -374,6 +1067,48 @@ impl SetupActivity {
                         .render(super::COMPONENT_INPUT_SSH_USERNAME, f, popup_chunks[1]);
                 }
             }
+            for id in [
+                super::COMPONENT_INPUT_RESTORE_BUNDLE,
+                super::COMPONENT_INPUT_EXPORT_BUNDLE,
+            ]
+            .iter()
+            {
+                if let Some(mut props) = self.view.get_props(id) {
+                    if props.build().visible {
+                        // make popup
+                        let popup = draw_area_in(f.size(), 60, 10);
+                        f.render_widget(Clear, popup);
+                        self.view.render(id, f, popup);
+                    }
+                }
+            }
+            if let Some(mut props) = self.view.get_props(super::COMPONENT_RADIO_RESTORE) {
+                if props.build().visible {
+                    // make popup
+                    let popup = draw_area_in(f.size(), 70, 70);
+                    f.render_widget(Clear, popup);
+                    let popup_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(
+                            [
+                                Constraint::Min(3),    // Preview
+                                Constraint::Length(3), // Confirm
+                            ]
+                            .as_ref(),
+                        )
+                        .split(popup);
+                    self.view
+                        .render(super::COMPONENT_TABLE_RESTORE_PREVIEW, f, popup_chunks[0]);
+                    self.view
+                        .render(super::COMPONENT_RADIO_RESTORE, f, popup_chunks[1]);
+                }
+            }
+            // Toast on top of everything
+            if let Some(toast) = toast {
+                f.render_widget(toast, f.size());
+            }
+            // Apply color mode (must come last)
+            f.render_widget(ColorFilter::new(color_mode), f.size());
         });
         // Put context back to context
         self.context = Some(ctx);
@@ -402,23 +1137,52 @@ impl SetupActivity {
 
     /// ### umount_error
     ///
-    /// Umount error message
-    pub(super) fn umount_error(&mut self) {
-        self.view.umount(super::COMPONENT_TEXT_ERROR);
+    /// Umount error message
+    pub(super) fn umount_error(&mut self) {
+        self.view.umount(super::COMPONENT_TEXT_ERROR);
+    }
+
+    /// ### mount_del_ssh_key
+    ///
+    /// Mount delete ssh key component
+    pub(super) fn mount_del_ssh_key(&mut self) {
+        self.view.mount(
+            super::COMPONENT_RADIO_DEL_SSH_KEY,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightRed)
+                    .bold()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Delete key?")),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .with_value(PropValue::Unsigned(1)) // Default: No
+                    .build(),
+            )),
+        );
+        // Active
+        self.view.active(super::COMPONENT_RADIO_DEL_SSH_KEY);
+    }
+
+    /// ### umount_del_ssh_key
+    ///
+    /// Umount delete ssh key
+    pub(super) fn umount_del_ssh_key(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_DEL_SSH_KEY);
     }
 
-    /// ### mount_del_ssh_key
+    /// ### mount_del_known_host
     ///
-    /// Mount delete ssh key component
-    pub(super) fn mount_del_ssh_key(&mut self) {
+    /// Mount delete known host component
+    pub(super) fn mount_del_known_host(&mut self) {
         self.view.mount(
-            super::COMPONENT_RADIO_DEL_SSH_KEY,
+            super::COMPONENT_RADIO_DEL_KNOWN_HOST,
             Box::new(RadioGroup::new(
                 PropsBuilder::default()
                     .with_foreground(Color::LightRed)
                     .bold()
                     .with_texts(TextParts::new(
-                        Some(String::from("Delete key?")),
+                        Some(String::from("Delete known host?")),
                         Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
                     ))
                     .with_value(PropValue::Unsigned(1)) // Default: No
@@ -426,14 +1190,14 @@ impl SetupActivity {
             )),
         );
         // Active
-        self.view.active(super::COMPONENT_RADIO_DEL_SSH_KEY);
+        self.view.active(super::COMPONENT_RADIO_DEL_KNOWN_HOST);
     }
 
-    /// ### umount_del_ssh_key
+    /// ### umount_del_known_host
     ///
-    /// Umount delete ssh key
-    pub(super) fn umount_del_ssh_key(&mut self) {
-        self.view.umount(super::COMPONENT_RADIO_DEL_SSH_KEY);
+    /// Umount delete known host
+    pub(super) fn umount_del_known_host(&mut self) {
+        self.view.umount(super::COMPONENT_RADIO_DEL_KNOWN_HOST);
     }
 
     /// ### mount_new_ssh_key
@@ -472,6 +1236,123 @@ impl SetupActivity {
         self.view.umount(super::COMPONENT_INPUT_SSH_USERNAME);
     }
 
+    /// ### mount_restore_input
+    ///
+    /// Mount prompt for the bundle to restore configuration from
+    pub(super) fn mount_restore_input(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_RESTORE_BUNDLE,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from(
+                            "Restore from bundle (empty: automatic backups)",
+                        )),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_RESTORE_BUNDLE);
+    }
+
+    /// ### umount_restore_input
+    ///
+    /// Umount restore prompt
+    pub(super) fn umount_restore_input(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_RESTORE_BUNDLE);
+    }
+
+    /// ### mount_export_input
+    ///
+    /// Mount prompt for the bundle to export configuration to
+    pub(super) fn mount_export_input(&mut self) {
+        self.view.mount(
+            super::COMPONENT_INPUT_EXPORT_BUNDLE,
+            Box::new(Input::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .with_texts(TextParts::new(
+                        Some(String::from("Export configuration to")),
+                        None,
+                    ))
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_INPUT_EXPORT_BUNDLE);
+    }
+
+    /// ### umount_export_input
+    ///
+    /// Umount export prompt
+    pub(super) fn umount_export_input(&mut self) {
+        self.view.umount(super::COMPONENT_INPUT_EXPORT_BUNDLE);
+    }
+
+    /// ### mount_restore_preview
+    ///
+    /// Mount the preview of the pending restore; only changed lines are displayed
+    pub(super) fn mount_restore_preview(&mut self) {
+        let mut table: Vec<Vec<TextSpan>> = Vec::new();
+        if let Some(plan) = self.restore.as_ref() {
+            for file in plan.files.iter() {
+                table.push(vec![TextSpanBuilder::new(
+                    file.path.to_string_lossy().to_string().as_str(),
+                )
+                .bold()
+                .build()]);
+                for line in file.diff.iter() {
+                    match line {
+                        DiffLine::Added(line) => {
+                            table.push(vec![TextSpanBuilder::new(format!("+ {}", line).as_str())
+                                .with_foreground(Color::Green)
+                                .build()])
+                        }
+                        DiffLine::Removed(line) => {
+                            table.push(vec![TextSpanBuilder::new(format!("- {}", line).as_str())
+                                .with_foreground(Color::Red)
+                                .build()])
+                        }
+                        DiffLine::Unchanged(_) => {}
+                    }
+                }
+            }
+        }
+        self.view.mount(
+            super::COMPONENT_TABLE_RESTORE_PREVIEW,
+            Box::new(Table::new(
+                PropsBuilder::default()
+                    .with_texts(TextParts::table(Some(String::from("Changes")), table))
+                    .build(),
+            )),
+        );
+        self.view.mount(
+            super::COMPONENT_RADIO_RESTORE,
+            Box::new(RadioGroup::new(
+                PropsBuilder::default()
+                    .with_foreground(Color::LightYellow)
+                    .bold()
+                    .with_texts(TextParts::new(
+                        Some(String::from("Apply changes?")),
+                        Some(vec![TextSpan::from("Yes"), TextSpan::from("No")]),
+                    ))
+                    .with_value(PropValue::Unsigned(1)) // Default: No
+                    .build(),
+            )),
+        );
+        self.view.active(super::COMPONENT_RADIO_RESTORE);
+    }
+
+    /// ### umount_restore_preview
+    ///
+    /// Umount restore preview; the pending restore is discarded
+    pub(super) fn umount_restore_preview(&mut self) {
+        self.restore = None;
+        self.view.umount(super::COMPONENT_TABLE_RESTORE_PREVIEW);
+        self.view.umount(super::COMPONENT_RADIO_RESTORE);
+    }
+
     /// ### mount_quit
     ///
     /// Mount quit popup
@@ -589,7 +1470,17 @@ impl SetupActivity {
                                     .with_foreground(Color::Cyan)
                                     .build(),
                             )
-                            .add_col(TextSpan::from("         Delete SSH key"))
+                            .add_col(TextSpan::from("         Delete SSH key / known host"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<V>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from(
+                                "             Re-verify known host on next connect",
+                            ))
                             .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<CTRL+N>")
@@ -599,6 +1490,30 @@ impl SetupActivity {
                             )
                             .add_col(TextSpan::from("        New SSH key"))
                             .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+Y>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Copy SSH key to clipboard"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+B>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Export configuration bundle"))
+                            .add_row()
+                            .add_col(
+                                TextSpanBuilder::new("<CTRL+O>")
+                                    .bold()
+                                    .with_foreground(Color::Cyan)
+                                    .build(),
+                            )
+                            .add_col(TextSpan::from("        Restore configuration"))
+                            .add_row()
                             .add_col(
                                 TextSpanBuilder::new("<CTRL+R>")
                                     .bold()
@@ -652,12 +1567,12 @@ impl SetupActivity {
                 .get_props(super::COMPONENT_RADIO_DEFAULT_PROTOCOL)
                 .as_mut()
             {
-                let protocol: usize = match cli.get_default_protocol() {
-                    FileTransferProtocol::Sftp => 0,
-                    FileTransferProtocol::Scp => 1,
-                    FileTransferProtocol::Ftp(false) => 2,
-                    FileTransferProtocol::Ftp(true) => 3,
-                };
+                // Protocols registered by plugins come after the builtin ones
+                let default_protocol: FileTransferProtocol = cli.get_default_protocol();
+                let protocol: usize = FileTransferProtocol::available()
+                    .iter()
+                    .position(|x| *x == default_protocol)
+                    .unwrap_or(0);
                 let props = props.with_value(PropValue::Unsigned(protocol)).build();
                 let _ = self
                     .view
@@ -709,6 +1624,62 @@ impl SetupActivity {
                 let props = props.with_value(PropValue::Str(file_fmt)).build();
                 let _ = self.view.update(super::COMPONENT_INPUT_FILE_FMT, props);
             }
+            // Color mode
+            if let Some(props) = self
+                .view
+                .get_props(super::COMPONENT_RADIO_COLOR_MODE)
+                .as_mut()
+            {
+                let mode: usize = match cli.get_color_mode() {
+                    ColorMode::Default => 0,
+                    ColorMode::HighContrast => 1,
+                    ColorMode::NoColor => 2,
+                };
+                let props = props.with_value(PropValue::Unsigned(mode)).build();
+                let _ = self.view.update(super::COMPONENT_RADIO_COLOR_MODE, props);
+            }
+            // Reduced motion
+            if let Some(props) = self
+                .view
+                .get_props(super::COMPONENT_RADIO_REDUCED_MOTION)
+                .as_mut()
+            {
+                let reduced: usize = match cli.get_reduced_motion() {
+                    true => 0,
+                    false => 1,
+                };
+                let props = props.with_value(PropValue::Unsigned(reduced)).build();
+                let _ = self
+                    .view
+                    .update(super::COMPONENT_RADIO_REDUCED_MOTION, props);
+            }
+            // Never store credentials
+            if let Some(props) = self
+                .view
+                .get_props(super::COMPONENT_RADIO_NEVER_STORE_CREDENTIALS)
+                .as_mut()
+            {
+                let never_store: usize = match cli.get_never_store_credentials() {
+                    true => 0,
+                    false => 1,
+                };
+                let props = props.with_value(PropValue::Unsigned(never_store)).build();
+                let _ = self
+                    .view
+                    .update(super::COMPONENT_RADIO_NEVER_STORE_CREDENTIALS, props);
+            }
+            // Clipboard timeout
+            if let Some(props) = self
+                .view
+                .get_props(super::COMPONENT_INPUT_CLIPBOARD_TIMEOUT)
+                .as_mut()
+            {
+                let timeout: String = cli.get_clipboard_clear_timeout().to_string();
+                let props = props.with_value(PropValue::Str(timeout)).build();
+                let _ = self
+                    .view
+                    .update(super::COMPONENT_INPUT_CLIPBOARD_TIMEOUT, props);
+            }
         }
     }
 
@@ -725,12 +1696,10 @@ impl SetupActivity {
             if let Some(Payload::Unsigned(protocol)) =
                 self.view.get_value(super::COMPONENT_RADIO_DEFAULT_PROTOCOL)
             {
-                let protocol: FileTransferProtocol = match protocol {
-                    1 => FileTransferProtocol::Scp,
-                    2 => FileTransferProtocol::Ftp(false),
-                    3 => FileTransferProtocol::Ftp(true),
-                    _ => FileTransferProtocol::Sftp,
-                };
+                let protocol: FileTransferProtocol = FileTransferProtocol::available()
+                    .get(protocol)
+                    .copied()
+                    .unwrap_or(FileTransferProtocol::Sftp);
                 cli.set_default_protocol(protocol);
             }
             if let Some(Payload::Unsigned(opt)) =
@@ -758,7 +1727,225 @@ impl SetupActivity {
                 };
                 cli.set_group_dirs(dirs);
             }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_COLOR_MODE)
+            {
+                let mode: ColorMode = match opt {
+                    1 => ColorMode::HighContrast,
+                    2 => ColorMode::NoColor,
+                    _ => ColorMode::Default,
+                };
+                cli.set_color_mode(mode);
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_REDUCED_MOTION)
+            {
+                let reduced: bool = matches!(opt, 0);
+                cli.set_reduced_motion(reduced);
+            }
+            if let Some(Payload::Unsigned(opt)) = self
+                .view
+                .get_value(super::COMPONENT_RADIO_NEVER_STORE_CREDENTIALS)
+            {
+                cli.set_never_store_credentials(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(secs)) = self
+                .view
+                .get_value(super::COMPONENT_INPUT_CLIPBOARD_TIMEOUT)
+            {
+                cli.set_clipboard_clear_timeout(secs as u64);
+            }
+        }
+    }
+
+    /// ### collect_network_values
+    ///
+    /// Collect values from network settings input fields and validate them through configuration
+    pub(super) fn collect_network_values(&mut self) -> Result<(), String> {
+        if let Some(cli) = self.context.as_mut().unwrap().config_client.as_mut() {
+            if let Some(Payload::Text(proxy)) = self.view.get_value(super::COMPONENT_INPUT_PROXY) {
+                cli.set_proxy(proxy.as_str())?;
+            }
+            if let Some(Payload::Unsigned(secs)) = self
+                .view
+                .get_value(super::COMPONENT_INPUT_CONNECTION_TIMEOUT)
+            {
+                cli.set_connection_timeout(secs as u64)?;
+            }
+            if let Some(Payload::Unsigned(secs)) = self
+                .view
+                .get_value(super::COMPONENT_INPUT_KEEPALIVE_INTERVAL)
+            {
+                cli.set_keepalive_interval(secs as u64)?;
+            }
+            if let Some(Payload::Unsigned(max)) =
+                self.view.get_value(super::COMPONENT_INPUT_MAX_CONCURRENCY)
+            {
+                cli.set_max_concurrency(max)?;
+            }
+            if let Some(Payload::Unsigned(kib)) =
+                self.view.get_value(super::COMPONENT_INPUT_BANDWIDTH_LIMIT)
+            {
+                cli.set_bandwidth_limit(kib as u64);
+            }
+            if let Some(Payload::Unsigned(retries)) =
+                self.view.get_value(super::COMPONENT_INPUT_RETRIES)
+            {
+                cli.set_retries(retries as u32)?;
+            }
+            if let Some(Payload::Unsigned(millis)) =
+                self.view.get_value(super::COMPONENT_INPUT_RETRY_BACKOFF)
+            {
+                cli.set_retry_backoff(millis as u64);
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_ADDRESS_FAMILY)
+            {
+                cli.set_address_family(match opt {
+                    1 => AddressFamily::Ipv4,
+                    2 => AddressFamily::Ipv6,
+                    _ => AddressFamily::Any,
+                });
+            }
+            if let Some(Payload::Text(url)) =
+                self.view.get_value(super::COMPONENT_INPUT_WEBHOOK_URL)
+            {
+                cli.set_webhook_url(url.as_str())?;
+            }
+            if let Some(Payload::Text(token)) =
+                self.view.get_value(super::COMPONENT_INPUT_WEBHOOK_TOKEN)
+            {
+                cli.set_webhook_token(token.as_str());
+            }
+        }
+        Ok(())
+    }
+
+    /// ### collect_transfer_values
+    ///
+    /// Collect values from transfer behaviour fields
+    pub(super) fn collect_transfer_values(&mut self) -> Result<(), String> {
+        if let Some(cli) = self.context.as_mut().unwrap().config_client.as_mut() {
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_OVERWRITE_POLICY)
+            {
+                cli.set_overwrite_policy(match opt {
+                    1 => OverwritePolicy::Skip,
+                    2 => OverwritePolicy::SkipSameSize,
+                    3 => OverwritePolicy::OverwriteIfNewer,
+                    4 => OverwritePolicy::Ask,
+                    _ => OverwritePolicy::Overwrite,
+                });
+            }
+            if let Some(Payload::Unsigned(opt)) = self
+                .view
+                .get_value(super::COMPONENT_RADIO_PRESERVE_TIMESTAMPS)
+            {
+                cli.set_preserve_timestamps(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) = self
+                .view
+                .get_value(super::COMPONENT_RADIO_PRESERVE_PERMISSIONS)
+            {
+                cli.set_preserve_permissions(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_ATOMIC_UPLOADS)
+            {
+                cli.set_atomic_uploads(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_PARTIAL_FILES)
+            {
+                cli.set_partial_files(match opt {
+                    0 => PartialFilePolicy::Keep,
+                    2 => PartialFilePolicy::Mark,
+                    _ => PartialFilePolicy::Remove,
+                });
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_SYMLINKS)
+            {
+                cli.set_symlinks(match opt {
+                    1 => SymlinkPolicy::Recreate,
+                    2 => SymlinkPolicy::Skip,
+                    _ => SymlinkPolicy::Follow,
+                });
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_VERIFY_CHECKSUMS)
+            {
+                cli.set_verify_checksums(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_SKIP_IDENTICAL)
+            {
+                cli.set_skip_identical(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) = self
+                .view
+                .get_value(super::COMPONENT_RADIO_CREATE_REMOTE_DIRS)
+            {
+                cli.set_create_remote_dirs(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_ONE_FILE_SYSTEM)
+            {
+                cli.set_one_file_system(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_INHIBIT_SLEEP)
+            {
+                cli.set_inhibit_sleep(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_COMPRESS_UPLOADS)
+            {
+                cli.set_compress_uploads(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_PREALLOCATE)
+            {
+                cli.set_preallocate(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_DURABLE_WRITES)
+            {
+                cli.set_durable_writes(matches!(opt, 0));
+            }
+            if let Some(Payload::Unsigned(opt)) =
+                self.view.get_value(super::COMPONENT_RADIO_USE_TRASH)
+            {
+                cli.set_use_trash(matches!(opt, 0));
+            }
+            if let Some(Payload::Text(dir)) = self.view.get_value(super::COMPONENT_INPUT_TRASH_DIR)
+            {
+                cli.set_trash_dir(dir.as_str());
+            }
+            if let Some(Payload::Text(rules)) =
+                self.view.get_value(super::COMPONENT_INPUT_IGNORE_RULES)
+            {
+                cli.set_ignore_rules(rules.as_str());
+            }
+            if let Some(Payload::Text(name)) = self
+                .view
+                .get_value(super::COMPONENT_INPUT_UPLOAD_PERMISSIONS)
+            {
+                cli.set_upload_permissions(name.as_str())?;
+            }
+            if let Some(Payload::Text(schedule)) = self
+                .view
+                .get_value(super::COMPONENT_INPUT_BANDWIDTH_SCHEDULE)
+            {
+                cli.set_bandwidth_schedule(schedule.as_str())?;
+            }
+            if let Some(Payload::Text(dir)) =
+                self.view.get_value(super::COMPONENT_INPUT_STAGING_DIR)
+            {
+                cli.set_staging_dir(dir.as_str())?;
+            }
         }
+        Ok(())
     }
 
     /// ### reload_ssh_keys
@@ -783,4 +1970,43 @@ impl SetupActivity {
             }
         }
     }
+
+    /// ### reload_known_hosts
+    ///
+    /// Reload known hosts list
+    pub(super) fn reload_known_hosts(&mut self) {
+        if let Some(hosts) = self.known_hosts.as_ref() {
+            if let Some(props) = self
+                .view
+                .get_props(super::COMPONENT_LIST_KNOWN_HOSTS)
+                .as_mut()
+            {
+                let entries: Vec<TextSpan> = hosts
+                    .iter()
+                    .map(|x| {
+                        TextSpan::from(
+                            format!(
+                                "{} {} {}{}",
+                                x.host,
+                                x.key_type,
+                                x.fingerprint(),
+                                match x.reverify {
+                                    true => " (re-verify)",
+                                    false => "",
+                                }
+                            )
+                            .as_str(),
+                        )
+                    })
+                    .collect();
+                let props = props
+                    .with_texts(TextParts::new(
+                        Some(String::from("Known Hosts")),
+                        Some(entries),
+                    ))
+                    .build();
+                self.view.update(super::COMPONENT_LIST_KNOWN_HOSTS, props);
+            }
+        }
+    }
 }
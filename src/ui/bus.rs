@@ -0,0 +1,209 @@
+//! ## Bus
+//!
+//! `bus` is the module which provides the notification bus shared between the activities thanks to the context.
+//! Activities (and background tasks) publish notifications on the bus; each subscriber receives them in its own
+//! mailbox, which is read whenever the subscriber is active.
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+
+/// ## ToastLevel
+///
+/// Describes the importance of a toast
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum ToastLevel {
+    Info,
+    Warn,
+    Error,
+}
+
+/// ## Notification
+///
+/// A notification sent on the bus
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Notification {
+    ThemeChanged,              // Color mode has changed
+    ConfigReloaded,            // Configuration has been read again
+    Toast(ToastLevel, String), // Message to show in the active activity
+}
+
+/// ## Topic
+///
+/// Topics notifications are grouped by; subscribers choose the topics they're interested in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(crate) enum Topic {
+    Config, // Theme and configuration changes
+    Toast,
+}
+
+impl Notification {
+    /// ### topic
+    ///
+    /// Returns the topic the notification belongs to
+    pub fn topic(&self) -> Topic {
+        match self {
+            Notification::ThemeChanged | Notification::ConfigReloaded => Topic::Config,
+            Notification::Toast(_, _) => Topic::Toast,
+        }
+    }
+}
+
+type Subscribers = Arc<Mutex<Vec<(Vec<Topic>, Sender<Notification>)>>>;
+
+/// ## NotificationBus
+///
+/// The notification bus
+pub(crate) struct NotificationBus {
+    subscribers: Subscribers,
+}
+
+/// ## Publisher
+///
+/// Publisher sends notifications on the bus; it can be moved to other threads
+#[derive(Clone)]
+pub(crate) struct Publisher {
+    subscribers: Subscribers,
+}
+
+/// ## Mailbox
+///
+/// Mailbox receives the notifications published on the subscribed topics.
+/// Dropping the mailbox cancels the subscription
+pub(crate) struct Mailbox {
+    receiver: Receiver<Notification>,
+}
+
+impl NotificationBus {
+    /// ### new
+    ///
+    /// Instantiates a new NotificationBus
+    pub fn new() -> Self {
+        NotificationBus {
+            subscribers: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// ### subscribe
+    ///
+    /// Subscribe to `topics`; notifications are delivered to the returned mailbox
+    pub fn subscribe(&mut self, topics: &[Topic]) -> Mailbox {
+        let (sender, receiver): (Sender<Notification>, Receiver<Notification>) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push((topics.to_vec(), sender));
+        }
+        Mailbox { receiver }
+    }
+
+    /// ### publisher
+    ///
+    /// Get a new publisher for the bus
+    pub fn publisher(&self) -> Publisher {
+        Publisher {
+            subscribers: Arc::clone(&self.subscribers),
+        }
+    }
+
+    /// ### publish
+    ///
+    /// Publish notification on the bus
+    pub fn publish(&self, notification: Notification) {
+        self.publisher().publish(notification)
+    }
+}
+
+impl Default for NotificationBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Publisher {
+    /// ### publish
+    ///
+    /// Deliver notification to all the subscribers of its topic.
+    /// Subscribers whose mailbox has been dropped are removed
+    pub fn publish(&self, notification: Notification) {
+        let topic: Topic = notification.topic();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain(|(topics, sender)| {
+                !topics.contains(&topic) || sender.send(notification.clone()).is_ok()
+            });
+        }
+    }
+
+    /// ### toast
+    ///
+    /// Publish a toast
+    pub fn toast(&self, level: ToastLevel, message: String) {
+        self.publish(Notification::Toast(level, message))
+    }
+}
+
+impl Mailbox {
+    /// ### read
+    ///
+    /// Take all the notifications received since last read
+    pub fn read(&self) -> Vec<Notification> {
+        self.receiver.try_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::thread;
+
+    #[test]
+    fn test_ui_bus() {
+        let mut bus: NotificationBus = NotificationBus::new();
+        let config: Mailbox = bus.subscribe(&[Topic::Config]);
+        let all: Mailbox = bus.subscribe(&[Topic::Config, Topic::Toast]);
+        bus.publish(Notification::ThemeChanged);
+        // Publish from another thread
+        let publisher: Publisher = bus.publisher();
+        thread::spawn(move || publisher.toast(ToastLevel::Info, String::from("hello")))
+            .join()
+            .unwrap();
+        assert_eq!(config.read(), vec![Notification::ThemeChanged]);
+        assert_eq!(
+            all.read(),
+            vec![
+                Notification::ThemeChanged,
+                Notification::Toast(ToastLevel::Info, String::from("hello"))
+            ]
+        );
+        // Mailboxes are empty once read
+        assert!(all.read().is_empty());
+        // Drop subscription
+        drop(config);
+        bus.publish(Notification::ConfigReloaded);
+        assert_eq!(bus.subscribers.lock().unwrap().len(), 1);
+        assert_eq!(all.read(), vec![Notification::ConfigReloaded]);
+    }
+}
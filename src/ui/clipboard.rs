@@ -0,0 +1,153 @@
+//! ## Clipboard
+//!
+//! `clipboard` is the module which copies text to the system clipboard through the terminal, using the OSC 52
+//! escape sequence; this way copying works on remote shells too, if the terminal emulator supports it.
+//! Secrets copied to the clipboard are wiped once their timeout expires.
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::bus::{Publisher, ToastLevel};
+// Ext
+use std::io::{self, stdout, Write};
+use std::time::{Duration, Instant};
+
+/// ## Clipboard
+///
+/// Clipboard copies text through the terminal and keeps track of the secrets to wipe
+pub(crate) struct Clipboard {
+    publisher: Publisher,
+    term: Box<dyn Write>,      // Where escape sequences are written to
+    clear_at: Option<Instant>, // When the copied secret must be wiped
+}
+
+impl Clipboard {
+    /// ### new
+    ///
+    /// Instantiates a new Clipboard; wipes are notified through `publisher`
+    pub fn new(publisher: Publisher) -> Self {
+        Clipboard {
+            publisher,
+            term: Box::new(stdout()),
+            clear_at: None,
+        }
+    }
+
+    /// ### copy_secret
+    ///
+    /// Copy `secret` to the clipboard; it is wiped after `timeout`, if any
+    pub fn copy_secret(&mut self, secret: &str, timeout: Option<Duration>) -> io::Result<()> {
+        self.write(osc52(secret).as_str())?;
+        self.clear_at = timeout.map(|x| Instant::now() + x);
+        Ok(())
+    }
+
+    /// ### poll
+    ///
+    /// Wipe the clipboard if the timeout of the copied secret has expired; a toast confirms the wipe
+    pub fn poll(&mut self) {
+        if matches!(self.clear_at, Some(clear_at) if Instant::now() >= clear_at) {
+            self.clear_at = None;
+            match self.clear() {
+                Ok(_) => self
+                    .publisher
+                    .toast(ToastLevel::Info, String::from("Clipboard cleared")),
+                Err(err) => self.publisher.toast(
+                    ToastLevel::Error,
+                    format!("Could not clear clipboard: {}", err),
+                ),
+            }
+        }
+    }
+
+    /// ### flush
+    ///
+    /// Wipe the clipboard now, if a secret is still waiting to be wiped
+    pub fn flush(&mut self) {
+        if self.clear_at.take().is_some() {
+            let _ = self.clear();
+        }
+    }
+
+    /// ### clear
+    ///
+    /// Replace the clipboard content with an empty string
+    fn clear(&mut self) -> io::Result<()> {
+        self.write(osc52("").as_str())
+    }
+
+    /// ### write
+    ///
+    /// Write escape sequence to the terminal
+    fn write(&mut self, seq: &str) -> io::Result<()> {
+        self.term.write_all(seq.as_bytes())?;
+        self.term.flush()
+    }
+}
+
+/// ### osc52
+///
+/// Make the OSC 52 escape sequence which sets the clipboard content to `text`
+fn osc52(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64::encode(text))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::ui::bus::{Mailbox, Notification, NotificationBus, Topic};
+
+    #[test]
+    fn test_ui_clipboard_osc52() {
+        assert_eq!(osc52("hello"), "\x1b]52;c;aGVsbG8=\x07");
+        assert_eq!(osc52(""), "\x1b]52;c;\x07");
+    }
+
+    #[test]
+    fn test_ui_clipboard_poll() {
+        let mut bus: NotificationBus = NotificationBus::new();
+        let toasts: Mailbox = bus.subscribe(&[Topic::Toast]);
+        let mut clipboard: Clipboard = Clipboard::new(bus.publisher());
+        clipboard.term = Box::new(io::sink());
+        // Not expired yet
+        clipboard.clear_at = Some(Instant::now() + Duration::from_secs(60));
+        clipboard.poll();
+        assert!(toasts.read().is_empty());
+        // Expired
+        clipboard.clear_at = Some(Instant::now());
+        clipboard.poll();
+        assert_eq!(
+            toasts.read(),
+            vec![Notification::Toast(
+                ToastLevel::Info,
+                String::from("Clipboard cleared")
+            )]
+        );
+        assert!(clipboard.clear_at.is_none());
+        // Nothing to wipe
+        clipboard.poll();
+        assert!(toasts.read().is_empty());
+    }
+}
@@ -27,24 +27,44 @@
  */
 // Dependencies
 extern crate crossterm;
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+extern crate libc;
 extern crate tui;
 
 // Locals
+use super::bus::{Mailbox, Notification, NotificationBus, ToastLevel, Topic};
+use super::clipboard::Clipboard;
 use super::input::InputHandler;
-use super::store::Store;
-use crate::filetransfer::FileTransferProtocol;
+use super::store::{Store, STORE_KEY_COLOR_MODE, STORE_KEY_CONFIG_RELOADED};
+use super::tabs::TabBar;
+use super::tasks::TaskRunner;
+use crate::filetransfer::{FileTransferProtocol, SshAlgorithms};
 use crate::host::Localhost;
 use crate::system::config_client::ConfigClient;
+use crate::system::environment;
+use crate::ui::layout::accessibility::ColorMode;
+use crate::ui::layout::toast::Toast;
+use crate::utils::secret::Secret;
 
 // Includes
 use crossterm::event::DisableMouseCapture;
 use crossterm::execute;
-use crossterm::terminal::{EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use std::fmt;
 use std::io::{stdout, Stdout};
 use std::path::PathBuf;
+use std::time::{Duration, Instant};
 use tui::backend::CrosstermBackend;
+use tui::layout::Rect;
 use tui::Terminal;
 
+/// Amount of threads running background tasks
+const TASK_WORKERS: usize = 4;
+/// How long a toast is displayed
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
 /// ## Context
 ///
 /// Context holds data structures used by the ui
@@ -53,21 +73,51 @@ pub struct Context {
     pub ft_params: Option<FileTransferParams>,
     pub(crate) config_client: Option<ConfigClient>,
     pub(crate) store: Store,
+    pub(crate) tasks: TaskRunner,
+    pub(crate) bus: NotificationBus,
+    pub(crate) clipboard: Clipboard,
+    toasts: Mailbox,
+    toast: Option<(Toast, Instant)>, // Toast currently displayed and when it has been received
     pub(crate) input_hnd: InputHandler,
     pub(crate) terminal: Terminal<CrosstermBackend<Stdout>>,
-    error: Option<String>,
+    pub(crate) tabs: TabBar, // File transfer sessions open in tabs
+    issues: Vec<StartupIssue>,
+}
+
+/// ## StartupIssueKind
+///
+/// Describes what went wrong while loading configuration and bookmarks
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StartupIssueKind {
+    ConfigUnreadable,
+    BookmarksCorrupt,
+    BookmarksUnwritable,
+    KeysMissing,
+}
+
+/// ## StartupIssue
+///
+/// An issue occurred while loading configuration and bookmarks
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartupIssue {
+    pub kind: StartupIssueKind,
+    pub message: String,
 }
 
 /// ### FileTransferParams
 ///
 /// Holds connection parameters for file transfers
+#[derive(Clone)]
 pub struct FileTransferParams {
     pub address: String,
     pub port: u16,
     pub protocol: FileTransferProtocol,
     pub username: Option<String>,
-    pub password: Option<String>,
+    pub password: Option<Secret>, // Kept in memory only
     pub entry_directory: Option<PathBuf>,
+    pub endpoint: Option<String>,  // S3 endpoint URL; AWS if `None`
+    pub region: Option<String>,    // S3 region
+    pub algorithms: SshAlgorithms, // Preferred SSH algorithms
 }
 
 impl Context {
@@ -77,36 +127,179 @@ impl Context {
     pub fn new(
         local: Localhost,
         config_client: Option<ConfigClient>,
-        error: Option<String>,
+        issues: Vec<StartupIssue>,
     ) -> Context {
         // Create terminal
         let mut stdout = stdout();
         assert!(execute!(stdout, EnterAlternateScreen).is_ok());
-        Context {
+        let mut bus: NotificationBus = NotificationBus::new();
+        let toasts: Mailbox = bus.subscribe(&[Topic::Toast]);
+        let clipboard: Clipboard = Clipboard::new(bus.publisher());
+        let mut ctx: Context = Context {
             local,
             ft_params: None,
             config_client,
             store: Store::init(),
+            tasks: TaskRunner::new(TASK_WORKERS),
+            bus,
+            clipboard,
+            toasts,
+            toast: None,
             input_hnd: InputHandler::new(),
             terminal: Terminal::new(CrosstermBackend::new(stdout)).unwrap(),
-            error,
+            tabs: TabBar::default(),
+            issues,
+        };
+        let color_mode: String = ctx.color_mode().to_string();
+        ctx.store.set_string(STORE_KEY_COLOR_MODE, color_mode);
+        ctx
+    }
+
+    /// ### init_config_client
+    ///
+    /// Initialize the configuration client from the configuration directory.
+    /// Returns the client, if it could be read, along with the issues occurred while loading it
+    pub fn init_config_client() -> (Option<ConfigClient>, Vec<StartupIssue>) {
+        let config_dir: PathBuf = match environment::init_config_dir() {
+            Ok(Some(config_dir)) => config_dir,
+            Ok(None) => {
+                return (
+                    None,
+                    vec![StartupIssue::new(
+                        StartupIssueKind::ConfigUnreadable,
+                        "Your system doesn't support configuration paths",
+                    )],
+                )
+            }
+            Err(err) => {
+                return (
+                    None,
+                    vec![StartupIssue::new(
+                        StartupIssueKind::ConfigUnreadable,
+                        format!("Could not initialize configuration directory: {}", err),
+                    )],
+                )
+            }
+        };
+        // Get config client paths
+        let (config_path, ssh_dir): (PathBuf, PathBuf) =
+            environment::get_config_paths(config_dir.as_path());
+        match ConfigClient::new(config_path.as_path(), ssh_dir.as_path()) {
+            Ok(cli) => {
+                let issues: Vec<StartupIssue> = Self::check_ssh_keys(&cli);
+                (Some(cli), issues)
+            }
+            Err(err) => (
+                None,
+                vec![StartupIssue::new(
+                    StartupIssueKind::ConfigUnreadable,
+                    format!("Could not read configuration: {}", err),
+                )],
+            ),
         }
     }
 
-    /* NOTE: in case is necessary
-    /// ### set_error
+    /// ### reload_config
     ///
-    /// Set context error
-    pub fn set_error(&mut self, err: String) {
-        self.error = Some(err);
+    /// Read the configuration again, replacing the current configuration client.
+    /// Returns the issues occurred while loading it
+    pub fn reload_config(&mut self) -> Vec<StartupIssue> {
+        let (config_client, issues) = Self::init_config_client();
+        self.config_client = config_client;
+        self.update_color_mode();
+        self.store.set(STORE_KEY_CONFIG_RELOADED);
+        self.bus.publish(Notification::ConfigReloaded);
+        issues
+    }
+
+    /// ### update_color_mode
+    ///
+    /// Put the color mode from configuration into the store, if it has changed; then notify the change on the bus
+    pub fn update_color_mode(&mut self) {
+        let color_mode: String = self.color_mode().to_string();
+        if self.store.get_string(STORE_KEY_COLOR_MODE) != Some(color_mode.as_str()) {
+            self.store.set_string(STORE_KEY_COLOR_MODE, color_mode);
+            self.bus.publish(Notification::ThemeChanged);
+        }
     }
-    */
 
-    /// ### get_error
+    /// ### poll_toast
     ///
-    /// Get error message and remove it from the context
-    pub fn get_error(&mut self) -> Option<String> {
-        self.error.take()
+    /// Read toasts published on the bus; the most recent one is displayed until it expires.
+    /// Returns whether the displayed toast has changed, hence the ui should be redrawn
+    pub(crate) fn poll_toast(&mut self) -> bool {
+        // Wipe copied secrets first, so that the confirmation is shown right away
+        self.clipboard.poll();
+        let mut changed: bool = false;
+        if let Some(Notification::Toast(level, message)) = self.toasts.read().pop() {
+            self.toast = Some((Toast::new(level, message), Instant::now()));
+            changed = true;
+        }
+        if matches!(self.toast.as_ref(), Some((_, received)) if received.elapsed() >= TOAST_DURATION)
+        {
+            self.toast = None;
+            changed = true;
+        }
+        changed
+    }
+
+    /// ### copy_secret
+    ///
+    /// Copy `secret` to the clipboard; it is wiped after the timeout set in configuration
+    pub(crate) fn copy_secret(&mut self, secret: &str) -> std::io::Result<()> {
+        let timeout: u64 = match self.config_client.as_ref() {
+            Some(cli) => cli.get_clipboard_clear_timeout(),
+            None => 0,
+        };
+        let timeout: Option<Duration> = match timeout {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        self.clipboard.copy_secret(secret, timeout)?;
+        let message: String = match timeout {
+            Some(timeout) => format!(
+                "Copied to clipboard; it will be cleared in {}s",
+                timeout.as_secs()
+            ),
+            None => String::from("Copied to clipboard"),
+        };
+        self.bus
+            .publish(Notification::Toast(ToastLevel::Info, message));
+        Ok(())
+    }
+
+    /// ### toast
+    ///
+    /// Get the toast to display, if any
+    pub(crate) fn toast(&self) -> Option<Toast> {
+        self.toast.as_ref().map(|(toast, _)| toast.clone())
+    }
+
+    /// ### take_issues
+    ///
+    /// Get the issues occurred at startup and remove them from the context
+    pub fn take_issues(&mut self) -> Vec<StartupIssue> {
+        self.issues.drain(..).collect()
+    }
+
+    /// ### color_mode
+    ///
+    /// Get the color mode to render the ui with, according to configuration and environment
+    pub fn color_mode(&self) -> ColorMode {
+        ColorMode::from_env(match self.config_client.as_ref() {
+            Some(cli) => cli.get_color_mode(),
+            None => ColorMode::Default,
+        })
+    }
+
+    /// ### reduced_motion
+    ///
+    /// Returns whether the ui should be redrawn as less as possible
+    pub fn reduced_motion(&self) -> bool {
+        match self.config_client.as_ref() {
+            Some(cli) => cli.get_reduced_motion(),
+            None => false,
+        }
     }
 
     /// ### enter_alternate_screen
@@ -131,16 +324,105 @@ impl Context {
         );
     }
 
+    /// ### resize
+    ///
+    /// Resize the terminal to the new size and clear the screen, so that the next draw lays out
+    /// the entire ui again
+    pub(crate) fn resize(&mut self, width: u16, height: u16) {
+        let _ = self.terminal.resize(Rect::new(0, 0, width, height));
+    }
+
     /// ### clear_screen
     ///
     /// Clear terminal screen
     pub fn clear_screen(&mut self) {
         let _ = self.terminal.clear();
     }
+
+    /// ### suspend
+    ///
+    /// Give the terminal back to the shell and stop the process, as Ctrl+Z does in cooked mode.
+    /// When resumed (`fg`), raw mode and alternate screen are restored and the screen is cleared,
+    /// so that the next draw renders the entire ui again
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    pub(crate) fn suspend(&mut self) {
+        let _ = self.terminal.show_cursor();
+        self.leave_alternate_screen();
+        let _ = disable_raw_mode();
+        // NOTE: SIGTSTP is caught by the input handler; SIGSTOP can't be caught
+        unsafe {
+            libc::raise(libc::SIGSTOP);
+        }
+        // Resumed
+        let _ = enable_raw_mode();
+        self.enter_alternate_screen();
+        let _ = self.terminal.hide_cursor();
+        self.clear_screen();
+    }
+
+    /// ### suspend
+    ///
+    /// Job control is not supported on this platform: nothing to do
+    #[cfg(not(any(target_os = "unix", target_os = "macos", target_os = "linux")))]
+    pub(crate) fn suspend(&mut self) {}
+
+    // -- privates
+
+    /// ### check_ssh_keys
+    ///
+    /// Report SSH keys registered in configuration whose file doesn't exist
+    fn check_ssh_keys(cli: &ConfigClient) -> Vec<StartupIssue> {
+        cli.iter_ssh_keys()
+            .filter_map(|mkey| match cli.get_ssh_key(mkey) {
+                Ok(Some((host, username, path))) if !path.exists() => Some(StartupIssue::new(
+                    StartupIssueKind::KeysMissing,
+                    format!(
+                        "SSH key for {}@{} not found at \"{}\"",
+                        username,
+                        host,
+                        path.display()
+                    ),
+                )),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+impl StartupIssue {
+    /// ### new
+    ///
+    /// Instantiates a new StartupIssue
+    pub fn new<S: AsRef<str>>(kind: StartupIssueKind, message: S) -> StartupIssue {
+        StartupIssue {
+            kind,
+            message: message.as_ref().to_string(),
+        }
+    }
+}
+
+impl fmt::Display for StartupIssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind: &str = match self {
+            StartupIssueKind::ConfigUnreadable => "Configuration unreadable",
+            StartupIssueKind::BookmarksCorrupt => "Bookmarks corrupted",
+            StartupIssueKind::BookmarksUnwritable => "Bookmarks unwritable",
+            StartupIssueKind::KeysMissing => "SSH keys missing",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+impl fmt::Display for StartupIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.kind, self.message)
+    }
 }
 
 impl Drop for Context {
     fn drop(&mut self) {
+        // Don't leave secrets in the clipboard
+        self.clipboard.flush();
         // Re-enable terminal stuff
         let _ = execute!(
             self.terminal.backend_mut(),
@@ -159,6 +441,9 @@ impl Default for FileTransferParams {
             username: None,
             password: None,
             entry_directory: None,
+            endpoint: None,
+            region: None,
+            algorithms: SshAlgorithms::default(),
         }
     }
 }
@@ -176,6 +461,32 @@ mod tests {
         assert_eq!(params.protocol, FileTransferProtocol::Sftp);
         assert!(params.username.is_none());
         assert!(params.password.is_none());
+        assert!(params.endpoint.is_none());
+        assert!(params.region.is_none());
+    }
+
+    #[test]
+    fn test_ui_context_startup_issue() {
+        let issue: StartupIssue =
+            StartupIssue::new(StartupIssueKind::BookmarksCorrupt, "invalid TOML at line 4");
+        assert_eq!(issue.kind, StartupIssueKind::BookmarksCorrupt);
+        assert_eq!(issue.message.as_str(), "invalid TOML at line 4");
+        assert_eq!(
+            issue.to_string().as_str(),
+            "Bookmarks corrupted: invalid TOML at line 4"
+        );
+        assert_eq!(
+            StartupIssueKind::ConfigUnreadable.to_string().as_str(),
+            "Configuration unreadable"
+        );
+        assert_eq!(
+            StartupIssueKind::BookmarksUnwritable.to_string().as_str(),
+            "Bookmarks unwritable"
+        );
+        assert_eq!(
+            StartupIssueKind::KeysMissing.to_string().as_str(),
+            "SSH keys missing"
+        );
     }
 
     //use crate::filetransfer::sftp_transfer::SftpFileTransfer;
@@ -26,22 +26,52 @@
  * SOFTWARE.
  */
 extern crate crossterm;
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+extern crate libc;
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+extern crate signal_hook;
 
-use crossterm::event::{poll, read, Event};
+use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use std::time::Duration;
 
+/// Key which suspends termscp, as it would do in cooked mode
+const SUSPEND_KEY: KeyEvent = KeyEvent {
+    code: KeyCode::Char('z'),
+    modifiers: KeyModifiers::CONTROL,
+};
+
 /// ## InputHandler
 ///
 /// InputHandler is the struct which runs a thread which waits for
 /// input events from the user and reports them through a receiver
-pub(crate) struct InputHandler;
+pub(crate) struct InputHandler {
+    suspend_requested: Arc<AtomicBool>, // Set when SIGTSTP is received
+}
 
 impl InputHandler {
     /// ### InputHandler
     ///
-    ///
+    /// Instantiates a new `InputHandler`.
+    /// SIGTSTP is caught (where supported), so that it's reported as a suspend key event
     pub(crate) fn new() -> InputHandler {
-        InputHandler {}
+        let suspend_requested: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+        let _ = signal_hook::flag::register(libc::SIGTSTP, suspend_requested.clone());
+        InputHandler { suspend_requested }
+    }
+
+    /// ### is_suspend_event
+    ///
+    /// Returns whether the event requests to suspend termscp (Ctrl+Z or SIGTSTP).
+    /// Always false where job control is not supported
+    pub(crate) fn is_suspend_event(event: &Event) -> bool {
+        cfg!(any(
+            target_os = "unix",
+            target_os = "macos",
+            target_os = "linux"
+        )) && *event == Event::Key(SUSPEND_KEY)
     }
 
     /// ### fetch_events
@@ -66,6 +96,10 @@ impl InputHandler {
     ///
     /// Read event from input listener
     pub(crate) fn read_event(&self) -> Result<Option<Event>, ()> {
+        // Report SIGTSTP as the suspend key
+        if self.suspend_requested.swap(false, Ordering::Relaxed) {
+            return Ok(Some(Event::Key(SUSPEND_KEY)));
+        }
         if let Ok(available) = poll(Duration::from_millis(10)) {
             match available {
                 true => {
@@ -94,6 +128,20 @@ mod tests {
         let _: InputHandler = InputHandler::new();
     }
 
+    #[test]
+    fn test_ui_input_suspend_request() {
+        let input_hnd: InputHandler = InputHandler::new();
+        input_hnd.suspend_requested.store(true, Ordering::Relaxed);
+        let event: Event = input_hnd.read_event().ok().unwrap().unwrap();
+        assert!(InputHandler::is_suspend_event(&event));
+        // Request is consumed
+        assert!(!input_hnd.suspend_requested.load(Ordering::Relaxed));
+        assert!(!InputHandler::is_suspend_event(&Event::Key(KeyEvent {
+            code: KeyCode::Char('z'),
+            modifiers: KeyModifiers::NONE,
+        })));
+    }
+
     /* ERRORS ON GITHUB ACTIONS
     #[test]
     fn test_ui_input_fetch() {
@@ -0,0 +1,237 @@
+//! ## Accessibility
+//!
+//! `accessibility` provides the color modes supported by the user interface and the filter
+//! which converts a rendered frame to a monochrome or high-contrast one
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::fmt;
+use std::str::FromStr;
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color, Modifier};
+use tui::widgets::Widget;
+
+/// ## ColorMode
+///
+/// ColorMode describes how colors and borders are rendered
+#[derive(Clone, Copy, PartialEq, std::fmt::Debug)]
+pub enum ColorMode {
+    Default,
+    HighContrast,
+    NoColor,
+}
+
+impl ColorMode {
+    /// ### from_env
+    ///
+    /// Get the color mode to use, given the configured one.
+    /// If the `NO_COLOR` environment variable is set and not empty, `NoColor` is always returned.
+    /// See <https://no-color.org/>
+    pub fn from_env(configured: ColorMode) -> ColorMode {
+        match std::env::var_os("NO_COLOR") {
+            Some(val) if !val.is_empty() => ColorMode::NoColor,
+            _ => configured,
+        }
+    }
+}
+
+impl fmt::Display for ColorMode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                ColorMode::Default => "default",
+                ColorMode::HighContrast => "high-contrast",
+                ColorMode::NoColor => "no-color",
+            }
+        )
+    }
+}
+
+impl FromStr for ColorMode {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "default" => Ok(ColorMode::Default),
+            "high-contrast" => Ok(ColorMode::HighContrast),
+            "no-color" => Ok(ColorMode::NoColor),
+            _ => Err(()),
+        }
+    }
+}
+
+/// ## ColorFilter
+///
+/// ColorFilter is a widget which doesn't draw anything by itself, but rewrites the cells
+/// already rendered in the area according to the color mode.
+/// It must then be rendered as the last widget of the frame.
+/// In both `HighContrast` and `NoColor` modes, box-drawing and block characters are replaced
+/// by their ASCII equivalent.
+pub struct ColorFilter {
+    mode: ColorMode,
+}
+
+impl ColorFilter {
+    /// ### new
+    ///
+    /// Instantiates a new `ColorFilter`
+    pub fn new(mode: ColorMode) -> ColorFilter {
+        ColorFilter { mode }
+    }
+}
+
+impl Widget for ColorFilter {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        if self.mode == ColorMode::Default {
+            return;
+        }
+        let area: Rect = area.intersection(*buf.area());
+        for y in area.top()..area.bottom() {
+            for x in area.left()..area.right() {
+                let cell = buf.get_mut(x, y);
+                // Highlighted cells are those with a background
+                let highlighted: bool = cell.bg != Color::Reset;
+                match self.mode {
+                    ColorMode::HighContrast => match highlighted {
+                        true => {
+                            cell.fg = Color::Black;
+                            cell.bg = Color::White;
+                            cell.modifier.insert(Modifier::BOLD);
+                        }
+                        false => {
+                            cell.fg = Color::White;
+                            cell.bg = Color::Black;
+                        }
+                    },
+                    _ => {
+                        if highlighted {
+                            cell.modifier.insert(Modifier::REVERSED);
+                        }
+                        cell.fg = Color::Reset;
+                        cell.bg = Color::Reset;
+                    }
+                }
+                if let Some(symbol) = ascii_symbol(cell.symbol.as_str()) {
+                    cell.set_symbol(symbol);
+                }
+            }
+        }
+    }
+}
+
+/// ### ascii_symbol
+///
+/// Get the ASCII replacement for a box-drawing or block symbol, if any
+fn ascii_symbol(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "─" | "═" | "━" => Some("-"),
+        "│" | "║" | "┃" => Some("|"),
+        "┌" | "┐" | "└" | "┘" | "╭" | "╮" | "╰" | "╯" | "╔" | "╗" | "╚" | "╝" | "┏" | "┓" | "┗"
+        | "┛" | "├" | "┤" | "┬" | "┴" | "┼" | "╠" | "╣" | "╦" | "╩" | "╬" | "┣" | "┫" | "┳"
+        | "┻" | "╋" => Some("+"),
+        "█" | "▉" | "▊" | "▋" | "▌" | "▇" | "▆" | "▅" | "▄" => Some("#"),
+        "▍" | "▎" | "▏" | "▃" | "▂" | "▁" => Some(" "),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use tui::style::Style;
+    use tui::widgets::{Block, BorderType, Borders};
+
+    #[test]
+    fn test_ui_layout_accessibility_color_mode() {
+        assert_eq!(ColorMode::from_str("default"), Ok(ColorMode::Default));
+        assert_eq!(
+            ColorMode::from_str("HIGH-CONTRAST"),
+            Ok(ColorMode::HighContrast)
+        );
+        assert_eq!(ColorMode::from_str("no-color"), Ok(ColorMode::NoColor));
+        assert!(ColorMode::from_str("rainbow").is_err());
+        assert_eq!(
+            ColorMode::HighContrast.to_string().as_str(),
+            "high-contrast"
+        );
+        assert_eq!(ColorMode::NoColor.to_string().as_str(), "no-color");
+        assert_eq!(ColorMode::Default.to_string().as_str(), "default");
+    }
+
+    #[test]
+    fn test_ui_layout_accessibility_filter_no_color() {
+        let area: Rect = Rect::new(0, 0, 6, 3);
+        let mut buf: Buffer = Buffer::empty(area);
+        Block::default()
+            .borders(Borders::ALL)
+            .border_type(BorderType::Rounded)
+            .border_style(Style::default().fg(Color::Yellow))
+            .render(area, &mut buf);
+        buf.set_string(
+            1,
+            1,
+            "ab",
+            Style::default().fg(Color::Black).bg(Color::Cyan),
+        );
+        ColorFilter::new(ColorMode::NoColor).render(area, &mut buf);
+        let mut expected: Buffer = Buffer::with_lines(vec!["+----+", "|ab  |", "+----+"]);
+        expected.set_style(
+            Rect::new(1, 1, 2, 1),
+            Style::default().add_modifier(Modifier::REVERSED),
+        );
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn test_ui_layout_accessibility_filter_high_contrast() {
+        let area: Rect = Rect::new(0, 0, 4, 1);
+        let mut buf: Buffer = Buffer::empty(area);
+        buf.set_string(0, 0, "a█", Style::default().fg(Color::Red));
+        buf.set_string(2, 0, "b", Style::default().bg(Color::Blue));
+        ColorFilter::new(ColorMode::HighContrast).render(area, &mut buf);
+        let cells = buf.content();
+        assert_eq!(cells[0].symbol.as_str(), "a");
+        assert_eq!(cells[0].fg, Color::White);
+        assert_eq!(cells[0].bg, Color::Black);
+        assert_eq!(cells[1].symbol.as_str(), "#");
+        assert_eq!(cells[2].fg, Color::Black);
+        assert_eq!(cells[2].bg, Color::White);
+        assert!(cells[2].modifier.contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_ui_layout_accessibility_filter_default() {
+        let area: Rect = Rect::new(0, 0, 2, 1);
+        let mut buf: Buffer = Buffer::empty(area);
+        buf.set_string(0, 0, "─", Style::default().fg(Color::Red));
+        let expected: Buffer = buf.clone();
+        ColorFilter::new(ColorMode::Default).render(area, &mut buf);
+        assert_eq!(buf, expected);
+    }
+}
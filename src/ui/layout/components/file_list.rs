@@ -145,7 +145,12 @@ impl Component for FileList {
                 None => vec![],
                 Some(lines) => lines
                     .iter()
-                    .map(|line| ListItem::new(Span::from(line.content.to_string())))
+                    .map(|line| {
+                        ListItem::new(Span::styled(
+                            line.content.to_string(),
+                            Style::default().fg(line.fg),
+                        ))
+                    })
                     .collect(),
             };
             let (fg, bg): (Color, Color) = match self.states.focus {
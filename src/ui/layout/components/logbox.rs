@@ -159,7 +159,9 @@ impl Component for LogBox {
                             })
                             .collect();
                         // Let's convert column spans into Spans rows NOTE: -4 because first line is always made by 5 columns; but there's always 1
-                        let mut rows: Vec<Spans> = Vec::with_capacity(columns.len() - 4);
+                        // (rows may be empty though, when there's nothing to log)
+                        let mut rows: Vec<Spans> =
+                            Vec::with_capacity(columns.len().saturating_sub(4));
                         // Get first row
                         let mut first_row: Vec<Span> = vec![Span::styled(
                             match self.states.list_index == idx {
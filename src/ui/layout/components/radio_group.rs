@@ -32,7 +32,7 @@ use super::{Canvas, Component, InputEvent, Msg, Payload, PropValue, Props, Props
 use crossterm::event::KeyCode;
 use tui::{
     layout::Rect,
-    style::{Color, Style},
+    style::{Color, Modifier, Style},
     text::Spans,
     widgets::{Block, BorderType, Tabs},
 };
@@ -128,15 +128,22 @@ impl Component for RadioGroup {
                 .iter()
                 .map(|x| Spans::from(x.clone()))
                 .collect();
-            // Make colors
-            let (bg, fg, block_color): (Color, Color, Color) = match &self.states.focus {
-                true => (
-                    self.props.foreground,
-                    self.props.background,
-                    self.props.foreground,
-                ),
-                false => (Color::Reset, self.props.foreground, Color::Reset),
-            };
+            // Make colors; when not focused, the choice is also marked in bold, since colors may be filtered out
+            let (bg, fg, block_color, modifiers): (Color, Color, Color, Modifier) =
+                match &self.states.focus {
+                    true => (
+                        self.props.foreground,
+                        self.props.background,
+                        self.props.foreground,
+                        self.props.get_modifiers(),
+                    ),
+                    false => (
+                        Color::Reset,
+                        self.props.foreground,
+                        Color::Reset,
+                        self.props.get_modifiers() | Modifier::BOLD,
+                    ),
+                };
             let title: String = match &self.props.texts.title {
                 Some(t) => t.clone(),
                 None => String::new(),
@@ -152,12 +159,7 @@ impl Component for RadioGroup {
                     )
                     .select(self.states.choice)
                     .style(Style::default().fg(block_color))
-                    .highlight_style(
-                        Style::default()
-                            .add_modifier(self.props.get_modifiers())
-                            .fg(fg)
-                            .bg(bg),
-                    ),
+                    .highlight_style(Style::default().add_modifier(modifiers).fg(fg).bg(bg)),
                 area,
             );
         }
@@ -26,8 +26,10 @@
  * SOFTWARE.
  */
 // Modules
+pub mod accessibility;
 pub mod components;
 pub mod props;
+pub mod toast;
 pub mod utils;
 pub mod view;
 
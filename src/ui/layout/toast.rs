@@ -0,0 +1,118 @@
+//! ## Toast
+//!
+//! `toast` provides the widget which displays the toasts published on the notification bus,
+//! in the top right corner of the frame
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use crate::ui::bus::ToastLevel;
+// Ext
+use tui::buffer::Buffer;
+use tui::layout::Rect;
+use tui::style::{Color, Style};
+use tui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Widget, Wrap};
+use unicode_width::UnicodeWidthStr;
+
+/// Maximum width of a toast (borders included)
+const TOAST_MAX_WIDTH: u16 = 48;
+
+/// ## Toast
+///
+/// Toast is a widget which renders a message in the top right corner of the area
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    pub level: ToastLevel,
+    pub message: String,
+}
+
+impl Toast {
+    /// ### new
+    ///
+    /// Instantiates a new Toast
+    pub fn new(level: ToastLevel, message: String) -> Self {
+        Toast { level, message }
+    }
+
+    /// ### area
+    ///
+    /// Get the area the toast is rendered in, inside of `parent`
+    fn area(&self, parent: Rect) -> Rect {
+        let width: u16 = (self.message.width() as u16 + 2)
+            .min(TOAST_MAX_WIDTH)
+            .min(parent.width);
+        let text_width: usize = width.saturating_sub(2).max(1) as usize;
+        let rows: u16 = textwrap::wrap(self.message.as_str(), text_width).len() as u16;
+        let height: u16 = (rows + 2).min(parent.height);
+        Rect::new(parent.x + parent.width - width, parent.y, width, height)
+    }
+}
+
+impl Widget for Toast {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let area: Rect = self.area(area);
+        if area.area() == 0 {
+            return;
+        }
+        let color: Color = match self.level {
+            ToastLevel::Info => Color::LightGreen,
+            ToastLevel::Warn => Color::Yellow,
+            ToastLevel::Error => Color::Red,
+        };
+        Clear.render(area, buf);
+        Paragraph::new(self.message.as_str())
+            .style(Style::default().fg(color))
+            .wrap(Wrap { trim: true })
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_type(BorderType::Rounded)
+                    .border_style(Style::default().fg(color)),
+            )
+            .render(area, buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ui_layout_toast_area() {
+        let parent: Rect = Rect::new(0, 0, 100, 40);
+        // Short message
+        let toast: Toast = Toast::new(ToastLevel::Info, String::from("Transfer completed"));
+        assert_eq!(toast.area(parent), Rect::new(80, 0, 20, 3));
+        // Long message is wrapped
+        let toast: Toast = Toast::new(ToastLevel::Error, "word ".repeat(20));
+        let area: Rect = toast.area(parent);
+        assert_eq!(area.width, TOAST_MAX_WIDTH);
+        assert_eq!(area.x, 100 - TOAST_MAX_WIDTH);
+        assert_eq!(area.height, 5);
+        // Never exceed parent
+        let parent: Rect = Rect::new(2, 2, 10, 2);
+        assert_eq!(toast.area(parent), Rect::new(2, 2, 10, 2));
+    }
+}
@@ -27,10 +27,23 @@
  */
 use tui::layout::{Constraint, Direction, Layout, Rect};
 
+/// Minimum size of an area drawn with `draw_area_in` (if the parent is big enough)
+const AREA_MIN_WIDTH: u16 = 24;
+const AREA_MIN_HEIGHT: u16 = 3;
+
 /// ### draw_area_in
 ///
-/// Draw an area (WxH / 3) in the middle of the parent area
+/// Draw an area (WxH / 3) in the middle of the parent area.
+/// The area is never smaller than `AREA_MIN_WIDTH`x`AREA_MIN_HEIGHT` nor bigger than the parent,
+/// so that popups are still usable when the terminal gets small
 pub fn draw_area_in(parent: Rect, width: u16, height: u16) -> Rect {
+    clamp_area_in(parent, split_area_in(parent, width, height))
+}
+
+/// ### split_area_in
+///
+/// Split parent area in order to get an area (WxH / 3) in the middle of it
+fn split_area_in(parent: Rect, width: u16, height: u16) -> Rect {
     let new_area = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -55,6 +68,22 @@ pub fn draw_area_in(parent: Rect, width: u16, height: u16) -> Rect {
         .split(new_area[1])[1]
 }
 
+/// ### clamp_area_in
+///
+/// Grow area to the minimum size, keeping it centered, and shrink it to fit in the parent
+fn clamp_area_in(parent: Rect, area: Rect) -> Rect {
+    let mut area: Rect = area;
+    if area.width < AREA_MIN_WIDTH {
+        area.width = AREA_MIN_WIDTH.min(parent.width);
+        area.x = parent.x + (parent.width - area.width) / 2;
+    }
+    if area.height < AREA_MIN_HEIGHT {
+        area.height = AREA_MIN_HEIGHT.min(parent.height);
+        area.y = parent.y + (parent.height - area.height) / 2;
+    }
+    area.intersection(parent)
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -70,4 +99,22 @@ mod tests {
         assert_eq!(child.width, 271);
         assert_eq!(child.height, 54);
     }
+
+    #[test]
+    fn test_ui_layout_utils_draw_area_in_clamped() {
+        // Area is too small: grow to minimum size
+        let area: Rect = Rect::new(0, 0, 40, 12);
+        let child: Rect = draw_area_in(area, 30, 10);
+        assert_eq!(child.width, 24);
+        assert_eq!(child.height, 3);
+        assert_eq!(child.x, 8);
+        assert_eq!(child.y, 4);
+        // Parent is too small: never exceed parent
+        let area: Rect = Rect::new(2, 1, 16, 2);
+        let child: Rect = draw_area_in(area, 30, 10);
+        assert_eq!(child, Rect::new(2, 1, 16, 2));
+        // Empty parent
+        let area: Rect = Rect::new(0, 0, 0, 0);
+        assert_eq!(draw_area_in(area, 50, 50).area(), 0);
+    }
 }
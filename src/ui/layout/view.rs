@@ -195,6 +195,13 @@ impl View {
         }
     }
 
+    /// ### focus
+    ///
+    /// Get the id of the focused component, if any
+    pub fn focus(&self) -> Option<&str> {
+        self.focus.as_deref()
+    }
+
     // -- private
 
     /// ### push_to_stack
@@ -284,6 +291,7 @@ mod tests {
         view.mount(text2, make_component_text());
         // Verify focus
         assert!(view.focus.is_none());
+        assert!(view.focus().is_none());
         assert_eq!(view.focus_stack.len(), 0);
         // Blur when nothing is selected
         view.blur();
@@ -297,6 +305,7 @@ mod tests {
         view.active(input1);
         // Check focus
         assert_eq!(view.focus.as_ref().unwrap().as_str(), input1);
+        assert_eq!(view.focus(), Some(input1));
         assert_eq!(view.focus_stack.len(), 0); // NOTE: stack is empty until a focus gets blurred
                                                // Active a new component
         view.active(input2);
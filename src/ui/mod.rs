@@ -27,7 +27,11 @@
  */
 // Modules
 pub mod activities;
+pub(crate) mod bus;
+pub(crate) mod clipboard;
 pub mod context;
 pub(crate) mod input;
 pub(crate) mod layout;
 pub(crate) mod store;
+pub(crate) mod tabs;
+pub(crate) mod tasks;
@@ -29,13 +29,23 @@
  */
 use std::collections::HashMap;
 
+// -- keys
+
+/// Set (as a flag) each time the configuration is read again
+pub(crate) const STORE_KEY_CONFIG_RELOADED: &str = "CONFIG_RELOADED";
+/// Color mode the ui is rendered with; updated when it is changed in setup
+pub(crate) const STORE_KEY_COLOR_MODE: &str = "COLOR_MODE";
+/// Path of the last file transferred
+pub(crate) const STORE_KEY_TRANSFER_FINISHED: &str = "TRANSFER_FINISHED";
+
 // -- store state
 
 /// ## StoreState
 ///
 /// Store state describes a value in the store
 #[allow(dead_code)]
-enum StoreState {
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum StoreState {
     Str(String),     // String
     Signed(isize),   // Signed number
     Unsigned(usize), // Unsigned number
@@ -44,15 +54,51 @@ enum StoreState {
     Flag,            // Empty value; used to work as a Flag (set unset)
 }
 
+/// ## StoreValue
+///
+/// StoreValue is implemented by the types which can be put into the store
+pub(crate) trait StoreValue: Sized {
+    /// ### into_state
+    ///
+    /// Convert value into a store state
+    fn into_state(self) -> StoreState;
+
+    /// ### from_state
+    ///
+    /// Get value from store state; None if the state has a different type
+    fn from_state(state: &StoreState) -> Option<Self>;
+}
+
+// -- events
+
+/// ## StoreEvent
+///
+/// StoreEvent is passed to subscribers when a key changes
+#[allow(dead_code)]
+pub(crate) struct StoreEvent<'a> {
+    pub key: &'a str,
+    pub value: Option<&'a StoreState>, // None if the key has been unset
+}
+
+/// ## SubscriptionId
+///
+/// Identifies a subscription to the store
+pub(crate) type SubscriptionId = usize;
+
+type StoreCallback = Box<dyn FnMut(&StoreEvent)>;
+
 // -- store
 
 /// ## Store
 ///
 /// Store represent the context store
 /// The store is a key-value hash map. Each key must be unique
-/// To each key a `StoreState` is assigned
+/// To each key a `StoreState` is assigned.
+/// Callbacks can subscribe to a key, to be notified each time it is set or unset
 pub(crate) struct Store {
     store: HashMap<String, StoreState>,
+    subscribers: Vec<(SubscriptionId, String, StoreCallback)>,
+    next_subscription: SubscriptionId,
 }
 
 #[allow(dead_code)]
@@ -63,9 +109,36 @@ impl Store {
     pub fn init() -> Self {
         Store {
             store: HashMap::new(),
+            subscribers: Vec::new(),
+            next_subscription: 0,
         }
     }
 
+    // -- subscriptions
+
+    /// ### subscribe
+    ///
+    /// Subscribe `callback` to changes of `key`; returns the id to unsubscribe with
+    pub fn subscribe<F>(&mut self, key: &str, callback: F) -> SubscriptionId
+    where
+        F: FnMut(&StoreEvent) + 'static,
+    {
+        let id: SubscriptionId = self.next_subscription;
+        self.next_subscription += 1;
+        self.subscribers
+            .push((id, key.to_string(), Box::new(callback)));
+        id
+    }
+
+    /// ### unsubscribe
+    ///
+    /// Remove subscription; returns whether it existed
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let count: usize = self.subscribers.len();
+        self.subscribers.retain(|(x, _, _)| *x != id);
+        count != self.subscribers.len()
+    }
+
     // -- getters
     /// ### get_string
     ///
@@ -117,6 +190,13 @@ impl Store {
         }
     }
 
+    /// ### get
+    ///
+    /// Get typed value from store
+    pub fn get<T: StoreValue>(&self, key: &str) -> Option<T> {
+        self.store.get(key).and_then(T::from_state)
+    }
+
     /// ### isset
     ///
     /// Check if a state is set in the store
@@ -130,43 +210,147 @@ impl Store {
     ///
     /// Set string into the store
     pub fn set_string(&mut self, key: &str, val: String) {
-        self.store.insert(key.to_string(), StoreState::Str(val));
+        self.update(key, StoreState::Str(val));
     }
 
     /// ### set_signed
     ///
     /// Set signed number
     pub fn set_signed(&mut self, key: &str, val: isize) {
-        self.store.insert(key.to_string(), StoreState::Signed(val));
+        self.update(key, StoreState::Signed(val));
     }
 
     /// ### set_signed
     ///
     /// Set unsigned number
     pub fn set_unsigned(&mut self, key: &str, val: usize) {
-        self.store
-            .insert(key.to_string(), StoreState::Unsigned(val));
+        self.update(key, StoreState::Unsigned(val));
     }
 
     /// ### set_float
     ///
     /// Set floating point number
     pub fn set_float(&mut self, key: &str, val: f64) {
-        self.store.insert(key.to_string(), StoreState::Float(val));
+        self.update(key, StoreState::Float(val));
     }
 
     /// ### set_boolean
     ///
     /// Set boolean
     pub fn set_boolean(&mut self, key: &str, val: bool) {
-        self.store.insert(key.to_string(), StoreState::Boolean(val));
+        self.update(key, StoreState::Boolean(val));
     }
 
     /// ### set
     ///
     /// Set a key as a flag; has no value
     pub fn set(&mut self, key: &str) {
-        self.store.insert(key.to_string(), StoreState::Flag);
+        self.update(key, StoreState::Flag);
+    }
+
+    /// ### put
+    ///
+    /// Put typed value into the store
+    pub fn put<T: StoreValue>(&mut self, key: &str, val: T) {
+        self.update(key, val.into_state());
+    }
+
+    /// ### unset
+    ///
+    /// Remove key from the store
+    pub fn unset(&mut self, key: &str) {
+        if self.store.remove(key).is_some() {
+            self.notify(key);
+        }
+    }
+
+    // -- privates
+
+    /// ### update
+    ///
+    /// Set state for key and notify subscribers
+    fn update(&mut self, key: &str, state: StoreState) {
+        self.store.insert(key.to_string(), state);
+        self.notify(key);
+    }
+
+    /// ### notify
+    ///
+    /// Notify subscribers of `key` of its current state
+    fn notify(&mut self, key: &str) {
+        let event: StoreEvent = StoreEvent {
+            key,
+            value: self.store.get(key),
+        };
+        self.subscribers
+            .iter_mut()
+            .filter(|(_, x, _)| x.as_str() == key)
+            .for_each(|(_, _, callback)| callback(&event));
+    }
+}
+
+impl StoreValue for String {
+    fn into_state(self) -> StoreState {
+        StoreState::Str(self)
+    }
+
+    fn from_state(state: &StoreState) -> Option<Self> {
+        match state {
+            StoreState::Str(s) => Some(s.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl StoreValue for isize {
+    fn into_state(self) -> StoreState {
+        StoreState::Signed(self)
+    }
+
+    fn from_state(state: &StoreState) -> Option<Self> {
+        match state {
+            StoreState::Signed(i) => Some(*i),
+            _ => None,
+        }
+    }
+}
+
+impl StoreValue for usize {
+    fn into_state(self) -> StoreState {
+        StoreState::Unsigned(self)
+    }
+
+    fn from_state(state: &StoreState) -> Option<Self> {
+        match state {
+            StoreState::Unsigned(u) => Some(*u),
+            _ => None,
+        }
+    }
+}
+
+impl StoreValue for f64 {
+    fn into_state(self) -> StoreState {
+        StoreState::Float(self)
+    }
+
+    fn from_state(state: &StoreState) -> Option<Self> {
+        match state {
+            StoreState::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+}
+
+impl StoreValue for bool {
+    fn into_state(self) -> StoreState {
+        StoreState::Boolean(self)
+    }
+
+    fn from_state(state: &StoreState) -> Option<Self> {
+        match state {
+            StoreState::Boolean(b) => Some(*b),
+            _ => None,
+        }
     }
 }
 
@@ -207,4 +391,57 @@ mod tests {
         assert!(store.get_string("unexisting-key").is_none());
         assert!(store.get_unsigned("unexisting-key").is_none());
     }
+
+    #[test]
+    fn test_ui_store_typed() {
+        let mut store: Store = Store::init();
+        store.put("test", String::from("hello"));
+        assert_eq!(store.get::<String>("test").unwrap().as_str(), "hello");
+        assert_eq!(store.get_string("test").unwrap(), "hello");
+        store.put("unumber", 1024_usize);
+        assert_eq!(store.get::<usize>("unumber").unwrap(), 1024);
+        store.put("number", -12_isize);
+        assert_eq!(store.get::<isize>("number").unwrap(), -12);
+        store.put("float", 3.33);
+        assert_eq!(store.get::<f64>("float").unwrap(), 3.33);
+        store.put("bool", true);
+        assert!(store.get::<bool>("bool").unwrap());
+        // Bad type
+        assert!(store.get::<bool>("test").is_none());
+        assert!(store.get::<String>("unexisting-key").is_none());
+        // Unset
+        store.unset("test");
+        assert!(!store.isset("test"));
+    }
+
+    #[test]
+    fn test_ui_store_subscribe() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+        let mut store: Store = Store::init();
+        let events: Rc<RefCell<Vec<Option<StoreState>>>> = Rc::new(RefCell::new(Vec::new()));
+        let subscriber = events.clone();
+        let id: SubscriptionId = store.subscribe("theme", move |ev| {
+            assert_eq!(ev.key, "theme");
+            subscriber.borrow_mut().push(ev.value.cloned());
+        });
+        store.set_string("theme", String::from("dark"));
+        store.set_string("other", String::from("ignored"));
+        store.set("theme");
+        store.unset("theme");
+        store.unset("theme"); // Not set; no event
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                Some(StoreState::Str(String::from("dark"))),
+                Some(StoreState::Flag),
+                None
+            ]
+        );
+        // Unsubscribe
+        assert!(store.unsubscribe(id));
+        assert!(!store.unsubscribe(id));
+        store.set_boolean("theme", true);
+        assert_eq!(events.borrow().len(), 3);
+    }
 }
@@ -0,0 +1,197 @@
+//! ## Tabs
+//!
+//! `tabs` is the module which keeps the file transfer sessions open in tabs, only one of which runs at a time
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+
+/// ## TabBar
+///
+/// What the running activity is told about the open tabs
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TabBar {
+    pub labels: Vec<String>,   // Label of each tab, in order
+    pub active: Option<usize>, // Tab of the running activity; None if the running activity is not a tab
+    pub can_open: bool,        // Whether new tabs can be opened
+}
+
+/// ## Tab
+///
+/// A tab and its item; the item is None while it is running
+struct Tab<T> {
+    label: String,
+    item: Option<T>,
+}
+
+/// ## Tabs
+///
+/// Ordered collection of tabs, one of which is the active one.
+/// The item of the active tab is taken out while it runs, and parked back when it stops running
+pub struct Tabs<T> {
+    tabs: Vec<Tab<T>>,
+    active: usize,
+}
+
+impl<T> Tabs<T> {
+    /// ### new
+    ///
+    /// Instantiates a new Tabs, without any tab
+    pub fn new() -> Self {
+        Tabs {
+            tabs: Vec::new(),
+            active: 0,
+        }
+    }
+
+    /// ### open
+    ///
+    /// Open a new tab named `label` after the others and make it the active one; its item is running
+    pub fn open(&mut self, label: String) {
+        self.tabs.push(Tab { label, item: None });
+        self.active = self.tabs.len() - 1;
+    }
+
+    /// ### park
+    ///
+    /// Give back the item of the active tab, once it stops running
+    pub fn park(&mut self, item: T) {
+        if let Some(tab) = self.tabs.get_mut(self.active) {
+            tab.item = Some(item);
+        }
+    }
+
+    /// ### switch
+    ///
+    /// Make active the tab `offset` positions after the active one (before, if negative), wrapping around.
+    /// Returns the item of the new active tab, which is now running; None if there are no tabs
+    /// or the item of the tab is already running
+    pub fn switch(&mut self, offset: isize) -> Option<T> {
+        if self.tabs.is_empty() {
+            return None;
+        }
+        let len: isize = self.tabs.len() as isize;
+        self.active = (self.active as isize + offset).rem_euclid(len) as usize;
+        self.tabs[self.active].item.take()
+    }
+
+    /// ### close
+    ///
+    /// Close the active tab, whose item is running. The previous tab becomes the active one (the next one,
+    /// if it was the first tab): its item is returned, since it is now running. Returns None if no tab is left
+    pub fn close(&mut self) -> Option<T> {
+        if self.active < self.tabs.len() {
+            self.tabs.remove(self.active);
+        }
+        self.active = self.active.saturating_sub(1);
+        self.tabs.get_mut(self.active).and_then(|x| x.item.take())
+    }
+
+    /// ### drain
+    ///
+    /// Close all the tabs; returns the items which are not running
+    pub fn drain(&mut self) -> Vec<T> {
+        self.active = 0;
+        self.tabs.drain(..).filter_map(|x| x.item).collect()
+    }
+
+    /// ### bar
+    ///
+    /// Describe the tabs to the running activity; `running` tells whether the running activity is the active tab
+    pub fn bar(&self, running: bool, can_open: bool) -> TabBar {
+        TabBar {
+            labels: self.tabs.iter().map(|x| x.label.clone()).collect(),
+            active: match running && !self.tabs.is_empty() {
+                true => Some(self.active),
+                false => None,
+            },
+            can_open,
+        }
+    }
+}
+
+impl<T> Default for Tabs<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_ui_tabs() {
+        let mut tabs: Tabs<&str> = Tabs::new();
+        assert!(tabs.tabs.is_empty());
+        assert_eq!(tabs.switch(1), None);
+        assert_eq!(
+            tabs.bar(false, true),
+            TabBar {
+                labels: vec![],
+                active: None,
+                can_open: true,
+            }
+        );
+        // Open tabs; the item of the active tab is running
+        tabs.open(String::from("omar@192.168.1.31"));
+        tabs.park("first");
+        tabs.open(String::from("demo"));
+        assert_eq!(tabs.tabs.len(), 2);
+        assert_eq!(
+            tabs.bar(true, true),
+            TabBar {
+                labels: vec![String::from("omar@192.168.1.31"), String::from("demo")],
+                active: Some(1),
+                can_open: true,
+            }
+        );
+        assert_eq!(tabs.bar(false, false).active, None);
+        // Switch; wraps around
+        tabs.park("second");
+        assert_eq!(tabs.switch(1), Some("first"));
+        tabs.park("first");
+        assert_eq!(tabs.switch(-1), Some("second"));
+        tabs.park("second");
+        assert_eq!(tabs.switch(0), Some("second"));
+        // Already running
+        assert_eq!(tabs.switch(0), None);
+        tabs.park("second");
+        // Close
+        assert_eq!(tabs.switch(-1), Some("first"));
+        tabs.park("first");
+        tabs.open(String::from("third"));
+        tabs.park("third");
+        assert_eq!(tabs.switch(1), Some("first"));
+        assert_eq!(tabs.close(), Some("second"));
+        assert_eq!(
+            tabs.bar(true, true).labels,
+            vec![String::from("demo"), String::from("third")]
+        );
+        assert_eq!(tabs.bar(true, true).active, Some(0));
+        assert_eq!(tabs.drain(), vec!["third"]);
+        assert!(tabs.tabs.is_empty());
+        assert_eq!(tabs.close(), None);
+    }
+}
@@ -0,0 +1,322 @@
+//! ## Tasks
+//!
+//! `tasks` is the module which provides the background task runner.
+//! The task runner is owned by the context, so long jobs keep running while the user switches activity;
+//! each task reports its progress and its result on a channel, which is polled by the current activity.
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::any::Any;
+use std::collections::{BTreeMap, HashMap};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// ## TaskId
+///
+/// Identifies a task spawned on the runner
+pub(crate) type TaskId = usize;
+
+/// ## TaskOutput
+///
+/// The value returned by a task; the task owner knows its actual type
+pub(crate) type TaskOutput = Box<dyn Any + Send>;
+
+/// ## TaskResult
+///
+/// The outcome of a task
+pub(crate) type TaskResult = Result<TaskOutput, String>;
+
+type Job = Box<dyn FnOnce(&TaskReporter) -> TaskResult + Send>;
+type QueuedJob = (TaskReporter, Job);
+
+/// ## TaskMessage
+///
+/// Message sent by tasks to the runner
+enum TaskMessage {
+    Progress(TaskId, Option<f64>, String),
+    Finished(TaskId, TaskResult),
+}
+
+/// ## TaskReporter
+///
+/// TaskReporter is given to each task, to report its progress
+pub(crate) struct TaskReporter {
+    id: TaskId,
+    sender: Sender<TaskMessage>,
+}
+
+impl TaskReporter {
+    /// ### report
+    ///
+    /// Report task progress (in range 0.0 - 1.0, if known) and status
+    pub fn report(&self, progress: Option<f64>, status: &str) {
+        let _ = self
+            .sender
+            .send(TaskMessage::Progress(self.id, progress, status.to_string()));
+    }
+}
+
+/// ## TaskInfo
+///
+/// Describes a running task
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct TaskInfo {
+    pub name: String,
+    pub progress: Option<f64>, // Progress in range 0.0 - 1.0, if known
+    pub status: String,        // Last status reported by the task
+}
+
+/// ## TaskRunner
+///
+/// TaskRunner runs jobs on a pool of worker threads
+pub(crate) struct TaskRunner {
+    jobs: Sender<QueuedJob>,
+    sender: Sender<TaskMessage>,
+    receiver: Receiver<TaskMessage>,
+    next_id: TaskId,
+    running: BTreeMap<TaskId, TaskInfo>,
+    results: HashMap<TaskId, TaskResult>,
+}
+
+impl TaskRunner {
+    /// ### new
+    ///
+    /// Instantiates a new TaskRunner with `workers` threads.
+    /// Workers terminate once the runner is dropped and their current job is finished
+    pub fn new(workers: usize) -> TaskRunner {
+        let (jobs, queue): (Sender<QueuedJob>, Receiver<QueuedJob>) = mpsc::channel();
+        let queue: Arc<Mutex<Receiver<QueuedJob>>> = Arc::new(Mutex::new(queue));
+        for _ in 0..workers.max(1) {
+            let queue = Arc::clone(&queue);
+            thread::spawn(move || loop {
+                // Lock is released as soon as a job is received
+                let next = match queue.lock() {
+                    Ok(queue) => queue.recv(),
+                    Err(_) => break,
+                };
+                match next {
+                    Ok((reporter, job)) => {
+                        let result: TaskResult =
+                            panic::catch_unwind(AssertUnwindSafe(|| job(&reporter)))
+                                .unwrap_or_else(|_| Err(String::from("task panicked")));
+                        let _ = reporter
+                            .sender
+                            .send(TaskMessage::Finished(reporter.id, result));
+                    }
+                    Err(_) => break, // Runner dropped
+                }
+            });
+        }
+        let (sender, receiver): (Sender<TaskMessage>, Receiver<TaskMessage>) = mpsc::channel();
+        TaskRunner {
+            jobs,
+            sender,
+            receiver,
+            next_id: 0,
+            running: BTreeMap::new(),
+            results: HashMap::new(),
+        }
+    }
+
+    /// ### spawn
+    ///
+    /// Queue a new task; the task starts as soon as a worker is available
+    pub fn spawn<F>(&mut self, name: &str, job: F) -> TaskId
+    where
+        F: FnOnce(&TaskReporter) -> TaskResult + Send + 'static,
+    {
+        let id: TaskId = self.next_id;
+        self.next_id += 1;
+        self.running.insert(
+            id,
+            TaskInfo {
+                name: name.to_string(),
+                progress: None,
+                status: String::new(),
+            },
+        );
+        let reporter: TaskReporter = TaskReporter {
+            id,
+            sender: self.sender.clone(),
+        };
+        if self.jobs.send((reporter, Box::new(job))).is_err() {
+            // Workers are gone; report the failure as the task result
+            self.running.remove(&id);
+            self.results
+                .insert(id, Err(String::from("no worker available")));
+        }
+        id
+    }
+
+    /// ### poll
+    ///
+    /// Collect the messages sent by the tasks.
+    /// Returns whether some task has changed its state since the last poll
+    pub fn poll(&mut self) -> bool {
+        let mut changed: bool = false;
+        while let Ok(message) = self.receiver.try_recv() {
+            changed = true;
+            match message {
+                TaskMessage::Progress(id, progress, status) => {
+                    if let Some(task) = self.running.get_mut(&id) {
+                        task.progress = progress;
+                        task.status = status;
+                    }
+                }
+                TaskMessage::Finished(id, result) => {
+                    self.running.remove(&id);
+                    self.results.insert(id, result);
+                }
+            }
+        }
+        changed
+    }
+
+    /// ### take_result
+    ///
+    /// Take the result of a finished task. Returns None if the task is still running (or the result has already been taken)
+    pub fn take_result(&mut self, id: TaskId) -> Option<TaskResult> {
+        self.results.remove(&id)
+    }
+
+    /// ### running
+    ///
+    /// Iterate over the running tasks, in the order they've been spawned
+    pub fn running(&self) -> impl Iterator<Item = &TaskInfo> {
+        self.running.values()
+    }
+
+    /// ### summary
+    ///
+    /// Returns a short description of the running tasks, if any
+    pub fn summary(&self) -> Option<String> {
+        let mut running = self.running();
+        match (running.next(), running.next()) {
+            (None, _) => None,
+            (Some(task), None) => {
+                let progress: String = match task.progress {
+                    Some(progress) => format!(" {:.0}%", progress * 100.0),
+                    None => String::new(),
+                };
+                let status: String = match task.status.is_empty() {
+                    true => String::new(),
+                    false => format!(" ({})", task.status),
+                };
+                Some(format!("{}...{}{}", task.name, progress, status))
+            }
+            (Some(_), Some(_)) => Some(format!(
+                "{} background tasks running...",
+                self.running.len()
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    use std::time::{Duration, Instant};
+
+    /// Poll runner until the result of `id` is available
+    fn wait_result(runner: &mut TaskRunner, id: TaskId) -> TaskResult {
+        let started: Instant = Instant::now();
+        loop {
+            runner.poll();
+            if let Some(result) = runner.take_result(id) {
+                return result;
+            }
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_ui_tasks_runner() {
+        let mut runner: TaskRunner = TaskRunner::new(2);
+        assert!(runner.summary().is_none());
+        let (tx, rx) = mpsc::channel::<()>();
+        let id: TaskId = runner.spawn("Counting", move |reporter| {
+            reporter.report(Some(0.5), "half way");
+            // Wait for test to check progress
+            let _ = rx.recv();
+            Ok(Box::new(64_u64) as TaskOutput)
+        });
+        // Wait for progress
+        let started: Instant = Instant::now();
+        while runner.running().next().unwrap().progress.is_none() {
+            runner.poll();
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(
+            runner.running().next().unwrap(),
+            &TaskInfo {
+                name: String::from("Counting"),
+                progress: Some(0.5),
+                status: String::from("half way"),
+            }
+        );
+        assert_eq!(
+            runner.summary().unwrap().as_str(),
+            "Counting... 50% (half way)"
+        );
+        assert!(runner.take_result(id).is_none());
+        // Let task terminate
+        tx.send(()).unwrap();
+        let output: TaskOutput = wait_result(&mut runner, id).ok().unwrap();
+        assert_eq!(*output.downcast::<u64>().ok().unwrap(), 64);
+        assert!(runner.running().next().is_none());
+        // Result can be taken only once
+        assert!(runner.take_result(id).is_none());
+    }
+
+    #[test]
+    fn test_ui_tasks_runner_errors() {
+        let mut runner: TaskRunner = TaskRunner::new(1);
+        let failed: TaskId = runner.spawn("Failing", |_| Err(String::from("oops")));
+        #[allow(unreachable_code)]
+        let panicked: TaskId = runner.spawn("Panicking", |_| {
+            panic!("boom");
+            Ok(Box::new(()) as TaskOutput)
+        });
+        assert_eq!(
+            runner.summary().unwrap().as_str(),
+            "2 background tasks running..."
+        );
+        assert_eq!(
+            wait_result(&mut runner, failed).err().unwrap().as_str(),
+            "oops"
+        );
+        assert_eq!(
+            wait_result(&mut runner, panicked).err().unwrap().as_str(),
+            "task panicked"
+        );
+        assert!(runner.summary().is_none());
+    }
+}
@@ -27,10 +27,12 @@
  */
 extern crate chrono;
 extern crate textwrap;
+extern crate unicode_width;
 
 use chrono::prelude::*;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, SystemTime};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 /// ### fmt_pex
 ///
@@ -93,6 +95,14 @@ pub fn fmt_time(time: SystemTime, fmt: &str) -> String {
     format!("{}", datetime.format(fmt))
 }
 
+/// ### fmt_utc_time
+///
+/// Format a `SystemTime` into a time string, in UTC
+pub fn fmt_utc_time(time: SystemTime, fmt: &str) -> String {
+    let datetime: DateTime<Utc> = time.into();
+    format!("{}", datetime.format(fmt))
+}
+
 /// ### fmt_millis
 ///
 /// Format duration as {secs}.{millis}
@@ -106,9 +116,9 @@ pub fn fmt_millis(duration: Duration) -> String {
 ///
 /// Align text to center for a given width
 pub fn align_text_center(text: &str, width: u16) -> String {
-    let indent_size: usize = match (width as usize) >= text.len() {
+    let indent_size: usize = match (width as usize) >= text.width() {
         // NOTE: The check prevents underflow
-        true => (width as usize - text.len()) / 2,
+        true => (width as usize - text.width()) / 2,
         false => 0,
     };
     textwrap::indent(
@@ -125,7 +135,7 @@ pub fn align_text_center(text: &str, width: u16) -> String {
 /// In this case, the path is formatted to {ANCESTOR[0]}/.../{PARENT[0]}/{BASENAME}
 pub fn fmt_path_elide(p: &Path, width: usize) -> String {
     let fmt_path: String = format!("{}", p.display());
-    match fmt_path.len() > width as usize {
+    match fmt_path.width() > width {
         false => fmt_path,
         true => {
             // Elide
@@ -152,6 +162,91 @@ pub fn fmt_path_elide(p: &Path, width: usize) -> String {
     }
 }
 
+/// ### fmt_elide_width
+///
+/// Elide text if it takes more than `width` columns on the terminal.
+/// In this case, the text is truncated and `...` is appended, so that the result fits in `width`.
+/// Wide characters (e.g. CJK and emoji) count as two columns
+pub fn fmt_elide_width(text: &str, width: usize) -> String {
+    if text.width() <= width {
+        return text.to_string();
+    }
+    let max_width: usize = width.saturating_sub(3);
+    let mut elided: String = String::with_capacity(width);
+    let mut cur_width: usize = 0;
+    for ch in text.chars() {
+        let ch_width: usize = ch.width().unwrap_or(0);
+        if cur_width + ch_width > max_width {
+            break;
+        }
+        cur_width += ch_width;
+        elided.push(ch);
+    }
+    elided.push_str("...");
+    elided
+}
+
+/// ### fmt_pad_width
+///
+/// Pad text with spaces until it takes `width` columns on the terminal
+pub fn fmt_pad_width(text: &str, width: usize) -> String {
+    let text_width: usize = text.width();
+    match text_width >= width {
+        true => text.to_string(),
+        false => format!("{}{}", text, " ".repeat(width - text_width)),
+    }
+}
+
+/// ### fmt_sanitize
+///
+/// Replace control characters and bidirectional formatting characters with `?`.
+/// This prevents a file name from breaking the layout or from reversing the text which follows it
+/// when rendered (e.g. with a right-to-left override)
+pub fn fmt_sanitize(text: &str) -> String {
+    text.chars()
+        .map(|ch| match ch {
+            '\u{061c}'
+            | '\u{200e}'
+            | '\u{200f}'
+            | '\u{202a}'..='\u{202e}'
+            | '\u{2066}'..='\u{2069}' => '?',
+            ch if ch.is_control() => '?',
+            ch => ch,
+        })
+        .collect()
+}
+
+/// ### fmt_host
+///
+/// Enclose IPv6 addresses in brackets, so that they can be followed by `:port`
+pub fn fmt_host(address: &str) -> String {
+    match address.contains(':') && !address.starts_with('[') {
+        true => format!("[{}]", address),
+        false => address.to_string(),
+    }
+}
+
+/// ### fmt_log_pattern
+///
+/// Mask the quoted parts of a log message (e.g. file names) with `"*"`.
+/// Messages with the same pattern only differ by these parts, so they can be collapsed
+pub fn fmt_log_pattern(msg: &str) -> String {
+    let mut pattern: String = String::with_capacity(msg.len());
+    let mut quoted: bool = false;
+    for ch in msg.chars() {
+        match (ch, quoted) {
+            ('"', false) => {
+                pattern.push_str("\"*\"");
+                quoted = true;
+            }
+            ('"', true) => quoted = false,
+            (_, true) => {}
+            (ch, false) => pattern.push(ch),
+        }
+    }
+    pattern
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -169,6 +264,14 @@ mod tests {
         assert_eq!(fmt_pex(1, 2, 1), String::from("--x-w---x"));
     }
 
+    #[test]
+    fn test_utils_fmt_host() {
+        assert_eq!(fmt_host("172.26.104.1"), String::from("172.26.104.1"));
+        assert_eq!(fmt_host("example.com"), String::from("example.com"));
+        assert_eq!(fmt_host("::1"), String::from("[::1]"));
+        assert_eq!(fmt_host("[fe80::1]"), String::from("[fe80::1]"));
+    }
+
     #[test]
     fn test_utils_fmt_time() {
         let system_time: SystemTime = SystemTime::from(SystemTime::UNIX_EPOCH);
@@ -178,6 +281,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_utils_fmt_utc_time() {
+        let system_time: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(1612164210);
+        assert_eq!(
+            fmt_utc_time(system_time, "%Y%m%d%H%M%S"),
+            String::from("20210201072330")
+        );
+    }
+
     #[test]
     fn test_utils_align_text_center() {
         assert_eq!(
@@ -217,4 +329,78 @@ mod tests {
         let p: &Path = &Path::new("/develop/pippo/foo/bar");
         assert_eq!(fmt_path_elide(p, 16), String::from("/develop/.../foo/bar"));
     }
+
+    #[test]
+    fn test_utils_fmt_elide_width() {
+        assert_eq!(fmt_elide_width("omar.txt", 16), String::from("omar.txt"));
+        assert_eq!(fmt_elide_width("omar.txt", 8), String::from("omar.txt"));
+        assert_eq!(
+            fmt_elide_width("piroparoporo.txt", 8),
+            String::from("pirop...")
+        );
+        // Wide chars take two columns
+        assert_eq!(
+            fmt_elide_width("日本語のファイル.txt", 10),
+            String::from("日本語...")
+        );
+        assert_eq!(
+            fmt_elide_width("日本語のファイル.txt", 11),
+            String::from("日本語の...")
+        );
+        assert_eq!(fmt_elide_width("🦀🦀🦀🦀", 6), String::from("🦀..."));
+        // Combining characters take no columns
+        assert_eq!(
+            fmt_elide_width("cafe\u{301}.txt", 9),
+            String::from("cafe\u{301}.txt")
+        );
+        assert_eq!(fmt_elide_width("omar", 2), String::from("..."));
+    }
+
+    #[test]
+    fn test_utils_fmt_pad_width() {
+        assert_eq!(fmt_pad_width("omar", 6), String::from("omar  "));
+        assert_eq!(fmt_pad_width("日本", 6), String::from("日本  "));
+        assert_eq!(
+            fmt_pad_width("cafe\u{301}", 6),
+            String::from("cafe\u{301}  ")
+        );
+        assert_eq!(
+            fmt_pad_width("piroparoporo", 6),
+            String::from("piroparoporo")
+        );
+    }
+
+    #[test]
+    fn test_utils_fmt_sanitize() {
+        assert_eq!(fmt_sanitize("omar.txt"), String::from("omar.txt"));
+        assert_eq!(fmt_sanitize("日本語.txt"), String::from("日本語.txt"));
+        assert_eq!(fmt_sanitize("a\tb\nc"), String::from("a?b?c"));
+        assert_eq!(
+            fmt_sanitize("invoice\u{202e}fdp.exe"),
+            String::from("invoice?fdp.exe")
+        );
+        assert_eq!(fmt_sanitize("\u{2067}שלום\u{2069}"), String::from("?שלום?"));
+    }
+
+    #[test]
+    fn test_utils_fmt_log_pattern() {
+        assert_eq!(
+            fmt_log_pattern("Connected to 127.0.0.1"),
+            String::from("Connected to 127.0.0.1")
+        );
+        assert_eq!(
+            fmt_log_pattern("Could not open file \"/tmp/a.txt\": Permission denied"),
+            String::from("Could not open file \"*\": Permission denied")
+        );
+        assert_eq!(
+            fmt_log_pattern("Could not open file \"/tmp/b.txt\": Permission denied"),
+            fmt_log_pattern("Could not open file \"/home/omar/c\": Permission denied")
+        );
+        assert_eq!(
+            fmt_log_pattern("Moved \"a\" to \"b\""),
+            String::from("Moved \"*\" to \"*\"")
+        );
+        // Unterminated quote
+        assert_eq!(fmt_log_pattern("Bad \"path"), String::from("Bad \"*\""));
+    }
 }
@@ -0,0 +1,529 @@
+//! ## Ls
+//!
+//! `ls` is the module which provides the parsers for the directory listings returned by servers,
+//! both in the UNIX `ls -l` syntax and in the DOS syntax
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Dependencies
+extern crate regex;
+
+// Locals
+use super::parser::{parse_datetime, parse_iso_datetime, parse_lstime};
+use crate::fs::{FsDirectory, FsEntry, FsFile};
+
+// Ext
+use regex::Regex;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+// Regex
+lazy_static! {
+    /**
+     * UNIX `ls -l` syntax:
+     * {FILE_TYPE}{UNIX_PEX}[ACL] {HARD_LINKS} {USER} {GROUP} {SIZE} {DATE} {FILENAME}
+     * -rw-r--r--   1 cvisintin  staff   4968 27 Dic 10:46 CHANGELOG.md
+     * -rw-r--r--   1 cvisintin  staff   4968 2020-12-27 10:46:05.000000000 +0100 CHANGELOG.md (GNU `--time-style=full-iso`)
+     * Regex matches:
+     *  - group 1: File type
+     *  - group 2: Permissions
+     *  - group 3: Hard links
+     *  - group 4: User
+     *  - group 5: Group
+     *  - group 6: Size (or major, minor for devices)
+     *  - group 7: Modification time (`ls` default or full ISO)
+     *  - group 8: File name (and symlink target)
+     * NOTE: about this damn regex <https://stackoverflow.com/questions/32480890/is-there-a-regex-to-parse-the-values-from-an-ftp-directory-listing>
+     */
+    static ref LS_RE: Regex = Regex::new(r#"^(\S)([\-rwxsStT]{9})[+@.]?\s+(\d+)\s+(\S+)\s+(\S+)\s+(\d+|\d+,\s*\d+)\s+(\w{3}\s+\d{1,2}\s+(?:\d{1,2}:\d{1,2}|\d{4})|\d{4}-\d{2}-\d{2}\s+\d{2}:\d{2}:\d{2}(?:\.\d+)?\s+[+\-]\d{4})\s+(.+)$"#).unwrap();
+    /**
+     * DOS syntax:
+     * {DATE} {TIME} {<DIR> | SIZE} {FILENAME}
+     * 10-19-20  03:19PM <DIR> pub
+     * 04-08-14  03:09PM 403   readme.txt
+     * Regex matches:
+     *  - group 1: Modification time
+     *  - group 2: Some(<DIR>) | None
+     *  - group 3: Size
+     *  - group 4: File name
+     * NOTE: you won't find this regex on the internet. It seems I'm the only person in the world who needs this
+     */
+    static ref DOS_RE: Regex = Regex::new(
+        r#"^(\d{2}\-\d{2}\-\d{2}\s+\d{2}:\d{2}\s*[AP]M)\s+(<DIR>)?([\d,]*)\s+(.+)$"#
+    )
+    .unwrap();
+}
+
+/// ## LsEntry
+///
+/// LsEntry describes an entry parsed from a line of a directory listing
+#[derive(Clone, std::fmt::Debug, PartialEq)]
+pub struct LsEntry {
+    pub name: String,
+    pub is_dir: bool,
+    pub symlink: Option<PathBuf>, // Symlink target, if entry is a symlink
+    pub size: usize,
+    pub mtime: SystemTime, // UNIX_EPOCH if time couldn't be parsed
+    pub user: Option<u32>,
+    pub group: Option<u32>,
+    pub unix_pex: Option<(u8, u8, u8)>,
+}
+
+/// ## LsParseError
+///
+/// Describes why a line of a directory listing couldn't be parsed into an entry
+#[derive(Clone, std::fmt::Debug, PartialEq)]
+pub enum LsParseError {
+    EmptyLine,
+    Header,   // e.g. "total 48"
+    DotEntry, // "." and ".."
+    UnknownFileType(char),
+    BadSyntax(String),
+}
+
+impl LsParseError {
+    /// ### is_ignorable
+    ///
+    /// Returns whether the line wasn't meant to describe an entry; otherwise the entry has been lost
+    pub fn is_ignorable(&self) -> bool {
+        matches!(
+            self,
+            LsParseError::EmptyLine | LsParseError::Header | LsParseError::DotEntry
+        )
+    }
+}
+
+impl fmt::Display for LsParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LsParseError::EmptyLine => write!(f, "Empty line"),
+            LsParseError::Header => write!(f, "Listing header"),
+            LsParseError::DotEntry => write!(f, "Current or parent directory entry"),
+            LsParseError::UnknownFileType(t) => write!(f, "Unknown file type '{}'", t),
+            LsParseError::BadSyntax(line) => write!(f, "Unrecognized listing syntax: \"{}\"", line),
+        }
+    }
+}
+
+impl LsEntry {
+    /// ### into_fsentry
+    ///
+    /// Convert `LsEntry` into a `FsEntry` located in `parent`
+    pub fn into_fsentry(self, parent: &Path, symlink: Option<Box<FsEntry>>) -> FsEntry {
+        let mut abs_path: PathBuf = PathBuf::from(parent);
+        abs_path.push(self.name.as_str());
+        match self.is_dir {
+            true => FsEntry::Directory(FsDirectory {
+                name: self.name,
+                abs_path,
+                last_change_time: self.mtime,
+                last_access_time: self.mtime,
+                creation_time: self.mtime,
+                readonly: false,
+                symlink,
+                user: self.user,
+                group: self.group,
+                unix_pex: self.unix_pex,
+            }),
+            false => FsEntry::File(FsFile {
+                name: self.name,
+                ftype: abs_path
+                    .as_path()
+                    .extension()
+                    .map(|s| String::from(s.to_string_lossy())),
+                abs_path,
+                last_change_time: self.mtime,
+                last_access_time: self.mtime,
+                creation_time: self.mtime,
+                size: self.size,
+                readonly: false,
+                symlink,
+                broken_link: None,
+                user: self.user,
+                group: self.group,
+                unix_pex: self.unix_pex,
+            }),
+        }
+    }
+}
+
+/// ### parse_ls_line
+///
+/// Parse a line of a directory listing, trying the UNIX syntax first and then the DOS syntax
+pub fn parse_ls_line(line: &str) -> Result<LsEntry, LsParseError> {
+    match parse_unix_ls_line(line) {
+        Err(LsParseError::BadSyntax(err)) => match parse_dos_ls_line(line) {
+            Err(LsParseError::BadSyntax(_)) => Err(LsParseError::BadSyntax(err)),
+            result => result,
+        },
+        result => result,
+    }
+}
+
+/// ### parse_unix_ls_line
+///
+/// Parse a line of `ls -l` output.
+/// Special files (devices, pipes and sockets) are reported as files
+pub fn parse_unix_ls_line(line: &str) -> Result<LsEntry, LsParseError> {
+    let line: &str = check_line(line)?;
+    let metadata = match LS_RE.captures(line) {
+        Some(metadata) => metadata,
+        None => return Err(LsParseError::BadSyntax(line.to_string())),
+    };
+    // Get file type
+    let file_type: char = metadata
+        .get(1)
+        .and_then(|x| x.as_str().chars().next())
+        .unwrap_or(' ');
+    let (is_dir, is_symlink): (bool, bool) = match file_type {
+        '-' | 'b' | 'c' | 'p' | 's' => (false, false),
+        'l' => (false, true),
+        'd' => (true, false),
+        t => return Err(LsParseError::UnknownFileType(t)),
+    };
+    // Get unix pex
+    let pex: Vec<char> = metadata.get(2).unwrap().as_str().chars().collect();
+    let pex = |i: usize| -> u8 {
+        let read: u8 = if pex[i] == 'r' { 4 } else { 0 };
+        let write: u8 = if pex[i + 1] == 'w' { 2 } else { 0 };
+        let exec: u8 = if matches!(pex[i + 2], 'x' | 's' | 't') {
+            1
+        } else {
+            0
+        };
+        read + write + exec
+    };
+    let unix_pex = (pex(0), pex(3), pex(6));
+    // Get link and name
+    let (name, symlink): (String, Option<PathBuf>) = match is_symlink {
+        true => get_name_and_link(metadata.get(8).unwrap().as_str()),
+        false => (String::from(metadata.get(8).unwrap().as_str()), None),
+    };
+    if name.as_str() == "." || name.as_str() == ".." {
+        return Err(LsParseError::DotEntry);
+    }
+    Ok(LsEntry {
+        name,
+        is_dir,
+        symlink,
+        size: metadata
+            .get(6)
+            .unwrap()
+            .as_str()
+            .parse::<usize>()
+            .unwrap_or(0),
+        mtime: parse_unix_lstime(metadata.get(7).unwrap().as_str()),
+        user: metadata.get(4).unwrap().as_str().parse::<u32>().ok(),
+        group: metadata.get(5).unwrap().as_str().parse::<u32>().ok(),
+        unix_pex: Some(unix_pex),
+    })
+}
+
+/// ### parse_unix_lstime
+///
+/// Parse the modification time of a `ls -l` line, either in full ISO or in the default format.
+/// Returns UNIX_EPOCH if the time couldn't be parsed
+fn parse_unix_lstime(tm: &str) -> SystemTime {
+    match tm.chars().next().map(|x| x.is_ascii_digit()) {
+        Some(true) => parse_iso_datetime(tm),
+        _ => parse_lstime(tm, "%b %d %Y", "%b %d %H:%M"),
+    }
+    .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// ### parse_dos_ls_line
+///
+/// Parse a line of a directory listing in DOS syntax
+pub fn parse_dos_ls_line(line: &str) -> Result<LsEntry, LsParseError> {
+    let line: &str = check_line(line)?;
+    let metadata = match DOS_RE.captures(line) {
+        Some(metadata) => metadata,
+        None => return Err(LsParseError::BadSyntax(line.to_string())),
+    };
+    let name: String = String::from(metadata.get(4).unwrap().as_str());
+    if name.as_str() == "." || name.as_str() == ".." {
+        return Err(LsParseError::DotEntry);
+    }
+    // Get if is a directory
+    let is_dir: bool = metadata.get(2).is_some();
+    Ok(LsEntry {
+        name,
+        is_dir,
+        symlink: None,
+        size: match is_dir {
+            true => 0, // If is directory, filesize is 0
+            false => metadata
+                .get(3)
+                .map(|x| x.as_str().replace(',', ""))
+                .and_then(|x| x.parse::<usize>().ok())
+                .unwrap_or(0),
+        },
+        mtime: parse_datetime(metadata.get(1).unwrap().as_str(), "%d-%m-%y %I:%M%p")
+            .unwrap_or(SystemTime::UNIX_EPOCH),
+        user: None,
+        group: None,
+        unix_pex: None,
+    })
+}
+
+/// ### check_line
+///
+/// Trim line and check whether it is meant to describe an entry
+fn check_line(line: &str) -> Result<&str, LsParseError> {
+    let line: &str = line.trim_end_matches(['\r', '\n']);
+    if line.trim().is_empty() {
+        Err(LsParseError::EmptyLine)
+    } else if line.starts_with("total ") {
+        Err(LsParseError::Header)
+    } else {
+        Ok(line)
+    }
+}
+
+/// ### get_name_and_link
+///
+/// Returns from a `ls -l` command output file name token, the name of the file and the symbolic link (if there is any)
+fn get_name_and_link(token: &str) -> (String, Option<PathBuf>) {
+    let tokens: Vec<&str> = token.splitn(2, " -> ").collect();
+    let filename: String = String::from(*tokens.first().unwrap());
+    let symlink: Option<PathBuf> = tokens.get(1).map(PathBuf::from);
+    (filename, symlink)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::utils::fmt::fmt_pex;
+
+    use proptest::prelude::*;
+    use std::time::Duration;
+
+    /// Listings returned by real servers; each line must produce an entry
+    const CORPUS: [&str; 16] = [
+        // GNU ls
+        "-rw-r--r-- 1 omar omar 4968 Dec 27 10:46 CHANGELOG.md",
+        "drwxrwxrwt 18 root root 4096 Mar 28 09:01 tmp",
+        "lrwxrwxrwx 1 root root 7 Jan 20 2021 bin -> usr/bin",
+        "-rwsr-xr-x 1 root root 68208 Jul 14 2020 passwd",
+        "crw-rw-rw- 1 root root 1, 3 Mar 28 09:00 null",
+        "drwxr-xr-x+ 2 www-data www-data 4096 Feb 1 2019 html",
+        "-rw-r--r--. 1 john.doe users 0 Nov 5 16:32 file with spaces.txt",
+        // BusyBox
+        "-rw-r--r--    1 0        0             21 Mar 28 09:01 hostname",
+        // vsftpd / ProFTPD
+        "-rw-r--r--    1 ftp      ftp      104857600 Feb 19  2016 100MB.zip",
+        "drwxr-xr-x    2 0        0            4096 Apr 16 12:05 upload",
+        "prw-r--r-- 1 omar omar 0 Oct 2 11:08 fifo",
+        // GNU ls with `--time-style=full-iso`
+        "-rw-r--r-- 1 root root 403 2014-08-04 15:09:00.000000000 +0000 readme.txt",
+        "lrwxrwxrwx 1 root root 7 2021-01-20 10:12:33.618924012 +0100 bin -> usr/bin",
+        // IIS (DOS)
+        "04-08-14  03:09PM                  403 readme.txt",
+        "10-19-20  03:19PM       <DIR>          pub",
+        "04-08-14  03:09PM            1,048,576 big file.bin",
+    ];
+
+    #[test]
+    fn test_utils_ls_corpus() {
+        for line in CORPUS.iter() {
+            assert!(parse_ls_line(line).is_ok(), "Dropped line: {}", line);
+        }
+    }
+
+    #[test]
+    fn test_utils_ls_parse_unix() {
+        let entry: LsEntry = parse_ls_line("-rwxr-xr-x 1 0  9  4096 Nov 5 2018 omar.txt")
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name.as_str(), "omar.txt");
+        assert!(!entry.is_dir);
+        assert!(entry.symlink.is_none());
+        assert_eq!(entry.size, 4096);
+        assert_eq!(entry.user, Some(0));
+        assert_eq!(entry.group, Some(9));
+        assert_eq!(entry.unix_pex, Some((7, 5, 5)));
+        assert_eq!(
+            entry.mtime.duration_since(SystemTime::UNIX_EPOCH).unwrap(),
+            Duration::from_secs(1541376000)
+        );
+        // Sticky bit, setuid and ACL
+        let entry: LsEntry = parse_ls_line("drwxrwxrwt+ 18 root root 4096 Mar 28 09:01 tmp")
+            .ok()
+            .unwrap();
+        assert!(entry.is_dir);
+        assert_eq!(entry.user, None);
+        assert_eq!(entry.unix_pex, Some((7, 7, 7)));
+        let entry: LsEntry = parse_ls_line("-rwSr-xr-T 1 root root 0 Mar 28 09:01 x")
+            .ok()
+            .unwrap();
+        assert_eq!(entry.unix_pex, Some((6, 5, 4)));
+        // Symlink
+        let entry: LsEntry = parse_ls_line("lrwxrwxrwx 1 root root 7 Jan 20 2021 bin -> usr/bin")
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name.as_str(), "bin");
+        assert_eq!(entry.symlink, Some(PathBuf::from("usr/bin")));
+        // Full ISO time; timezone is applied
+        let entry: LsEntry = parse_ls_line(
+            "-rw-r--r-- 1 root root 403 2014-08-04 17:09:00.123456789 +0200 my file.txt",
+        )
+        .ok()
+        .unwrap();
+        assert_eq!(entry.name.as_str(), "my file.txt");
+        assert_eq!(entry.size, 403);
+        assert_eq!(
+            entry.mtime.duration_since(SystemTime::UNIX_EPOCH).unwrap(),
+            Duration::from_secs(1407164940)
+        );
+    }
+
+    #[test]
+    fn test_utils_ls_parse_dos() {
+        let entry: LsEntry = parse_ls_line("04-08-14  03:09PM  1,024 omar.txt")
+            .ok()
+            .unwrap();
+        assert_eq!(entry.name.as_str(), "omar.txt");
+        assert!(!entry.is_dir);
+        assert_eq!(entry.size, 1024);
+        assert_eq!(entry.unix_pex, None);
+        assert_eq!(
+            entry.mtime.duration_since(SystemTime::UNIX_EPOCH).unwrap(),
+            Duration::from_secs(1407164940)
+        );
+        let entry: LsEntry = parse_ls_line("04-08-14  03:09PM  <DIR> docs").ok().unwrap();
+        assert!(entry.is_dir);
+        assert_eq!(entry.size, 0);
+    }
+
+    #[test]
+    fn test_utils_ls_errors() {
+        assert_eq!(parse_ls_line("").err().unwrap(), LsParseError::EmptyLine);
+        assert_eq!(
+            parse_ls_line("  \r\n").err().unwrap(),
+            LsParseError::EmptyLine
+        );
+        assert_eq!(
+            parse_ls_line("total 48").err().unwrap(),
+            LsParseError::Header
+        );
+        assert_eq!(
+            parse_ls_line("drwxr-xr-x 2 root root 4096 Mar 28 09:01 ..")
+                .err()
+                .unwrap(),
+            LsParseError::DotEntry
+        );
+        assert_eq!(
+            parse_ls_line("10-19-20  03:19PM  <DIR> .").err().unwrap(),
+            LsParseError::DotEntry
+        );
+        assert_eq!(
+            parse_ls_line("Drwxr-xr-x 2 root root 4096 Mar 28 09:01 door")
+                .err()
+                .unwrap(),
+            LsParseError::UnknownFileType('D')
+        );
+        assert_eq!(
+            parse_ls_line("drwxrwxr-x 1 0  9  Nov 5 2018 docs")
+                .err()
+                .unwrap(),
+            LsParseError::BadSyntax(String::from("drwxrwxr-x 1 0  9  Nov 5 2018 docs"))
+        );
+        assert!(LsParseError::Header.is_ignorable());
+        assert!(!LsParseError::UnknownFileType('D').is_ignorable());
+        assert_eq!(
+            LsParseError::BadSyntax(String::from("omar")).to_string(),
+            String::from("Unrecognized listing syntax: \"omar\"")
+        );
+    }
+
+    #[test]
+    fn test_utils_ls_into_fsentry() {
+        let entry: FsEntry = parse_ls_line("-rw-r--r-- 1 0 0 8192 Nov 5 2018 omar.txt")
+            .ok()
+            .unwrap()
+            .into_fsentry(Path::new("/tmp"), None);
+        assert_eq!(entry.get_abs_path(), PathBuf::from("/tmp/omar.txt"));
+        assert_eq!(entry.get_ftype(), Some(String::from("txt")));
+        assert_eq!(entry.get_size(), 8192);
+        let entry: FsEntry = parse_ls_line("drwxr-xr-x 1 0 0 4096 Nov 5 2018 docs")
+            .ok()
+            .unwrap()
+            .into_fsentry(Path::new("/tmp"), None);
+        assert!(entry.is_dir());
+        assert_eq!(entry.get_abs_path(), PathBuf::from("/tmp/docs"));
+    }
+
+    proptest! {
+        #[test]
+        fn test_utils_ls_never_panics(line in "\\PC*") {
+            let _ = parse_ls_line(line.as_str());
+        }
+
+        #[test]
+        fn test_utils_ls_unix_roundtrip(
+            ftype in "[-dl]",
+            user in 0u8..8,
+            group in 0u8..8,
+            others in 0u8..8,
+            uid in 0u32..65536,
+            size in 0usize..(u32::MAX as usize),
+            name in "[a-zA-Z0-9_][a-zA-Z0-9_. -]{0,32}[a-zA-Z0-9_]",
+        ) {
+            prop_assume!(name.as_str() != "." && name.as_str() != "..");
+            let line: String = format!(
+                "{}{} 1 {} users {} Nov 5 2018 {}",
+                ftype,
+                fmt_pex(user, group, others),
+                uid,
+                size,
+                name
+            );
+            let entry: LsEntry = parse_ls_line(line.as_str()).unwrap();
+            prop_assert_eq!(entry.name, name);
+            prop_assert_eq!(entry.is_dir, ftype.as_str() == "d");
+            prop_assert_eq!(entry.size, size);
+            prop_assert_eq!(entry.user, Some(uid));
+            prop_assert_eq!(entry.group, None);
+            prop_assert_eq!(entry.unix_pex, Some((user, group, others)));
+        }
+
+        #[test]
+        fn test_utils_ls_dos_roundtrip(
+            size in proptest::option::of(0usize..(u32::MAX as usize)),
+            name in "[a-zA-Z0-9_][a-zA-Z0-9_. ]{0,32}[a-zA-Z0-9_]",
+        ) {
+            let line: String = format!(
+                "04-08-14  03:09PM  {} {}",
+                size.map(|x| x.to_string()).unwrap_or_else(|| String::from("<DIR>")),
+                name
+            );
+            let entry: LsEntry = parse_ls_line(line.as_str()).unwrap();
+            prop_assert_eq!(entry.name, name);
+            prop_assert_eq!(entry.is_dir, size.is_none());
+            prop_assert_eq!(entry.size, size.unwrap_or(0));
+        }
+    }
+}
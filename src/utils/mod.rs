@@ -29,5 +29,10 @@
 pub mod crypto;
 pub mod fmt;
 pub mod git;
+pub mod ls;
 pub mod parser;
+pub mod path;
 pub mod random;
+pub mod secret;
+pub mod shell;
+pub mod template;
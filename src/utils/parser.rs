@@ -51,11 +51,11 @@ lazy_static! {
      * Regex matches:
      *  - group 1: Some(protocol) | None
      *  - group 2: Some(user) | None
-     *  - group 3: Address
+     *  - group 3: Address (IPv6 addresses are enclosed in brackets)
      *  - group 4: Some(port) | None
      *  - group 5: Some(path) | None
      */
-    static ref REMOTE_OPT_REGEX: Regex = Regex::new(r"(?:([a-z]+)://)?(?:([^@]+)@)?(?:([^:]+))(?::((?:[0-9]{1,4}|[1-5][0-9]{4}|6[0-4][0-9]{3}|65[0-4][0-9]{2}|655[0-2][0-9]|6553[0-5])(?:[0-9]{1,4}|[1-5][0-9]{4}|6[0-4][0-9]{3}|65[0-4][0-9]{2}|655[0-2][0-9]|6553[0-5])))?(?::([^:]+))?").ok().unwrap();
+    static ref REMOTE_OPT_REGEX: Regex = Regex::new(r"^(?:([a-zA-Z][a-zA-Z0-9]*)://)?(?:([^@]+)@)?(\[[0-9a-fA-F:.]+\]|[^:@/\[\]\s]+)(?::([0-9]+))?(?::(.+))?$").ok().unwrap();
     /**
      * Regex matches:
      * - group 1: Version
@@ -65,6 +65,32 @@ lazy_static! {
     static ref SEMVER_REGEX: Regex = Regex::new(r".*(:?[0-9]\.[0-9]\.[0-9])").unwrap();
 }
 
+/// ## RemoteOptError
+///
+/// Describes why the remote option string couldn't be parsed
+#[derive(Clone, std::fmt::Debug, PartialEq)]
+pub enum RemoteOptError {
+    BadSyntax,
+    UnknownProtocol(String),
+    BadPort(String),
+}
+
+impl std::fmt::Display for RemoteOptError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            RemoteOptError::BadSyntax => write!(f, "Bad remote host syntax!"),
+            RemoteOptError::UnknownProtocol(protocol) => {
+                write!(f, "Unknown protocol \"{}\"", protocol)
+            }
+            RemoteOptError::BadPort(port) => write!(
+                f,
+                "Bad port \"{}\": must be a number between 1 and 65535",
+                port
+            ),
+        }
+    }
+}
+
 pub struct RemoteOptions {
     pub hostname: String,
     pub port: u16,
@@ -77,7 +103,7 @@ pub struct RemoteOptions {
 ///
 /// Parse remote option string. Returns in case of success a RemoteOptions struct
 /// For ssh if username is not provided, current user will be used.
-/// In case of error, message is returned
+/// In case of error, the reason is returned as `RemoteOptError`
 /// If port is missing default port will be used for each protocol
 ///     SFTP => 22
 ///     FTP => 21
@@ -92,7 +118,7 @@ pub struct RemoteOptions {
 /// - sftp://172.26.104.1
 /// - ...
 ///
-pub fn parse_remote_opt(remote: &str) -> Result<RemoteOptions, String> {
+pub fn parse_remote_opt(remote: &str) -> Result<RemoteOptions, RemoteOptError> {
     // Set protocol to default protocol
     #[cfg(not(test))] // NOTE: don't use configuration during tests
     let mut protocol: FileTransferProtocol = match environment::init_config_dir() {
@@ -113,18 +139,20 @@ pub fn parse_remote_opt(remote: &str) -> Result<RemoteOptions, String> {
     let mut protocol: FileTransferProtocol = FileTransferProtocol::Sftp;
     // Match against regex
     match REMOTE_OPT_REGEX.captures(remote) {
+        // A scheme separator which hasn't been matched as protocol means the protocol is malformed
+        Some(groups) if groups.get(1).is_none() && remote.contains("://") => {
+            Err(RemoteOptError::BadSyntax)
+        }
         Some(groups) => {
             // Match protocol
             let mut port: u16 = 22;
             if let Some(group) = groups.get(1) {
                 // Set protocol from group
                 let (m_protocol, m_port) = match FileTransferProtocol::from_str(group.as_str()) {
-                    Ok(proto) => match proto {
-                        FileTransferProtocol::Ftp(_) => (proto, 21),
-                        FileTransferProtocol::Scp => (proto, 22),
-                        FileTransferProtocol::Sftp => (proto, 22),
-                    },
-                    Err(_) => return Err(format!("Unknown protocol \"{}\"", group.as_str())),
+                    Ok(proto) => (proto, proto.default_port()),
+                    Err(_) => {
+                        return Err(RemoteOptError::UnknownProtocol(group.as_str().to_string()))
+                    }
                 };
                 // NOTE: tuple destructuring assignment is not supported yet :(
                 protocol = m_protocol;
@@ -141,16 +169,20 @@ pub fn parse_remote_opt(remote: &str) -> Result<RemoteOptions, String> {
                     _ => None,
                 },
             };
-            // Get address
+            // Get address (strip brackets from IPv6 addresses)
             let hostname: String = match groups.get(3) {
-                Some(group) => group.as_str().to_string(),
-                None => return Err(String::from("Missing address")),
+                Some(group) => group
+                    .as_str()
+                    .trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .to_string(),
+                None => return Err(RemoteOptError::BadSyntax),
             };
             // Get port
             if let Some(group) = groups.get(4) {
                 port = match group.as_str().parse::<u16>() {
-                    Ok(p) => p,
-                    Err(err) => return Err(format!("Bad port \"{}\": {}", group.as_str(), err)),
+                    Ok(p) if p > 0 => p,
+                    _ => return Err(RemoteOptError::BadPort(group.as_str().to_string())),
                 };
             }
             // Get workdir
@@ -163,7 +195,7 @@ pub fn parse_remote_opt(remote: &str) -> Result<RemoteOptions, String> {
                 wrkdir,
             })
         }
-        None => Err(String::from("Bad remote host syntax!")),
+        None => Err(RemoteOptError::BadSyntax),
     }
 }
 
@@ -217,6 +249,19 @@ pub fn parse_datetime(tm: &str, fmt: &str) -> Result<SystemTime, ParseError> {
     }
 }
 
+/// ### parse_iso_datetime
+///
+/// Parse ISO 8601 date time with timezone, as printed by `ls --time-style=full-iso`
+/// (e.g. `2021-05-01 12:34:56.123456789 +0200`), and transform it into `SystemTime`
+pub fn parse_iso_datetime(tm: &str) -> Result<SystemTime, ParseError> {
+    let tm: String = tm.split_whitespace().collect::<Vec<&str>>().join(" ");
+    DateTime::parse_from_str(tm.as_str(), "%Y-%m-%d %H:%M:%S%.f %z").map(|dt| {
+        SystemTime::UNIX_EPOCH
+            .checked_add(Duration::from_secs(dt.timestamp().max(0) as u64))
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    })
+}
+
 /// ### parse_semver
 ///
 /// Parse semver string
@@ -227,12 +272,47 @@ pub fn parse_semver(haystack: &str) -> Option<String> {
     }
 }
 
+/// ## Permissions
+///
+/// UNIX mode and, optionally, the owner as (uid, gid)
+pub type Permissions = ((u8, u8, u8), Option<(u32, u32)>);
+
+/// ### parse_permissions
+///
+/// Parse permissions in the format `MODE [UID:GID]` (e.g. `644` or `755 1000:100`), where mode is in octal notation.
+/// Returns the mode and, if specified, the owner
+pub fn parse_permissions(s: &str) -> Option<Permissions> {
+    let mut tokens = s.split_whitespace();
+    let mode: Vec<u8> = tokens
+        .next()?
+        .chars()
+        .map(|x| x.to_digit(8).map(|x| x as u8))
+        .collect::<Option<Vec<u8>>>()?;
+    let mode: (u8, u8, u8) = match mode.as_slice() {
+        [u, g, o] => (*u, *g, *o),
+        _ => return None,
+    };
+    let owner: Option<(u32, u32)> = match tokens.next() {
+        None => None,
+        Some(owner) => {
+            let (uid, gid) = owner.split_once(':')?;
+            Some((u32::from_str(uid).ok()?, u32::from_str(gid).ok()?))
+        }
+    };
+    match tokens.next() {
+        None => Some((mode, owner)),
+        Some(_) => None,
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
     use crate::utils::fmt::fmt_time;
 
+    use proptest::prelude::*;
+
     #[test]
     fn test_utils_parse_remote_opt() {
         // Base case
@@ -296,6 +376,21 @@ mod tests {
         assert_eq!(result.protocol, FileTransferProtocol::Scp);
         assert!(result.username.is_some()); // Doesn't fall back
         assert!(result.wrkdir.is_none());
+        let result: RemoteOptions = parse_remote_opt(&String::from("webdavs://172.26.104.1"))
+            .ok()
+            .unwrap();
+        assert_eq!(result.hostname, String::from("172.26.104.1"));
+        assert_eq!(result.port, 443); // Fallback to https default
+        assert_eq!(result.protocol, FileTransferProtocol::WebDav(true));
+        assert!(result.username.is_none()); // Doesn't fall back
+        assert!(result.wrkdir.is_none());
+        let result: RemoteOptions = parse_remote_opt(&String::from("smb://172.26.104.1:/public"))
+            .ok()
+            .unwrap();
+        assert_eq!(result.port, 445); // Fallback to smb default
+        assert_eq!(result.protocol, FileTransferProtocol::Smb);
+        assert!(result.username.is_none()); // Doesn't fall back
+        assert_eq!(result.wrkdir.unwrap(), PathBuf::from("/public"));
         // Protocol + user
         let result: RemoteOptions = parse_remote_opt(&String::from("ftps://anon@172.26.104.1"))
             .ok()
@@ -333,12 +428,96 @@ mod tests {
         assert_eq!(result.protocol, FileTransferProtocol::Ftp(false));
         assert_eq!(result.username.unwrap(), String::from("anon"));
         assert_eq!(result.wrkdir.unwrap(), PathBuf::from("/tmp"));
+        // S3 (bucket name as address)
+        let result: RemoteOptions = parse_remote_opt(&String::from("s3://AKIAEXAMPLE@my-bucket"))
+            .ok()
+            .unwrap();
+        assert_eq!(result.hostname, String::from("my-bucket"));
+        assert_eq!(result.port, 0);
+        assert_eq!(result.protocol, FileTransferProtocol::S3);
+        assert_eq!(result.username.unwrap(), String::from("AKIAEXAMPLE"));
+        // IPv6
+        let result: RemoteOptions = parse_remote_opt(&String::from("[::1]")).ok().unwrap();
+        assert_eq!(result.hostname, String::from("::1"));
+        assert_eq!(result.port, 22);
+        assert!(result.wrkdir.is_none());
+        let result: RemoteOptions =
+            parse_remote_opt(&String::from("sftp://root@[fe80::1:2]:2222:/tmp"))
+                .ok()
+                .unwrap();
+        assert_eq!(result.hostname, String::from("fe80::1:2"));
+        assert_eq!(result.port, 2222);
+        assert_eq!(result.protocol, FileTransferProtocol::Sftp);
+        assert_eq!(result.username.unwrap(), String::from("root"));
+        assert_eq!(result.wrkdir.unwrap(), PathBuf::from("/tmp"));
+        let result: RemoteOptions = parse_remote_opt(&String::from("ftp://[::ffff:10.0.0.1]"))
+            .ok()
+            .unwrap();
+        assert_eq!(result.hostname, String::from("::ffff:10.0.0.1"));
+        assert_eq!(result.port, 21);
         // bad syntax
         assert!(parse_remote_opt(&String::from("omar://172.26.104.1")).is_err()); // Bad protocol
         assert!(parse_remote_opt(&String::from("omar://172.26.104.1:650000")).is_err());
         // Bad port
     }
 
+    #[test]
+    fn test_utils_parse_remote_opt_errors() {
+        assert_eq!(
+            parse_remote_opt("omar://172.26.104.1").err().unwrap(),
+            RemoteOptError::UnknownProtocol(String::from("omar"))
+        );
+        assert_eq!(
+            parse_remote_opt("172.26.104.1:650000").err().unwrap(),
+            RemoteOptError::BadPort(String::from("650000"))
+        );
+        assert_eq!(
+            parse_remote_opt("172.26.104.1:0").err().unwrap(),
+            RemoteOptError::BadPort(String::from("0"))
+        );
+        assert_eq!(
+            parse_remote_opt("").err().unwrap(),
+            RemoteOptError::BadSyntax
+        );
+        assert_eq!(
+            parse_remote_opt("@172.26.104.1").err().unwrap(),
+            RemoteOptError::BadSyntax
+        );
+        assert_eq!(
+            parse_remote_opt("sftp://").err().unwrap(),
+            RemoteOptError::BadSyntax
+        );
+        assert_eq!(
+            parse_remote_opt("172.26.104.1 :22").err().unwrap(),
+            RemoteOptError::BadSyntax
+        );
+        assert_eq!(
+            parse_remote_opt("[::1:22").err().unwrap(),
+            RemoteOptError::BadSyntax
+        );
+        // Protocol is case insensitive
+        assert_eq!(
+            parse_remote_opt("SCP://172.26.104.1")
+                .ok()
+                .unwrap()
+                .protocol,
+            FileTransferProtocol::Scp
+        );
+        // Fmt
+        assert_eq!(
+            RemoteOptError::BadPort(String::from("0")).to_string(),
+            String::from("Bad port \"0\": must be a number between 1 and 65535")
+        );
+        assert_eq!(
+            RemoteOptError::UnknownProtocol(String::from("omar")).to_string(),
+            String::from("Unknown protocol \"omar\"")
+        );
+        assert_eq!(
+            RemoteOptError::BadSyntax.to_string(),
+            String::from("Bad remote host syntax!")
+        );
+    }
+
     #[test]
     fn test_utils_parse_lstime() {
         // Good cases
@@ -401,6 +580,84 @@ mod tests {
         assert!(parse_datetime("04-08-14", "%d-%m-%y").is_err());
     }
 
+    #[test]
+    fn test_utils_parse_iso_datetime() {
+        assert_eq!(
+            parse_iso_datetime("2014-08-04 17:09:00.123456789 +0200")
+                .ok()
+                .unwrap()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()
+                .unwrap(),
+            Duration::from_secs(1407164940)
+        );
+        assert_eq!(
+            parse_iso_datetime("2014-08-04  15:09:00 +0000")
+                .ok()
+                .unwrap(),
+            SystemTime::UNIX_EPOCH + Duration::from_secs(1407164940)
+        );
+        // Timezone is mandatory
+        assert!(parse_iso_datetime("2014-08-04 15:09:00").is_err());
+        assert!(parse_iso_datetime("Aug 4 15:09").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn test_utils_parse_remote_opt_never_panics(remote in "\\PC*") {
+            let _ = parse_remote_opt(remote.as_str());
+        }
+
+        #[test]
+        fn test_utils_parse_remote_opt_roundtrip(
+            protocol in "(sftp|scp|ftp|ftps)",
+            username in "[a-z_][a-z0-9_.-]{0,16}",
+            hostname in "[a-z0-9][a-z0-9.-]{0,32}",
+            port in 1u16..,
+            wrkdir in "/[a-zA-Z0-9_./ -]{0,32}",
+        ) {
+            let remote: String = format!("{}://{}@{}:{}:{}", protocol, username, hostname, port, wrkdir);
+            let result: RemoteOptions = parse_remote_opt(remote.as_str()).unwrap();
+            prop_assert_eq!(result.protocol, FileTransferProtocol::from_str(protocol.as_str()).unwrap());
+            prop_assert_eq!(result.username, Some(username));
+            prop_assert_eq!(result.hostname, hostname);
+            prop_assert_eq!(result.port, port);
+            prop_assert_eq!(result.wrkdir, Some(PathBuf::from(wrkdir)));
+        }
+
+        #[test]
+        fn test_utils_parse_lstime_never_panics(tm in "\\PC*") {
+            let _ = parse_lstime(tm.as_str(), "%b %d %Y", "%b %d %H:%M");
+        }
+
+        #[test]
+        fn test_utils_parse_lstime_roundtrip(secs in 0i64..4102444800) {
+            let datetime: NaiveDateTime = NaiveDateTime::from_timestamp(secs - (secs % 86400), 0);
+            let tm: String = datetime.format("%b %d %Y").to_string();
+            prop_assert_eq!(
+                parse_lstime(tm.as_str(), "%b %d %Y", "%b %d %H:%M").unwrap(),
+                SystemTime::UNIX_EPOCH + Duration::from_secs(datetime.timestamp() as u64)
+            );
+        }
+    }
+
+    #[test]
+    fn test_utils_parse_permissions() {
+        assert_eq!(parse_permissions("644"), Some(((6, 4, 4), None)));
+        assert_eq!(
+            parse_permissions(" 750  1000:100 "),
+            Some(((7, 5, 0), Some((1000, 100))))
+        );
+        // Errors
+        assert!(parse_permissions("").is_none());
+        assert!(parse_permissions("64").is_none());
+        assert!(parse_permissions("6444").is_none());
+        assert!(parse_permissions("648").is_none());
+        assert!(parse_permissions("644 1000").is_none());
+        assert!(parse_permissions("644 root:root").is_none());
+        assert!(parse_permissions("644 0:0 foo").is_none());
+    }
+
     #[test]
     fn test_utils_parse_semver() {
         assert_eq!(
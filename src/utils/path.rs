@@ -0,0 +1,161 @@
+//! ## Path
+//!
+//! `path` is the module which provides utilities to work with paths
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+use std::path::{Component, Path, PathBuf};
+
+/// ### mirror_path
+///
+/// Replicate the change of directory from `prev` to `next` starting from `other`.
+/// The change is expressed as the amount of directories to go up to reach the common ancestor of `prev` and `next`,
+/// followed by the path from the ancestor to `next`. Returns None if `other` has not enough ancestors
+pub fn mirror_path(prev: &Path, next: &Path, other: &Path) -> Option<PathBuf> {
+    // Get common ancestor
+    let common: PathBuf = prev
+        .components()
+        .zip(next.components())
+        .take_while(|(a, b)| a == b)
+        .map(|(a, _)| a)
+        .collect();
+    let up: usize = prev
+        .strip_prefix(common.as_path())
+        .ok()?
+        .components()
+        .count();
+    let down: &Path = next.strip_prefix(common.as_path()).ok()?;
+    let mut path: PathBuf = other.to_path_buf();
+    for _ in 0..up {
+        // Never pop the root directory
+        match path.components().next_back() {
+            Some(Component::Normal(_)) => {
+                path.pop();
+            }
+            _ => return None,
+        }
+    }
+    path.push(down);
+    Some(path)
+}
+
+/// ### normalize_path
+///
+/// Resolve `.` and `..` components of path lexically, without accessing the file system.
+/// `..` never goes above the root directory
+pub fn normalize_path(p: &Path) -> PathBuf {
+    let mut path: PathBuf = PathBuf::new();
+    for component in p.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match path.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    path.pop();
+                }
+                Some(_) => {}
+                None => path.push(".."),
+            },
+            c => path.push(c),
+        }
+    }
+    path
+}
+
+/// ### is_jailed
+///
+/// Returns whether `p` is `root` or one of its descendants
+pub fn is_jailed(p: &Path, root: &Path) -> bool {
+    normalize_path(p).starts_with(normalize_path(root))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_path_mirror_path() {
+        // Enter directory
+        assert_eq!(
+            mirror_path(
+                Path::new("/home/omar"),
+                Path::new("/home/omar/www/assets"),
+                Path::new("/var")
+            )
+            .unwrap(),
+            PathBuf::from("/var/www/assets")
+        );
+        // Go to parent
+        assert_eq!(
+            mirror_path(
+                Path::new("/home/omar"),
+                Path::new("/home"),
+                Path::new("/var/www")
+            )
+            .unwrap(),
+            PathBuf::from("/var")
+        );
+        // Sibling
+        assert_eq!(
+            mirror_path(
+                Path::new("/srv/app/releases/v1"),
+                Path::new("/srv/app/releases/v2"),
+                Path::new("/opt/app/v1")
+            )
+            .unwrap(),
+            PathBuf::from("/opt/app/v2")
+        );
+        // Same directory
+        assert_eq!(
+            mirror_path(Path::new("/tmp"), Path::new("/tmp"), Path::new("/var")).unwrap(),
+            PathBuf::from("/var")
+        );
+        // Can't go above root
+        assert!(mirror_path(Path::new("/a/b"), Path::new("/"), Path::new("/c")).is_none());
+    }
+
+    #[test]
+    fn test_utils_path_normalize_path() {
+        assert_eq!(
+            normalize_path(Path::new("/srv/app/./releases/../shared")),
+            PathBuf::from("/srv/app/shared")
+        );
+        assert_eq!(normalize_path(Path::new("/../..")), PathBuf::from("/"));
+        assert_eq!(
+            normalize_path(Path::new("a/../../b")),
+            PathBuf::from("../b")
+        );
+    }
+
+    #[test]
+    fn test_utils_path_is_jailed() {
+        let root: &Path = Path::new("/srv/app");
+        assert!(is_jailed(Path::new("/srv/app"), root));
+        assert!(is_jailed(Path::new("/srv/app/releases/v1"), root));
+        assert!(is_jailed(Path::new("/srv/app/releases/../shared"), root));
+        assert!(!is_jailed(Path::new("/srv/app/.."), root));
+        assert!(!is_jailed(Path::new("/srv/application"), root));
+        assert!(!is_jailed(Path::new("/etc"), root));
+    }
+}
@@ -0,0 +1,155 @@
+//! ## Secret
+//!
+//! `secret` is the module which provides a container for credentials which must never leave memory
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Deps
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+extern crate libc;
+
+/// ## Secret
+///
+/// Secret holds a credential (e.g. a password or an OTP answer) in memory only.
+/// On unix systems the buffer is locked, so it can't be swapped to disk;
+/// the buffer is zeroed when the secret is dropped
+pub struct Secret {
+    buf: Box<[u8]>,
+}
+
+impl Secret {
+    /// ### new
+    ///
+    /// Move `secret` into a new Secret; the original string buffer is zeroed
+    pub fn new(secret: String) -> Self {
+        let mut bytes: Vec<u8> = secret.into_bytes();
+        let secret = Self::from(std::str::from_utf8(bytes.as_slice()).unwrap_or_default());
+        wipe(bytes.as_mut_slice());
+        secret
+    }
+
+    /// ### expose
+    ///
+    /// Get the secret value; don't keep copies of it around
+    pub fn expose(&self) -> &str {
+        std::str::from_utf8(&self.buf).unwrap_or_default()
+    }
+}
+
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        let buf: Box<[u8]> = secret.as_bytes().to_vec().into_boxed_slice();
+        lock(&buf);
+        Secret { buf }
+    }
+}
+
+impl Clone for Secret {
+    fn clone(&self) -> Self {
+        Self::from(self.expose())
+    }
+}
+
+impl PartialEq for Secret {
+    fn eq(&self, other: &Self) -> bool {
+        self.buf == other.buf
+    }
+}
+
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("Secret(***)")
+    }
+}
+
+impl Drop for Secret {
+    fn drop(&mut self) {
+        wipe(&mut self.buf);
+        unlock(&self.buf);
+    }
+}
+
+/// ### wipe
+///
+/// Zero `buf`; volatile writes can't be optimized away
+fn wipe(buf: &mut [u8]) {
+    for byte in buf.iter_mut() {
+        unsafe {
+            std::ptr::write_volatile(byte, 0);
+        }
+    }
+    std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+}
+
+/// ### lock
+///
+/// Prevent `buf` from being swapped to disk. Locking is best effort, since it may exceed `RLIMIT_MEMLOCK`
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+fn lock(buf: &[u8]) {
+    if !buf.is_empty() {
+        unsafe {
+            let _ = libc::mlock(buf.as_ptr() as *const libc::c_void, buf.len());
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn lock(_buf: &[u8]) {}
+
+/// ### unlock
+///
+/// Unlock memory locked with `lock`
+#[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+fn unlock(buf: &[u8]) {
+    if !buf.is_empty() {
+        unsafe {
+            let _ = libc::munlock(buf.as_ptr() as *const libc::c_void, buf.len());
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn unlock(_buf: &[u8]) {}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_secret() {
+        let secret: Secret = Secret::new(String::from("123456"));
+        assert_eq!(secret.expose(), "123456");
+        assert_eq!(format!("{:?}", secret).as_str(), "Secret(***)");
+        let copy: Secret = secret.clone();
+        assert_eq!(copy, secret);
+        drop(secret);
+        assert_eq!(copy.expose(), "123456");
+        assert_eq!(Secret::from("").expose(), "");
+        // Wipe
+        let mut buf: Vec<u8> = b"otp".to_vec();
+        wipe(buf.as_mut_slice());
+        assert_eq!(buf, vec![0, 0, 0]);
+    }
+}
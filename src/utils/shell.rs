@@ -0,0 +1,124 @@
+//! ## Shell
+//!
+//! `shell` is the module which provides utilities to build shell commands to run on the remote host,
+//! and to run them on the local one
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Ext
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// ### quote
+///
+/// Quote `arg` for a POSIX shell, so that it is passed to the command as a single word, as it is.
+/// The argument is wrapped in single quotes, which disable any expansion; single quotes inside
+/// of it are closed, escaped and reopened
+pub fn quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
+
+/// ### quote_path
+///
+/// Quote `path` for a POSIX shell.
+/// Relative paths starting with `-` are prefixed with `./`, so that they're not taken as options
+pub fn quote_path(path: &Path) -> String {
+    let path: String = path.to_string_lossy().to_string();
+    match path.starts_with('-') {
+        true => quote(format!("./{}", path).as_str()),
+        false => quote(path.as_str()),
+    }
+}
+
+/// ### command
+///
+/// Make a `Command` running `cmd` through the shell of the local host (`sh` or, on Windows, `cmd`).
+/// The command doesn't read from the terminal: its standard input is closed
+pub fn command(cmd: &str) -> Command {
+    #[cfg(target_family = "windows")]
+    let mut command: Command = {
+        let mut command: Command = Command::new("cmd");
+        command.arg("/C").arg(cmd);
+        command
+    };
+    #[cfg(not(target_family = "windows"))]
+    let mut command: Command = {
+        let mut command: Command = Command::new("sh");
+        command.arg("-c").arg(cmd);
+        command
+    };
+    command.stdin(Stdio::null());
+    command
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_shell_quote() {
+        assert_eq!(quote("omar.txt").as_str(), "'omar.txt'");
+        assert_eq!(quote("").as_str(), "''");
+        assert_eq!(quote("my file.txt").as_str(), "'my file.txt'");
+        assert_eq!(quote("\"$HOME\" `id`").as_str(), "'\"$HOME\" `id`'");
+        assert_eq!(quote("omar's file").as_str(), r"'omar'\''s file'");
+        assert_eq!(
+            quote_path(Path::new("/tmp/$(reboot)")).as_str(),
+            "'/tmp/$(reboot)'"
+        );
+        assert_eq!(quote_path(Path::new("-rf")).as_str(), "'./-rf'");
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_utils_shell_quote_roundtrip() {
+        for arg in [
+            "omar.txt",
+            "my file.txt",
+            "it's \"quoted\"",
+            "$HOME `id` $(id) \\ ; | & > < * ? ~ ! # \n",
+            "'''",
+        ]
+        .iter()
+        {
+            let output = std::process::Command::new("sh")
+                .arg("-c")
+                .arg(format!("printf %s {}", quote(arg)))
+                .output()
+                .unwrap();
+            assert_eq!(String::from_utf8_lossy(&output.stdout).as_ref(), *arg);
+        }
+    }
+
+    #[test]
+    #[cfg(any(target_os = "unix", target_os = "macos", target_os = "linux"))]
+    fn test_utils_shell_command() {
+        let output = command(format!("printf %s {} && exit 3", quote("it's")).as_str())
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout).as_ref(), "it's");
+        assert_eq!(output.status.code(), Some(3));
+    }
+}
@@ -0,0 +1,232 @@
+//! ## Template
+//!
+//! `template` is the module which provides the expansion of placeholders in commands
+
+/**
+ * MIT License
+ *
+ * termscp - Copyright (c) 2021 Christian Visintin
+ *
+ * Permission is hereby granted, free of charge, to any person obtaining a copy
+ * of this software and associated documentation files (the "Software"), to deal
+ * in the Software without restriction, including without limitation the rights
+ * to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+ * copies of the Software, and to permit persons to whom the Software is
+ * furnished to do so, subject to the following conditions:
+ *
+ * The above copyright notice and this permission notice shall be included in all
+ * copies or substantial portions of the Software.
+ *
+ * THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+ * IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+ * FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+ * AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+ * LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+ * OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+ * SOFTWARE.
+ */
+// Locals
+use super::shell;
+// Ext
+use std::fmt;
+
+/// ## Placeholders
+///
+/// Placeholders holds the values which can be expanded in a command template:
+///
+/// - `{FILE}`: absolute path of the selected file
+/// - `{DIR}`: current working directory
+/// - `{SELECTION}`: name of the selected file
+/// - `{HOST}`: remote host address
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Placeholders {
+    pub file: Option<String>,
+    pub dir: String,
+    pub selection: Option<String>,
+    pub host: String,
+}
+
+/// ## TemplateError
+///
+/// Describes an error while expanding a template
+#[derive(Clone, Debug, PartialEq)]
+pub enum TemplateError {
+    NoSelection(String), // Placeholder requires a selected file
+    UnterminatedPlaceholder,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TemplateError::NoSelection(placeholder) => {
+                write!(f, "{{{}}} requires a selected file", placeholder)
+            }
+            TemplateError::UnterminatedPlaceholder => write!(f, "Unterminated placeholder"),
+        }
+    }
+}
+
+/// ### expand
+///
+/// Expand placeholders in `template` with the values in `placeholders`.
+/// Unknown placeholders are left untouched; `{{` and `}}` are expanded to `{` and `}`.
+/// If `quote` is true, values are quoted for a POSIX shell, when required
+pub fn expand(
+    template: &str,
+    placeholders: &Placeholders,
+    quote: bool,
+) -> Result<String, TemplateError> {
+    let mut expanded: String = String::with_capacity(template.len());
+    let mut chars = template.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                expanded.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                expanded.push('}');
+            }
+            '{' => {
+                // Read placeholder name
+                let mut name: String = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(ch) => name.push(ch),
+                        None => return Err(TemplateError::UnterminatedPlaceholder),
+                    }
+                }
+                let value: Option<&str> = match name.as_str() {
+                    "FILE" => Some(
+                        placeholders
+                            .file
+                            .as_deref()
+                            .ok_or_else(|| TemplateError::NoSelection(name.clone()))?,
+                    ),
+                    "DIR" => Some(placeholders.dir.as_str()),
+                    "SELECTION" => Some(
+                        placeholders
+                            .selection
+                            .as_deref()
+                            .ok_or_else(|| TemplateError::NoSelection(name.clone()))?,
+                    ),
+                    "HOST" => Some(placeholders.host.as_str()),
+                    _ => None,
+                };
+                match value {
+                    Some(value) if quote => expanded.push_str(shell_quote(value).as_str()),
+                    Some(value) => expanded.push_str(value),
+                    None => {
+                        // Keep unknown placeholder
+                        expanded.push('{');
+                        expanded.push_str(name.as_str());
+                        expanded.push('}');
+                    }
+                }
+            }
+            ch => expanded.push(ch),
+        }
+    }
+    Ok(expanded)
+}
+
+/// ### shell_quote
+///
+/// Quote `s` with single quotes if it contains characters which have a meaning for the shell
+fn shell_quote(s: &str) -> String {
+    let safe: bool = !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./@:+,=%".contains(c));
+    match safe {
+        true => s.to_string(),
+        false => shell::quote(s),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_utils_template_expand() {
+        let placeholders: Placeholders = Placeholders {
+            file: Some(String::from("/etc/nginx/nginx.conf")),
+            dir: String::from("/etc/nginx"),
+            selection: Some(String::from("nginx.conf")),
+            host: String::from("192.168.1.31"),
+        };
+        assert_eq!(
+            expand("cat {FILE}", &placeholders, false).unwrap().as_str(),
+            "cat /etc/nginx/nginx.conf"
+        );
+        assert_eq!(
+            expand("ls {DIR}; echo {SELECTION}@{HOST}", &placeholders, false)
+                .unwrap()
+                .as_str(),
+            "ls /etc/nginx; echo nginx.conf@192.168.1.31"
+        );
+        // No placeholders
+        assert_eq!(
+            expand("uptime", &placeholders, true).unwrap().as_str(),
+            "uptime"
+        );
+        // Unknown placeholder and escapes
+        assert_eq!(
+            expand("echo {USER} {{FILE}} ${HOME}", &placeholders, false)
+                .unwrap()
+                .as_str(),
+            "echo {USER} {FILE} ${HOME}"
+        );
+    }
+
+    #[test]
+    fn test_utils_template_expand_quoted() {
+        let placeholders: Placeholders = Placeholders {
+            file: Some(String::from("/home/omar/my file's.txt")),
+            dir: String::from("/home/omar"),
+            selection: Some(String::from("nginx")),
+            host: String::from("localhost"),
+        };
+        assert_eq!(
+            expand("systemctl restart {SELECTION}", &placeholders, true)
+                .unwrap()
+                .as_str(),
+            "systemctl restart nginx"
+        );
+        assert_eq!(
+            expand("rm {FILE}", &placeholders, true).unwrap().as_str(),
+            "rm '/home/omar/my file'\\''s.txt'"
+        );
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("a;b"), "'a;b'");
+    }
+
+    #[test]
+    fn test_utils_template_expand_err() {
+        let placeholders: Placeholders = Placeholders {
+            file: None,
+            dir: String::from("/"),
+            selection: None,
+            host: String::from("localhost"),
+        };
+        assert_eq!(
+            expand("cat {FILE}", &placeholders, false).err().unwrap(),
+            TemplateError::NoSelection(String::from("FILE"))
+        );
+        assert_eq!(
+            expand("echo {SELECTION}", &placeholders, false)
+                .err()
+                .unwrap()
+                .to_string()
+                .as_str(),
+            "{SELECTION} requires a selected file"
+        );
+        assert_eq!(
+            expand("echo {DIR", &placeholders, false).err().unwrap(),
+            TemplateError::UnterminatedPlaceholder
+        );
+    }
+}